@@ -0,0 +1,377 @@
+//! AV1 Annex B length-delimited bitstream format
+//!
+//! Annex B replaces the `obu_size` fields of the low-overhead format
+//! (Section 5) with a three-level size hierarchy:
+//!
+//! ```text
+//! temporal_unit(sz) {
+//!     while (sz > 0) {
+//!         frame_unit_size                  leb128()
+//!         frame_unit(frame_unit_size)
+//!     }
+//! }
+//!
+//! frame_unit(sz) {
+//!     while (sz > 0) {
+//!         obu_length                       leb128()
+//!         open_bitstream_unit(obu_length)
+//!     }
+//! }
+//! ```
+//!
+//! Each temporal unit is itself preceded by `temporal_unit_size`.
+//! Conformance suites (e.g. Argon) and some hardware encoders produce
+//! this format. [`TemporalUnit::to_low_overhead`] rewrites a temporal unit
+//! as Section 5 OBUs so the rest of the crate, and dav1d, can consume it.
+
+use crate::leb128::{decode_uleb128, encode_uleb128};
+use crate::obu::{parse_obu, Obu, ObuType};
+use bitvue_core::{BitvueError, Result, StreamId, UnitNode};
+
+/// Number of temporal units checked by [`is_annex_b`]
+const DETECTION_TEMPORAL_UNITS: usize = 4;
+
+/// An OBU inside a frame unit
+#[derive(Debug, Clone)]
+pub struct AnnexBObu {
+    /// Byte offset of the `obu_length` field
+    pub offset: usize,
+    /// Byte offset of the OBU header
+    pub obu_offset: usize,
+    /// `obu_length`: size of the OBU, header included
+    pub obu_length: usize,
+    /// The parsed OBU (`offset` is its position in the Annex B data)
+    pub obu: Obu,
+}
+
+/// A frame unit: one frame header and its tile groups, plus any OBUs
+/// that precede them
+#[derive(Debug, Clone)]
+pub struct FrameUnit {
+    /// Byte offset of the `frame_unit_size` field
+    pub offset: usize,
+    /// Size in bytes, `frame_unit_size` field included
+    pub size: usize,
+    pub obus: Vec<AnnexBObu>,
+}
+
+/// A temporal unit: everything with the same presentation time
+#[derive(Debug, Clone)]
+pub struct TemporalUnit {
+    /// Byte offset of the `temporal_unit_size` field
+    pub offset: usize,
+    /// Size in bytes, `temporal_unit_size` field included
+    pub size: usize,
+    pub frame_units: Vec<FrameUnit>,
+}
+
+impl TemporalUnit {
+    /// All OBUs of the temporal unit, in bitstream order
+    pub fn obus(&self) -> impl Iterator<Item = &AnnexBObu> {
+        self.frame_units.iter().flat_map(|fu| fu.obus.iter())
+    }
+
+    /// Rewrite the temporal unit in the low-overhead (Section 5) format
+    ///
+    /// OBUs without `obu_has_size_field` get one; OBUs that already carry
+    /// it are copied unchanged. `data` is the Annex B stream the temporal
+    /// unit was parsed from.
+    pub fn to_low_overhead(&self, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.size);
+        for item in self.obus() {
+            let bytes = &data[item.obu_offset..item.obu_offset + item.obu_length];
+            if item.obu.header.has_size {
+                out.extend_from_slice(bytes);
+                continue;
+            }
+            let header_size = item.obu.header.header_size;
+            // obu_has_size_field is bit 1 of the first header byte
+            out.push(bytes[0] | 0x02);
+            out.extend_from_slice(&bytes[1..header_size]);
+            out.extend_from_slice(&encode_uleb128((item.obu_length - header_size) as u64));
+            out.extend_from_slice(&bytes[header_size..]);
+        }
+        out
+    }
+}
+
+/// Parse an Annex B stream into temporal units
+pub fn parse_annex_b(data: &[u8]) -> Result<Vec<TemporalUnit>> {
+    let mut units = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let unit = parse_temporal_unit(data, offset)?;
+        offset += unit.size;
+        units.push(unit);
+    }
+    Ok(units)
+}
+
+/// Check whether data looks like an Annex B stream
+///
+/// The first temporal units must parse with consistent sizes and the
+/// stream must start with a temporal delimiter, as every temporal unit
+/// does. A low-overhead stream fails this: its leading temporal
+/// delimiter (`0x12 0x00`) reads as an empty frame unit.
+pub fn is_annex_b(data: &[u8]) -> bool {
+    let mut offset = 0;
+    for index in 0..DETECTION_TEMPORAL_UNITS {
+        if offset >= data.len() {
+            return index > 0;
+        }
+        let Ok(unit) = parse_temporal_unit(data, offset) else {
+            return false;
+        };
+        let starts_with_td = unit
+            .obus()
+            .next()
+            .is_some_and(|o| o.obu.header.obu_type == ObuType::TemporalDelimiter);
+        if !starts_with_td {
+            return false;
+        }
+        offset += unit.size;
+    }
+    true
+}
+
+/// Convert a whole Annex B stream to the low-overhead format
+pub fn annex_b_to_low_overhead(data: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 16);
+    for unit in parse_annex_b(data)? {
+        out.extend_from_slice(&unit.to_low_overhead(data));
+    }
+    Ok(out)
+}
+
+/// Build the unit tree of an Annex B stream
+///
+/// Temporal units are the top-level nodes (one per frame index, as IVF
+/// frames are), with their frame units and OBUs as children.
+pub fn annex_b_unit_nodes(units: &[TemporalUnit]) -> Vec<UnitNode> {
+    units
+        .iter()
+        .enumerate()
+        .map(|(tu_index, tu)| {
+            let frame_type = tu.obus().find_map(|o| o.obu.frame_type);
+            let mut node = UnitNode::new(
+                StreamId::A,
+                "TEMPORAL_UNIT".to_string(),
+                tu.offset as u64,
+                tu.size,
+            );
+            node.frame_index = Some(tu_index);
+            node.frame_type = frame_type.map(|t| t.as_str().into());
+            node.display_name = format!("Temporal Unit {}", tu_index).into();
+            node.children = tu
+                .frame_units
+                .iter()
+                .enumerate()
+                .map(|(fu_index, fu)| frame_unit_node(fu_index, fu))
+                .collect();
+            node
+        })
+        .collect()
+}
+
+fn frame_unit_node(index: usize, fu: &FrameUnit) -> UnitNode {
+    let mut node = UnitNode::new(
+        StreamId::A,
+        "FRAME_UNIT".to_string(),
+        fu.offset as u64,
+        fu.size,
+    );
+    let frame_type = fu.obus.iter().find_map(|o| o.obu.frame_type);
+    node.display_name = match frame_type {
+        Some(t) => format!("Frame Unit {} ({})", index, t.as_str()),
+        None => format!("Frame Unit {}", index),
+    }
+    .into();
+    node.frame_type = frame_type.map(|t| t.as_str().into());
+    node.children = fu
+        .obus
+        .iter()
+        .map(|o| {
+            let mut child = UnitNode::new(
+                StreamId::A,
+                o.obu.header.obu_type.name().to_string(),
+                o.obu_offset as u64,
+                o.obu_length,
+            );
+            child.temporal_id = Some(o.obu.header.temporal_id);
+            child.frame_type = o.obu.frame_type.map(|t| t.as_str().into());
+            child
+        })
+        .collect();
+    node
+}
+
+/// Read a leb128 size field at `offset`, bounded by `end`
+///
+/// Returns the size and the number of bytes of the field. The sized unit
+/// must fit before `end` and must not be empty.
+fn read_size(data: &[u8], offset: usize, end: usize, what: &str) -> Result<(usize, usize)> {
+    let (size, len) = decode_uleb128(&data[offset..end]).map_err(|_| BitvueError::Parse {
+        offset: offset as u64,
+        message: format!("Truncated {}", what),
+    })?;
+    let available = end - offset - len;
+    if size == 0 || size > available as u64 {
+        return Err(BitvueError::Parse {
+            offset: offset as u64,
+            message: format!(
+                "{} {} exceeds the {} bytes available",
+                what, size, available
+            ),
+        });
+    }
+    Ok((size as usize, len))
+}
+
+fn parse_temporal_unit(data: &[u8], offset: usize) -> Result<TemporalUnit> {
+    let (tu_size, len) = read_size(data, offset, data.len(), "temporal_unit_size")?;
+    let end = offset + len + tu_size;
+    let mut frame_units = Vec::new();
+    let mut pos = offset + len;
+    while pos < end {
+        let (fu_size, len) = read_size(data, pos, end, "frame_unit_size")?;
+        let fu_end = pos + len + fu_size;
+        let mut obus = Vec::new();
+        let mut obu_pos = pos + len;
+        while obu_pos < fu_end {
+            let (obu_length, len) = read_size(data, obu_pos, fu_end, "obu_length")?;
+            let obu_offset = obu_pos + len;
+            let (mut obu, consumed) = parse_obu(&data[obu_offset..obu_offset + obu_length], 0)?;
+            if consumed != obu_length {
+                return Err(BitvueError::Parse {
+                    offset: obu_offset as u64,
+                    message: format!(
+                        "obu_size {} disagrees with obu_length {}",
+                        consumed, obu_length
+                    ),
+                });
+            }
+            obu.offset = obu_offset as u64;
+            obus.push(AnnexBObu {
+                offset: obu_pos,
+                obu_offset,
+                obu_length,
+                obu,
+            });
+            obu_pos = obu_offset + obu_length;
+        }
+        frame_units.push(FrameUnit {
+            offset: pos,
+            size: fu_end - pos,
+            obus,
+        });
+        pos = fu_end;
+    }
+    Ok(TemporalUnit {
+        offset,
+        size: end - offset,
+        frame_units,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wrap a low-overhead temporal unit in Annex B framing, dropping the
+    /// OBU size fields and starting a new frame unit at every frame header
+    fn to_annex_b(temporal_units: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for tu in temporal_units {
+            let mut frame_units: Vec<Vec<u8>> = vec![Vec::new()];
+            let mut has_frame = false;
+            let mut iter = crate::obu::ObuIterator::new(tu);
+            while let Some(item) = iter.next_obu_with_offset() {
+                let item = item.unwrap();
+                let header = &item.obu.header;
+                let mut obu = tu[item.offset..item.offset + header.header_size].to_vec();
+                obu[0] &= !0x02;
+                obu.extend_from_slice(&item.obu.payload);
+
+                let is_frame = matches!(header.obu_type, ObuType::Frame | ObuType::FrameHeader);
+                if is_frame && has_frame {
+                    frame_units.push(Vec::new());
+                }
+                has_frame |= is_frame;
+                let fu = frame_units.last_mut().unwrap();
+                fu.extend_from_slice(&encode_uleb128(obu.len() as u64));
+                fu.extend_from_slice(&obu);
+            }
+            let mut tu_bytes = Vec::new();
+            for fu in frame_units {
+                tu_bytes.extend_from_slice(&encode_uleb128(fu.len() as u64));
+                tu_bytes.extend_from_slice(&fu);
+            }
+            out.extend_from_slice(&encode_uleb128(tu_bytes.len() as u64));
+            out.extend_from_slice(&tu_bytes);
+        }
+        out
+    }
+
+    #[test]
+    fn test_parse_minimal_temporal_unit() {
+        // temporal_unit_size=4, frame_unit_size=3, obu_length=1 (TD without
+        // size field), obu_length=1 (padding OBU)
+        let data = [0x05, 0x04, 0x01, 0x10, 0x01, 0x78];
+        let units = parse_annex_b(&data).unwrap();
+        assert_eq!(units.len(), 1);
+        assert_eq!(units[0].size, 6);
+        let fu = &units[0].frame_units[0];
+        assert_eq!((fu.offset, fu.size), (1, 5));
+        assert_eq!(fu.obus.len(), 2);
+        assert_eq!(fu.obus[0].obu.header.obu_type, ObuType::TemporalDelimiter);
+        assert_eq!(fu.obus[1].obu_offset, 5);
+        assert!(is_annex_b(&data));
+
+        // Size fields are added when converting
+        assert_eq!(units[0].to_low_overhead(&data), [0x12, 0x00, 0x7A, 0x00]);
+    }
+
+    #[test]
+    fn test_rejects_low_overhead_and_bad_sizes() {
+        // Temporal delimiter + padding in the low-overhead format
+        assert!(!is_annex_b(&[0x12, 0x00, 0x7A, 0x00]));
+        // frame_unit_size larger than the temporal unit
+        assert!(parse_annex_b(&[0x03, 0x05, 0x01, 0x10]).is_err());
+        // Does not start with a temporal delimiter
+        assert!(!is_annex_b(&[0x03, 0x02, 0x01, 0x78]));
+        assert!(!is_annex_b(&[]));
+    }
+
+    #[test]
+    fn test_round_trip_ivf_sample() {
+        let path =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../samples/foreman_av1.ivf");
+        let Ok(data) = std::fs::read(&path) else {
+            eprintln!("Skipping test: {} not found", path.display());
+            return;
+        };
+        let (_, frames) = crate::parse_ivf_frames(&data).unwrap();
+        let temporal_units: Vec<Vec<u8>> = frames.into_iter().map(|f| f.data).collect();
+        let annex_b = to_annex_b(&temporal_units);
+
+        assert!(is_annex_b(&annex_b));
+        let units = parse_annex_b(&annex_b).unwrap();
+        assert_eq!(units.len(), temporal_units.len());
+        for (unit, original) in units.iter().zip(&temporal_units) {
+            assert_eq!(&unit.to_low_overhead(&annex_b), original);
+        }
+
+        let nodes = annex_b_unit_nodes(&units);
+        assert_eq!(nodes.len(), units.len());
+        assert_eq!(
+            nodes[0].frame_type.as_deref(),
+            Some(crate::FrameType::Key.as_str())
+        );
+        let first_obu = &nodes[0].children[0].children[0];
+        assert_eq!(&*first_obu.unit_type, "TEMPORAL_DELIMITER");
+        assert_eq!(
+            first_obu.offset,
+            units[0].frame_units[0].obus[0].obu_offset as u64
+        );
+    }
+}
@@ -235,6 +235,7 @@ mod tests {
             offset: 0,
             frame_type,
             frame_header: None,
+            metadata: None,
         }
     }
 
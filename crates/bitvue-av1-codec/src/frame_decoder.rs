@@ -0,0 +1,272 @@
+//! AV1 Frame Decoding (syntax level)
+//!
+//! Per AV1 Specification Section 7 (Decoding process), limited to the state
+//! that the syntax of later frames depends on.
+//!
+//! [`FrameDecoder`] consumes OBUs in stream order and decodes the tiles of
+//! every frame. Between frames it maintains the eight reference slots:
+//! frame header state, CDFs (Section 7.20, loaded again through
+//! `primary_ref_frame`), motion vectors for the motion field estimation
+//! (Section 7.9) and segmentation maps. Decoding a frame therefore needs all
+//! OBUs from the preceding key frame.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use bitvue_av1_codec::FrameDecoder;
+//!
+//! let data = std::fs::read("video.obu").unwrap();
+//! let frames = FrameDecoder::new().decode(&data).unwrap();
+//! for frame in &frames {
+//!     println!("{} coding units", frame.coding_units().count());
+//! }
+//! ```
+
+use crate::obu::{parse_all_obus, Obu, ObuType};
+use crate::sequence::{parse_sequence_header, SequenceHeader};
+use crate::symbol::{CdfContext, SymbolDecoder};
+use crate::tile::decoder::{FrameState, TileDecoder};
+use crate::tile::motion_field::{MotionField, SavedMvs};
+use crate::tile::{parse_tile_group, CodingUnit, Superblock};
+use crate::uncompressed_header::{
+    parse_uncompressed_header, RefFrameInfo, UncompressedHeader, NUM_REF_FRAMES, PRIMARY_REF_NONE,
+    REFS_PER_FRAME,
+};
+use bitvue_core::{BitvueError, FrameType, Result};
+
+/// REF_SCALE_SHIFT
+const REF_SCALE_SHIFT: u32 = 14;
+
+/// A frame whose tiles have been decoded
+#[derive(Debug, Clone)]
+pub struct DecodedFrame {
+    /// The frame's uncompressed header
+    pub header: UncompressedHeader,
+    /// Superblocks of all tiles, in tile order
+    pub superblocks: Vec<Superblock>,
+}
+
+impl DecodedFrame {
+    /// Coding units of the whole frame
+    pub fn coding_units(&self) -> impl Iterator<Item = &CodingUnit> {
+        self.superblocks
+            .iter()
+            .flat_map(|sb| sb.coding_units.iter())
+    }
+}
+
+/// Decoder state saved with a reference slot besides its [`RefFrameInfo`]
+#[derive(Clone, Default)]
+struct SlotState {
+    /// Saved CDFs
+    cdfs: CdfContext,
+    /// SavedRefFrames / SavedMvs
+    mvs: SavedMvs,
+    /// SavedSegmentIds
+    segment_ids: Vec<u8>,
+}
+
+/// A frame whose tiles are being decoded
+struct FrameInProgress {
+    header: UncompressedHeader,
+    /// CDFs at the start of each tile
+    cdfs: CdfContext,
+    /// CDFs of tile `context_update_tile_id` once decoded
+    saved_cdfs: Option<CdfContext>,
+    state: FrameState,
+    superblocks: Vec<Superblock>,
+}
+
+/// Decoder of the syntax of a sequence of frames
+pub struct FrameDecoder {
+    seq: Option<SequenceHeader>,
+    refs: [RefFrameInfo; NUM_REF_FRAMES],
+    slots: [SlotState; NUM_REF_FRAMES],
+    frame: Option<FrameInProgress>,
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameDecoder {
+    pub fn new() -> Self {
+        Self {
+            seq: None,
+            refs: std::array::from_fn(|_| RefFrameInfo::default()),
+            slots: std::array::from_fn(|_| SlotState::default()),
+            frame: None,
+        }
+    }
+
+    /// Decode all frames of a low overhead bitstream (a sequence of OBUs)
+    pub fn decode(&mut self, data: &[u8]) -> Result<Vec<DecodedFrame>> {
+        let mut frames = Vec::new();
+        for obu in parse_all_obus(data)? {
+            if let Some(frame) = self.decode_obu(&obu)? {
+                frames.push(frame);
+            }
+        }
+        Ok(frames)
+    }
+
+    /// Decode one OBU, returning the frame it completes
+    pub fn decode_obu(&mut self, obu: &Obu) -> Result<Option<DecodedFrame>> {
+        match obu.header.obu_type {
+            ObuType::SequenceHeader => {
+                self.seq = Some(parse_sequence_header(&obu.payload)?);
+                Ok(None)
+            }
+            ObuType::TemporalDelimiter => {
+                self.frame = None;
+                Ok(None)
+            }
+            ObuType::FrameHeader | ObuType::RedundantFrameHeader if self.frame.is_some() => {
+                // Copy of the header of the frame being decoded
+                Ok(None)
+            }
+            ObuType::FrameHeader | ObuType::RedundantFrameHeader => {
+                self.start_frame(obu)?;
+                Ok(None)
+            }
+            ObuType::Frame => {
+                let header_bytes = self.start_frame(obu)?;
+                self.decode_tile_group(&obu.payload[header_bytes.min(obu.payload.len())..])
+            }
+            ObuType::TileGroup => self.decode_tile_group(&obu.payload),
+            _ => Ok(None),
+        }
+    }
+
+    /// Parse a frame header and set up the decoding of its tiles
+    ///
+    /// Returns the size of the header in bytes.
+    fn start_frame(&mut self, obu: &Obu) -> Result<usize> {
+        let seq = self
+            .seq
+            .as_ref()
+            .ok_or_else(|| BitvueError::InvalidData("Frame before sequence header".into()))?;
+        let header = parse_uncompressed_header(
+            &obu.payload,
+            seq,
+            &self.refs,
+            obu.header.temporal_id,
+            obu.header.spatial_id,
+        )?;
+        let header_bytes = header.header_bytes;
+        if header.show_existing_frame {
+            if header.frame_type == FrameType::Key {
+                // Reference frame loading process followed by a refresh of
+                // every slot
+                let shown = header.frame_to_show_map_idx as usize;
+                let (info, slot) = (self.refs[shown].clone(), self.slots[shown].clone());
+                self.refs.fill(info);
+                self.slots.fill(slot);
+            }
+            return Ok(header_bytes);
+        }
+
+        let mut state = FrameState::new(header.mi_rows, header.mi_cols);
+        let cdfs = if header.primary_ref_frame == PRIMARY_REF_NONE {
+            CdfContext::with_base_q_idx(header.quantization.base_q_idx)
+        } else {
+            let slot = header.ref_frame_idx[header.primary_ref_frame as usize] as usize;
+            if self.refs[slot].mi_rows == header.mi_rows
+                && self.refs[slot].mi_cols == header.mi_cols
+            {
+                state.prev_segment_ids = self.slots[slot].segment_ids.clone();
+            }
+            let mut cdfs = self.slots[slot].cdfs.clone();
+            cdfs.reset_counters();
+            cdfs
+        };
+        if !header.frame_is_intra() {
+            for i in 0..REFS_PER_FRAME {
+                let info = &self.refs[header.ref_frame_idx[i] as usize];
+                state.ref_scaled[i + 1] = is_scaled(&header, info);
+            }
+            if header.use_ref_frame_mvs {
+                let slots = std::array::from_fn(|i| (&self.refs[i], &self.slots[i].mvs));
+                state.motion_field = Some(MotionField::estimate(&header, &slots));
+            }
+        }
+
+        self.frame = Some(FrameInProgress {
+            header,
+            cdfs,
+            saved_cdfs: None,
+            state,
+            superblocks: Vec::new(),
+        });
+        Ok(header_bytes)
+    }
+
+    /// Decode the tiles of a tile group of the current frame
+    fn decode_tile_group(&mut self, data: &[u8]) -> Result<Option<DecodedFrame>> {
+        let (Some(seq), Some(frame)) = (self.seq.as_ref(), self.frame.as_mut()) else {
+            return Ok(None);
+        };
+        let fh = &frame.header;
+        let group = parse_tile_group(data, fh.tile_info.clone())?;
+        for tile in &group.tiles {
+            let sd = SymbolDecoder::new(&tile.data, frame.cdfs.clone(), fh.disable_cdf_update)?;
+            let (row, col) = (tile.tile_row as usize, tile.tile_col as usize);
+            let mut decoder = TileDecoder::new(sd, seq, fh, &mut frame.state, row, col);
+            frame.superblocks.extend(decoder.decode_tile()?);
+            let cdfs = decoder.finish()?;
+            let tile_num = tile.tile_row * fh.tile_info.tile_cols + tile.tile_col;
+            if tile_num == fh.tile_info.context_update_tile_id {
+                frame.saved_cdfs = Some(cdfs);
+            }
+        }
+        if (group.tile_end_idx as usize) + 1 < fh.tile_info.tile_count() {
+            return Ok(None);
+        }
+        Ok(self.frame.take().map(|frame| self.finish_frame(frame)))
+    }
+
+    /// Save the state of a decoded frame into the refreshed slots
+    fn finish_frame(&mut self, frame: FrameInProgress) -> DecodedFrame {
+        let fh = frame.header;
+        let cdfs = match frame.saved_cdfs {
+            Some(cdfs) if !fh.disable_frame_end_update_cdf => cdfs,
+            _ => frame.cdfs,
+        };
+        let segmentation = &fh.segmentation;
+        let segment_ids = if segmentation.enabled && !segmentation.update_map {
+            if frame.state.prev_segment_ids.is_empty() {
+                vec![0; frame.state.mi.len()]
+            } else {
+                frame.state.prev_segment_ids.clone()
+            }
+        } else {
+            frame.state.segment_ids()
+        };
+        let slot = SlotState {
+            cdfs,
+            mvs: SavedMvs::store(&fh, &frame.state),
+            segment_ids,
+        };
+        let info = fh.ref_frame_info();
+        for i in 0..NUM_REF_FRAMES {
+            if fh.refresh_frame_flags & (1 << i) != 0 {
+                self.refs[i] = info.clone();
+                self.slots[i] = slot.clone();
+            }
+        }
+        DecodedFrame {
+            header: fh,
+            superblocks: frame.superblocks,
+        }
+    }
+}
+
+/// Whether a reference frame has a different size than the current frame
+fn is_scaled(fh: &UncompressedHeader, reference: &RefFrameInfo) -> bool {
+    let (width, height) = (fh.frame_width.max(1), fh.frame_height.max(1));
+    let x_scale = ((reference.upscaled_width << REF_SCALE_SHIFT) + width / 2) / width;
+    let y_scale = ((reference.frame_height << REF_SCALE_SHIFT) + height / 2) / height;
+    x_scale != 1 << REF_SCALE_SHIFT || y_scale != 1 << REF_SCALE_SHIFT
+}
@@ -25,11 +25,14 @@
 #![allow(clippy::too_many_arguments)]
 #![allow(clippy::ptr_arg)]
 
+pub mod annexb;
 pub mod bitreader;
 pub mod dependency;
+pub mod frame_decoder;
 pub mod frame_header;
 pub mod ivf;
 pub mod leb128;
+pub mod metadata;
 pub mod obu;
 pub mod overlay_extraction;
 pub mod sequence;
@@ -37,17 +40,26 @@ pub mod symbol;
 pub mod syntax_parser;
 pub mod tile;
 pub mod types;
+pub mod uncompressed_header;
 
 // Re-export main types
+pub use annexb::{
+    annex_b_to_low_overhead, annex_b_unit_nodes, is_annex_b, parse_annex_b, AnnexBObu, FrameUnit,
+    TemporalUnit,
+};
 pub use bitreader::BitReader;
 pub use dependency::{
     extract_required_obus, DependencyGraph, ExtractionRequest, ExtractionResult, FrameNode,
 };
+pub use frame_decoder::{DecodedFrame, FrameDecoder};
 pub use frame_header::{parse_frame_header_basic, FrameHeader, FrameType};
 pub use ivf::{
     extract_obu_data, is_av1_ivf, is_ivf, parse_ivf_frames, parse_ivf_header, IvfFrame, IvfHeader,
 };
 pub use leb128::{decode_uleb128, encode_uleb128, leb128_size};
+pub use metadata::{
+    parse_metadata_obu, stream_metadata, MetadataObu, MetadataPayload, MetadataType, T35PayloadKind,
+};
 pub use obu::{
     parse_all_obus, parse_all_obus_resilient, parse_obu, parse_obu_header, Obu, ObuHeader,
     ObuIterator, ObuType,
@@ -59,23 +71,24 @@ pub use overlay_extraction::{
     extract_transform_grid, extract_transform_grid_from_parsed,
 };
 pub use sequence::{parse_sequence_header, Av1Profile, ColorConfig, SequenceHeader};
-pub use symbol::{ArithmeticDecoder, CdfContext, PartitionCdf, SymbolDecoder};
+pub use symbol::{ArithmeticDecoder, CdfContext, SymbolDecoder};
 pub use syntax_parser::{
     parse_bitstream_syntax, parse_frame_header_syntax, parse_obu_syntax,
-    parse_sequence_header_syntax, TrackedBitReader,
+    parse_sequence_header_syntax, parse_uncompressed_header_syntax, SyntaxContext,
+    TrackedBitReader,
 };
 pub use tile::{
-    parse_coding_unit, parse_partition_tree, parse_superblock, parse_tile_group,
-    partition_tree_to_grid, BlockSize, CodingUnit, MotionVector, PartitionNode, PartitionType,
-    PredictionMode, RefFrame, Superblock, SuperblockSize, Tile, TileGroup, TileInfo,
+    parse_tile_group, partition_tree_to_grid, BlockSize, CodingUnit, MotionVector, PartitionNode,
+    PartitionType, PredictionMode, RefFrame, Superblock, SuperblockSize, Tile, TileGroup, TileInfo,
+    TxSize,
 };
 pub use types::{Qp, QuarterPel, TimestampPts};
 
 /// Parses an AV1 bitstream and returns basic information
 ///
 /// This is a convenience function that parses all OBUs and extracts
-/// the sequence header if present. Supports raw OBU format, IVF, MP4,
-/// MOV, MKV, WebM, and TS container formats.
+/// the sequence header if present. Supports raw OBU format (low-overhead
+/// or Annex B), IVF, MP4, MOV, MKV, WebM, and TS container formats.
 pub fn parse_av1(data: &[u8]) -> bitvue_core::Result<Av1Info> {
     // Check format and extract OBU data (order matters for detection)
     let obu_data = if is_ivf(data) {
@@ -90,6 +103,9 @@ pub fn parse_av1(data: &[u8]) -> bitvue_core::Result<Av1Info> {
     } else if is_mkv(data) {
         // MKV/WebM container (same format)
         std::borrow::Cow::Owned(extract_obu_data_from_mkv(data)?)
+    } else if is_annex_b(data) {
+        // Length-delimited OBUs
+        std::borrow::Cow::Owned(annex_b_to_low_overhead(data)?)
     } else {
         // Raw OBU format
         std::borrow::Cow::Borrowed(data)
@@ -153,6 +169,11 @@ impl Av1Info {
     pub fn profile(&self) -> Option<&Av1Profile> {
         self.sequence_header.as_ref().map(|s| &s.profile)
     }
+
+    /// Collects colour information and per-frame metadata OBUs
+    pub fn stream_metadata(&self) -> bitvue_core::metadata::StreamMetadata {
+        stream_metadata(&self.obus)
+    }
 }
 
 /// Check if data is an MP4 file
@@ -0,0 +1,741 @@
+//! AV1 Metadata OBU parsing
+//!
+//! Reference: AV1 Specification Section 5.8 (metadata OBU syntax) and
+//! Section 6.7 (metadata OBU semantics).
+//!
+//! Parsed metadata is converted into `bitvue_core::metadata` structures so
+//! AV1 streams expose HDR and user data the same way SEI-based codecs do.
+
+use serde::{Deserialize, Serialize};
+
+use bitvue_core::metadata::{
+    ContentLightLevel, HdrFormat, MasteringDisplayMetadata, SeiData, SeiMessage, SeiMessageType,
+    StreamMetadata,
+};
+use bitvue_core::{BitvueError, Result};
+
+use crate::bitreader::BitReader;
+use crate::leb128::decode_uleb128;
+use crate::obu::{Obu, ObuType};
+
+/// scalability_mode_idc value that signals an explicit scalability structure
+pub const SCALABILITY_SS: u8 = 14;
+
+/// ITU-T T.35 country code for the United States
+const T35_COUNTRY_CODE_US: u8 = 0xB5;
+
+/// Metadata type (Section 6.7.1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MetadataType {
+    /// METADATA_TYPE_HDR_CLL (1)
+    HdrCll,
+    /// METADATA_TYPE_HDR_MDCV (2)
+    HdrMdcv,
+    /// METADATA_TYPE_SCALABILITY (3)
+    Scalability,
+    /// METADATA_TYPE_ITUT_T35 (4)
+    ItutT35,
+    /// METADATA_TYPE_TIMECODE (5)
+    Timecode,
+    /// Unregistered user private (6-31)
+    UnregisteredUserPrivate(u64),
+    /// Reserved (0, 32 and above)
+    Reserved(u64),
+}
+
+impl MetadataType {
+    /// Converts a coded metadata_type to MetadataType
+    pub fn from_u64(value: u64) -> Self {
+        match value {
+            1 => MetadataType::HdrCll,
+            2 => MetadataType::HdrMdcv,
+            3 => MetadataType::Scalability,
+            4 => MetadataType::ItutT35,
+            5 => MetadataType::Timecode,
+            6..=31 => MetadataType::UnregisteredUserPrivate(value),
+            v => MetadataType::Reserved(v),
+        }
+    }
+
+    /// Returns the coded metadata_type value
+    pub fn to_u64(&self) -> u64 {
+        match self {
+            MetadataType::HdrCll => 1,
+            MetadataType::HdrMdcv => 2,
+            MetadataType::Scalability => 3,
+            MetadataType::ItutT35 => 4,
+            MetadataType::Timecode => 5,
+            MetadataType::UnregisteredUserPrivate(v) | MetadataType::Reserved(v) => *v,
+        }
+    }
+
+    /// Returns the spec name of the metadata type
+    pub fn name(&self) -> &'static str {
+        match self {
+            MetadataType::HdrCll => "METADATA_TYPE_HDR_CLL",
+            MetadataType::HdrMdcv => "METADATA_TYPE_HDR_MDCV",
+            MetadataType::Scalability => "METADATA_TYPE_SCALABILITY",
+            MetadataType::ItutT35 => "METADATA_TYPE_ITUT_T35",
+            MetadataType::Timecode => "METADATA_TYPE_TIMECODE",
+            MetadataType::UnregisteredUserPrivate(_) => "Unregistered user private",
+            MetadataType::Reserved(_) => "Reserved",
+        }
+    }
+}
+
+/// Content light level (metadata_hdr_cll)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HdrCll {
+    /// Maximum content light level in cd/m²
+    pub max_cll: u16,
+    /// Maximum frame-average light level in cd/m²
+    pub max_fall: u16,
+}
+
+/// Mastering display colour volume (metadata_hdr_mdcv)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HdrMdcv {
+    /// Red, green and blue x chromaticity (0.16 fixed point)
+    pub primary_chromaticity_x: [u16; 3],
+    /// Red, green and blue y chromaticity (0.16 fixed point)
+    pub primary_chromaticity_y: [u16; 3],
+    /// White point x chromaticity (0.16 fixed point)
+    pub white_point_chromaticity_x: u16,
+    /// White point y chromaticity (0.16 fixed point)
+    pub white_point_chromaticity_y: u16,
+    /// Maximum luminance in cd/m² (24.8 fixed point)
+    pub luminance_max: u32,
+    /// Minimum luminance in cd/m² (18.14 fixed point)
+    pub luminance_min: u32,
+}
+
+impl HdrMdcv {
+    /// Converts to SMPTE ST 2086 units (0.00002 chromaticity, 0.0001 cd/m²)
+    pub fn to_mastering_display(&self) -> MasteringDisplayMetadata {
+        let chroma = |v: u16| ((v as u32 * 50000 + (1 << 15)) >> 16) as u16;
+        let luma =
+            |v: u32, frac_bits: u32| ((v as u64 * 10000) >> frac_bits).min(u32::MAX as u64) as u32;
+        let (x, y) = (self.primary_chromaticity_x, self.primary_chromaticity_y);
+
+        MasteringDisplayMetadata {
+            red_x: chroma(x[0]),
+            red_y: chroma(y[0]),
+            green_x: chroma(x[1]),
+            green_y: chroma(y[1]),
+            blue_x: chroma(x[2]),
+            blue_y: chroma(y[2]),
+            white_point_x: chroma(self.white_point_chromaticity_x),
+            white_point_y: chroma(self.white_point_chromaticity_y),
+            max_luminance: luma(self.luminance_max, 8),
+            min_luminance: luma(self.luminance_min, 14),
+        }
+    }
+}
+
+/// One spatial layer of a scalability structure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SpatialLayer {
+    /// spatial_layer_max_width (if dimensions are present)
+    pub max_width: Option<u16>,
+    /// spatial_layer_max_height (if dimensions are present)
+    pub max_height: Option<u16>,
+    /// spatial_layer_ref_id (if descriptions are present)
+    pub ref_id: Option<u8>,
+}
+
+/// One picture of the temporal group in a scalability structure
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemporalGroupEntry {
+    pub temporal_id: u8,
+    pub temporal_switching_up_point_flag: bool,
+    pub spatial_switching_up_point_flag: bool,
+    /// temporal_group_ref_pic_diff for each reference
+    pub ref_pic_diff: Vec<u8>,
+}
+
+/// Explicit scalability structure (scalability_structure)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScalabilityStructure {
+    pub spatial_layers_cnt_minus_1: u8,
+    pub spatial_layer_dimensions_present_flag: bool,
+    pub spatial_layer_description_present_flag: bool,
+    pub temporal_group_description_present_flag: bool,
+    /// Per spatial layer dimensions and references
+    pub spatial_layers: Vec<SpatialLayer>,
+    /// Temporal group description (empty if not present)
+    pub temporal_group: Vec<TemporalGroupEntry>,
+}
+
+/// Scalability metadata (metadata_scalability)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScalabilityMetadata {
+    pub scalability_mode_idc: u8,
+    /// Present when scalability_mode_idc is SCALABILITY_SS
+    pub structure: Option<ScalabilityStructure>,
+}
+
+impl ScalabilityMetadata {
+    /// Returns the spec name of scalability_mode_idc (Section 6.7.5)
+    pub fn mode_name(&self) -> &'static str {
+        match self.scalability_mode_idc {
+            0 => "SCALABILITY_L1T2",
+            1 => "SCALABILITY_L1T3",
+            2 => "SCALABILITY_L2T1",
+            3 => "SCALABILITY_L2T2",
+            4 => "SCALABILITY_L2T3",
+            5 => "SCALABILITY_S2T1",
+            6 => "SCALABILITY_S2T2",
+            7 => "SCALABILITY_S2T3",
+            8 => "SCALABILITY_L2T1h",
+            9 => "SCALABILITY_L2T2h",
+            10 => "SCALABILITY_L2T3h",
+            11 => "SCALABILITY_S2T1h",
+            12 => "SCALABILITY_S2T2h",
+            13 => "SCALABILITY_S2T3h",
+            SCALABILITY_SS => "SCALABILITY_SS",
+            _ => "Reserved",
+        }
+    }
+}
+
+/// Known payloads carried in ITU-T T.35 metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum T35PayloadKind {
+    /// SMPTE ST 2094-40 dynamic metadata (HDR10+)
+    Hdr10Plus,
+    /// Dolby Vision RPU
+    DolbyVision,
+    /// Any other registered payload
+    Other,
+}
+
+/// ITU-T T.35 metadata (metadata_itut_t35)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItutT35Metadata {
+    pub country_code: u8,
+    /// Present when country_code is 0xFF
+    pub country_code_extension: Option<u8>,
+    /// itu_t_t35_payload_bytes without the OBU trailing bits
+    pub payload: Vec<u8>,
+}
+
+impl ItutT35Metadata {
+    /// Identifies the payload from its terminal provider codes
+    pub fn kind(&self) -> T35PayloadKind {
+        if self.country_code != T35_COUNTRY_CODE_US || self.payload.len() < 4 {
+            return T35PayloadKind::Other;
+        }
+        let provider_code = u16::from_be_bytes([self.payload[0], self.payload[1]]);
+        match provider_code {
+            // Samsung: provider oriented code 1, application identifier 4
+            0x003C if self.payload.len() >= 5 && self.payload[2..5] == [0x00, 0x01, 0x04] => {
+                T35PayloadKind::Hdr10Plus
+            }
+            // Dolby: provider oriented code 0x00000800
+            0x003B if self.payload.get(2..6) == Some(&[0x00, 0x00, 0x08, 0x00][..]) => {
+                T35PayloadKind::DolbyVision
+            }
+            _ => T35PayloadKind::Other,
+        }
+    }
+}
+
+/// Timecode metadata (metadata_timecode)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimecodeMetadata {
+    pub counting_type: u8,
+    pub full_timestamp_flag: bool,
+    pub discontinuity_flag: bool,
+    pub cnt_dropped_flag: bool,
+    pub n_frames: u16,
+    pub seconds: Option<u8>,
+    pub minutes: Option<u8>,
+    pub hours: Option<u8>,
+    pub time_offset_length: u8,
+    pub time_offset_value: u32,
+}
+
+impl TimecodeMetadata {
+    /// Format as `HH:MM:SS:FF` (`;` before frames when frames were dropped).
+    pub fn format_smpte(&self) -> String {
+        format!(
+            "{:02}:{:02}:{:02}{}{:02}",
+            self.hours.unwrap_or(0),
+            self.minutes.unwrap_or(0),
+            self.seconds.unwrap_or(0),
+            if self.cnt_dropped_flag { ';' } else { ':' },
+            self.n_frames
+        )
+    }
+}
+
+/// Parsed metadata payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MetadataPayload {
+    HdrCll(HdrCll),
+    HdrMdcv(HdrMdcv),
+    Scalability(ScalabilityMetadata),
+    ItutT35(ItutT35Metadata),
+    Timecode(TimecodeMetadata),
+    /// Unregistered or reserved payload bytes
+    Raw(Vec<u8>),
+}
+
+/// A parsed metadata OBU
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataObu {
+    pub metadata_type: MetadataType,
+    /// OBU payload size in bytes
+    pub payload_size: usize,
+    pub payload: MetadataPayload,
+}
+
+impl MetadataObu {
+    /// Returns the content light level in `bitvue_core` form
+    pub fn content_light_level(&self) -> Option<ContentLightLevel> {
+        match &self.payload {
+            MetadataPayload::HdrCll(cll) => Some(ContentLightLevel::new(cll.max_cll, cll.max_fall)),
+            _ => None,
+        }
+    }
+
+    /// Returns the mastering display colour volume in `bitvue_core` form
+    pub fn mastering_display(&self) -> Option<MasteringDisplayMetadata> {
+        match &self.payload {
+            MetadataPayload::HdrMdcv(mdcv) => Some(mdcv.to_mastering_display()),
+            _ => None,
+        }
+    }
+
+    /// Converts to the codec-agnostic message shown by the metadata inspector
+    pub fn to_metadata(&self, byte_offset: u64, frame_idx: Option<usize>) -> SeiMessage {
+        let message_type = match self.metadata_type {
+            MetadataType::HdrCll => SeiMessageType::ContentLightLevelInfo,
+            MetadataType::HdrMdcv => SeiMessageType::MasteringDisplayColourVolume,
+            MetadataType::Scalability => SeiMessageType::ScalabilityInfo,
+            MetadataType::ItutT35 => SeiMessageType::UserDataRegistered,
+            MetadataType::Timecode => SeiMessageType::TimeCode,
+            other => SeiMessageType::Unknown(other.to_u64().min(u32::MAX as u64) as u32),
+        };
+
+        let data = match &self.payload {
+            MetadataPayload::HdrCll(cll) => {
+                SeiData::ContentLightLevel(ContentLightLevel::new(cll.max_cll, cll.max_fall))
+            }
+            MetadataPayload::HdrMdcv(mdcv) => {
+                SeiData::MasteringDisplay(mdcv.to_mastering_display())
+            }
+            MetadataPayload::ItutT35(t35) => SeiData::UserDataRegistered {
+                country_code: t35.country_code,
+                data: t35.payload.clone(),
+            },
+            MetadataPayload::Timecode(tc) => SeiData::TimeCode {
+                timecodes: vec![tc.format_smpte()],
+            },
+            MetadataPayload::Scalability(scalability) => {
+                SeiData::Raw(vec![scalability.scalability_mode_idc])
+            }
+            MetadataPayload::Raw(bytes) => SeiData::Raw(bytes.clone()),
+        };
+
+        SeiMessage {
+            message_type,
+            payload_size: self.payload_size,
+            byte_offset,
+            frame_idx,
+            data,
+        }
+    }
+}
+
+/// Parses a metadata OBU payload (metadata_obu)
+pub fn parse_metadata_obu(data: &[u8]) -> Result<MetadataObu> {
+    let (type_value, type_len) = decode_uleb128(data)?;
+    let metadata_type = MetadataType::from_u64(type_value);
+    let body = &data[type_len..];
+    let mut reader = BitReader::new(body);
+
+    let payload = match metadata_type {
+        MetadataType::HdrCll => MetadataPayload::HdrCll(HdrCll {
+            max_cll: reader.read_bits(16)? as u16,
+            max_fall: reader.read_bits(16)? as u16,
+        }),
+        MetadataType::HdrMdcv => {
+            let mut primary_chromaticity_x = [0u16; 3];
+            let mut primary_chromaticity_y = [0u16; 3];
+            for i in 0..3 {
+                primary_chromaticity_x[i] = reader.read_bits(16)? as u16;
+                primary_chromaticity_y[i] = reader.read_bits(16)? as u16;
+            }
+            MetadataPayload::HdrMdcv(HdrMdcv {
+                primary_chromaticity_x,
+                primary_chromaticity_y,
+                white_point_chromaticity_x: reader.read_bits(16)? as u16,
+                white_point_chromaticity_y: reader.read_bits(16)? as u16,
+                luminance_max: reader.read_bits(32)?,
+                luminance_min: reader.read_bits(32)?,
+            })
+        }
+        MetadataType::Scalability => MetadataPayload::Scalability(parse_scalability(&mut reader)?),
+        MetadataType::ItutT35 => {
+            let payload = strip_trailing_bits(body);
+            let (&country_code, rest) = payload
+                .split_first()
+                .ok_or_else(|| BitvueError::InvalidData("Empty ITU-T T.35 metadata".to_string()))?;
+            let (country_code_extension, rest) = if country_code == 0xFF {
+                let (&ext, rest) = rest.split_first().ok_or_else(|| {
+                    BitvueError::InvalidData("Missing T.35 country code extension".to_string())
+                })?;
+                (Some(ext), rest)
+            } else {
+                (None, rest)
+            };
+            MetadataPayload::ItutT35(ItutT35Metadata {
+                country_code,
+                country_code_extension,
+                payload: rest.to_vec(),
+            })
+        }
+        MetadataType::Timecode => MetadataPayload::Timecode(parse_timecode(&mut reader)?),
+        MetadataType::UnregisteredUserPrivate(_) | MetadataType::Reserved(_) => {
+            MetadataPayload::Raw(strip_trailing_bits(body).to_vec())
+        }
+    };
+
+    Ok(MetadataObu {
+        metadata_type,
+        payload_size: data.len(),
+        payload,
+    })
+}
+
+/// metadata_scalability and scalability_structure
+fn parse_scalability(reader: &mut BitReader) -> Result<ScalabilityMetadata> {
+    let scalability_mode_idc = reader.read_bits(8)? as u8;
+    if scalability_mode_idc != SCALABILITY_SS {
+        return Ok(ScalabilityMetadata {
+            scalability_mode_idc,
+            structure: None,
+        });
+    }
+
+    let spatial_layers_cnt_minus_1 = reader.read_bits(2)? as u8;
+    let spatial_layer_dimensions_present_flag = reader.read_bit()?;
+    let spatial_layer_description_present_flag = reader.read_bit()?;
+    let temporal_group_description_present_flag = reader.read_bit()?;
+    reader.skip_bits(3)?; // scalability_structure_reserved_3bits
+
+    let mut spatial_layers = vec![SpatialLayer::default(); spatial_layers_cnt_minus_1 as usize + 1];
+    if spatial_layer_dimensions_present_flag {
+        for layer in &mut spatial_layers {
+            layer.max_width = Some(reader.read_bits(16)? as u16);
+            layer.max_height = Some(reader.read_bits(16)? as u16);
+        }
+    }
+    if spatial_layer_description_present_flag {
+        for layer in &mut spatial_layers {
+            layer.ref_id = Some(reader.read_bits(8)? as u8);
+        }
+    }
+
+    let mut temporal_group = Vec::new();
+    if temporal_group_description_present_flag {
+        let temporal_group_size = reader.read_bits(8)?;
+        for _ in 0..temporal_group_size {
+            let temporal_id = reader.read_bits(3)? as u8;
+            let temporal_switching_up_point_flag = reader.read_bit()?;
+            let spatial_switching_up_point_flag = reader.read_bit()?;
+            let ref_cnt = reader.read_bits(3)?;
+            let ref_pic_diff = (0..ref_cnt)
+                .map(|_| reader.read_bits(8).map(|d| d as u8))
+                .collect::<Result<Vec<_>>>()?;
+            temporal_group.push(TemporalGroupEntry {
+                temporal_id,
+                temporal_switching_up_point_flag,
+                spatial_switching_up_point_flag,
+                ref_pic_diff,
+            });
+        }
+    }
+
+    Ok(ScalabilityMetadata {
+        scalability_mode_idc,
+        structure: Some(ScalabilityStructure {
+            spatial_layers_cnt_minus_1,
+            spatial_layer_dimensions_present_flag,
+            spatial_layer_description_present_flag,
+            temporal_group_description_present_flag,
+            spatial_layers,
+            temporal_group,
+        }),
+    })
+}
+
+/// metadata_timecode
+fn parse_timecode(reader: &mut BitReader) -> Result<TimecodeMetadata> {
+    let counting_type = reader.read_bits(5)? as u8;
+    let full_timestamp_flag = reader.read_bit()?;
+    let discontinuity_flag = reader.read_bit()?;
+    let cnt_dropped_flag = reader.read_bit()?;
+    let n_frames = reader.read_bits(9)? as u16;
+
+    let (mut seconds, mut minutes, mut hours) = (None, None, None);
+    if full_timestamp_flag {
+        seconds = Some(reader.read_bits(6)? as u8);
+        minutes = Some(reader.read_bits(6)? as u8);
+        hours = Some(reader.read_bits(5)? as u8);
+    } else if reader.read_bit()? {
+        seconds = Some(reader.read_bits(6)? as u8);
+        if reader.read_bit()? {
+            minutes = Some(reader.read_bits(6)? as u8);
+            if reader.read_bit()? {
+                hours = Some(reader.read_bits(5)? as u8);
+            }
+        }
+    }
+
+    let time_offset_length = reader.read_bits(5)? as u8;
+    let time_offset_value = if time_offset_length > 0 {
+        reader.read_bits(time_offset_length)?
+    } else {
+        0
+    };
+
+    Ok(TimecodeMetadata {
+        counting_type,
+        full_timestamp_flag,
+        discontinuity_flag,
+        cnt_dropped_flag,
+        n_frames,
+        seconds,
+        minutes,
+        hours,
+        time_offset_length,
+        time_offset_value,
+    })
+}
+
+/// Removes the byte-aligned trailing_bits() that end a metadata OBU
+fn strip_trailing_bits(data: &[u8]) -> &[u8] {
+    match data.iter().rposition(|&b| b != 0) {
+        Some(last) if data[last] == 0x80 => &data[..last],
+        Some(last) => &data[..=last],
+        None => &[],
+    }
+}
+
+/// Collects stream-level metadata from parsed OBUs
+///
+/// Colour information comes from the first sequence header. Metadata OBUs
+/// apply to the temporal unit they appear in, so each one is attached to the
+/// first frame of its temporal unit.
+pub fn stream_metadata(obus: &[Obu]) -> StreamMetadata {
+    use bitvue_core::metadata::{ColorPrimaries, MatrixCoefficients, TransferCharacteristics};
+
+    let mut metadata = StreamMetadata::new();
+
+    let sequence_header = obus
+        .iter()
+        .filter(|obu| obu.header.obu_type == ObuType::SequenceHeader)
+        .find_map(|obu| crate::sequence::parse_sequence_header(&obu.payload).ok());
+    if let Some(seq) = &sequence_header {
+        let cc = &seq.color_config;
+        metadata.bit_depth = Some(cc.bit_depth);
+        metadata.chroma_subsampling = Some(cc.chroma_subsampling_str().to_string());
+        metadata.video_full_range = Some(cc.color_range);
+        metadata.color_primaries = Some(ColorPrimaries::from_code(cc.color_primaries.to_u8()));
+        metadata.transfer_characteristics = Some(TransferCharacteristics::from_code(
+            cc.transfer_characteristics.to_u8(),
+        ));
+        metadata.matrix_coefficients = Some(MatrixCoefficients::from_code(
+            cc.matrix_coefficients.to_u8(),
+        ));
+    }
+
+    let mut frame_count = 0;
+    let mut tu_frame: Option<usize> = None;
+    let (mut hdr10_plus, mut dolby_vision) = (false, false);
+    for obu in obus {
+        match obu.header.obu_type {
+            ObuType::TemporalDelimiter => tu_frame = None,
+            ObuType::Frame | ObuType::FrameHeader => {
+                tu_frame.get_or_insert(frame_count);
+                frame_count += 1;
+            }
+            ObuType::Metadata => {
+                let Some(parsed) = &obu.metadata else {
+                    continue;
+                };
+                if metadata.mastering_display.is_none() {
+                    metadata.mastering_display = parsed.mastering_display();
+                }
+                if metadata.content_light_level.is_none() {
+                    metadata.content_light_level = parsed.content_light_level();
+                }
+                match &parsed.payload {
+                    MetadataPayload::ItutT35(t35) => match t35.kind() {
+                        T35PayloadKind::Hdr10Plus => hdr10_plus = true,
+                        T35PayloadKind::DolbyVision => dolby_vision = true,
+                        T35PayloadKind::Other => {}
+                    },
+                    MetadataPayload::Scalability(scalability) => {
+                        metadata
+                            .custom
+                            .entry("scalability_mode".to_string())
+                            .or_insert_with(|| scalability.mode_name().to_string());
+                    }
+                    _ => {}
+                }
+
+                let frame_idx = tu_frame.unwrap_or(frame_count);
+                metadata
+                    .sei_messages
+                    .push(parsed.to_metadata(obu.offset, Some(frame_idx)));
+            }
+            _ => {}
+        }
+    }
+
+    let has_static = metadata.mastering_display.is_some() || metadata.content_light_level.is_some();
+    metadata.hdr_format = Some(match metadata.transfer_characteristics {
+        _ if dolby_vision => HdrFormat::DolbyVision,
+        _ if hdr10_plus => HdrFormat::Hdr10Plus,
+        Some(TransferCharacteristics::Pq) if has_static => HdrFormat::Hdr10,
+        Some(TransferCharacteristics::Pq) => HdrFormat::Pq,
+        Some(TransferCharacteristics::Hlg) => HdrFormat::Hlg,
+        _ => HdrFormat::Sdr,
+    });
+
+    metadata
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::obu::parse_all_obus;
+
+    fn metadata_obu(payload: &[u8]) -> Vec<u8> {
+        // obu_type = 5, has_size_field = 1
+        let mut obu = vec![0x2A, payload.len() as u8];
+        obu.extend_from_slice(payload);
+        obu
+    }
+
+    #[test]
+    fn test_parse_hdr_metadata() {
+        let cll = parse_metadata_obu(&[0x01, 0x03, 0xE8, 0x01, 0x90, 0x80]).unwrap();
+        assert_eq!(cll.metadata_type, MetadataType::HdrCll);
+        let level = cll.content_light_level().unwrap();
+        assert_eq!((level.max_cll, level.max_fall), (1000, 400));
+
+        // BT.2020 primaries, D65 white point, 1000 / 0.005 cd/m²
+        let mut mdcv = vec![0x02];
+        for v in [
+            0xB53Fu16, 0x4AC1, 0x2B85, 0xCC08, 0x2189, 0x0BC7, 0x500D, 0x5439,
+        ] {
+            mdcv.extend_from_slice(&v.to_be_bytes());
+        }
+        mdcv.extend_from_slice(&(1000u32 << 8).to_be_bytes());
+        mdcv.extend_from_slice(&82u32.to_be_bytes());
+        mdcv.push(0x80);
+        let md = parse_metadata_obu(&mdcv)
+            .unwrap()
+            .mastering_display()
+            .unwrap();
+        assert_eq!((md.red_x, md.red_y), (35400, 14600));
+        assert_eq!((md.white_point_x, md.white_point_y), (15635, 16450));
+        assert_eq!(md.max_luminance, 10_000_000);
+        assert_eq!(md.min_luminance, 50);
+    }
+
+    #[test]
+    fn test_parse_scalability_structure() {
+        // SCALABILITY_SS, 2 spatial layers with dimensions, 2-picture temporal group
+        let data = [
+            0x03,
+            0x0E,
+            0b0110_1000,
+            0x01,
+            0x40,
+            0x00,
+            0xB4,
+            0x02,
+            0x80,
+            0x01,
+            0x68,
+            0x02,
+            0b0000_0000,
+            0b0011_1001,
+            0x01,
+            0x80,
+        ];
+        let parsed = parse_metadata_obu(&data).unwrap();
+        let MetadataPayload::Scalability(scalability) = &parsed.payload else {
+            panic!("expected scalability metadata");
+        };
+        assert_eq!(scalability.mode_name(), "SCALABILITY_SS");
+        let structure = scalability.structure.as_ref().unwrap();
+        assert_eq!(structure.spatial_layers.len(), 2);
+        assert_eq!(structure.spatial_layers[1].max_width, Some(640));
+        assert_eq!(structure.spatial_layers[1].max_height, Some(360));
+        assert_eq!(structure.temporal_group.len(), 2);
+        assert!(structure.temporal_group[0].ref_pic_diff.is_empty());
+        assert_eq!(structure.temporal_group[1].temporal_id, 1);
+        assert_eq!(structure.temporal_group[1].ref_pic_diff, vec![1]);
+    }
+
+    #[test]
+    fn test_parse_t35_and_timecode() {
+        let hdr10_plus =
+            parse_metadata_obu(&[0x04, 0xB5, 0x00, 0x3C, 0x00, 0x01, 0x04, 0x01, 0x80]).unwrap();
+        let MetadataPayload::ItutT35(t35) = &hdr10_plus.payload else {
+            panic!("expected T.35 metadata");
+        };
+        assert_eq!(t35.kind(), T35PayloadKind::Hdr10Plus);
+        assert_eq!(t35.payload, vec![0x00, 0x3C, 0x00, 0x01, 0x04, 0x01]);
+
+        let dovi =
+            parse_metadata_obu(&[0x04, 0xB5, 0x00, 0x3B, 0x00, 0x00, 0x08, 0x00, 0x37, 0x80])
+                .unwrap();
+        let MetadataPayload::ItutT35(t35) = &dovi.payload else {
+            panic!("expected T.35 metadata");
+        };
+        assert_eq!(t35.kind(), T35PayloadKind::DolbyVision);
+
+        // counting_type 0, full timestamp, n_frames 12, 10:20:30, no offset
+        let tc = parse_metadata_obu(&[0x05, 0x04, 0x06, 0x3C, 0xA2, 0x80, 0x80]).unwrap();
+        let MetadataPayload::Timecode(timecode) = tc.payload else {
+            panic!("expected timecode metadata");
+        };
+        assert_eq!(timecode.format_smpte(), "10:20:30:12");
+    }
+
+    #[test]
+    fn test_stream_metadata_frame_association() {
+        let mut data = Vec::new();
+        // TU 0: TD, CLL, frame header
+        data.extend_from_slice(&[0x12, 0x00]);
+        data.extend_from_slice(&metadata_obu(&[0x01, 0x03, 0xE8, 0x01, 0x90, 0x80]));
+        data.extend_from_slice(&[0x1A, 0x01, 0x10]);
+        // TU 1: TD, frame header, HDR10+ T.35 after the frame
+        data.extend_from_slice(&[0x12, 0x00]);
+        data.extend_from_slice(&[0x1A, 0x01, 0x10]);
+        data.extend_from_slice(&metadata_obu(&[
+            0x04, 0xB5, 0x00, 0x3C, 0x00, 0x01, 0x04, 0x01, 0x80,
+        ]));
+
+        let obus = parse_all_obus(&data).unwrap();
+        assert!(obus[1].metadata.is_some());
+
+        let metadata = stream_metadata(&obus);
+        assert_eq!(metadata.sei_messages.len(), 2);
+        assert_eq!(metadata.sei_messages[0].frame_idx, Some(0));
+        assert_eq!(
+            metadata.sei_messages[0].message_type,
+            SeiMessageType::ContentLightLevelInfo
+        );
+        assert_eq!(metadata.sei_messages[1].frame_idx, Some(1));
+        assert_eq!(metadata.content_light_level.unwrap().max_cll, 1000);
+        assert_eq!(metadata.hdr_format, Some(HdrFormat::Hdr10Plus));
+    }
+}
@@ -149,6 +149,8 @@ pub struct Obu {
     /// Parsed frame header (only for Frame/FrameHeader OBUs)
     #[serde(skip)]
     pub frame_header: Option<crate::frame_header::FrameHeader>,
+    /// Parsed metadata (only for Metadata OBUs)
+    pub metadata: Option<crate::metadata::MetadataObu>,
 }
 
 impl Obu {
@@ -329,6 +331,12 @@ pub fn parse_obu(data: &[u8], offset: usize) -> Result<(Obu, usize)> {
             (None, None)
         };
 
+    let metadata = if header.obu_type == ObuType::Metadata {
+        crate::metadata::parse_metadata_obu(&payload).ok()
+    } else {
+        None
+    };
+
     let obu = Obu {
         header,
         payload_size,
@@ -337,6 +345,7 @@ pub fn parse_obu(data: &[u8], offset: usize) -> Result<(Obu, usize)> {
         payload,
         frame_type,
         frame_header,
+        metadata,
     };
 
     Ok((obu, total_size))
@@ -9,85 +9,43 @@ use bitvue_core::BitvueError;
 
 use super::cache::{compute_cache_key, get_or_parse_coding_units};
 use super::parser::ParsedFrame;
+use crate::frame_decoder::{DecodedFrame, FrameDecoder};
 
-/// Parse all coding units from tile data
+/// Decode the last frame of the OBU data
 ///
-/// Uses thread-safe LRU cache to avoid re-parsing the same tile data
+/// The data must start at a sequence header and include every frame the
+/// last one depends on, as the CDFs, motion vectors and segmentation maps
+/// of reference frames are carried over.
+pub fn decode_last_frame(parsed: &ParsedFrame) -> Result<DecodedFrame, BitvueError> {
+    FrameDecoder::new()
+        .decode(&parsed.obu_data)?
+        .pop()
+        .ok_or_else(|| BitvueError::InvalidData("No decodable frame in OBU data".into()))
+}
+
+/// Parse all coding units of the last frame in the OBU data
+///
+/// Uses thread-safe LRU cache to avoid re-decoding the same data
 /// when extracting multiple overlays.
 ///
 /// Returns `Arc<Vec<CodingUnit>>` for O(1) cloning on cache hits.
 /// Use `&*result` or `result.as_ref()` to access the slice of coding units.
-/// This is used by QP, MV, and prediction mode grid extraction.
+/// This is used by QP, MV, prediction mode and transform grid extraction.
 pub fn parse_all_coding_units(
     parsed: &ParsedFrame,
 ) -> Result<Arc<Vec<crate::tile::CodingUnit>>, BitvueError> {
     let base_qp = parsed.frame_type.base_qp.unwrap_or(128) as i16;
-    let cache_key = compute_cache_key(&parsed.tile_data, base_qp);
-
-    // Arc-clone is cheap (just reference count increment, no data copy)
-    let tile_data = Arc::clone(&parsed.tile_data);
-    let sb_size = parsed.dimensions.sb_size;
-    let sb_cols = parsed.dimensions.sb_cols;
-    let sb_rows = parsed.dimensions.sb_rows;
-    let is_key_frame = parsed.frame_type.is_intra_only;
-    let delta_q_enabled = parsed.delta_q_enabled;
+    let cache_key = compute_cache_key(&parsed.obu_data, base_qp);
 
     // Use get_or_parse helper for cache pattern
     get_or_parse_coding_units(cache_key, || {
-        let mut all_cus = Vec::new();
-
-        // Pre-allocate capacity based on superblock count
-        let estimated_cus = (sb_cols * sb_rows) as usize * 4;
-        all_cus.reserve(estimated_cus);
-
-        // Create SymbolDecoder for tile data
-        let mut decoder = crate::SymbolDecoder::new(&tile_data)?;
-
-        // Track running QP value across superblocks
-        let mut current_qp = base_qp;
-
-        // Create MV predictor context
-        let mut mv_ctx = crate::tile::MvPredictorContext::new(sb_cols, sb_rows);
-
-        // Parse each superblock
-        for sb_y in 0..sb_rows {
-            for sb_x in 0..sb_cols {
-                let sb_pixel_x = sb_x * sb_size;
-                let sb_pixel_y = sb_y * sb_size;
-
-                // Try to parse the superblock
-                match crate::parse_superblock(
-                    &mut decoder,
-                    sb_pixel_x,
-                    sb_pixel_y,
-                    sb_size,
-                    is_key_frame,
-                    current_qp,
-                    delta_q_enabled,
-                    &mut mv_ctx,
-                ) {
-                    Ok((sb, new_qp)) => {
-                        // Collect all coding units from this superblock
-                        all_cus.extend(sb.coding_units);
-                        current_qp = new_qp;
-                    }
-                    Err(e) => {
-                        tracing::debug!(
-                            "Failed to parse superblock ({}, {}): {}, skipping",
-                            sb_pixel_x,
-                            sb_pixel_y,
-                            e
-                        );
-                        // Continue parsing other superblocks
-                    }
-                }
-            }
-        }
+        let frame = decode_last_frame(parsed)?;
+        let all_cus: Vec<_> = frame.coding_units().cloned().collect();
 
         tracing::debug!(
-            "Parsed {} coding units from tile data (final QP: {})",
+            "Parsed {} coding units from {} superblocks",
             all_cus.len(),
-            current_qp
+            frame.superblocks.len()
         );
         Ok(all_cus)
     })
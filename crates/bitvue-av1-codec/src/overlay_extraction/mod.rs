@@ -73,6 +73,7 @@ pub fn strict_mode_enabled() -> bool {
 }
 
 // Re-export public API
+pub use cu_parser::parse_all_coding_units;
 pub use mv_extractor::{extract_mv_grid, extract_mv_grid_from_parsed};
 pub use parser::{
     extract_pixel_info, FrameDimensions, FrameTypeInfo, ObuRef, ParsedFrame, PixelInfo,
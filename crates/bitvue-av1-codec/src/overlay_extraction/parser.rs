@@ -22,6 +22,8 @@ pub struct ParsedFrame {
     /// Frame type information
     pub frame_type: FrameTypeInfo,
     /// Tile group data (shared reference to avoid copies in QP/MV extraction)
+    ///
+    /// Frame OBU payloads are included whole, frame header first.
     pub tile_data: Arc<[u8]>,
     /// Whether delta Q is enabled for this frame
     pub delta_q_enabled: bool,
@@ -147,6 +149,9 @@ impl ParsedFrame {
                         frame_type.base_qp = frame_hdr.base_q_idx;
                         delta_q_enabled = frame_hdr.delta_q_present;
                     }
+                    if obu.header.obu_type == ObuType::Frame {
+                        tile_data.extend_from_slice(&obu.payload);
+                    }
                 }
                 ObuType::TileGroup => {
                     tile_data.extend_from_slice(&obu.payload);
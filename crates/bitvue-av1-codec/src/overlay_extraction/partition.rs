@@ -3,17 +3,16 @@
 //! Provides functions to extract partition trees, prediction modes,
 //! and transform sizes from AV1 bitstreams.
 
-use std::sync::Arc;
-
 use bitvue_core::{
     limits::{AV1_BLOCK_SIZE, MAX_GRID_BLOCKS, MAX_GRID_DIMENSION},
     partition_grid::{PartitionGrid, PartitionType},
     BitvueError,
 };
 
-use super::cache::{compute_cache_key, get_or_parse_coding_units};
+use super::cu_parser::{decode_last_frame, parse_all_coding_units};
 use super::parser::ParsedFrame;
-use crate::tile::{BlockSize, PredictionMode, TxSize};
+use crate::tile::partition::add_partition_tree_to_grid;
+use crate::tile::{PredictionMode, TxSize};
 
 /// Extract Partition Grid from AV1 bitstream data
 ///
@@ -105,125 +104,22 @@ pub fn extract_partition_grid_from_parsed(
     Ok(grid)
 }
 
-/// Parse partition trees from tile data using SymbolDecoder
-///
-/// Attempts to parse actual partition structures from tile group payload.
+/// Build the partition grid from the decoded partition trees
 fn parse_partition_trees_from_tile_data(
     parsed: &ParsedFrame,
 ) -> Result<PartitionGrid, BitvueError> {
+    let frame = decode_last_frame(parsed)?;
     let mut grid = PartitionGrid::new(
-        parsed.dimensions.width,
-        parsed.dimensions.height,
+        frame.header.upscaled_width,
+        frame.header.frame_height,
         parsed.dimensions.sb_size,
     );
-
-    // Create SymbolDecoder for tile data
-    let mut decoder = crate::SymbolDecoder::new(&parsed.tile_data)?;
-
-    let sb_size = parsed.dimensions.sb_size;
-    let block_size = if sb_size == 128 {
-        BlockSize::Block128x128
-    } else {
-        BlockSize::Block64x64
-    };
-
-    let is_key_frame = parsed.frame_type.is_intra_only;
-
-    // Parse each superblock
-    for sb_y in 0..parsed.dimensions.sb_rows {
-        for sb_x in 0..parsed.dimensions.sb_cols {
-            let sb_pixel_x = sb_x * sb_size;
-            let sb_pixel_y = sb_y * sb_size;
-
-            // Ensure we don't go out of bounds
-            let remaining_w = sb_size.min(parsed.dimensions.width.saturating_sub(sb_pixel_x));
-            let remaining_h = sb_size.min(parsed.dimensions.height.saturating_sub(sb_pixel_y));
-
-            // Adjust for edge superblocks
-            let actual_block_size =
-                if remaining_w < block_size.width() || remaining_h < block_size.height() {
-                    // Adjust to smaller block size at edges
-                    let w = remaining_w.max(block_size.width() / 2);
-                    let h = remaining_h.max(block_size.height() / 2);
-                    match (w, h) {
-                        (w, h) if w <= 32 && h <= 32 => BlockSize::Block32x32,
-                        (w, h) if w <= 16 && h <= 16 => BlockSize::Block16x16,
-                        (w, h) if w <= 8 && h <= 8 => BlockSize::Block8x8,
-                        _ => BlockSize::Block4x4,
-                    }
-                } else {
-                    block_size
-                };
-
-            // Try to parse the superblock
-            // Note: For MVP, we use default QP=128 and delta_q_enabled=false
-            let base_qp = parsed.frame_type.base_qp.unwrap_or(128) as i16;
-
-            // Create MV predictor context (local for partition extraction)
-            let mut mv_ctx = crate::tile::MvPredictorContext::new(
-                parsed.dimensions.sb_cols,
-                parsed.dimensions.sb_rows,
-            );
-
-            let sb_result = crate::parse_superblock(
-                &mut decoder,
-                sb_pixel_x,
-                sb_pixel_y,
-                actual_block_size.width(),
-                is_key_frame,
-                base_qp,
-                false, // delta_q_enabled - not implemented for MVP
-                &mut mv_ctx,
-            );
-
-            match sb_result {
-                Ok((sb, _final_qp)) => {
-                    // Convert partition tree to grid blocks
-                    for cu in &sb.coding_units {
-                        grid.add_block(bitvue_core::partition_grid::PartitionBlock::new(
-                            cu.x,
-                            cu.y,
-                            cu.width,
-                            cu.height,
-                            partition_type_from_prediction_mode(cu.mode),
-                            0,
-                        ));
-                    }
-                }
-                Err(e) => {
-                    // On parse error, add scaffold block
-                    tracing::warn!(
-                        "Failed to parse superblock ({}, {}): {}, using scaffold",
-                        sb_pixel_x,
-                        sb_pixel_y,
-                        e
-                    );
-                    grid.add_block(bitvue_core::partition_grid::PartitionBlock::new(
-                        sb_pixel_x,
-                        sb_pixel_y,
-                        remaining_w,
-                        remaining_h,
-                        PartitionType::None,
-                        0,
-                    ));
-                }
-            }
-        }
+    for sb in &frame.superblocks {
+        add_partition_tree_to_grid(&mut grid, &sb.partition);
     }
-
     Ok(grid)
 }
 
-/// Convert prediction mode to partition type for visualization
-fn partition_type_from_prediction_mode(mode: PredictionMode) -> PartitionType {
-    match mode {
-        PredictionMode::DcPred => PartitionType::None,
-        PredictionMode::VPred => PartitionType::Vert,
-        PredictionMode::HPred => PartitionType::Horz,
-        _ => PartitionType::None,
-    }
-}
-
 /// Prediction Mode Grid for visualization
 #[derive(Debug, Clone)]
 pub struct PredictionModeGrid {
@@ -678,6 +574,8 @@ where
 {
     let grid_w = parsed.dimensions.width.div_ceil(block_w);
     let grid_h = parsed.dimensions.height.div_ceil(block_h);
+    output.clear();
+    output.resize_with((grid_w * grid_h) as usize, || None);
 
     // Build spatial index: map superblock position to relevant CUs
     // This allows O(1) lookup of which CUs to check for each grid block
@@ -736,89 +634,6 @@ where
     Ok(())
 }
 
-/// Parse all coding units from tile data
-///
-/// Per optimize-code skill: Uses thread-safe LRU cache to avoid re-parsing
-/// the same tile data when extracting multiple overlays.
-///
-/// Returns `Arc<Vec<CodingUnit>>` for O(1) cloning on cache hits.
-/// Use `&*result` or `result.as_ref()` to access the slice of coding units.
-/// This is used by prediction mode and transform grid extraction.
-fn parse_all_coding_units(
-    parsed: &ParsedFrame,
-) -> Result<std::sync::Arc<Vec<crate::tile::CodingUnit>>, BitvueError> {
-    let base_qp = parsed.frame_type.base_qp.unwrap_or(128) as i16;
-    let cache_key = compute_cache_key(&parsed.tile_data, base_qp);
-
-    // Clone data needed for parsing (move into closure)
-    let tile_data = Arc::clone(&parsed.tile_data);
-    let sb_size = parsed.dimensions.sb_size;
-    let sb_cols = parsed.dimensions.sb_cols;
-    let sb_rows = parsed.dimensions.sb_rows;
-    let is_key_frame = parsed.frame_type.is_intra_only;
-    let delta_q_enabled = parsed.delta_q_enabled;
-
-    // Per optimize-code skill: Use get_or_parse helper for cache pattern
-    get_or_parse_coding_units(cache_key, || {
-        let mut all_cus = Vec::new();
-
-        // Pre-allocate capacity based on superblock count (per optimize-code)
-        let estimated_cus = (sb_cols * sb_rows) as usize * 4;
-        all_cus.reserve(estimated_cus);
-
-        // Create SymbolDecoder for tile data
-        let mut decoder = crate::SymbolDecoder::new(&tile_data)?;
-
-        // Track running QP value across superblocks
-        let mut current_qp = base_qp;
-
-        // Create MV predictor context
-        let mut mv_ctx = crate::tile::MvPredictorContext::new(sb_cols, sb_rows);
-
-        // Parse each superblock
-        for sb_y in 0..sb_rows {
-            for sb_x in 0..sb_cols {
-                let sb_pixel_x = sb_x * sb_size;
-                let sb_pixel_y = sb_y * sb_size;
-
-                // Try to parse the superblock
-                match crate::parse_superblock(
-                    &mut decoder,
-                    sb_pixel_x,
-                    sb_pixel_y,
-                    sb_size,
-                    is_key_frame,
-                    current_qp,
-                    delta_q_enabled,
-                    &mut mv_ctx,
-                ) {
-                    Ok((sb, new_qp)) => {
-                        // Collect all coding units from this superblock
-                        all_cus.extend(sb.coding_units);
-                        current_qp = new_qp;
-                    }
-                    Err(e) => {
-                        tracing::debug!(
-                            "Failed to parse superblock ({}, {}): {}, skipping",
-                            sb_pixel_x,
-                            sb_pixel_y,
-                            e
-                        );
-                        // Continue parsing other superblocks
-                    }
-                }
-            }
-        }
-
-        tracing::debug!(
-            "Parsed {} coding units from tile data (final QP: {})",
-            all_cus.len(),
-            current_qp
-        );
-        Ok(all_cus)
-    })
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -84,6 +84,25 @@ impl ColorPrimaries {
             v => ColorPrimaries::Reserved(v),
         }
     }
+
+    /// Returns the code point as coded in the color config
+    pub fn to_u8(&self) -> u8 {
+        match self {
+            ColorPrimaries::Bt709 => 1,
+            ColorPrimaries::Unspecified => 2,
+            ColorPrimaries::Bt470M => 4,
+            ColorPrimaries::Bt470Bg => 5,
+            ColorPrimaries::Bt601 => 6,
+            ColorPrimaries::Smpte240 => 7,
+            ColorPrimaries::GenericFilm => 8,
+            ColorPrimaries::Bt2020 => 9,
+            ColorPrimaries::Xyz => 10,
+            ColorPrimaries::Smpte431 => 11,
+            ColorPrimaries::Smpte432 => 12,
+            ColorPrimaries::Ebu3213 => 22,
+            ColorPrimaries::Reserved(v) => *v,
+        }
+    }
 }
 
 /// Transfer characteristics
@@ -133,6 +152,30 @@ impl TransferCharacteristics {
             v => TransferCharacteristics::Reserved(v),
         }
     }
+
+    /// Returns the code point as coded in the color config
+    pub fn to_u8(&self) -> u8 {
+        match self {
+            TransferCharacteristics::Bt709 => 1,
+            TransferCharacteristics::Unspecified => 2,
+            TransferCharacteristics::Bt470M => 4,
+            TransferCharacteristics::Bt470Bg => 5,
+            TransferCharacteristics::Bt601 => 6,
+            TransferCharacteristics::Smpte240 => 7,
+            TransferCharacteristics::Linear => 8,
+            TransferCharacteristics::Log100 => 9,
+            TransferCharacteristics::Log100Sqrt10 => 10,
+            TransferCharacteristics::Iec61966 => 11,
+            TransferCharacteristics::Bt1361 => 12,
+            TransferCharacteristics::Srgb => 13,
+            TransferCharacteristics::Bt202010Bit => 14,
+            TransferCharacteristics::Bt202012Bit => 15,
+            TransferCharacteristics::Smpte2084 => 16,
+            TransferCharacteristics::Smpte428 => 17,
+            TransferCharacteristics::Hlg => 18,
+            TransferCharacteristics::Reserved(v) => *v,
+        }
+    }
 }
 
 /// Matrix coefficients
@@ -176,6 +219,27 @@ impl MatrixCoefficients {
             v => MatrixCoefficients::Reserved(v),
         }
     }
+
+    /// Returns the code point as coded in the color config
+    pub fn to_u8(&self) -> u8 {
+        match self {
+            MatrixCoefficients::Identity => 0,
+            MatrixCoefficients::Bt709 => 1,
+            MatrixCoefficients::Unspecified => 2,
+            MatrixCoefficients::Fcc => 4,
+            MatrixCoefficients::Bt470Bg => 5,
+            MatrixCoefficients::Bt601 => 6,
+            MatrixCoefficients::Smpte240 => 7,
+            MatrixCoefficients::YCgCo => 8,
+            MatrixCoefficients::Bt2020Ncl => 9,
+            MatrixCoefficients::Bt2020Cl => 10,
+            MatrixCoefficients::Smpte2085 => 11,
+            MatrixCoefficients::ChromaDerivedNcl => 12,
+            MatrixCoefficients::ChromaDerivedCl => 13,
+            MatrixCoefficients::ICtCp => 14,
+            MatrixCoefficients::Reserved(v) => *v,
+        }
+    }
 }
 
 /// Chroma sample position
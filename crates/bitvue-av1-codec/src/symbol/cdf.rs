@@ -1,452 +1,428 @@
 //! CDF (Cumulative Distribution Function) Tables
 //!
-//! Per AV1 Specification Section 5.11.44 (Partition CDF)
+//! Per AV1 Specification Section 8.3 (Parsing process for CDF encoded
+//! syntax elements) and Section 10.3 (Default CDF tables)
 //!
-//! CDFs represent probability distributions for symbols.
-//! Each CDF is an array where:
-//! - `cdf[0]` = 0
-//! - `cdf[i]` = cumulative probability for symbols 0..i (scaled to 0..32768)
-//! - `cdf[n]` = 32768 (total probability)
+//! [`CdfContext`] holds every adaptive CDF of a frame. Each CDF is stored
+//! in the specification layout: N cumulative probabilities in ascending
+//! order ending with 32768, followed by the adaptation counter.
 //!
-//! ## Partition CDFs
+//! ## CDF lifecycle
 //!
-//! Partition symbols have different distributions based on:
-//! - Block size (larger blocks more likely to split)
-//! - Context (neighboring partition types)
-//!
-//! For MVP, we use simplified uniform distributions.
+//! - Frames with `primary_ref_frame == PRIMARY_REF_NONE` start from the
+//!   defaults, with the coefficient CDFs selected by `base_q_idx`
+//!   ([`CdfContext::with_base_q_idx`])
+//! - Other frames start from the CDFs saved with the reference frame
+//!   (`load_cdfs`), which are plain clones of the context
+//! - Each tile adapts its own copy; the copy of tile
+//!   `context_update_tile_id` becomes the frame's final CDFs unless
+//!   `disable_frame_end_update_cdf` is set ([`CdfContext::reset_counters`]
+//!   clears the adaptation counters before it is saved)
+
+use super::coeff_cdfs::*;
+use super::default_cdfs::*;
 
 /// CDF scale (2^15)
 pub const CDF_SCALE: u16 = 32768;
 
-/// Partition CDF for a specific block size
+/// Select the default coefficient CDFs for a frame (spec `get_qctx`)
 ///
-/// Contains probability distribution for partition types.
-/// Number of symbols varies by block size:
-/// - 4x4: 1 symbol (NONE only)
-/// - 8x8: 4 symbols (NONE, HORZ, VERT, SPLIT)
-/// - 16x16+: 10 symbols (all partition types)
-#[derive(Debug, Clone)]
-pub struct PartitionCdf {
-    /// CDF array (cumulative probabilities)
-    /// Length = num_symbols + 1
-    pub cdf: Vec<u16>,
-    /// Number of symbols
-    pub num_symbols: usize,
+/// Returns the index of the default coefficient CDF set used for
+/// `base_q_idx`.
+pub fn coeff_cdf_q_ctx(base_q_idx: u8) -> usize {
+    match base_q_idx {
+        0..=20 => 0,
+        21..=60 => 1,
+        61..=120 => 2,
+        _ => 3,
+    }
 }
 
-impl PartitionCdf {
-    /// Create uniform distribution CDF
-    ///
-    /// All symbols have equal probability.
-    pub fn uniform(num_symbols: usize) -> Self {
-        let mut cdf = Vec::with_capacity(num_symbols + 1);
-
-        // First entry is always 0
-        cdf.push(0);
+/// Motion vector CDFs for one MV context
+#[derive(Debug, Clone)]
+pub struct MvCdfs {
+    /// `mv_joint`
+    pub joint: [u16; 5],
+    /// `mv_class`, indexed by component
+    pub class: [[u16; 12]; 2],
+    /// `mv_class0_bit`, indexed by component
+    pub class0_bit: [[u16; 3]; 2],
+    /// `mv_class0_fr`, indexed by component and `mv_class0_bit`
+    pub class0_fr: [[[u16; 5]; 2]; 2],
+    /// `mv_class0_hp`, indexed by component
+    pub class0_hp: [[u16; 3]; 2],
+    /// `mv_sign`, indexed by component
+    pub sign: [[u16; 3]; 2],
+    /// `mv_bit`, indexed by component and bit position
+    pub bits: [[[u16; 3]; 10]; 2],
+    /// `mv_fr`, indexed by component
+    pub fr: [[u16; 5]; 2],
+    /// `mv_hp`, indexed by component
+    pub hp: [[u16; 3]; 2],
+}
 
-        // Divide probability space equally
-        // Handle remainder to ensure last value is exactly CDF_SCALE (32768)
-        let step = CDF_SCALE / num_symbols as u16;
-        let remainder = CDF_SCALE % num_symbols as u16;
-        for i in 1..=num_symbols {
-            let value = if i == num_symbols {
-                CDF_SCALE // Last entry must be exactly 32768
-            } else {
-                // Distribute remainder across early entries
-                let base = step * i as u16;
-                let extra = remainder.min(i as u16 - 1);
-                base + extra
-            };
-            cdf.push(value);
+impl Default for MvCdfs {
+    fn default() -> Self {
+        Self {
+            joint: DEFAULT_MV_JOINT_CDF,
+            class: [DEFAULT_MV_CLASS_CDF; 2],
+            class0_bit: [DEFAULT_MV_CLASS0_BIT_CDF; 2],
+            class0_fr: [DEFAULT_MV_CLASS0_FR_CDF; 2],
+            class0_hp: [DEFAULT_MV_CLASS0_HP_CDF; 2],
+            sign: [DEFAULT_MV_SIGN_CDF; 2],
+            bits: [DEFAULT_MV_BIT_CDF; 2],
+            fr: [DEFAULT_MV_FR_CDF; 2],
+            hp: [DEFAULT_MV_HP_CDF; 2],
         }
-
-        Self { cdf, num_symbols }
-    }
-
-    /// Create biased CDF (NONE is most likely)
-    ///
-    /// This better reflects actual AV1 encoding where:
-    /// - NONE is very common (no split)
-    /// - SPLIT is common for large blocks
-    /// - Other partitions are less common
-    pub fn biased_none(num_symbols: usize) -> Self {
-        let mut cdf = Vec::with_capacity(num_symbols + 1);
-        cdf.push(0);
-
-        // Assign probabilities:
-        // - NONE: 60%
-        // - SPLIT: 20%
-        // - Others: divide remaining 20%
-
-        if num_symbols == 1 {
-            // 4x4 block - only NONE
-            cdf.push(CDF_SCALE);
-        } else if num_symbols == 4 {
-            // 8x8 block - NONE, HORZ, VERT, SPLIT
-            cdf.push((CDF_SCALE as f32 * 0.6) as u16); // NONE: 60%
-            cdf.push((CDF_SCALE as f32 * 0.7) as u16); // HORZ: 10%
-            cdf.push((CDF_SCALE as f32 * 0.8) as u16); // VERT: 10%
-            cdf.push(CDF_SCALE); // SPLIT: 20%
-        } else {
-            // 16x16+ block - all 10 partition types
-            cdf.push((CDF_SCALE as f32 * 0.5) as u16); // NONE: 50%
-            cdf.push((CDF_SCALE as f32 * 0.55) as u16); // HORZ: 5%
-            cdf.push((CDF_SCALE as f32 * 0.60) as u16); // VERT: 5%
-            cdf.push((CDF_SCALE as f32 * 0.75) as u16); // SPLIT: 15%
-            cdf.push((CDF_SCALE as f32 * 0.80) as u16); // HORZ_A: 5%
-            cdf.push((CDF_SCALE as f32 * 0.85) as u16); // HORZ_B: 5%
-            cdf.push((CDF_SCALE as f32 * 0.90) as u16); // VERT_A: 5%
-            cdf.push((CDF_SCALE as f32 * 0.93) as u16); // VERT_B: 3%
-            cdf.push((CDF_SCALE as f32 * 0.96) as u16); // HORZ_4: 3%
-            cdf.push(CDF_SCALE); // VERT_4: 3% (last entry must be exactly 32768)
-        }
-
-        Self { cdf, num_symbols }
-    }
-
-    /// Get CDF as slice
-    pub fn as_slice(&self) -> &[u16] {
-        &self.cdf
     }
 }
 
-/// CDF context (collection of all CDF tables)
+/// All adaptive CDFs of a frame or tile
 ///
-/// For MVP, we maintain simplified CDFs.
-/// Full implementation would have many more contexts based on neighbors.
+/// Field names follow the syntax elements they decode. Context indices are
+/// documented on the default tables in [`super::default_cdfs`] and
+/// [`super::coeff_cdfs`].
+#[derive(Debug, Clone)]
 pub struct CdfContext {
-    /// Partition CDFs indexed by block size log2 (2..=7)
-    /// - block_size_log2 = 2 → 4x4 (1 symbol)
-    /// - block_size_log2 = 3 → 8x8 (4 symbols)
-    /// - block_size_log2 = 4 → 16x16 (10 symbols)
-    /// - block_size_log2 = 5 → 32x32 (10 symbols)
-    /// - block_size_log2 = 6 → 64x64 (10 symbols)
-    /// - block_size_log2 = 7 → 128x128 (10 symbols)
-    partition_cdfs: Vec<PartitionCdf>,
-
-    /// Skip flag CDF (2 symbols: false, true)
-    /// [0, prob_true, 32768]
-    skip_cdf: Vec<u16>,
-
-    /// Prediction mode CDFs
-    /// For INTRA: 13 modes (DC, V, H, D45, D135, D113, D157, D203, D67, SMOOTH, SMOOTH_V, SMOOTH_H, PAETH)
-    /// For INTER: 4 modes (NEWMV, NEARESTMV, NEARMV, GLOBALMV)
-    intra_mode_cdf: Vec<u16>,
-    inter_mode_cdf: Vec<u16>,
-
-    /// Motion Vector CDFs
-    /// MV joint CDF (4 symbols: correlation between horizontal/vertical components)
-    /// - MV_JOINT_ZERO (both zero)
-    /// - MV_JOINT_HNZVZ (horz non-zero, vert zero)
-    /// - MV_JOINT_HZVNZ (horz zero, vert non-zero)
-    /// - MV_JOINT_HNZVNZ (both non-zero)
-    mv_joint_cdf: Vec<u16>,
-    /// MV sign CDF (2 symbols: positive, negative)
-    mv_sign_cdf: Vec<u16>,
-    /// MV class CDF (11 classes for magnitude range)
-    mv_class_cdf: Vec<u16>,
-    /// MV bit CDFs for reading magnitude bits
-    mv_bit_cdf: Vec<u16>,
-
-    /// Delta Q CDF (for quantization parameter deltas)
-    /// Per AV1 Spec Section 5.11.38 (Quantization Parameter Delta)
-    /// Delta Q values are in range [-MAX_DELTA_Q, MAX_DELTA_Q] where MAX_DELTA_Q = 63
-    /// We encode the absolute value (0..63) and sign separately
-    delta_q_cdf: Vec<u16>,
-
-    /// Delta Q sign CDF (2 symbols: positive, negative)
-    delta_q_sign_cdf: Vec<u16>,
-
-    /// General diff CDF for reading variable-length differences
-    /// Used for delta_q_abs when larger values are needed
-    diff_cdf: Vec<u16>,
+    pub intra_frame_y_mode: [[[u16; 14]; 5]; 5],
+    pub y_mode: [[u16; 14]; 4],
+    pub uv_mode_cfl_not_allowed: [[u16; 14]; 13],
+    pub uv_mode_cfl_allowed: [[u16; 15]; 13],
+    pub angle_delta: [[u16; 8]; 8],
+    pub intrabc: [u16; 3],
+    pub partition_w8: [[u16; 5]; 4],
+    pub partition: [[u16; 11]; 12],
+    pub partition_w128: [[u16; 9]; 4],
+    pub segment_id: [[u16; 9]; 3],
+    pub segment_id_predicted: [[u16; 3]; 3],
+    pub tx_8x8: [[u16; 3]; 3],
+    pub tx_size: [[[u16; 4]; 3]; 3],
+    pub txfm_split: [[u16; 3]; 21],
+    pub intra_tx_type_set1: [[[u16; 8]; 13]; 4],
+    pub intra_tx_type_set2: [[[u16; 6]; 13]; 4],
+    pub inter_tx_type_set1: [[u16; 17]; 4],
+    pub inter_tx_type_set2: [[u16; 13]; 4],
+    pub inter_tx_type_set3: [[u16; 3]; 4],
+    pub cfl_sign: [u16; 9],
+    pub cfl_alpha: [[u16; 17]; 6],
+    pub interp_filter: [[u16; 4]; 16],
+    pub new_mv: [[u16; 3]; 6],
+    pub zero_mv: [[u16; 3]; 2],
+    pub ref_mv: [[u16; 3]; 6],
+    pub drl_mode: [[u16; 3]; 3],
+    pub compound_mode: [[u16; 9]; 8],
+    pub inter_intra: [[u16; 3]; 4],
+    pub inter_intra_mode: [[u16; 5]; 4],
+    pub wedge_inter_intra: [[u16; 3]; 22],
+    pub compound_type: [[u16; 3]; 22],
+    pub wedge_index: [[u16; 17]; 22],
+    pub motion_mode: [[u16; 4]; 22],
+    pub use_obmc: [[u16; 3]; 22],
+    pub is_inter: [[u16; 3]; 4],
+    pub comp_mode: [[u16; 3]; 5],
+    pub comp_ref_type: [[u16; 3]; 5],
+    pub uni_comp_ref: [[[u16; 3]; 3]; 3],
+    pub single_ref: [[[u16; 3]; 6]; 3],
+    pub comp_ref: [[[u16; 3]; 3]; 3],
+    pub comp_bwd_ref: [[[u16; 3]; 2]; 3],
+    pub palette_y_mode: [[[u16; 3]; 3]; 7],
+    pub palette_uv_mode: [[u16; 3]; 2],
+    pub palette_y_size: [[u16; 8]; 7],
+    pub palette_uv_size: [[u16; 8]; 7],
+    pub palette_y_color: [[[u16; 9]; 5]; 7],
+    pub palette_uv_color: [[[u16; 9]; 5]; 7],
+    pub skip: [[u16; 3]; 3],
+    pub skip_mode: [[u16; 3]; 3],
+    pub compound_idx: [[u16; 3]; 6],
+    pub comp_group_idx: [[u16; 3]; 6],
+    pub filter_intra_mode: [u16; 6],
+    pub filter_intra: [[u16; 3]; 22],
+    pub restoration_type: [u16; 4],
+    pub use_wiener: [u16; 3],
+    pub use_sgrproj: [u16; 3],
+    pub delta_q: [u16; 5],
+    pub delta_lf: [u16; 5],
+    pub delta_lf_multi: [[u16; 5]; 4],
+    /// MV CDFs, indexed by MvCtx (1 for intra block copy)
+    pub mv: [MvCdfs; 2],
+    pub txb_skip: [[[u16; 3]; 13]; 5],
+    pub eob_pt_16: [[[u16; 6]; 2]; 2],
+    pub eob_pt_32: [[[u16; 7]; 2]; 2],
+    pub eob_pt_64: [[[u16; 8]; 2]; 2],
+    pub eob_pt_128: [[[u16; 9]; 2]; 2],
+    pub eob_pt_256: [[[u16; 10]; 2]; 2],
+    pub eob_pt_512: [[[u16; 11]; 2]; 2],
+    pub eob_pt_1024: [[[u16; 12]; 2]; 2],
+    pub eob_extra: [[[[u16; 3]; 9]; 2]; 5],
+    pub dc_sign: [[[u16; 3]; 3]; 2],
+    pub coeff_base_eob: [[[[u16; 4]; 4]; 2]; 5],
+    pub coeff_base: [[[[u16; 5]; 42]; 2]; 5],
+    pub coeff_br: [[[[u16; 5]; 21]; 2]; 5],
 }
 
 impl CdfContext {
-    /// Create new CDF context with default values
+    /// Create the default CDFs with the coefficient CDFs of quantizer
+    /// context 0
     pub fn new() -> Self {
-        let mut partition_cdfs = Vec::new();
-
-        // Block size log2 = 2 (4x4): NONE only
-        partition_cdfs.push(PartitionCdf::biased_none(1));
-
-        // Block size log2 = 3 (8x8): NONE, HORZ, VERT, SPLIT
-        partition_cdfs.push(PartitionCdf::biased_none(4));
-
-        // Block size log2 = 4..=7 (16x16, 32x32, 64x64, 128x128): All 10 partitions
-        for _ in 4..=7 {
-            partition_cdfs.push(PartitionCdf::biased_none(10));
-        }
-
-        // Skip flag CDF: 20% skip rate (most blocks are not skipped)
-        let skip_cdf = vec![
-            0,                               // false: 0
-            (CDF_SCALE as f32 * 0.8) as u16, // false: 80%
-            CDF_SCALE,                       // true: 20%
-        ];
-
-        // INTRA mode CDF (13 modes)
-        // Biased toward DC_PRED (most common)
-        let intra_mode_cdf = vec![
-            0,                                // Start
-            (CDF_SCALE as f32 * 0.30) as u16, // DC_PRED: 30%
-            (CDF_SCALE as f32 * 0.40) as u16, // V_PRED: 10%
-            (CDF_SCALE as f32 * 0.50) as u16, // H_PRED: 10%
-            (CDF_SCALE as f32 * 0.58) as u16, // D45_PRED: 8%
-            (CDF_SCALE as f32 * 0.66) as u16, // D135_PRED: 8%
-            (CDF_SCALE as f32 * 0.72) as u16, // D113_PRED: 6%
-            (CDF_SCALE as f32 * 0.78) as u16, // D157_PRED: 6%
-            (CDF_SCALE as f32 * 0.84) as u16, // D203_PRED: 6%
-            (CDF_SCALE as f32 * 0.90) as u16, // D67_PRED: 6%
-            (CDF_SCALE as f32 * 0.94) as u16, // SMOOTH_PRED: 4%
-            (CDF_SCALE as f32 * 0.97) as u16, // SMOOTH_V_PRED: 3%
-            (CDF_SCALE as f32 * 0.99) as u16, // SMOOTH_H_PRED: 2%
-            CDF_SCALE,                        // PAETH_PRED: 1%
-        ];
-
-        // INTER mode CDF (4 modes)
-        // Biased toward NEWMV (explicit motion vectors)
-        let inter_mode_cdf = vec![
-            0,                                // Start
-            (CDF_SCALE as f32 * 0.50) as u16, // NEWMV: 50%
-            (CDF_SCALE as f32 * 0.75) as u16, // NEARESTMV: 25%
-            (CDF_SCALE as f32 * 0.95) as u16, // NEARMV: 20%
-            CDF_SCALE,                        // GLOBALMV: 5%
-        ];
-
-        // MV joint CDF (correlation between horizontal/vertical MV components)
-        // Default values from AV1 spec / rav1d reference implementation
-        // Source: https://github.com/memorysafety/rav1d (BSD-2-Clause license)
-        // Forward CDF calculation from probabilities
-        let mv_joint_counts = [
-            4096,  // MV_JOINT_ZERO (both zero)
-            7168,  // MV_JOINT_HNZVZ (H non-zero, V zero)
-            8064,  // MV_JOINT_HZVNZ (H zero, V non-zero)
-            13440, // MV_JOINT_HNZVNZ (both non-zero)
-        ];
-        let mut mv_joint_cdf = Vec::with_capacity(mv_joint_counts.len() + 1);
-        mv_joint_cdf.push(0);
-        let mut cumulative = 0;
-        for &count in &mv_joint_counts {
-            cumulative += count;
-            mv_joint_cdf.push(cumulative);
-        }
-        // Runtime check that works in all builds (not just debug)
-        // These are hardcoded constants that should sum to CDF_SCALE
-        if *mv_joint_cdf.last().unwrap_or(&0) != CDF_SCALE {
-            panic!(
-                "MV joint CDF counts sum to {}, expected {} (CDF_SCALE) - counts may be miscalculated",
-                mv_joint_cdf.last().unwrap_or(&0),
-                CDF_SCALE
-            );
-        }
-
-        // MV sign CDF: 50/50 positive/negative (uniform)
-        let mv_sign_cdf = vec![
-            0,                               // Start
-            (CDF_SCALE as f32 * 0.5) as u16, // Positive: 50%
-            CDF_SCALE,                       // Negative: 50%
-        ];
-
-        // MV class CDF (11 classes for magnitude)
-        // Per AV1 spec Section 5.11.47 (Motion Vector Component)
-        // Default values from AV1 spec / rav1d reference implementation
-        // Source: https://github.com/memorysafety/rav1d (BSD-2-Clause license)
-        // Forward CDF calculation from probabilities
-        let mv_class_counts = [
-            28672, // Class 0 (0 qpel)
-            2304,  // Class 1 (±1 qpel)
-            882,   // Class 2 (±2-3 qpel)
-            462,   // Class 3 (±4-7 qpel)
-            231,   // Class 4 (±8-15 qpel)
-            105,   // Class 5 (±16-31 qpel)
-            84,    // Class 6 (±32-63 qpel)
-            17,    // Class 7 (±64-127 qpel)
-            5,     // Class 8 (±128-255 qpel)
-            5,     // Class 9 (±256-511 qpel)
-            1,     // Class 10 (±512-1023 qpel)
-        ];
-        let mut mv_class_cdf = Vec::with_capacity(mv_class_counts.len() + 1);
-        mv_class_cdf.push(0);
-        let mut cumulative = 0;
-        for &count in &mv_class_counts {
-            cumulative += count;
-            mv_class_cdf.push(cumulative);
-        }
-        // Runtime check that works in all builds (not just debug)
-        // These are hardcoded constants that should sum to CDF_SCALE
-        if *mv_class_cdf.last().unwrap_or(&0) != CDF_SCALE {
-            panic!(
-                "MV class CDF counts sum to {}, expected {} (CDF_SCALE) - counts may be miscalculated",
-                mv_class_cdf.last().unwrap_or(&0),
-                CDF_SCALE
-            );
-        }
-
-        // MV bit CDF: 50/50 for each bit (uniform)
-        let mv_bit_cdf = vec![
-            0,                               // Start
-            (CDF_SCALE as f32 * 0.5) as u16, // 0: 50%
-            CDF_SCALE,                       // 1: 50%
-        ];
-
-        // Delta Q CDF (for reading delta_q_abs)
-        // Per AV1 spec, delta_q_abs is encoded using a variable-length code
-        // Values are heavily biased toward 0 (most QP deltas are small)
-        // Default values from AV1 spec / rav1d reference implementation
-        let delta_q_counts: [u16; 5] = [
-            28672, // 0 (no change): ~87%
-            3488,  // 1: ~11%
-            448,   // 2: ~1.4%
-            96,    // 3: ~0.3%
-            64,    // 4+: ~0.2% (collapsed into "4+" for simplicity)
-        ];
-        let mut delta_q_cdf = Vec::with_capacity(delta_q_counts.len() + 1);
-        delta_q_cdf.push(0);
-        let mut cumulative = 0u16;
-        for &count in &delta_q_counts {
-            cumulative = cumulative.saturating_add(count);
-            delta_q_cdf.push(cumulative.min(CDF_SCALE));
-        }
-        // Ensure the last entry is exactly CDF_SCALE
-        if let Some(last) = delta_q_cdf.last_mut() {
-            *last = CDF_SCALE;
-        }
-
-        // Delta Q sign CDF: Slightly biased toward positive
-        let delta_q_sign_cdf = vec![
-            0,                                // Start
-            (CDF_SCALE as f32 * 0.55) as u16, // Positive: 55%
-            CDF_SCALE,                        // Negative: 45%
-        ];
-
-        // General diff CDF for variable-length differences
-        // Used when delta_q_abs is >= 4
-        // Uses a geometric distribution (higher values less likely)
-        let diff_cdf = vec![
-            0,                                // Start
-            (CDF_SCALE as f32 * 0.50) as u16, // 0: 50%
-            (CDF_SCALE as f32 * 0.75) as u16, // 1: 25%
-            (CDF_SCALE as f32 * 0.90) as u16, // 2: 15%
-            (CDF_SCALE as f32 * 0.97) as u16, // 3: 7%
-            (CDF_SCALE as f32 * 0.99) as u16, // 4: 2%
-            CDF_SCALE,                        // 5+: 1%
-        ];
-
-        Self {
-            partition_cdfs,
-            skip_cdf,
-            intra_mode_cdf,
-            inter_mode_cdf,
-            mv_joint_cdf,
-            mv_sign_cdf,
-            mv_class_cdf,
-            mv_bit_cdf,
-            delta_q_cdf,
-            delta_q_sign_cdf,
-            diff_cdf,
-        }
-    }
-
-    /// Get partition CDF for block size
-    ///
-    /// Block size is log2 of actual size:
-    /// - 2 → 4x4
-    /// - 3 → 8x8
-    /// - 4 → 16x16
-    /// - 5 → 32x32
-    /// - 6 → 64x64
-    /// - 7 → 128x128
-    pub fn get_partition_cdf(&self, block_size_log2: u8) -> &[u16] {
-        let index = (block_size_log2 as usize)
-            .saturating_sub(2)
-            .min(self.partition_cdfs.len() - 1);
-        self.partition_cdfs[index].as_slice()
-    }
-
-    /// Update partition CDF (TODO: implement adaptive CDFs)
-    #[allow(dead_code)]
-    pub fn update_partition_cdf(&mut self, _block_size_log2: u8, _symbol: u8) {
-        // TODO: Implement CDF adaptation
-        // Per AV1 spec, CDFs are updated after each symbol to improve compression
-        // For MVP, we use static CDFs
-    }
-
-    /// Get skip flag CDF
-    ///
-    /// Returns CDF for skip flag (2 symbols: false, true)
-    pub fn get_skip_cdf(&self) -> &[u16] {
-        &self.skip_cdf
-    }
-
-    /// Get INTRA prediction mode CDF
-    ///
-    /// Returns CDF for INTRA modes (13 symbols)
-    pub fn get_intra_mode_cdf(&self) -> &[u16] {
-        &self.intra_mode_cdf
-    }
-
-    /// Get INTER prediction mode CDF
-    ///
-    /// Returns CDF for INTER modes (4 symbols)
-    pub fn get_inter_mode_cdf(&self) -> &[u16] {
-        &self.inter_mode_cdf
-    }
-
-    /// Get MV joint CDF
-    ///
-    /// Returns CDF for MV joint type (4 symbols):
-    /// - MV_JOINT_ZERO (both components zero)
-    /// - MV_JOINT_HNZVZ (horizontal non-zero, vertical zero)
-    /// - MV_JOINT_HZVNZ (horizontal zero, vertical non-zero)
-    /// - MV_JOINT_HNZVNZ (both components non-zero)
-    pub fn get_mv_joint_cdf(&self) -> &[u16] {
-        &self.mv_joint_cdf
-    }
-
-    /// Get MV sign CDF
-    ///
-    /// Returns CDF for MV sign (2 symbols: positive, negative)
-    pub fn get_mv_sign_cdf(&self) -> &[u16] {
-        &self.mv_sign_cdf
+        Self::with_base_q_idx(0)
     }
 
-    /// Get MV class CDF
+    /// Create the default CDFs for a frame with the given `base_q_idx`
     ///
-    /// Returns CDF for MV magnitude class (12 symbols)
-    pub fn get_mv_class_cdf(&self) -> &[u16] {
-        &self.mv_class_cdf
-    }
-
-    /// Get MV bit CDF
-    ///
-    /// Returns CDF for MV magnitude bits (2 symbols: 0, 1)
-    pub fn get_mv_bit_cdf(&self) -> &[u16] {
-        &self.mv_bit_cdf
-    }
-
-    /// Get Delta Q CDF
-    ///
-    /// Returns CDF for delta_q_abs (quantization parameter delta)
-    /// Per AV1 Spec Section 5.11.38 (Quantization Parameter Delta)
-    pub fn get_delta_q_cdf(&self) -> &[u16] {
-        &self.delta_q_cdf
+    /// Per AV1 spec `init_non_coeff_cdfs()` followed by `init_coeff_cdfs()`.
+    pub fn with_base_q_idx(base_q_idx: u8) -> Self {
+        let q = coeff_cdf_q_ctx(base_q_idx);
+        Self {
+            intra_frame_y_mode: DEFAULT_INTRA_FRAME_Y_MODE_CDF,
+            y_mode: DEFAULT_Y_MODE_CDF,
+            uv_mode_cfl_not_allowed: DEFAULT_UV_MODE_CFL_NOT_ALLOWED_CDF,
+            uv_mode_cfl_allowed: DEFAULT_UV_MODE_CFL_ALLOWED_CDF,
+            angle_delta: DEFAULT_ANGLE_DELTA_CDF,
+            intrabc: DEFAULT_INTRABC_CDF,
+            partition_w8: DEFAULT_PARTITION_W8_CDF,
+            partition: DEFAULT_PARTITION_CDF,
+            partition_w128: DEFAULT_PARTITION_W128_CDF,
+            segment_id: DEFAULT_SEGMENT_ID_CDF,
+            segment_id_predicted: DEFAULT_SEGMENT_ID_PREDICTED_CDF,
+            tx_8x8: DEFAULT_TX_8X8_CDF,
+            tx_size: DEFAULT_TX_SIZE_CDF,
+            txfm_split: DEFAULT_TXFM_SPLIT_CDF,
+            intra_tx_type_set1: DEFAULT_INTRA_TX_TYPE_SET1_CDF,
+            intra_tx_type_set2: DEFAULT_INTRA_TX_TYPE_SET2_CDF,
+            inter_tx_type_set1: DEFAULT_INTER_TX_TYPE_SET1_CDF,
+            inter_tx_type_set2: DEFAULT_INTER_TX_TYPE_SET2_CDF,
+            inter_tx_type_set3: DEFAULT_INTER_TX_TYPE_SET3_CDF,
+            cfl_sign: DEFAULT_CFL_SIGN_CDF,
+            cfl_alpha: DEFAULT_CFL_ALPHA_CDF,
+            interp_filter: DEFAULT_INTERP_FILTER_CDF,
+            new_mv: DEFAULT_NEW_MV_CDF,
+            zero_mv: DEFAULT_ZERO_MV_CDF,
+            ref_mv: DEFAULT_REF_MV_CDF,
+            drl_mode: DEFAULT_DRL_MODE_CDF,
+            compound_mode: DEFAULT_COMPOUND_MODE_CDF,
+            inter_intra: DEFAULT_INTER_INTRA_CDF,
+            inter_intra_mode: DEFAULT_INTER_INTRA_MODE_CDF,
+            wedge_inter_intra: DEFAULT_WEDGE_INTER_INTRA_CDF,
+            compound_type: DEFAULT_COMPOUND_TYPE_CDF,
+            wedge_index: DEFAULT_WEDGE_INDEX_CDF,
+            motion_mode: DEFAULT_MOTION_MODE_CDF,
+            use_obmc: DEFAULT_USE_OBMC_CDF,
+            is_inter: DEFAULT_IS_INTER_CDF,
+            comp_mode: DEFAULT_COMP_MODE_CDF,
+            comp_ref_type: DEFAULT_COMP_REF_TYPE_CDF,
+            uni_comp_ref: DEFAULT_UNI_COMP_REF_CDF,
+            single_ref: DEFAULT_SINGLE_REF_CDF,
+            comp_ref: DEFAULT_COMP_REF_CDF,
+            comp_bwd_ref: DEFAULT_COMP_BWD_REF_CDF,
+            palette_y_mode: DEFAULT_PALETTE_Y_MODE_CDF,
+            palette_uv_mode: DEFAULT_PALETTE_UV_MODE_CDF,
+            palette_y_size: DEFAULT_PALETTE_Y_SIZE_CDF,
+            palette_uv_size: DEFAULT_PALETTE_UV_SIZE_CDF,
+            palette_y_color: DEFAULT_PALETTE_Y_COLOR_CDF,
+            palette_uv_color: DEFAULT_PALETTE_UV_COLOR_CDF,
+            skip: DEFAULT_SKIP_CDF,
+            skip_mode: DEFAULT_SKIP_MODE_CDF,
+            compound_idx: DEFAULT_COMPOUND_IDX_CDF,
+            comp_group_idx: DEFAULT_COMP_GROUP_IDX_CDF,
+            filter_intra_mode: DEFAULT_FILTER_INTRA_MODE_CDF,
+            filter_intra: DEFAULT_FILTER_INTRA_CDF,
+            restoration_type: DEFAULT_RESTORATION_TYPE_CDF,
+            use_wiener: DEFAULT_USE_WIENER_CDF,
+            use_sgrproj: DEFAULT_USE_SGRPROJ_CDF,
+            delta_q: DEFAULT_DELTA_Q_CDF,
+            delta_lf: DEFAULT_DELTA_LF_CDF,
+            delta_lf_multi: [DEFAULT_DELTA_LF_CDF; 4],
+            mv: [MvCdfs::default(), MvCdfs::default()],
+            txb_skip: DEFAULT_TXB_SKIP_CDF[q],
+            eob_pt_16: DEFAULT_EOB_PT_16_CDF[q],
+            eob_pt_32: DEFAULT_EOB_PT_32_CDF[q],
+            eob_pt_64: DEFAULT_EOB_PT_64_CDF[q],
+            eob_pt_128: DEFAULT_EOB_PT_128_CDF[q],
+            eob_pt_256: DEFAULT_EOB_PT_256_CDF[q],
+            eob_pt_512: DEFAULT_EOB_PT_512_CDF[q],
+            eob_pt_1024: DEFAULT_EOB_PT_1024_CDF[q],
+            eob_extra: DEFAULT_EOB_EXTRA_CDF[q],
+            dc_sign: DEFAULT_DC_SIGN_CDF[q],
+            coeff_base_eob: DEFAULT_COEFF_BASE_EOB_CDF[q],
+            coeff_base: DEFAULT_COEFF_BASE_CDF[q],
+            coeff_br: DEFAULT_COEFF_BR_CDF[q],
+        }
     }
 
-    /// Get Delta Q sign CDF
+    /// Clear the adaptation counter of every CDF
     ///
-    /// Returns CDF for delta_q_sign_bit (2 symbols: positive, negative)
-    pub fn get_delta_q_sign_cdf(&self) -> &[u16] {
-        &self.delta_q_sign_cdf
+    /// Applied to the CDFs saved at the end of a frame, so every frame
+    /// starts adapting at the fastest rate.
+    pub fn reset_counters(&mut self) {
+        let Self {
+            intra_frame_y_mode,
+            y_mode,
+            uv_mode_cfl_not_allowed,
+            uv_mode_cfl_allowed,
+            angle_delta,
+            intrabc,
+            partition_w8,
+            partition,
+            partition_w128,
+            segment_id,
+            segment_id_predicted,
+            tx_8x8,
+            tx_size,
+            txfm_split,
+            intra_tx_type_set1,
+            intra_tx_type_set2,
+            inter_tx_type_set1,
+            inter_tx_type_set2,
+            inter_tx_type_set3,
+            cfl_sign,
+            cfl_alpha,
+            interp_filter,
+            new_mv,
+            zero_mv,
+            ref_mv,
+            drl_mode,
+            compound_mode,
+            inter_intra,
+            inter_intra_mode,
+            wedge_inter_intra,
+            compound_type,
+            wedge_index,
+            motion_mode,
+            use_obmc,
+            is_inter,
+            comp_mode,
+            comp_ref_type,
+            uni_comp_ref,
+            single_ref,
+            comp_ref,
+            comp_bwd_ref,
+            palette_y_mode,
+            palette_uv_mode,
+            palette_y_size,
+            palette_uv_size,
+            palette_y_color,
+            palette_uv_color,
+            skip,
+            skip_mode,
+            compound_idx,
+            comp_group_idx,
+            filter_intra_mode,
+            filter_intra,
+            restoration_type,
+            use_wiener,
+            use_sgrproj,
+            delta_q,
+            delta_lf,
+            delta_lf_multi,
+            mv,
+            txb_skip,
+            eob_pt_16,
+            eob_pt_32,
+            eob_pt_64,
+            eob_pt_128,
+            eob_pt_256,
+            eob_pt_512,
+            eob_pt_1024,
+            eob_extra,
+            dc_sign,
+            coeff_base_eob,
+            coeff_base,
+            coeff_br,
+        } = self;
+
+        intra_frame_y_mode.reset_counters();
+        y_mode.reset_counters();
+        uv_mode_cfl_not_allowed.reset_counters();
+        uv_mode_cfl_allowed.reset_counters();
+        angle_delta.reset_counters();
+        intrabc.reset_counters();
+        partition_w8.reset_counters();
+        partition.reset_counters();
+        partition_w128.reset_counters();
+        segment_id.reset_counters();
+        segment_id_predicted.reset_counters();
+        tx_8x8.reset_counters();
+        tx_size.reset_counters();
+        txfm_split.reset_counters();
+        intra_tx_type_set1.reset_counters();
+        intra_tx_type_set2.reset_counters();
+        inter_tx_type_set1.reset_counters();
+        inter_tx_type_set2.reset_counters();
+        inter_tx_type_set3.reset_counters();
+        cfl_sign.reset_counters();
+        cfl_alpha.reset_counters();
+        interp_filter.reset_counters();
+        new_mv.reset_counters();
+        zero_mv.reset_counters();
+        ref_mv.reset_counters();
+        drl_mode.reset_counters();
+        compound_mode.reset_counters();
+        inter_intra.reset_counters();
+        inter_intra_mode.reset_counters();
+        wedge_inter_intra.reset_counters();
+        compound_type.reset_counters();
+        wedge_index.reset_counters();
+        motion_mode.reset_counters();
+        use_obmc.reset_counters();
+        is_inter.reset_counters();
+        comp_mode.reset_counters();
+        comp_ref_type.reset_counters();
+        uni_comp_ref.reset_counters();
+        single_ref.reset_counters();
+        comp_ref.reset_counters();
+        comp_bwd_ref.reset_counters();
+        palette_y_mode.reset_counters();
+        palette_uv_mode.reset_counters();
+        palette_y_size.reset_counters();
+        palette_uv_size.reset_counters();
+        palette_y_color.reset_counters();
+        palette_uv_color.reset_counters();
+        skip.reset_counters();
+        skip_mode.reset_counters();
+        compound_idx.reset_counters();
+        comp_group_idx.reset_counters();
+        filter_intra_mode.reset_counters();
+        filter_intra.reset_counters();
+        restoration_type.reset_counters();
+        use_wiener.reset_counters();
+        use_sgrproj.reset_counters();
+        delta_q.reset_counters();
+        delta_lf.reset_counters();
+        delta_lf_multi.reset_counters();
+        for ctx in mv.iter_mut() {
+            ctx.joint.reset_counters();
+            ctx.class.reset_counters();
+            ctx.class0_bit.reset_counters();
+            ctx.class0_fr.reset_counters();
+            ctx.class0_hp.reset_counters();
+            ctx.sign.reset_counters();
+            ctx.bits.reset_counters();
+            ctx.fr.reset_counters();
+            ctx.hp.reset_counters();
+        }
+        txb_skip.reset_counters();
+        eob_pt_16.reset_counters();
+        eob_pt_32.reset_counters();
+        eob_pt_64.reset_counters();
+        eob_pt_128.reset_counters();
+        eob_pt_256.reset_counters();
+        eob_pt_512.reset_counters();
+        eob_pt_1024.reset_counters();
+        eob_extra.reset_counters();
+        dc_sign.reset_counters();
+        coeff_base_eob.reset_counters();
+        coeff_base.reset_counters();
+        coeff_br.reset_counters();
     }
 
-    /// Get general diff CDF
-    ///
-    /// Returns CDF for reading variable-length differences
-    /// Used when delta_q_abs is >= 4
-    pub fn get_diff_cdf(&self) -> &[u16] {
-        &self.diff_cdf
+    /// CDF of `partition` for a square block of `1 << bsl_log2` 8x8 units
+    /// (0 for 8x8 up to 4 for 128x128)
+    pub fn partition_cdf(&mut self, bsl_log2: usize, ctx: usize) -> &mut [u16] {
+        match bsl_log2 {
+            0 => &mut self.partition_w8[ctx],
+            1..=3 => &mut self.partition[(bsl_log2 - 1) * 4 + ctx],
+            _ => &mut self.partition_w128[ctx],
+        }
     }
 }
 
@@ -456,158 +432,79 @@ impl Default for CdfContext {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_partition_cdf_uniform() {
-        let cdf = PartitionCdf::uniform(4);
-        assert_eq!(cdf.num_symbols, 4);
-        assert_eq!(cdf.cdf.len(), 5);
-        assert_eq!(cdf.cdf[0], 0);
-        assert_eq!(cdf.cdf[4], CDF_SCALE);
-
-        // Check uniform distribution
-        assert_eq!(cdf.cdf[1], 8192); // 1/4
-        assert_eq!(cdf.cdf[2], 16384); // 2/4
-        assert_eq!(cdf.cdf[3], 24576); // 3/4
-    }
-
-    #[test]
-    fn test_partition_cdf_biased() {
-        let cdf = PartitionCdf::biased_none(4);
-        assert_eq!(cdf.num_symbols, 4);
-        assert_eq!(cdf.cdf[0], 0);
-        assert_eq!(cdf.cdf[4], CDF_SCALE);
-
-        // NONE should have highest probability
-        assert!(cdf.cdf[1] > cdf.cdf[2] - cdf.cdf[1]);
-    }
-
-    #[test]
-    fn test_cdf_context_creation() {
-        let context = CdfContext::new();
+/// Arrays of CDFs whose adaptation counters can be cleared
+trait CdfArray {
+    fn reset_counters(&mut self);
+}
 
-        // Check we have CDFs for all block sizes
-        assert_eq!(context.partition_cdfs.len(), 6); // log2(4) to log2(128)
+impl<const N: usize> CdfArray for [u16; N] {
+    fn reset_counters(&mut self) {
+        // The counter follows the terminating 32768; shorter CDFs are
+        // zero-padded after the counter
+        if let Some(end) = self.iter().rposition(|&p| p == CDF_SCALE) {
+            if end + 1 < N {
+                self[end + 1] = 0;
+            }
+        }
     }
+}
 
-    #[test]
-    fn test_cdf_context_get_partition() {
-        let context = CdfContext::new();
-
-        // 4x4 block (log2 = 2): 1 symbol
-        let cdf_4x4 = context.get_partition_cdf(2);
-        assert_eq!(cdf_4x4.len(), 2); // 1 symbol + end marker
-
-        // 8x8 block (log2 = 3): 4 symbols
-        let cdf_8x8 = context.get_partition_cdf(3);
-        assert_eq!(cdf_8x8.len(), 5); // 4 symbols + end marker
-
-        // 16x16 block (log2 = 4): 10 symbols
-        let cdf_16x16 = context.get_partition_cdf(4);
-        assert_eq!(cdf_16x16.len(), 11); // 10 symbols + end marker
-
-        // 128x128 block (log2 = 7): 10 symbols
-        let cdf_128x128 = context.get_partition_cdf(7);
-        assert_eq!(cdf_128x128.len(), 11);
+impl<T: CdfArray, const M: usize> CdfArray for [T; M] {
+    fn reset_counters(&mut self) {
+        for cdf in self.iter_mut() {
+            cdf.reset_counters();
+        }
     }
+}
 
-    #[test]
-    fn test_cdf_scale() {
-        assert_eq!(CDF_SCALE, 32768);
-        assert_eq!(CDF_SCALE, 1 << 15);
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
     #[test]
-    fn test_mv_class_cdf_spec_compliant() {
-        let context = CdfContext::new();
-        let cdf = context.get_mv_class_cdf();
-
-        // Verify length: 11 classes + start + end = 12 values
-        assert_eq!(cdf.len(), 12);
-
-        // Verify first and last values
-        assert_eq!(cdf[0], 0, "CDF should start at 0");
-        assert_eq!(cdf[11], CDF_SCALE, "CDF should end at 32768");
-
-        // Verify monotonically increasing
-        for i in 1..cdf.len() {
-            assert!(
-                cdf[i] >= cdf[i - 1],
-                "CDF should be monotonically increasing at index {}",
-                i
-            );
-        }
-
-        // Verify spec-compliant values from rav1d
-        assert_eq!(cdf[1], 28672, "Class 0 (0 qpel) cumulative probability");
-        assert_eq!(cdf[2], 30976, "Class 1 (±1 qpel) cumulative probability");
-        assert_eq!(cdf[3], 31858, "Class 2 (±2-3 qpel) cumulative probability");
-        assert_eq!(cdf[4], 32320, "Class 3 (±4-7 qpel) cumulative probability");
-
-        // Verify realistic distribution (most MVs are small magnitude)
-        let prob_class_0 = cdf[1] as f32 / CDF_SCALE as f32;
-        assert!(
-            prob_class_0 > 0.85,
-            "Class 0 should be very common (>85%), got {:.1}%",
-            prob_class_0 * 100.0
-        );
+    fn test_coeff_cdf_q_ctx() {
+        assert_eq!(coeff_cdf_q_ctx(0), 0);
+        assert_eq!(coeff_cdf_q_ctx(20), 0);
+        assert_eq!(coeff_cdf_q_ctx(21), 1);
+        assert_eq!(coeff_cdf_q_ctx(60), 1);
+        assert_eq!(coeff_cdf_q_ctx(120), 2);
+        assert_eq!(coeff_cdf_q_ctx(121), 3);
+        assert_eq!(coeff_cdf_q_ctx(255), 3);
     }
 
     #[test]
-    fn test_mv_joint_cdf_spec_compliant() {
-        let context = CdfContext::new();
-        let cdf = context.get_mv_joint_cdf();
-
-        // Verify length: 4 symbols + start = 5 values
-        assert_eq!(cdf.len(), 5);
-
-        // Verify first and last values
-        assert_eq!(cdf[0], 0, "CDF should start at 0");
-        assert_eq!(cdf[4], CDF_SCALE, "CDF should end at 32768");
-
-        // Verify monotonically increasing
-        for i in 1..cdf.len() {
-            assert!(
-                cdf[i] >= cdf[i - 1],
-                "CDF should be monotonically increasing at index {}",
-                i
-            );
+    fn test_default_cdfs_are_well_formed() {
+        let cdfs = CdfContext::new();
+        for row in cdfs.partition.iter() {
+            assert!(row.windows(2).take(9).all(|w| w[0] <= w[1]));
+            assert_eq!(row[9], CDF_SCALE);
+            assert_eq!(row[10], 0);
         }
-
-        // Verify spec-compliant values from rav1d
-        assert_eq!(cdf[1], 4096, "MV_JOINT_ZERO cumulative probability");
-        assert_eq!(cdf[2], 11264, "MV_JOINT_HNZVZ cumulative probability");
-        assert_eq!(cdf[3], 19328, "MV_JOINT_HZVNZ cumulative probability");
+        assert_eq!(cdfs.skip[0], [31671, 32768, 0]);
+        assert_eq!(cdfs.mv[0].joint[3], CDF_SCALE);
     }
 
     #[test]
-    fn test_mv_sign_cdf() {
-        let context = CdfContext::new();
-        let cdf = context.get_mv_sign_cdf();
-
-        // Verify length: 2 symbols + start = 3 values
-        assert_eq!(cdf.len(), 3);
-
-        // Verify uniform distribution (50/50)
-        assert_eq!(cdf[0], 0);
-        assert_eq!(cdf[1], 16384, "Sign should be 50/50");
-        assert_eq!(cdf[2], CDF_SCALE);
+    fn test_coefficient_cdfs_follow_base_q_idx() {
+        let low = CdfContext::with_base_q_idx(10);
+        let high = CdfContext::with_base_q_idx(200);
+        assert_eq!(low.txb_skip, DEFAULT_TXB_SKIP_CDF[0]);
+        assert_eq!(high.txb_skip, DEFAULT_TXB_SKIP_CDF[3]);
+        assert_eq!(low.skip, high.skip);
     }
 
     #[test]
-    fn test_mv_bit_cdf() {
-        let context = CdfContext::new();
-        let cdf = context.get_mv_bit_cdf();
-
-        // Verify length: 2 symbols + start = 3 values
-        assert_eq!(cdf.len(), 3);
-
-        // Verify uniform distribution (50/50)
-        assert_eq!(cdf[0], 0);
-        assert_eq!(cdf[1], 16384, "Bit should be 50/50");
-        assert_eq!(cdf[2], CDF_SCALE);
+    fn test_reset_counters() {
+        let mut cdfs = CdfContext::new();
+        cdfs.skip[1][2] = 17;
+        cdfs.partition[0][10] = 32;
+        cdfs.palette_y_color[0][0][2] = 5;
+        cdfs.coeff_br[4][1][20][4] = 9;
+        cdfs.reset_counters();
+        assert_eq!(cdfs.skip[1][2], 0);
+        assert_eq!(cdfs.partition[0][10], 0);
+        assert_eq!(cdfs.palette_y_color[0][0][2], 0);
+        assert_eq!(cdfs.coeff_br[4][1][20][4], 0);
+        assert_eq!(cdfs.skip, CdfContext::new().skip);
     }
 }
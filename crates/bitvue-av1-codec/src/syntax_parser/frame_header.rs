@@ -1,14 +1,18 @@
-//! Frame Header syntax parsing (simplified for Phase 0)
+//! Frame Header syntax parsing
 //!
 //! This module provides syntax tree generation for AV1 Frame Headers,
 //! tracking exact bit ranges for Tri-sync functionality.
 //!
-//! **Phase 0 Scope**: Parses basic fields only (~5-10 syntax nodes)
-//! - show_existing_frame, frame_to_show_map_idx
-//! - frame_type, show_frame
-//! - error_resilient_mode (conditional)
+//! [`parse_uncompressed_header_syntax`] emits every element of
+//! `uncompressed_header()`: reference order hints, frame and render
+//! size, tile info, quantization, segmentation, delta Q/LF, loop filter
+//! deltas, CDEF, loop restoration, tx_mode, reference_select, skip mode,
+//! global motion and film grain parameters. It needs the sequence header
+//! and the reference frame slots, since both decide which elements are
+//! present and how wide they are.
 //!
-//! **Deferred**: Quantization params, loop filter, segmentation, etc.
+//! [`parse_frame_header_syntax`] is the fallback for a frame header seen
+//! without its sequence header and stops after `error_resilient_mode`.
 //!
 //! # AV1 Specification Reference
 //! - Section 5.9: Frame Header OBU
@@ -16,9 +20,58 @@
 
 use super::{SyntaxBuilder, TrackedBitReader};
 use crate::frame_header::FrameType;
+use crate::sequence::SequenceHeader;
+use crate::uncompressed_header::{RefFrameInfo, UncompressedHeader, NUM_REF_FRAMES};
 use bitvue_core::Result;
 
-/// Parse Frame Header OBU payload with bit-level tracking (simplified)
+/// Parse a complete Frame Header OBU payload with bit-level tracking
+///
+/// `refs` is the state of the reference frame slots before this frame and
+/// `temporal_id`/`spatial_id` come from the OBU extension header. Returns
+/// the parsed header so callers can update the reference slots.
+///
+/// # Example Syntax Tree
+///
+/// ```text
+/// frame_header
+/// ├── show_existing_frame: "0"
+/// ├── frame_type: "1 (INTER)"
+/// ├── ...
+/// ├── frame_size
+/// ├── tile_info
+/// ├── quantization_params
+/// │   ├── base_q_idx: "112"
+/// │   └── ...
+/// ├── ...
+/// └── global_motion_params
+///     ├── LAST_FRAME
+///     │   └── is_global: "0"
+///     └── ...
+/// ```
+pub fn parse_uncompressed_header_syntax(
+    reader: &mut TrackedBitReader,
+    builder: &mut SyntaxBuilder,
+    seq: &SequenceHeader,
+    refs: &[RefFrameInfo; NUM_REF_FRAMES],
+    temporal_id: u8,
+    spatial_id: u8,
+) -> Result<UncompressedHeader> {
+    let start = reader.position();
+    builder.push_container("frame_header", start);
+    let header = crate::uncompressed_header::parse_uncompressed_header_syntax(
+        reader,
+        builder,
+        seq,
+        refs,
+        temporal_id,
+        spatial_id,
+    )?;
+    let end = reader.position();
+    builder.pop_container(end);
+    Ok(header)
+}
+
+/// Parse the start of a Frame Header OBU payload without a sequence header
 ///
 /// Creates a syntax tree for the frame header, including:
 /// - show_existing_frame flag
@@ -36,12 +89,11 @@ use bitvue_core::Result;
 ///
 /// `Ok(())` if parsing succeeds, or an error if the bitstream is malformed.
 ///
-/// # Phase 0 Simplification
+/// # Scope
 ///
-/// This parser only extracts the first few fields of the frame header.
-/// Full frame header parsing requires sequence header context and is
-/// extremely complex (100+ conditional fields). For Phase 0 tri-sync
-/// demonstration, basic fields are sufficient.
+/// Everything after `error_resilient_mode` depends on the sequence header
+/// (and the reference frames), so this parser stops there. Use
+/// [`parse_uncompressed_header_syntax`] when the sequence header is known.
 ///
 /// # Example Syntax Tree
 ///
@@ -124,33 +176,7 @@ pub fn parse_frame_header_syntax(
         }
     };
 
-    // Phase 0: Stop here
-    // Full frame header has 100+ more fields:
-    // - disable_cdf_update
-    // - allow_screen_content_tools
-    // - force_integer_mv
-    // - frame_size
-    // - render_size
-    // - allow_intrabc
-    // - frame_refs_short_signaling
-    // - ref_frame_idx[]
-    // - allow_high_precision_mv
-    // - interpolation_filter
-    // - is_motion_mode_switchable
-    // - use_ref_frame_mvs
-    // - disable_frame_end_update_cdf
-    // - tile_info
-    // - quantization_params
-    // - segmentation_params
-    // - delta_q_params
-    // - delta_lf_params
-    // - loop_filter_params
-    // - cdef_params
-    // - lr_params
-    // - tx_mode
-    // - frame_reference_mode
-    // - skip_mode_params
-    // - ... etc
+    // The remaining fields need the sequence header
 
     let end = reader.position();
     builder.pop_container(end);
@@ -297,4 +323,54 @@ mod tests {
         assert_eq!(err_resilient.bit_range, BitRange::new(5, 6));
         assert_eq!(err_resilient.value.as_ref().unwrap(), "0");
     }
+
+    #[test]
+    fn test_full_frame_header_syntax() {
+        let path =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../samples/foreman_av1.ivf");
+        let Ok(data) = std::fs::read(&path) else {
+            eprintln!("Skipping test: {} not found", path.display());
+            return;
+        };
+        let (_, frames) = crate::parse_ivf_frames(&data).unwrap();
+        let mut context = crate::syntax_parser::SyntaxContext::new();
+        let mut inter_frames = 0;
+
+        for frame in frames.iter().take(12) {
+            let mut iter = crate::obu::ObuIterator::new(&frame.data);
+            while let Some(obu) = iter.next_obu_with_offset() {
+                let obu = obu.unwrap();
+                let bytes = &frame.data[obu.offset..obu.offset + obu.consumed];
+                let model = context.parse_obu(bytes, 0, 0).unwrap();
+                if obu.obu.header.obu_type != crate::ObuType::Frame {
+                    continue;
+                }
+                let value = |name: &str| {
+                    model
+                        .get_node(&format!("obu[0].frame_header.{}", name))
+                        .and_then(|n| n.value.clone())
+                        .unwrap_or_else(|| panic!("missing {}", name))
+                };
+
+                let header = model.get_node("obu[0].frame_header").unwrap();
+                assert!(header.bit_range.end_bit <= 8 * bytes.len() as u64);
+
+                let base_q_idx = value("quantization_params.base_q_idx");
+                assert!(base_q_idx.parse::<u8>().is_ok());
+                assert!(model
+                    .get_node("obu[0].frame_header.tile_info.uniform_tile_spacing_flag")
+                    .is_some());
+                assert!(model
+                    .get_node("obu[0].frame_header.loop_filter_params")
+                    .is_some());
+                if value("frame_type").contains("INTER") {
+                    inter_frames += 1;
+                    value("ref_frame_idx[0]");
+                    value("global_motion_params.LAST_FRAME.is_global");
+                    value("reference_select");
+                }
+            }
+        }
+        assert!(inter_frames > 0);
+    }
 }
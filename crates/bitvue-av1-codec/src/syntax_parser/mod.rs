@@ -9,14 +9,18 @@
 //! - `TrackedBitReader`: Wraps `BitReader` with absolute bit position tracking
 //! - `SyntaxBuilder`: Builds `SyntaxModel` tree structure during parsing
 //! - OBU-specific parsers: Parse different OBU types with bit-level detail
+//! - `SyntaxContext`: Carries the sequence header and reference slots
+//!   between OBUs, which the frame header syntax depends on
 //!
 //! # Usage
 //!
 //! ```ignore
-//! use bitvue_av1::syntax_parser::parse_obu_syntax;
+//! use bitvue_av1::syntax_parser::SyntaxContext;
 //!
-//! // Parse a single OBU at byte offset 128
-//! let model = parse_obu_syntax(obu_data, 0, 128 * 8)?;
+//! // Parse OBUs in stream order; the OBU at byte offset 128 is the second
+//! let mut context = SyntaxContext::new();
+//! context.parse_obu(sequence_header_obu, 0, 0)?;
+//! let model = context.parse_obu(frame_obu, 1, 128 * 8)?;
 //!
 //! // Access syntax nodes
 //! for node in model.nodes.values() {
@@ -34,14 +38,16 @@ mod obu;
 mod sequence;
 mod tracked_bitreader;
 
-pub use frame_header::parse_frame_header_syntax;
+pub use frame_header::{parse_frame_header_syntax, parse_uncompressed_header_syntax};
 pub use sequence::parse_sequence_header_syntax;
 pub use tracked_bitreader::TrackedBitReader;
 
-use crate::obu::{ObuIterator, ObuWithOffset};
+use crate::obu::{ObuIterator, ObuType, ObuWithOffset};
+use crate::sequence::{parse_sequence_header, SequenceHeader};
+use crate::uncompressed_header::{RefFrameInfo, NUM_REF_FRAMES};
 use bitvue_core::{
     types::{BitRange, SyntaxModel, SyntaxNode, SyntaxNodeId},
-    Result,
+    FrameType, Result,
 };
 use obu::{parse_leb128_size_syntax, parse_obu_header_syntax};
 
@@ -209,8 +215,124 @@ impl SyntaxBuilder {
     }
 }
 
+/// Decoder state carried from one OBU's syntax tree to the next
+///
+/// Most of the frame header syntax depends on the active sequence header
+/// and on the reference slots filled by earlier frames (frame sizes, order
+/// hints, previous global motion). Feed every OBU of a stream through the
+/// same context, in order, to get the complete tree for each frame header.
+/// Without a sequence header, frame headers fall back to
+/// [`parse_frame_header_syntax`].
+#[derive(Debug, Default)]
+pub struct SyntaxContext {
+    sequence_header: Option<SequenceHeader>,
+    refs: [RefFrameInfo; NUM_REF_FRAMES],
+    /// Slots seen by the last frame header, for its redundant copies
+    refs_before: [RefFrameInfo; NUM_REF_FRAMES],
+}
+
+impl SyntaxContext {
+    /// Create a context with no sequence header and empty reference slots
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The most recent sequence header, if one has been parsed
+    pub fn sequence_header(&self) -> Option<&SequenceHeader> {
+        self.sequence_header.as_ref()
+    }
+
+    /// Parse a single OBU and return its detailed syntax tree
+    ///
+    /// Sequence headers and frame headers update the context for the
+    /// OBUs that follow. Arguments are as for [`parse_obu_syntax`].
+    pub fn parse_obu(
+        &mut self,
+        data: &[u8],
+        obu_index: usize,
+        global_offset: u64,
+    ) -> Result<SyntaxModel> {
+        let root_id = format!("obu[{}]", obu_index);
+        let unit_key = format!("obu_{}", obu_index);
+        let mut builder = SyntaxBuilder::new(root_id, unit_key);
+        let mut reader = TrackedBitReader::new(data, global_offset);
+
+        // Parse OBU header
+        let header = parse_obu_header_syntax(&mut reader, &mut builder)?;
+
+        // Parse size field if present
+        let obu_size = if header.has_size {
+            Some(parse_leb128_size_syntax(&mut reader, &mut builder)? as usize)
+        } else {
+            None
+        };
+        let payload_start = ((reader.position() - global_offset) / 8) as usize;
+        let payload_end = obu_size.map_or(data.len(), |size| payload_start + size);
+        let payload = data.get(payload_start..payload_end).unwrap_or_default();
+
+        // Parse payload based on OBU type
+        match header.obu_type {
+            ObuType::SequenceHeader => {
+                parse_sequence_header_syntax(&mut reader, &mut builder)?;
+                self.sequence_header = Some(parse_sequence_header(payload)?);
+            }
+            ObuType::Frame | ObuType::FrameHeader | ObuType::RedundantFrameHeader => {
+                let Some(seq) = &self.sequence_header else {
+                    parse_frame_header_syntax(&mut reader, &mut builder)?;
+                    return Ok(builder.build());
+                };
+                if header.obu_type == ObuType::RedundantFrameHeader {
+                    parse_uncompressed_header_syntax(
+                        &mut reader,
+                        &mut builder,
+                        seq,
+                        &self.refs_before,
+                        header.temporal_id,
+                        header.spatial_id,
+                    )?;
+                    return Ok(builder.build());
+                }
+
+                let frame = parse_uncompressed_header_syntax(
+                    &mut reader,
+                    &mut builder,
+                    seq,
+                    &self.refs,
+                    header.temporal_id,
+                    header.spatial_id,
+                )?;
+                self.refs_before = self.refs.clone();
+                if frame.show_existing_frame {
+                    if frame.frame_type == FrameType::Key {
+                        // Reference frame loading process, then every slot
+                        // is refreshed from the shown frame
+                        let shown = self.refs[frame.frame_to_show_map_idx as usize].clone();
+                        self.refs.fill(shown);
+                    }
+                } else {
+                    let info = frame.ref_frame_info();
+                    for (i, slot) in self.refs.iter_mut().enumerate() {
+                        if frame.refresh_frame_flags & (1 << i) != 0 {
+                            *slot = info.clone();
+                        }
+                    }
+                }
+            }
+            _ => {
+                // Other OBU types - not implemented yet
+                // (Temporal Delimiter, Tile Group, Metadata, Padding, etc.)
+            }
+        }
+
+        Ok(builder.build())
+    }
+}
+
 /// Parse a single OBU and return its detailed syntax tree
 ///
+/// The OBU is parsed without stream context, so a frame header only gets
+/// its leading fields. Use [`SyntaxContext`] to parse frame headers in full.
+///
 /// # Arguments
 ///
 /// * `data` - The OBU data (including header and payload)
@@ -231,37 +353,7 @@ impl SyntaxBuilder {
 /// let model = parse_obu_syntax(obu_data, 2, 512 * 8)?;
 /// ```
 pub fn parse_obu_syntax(data: &[u8], obu_index: usize, global_offset: u64) -> Result<SyntaxModel> {
-    let root_id = format!("obu[{}]", obu_index);
-    let unit_key = format!("obu_{}", obu_index);
-    let mut builder = SyntaxBuilder::new(root_id, unit_key);
-    let mut reader = TrackedBitReader::new(data, global_offset);
-
-    // Parse OBU header
-    let (obu_type, has_size_field) = parse_obu_header_syntax(&mut reader, &mut builder)?;
-
-    // Parse size field if present (Step 3)
-    let _obu_size = if has_size_field {
-        Some(parse_leb128_size_syntax(&mut reader, &mut builder)?)
-    } else {
-        None
-    };
-
-    // Parse payload based on OBU type (Steps 4-5)
-    use crate::obu::ObuType;
-    match obu_type {
-        ObuType::SequenceHeader => {
-            parse_sequence_header_syntax(&mut reader, &mut builder)?;
-        }
-        ObuType::Frame | ObuType::FrameHeader => {
-            parse_frame_header_syntax(&mut reader, &mut builder)?;
-        }
-        _ => {
-            // Other OBU types - not implemented yet
-            // (Temporal Delimiter, Tile Group, Metadata, Padding, etc.)
-        }
-    }
-
-    Ok(builder.build())
+    SyntaxContext::new().parse_obu(data, obu_index, global_offset)
 }
 
 /// Parse all OBUs in a bitstream and return syntax models
@@ -274,6 +366,7 @@ pub fn parse_obu_syntax(data: &[u8], obu_index: usize, global_offset: u64) -> Re
 ///
 /// A vector of `SyntaxModel`, one for each OBU.
 pub fn parse_bitstream_syntax(data: &[u8]) -> Result<Vec<SyntaxModel>> {
+    let mut context = SyntaxContext::new();
     let mut models = Vec::new();
     let mut obu_index = 0;
     let mut iter = ObuIterator::new(data);
@@ -287,7 +380,7 @@ pub fn parse_bitstream_syntax(data: &[u8]) -> Result<Vec<SyntaxModel>> {
         } = obu_with_offset;
 
         // Parse syntax for this OBU
-        let model = parse_obu_syntax(
+        let model = context.parse_obu(
             &data[offset..offset + consumed],
             obu_index,
             (offset * 8) as u64,
@@ -3,7 +3,7 @@
 //! Parses AV1 OBU headers and creates detailed syntax trees with exact bit ranges.
 
 use super::{SyntaxBuilder, TrackedBitReader};
-use crate::obu::ObuType;
+use crate::obu::{ObuHeader, ObuType};
 use bitvue_core::{BitvueError, Result};
 
 /// Maximum bytes for a valid LEB128 in AV1 (8 bytes = 56 bits max)
@@ -27,11 +27,11 @@ const MAX_LEB128_BYTES: usize = 8;
 ///
 /// # Returns
 ///
-/// Returns a tuple of (OBU type, has_size_field flag)
+/// Returns the decoded header, including the extension's temporal/spatial ids
 pub fn parse_obu_header_syntax(
     reader: &mut TrackedBitReader,
     builder: &mut SyntaxBuilder,
-) -> Result<(ObuType, bool)> {
+) -> Result<ObuHeader> {
     let start = reader.position();
     builder.push_container("obu_header", start);
 
@@ -69,14 +69,23 @@ pub fn parse_obu_header_syntax(
     builder.add_field("obu_reserved_1bit", range, format!("{}", reserved as u8));
 
     // Conditional: extension header
-    if has_ext {
-        parse_extension_header(reader, builder)?;
-    }
+    let (temporal_id, spatial_id) = if has_ext {
+        parse_extension_header(reader, builder)?
+    } else {
+        (0, 0)
+    };
 
     let end = reader.position();
     builder.pop_container(end);
 
-    Ok((obu_type, has_size))
+    Ok(ObuHeader {
+        obu_type,
+        has_extension: has_ext,
+        has_size,
+        temporal_id,
+        spatial_id,
+        header_size: ((end - start) / 8) as usize,
+    })
 }
 
 /// Parse OBU extension header
@@ -92,7 +101,7 @@ pub fn parse_obu_header_syntax(
 fn parse_extension_header(
     reader: &mut TrackedBitReader,
     builder: &mut SyntaxBuilder,
-) -> Result<()> {
+) -> Result<(u8, u8)> {
     let start = reader.position();
     builder.push_container("obu_extension_header", start);
 
@@ -115,7 +124,7 @@ fn parse_extension_header(
     let end = reader.position();
     builder.pop_container(end);
 
-    Ok(())
+    Ok((temporal_id as u8, spatial_id as u8))
 }
 
 /// Parse LEB128 size field with bit-level tracking
@@ -207,10 +216,10 @@ mod tests {
         let mut reader = TrackedBitReader::new(&data, 0);
         let mut builder = SyntaxBuilder::new("obu[0]".to_string(), "obu_0".to_string());
 
-        let (obu_type, has_size) = parse_obu_header_syntax(&mut reader, &mut builder).unwrap();
+        let header = parse_obu_header_syntax(&mut reader, &mut builder).unwrap();
 
-        assert_eq!(obu_type, ObuType::TemporalDelimiter);
-        assert!(has_size);
+        assert_eq!(header.obu_type, ObuType::TemporalDelimiter);
+        assert!(header.has_size);
 
         let model = builder.build();
 
@@ -242,10 +251,10 @@ mod tests {
         let mut reader = TrackedBitReader::new(&data, 0);
         let mut builder = SyntaxBuilder::new("obu[0]".to_string(), "obu_0".to_string());
 
-        let (obu_type, has_size) = parse_obu_header_syntax(&mut reader, &mut builder).unwrap();
+        let header = parse_obu_header_syntax(&mut reader, &mut builder).unwrap();
 
-        assert_eq!(obu_type, ObuType::SequenceHeader);
-        assert!(has_size);
+        assert_eq!(header.obu_type, ObuType::SequenceHeader);
+        assert!(header.has_size);
 
         let model = builder.build();
         assert!(model.get_node("obu[0].obu_header").is_some());
@@ -268,8 +277,10 @@ mod tests {
         let mut reader = TrackedBitReader::new(&data, 0);
         let mut builder = SyntaxBuilder::new("obu[0]".to_string(), "obu_0".to_string());
 
-        let (_obu_type, has_size) = parse_obu_header_syntax(&mut reader, &mut builder).unwrap();
-        assert!(has_size);
+        let header = parse_obu_header_syntax(&mut reader, &mut builder).unwrap();
+        assert!(header.has_size);
+        assert_eq!((header.temporal_id, header.spatial_id), (5, 1));
+        assert_eq!(header.header_size, 2);
 
         let model = builder.build();
 
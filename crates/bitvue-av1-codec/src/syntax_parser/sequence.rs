@@ -1,12 +1,13 @@
-//! Sequence Header syntax parsing (simplified for Phase 0)
+//! Sequence Header syntax parsing
 //!
 //! This module provides syntax tree generation for AV1 Sequence Headers,
 //! tracking exact bit ranges for Tri-sync functionality.
 //!
-//! **Phase 0 Scope**: Parses essential fields only (~25-30 syntax nodes)
-//! - Core fields: profile, dimensions, level, key feature flags
-//! - Simplified sub-structures: color_config basics, operating points
-//! - Deferred: Inter flags, screen content tools, advanced color config
+//! Every element of `sequence_header_obu()` is emitted, including
+//! `timing_info()`, `decoder_model_info()`, the per operating point
+//! decoder model and display delay parameters, and the full
+//! `color_config()`. Values the spec infers rather than reads (for
+//! example the subsampling of profile 0 streams) are not emitted.
 //!
 //! # AV1 Specification Reference
 //! - Section 5.5: Sequence Header OBU
@@ -16,14 +17,27 @@ use super::{SyntaxBuilder, TrackedBitReader};
 use crate::sequence::Av1Profile;
 use bitvue_core::Result;
 
+/// `seq_force_screen_content_tools` / `seq_force_integer_mv` value
+/// meaning "chosen per frame"
+const SELECT_SCREEN_CONTENT_TOOLS: u32 = 2;
+
+/// color_primaries value for BT.709
+const CP_BT_709: u32 = 1;
+
+/// transfer_characteristics value for sRGB
+const TC_SRGB: u32 = 13;
+
+/// matrix_coefficients value for identity (GBR)
+const MC_IDENTITY: u32 = 0;
+
 /// Parse Sequence Header OBU payload with bit-level tracking
 ///
 /// Creates a detailed syntax tree for the sequence header, including:
 /// - Basic header fields (profile, still_picture, reduced_header)
-/// - Frame dimensions (max_frame_width, max_frame_height)
-/// - Operating points array
+/// - Timing info, decoder model info and the operating points array
+/// - Frame dimensions and frame id lengths
 /// - Feature enable flags
-/// - Simplified color configuration
+/// - Color configuration
 ///
 /// # Arguments
 ///
@@ -41,11 +55,15 @@ use bitvue_core::Result;
 /// ├── seq_profile: "0 (Main)"
 /// ├── still_picture: "0"
 /// ├── reduced_still_picture_header: "0"
+/// ├── timing_info_present_flag: "0"
+/// ├── initial_display_delay_present_flag: "0"
+/// ├── operating_points_cnt_minus_1: "0"
 /// ├── operating_points[0]
 /// │   ├── operating_point_idc: "0x000"
 /// │   └── seq_level_idx: "5"
 /// ├── max_frame_width_minus_1: "1919"
 /// ├── max_frame_height_minus_1: "1079"
+/// ├── ...
 /// └── color_config
 ///     ├── high_bitdepth: "0"
 ///     └── ...
@@ -66,123 +84,88 @@ pub fn parse_sequence_header_syntax(
         format!("{} ({})", profile_val, profile.name()),
     );
 
-    // still_picture (1 bit) - AV1 Spec 5.5.2
-    let (still, range) = reader.read_bit_tracked()?;
-    builder.add_field("still_picture", range, format!("{}", still as u8));
-
-    // reduced_still_picture_header (1 bit) - AV1 Spec 5.5.3
-    let (reduced, range) = reader.read_bit_tracked()?;
-    builder.add_field(
-        "reduced_still_picture_header",
-        range,
-        format!("{}", reduced as u8),
-    );
+    read_flag(reader, builder, "still_picture")?;
+    let reduced = read_flag(reader, builder, "reduced_still_picture_header")?;
 
     if reduced {
-        // Simplified path for still pictures - AV1 Spec 5.5.4
-        // Only parse seq_level_idx, then skip to color_config
-        let (level, range) = reader.read_bits_tracked(5)?;
-        builder.add_field("seq_level_idx", range, format!("{}", level));
-
-        // In reduced mode, skip to color_config (no dimensions, no feature flags)
-        parse_color_config_simplified(reader, builder, profile_val as u8)?;
+        // Reduced still picture header: a single level, no timing info
+        read_bits(reader, builder, "seq_level_idx", 5)?;
     } else {
-        // Full sequence header - AV1 Spec 5.5.5+
-        parse_non_reduced_path(reader, builder, profile_val as u8)?;
-
-        // Parse remaining common fields
-        parse_frame_dimensions(reader, builder)?;
-        parse_feature_flags(reader, builder)?;
-        parse_color_config_simplified(reader, builder, profile_val as u8)?;
+        parse_non_reduced_path(reader, builder)?;
     }
 
+    parse_frame_dimensions(reader, builder, reduced)?;
+    parse_feature_flags(reader, builder, reduced)?;
+    parse_color_config(reader, builder, profile_val)?;
+
     // film_grain_params_present (1 bit) - AV1 Spec 5.5.26
-    let (val, range) = reader.read_bit_tracked()?;
-    builder.add_field("film_grain_params_present", range, format!("{}", val as u8));
+    read_flag(reader, builder, "film_grain_params_present")?;
 
     let end = reader.position();
     builder.pop_container(end);
     Ok(())
 }
 
+/// Read a single bit flag and add it as a field
+fn read_flag(
+    reader: &mut TrackedBitReader,
+    builder: &mut SyntaxBuilder,
+    name: &str,
+) -> Result<bool> {
+    let (val, range) = reader.read_bit_tracked()?;
+    builder.add_field(name, range, format!("{}", val as u8));
+    Ok(val)
+}
+
+/// Read an `n`-bit unsigned field and add it
+fn read_bits(
+    reader: &mut TrackedBitReader,
+    builder: &mut SyntaxBuilder,
+    name: &str,
+    n: u8,
+) -> Result<u32> {
+    let (val, range) = reader.read_bits_tracked(n)?;
+    builder.add_field(name, range, format!("{}", val));
+    Ok(val)
+}
+
 /// Parse non-reduced sequence header path (timing info, operating points)
 fn parse_non_reduced_path(
     reader: &mut TrackedBitReader,
     builder: &mut SyntaxBuilder,
-    _profile: u8,
 ) -> Result<()> {
     // timing_info_present_flag (1 bit) - AV1 Spec 5.5.5
-    let (timing_present, range) = reader.read_bit_tracked()?;
-    builder.add_field(
-        "timing_info_present_flag",
-        range,
-        format!("{}", timing_present as u8),
-    );
+    let timing_present = read_flag(reader, builder, "timing_info_present_flag")?;
 
-    let decoder_model_present = if timing_present {
-        parse_timing_info_simplified(reader, builder)?;
+    let buffer_delay_length = if timing_present {
+        parse_timing_info(reader, builder)?;
 
         // decoder_model_info_present_flag (1 bit) - AV1 Spec 5.5.7
-        let (decoder_present, range) = reader.read_bit_tracked()?;
-        builder.add_field(
-            "decoder_model_info_present_flag",
-            range,
-            format!("{}", decoder_present as u8),
-        );
-
-        if decoder_present {
-            // Phase 0: Skip decoder_model_info details
-            // Placeholder for future expansion
-            skip_decoder_model_info(reader)?;
+        if read_flag(reader, builder, "decoder_model_info_present_flag")? {
+            Some(parse_decoder_model_info(reader, builder)?)
+        } else {
+            None
         }
-        decoder_present
     } else {
-        false
+        None
     };
 
     // initial_display_delay_present_flag (1 bit) - AV1 Spec 5.5.8
-    let (display_delay, range) = reader.read_bit_tracked()?;
-    builder.add_field(
-        "initial_display_delay_present_flag",
-        range,
-        format!("{}", display_delay as u8),
-    );
+    let display_delay = read_flag(reader, builder, "initial_display_delay_present_flag")?;
 
-    // Parse operating points array
-    parse_operating_points_simplified(
-        reader,
-        builder,
-        timing_present,
-        decoder_model_present,
-        display_delay,
-    )?;
-
-    Ok(())
+    parse_operating_points(reader, builder, buffer_delay_length, display_delay)
 }
 
-/// Parse timing_info structure (simplified)
-///
-/// Phase 0: Parse only key fields, skip uvlc conditional
-fn parse_timing_info_simplified(
-    reader: &mut TrackedBitReader,
-    builder: &mut SyntaxBuilder,
-) -> Result<()> {
+/// Parse timing_info structure
+fn parse_timing_info(reader: &mut TrackedBitReader, builder: &mut SyntaxBuilder) -> Result<()> {
     let start = reader.position();
     builder.push_container("timing_info", start);
 
     // num_units_in_display_tick (32 bits) - AV1 Spec 5.5.6
-    let (val, range) = reader.read_bits_tracked(32)?;
-    builder.add_field("num_units_in_display_tick", range, format!("{}", val));
-
-    // time_scale (32 bits)
-    let (val, range) = reader.read_bits_tracked(32)?;
-    builder.add_field("time_scale", range, format!("{}", val));
-
-    // equal_picture_interval (1 bit)
-    let (equal, range) = reader.read_bit_tracked()?;
-    builder.add_field("equal_picture_interval", range, format!("{}", equal as u8));
+    read_bits(reader, builder, "num_units_in_display_tick", 32)?;
+    read_bits(reader, builder, "time_scale", 32)?;
 
-    if equal {
+    if read_flag(reader, builder, "equal_picture_interval")? {
         // num_ticks_per_picture_minus_1 (uvlc) - Variable length
         let (val, range) = reader.read_uvlc_tracked()?;
         builder.add_field("num_ticks_per_picture_minus_1", range, format!("{}", val));
@@ -193,41 +176,39 @@ fn parse_timing_info_simplified(
     Ok(())
 }
 
-/// Skip decoder_model_info structure
+/// Parse decoder_model_info structure
 ///
-/// Phase 0: Deferred to future implementation
-/// Skips 5 + 32 + 5 + 5 = 47 bits
-fn skip_decoder_model_info(reader: &mut TrackedBitReader) -> Result<()> {
-    // buffer_delay_length_minus_1 (5 bits)
-    // num_units_in_decoding_tick (32 bits)
-    // buffer_removal_time_length_minus_1 (5 bits)
-    // frame_presentation_time_length_minus_1 (5 bits)
-    reader.skip_bits(47)?;
-    Ok(())
+/// Returns the length in bits of the operating points' buffer delays.
+fn parse_decoder_model_info(
+    reader: &mut TrackedBitReader,
+    builder: &mut SyntaxBuilder,
+) -> Result<u8> {
+    let start = reader.position();
+    builder.push_container("decoder_model_info", start);
+
+    let buffer_delay_length_minus_1 = read_bits(reader, builder, "buffer_delay_length_minus_1", 5)?;
+    read_bits(reader, builder, "num_units_in_decoding_tick", 32)?;
+    read_bits(reader, builder, "buffer_removal_time_length_minus_1", 5)?;
+    read_bits(reader, builder, "frame_presentation_time_length_minus_1", 5)?;
+
+    let end = reader.position();
+    builder.pop_container(end);
+    Ok(buffer_delay_length_minus_1 as u8 + 1)
 }
 
-/// Parse operating points array (simplified)
+/// Parse operating points array
 ///
-/// Phase 0: Parse idc, seq_level_idx, seq_tier.
-/// Skip decoder_model params but correctly handle initial_display_delay.
-fn parse_operating_points_simplified(
+/// `buffer_delay_length` is set when decoder model info is present.
+fn parse_operating_points(
     reader: &mut TrackedBitReader,
     builder: &mut SyntaxBuilder,
-    timing_info_present: bool,
-    decoder_model_present: bool,
+    buffer_delay_length: Option<u8>,
     initial_display_delay_present: bool,
 ) -> Result<()> {
     // operating_points_cnt_minus_1 (5 bits) - AV1 Spec 5.5.9
-    let (cnt_minus_1, range) = reader.read_bits_tracked(5)?;
-    builder.add_field(
-        "operating_points_cnt_minus_1",
-        range,
-        format!("{}", cnt_minus_1),
-    );
-
-    let count = cnt_minus_1 + 1;
+    let cnt_minus_1 = read_bits(reader, builder, "operating_points_cnt_minus_1", 5)?;
 
-    for i in 0..count {
+    for i in 0..=cnt_minus_1 {
         let op_start = reader.position();
         builder.push_container(&format!("operating_points[{}]", i), op_start);
 
@@ -236,34 +217,30 @@ fn parse_operating_points_simplified(
         builder.add_field("operating_point_idc", range, format!("0x{:03X}", idc));
 
         // seq_level_idx (5 bits) - AV1 Spec 5.5.11
-        let (level, range) = reader.read_bits_tracked(5)?;
-        builder.add_field("seq_level_idx", range, format!("{}", level));
+        let level = read_bits(reader, builder, "seq_level_idx", 5)?;
 
         // seq_tier (1 bit) - Conditional on level > 7 - AV1 Spec 5.5.12
         if level > 7 {
-            let (tier, range) = reader.read_bit_tracked()?;
-            builder.add_field("seq_tier", range, format!("{}", tier as u8));
+            read_flag(reader, builder, "seq_tier")?;
         }
 
-        // decoder_model_present_for_this_op (conditional)
-        if timing_info_present && decoder_model_present {
-            let (decoder_model_for_op, _) = reader.read_bit_tracked()?;
-            if decoder_model_for_op {
-                // Phase 0: Skip operating_parameters_info()
-                // decoder_buffer_delay (n bits), encoder_buffer_delay (n bits), low_delay_mode_flag (1 bit)
-                // n = buffer_delay_length_minus_1 + 1 (from decoder_model_info)
-                // For simplicity, skip 60 bits (approximate, actual depends on buffer_delay_length)
-                reader.skip_bits(60)?;
+        if let Some(n) = buffer_delay_length {
+            if read_flag(reader, builder, "decoder_model_present_for_this_op")? {
+                // operating_parameters_info()
+                let start = reader.position();
+                builder.push_container("operating_parameters_info", start);
+                read_bits(reader, builder, "decoder_buffer_delay", n)?;
+                read_bits(reader, builder, "encoder_buffer_delay", n)?;
+                read_flag(reader, builder, "low_delay_mode_flag")?;
+                let end = reader.position();
+                builder.pop_container(end);
             }
         }
 
-        // initial_display_delay_present_for_this_op (conditional)
-        if initial_display_delay_present {
-            let (delay_for_op, _) = reader.read_bit_tracked()?;
-            if delay_for_op {
-                // initial_display_delay_minus_1 (4 bits)
-                reader.skip_bits(4)?;
-            }
+        if initial_display_delay_present
+            && read_flag(reader, builder, "initial_display_delay_present_for_this_op")?
+        {
+            read_bits(reader, builder, "initial_display_delay_minus_1", 4)?;
         }
 
         let op_end = reader.position();
@@ -273,219 +250,133 @@ fn parse_operating_points_simplified(
     Ok(())
 }
 
-/// Parse frame dimensions
+/// Parse frame dimensions and frame id lengths
 fn parse_frame_dimensions(
     reader: &mut TrackedBitReader,
     builder: &mut SyntaxBuilder,
+    reduced: bool,
 ) -> Result<()> {
-    // frame_width_bits_minus_1 (4 bits) - AV1 Spec 5.5.15
-    let (width_bits, range) = reader.read_bits_tracked(4)?;
-    builder.add_field("frame_width_bits_minus_1", range, format!("{}", width_bits));
+    // frame_width_bits_minus_1 / frame_height_bits_minus_1 - AV1 Spec 5.5.15-16
+    let width_bits = read_bits(reader, builder, "frame_width_bits_minus_1", 4)?;
+    let height_bits = read_bits(reader, builder, "frame_height_bits_minus_1", 4)?;
 
-    // frame_height_bits_minus_1 (4 bits) - AV1 Spec 5.5.16
-    let (height_bits, range) = reader.read_bits_tracked(4)?;
-    builder.add_field(
-        "frame_height_bits_minus_1",
-        range,
-        format!("{}", height_bits),
-    );
-
-    // max_frame_width_minus_1 (n+1 bits) - Variable width! - AV1 Spec 5.5.17
-    let width_n = (width_bits + 1) as u8;
-    let (width, range) = reader.read_bits_tracked(width_n)?;
-    builder.add_field("max_frame_width_minus_1", range, format!("{}", width));
+    // max_frame_width_minus_1 / max_frame_height_minus_1 (n+1 bits) - AV1 Spec 5.5.17-18
+    read_bits(
+        reader,
+        builder,
+        "max_frame_width_minus_1",
+        width_bits as u8 + 1,
+    )?;
+    read_bits(
+        reader,
+        builder,
+        "max_frame_height_minus_1",
+        height_bits as u8 + 1,
+    )?;
 
-    // max_frame_height_minus_1 (n+1 bits) - Variable width! - AV1 Spec 5.5.18
-    let height_n = (height_bits + 1) as u8;
-    let (height, range) = reader.read_bits_tracked(height_n)?;
-    builder.add_field("max_frame_height_minus_1", range, format!("{}", height));
+    // Frame ID fields - AV1 Spec 5.5.19-21
+    if !reduced && read_flag(reader, builder, "frame_id_numbers_present_flag")? {
+        read_bits(reader, builder, "delta_frame_id_length_minus_2", 4)?;
+        read_bits(reader, builder, "additional_frame_id_length_minus_1", 3)?;
+    }
 
     Ok(())
 }
 
-/// Parse feature enable flags (non-reduced path only)
-fn parse_feature_flags(reader: &mut TrackedBitReader, builder: &mut SyntaxBuilder) -> Result<()> {
-    // Frame ID fields (conditional) - AV1 Spec 5.5.19-21
-    let (frame_ids, range) = reader.read_bit_tracked()?;
-    builder.add_field(
-        "frame_id_numbers_present_flag",
-        range,
-        format!("{}", frame_ids as u8),
-    );
-
-    if frame_ids {
-        // Phase 0: Skip delta_frame_id_length_minus_2 (4 bits)
-        // and additional_frame_id_length_minus_1 (3 bits)
-        reader.skip_bits(7)?;
-    }
-
-    // use_128x128_superblock (1 bit) - AV1 Spec 5.5.22
-    let (val, range) = reader.read_bit_tracked()?;
-    builder.add_field("use_128x128_superblock", range, format!("{}", val as u8));
-
-    // enable_filter_intra (1 bit) - AV1 Spec 5.5.23
-    let (val, range) = reader.read_bit_tracked()?;
-    builder.add_field("enable_filter_intra", range, format!("{}", val as u8));
-
-    // enable_intra_edge_filter (1 bit) - AV1 Spec 5.5.24
-    let (val, range) = reader.read_bit_tracked()?;
-    builder.add_field("enable_intra_edge_filter", range, format!("{}", val as u8));
-
-    // Phase 0: Parse inter/compound flags to properly track bit positions
-    // enable_interintra_compound (1 bit)
-    reader.skip_bits(1)?;
-    // enable_masked_compound (1 bit)
-    reader.skip_bits(1)?;
-    // enable_warped_motion (1 bit)
-    reader.skip_bits(1)?;
-    // enable_dual_filter (1 bit)
-    reader.skip_bits(1)?;
-
-    // enable_order_hint (1 bit) - Parse this since it affects skip logic
-    let (enable_order_hint, range) = reader.read_bit_tracked()?;
-    builder.add_field(
-        "enable_order_hint",
-        range,
-        format!("{}", enable_order_hint as u8),
-    );
-
-    if enable_order_hint {
-        // enable_jnt_comp (1 bit)
-        reader.skip_bits(1)?;
-        // enable_ref_frame_mvs (1 bit)
-        reader.skip_bits(1)?;
-    }
+/// Parse feature enable flags
+fn parse_feature_flags(
+    reader: &mut TrackedBitReader,
+    builder: &mut SyntaxBuilder,
+    reduced: bool,
+) -> Result<()> {
+    // AV1 Spec 5.5.22-24
+    read_flag(reader, builder, "use_128x128_superblock")?;
+    read_flag(reader, builder, "enable_filter_intra")?;
+    read_flag(reader, builder, "enable_intra_edge_filter")?;
+
+    if !reduced {
+        read_flag(reader, builder, "enable_interintra_compound")?;
+        read_flag(reader, builder, "enable_masked_compound")?;
+        read_flag(reader, builder, "enable_warped_motion")?;
+        read_flag(reader, builder, "enable_dual_filter")?;
+
+        let enable_order_hint = read_flag(reader, builder, "enable_order_hint")?;
+        if enable_order_hint {
+            read_flag(reader, builder, "enable_jnt_comp")?;
+            read_flag(reader, builder, "enable_ref_frame_mvs")?;
+        }
 
-    // seq_choose_screen_content_tools (1 bit)
-    let (choose_sct, _) = reader.read_bit_tracked()?;
-    if !choose_sct {
-        // seq_force_screen_content_tools (1 bit)
-        let (force_sct, _) = reader.read_bits_tracked(1)?;
-        if force_sct > 0 {
-            // seq_choose_integer_mv (1 bit)
-            let (choose_imv, _) = reader.read_bit_tracked()?;
-            if !choose_imv {
-                // seq_force_integer_mv (1 bit)
-                reader.skip_bits(1)?;
-            }
+        let force_screen_content_tools =
+            if read_flag(reader, builder, "seq_choose_screen_content_tools")? {
+                SELECT_SCREEN_CONTENT_TOOLS
+            } else {
+                read_bits(reader, builder, "seq_force_screen_content_tools", 1)?
+            };
+        if force_screen_content_tools > 0 && !read_flag(reader, builder, "seq_choose_integer_mv")? {
+            read_bits(reader, builder, "seq_force_integer_mv", 1)?;
         }
-    }
 
-    if enable_order_hint {
-        // order_hint_bits_minus_1 (3 bits)
-        reader.skip_bits(3)?;
+        if enable_order_hint {
+            read_bits(reader, builder, "order_hint_bits_minus_1", 3)?;
+        }
     }
 
-    // enable_superres (1 bit) - AV1 Spec 5.5.25
-    let (val, range) = reader.read_bit_tracked()?;
-    builder.add_field("enable_superres", range, format!("{}", val as u8));
-
-    // enable_cdef (1 bit)
-    let (val, range) = reader.read_bit_tracked()?;
-    builder.add_field("enable_cdef", range, format!("{}", val as u8));
-
-    // enable_restoration (1 bit)
-    let (val, range) = reader.read_bit_tracked()?;
-    builder.add_field("enable_restoration", range, format!("{}", val as u8));
+    // enable_superres / enable_cdef / enable_restoration - AV1 Spec 5.5.25
+    read_flag(reader, builder, "enable_superres")?;
+    read_flag(reader, builder, "enable_cdef")?;
+    read_flag(reader, builder, "enable_restoration")?;
 
     Ok(())
 }
 
-/// Parse color_config structure (simplified for Phase 0)
-///
-/// Phase 0 scope:
-/// - high_bitdepth, twelve_bit (conditional), mono_chrome (conditional)
-/// - color_description_present_flag and 3 color fields (if present)
-/// - color_range
-///
-/// Deferred:
-/// - subsampling_x, subsampling_y (profile-dependent logic)
-/// - chroma_sample_position (conditional on subsampling)
-/// - separate_uv_delta_q
-fn parse_color_config_simplified(
+/// Parse color_config structure - AV1 Spec 5.5.2 / 6.4
+fn parse_color_config(
     reader: &mut TrackedBitReader,
     builder: &mut SyntaxBuilder,
-    profile: u8,
+    profile: u32,
 ) -> Result<()> {
     let start = reader.position();
     builder.push_container("color_config", start);
 
-    // high_bitdepth (1 bit) - AV1 Spec 6.4.1
-    let (high_bd, range) = reader.read_bit_tracked()?;
-    builder.add_field("high_bitdepth", range, format!("{}", high_bd as u8));
-
-    // twelve_bit (1 bit) - Conditional on Professional profile + high_bd - AV1 Spec 6.4.2
-    if profile == 2 && high_bd {
-        let (twelve, range) = reader.read_bit_tracked()?;
-        builder.add_field("twelve_bit", range, format!("{}", twelve as u8));
-    }
-
-    // mono_chrome (1 bit) - Conditional on !High profile - AV1 Spec 6.4.3
-    let _mono = if profile != 1 {
-        let (mono, range) = reader.read_bit_tracked()?;
-        builder.add_field("mono_chrome", range, format!("{}", mono as u8));
-        mono
-    } else {
-        false
-    };
-
-    // color_description_present_flag (1 bit) - AV1 Spec 6.4.4
-    let (desc_present, range) = reader.read_bit_tracked()?;
-    builder.add_field(
-        "color_description_present_flag",
-        range,
-        format!("{}", desc_present as u8),
-    );
-
-    if desc_present {
-        // color_primaries (8 bits) - AV1 Spec 6.4.5
-        let (val, range) = reader.read_bits_tracked(8)?;
-        builder.add_field("color_primaries", range, format!("{}", val));
-
-        // transfer_characteristics (8 bits) - AV1 Spec 6.4.6
-        let (val, range) = reader.read_bits_tracked(8)?;
-        builder.add_field("transfer_characteristics", range, format!("{}", val));
-
-        // matrix_coefficients (8 bits) - AV1 Spec 6.4.7
-        let (val, range) = reader.read_bits_tracked(8)?;
-        builder.add_field("matrix_coefficients", range, format!("{}", val));
-    }
-
-    // color_range (1 bit) - AV1 Spec 6.4.8
-    let (val, range) = reader.read_bit_tracked()?;
-    builder.add_field("color_range", range, format!("{}", val as u8));
-
-    // Subsampling (conditional on profile and mono_chrome)
-    if profile == 1 {
-        // High Profile: 4:4:4 (no subsampling)
-        // subsampling_x = 0, subsampling_y = 0 (implicit)
-    } else if profile == 2 {
-        // Professional Profile: bit_depth determines subsampling
-        // For simplicity, skip detailed logic - just parse the bits
-        // subsampling_x (1 bit)
-        let (sub_x, range) = reader.read_bit_tracked()?;
-        builder.add_field("subsampling_x", range, format!("{}", sub_x as u8));
-
-        if sub_x {
-            // subsampling_y (1 bit)
-            let (sub_y, range) = reader.read_bit_tracked()?;
-            builder.add_field("subsampling_y", range, format!("{}", sub_y as u8));
-        }
-        // chroma_sample_position is conditional - skip for Phase 0
+    let high_bd = read_flag(reader, builder, "high_bitdepth")?;
+    let twelve_bit = profile == 2 && high_bd && read_flag(reader, builder, "twelve_bit")?;
+
+    // High profile is always 4:4:4 with chroma
+    let mono = profile != 1 && read_flag(reader, builder, "mono_chrome")?;
+
+    let (primaries, transfer, matrix) =
+        if read_flag(reader, builder, "color_description_present_flag")? {
+            (
+                read_bits(reader, builder, "color_primaries", 8)?,
+                read_bits(reader, builder, "transfer_characteristics", 8)?,
+                read_bits(reader, builder, "matrix_coefficients", 8)?,
+            )
+        } else {
+            // CP_UNSPECIFIED, TC_UNSPECIFIED, MC_UNSPECIFIED
+            (2, 2, 2)
+        };
+
+    if mono {
+        read_flag(reader, builder, "color_range")?;
     } else {
-        // Main Profile (profile == 0): typically 4:2:0
-        if !_mono {
-            // subsampling_x = 1, subsampling_y = 1 (implicit for 4:2:0)
-            // chroma_sample_position (2 bits)
-            let (val, range) = reader.read_bits_tracked(2)?;
-            builder.add_field("chroma_sample_position", range, format!("{}", val));
+        let srgb = primaries == CP_BT_709 && transfer == TC_SRGB && matrix == MC_IDENTITY;
+        if !srgb {
+            read_flag(reader, builder, "color_range")?;
+            let (subsampling_x, subsampling_y) = match profile {
+                0 => (true, true),
+                1 => (false, false),
+                _ if twelve_bit => {
+                    let x = read_flag(reader, builder, "subsampling_x")?;
+                    let y = x && read_flag(reader, builder, "subsampling_y")?;
+                    (x, y)
+                }
+                _ => (true, false),
+            };
+            if subsampling_x && subsampling_y {
+                read_bits(reader, builder, "chroma_sample_position", 2)?;
+            }
         }
-    }
-
-    // separate_uv_delta_q (1 bit) - AV1 Spec 6.4.11
-    if !_mono {
-        let (val, range) = reader.read_bit_tracked()?;
-        builder.add_field("separate_uv_delta_q", range, format!("{}", val as u8));
+        read_flag(reader, builder, "separate_uv_delta_q")?;
     }
 
     let end = reader.position();
@@ -546,5 +437,71 @@ mod tests {
             .get_node("obu[0].sequence_header.seq_level_idx")
             .unwrap();
         assert_eq!(level.bit_range, BitRange::new(5, 10));
+
+        // Frame dimensions follow, but no frame id or inter tool flags
+        let width_bits = model
+            .get_node("obu[0].sequence_header.frame_width_bits_minus_1")
+            .unwrap();
+        assert_eq!(width_bits.bit_range, BitRange::new(10, 14));
+        assert!(model
+            .get_node("obu[0].sequence_header.enable_order_hint")
+            .is_none());
+    }
+
+    #[test]
+    fn test_sequence_header_matches_parser() {
+        let path =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../samples/foreman_av1.ivf");
+        let Ok(data) = std::fs::read(&path) else {
+            eprintln!("Skipping test: {} not found", path.display());
+            return;
+        };
+        let (_, frames) = crate::parse_ivf_frames(&data).unwrap();
+        let obus = crate::parse_all_obus(&frames[0].data).unwrap();
+        let payload = &obus
+            .iter()
+            .find(|o| o.header.obu_type == crate::ObuType::SequenceHeader)
+            .unwrap()
+            .payload;
+        let seq = crate::parse_sequence_header(payload).unwrap();
+
+        let mut reader = TrackedBitReader::new(payload, 0);
+        let mut builder = SyntaxBuilder::new("obu[0]".to_string(), "obu_0".to_string());
+        parse_sequence_header_syntax(&mut reader, &mut builder).unwrap();
+        let model = builder.build();
+
+        let value = |name: &str| {
+            model
+                .get_node(&format!("obu[0].sequence_header.{}", name))
+                .and_then(|n| n.value.clone())
+                .unwrap_or_else(|| panic!("missing {}", name))
+        };
+        assert_eq!(
+            value("max_frame_width_minus_1"),
+            (seq.max_frame_width - 1).to_string()
+        );
+        assert_eq!(
+            value("enable_order_hint"),
+            (seq.enable_order_hint as u8).to_string()
+        );
+        assert_eq!(
+            value("order_hint_bits_minus_1"),
+            seq.order_hint_bits_minus_1.unwrap().to_string()
+        );
+        assert_eq!(
+            value("film_grain_params_present"),
+            (seq.film_grain_params_present as u8).to_string()
+        );
+        assert!(model
+            .get_node("obu[0].sequence_header.color_config.separate_uv_delta_q")
+            .is_some());
+
+        // The tree accounts for every bit up to the trailing one bit
+        let end = model
+            .get_node("obu[0].sequence_header")
+            .unwrap()
+            .bit_range
+            .end_bit;
+        assert_eq!((end + 1).div_ceil(8) as usize, payload.len());
     }
 }
@@ -1,45 +1,30 @@
-//! Coding Unit Parsing
+//! Coding Unit Types
 //!
-//! Per AV1 Specification Section 5.11 (Coding Block Syntax)
+//! Per AV1 Specification Section 5.11.5 (Decode block syntax)
 //!
-//! A Coding Unit contains:
-//! - Prediction information (INTRA/INTER mode)
-//! - Motion vectors (for INTER blocks)
-//! - Transform information
-//! - Quantization parameters
-//! - Residual data
+//! A Coding Unit describes one coded block:
+//! - Prediction information (INTRA/INTER mode, reference frames)
+//! - Motion vectors (for INTER and intra block copy blocks)
+//! - Transform size
+//! - Segment and quantizer index
 //!
-//! ## Implementation Strategy
-//!
-//! **Phase 1** (Current):
-//! - Parse skip flag
-//! - Parse prediction mode
-//! - Parse reference frames (for INTER)
-//!
-//! **Phase 2**:
-//! - Parse motion vectors
-//! - Calculate MV predictors
-//! - Reconstruct final MVs
-//!
-//! **Phase 3**:
-//! - Parse transform sizes
-//! - Parse quantization info
-//! - Parse residuals (optional for visualization)
+//! The syntax is parsed by the tile decoder ([`super::decoder`]).
 
-use crate::symbol::SymbolDecoder;
-use bitvue_core::{BitvueError, Result};
 use serde::{Deserialize, Serialize};
 
 /// Prediction mode for intra and inter prediction
 ///
+/// Discriminants are the specification values of `YMode`.
+///
 /// # Intra Modes (DcPred through PaethPred)
 /// Used for intra-frame prediction where pixels are predicted from previously
 /// coded samples within the same frame. Each mode uses a specific directional
 /// or DC-based prediction strategy.
 ///
-/// # Inter Modes (NewMv through GlobalMv)
+/// # Inter Modes (NearestMv through NewNewMv)
 /// Used for inter-frame prediction where pixels are predicted from reference frames
-/// using motion vectors. Each mode represents a different MV selection strategy.
+/// using motion vectors. Each mode represents a different MV selection strategy;
+/// the two-part names select the MV of each reference of a compound block.
 ///
 /// # AV1 Specific Modes
 /// - **DcPred**: DC prediction (average of above/left samples)
@@ -50,63 +35,99 @@ use serde::{Deserialize, Serialize};
 /// - **NearMv**: Use near MV from neighboring blocks
 /// - **GlobalMv**: Use global motion vector
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
 pub enum PredictionMode {
     /// DC prediction (INTRA)
-    DcPred,
+    DcPred = 0,
     /// Vertical prediction (INTRA)
-    VPred,
+    VPred = 1,
     /// Horizontal prediction (INTRA)
-    HPred,
+    HPred = 2,
     /// Diagonal prediction (INTRA)
-    D45Pred,
+    D45Pred = 3,
     /// Diagonal prediction (INTRA)
-    D135Pred,
+    D135Pred = 4,
     /// Diagonal prediction (INTRA)
-    D113Pred,
+    D113Pred = 5,
     /// Diagonal prediction (INTRA)
-    D157Pred,
+    D157Pred = 6,
     /// Diagonal prediction (INTRA)
-    D203Pred,
+    D203Pred = 7,
     /// Diagonal prediction (INTRA)
-    D67Pred,
+    D67Pred = 8,
     /// Smooth prediction (INTRA)
-    SmoothPred,
+    SmoothPred = 9,
     /// Smooth vertical (INTRA)
-    SmoothVPred,
+    SmoothVPred = 10,
     /// Smooth horizontal (INTRA)
-    SmoothHPred,
+    SmoothHPred = 11,
     /// Paeth prediction (INTRA)
-    PaethPred,
+    PaethPred = 12,
 
-    /// INTER: Single reference, single MV
-    NewMv,
     /// INTER: Use nearest MV from neighbors
-    NearestMv,
+    NearestMv = 13,
     /// INTER: Use near MV from neighbors
-    NearMv,
+    NearMv = 14,
     /// INTER: Global motion
-    GlobalMv,
+    GlobalMv = 15,
+    /// INTER: Single reference, single MV
+    NewMv = 16,
+    /// INTER compound: nearest MV for both references
+    NearestNearestMv = 17,
+    /// INTER compound: near MV for both references
+    NearNearMv = 18,
+    /// INTER compound: nearest MV, then a new MV
+    NearestNewMv = 19,
+    /// INTER compound: new MV, then the nearest MV
+    NewNearestMv = 20,
+    /// INTER compound: near MV, then a new MV
+    NearNewMv = 21,
+    /// INTER compound: new MV, then a near MV
+    NewNearMv = 22,
+    /// INTER compound: global motion for both references
+    GlobalGlobalMv = 23,
+    /// INTER compound: new MVs for both references
+    NewNewMv = 24,
 }
 
+/// All prediction modes in specification order
+const PREDICTION_MODES: [PredictionMode; 25] = [
+    PredictionMode::DcPred,
+    PredictionMode::VPred,
+    PredictionMode::HPred,
+    PredictionMode::D45Pred,
+    PredictionMode::D135Pred,
+    PredictionMode::D113Pred,
+    PredictionMode::D157Pred,
+    PredictionMode::D203Pred,
+    PredictionMode::D67Pred,
+    PredictionMode::SmoothPred,
+    PredictionMode::SmoothVPred,
+    PredictionMode::SmoothHPred,
+    PredictionMode::PaethPred,
+    PredictionMode::NearestMv,
+    PredictionMode::NearMv,
+    PredictionMode::GlobalMv,
+    PredictionMode::NewMv,
+    PredictionMode::NearestNearestMv,
+    PredictionMode::NearNearMv,
+    PredictionMode::NearestNewMv,
+    PredictionMode::NewNearestMv,
+    PredictionMode::NearNewMv,
+    PredictionMode::NewNearMv,
+    PredictionMode::GlobalGlobalMv,
+    PredictionMode::NewNewMv,
+];
+
 impl PredictionMode {
+    /// Parse from the specification value (0-24)
+    pub fn from_u8(value: u8) -> Option<Self> {
+        PREDICTION_MODES.get(value as usize).copied()
+    }
+
     /// Check if this is an INTRA mode
     pub fn is_intra(&self) -> bool {
-        matches!(
-            self,
-            PredictionMode::DcPred
-                | PredictionMode::VPred
-                | PredictionMode::HPred
-                | PredictionMode::D45Pred
-                | PredictionMode::D135Pred
-                | PredictionMode::D113Pred
-                | PredictionMode::D157Pred
-                | PredictionMode::D203Pred
-                | PredictionMode::D67Pred
-                | PredictionMode::SmoothPred
-                | PredictionMode::SmoothVPred
-                | PredictionMode::SmoothHPred
-                | PredictionMode::PaethPred
-        )
+        (*self as u8) <= PredictionMode::PaethPred as u8
     }
 
     /// Check if this is an INTER mode
@@ -114,9 +135,22 @@ impl PredictionMode {
         !self.is_intra()
     }
 
+    /// Check if this is a compound (two reference) INTER mode
+    pub fn is_compound(&self) -> bool {
+        (*self as u8) >= PredictionMode::NearestNearestMv as u8
+    }
+
     /// Check if this mode requires reading motion vectors
     pub fn needs_mv(&self) -> bool {
-        matches!(self, PredictionMode::NewMv)
+        matches!(
+            self,
+            PredictionMode::NewMv
+                | PredictionMode::NearestNewMv
+                | PredictionMode::NewNearestMv
+                | PredictionMode::NearNewMv
+                | PredictionMode::NewNearMv
+                | PredictionMode::NewNewMv
+        )
     }
 }
 
@@ -174,6 +208,13 @@ impl MotionVector {
         Self { x, y }
     }
 
+    /// Create from components in 1/8 pel units (the AV1 MV precision)
+    ///
+    /// Components are halved, rounding towards zero.
+    pub fn from_eighth_pel(x: i32, y: i32) -> Self {
+        Self { x: x / 2, y: y / 2 }
+    }
+
     /// Zero motion vector
     pub fn zero() -> Self {
         Self { x: 0, y: 0 }
@@ -249,37 +290,94 @@ impl MotionVector {
 }
 
 /// Transform size enumeration
+///
+/// Discriminants are the specification `TX_*` values.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
 pub enum TxSize {
     Tx4x4 = 0,
     Tx8x8 = 1,
     Tx16x16 = 2,
     Tx32x32 = 3,
     Tx64x64 = 4,
+    Tx4x8 = 5,
+    Tx8x4 = 6,
+    Tx8x16 = 7,
+    Tx16x8 = 8,
+    Tx16x32 = 9,
+    Tx32x16 = 10,
+    Tx32x64 = 11,
+    Tx64x32 = 12,
+    Tx4x16 = 13,
+    Tx16x4 = 14,
+    Tx8x32 = 15,
+    Tx32x8 = 16,
+    Tx16x64 = 17,
+    Tx64x16 = 18,
 }
 
+/// All transform sizes in specification order
+const TX_SIZES: [TxSize; 19] = [
+    TxSize::Tx4x4,
+    TxSize::Tx8x8,
+    TxSize::Tx16x16,
+    TxSize::Tx32x32,
+    TxSize::Tx64x64,
+    TxSize::Tx4x8,
+    TxSize::Tx8x4,
+    TxSize::Tx8x16,
+    TxSize::Tx16x8,
+    TxSize::Tx16x32,
+    TxSize::Tx32x16,
+    TxSize::Tx32x64,
+    TxSize::Tx64x32,
+    TxSize::Tx4x16,
+    TxSize::Tx16x4,
+    TxSize::Tx8x32,
+    TxSize::Tx32x8,
+    TxSize::Tx16x64,
+    TxSize::Tx64x16,
+];
+
+/// log2 of the transform width (Tx_Width_Log2)
+const TX_WIDTH_LOG2: [u8; 19] = [2, 3, 4, 5, 6, 2, 3, 3, 4, 4, 5, 5, 6, 2, 4, 3, 5, 4, 6];
+
+/// log2 of the transform height (Tx_Height_Log2)
+const TX_HEIGHT_LOG2: [u8; 19] = [2, 3, 4, 5, 6, 3, 2, 4, 3, 5, 4, 6, 5, 4, 2, 5, 3, 6, 4];
+
 impl TxSize {
-    /// Get the size in pixels
+    /// Parse from the specification value (0-18)
+    pub fn from_u8(value: u8) -> Option<Self> {
+        TX_SIZES.get(value as usize).copied()
+    }
+
+    /// Get the size in pixels (the larger dimension)
     pub fn size(&self) -> u32 {
-        match self {
-            TxSize::Tx4x4 => 4,
-            TxSize::Tx8x8 => 8,
-            TxSize::Tx16x16 => 16,
-            TxSize::Tx32x32 => 32,
-            TxSize::Tx64x64 => 64,
-        }
+        self.width().max(self.height())
+    }
+
+    /// Transform width in pixels
+    pub fn width(&self) -> u32 {
+        1 << TX_WIDTH_LOG2[*self as usize]
+    }
+
+    /// Transform height in pixels
+    pub fn height(&self) -> u32 {
+        1 << TX_HEIGHT_LOG2[*self as usize]
     }
 
-    /// Get TxSize from block dimensions
+    /// Get the largest TxSize that fits a block (spec `Max_Tx_Size_Rect`)
+    ///
+    /// Dimensions are clamped to 4..=64 and transforms are at most 4:1.
     pub fn from_dimensions(width: u32, height: u32) -> Self {
-        let size = width.max(height);
-        match size {
-            0..=4 => TxSize::Tx4x4,
-            5..=8 => TxSize::Tx8x8,
-            9..=16 => TxSize::Tx16x16,
-            17..=32 => TxSize::Tx32x32,
-            _ => TxSize::Tx64x64,
-        }
+        let w = width.clamp(4, 64).next_power_of_two().min(64);
+        let h = height.clamp(4, 64).next_power_of_two().min(64);
+        let (w, h) = (w.min(h * 4), h.min(w * 4));
+        TX_SIZES
+            .iter()
+            .copied()
+            .find(|t| t.width() == w && t.height() == h)
+            .unwrap_or(TxSize::Tx4x4)
     }
 }
 
@@ -309,11 +407,26 @@ pub struct CodingUnit {
     pub mv: [MotionVector; 2],
 
     /// Transform size (for residual coding)
+    ///
+    /// The luma transform size of intra blocks and the largest one of
+    /// inter blocks with a transform tree.
     pub tx_size: TxSize,
 
     /// QP value (quantization parameter)
-    /// None for blocks that don't have QP (e.g., skip blocks)
+    ///
+    /// The quantizer index (`qindex`) of the block after segmentation and
+    /// delta Q, as used for dequantization.
     pub qp: Option<i16>,
+
+    /// Segment ID (0 when segmentation is disabled)
+    pub segment_id: u8,
+
+    /// Intra block copy (screen content); `mv[0]` holds the displacement
+    pub use_intrabc: bool,
+
+    /// Nonzero quantized coefficients over all planes (0 for skipped blocks)
+    #[serde(default)]
+    pub coeff_count: u32,
 }
 
 impl CodingUnit {
@@ -331,6 +444,9 @@ impl CodingUnit {
             mv: [MotionVector::zero(), MotionVector::zero()],
             tx_size,
             qp: None,
+            segment_id: 0,
+            use_intrabc: false,
+            coeff_count: 0,
         }
     }
 
@@ -351,176 +467,6 @@ impl CodingUnit {
     }
 }
 
-/// Parse coding unit from symbol decoder
-///
-/// Reads block-level syntax elements from the bitstream.
-///
-/// # Arguments
-///
-/// * `decoder` - Symbol decoder for reading entropy-coded symbols
-/// * `x`, `y` - Block position in pixels
-/// * `width`, `height` - Block dimensions in pixels
-/// * `is_key_frame` - True if this is a KEY frame (INTRA only)
-/// * `current_qp` - Current quantization parameter value
-/// * `delta_q_enabled` - True if delta Q is enabled for this frame
-/// * `mv_ctx` - MV predictor context for calculating motion vector predictors
-///
-/// # Returns
-///
-/// Parsed coding unit with prediction info, motion vectors (if INTER), and QP value
-pub fn parse_coding_unit(
-    decoder: &mut SymbolDecoder,
-    x: u32,
-    y: u32,
-    width: u32,
-    height: u32,
-    is_key_frame: bool,
-    current_qp: i16,
-    delta_q_enabled: bool,
-    mv_ctx: &mut crate::tile::MvPredictorContext,
-) -> Result<(CodingUnit, i16)> {
-    let mut cu = CodingUnit::new(x, y, width, height);
-
-    // Read skip flag
-    cu.skip = decoder.read_skip()?;
-
-    // TODO: Read segment ID (if segmentation enabled)
-
-    // Determine if INTRA or INTER
-    if is_key_frame {
-        // KEY frames are always INTRA
-        cu.ref_frames = [RefFrame::Intra, RefFrame::Intra];
-
-        // Read INTRA prediction mode
-        let mode_symbol = decoder.read_intra_mode()?;
-        cu.mode = intra_mode_from_symbol(mode_symbol)?;
-    } else {
-        // INTER frame - read prediction mode
-        let mode_symbol = decoder.read_inter_mode()?;
-        cu.mode = inter_mode_from_symbol(mode_symbol)?;
-
-        // TODO: Read reference frames
-        // For MVP, use LAST frame as default
-        cu.ref_frames = [RefFrame::Last, RefFrame::Intra];
-
-        // If NEWMV, read motion vectors
-        if cu.mode == PredictionMode::NewMv {
-            // Read MV for L0 (forward reference)
-            let mv_x = decoder.read_mv_component()?;
-            let mv_y = decoder.read_mv_component()?;
-            let explicit_mv = MotionVector::new(mv_x, mv_y);
-
-            // Get MV predictor and add to explicit MV
-            let predictor = mv_ctx.get_mv_predictor(cu.mode, x, y, cu.ref_frames[0]);
-            cu.mv[0] = MotionVector::new(explicit_mv.x + predictor.x, explicit_mv.y + predictor.y);
-
-            // TODO: If compound prediction (2 references), read L1 MV
-            // For MVP, single reference only
-            cu.mv[1] = MotionVector::zero();
-
-            tracing::debug!(
-                "NEWMV at ({}, {}): explicit=({:?}), predictor=({:?}), final=({:?})",
-                x,
-                y,
-                explicit_mv,
-                predictor,
-                cu.mv[0]
-            );
-        } else {
-            // For NEARESTMV, NEARMV, GLOBALMV: use predictor directly
-            let predictor = mv_ctx.get_mv_predictor(cu.mode, x, y, cu.ref_frames[0]);
-            cu.mv = [predictor, MotionVector::zero()];
-
-            tracing::debug!(
-                "Mode {:?} at ({}, {}): using predictor {:?}",
-                cu.mode,
-                x,
-                y,
-                cu.mv[0]
-            );
-        }
-    }
-
-    // Add this CU to the MV predictor context for future blocks
-    // Now uses zero-copy reference instead of cloning the entire CU
-    mv_ctx.add_cu(&cu);
-
-    // Read delta Q if enabled
-    // Per AV1 Spec Section 5.11.38 (Quantization Parameter Delta)
-    let new_qp = if delta_q_enabled {
-        match decoder.read_delta_q() {
-            Ok(delta_q) => {
-                // Apply delta Q to current QP
-                // Clamp to valid range [0, 255]
-                let qp = (current_qp + delta_q).clamp(0, 255);
-                tracing::debug!(
-                    "Delta Q applied at ({}, {}): {} + {} = {}",
-                    x,
-                    y,
-                    current_qp,
-                    delta_q,
-                    qp
-                );
-                cu.qp = Some(qp);
-                qp
-            }
-            Err(e) => {
-                tracing::warn!(
-                    "Failed to read delta Q at ({}, {}): {}, using current QP",
-                    x,
-                    y,
-                    e
-                );
-                cu.qp = Some(current_qp);
-                current_qp
-            }
-        }
-    } else {
-        // Delta Q not enabled, use current QP
-        cu.qp = Some(current_qp);
-        current_qp
-    };
-
-    Ok((cu, new_qp))
-}
-
-/// Convert INTRA mode symbol to PredictionMode
-fn intra_mode_from_symbol(symbol: u8) -> Result<PredictionMode> {
-    match symbol {
-        0 => Ok(PredictionMode::DcPred),
-        1 => Ok(PredictionMode::VPred),
-        2 => Ok(PredictionMode::HPred),
-        3 => Ok(PredictionMode::D45Pred),
-        4 => Ok(PredictionMode::D135Pred),
-        5 => Ok(PredictionMode::D113Pred),
-        6 => Ok(PredictionMode::D157Pred),
-        7 => Ok(PredictionMode::D203Pred),
-        8 => Ok(PredictionMode::D67Pred),
-        9 => Ok(PredictionMode::SmoothPred),
-        10 => Ok(PredictionMode::SmoothVPred),
-        11 => Ok(PredictionMode::SmoothHPred),
-        12 => Ok(PredictionMode::PaethPred),
-        _ => Err(BitvueError::InvalidData(format!(
-            "Invalid INTRA mode symbol: {}",
-            symbol
-        ))),
-    }
-}
-
-/// Convert INTER mode symbol to PredictionMode
-fn inter_mode_from_symbol(symbol: u8) -> Result<PredictionMode> {
-    match symbol {
-        0 => Ok(PredictionMode::NewMv),
-        1 => Ok(PredictionMode::NearestMv),
-        2 => Ok(PredictionMode::NearMv),
-        3 => Ok(PredictionMode::GlobalMv),
-        _ => Err(BitvueError::InvalidData(format!(
-            "Invalid INTER mode symbol: {}",
-            symbol
-        ))),
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -546,6 +492,34 @@ mod tests {
         assert!(!PredictionMode::DcPred.needs_mv());
     }
 
+    #[test]
+    fn test_prediction_mode_spec_values() {
+        assert_eq!(PredictionMode::from_u8(13), Some(PredictionMode::NearestMv));
+        assert_eq!(PredictionMode::from_u8(16), Some(PredictionMode::NewMv));
+        assert_eq!(PredictionMode::from_u8(24), Some(PredictionMode::NewNewMv));
+        assert_eq!(PredictionMode::from_u8(25), None);
+        assert!(PredictionMode::NearNewMv.is_compound());
+        assert!(PredictionMode::NearNewMv.needs_mv());
+        assert!(!PredictionMode::GlobalMv.is_compound());
+    }
+
+    #[test]
+    fn test_tx_size_dimensions() {
+        assert_eq!(TxSize::Tx16x64.width(), 16);
+        assert_eq!(TxSize::Tx16x64.height(), 64);
+        assert_eq!(TxSize::Tx8x4.size(), 8);
+        assert_eq!(TxSize::from_dimensions(16, 8), TxSize::Tx16x8);
+        assert_eq!(TxSize::from_dimensions(128, 128), TxSize::Tx64x64);
+        assert_eq!(TxSize::from_dimensions(4, 64), TxSize::Tx4x16);
+        assert_eq!(TxSize::from_dimensions(128, 32), TxSize::Tx64x32);
+    }
+
+    #[test]
+    fn test_motion_vector_from_eighth_pel() {
+        let mv = MotionVector::from_eighth_pel(-9, 16);
+        assert_eq!(mv, MotionVector::new(-4, 8));
+    }
+
     #[test]
     fn test_ref_frame_from_u8() {
         assert_eq!(RefFrame::from_u8(0), Some(RefFrame::Intra));
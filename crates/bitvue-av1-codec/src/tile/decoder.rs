@@ -0,0 +1,950 @@
+//! Tile Decoding
+//!
+//! Per AV1 Specification Section 5.11 (Tile group OBU syntax)
+//!
+//! [`TileDecoder`] reads every syntax element of one tile in bitstream
+//! order: superblock partitions, mode info, palette tokens, transform sizes
+//! and coefficients, CDEF indices, delta Q/LF and loop restoration units.
+//! Every context the specification derives from previously decoded blocks
+//! is tracked, so the symbol decoder stays in sync with the encoder for the
+//! whole tile. Pixels are not reconstructed.
+//!
+//! The syntax is split over several files:
+//! - this module: tile, partition and block level syntax
+//! - `mode_info`: intra/inter mode info, segment IDs, palettes, references
+//! - `mv_prediction`: the motion vector prediction processes (Section 7.10)
+//! - `residual`: transform sizes, transform types and coefficients
+//!
+//! Block information later blocks (and later frames) depend on is kept per
+//! 4x4 mode info unit in [`FrameState`].
+
+use crate::sequence::SequenceHeader;
+use crate::symbol::SymbolDecoder;
+use crate::tile::motion_field::MotionField;
+use crate::tile::mv_prediction::MvStack;
+use crate::tile::tables::{mi_height_log2, mi_width_log2, num_4x4_high, num_4x4_wide};
+use crate::tile::{
+    BlockSize, CodingUnit, MotionVector, PartitionNode, PartitionType, PredictionMode, RefFrame,
+    Superblock, TxSize,
+};
+use crate::uncompressed_header::{RestorationType, UncompressedHeader};
+use bitvue_core::Result;
+
+/// Motion vector in 1/8 pel units, `[row, col]` as in the specification
+pub(crate) type Mv = [i32; 2];
+
+/// Reference frame values (`NONE`, `INTRA_FRAME` .. `ALTREF_FRAME`)
+pub(crate) const NONE: i8 = -1;
+pub(crate) const INTRA_FRAME: i8 = 0;
+pub(crate) const LAST_FRAME: i8 = 1;
+pub(crate) const LAST2_FRAME: i8 = 2;
+pub(crate) const LAST3_FRAME: i8 = 3;
+pub(crate) const GOLDEN_FRAME: i8 = 4;
+pub(crate) const BWDREF_FRAME: i8 = 5;
+pub(crate) const ALTREF2_FRAME: i8 = 6;
+pub(crate) const ALTREF_FRAME: i8 = 7;
+
+/// Prediction modes (`YMode` values)
+pub(crate) const DC_PRED: u8 = 0;
+pub(crate) const V_PRED: u8 = 1;
+pub(crate) const D67_PRED: u8 = 8;
+pub(crate) const UV_CFL_PRED: u8 = 13;
+pub(crate) const NEARESTMV: u8 = 13;
+pub(crate) const NEARMV: u8 = 14;
+pub(crate) const GLOBALMV: u8 = 15;
+pub(crate) const NEWMV: u8 = 16;
+pub(crate) const NEAREST_NEARESTMV: u8 = 17;
+pub(crate) const NEAR_NEARMV: u8 = 18;
+pub(crate) const NEAREST_NEWMV: u8 = 19;
+pub(crate) const NEW_NEARESTMV: u8 = 20;
+pub(crate) const NEAR_NEWMV: u8 = 21;
+pub(crate) const NEW_NEARMV: u8 = 22;
+pub(crate) const GLOBAL_GLOBALMV: u8 = 23;
+pub(crate) const NEW_NEWMV: u8 = 24;
+
+/// Wiener_Taps_Min / Wiener_Taps_Max / Wiener_Taps_K / Wiener_Taps_Mid
+const WIENER_TAPS_MIN: [i32; 3] = [-5, -23, -17];
+const WIENER_TAPS_MAX: [i32; 3] = [10, 8, 46];
+const WIENER_TAPS_K: [u32; 3] = [1, 2, 3];
+const WIENER_TAPS_MID: [i32; 3] = [3, -7, 15];
+
+/// Sgrproj_Xqd_Min / Sgrproj_Xqd_Max / Sgrproj_Xqd_Mid
+const SGRPROJ_XQD_MIN: [i32; 2] = [-96, -32];
+const SGRPROJ_XQD_MAX: [i32; 2] = [31, 95];
+const SGRPROJ_XQD_MID: [i32; 2] = [-32, 31];
+
+/// Extra context entries past the frame edge (transforms may overhang it)
+const CONTEXT_MARGIN: usize = 32;
+
+/// Block information stored per 4x4 mode info unit
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MiInfo {
+    pub size: BlockSize,
+    pub y_mode: u8,
+    pub ref_frame: [i8; 2],
+    pub mv: [Mv; 2],
+    pub is_inter: bool,
+    pub skip: bool,
+    pub skip_mode: bool,
+    pub segment_id: u8,
+    pub palette_size: [u8; 2],
+    pub interp_filter: [u8; 2],
+    pub comp_group_idx: u8,
+    pub compound_idx: u8,
+    /// A block covering this unit has been decoded in the current frame
+    pub written: bool,
+}
+
+impl Default for MiInfo {
+    fn default() -> Self {
+        Self {
+            size: BlockSize::Block4x4,
+            y_mode: DC_PRED,
+            ref_frame: [INTRA_FRAME, NONE],
+            mv: [[0; 2]; 2],
+            is_inter: false,
+            skip: false,
+            skip_mode: false,
+            segment_id: 0,
+            palette_size: [0; 2],
+            interp_filter: [0; 2],
+            comp_group_idx: 0,
+            compound_idx: 0,
+            written: false,
+        }
+    }
+}
+
+/// Frame-wide block state shared by the tiles of a frame
+pub(crate) struct FrameState {
+    pub mi_rows: i32,
+    pub mi_cols: i32,
+    pub mi: Vec<MiInfo>,
+    /// InterTxSizes
+    pub inter_tx_sizes: Vec<TxSize>,
+    /// TxTypes (luma transform type per 4x4 unit)
+    pub tx_types: Vec<u8>,
+    /// PaletteColors of the Y and U planes
+    pub palette_colors: Vec<[[u16; 8]; 2]>,
+    /// PrevSegmentIds (empty when all zero)
+    pub prev_segment_ids: Vec<u8>,
+    /// Projected motion field (MotionFieldMvs), when `use_ref_frame_mvs`
+    pub motion_field: Option<MotionField>,
+    /// Whether each reference frame (by `RefFrame` value) is scaled
+    pub ref_scaled: [bool; 8],
+}
+
+impl FrameState {
+    pub fn new(mi_rows: u32, mi_cols: u32) -> Self {
+        let count = (mi_rows * mi_cols) as usize;
+        Self {
+            mi_rows: mi_rows as i32,
+            mi_cols: mi_cols as i32,
+            mi: vec![MiInfo::default(); count],
+            inter_tx_sizes: vec![TxSize::Tx4x4; count],
+            tx_types: vec![0; count],
+            palette_colors: vec![[[0; 8]; 2]; count],
+            prev_segment_ids: Vec::new(),
+            motion_field: None,
+            ref_scaled: [false; 8],
+        }
+    }
+
+    /// Index of the mode info unit at `(row, col)`
+    #[inline]
+    pub fn idx(&self, row: i32, col: i32) -> usize {
+        (row * self.mi_cols + col) as usize
+    }
+
+    /// SegmentIds of the decoded frame
+    pub fn segment_ids(&self) -> Vec<u8> {
+        self.mi.iter().map(|mi| mi.segment_id).collect()
+    }
+}
+
+/// State of the block being decoded
+pub(crate) struct Block {
+    pub mi_row: i32,
+    pub mi_col: i32,
+    pub size: BlockSize,
+    pub bw4: i32,
+    pub bh4: i32,
+    pub has_chroma: bool,
+    pub avail_u: bool,
+    pub avail_l: bool,
+    pub avail_u_chroma: bool,
+    pub avail_l_chroma: bool,
+    pub skip: bool,
+    pub skip_mode: bool,
+    pub is_inter: bool,
+    pub use_intrabc: bool,
+    pub segment_id: u8,
+    pub lossless: bool,
+    pub y_mode: u8,
+    pub uv_mode: u8,
+    pub use_filter_intra: bool,
+    pub filter_intra_mode: u8,
+    pub palette_size: [u8; 2],
+    /// Palette colors of the Y, U and V planes
+    pub palette_colors: [[u16; 8]; 3],
+    pub ref_frame: [i8; 2],
+    pub mv: [Mv; 2],
+    pub ref_mv_idx: usize,
+    pub interp_filter: [u8; 2],
+    pub motion_mode: u8,
+    pub interintra: bool,
+    pub comp_group_idx: u8,
+    pub compound_idx: u8,
+    pub tx_size: TxSize,
+    /// Nonzero coefficients decoded for the block, over all planes
+    pub coeff_count: u32,
+    /// Neighbour references (AboveRefFrame / LeftRefFrame)
+    pub above_ref_frame: [i8; 2],
+    pub left_ref_frame: [i8; 2],
+}
+
+impl Block {
+    fn new(mi_row: i32, mi_col: i32, size: BlockSize) -> Self {
+        Self {
+            mi_row,
+            mi_col,
+            size,
+            bw4: num_4x4_wide(size) as i32,
+            bh4: num_4x4_high(size) as i32,
+            has_chroma: false,
+            avail_u: false,
+            avail_l: false,
+            avail_u_chroma: false,
+            avail_l_chroma: false,
+            skip: false,
+            skip_mode: false,
+            is_inter: false,
+            use_intrabc: false,
+            segment_id: 0,
+            lossless: false,
+            y_mode: DC_PRED,
+            uv_mode: DC_PRED,
+            use_filter_intra: false,
+            filter_intra_mode: 0,
+            palette_size: [0; 2],
+            palette_colors: [[0; 8]; 3],
+            ref_frame: [INTRA_FRAME, NONE],
+            mv: [[0; 2]; 2],
+            ref_mv_idx: 0,
+            interp_filter: [0; 2],
+            motion_mode: 0,
+            interintra: false,
+            comp_group_idx: 0,
+            compound_idx: 1,
+            tx_size: TxSize::Tx4x4,
+            coeff_count: 0,
+            above_ref_frame: [INTRA_FRAME, NONE],
+            left_ref_frame: [INTRA_FRAME, NONE],
+        }
+    }
+
+    pub fn is_compound(&self) -> bool {
+        self.ref_frame[1] > INTRA_FRAME
+    }
+}
+
+/// Decoder of the syntax of one tile
+pub(crate) struct TileDecoder<'a> {
+    pub sd: SymbolDecoder<'a>,
+    pub seq: &'a SequenceHeader,
+    pub fh: &'a UncompressedHeader,
+    pub fs: &'a mut FrameState,
+    pub mi_row_start: i32,
+    pub mi_row_end: i32,
+    pub mi_col_start: i32,
+    pub mi_col_end: i32,
+    pub sb_size: BlockSize,
+    /// CurrentQIndex
+    pub current_q_index: u8,
+    /// DeltaLF
+    pub delta_lf: [i32; 4],
+    /// ReadDeltas
+    pub read_deltas: bool,
+    /// Whether `cdef_idx` was read for each 64x64 of the superblock
+    cdef_read: [bool; 4],
+    ref_lr_wiener: [[[i32; 3]; 2]; 3],
+    ref_sgr_xqd: [[i32; 2]; 3],
+    /// AboveLevelContext / AboveDcContext / LeftLevelContext / LeftDcContext
+    pub above_level_ctx: [Vec<u8>; 3],
+    pub above_dc_ctx: [Vec<u8>; 3],
+    pub left_level_ctx: [Vec<u8>; 3],
+    pub left_dc_ctx: [Vec<u8>; 3],
+    /// AboveSegPredContext / LeftSegPredContext
+    pub above_seg_pred: Vec<bool>,
+    pub left_seg_pred: Vec<bool>,
+    pub b: Block,
+    pub stack: MvStack,
+    /// Quant (coefficient levels of the current transform block)
+    pub quant: Vec<i32>,
+    /// ColorMapY / ColorMapUV of the current block, 64 entries per row
+    pub color_map: Vec<u8>,
+    coding_units: Vec<CodingUnit>,
+}
+
+impl<'a> TileDecoder<'a> {
+    /// Set up decoding of the tile at `(tile_row, tile_col)`
+    ///
+    /// Per AV1 spec Section 5.11.1, `CurrentQIndex` starts at `base_q_idx`
+    /// for every tile.
+    pub fn new(
+        sd: SymbolDecoder<'a>,
+        seq: &'a SequenceHeader,
+        fh: &'a UncompressedHeader,
+        fs: &'a mut FrameState,
+        tile_row: usize,
+        tile_col: usize,
+    ) -> Self {
+        let info = &fh.tile_info;
+        let sb_shift = if seq.use_128x128_superblock { 5 } else { 4 };
+        let mi_start = |starts: &[u32], i: usize, limit: i32| {
+            starts
+                .get(i)
+                .map_or(limit, |&sb| ((sb << sb_shift) as i32).min(limit))
+        };
+        let (mi_rows, mi_cols) = (fs.mi_rows, fs.mi_cols);
+        let mi_row_start = mi_start(&info.tile_row_start_sb, tile_row, mi_rows);
+        let mi_row_end = mi_start(&info.tile_row_start_sb, tile_row + 1, mi_rows);
+        let mi_col_start = mi_start(&info.tile_col_start_sb, tile_col, mi_cols);
+        let mi_col_end = mi_start(&info.tile_col_start_sb, tile_col + 1, mi_cols);
+
+        let cols = mi_cols as usize + CONTEXT_MARGIN;
+        let rows = mi_rows as usize + CONTEXT_MARGIN;
+        Self {
+            sd,
+            seq,
+            fh,
+            fs,
+            mi_row_start,
+            mi_row_end,
+            mi_col_start,
+            mi_col_end,
+            sb_size: if seq.use_128x128_superblock {
+                BlockSize::Block128x128
+            } else {
+                BlockSize::Block64x64
+            },
+            current_q_index: fh.quantization.base_q_idx,
+            delta_lf: [0; 4],
+            read_deltas: false,
+            cdef_read: [false; 4],
+            ref_lr_wiener: [[WIENER_TAPS_MID; 2]; 3],
+            ref_sgr_xqd: [SGRPROJ_XQD_MID; 3],
+            above_level_ctx: std::array::from_fn(|_| vec![0; cols]),
+            above_dc_ctx: std::array::from_fn(|_| vec![0; cols]),
+            left_level_ctx: std::array::from_fn(|_| vec![0; rows]),
+            left_dc_ctx: std::array::from_fn(|_| vec![0; rows]),
+            above_seg_pred: vec![false; cols],
+            left_seg_pred: vec![false; rows],
+            b: Block::new(0, 0, BlockSize::Block4x4),
+            stack: MvStack::default(),
+            quant: vec![0; 1024],
+            color_map: vec![0; 64 * 64],
+            coding_units: Vec::new(),
+        }
+    }
+
+    /// Decode all superblocks of the tile (spec `decode_tile()`)
+    pub fn decode_tile(&mut self) -> Result<Vec<Superblock>> {
+        let sb_size4 = num_4x4_wide(self.sb_size) as i32;
+        let mut superblocks = Vec::new();
+        let mut r = self.mi_row_start;
+        while r < self.mi_row_end {
+            self.clear_left_context();
+            let mut c = self.mi_col_start;
+            while c < self.mi_col_end {
+                self.read_deltas = self.fh.delta_q_present;
+                self.cdef_read = [false; 4];
+                self.read_lr(r, c)?;
+                if let Some(partition) = self.decode_partition(r, c, self.sb_size)? {
+                    let mut sb = Superblock::new(
+                        (c * 4) as u32,
+                        (r * 4) as u32,
+                        self.sb_size.width(),
+                        partition,
+                    );
+                    sb.coding_units = std::mem::take(&mut self.coding_units);
+                    superblocks.push(sb);
+                }
+                c += sb_size4;
+            }
+            r += sb_size4;
+        }
+        Ok(superblocks)
+    }
+
+    /// Finish the tile, returning its adapted CDFs
+    pub fn finish(self) -> Result<crate::symbol::CdfContext> {
+        self.sd.exit()
+    }
+
+    fn clear_left_context(&mut self) {
+        for plane in 0..3 {
+            self.left_level_ctx[plane].fill(0);
+            self.left_dc_ctx[plane].fill(0);
+        }
+        self.left_seg_pred.fill(false);
+    }
+
+    /// Spec `is_inside()`: whether a position lies in the current tile
+    #[inline]
+    pub fn is_inside(&self, row: i32, col: i32) -> bool {
+        col >= self.mi_col_start
+            && col < self.mi_col_end
+            && row >= self.mi_row_start
+            && row < self.mi_row_end
+    }
+
+    /// Mode info of the unit at `(row, col)` (which must be in the frame)
+    #[inline]
+    pub fn mi(&self, row: i32, col: i32) -> &MiInfo {
+        &self.fs.mi[self.fs.idx(row, col)]
+    }
+
+    pub fn num_planes(&self) -> usize {
+        self.seq.color_config.num_planes as usize
+    }
+
+    pub fn subsampling(&self) -> (bool, bool) {
+        let cc = &self.seq.color_config;
+        (cc.subsampling_x, cc.subsampling_y)
+    }
+
+    /// Spec `decode_partition()`
+    ///
+    /// Returns `None` for blocks entirely outside the frame.
+    fn decode_partition(
+        &mut self,
+        r: i32,
+        c: i32,
+        bsize: BlockSize,
+    ) -> Result<Option<PartitionNode>> {
+        if r >= self.fs.mi_rows || c >= self.fs.mi_cols {
+            return Ok(None);
+        }
+        let num4x4 = num_4x4_wide(bsize) as i32;
+        let half_block4x4 = num4x4 >> 1;
+        let quarter_block4x4 = half_block4x4 >> 1;
+        let has_rows = r + half_block4x4 < self.fs.mi_rows;
+        let has_cols = c + half_block4x4 < self.fs.mi_cols;
+
+        let partition = if bsize < BlockSize::Block8x8 {
+            PartitionType::None
+        } else if has_rows && has_cols {
+            let (bsl, ctx) = self.partition_ctx(r, c, bsize);
+            let symbol = self.sd.read(|cdfs| cdfs.partition_cdf(bsl, ctx));
+            PartitionType::from_u8(symbol as u8).unwrap_or(PartitionType::None)
+        } else if has_cols {
+            if self.read_split_or(r, c, bsize, true) {
+                PartitionType::Split
+            } else {
+                PartitionType::Horz
+            }
+        } else if has_rows {
+            if self.read_split_or(r, c, bsize, false) {
+                PartitionType::Split
+            } else {
+                PartitionType::Vert
+            }
+        } else {
+            PartitionType::Split
+        };
+
+        let sub_size = bsize.subsize(partition).unwrap_or(bsize);
+        let split_size = bsize.subsize(PartitionType::Split).unwrap_or(bsize);
+        let mut node = PartitionNode::new((c * 4) as u32, (r * 4) as u32, bsize, partition);
+        let (h, q) = (half_block4x4, quarter_block4x4);
+        match partition {
+            PartitionType::None => {
+                self.decode_block(r, c, sub_size)?;
+                return Ok(Some(node));
+            }
+            PartitionType::Horz => {
+                self.coded_block(&mut node, r, c, sub_size)?;
+                if has_rows {
+                    self.coded_block(&mut node, r + h, c, sub_size)?;
+                }
+            }
+            PartitionType::Vert => {
+                self.coded_block(&mut node, r, c, sub_size)?;
+                if has_cols {
+                    self.coded_block(&mut node, r, c + h, sub_size)?;
+                }
+            }
+            PartitionType::Split => {
+                for (dr, dc) in [(0, 0), (0, h), (h, 0), (h, h)] {
+                    if let Some(child) = self.decode_partition(r + dr, c + dc, sub_size)? {
+                        node.children.push(child);
+                    }
+                }
+            }
+            PartitionType::HorzA => {
+                self.coded_block(&mut node, r, c, split_size)?;
+                self.coded_block(&mut node, r, c + h, split_size)?;
+                self.coded_block(&mut node, r + h, c, sub_size)?;
+            }
+            PartitionType::HorzB => {
+                self.coded_block(&mut node, r, c, sub_size)?;
+                self.coded_block(&mut node, r + h, c, split_size)?;
+                self.coded_block(&mut node, r + h, c + h, split_size)?;
+            }
+            PartitionType::VertA => {
+                self.coded_block(&mut node, r, c, split_size)?;
+                self.coded_block(&mut node, r + h, c, split_size)?;
+                self.coded_block(&mut node, r, c + h, sub_size)?;
+            }
+            PartitionType::VertB => {
+                self.coded_block(&mut node, r, c, sub_size)?;
+                self.coded_block(&mut node, r, c + h, split_size)?;
+                self.coded_block(&mut node, r + h, c + h, split_size)?;
+            }
+            PartitionType::Horz4 => {
+                for i in 0..4 {
+                    if i < 3 || r + q * 3 < self.fs.mi_rows {
+                        self.coded_block(&mut node, r + q * i, c, sub_size)?;
+                    }
+                }
+            }
+            PartitionType::Vert4 => {
+                for i in 0..4 {
+                    if i < 3 || c + q * 3 < self.fs.mi_cols {
+                        self.coded_block(&mut node, r, c + q * i, sub_size)?;
+                    }
+                }
+            }
+        }
+        Ok(Some(node))
+    }
+
+    /// Decode a block of a partition and record it as a leaf child
+    fn coded_block(
+        &mut self,
+        node: &mut PartitionNode,
+        r: i32,
+        c: i32,
+        bsize: BlockSize,
+    ) -> Result<()> {
+        self.decode_block(r, c, bsize)?;
+        node.children.push(PartitionNode::new(
+            (c * 4) as u32,
+            (r * 4) as u32,
+            bsize,
+            PartitionType::None,
+        ));
+        Ok(())
+    }
+
+    /// CDF selection (`bsl`) and context of the `partition` symbol
+    fn partition_ctx(&self, r: i32, c: i32, bsize: BlockSize) -> (usize, usize) {
+        let bsl = mi_width_log2(bsize);
+        let above = self.is_inside(r - 1, c) && mi_width_log2(self.mi(r - 1, c).size) < bsl;
+        let left = self.is_inside(r, c - 1) && mi_height_log2(self.mi(r, c - 1).size) < bsl;
+        (bsl as usize - 1, left as usize * 2 + above as usize)
+    }
+
+    /// Read `split_or_horz` (`horz`) or `split_or_vert`
+    ///
+    /// The boolean CDF is derived from the partition CDF: `split_or_horz`
+    /// sums the probabilities of the partitions with a vertical split and
+    /// `split_or_vert` those with a horizontal split.
+    fn read_split_or(&mut self, r: i32, c: i32, bsize: BlockSize, horz: bool) -> bool {
+        let (bsl, ctx) = self.partition_ctx(r, c, bsize);
+        let partition_cdf = self.sd.cdfs.partition_cdf(bsl, ctx);
+        let n = partition_cdf.len() - 1;
+        let prob = |p: PartitionType| -> u32 {
+            let k = p as usize;
+            if k >= n {
+                return 0;
+            }
+            let prev = if k == 0 { 0 } else { partition_cdf[k - 1] };
+            (partition_cdf[k] - prev) as u32
+        };
+        let mut parts = if horz {
+            vec![
+                PartitionType::Vert,
+                PartitionType::Split,
+                PartitionType::HorzA,
+                PartitionType::VertA,
+                PartitionType::VertB,
+            ]
+        } else {
+            vec![
+                PartitionType::Horz,
+                PartitionType::Split,
+                PartitionType::HorzA,
+                PartitionType::HorzB,
+                PartitionType::VertA,
+            ]
+        };
+        if bsize != BlockSize::Block128x128 {
+            parts.push(if horz {
+                PartitionType::Vert4
+            } else {
+                PartitionType::Horz4
+            });
+        }
+        let psum: u32 = parts.into_iter().map(prob).sum();
+        // The derived CDF is temporary, its adaptation is discarded
+        let mut cdf = [(32768 - psum.min(32768)) as u16, 32768, 0];
+        self.sd.decoder.read_symbol(&mut cdf) == 1
+    }
+
+    /// Spec `decode_block()`
+    fn decode_block(&mut self, r: i32, c: i32, bsize: BlockSize) -> Result<()> {
+        let (ss_x, ss_y) = self.subsampling();
+        let mut b = Block::new(r, c, bsize);
+        b.has_chroma =
+            if (b.bh4 == 1 && ss_y && (r & 1) == 0) || (b.bw4 == 1 && ss_x && (c & 1) == 0) {
+                false
+            } else {
+                self.num_planes() > 1
+            };
+        b.avail_u = self.is_inside(r - 1, c);
+        b.avail_l = self.is_inside(r, c - 1);
+        b.avail_u_chroma = b.avail_u;
+        b.avail_l_chroma = b.avail_l;
+        if b.has_chroma {
+            if ss_y && b.bh4 == 1 {
+                b.avail_u_chroma = self.is_inside(r - 2, c);
+            }
+            if ss_x && b.bw4 == 1 {
+                b.avail_l_chroma = self.is_inside(r, c - 2);
+            }
+        }
+        self.b = b;
+
+        self.mode_info()?;
+        self.palette_tokens();
+        let max_luma_tx = self.read_block_tx_size();
+        if self.b.skip {
+            self.reset_block_context();
+        }
+        self.residual()?;
+        self.store_block_info();
+        self.push_coding_unit(max_luma_tx);
+        Ok(())
+    }
+
+    /// Spec `reset_block_context()`
+    fn reset_block_context(&mut self) {
+        let (ss_x, ss_y) = self.subsampling();
+        let planes = if self.b.has_chroma { 3 } else { 1 };
+        for plane in 0..planes {
+            let (sx, sy) = if plane > 0 {
+                (ss_x as i32, ss_y as i32)
+            } else {
+                (0, 0)
+            };
+            let cols = (self.b.mi_col >> sx)..=((self.b.mi_col + self.b.bw4 - 1) >> sx);
+            for i in cols {
+                self.above_level_ctx[plane][i as usize] = 0;
+                self.above_dc_ctx[plane][i as usize] = 0;
+            }
+            let rows = (self.b.mi_row >> sy)..=((self.b.mi_row + self.b.bh4 - 1) >> sy);
+            for i in rows {
+                self.left_level_ctx[plane][i as usize] = 0;
+                self.left_dc_ctx[plane][i as usize] = 0;
+            }
+        }
+    }
+
+    /// Store the block's mode info over the units it covers in the frame
+    fn store_block_info(&mut self) {
+        let b = &self.b;
+        let info = MiInfo {
+            size: b.size,
+            y_mode: b.y_mode,
+            ref_frame: b.ref_frame,
+            mv: if b.is_inter { b.mv } else { [[0; 2]; 2] },
+            is_inter: b.is_inter,
+            skip: b.skip,
+            skip_mode: b.skip_mode,
+            segment_id: b.segment_id,
+            palette_size: b.palette_size,
+            interp_filter: b.interp_filter,
+            comp_group_idx: b.comp_group_idx,
+            compound_idx: b.compound_idx,
+            written: true,
+        };
+        let colors = [b.palette_colors[0], b.palette_colors[1]];
+        let rows = b.mi_row..(b.mi_row + b.bh4).min(self.fs.mi_rows);
+        let cols = b.mi_col..(b.mi_col + b.bw4).min(self.fs.mi_cols);
+        for row in rows {
+            for col in cols.clone() {
+                let idx = self.fs.idx(row, col);
+                self.fs.mi[idx] = info;
+                self.fs.palette_colors[idx] = colors;
+            }
+        }
+    }
+
+    /// Record the decoded block for the overlays
+    fn push_coding_unit(&mut self, max_luma_tx: TxSize) {
+        let b = &self.b;
+        let mut cu = CodingUnit::new(
+            (b.mi_col * 4) as u32,
+            (b.mi_row * 4) as u32,
+            b.size.width(),
+            b.size.height(),
+        );
+        cu.skip = b.skip;
+        cu.mode = PredictionMode::from_u8(b.y_mode).unwrap_or(PredictionMode::DcPred);
+        let ref_frame = |r: i8| {
+            if r > INTRA_FRAME {
+                RefFrame::from_u8(r as u8).unwrap_or(RefFrame::Intra)
+            } else {
+                RefFrame::Intra
+            }
+        };
+        cu.ref_frames = [ref_frame(b.ref_frame[0]), ref_frame(b.ref_frame[1])];
+        if b.is_inter {
+            cu.mv = [
+                MotionVector::from_eighth_pel(b.mv[0][1], b.mv[0][0]),
+                MotionVector::from_eighth_pel(b.mv[1][1], b.mv[1][0]),
+            ];
+        }
+        cu.tx_size = max_luma_tx;
+        let qindex = self
+            .fh
+            .get_qindex(false, b.segment_id as usize, self.current_q_index);
+        cu.qp = Some(qindex as i16);
+        cu.segment_id = b.segment_id;
+        cu.use_intrabc = b.use_intrabc;
+        cu.coeff_count = b.coeff_count;
+        self.coding_units.push(cu);
+    }
+
+    /// Spec `read_cdef()`
+    pub(crate) fn read_cdef(&mut self) {
+        if self.b.skip || self.fh.coded_lossless || !self.seq.enable_cdef || self.fh.allow_intrabc {
+            return;
+        }
+        let cdef_size4 = 16;
+        let cdef_mask4 = !(cdef_size4 - 1);
+        let r = self.b.mi_row & cdef_mask4;
+        let c = self.b.mi_col & cdef_mask4;
+        let slot = |y: i32, x: i32| (((y & 16) != 0) as usize) * 2 + ((x & 16) != 0) as usize;
+        if !self.cdef_read[slot(r, c)] {
+            let _cdef_idx = self.sd.decoder.read_literal(self.fh.cdef.bits as u32);
+            let mut y = r;
+            while y < r + self.b.bh4 {
+                let mut x = c;
+                while x < c + self.b.bw4 {
+                    self.cdef_read[slot(y, x)] = true;
+                    x += cdef_size4;
+                }
+                y += cdef_size4;
+            }
+        }
+    }
+
+    /// Spec `read_delta_qindex()`
+    pub(crate) fn read_delta_qindex(&mut self) {
+        if self.b.size == self.sb_size && self.b.skip {
+            return;
+        }
+        if self.read_deltas {
+            let delta_q_abs = self.read_delta_abs(|sd| sd.read(|c| &mut c.delta_q[..]));
+            if delta_q_abs != 0 {
+                let sign = self.sd.decoder.read_literal(1) == 1;
+                let reduced = if sign { -delta_q_abs } else { delta_q_abs };
+                let q = self.current_q_index as i32 + (reduced << self.fh.delta_q_res);
+                self.current_q_index = q.clamp(1, 255) as u8;
+            }
+        }
+    }
+
+    /// Spec `read_delta_lf()`
+    pub(crate) fn read_delta_lf(&mut self) {
+        if self.b.size == self.sb_size && self.b.skip {
+            return;
+        }
+        if self.read_deltas && self.fh.delta_lf_present {
+            let frame_lf_count = if self.fh.delta_lf_multi {
+                if self.num_planes() > 1 {
+                    4
+                } else {
+                    2
+                }
+            } else {
+                1
+            };
+            let multi = self.fh.delta_lf_multi;
+            for i in 0..frame_lf_count {
+                let delta_lf_abs = self.read_delta_abs(|sd| {
+                    if multi {
+                        sd.read(|c| &mut c.delta_lf_multi[i][..])
+                    } else {
+                        sd.read(|c| &mut c.delta_lf[..])
+                    }
+                });
+                if delta_lf_abs != 0 {
+                    let sign = self.sd.decoder.read_literal(1) == 1;
+                    let reduced = if sign { -delta_lf_abs } else { delta_lf_abs };
+                    let lf = self.delta_lf[i] + (reduced << self.fh.delta_lf_res);
+                    self.delta_lf[i] = lf.clamp(-63, 63);
+                }
+            }
+        }
+    }
+
+    /// Absolute value of a delta Q/LF: a symbol, with 3 escaping to
+    /// `rem_bits` and `abs_bits` literals
+    fn read_delta_abs(&mut self, read: impl FnOnce(&mut SymbolDecoder<'a>) -> usize) -> i32 {
+        let abs = read(&mut self.sd) as i32;
+        if abs == 3 {
+            let rem_bits = self.sd.decoder.read_literal(3) + 1;
+            let abs_bits = self.sd.decoder.read_literal(rem_bits) as i32;
+            abs_bits + (1 << rem_bits) + 1
+        } else {
+            abs
+        }
+    }
+
+    /// Spec `read_lr()` for the superblock at `(r, c)`
+    fn read_lr(&mut self, r: i32, c: i32) -> Result<()> {
+        if self.fh.allow_intrabc {
+            return Ok(());
+        }
+        let w = num_4x4_wide(self.sb_size) as i32;
+        let h = num_4x4_high(self.sb_size) as i32;
+        let (ss_x, ss_y) = self.subsampling();
+        let lr = &self.fh.loop_restoration;
+        for plane in 0..self.num_planes() {
+            let frame_type = lr.frame_restoration_type[plane];
+            if frame_type == RestorationType::None {
+                continue;
+            }
+            let (sx, sy) = if plane > 0 {
+                (ss_x as i32, ss_y as i32)
+            } else {
+                (0, 0)
+            };
+            let unit_size = lr.loop_restoration_size[plane] as i32;
+            let round2 = |x: i32, n: i32| if n == 0 { x } else { (x + (1 << (n - 1))) >> n };
+            let count_units =
+                |frame_size: i32| ((frame_size + (unit_size >> 1)) / unit_size).max(1);
+            let unit_rows = count_units(round2(self.fh.frame_height as i32, sy));
+            let unit_cols = count_units(round2(self.fh.upscaled_width as i32, sx));
+            let unit_row_start = (r * (4 >> sy) + unit_size - 1) / unit_size;
+            let unit_row_end = unit_rows.min(((r + h) * (4 >> sy) + unit_size - 1) / unit_size);
+            let (numerator, denominator) = if self.fh.use_superres {
+                ((4 >> sx) * self.fh.superres_denom as i32, unit_size * 8)
+            } else {
+                (4 >> sx, unit_size)
+            };
+            let unit_col_start = (c * numerator + denominator - 1) / denominator;
+            let unit_col_end = unit_cols.min(((c + w) * numerator + denominator - 1) / denominator);
+            for _ in unit_row_start..unit_row_end {
+                for _ in unit_col_start..unit_col_end {
+                    self.read_lr_unit(plane, frame_type);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Spec `read_lr_unit()`
+    fn read_lr_unit(&mut self, plane: usize, frame_type: RestorationType) {
+        let restoration_type = match frame_type {
+            RestorationType::Wiener => {
+                if self.sd.read_flag(|c| &mut c.use_wiener[..]) {
+                    RestorationType::Wiener
+                } else {
+                    RestorationType::None
+                }
+            }
+            RestorationType::Sgrproj => {
+                if self.sd.read_flag(|c| &mut c.use_sgrproj[..]) {
+                    RestorationType::Sgrproj
+                } else {
+                    RestorationType::None
+                }
+            }
+            _ => match self.sd.read(|c| &mut c.restoration_type[..]) {
+                1 => RestorationType::Wiener,
+                2 => RestorationType::Sgrproj,
+                _ => RestorationType::None,
+            },
+        };
+        match restoration_type {
+            RestorationType::Wiener => {
+                let first_coeff = if plane > 0 { 1 } else { 0 };
+                for pass in 0..2 {
+                    for j in first_coeff..3 {
+                        let v = self.decode_signed_subexp_with_ref_bool(
+                            WIENER_TAPS_MIN[j],
+                            WIENER_TAPS_MAX[j] + 1,
+                            WIENER_TAPS_K[j],
+                            self.ref_lr_wiener[plane][pass][j],
+                        );
+                        self.ref_lr_wiener[plane][pass][j] = v;
+                    }
+                }
+            }
+            RestorationType::Sgrproj => {
+                let set = self.sd.decoder.read_literal(4);
+                for i in 0..2 {
+                    // Sgr_Params radii: sets 10..13 have no first pass,
+                    // sets 14 and 15 no second pass
+                    let radius_zero = if i == 0 {
+                        (10..14).contains(&set)
+                    } else {
+                        set >= 14
+                    };
+                    let (min, max) = (SGRPROJ_XQD_MIN[i], SGRPROJ_XQD_MAX[i]);
+                    let v = if !radius_zero {
+                        self.decode_signed_subexp_with_ref_bool(
+                            min,
+                            max + 1,
+                            4,
+                            self.ref_sgr_xqd[plane][i],
+                        )
+                    } else if i == 1 {
+                        ((1 << 7) - self.ref_sgr_xqd[plane][0]).clamp(min, max)
+                    } else {
+                        0
+                    };
+                    self.ref_sgr_xqd[plane][i] = v;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn decode_signed_subexp_with_ref_bool(&mut self, low: i32, high: i32, k: u32, r: i32) -> i32 {
+        let x = self.decode_unsigned_subexp_with_ref_bool(high - low, k, r - low);
+        x + low
+    }
+
+    fn decode_unsigned_subexp_with_ref_bool(&mut self, mx: i32, k: u32, r: i32) -> i32 {
+        let v = self.decode_subexp_bool(mx, k);
+        if (r << 1) <= mx {
+            crate::uncompressed_header::inverse_recenter(r, v)
+        } else {
+            mx - 1 - crate::uncompressed_header::inverse_recenter(mx - 1 - r, v)
+        }
+    }
+
+    fn decode_subexp_bool(&mut self, num_syms: i32, k: u32) -> i32 {
+        let mut i = 0;
+        let mut mk = 0;
+        loop {
+            let b2 = if i > 0 { k + i - 1 } else { k };
+            let a = 1 << b2;
+            if num_syms <= mk + 3 * a {
+                return self.sd.decoder.read_ns((num_syms - mk) as u32) as i32 + mk;
+            }
+            if self.sd.decoder.read_literal(1) == 1 {
+                i += 1;
+                mk += a;
+            } else {
+                return self.sd.decoder.read_literal(b2) as i32 + mk;
+            }
+        }
+    }
+}
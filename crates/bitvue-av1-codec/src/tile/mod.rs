@@ -1,40 +1,31 @@
 //! AV1 Tile Parsing
 //!
 //! Per AV1 Specification Section 5.11 (Tile Syntax)
-//! This module parses tile data to extract block-level information:
+//! This module decodes the syntax of tile data to extract block-level
+//! information:
 //! - Partition structure (superblock → block tree)
 //! - Prediction modes (Intra/Inter)
 //! - Motion vectors (for Inter frames)
-//! - Transform information
+//! - Transform sizes and quantizer indices
 //!
-//! ## Implementation Status
-//!
-//! **MVP Phase (KEY Frames Only)**:
-//! - ✅ Tile group structure
-//! - 🚧 Partition tree parser (in progress)
-//! - ⏳ Intra mode extraction
-//! - ⏳ Block size grid
-//!
-//! **Full Implementation (Later)**:
-//! - ⏳ Symbol decoder (arithmetic coding)
-//! - ⏳ INTER frame support
-//! - ⏳ Motion vector extraction
-//! - ⏳ Transform coefficient parsing
+//! The tile decoder reads every symbol of a tile with the adaptive CDFs, so
+//! the decoded blocks match those of a conforming decoder. Pixels are not
+//! reconstructed.
 
 pub mod coding_unit;
-pub mod mv_prediction;
+pub(crate) mod decoder;
+mod mode_info;
+pub(crate) mod motion_field;
+mod mv_prediction;
 pub mod partition;
+mod residual;
 pub mod superblock;
+pub(crate) mod tables;
 pub mod tile_group;
 
-pub use coding_unit::{
-    parse_coding_unit, CodingUnit, MotionVector, PredictionMode, RefFrame, TxSize,
-};
-pub use mv_prediction::MvPredictorContext;
-pub use partition::{
-    parse_partition_tree, partition_tree_to_grid, BlockSize, PartitionNode, PartitionType,
-};
-pub use superblock::{parse_superblock, Superblock};
+pub use coding_unit::{CodingUnit, MotionVector, PredictionMode, RefFrame, TxSize};
+pub use partition::{partition_tree_to_grid, BlockSize, PartitionNode, PartitionType};
+pub use superblock::Superblock;
 pub use tile_group::{parse_tile_group, TileGroup, TileInfo};
 
 use serde::{Deserialize, Serialize};
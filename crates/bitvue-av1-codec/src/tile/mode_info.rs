@@ -0,0 +1,1337 @@
+//! Mode Info Syntax
+//!
+//! Per AV1 Specification Sections 5.11.6 - 5.11.33
+//!
+//! Intra frame and inter frame mode info: segment IDs, skip flags, intra
+//! modes (angle deltas, CfL, palette, filter intra), reference frames,
+//! inter modes, motion vectors, inter-intra, motion mode, compound type and
+//! interpolation filters. Also the palette color index map
+//! (`palette_tokens()`), which follows the mode info in the bitstream.
+
+use crate::tile::decoder::{
+    Mv, TileDecoder, ALTREF2_FRAME, ALTREF_FRAME, BWDREF_FRAME, D67_PRED, DC_PRED, GLOBALMV,
+    GLOBAL_GLOBALMV, GOLDEN_FRAME, INTRA_FRAME, LAST2_FRAME, LAST3_FRAME, LAST_FRAME, NEARESTMV,
+    NEAREST_NEARESTMV, NEAREST_NEWMV, NEARMV, NEAR_NEARMV, NEAR_NEWMV, NEWMV, NEW_NEARESTMV,
+    NEW_NEARMV, NEW_NEWMV, NONE, UV_CFL_PRED, V_PRED,
+};
+use crate::tile::tables::{
+    mi_height_log2, mi_width_log2, subsampled_size, COMPOUND_MODE_CTX_MAP, INTRA_MODE_CONTEXT,
+    PALETTE_COLOR_CONTEXT, PALETTE_COLOR_HASH_MULTIPLIERS, SIZE_GROUP, WEDGE_BITS,
+};
+use crate::tile::BlockSize;
+use crate::uncompressed_header::{
+    GlobalMotionType, SEG_LVL_GLOBALMV, SEG_LVL_REF_FRAME, SEG_LVL_SKIP, SWITCHABLE,
+};
+use bitvue_core::Result;
+
+/// Motion modes
+const SIMPLE: u8 = 0;
+const OBMC: u8 = 1;
+const LOCALWARP: u8 = 2;
+
+/// Interpolation filters
+const EIGHTTAP: u8 = 0;
+const BILINEAR: u8 = 3;
+
+/// MV_INTRABC_CONTEXT
+const MV_INTRABC_CONTEXT: usize = 1;
+
+/// INTRABC_DELAY_PIXELS
+const INTRABC_DELAY_PIXELS: i32 = 256;
+
+impl<'a> TileDecoder<'a> {
+    /// Read the mode info of the current block
+    pub(crate) fn mode_info(&mut self) -> Result<()> {
+        if self.fh.frame_is_intra() {
+            self.intra_frame_mode_info()
+        } else {
+            self.inter_frame_mode_info()
+        }
+    }
+
+    fn seg_feature_active(&self, feature: usize) -> bool {
+        self.fh
+            .segmentation
+            .feature_active(self.b.segment_id as usize, feature)
+    }
+
+    /// Spec `intra_frame_mode_info()`
+    fn intra_frame_mode_info(&mut self) -> Result<()> {
+        let pre_skip = self.fh.segmentation.seg_id_pre_skip;
+        self.b.skip = false;
+        if pre_skip {
+            self.intra_segment_id();
+        }
+        self.b.skip_mode = false;
+        self.read_skip();
+        if !pre_skip {
+            self.intra_segment_id();
+        }
+        self.read_cdef();
+        self.read_delta_qindex();
+        self.read_delta_lf();
+        self.read_deltas = false;
+        self.b.ref_frame = [INTRA_FRAME, NONE];
+        self.b.use_intrabc = self.fh.allow_intrabc && self.sd.read_flag(|c| &mut c.intrabc[..]);
+        if self.b.use_intrabc {
+            self.b.is_inter = true;
+            self.b.motion_mode = SIMPLE;
+            self.b.palette_size = [0; 2];
+            self.b.interp_filter = [BILINEAR; 2];
+            self.find_mv_stack(false);
+            self.assign_mv(false);
+        } else {
+            self.b.is_inter = false;
+            let mode_of = |avail: bool, row: i32, col: i32| {
+                if avail {
+                    self.mi(row, col).y_mode
+                } else {
+                    DC_PRED
+                }
+            };
+            let above = mode_of(self.b.avail_u, self.b.mi_row - 1, self.b.mi_col);
+            let left = mode_of(self.b.avail_l, self.b.mi_row, self.b.mi_col - 1);
+            let above_ctx = INTRA_MODE_CONTEXT[above as usize];
+            let left_ctx = INTRA_MODE_CONTEXT[left as usize];
+            self.b.y_mode = self
+                .sd
+                .read(|c| &mut c.intra_frame_y_mode[above_ctx][left_ctx][..])
+                as u8;
+            self.intra_modes_after_y();
+        }
+        Ok(())
+    }
+
+    /// The intra mode syntax following the luma mode: angle deltas, chroma
+    /// mode, palette and filter intra
+    fn intra_modes_after_y(&mut self) {
+        self.intra_angle_info(self.b.y_mode);
+        if self.b.has_chroma {
+            let cfl_allowed = self.cfl_allowed();
+            let y_mode = self.b.y_mode as usize;
+            self.b.uv_mode = if cfl_allowed {
+                self.sd.read(|c| &mut c.uv_mode_cfl_allowed[y_mode][..]) as u8
+            } else {
+                self.sd.read(|c| &mut c.uv_mode_cfl_not_allowed[y_mode][..]) as u8
+            };
+            if self.b.uv_mode == UV_CFL_PRED {
+                self.read_cfl_alphas();
+            }
+            self.intra_angle_info(self.b.uv_mode);
+        }
+        self.b.palette_size = [0; 2];
+        let size = self.b.size;
+        if size >= BlockSize::Block8x8
+            && size.width() <= 64
+            && size.height() <= 64
+            && self.fh.allow_screen_content_tools
+        {
+            self.palette_mode_info();
+        }
+        self.filter_intra_mode_info();
+    }
+
+    /// Whether CfL is allowed for the block (selects the `uv_mode` CDF)
+    fn cfl_allowed(&self) -> bool {
+        let size = self.b.size;
+        if self.b.lossless {
+            let (ss_x, ss_y) = self.subsampling();
+            subsampled_size(size, ss_x, ss_y) == BlockSize::Block4x4
+        } else {
+            size.width().max(size.height()) <= 32
+        }
+    }
+
+    /// Spec `intra_angle_info_y()` / `intra_angle_info_uv()`
+    fn intra_angle_info(&mut self, mode: u8) {
+        if self.b.size >= BlockSize::Block8x8 && (V_PRED..=D67_PRED).contains(&mode) {
+            let idx = (mode - V_PRED) as usize;
+            self.sd.read(|c| &mut c.angle_delta[idx][..]);
+        }
+    }
+
+    /// Spec `read_cfl_alphas()`
+    fn read_cfl_alphas(&mut self) {
+        let signs = self.sd.read(|c| &mut c.cfl_sign[..]);
+        let sign_u = (signs + 1) / 3;
+        let sign_v = (signs + 1) % 3;
+        if sign_u != 0 {
+            let ctx = (sign_u - 1) * 3 + sign_v;
+            self.sd.read(|c| &mut c.cfl_alpha[ctx][..]);
+        }
+        if sign_v != 0 {
+            let ctx = (sign_v - 1) * 3 + sign_u;
+            self.sd.read(|c| &mut c.cfl_alpha[ctx][..]);
+        }
+    }
+
+    /// Spec `filter_intra_mode_info()`
+    fn filter_intra_mode_info(&mut self) {
+        self.b.use_filter_intra = false;
+        let size = self.b.size;
+        if self.seq.enable_filter_intra
+            && self.b.y_mode == DC_PRED
+            && self.b.palette_size[0] == 0
+            && size.width().max(size.height()) <= 32
+        {
+            let bsize = size as usize;
+            self.b.use_filter_intra = self.sd.read_flag(|c| &mut c.filter_intra[bsize][..]);
+            if self.b.use_filter_intra {
+                self.b.filter_intra_mode = self.sd.read(|c| &mut c.filter_intra_mode[..]) as u8;
+            }
+        }
+    }
+
+    /// Spec `intra_segment_id()`
+    fn intra_segment_id(&mut self) {
+        if self.fh.segmentation.enabled {
+            self.read_segment_id();
+        } else {
+            self.b.segment_id = 0;
+        }
+        self.b.lossless = self.fh.lossless_array[self.b.segment_id as usize];
+    }
+
+    /// Spec `read_segment_id()`
+    fn read_segment_id(&mut self) {
+        let (r, c) = (self.b.mi_row, self.b.mi_col);
+        let seg = |avail: bool, row: i32, col: i32| {
+            if avail {
+                self.mi(row, col).segment_id as i32
+            } else {
+                -1
+            }
+        };
+        let prev_ul = seg(self.b.avail_u && self.b.avail_l, r - 1, c - 1);
+        let prev_u = seg(self.b.avail_u, r - 1, c);
+        let prev_l = seg(self.b.avail_l, r, c - 1);
+        let pred = if prev_u == -1 {
+            prev_l.max(0)
+        } else if prev_l == -1 || prev_ul == prev_u {
+            prev_u
+        } else {
+            prev_l
+        };
+        if self.b.skip {
+            self.b.segment_id = pred as u8;
+            return;
+        }
+        let ctx = if prev_ul < 0 {
+            0
+        } else if prev_ul == prev_u && prev_ul == prev_l {
+            2
+        } else if prev_ul == prev_u || prev_ul == prev_l || prev_u == prev_l {
+            1
+        } else {
+            0
+        };
+        let coded = self.sd.read(|c| &mut c.segment_id[ctx][..]) as i32;
+        let last_active = self.fh.segmentation.last_active_seg_id as i32;
+        let id = neg_deinterleave(coded, pred, last_active + 1);
+        self.b.segment_id = id.clamp(0, last_active) as u8;
+    }
+
+    /// Spec `get_segment_id()`: the smallest previous segment ID the block
+    /// covers
+    fn get_segment_id(&self) -> u8 {
+        let fs = &self.fs;
+        if fs.prev_segment_ids.is_empty() {
+            return 0;
+        }
+        let x_mis = (fs.mi_cols - self.b.mi_col).min(self.b.bw4);
+        let y_mis = (fs.mi_rows - self.b.mi_row).min(self.b.bh4);
+        let mut seg = 7;
+        for y in 0..y_mis {
+            for x in 0..x_mis {
+                seg = seg.min(fs.prev_segment_ids[fs.idx(self.b.mi_row + y, self.b.mi_col + x)]);
+            }
+        }
+        seg
+    }
+
+    /// Spec `inter_segment_id()`
+    fn inter_segment_id(&mut self, pre_skip: bool) {
+        let seg = &self.fh.segmentation;
+        if !seg.enabled {
+            self.b.segment_id = 0;
+            return;
+        }
+        let predicted = self.get_segment_id();
+        if !seg.update_map {
+            self.b.segment_id = predicted;
+            return;
+        }
+        if pre_skip && !seg.seg_id_pre_skip {
+            self.b.segment_id = 0;
+            return;
+        }
+        if !pre_skip && self.b.skip {
+            self.set_seg_pred_context(false);
+            self.read_segment_id();
+            return;
+        }
+        if seg.temporal_update {
+            let (r, c) = (self.b.mi_row as usize, self.b.mi_col as usize);
+            let ctx = self.left_seg_pred[r] as usize + self.above_seg_pred[c] as usize;
+            let seg_id_predicted = self
+                .sd
+                .read_flag(|cdf| &mut cdf.segment_id_predicted[ctx][..]);
+            if seg_id_predicted {
+                self.b.segment_id = predicted;
+            } else {
+                self.read_segment_id();
+            }
+            self.set_seg_pred_context(seg_id_predicted);
+        } else {
+            self.read_segment_id();
+        }
+    }
+
+    fn set_seg_pred_context(&mut self, value: bool) {
+        let (r, c) = (self.b.mi_row as usize, self.b.mi_col as usize);
+        self.above_seg_pred[c..c + self.b.bw4 as usize].fill(value);
+        self.left_seg_pred[r..r + self.b.bh4 as usize].fill(value);
+    }
+
+    /// Above and left mode info, when available
+    fn neighbours(&self) -> [Option<crate::tile::decoder::MiInfo>; 2] {
+        let (r, c) = (self.b.mi_row, self.b.mi_col);
+        [
+            self.b.avail_u.then(|| *self.mi(r - 1, c)),
+            self.b.avail_l.then(|| *self.mi(r, c - 1)),
+        ]
+    }
+
+    /// Spec `read_skip()`
+    fn read_skip(&mut self) {
+        if self.fh.segmentation.seg_id_pre_skip && self.seg_feature_active(SEG_LVL_SKIP) {
+            self.b.skip = true;
+        } else {
+            let ctx: usize = self
+                .neighbours()
+                .iter()
+                .flatten()
+                .map(|n| n.skip as usize)
+                .sum();
+            self.b.skip = self.sd.read_flag(|c| &mut c.skip[ctx][..]);
+        }
+    }
+
+    /// Spec `read_skip_mode()`
+    fn read_skip_mode(&mut self) {
+        let size = self.b.size;
+        if self.seg_feature_active(SEG_LVL_SKIP)
+            || self.seg_feature_active(SEG_LVL_REF_FRAME)
+            || self.seg_feature_active(SEG_LVL_GLOBALMV)
+            || !self.fh.skip_mode_present
+            || size.width() < 8
+            || size.height() < 8
+        {
+            self.b.skip_mode = false;
+        } else {
+            let ctx: usize = self
+                .neighbours()
+                .iter()
+                .flatten()
+                .map(|n| n.skip_mode as usize)
+                .sum();
+            self.b.skip_mode = self.sd.read_flag(|c| &mut c.skip_mode[ctx][..]);
+        }
+    }
+
+    /// Spec `inter_frame_mode_info()`
+    fn inter_frame_mode_info(&mut self) -> Result<()> {
+        self.b.use_intrabc = false;
+        let [above, left] = self.neighbours();
+        self.b.above_ref_frame = above.map_or([INTRA_FRAME, NONE], |n| n.ref_frame);
+        self.b.left_ref_frame = left.map_or([INTRA_FRAME, NONE], |n| n.ref_frame);
+        self.b.skip = false;
+        self.inter_segment_id(true);
+        self.read_skip_mode();
+        if self.b.skip_mode {
+            self.b.skip = true;
+        } else {
+            self.read_skip();
+        }
+        if !self.fh.segmentation.seg_id_pre_skip {
+            self.inter_segment_id(false);
+        }
+        self.b.lossless = self.fh.lossless_array[self.b.segment_id as usize];
+        self.read_cdef();
+        self.read_delta_qindex();
+        self.read_delta_lf();
+        self.read_deltas = false;
+        self.read_is_inter();
+        if self.b.is_inter {
+            self.inter_block_mode_info();
+        } else {
+            self.intra_block_mode_info();
+        }
+        Ok(())
+    }
+
+    /// Spec `read_is_inter()`
+    fn read_is_inter(&mut self) {
+        self.b.is_inter = if self.b.skip_mode {
+            true
+        } else if self.seg_feature_active(SEG_LVL_REF_FRAME) {
+            let seg = self.b.segment_id as usize;
+            self.fh.segmentation.feature_data[seg][SEG_LVL_REF_FRAME] != INTRA_FRAME as i16
+        } else if self.seg_feature_active(SEG_LVL_GLOBALMV) {
+            true
+        } else {
+            let above_intra = self.b.above_ref_frame[0] <= INTRA_FRAME;
+            let left_intra = self.b.left_ref_frame[0] <= INTRA_FRAME;
+            let ctx = match (self.b.avail_u, self.b.avail_l) {
+                (true, true) => {
+                    if left_intra && above_intra {
+                        3
+                    } else {
+                        (left_intra || above_intra) as usize
+                    }
+                }
+                (true, false) => 2 * above_intra as usize,
+                (false, true) => 2 * left_intra as usize,
+                (false, false) => 0,
+            };
+            self.sd.read_flag(|c| &mut c.is_inter[ctx][..])
+        };
+    }
+
+    /// Spec `intra_block_mode_info()`
+    fn intra_block_mode_info(&mut self) {
+        self.b.ref_frame = [INTRA_FRAME, NONE];
+        let group = SIZE_GROUP[self.b.size as usize] as usize;
+        self.b.y_mode = self.sd.read(|c| &mut c.y_mode[group][..]) as u8;
+        self.intra_modes_after_y();
+    }
+
+    /// Spec `palette_mode_info()`
+    fn palette_mode_info(&mut self) {
+        let bsize_ctx = (mi_width_log2(self.b.size) + mi_height_log2(self.b.size) - 2) as usize;
+        let bit_depth = self.seq.color_config.bit_depth as u32;
+        if self.b.y_mode == DC_PRED {
+            let ctx: usize = self
+                .neighbours()
+                .iter()
+                .flatten()
+                .map(|n| (n.palette_size[0] > 0) as usize)
+                .sum();
+            let has_palette_y = self
+                .sd
+                .read_flag(|c| &mut c.palette_y_mode[bsize_ctx][ctx][..]);
+            if has_palette_y {
+                let n = self.sd.read(|c| &mut c.palette_y_size[bsize_ctx][..]) + 2;
+                self.b.palette_size[0] = n as u8;
+                let mut colors = [0u16; 8];
+                self.read_palette_colors(0, n, bit_depth, &mut colors, 1);
+                self.b.palette_colors[0] = colors;
+            }
+        }
+        if self.b.has_chroma && self.b.uv_mode == DC_PRED {
+            let ctx = (self.b.palette_size[0] > 0) as usize;
+            let has_palette_uv = self.sd.read_flag(|c| &mut c.palette_uv_mode[ctx][..]);
+            if has_palette_uv {
+                let n = self.sd.read(|c| &mut c.palette_uv_size[bsize_ctx][..]) + 2;
+                self.b.palette_size[1] = n as u8;
+                let mut colors = [0u16; 8];
+                self.read_palette_colors(1, n, bit_depth, &mut colors, 0);
+                self.b.palette_colors[1] = colors;
+
+                let mut v = [0u16; 8];
+                let max_val = 1i32 << bit_depth;
+                if self.sd.decoder.read_literal(1) == 1 {
+                    let min_bits = bit_depth - 4;
+                    let bits = min_bits + self.sd.decoder.read_literal(2);
+                    v[0] = self.sd.decoder.read_literal(bit_depth) as u16;
+                    for idx in 1..n {
+                        let mut delta = self.sd.decoder.read_literal(bits) as i32;
+                        if delta != 0 && self.sd.decoder.read_literal(1) == 1 {
+                            delta = -delta;
+                        }
+                        let mut val = v[idx - 1] as i32 + delta;
+                        if val < 0 {
+                            val += max_val;
+                        }
+                        if val >= max_val {
+                            val -= max_val;
+                        }
+                        v[idx] = val.clamp(0, max_val - 1) as u16;
+                    }
+                } else {
+                    for color in v.iter_mut().take(n) {
+                        *color = self.sd.decoder.read_literal(bit_depth) as u16;
+                    }
+                }
+                self.b.palette_colors[2] = v;
+            }
+        }
+    }
+
+    /// Palette colors of the Y (`plane` 0) or U plane: cache reuse flags,
+    /// a literal and ascending deltas (`delta_offset` is 1 for Y)
+    fn read_palette_colors(
+        &mut self,
+        plane: usize,
+        n: usize,
+        bit_depth: u32,
+        colors: &mut [u16; 8],
+        delta_offset: i32,
+    ) {
+        let cache = self.get_palette_cache(plane);
+        let mut idx = 0;
+        for &cached in &cache {
+            if idx >= n {
+                break;
+            }
+            if self.sd.decoder.read_literal(1) == 1 {
+                colors[idx] = cached;
+                idx += 1;
+            }
+        }
+        if idx < n {
+            colors[idx] = self.sd.decoder.read_literal(bit_depth) as u16;
+            idx += 1;
+        }
+        let mut palette_bits = 0;
+        if idx < n {
+            let min_bits = bit_depth - 3;
+            palette_bits = min_bits + self.sd.decoder.read_literal(2);
+        }
+        let max = (1i32 << bit_depth) - 1;
+        while idx < n {
+            let delta = self.sd.decoder.read_literal(palette_bits) as i32 + delta_offset;
+            let color = (colors[idx - 1] as i32 + delta).clamp(0, max);
+            colors[idx] = color as u16;
+            let range = (1 << bit_depth) - color - delta_offset;
+            palette_bits = palette_bits.min(ceil_log2(range.max(0) as u32));
+            idx += 1;
+        }
+        colors[..n].sort_unstable();
+    }
+
+    /// Spec `get_palette_cache()`: the sorted, deduplicated colors of the
+    /// above (within the same 64 row band) and left palettes
+    fn get_palette_cache(&self, plane: usize) -> Vec<u16> {
+        let (r, c) = (self.b.mi_row, self.b.mi_col);
+        let palette = |row: i32, col: i32| {
+            let idx = self.fs.idx(row, col);
+            let n = self.fs.mi[idx].palette_size[plane] as usize;
+            self.fs.palette_colors[idx][plane][..n].to_vec()
+        };
+        let above = if (r * 4) % 64 != 0 && self.b.avail_u {
+            palette(r - 1, c)
+        } else {
+            Vec::new()
+        };
+        let left = if self.b.avail_l {
+            palette(r, c - 1)
+        } else {
+            Vec::new()
+        };
+
+        let mut cache: Vec<u16> = Vec::with_capacity(16);
+        let push = |cache: &mut Vec<u16>, v: u16| {
+            if cache.last() != Some(&v) {
+                cache.push(v);
+            }
+        };
+        let (mut ai, mut li) = (0, 0);
+        while ai < above.len() && li < left.len() {
+            let (above_c, left_c) = (above[ai], left[li]);
+            if left_c < above_c {
+                push(&mut cache, left_c);
+                li += 1;
+            } else {
+                push(&mut cache, above_c);
+                ai += 1;
+                if left_c == above_c {
+                    li += 1;
+                }
+            }
+        }
+        for &v in &above[ai..] {
+            push(&mut cache, v);
+        }
+        for &v in &left[li..] {
+            push(&mut cache, v);
+        }
+        cache
+    }
+
+    /// Spec `palette_tokens()`
+    pub(crate) fn palette_tokens(&mut self) {
+        let size = self.b.size;
+        let mut block_height = size.height() as i32;
+        let mut block_width = size.width() as i32;
+        let mut onscreen_height = block_height.min((self.fs.mi_rows - self.b.mi_row) * 4);
+        let mut onscreen_width = block_width.min((self.fs.mi_cols - self.b.mi_col) * 4);
+        if self.b.palette_size[0] > 0 {
+            let n = self.b.palette_size[0] as usize;
+            self.read_color_map(0, n, onscreen_width, onscreen_height);
+        }
+        if self.b.palette_size[1] > 0 {
+            let n = self.b.palette_size[1] as usize;
+            let (ss_x, ss_y) = self.subsampling();
+            block_height >>= ss_y as i32;
+            block_width >>= ss_x as i32;
+            onscreen_height >>= ss_y as i32;
+            onscreen_width >>= ss_x as i32;
+            if block_width < 4 {
+                onscreen_width += 2;
+            }
+            if block_height < 4 {
+                onscreen_height += 2;
+            }
+            self.read_color_map(1, n, onscreen_width, onscreen_height);
+        }
+    }
+
+    /// Read a color index map in wavefront order
+    fn read_color_map(&mut self, plane: usize, n: usize, width: i32, height: i32) {
+        const STRIDE: usize = 64;
+        self.color_map[0] = self.sd.decoder.read_ns(n as u32) as u8;
+        for i in 1..(height + width - 1) {
+            let mut j = i.min(width - 1);
+            while j >= (i - height + 1).max(0) {
+                let (r, c) = ((i - j) as usize, j as usize);
+                let (ctx, order) = palette_color_context(&self.color_map, STRIDE, r, c, n);
+                let symbol = if plane == 0 {
+                    self.sd
+                        .read(|cdf| &mut cdf.palette_y_color[n - 2][ctx][..n + 1])
+                } else {
+                    self.sd
+                        .read(|cdf| &mut cdf.palette_uv_color[n - 2][ctx][..n + 1])
+                };
+                self.color_map[r * STRIDE + c] = order[symbol];
+                j -= 1;
+            }
+        }
+    }
+
+    /// Spec `inter_block_mode_info()`
+    fn inter_block_mode_info(&mut self) {
+        self.b.palette_size = [0; 2];
+        self.read_ref_frames();
+        let is_compound = self.b.is_compound();
+        self.find_mv_stack(is_compound);
+        let st = &self.stack;
+        let (new_mv_ctx, ref_mv_ctx, zero_mv_ctx) =
+            (st.new_mv_context, st.ref_mv_context, st.zero_mv_context);
+        self.b.y_mode = if self.b.skip_mode {
+            NEAREST_NEARESTMV
+        } else if self.seg_feature_active(SEG_LVL_SKIP) || self.seg_feature_active(SEG_LVL_GLOBALMV)
+        {
+            GLOBALMV
+        } else if is_compound {
+            let ctx = COMPOUND_MODE_CTX_MAP[ref_mv_ctx >> 1][new_mv_ctx.min(4)];
+            NEAREST_NEARESTMV + self.sd.read(|c| &mut c.compound_mode[ctx][..]) as u8
+        } else if !self.sd.read_flag(|c| &mut c.new_mv[new_mv_ctx][..]) {
+            NEWMV
+        } else if !self.sd.read_flag(|c| &mut c.zero_mv[zero_mv_ctx][..]) {
+            GLOBALMV
+        } else if !self.sd.read_flag(|c| &mut c.ref_mv[ref_mv_ctx][..]) {
+            NEARESTMV
+        } else {
+            NEARMV
+        };
+
+        self.b.ref_mv_idx = 0;
+        let y_mode = self.b.y_mode;
+        let drl_range = if y_mode == NEWMV || y_mode == NEW_NEWMV {
+            Some(0..2)
+        } else if has_nearmv(y_mode) {
+            self.b.ref_mv_idx = 1;
+            Some(1..3)
+        } else {
+            None
+        };
+        if let Some(range) = drl_range {
+            for idx in range {
+                if self.stack.num_mv_found > idx + 1 {
+                    let ctx = self.stack.drl_ctx_stack[idx];
+                    if !self.sd.read_flag(|c| &mut c.drl_mode[ctx][..]) {
+                        self.b.ref_mv_idx = idx;
+                        break;
+                    }
+                    self.b.ref_mv_idx = idx + 1;
+                }
+            }
+        }
+
+        self.assign_mv(is_compound);
+        self.read_interintra_mode(is_compound);
+        self.read_motion_mode(is_compound);
+        self.read_compound_type(is_compound);
+        if self.fh.interpolation_filter == SWITCHABLE {
+            let dirs = if self.seq.enable_dual_filter { 2 } else { 1 };
+            for dir in 0..dirs {
+                self.b.interp_filter[dir] = if self.needs_interp_filter() {
+                    let ctx = self.interp_filter_ctx(dir);
+                    self.sd.read(|c| &mut c.interp_filter[ctx][..]) as u8
+                } else {
+                    EIGHTTAP
+                };
+            }
+            if !self.seq.enable_dual_filter {
+                self.b.interp_filter[1] = self.b.interp_filter[0];
+            }
+        } else {
+            self.b.interp_filter = [self.fh.interpolation_filter; 2];
+        }
+    }
+
+    fn gm_type(&self, ref_frame: i8) -> GlobalMotionType {
+        self.fh.gm_type[ref_frame.max(0) as usize]
+    }
+
+    /// Spec `needs_interp_filter()`
+    fn needs_interp_filter(&self) -> bool {
+        let size = self.b.size;
+        let large = size.width().min(size.height()) >= 8;
+        if self.b.skip_mode || self.b.motion_mode == LOCALWARP {
+            false
+        } else if large && self.b.y_mode == GLOBALMV {
+            self.gm_type(self.b.ref_frame[0]) == GlobalMotionType::Translation
+        } else if large && self.b.y_mode == GLOBAL_GLOBALMV {
+            self.gm_type(self.b.ref_frame[0]) == GlobalMotionType::Translation
+                || self.gm_type(self.b.ref_frame[1]) == GlobalMotionType::Translation
+        } else {
+            true
+        }
+    }
+
+    /// Context of `interp_filter[dir]`
+    fn interp_filter_ctx(&self, dir: usize) -> usize {
+        let mut ctx = ((dir & 1) * 2 + self.b.is_compound() as usize) * 4;
+        let ref0 = self.b.ref_frame[0];
+        let filter_type = |n: Option<crate::tile::decoder::MiInfo>| {
+            n.filter(|n| n.ref_frame[0] == ref0 || n.ref_frame[1] == ref0)
+                .map_or(3, |n| n.interp_filter[dir] as usize)
+        };
+        let [above, left] = self.neighbours();
+        let (above_type, left_type) = (filter_type(above), filter_type(left));
+        ctx += if left_type == above_type {
+            left_type
+        } else if left_type == 3 {
+            above_type
+        } else if above_type == 3 {
+            left_type
+        } else {
+            3
+        };
+        ctx
+    }
+
+    /// Spec `count_refs()`
+    fn count_refs(&self, frame_type: i8) -> usize {
+        let mut c = 0;
+        if self.b.avail_u {
+            c += self
+                .b
+                .above_ref_frame
+                .iter()
+                .filter(|&&r| r == frame_type)
+                .count();
+        }
+        if self.b.avail_l {
+            c += self
+                .b
+                .left_ref_frame
+                .iter()
+                .filter(|&&r| r == frame_type)
+                .count();
+        }
+        c
+    }
+
+    /// Context from comparing the neighbour counts of two reference groups
+    fn ref_count_ctx(&self, group0: &[i8], group1: &[i8]) -> usize {
+        let count = |group: &[i8]| group.iter().map(|&r| self.count_refs(r)).sum::<usize>();
+        let (c0, c1) = (count(group0), count(group1));
+        match c0.cmp(&c1) {
+            std::cmp::Ordering::Less => 0,
+            std::cmp::Ordering::Equal => 1,
+            std::cmp::Ordering::Greater => 2,
+        }
+    }
+
+    /// Spec `read_ref_frames()`
+    fn read_ref_frames(&mut self) {
+        const FWD: [i8; 4] = [LAST_FRAME, LAST2_FRAME, LAST3_FRAME, GOLDEN_FRAME];
+        const BWD: [i8; 3] = [BWDREF_FRAME, ALTREF2_FRAME, ALTREF_FRAME];
+        const LAST12: [i8; 2] = [LAST_FRAME, LAST2_FRAME];
+        const LAST3_GOLD: [i8; 2] = [LAST3_FRAME, GOLDEN_FRAME];
+        const BWD_ALT2: [i8; 2] = [BWDREF_FRAME, ALTREF2_FRAME];
+
+        if self.b.skip_mode {
+            let frames = self.fh.skip_mode_frame;
+            self.b.ref_frame = [frames[0] as i8, frames[1] as i8];
+            return;
+        }
+        if self.seg_feature_active(SEG_LVL_REF_FRAME) {
+            let seg = self.b.segment_id as usize;
+            let data = self.fh.segmentation.feature_data[seg][SEG_LVL_REF_FRAME];
+            self.b.ref_frame = [data as i8, NONE];
+            return;
+        }
+        if self.seg_feature_active(SEG_LVL_SKIP) || self.seg_feature_active(SEG_LVL_GLOBALMV) {
+            self.b.ref_frame = [LAST_FRAME, NONE];
+            return;
+        }
+
+        let compound = self.fh.reference_select && self.b.bw4.min(self.b.bh4) >= 2 && {
+            let ctx = self.comp_mode_ctx();
+            self.sd.read_flag(|c| &mut c.comp_mode[ctx][..])
+        };
+        if compound {
+            let ctx = self.comp_ref_type_ctx();
+            let bidir = self.sd.read_flag(|c| &mut c.comp_ref_type[ctx][..]);
+            if !bidir {
+                let ctx = self.ref_count_ctx(&FWD, &BWD);
+                if self.sd.read_flag(|c| &mut c.uni_comp_ref[ctx][0][..]) {
+                    self.b.ref_frame = [BWDREF_FRAME, ALTREF_FRAME];
+                } else {
+                    let ctx = self.ref_count_ctx(&[LAST2_FRAME], &LAST3_GOLD);
+                    if self.sd.read_flag(|c| &mut c.uni_comp_ref[ctx][1][..]) {
+                        let ctx = self.ref_count_ctx(&[LAST3_FRAME], &[GOLDEN_FRAME]);
+                        if self.sd.read_flag(|c| &mut c.uni_comp_ref[ctx][2][..]) {
+                            self.b.ref_frame = [LAST_FRAME, GOLDEN_FRAME];
+                        } else {
+                            self.b.ref_frame = [LAST_FRAME, LAST3_FRAME];
+                        }
+                    } else {
+                        self.b.ref_frame = [LAST_FRAME, LAST2_FRAME];
+                    }
+                }
+            } else {
+                let ctx = self.ref_count_ctx(&LAST12, &LAST3_GOLD);
+                if !self.sd.read_flag(|c| &mut c.comp_ref[ctx][0][..]) {
+                    let ctx = self.ref_count_ctx(&[LAST_FRAME], &[LAST2_FRAME]);
+                    self.b.ref_frame[0] = if self.sd.read_flag(|c| &mut c.comp_ref[ctx][1][..]) {
+                        LAST2_FRAME
+                    } else {
+                        LAST_FRAME
+                    };
+                } else {
+                    let ctx = self.ref_count_ctx(&[LAST3_FRAME], &[GOLDEN_FRAME]);
+                    self.b.ref_frame[0] = if self.sd.read_flag(|c| &mut c.comp_ref[ctx][2][..]) {
+                        GOLDEN_FRAME
+                    } else {
+                        LAST3_FRAME
+                    };
+                }
+                let ctx = self.ref_count_ctx(&BWD_ALT2, &[ALTREF_FRAME]);
+                if !self.sd.read_flag(|c| &mut c.comp_bwd_ref[ctx][0][..]) {
+                    let ctx = self.ref_count_ctx(&[BWDREF_FRAME], &[ALTREF2_FRAME]);
+                    self.b.ref_frame[1] = if self.sd.read_flag(|c| &mut c.comp_bwd_ref[ctx][1][..])
+                    {
+                        ALTREF2_FRAME
+                    } else {
+                        BWDREF_FRAME
+                    };
+                } else {
+                    self.b.ref_frame[1] = ALTREF_FRAME;
+                }
+            }
+        } else {
+            let ctx = self.ref_count_ctx(&FWD, &BWD);
+            let ref_frame = if self.sd.read_flag(|c| &mut c.single_ref[ctx][0][..]) {
+                let ctx = self.ref_count_ctx(&BWD_ALT2, &[ALTREF_FRAME]);
+                if !self.sd.read_flag(|c| &mut c.single_ref[ctx][1][..]) {
+                    let ctx = self.ref_count_ctx(&[BWDREF_FRAME], &[ALTREF2_FRAME]);
+                    if self.sd.read_flag(|c| &mut c.single_ref[ctx][5][..]) {
+                        ALTREF2_FRAME
+                    } else {
+                        BWDREF_FRAME
+                    }
+                } else {
+                    ALTREF_FRAME
+                }
+            } else {
+                let ctx = self.ref_count_ctx(&LAST12, &LAST3_GOLD);
+                if self.sd.read_flag(|c| &mut c.single_ref[ctx][2][..]) {
+                    let ctx = self.ref_count_ctx(&[LAST3_FRAME], &[GOLDEN_FRAME]);
+                    if self.sd.read_flag(|c| &mut c.single_ref[ctx][4][..]) {
+                        GOLDEN_FRAME
+                    } else {
+                        LAST3_FRAME
+                    }
+                } else {
+                    let ctx = self.ref_count_ctx(&[LAST_FRAME], &[LAST2_FRAME]);
+                    if self.sd.read_flag(|c| &mut c.single_ref[ctx][3][..]) {
+                        LAST2_FRAME
+                    } else {
+                        LAST_FRAME
+                    }
+                }
+            };
+            self.b.ref_frame = [ref_frame, NONE];
+        }
+    }
+
+    /// Context of `comp_mode`
+    fn comp_mode_ctx(&self) -> usize {
+        let check_backward = |r: i8| (BWDREF_FRAME..=ALTREF_FRAME).contains(&r);
+        let (above, left) = (self.b.above_ref_frame, self.b.left_ref_frame);
+        let above_single = above[1] <= INTRA_FRAME;
+        let left_single = left[1] <= INTRA_FRAME;
+        let above_intra = above[0] <= INTRA_FRAME;
+        let left_intra = left[0] <= INTRA_FRAME;
+        match (self.b.avail_u, self.b.avail_l) {
+            (true, true) => {
+                if above_single && left_single {
+                    (check_backward(above[0]) ^ check_backward(left[0])) as usize
+                } else if above_single {
+                    2 + (check_backward(above[0]) || above_intra) as usize
+                } else if left_single {
+                    2 + (check_backward(left[0]) || left_intra) as usize
+                } else {
+                    4
+                }
+            }
+            (true, false) => {
+                if above_single {
+                    check_backward(above[0]) as usize
+                } else {
+                    3
+                }
+            }
+            (false, true) => {
+                if left_single {
+                    check_backward(left[0]) as usize
+                } else {
+                    3
+                }
+            }
+            (false, false) => 1,
+        }
+    }
+
+    /// Context of `comp_ref_type`
+    fn comp_ref_type_ctx(&self) -> usize {
+        let samedir = |a: i8, b: i8| (a >= BWDREF_FRAME) == (b >= BWDREF_FRAME);
+        let (above, left) = (self.b.above_ref_frame, self.b.left_ref_frame);
+        let (avail_u, avail_l) = (self.b.avail_u, self.b.avail_l);
+        let above_intra = above[0] <= INTRA_FRAME;
+        let left_intra = left[0] <= INTRA_FRAME;
+        let above_comp_inter = avail_u && !above_intra && above[1] > INTRA_FRAME;
+        let left_comp_inter = avail_l && !left_intra && left[1] > INTRA_FRAME;
+        let above_uni_comp = above_comp_inter && samedir(above[0], above[1]);
+        let left_uni_comp = left_comp_inter && samedir(left[0], left[1]);
+        if avail_u && !above_intra && avail_l && !left_intra {
+            let same = samedir(above[0], left[0]) as usize;
+            if !above_comp_inter && !left_comp_inter {
+                1 + 2 * same
+            } else if !above_comp_inter {
+                if !left_uni_comp {
+                    1
+                } else {
+                    3 + same
+                }
+            } else if !left_comp_inter {
+                if !above_uni_comp {
+                    1
+                } else {
+                    3 + same
+                }
+            } else if !above_uni_comp && !left_uni_comp {
+                0
+            } else if !above_uni_comp || !left_uni_comp {
+                2
+            } else {
+                3 + ((above[0] == BWDREF_FRAME) == (left[0] == BWDREF_FRAME)) as usize
+            }
+        } else if avail_u && avail_l {
+            if above_comp_inter {
+                1 + 2 * above_uni_comp as usize
+            } else if left_comp_inter {
+                1 + 2 * left_uni_comp as usize
+            } else {
+                2
+            }
+        } else if above_comp_inter {
+            4 * above_uni_comp as usize
+        } else if left_comp_inter {
+            4 * left_uni_comp as usize
+        } else {
+            2
+        }
+    }
+
+    /// Spec `get_mode()`: the single reference mode of list `ref_list`
+    fn get_mode(&self, ref_list: usize) -> u8 {
+        let y = self.b.y_mode;
+        if ref_list == 0 {
+            match y {
+                _ if y < NEAREST_NEARESTMV => y,
+                NEW_NEWMV | NEW_NEARESTMV | NEW_NEARMV => NEWMV,
+                NEAREST_NEARESTMV | NEAREST_NEWMV => NEARESTMV,
+                NEAR_NEARMV | NEAR_NEWMV => NEARMV,
+                _ => GLOBALMV,
+            }
+        } else {
+            match y {
+                NEW_NEWMV | NEAREST_NEWMV | NEAR_NEWMV => NEWMV,
+                NEAREST_NEARESTMV | NEW_NEARESTMV => NEARESTMV,
+                NEAR_NEARMV | NEW_NEARMV => NEARMV,
+                _ => GLOBALMV,
+            }
+        }
+    }
+
+    /// Spec `assign_mv()`
+    fn assign_mv(&mut self, is_compound: bool) {
+        for i in 0..1 + is_compound as usize {
+            let comp_mode = if self.b.use_intrabc {
+                NEWMV
+            } else {
+                self.get_mode(i)
+            };
+            let pred_mv: Mv = if self.b.use_intrabc {
+                let mut pred = self.stack.ref_stack_mv[0][0];
+                if pred == [0, 0] {
+                    pred = self.stack.ref_stack_mv[1][0];
+                }
+                if pred == [0, 0] {
+                    let sb_size4 = (self.sb_size.height() >> 2) as i32;
+                    if self.b.mi_row - sb_size4 < self.mi_row_start {
+                        pred = [0, -(sb_size4 * 4 + INTRABC_DELAY_PIXELS) * 8];
+                    } else {
+                        pred = [-(sb_size4 * 4 * 8), 0];
+                    }
+                }
+                pred
+            } else if comp_mode == GLOBALMV {
+                self.stack.global_mvs[i]
+            } else {
+                let mut pos = if comp_mode == NEARESTMV {
+                    0
+                } else {
+                    self.b.ref_mv_idx
+                };
+                if comp_mode == NEWMV && self.stack.num_mv_found <= 1 {
+                    pos = 0;
+                }
+                self.stack.ref_stack_mv[pos][i]
+            };
+            self.b.mv[i] = if comp_mode == NEWMV {
+                let diff = self.read_mv();
+                [pred_mv[0] + diff[0], pred_mv[1] + diff[1]]
+            } else {
+                pred_mv
+            };
+        }
+    }
+
+    /// Spec `read_mv()`: the coded motion vector difference
+    fn read_mv(&mut self) -> Mv {
+        let mv_ctx = if self.b.use_intrabc {
+            MV_INTRABC_CONTEXT
+        } else {
+            0
+        };
+        let mut diff = [0; 2];
+        let joint = self.sd.read(|c| &mut c.mv[mv_ctx].joint[..]);
+        if joint == 2 || joint == 3 {
+            diff[0] = self.read_mv_component(mv_ctx, 0);
+        }
+        if joint == 1 || joint == 3 {
+            diff[1] = self.read_mv_component(mv_ctx, 1);
+        }
+        diff
+    }
+
+    /// Spec `read_mv_component()`
+    fn read_mv_component(&mut self, ctx: usize, comp: usize) -> i32 {
+        let force_integer_mv = self.fh.force_integer_mv;
+        let allow_hp = self.fh.allow_high_precision_mv;
+        let sign = self.sd.read_flag(|c| &mut c.mv[ctx].sign[comp][..]);
+        let class = self.sd.read(|c| &mut c.mv[ctx].class[comp][..]);
+        let mag = if class == 0 {
+            let class0_bit = self.sd.read(|c| &mut c.mv[ctx].class0_bit[comp][..]) as i32;
+            let fr = if force_integer_mv {
+                3
+            } else {
+                self.sd
+                    .read(|c| &mut c.mv[ctx].class0_fr[comp][class0_bit as usize][..])
+                    as i32
+            };
+            let hp = if allow_hp {
+                self.sd.read(|c| &mut c.mv[ctx].class0_hp[comp][..]) as i32
+            } else {
+                1
+            };
+            ((class0_bit << 3) | (fr << 1) | hp) + 1
+        } else {
+            let mut d = 0;
+            for i in 0..class {
+                let bit = self.sd.read(|c| &mut c.mv[ctx].bits[comp][i][..]) as i32;
+                d |= bit << i;
+            }
+            let mut mag = 2 << (class + 2);
+            let fr = if force_integer_mv {
+                3
+            } else {
+                self.sd.read(|c| &mut c.mv[ctx].fr[comp][..]) as i32
+            };
+            let hp = if allow_hp {
+                self.sd.read(|c| &mut c.mv[ctx].hp[comp][..]) as i32
+            } else {
+                1
+            };
+            mag += ((d << 3) | (fr << 1) | hp) + 1;
+            mag
+        };
+        if sign {
+            -mag
+        } else {
+            mag
+        }
+    }
+
+    /// Spec `read_interintra_mode()`
+    fn read_interintra_mode(&mut self, is_compound: bool) {
+        self.b.interintra = false;
+        let size = self.b.size;
+        if !self.b.skip_mode
+            && self.seq.enable_interintra_compound
+            && !is_compound
+            && size >= BlockSize::Block8x8
+            && size <= BlockSize::Block32x32
+        {
+            let group = SIZE_GROUP[size as usize] as usize;
+            self.b.interintra = self.sd.read_flag(|c| &mut c.inter_intra[group][..]);
+            if self.b.interintra {
+                self.sd.read(|c| &mut c.inter_intra_mode[group][..]);
+                self.b.ref_frame[1] = INTRA_FRAME;
+                self.b.use_filter_intra = false;
+                let bsize = size as usize;
+                if self.sd.read_flag(|c| &mut c.wedge_inter_intra[bsize][..]) {
+                    self.sd.read(|c| &mut c.wedge_index[bsize][..]);
+                }
+            }
+        }
+    }
+
+    /// Spec `read_motion_mode()`
+    fn read_motion_mode(&mut self, is_compound: bool) {
+        self.b.motion_mode = SIMPLE;
+        let size = self.b.size;
+        if self.b.skip_mode
+            || !self.fh.is_motion_mode_switchable
+            || size.width().min(size.height()) < 8
+        {
+            return;
+        }
+        if !self.fh.force_integer_mv
+            && (self.b.y_mode == GLOBALMV || self.b.y_mode == GLOBAL_GLOBALMV)
+            && self.gm_type(self.b.ref_frame[0]) > GlobalMotionType::Translation
+        {
+            return;
+        }
+        if is_compound || self.b.ref_frame[1] == INTRA_FRAME || !self.has_overlappable_candidates()
+        {
+            return;
+        }
+        let bsize = size as usize;
+        let has_samples = self.has_warp_samples();
+        if self.fh.force_integer_mv
+            || !has_samples
+            || !self.fh.allow_warped_motion
+            || self.fs.ref_scaled[self.b.ref_frame[0] as usize]
+        {
+            if self.sd.read_flag(|c| &mut c.use_obmc[bsize][..]) {
+                self.b.motion_mode = OBMC;
+            }
+        } else {
+            self.b.motion_mode = self.sd.read(|c| &mut c.motion_mode[bsize][..]) as u8;
+        }
+    }
+
+    /// Spec `read_compound_type()`
+    fn read_compound_type(&mut self, is_compound: bool) {
+        self.b.comp_group_idx = 0;
+        self.b.compound_idx = 1;
+        if self.b.skip_mode || !is_compound {
+            return;
+        }
+        let size = self.b.size;
+        if self.seq.enable_masked_compound {
+            let ctx = self.comp_group_idx_ctx();
+            self.b.comp_group_idx = self.sd.read(|c| &mut c.comp_group_idx[ctx][..]) as u8;
+        }
+        let bsize = size as usize;
+        if self.b.comp_group_idx == 0 {
+            if self.seq.enable_jnt_comp {
+                let ctx = self.compound_idx_ctx();
+                self.b.compound_idx = self.sd.read(|c| &mut c.compound_idx[ctx][..]) as u8;
+            }
+        } else {
+            // COMPOUND_WEDGE (0) or COMPOUND_DIFFWTD (1)
+            let wedge =
+                WEDGE_BITS[bsize] != 0 && self.sd.read(|c| &mut c.compound_type[bsize][..]) == 0;
+            if wedge {
+                self.sd.read(|c| &mut c.wedge_index[bsize][..]);
+                self.sd.decoder.read_literal(1);
+            } else {
+                // mask_type
+                self.sd.decoder.read_literal(1);
+            }
+        }
+    }
+
+    /// Context of `comp_group_idx`
+    fn comp_group_idx_ctx(&self) -> usize {
+        let mut ctx = 0;
+        for n in self.neighbours().into_iter().flatten() {
+            if n.ref_frame[1] > INTRA_FRAME {
+                ctx += n.comp_group_idx as usize;
+            } else if n.ref_frame[0] == ALTREF_FRAME {
+                ctx += 3;
+            }
+        }
+        ctx.min(5)
+    }
+
+    /// Context of `compound_idx`
+    fn compound_idx_ctx(&self) -> usize {
+        let hints = &self.fh.order_hints;
+        let dist = |r: i8| {
+            self.fh
+                .get_relative_dist(hints[r as usize], self.fh.order_hint)
+                .abs()
+        };
+        let fwd = dist(self.b.ref_frame[0]);
+        let bck = dist(self.b.ref_frame[1]);
+        let mut ctx = if fwd == bck { 3 } else { 0 };
+        for n in self.neighbours().into_iter().flatten() {
+            if n.ref_frame[1] > INTRA_FRAME {
+                ctx += n.compound_idx as usize;
+            } else if n.ref_frame[0] == ALTREF_FRAME {
+                ctx += 1;
+            }
+        }
+        ctx
+    }
+}
+
+/// Whether a mode uses the second or third stack entry
+fn has_nearmv(mode: u8) -> bool {
+    matches!(mode, NEARMV | NEAR_NEARMV | NEAR_NEWMV | NEW_NEARMV)
+}
+
+/// Spec `neg_deinterleave()`
+fn neg_deinterleave(diff: i32, r: i32, max: i32) -> i32 {
+    if r == 0 {
+        return diff;
+    }
+    if r >= max - 1 {
+        return max - diff - 1;
+    }
+    if 2 * r < max {
+        if diff <= 2 * r {
+            if diff & 1 == 1 {
+                return r + ((diff + 1) >> 1);
+            }
+            return r - (diff >> 1);
+        }
+        return diff;
+    }
+    if diff <= 2 * (max - r - 1) {
+        if diff & 1 == 1 {
+            return r + ((diff + 1) >> 1);
+        }
+        return r - (diff >> 1);
+    }
+    max - (diff + 1)
+}
+
+/// Spec `CeilLog2()`
+fn ceil_log2(x: u32) -> u32 {
+    if x < 2 {
+        0
+    } else {
+        32 - (x - 1).leading_zeros()
+    }
+}
+
+/// Spec `get_palette_color_context()`
+///
+/// Returns the context and the color order: palette indices sorted by how
+/// often they occur in the left, above-left and above neighbours.
+fn palette_color_context(
+    color_map: &[u8],
+    stride: usize,
+    r: usize,
+    c: usize,
+    n: usize,
+) -> (usize, [u8; 8]) {
+    let mut scores = [0u32; 8];
+    let mut order = [0u8, 1, 2, 3, 4, 5, 6, 7];
+    if c > 0 {
+        scores[color_map[r * stride + c - 1] as usize] += 2;
+    }
+    if r > 0 && c > 0 {
+        scores[color_map[(r - 1) * stride + c - 1] as usize] += 1;
+    }
+    if r > 0 {
+        scores[color_map[(r - 1) * stride + c] as usize] += 2;
+    }
+    for i in 0..3 {
+        let mut max_score = scores[i];
+        let mut max_idx = i;
+        for (j, &score) in scores.iter().enumerate().take(n).skip(i + 1) {
+            if score > max_score {
+                max_score = score;
+                max_idx = j;
+            }
+        }
+        if max_idx != i {
+            let max_order = order[max_idx];
+            for k in (i + 1..=max_idx).rev() {
+                scores[k] = scores[k - 1];
+                order[k] = order[k - 1];
+            }
+            scores[i] = max_score;
+            order[i] = max_order;
+        }
+    }
+    let hash: u32 = (0..3)
+        .map(|i| scores[i] * PALETTE_COLOR_HASH_MULTIPLIERS[i])
+        .sum();
+    let ctx = PALETTE_COLOR_CONTEXT[hash as usize].max(0) as usize;
+    (ctx, order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_neg_deinterleave() {
+        // Without a prediction the coded value is the segment ID
+        assert_eq!(neg_deinterleave(3, 0, 8), 3);
+        // Small coded values alternate around the prediction
+        assert_eq!(neg_deinterleave(0, 3, 8), 3);
+        assert_eq!(neg_deinterleave(1, 3, 8), 4);
+        assert_eq!(neg_deinterleave(2, 3, 8), 2);
+        assert_eq!(neg_deinterleave(7, 3, 8), 7);
+        // Prediction at the top of the range counts downwards
+        assert_eq!(neg_deinterleave(0, 7, 8), 7);
+        assert_eq!(neg_deinterleave(2, 7, 8), 5);
+    }
+
+    #[test]
+    fn test_palette_color_context() {
+        let stride = 64;
+        let mut map = vec![0u8; stride * 2];
+        // left = 1, above-left = 0, above = 1
+        map[1] = 1;
+        map[stride] = 1;
+        let (ctx, order) = palette_color_context(&map, stride, 1, 1, 3);
+        assert_eq!(order[..3], [1, 0, 2]);
+        // scores [4, 1, 0] hash to 4 + 2 = 6
+        assert_eq!(ctx, PALETTE_COLOR_CONTEXT[6] as usize);
+    }
+}
@@ -0,0 +1,241 @@
+//! Motion Field Estimation
+//!
+//! Per AV1 Specification Sections 7.9 (Motion field estimation process) and
+//! 7.19 (Motion field motion vector storage process)
+//!
+//! The motion vectors of a decoded frame are saved at 8x8 resolution with
+//! its reference slots. When a later frame enables `use_ref_frame_mvs`,
+//! they are projected onto it to provide the temporal candidates of the
+//! MV prediction.
+
+use crate::tile::decoder::{
+    FrameState, Mv, ALTREF2_FRAME, ALTREF_FRAME, BWDREF_FRAME, GOLDEN_FRAME, INTRA_FRAME,
+    LAST2_FRAME, LAST_FRAME, NONE,
+};
+use crate::tile::tables::DIV_MULT;
+use crate::uncompressed_header::{RefFrameInfo, UncompressedHeader};
+use bitvue_core::FrameType;
+
+/// Marks a motion field position without a projected motion vector
+pub(crate) const INVALID_MV: i32 = -1 << 15;
+
+/// MAX_FRAME_DISTANCE
+const MAX_FRAME_DISTANCE: i32 = 31;
+
+/// REFMVS_LIMIT
+const REFMVS_LIMIT: i32 = (1 << 12) - 1;
+
+/// MAX_OFFSET_WIDTH / MAX_OFFSET_HEIGHT (in 8x8 units)
+const MAX_OFFSET_WIDTH: i32 = 8;
+const MAX_OFFSET_HEIGHT: i32 = 0;
+
+/// SavedRefFrames / SavedMvs of a reference slot, sampled at 8x8
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SavedMvs {
+    rows8: i32,
+    cols8: i32,
+    refs: Vec<i8>,
+    mvs: Vec<Mv>,
+}
+
+impl SavedMvs {
+    /// Spec motion field motion vector storage process (7.19)
+    ///
+    /// Only the units sampled by the projection, `(2 * y8 + 1, 2 * x8 + 1)`,
+    /// are kept.
+    pub fn store(fh: &UncompressedHeader, fs: &FrameState) -> Self {
+        let (rows8, cols8) = (fs.mi_rows >> 1, fs.mi_cols >> 1);
+        let count = (rows8 * cols8) as usize;
+        let mut saved = Self {
+            rows8,
+            cols8,
+            refs: vec![NONE; count],
+            mvs: vec![[0; 2]; count],
+        };
+        for y8 in 0..rows8 {
+            for x8 in 0..cols8 {
+                let mi = &fs.mi[fs.idx(2 * y8 + 1, 2 * x8 + 1)];
+                let i = (y8 * cols8 + x8) as usize;
+                for list in 0..2 {
+                    let r = mi.ref_frame[list];
+                    if r <= INTRA_FRAME || !mi.is_inter {
+                        continue;
+                    }
+                    let dist = fh.get_relative_dist(fh.order_hints[r as usize], fh.order_hint);
+                    let mv = mi.mv[list];
+                    if dist < 0 && mv[0].abs() <= REFMVS_LIMIT && mv[1].abs() <= REFMVS_LIMIT {
+                        saved.refs[i] = r;
+                        saved.mvs[i] = mv;
+                    }
+                }
+            }
+        }
+        saved
+    }
+}
+
+/// MotionFieldMvs: projected motion vectors per reference frame at 8x8
+#[derive(Debug, Clone)]
+pub(crate) struct MotionField {
+    rows8: i32,
+    cols8: i32,
+    /// Indexed by reference frame (`LAST_FRAME` .. `ALTREF_FRAME`)
+    mvs: [Vec<Mv>; 8],
+}
+
+impl MotionField {
+    /// Spec motion field estimation process (7.9)
+    ///
+    /// `slots` are the reference slots (frame info and saved motion vectors)
+    /// before the current frame.
+    pub fn estimate(fh: &UncompressedHeader, slots: &[(&RefFrameInfo, &SavedMvs); 8]) -> Self {
+        let (rows8, cols8) = ((fh.mi_rows >> 1) as i32, (fh.mi_cols >> 1) as i32);
+        let count = (rows8 * cols8) as usize;
+        let mut field = Self {
+            rows8,
+            cols8,
+            mvs: std::array::from_fn(|_| vec![[INVALID_MV; 2]; count]),
+        };
+
+        let slot = |r: i8| slots[fh.ref_frame_idx[(r - LAST_FRAME) as usize] as usize];
+        let last_alt_hint = slot(LAST_FRAME).0.saved_order_hints[ALTREF_FRAME as usize];
+        if last_alt_hint != fh.order_hints[GOLDEN_FRAME as usize] {
+            field.project(fh, slot(LAST_FRAME), LAST_FRAME, -1);
+        }
+        let mut ref_stamp = 1;
+        let ahead = |r: i8| fh.get_relative_dist(fh.order_hints[r as usize], fh.order_hint) > 0;
+        if ahead(BWDREF_FRAME) && field.project(fh, slot(BWDREF_FRAME), BWDREF_FRAME, 1) {
+            ref_stamp -= 1;
+        }
+        if ahead(ALTREF2_FRAME) && field.project(fh, slot(ALTREF2_FRAME), ALTREF2_FRAME, 1) {
+            ref_stamp -= 1;
+        }
+        if ahead(ALTREF_FRAME)
+            && ref_stamp >= 0
+            && field.project(fh, slot(ALTREF_FRAME), ALTREF_FRAME, 1)
+        {
+            ref_stamp -= 1;
+        }
+        if ref_stamp >= 0 {
+            field.project(fh, slot(LAST2_FRAME), LAST2_FRAME, -1);
+        }
+        field
+    }
+
+    /// MotionFieldMvs[ref_frame][y8][x8]
+    pub fn get(&self, ref_frame: i8, y8: i32, x8: i32) -> Mv {
+        if y8 >= self.rows8 || x8 >= self.cols8 || ref_frame <= INTRA_FRAME {
+            return [INVALID_MV; 2];
+        }
+        self.mvs[ref_frame as usize][(y8 * self.cols8 + x8) as usize]
+    }
+
+    /// Spec projection process (7.9.2); returns whether the source was usable
+    fn project(
+        &mut self,
+        fh: &UncompressedHeader,
+        (info, saved): (&RefFrameInfo, &SavedMvs),
+        src: i8,
+        dst_sign: i32,
+    ) -> bool {
+        if info.mi_rows != fh.mi_rows
+            || info.mi_cols != fh.mi_cols
+            || matches!(info.frame_type, FrameType::Key | FrameType::IntraOnly)
+            || saved.rows8 != self.rows8
+            || saved.cols8 != self.cols8
+        {
+            return false;
+        }
+        let src_hint = fh.order_hints[src as usize];
+        let ref_to_cur = fh.get_relative_dist(src_hint, fh.order_hint);
+        let dst_dists: Vec<i32> = (LAST_FRAME..=ALTREF_FRAME)
+            .map(|dst| fh.get_relative_dist(fh.order_hint, fh.order_hints[dst as usize]))
+            .collect();
+        for y8 in 0..self.rows8 {
+            for x8 in 0..self.cols8 {
+                let i = (y8 * self.cols8 + x8) as usize;
+                let src_ref = saved.refs[i];
+                if src_ref <= INTRA_FRAME {
+                    continue;
+                }
+                let ref_offset =
+                    fh.get_relative_dist(src_hint, info.saved_order_hints[src_ref as usize]);
+                if ref_to_cur.abs() > MAX_FRAME_DISTANCE
+                    || ref_offset.abs() > MAX_FRAME_DISTANCE
+                    || ref_offset <= 0
+                {
+                    continue;
+                }
+                let mv = saved.mvs[i];
+                let proj = get_mv_projection(mv, ref_to_cur * dst_sign, ref_offset);
+                let pos_y8 = project(y8, proj[0], dst_sign, self.rows8, MAX_OFFSET_HEIGHT);
+                let pos_x8 = project(x8, proj[1], dst_sign, self.cols8, MAX_OFFSET_WIDTH);
+                let (Some(pos_y8), Some(pos_x8)) = (pos_y8, pos_x8) else {
+                    continue;
+                };
+                let pos = (pos_y8 * self.cols8 + pos_x8) as usize;
+                for (dst, &dist) in (LAST_FRAME..=ALTREF_FRAME).zip(&dst_dists) {
+                    self.mvs[dst as usize][pos] = get_mv_projection(mv, dist, ref_offset);
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Spec `get_mv_projection()`
+fn get_mv_projection(mv: Mv, numerator: i32, denominator: i32) -> Mv {
+    let denominator = denominator.min(MAX_FRAME_DISTANCE);
+    let numerator = numerator.clamp(-MAX_FRAME_DISTANCE, MAX_FRAME_DISTANCE);
+    mv.map(|v| {
+        let scaled = v as i64 * numerator as i64 * DIV_MULT[denominator as usize] as i64;
+        let round = 1i64 << 13;
+        let scaled = if scaled >= 0 {
+            (scaled + round) >> 14
+        } else {
+            -((-scaled + round) >> 14)
+        };
+        scaled.clamp(-(1 << 14) + 1, (1 << 14) - 1) as i32
+    })
+}
+
+/// Spec `project()`: the 8x8 position a projected vector lands on
+fn project(v8: i32, delta: i32, dst_sign: i32, max8: i32, max_off8: i32) -> Option<i32> {
+    let base8 = (v8 >> 3) << 3;
+    let offset8 = if delta >= 0 {
+        delta >> 6
+    } else {
+        -((-delta) >> 6)
+    };
+    let v8 = v8 + dst_sign * offset8;
+    if v8 < 0 || v8 >= max8 || v8 < base8 - max_off8 || v8 >= base8 + 8 + max_off8 {
+        None
+    } else {
+        Some(v8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_mv_projection() {
+        // Same distance: the vector is unchanged
+        assert_eq!(get_mv_projection([24, -40], 2, 2), [24, -40]);
+        // Half the distance, in the opposite direction
+        assert_eq!(get_mv_projection([24, -40], -1, 2), [-12, 20]);
+        // The result is clamped
+        assert_eq!(get_mv_projection([16000, 0], 31, 1)[0], (1 << 14) - 1);
+    }
+
+    #[test]
+    fn test_project_limits() {
+        assert_eq!(project(3, 64, 1, 10, 0), Some(4));
+        assert_eq!(project(3, -64 * 4, 1, 10, 0), None);
+        // Rows may not leave the 64x64 region
+        assert_eq!(project(7, 64, 1, 20, 0), None);
+        // Columns may move up to 8 positions past it
+        assert_eq!(project(7, 64, 1, 20, 8), Some(8));
+    }
+}
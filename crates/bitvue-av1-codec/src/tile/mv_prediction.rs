@@ -1,238 +1,634 @@
 //! Motion Vector Prediction
 //!
-//! Per AV1 Specification Section 5.10 (Motion Vector Prediction Process)
+//! Per AV1 Specification Section 7.10.2 (Find MV stack process)
 //!
-//! This module implements MV predictor calculation for:
-//! - NEARESTMV: Use nearest available MV from neighboring blocks
-//! - NEARMV: Use near MV from candidate list
-//! - GLOBALMV: Use global motion parameters
-//! - NEWMV: Use explicit MV from bitstream (with predictor added)
-
-use crate::tile::coding_unit::{CodingUnit, MotionVector, PredictionMode, RefFrame};
-
-/// Lightweight MV context entry
-///
-/// Stores only the fields needed for MV prediction, avoiding the need to clone
-/// the entire CodingUnit struct (~40-50 bytes).
-#[derive(Debug, Clone)]
-struct MvCuEntry {
-    /// Block position (top-left corner) in pixels
-    x: u32,
-    /// Block position (top-left corner) in pixels
-    y: u32,
-    /// Block width in pixels
-    width: u32,
-    /// Block height in pixels
-    height: u32,
-    /// Prediction mode
-    mode: PredictionMode,
-    /// Reference frames (for INTER)
-    ref_frames: [RefFrame; 2],
-    /// Motion vectors (for INTER)
-    mv: [MotionVector; 2],
+//! Builds the candidate list (`RefStackMv`) used by NEARESTMV, NEARMV and
+//! NEWMV from the spatial neighbours, the projected motion field and the
+//! global motion parameters, together with the contexts of the `new_mv`,
+//! `zero_mv`, `ref_mv` and `drl_mode` symbols. Also implements the
+//! neighbour checks that gate `use_obmc` and the local warp motion mode.
+
+use crate::tile::decoder::{
+    Mv, TileDecoder, GLOBALMV, GLOBAL_GLOBALMV, INTRA_FRAME, NEAREST_NEWMV, NEAR_NEWMV, NEWMV,
+    NEW_NEARESTMV, NEW_NEARMV, NEW_NEWMV, NONE,
+};
+use crate::tile::motion_field::INVALID_MV;
+use crate::tile::tables::{num_4x4_high, num_4x4_wide};
+use crate::uncompressed_header::GlobalMotionType;
+
+/// MAX_REF_MV_STACK_SIZE
+const MAX_REF_MV_STACK_SIZE: usize = 8;
+
+/// REF_CAT_LEVEL
+const REF_CAT_LEVEL: u32 = 640;
+
+/// MV_BORDER (in 1/8 pel)
+const MV_BORDER: i32 = 128;
+
+/// WARPEDMODEL_PREC_BITS
+const WARPEDMODEL_PREC_BITS: u32 = 16;
+
+/// Output of the find MV stack process
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MvStack {
+    /// NumMvFound
+    pub num_mv_found: usize,
+    /// NewMvCount
+    new_mv_count: usize,
+    /// RefStackMv
+    pub ref_stack_mv: [[Mv; 2]; MAX_REF_MV_STACK_SIZE],
+    /// WeightStack
+    weight_stack: [u32; MAX_REF_MV_STACK_SIZE],
+    /// GlobalMvs
+    pub global_mvs: [Mv; 2],
+    /// NewMvContext / RefMvContext / ZeroMvContext
+    pub new_mv_context: usize,
+    pub ref_mv_context: usize,
+    pub zero_mv_context: usize,
+    /// DrlCtxStack
+    pub drl_ctx_stack: [usize; MAX_REF_MV_STACK_SIZE],
+    /// FoundMatch
+    found_match: bool,
+    /// RefIdCount / RefIdMvs / RefDiffCount / RefDiffMvs (extra search)
+    ref_id_mvs: [Vec<Mv>; 2],
+    ref_diff_mvs: [Vec<Mv>; 2],
 }
 
-impl MvCuEntry {
-    /// Create from a CodingUnit reference (no clone needed)
-    fn from_cu(cu: &CodingUnit) -> Self {
-        Self {
-            x: cu.x,
-            y: cu.y,
-            width: cu.width,
-            height: cu.height,
-            mode: cu.mode,
-            ref_frames: cu.ref_frames,
-            mv: cu.mv,
+impl MvStack {
+    /// Add `mvs` with `weight`, or add the weight to an identical entry
+    fn push(&mut self, mvs: [Mv; 2], is_compound: bool, weight: u32) {
+        let n = self.num_mv_found;
+        let matches = |entry: &[Mv; 2]| entry[0] == mvs[0] && (!is_compound || entry[1] == mvs[1]);
+        if let Some(idx) = self.ref_stack_mv[..n].iter().position(matches) {
+            self.weight_stack[idx] += weight;
+        } else if n < MAX_REF_MV_STACK_SIZE {
+            self.ref_stack_mv[n] = mvs;
+            self.weight_stack[n] = weight;
+            self.num_mv_found += 1;
         }
     }
 
-    /// Check if this is an INTER block
-    fn is_inter(&self) -> bool {
-        self.mode.is_inter()
+    /// Spec sorting process (7.10.2.11), a stable bubble sort by weight
+    fn sort(&mut self, start: usize, mut end: usize) {
+        while end > start {
+            let mut new_end = start;
+            for idx in start + 1..end {
+                if self.weight_stack[idx - 1] < self.weight_stack[idx] {
+                    self.weight_stack.swap(idx - 1, idx);
+                    self.ref_stack_mv.swap(idx - 1, idx);
+                    new_end = idx;
+                }
+            }
+            end = new_end;
+        }
     }
 }
 
-/// MV predictor context
-///
-/// Tracks previously parsed coding units for MV prediction.
-pub struct MvPredictorContext {
-    /// Previously parsed coding units, indexed by position
-    /// For MVP, we use a simplified neighbor tracking
-    parsed_cus: Vec<MvCuEntry>,
-    /// Frame width in superblocks
-    _sb_cols: u32,
-    /// Frame height in superblocks
-    _sb_rows: u32,
+/// Whether a mode codes a new motion vector for any reference
+fn has_newmv(mode: u8) -> bool {
+    matches!(
+        mode,
+        NEWMV | NEW_NEWMV | NEAR_NEWMV | NEW_NEARMV | NEAREST_NEWMV | NEW_NEARESTMV
+    )
 }
 
-impl MvPredictorContext {
-    /// Create a new MV predictor context
-    pub fn new(sb_cols: u32, sb_rows: u32) -> Self {
-        Self {
-            parsed_cus: Vec::new(),
-            _sb_cols: sb_cols,
-            _sb_rows: sb_rows,
+impl<'a> TileDecoder<'a> {
+    /// Spec find MV stack process (7.10.2)
+    pub(crate) fn find_mv_stack(&mut self, is_compound: bool) {
+        let (bw4, bh4) = (self.b.bw4, self.b.bh4);
+        let mut st = std::mem::take(&mut self.stack);
+        st.num_mv_found = 0;
+        st.new_mv_count = 0;
+        st.global_mvs[0] = self.setup_global_mv(0);
+        if is_compound {
+            st.global_mvs[1] = self.setup_global_mv(1);
+        }
+
+        st.found_match = false;
+        self.scan_row(&mut st, -1, is_compound);
+        let mut found_above_match = st.found_match;
+        st.found_match = false;
+        self.scan_col(&mut st, -1, is_compound);
+        let mut found_left_match = st.found_match;
+        st.found_match = false;
+        if bw4.max(bh4) <= 16 {
+            self.scan_point(&mut st, -1, bw4, is_compound);
+        }
+        found_above_match |= st.found_match;
+        let close_matches = found_above_match as usize + found_left_match as usize;
+        let num_nearest = st.num_mv_found;
+        let num_new = st.new_mv_count;
+        for weight in &mut st.weight_stack[..num_nearest] {
+            *weight += REF_CAT_LEVEL;
+        }
+
+        st.zero_mv_context = 0;
+        if self.fh.use_ref_frame_mvs {
+            self.temporal_scan(&mut st, is_compound);
+        }
+
+        self.scan_point(&mut st, -1, -1, is_compound);
+        found_above_match |= st.found_match;
+        st.found_match = false;
+        self.scan_row(&mut st, -3, is_compound);
+        found_above_match |= st.found_match;
+        st.found_match = false;
+        self.scan_col(&mut st, -3, is_compound);
+        found_left_match |= st.found_match;
+        st.found_match = false;
+        if bh4 > 1 {
+            self.scan_row(&mut st, -5, is_compound);
         }
+        found_above_match |= st.found_match;
+        st.found_match = false;
+        if bw4 > 1 {
+            self.scan_col(&mut st, -5, is_compound);
+        }
+        found_left_match |= st.found_match;
+        let total_matches = found_above_match as usize + found_left_match as usize;
+
+        let found = st.num_mv_found;
+        st.sort(0, num_nearest);
+        st.sort(num_nearest, found);
+        if st.num_mv_found < 2 {
+            self.extra_search(&mut st, is_compound);
+        }
+        self.context_and_clamping(&mut st, is_compound);
+
+        (st.new_mv_context, st.ref_mv_context) = match close_matches {
+            0 => (total_matches.min(1), total_matches),
+            1 => (3 - num_new.min(1), 2 + total_matches),
+            _ => (5 - num_new.min(1), 5),
+        };
+        self.stack = st;
     }
 
-    /// Add a parsed coding unit to the context
-    ///
-    /// This is now zero-copy - we extract only the fields needed for MV prediction.
-    pub fn add_cu(&mut self, cu: &CodingUnit) {
-        self.parsed_cus.push(MvCuEntry::from_cu(cu));
+    /// Spec setup global mv process (7.10.2.1)
+    fn setup_global_mv(&self, ref_list: usize) -> Mv {
+        let ref_frame = self.b.ref_frame[ref_list];
+        let mut mv = [0; 2];
+        if ref_frame > INTRA_FRAME {
+            let gm = &self.fh.gm_params[ref_frame as usize];
+            match self.fh.gm_type[ref_frame as usize] {
+                GlobalMotionType::Identity => {}
+                GlobalMotionType::Translation => {
+                    // The specification takes the row from gm_params[0]
+                    mv = [
+                        gm[0] >> (WARPEDMODEL_PREC_BITS - 3),
+                        gm[1] >> (WARPEDMODEL_PREC_BITS - 3),
+                    ];
+                }
+                _ => {
+                    let x = (self.b.mi_col * 4 + (self.b.size.width() / 2) as i32 - 1) as i64;
+                    let y = (self.b.mi_row * 4 + (self.b.size.height() / 2) as i32 - 1) as i64;
+                    let gm = gm.map(i64::from);
+                    let one = 1i64 << WARPEDMODEL_PREC_BITS;
+                    let xc = (gm[2] - one) * x + gm[3] * y + gm[0];
+                    let yc = gm[4] * x + (gm[5] - one) * y + gm[1];
+                    mv = if self.fh.allow_high_precision_mv {
+                        [
+                            round2_signed(yc, WARPEDMODEL_PREC_BITS - 3),
+                            round2_signed(xc, WARPEDMODEL_PREC_BITS - 3),
+                        ]
+                    } else {
+                        [
+                            round2_signed(yc, WARPEDMODEL_PREC_BITS - 2) * 2,
+                            round2_signed(xc, WARPEDMODEL_PREC_BITS - 2) * 2,
+                        ]
+                    };
+                }
+            }
+        }
+        self.lower_mv_precision(&mut mv);
+        mv
     }
 
-    /// Find the nearest neighbor CU
-    ///
-    /// Searches in order: left, above, above-right, above-left
-    fn find_nearest_neighbor(&self, x: u32, y: u32) -> Option<&MvCuEntry> {
-        // Define search order with weights
-        // Lower weight = higher priority
-        let neighbors = [
-            // Left neighbor (same row, previous block)
-            (x.wrapping_sub(8), y, 1),
-            // Above neighbor (previous row, same column)
-            (x, y.wrapping_sub(8), 2),
-            // Above-right neighbor (previous row, next column)
-            (x + 8, y.wrapping_sub(8), 3),
-            // Above-left neighbor (previous row, previous column)
-            (x.wrapping_sub(8), y.wrapping_sub(8), 4),
-        ];
-
-        neighbors
-            .iter()
-            .filter_map(|(nx, ny, _weight)| {
-                self.parsed_cus.iter().find(|cu| {
-                    // Check if this CU overlaps with the neighbor position
-                    cu.x < nx + 8
-                        && cu.x + cu.width > *nx
-                        && cu.y < ny + 8
-                        && cu.y + cu.height > *ny
-                        && cu.is_inter()
-                })
-            })
-            .next()
+    /// Spec lower precision process (7.10.2.10)
+    fn lower_mv_precision(&self, mv: &mut Mv) {
+        if self.fh.allow_high_precision_mv {
+            return;
+        }
+        for comp in mv.iter_mut() {
+            if self.fh.force_integer_mv {
+                let a_int = (comp.abs() + 3) >> 3;
+                *comp = if *comp > 0 { a_int << 3 } else { -(a_int << 3) };
+            } else if *comp & 1 != 0 {
+                *comp += if *comp > 0 { -1 } else { 1 };
+            }
+        }
     }
 
-    /// Get MV candidate list
-    ///
-    /// Returns up to 2 MV candidates from neighboring blocks
-    fn get_mv_candidates(&self, x: u32, y: u32, ref_frame: RefFrame) -> Vec<MotionVector> {
-        let mut candidates = Vec::new();
-
-        // Find nearest neighbor with same reference frame
-        if let Some(neighbor) = self.find_nearest_neighbor(x, y) {
-            if neighbor.ref_frames[0] == ref_frame {
-                candidates.push(neighbor.mv[0]);
+    /// Spec scan row process (7.10.2.2)
+    fn scan_row(&self, st: &mut MvStack, mut delta_row: i32, is_compound: bool) {
+        let bw4 = self.b.bw4;
+        let end4 = bw4.min(self.fs.mi_cols - self.b.mi_col).min(16);
+        let mut delta_col = 0;
+        let use_step16 = bw4 >= 16;
+        if delta_row.abs() > 1 {
+            delta_row += self.b.mi_row & 1;
+            delta_col = 1 - (self.b.mi_col & 1);
+        }
+        let mut i = 0;
+        while i < end4 {
+            let mv_row = self.b.mi_row + delta_row;
+            let mv_col = self.b.mi_col + delta_col + i;
+            if !self.is_inside(mv_row, mv_col) {
+                break;
+            }
+            let mut len = bw4.min(num_4x4_wide(self.mi(mv_row, mv_col).size) as i32);
+            if delta_row.abs() > 1 {
+                len = len.max(2);
             }
+            if use_step16 {
+                len = len.max(4);
+            }
+            self.add_ref_mv_candidate(st, mv_row, mv_col, is_compound, len as u32 * 2);
+            i += len;
         }
+    }
 
-        // For MVP, if we don't have enough candidates, use zero MV
-        while candidates.len() < 2 {
-            candidates.push(MotionVector::zero());
+    /// Spec scan col process (7.10.2.3)
+    fn scan_col(&self, st: &mut MvStack, mut delta_col: i32, is_compound: bool) {
+        let bh4 = self.b.bh4;
+        let end4 = bh4.min(self.fs.mi_rows - self.b.mi_row).min(16);
+        let mut delta_row = 0;
+        let use_step16 = bh4 >= 16;
+        if delta_col.abs() > 1 {
+            delta_row = 1 - (self.b.mi_row & 1);
+            delta_col += self.b.mi_col & 1;
         }
+        let mut i = 0;
+        while i < end4 {
+            let mv_row = self.b.mi_row + delta_row + i;
+            let mv_col = self.b.mi_col + delta_col;
+            if !self.is_inside(mv_row, mv_col) {
+                break;
+            }
+            let mut len = bh4.min(num_4x4_high(self.mi(mv_row, mv_col).size) as i32);
+            if delta_col.abs() > 1 {
+                len = len.max(2);
+            }
+            if use_step16 {
+                len = len.max(4);
+            }
+            self.add_ref_mv_candidate(st, mv_row, mv_col, is_compound, len as u32 * 2);
+            i += len;
+        }
+    }
 
-        candidates
+    /// Spec scan point process (7.10.2.4)
+    fn scan_point(&self, st: &mut MvStack, delta_row: i32, delta_col: i32, is_compound: bool) {
+        let mv_row = self.b.mi_row + delta_row;
+        let mv_col = self.b.mi_col + delta_col;
+        if self.is_inside(mv_row, mv_col) && self.mi(mv_row, mv_col).written {
+            self.add_ref_mv_candidate(st, mv_row, mv_col, is_compound, 4);
+        }
     }
 
-    /// Calculate NEARESTMV predictor
-    ///
-    /// Uses the MV from the nearest neighboring block
-    pub fn predict_nearest_mv(&self, x: u32, y: u32, ref_frame: RefFrame) -> MotionVector {
-        if let Some(neighbor) = self.find_nearest_neighbor(x, y) {
-            // If neighbor has same reference frame, use its MV
-            if neighbor.ref_frames[0] == ref_frame {
-                return neighbor.mv[0];
+    /// Spec temporal scan process (7.10.2.5)
+    fn temporal_scan(&self, st: &mut MvStack, is_compound: bool) {
+        let (bw4, bh4) = (self.b.bw4, self.b.bh4);
+        let step_w4 = if bw4 >= 16 { 4 } else { 2 };
+        let step_h4 = if bh4 >= 16 { 4 } else { 2 };
+        for delta_row in (0..bh4.min(16)).step_by(step_h4) {
+            for delta_col in (0..bw4.min(16)).step_by(step_w4) {
+                self.add_tpl_ref_mv(st, delta_row, delta_col, is_compound);
             }
-            // Otherwise, use MV anyway (simplified)
-            if neighbor.is_inter() {
-                return neighbor.mv[0];
+        }
+        let allow_extension = (2..16).contains(&bh4) && (2..16).contains(&bw4);
+        if allow_extension {
+            for (delta_row, delta_col) in [(bh4, -2), (bh4, bw4), (bh4 - 2, bw4)] {
+                let row = (self.b.mi_row & 15) + delta_row;
+                let col = (self.b.mi_col & 15) + delta_col;
+                if (0..16).contains(&row) && (0..16).contains(&col) {
+                    self.add_tpl_ref_mv(st, delta_row, delta_col, is_compound);
+                }
             }
         }
-        MotionVector::zero()
     }
 
-    /// Calculate NEARMV predictor
-    ///
-    /// Uses the second candidate from the MV candidate list
-    pub fn predict_near_mv(&self, x: u32, y: u32, ref_frame: RefFrame) -> MotionVector {
-        let candidates = self.get_mv_candidates(x, y, ref_frame);
-        if candidates.len() >= 2 {
-            candidates[1]
-        } else {
-            MotionVector::zero()
+    /// Spec temporal sample process (7.10.2.6)
+    fn add_tpl_ref_mv(&self, st: &mut MvStack, delta_row: i32, delta_col: i32, is_compound: bool) {
+        let mv_row = (self.b.mi_row + delta_row) | 1;
+        let mv_col = (self.b.mi_col + delta_col) | 1;
+        if !self.is_inside(mv_row, mv_col) {
+            return;
+        }
+        let at_origin = delta_row == 0 && delta_col == 0;
+        if at_origin {
+            st.zero_mv_context = 1;
         }
+        let Some(field) = self.fs.motion_field.as_ref() else {
+            return;
+        };
+        let lists = 1 + is_compound as usize;
+        let mut cand = [[0; 2]; 2];
+        for (list, mv) in cand.iter_mut().enumerate().take(lists) {
+            *mv = field.get(self.b.ref_frame[list], mv_row >> 1, mv_col >> 1);
+            if mv[0] == INVALID_MV {
+                return;
+            }
+            self.lower_mv_precision(mv);
+        }
+        if at_origin {
+            let far = (0..lists).any(|list| {
+                (cand[list][0] - st.global_mvs[list][0]).abs() >= 16
+                    || (cand[list][1] - st.global_mvs[list][1]).abs() >= 16
+            });
+            st.zero_mv_context = far as usize;
+        }
+        st.push(cand, is_compound, 2);
     }
 
-    /// Calculate GLOBALMV predictor
-    ///
-    /// For MVP, we use zero as global motion predictor
-    /// Full implementation would parse global motion parameters from frame header
-    pub fn predict_global_mv(&self) -> MotionVector {
-        // TODO: Parse actual global motion parameters from frame header
-        // Global motion is specified per reference frame (LAST, GOLDEN, ALTREF)
-        // For MVP, we use zero
-        MotionVector::zero()
+    /// Spec add reference motion vector process (7.10.2.7)
+    fn add_ref_mv_candidate(
+        &self,
+        st: &mut MvStack,
+        mv_row: i32,
+        mv_col: i32,
+        is_compound: bool,
+        weight: u32,
+    ) {
+        let cand = *self.mi(mv_row, mv_col);
+        if !cand.is_inter {
+            return;
+        }
+        let ref_frame = self.b.ref_frame;
+        let large = cand.size.width().min(cand.size.height()) >= 8;
+        let is_global = |r: i8| {
+            (cand.y_mode == GLOBALMV || cand.y_mode == GLOBAL_GLOBALMV)
+                && self.fh.gm_type[r.max(0) as usize] > GlobalMotionType::Translation
+                && large
+        };
+        if !is_compound {
+            // Spec search stack process (7.10.2.8)
+            for cand_list in 0..2 {
+                if cand.ref_frame[cand_list] != ref_frame[0] {
+                    continue;
+                }
+                let mut mv = if is_global(ref_frame[0]) {
+                    st.global_mvs[0]
+                } else {
+                    cand.mv[cand_list]
+                };
+                self.lower_mv_precision(&mut mv);
+                if has_newmv(cand.y_mode) {
+                    st.new_mv_count += 1;
+                }
+                st.found_match = true;
+                st.push([mv, [0; 2]], false, weight);
+            }
+        } else if cand.ref_frame == ref_frame {
+            // Spec compound search stack process (7.10.2.9)
+            let mut mvs = cand.mv;
+            for (list, mv) in mvs.iter_mut().enumerate() {
+                if is_global(ref_frame[list]) {
+                    *mv = st.global_mvs[list];
+                }
+                self.lower_mv_precision(mv);
+            }
+            st.found_match = true;
+            st.push(mvs, true, weight);
+            if has_newmv(cand.y_mode) {
+                st.new_mv_count += 1;
+            }
+        }
     }
 
-    /// Get MV predictor for the given mode
-    ///
-    /// Returns the appropriate MV predictor based on prediction mode
-    pub fn get_mv_predictor(
+    /// Spec extra search process (7.10.2.12)
+    fn extra_search(&self, st: &mut MvStack, is_compound: bool) {
+        for list in 0..2 {
+            st.ref_id_mvs[list].clear();
+            st.ref_diff_mvs[list].clear();
+        }
+        let w4 = self.b.bw4.min(16).min(self.fs.mi_cols - self.b.mi_col);
+        let h4 = self.b.bh4.min(16).min(self.fs.mi_rows - self.b.mi_row);
+        let num4x4 = w4.min(h4);
+        for pass in 0..2 {
+            let mut idx = 0;
+            while idx < num4x4 && st.num_mv_found < 2 {
+                let (mv_row, mv_col) = if pass == 0 {
+                    (self.b.mi_row - 1, self.b.mi_col + idx)
+                } else {
+                    (self.b.mi_row + idx, self.b.mi_col - 1)
+                };
+                if !self.is_inside(mv_row, mv_col) {
+                    break;
+                }
+                self.add_extra_mv_candidate(st, mv_row, mv_col, is_compound);
+                let size = self.mi(mv_row, mv_col).size;
+                idx += if pass == 0 {
+                    num_4x4_wide(size)
+                } else {
+                    num_4x4_high(size)
+                } as i32;
+            }
+        }
+
+        if is_compound {
+            let mut combined = [[[0; 2]; 2]; 2];
+            for (list, &global_mv) in st.global_mvs.iter().enumerate() {
+                let candidates = st.ref_id_mvs[list]
+                    .iter()
+                    .chain(&st.ref_diff_mvs[list])
+                    .copied()
+                    .chain(std::iter::repeat(global_mv));
+                for (comp, mv) in candidates.take(2).enumerate() {
+                    combined[comp][list] = mv;
+                }
+            }
+            let n = st.num_mv_found;
+            if n == 1 {
+                st.ref_stack_mv[n] = if combined[0] == st.ref_stack_mv[0] {
+                    combined[1]
+                } else {
+                    combined[0]
+                };
+                st.weight_stack[n] = 2;
+                st.num_mv_found += 1;
+            } else {
+                for mvs in combined {
+                    let n = st.num_mv_found;
+                    st.ref_stack_mv[n] = mvs;
+                    st.weight_stack[n] = 2;
+                    st.num_mv_found += 1;
+                }
+            }
+        } else {
+            // Not counted in NumMvFound
+            for idx in st.num_mv_found..2 {
+                st.ref_stack_mv[idx][0] = st.global_mvs[0];
+            }
+        }
+    }
+
+    /// Spec add extra MV candidate process (7.10.2.13)
+    fn add_extra_mv_candidate(
         &self,
-        mode: PredictionMode,
-        x: u32,
-        y: u32,
-        ref_frame: RefFrame,
-    ) -> MotionVector {
-        match mode {
-            PredictionMode::NearestMv => self.predict_nearest_mv(x, y, ref_frame),
-            PredictionMode::NearMv => self.predict_near_mv(x, y, ref_frame),
-            PredictionMode::GlobalMv => self.predict_global_mv(),
-            PredictionMode::NewMv => {
-                // For NEWMV, we still need a predictor to add to the explicit MV
-                // The predictor is typically the nearest MV
-                self.predict_nearest_mv(x, y, ref_frame)
+        st: &mut MvStack,
+        mv_row: i32,
+        mv_col: i32,
+        is_compound: bool,
+    ) {
+        let cand = *self.mi(mv_row, mv_col);
+        let sign_bias = |r: i8| self.fh.ref_frame_sign_bias[r.max(0) as usize];
+        for cand_list in 0..2 {
+            let cand_ref = cand.ref_frame[cand_list];
+            if cand_ref <= INTRA_FRAME {
+                continue;
+            }
+            if is_compound {
+                for list in 0..2 {
+                    let mut mv = cand.mv[cand_list];
+                    if cand_ref == self.b.ref_frame[list] && st.ref_id_mvs[list].len() < 2 {
+                        st.ref_id_mvs[list].push(mv);
+                    } else if st.ref_diff_mvs[list].len() < 2 {
+                        if sign_bias(cand_ref) != sign_bias(self.b.ref_frame[list]) {
+                            mv = [-mv[0], -mv[1]];
+                        }
+                        st.ref_diff_mvs[list].push(mv);
+                    }
+                }
+            } else {
+                let mut mv = cand.mv[cand_list];
+                if sign_bias(cand_ref) != sign_bias(self.b.ref_frame[0]) {
+                    mv = [-mv[0], -mv[1]];
+                }
+                let n = st.num_mv_found;
+                if !st.ref_stack_mv[..n].iter().any(|entry| entry[0] == mv) {
+                    st.ref_stack_mv[n][0] = mv;
+                    st.weight_stack[n] = 2;
+                    st.num_mv_found += 1;
+                }
             }
-            _ => MotionVector::zero(),
         }
     }
 
-    /// Check if a position has been parsed
-    pub fn is_position_parsed(&self, x: u32, y: u32) -> bool {
-        self.parsed_cus.iter().any(|cu| cu.x == x && cu.y == y)
+    /// Spec context and clamping process (7.10.2.14)
+    fn context_and_clamping(&self, st: &mut MvStack, is_compound: bool) {
+        let n = st.num_mv_found;
+        for idx in 0..n {
+            st.drl_ctx_stack[idx] = if idx + 1 < n {
+                let (w0, w1) = (st.weight_stack[idx], st.weight_stack[idx + 1]);
+                match (w0 >= REF_CAT_LEVEL, w1 >= REF_CAT_LEVEL) {
+                    (true, true) => 0,
+                    (true, false) => 1,
+                    _ => 2,
+                }
+            } else {
+                0
+            };
+        }
+        let (b, mi_rows, mi_cols) = (&self.b, self.fs.mi_rows, self.fs.mi_cols);
+        let row_border = MV_BORDER + b.bh4 * 4 * 8;
+        let col_border = MV_BORDER + b.bw4 * 4 * 8;
+        let to_top = -(b.mi_row * 4 * 8);
+        let to_bottom = (mi_rows - b.bh4 - b.mi_row) * 4 * 8;
+        let to_left = -(b.mi_col * 4 * 8);
+        let to_right = (mi_cols - b.bw4 - b.mi_col) * 4 * 8;
+        for entry in &mut st.ref_stack_mv[..n] {
+            for mv in entry.iter_mut().take(1 + is_compound as usize) {
+                mv[0] = mv[0].clamp(to_top - row_border, to_bottom + row_border);
+                mv[1] = mv[1].clamp(to_left - col_border, to_right + col_border);
+            }
+        }
+    }
+
+    /// Spec `has_overlappable_candidates()`
+    pub(crate) fn has_overlappable_candidates(&self) -> bool {
+        let b = &self.b;
+        if b.avail_u {
+            let end = self.fs.mi_cols.min(b.mi_col + b.bw4);
+            for x4 in (b.mi_col..end).step_by(2) {
+                let x5 = (x4 | 1).min(self.fs.mi_cols - 1);
+                if self.mi(b.mi_row - 1, x5).ref_frame[0] > INTRA_FRAME {
+                    return true;
+                }
+            }
+        }
+        if b.avail_l {
+            let end = self.fs.mi_rows.min(b.mi_row + b.bh4);
+            for y4 in (b.mi_row..end).step_by(2) {
+                let y5 = (y4 | 1).min(self.fs.mi_rows - 1);
+                if self.mi(y5, b.mi_col - 1).ref_frame[0] > INTRA_FRAME {
+                    return true;
+                }
+            }
+        }
+        false
     }
-}
 
-/// Apply MV predictor to explicit MV
-///
-/// For NEWMV mode, the predictor is added to the explicitly coded MV.
-///
-/// # Arguments
-///
-/// * `mv` - Explicitly coded motion vector from bitstream (quarter-pel units)
-/// * `predictor` - Predicted motion vector (quarter-pel units)
-///
-/// # Returns
-///
-/// The final motion vector (explicit + predictor, both in quarter-pel units)
-pub fn apply_mv_predictor(mv: MotionVector, predictor: MotionVector) -> MotionVector {
-    mv.add(predictor)
+    /// Whether the find warp samples process (7.10.4) finds any sample
+    ///
+    /// `NumSamples` is forced to 1 when candidates were scanned but all were
+    /// rejected, so only the scan itself matters to the syntax.
+    pub(crate) fn has_warp_samples(&self) -> bool {
+        let b = &self.b;
+        let (w4, h4) = (b.bw4, b.bh4);
+        let mut do_top_left = true;
+        let mut do_top_right = true;
+        let mut positions = Vec::new();
+        if b.avail_u {
+            let src_w = num_4x4_wide(self.mi(b.mi_row - 1, b.mi_col).size) as i32;
+            if w4 <= src_w {
+                let col_offset = -(b.mi_col & (src_w - 1));
+                if col_offset < 0 {
+                    do_top_left = false;
+                }
+                if col_offset + src_w > w4 {
+                    do_top_right = false;
+                }
+                positions.push((-1, 0));
+            } else {
+                let mut i = 0;
+                while i < w4.min(self.fs.mi_cols - b.mi_col) {
+                    let src_w = num_4x4_wide(self.mi(b.mi_row - 1, b.mi_col + i).size) as i32;
+                    positions.push((-1, i));
+                    i += src_w;
+                }
+            }
+        }
+        if b.avail_l {
+            let src_h = num_4x4_high(self.mi(b.mi_row, b.mi_col - 1).size) as i32;
+            if h4 <= src_h {
+                if b.mi_row & (src_h - 1) != 0 {
+                    do_top_left = false;
+                }
+                positions.push((0, -1));
+            } else {
+                let mut i = 0;
+                while i < h4.min(self.fs.mi_rows - b.mi_row) {
+                    let src_h = num_4x4_high(self.mi(b.mi_row + i, b.mi_col - 1).size) as i32;
+                    positions.push((i, -1));
+                    i += src_h;
+                }
+            }
+        }
+        if do_top_left {
+            positions.push((-1, -1));
+        }
+        if do_top_right && w4.max(h4) <= 16 {
+            positions.push((-1, w4));
+        }
+        positions.into_iter().any(|(dr, dc)| {
+            let (row, col) = (b.mi_row + dr, b.mi_col + dc);
+            if !self.is_inside(row, col) {
+                return false;
+            }
+            let cand = self.mi(row, col);
+            cand.written && cand.ref_frame[0] == b.ref_frame[0] && cand.ref_frame[1] == NONE
+        })
+    }
 }
 
-/// Parse MV with predictor
-///
-/// Reads the explicit MV from bitstream and adds the predictor
-pub fn parse_mv_with_predictor(
-    explicit_mv: MotionVector,
-    mode: PredictionMode,
-    x: u32,
-    y: u32,
-    ref_frame: RefFrame,
-    ctx: &MvPredictorContext,
-) -> MotionVector {
-    let predictor = ctx.get_mv_predictor(mode, x, y, ref_frame);
-    apply_mv_predictor(explicit_mv, predictor)
+/// Round2Signed
+fn round2_signed(x: i64, n: u32) -> i32 {
+    let round = (1i64 << n) >> 1;
+    if x >= 0 {
+        ((x + round) >> n) as i32
+    } else {
+        -((-x + round) >> n) as i32
+    }
 }
 
 #[cfg(test)]
@@ -240,80 +636,37 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_mv_predictor_context_creation() {
-        // Per generate-tests skill: Test public interface only
-        let ctx = MvPredictorContext::new(10, 10);
-        assert_eq!(ctx.parsed_cus.len(), 0);
-        // Note: sb_cols and sb_rows are private fields (prefixed with _)
-        // We verify the context was created successfully by checking parsed_cus
-    }
-
-    #[test]
-    fn test_nearest_mv_no_neighbors() {
-        let ctx = MvPredictorContext::new(10, 10);
-        let mv = ctx.predict_nearest_mv(64, 64, RefFrame::Last);
-        assert_eq!(mv.x, 0);
-        assert_eq!(mv.y, 0);
-    }
-
-    #[test]
-    fn test_nearest_mv_with_left_neighbor() {
-        let mut ctx = MvPredictorContext::new(10, 10);
-
-        // Add left neighbor (using reference, no clone)
-        let mut neighbor = CodingUnit::new(0, 64, 64, 64);
-        neighbor.mode = PredictionMode::NewMv;
-        neighbor.ref_frames = [RefFrame::Last, RefFrame::Intra];
-        neighbor.mv[0] = MotionVector::new(10, -5);
-        ctx.add_cu(&neighbor); // Now takes reference instead of ownership
-
-        let mv = ctx.predict_nearest_mv(64, 64, RefFrame::Last);
-        assert_eq!(mv.x, 10);
-        assert_eq!(mv.y, -5);
+    fn test_round2_signed() {
+        assert_eq!(round2_signed(12, 3), 2);
+        assert_eq!(round2_signed(-12, 3), -2);
+        assert_eq!(round2_signed(-11, 3), -1);
+        assert_eq!(round2_signed(0, 5), 0);
     }
 
     #[test]
-    fn test_apply_mv_predictor() {
-        let explicit = MotionVector::new(5, 3);
-        let predictor = MotionVector::new(10, -5);
-        let result = apply_mv_predictor(explicit, predictor);
-        assert_eq!(result.x, 15);
-        assert_eq!(result.y, -2);
-    }
-
-    #[test]
-    fn test_apply_mv_predictor_with_quarter_pel() {
-        use crate::QuarterPel;
-
-        // Demonstrate quarter-pel precision awareness
-        let explicit_mv = MotionVector::from_quarter_pel(
-            QuarterPel::from_pel(2), // 8 quarter-pels
-            QuarterPel::from_pel(1), // 4 quarter-pels
-        );
-        let predictor_mv = MotionVector::from_quarter_pel(
-            QuarterPel::from_pel(1),   // 4 quarter-pels
-            QuarterPel::from_qpel(-4), // -4 quarter-pels
-        );
-
-        let result = apply_mv_predictor(explicit_mv, predictor_mv);
-
-        // Result: 8+4=12 qpel X, 4-4=0 qpel Y
-        assert_eq!(result.x_quarter_pel().qpel(), 12);
-        assert_eq!(result.y_quarter_pel().qpel(), 0);
-
-        // In pixels: 12/4=3 pel X, 0/4=0 pel Y
-        assert_eq!(result.x_pel(), 3);
-        assert_eq!(result.y_pel(), 0);
+    fn test_sort_is_stable_by_weight() {
+        let mut st = MvStack {
+            num_mv_found: 4,
+            ..Default::default()
+        };
+        st.weight_stack[..4].copy_from_slice(&[2, 8, 4, 8]);
+        for (i, entry) in st.ref_stack_mv[..4].iter_mut().enumerate() {
+            entry[0] = [i as i32, 0];
+        }
+        st.sort(0, 4);
+        assert_eq!(&st.weight_stack[..4], &[8, 8, 4, 2]);
+        let order: Vec<i32> = st.ref_stack_mv[..4].iter().map(|e| e[0][0]).collect();
+        assert_eq!(order, vec![1, 3, 2, 0]);
     }
 
     #[test]
-    fn test_mv_magnitude_in_pixels() {
-        let mv = MotionVector::new(16, -8);
-
-        // Quarter-pel magnitude
-        assert_eq!(mv.magnitude_qpel(), 24);
-
-        // Pixel magnitude (rounded)
-        assert_eq!(mv.magnitude_pel(), 6);
+    fn test_push_merges_identical_candidates() {
+        let mut st = MvStack::default();
+        st.push([[4, 8], [0, 0]], false, 2);
+        st.push([[4, 8], [1, 1]], false, 4);
+        assert_eq!(st.num_mv_found, 1);
+        assert_eq!(st.weight_stack[0], 6);
+        st.push([[4, 8], [1, 1]], true, 2);
+        assert_eq!(st.num_mv_found, 2);
     }
 }
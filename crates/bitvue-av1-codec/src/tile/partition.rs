@@ -36,8 +36,6 @@
 //! └──┴──┴──┴──┘
 //! ```
 
-use crate::symbol::SymbolDecoder;
-use bitvue_core::{BitvueError, Result};
 use serde::{Deserialize, Serialize};
 
 /// Partition type (AV1 Spec Section 5.11.4)
@@ -97,27 +95,35 @@ impl PartitionType {
     }
 
     /// Check if this partition type is allowed for given block size
+    ///
+    /// Partitions only apply to square blocks of 8x8 and up; the 3-way
+    /// partitions need 16x16 and the 4-way partitions are limited to
+    /// 16x16..64x64.
     pub fn is_allowed(&self, block_size: BlockSize) -> bool {
+        let w = block_size.width();
+        if w != block_size.height() || w < 8 {
+            return *self == PartitionType::None;
+        }
         match self {
-            PartitionType::None => true,
-            PartitionType::Horz | PartitionType::Vert => {
-                block_size.width() >= 8 && block_size.height() >= 8
-            }
-            PartitionType::Split => block_size.width() >= 8 && block_size.height() >= 8,
-            PartitionType::HorzA | PartitionType::HorzB => {
-                block_size.width() >= 16 && block_size.height() >= 16
-            }
-            PartitionType::VertA | PartitionType::VertB => {
-                block_size.width() >= 16 && block_size.height() >= 16
-            }
-            PartitionType::Horz4 => block_size.width() >= 16 && block_size.height() >= 32,
-            PartitionType::Vert4 => block_size.width() >= 32 && block_size.height() >= 16,
+            PartitionType::None
+            | PartitionType::Horz
+            | PartitionType::Vert
+            | PartitionType::Split => true,
+            PartitionType::HorzA
+            | PartitionType::HorzB
+            | PartitionType::VertA
+            | PartitionType::VertB => w >= 16,
+            PartitionType::Horz4 | PartitionType::Vert4 => (16..=64).contains(&w),
         }
     }
 }
 
 /// Block size enumeration
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// Variants are in the order of the specification's block size constants
+/// (`BLOCK_4X4` = 0 .. `BLOCK_64X16` = 21).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[repr(u8)]
 pub enum BlockSize {
     /// 4x4 block
     Block4x4,
@@ -151,79 +157,125 @@ pub enum BlockSize {
     Block128x64,
     /// 128x128 block
     Block128x128,
+    /// 4x16 block
+    Block4x16,
+    /// 16x4 block
+    Block16x4,
+    /// 8x32 block
+    Block8x32,
+    /// 32x8 block
+    Block32x8,
+    /// 16x64 block
+    Block16x64,
+    /// 64x16 block
+    Block64x16,
 }
 
+/// All block sizes in specification order
+const BLOCK_SIZES: [BlockSize; 22] = [
+    BlockSize::Block4x4,
+    BlockSize::Block4x8,
+    BlockSize::Block8x4,
+    BlockSize::Block8x8,
+    BlockSize::Block8x16,
+    BlockSize::Block16x8,
+    BlockSize::Block16x16,
+    BlockSize::Block16x32,
+    BlockSize::Block32x16,
+    BlockSize::Block32x32,
+    BlockSize::Block32x64,
+    BlockSize::Block64x32,
+    BlockSize::Block64x64,
+    BlockSize::Block64x128,
+    BlockSize::Block128x64,
+    BlockSize::Block128x128,
+    BlockSize::Block4x16,
+    BlockSize::Block16x4,
+    BlockSize::Block8x32,
+    BlockSize::Block32x8,
+    BlockSize::Block16x64,
+    BlockSize::Block64x16,
+];
+
+/// log2 of the block width in pixels (Block_Width = 1 << this)
+const BLOCK_WIDTH_LOG2: [u8; 22] = [
+    2, 2, 3, 3, 3, 4, 4, 4, 5, 5, 5, 6, 6, 6, 7, 7, 2, 4, 3, 5, 4, 6,
+];
+
+/// log2 of the block height in pixels (Block_Height = 1 << this)
+const BLOCK_HEIGHT_LOG2: [u8; 22] = [
+    2, 3, 2, 3, 4, 3, 4, 5, 4, 5, 6, 5, 6, 7, 6, 7, 4, 2, 5, 3, 6, 4,
+];
+
 impl BlockSize {
+    /// Parse from the specification value (0-21)
+    pub fn from_u8(value: u8) -> Option<Self> {
+        BLOCK_SIZES.get(value as usize).copied()
+    }
+
+    /// Block size with the given dimensions in pixels, if AV1 has one
+    pub fn from_dimensions(width: u32, height: u32) -> Option<Self> {
+        BLOCK_SIZES
+            .iter()
+            .copied()
+            .find(|b| b.width() == width && b.height() == height)
+    }
+
     /// Get block width in pixels
     pub fn width(&self) -> u32 {
-        match self {
-            BlockSize::Block4x4 | BlockSize::Block4x8 => 4,
-            BlockSize::Block8x4 | BlockSize::Block8x8 | BlockSize::Block8x16 => 8,
-            BlockSize::Block16x8 | BlockSize::Block16x16 | BlockSize::Block16x32 => 16,
-            BlockSize::Block32x16 | BlockSize::Block32x32 | BlockSize::Block32x64 => 32,
-            BlockSize::Block64x32 | BlockSize::Block64x64 | BlockSize::Block64x128 => 64,
-            BlockSize::Block128x64 | BlockSize::Block128x128 => 128,
-        }
+        1 << BLOCK_WIDTH_LOG2[*self as usize]
     }
 
     /// Get block height in pixels
     pub fn height(&self) -> u32 {
-        match self {
-            BlockSize::Block4x4 | BlockSize::Block8x4 => 4,
-            BlockSize::Block4x8 | BlockSize::Block8x8 | BlockSize::Block16x8 => 8,
-            BlockSize::Block8x16 | BlockSize::Block16x16 | BlockSize::Block32x16 => 16,
-            BlockSize::Block16x32 | BlockSize::Block32x32 | BlockSize::Block64x32 => 32,
-            BlockSize::Block32x64 | BlockSize::Block64x64 | BlockSize::Block128x64 => 64,
-            BlockSize::Block64x128 | BlockSize::Block128x128 => 128,
+        1 << BLOCK_HEIGHT_LOG2[*self as usize]
+    }
+
+    /// Size of the blocks a partition produces (spec `Partition_Subsize`)
+    ///
+    /// For the 3-way partitions this is the size of the unsplit half.
+    /// Returns `None` when the partition is not allowed for this size.
+    pub fn subsize(&self, partition: PartitionType) -> Option<BlockSize> {
+        if !partition.is_allowed(*self) {
+            return None;
         }
+        let w = self.width();
+        let (sub_w, sub_h) = match partition {
+            PartitionType::None => (w, w),
+            PartitionType::Horz | PartitionType::HorzA | PartitionType::HorzB => (w, w / 2),
+            PartitionType::Vert | PartitionType::VertA | PartitionType::VertB => (w / 2, w),
+            PartitionType::Split => (w / 2, w / 2),
+            PartitionType::Horz4 => (w, w / 4),
+            PartitionType::Vert4 => (w / 4, w),
+        };
+        BlockSize::from_dimensions(sub_w, sub_h)
     }
 
-    /// Get sub-block size after partition
+    /// Get sub-block sizes after partition, in decoding order
+    ///
+    /// Returns just this size when the partition is not allowed.
     pub fn sub_block_size(&self, partition: PartitionType) -> Vec<BlockSize> {
+        let (Some(sub), Some(split)) =
+            (self.subsize(partition), self.subsize(PartitionType::Split))
+        else {
+            return vec![*self];
+        };
         match partition {
-            PartitionType::None => vec![*self],
-            PartitionType::Horz => {
-                // Split horizontally
-                match self {
-                    BlockSize::Block8x8 => vec![BlockSize::Block8x4, BlockSize::Block8x4],
-                    BlockSize::Block16x16 => vec![BlockSize::Block16x8, BlockSize::Block16x8],
-                    BlockSize::Block32x32 => vec![BlockSize::Block32x16, BlockSize::Block32x16],
-                    BlockSize::Block64x64 => vec![BlockSize::Block64x32, BlockSize::Block64x32],
-                    BlockSize::Block128x128 => vec![BlockSize::Block128x64, BlockSize::Block128x64],
-                    _ => vec![*self], // Fallback
-                }
-            }
-            PartitionType::Vert => {
-                // Split vertically
-                match self {
-                    BlockSize::Block8x8 => vec![BlockSize::Block4x8, BlockSize::Block4x8],
-                    BlockSize::Block16x16 => vec![BlockSize::Block8x16, BlockSize::Block8x16],
-                    BlockSize::Block32x32 => vec![BlockSize::Block16x32, BlockSize::Block16x32],
-                    BlockSize::Block64x64 => vec![BlockSize::Block32x64, BlockSize::Block32x64],
-                    BlockSize::Block128x128 => vec![BlockSize::Block64x128, BlockSize::Block64x128],
-                    _ => vec![*self], // Fallback
-                }
-            }
-            PartitionType::Split => {
-                // Split into 4 equal blocks
-                match self {
-                    BlockSize::Block8x8 => vec![BlockSize::Block4x4; 4],
-                    BlockSize::Block16x16 => vec![BlockSize::Block8x8; 4],
-                    BlockSize::Block32x32 => vec![BlockSize::Block16x16; 4],
-                    BlockSize::Block64x64 => vec![BlockSize::Block32x32; 4],
-                    BlockSize::Block128x128 => vec![BlockSize::Block64x64; 4],
-                    _ => vec![*self], // Fallback
-                }
-            }
-            _ => {
-                // Complex partitions - TODO: implement
-                vec![*self]
-            }
+            PartitionType::None => vec![sub],
+            PartitionType::Horz | PartitionType::Vert => vec![sub; 2],
+            PartitionType::Split | PartitionType::Horz4 | PartitionType::Vert4 => vec![sub; 4],
+            PartitionType::HorzA | PartitionType::VertA => vec![split, split, sub],
+            PartitionType::HorzB | PartitionType::VertB => vec![sub, split, split],
         }
     }
 }
 
 /// Partition tree node
+///
+/// Inner nodes are the square blocks a `partition` symbol was decoded
+/// for; their children are the coded blocks (and further partitioned
+/// squares) in decoding order. Blocks lying entirely outside the frame are
+/// not coded and have no node.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PartitionNode {
     /// Block position (top-left corner) in pixels
@@ -267,201 +319,47 @@ impl PartitionNode {
     }
 }
 
-/// Calculate block size as log2 (for CDF lookup)
-///
-/// # Safety
-///
-/// This function is safe for all BlockSize variants because:
-/// - All BlockSize variants have positive dimensions (4x4 and up)
-/// - width() and height() always return values >= 4
-/// - ilog2() will never panic on these values
-///
-/// # Returns
-///
-/// Log2 of the larger dimension, clamped to [2, 7]
-fn block_size_log2(block_size: BlockSize) -> u8 {
-    // For square blocks, use width
-    // For non-square, use larger dimension
-    let size = block_size.width().max(block_size.height());
-
-    // Safety: All BlockSize variants have dimensions >= 4, so size >= 4
-    // ilog2() is safe for values >= 1
-    debug_assert!(size >= 4, "BlockSize dimensions must be >= 4");
-
-    (size.ilog2() as u8).clamp(2, 7)
-}
-
 /// Calculate child block position for a given partition
-fn child_position(
+///
+/// `child_index` follows the decoding order of [`BlockSize::sub_block_size`].
+pub fn child_position(
     parent_x: u32,
     parent_y: u32,
     child_index: usize,
     partition: PartitionType,
     parent_size: BlockSize,
 ) -> (u32, u32) {
-    let w = parent_size.width();
-    let h = parent_size.height();
-
-    match partition {
-        PartitionType::None => (parent_x, parent_y),
-        PartitionType::Horz => {
-            // Top and bottom halves
-            if child_index == 0 {
-                (parent_x, parent_y)
-            } else {
-                (parent_x, parent_y + h / 2)
-            }
-        }
-        PartitionType::Vert => {
-            // Left and right halves
-            if child_index == 0 {
-                (parent_x, parent_y)
-            } else {
-                (parent_x + w / 2, parent_y)
-            }
-        }
-        PartitionType::Split => {
-            // 4-way split: top-left, top-right, bottom-left, bottom-right
-            match child_index {
-                0 => (parent_x, parent_y),
-                1 => (parent_x + w / 2, parent_y),
-                2 => (parent_x, parent_y + h / 2),
-                3 => (parent_x + w / 2, parent_y + h / 2),
-                _ => (parent_x, parent_y),
-            }
-        }
-        _ => {
-            // Complex partitions - for MVP, just return parent position
-            (parent_x, parent_y)
-        }
-    }
-}
-
-/// Maximum partition recursion depth to prevent infinite loops
-/// AV1 maximum recursion is: 128x128 → 64x64 → 32x32 → 16x16 → 8x8 → 4x4 (6 levels)
-/// Set to 10 to provide safety margin while allowing valid deep recursion
-const MAX_PARTITION_DEPTH: u8 = 10;
-
-/// Recursively parse partition tree using symbol decoder
-fn parse_partition_recursive(
-    decoder: &mut SymbolDecoder,
-    x: u32,
-    y: u32,
-    block_size: BlockSize,
-    has_rows: bool,
-    has_cols: bool,
-    depth: u8,
-) -> Result<PartitionNode> {
-    // Prevent infinite recursion from malformed bitstreams
-    if depth >= MAX_PARTITION_DEPTH {
-        return Err(BitvueError::InvalidData(format!(
-            "Partition recursion depth exceeded maximum ({}), possible infinite loop in bitstream",
-            MAX_PARTITION_DEPTH
-        )));
-    }
-
-    // Get block size log2 for CDF lookup
-    let bsize_log2 = block_size_log2(block_size);
-
-    // Read partition symbol from bitstream
-    let partition_symbol = decoder.read_partition(bsize_log2, has_rows, has_cols)?;
-
-    // Convert symbol to partition type
-    let partition = PartitionType::from_u8(partition_symbol).ok_or_else(|| {
-        BitvueError::InvalidData(format!("Invalid partition symbol: {}", partition_symbol))
-    })?;
-
-    // Validate partition is allowed for this block size
-    if !partition.is_allowed(block_size) {
-        return Err(BitvueError::InvalidData(format!(
-            "Partition {:?} not allowed for block size {:?}",
-            partition, block_size
-        )));
-    }
-
-    // Validate sub_block_size doesn't return same size (prevents infinite recursion)
-    let sub_sizes = block_size.sub_block_size(partition);
-    if partition != PartitionType::None {
-        // Check that at least one sub-block is smaller than parent
-        let has_smaller = sub_sizes
-            .iter()
-            .any(|&s| s.width() < block_size.width() || s.height() < block_size.height());
-        if !has_smaller {
-            return Err(BitvueError::InvalidData(format!(
-                "Partition {:?} on block size {:?} produces sub-blocks of same size - not supported",
-                partition, block_size
-            )));
-        }
-    }
-
-    // Create node
-    let mut node = PartitionNode::new(x, y, block_size, partition);
-
-    // If not NONE, recursively parse children
-    if partition != PartitionType::None {
-        let sub_sizes = block_size.sub_block_size(partition);
-
-        for (i, sub_size) in sub_sizes.iter().enumerate() {
-            let (child_x, child_y) = child_position(x, y, i, partition, block_size);
-
-            // Check if child blocks are at frame boundaries
-            // For MVP, we assume all blocks are within frame
-            let child_has_rows = has_rows;
-            let child_has_cols = has_cols;
-
-            // Recursively parse child with incremented depth
-            let child = parse_partition_recursive(
-                decoder,
-                child_x,
-                child_y,
-                *sub_size,
-                child_has_rows,
-                child_has_cols,
-                depth + 1,
-            )?;
-
-            node.children.push(child);
-        }
-    }
-
-    Ok(node)
-}
-
-/// Parse partition tree from tile data
-///
-/// Uses symbol decoder to read partition symbols from the bitstream
-/// and recursively builds the partition tree.
-///
-/// # Arguments
-///
-/// * `x` - Block X position in pixels
-/// * `y` - Block Y position in pixels
-/// * `block_size` - Starting block size (typically superblock size)
-/// * `tile_data` - Tile bitstream data
-///
-/// # Returns
-///
-/// Root partition node with recursively parsed children.
-pub fn parse_partition_tree(
-    x: u32,
-    y: u32,
-    block_size: BlockSize,
-    tile_data: &[u8],
-) -> Result<PartitionNode> {
-    // Create symbol decoder for tile data
-    let mut decoder = SymbolDecoder::new(tile_data)?;
-
-    // Recursively parse partition tree starting at depth 0
-    // For MVP, assume all blocks are within frame boundaries
-    parse_partition_recursive(&mut decoder, x, y, block_size, true, true, 0)
+    let half = parent_size.width() / 2;
+    let quarter = parent_size.width() / 4;
+    let i = child_index as u32;
+
+    let (dx, dy) = match partition {
+        PartitionType::None => (0, 0),
+        PartitionType::Horz => (0, i * half),
+        PartitionType::Vert => (i * half, 0),
+        PartitionType::Split => ((i & 1) * half, (i >> 1) * half),
+        // Two quarters on top, then the bottom half
+        PartitionType::HorzA => [(0, 0), (half, 0), (0, half)][child_index.min(2)],
+        // The top half, then two quarters at the bottom
+        PartitionType::HorzB => [(0, 0), (0, half), (half, half)][child_index.min(2)],
+        // Two quarters on the left, then the right half
+        PartitionType::VertA => [(0, 0), (0, half), (half, 0)][child_index.min(2)],
+        // The left half, then two quarters on the right
+        PartitionType::VertB => [(0, 0), (half, 0), (half, half)][child_index.min(2)],
+        PartitionType::Horz4 => (0, i * quarter),
+        PartitionType::Vert4 => (i * quarter, 0),
+    };
+    (parent_x + dx, parent_y + dy)
 }
 
 /// Flatten partition tree to list of leaf blocks
 ///
 /// Converts hierarchical partition tree to a flat list of blocks
-/// for visualization in PartitionGrid.
+/// for visualization in PartitionGrid. Each leaf carries the partition
+/// type of its parent, i.e. the partition that produced the block.
 fn flatten_partition_tree(
     node: &PartitionNode,
+    parent_partition: PartitionType,
     depth: u8,
     blocks: &mut Vec<(u32, u32, u32, u32, u8, u8)>,
 ) {
@@ -472,13 +370,13 @@ fn flatten_partition_tree(
             node.y,
             node.size.width(),
             node.size.height(),
-            node.partition as u8,
+            parent_partition as u8,
             depth,
         ));
     } else {
         // Non-leaf: recursively process children
         for child in &node.children {
-            flatten_partition_tree(child, depth + 1, blocks);
+            flatten_partition_tree(child, node.partition, depth + 1, blocks);
         }
     }
 }
@@ -494,10 +392,15 @@ pub fn partition_tree_to_grid(
     sb_size: u32,
 ) -> bitvue_core::PartitionGrid {
     let mut grid = bitvue_core::PartitionGrid::new(coded_width, coded_height, sb_size);
+    add_partition_tree_to_grid(&mut grid, root);
+    grid
+}
 
+/// Add the leaf blocks of a partition tree to an existing PartitionGrid
+pub fn add_partition_tree_to_grid(grid: &mut bitvue_core::PartitionGrid, root: &PartitionNode) {
     // Flatten tree to list of blocks
     let mut blocks = Vec::new();
-    flatten_partition_tree(root, 0, &mut blocks);
+    flatten_partition_tree(root, PartitionType::None, 0, &mut blocks);
 
     // Convert to PartitionGrid blocks
     for (x, y, width, height, partition, depth) in blocks {
@@ -512,8 +415,6 @@ pub fn partition_tree_to_grid(
         );
         grid.add_block(block);
     }
-
-    grid
 }
 
 #[cfg(test)]
@@ -537,6 +438,10 @@ mod tests {
         assert_eq!(BlockSize::Block16x32.height(), 32);
         assert_eq!(BlockSize::Block128x128.width(), 128);
         assert_eq!(BlockSize::Block128x128.height(), 128);
+        assert_eq!(BlockSize::Block64x16.width(), 64);
+        assert_eq!(BlockSize::Block64x16.height(), 16);
+        assert_eq!(BlockSize::from_u8(16), Some(BlockSize::Block4x16));
+        assert_eq!(BlockSize::from_u8(22), None);
     }
 
     #[test]
@@ -550,6 +455,46 @@ mod tests {
         assert_eq!(subs[0], BlockSize::Block32x16);
     }
 
+    #[test]
+    fn test_complex_partition_subsizes() {
+        let b = BlockSize::Block32x32;
+        assert_eq!(
+            b.sub_block_size(PartitionType::HorzA),
+            vec![
+                BlockSize::Block16x16,
+                BlockSize::Block16x16,
+                BlockSize::Block32x16
+            ]
+        );
+        assert_eq!(
+            b.sub_block_size(PartitionType::VertB),
+            vec![
+                BlockSize::Block16x32,
+                BlockSize::Block16x16,
+                BlockSize::Block16x16
+            ]
+        );
+        assert_eq!(b.subsize(PartitionType::Horz4), Some(BlockSize::Block32x8));
+        assert_eq!(b.subsize(PartitionType::Vert4), Some(BlockSize::Block8x32));
+        assert_eq!(BlockSize::Block128x128.subsize(PartitionType::Horz4), None);
+        assert_eq!(BlockSize::Block8x8.subsize(PartitionType::HorzA), None);
+        assert_eq!(
+            BlockSize::Block8x8.subsize(PartitionType::Split),
+            Some(BlockSize::Block4x4)
+        );
+    }
+
+    #[test]
+    fn test_child_positions() {
+        let b = BlockSize::Block32x32;
+        let positions: Vec<_> = (0..3)
+            .map(|i| child_position(64, 0, i, PartitionType::HorzB, b))
+            .collect();
+        assert_eq!(positions, vec![(64, 0), (64, 16), (80, 16)]);
+        assert_eq!(child_position(0, 0, 3, PartitionType::Vert4, b), (24, 0));
+        assert_eq!(child_position(0, 0, 3, PartitionType::Split, b), (16, 16));
+    }
+
     #[test]
     fn test_partition_node_leaf_blocks() {
         let node = PartitionNode::new(0, 0, BlockSize::Block16x16, PartitionType::None);
@@ -591,4 +536,27 @@ mod tests {
         let leaves = parent.leaf_blocks();
         assert_eq!(leaves.len(), 4);
     }
+
+    #[test]
+    fn test_grid_leaves_carry_parent_partition() {
+        let mut parent = PartitionNode::new(0, 0, BlockSize::Block16x16, PartitionType::Horz);
+        parent.children.push(PartitionNode::new(
+            0,
+            0,
+            BlockSize::Block16x8,
+            PartitionType::None,
+        ));
+        parent.children.push(PartitionNode::new(
+            0,
+            8,
+            BlockSize::Block16x8,
+            PartitionType::None,
+        ));
+        let grid = partition_tree_to_grid(&parent, 16, 16, 64);
+        assert_eq!(grid.blocks.len(), 2);
+        assert_eq!(
+            grid.blocks[1].partition,
+            bitvue_core::partition_grid::PartitionType::Horz
+        );
+    }
 }
@@ -0,0 +1,771 @@
+//! Residual Syntax
+//!
+//! Per AV1 Specification Sections 5.11.15 - 5.11.17 (transform size),
+//! 5.11.34 - 5.11.39 (residual and coefficients) and 5.11.47 (transform
+//! type)
+//!
+//! Coefficient levels are decoded into `Quant` so that the contexts of the
+//! following symbols can be derived; they are not dequantized.
+
+use crate::tile::decoder::{TileDecoder, DC_PRED, V_PRED};
+use crate::tile::tables::{
+    adjusted_tx_size, coeff_base_ctx_offset, is_tx_type_in_set, max_tx_size_rect, scan,
+    split_tx_size, subsampled_size, tx_height_log2, tx_size_ctx, tx_size_sqr, tx_size_sqr_up,
+    tx_width_log2, TxClass, DCT_DCT, MAX_TX_DEPTH, MODE_TO_TXFM, TX_TYPE_INTER_INV_SET1,
+    TX_TYPE_INTER_INV_SET2, TX_TYPE_INTER_INV_SET3, TX_TYPE_INTRA_INV_SET1, TX_TYPE_INTRA_INV_SET2,
+};
+use crate::tile::{BlockSize, TxSize};
+use crate::uncompressed_header::TxMode;
+use bitvue_core::Result;
+
+/// MAX_VARTX_DEPTH
+const MAX_VARTX_DEPTH: u32 = 2;
+
+/// NUM_BASE_LEVELS / COEFF_BASE_RANGE / BR_CDF_SIZE
+const NUM_BASE_LEVELS: i32 = 2;
+const COEFF_BASE_RANGE: i32 = 12;
+const BR_CDF_SIZE: usize = 4;
+
+/// Filter_Intra_Mode_To_Intra_Dir (H_PRED is 2, D157_PRED is 6)
+const FILTER_INTRA_MODE_TO_INTRA_DIR: [u8; 5] = [DC_PRED, V_PRED, 2, 6, DC_PRED];
+
+/// Sig_Ref_Diff_Offset per transform class, as `[row, col]`
+const SIG_REF_DIFF_OFFSET: [[[usize; 2]; 5]; 3] = [
+    [[0, 1], [1, 0], [1, 1], [0, 2], [2, 0]],
+    [[0, 1], [1, 0], [0, 2], [0, 3], [0, 4]],
+    [[0, 1], [1, 0], [2, 0], [3, 0], [4, 0]],
+];
+
+/// Mag_Ref_Offset_With_Tx_Class, as `[row, col]`
+const MAG_REF_OFFSET: [[[usize; 2]; 3]; 3] = [
+    [[0, 1], [1, 0], [1, 1]],
+    [[0, 1], [1, 0], [0, 2]],
+    [[0, 1], [1, 0], [2, 0]],
+];
+
+/// Coeff_Base_Pos_Ctx_Offset
+const COEFF_BASE_POS_CTX_OFFSET: [usize; 3] = [26, 31, 36];
+
+/// Transform sets (`TX_SET_*`)
+const TX_SET_DCTONLY: usize = 0;
+const TX_SET_INTRA_1: usize = 1;
+const TX_SET_INTRA_2: usize = 2;
+const TX_SET_INTER_1: usize = 1;
+const TX_SET_INTER_2: usize = 2;
+const TX_SET_INTER_3: usize = 3;
+
+impl TxClass {
+    fn index(self) -> usize {
+        match self {
+            TxClass::TwoD => 0,
+            TxClass::Horiz => 1,
+            TxClass::Vert => 2,
+        }
+    }
+}
+
+impl<'a> TileDecoder<'a> {
+    /// Spec `read_block_tx_size()`
+    ///
+    /// Returns the largest luma transform of the block.
+    pub(crate) fn read_block_tx_size(&mut self) -> TxSize {
+        let b = &self.b;
+        let (bw4, bh4) = (b.bw4, b.bh4);
+        if self.fh.tx_mode == TxMode::Select
+            && b.size > BlockSize::Block4x4
+            && b.is_inter
+            && !b.skip
+            && !b.lossless
+        {
+            let max_tx = max_tx_size_rect(b.size);
+            let (tx_w4, tx_h4) = ((max_tx.width() >> 2) as i32, (max_tx.height() >> 2) as i32);
+            let (mi_row, mi_col) = (b.mi_row, b.mi_col);
+            let mut largest = TxSize::Tx4x4;
+            for row in (mi_row..mi_row + bh4).step_by(tx_h4 as usize) {
+                for col in (mi_col..mi_col + bw4).step_by(tx_w4 as usize) {
+                    self.read_var_tx_size(row, col, max_tx, 0, &mut largest);
+                }
+            }
+            largest
+        } else {
+            let allow_select = !self.b.skip || !self.b.is_inter;
+            self.read_tx_size(allow_select);
+            let tx_size = self.b.tx_size;
+            self.fill_inter_tx_sizes(self.b.mi_row, self.b.mi_col, bw4, bh4, tx_size);
+            tx_size
+        }
+    }
+
+    /// Spec `read_var_tx_size()`
+    fn read_var_tx_size(
+        &mut self,
+        row: i32,
+        col: i32,
+        tx_size: TxSize,
+        depth: u32,
+        largest: &mut TxSize,
+    ) {
+        if row >= self.fs.mi_rows || col >= self.fs.mi_cols {
+            return;
+        }
+        let split = if tx_size == TxSize::Tx4x4 || depth == MAX_VARTX_DEPTH {
+            false
+        } else {
+            let ctx = self.txfm_split_ctx(row, col, tx_size);
+            self.sd.read_flag(|c| &mut c.txfm_split[ctx][..])
+        };
+        let (w4, h4) = (
+            (tx_size.width() >> 2) as i32,
+            (tx_size.height() >> 2) as i32,
+        );
+        if split {
+            let sub = split_tx_size(tx_size);
+            let (step_w, step_h) = ((sub.width() >> 2) as usize, (sub.height() >> 2) as usize);
+            for i in (0..h4).step_by(step_h) {
+                for j in (0..w4).step_by(step_w) {
+                    self.read_var_tx_size(row + i, col + j, sub, depth + 1, largest);
+                }
+            }
+        } else {
+            self.fill_inter_tx_sizes(row, col, w4, h4, tx_size);
+            self.b.tx_size = tx_size;
+            if tx_size.width() * tx_size.height() > largest.width() * largest.height() {
+                *largest = tx_size;
+            }
+        }
+    }
+
+    /// Set InterTxSizes over an area, clipped to the frame
+    fn fill_inter_tx_sizes(&mut self, row: i32, col: i32, w4: i32, h4: i32, tx_size: TxSize) {
+        for r in row..(row + h4).min(self.fs.mi_rows) {
+            for c in col..(col + w4).min(self.fs.mi_cols) {
+                let idx = self.fs.idx(r, c);
+                self.fs.inter_tx_sizes[idx] = tx_size;
+            }
+        }
+    }
+
+    /// Spec `read_tx_size()`
+    fn read_tx_size(&mut self, allow_select: bool) {
+        if self.b.lossless {
+            self.b.tx_size = TxSize::Tx4x4;
+            return;
+        }
+        let size = self.b.size;
+        let max_rect = max_tx_size_rect(size);
+        let max_tx_depth = MAX_TX_DEPTH[size as usize] as usize;
+        self.b.tx_size = max_rect;
+        if size > BlockSize::Block4x4 && allow_select && self.fh.tx_mode == TxMode::Select {
+            let ctx = self.tx_depth_ctx(max_rect);
+            let depth = if max_tx_depth > 1 {
+                self.sd.read(|c| &mut c.tx_size[max_tx_depth - 2][ctx][..])
+            } else {
+                self.sd.read(|c| &mut c.tx_8x8[ctx][..])
+            };
+            for _ in 0..depth {
+                self.b.tx_size = split_tx_size(self.b.tx_size);
+            }
+        }
+    }
+
+    /// Context of `tx_depth`
+    fn tx_depth_ctx(&self, max_rect: TxSize) -> usize {
+        let b = &self.b;
+        let above_w = if !b.avail_u {
+            0
+        } else if self.mi(b.mi_row - 1, b.mi_col).is_inter {
+            self.mi(b.mi_row - 1, b.mi_col).size.width()
+        } else {
+            self.above_tx_width(b.mi_row, b.mi_col)
+        };
+        let left_h = if !b.avail_l {
+            0
+        } else if self.mi(b.mi_row, b.mi_col - 1).is_inter {
+            self.mi(b.mi_row, b.mi_col - 1).size.height()
+        } else {
+            self.left_tx_height(b.mi_row, b.mi_col)
+        };
+        (above_w >= max_rect.width()) as usize + (left_h >= max_rect.height()) as usize
+    }
+
+    /// Context of `txfm_split`
+    fn txfm_split_ctx(&self, row: i32, col: i32, tx_size: TxSize) -> usize {
+        let above = (self.above_tx_width(row, col) < tx_size.width()) as usize;
+        let left = (self.left_tx_height(row, col) < tx_size.height()) as usize;
+        let size = self.b.size.width().max(self.b.size.height()).min(64);
+        let max_tx = TxSize::from_dimensions(size, size) as usize;
+        let sqr_up = tx_size_sqr_up(tx_size) as usize;
+        (sqr_up != max_tx) as usize * 3 + (TxSize::Tx64x64 as usize - max_tx) * 6 + above + left
+    }
+
+    /// Spec `get_above_tx_width()`
+    fn above_tx_width(&self, row: i32, col: i32) -> u32 {
+        if row == self.b.mi_row {
+            if !self.b.avail_u {
+                return 64;
+            }
+            let above = self.mi(row - 1, col);
+            if above.skip && above.is_inter {
+                return above.size.width();
+            }
+        }
+        self.fs.inter_tx_sizes[self.fs.idx(row - 1, col)].width()
+    }
+
+    /// Spec `get_left_tx_height()`
+    fn left_tx_height(&self, row: i32, col: i32) -> u32 {
+        if col == self.b.mi_col {
+            if !self.b.avail_l {
+                return 64;
+            }
+            let left = self.mi(row, col - 1);
+            if left.skip && left.is_inter {
+                return left.size.height();
+            }
+        }
+        self.fs.inter_tx_sizes[self.fs.idx(row, col - 1)].height()
+    }
+
+    /// Spec `residual()`
+    ///
+    /// The block is coded in 64x64 chunks; the luma transforms of inter
+    /// blocks follow the transform partitioning read by
+    /// `read_block_tx_size()`.
+    pub(crate) fn residual(&mut self) -> Result<()> {
+        let (ss_x, ss_y) = self.subsampling();
+        let (bw4, bh4) = (self.b.bw4, self.b.bh4);
+        let (mi_row, mi_col) = (self.b.mi_row, self.b.mi_col);
+        let max_w4 = bw4.min(self.fs.mi_cols - mi_col);
+        let max_h4 = bh4.min(self.fs.mi_rows - mi_row);
+        let planes = if self.b.has_chroma { 3 } else { 1 };
+        for chunk_y in 0..(bh4 >> 4).max(1) {
+            for chunk_x in 0..(bw4 >> 4).max(1) {
+                let (row0, col0) = (chunk_y << 4, chunk_x << 4);
+                for plane in 0..planes {
+                    if plane == 0 && self.b.is_inter && !self.b.lossless {
+                        let max_tx = max_tx_size_rect(self.b.size);
+                        let (tx_w4, tx_h4) = (max_tx.width() >> 2, max_tx.height() >> 2);
+                        let unit_h = (row0 + 16).min(max_h4);
+                        let unit_w = (col0 + 16).min(max_w4);
+                        for r in (row0..unit_h).step_by(tx_h4 as usize) {
+                            for c in (col0..unit_w).step_by(tx_w4 as usize) {
+                                self.transform_tree(r, c, max_tx, max_w4, max_h4)?;
+                            }
+                        }
+                        continue;
+                    }
+                    let (sx, sy) = if plane > 0 {
+                        (ss_x as i32, ss_y as i32)
+                    } else {
+                        (0, 0)
+                    };
+                    let tx_size = self.plane_tx_size(plane);
+                    let step_x = (tx_size.width() >> 2) as usize;
+                    let step_y = (tx_size.height() >> 2) as usize;
+                    let plane_size = subsampled_size(self.b.size, sx != 0, sy != 0);
+                    let num4x4_w = (plane_size.width() >> 2) as i32;
+                    let num4x4_h = (plane_size.height() >> 2) as i32;
+                    let base_x = (mi_col >> sx) * 4;
+                    let base_y = (mi_row >> sy) * 4;
+                    for y in (0..num4x4_h.min(16 >> sy)).step_by(step_y) {
+                        for x in (0..num4x4_w.min(16 >> sx)).step_by(step_x) {
+                            let start_x = base_x + 4 * (x + (col0 >> sx));
+                            let start_y = base_y + 4 * (y + (row0 >> sy));
+                            self.transform_block(plane, start_x, start_y, tx_size)?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Walk the luma transform partitioning of an inter block
+    fn transform_tree(
+        &mut self,
+        row: i32,
+        col: i32,
+        tx_size: TxSize,
+        max_w4: i32,
+        max_h4: i32,
+    ) -> Result<()> {
+        if row >= max_h4 || col >= max_w4 {
+            return Ok(());
+        }
+        let (mi_row, mi_col) = (self.b.mi_row + row, self.b.mi_col + col);
+        let coded = self.fs.inter_tx_sizes[self.fs.idx(mi_row, mi_col)];
+        if coded == tx_size || tx_size == TxSize::Tx4x4 {
+            return self.transform_block(0, mi_col * 4, mi_row * 4, tx_size);
+        }
+        let sub = split_tx_size(tx_size);
+        let (sub_w4, sub_h4) = ((sub.width() >> 2) as i32, (sub.height() >> 2) as i32);
+        let (w4, h4) = (
+            (tx_size.width() >> 2) as i32,
+            (tx_size.height() >> 2) as i32,
+        );
+        for i in (0..h4).step_by(sub_h4 as usize) {
+            for j in (0..w4).step_by(sub_w4 as usize) {
+                self.transform_tree(row + i, col + j, sub, max_w4, max_h4)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Spec `get_tx_size()` for a chroma plane, or `TxSize` for luma
+    fn plane_tx_size(&self, plane: usize) -> TxSize {
+        if self.b.lossless {
+            return TxSize::Tx4x4;
+        }
+        if plane == 0 {
+            return self.b.tx_size;
+        }
+        let (ss_x, ss_y) = self.subsampling();
+        let uv_tx = max_tx_size_rect(subsampled_size(self.b.size, ss_x, ss_y));
+        match (uv_tx.width(), uv_tx.height()) {
+            (16, 64) => TxSize::Tx16x32,
+            (64, 16) => TxSize::Tx32x16,
+            (64, _) | (_, 64) => TxSize::Tx32x32,
+            _ => uv_tx,
+        }
+    }
+
+    /// Spec `transform_block()`, reduced to the coefficient syntax
+    fn transform_block(
+        &mut self,
+        plane: usize,
+        start_x: i32,
+        start_y: i32,
+        tx_size: TxSize,
+    ) -> Result<()> {
+        let (sx, sy) = self.plane_subsampling(plane);
+        let max_x = (self.fs.mi_cols * 4) >> sx;
+        let max_y = (self.fs.mi_rows * 4) >> sy;
+        if start_x >= max_x || start_y >= max_y {
+            return Ok(());
+        }
+        if !self.b.skip {
+            self.coeffs(plane, start_x, start_y, tx_size);
+        }
+        Ok(())
+    }
+
+    fn plane_subsampling(&self, plane: usize) -> (i32, i32) {
+        if plane == 0 {
+            return (0, 0);
+        }
+        let (ss_x, ss_y) = self.subsampling();
+        (ss_x as i32, ss_y as i32)
+    }
+
+    /// Spec `coeffs()`; returns the end of block position
+    fn coeffs(&mut self, plane: usize, start_x: i32, start_y: i32, tx_size: TxSize) -> usize {
+        let x4 = (start_x >> 2) as usize;
+        let y4 = (start_y >> 2) as usize;
+        let w4 = (tx_size.width() >> 2) as usize;
+        let h4 = (tx_size.height() >> 2) as usize;
+        let tx_sz_ctx = tx_size_ctx(tx_size);
+        let ptype = (plane > 0) as usize;
+        let seg_eob = if matches!(tx_size, TxSize::Tx16x64 | TxSize::Tx64x16) {
+            512
+        } else {
+            (tx_size.width() * tx_size.height()).min(1024) as usize
+        };
+        self.quant[..seg_eob].fill(0);
+
+        let mut eob = 0;
+        let mut cul_level = 0u32;
+        let mut dc_category = 0u8;
+        let ctx = self.all_zero_ctx(plane, x4, y4, tx_size);
+        let all_zero = self.sd.read_flag(|c| &mut c.txb_skip[tx_sz_ctx][ctx][..]);
+        if all_zero {
+            if plane == 0 {
+                self.set_tx_types(x4, y4, w4, h4, DCT_DCT);
+            }
+        } else {
+            if plane == 0 {
+                self.transform_type(x4, y4, tx_size);
+            }
+            let tx_type = self.compute_tx_type(plane, tx_size, x4, y4);
+            let tx_class = TxClass::of(tx_type);
+            let scan = scan(tx_size, tx_type);
+            eob = self.read_eob(tx_size, ptype, tx_class);
+
+            let adjusted = adjusted_tx_size(tx_size);
+            let bwl = tx_width_log2(adjusted);
+            let txh = adjusted.height() as usize;
+            let area = (adjusted.width() * adjusted.height()) as usize;
+            for c in (0..eob).rev() {
+                let pos = scan[c] as usize;
+                let mut level = if c == eob - 1 {
+                    let ctx = if c == 0 {
+                        0
+                    } else if c <= area / 8 {
+                        1
+                    } else if c <= area / 4 {
+                        2
+                    } else {
+                        3
+                    };
+                    self.sd
+                        .read(|cdf| &mut cdf.coeff_base_eob[tx_sz_ctx][ptype][ctx][..])
+                        as i32
+                        + 1
+                } else {
+                    let ctx = self.coeff_base_ctx(tx_size, tx_class, bwl, txh, pos);
+                    self.sd
+                        .read(|cdf| &mut cdf.coeff_base[tx_sz_ctx][ptype][ctx][..])
+                        as i32
+                };
+                if level > NUM_BASE_LEVELS {
+                    let ctx = self.coeff_br_ctx(tx_class, bwl, txh, pos);
+                    let br_tx = tx_sz_ctx.min(TxSize::Tx32x32 as usize);
+                    for _ in 0..COEFF_BASE_RANGE as usize / (BR_CDF_SIZE - 1) {
+                        let br = self.sd.read(|cdf| &mut cdf.coeff_br[br_tx][ptype][ctx][..]);
+                        level += br as i32;
+                        if br < BR_CDF_SIZE - 1 {
+                            break;
+                        }
+                    }
+                }
+                self.quant[pos] = level;
+            }
+
+            for (c, &pos) in scan.iter().enumerate().take(eob) {
+                let pos = pos as usize;
+                let sign = if self.quant[pos] == 0 {
+                    false
+                } else if c == 0 {
+                    let ctx = self.dc_sign_ctx(plane, x4, y4, w4, h4);
+                    self.sd.read_flag(|cdf| &mut cdf.dc_sign[ptype][ctx][..])
+                } else {
+                    self.sd.decoder.read_bool()
+                };
+                if self.quant[pos] > NUM_BASE_LEVELS + COEFF_BASE_RANGE {
+                    let golomb = self.read_golomb() as i32;
+                    self.quant[pos] = golomb + COEFF_BASE_RANGE + NUM_BASE_LEVELS + 1;
+                }
+                if pos == 0 && self.quant[pos] > 0 {
+                    dc_category = if sign { 1 } else { 2 };
+                }
+                self.quant[pos] &= 0xFFFFF;
+                cul_level += self.quant[pos] as u32;
+                if sign {
+                    self.quant[pos] = -self.quant[pos];
+                }
+            }
+            cul_level = cul_level.min(63);
+            self.b.coeff_count += scan
+                .iter()
+                .take(eob)
+                .filter(|&&pos| self.quant[pos as usize] != 0)
+                .count() as u32;
+        }
+
+        for i in 0..w4 {
+            self.above_level_ctx[plane][x4 + i] = cul_level as u8;
+            self.above_dc_ctx[plane][x4 + i] = dc_category;
+        }
+        for i in 0..h4 {
+            self.left_level_ctx[plane][y4 + i] = cul_level as u8;
+            self.left_dc_ctx[plane][y4 + i] = dc_category;
+        }
+        eob
+    }
+
+    /// Read `eob_pt_*`, `eob_extra` and `eob_extra_bit`
+    fn read_eob(&mut self, tx_size: TxSize, ptype: usize, tx_class: TxClass) -> usize {
+        let eob_multisize = tx_width_log2(tx_size).min(5) + tx_height_log2(tx_size).min(5) - 4;
+        let ctx = (tx_class != TxClass::TwoD) as usize;
+        let eob_pt = 1 + match eob_multisize {
+            0 => self.sd.read(|c| &mut c.eob_pt_16[ptype][ctx][..]),
+            1 => self.sd.read(|c| &mut c.eob_pt_32[ptype][ctx][..]),
+            2 => self.sd.read(|c| &mut c.eob_pt_64[ptype][ctx][..]),
+            3 => self.sd.read(|c| &mut c.eob_pt_128[ptype][ctx][..]),
+            4 => self.sd.read(|c| &mut c.eob_pt_256[ptype][ctx][..]),
+            5 => self.sd.read(|c| &mut c.eob_pt_512[ptype][ctx][..]),
+            _ => self.sd.read(|c| &mut c.eob_pt_1024[ptype][ctx][..]),
+        };
+        let mut eob = if eob_pt < 2 {
+            eob_pt
+        } else {
+            (1 << (eob_pt - 2)) + 1
+        };
+        if eob_pt >= 3 {
+            let tx_sz_ctx = tx_size_ctx(tx_size);
+            let eob_extra = self
+                .sd
+                .read_flag(|c| &mut c.eob_extra[tx_sz_ctx][ptype][eob_pt - 3][..]);
+            if eob_extra {
+                eob += 1 << (eob_pt - 3);
+            }
+            for i in 1..eob_pt - 2 {
+                let shift = eob_pt - 3 - i;
+                if self.sd.decoder.read_bool() {
+                    eob += 1 << shift;
+                }
+            }
+        }
+        eob
+    }
+
+    /// Read the Exp-Golomb coded remainder of a large coefficient
+    fn read_golomb(&mut self) -> u32 {
+        let mut length = 0;
+        loop {
+            length += 1;
+            if self.sd.decoder.read_bool() || length == 32 {
+                break;
+            }
+        }
+        let mut x = 1u32;
+        for _ in 0..length - 1 {
+            x = (x << 1) | self.sd.decoder.read_bool() as u32;
+        }
+        x - 1
+    }
+
+    /// Context of `all_zero`
+    fn all_zero_ctx(&self, plane: usize, x4: usize, y4: usize, tx_size: TxSize) -> usize {
+        let (sx, sy) = self.plane_subsampling(plane);
+        let max_x4 = (self.fs.mi_cols >> sx) as usize;
+        let max_y4 = (self.fs.mi_rows >> sy) as usize;
+        let (w, h) = (tx_size.width(), tx_size.height());
+        let (w4, h4) = ((w >> 2) as usize, (h >> 2) as usize);
+        let bsize = subsampled_size(self.b.size, sx != 0, sy != 0);
+        let above = (x4..x4 + w4).filter(|&x| x < max_x4);
+        let left = (y4..y4 + h4).filter(|&y| y < max_y4);
+        if plane == 0 {
+            let top = above.map(|x| self.above_level_ctx[0][x]).max().unwrap_or(0);
+            let left = left.map(|y| self.left_level_ctx[0][y]).max().unwrap_or(0);
+            if bsize.width() == w && bsize.height() == h {
+                0
+            } else if top == 0 && left == 0 {
+                1
+            } else if top == 0 || left == 0 {
+                2 + (top.max(left) > 3) as usize
+            } else if top.max(left) <= 3 {
+                4
+            } else if top.min(left) <= 3 {
+                5
+            } else {
+                6
+            }
+        } else {
+            let above = above.fold(0, |acc, x| {
+                acc | self.above_level_ctx[plane][x] | self.above_dc_ctx[plane][x]
+            });
+            let left = left.fold(0, |acc, y| {
+                acc | self.left_level_ctx[plane][y] | self.left_dc_ctx[plane][y]
+            });
+            let mut ctx = 7 + (above != 0) as usize + (left != 0) as usize;
+            if bsize.width() * bsize.height() > w * h {
+                ctx += 3;
+            }
+            ctx
+        }
+    }
+
+    /// Context of `dc_sign`
+    fn dc_sign_ctx(&self, plane: usize, x4: usize, y4: usize, w4: usize, h4: usize) -> usize {
+        let (sx, sy) = self.plane_subsampling(plane);
+        let max_x4 = (self.fs.mi_cols >> sx) as usize;
+        let max_y4 = (self.fs.mi_rows >> sy) as usize;
+        let weight = |category: u8| match category {
+            1 => -1,
+            2 => 1,
+            _ => 0,
+        };
+        let above = (x4..x4 + w4)
+            .filter(|&x| x < max_x4)
+            .map(|x| weight(self.above_dc_ctx[plane][x]));
+        let left = (y4..y4 + h4)
+            .filter(|&y| y < max_y4)
+            .map(|y| weight(self.left_dc_ctx[plane][y]));
+        let dc_sign: i32 = above.chain(left).sum();
+        match dc_sign.signum() {
+            -1 => 1,
+            1 => 2,
+            _ => 0,
+        }
+    }
+
+    /// Context of `coeff_base`
+    fn coeff_base_ctx(
+        &self,
+        tx_size: TxSize,
+        tx_class: TxClass,
+        bwl: u32,
+        txh: usize,
+        pos: usize,
+    ) -> usize {
+        let row = pos >> bwl;
+        let col = pos - (row << bwl);
+        let mut mag = 0;
+        for [dr, dc] in SIG_REF_DIFF_OFFSET[tx_class.index()] {
+            let (ref_row, ref_col) = (row + dr, col + dc);
+            if ref_row < txh && ref_col < (1 << bwl) {
+                mag += self.quant[(ref_row << bwl) + ref_col].abs().min(3) as usize;
+            }
+        }
+        let ctx = ((mag + 1) >> 1).min(4);
+        match tx_class {
+            TxClass::TwoD if row == 0 && col == 0 => 0,
+            TxClass::TwoD => ctx + coeff_base_ctx_offset(tx_size, row, col),
+            TxClass::Vert => ctx + COEFF_BASE_POS_CTX_OFFSET[row.min(2)],
+            TxClass::Horiz => ctx + COEFF_BASE_POS_CTX_OFFSET[col.min(2)],
+        }
+    }
+
+    /// Context of `coeff_br`
+    fn coeff_br_ctx(&self, tx_class: TxClass, bwl: u32, txh: usize, pos: usize) -> usize {
+        let row = pos >> bwl;
+        let col = pos - (row << bwl);
+        let mut mag = 0;
+        for [dr, dc] in MAG_REF_OFFSET[tx_class.index()] {
+            let (ref_row, ref_col) = (row + dr, col + dc);
+            if ref_row < txh && ref_col < (1 << bwl) {
+                let level = self.quant[(ref_row << bwl) + ref_col];
+                mag += level.min(COEFF_BASE_RANGE + NUM_BASE_LEVELS + 1) as usize;
+            }
+        }
+        let mag = ((mag + 1) >> 1).min(6);
+        if pos == 0 {
+            return mag;
+        }
+        let near_origin = match tx_class {
+            TxClass::TwoD => row < 2 && col < 2,
+            TxClass::Horiz => col == 0,
+            TxClass::Vert => row == 0,
+        };
+        if near_origin {
+            mag + 7
+        } else {
+            mag + 14
+        }
+    }
+
+    /// Spec `get_tx_set()`
+    fn tx_set(&self, tx_size: TxSize) -> usize {
+        let sqr = tx_size_sqr(tx_size);
+        let sqr_up = tx_size_sqr_up(tx_size);
+        if sqr_up == TxSize::Tx64x64 {
+            return TX_SET_DCTONLY;
+        }
+        if self.b.is_inter {
+            if self.fh.reduced_tx_set || sqr_up == TxSize::Tx32x32 {
+                TX_SET_INTER_3
+            } else if sqr == TxSize::Tx16x16 {
+                TX_SET_INTER_2
+            } else {
+                TX_SET_INTER_1
+            }
+        } else if sqr_up == TxSize::Tx32x32 {
+            TX_SET_DCTONLY
+        } else if self.fh.reduced_tx_set || sqr == TxSize::Tx16x16 {
+            TX_SET_INTRA_2
+        } else {
+            TX_SET_INTRA_1
+        }
+    }
+
+    /// Spec `transform_type()`: read the luma transform type
+    fn transform_type(&mut self, x4: usize, y4: usize, tx_size: TxSize) {
+        let set = self.tx_set(tx_size);
+        let qindex = if self.fh.segmentation.enabled {
+            self.fh
+                .get_qindex(true, self.b.segment_id as usize, self.current_q_index)
+        } else {
+            self.fh.quantization.base_q_idx
+        };
+        let sqr = tx_size_sqr(tx_size) as usize;
+        let tx_type = if set > 0 && qindex > 0 {
+            if self.b.is_inter {
+                match set {
+                    TX_SET_INTER_1 => {
+                        TX_TYPE_INTER_INV_SET1[self.sd.read(|c| &mut c.inter_tx_type_set1[sqr][..])]
+                    }
+                    TX_SET_INTER_2 => {
+                        TX_TYPE_INTER_INV_SET2[self.sd.read(|c| &mut c.inter_tx_type_set2[sqr][..])]
+                    }
+                    _ => {
+                        TX_TYPE_INTER_INV_SET3[self.sd.read(|c| &mut c.inter_tx_type_set3[sqr][..])]
+                    }
+                }
+            } else {
+                let intra_dir = if self.b.use_filter_intra {
+                    FILTER_INTRA_MODE_TO_INTRA_DIR[self.b.filter_intra_mode as usize]
+                } else {
+                    self.b.y_mode
+                } as usize;
+                if set == TX_SET_INTRA_1 {
+                    TX_TYPE_INTRA_INV_SET1[self
+                        .sd
+                        .read(|c| &mut c.intra_tx_type_set1[sqr][intra_dir][..])]
+                } else {
+                    TX_TYPE_INTRA_INV_SET2[self
+                        .sd
+                        .read(|c| &mut c.intra_tx_type_set2[sqr][intra_dir][..])]
+                }
+            }
+        } else {
+            DCT_DCT
+        };
+        let (w4, h4) = (
+            (tx_size.width() >> 2) as usize,
+            (tx_size.height() >> 2) as usize,
+        );
+        self.set_tx_types(x4, y4, w4, h4, tx_type);
+    }
+
+    /// Set TxTypes over a luma area, clipped to the frame
+    fn set_tx_types(&mut self, x4: usize, y4: usize, w4: usize, h4: usize, tx_type: u8) {
+        let rows = y4..(y4 + h4).min(self.fs.mi_rows as usize);
+        let cols = x4..(x4 + w4).min(self.fs.mi_cols as usize);
+        for row in rows {
+            for col in cols.clone() {
+                let idx = self.fs.idx(row as i32, col as i32);
+                self.fs.tx_types[idx] = tx_type;
+            }
+        }
+    }
+
+    /// Spec `compute_tx_type()`
+    fn compute_tx_type(&self, plane: usize, tx_size: TxSize, x4: usize, y4: usize) -> u8 {
+        if self.b.lossless || tx_size_sqr_up(tx_size) == TxSize::Tx64x64 {
+            return DCT_DCT;
+        }
+        let set = self.tx_set(tx_size);
+        if plane == 0 {
+            return self.fs.tx_types[self.fs.idx(y4 as i32, x4 as i32)];
+        }
+        let tx_type = if self.b.is_inter {
+            let (sx, sy) = self.plane_subsampling(plane);
+            let x = self.b.mi_col.max((x4 as i32) << sx);
+            let y = self.b.mi_row.max((y4 as i32) << sy);
+            self.fs.tx_types[self.fs.idx(y, x)]
+        } else {
+            MODE_TO_TXFM[self.b.uv_mode as usize]
+        };
+        if is_tx_type_in_set(self.b.is_inter, set, tx_type) {
+            tx_type
+        } else {
+            DCT_DCT
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sig_ref_offsets_stay_in_class_direction() {
+        // Horizontal classes look along the row, vertical along the column
+        assert!(SIG_REF_DIFF_OFFSET[TxClass::Horiz.index()][2..]
+            .iter()
+            .all(|[row, _]| *row == 0));
+        assert!(SIG_REF_DIFF_OFFSET[TxClass::Vert.index()][2..]
+            .iter()
+            .all(|[_, col]| *col == 0));
+    }
+}
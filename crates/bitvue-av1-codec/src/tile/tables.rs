@@ -0,0 +1,320 @@
+//! Constant tables of the tile decoding process
+//!
+//! Per AV1 Specification Section 9.3 (Additional tables). Tables indexed by
+//! block size use the specification order of [`BlockSize`], tables indexed
+//! by transform size the order of [`TxSize`].
+//!
+//! The coefficient scan orders are generated instead of being listed: the
+//! default scan walks the anti-diagonals of the (at most 32x32) coded
+//! transform area, the row scan is raster order and the column scan is its
+//! transpose.
+
+use crate::tile::{BlockSize, TxSize};
+
+/// Size_Group (context of `y_mode` in inter frames)
+pub(crate) const SIZE_GROUP: [u8; 22] = [
+    0, 0, 0, 1, 1, 1, 2, 2, 2, 3, 3, 3, 3, 3, 3, 3, 0, 0, 1, 1, 2, 2,
+];
+
+/// Max_Tx_Depth
+pub(crate) const MAX_TX_DEPTH: [u8; 22] = [
+    0, 1, 1, 1, 2, 2, 2, 3, 3, 3, 4, 4, 4, 4, 4, 4, 2, 2, 3, 3, 4, 4,
+];
+
+/// Wedge_Bits
+pub(crate) const WEDGE_BITS: [u8; 22] = [
+    0, 0, 0, 4, 4, 4, 4, 4, 4, 4, 0, 0, 0, 0, 0, 0, 0, 0, 4, 4, 0, 0,
+];
+
+/// Intra_Mode_Context (context of `intra_frame_y_mode`)
+pub(crate) const INTRA_MODE_CONTEXT: [usize; 13] = [0, 1, 2, 3, 4, 4, 4, 4, 3, 0, 1, 2, 0];
+
+/// Compound_Mode_Ctx_Map
+pub(crate) const COMPOUND_MODE_CTX_MAP: [[usize; 5]; 3] =
+    [[0, 1, 1, 1, 1], [1, 2, 3, 4, 4], [4, 4, 5, 6, 7]];
+
+/// Palette_Color_Context, indexed by the palette neighbour hash
+pub(crate) const PALETTE_COLOR_CONTEXT: [i8; 9] = [-1, -1, 0, -1, -1, 4, 3, 2, 1];
+
+/// Palette_Color_Hash_Multipliers
+pub(crate) const PALETTE_COLOR_HASH_MULTIPLIERS: [u32; 3] = [1, 2, 2];
+
+/// Div_Mult (reciprocals used by motion vector projection)
+pub(crate) const DIV_MULT: [i32; 32] = [
+    0, 16384, 8192, 5461, 4096, 3276, 2730, 2340, 2048, 1820, 1638, 1489, 1365, 1260, 1170, 1092,
+    1024, 963, 910, 862, 819, 780, 744, 712, 682, 655, 630, 606, 585, 564, 546, 528,
+];
+
+/// Transform types (spec `DCT_DCT` .. `H_FLIPADST`)
+pub(crate) const DCT_DCT: u8 = 0;
+pub(crate) const ADST_DCT: u8 = 1;
+pub(crate) const DCT_ADST: u8 = 2;
+pub(crate) const ADST_ADST: u8 = 3;
+pub(crate) const IDTX: u8 = 9;
+pub(crate) const V_DCT: u8 = 10;
+pub(crate) const H_DCT: u8 = 11;
+pub(crate) const V_ADST: u8 = 12;
+pub(crate) const H_ADST: u8 = 13;
+pub(crate) const V_FLIPADST: u8 = 14;
+pub(crate) const H_FLIPADST: u8 = 15;
+
+/// Mode_To_Txfm: default transform type of each intra mode (UV_CFL_PRED
+/// last)
+pub(crate) const MODE_TO_TXFM: [u8; 14] = [
+    DCT_DCT, ADST_DCT, DCT_ADST, DCT_DCT, ADST_ADST, ADST_DCT, DCT_ADST, DCT_ADST, ADST_DCT,
+    ADST_ADST, ADST_DCT, DCT_ADST, ADST_ADST, DCT_DCT,
+];
+
+/// Tx_Type_Intra_Inv_Set1
+pub(crate) const TX_TYPE_INTRA_INV_SET1: [u8; 7] =
+    [IDTX, DCT_DCT, V_DCT, H_DCT, ADST_ADST, ADST_DCT, DCT_ADST];
+
+/// Tx_Type_Intra_Inv_Set2
+pub(crate) const TX_TYPE_INTRA_INV_SET2: [u8; 5] = [IDTX, DCT_DCT, ADST_ADST, ADST_DCT, DCT_ADST];
+
+/// Tx_Type_Inter_Inv_Set1
+pub(crate) const TX_TYPE_INTER_INV_SET1: [u8; 16] = [
+    IDTX, V_DCT, H_DCT, V_ADST, H_ADST, V_FLIPADST, H_FLIPADST, DCT_DCT, ADST_DCT, DCT_ADST, 4, 5,
+    ADST_ADST, 6, 7, 8,
+];
+
+/// Tx_Type_Inter_Inv_Set2
+pub(crate) const TX_TYPE_INTER_INV_SET2: [u8; 12] = [
+    IDTX, V_DCT, H_DCT, DCT_DCT, ADST_DCT, DCT_ADST, 4, 5, ADST_ADST, 6, 7, 8,
+];
+
+/// Tx_Type_Inter_Inv_Set3
+pub(crate) const TX_TYPE_INTER_INV_SET3: [u8; 2] = [IDTX, DCT_DCT];
+
+/// Tx_Type_In_Set_Intra, one bit per transform type
+const TX_TYPE_IN_SET_INTRA: [u16; 3] = [
+    1 << DCT_DCT,
+    (1 << IDTX)
+        | (1 << DCT_DCT)
+        | (1 << V_DCT)
+        | (1 << H_DCT)
+        | (1 << ADST_ADST)
+        | (1 << ADST_DCT)
+        | (1 << DCT_ADST),
+    (1 << IDTX) | (1 << DCT_DCT) | (1 << ADST_ADST) | (1 << ADST_DCT) | (1 << DCT_ADST),
+];
+
+/// Tx_Type_In_Set_Inter, one bit per transform type
+const TX_TYPE_IN_SET_INTER: [u16; 4] = [
+    1 << DCT_DCT,
+    0xFFFF,
+    !(1 << V_ADST) & !(1 << H_ADST) & !(1 << V_FLIPADST) & !(1 << H_FLIPADST),
+    (1 << IDTX) | (1 << DCT_DCT),
+];
+
+/// Whether `tx_type` is a member of transform set `tx_set`
+pub(crate) fn is_tx_type_in_set(is_inter: bool, tx_set: usize, tx_type: u8) -> bool {
+    let set = if is_inter {
+        TX_TYPE_IN_SET_INTER[tx_set]
+    } else {
+        TX_TYPE_IN_SET_INTRA[tx_set]
+    };
+    set & (1 << tx_type) != 0
+}
+
+/// Transform class (spec `get_tx_class`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TxClass {
+    TwoD,
+    Horiz,
+    Vert,
+}
+
+impl TxClass {
+    pub(crate) fn of(tx_type: u8) -> Self {
+        match tx_type {
+            V_DCT | V_ADST | V_FLIPADST => TxClass::Vert,
+            H_DCT | H_ADST | H_FLIPADST => TxClass::Horiz,
+            _ => TxClass::TwoD,
+        }
+    }
+}
+
+/// Num_4x4_Blocks_Wide
+pub(crate) fn num_4x4_wide(bsize: BlockSize) -> u32 {
+    bsize.width() >> 2
+}
+
+/// Num_4x4_Blocks_High
+pub(crate) fn num_4x4_high(bsize: BlockSize) -> u32 {
+    bsize.height() >> 2
+}
+
+/// Mi_Width_Log2
+pub(crate) fn mi_width_log2(bsize: BlockSize) -> u32 {
+    bsize.width().trailing_zeros() - 2
+}
+
+/// Mi_Height_Log2
+pub(crate) fn mi_height_log2(bsize: BlockSize) -> u32 {
+    bsize.height().trailing_zeros() - 2
+}
+
+/// Subsampled_Size, the size of a block in a subsampled plane
+pub(crate) fn subsampled_size(bsize: BlockSize, ss_x: bool, ss_y: bool) -> BlockSize {
+    let w = (bsize.width() >> ss_x as u32).max(4);
+    let h = (bsize.height() >> ss_y as u32).max(4);
+    BlockSize::from_dimensions(w, h).unwrap_or(BlockSize::Block4x4)
+}
+
+/// Max_Tx_Size_Rect
+pub(crate) fn max_tx_size_rect(bsize: BlockSize) -> TxSize {
+    TxSize::from_dimensions(bsize.width(), bsize.height())
+}
+
+/// Tx_Width_Log2
+pub(crate) fn tx_width_log2(tx: TxSize) -> u32 {
+    tx.width().trailing_zeros()
+}
+
+/// Tx_Height_Log2
+pub(crate) fn tx_height_log2(tx: TxSize) -> u32 {
+    tx.height().trailing_zeros()
+}
+
+/// Square transform of the given side
+fn square_tx(side: u32) -> TxSize {
+    TxSize::from_dimensions(side, side)
+}
+
+/// Tx_Size_Sqr, the largest square transform inside `tx`
+pub(crate) fn tx_size_sqr(tx: TxSize) -> TxSize {
+    square_tx(tx.width().min(tx.height()))
+}
+
+/// Tx_Size_Sqr_Up, the smallest square transform containing `tx`
+pub(crate) fn tx_size_sqr_up(tx: TxSize) -> TxSize {
+    square_tx(tx.width().max(tx.height()))
+}
+
+/// Split_Tx_Size
+pub(crate) fn split_tx_size(tx: TxSize) -> TxSize {
+    let (w, h) = (tx.width(), tx.height());
+    if w == h {
+        square_tx((w / 2).max(4))
+    } else if w > h {
+        TxSize::from_dimensions((w / 2).max(h), h)
+    } else {
+        TxSize::from_dimensions(w, (h / 2).max(w))
+    }
+}
+
+/// Adjusted_Tx_Size, the coded area of a transform (64 becomes 32)
+pub(crate) fn adjusted_tx_size(tx: TxSize) -> TxSize {
+    TxSize::from_dimensions(tx.width().min(32), tx.height().min(32))
+}
+
+/// Transform size context of the coefficient CDFs (`txSzCtx`)
+pub(crate) fn tx_size_ctx(tx: TxSize) -> usize {
+    (tx_size_sqr(tx) as usize + tx_size_sqr_up(tx) as usize + 1) >> 1
+}
+
+/// Coeff_Base_Ctx_Offset[txSz][min(row, 4)][min(col, 4)]
+pub(crate) fn coeff_base_ctx_offset(tx: TxSize, row: usize, col: usize) -> usize {
+    let (w, h) = (tx.width(), tx.height());
+    let (row, col) = (row.min(4), col.min(4));
+    if row == 0 && col == 0 {
+        0
+    } else if w < h && row < 2 {
+        11
+    } else if w > h && col < 2 {
+        16
+    } else if row + col < 2 {
+        1
+    } else if row + col < 4 {
+        6
+    } else {
+        21
+    }
+}
+
+/// Scan order of a transform block (spec `get_scan`)
+///
+/// Positions index the coded area row by row (`row * width + col`, with the
+/// dimensions of [`adjusted_tx_size`]).
+pub(crate) fn scan(tx: TxSize, tx_type: u8) -> Vec<u16> {
+    let adjusted = adjusted_tx_size(tx);
+    let (w, h) = (adjusted.width() as usize, adjusted.height() as usize);
+    let one_d = tx_size_sqr_up(tx) != TxSize::Tx64x64;
+    match TxClass::of(tx_type) {
+        TxClass::Vert if one_d => (0..w * h).map(|i| i as u16).collect(),
+        TxClass::Horiz if one_d => (0..w * h).map(|i| ((i % h) * w + i / h) as u16).collect(),
+        _ => default_scan(w, h),
+    }
+}
+
+/// Default (zig-zag) scan of a `w` x `h` area
+fn default_scan(w: usize, h: usize) -> Vec<u16> {
+    let mut scan = Vec::with_capacity(w * h);
+    for d in 0..w + h - 1 {
+        let rows = d.saturating_sub(w - 1)..=d.min(h - 1);
+        let row_increasing = match w.cmp(&h) {
+            std::cmp::Ordering::Less => true,
+            std::cmp::Ordering::Greater => false,
+            std::cmp::Ordering::Equal => d % 2 == 1,
+        };
+        if row_increasing {
+            scan.extend(rows.map(|r| (r * w + d - r) as u16));
+        } else {
+            scan.extend(rows.rev().map(|r| (r * w + d - r) as u16));
+        }
+    }
+    scan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_scans() {
+        assert_eq!(
+            scan(TxSize::Tx4x4, DCT_DCT),
+            [0, 1, 4, 8, 5, 2, 3, 6, 9, 12, 13, 10, 7, 11, 14, 15]
+        );
+        assert_eq!(scan(TxSize::Tx4x8, DCT_DCT)[..6], [0, 1, 4, 2, 5, 8]);
+        assert_eq!(scan(TxSize::Tx8x4, DCT_DCT)[..6], [0, 8, 1, 16, 9, 2]);
+        // 64-point transforms code their top-left 32x32 coefficients
+        assert_eq!(scan(TxSize::Tx64x64, DCT_DCT).len(), 1024);
+        assert_eq!(scan(TxSize::Tx16x64, DCT_DCT).len(), 512);
+    }
+
+    #[test]
+    fn test_one_dimensional_scans() {
+        assert_eq!(scan(TxSize::Tx4x4, V_DCT)[..5], [0, 1, 2, 3, 4]);
+        assert_eq!(scan(TxSize::Tx8x4, H_DCT)[..5], [0, 8, 16, 24, 1]);
+        assert_eq!(scan(TxSize::Tx4x4, IDTX), scan(TxSize::Tx4x4, DCT_DCT));
+    }
+
+    #[test]
+    fn test_transform_size_relations() {
+        assert_eq!(split_tx_size(TxSize::Tx64x64), TxSize::Tx32x32);
+        assert_eq!(split_tx_size(TxSize::Tx4x16), TxSize::Tx4x8);
+        assert_eq!(split_tx_size(TxSize::Tx64x32), TxSize::Tx32x32);
+        assert_eq!(split_tx_size(TxSize::Tx8x4), TxSize::Tx4x4);
+        assert_eq!(adjusted_tx_size(TxSize::Tx16x64), TxSize::Tx16x32);
+        assert_eq!(tx_size_ctx(TxSize::Tx4x4), 0);
+        assert_eq!(tx_size_ctx(TxSize::Tx8x16), 2);
+        assert_eq!(tx_size_ctx(TxSize::Tx64x64), 4);
+        assert_eq!(max_tx_size_rect(BlockSize::Block128x64), TxSize::Tx64x64);
+        assert_eq!(
+            subsampled_size(BlockSize::Block4x16, true, true),
+            BlockSize::Block4x8
+        );
+    }
+
+    #[test]
+    fn test_coeff_base_ctx_offset() {
+        assert_eq!(coeff_base_ctx_offset(TxSize::Tx8x8, 0, 0), 0);
+        assert_eq!(coeff_base_ctx_offset(TxSize::Tx8x8, 0, 1), 1);
+        assert_eq!(coeff_base_ctx_offset(TxSize::Tx8x8, 1, 2), 6);
+        assert_eq!(coeff_base_ctx_offset(TxSize::Tx8x8, 4, 4), 21);
+        assert_eq!(coeff_base_ctx_offset(TxSize::Tx4x8, 1, 3), 11);
+        assert_eq!(coeff_base_ctx_offset(TxSize::Tx8x4, 3, 1), 16);
+    }
+}
@@ -6,7 +6,6 @@
 //! tile group contains all tiles for a frame.
 
 use crate::bitreader::BitReader;
-use crate::leb128::decode_uleb128;
 use crate::tile::Tile;
 use bitvue_core::{BitvueError, Result};
 use serde::{Deserialize, Serialize};
@@ -20,7 +19,6 @@ const MAX_TOTAL_TILES: u32 = 1024; // Conservative limit: 64x64 = 4096 max, we u
 /// Tile configuration information
 ///
 /// This comes from the frame header's tile_info() section.
-/// For MVP, we'll use simplified defaults.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TileInfo {
     /// Number of tile columns
@@ -33,6 +31,14 @@ pub struct TileInfo {
     pub tile_row_start_sb: Vec<u32>,
     /// Use 128x128 superblock
     pub use_128x128_superblock: bool,
+    /// TileColsLog2
+    pub tile_cols_log2: u32,
+    /// TileRowsLog2
+    pub tile_rows_log2: u32,
+    /// Tile whose final CDFs are saved for later frames
+    pub context_update_tile_id: u32,
+    /// TileSizeBytes (bytes of each tile_size_minus_1 field)
+    pub tile_size_bytes: u8,
 }
 
 impl TileInfo {
@@ -47,6 +53,10 @@ impl TileInfo {
             tile_col_start_sb: vec![0, sb_cols],
             tile_row_start_sb: vec![0, sb_rows],
             use_128x128_superblock: sb_size == 128,
+            tile_cols_log2: 0,
+            tile_rows_log2: 0,
+            context_update_tile_id: 0,
+            tile_size_bytes: 4,
         }
     }
 
@@ -87,6 +97,10 @@ impl TileInfo {
             tile_col_start_sb: Vec::new(),
             tile_row_start_sb: Vec::new(),
             use_128x128_superblock: false,
+            tile_cols_log2: 0,
+            tile_rows_log2: 0,
+            context_update_tile_id: 0,
+            tile_size_bytes: 4,
         })
     }
 
@@ -139,82 +153,48 @@ impl TileGroup {
 
 /// Parse tile group from OBU payload
 ///
-/// **Implementation Status**: v0.3.x - Multi-tile support added
-/// - Single tile per frame (most common case)
-/// - Multi-tile frames with tile size delimiters
-/// - Handles tile_start_idx and tile_end_idx
+/// Per AV1 spec Section 5.11.1: with more than one tile the group starts
+/// with `tile_start_and_end_present_flag` (and the tile range), then every
+/// tile but the last is prefixed by `tile_size_minus_1` coded as a
+/// `TileSizeBytes` little-endian integer.
 ///
 /// # Arguments
 ///
-/// * `data` - Tile group OBU payload
+/// * `data` - Tile group OBU payload (for a frame OBU, the bytes after the
+///   frame header)
 /// * `tile_info` - Tile configuration from frame header
-/// * `frame_header_bytes` - Size of frame header (for positioning)
-/// * `tile_start_and_end_present` - Whether tile_start/end indices are present
 ///
 /// # Returns
 ///
 /// Parsed tile group with individual tiles
-pub fn parse_tile_group(
-    data: &[u8],
-    tile_info: TileInfo,
-    _frame_header_bytes: usize,
-) -> Result<TileGroup> {
-    let tile_count = tile_info.tile_count();
-
-    if tile_count == 1 {
-        // Single tile - simple case
-        // Entire payload is the tile data
-        let (sb_cols, sb_rows) = tile_info.tile_sb_dimensions(0, 0).unwrap();
-
-        let tile = Tile::new(0, 0, sb_cols, sb_rows, data.to_vec());
-
-        Ok(TileGroup {
-            tile_start_idx: 0,
-            tile_end_idx: 0,
-            tiles: vec![tile],
-            tile_info,
-        })
-    } else {
-        // Multi-tile - use full implementation
-        // For now, assume tile_start_and_end_present is false (common case)
-        // In full implementation, this would come from frame header
-        parse_tile_group_full(data, tile_info, false)
-    }
-}
-
-/// Parse tile group with size delimiters (full implementation)
-///
-/// Parses multi-tile frames by reading tile size delimiters.
-/// This is used when a frame has multiple tiles.
-///
-/// # Arguments
-///
-/// * `data` - Tile group OBU payload
-/// * `tile_info` - Tile configuration from frame header
-/// * `tile_start_and_end_present` - Whether tile_start/end indices are present
-fn parse_tile_group_full(
-    data: &[u8],
-    tile_info: TileInfo,
-    tile_start_and_end_present: bool,
-) -> Result<TileGroup> {
+pub fn parse_tile_group(data: &[u8], tile_info: TileInfo) -> Result<TileGroup> {
     let mut reader = BitReader::new(data);
     let tile_count = tile_info.tile_count();
+    if tile_count == 0 {
+        return Err(BitvueError::InvalidData("Frame has no tiles".to_string()));
+    }
 
-    // Read tile start/end indices if present
+    let tile_start_and_end_present = tile_count > 1 && reader.read_bit()?;
     let (tile_start_idx, tile_end_idx) = if tile_start_and_end_present {
-        let tile_bits = (tile_info.tile_cols.max(tile_info.tile_rows).ilog2() as u8).max(1);
+        let tile_bits = (tile_info.tile_cols_log2 + tile_info.tile_rows_log2) as u8;
         let start = reader.read_bits(tile_bits)?;
         let end = reader.read_bits(tile_bits)?;
         (start, end)
     } else {
         (0, (tile_count - 1) as u32)
     };
+    if tile_start_idx > tile_end_idx || tile_end_idx as usize >= tile_count {
+        return Err(BitvueError::InvalidData(format!(
+            "Invalid tile group range {}..={} for {} tiles",
+            tile_start_idx, tile_end_idx, tile_count
+        )));
+    }
+    reader.byte_align();
 
     let num_tiles = (tile_end_idx - tile_start_idx + 1) as usize;
     let mut tiles = Vec::with_capacity(num_tiles);
-
-    // Read tile data
     let mut offset = reader.byte_position();
+    let size_bytes = tile_info.tile_size_bytes as usize;
 
     for tile_idx in tile_start_idx..=tile_end_idx {
         // Calculate tile col/row from linear index
@@ -231,15 +211,19 @@ fn parse_tile_group_full(
                 ))
             })?;
 
-        // Read tile size (except for last tile)
         let tile_size = if tile_idx == tile_end_idx {
             // Last tile - extends to end of data
-            data.len() - offset
+            data.len().saturating_sub(offset)
         } else {
-            // Read tile_size_minus_1 as LEB128
-            let (size_minus_1, leb_bytes) = decode_uleb128(&data[offset..])?;
-            offset += leb_bytes;
-            (size_minus_1 + 1) as usize
+            let size_field = data
+                .get(offset..offset + size_bytes)
+                .ok_or(BitvueError::UnexpectedEof((offset + size_bytes) as u64))?;
+            offset += size_bytes;
+            let size_minus_1 = size_field
+                .iter()
+                .rev()
+                .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+            size_minus_1 + 1
         };
 
         // Extract tile data
@@ -293,7 +277,7 @@ mod tests {
         let tile_info = TileInfo::single_tile(352, 288, 64);
         let data = vec![0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC];
 
-        let result = parse_tile_group(&data, tile_info.clone(), 0);
+        let result = parse_tile_group(&data, tile_info.clone());
         assert!(result.is_ok());
 
         let tile_group = result.unwrap();
@@ -332,4 +316,24 @@ mod tests {
         assert!(group_complete.is_complete_frame());
         assert!(!group_partial.is_complete_frame());
     }
+
+    #[test]
+    fn test_parse_two_tile_group_with_size_field() {
+        let mut tile_info = TileInfo::new(2, 1).unwrap();
+        tile_info.tile_col_start_sb = vec![0, 3, 6];
+        tile_info.tile_row_start_sb = vec![0, 5];
+        tile_info.tile_cols_log2 = 1;
+        tile_info.tile_size_bytes = 2;
+
+        // tile_start_and_end_present_flag = 0, byte aligned, then a 2-byte
+        // little-endian tile_size_minus_1 = 2 for the first tile
+        let data = [0x00, 0x02, 0x00, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE];
+        let group = parse_tile_group(&data, tile_info).unwrap();
+
+        assert_eq!(group.tile_count(), 2);
+        assert_eq!(group.tiles[0].data, vec![0xAA, 0xBB, 0xCC]);
+        assert_eq!(group.tiles[1].data, vec![0xDD, 0xEE]);
+        assert_eq!(group.tiles[1].tile_col, 1);
+        assert_eq!(group.tiles[1].sb_cols, 3);
+    }
 }
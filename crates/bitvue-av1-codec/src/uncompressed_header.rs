@@ -0,0 +1,1834 @@
+//! AV1 Uncompressed Frame Header
+//!
+//! Per AV1 Specification Section 5.9 (Frame header OBU syntax) and the
+//! reference frame processes it depends on (Section 7.8 Set frame refs,
+//! Section 7.20 Reference frame update).
+//!
+//! Unlike [`crate::frame_header::parse_frame_header_basic`], this parser
+//! reads every field of `uncompressed_header()`, so it needs the sequence
+//! header and the state of the eight reference frame slots
+//! ([`RefFrameInfo`]). The result carries everything the tile decoder
+//! needs: frame size, tile layout, quantizer, segmentation, delta Q/LF,
+//! loop filter, CDEF, loop restoration, transform mode, reference
+//! selection, skip mode, global motion and film grain parameters.
+
+use crate::sequence::SequenceHeader;
+use crate::syntax_parser::{SyntaxBuilder, TrackedBitReader};
+use crate::tile::TileInfo;
+use bitvue_core::{types::BitRange, BitvueError, FrameType, Result};
+
+/// Number of reference frame slots
+pub const NUM_REF_FRAMES: usize = 8;
+
+/// Number of references an inter frame uses (LAST_FRAME..ALTREF_FRAME)
+pub const REFS_PER_FRAME: usize = 7;
+
+/// `primary_ref_frame` value meaning "no primary reference"
+pub const PRIMARY_REF_NONE: u8 = 7;
+
+/// Number of segments
+pub const MAX_SEGMENTS: usize = 8;
+
+/// Number of segmentation features
+pub const SEG_LVL_MAX: usize = 8;
+
+/// Segmentation feature: quantizer offset
+pub const SEG_LVL_ALT_Q: usize = 0;
+
+/// Segmentation feature: reference frame
+pub const SEG_LVL_REF_FRAME: usize = 5;
+
+/// Segmentation feature: skip
+pub const SEG_LVL_SKIP: usize = 6;
+
+/// Segmentation feature: global motion
+pub const SEG_LVL_GLOBALMV: usize = 7;
+
+/// `interpolation_filter` value for per-block switchable filters
+pub const SWITCHABLE: u8 = 4;
+
+/// Fractional bits of the warped motion model parameters
+pub const WARPEDMODEL_PREC_BITS: u32 = 16;
+
+/// Segmentation_Feature_Bits
+const SEGMENTATION_FEATURE_BITS: [u8; SEG_LVL_MAX] = [8, 6, 6, 6, 6, 3, 0, 0];
+
+/// Segmentation_Feature_Signed
+const SEGMENTATION_FEATURE_SIGNED: [bool; SEG_LVL_MAX] =
+    [true, true, true, true, true, false, false, false];
+
+/// Segmentation_Feature_Max
+const SEGMENTATION_FEATURE_MAX: [i16; SEG_LVL_MAX] = [255, 63, 63, 63, 63, 7, 0, 0];
+
+/// Default loop filter reference deltas (INTRA_FRAME..ALTREF_FRAME)
+const DEFAULT_LOOP_FILTER_REF_DELTAS: [i8; 8] = [1, 0, 0, 0, -1, 0, -1, -1];
+
+/// Global motion model type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum GlobalMotionType {
+    #[default]
+    Identity = 0,
+    Translation = 1,
+    RotZoom = 2,
+    Affine = 3,
+}
+
+/// Transform mode (`TxMode`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TxMode {
+    /// Only 4x4 transforms (coded lossless frames)
+    Only4x4,
+    /// Largest transform that fits the block
+    #[default]
+    Largest,
+    /// Transform size signalled per block
+    Select,
+}
+
+/// Loop restoration type (`FrameRestorationType`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RestorationType {
+    #[default]
+    None,
+    Wiener,
+    Sgrproj,
+    Switchable,
+}
+
+/// Quantization parameters (spec `quantization_params()`)
+#[derive(Debug, Clone, Default)]
+pub struct QuantizationParams {
+    pub base_q_idx: u8,
+    pub delta_q_y_dc: i8,
+    pub delta_q_u_dc: i8,
+    pub delta_q_u_ac: i8,
+    pub delta_q_v_dc: i8,
+    pub delta_q_v_ac: i8,
+    pub using_qmatrix: bool,
+    pub qm_y: u8,
+    pub qm_u: u8,
+    pub qm_v: u8,
+}
+
+/// Segmentation parameters (spec `segmentation_params()`)
+#[derive(Debug, Clone, Default)]
+pub struct SegmentationParams {
+    pub enabled: bool,
+    pub update_map: bool,
+    pub temporal_update: bool,
+    pub update_data: bool,
+    pub feature_enabled: [[bool; SEG_LVL_MAX]; MAX_SEGMENTS],
+    pub feature_data: [[i16; SEG_LVL_MAX]; MAX_SEGMENTS],
+    /// SegIdPreSkip
+    pub seg_id_pre_skip: bool,
+    /// LastActiveSegId
+    pub last_active_seg_id: u8,
+}
+
+impl SegmentationParams {
+    /// Spec `seg_feature_active_idx()`
+    pub fn feature_active(&self, segment_id: usize, feature: usize) -> bool {
+        self.enabled && self.feature_enabled[segment_id][feature]
+    }
+}
+
+/// Loop filter parameters (spec `loop_filter_params()`)
+#[derive(Debug, Clone)]
+pub struct LoopFilterParams {
+    pub level: [u8; 4],
+    pub sharpness: u8,
+    pub delta_enabled: bool,
+    pub delta_update: bool,
+    pub ref_deltas: [i8; 8],
+    pub mode_deltas: [i8; 2],
+}
+
+impl Default for LoopFilterParams {
+    fn default() -> Self {
+        Self {
+            level: [0; 4],
+            sharpness: 0,
+            delta_enabled: false,
+            delta_update: false,
+            ref_deltas: DEFAULT_LOOP_FILTER_REF_DELTAS,
+            mode_deltas: [0; 2],
+        }
+    }
+}
+
+/// CDEF parameters (spec `cdef_params()`)
+#[derive(Debug, Clone, Default)]
+pub struct CdefParams {
+    pub damping: u8,
+    pub bits: u8,
+    pub y_pri_strength: [u8; 8],
+    pub y_sec_strength: [u8; 8],
+    pub uv_pri_strength: [u8; 8],
+    pub uv_sec_strength: [u8; 8],
+}
+
+/// Loop restoration parameters (spec `lr_params()`)
+#[derive(Debug, Clone, Default)]
+pub struct LoopRestorationParams {
+    pub frame_restoration_type: [RestorationType; 3],
+    /// LoopRestorationSize per plane, in samples
+    pub loop_restoration_size: [u32; 3],
+    pub uses_lr: bool,
+    pub uses_chroma_lr: bool,
+}
+
+/// Film grain parameters (spec `film_grain_params()`)
+#[derive(Debug, Clone, Default)]
+pub struct FilmGrainParams {
+    pub apply_grain: bool,
+    pub grain_seed: u16,
+    pub update_grain: bool,
+    /// (point_y_value, point_y_scaling)
+    pub point_y: Vec<(u8, u8)>,
+    pub chroma_scaling_from_luma: bool,
+    /// (point_cb_value, point_cb_scaling)
+    pub point_cb: Vec<(u8, u8)>,
+    /// (point_cr_value, point_cr_scaling)
+    pub point_cr: Vec<(u8, u8)>,
+    pub grain_scaling_minus_8: u8,
+    pub ar_coeff_lag: u8,
+    pub ar_coeffs_y_plus_128: Vec<u8>,
+    pub ar_coeffs_cb_plus_128: Vec<u8>,
+    pub ar_coeffs_cr_plus_128: Vec<u8>,
+    pub ar_coeff_shift_minus_6: u8,
+    pub grain_scale_shift: u8,
+    pub cb_mult: u8,
+    pub cb_luma_mult: u8,
+    pub cb_offset: u16,
+    pub cr_mult: u8,
+    pub cr_luma_mult: u8,
+    pub cr_offset: u16,
+    pub overlap_flag: bool,
+    pub clip_to_restricted_range: bool,
+}
+
+/// Frame header state saved with a reference frame slot
+///
+/// Per AV1 spec Section 7.20 (Reference frame update process), these are
+/// the values later frame headers read back through `ref_frame_idx`,
+/// `primary_ref_frame` and `film_grain_params_ref_idx`.
+#[derive(Debug, Clone)]
+pub struct RefFrameInfo {
+    /// RefValid
+    pub valid: bool,
+    /// RefFrameId
+    pub frame_id: u32,
+    /// RefFrameType
+    pub frame_type: FrameType,
+    /// RefOrderHint
+    pub order_hint: u32,
+    /// SavedOrderHints, indexed by reference frame (LAST_FRAME..ALTREF_FRAME)
+    pub saved_order_hints: [u32; 8],
+    /// RefShowableFrame
+    pub showable_frame: bool,
+    pub upscaled_width: u32,
+    pub frame_width: u32,
+    pub frame_height: u32,
+    pub render_width: u32,
+    pub render_height: u32,
+    pub mi_cols: u32,
+    pub mi_rows: u32,
+    /// Saved loop filter deltas
+    pub loop_filter_ref_deltas: [i8; 8],
+    pub loop_filter_mode_deltas: [i8; 2],
+    /// Saved segmentation feature data
+    pub segmentation: SegmentationParams,
+    /// SavedGmParams
+    pub gm_params: [[i32; 6]; 8],
+    /// Saved film grain parameters
+    pub film_grain: FilmGrainParams,
+}
+
+impl Default for RefFrameInfo {
+    fn default() -> Self {
+        Self {
+            valid: false,
+            frame_id: 0,
+            frame_type: FrameType::Key,
+            order_hint: 0,
+            saved_order_hints: [0; 8],
+            showable_frame: false,
+            upscaled_width: 0,
+            frame_width: 0,
+            frame_height: 0,
+            render_width: 0,
+            render_height: 0,
+            mi_cols: 0,
+            mi_rows: 0,
+            loop_filter_ref_deltas: DEFAULT_LOOP_FILTER_REF_DELTAS,
+            loop_filter_mode_deltas: [0; 2],
+            segmentation: SegmentationParams::default(),
+            gm_params: default_gm_params(),
+            film_grain: FilmGrainParams::default(),
+        }
+    }
+}
+
+/// Fully parsed uncompressed frame header
+#[derive(Debug, Clone)]
+pub struct UncompressedHeader {
+    pub show_existing_frame: bool,
+    pub frame_to_show_map_idx: u8,
+    pub frame_type: FrameType,
+    pub show_frame: bool,
+    pub showable_frame: bool,
+    pub error_resilient_mode: bool,
+    pub disable_cdf_update: bool,
+    pub allow_screen_content_tools: bool,
+    pub force_integer_mv: bool,
+    pub current_frame_id: u32,
+    pub frame_size_override_flag: bool,
+    /// OrderHint
+    pub order_hint: u32,
+    /// OrderHintBits from the sequence header
+    pub order_hint_bits: u32,
+    pub primary_ref_frame: u8,
+    pub refresh_frame_flags: u8,
+    pub allow_intrabc: bool,
+    pub frame_refs_short_signaling: bool,
+    /// Reference slot of each of LAST_FRAME..ALTREF_FRAME
+    pub ref_frame_idx: [u8; REFS_PER_FRAME],
+    pub allow_high_precision_mv: bool,
+    pub is_filter_switchable: bool,
+    /// 0..3 for a fixed filter, [`SWITCHABLE`] for per-block filters
+    pub interpolation_filter: u8,
+    pub is_motion_mode_switchable: bool,
+    pub use_ref_frame_mvs: bool,
+    /// OrderHints, indexed by reference frame (LAST_FRAME..ALTREF_FRAME)
+    pub order_hints: [u32; 8],
+    /// RefFrameSignBias, indexed by reference frame
+    pub ref_frame_sign_bias: [bool; 8],
+    pub disable_frame_end_update_cdf: bool,
+    pub frame_width: u32,
+    pub frame_height: u32,
+    pub upscaled_width: u32,
+    pub render_width: u32,
+    pub render_height: u32,
+    pub use_superres: bool,
+    pub superres_denom: u32,
+    /// MiCols (frame width in 4x4 units, rounded up to 8 samples)
+    pub mi_cols: u32,
+    /// MiRows
+    pub mi_rows: u32,
+    pub tile_info: TileInfo,
+    pub quantization: QuantizationParams,
+    pub segmentation: SegmentationParams,
+    pub delta_q_present: bool,
+    pub delta_q_res: u8,
+    pub delta_lf_present: bool,
+    pub delta_lf_res: u8,
+    pub delta_lf_multi: bool,
+    /// LosslessArray
+    pub lossless_array: [bool; MAX_SEGMENTS],
+    pub coded_lossless: bool,
+    pub all_lossless: bool,
+    pub loop_filter: LoopFilterParams,
+    pub cdef: CdefParams,
+    pub loop_restoration: LoopRestorationParams,
+    pub tx_mode: TxMode,
+    pub reference_select: bool,
+    pub skip_mode_present: bool,
+    /// SkipModeFrame
+    pub skip_mode_frame: [u8; 2],
+    pub allow_warped_motion: bool,
+    pub reduced_tx_set: bool,
+    /// GmType, indexed by reference frame
+    pub gm_type: [GlobalMotionType; 8],
+    /// gm_params, indexed by reference frame
+    pub gm_params: [[i32; 6]; 8],
+    pub film_grain: FilmGrainParams,
+    /// Size of the header in bytes, including the trailing byte alignment
+    pub header_bytes: usize,
+}
+
+impl Default for UncompressedHeader {
+    fn default() -> Self {
+        Self {
+            show_existing_frame: false,
+            frame_to_show_map_idx: 0,
+            frame_type: FrameType::Key,
+            show_frame: true,
+            showable_frame: false,
+            error_resilient_mode: true,
+            disable_cdf_update: false,
+            allow_screen_content_tools: false,
+            force_integer_mv: false,
+            current_frame_id: 0,
+            frame_size_override_flag: false,
+            order_hint: 0,
+            order_hint_bits: 0,
+            primary_ref_frame: PRIMARY_REF_NONE,
+            refresh_frame_flags: 0xFF,
+            allow_intrabc: false,
+            frame_refs_short_signaling: false,
+            ref_frame_idx: [0; REFS_PER_FRAME],
+            allow_high_precision_mv: false,
+            is_filter_switchable: false,
+            interpolation_filter: 0,
+            is_motion_mode_switchable: false,
+            use_ref_frame_mvs: false,
+            order_hints: [0; 8],
+            ref_frame_sign_bias: [false; 8],
+            disable_frame_end_update_cdf: true,
+            frame_width: 0,
+            frame_height: 0,
+            upscaled_width: 0,
+            render_width: 0,
+            render_height: 0,
+            use_superres: false,
+            superres_denom: 8,
+            mi_cols: 0,
+            mi_rows: 0,
+            tile_info: TileInfo::single_tile(0, 0, 64),
+            quantization: QuantizationParams::default(),
+            segmentation: SegmentationParams::default(),
+            delta_q_present: false,
+            delta_q_res: 0,
+            delta_lf_present: false,
+            delta_lf_res: 0,
+            delta_lf_multi: false,
+            lossless_array: [false; MAX_SEGMENTS],
+            coded_lossless: false,
+            all_lossless: false,
+            loop_filter: LoopFilterParams::default(),
+            cdef: CdefParams::default(),
+            loop_restoration: LoopRestorationParams::default(),
+            tx_mode: TxMode::Largest,
+            reference_select: false,
+            skip_mode_present: false,
+            skip_mode_frame: [0; 2],
+            allow_warped_motion: false,
+            reduced_tx_set: false,
+            gm_type: [GlobalMotionType::Identity; 8],
+            gm_params: default_gm_params(),
+            film_grain: FilmGrainParams::default(),
+            header_bytes: 0,
+        }
+    }
+}
+
+impl UncompressedHeader {
+    /// FrameIsIntra
+    pub fn frame_is_intra(&self) -> bool {
+        matches!(self.frame_type, FrameType::Key | FrameType::IntraOnly)
+    }
+
+    /// Spec `get_relative_dist()` between two order hints
+    pub fn get_relative_dist(&self, a: u32, b: u32) -> i32 {
+        relative_dist(self.order_hint_bits, a, b)
+    }
+
+    /// Spec `get_qindex()`
+    ///
+    /// `current_q_index` is `CurrentQIndex`, the quantizer index after the
+    /// delta Q updates decoded so far in the tile.
+    pub fn get_qindex(&self, ignore_delta_q: bool, segment_id: usize, current_q_index: u8) -> u8 {
+        let base = self.quantization.base_q_idx as i32;
+        let use_delta = !ignore_delta_q && self.delta_q_present;
+        if self.segmentation.feature_active(segment_id, SEG_LVL_ALT_Q) {
+            let data = self.segmentation.feature_data[segment_id][SEG_LVL_ALT_Q] as i32;
+            let q = if use_delta {
+                current_q_index as i32 + data
+            } else {
+                base + data
+            };
+            q.clamp(0, 255) as u8
+        } else if use_delta {
+            current_q_index
+        } else {
+            base as u8
+        }
+    }
+
+    /// State saved into the reference slots refreshed by this frame
+    pub fn ref_frame_info(&self) -> RefFrameInfo {
+        RefFrameInfo {
+            valid: true,
+            frame_id: self.current_frame_id,
+            frame_type: self.frame_type,
+            order_hint: self.order_hint,
+            saved_order_hints: self.order_hints,
+            showable_frame: self.showable_frame,
+            upscaled_width: self.upscaled_width,
+            frame_width: self.frame_width,
+            frame_height: self.frame_height,
+            render_width: self.render_width,
+            render_height: self.render_height,
+            mi_cols: self.mi_cols,
+            mi_rows: self.mi_rows,
+            loop_filter_ref_deltas: self.loop_filter.ref_deltas,
+            loop_filter_mode_deltas: self.loop_filter.mode_deltas,
+            segmentation: self.segmentation.clone(),
+            gm_params: self.gm_params,
+            film_grain: self.film_grain.clone(),
+        }
+    }
+}
+
+/// Spec `get_relative_dist()` for `order_hint_bits` bit order hints
+pub fn relative_dist(order_hint_bits: u32, a: u32, b: u32) -> i32 {
+    if order_hint_bits == 0 {
+        return 0;
+    }
+    let diff = a as i32 - b as i32;
+    let m = 1 << (order_hint_bits - 1);
+    (diff & (m - 1)) - (diff & m)
+}
+
+/// Reference frame names, indexed by reference frame (INTRA_FRAME..ALTREF_FRAME)
+const REF_FRAME_NAMES: [&str; 8] = [
+    "INTRA_FRAME",
+    "LAST_FRAME",
+    "LAST2_FRAME",
+    "LAST3_FRAME",
+    "GOLDEN_FRAME",
+    "BWDREF_FRAME",
+    "ALTREF2_FRAME",
+    "ALTREF_FRAME",
+];
+
+/// Identity global motion parameters for every reference frame
+fn default_gm_params() -> [[i32; 6]; 8] {
+    let identity = [
+        0,
+        0,
+        1 << WARPEDMODEL_PREC_BITS,
+        0,
+        0,
+        1 << WARPEDMODEL_PREC_BITS,
+    ];
+    [identity; 8]
+}
+
+/// Parse `uncompressed_header()` from a frame header or frame OBU payload
+///
+/// `refs` is the state of the reference frame slots before this frame,
+/// `temporal_id`/`spatial_id` come from the OBU extension header.
+pub fn parse_uncompressed_header(
+    data: &[u8],
+    seq: &SequenceHeader,
+    refs: &[RefFrameInfo; NUM_REF_FRAMES],
+    temporal_id: u8,
+    spatial_id: u8,
+) -> Result<UncompressedHeader> {
+    let mut reader = TrackedBitReader::new(data, 0);
+    let mut header =
+        HeaderParser::new(&mut reader, None, seq, refs).parse(temporal_id, spatial_id)?;
+    header.header_bytes = reader.position().div_ceil(8) as usize;
+    Ok(header)
+}
+
+/// Parse `uncompressed_header()`, adding every syntax element to `builder`
+///
+/// Fields land under the builder's current container, with nested
+/// containers for the spec's sub-syntax structures (`frame_size`,
+/// `tile_info`, `quantization_params`, ...). `header_bytes` counts from
+/// the reader position on entry.
+pub(crate) fn parse_uncompressed_header_syntax(
+    reader: &mut TrackedBitReader,
+    builder: &mut SyntaxBuilder,
+    seq: &SequenceHeader,
+    refs: &[RefFrameInfo; NUM_REF_FRAMES],
+    temporal_id: u8,
+    spatial_id: u8,
+) -> Result<UncompressedHeader> {
+    let start = reader.position();
+    let mut header =
+        HeaderParser::new(reader, Some(builder), seq, refs).parse(temporal_id, spatial_id)?;
+    header.header_bytes = (reader.position() - start).div_ceil(8) as usize;
+    Ok(header)
+}
+
+struct HeaderParser<'a, 'b> {
+    r: &'b mut TrackedBitReader<'a>,
+    /// Receives a node per syntax element when building a syntax tree
+    syntax: Option<&'b mut SyntaxBuilder>,
+    seq: &'b SequenceHeader,
+    /// Reference slots, updated by mark_ref_frames and ref_order_hint
+    refs: [RefFrameInfo; NUM_REF_FRAMES],
+}
+
+impl<'a, 'b> HeaderParser<'a, 'b> {
+    fn new(
+        r: &'b mut TrackedBitReader<'a>,
+        syntax: Option<&'b mut SyntaxBuilder>,
+        seq: &'b SequenceHeader,
+        refs: &[RefFrameInfo; NUM_REF_FRAMES],
+    ) -> Self {
+        Self {
+            r,
+            syntax,
+            seq,
+            refs: refs.clone(),
+        }
+    }
+
+    /// Read `n` bits without recording a syntax element
+    fn bits(&mut self, n: u32) -> Result<u32> {
+        self.r.reader.read_bits(n as u8)
+    }
+
+    fn record(&mut self, name: &str, range: BitRange, value: impl std::fmt::Display) {
+        if let Some(builder) = self.syntax.as_deref_mut() {
+            builder.add_field(name, range, value.to_string());
+        }
+    }
+
+    fn begin(&mut self, name: &str) {
+        let start = self.r.position();
+        if let Some(builder) = self.syntax.as_deref_mut() {
+            builder.push_container(name, start);
+        }
+    }
+
+    fn end(&mut self) {
+        let end = self.r.position();
+        if let Some(builder) = self.syntax.as_deref_mut() {
+            builder.pop_container(end);
+        }
+    }
+
+    /// Spec `f(n)`
+    fn f(&mut self, name: &str, n: u32) -> Result<u32> {
+        let (value, range) = self.r.read_bits_tracked(n as u8)?;
+        if n > 0 {
+            self.record(name, range, value);
+        }
+        Ok(value)
+    }
+
+    fn flag(&mut self, name: &str) -> Result<bool> {
+        Ok(self.f(name, 1)? != 0)
+    }
+
+    /// Spec `su(n)`
+    fn su(&mut self, name: &str, n: u32) -> Result<i32> {
+        let (value, range) = self.r.read_su_tracked(n as u8)?;
+        self.record(name, range, value);
+        Ok(value)
+    }
+
+    /// Spec `ns(n)`
+    fn ns(&mut self, name: &str, n: u32) -> Result<u32> {
+        let start = self.r.position();
+        let value = self.read_ns(n)?;
+        let range = BitRange::new(start, self.r.position());
+        self.record(name, range, value);
+        Ok(value)
+    }
+
+    fn read_ns(&mut self, n: u32) -> Result<u32> {
+        let w = 32 - n.leading_zeros();
+        let m = (1 << w) - n;
+        let v = self.bits(w - 1)?;
+        if v < m {
+            return Ok(v);
+        }
+        let extra_bit = self.bits(1)?;
+        Ok((v << 1) - m + extra_bit)
+    }
+
+    fn parse(mut self, temporal_id: u8, spatial_id: u8) -> Result<UncompressedHeader> {
+        let seq = self.seq;
+        let id_len = match (
+            seq.frame_id_numbers_present,
+            seq.additional_frame_id_length_minus_1,
+            seq.delta_frame_id_length_minus_2,
+        ) {
+            (true, Some(additional), Some(delta)) => additional as u32 + delta as u32 + 3,
+            _ => 0,
+        };
+        let order_hint_bits = match seq.order_hint_bits_minus_1 {
+            Some(bits) if seq.enable_order_hint => bits as u32 + 1,
+            _ => 0,
+        };
+        let all_frames = 0xFF;
+        let decoder_model_info = seq.decoder_model_info.as_ref();
+        let equal_picture_interval = seq
+            .timing_info
+            .as_ref()
+            .is_some_and(|t| t.equal_picture_interval);
+
+        let mut h = UncompressedHeader {
+            order_hint_bits,
+            ..Default::default()
+        };
+
+        if !seq.reduced_still_picture_header {
+            h.show_existing_frame = self.flag("show_existing_frame")?;
+            if h.show_existing_frame {
+                h.frame_to_show_map_idx = self.f("frame_to_show_map_idx", 3)? as u8;
+                if let (Some(info), false) = (decoder_model_info, equal_picture_interval) {
+                    self.begin("temporal_point_info");
+                    let n = info.frame_presentation_time_length_minus_1 as u32 + 1;
+                    self.f("frame_presentation_time", n)?;
+                    self.end();
+                }
+                h.refresh_frame_flags = 0;
+                if seq.frame_id_numbers_present {
+                    self.f("display_frame_id", id_len)?;
+                }
+                let shown = &self.refs[h.frame_to_show_map_idx as usize];
+                if !shown.valid {
+                    return Err(BitvueError::InvalidData(format!(
+                        "show_existing_frame of empty reference slot {}",
+                        h.frame_to_show_map_idx
+                    )));
+                }
+                h.frame_type = shown.frame_type;
+                if h.frame_type == FrameType::Key {
+                    h.refresh_frame_flags = all_frames;
+                }
+                if seq.film_grain_params_present {
+                    h.film_grain = shown.film_grain.clone();
+                }
+                h.showable_frame = shown.showable_frame;
+                h.order_hint = shown.order_hint;
+                h.order_hints = shown.saved_order_hints;
+                h.upscaled_width = shown.upscaled_width;
+                h.frame_width = shown.frame_width;
+                h.frame_height = shown.frame_height;
+                h.render_width = shown.render_width;
+                h.render_height = shown.render_height;
+                h.mi_cols = shown.mi_cols;
+                h.mi_rows = shown.mi_rows;
+                h.current_frame_id = shown.frame_id;
+                h.loop_filter.ref_deltas = shown.loop_filter_ref_deltas;
+                h.loop_filter.mode_deltas = shown.loop_filter_mode_deltas;
+                h.segmentation = shown.segmentation.clone();
+                h.gm_params = shown.gm_params;
+                return Ok(h);
+            }
+
+            let (frame_type, range) = self.r.read_bits_tracked(2)?;
+            h.frame_type = FrameType::from_av1_bits(frame_type);
+            self.record(
+                "frame_type",
+                range,
+                format_args!("{} ({})", frame_type, h.frame_type),
+            );
+            h.show_frame = self.flag("show_frame")?;
+            if h.show_frame {
+                if let (Some(info), false) = (decoder_model_info, equal_picture_interval) {
+                    self.begin("temporal_point_info");
+                    let n = info.frame_presentation_time_length_minus_1 as u32 + 1;
+                    self.f("frame_presentation_time", n)?;
+                    self.end();
+                }
+                h.showable_frame = h.frame_type != FrameType::Key;
+            } else {
+                h.showable_frame = self.flag("showable_frame")?;
+            }
+            h.error_resilient_mode = if h.frame_type == FrameType::Switch
+                || (h.frame_type == FrameType::Key && h.show_frame)
+            {
+                let at = self.r.position();
+                self.record(
+                    "error_resilient_mode",
+                    BitRange::new(at, at),
+                    "1 (implicit)",
+                );
+                true
+            } else {
+                self.flag("error_resilient_mode")?
+            };
+        } else {
+            // A reduced still picture is always a shown key frame
+            let at = self.r.position();
+            for (name, value) in [
+                ("frame_type", "0 (KEY, implicit)"),
+                ("show_frame", "1 (implicit)"),
+                ("error_resilient_mode", "1 (implicit)"),
+            ] {
+                self.record(name, BitRange::new(at, at), value);
+            }
+        }
+        let frame_is_intra = h.frame_is_intra();
+
+        if h.frame_type == FrameType::Key && h.show_frame {
+            for slot in self.refs.iter_mut() {
+                slot.valid = false;
+                slot.order_hint = 0;
+            }
+        }
+
+        h.disable_cdf_update = self.flag("disable_cdf_update")?;
+        h.allow_screen_content_tools = if seq.seq_force_screen_content_tools == 2 {
+            self.flag("allow_screen_content_tools")?
+        } else {
+            seq.seq_force_screen_content_tools != 0
+        };
+        if h.allow_screen_content_tools {
+            h.force_integer_mv = if seq.seq_force_integer_mv == 2 {
+                self.flag("force_integer_mv")?
+            } else {
+                seq.seq_force_integer_mv != 0
+            };
+        }
+        if frame_is_intra {
+            h.force_integer_mv = true;
+        }
+
+        if seq.frame_id_numbers_present {
+            h.current_frame_id = self.f("current_frame_id", id_len)?;
+            self.mark_ref_frames(id_len, h.current_frame_id);
+        }
+
+        h.frame_size_override_flag = if h.frame_type == FrameType::Switch {
+            true
+        } else if seq.reduced_still_picture_header {
+            false
+        } else {
+            self.flag("frame_size_override_flag")?
+        };
+        h.order_hint = self.f("order_hint", order_hint_bits)?;
+        h.primary_ref_frame = if frame_is_intra || h.error_resilient_mode {
+            PRIMARY_REF_NONE
+        } else {
+            self.f("primary_ref_frame", 3)? as u8
+        };
+
+        if let Some(info) = decoder_model_info {
+            let buffer_removal_time_present = self.flag("buffer_removal_time_present_flag")?;
+            if buffer_removal_time_present {
+                for (op_num, op) in seq.operating_points.iter().enumerate() {
+                    if op.decoder_model_present {
+                        let in_temporal_layer = (op.idc >> temporal_id) & 1 != 0;
+                        let in_spatial_layer = (op.idc >> (spatial_id + 8)) & 1 != 0;
+                        if op.idc == 0 || (in_temporal_layer && in_spatial_layer) {
+                            let n = info.buffer_removal_time_length_minus_1 as u32 + 1;
+                            self.f(&format!("buffer_removal_time[{}]", op_num), n)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        h.refresh_frame_flags = if h.frame_type == FrameType::Switch
+            || (h.frame_type == FrameType::Key && h.show_frame)
+        {
+            all_frames
+        } else {
+            let (flags, range) = self.r.read_bits_tracked(8)?;
+            self.record(
+                "refresh_frame_flags",
+                range,
+                format_args!("0x{:02X}", flags),
+            );
+            flags as u8
+        };
+
+        if (!frame_is_intra || h.refresh_frame_flags != all_frames)
+            && h.error_resilient_mode
+            && seq.enable_order_hint
+        {
+            for i in 0..NUM_REF_FRAMES {
+                let ref_order_hint = self.f(&format!("ref_order_hint[{}]", i), order_hint_bits)?;
+                if ref_order_hint != self.refs[i].order_hint {
+                    self.refs[i].valid = false;
+                    self.refs[i].order_hint = ref_order_hint;
+                }
+            }
+        }
+
+        if frame_is_intra {
+            self.frame_size(&mut h)?;
+            self.render_size(&mut h)?;
+            if h.allow_screen_content_tools && h.upscaled_width == h.frame_width {
+                h.allow_intrabc = self.flag("allow_intrabc")?;
+            }
+        } else {
+            if seq.enable_order_hint {
+                h.frame_refs_short_signaling = self.flag("frame_refs_short_signaling")?;
+                if h.frame_refs_short_signaling {
+                    let last_frame_idx = self.f("last_frame_idx", 3)? as u8;
+                    let gold_frame_idx = self.f("gold_frame_idx", 3)? as u8;
+                    h.ref_frame_idx = self.set_frame_refs(&h, last_frame_idx, gold_frame_idx);
+                }
+            }
+            for i in 0..REFS_PER_FRAME {
+                if !h.frame_refs_short_signaling {
+                    h.ref_frame_idx[i] = self.f(&format!("ref_frame_idx[{}]", i), 3)? as u8;
+                }
+                if seq.frame_id_numbers_present {
+                    let n = seq.delta_frame_id_length_minus_2.unwrap_or(0) as u32 + 2;
+                    self.f(&format!("delta_frame_id_minus_1[{}]", i), n)?;
+                }
+            }
+            if h.frame_size_override_flag && !h.error_resilient_mode {
+                self.frame_size_with_refs(&mut h)?;
+            } else {
+                self.frame_size(&mut h)?;
+                self.render_size(&mut h)?;
+            }
+            h.allow_high_precision_mv = if h.force_integer_mv {
+                false
+            } else {
+                self.flag("allow_high_precision_mv")?
+            };
+            self.begin("read_interpolation_filter");
+            h.is_filter_switchable = self.flag("is_filter_switchable")?;
+            h.interpolation_filter = if h.is_filter_switchable {
+                SWITCHABLE
+            } else {
+                self.f("interpolation_filter", 2)? as u8
+            };
+            self.end();
+            h.is_motion_mode_switchable = self.flag("is_motion_mode_switchable")?;
+            h.use_ref_frame_mvs = if h.error_resilient_mode || !seq.enable_ref_frame_mvs {
+                false
+            } else {
+                self.flag("use_ref_frame_mvs")?
+            };
+            for i in 0..REFS_PER_FRAME {
+                let ref_frame = i + 1;
+                let hint = self.refs[h.ref_frame_idx[i] as usize].order_hint;
+                h.order_hints[ref_frame] = hint;
+                h.ref_frame_sign_bias[ref_frame] =
+                    seq.enable_order_hint && h.get_relative_dist(hint, h.order_hint) > 0;
+            }
+        }
+
+        h.disable_frame_end_update_cdf = if seq.reduced_still_picture_header || h.disable_cdf_update
+        {
+            true
+        } else {
+            self.flag("disable_frame_end_update_cdf")?
+        };
+
+        // load_previous() / setup_past_independence()
+        let mut prev_gm_params = default_gm_params();
+        if h.primary_ref_frame != PRIMARY_REF_NONE {
+            let prev = &self.refs[h.ref_frame_idx[h.primary_ref_frame as usize] as usize];
+            prev_gm_params = prev.gm_params;
+            h.loop_filter.ref_deltas = prev.loop_filter_ref_deltas;
+            h.loop_filter.mode_deltas = prev.loop_filter_mode_deltas;
+            h.segmentation.feature_enabled = prev.segmentation.feature_enabled;
+            h.segmentation.feature_data = prev.segmentation.feature_data;
+        }
+
+        self.tile_info(&mut h)?;
+        self.quantization_params(&mut h)?;
+        self.segmentation_params(&mut h)?;
+        self.delta_q_lf_params(&mut h)?;
+
+        h.coded_lossless = true;
+        for segment_id in 0..MAX_SEGMENTS {
+            let q = &h.quantization;
+            let qindex = h.get_qindex(true, segment_id, 0);
+            h.lossless_array[segment_id] = qindex == 0
+                && q.delta_q_y_dc == 0
+                && q.delta_q_u_ac == 0
+                && q.delta_q_u_dc == 0
+                && q.delta_q_v_ac == 0
+                && q.delta_q_v_dc == 0;
+            if !h.lossless_array[segment_id] {
+                h.coded_lossless = false;
+            }
+        }
+        h.all_lossless = h.coded_lossless && h.frame_width == h.upscaled_width;
+
+        self.loop_filter_params(&mut h)?;
+        self.cdef_params(&mut h)?;
+        self.lr_params(&mut h)?;
+
+        h.tx_mode = if h.coded_lossless {
+            TxMode::Only4x4
+        } else if self.flag("tx_mode_select")? {
+            TxMode::Select
+        } else {
+            TxMode::Largest
+        };
+        h.reference_select = if frame_is_intra {
+            false
+        } else {
+            self.flag("reference_select")?
+        };
+        self.skip_mode_params(&mut h)?;
+        h.allow_warped_motion =
+            if frame_is_intra || h.error_resilient_mode || !seq.enable_warped_motion {
+                false
+            } else {
+                self.flag("allow_warped_motion")?
+            };
+        h.reduced_tx_set = self.flag("reduced_tx_set")?;
+        self.global_motion_params(&mut h, &prev_gm_params)?;
+        self.film_grain_params(&mut h)?;
+
+        Ok(h)
+    }
+
+    /// Spec `mark_ref_frames()`
+    fn mark_ref_frames(&mut self, id_len: u32, current_frame_id: u32) {
+        let diff_len = self.seq.delta_frame_id_length_minus_2.unwrap_or(0) as u32 + 2;
+        let cur = current_frame_id as i64;
+        let diff = 1i64 << diff_len;
+        for slot in self.refs.iter_mut() {
+            let id = slot.frame_id as i64;
+            let stale = if cur > diff {
+                id > cur || id < cur - diff
+            } else {
+                id > cur && id < (1i64 << id_len) + cur - diff
+            };
+            if stale {
+                slot.valid = false;
+            }
+        }
+    }
+
+    /// Spec `frame_size()` followed by `superres_params()` and
+    /// `compute_image_size()`
+    fn frame_size(&mut self, h: &mut UncompressedHeader) -> Result<()> {
+        let seq = self.seq;
+        self.begin("frame_size");
+        if h.frame_size_override_flag {
+            h.frame_width = self.f(
+                "frame_width_minus_1",
+                seq.frame_width_bits_minus_1 as u32 + 1,
+            )? + 1;
+            h.frame_height = self.f(
+                "frame_height_minus_1",
+                seq.frame_height_bits_minus_1 as u32 + 1,
+            )? + 1;
+        } else {
+            h.frame_width = seq.max_frame_width;
+            h.frame_height = seq.max_frame_height;
+        }
+        self.superres_params(h)?;
+        self.end();
+        Ok(())
+    }
+
+    fn superres_params(&mut self, h: &mut UncompressedHeader) -> Result<()> {
+        if self.seq.enable_superres {
+            self.begin("superres_params");
+            h.use_superres = self.flag("use_superres")?;
+            h.superres_denom = if h.use_superres {
+                self.f("coded_denom", 3)? + 9
+            } else {
+                8
+            };
+            self.end();
+        } else {
+            h.use_superres = false;
+            h.superres_denom = 8;
+        }
+        h.upscaled_width = h.frame_width;
+        h.frame_width = (h.upscaled_width * 8 + h.superres_denom / 2) / h.superres_denom;
+        h.mi_cols = 2 * h.frame_width.div_ceil(8);
+        h.mi_rows = 2 * h.frame_height.div_ceil(8);
+        if h.frame_width == 0 || h.frame_height == 0 {
+            return Err(BitvueError::InvalidData(
+                "Frame has zero width or height".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn render_size(&mut self, h: &mut UncompressedHeader) -> Result<()> {
+        self.begin("render_size");
+        if self.flag("render_and_frame_size_different")? {
+            h.render_width = self.f("render_width_minus_1", 16)? + 1;
+            h.render_height = self.f("render_height_minus_1", 16)? + 1;
+        } else {
+            h.render_width = h.upscaled_width;
+            h.render_height = h.frame_height;
+        }
+        self.end();
+        Ok(())
+    }
+
+    fn frame_size_with_refs(&mut self, h: &mut UncompressedHeader) -> Result<()> {
+        self.begin("frame_size_with_refs");
+        let mut found_ref = false;
+        for i in 0..REFS_PER_FRAME {
+            if self.flag(&format!("found_ref[{}]", i))? {
+                let slot = &self.refs[h.ref_frame_idx[i] as usize];
+                h.upscaled_width = slot.upscaled_width;
+                h.frame_width = h.upscaled_width;
+                h.frame_height = slot.frame_height;
+                h.render_width = slot.render_width;
+                h.render_height = slot.render_height;
+                found_ref = true;
+                break;
+            }
+        }
+        if found_ref {
+            self.superres_params(h)?;
+        } else {
+            self.frame_size(h)?;
+            self.render_size(h)?;
+        }
+        self.end();
+        Ok(())
+    }
+
+    /// Spec Section 7.8 (Set frame refs process)
+    fn set_frame_refs(
+        &self,
+        h: &UncompressedHeader,
+        last_frame_idx: u8,
+        gold_frame_idx: u8,
+    ) -> [u8; REFS_PER_FRAME] {
+        let mut ref_frame_idx = [-1i32; REFS_PER_FRAME];
+        ref_frame_idx[0] = last_frame_idx as i32;
+        ref_frame_idx[3] = gold_frame_idx as i32;
+        let mut used = [false; NUM_REF_FRAMES];
+        used[last_frame_idx as usize] = true;
+        used[gold_frame_idx as usize] = true;
+
+        let cur_frame_hint = 1i32 << (h.order_hint_bits - 1);
+        let shifted: Vec<i32> = self
+            .refs
+            .iter()
+            .map(|slot| cur_frame_hint + h.get_relative_dist(slot.order_hint, h.order_hint))
+            .collect();
+
+        // find_latest_backward / find_earliest_backward / find_latest_forward
+        let find = |used: &[bool; NUM_REF_FRAMES], backward: bool, latest: bool| {
+            let mut best: Option<(usize, i32)> = None;
+            for (i, &hint) in shifted.iter().enumerate() {
+                let side = if backward {
+                    hint >= cur_frame_hint
+                } else {
+                    hint < cur_frame_hint
+                };
+                if used[i] || !side {
+                    continue;
+                }
+                let better = match best {
+                    None => true,
+                    Some((_, b)) if latest => hint >= b,
+                    Some((_, b)) => hint < b,
+                };
+                if better {
+                    best = Some((i, hint));
+                }
+            }
+            best.map(|(i, _)| i)
+        };
+
+        if let Some(r) = find(&used, true, true) {
+            ref_frame_idx[6] = r as i32;
+            used[r] = true;
+        }
+        if let Some(r) = find(&used, true, false) {
+            ref_frame_idx[4] = r as i32;
+            used[r] = true;
+        }
+        if let Some(r) = find(&used, true, false) {
+            ref_frame_idx[5] = r as i32;
+            used[r] = true;
+        }
+        // Ref_Frame_List: LAST2, LAST3, BWDREF, ALTREF2, ALTREF
+        for i in [1, 2, 4, 5, 6] {
+            if ref_frame_idx[i] < 0 {
+                if let Some(r) = find(&used, false, true) {
+                    ref_frame_idx[i] = r as i32;
+                    used[r] = true;
+                }
+            }
+        }
+
+        let mut earliest: Option<(usize, i32)> = None;
+        for (i, &hint) in shifted.iter().enumerate() {
+            if earliest.is_none_or(|(_, e)| hint < e) {
+                earliest = Some((i, hint));
+            }
+        }
+        let fallback = earliest.map_or(0, |(i, _)| i as i32);
+        ref_frame_idx.map(|idx| if idx < 0 { fallback } else { idx } as u8)
+    }
+
+    /// Spec `tile_info()`
+    fn tile_info(&mut self, h: &mut UncompressedHeader) -> Result<()> {
+        let use_128 = self.seq.use_128x128_superblock;
+        let sb_cols = if use_128 {
+            (h.mi_cols + 31) >> 5
+        } else {
+            (h.mi_cols + 15) >> 4
+        };
+        let sb_rows = if use_128 {
+            (h.mi_rows + 31) >> 5
+        } else {
+            (h.mi_rows + 15) >> 4
+        };
+        let sb_size_log2 = if use_128 { 7 } else { 6 };
+        let max_tile_width_sb = 4096 >> sb_size_log2;
+        let mut max_tile_area_sb = (4096 * 2304) >> (2 * sb_size_log2);
+        let min_log2_tile_cols = tile_log2(max_tile_width_sb, sb_cols);
+        let max_log2_tile_cols = tile_log2(1, sb_cols.min(64));
+        let max_log2_tile_rows = tile_log2(1, sb_rows.min(64));
+        let min_log2_tiles = min_log2_tile_cols.max(tile_log2(max_tile_area_sb, sb_rows * sb_cols));
+
+        let mut col_starts = Vec::new();
+        let mut row_starts = Vec::new();
+        let tile_cols_log2;
+        let tile_rows_log2;
+
+        self.begin("tile_info");
+        if self.flag("uniform_tile_spacing_flag")? {
+            let mut cols_log2 = min_log2_tile_cols;
+            while cols_log2 < max_log2_tile_cols {
+                let name = format!(
+                    "increment_tile_cols_log2[{}]",
+                    cols_log2 - min_log2_tile_cols
+                );
+                if !self.flag(&name)? {
+                    break;
+                }
+                cols_log2 += 1;
+            }
+            let tile_width_sb = (sb_cols + (1 << cols_log2) - 1) >> cols_log2;
+            col_starts.extend((0..sb_cols).step_by(tile_width_sb as usize));
+
+            let min_log2_tile_rows = min_log2_tiles.saturating_sub(cols_log2);
+            let mut rows_log2 = min_log2_tile_rows;
+            while rows_log2 < max_log2_tile_rows {
+                let name = format!(
+                    "increment_tile_rows_log2[{}]",
+                    rows_log2 - min_log2_tile_rows
+                );
+                if !self.flag(&name)? {
+                    break;
+                }
+                rows_log2 += 1;
+            }
+            let tile_height_sb = (sb_rows + (1 << rows_log2) - 1) >> rows_log2;
+            row_starts.extend((0..sb_rows).step_by(tile_height_sb as usize));
+            tile_cols_log2 = cols_log2;
+            tile_rows_log2 = rows_log2;
+        } else {
+            let mut widest_tile_sb = 0;
+            let mut start_sb = 0;
+            while start_sb < sb_cols {
+                let name = format!("width_in_sbs_minus_1[{}]", col_starts.len());
+                col_starts.push(start_sb);
+                let max_width = (sb_cols - start_sb).min(max_tile_width_sb);
+                let size_sb = self.ns(&name, max_width)? + 1;
+                widest_tile_sb = widest_tile_sb.max(size_sb);
+                start_sb += size_sb;
+            }
+            tile_cols_log2 = tile_log2(1, col_starts.len() as u32);
+
+            max_tile_area_sb = if min_log2_tiles > 0 {
+                (sb_rows * sb_cols) >> (min_log2_tiles + 1)
+            } else {
+                sb_rows * sb_cols
+            };
+            let max_tile_height_sb = (max_tile_area_sb / widest_tile_sb).max(1);
+            let mut start_sb = 0;
+            while start_sb < sb_rows {
+                let name = format!("height_in_sbs_minus_1[{}]", row_starts.len());
+                row_starts.push(start_sb);
+                let max_height = (sb_rows - start_sb).min(max_tile_height_sb);
+                start_sb += self.ns(&name, max_height)? + 1;
+            }
+            tile_rows_log2 = tile_log2(1, row_starts.len() as u32);
+        }
+
+        let mut info = TileInfo::new(col_starts.len() as u32, row_starts.len() as u32)?;
+        col_starts.push(sb_cols);
+        row_starts.push(sb_rows);
+        info.tile_col_start_sb = col_starts;
+        info.tile_row_start_sb = row_starts;
+        info.use_128x128_superblock = use_128;
+        info.tile_cols_log2 = tile_cols_log2;
+        info.tile_rows_log2 = tile_rows_log2;
+        if tile_cols_log2 > 0 || tile_rows_log2 > 0 {
+            info.context_update_tile_id =
+                self.f("context_update_tile_id", tile_rows_log2 + tile_cols_log2)?;
+            info.tile_size_bytes = self.f("tile_size_bytes_minus_1", 2)? as u8 + 1;
+            if info.context_update_tile_id as usize >= info.tile_count() {
+                return Err(BitvueError::InvalidData(format!(
+                    "context_update_tile_id {} out of range",
+                    info.context_update_tile_id
+                )));
+            }
+        }
+        self.end();
+        h.tile_info = info;
+        Ok(())
+    }
+
+    /// Spec `read_delta_q()`, as a container named after the delta
+    fn read_delta_q(&mut self, name: &str) -> Result<i8> {
+        self.begin(name);
+        let delta = if self.flag("delta_coded")? {
+            self.su("delta_q", 7)? as i8
+        } else {
+            0
+        };
+        self.end();
+        Ok(delta)
+    }
+
+    fn quantization_params(&mut self, h: &mut UncompressedHeader) -> Result<()> {
+        let color = &self.seq.color_config;
+        let q = &mut h.quantization;
+        self.begin("quantization_params");
+        q.base_q_idx = self.f("base_q_idx", 8)? as u8;
+        q.delta_q_y_dc = self.read_delta_q("delta_q_y_dc")?;
+        if color.num_planes > 1 {
+            let diff_uv_delta = color.separate_uv_delta_q && self.flag("diff_uv_delta")?;
+            q.delta_q_u_dc = self.read_delta_q("delta_q_u_dc")?;
+            q.delta_q_u_ac = self.read_delta_q("delta_q_u_ac")?;
+            if diff_uv_delta {
+                q.delta_q_v_dc = self.read_delta_q("delta_q_v_dc")?;
+                q.delta_q_v_ac = self.read_delta_q("delta_q_v_ac")?;
+            } else {
+                q.delta_q_v_dc = q.delta_q_u_dc;
+                q.delta_q_v_ac = q.delta_q_u_ac;
+            }
+        }
+        q.using_qmatrix = self.flag("using_qmatrix")?;
+        if q.using_qmatrix {
+            q.qm_y = self.f("qm_y", 4)? as u8;
+            q.qm_u = self.f("qm_u", 4)? as u8;
+            q.qm_v = if color.separate_uv_delta_q {
+                self.f("qm_v", 4)? as u8
+            } else {
+                q.qm_u
+            };
+        }
+        self.end();
+        Ok(())
+    }
+
+    fn segmentation_params(&mut self, h: &mut UncompressedHeader) -> Result<()> {
+        let primary_ref_none = h.primary_ref_frame == PRIMARY_REF_NONE;
+        let seg = &mut h.segmentation;
+        self.begin("segmentation_params");
+        seg.enabled = self.flag("segmentation_enabled")?;
+        if seg.enabled {
+            if primary_ref_none {
+                seg.update_map = true;
+                seg.temporal_update = false;
+                seg.update_data = true;
+            } else {
+                seg.update_map = self.flag("segmentation_update_map")?;
+                seg.temporal_update =
+                    seg.update_map && self.flag("segmentation_temporal_update")?;
+                seg.update_data = self.flag("segmentation_update_data")?;
+            }
+            if seg.update_data {
+                for i in 0..MAX_SEGMENTS {
+                    for j in 0..SEG_LVL_MAX {
+                        let enabled = self.flag(&format!("feature_enabled[{}][{}]", i, j))?;
+                        seg.feature_enabled[i][j] = enabled;
+                        let mut value = 0;
+                        if enabled {
+                            let name = format!("feature_value[{}][{}]", i, j);
+                            let bits = SEGMENTATION_FEATURE_BITS[j] as u32;
+                            let limit = SEGMENTATION_FEATURE_MAX[j];
+                            value = if SEGMENTATION_FEATURE_SIGNED[j] {
+                                (self.su(&name, bits + 1)? as i16).clamp(-limit, limit)
+                            } else {
+                                (self.f(&name, bits)? as i16).clamp(0, limit)
+                            };
+                        }
+                        seg.feature_data[i][j] = value;
+                    }
+                }
+            }
+        } else {
+            seg.feature_enabled = [[false; SEG_LVL_MAX]; MAX_SEGMENTS];
+            seg.feature_data = [[0; SEG_LVL_MAX]; MAX_SEGMENTS];
+        }
+        self.end();
+        seg.seg_id_pre_skip = false;
+        seg.last_active_seg_id = 0;
+        for i in 0..MAX_SEGMENTS {
+            for j in 0..SEG_LVL_MAX {
+                if seg.feature_enabled[i][j] {
+                    seg.last_active_seg_id = i as u8;
+                    if j >= SEG_LVL_REF_FRAME {
+                        seg.seg_id_pre_skip = true;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Spec `delta_q_params()` and `delta_lf_params()`
+    fn delta_q_lf_params(&mut self, h: &mut UncompressedHeader) -> Result<()> {
+        self.begin("delta_q_params");
+        if h.quantization.base_q_idx > 0 {
+            h.delta_q_present = self.flag("delta_q_present")?;
+        }
+        if h.delta_q_present {
+            h.delta_q_res = self.f("delta_q_res", 2)? as u8;
+        }
+        self.end();
+        self.begin("delta_lf_params");
+        if h.delta_q_present {
+            if !h.allow_intrabc {
+                h.delta_lf_present = self.flag("delta_lf_present")?;
+            }
+            if h.delta_lf_present {
+                h.delta_lf_res = self.f("delta_lf_res", 2)? as u8;
+                h.delta_lf_multi = self.flag("delta_lf_multi")?;
+            }
+        }
+        self.end();
+        Ok(())
+    }
+
+    fn loop_filter_params(&mut self, h: &mut UncompressedHeader) -> Result<()> {
+        let lf = &mut h.loop_filter;
+        if h.coded_lossless || h.allow_intrabc {
+            lf.ref_deltas = DEFAULT_LOOP_FILTER_REF_DELTAS;
+            lf.mode_deltas = [0; 2];
+            return Ok(());
+        }
+        self.begin("loop_filter_params");
+        lf.level[0] = self.f("loop_filter_level[0]", 6)? as u8;
+        lf.level[1] = self.f("loop_filter_level[1]", 6)? as u8;
+        if self.seq.color_config.num_planes > 1 && (lf.level[0] != 0 || lf.level[1] != 0) {
+            lf.level[2] = self.f("loop_filter_level[2]", 6)? as u8;
+            lf.level[3] = self.f("loop_filter_level[3]", 6)? as u8;
+        }
+        lf.sharpness = self.f("loop_filter_sharpness", 3)? as u8;
+        lf.delta_enabled = self.flag("loop_filter_delta_enabled")?;
+        if lf.delta_enabled {
+            lf.delta_update = self.flag("loop_filter_delta_update")?;
+            if lf.delta_update {
+                for (i, delta) in lf.ref_deltas.iter_mut().enumerate() {
+                    if self.flag(&format!("update_ref_delta[{}]", i))? {
+                        *delta = self.su(&format!("loop_filter_ref_deltas[{}]", i), 7)? as i8;
+                    }
+                }
+                for (i, delta) in lf.mode_deltas.iter_mut().enumerate() {
+                    if self.flag(&format!("update_mode_delta[{}]", i))? {
+                        *delta = self.su(&format!("loop_filter_mode_deltas[{}]", i), 7)? as i8;
+                    }
+                }
+            }
+        }
+        self.end();
+        Ok(())
+    }
+
+    fn cdef_params(&mut self, h: &mut UncompressedHeader) -> Result<()> {
+        let cdef = &mut h.cdef;
+        if h.coded_lossless || h.allow_intrabc || !self.seq.enable_cdef {
+            cdef.damping = 3;
+            return Ok(());
+        }
+        self.begin("cdef_params");
+        cdef.damping = self.f("cdef_damping_minus_3", 2)? as u8 + 3;
+        cdef.bits = self.f("cdef_bits", 2)? as u8;
+        for i in 0..(1usize << cdef.bits) {
+            cdef.y_pri_strength[i] = self.f(&format!("cdef_y_pri_strength[{}]", i), 4)? as u8;
+            cdef.y_sec_strength[i] = self.f(&format!("cdef_y_sec_strength[{}]", i), 2)? as u8;
+            if cdef.y_sec_strength[i] == 3 {
+                cdef.y_sec_strength[i] += 1;
+            }
+            if self.seq.color_config.num_planes > 1 {
+                cdef.uv_pri_strength[i] = self.f(&format!("cdef_uv_pri_strength[{}]", i), 4)? as u8;
+                cdef.uv_sec_strength[i] = self.f(&format!("cdef_uv_sec_strength[{}]", i), 2)? as u8;
+                if cdef.uv_sec_strength[i] == 3 {
+                    cdef.uv_sec_strength[i] += 1;
+                }
+            }
+        }
+        self.end();
+        Ok(())
+    }
+
+    fn lr_params(&mut self, h: &mut UncompressedHeader) -> Result<()> {
+        let seq = self.seq;
+        let lr = &mut h.loop_restoration;
+        if h.all_lossless || h.allow_intrabc || !seq.enable_restoration {
+            return Ok(());
+        }
+        self.begin("lr_params");
+        for i in 0..seq.color_config.num_planes as usize {
+            lr.frame_restoration_type[i] = match self.f(&format!("lr_type[{}]", i), 2)? {
+                0 => RestorationType::None,
+                1 => RestorationType::Switchable,
+                2 => RestorationType::Wiener,
+                _ => RestorationType::Sgrproj,
+            };
+            if lr.frame_restoration_type[i] != RestorationType::None {
+                lr.uses_lr = true;
+                if i > 0 {
+                    lr.uses_chroma_lr = true;
+                }
+            }
+        }
+        if lr.uses_lr {
+            let mut lr_unit_shift = self.f("lr_unit_shift", 1)?;
+            if seq.use_128x128_superblock {
+                lr_unit_shift += 1;
+            } else if lr_unit_shift != 0 {
+                lr_unit_shift += self.f("lr_unit_extra_shift", 1)?;
+            }
+            lr.loop_restoration_size[0] = 256 >> (2 - lr_unit_shift);
+            let color = &seq.color_config;
+            let lr_uv_shift = if color.subsampling_x && color.subsampling_y && lr.uses_chroma_lr {
+                self.f("lr_uv_shift", 1)?
+            } else {
+                0
+            };
+            lr.loop_restoration_size[1] = lr.loop_restoration_size[0] >> lr_uv_shift;
+            lr.loop_restoration_size[2] = lr.loop_restoration_size[0] >> lr_uv_shift;
+        }
+        self.end();
+        Ok(())
+    }
+
+    fn skip_mode_params(&mut self, h: &mut UncompressedHeader) -> Result<()> {
+        if h.frame_is_intra() || !h.reference_select || !self.seq.enable_order_hint {
+            return Ok(());
+        }
+        let mut forward: Option<(usize, u32)> = None;
+        let mut backward: Option<(usize, u32)> = None;
+        for i in 0..REFS_PER_FRAME {
+            let ref_hint = self.refs[h.ref_frame_idx[i] as usize].order_hint;
+            if h.get_relative_dist(ref_hint, h.order_hint) < 0 {
+                if forward.is_none_or(|(_, f)| h.get_relative_dist(ref_hint, f) > 0) {
+                    forward = Some((i, ref_hint));
+                }
+            } else if h.get_relative_dist(ref_hint, h.order_hint) > 0
+                && backward.is_none_or(|(_, b)| h.get_relative_dist(ref_hint, b) < 0)
+            {
+                backward = Some((i, ref_hint));
+            }
+        }
+
+        let pair = match (forward, backward) {
+            (None, _) => None,
+            (Some((f, _)), Some((b, _))) => Some((f, b)),
+            (Some((f, forward_hint)), None) => {
+                let mut second: Option<(usize, u32)> = None;
+                for i in 0..REFS_PER_FRAME {
+                    let ref_hint = self.refs[h.ref_frame_idx[i] as usize].order_hint;
+                    if h.get_relative_dist(ref_hint, forward_hint) < 0
+                        && second.is_none_or(|(_, s)| h.get_relative_dist(ref_hint, s) > 0)
+                    {
+                        second = Some((i, ref_hint));
+                    }
+                }
+                second.map(|(s, _)| (f, s))
+            }
+        };
+        if let Some((a, b)) = pair {
+            h.skip_mode_frame = [1 + a.min(b) as u8, 1 + a.max(b) as u8];
+            h.skip_mode_present = self.flag("skip_mode_present")?;
+        }
+        Ok(())
+    }
+
+    fn global_motion_params(
+        &mut self,
+        h: &mut UncompressedHeader,
+        prev_gm_params: &[[i32; 6]; 8],
+    ) -> Result<()> {
+        if h.frame_is_intra() {
+            return Ok(());
+        }
+        self.begin("global_motion_params");
+        for (ref_frame, prev) in prev_gm_params.iter().enumerate().skip(1) {
+            self.begin(REF_FRAME_NAMES[ref_frame]);
+            let gm_type = if self.flag("is_global")? {
+                if self.flag("is_rot_zoom")? {
+                    GlobalMotionType::RotZoom
+                } else if self.flag("is_translation")? {
+                    GlobalMotionType::Translation
+                } else {
+                    GlobalMotionType::Affine
+                }
+            } else {
+                GlobalMotionType::Identity
+            };
+            h.gm_type[ref_frame] = gm_type;
+            let mut params = h.gm_params[ref_frame];
+            if gm_type >= GlobalMotionType::RotZoom {
+                params[2] = self.read_global_param(h, gm_type, prev, 2)?;
+                params[3] = self.read_global_param(h, gm_type, prev, 3)?;
+                if gm_type == GlobalMotionType::Affine {
+                    params[4] = self.read_global_param(h, gm_type, prev, 4)?;
+                    params[5] = self.read_global_param(h, gm_type, prev, 5)?;
+                } else {
+                    params[4] = -params[3];
+                    params[5] = params[2];
+                }
+            }
+            if gm_type >= GlobalMotionType::Translation {
+                params[0] = self.read_global_param(h, gm_type, prev, 0)?;
+                params[1] = self.read_global_param(h, gm_type, prev, 1)?;
+            }
+            h.gm_params[ref_frame] = params;
+            self.end();
+        }
+        self.end();
+        Ok(())
+    }
+
+    /// Spec `read_global_param()`, recorded as one `gm_params[idx]` element
+    fn read_global_param(
+        &mut self,
+        h: &UncompressedHeader,
+        gm_type: GlobalMotionType,
+        prev: &[i32; 6],
+        idx: usize,
+    ) -> Result<i32> {
+        let (abs_bits, prec_bits) = if idx < 2 {
+            if gm_type == GlobalMotionType::Translation {
+                let hp = !h.allow_high_precision_mv as u32;
+                (9 - hp, 3 - hp)
+            } else {
+                (12, 6)
+            }
+        } else {
+            (12, 15)
+        };
+        let prec_diff = WARPEDMODEL_PREC_BITS - prec_bits;
+        let (round, sub) = if idx % 3 == 2 {
+            (1 << WARPEDMODEL_PREC_BITS, 1 << prec_bits)
+        } else {
+            (0, 0)
+        };
+        let mx = 1i32 << abs_bits;
+        let r = (prev[idx] >> prec_diff) - sub;
+        let start = self.r.position();
+        let value = self.decode_signed_subexp_with_ref(-mx, mx + 1, r)?;
+        let param = (value << prec_diff) + round;
+        let range = BitRange::new(start, self.r.position());
+        self.record(&format!("gm_params[{}]", idx), range, param);
+        Ok(param)
+    }
+
+    fn decode_signed_subexp_with_ref(&mut self, low: i32, high: i32, r: i32) -> Result<i32> {
+        let x = self.decode_unsigned_subexp_with_ref((high - low) as u32, r - low)?;
+        Ok(x + low)
+    }
+
+    fn decode_unsigned_subexp_with_ref(&mut self, mx: u32, r: i32) -> Result<i32> {
+        let v = self.decode_subexp(mx)? as i32;
+        let mx = mx as i32;
+        Ok(if (r << 1) <= mx {
+            inverse_recenter(r, v)
+        } else {
+            mx - 1 - inverse_recenter(mx - 1 - r, v)
+        })
+    }
+
+    fn decode_subexp(&mut self, num_syms: u32) -> Result<u32> {
+        let mut i = 0;
+        let mut mk = 0;
+        let k = 3;
+        loop {
+            let b2 = if i > 0 { k + i - 1 } else { k };
+            let a = 1 << b2;
+            if num_syms <= mk + 3 * a {
+                return Ok(self.read_ns(num_syms - mk)? + mk);
+            }
+            if self.bits(1)? != 0 {
+                i += 1;
+                mk += a;
+            } else {
+                return Ok(self.bits(b2)? + mk);
+            }
+        }
+    }
+
+    fn film_grain_params(&mut self, h: &mut UncompressedHeader) -> Result<()> {
+        let seq = self.seq;
+        if !seq.film_grain_params_present || (!h.show_frame && !h.showable_frame) {
+            return Ok(());
+        }
+        self.begin("film_grain_params");
+        h.film_grain = self.read_film_grain(h)?;
+        self.end();
+        Ok(())
+    }
+
+    fn read_film_grain(&mut self, h: &UncompressedHeader) -> Result<FilmGrainParams> {
+        let mut fg = FilmGrainParams {
+            apply_grain: self.flag("apply_grain")?,
+            ..Default::default()
+        };
+        if !fg.apply_grain {
+            return Ok(fg);
+        }
+        fg.grain_seed = self.f("grain_seed", 16)? as u16;
+        fg.update_grain = if h.frame_type == FrameType::Inter {
+            self.flag("update_grain")?
+        } else {
+            true
+        };
+        if !fg.update_grain {
+            let ref_idx = self.f("film_grain_params_ref_idx", 3)? as usize;
+            let grain_seed = fg.grain_seed;
+            fg = self.refs[ref_idx].film_grain.clone();
+            fg.grain_seed = grain_seed;
+            return Ok(fg);
+        }
+
+        let num_y_points = self.f("num_y_points", 4)?;
+        fg.point_y = self.read_scaling_points("y", num_y_points)?;
+        let color = &self.seq.color_config;
+        fg.chroma_scaling_from_luma =
+            !color.mono_chrome && self.flag("chroma_scaling_from_luma")?;
+        if !(color.mono_chrome
+            || fg.chroma_scaling_from_luma
+            || (color.subsampling_x && color.subsampling_y && num_y_points == 0))
+        {
+            let num_cb_points = self.f("num_cb_points", 4)?;
+            fg.point_cb = self.read_scaling_points("cb", num_cb_points)?;
+            let num_cr_points = self.f("num_cr_points", 4)?;
+            fg.point_cr = self.read_scaling_points("cr", num_cr_points)?;
+        }
+        fg.grain_scaling_minus_8 = self.f("grain_scaling_minus_8", 2)? as u8;
+        fg.ar_coeff_lag = self.f("ar_coeff_lag", 2)? as u8;
+        let lag = fg.ar_coeff_lag as usize;
+        let num_pos_luma = 2 * lag * (lag + 1);
+        let num_pos_chroma = if num_y_points > 0 {
+            fg.ar_coeffs_y_plus_128 = self.read_ar_coeffs("y", num_pos_luma)?;
+            num_pos_luma + 1
+        } else {
+            num_pos_luma
+        };
+        if fg.chroma_scaling_from_luma || !fg.point_cb.is_empty() {
+            fg.ar_coeffs_cb_plus_128 = self.read_ar_coeffs("cb", num_pos_chroma)?;
+        }
+        if fg.chroma_scaling_from_luma || !fg.point_cr.is_empty() {
+            fg.ar_coeffs_cr_plus_128 = self.read_ar_coeffs("cr", num_pos_chroma)?;
+        }
+        fg.ar_coeff_shift_minus_6 = self.f("ar_coeff_shift_minus_6", 2)? as u8;
+        fg.grain_scale_shift = self.f("grain_scale_shift", 2)? as u8;
+        if !fg.point_cb.is_empty() {
+            fg.cb_mult = self.f("cb_mult", 8)? as u8;
+            fg.cb_luma_mult = self.f("cb_luma_mult", 8)? as u8;
+            fg.cb_offset = self.f("cb_offset", 9)? as u16;
+        }
+        if !fg.point_cr.is_empty() {
+            fg.cr_mult = self.f("cr_mult", 8)? as u8;
+            fg.cr_luma_mult = self.f("cr_luma_mult", 8)? as u8;
+            fg.cr_offset = self.f("cr_offset", 9)? as u16;
+        }
+        fg.overlap_flag = self.flag("overlap_flag")?;
+        fg.clip_to_restricted_range = self.flag("clip_to_restricted_range")?;
+        Ok(fg)
+    }
+
+    /// `point_<plane>_value` / `point_<plane>_scaling` pairs
+    fn read_scaling_points(&mut self, plane: &str, count: u32) -> Result<Vec<(u8, u8)>> {
+        (0..count)
+            .map(|i| {
+                let value = self.f(&format!("point_{}_value[{}]", plane, i), 8)? as u8;
+                let scaling = self.f(&format!("point_{}_scaling[{}]", plane, i), 8)? as u8;
+                Ok((value, scaling))
+            })
+            .collect()
+    }
+
+    fn read_ar_coeffs(&mut self, plane: &str, count: usize) -> Result<Vec<u8>> {
+        (0..count)
+            .map(|i| Ok(self.f(&format!("ar_coeffs_{}_plus_128[{}]", plane, i), 8)? as u8))
+            .collect()
+    }
+}
+
+/// Spec `tile_log2()`: smallest k such that `blk_size << k >= target`
+fn tile_log2(blk_size: u32, target: u32) -> u32 {
+    let mut k = 0;
+    while (blk_size << k) < target {
+        k += 1;
+    }
+    k
+}
+
+/// Spec `inverse_recenter()`
+pub(crate) fn inverse_recenter(r: i32, v: i32) -> i32 {
+    if v > 2 * r {
+        v
+    } else if v & 1 != 0 {
+        r - ((v + 1) >> 1)
+    } else {
+        r + (v >> 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_dist_wraps() {
+        assert_eq!(relative_dist(7, 5, 3), 2);
+        assert_eq!(relative_dist(7, 3, 5), -2);
+        // 127 is just before 1 with 7-bit order hints
+        assert_eq!(relative_dist(7, 127, 1), -2);
+        assert_eq!(relative_dist(0, 10, 3), 0);
+    }
+
+    #[test]
+    fn test_tile_log2() {
+        assert_eq!(tile_log2(1, 1), 0);
+        assert_eq!(tile_log2(1, 5), 3);
+        assert_eq!(tile_log2(64, 6), 0);
+    }
+
+    #[test]
+    fn test_inverse_recenter() {
+        assert_eq!(inverse_recenter(4, 10), 10);
+        assert_eq!(inverse_recenter(4, 3), 2);
+        assert_eq!(inverse_recenter(4, 2), 5);
+    }
+
+    #[test]
+    fn test_get_qindex_with_segment_offset() {
+        let mut header = UncompressedHeader::default();
+        header.quantization.base_q_idx = 100;
+        header.segmentation.enabled = true;
+        header.segmentation.feature_enabled[2][SEG_LVL_ALT_Q] = true;
+        header.segmentation.feature_data[2][SEG_LVL_ALT_Q] = -30;
+        assert_eq!(header.get_qindex(true, 0, 0), 100);
+        assert_eq!(header.get_qindex(true, 2, 0), 70);
+        header.delta_q_present = true;
+        assert_eq!(header.get_qindex(false, 0, 120), 120);
+        assert_eq!(header.get_qindex(false, 2, 20), 0);
+    }
+
+    #[test]
+    fn test_parse_foreman_key_frame() {
+        let path =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../samples/foreman_av1.ivf");
+        let Ok(data) = std::fs::read(&path) else {
+            eprintln!("Skipping test: {} not found", path.display());
+            return;
+        };
+        let (_, frames) = crate::parse_ivf_frames(&data).unwrap();
+        let obus = crate::parse_all_obus(&frames[0].data).unwrap();
+        let seq = obus
+            .iter()
+            .find(|o| o.header.obu_type == crate::ObuType::SequenceHeader)
+            .map(|o| crate::parse_sequence_header(&o.payload).unwrap())
+            .unwrap();
+        let frame = obus
+            .iter()
+            .find(|o| o.header.obu_type == crate::ObuType::Frame)
+            .unwrap();
+        let refs: [RefFrameInfo; NUM_REF_FRAMES] = Default::default();
+        let header = parse_uncompressed_header(&frame.payload, &seq, &refs, 0, 0).unwrap();
+
+        assert_eq!(header.frame_type, FrameType::Key);
+        assert!(header.show_frame);
+        assert_eq!((header.frame_width, header.frame_height), (320, 240));
+        assert_eq!((header.mi_cols, header.mi_rows), (80, 60));
+        assert_eq!(header.refresh_frame_flags, 0xFF);
+        assert_eq!(header.primary_ref_frame, PRIMARY_REF_NONE);
+        assert_eq!(header.tile_info.tile_count(), 1);
+        assert!(header.quantization.base_q_idx > 0);
+        assert!(header.header_bytes > 0);
+    }
+}
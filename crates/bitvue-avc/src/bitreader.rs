@@ -130,10 +130,11 @@ impl<'a> BitReader<'a> {
 
     /// Read more_rbsp_data() check - true if there's more data before trailing bits.
     pub fn more_rbsp_data(&self) -> bool {
-        let pos = self.inner.position() as usize;
+        // `remaining_data` starts at the current byte
+        let pos = self.inner.position() as usize % 8;
         let data = self.inner.remaining_data();
 
-        if pos / 8 >= data.len() {
+        if data.is_empty() {
             return false;
         }
 
@@ -157,17 +158,12 @@ impl<'a> BitReader<'a> {
         // Same byte - check for stop bit
         let remaining = 8 - current_bit_in_byte;
 
-        // If remaining is 0, we're at byte boundary with no bits left
-        if remaining == 0 {
-            return false;
-        }
-
-        let mask = (1u8 << remaining) - 1;
+        let mask = ((1u16 << remaining) - 1) as u8;
         let trailing = data[last_byte_idx] & mask;
 
         // If there's only the stop bit (10...0), no more data
         // Otherwise, there's more data
-        trailing != (1u8 << (remaining - 1))
+        trailing != (1u16 << (remaining - 1)) as u8
     }
 }
 
@@ -197,6 +193,19 @@ mod tests {
         assert_eq!(reader.read_ue().unwrap(), 1); // codeword: 010
     }
 
+    #[test]
+    fn test_more_rbsp_data_mid_buffer() {
+        // Two payload bits in the second byte, then the rbsp_stop_one_bit
+        let data = [0xff, 0b1010_0000];
+        let mut reader = BitReader::new(&data);
+        reader.read_bits(8).unwrap();
+        assert!(reader.more_rbsp_data());
+        reader.read_bits(1).unwrap();
+        assert!(reader.more_rbsp_data());
+        reader.read_bits(1).unwrap();
+        assert!(!reader.more_rbsp_data());
+    }
+
     #[test]
     fn test_remove_emulation_prevention() {
         let data = [0x00, 0x00, 0x03, 0x01, 0x00, 0x00, 0x03, 0x02];
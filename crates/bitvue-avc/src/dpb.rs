@@ -0,0 +1,1254 @@
+//! H.264 decoded picture buffer simulation.
+//!
+//! Tracks the reference marking of decoded pictures through a stream:
+//! frame_num gap handling, sliding window and adaptive memory control
+//! marking (ITU-T H.264 Section 8.2.5), and reference picture list
+//! initialisation and modification for frames and fields (Section 8.2.4).
+//! No samples are decoded; pictures are identified by the NAL unit holding
+//! their first slice.
+//!
+//! Only reference pictures are stored, so a non-reference B picture never
+//! shows up in the DPB contents, and markings use the same
+//! [`ReferenceMarking`] states as the player's H.264 reference tracking.
+
+use crate::slice::{DecRefPicMarking, RefPicListModification, SliceHeader};
+use crate::sps::Sps;
+use bitvue_core::player::ReferenceMarking;
+use serde::{Deserialize, Serialize};
+
+/// Structure of a coded picture or of a reference picture.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PictureStructure {
+    /// Frame (or complementary field pair)
+    #[default]
+    Frame,
+    /// Top field
+    TopField,
+    /// Bottom field
+    BottomField,
+}
+
+impl PictureStructure {
+    /// Get the structure of the picture a slice belongs to.
+    pub fn from_header(header: &SliceHeader) -> Self {
+        match (header.field_pic_flag, header.bottom_field_flag) {
+            (false, _) => PictureStructure::Frame,
+            (true, false) => PictureStructure::TopField,
+            (true, true) => PictureStructure::BottomField,
+        }
+    }
+
+    /// Check if this is a field.
+    pub fn is_field(&self) -> bool {
+        !matches!(self, PictureStructure::Frame)
+    }
+
+    /// Field parity as an index (0 = top, 1 = bottom).
+    fn parity(&self) -> Option<usize> {
+        match self {
+            PictureStructure::Frame => None,
+            PictureStructure::TopField => Some(0),
+            PictureStructure::BottomField => Some(1),
+        }
+    }
+
+    fn from_parity(parity: usize) -> Self {
+        if parity == 0 {
+            PictureStructure::TopField
+        } else {
+            PictureStructure::BottomField
+        }
+    }
+}
+
+/// Entry of a reference picture list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefPicEntry {
+    /// NAL unit index of the first slice of the referenced picture (of the
+    /// first field for a complementary field pair). `None` for frames
+    /// inferred from a frame_num gap and for pictures missing from the DPB.
+    pub nal_index: Option<usize>,
+    /// Whether a frame or a single field is referenced.
+    pub structure: PictureStructure,
+    /// POC of the frame or field.
+    pub poc: i32,
+    /// frame_num of the frame holding the picture.
+    pub frame_num: u32,
+    /// PicNum, or LongTermPicNum for long-term references.
+    pub pic_num: i32,
+    /// Whether the picture is marked as used for long-term reference.
+    pub long_term: bool,
+}
+
+/// Reference picture lists of a slice.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefPicLists {
+    /// RefPicList0.
+    pub list0: Vec<RefPicEntry>,
+    /// RefPicList1 (B slices only).
+    pub list1: Vec<RefPicEntry>,
+}
+
+impl RefPicLists {
+    /// Check if both lists are empty (intra slices).
+    pub fn is_empty(&self) -> bool {
+        self.list0.is_empty() && self.list1.is_empty()
+    }
+
+    /// Iterate over the entries of RefPicList0 followed by RefPicList1.
+    pub fn iter(&self) -> impl Iterator<Item = &RefPicEntry> {
+        self.list0.iter().chain(&self.list1)
+    }
+}
+
+/// Field of a frame stored in the DPB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DpbField {
+    /// NAL unit index of the first slice of the picture holding the field.
+    pub nal_index: Option<usize>,
+    /// POC of the field.
+    pub poc: i32,
+    /// Reference marking of the field.
+    pub marking: ReferenceMarking,
+}
+
+/// Frame, complementary field pair or non-paired field stored in the DPB.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DpbEntry {
+    /// frame_num of the frame.
+    pub frame_num: u32,
+    /// Top field, if decoded.
+    pub top: Option<DpbField>,
+    /// Bottom field, if decoded.
+    pub bottom: Option<DpbField>,
+    /// LongTermFrameIdx, when a field is marked as used for long-term reference.
+    pub long_term_frame_idx: Option<u32>,
+    /// Frame inferred from a gap in frame_num ("non-existing" frame).
+    pub non_existing: bool,
+}
+
+impl DpbEntry {
+    fn field(&self, parity: usize) -> Option<&DpbField> {
+        if parity == 0 {
+            self.top.as_ref()
+        } else {
+            self.bottom.as_ref()
+        }
+    }
+
+    fn fields_mut(&mut self) -> impl Iterator<Item = &mut DpbField> {
+        self.top.iter_mut().chain(self.bottom.iter_mut())
+    }
+
+    fn field_marked(&self, parity: usize, marking: ReferenceMarking) -> bool {
+        self.field(parity).is_some_and(|f| f.marking == marking)
+    }
+
+    /// Both fields are marked with `marking` (a reference frame or
+    /// complementary reference field pair).
+    fn frame_marked(&self, marking: ReferenceMarking) -> bool {
+        self.field_marked(0, marking) && self.field_marked(1, marking)
+    }
+
+    /// At least one field is marked with `marking`.
+    fn any_marked(&self, marking: ReferenceMarking) -> bool {
+        self.field_marked(0, marking) || self.field_marked(1, marking)
+    }
+
+    /// Check if any field is used for reference.
+    pub fn is_reference(&self) -> bool {
+        self.any_marked(ReferenceMarking::ShortTerm) || self.any_marked(ReferenceMarking::LongTerm)
+    }
+
+    /// Marking of the entry as a whole; long-term wins over short-term.
+    pub fn marking(&self) -> ReferenceMarking {
+        if self.any_marked(ReferenceMarking::LongTerm) {
+            ReferenceMarking::LongTerm
+        } else if self.any_marked(ReferenceMarking::ShortTerm) {
+            ReferenceMarking::ShortTerm
+        } else {
+            ReferenceMarking::Unused
+        }
+    }
+
+    /// NAL unit index of the first decoded field.
+    fn nal_index(&self) -> Option<usize> {
+        self.top
+            .iter()
+            .chain(&self.bottom)
+            .filter_map(|f| f.nal_index)
+            .min()
+    }
+
+    /// PicOrderCnt of the frame: the smaller POC of its fields.
+    fn poc(&self) -> i32 {
+        self.top
+            .iter()
+            .chain(&self.bottom)
+            .map(|f| f.poc)
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// POC of the fields marked with `marking`, as used for field decoding.
+    fn poc_of_marked(&self, marking: ReferenceMarking) -> i32 {
+        self.top
+            .iter()
+            .chain(&self.bottom)
+            .filter(|f| f.marking == marking)
+            .map(|f| f.poc)
+            .min()
+            .unwrap_or(0)
+    }
+
+    fn mark_unused(&mut self, marking: ReferenceMarking) {
+        for field in self.fields_mut().filter(|f| f.marking == marking) {
+            field.marking = ReferenceMarking::Unused;
+        }
+    }
+}
+
+/// Memory management control operation (Section 7.4.3.3).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mmco {
+    /// 1: mark a short-term picture as unused.
+    ShortTermUnused { difference_of_pic_nums_minus1: u32 },
+    /// 2: mark a long-term picture as unused.
+    LongTermUnused { long_term_pic_num: u32 },
+    /// 3: convert a short-term picture to long-term.
+    ShortTermToLongTerm {
+        difference_of_pic_nums_minus1: u32,
+        long_term_frame_idx: u32,
+    },
+    /// 4: set MaxLongTermFrameIdx.
+    MaxLongTermFrameIdx { max_long_term_frame_idx_plus1: u32 },
+    /// 5: mark all pictures as unused.
+    AllUnused,
+    /// 6: mark the current picture as long-term.
+    CurrentLongTerm { long_term_frame_idx: u32 },
+}
+
+impl Mmco {
+    fn from_marking(marking: &DecRefPicMarking) -> Vec<Self> {
+        marking
+            .mmco_operations
+            .iter()
+            .filter_map(|&(op, diff, idx)| match op {
+                1 => Some(Mmco::ShortTermUnused {
+                    difference_of_pic_nums_minus1: diff,
+                }),
+                2 => Some(Mmco::LongTermUnused {
+                    long_term_pic_num: idx,
+                }),
+                3 => Some(Mmco::ShortTermToLongTerm {
+                    difference_of_pic_nums_minus1: diff,
+                    long_term_frame_idx: idx,
+                }),
+                4 => Some(Mmco::MaxLongTermFrameIdx {
+                    max_long_term_frame_idx_plus1: idx,
+                }),
+                5 => Some(Mmco::AllUnused),
+                6 => Some(Mmco::CurrentLongTerm {
+                    long_term_frame_idx: idx,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Picture being decoded.
+#[derive(Debug, Clone)]
+struct CurrentPicture {
+    nal_index: usize,
+    is_idr: bool,
+    is_reference: bool,
+    frame_num: u32,
+    structure: PictureStructure,
+    top_poc: i32,
+    bottom_poc: i32,
+    marking: DecRefPicMarking,
+    /// DPB index of the first field when this is the second field of a
+    /// complementary reference field pair.
+    first_field: Option<usize>,
+}
+
+impl CurrentPicture {
+    /// CurrPicNum and MaxPicNum (Section 7.4.3).
+    fn pic_nums(&self, max_frame_num: u32) -> (i32, i32) {
+        if self.structure.is_field() {
+            (2 * self.frame_num as i32 + 1, 2 * max_frame_num as i32)
+        } else {
+            (self.frame_num as i32, max_frame_num as i32)
+        }
+    }
+
+    fn poc(&self) -> i32 {
+        match self.structure {
+            PictureStructure::Frame => self.top_poc.min(self.bottom_poc),
+            PictureStructure::TopField => self.top_poc,
+            PictureStructure::BottomField => self.bottom_poc,
+        }
+    }
+}
+
+/// First field of a possible field pair.
+#[derive(Debug, Clone, Copy)]
+struct PrevField {
+    frame_num: u32,
+    parity: usize,
+    is_reference: bool,
+    dpb_index: Option<usize>,
+}
+
+/// H.264 decoded picture buffer.
+#[derive(Debug, Clone, Default)]
+pub struct Dpb {
+    frames: Vec<DpbEntry>,
+    current: Option<CurrentPicture>,
+    max_frame_num: u32,
+    max_num_ref_frames: usize,
+    prev_ref_frame_num: u32,
+    /// MaxLongTermFrameIdx; `None` means "no long-term frame indices".
+    max_long_term_frame_idx: Option<u32>,
+    prev_field: Option<PrevField>,
+}
+
+impl Dpb {
+    /// Create an empty DPB.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reference frames currently held by the DPB.
+    pub fn contents(&self) -> &[DpbEntry] {
+        &self.frames
+    }
+
+    /// Start decoding a picture from its first slice.
+    ///
+    /// Completes the marking of the previous picture, infers frames for a
+    /// gap in frame_num, and returns the DPB contents the picture sees.
+    /// `poc` is the POC of the picture (the top field POC for frames).
+    pub fn start_picture(
+        &mut self,
+        nal_index: usize,
+        nal_ref_idc: u8,
+        is_idr: bool,
+        header: &SliceHeader,
+        sps: &Sps,
+        poc: i32,
+    ) -> Vec<DpbEntry> {
+        self.finish_picture();
+
+        self.max_frame_num = 1 << (sps.log2_max_frame_num_minus4 as u32 + 4);
+        self.max_num_ref_frames = sps.max_num_ref_frames.max(1) as usize;
+
+        let structure = PictureStructure::from_header(header);
+        let is_reference = nal_ref_idc != 0;
+        let (top_poc, bottom_poc) = match structure {
+            PictureStructure::Frame => {
+                let bottom_offset = match sps.pic_order_cnt_type {
+                    0 => header.delta_pic_order_cnt_bottom,
+                    1 => sps.offset_for_top_to_bottom_field + header.delta_pic_order_cnt[1],
+                    _ => 0,
+                };
+                (poc, poc + bottom_offset)
+            }
+            _ => (poc, poc),
+        };
+
+        // Second field of a complementary field pair (Section 3.30)
+        let pair = self.prev_field.take().filter(|prev| {
+            !is_idr
+                && structure.parity() == Some(1 - prev.parity)
+                && prev.frame_num == header.frame_num
+                && prev.is_reference == is_reference
+        });
+        let first_field = pair.and_then(|prev| prev.dpb_index);
+
+        if is_idr {
+            self.prev_ref_frame_num = 0;
+        } else if pair.is_none() {
+            self.fill_frame_num_gap(header.frame_num, sps);
+        }
+
+        self.current = Some(CurrentPicture {
+            nal_index,
+            is_idr,
+            is_reference,
+            frame_num: header.frame_num,
+            structure,
+            top_poc,
+            bottom_poc,
+            marking: header.dec_ref_pic_marking.clone(),
+            first_field,
+        });
+
+        self.frames.clone()
+    }
+
+    /// Complete the decoding of the current picture: apply its reference
+    /// marking and store it if it is a reference picture.
+    pub fn finish_picture(&mut self) {
+        let Some(mut cur) = self.current.take() else {
+            return;
+        };
+
+        if cur.is_reference {
+            let mut current_long_term: Option<u32> = None;
+            let mut memory_reset = false;
+
+            if cur.is_idr {
+                for frame in &mut self.frames {
+                    frame.mark_unused(ReferenceMarking::ShortTerm);
+                    frame.mark_unused(ReferenceMarking::LongTerm);
+                }
+                if cur.marking.long_term_reference_flag {
+                    self.max_long_term_frame_idx = Some(0);
+                    current_long_term = Some(0);
+                } else {
+                    self.max_long_term_frame_idx = None;
+                }
+            } else if cur.marking.adaptive_ref_pic_marking_mode_flag {
+                for op in Mmco::from_marking(&cur.marking) {
+                    match op {
+                        Mmco::CurrentLongTerm {
+                            long_term_frame_idx,
+                        } => {
+                            self.release_long_term_frame_idx(long_term_frame_idx, cur.first_field);
+                            current_long_term = Some(long_term_frame_idx);
+                        }
+                        Mmco::AllUnused => {
+                            for frame in &mut self.frames {
+                                frame.mark_unused(ReferenceMarking::ShortTerm);
+                                frame.mark_unused(ReferenceMarking::LongTerm);
+                            }
+                            self.max_long_term_frame_idx = None;
+                            memory_reset = true;
+                        }
+                        op => self.apply_mmco(op, &cur),
+                    }
+                }
+            } else {
+                // The second field of a pair whose first field is a short-term
+                // reference joins it without invoking the sliding window
+                let first_short_term = cur
+                    .first_field
+                    .and_then(|i| self.frames.get(i))
+                    .is_some_and(|f| f.any_marked(ReferenceMarking::ShortTerm));
+                if !first_short_term {
+                    self.sliding_window(cur.frame_num, cur.first_field);
+                }
+            }
+
+            if memory_reset {
+                // Section 8.2.1: the picture is inferred to have frame_num 0
+                // and POCs relative to itself
+                let temp = cur.poc();
+                cur.frame_num = 0;
+                cur.top_poc -= temp;
+                cur.bottom_poc -= temp;
+            }
+            self.store_current(&cur, current_long_term);
+            self.prev_ref_frame_num = cur.frame_num;
+        }
+
+        // Drop frames no longer used for reference
+        self.frames.retain(DpbEntry::is_reference);
+
+        self.prev_field = match cur.structure.parity() {
+            Some(parity) if cur.first_field.is_none() => Some(PrevField {
+                frame_num: cur.frame_num,
+                parity,
+                is_reference: cur.is_reference,
+                dpb_index: self
+                    .frames
+                    .iter()
+                    .position(|f| f.field(parity).and_then(|f| f.nal_index) == Some(cur.nal_index)),
+            }),
+            _ => None,
+        };
+        for frame in &mut self.frames {
+            if !frame.any_marked(ReferenceMarking::LongTerm) {
+                frame.long_term_frame_idx = None;
+            }
+        }
+    }
+
+    /// Construct the reference picture lists of a slice of the current picture.
+    pub fn ref_pic_lists(&self, header: &SliceHeader) -> RefPicLists {
+        let Some(cur) = &self.current else {
+            return RefPicLists::default();
+        };
+        if header.slice_type.is_intra() {
+            return RefPicLists::default();
+        }
+
+        let (list0, list1) = if header.slice_type.is_b() {
+            self.initial_b_lists(cur)
+        } else {
+            (self.initial_p_list(cur), Vec::new())
+        };
+
+        let mut lists = RefPicLists {
+            list0: self.modify_list(
+                cur,
+                list0,
+                header.num_ref_idx_l0_active_minus1 as usize + 1,
+                header
+                    .ref_pic_list_modification_flag_l0
+                    .then_some(&header.ref_pic_list_modification_l0),
+            ),
+            list1: Vec::new(),
+        };
+        if header.slice_type.is_b() {
+            lists.list1 = self.modify_list(
+                cur,
+                list1,
+                header.num_ref_idx_l1_active_minus1 as usize + 1,
+                header
+                    .ref_pic_list_modification_flag_l1
+                    .then_some(&header.ref_pic_list_modification_l1),
+            );
+        }
+        lists
+    }
+
+    /// FrameNumWrap of a short-term frame (equation 8-27).
+    fn frame_num_wrap(&self, frame: &DpbEntry, cur_frame_num: u32) -> i32 {
+        if frame.frame_num > cur_frame_num {
+            frame.frame_num as i32 - self.max_frame_num as i32
+        } else {
+            frame.frame_num as i32
+        }
+    }
+
+    /// Build the entry for a frame, or for one field when `parity` is set.
+    fn entry(
+        &self,
+        frame: &DpbEntry,
+        cur: &CurrentPicture,
+        parity: Option<usize>,
+        long_term: bool,
+    ) -> RefPicEntry {
+        let base = if long_term {
+            frame.long_term_frame_idx.unwrap_or(0) as i32
+        } else {
+            self.frame_num_wrap(frame, cur.frame_num)
+        };
+        match parity {
+            None => RefPicEntry {
+                nal_index: frame.nal_index(),
+                structure: PictureStructure::Frame,
+                poc: frame.poc(),
+                frame_num: frame.frame_num,
+                pic_num: base,
+                long_term,
+            },
+            Some(parity) => {
+                // Equations 8-30 to 8-33
+                let same_parity = cur.structure.parity() == Some(parity);
+                let field = frame.field(parity);
+                RefPicEntry {
+                    nal_index: field.and_then(|f| f.nal_index),
+                    structure: PictureStructure::from_parity(parity),
+                    poc: field.map_or(0, |f| f.poc),
+                    frame_num: frame.frame_num,
+                    pic_num: 2 * base + same_parity as i32,
+                    long_term,
+                }
+            }
+        }
+    }
+
+    /// Short-term and long-term reference pictures usable by the current
+    /// picture, with their picture numbers.
+    fn reference_pictures(&self, cur: &CurrentPicture, long_term: bool) -> Vec<RefPicEntry> {
+        let marking = if long_term {
+            ReferenceMarking::LongTerm
+        } else {
+            ReferenceMarking::ShortTerm
+        };
+        let mut pictures = Vec::new();
+        for frame in &self.frames {
+            if cur.structure.is_field() {
+                for parity in 0..2 {
+                    if frame.field_marked(parity, marking) {
+                        pictures.push(self.entry(frame, cur, Some(parity), long_term));
+                    }
+                }
+            } else if frame.frame_marked(marking) {
+                pictures.push(self.entry(frame, cur, None, long_term));
+            }
+        }
+        pictures
+    }
+
+    /// Initial RefPicList0 of a P or SP slice (Sections 8.2.4.2.1 and 8.2.4.2.2).
+    fn initial_p_list(&self, cur: &CurrentPicture) -> Vec<RefPicEntry> {
+        if !cur.structure.is_field() {
+            let mut short_term = self.reference_pictures(cur, false);
+            short_term.sort_by_key(|e| std::cmp::Reverse(e.pic_num));
+            let mut long_term = self.reference_pictures(cur, true);
+            long_term.sort_by_key(|e| e.pic_num);
+            short_term.extend(long_term);
+            return short_term;
+        }
+
+        let mut short_term: Vec<&DpbEntry> = self
+            .frames
+            .iter()
+            .filter(|f| f.any_marked(ReferenceMarking::ShortTerm))
+            .collect();
+        short_term.sort_by_key(|f| std::cmp::Reverse(self.frame_num_wrap(f, cur.frame_num)));
+        let mut list = self.alternate_fields(cur, &short_term, false);
+        list.extend(self.alternate_fields(cur, &self.long_term_frames(), true));
+        list
+    }
+
+    /// Initial RefPicList0 and RefPicList1 of a B slice (Sections 8.2.4.2.3
+    /// and 8.2.4.2.4).
+    fn initial_b_lists(&self, cur: &CurrentPicture) -> (Vec<RefPicEntry>, Vec<RefPicEntry>) {
+        let cur_poc = cur.poc();
+        let (list0, mut list1) = if !cur.structure.is_field() {
+            let short_term: Vec<RefPicEntry> = self
+                .reference_pictures(cur, false)
+                .into_iter()
+                .filter(|e| e.nal_index.is_some())
+                .collect();
+            let mut before: Vec<RefPicEntry> = short_term
+                .iter()
+                .filter(|e| e.poc < cur_poc)
+                .copied()
+                .collect();
+            before.sort_by_key(|e| std::cmp::Reverse(e.poc));
+            let mut after: Vec<RefPicEntry> = short_term
+                .iter()
+                .filter(|e| e.poc > cur_poc)
+                .copied()
+                .collect();
+            after.sort_by_key(|e| e.poc);
+            let mut long_term = self.reference_pictures(cur, true);
+            long_term.sort_by_key(|e| e.pic_num);
+
+            let list0 = [&before[..], &after, &long_term].concat();
+            let list1 = [&after[..], &before, &long_term].concat();
+            (list0, list1)
+        } else {
+            let short_term =
+                |f: &&DpbEntry| f.any_marked(ReferenceMarking::ShortTerm) && !f.non_existing;
+            let poc = |f: &&DpbEntry| f.poc_of_marked(ReferenceMarking::ShortTerm);
+            let mut before: Vec<&DpbEntry> = self
+                .frames
+                .iter()
+                .filter(short_term)
+                .filter(|f| poc(f) <= cur_poc)
+                .collect();
+            before.sort_by_key(|f| std::cmp::Reverse(poc(f)));
+            let mut after: Vec<&DpbEntry> = self
+                .frames
+                .iter()
+                .filter(short_term)
+                .filter(|f| poc(f) > cur_poc)
+                .collect();
+            after.sort_by_key(poc);
+            let long_term = self.alternate_fields(cur, &self.long_term_frames(), true);
+
+            let mut list0 = self.alternate_fields(cur, &[&before[..], &after].concat(), false);
+            list0.extend(&long_term);
+            let mut list1 = self.alternate_fields(cur, &[&after[..], &before].concat(), false);
+            list1.extend(&long_term);
+            (list0, list1)
+        };
+
+        if list1.len() > 1 && list0 == list1 {
+            list1.swap(0, 1);
+        }
+        (list0, list1)
+    }
+
+    /// Frames with a long-term field, by ascending LongTermFrameIdx.
+    fn long_term_frames(&self) -> Vec<&DpbEntry> {
+        let mut frames: Vec<&DpbEntry> = self
+            .frames
+            .iter()
+            .filter(|f| f.any_marked(ReferenceMarking::LongTerm))
+            .collect();
+        frames.sort_by_key(|f| f.long_term_frame_idx);
+        frames
+    }
+
+    /// Derive a field list from a frame list, alternating parities starting
+    /// with the parity of the current field (Section 8.2.4.2.5).
+    fn alternate_fields(
+        &self,
+        cur: &CurrentPicture,
+        frames: &[&DpbEntry],
+        long_term: bool,
+    ) -> Vec<RefPicEntry> {
+        let marking = if long_term {
+            ReferenceMarking::LongTerm
+        } else {
+            ReferenceMarking::ShortTerm
+        };
+        let same = cur.structure.parity().unwrap_or(0);
+        let fields = |parity: usize| {
+            frames
+                .iter()
+                .filter(move |f| f.field_marked(parity, marking))
+                .map(move |f| self.entry(f, cur, Some(parity), long_term))
+        };
+        let mut same_parity = fields(same);
+        let mut opposite_parity = fields(1 - same);
+
+        let mut list = Vec::new();
+        loop {
+            match (same_parity.next(), opposite_parity.next()) {
+                (Some(a), Some(b)) => list.extend([a, b]),
+                (Some(a), None) => {
+                    list.push(a);
+                    list.extend(same_parity);
+                    break;
+                }
+                (None, Some(b)) => {
+                    list.push(b);
+                    list.extend(opposite_parity);
+                    break;
+                }
+                (None, None) => break,
+            }
+        }
+        list
+    }
+
+    /// Apply ref_pic_list_modification() to an initial list and truncate it
+    /// to its active size (Section 8.2.4.3).
+    fn modify_list(
+        &self,
+        cur: &CurrentPicture,
+        initial: Vec<RefPicEntry>,
+        num_active: usize,
+        modification: Option<&RefPicListModification>,
+    ) -> Vec<RefPicEntry> {
+        let mut list = initial;
+        list.truncate(num_active);
+        let Some(modification) = modification else {
+            return list;
+        };
+
+        let (curr_pic_num, max_pic_num) = cur.pic_nums(self.max_frame_num);
+        let mut pic_num_pred = curr_pic_num;
+        for (ref_idx, &(idc, value)) in modification.modifications.iter().enumerate() {
+            let (pic_num, long_term) = match idc {
+                0 | 1 => {
+                    let abs_diff_pic_num = value as i32 + 1;
+                    // Equations 8-34 to 8-36
+                    let mut no_wrap = if idc == 0 {
+                        pic_num_pred - abs_diff_pic_num
+                    } else {
+                        pic_num_pred + abs_diff_pic_num
+                    };
+                    if no_wrap < 0 {
+                        no_wrap += max_pic_num;
+                    } else if no_wrap >= max_pic_num {
+                        no_wrap -= max_pic_num;
+                    }
+                    pic_num_pred = no_wrap;
+                    let pic_num = if no_wrap > curr_pic_num {
+                        no_wrap - max_pic_num
+                    } else {
+                        no_wrap
+                    };
+                    (pic_num, false)
+                }
+                2 => (value as i32, true),
+                _ => continue,
+            };
+
+            let entry = self
+                .reference_pictures(cur, long_term)
+                .into_iter()
+                .find(|e| e.pic_num == pic_num)
+                .unwrap_or(RefPicEntry {
+                    nal_index: None,
+                    structure: cur.structure,
+                    poc: 0,
+                    frame_num: 0,
+                    pic_num,
+                    long_term,
+                });
+
+            let ref_idx = ref_idx.min(list.len());
+            list.insert(ref_idx, entry);
+            if let Some(dup) = list[ref_idx + 1..]
+                .iter()
+                .position(|e| e.pic_num == pic_num && e.long_term == long_term)
+            {
+                list.remove(ref_idx + 1 + dup);
+            }
+            list.truncate(num_active);
+        }
+        list
+    }
+
+    /// Sliding window marking (Section 8.2.5.3).
+    fn sliding_window(&mut self, cur_frame_num: u32, exclude: Option<usize>) {
+        loop {
+            let num_ref = self
+                .frames
+                .iter()
+                .enumerate()
+                .filter(|&(i, f)| Some(i) != exclude && f.is_reference())
+                .count();
+            if num_ref < self.max_num_ref_frames {
+                return;
+            }
+            let oldest = self
+                .frames
+                .iter()
+                .enumerate()
+                .filter(|&(i, f)| Some(i) != exclude && f.any_marked(ReferenceMarking::ShortTerm))
+                .min_by_key(|(_, f)| self.frame_num_wrap(f, cur_frame_num))
+                .map(|(i, _)| i);
+            match oldest {
+                Some(i) => self.frames[i].mark_unused(ReferenceMarking::ShortTerm),
+                None => return,
+            }
+        }
+    }
+
+    /// Infer "non-existing" frames for a gap in frame_num (Section 8.2.5.2).
+    fn fill_frame_num_gap(&mut self, frame_num: u32, sps: &Sps) {
+        let expected = (self.prev_ref_frame_num + 1) % self.max_frame_num;
+        if !sps.gaps_in_frame_num_value_allowed_flag
+            || frame_num == self.prev_ref_frame_num
+            || frame_num == expected
+        {
+            return;
+        }
+
+        // Only the last max_num_ref_frames inferred frames can survive the
+        // sliding window
+        let gap = (frame_num + self.max_frame_num - expected) % self.max_frame_num;
+        let skip = gap.saturating_sub(self.max_num_ref_frames as u32);
+        for i in skip..gap {
+            let unused_frame_num = (expected + i) % self.max_frame_num;
+            self.sliding_window(unused_frame_num, None);
+            let field = DpbField {
+                nal_index: None,
+                poc: 0,
+                marking: ReferenceMarking::ShortTerm,
+            };
+            self.frames.push(DpbEntry {
+                frame_num: unused_frame_num,
+                top: Some(field),
+                bottom: Some(field),
+                long_term_frame_idx: None,
+                non_existing: true,
+            });
+            self.frames.retain(DpbEntry::is_reference);
+            self.prev_ref_frame_num = unused_frame_num;
+        }
+    }
+
+    /// Find the short-term (or long-term) picture with a picture number,
+    /// as a DPB index and field parity.
+    fn find_picture(
+        &self,
+        cur: &CurrentPicture,
+        pic_num: i32,
+        long_term: bool,
+    ) -> Option<(usize, Option<usize>)> {
+        let marking = if long_term {
+            ReferenceMarking::LongTerm
+        } else {
+            ReferenceMarking::ShortTerm
+        };
+        self.frames.iter().enumerate().find_map(|(i, frame)| {
+            if cur.structure.is_field() {
+                (0..2)
+                    .filter(|&p| frame.field_marked(p, marking))
+                    .find(|&p| self.entry(frame, cur, Some(p), long_term).pic_num == pic_num)
+                    .map(|p| (i, Some(p)))
+            } else {
+                (frame.frame_marked(marking)
+                    && self.entry(frame, cur, None, long_term).pic_num == pic_num)
+                    .then_some((i, None))
+            }
+        })
+    }
+
+    /// Mark a frame, or one of its fields, as unused.
+    fn unmark(&mut self, index: usize, parity: Option<usize>) {
+        let frame = &mut self.frames[index];
+        match parity {
+            None => {
+                for field in frame.fields_mut() {
+                    field.marking = ReferenceMarking::Unused;
+                }
+            }
+            Some(0) => frame
+                .top
+                .iter_mut()
+                .for_each(|f| f.marking = ReferenceMarking::Unused),
+            Some(_) => frame
+                .bottom
+                .iter_mut()
+                .for_each(|f| f.marking = ReferenceMarking::Unused),
+        }
+    }
+
+    /// Free a LongTermFrameIdx held by a frame other than `keep`.
+    fn release_long_term_frame_idx(&mut self, long_term_frame_idx: u32, keep: Option<usize>) {
+        for (i, frame) in self.frames.iter_mut().enumerate() {
+            if Some(i) != keep && frame.long_term_frame_idx == Some(long_term_frame_idx) {
+                frame.mark_unused(ReferenceMarking::LongTerm);
+                frame.long_term_frame_idx = None;
+            }
+        }
+    }
+
+    /// Apply a memory management control operation (Section 8.2.5.4).
+    fn apply_mmco(&mut self, op: Mmco, cur: &CurrentPicture) {
+        let (curr_pic_num, _) = cur.pic_nums(self.max_frame_num);
+        match op {
+            Mmco::ShortTermUnused {
+                difference_of_pic_nums_minus1,
+            } => {
+                let pic_num = curr_pic_num - (difference_of_pic_nums_minus1 as i32 + 1);
+                if let Some((i, parity)) = self.find_picture(cur, pic_num, false) {
+                    self.unmark(i, parity);
+                }
+            }
+            Mmco::LongTermUnused { long_term_pic_num } => {
+                if let Some((i, parity)) = self.find_picture(cur, long_term_pic_num as i32, true) {
+                    self.unmark(i, parity);
+                }
+            }
+            Mmco::ShortTermToLongTerm {
+                difference_of_pic_nums_minus1,
+                long_term_frame_idx,
+            } => {
+                let pic_num = curr_pic_num - (difference_of_pic_nums_minus1 as i32 + 1);
+                let Some((i, parity)) = self.find_picture(cur, pic_num, false) else {
+                    return;
+                };
+                self.release_long_term_frame_idx(long_term_frame_idx, Some(i));
+                let frame = &mut self.frames[i];
+                if frame
+                    .long_term_frame_idx
+                    .is_some_and(|idx| idx != long_term_frame_idx)
+                {
+                    frame.mark_unused(ReferenceMarking::LongTerm);
+                }
+                frame.long_term_frame_idx = Some(long_term_frame_idx);
+                let fields: Vec<&mut DpbField> = match parity {
+                    None => frame.fields_mut().collect(),
+                    Some(0) => frame.top.iter_mut().collect(),
+                    Some(_) => frame.bottom.iter_mut().collect(),
+                };
+                for field in fields {
+                    field.marking = ReferenceMarking::LongTerm;
+                }
+            }
+            Mmco::MaxLongTermFrameIdx {
+                max_long_term_frame_idx_plus1,
+            } => {
+                self.max_long_term_frame_idx = max_long_term_frame_idx_plus1.checked_sub(1);
+                let max = self.max_long_term_frame_idx;
+                for frame in &mut self.frames {
+                    if frame
+                        .long_term_frame_idx
+                        .is_some_and(|idx| max.is_none_or(|max| idx > max))
+                    {
+                        frame.mark_unused(ReferenceMarking::LongTerm);
+                        frame.long_term_frame_idx = None;
+                    }
+                }
+            }
+            // Handled by finish_picture, which owns the current picture
+            Mmco::AllUnused | Mmco::CurrentLongTerm { .. } => {}
+        }
+    }
+
+    /// Store the current reference picture, as a new frame or as the second
+    /// field of the frame holding its first field.
+    fn store_current(&mut self, cur: &CurrentPicture, long_term_frame_idx: Option<u32>) {
+        let marking = if long_term_frame_idx.is_some() {
+            ReferenceMarking::LongTerm
+        } else {
+            ReferenceMarking::ShortTerm
+        };
+        let field = |poc| DpbField {
+            nal_index: Some(cur.nal_index),
+            poc,
+            marking,
+        };
+
+        let index = match cur.first_field.filter(|&i| i < self.frames.len()) {
+            Some(i) => i,
+            None => {
+                self.frames.push(DpbEntry {
+                    frame_num: cur.frame_num,
+                    top: None,
+                    bottom: None,
+                    long_term_frame_idx: None,
+                    non_existing: false,
+                });
+                self.frames.len() - 1
+            }
+        };
+        let frame = &mut self.frames[index];
+        frame.frame_num = cur.frame_num;
+        match cur.structure {
+            PictureStructure::Frame => {
+                frame.top = Some(field(cur.top_poc));
+                frame.bottom = Some(field(cur.bottom_poc));
+            }
+            PictureStructure::TopField => frame.top = Some(field(cur.top_poc)),
+            PictureStructure::BottomField => frame.bottom = Some(field(cur.bottom_poc)),
+        }
+        if long_term_frame_idx.is_some() {
+            frame.long_term_frame_idx = long_term_frame_idx;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slice::SliceType;
+    use crate::sps::{ChromaFormat, ProfileIdc};
+
+    fn sps(max_num_ref_frames: u32) -> Sps {
+        Sps {
+            profile_idc: ProfileIdc::High,
+            constraint_set0_flag: false,
+            constraint_set1_flag: false,
+            constraint_set2_flag: false,
+            constraint_set3_flag: false,
+            constraint_set4_flag: false,
+            constraint_set5_flag: false,
+            level_idc: 41,
+            seq_parameter_set_id: 0,
+            chroma_format_idc: ChromaFormat::Yuv420,
+            separate_colour_plane_flag: false,
+            bit_depth_luma_minus8: 0,
+            bit_depth_chroma_minus8: 0,
+            qpprime_y_zero_transform_bypass_flag: false,
+            seq_scaling_matrix_present_flag: false,
+            log2_max_frame_num_minus4: 0,
+            pic_order_cnt_type: 0,
+            log2_max_pic_order_cnt_lsb_minus4: 0,
+            delta_pic_order_always_zero_flag: false,
+            offset_for_non_ref_pic: 0,
+            offset_for_top_to_bottom_field: 0,
+            num_ref_frames_in_pic_order_cnt_cycle: 0,
+            offset_for_ref_frame: vec![],
+            max_num_ref_frames,
+            gaps_in_frame_num_value_allowed_flag: false,
+            pic_width_in_mbs_minus1: 10,
+            pic_height_in_map_units_minus1: 10,
+            frame_mbs_only_flag: false,
+            mb_adaptive_frame_field_flag: false,
+            direct_8x8_inference_flag: true,
+            frame_cropping_flag: false,
+            frame_crop_left_offset: 0,
+            frame_crop_right_offset: 0,
+            frame_crop_top_offset: 0,
+            frame_crop_bottom_offset: 0,
+            vui_parameters_present_flag: false,
+            vui_parameters: None,
+        }
+    }
+
+    fn header(slice_type: SliceType, frame_num: u32) -> SliceHeader {
+        SliceHeader {
+            first_mb_in_slice: 0,
+            slice_type,
+            pic_parameter_set_id: 0,
+            colour_plane_id: 0,
+            frame_num,
+            field_pic_flag: false,
+            bottom_field_flag: false,
+            idr_pic_id: 0,
+            pic_order_cnt_lsb: 0,
+            delta_pic_order_cnt_bottom: 0,
+            delta_pic_order_cnt: [0, 0],
+            redundant_pic_cnt: 0,
+            direct_spatial_mv_pred_flag: false,
+            num_ref_idx_active_override_flag: false,
+            num_ref_idx_l0_active_minus1: 3,
+            num_ref_idx_l1_active_minus1: 3,
+            ref_pic_list_modification_flag_l0: false,
+            ref_pic_list_modification_flag_l1: false,
+            ref_pic_list_modification_l0: RefPicListModification::default(),
+            ref_pic_list_modification_l1: RefPicListModification::default(),
+            dec_ref_pic_marking: DecRefPicMarking::default(),
+            cabac_init_idc: 0,
+            slice_qp_delta: 0,
+            sp_for_switch_flag: false,
+            slice_qs_delta: 0,
+            disable_deblocking_filter_idc: 0,
+            slice_alpha_c0_offset_div2: 0,
+            slice_beta_offset_div2: 0,
+            slice_group_change_cycle: 0,
+        }
+    }
+
+    fn pocs(list: &[RefPicEntry]) -> Vec<i32> {
+        list.iter().map(|e| e.poc).collect()
+    }
+
+    #[test]
+    fn test_sliding_window_and_p_list() {
+        let sps = sps(2);
+        let mut dpb = Dpb::new();
+        dpb.start_picture(0, 3, true, &header(SliceType::I, 0), &sps, 0);
+        for frame_num in 1..4 {
+            let h = header(SliceType::P, frame_num);
+            dpb.start_picture(frame_num as usize, 2, false, &h, &sps, 2 * frame_num as i32);
+            let lists = dpb.ref_pic_lists(&h);
+            // Most recent frame first, at most max_num_ref_frames frames
+            assert_eq!(lists.list0[0].poc, 2 * frame_num as i32 - 2);
+            assert!(lists.list0.len() <= 2);
+        }
+        dpb.finish_picture();
+        let frame_nums: Vec<u32> = dpb.contents().iter().map(|f| f.frame_num).collect();
+        assert_eq!(frame_nums, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_b_lists_and_non_reference_b() {
+        let sps = sps(4);
+        let mut dpb = Dpb::new();
+        dpb.start_picture(0, 3, true, &header(SliceType::I, 0), &sps, 0);
+        dpb.start_picture(1, 2, false, &header(SliceType::P, 1), &sps, 8);
+        dpb.start_picture(2, 2, false, &header(SliceType::P, 2), &sps, 4);
+
+        let b = header(SliceType::B, 3);
+        let contents = dpb.start_picture(3, 0, false, &b, &sps, 2);
+        assert_eq!(contents.len(), 3);
+        let lists = dpb.ref_pic_lists(&b);
+        assert_eq!(pocs(&lists.list0), vec![0, 4, 8]);
+        assert_eq!(pocs(&lists.list1), vec![4, 8, 0]);
+        assert_eq!(lists.list0[0].nal_index, Some(0));
+
+        // Non-reference B pictures are not stored
+        dpb.finish_picture();
+        assert_eq!(dpb.contents().len(), 3);
+    }
+
+    #[test]
+    fn test_mmco_long_term() {
+        let sps = sps(4);
+        let mut dpb = Dpb::new();
+        dpb.start_picture(0, 3, true, &header(SliceType::I, 0), &sps, 0);
+        dpb.start_picture(1, 2, false, &header(SliceType::P, 1), &sps, 2);
+
+        // Convert frame 0 to long-term index 0, drop nothing else
+        let mut p = header(SliceType::P, 2);
+        p.dec_ref_pic_marking.adaptive_ref_pic_marking_mode_flag = true;
+        p.dec_ref_pic_marking.mmco_operations = vec![(4, 0, 1), (3, 1, 0)];
+        dpb.start_picture(2, 2, false, &p, &sps, 4);
+        dpb.finish_picture();
+
+        let frame0 = &dpb.contents()[0];
+        assert_eq!(frame0.marking(), ReferenceMarking::LongTerm);
+        assert_eq!(frame0.long_term_frame_idx, Some(0));
+
+        // Long-term frames follow short-term ones in P lists
+        let h = header(SliceType::P, 3);
+        dpb.start_picture(3, 2, false, &h, &sps, 6);
+        let lists = dpb.ref_pic_lists(&h);
+        assert_eq!(pocs(&lists.list0), vec![4, 2, 0]);
+        assert!(lists.list0[2].long_term);
+
+        // Move the long-term frame to the front
+        let mut h = header(SliceType::P, 3);
+        h.ref_pic_list_modification_flag_l0 = true;
+        h.ref_pic_list_modification_l0.modifications = vec![(2, 0)];
+        assert_eq!(pocs(&dpb.ref_pic_lists(&h).list0), vec![0, 4, 2]);
+
+        // MMCO 5 clears the DPB and resets frame_num
+        let mut p = header(SliceType::P, 3);
+        p.dec_ref_pic_marking.adaptive_ref_pic_marking_mode_flag = true;
+        p.dec_ref_pic_marking.mmco_operations = vec![(5, 0, 0)];
+        dpb.start_picture(4, 2, false, &p, &sps, 6);
+        dpb.finish_picture();
+        assert_eq!(dpb.contents().len(), 1);
+        assert_eq!(dpb.contents()[0].frame_num, 0);
+        assert_eq!(dpb.contents()[0].top.unwrap().poc, 0);
+    }
+
+    #[test]
+    fn test_modification_short_term() {
+        let sps = sps(4);
+        let mut dpb = Dpb::new();
+        dpb.start_picture(0, 3, true, &header(SliceType::I, 0), &sps, 0);
+        dpb.start_picture(1, 2, false, &header(SliceType::P, 1), &sps, 2);
+        dpb.start_picture(2, 2, false, &header(SliceType::P, 2), &sps, 4);
+
+        // picNumPred 3 - 3 = 0: frame 0 first
+        let mut h = header(SliceType::P, 3);
+        h.num_ref_idx_l0_active_minus1 = 1;
+        h.ref_pic_list_modification_flag_l0 = true;
+        h.ref_pic_list_modification_l0.modifications = vec![(0, 2)];
+        dpb.start_picture(3, 2, false, &h, &sps, 6);
+        let lists = dpb.ref_pic_lists(&h);
+        assert_eq!(pocs(&lists.list0), vec![0, 4]);
+        assert_eq!(lists.list0[0].pic_num, 0);
+    }
+
+    #[test]
+    fn test_field_pair() {
+        let sps = sps(4);
+        let mut dpb = Dpb::new();
+
+        let mut top = header(SliceType::I, 0);
+        top.field_pic_flag = true;
+        dpb.start_picture(0, 3, true, &top, &sps, 0);
+
+        // Second field predicts from the first
+        let mut bottom = header(SliceType::P, 0);
+        bottom.field_pic_flag = true;
+        bottom.bottom_field_flag = true;
+        dpb.start_picture(1, 3, false, &bottom, &sps, 1);
+        let lists = dpb.ref_pic_lists(&bottom);
+        assert_eq!(lists.list0.len(), 1);
+        assert_eq!(lists.list0[0].structure, PictureStructure::TopField);
+        // Opposite parity: PicNum = 2 * FrameNumWrap
+        assert_eq!(lists.list0[0].pic_num, 0);
+
+        let mut top = header(SliceType::P, 1);
+        top.field_pic_flag = true;
+        dpb.start_picture(2, 2, false, &top, &sps, 4);
+        assert_eq!(dpb.contents().len(), 1);
+        let lists = dpb.ref_pic_lists(&top);
+        // Same parity first, then alternating
+        let structures: Vec<PictureStructure> = lists.list0.iter().map(|e| e.structure).collect();
+        assert_eq!(
+            structures,
+            vec![PictureStructure::TopField, PictureStructure::BottomField]
+        );
+        assert_eq!(lists.list0[0].nal_index, Some(0));
+        assert_eq!(lists.list0[1].nal_index, Some(1));
+    }
+
+    #[test]
+    fn test_frame_num_gap() {
+        let mut sps = sps(3);
+        sps.gaps_in_frame_num_value_allowed_flag = true;
+        let mut dpb = Dpb::new();
+        dpb.start_picture(0, 3, true, &header(SliceType::I, 0), &sps, 0);
+        let h = header(SliceType::P, 3);
+        let contents = dpb.start_picture(1, 2, false, &h, &sps, 6);
+        let frame_nums: Vec<u32> = contents.iter().map(|f| f.frame_num).collect();
+        assert_eq!(frame_nums, vec![0, 1, 2]);
+        assert!(contents[1].non_existing);
+        let lists = dpb.ref_pic_lists(&h);
+        assert_eq!(lists.list0[0].nal_index, None);
+        assert_eq!(lists.list0[2].nal_index, Some(0));
+    }
+}
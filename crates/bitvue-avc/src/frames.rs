@@ -2,11 +2,16 @@
 //!
 //! Functions for extracting individual frames from H.264 bitstreams
 
+use crate::dpb::{DpbEntry, PictureStructure, RefPicEntry, RefPicLists};
 use crate::nal::{find_nal_units, parse_nal_header, NalUnitType};
-use crate::parse_avc;
 use crate::slice::{SliceHeader, SliceType};
+use crate::{parse_avc, ParsedSlice};
+use bitvue_core::reference_graph::{
+    GraphNode, ReferenceEdge, ReferenceGraphView, ReferenceType, WorldBounds,
+};
 use bitvue_core::BitvueError;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// H.264 frame data extracted from the bitstream
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +36,15 @@ pub struct AvcFrame {
     pub is_ref: bool,
     /// Slice header (if available)
     pub slice_header: Option<SliceHeader>,
+    /// Reference picture lists of the first slice
+    #[serde(default)]
+    pub ref_pic_lists: RefPicLists,
+    /// Reference frames in the DPB when the frame starts decoding
+    #[serde(default)]
+    pub dpb: Vec<DpbEntry>,
+    /// Indices of the frames referenced by the reference picture lists
+    #[serde(default)]
+    pub ref_frames: Vec<usize>,
 }
 
 impl AvcFrame {
@@ -71,6 +85,9 @@ pub struct AvcFrameBuilder {
     is_idr: Option<bool>,
     is_ref: Option<bool>,
     slice_header: Option<SliceHeader>,
+    ref_pic_lists: Option<RefPicLists>,
+    dpb: Option<Vec<DpbEntry>>,
+    ref_frames: Option<Vec<usize>>,
 }
 
 impl AvcFrameBuilder {
@@ -134,6 +151,24 @@ impl AvcFrameBuilder {
         self
     }
 
+    /// Set the reference picture lists
+    pub fn ref_pic_lists(mut self, value: RefPicLists) -> Self {
+        self.ref_pic_lists = Some(value);
+        self
+    }
+
+    /// Set the DPB contents
+    pub fn dpb(mut self, value: Vec<DpbEntry>) -> Self {
+        self.dpb = Some(value);
+        self
+    }
+
+    /// Set the indices of the referenced frames
+    pub fn ref_frames(mut self, value: Vec<usize>) -> Self {
+        self.ref_frames = Some(value);
+        self
+    }
+
     /// Build the AvcFrame
     ///
     /// Returns an error if required fields are not set.
@@ -161,6 +196,9 @@ impl AvcFrameBuilder {
                 .is_ref
                 .ok_or_else(|| "is_ref is required".to_string())?,
             slice_header: self.slice_header,
+            ref_pic_lists: self.ref_pic_lists.unwrap_or_default(),
+            dpb: self.dpb.unwrap_or_default(),
+            ref_frames: self.ref_frames.unwrap_or_default(),
         })
     }
 }
@@ -225,32 +263,45 @@ pub fn extract_annex_b_frames(data: &[u8]) -> Result<Vec<AvcFrame>, BitvueError>
         return Ok(Vec::new());
     }
 
-    // Build NAL unit ranges (start, end) by pairing positions
-    // OPTIMIZATION: Use iterator chain instead of manual indexing for better performance
-    let nal_ranges: Vec<(usize, usize)> = nal_positions
+    // Build NAL unit ranges (start, end) by pairing positions. Positions
+    // point past the start code, which may be 3 or 4 bytes long; each range
+    // includes its own start code and ends where the next start code begins.
+    let nal_starts: Vec<usize> = nal_positions
+        .iter()
+        .map(|&start| {
+            if start >= 4 && data[start - 4..start] == [0, 0, 0, 1] {
+                start - 4
+            } else {
+                start.saturating_sub(3)
+            }
+        })
+        .collect();
+    let nal_ranges: Vec<(usize, usize)> = nal_starts
         .iter()
         .zip(
-            nal_positions
+            nal_starts
                 .iter()
                 .skip(1)
                 .chain(std::iter::once(&data.len())),
         )
-        .map(|(&start, &end)| {
-            // Adjust for start code (include start code in range)
-            let adjusted_start = start.saturating_sub(4);
-            (adjusted_start, end)
-        })
+        .map(|(&start, &end)| (start, end))
         .collect();
 
     let mut frames = Vec::new();
     let mut current_frame_nals: Vec<(usize, usize)> = Vec::new();
     let mut current_frame_index = 0;
-    let _current_poc: Option<i32> = None;
+    let mut current_slice: Option<&ParsedSlice> = None;
     let mut current_frame_num: Option<u32> = None;
     let mut current_is_idr = false;
     let mut current_is_ref = false;
     let mut current_frame_type = AvcFrameType::Unknown;
-    let mut current_slice_header: Option<SliceHeader> = None;
+
+    // Slices parsed by parse_avc, keyed by NAL unit offset (start code included)
+    let slices: HashMap<usize, &ParsedSlice> = stream
+        .slices
+        .iter()
+        .map(|slice| (stream.nal_units[slice.nal_index].offset, slice))
+        .collect();
 
     for (nal_start, nal_end) in nal_ranges {
         // Find the first byte after start code (actual NAL data)
@@ -284,38 +335,25 @@ pub fn extract_annex_b_frames(data: &[u8]) -> Result<Vec<AvcFrame>, BitvueError>
             // Check if this starts a new frame
             let new_frame = if current_frame_nals.is_empty() || is_idr != current_is_idr {
                 true // First NAL or IDR boundary
-            } else if let Some(slice) = &current_slice_header {
-                // Try to parse the current slice header
-                if let Ok(new_slice) = crate::slice::parse_slice_header(
-                    &data[nal_data_start + 1..nal_end],
-                    &stream.sps_map,
-                    &stream.pps_map,
-                    nal_type,
-                    nal_header.nal_ref_idc,
-                ) {
-                    // New frame if frame_num changes or first_mb_in_slice == 0
-                    new_slice.frame_num != slice.frame_num || new_slice.first_mb_in_slice == 0
-                } else {
-                    false
-                }
+            } else if let (Some(slice), Some(new_slice)) = (current_slice, slices.get(&nal_start)) {
+                // New frame if frame_num changes or first_mb_in_slice == 0
+                new_slice.header.frame_num != slice.header.frame_num
+                    || new_slice.header.first_mb_in_slice == 0
             } else {
                 false
             };
 
             if new_frame && !current_frame_nals.is_empty() {
                 // Finalize previous frame
-                // OPTIMIZATION: Use take() to move the Option instead of cloning
-                let slice_header = current_slice_header.take();
                 if let Some(frame) = build_frame_from_nals(
                     current_frame_index,
                     &current_frame_nals,
                     data,
-                    0,
                     current_frame_num.unwrap_or(0),
                     current_is_idr,
                     current_is_ref,
                     current_frame_type,
-                    slice_header,
+                    current_slice.take(),
                 ) {
                     frames.push(frame);
                 }
@@ -327,22 +365,15 @@ pub fn extract_annex_b_frames(data: &[u8]) -> Result<Vec<AvcFrame>, BitvueError>
             current_is_idr = is_idr;
             current_is_ref = is_ref;
 
-            // Try to parse slice header for frame type
-            if let Ok(slice) = crate::slice::parse_slice_header(
-                &data[nal_data_start + 1..nal_end],
-                &stream.sps_map,
-                &stream.pps_map,
-                nal_type,
-                nal_header.nal_ref_idc,
-            ) {
+            // Take the frame type from the parsed slice header
+            if let Some(&slice) = slices.get(&nal_start) {
                 if current_frame_nals.is_empty() {
                     current_frame_num = Some(slice.frame_num);
-                    // OPTIMIZATION: Move the slice instead of cloning since we own it
-                    current_slice_header = Some(slice);
-                } else {
-                    // Determine frame type from slice type without storing the header
-                    current_frame_type = AvcFrameType::from_slice_type(slice.slice_type);
+                    current_slice = Some(slice);
                 }
+
+                // Determine frame type from slice type
+                current_frame_type = AvcFrameType::from_slice_type(slice.header.slice_type);
             }
 
             current_frame_nals.push((nal_start, nal_end));
@@ -350,18 +381,15 @@ pub fn extract_annex_b_frames(data: &[u8]) -> Result<Vec<AvcFrame>, BitvueError>
             // Non-VCL NAL after some VCL NALs
             if nal_type == NalUnitType::Aud {
                 // AUD definitely ends the current frame
-                // OPTIMIZATION: Use take() to move the Option instead of cloning
-                let slice_header = current_slice_header.take();
                 if let Some(frame) = build_frame_from_nals(
                     current_frame_index,
                     &current_frame_nals,
                     data,
-                    0,
                     current_frame_num.unwrap_or(0),
                     current_is_idr,
                     current_is_ref,
                     current_frame_type,
-                    slice_header,
+                    current_slice.take(),
                 ) {
                     frames.push(frame);
                 }
@@ -373,44 +401,95 @@ pub fn extract_annex_b_frames(data: &[u8]) -> Result<Vec<AvcFrame>, BitvueError>
 
     // Don't forget the last frame
     if !current_frame_nals.is_empty() {
-        // Find POC from parsed slices
-        let poc = stream
-            .slices
-            .get(current_frame_index)
-            .map(|s| s.poc)
-            .unwrap_or(0);
-
-        // OPTIMIZATION: Use take() to move the Option instead of cloning
-        let slice_header = current_slice_header.take();
         if let Some(frame) = build_frame_from_nals(
             current_frame_index,
             &current_frame_nals,
             data,
-            poc,
             current_frame_num.unwrap_or(0),
             current_is_idr,
             current_is_ref,
             current_frame_type,
-            slice_header,
+            current_slice.take(),
         ) {
             frames.push(frame);
         }
     }
 
+    resolve_ref_frames(&mut frames);
     Ok(frames)
 }
 
+/// Field parity of a frame coded as a field (`true` for a bottom field)
+fn field_parity(frame: &AvcFrame) -> Option<bool> {
+    frame
+        .slice_header
+        .as_ref()
+        .filter(|h| h.field_pic_flag)
+        .map(|h| h.bottom_field_flag)
+}
+
+/// Find the frame holding the reference picture `entry` of `frame_index`
+///
+/// Reference pictures are identified by frame_num, searching backwards in
+/// decoding order without crossing an IDR frame. A frame reference made of
+/// two fields resolves to its first field.
+fn find_ref_frame(frames: &[AvcFrame], frame_index: usize, entry: &RefPicEntry) -> Option<usize> {
+    entry.nal_index?;
+    let parity = match entry.structure {
+        PictureStructure::Frame => None,
+        PictureStructure::TopField => Some(false),
+        PictureStructure::BottomField => Some(true),
+    };
+
+    let end = frame_index.min(frames.len());
+    for i in (0..end).rev() {
+        let frame = &frames[i];
+        let matches = frame.is_ref
+            && frame.frame_num == entry.frame_num
+            && (parity.is_none() || field_parity(frame) == parity);
+        if matches {
+            // Step back to the first field of a complementary field pair
+            let first_field = parity.is_none()
+                && i > 0
+                && field_parity(frame).is_some()
+                && !frame.is_idr
+                && frames[i - 1].is_ref
+                && frames[i - 1].frame_num == frame.frame_num
+                && field_parity(&frames[i - 1]).is_some_and(|p| Some(!p) == field_parity(frame));
+            return Some(if first_field { i - 1 } else { i });
+        }
+        if frame.is_idr {
+            break;
+        }
+    }
+    None
+}
+
+/// Resolve the reference picture lists of every frame to frame indices
+fn resolve_ref_frames(frames: &mut [AvcFrame]) {
+    for i in 0..frames.len() {
+        let mut ref_frames = Vec::new();
+        for entry in frames[i].ref_pic_lists.iter() {
+            if let Some(j) = find_ref_frame(frames, i, entry) {
+                if !ref_frames.contains(&j) {
+                    ref_frames.push(j);
+                }
+            }
+        }
+        frames[i].ref_frames = ref_frames;
+    }
+}
+
 /// Build a frame from collected NAL unit positions
 fn build_frame_from_nals(
     frame_index: usize,
     nal_positions: &[(usize, usize)],
     data: &[u8],
-    poc: i32,
     frame_num: u32,
     is_idr: bool,
     is_ref: bool,
     frame_type: AvcFrameType,
-    slice_header: Option<SliceHeader>,
+    slice: Option<&ParsedSlice>,
 ) -> Option<AvcFrame> {
     if nal_positions.is_empty() {
         return None;
@@ -431,11 +510,14 @@ fn build_frame_from_nals(
         nal_data,
         offset,
         size,
-        poc,
+        poc: slice.map_or(0, |s| s.poc),
         frame_num,
         is_idr,
         is_ref,
-        slice_header,
+        slice_header: slice.map(|s| s.header.clone()),
+        ref_pic_lists: slice.map(|s| s.ref_pic_lists.clone()).unwrap_or_default(),
+        dpb: slice.map(|s| s.dpb.clone()).unwrap_or_default(),
+        ref_frames: Vec::new(),
     })
 }
 
@@ -478,7 +560,7 @@ pub fn avc_frame_to_unit_node(frame: &AvcFrame, _stream_id: u8) -> bitvue_core::
         qp_avg,
         mv_grid: None, // TODO: Extract from slice data
         temporal_id: None,
-        ref_frames: None, // TODO: Calculate from slice header
+        ref_frames: (!frame.ref_frames.is_empty()).then(|| frame.ref_frames.clone()),
         ref_slots: None,
     }
 }
@@ -491,6 +573,67 @@ pub fn avc_frames_to_unit_nodes(frames: &[AvcFrame]) -> Vec<bitvue_core::UnitNod
         .collect()
 }
 
+/// Build the reference graph of a stream from its frames
+///
+/// Nodes are laid out in display order (IDR periods, then POC order).
+/// Edges point from a frame to the frames in its reference picture lists.
+pub fn avc_reference_graph(frames: &[AvcFrame]) -> ReferenceGraphView {
+    const NODE_SPACING: f32 = 40.0;
+
+    let mut idr_period = 0;
+    let mut order: Vec<(usize, i32, usize)> = Vec::with_capacity(frames.len());
+    for (i, frame) in frames.iter().enumerate() {
+        if frame.is_idr && i > 0 {
+            idr_period += 1;
+        }
+        order.push((idr_period, frame.poc, i));
+    }
+    order.sort_unstable();
+    let mut display_idx = vec![0; frames.len()];
+    for (display, &(_, _, i)) in order.iter().enumerate() {
+        display_idx[i] = display;
+    }
+
+    let mut graph = ReferenceGraphView::new(WorldBounds::new(
+        0.0,
+        0.0,
+        frames.len().max(1) as f32 * NODE_SPACING,
+        NODE_SPACING,
+    ));
+
+    for (i, frame) in frames.iter().enumerate() {
+        graph.add_node(GraphNode::new(
+            display_idx[i],
+            frame.frame_type.as_str().to_string(),
+            (display_idx[i] as f32 * NODE_SPACING, 0.0),
+        ));
+
+        let lists = &frame.ref_pic_lists;
+        let entries = lists
+            .list0
+            .iter()
+            .map(|e| (e, ReferenceType::L0))
+            .chain(lists.list1.iter().map(|e| (e, ReferenceType::L1)));
+        let mut edges: Vec<(usize, ReferenceType)> = Vec::new();
+        for (entry, list_type) in entries {
+            let Some(j) = find_ref_frame(frames, i, entry) else {
+                continue;
+            };
+            let ref_type = if entry.long_term {
+                ReferenceType::LongTerm
+            } else {
+                list_type
+            };
+            if !edges.contains(&(j, ref_type)) {
+                edges.push((j, ref_type));
+                graph.add_edge(ReferenceEdge::new(display_idx[i], display_idx[j], ref_type));
+            }
+        }
+    }
+
+    graph
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -516,4 +659,58 @@ mod tests {
         assert_eq!(AvcFrameType::from_slice_type(SliceType::P), AvcFrameType::P);
         assert_eq!(AvcFrameType::from_slice_type(SliceType::B), AvcFrameType::B);
     }
+
+    #[test]
+    fn test_reference_graph() {
+        let entry = |frame_num: u32, poc| RefPicEntry {
+            nal_index: Some(frame_num as usize),
+            structure: PictureStructure::Frame,
+            poc,
+            frame_num,
+            pic_num: frame_num as i32,
+            long_term: false,
+        };
+        let frame = |index, frame_type, poc, frame_num, is_ref, list0: Vec<(u32, i32)>| {
+            let list1: Vec<(u32, i32)> = if frame_type == AvcFrameType::B {
+                vec![(1, 4)]
+            } else {
+                vec![]
+            };
+            AvcFrame::builder()
+                .frame_index(index)
+                .frame_type(frame_type)
+                .offset(index)
+                .size(1)
+                .poc(poc)
+                .frame_num(frame_num)
+                .is_idr(index == 0)
+                .is_ref(is_ref)
+                .ref_pic_lists(RefPicLists {
+                    list0: list0.into_iter().map(|(n, p)| entry(n, p)).collect(),
+                    list1: list1.into_iter().map(|(n, p)| entry(n, p)).collect(),
+                })
+                .build()
+                .unwrap()
+        };
+        // Decode order I0 P4 B2, with a non-reference B sharing frame_num 2
+        let mut frames = vec![
+            frame(0, AvcFrameType::I, 0, 0, true, vec![]),
+            frame(1, AvcFrameType::P, 4, 1, true, vec![(0, 0)]),
+            frame(2, AvcFrameType::B, 2, 2, false, vec![(0, 0)]),
+        ];
+        resolve_ref_frames(&mut frames);
+        assert_eq!(frames[1].ref_frames, vec![0]);
+        assert_eq!(frames[2].ref_frames, vec![0, 1]);
+        assert_eq!(
+            avc_frame_to_unit_node(&frames[2], 0).ref_frames,
+            Some(vec![0, 1])
+        );
+        assert_eq!(avc_frame_to_unit_node(&frames[0], 0).ref_frames, None);
+
+        // Display order I0 B2 P4
+        let graph = avc_reference_graph(&frames);
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.get_references(1), [0, 2].into_iter().collect());
+        assert_eq!(graph.get_dependents(0), [1, 2].into_iter().collect());
+    }
 }
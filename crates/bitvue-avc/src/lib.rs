@@ -30,6 +30,7 @@
 //! ```
 
 pub mod bitreader;
+pub mod dpb;
 pub mod error;
 pub mod frames;
 pub mod nal;
@@ -37,24 +38,32 @@ pub mod overlay_extraction;
 pub mod pps;
 pub mod sei;
 pub mod slice;
+pub mod slice_data;
 pub mod sps;
+pub mod syntax;
 
 pub use bitreader::{remove_emulation_prevention_bytes, BitReader};
+pub use dpb::{Dpb, DpbEntry, DpbField, PictureStructure, RefPicEntry, RefPicLists};
 pub use error::{AvcError, Result};
 pub use frames::{
-    avc_frame_to_unit_node, avc_frames_to_unit_nodes, extract_annex_b_frames,
+    avc_frame_to_unit_node, avc_frames_to_unit_nodes, avc_reference_graph, extract_annex_b_frames,
     extract_frame_at_index, AvcFrame, AvcFrameType,
 };
 pub use nal::{
     find_nal_units, parse_nal_header, parse_nal_units, NalUnit, NalUnitHeader, NalUnitType,
 };
 pub use overlay_extraction::{
-    extract_mv_grid, extract_partition_grid, extract_qp_grid, Macroblock, MbType, MotionVector,
+    extract_macroblocks, extract_mv_grid, extract_partition_grid, extract_qp_grid, Macroblock,
+    MbPartition, MbType, MotionVector,
 };
 pub use pps::{parse_pps, Pps};
-pub use sei::{parse_sei, SeiMessage, SeiPayloadType};
+pub use sei::{
+    parse_sei, parse_sei_with_sps, BufferingPeriod, InitialCpbRemoval, PicTiming, SeiMessage,
+    SeiParsedData, SeiPayloadType,
+};
 pub use slice::{parse_slice_header, SliceHeader, SliceType};
-pub use sps::{parse_sps, ChromaFormat, ProfileIdc, Sps};
+pub use slice_data::{parse_slice_data, SliceMacroblocks};
+pub use sps::{parse_sps, ChromaFormat, CpbSpec, HrdParameters, HrdType, ProfileIdc, Sps};
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -85,6 +94,13 @@ pub struct ParsedSlice {
     pub poc: i32,
     /// Frame number.
     pub frame_num: u32,
+    /// Reference picture lists of the slice.
+    #[serde(default)]
+    pub ref_pic_lists: RefPicLists,
+    /// Reference frames in the DPB when the picture containing the slice
+    /// starts decoding.
+    #[serde(default)]
+    pub dpb: Vec<DpbEntry>,
 }
 
 impl AvcStream {
@@ -177,6 +193,222 @@ impl AvcStream {
             format!("{}.{}", major, minor)
         })
     }
+
+    /// SPS referenced by the first slice, or the lowest numbered SPS if
+    /// there are no slices.
+    fn active_sps(&self) -> Option<&Sps> {
+        self.slices
+            .first()
+            .and_then(|s| self.get_pps(s.header.pic_parameter_set_id))
+            .and_then(|pps| self.get_sps(pps.seq_parameter_set_id))
+            .or_else(|| self.sps_map.keys().min().and_then(|id| self.get_sps(*id)))
+    }
+
+    /// HRD parameters of the NAL or VCL HRD signalled in the active SPS VUI.
+    ///
+    /// Uses SchedSelIdx 0. The initial removal delay comes from the first
+    /// buffering period SEI. Returns `None` when the stream carries no
+    /// parameters for that HRD or no VUI timing.
+    pub fn hrd_parameters(&self, hrd: HrdType) -> Option<bitvue_core::hrd::HrdParameters> {
+        let vui = self.active_sps()?.vui_parameters.as_ref()?;
+        let params = vui.hrd_parameters(hrd)?;
+        let cpb = params.cpbs.first()?;
+
+        let initial_cpb_removal_delay = self
+            .sei_messages
+            .iter()
+            .find_map(|sei| match &sei.parsed {
+                Some(SeiParsedData::BufferingPeriod(bp)) => match hrd {
+                    HrdType::Nal => &bp.nal_initial_cpb_removal,
+                    HrdType::Vcl => &bp.vcl_initial_cpb_removal,
+                }
+                .first()
+                .map(|r| r.initial_cpb_removal_delay as u64),
+                _ => None,
+            })
+            .unwrap_or(0);
+
+        Some(bitvue_core::hrd::HrdParameters {
+            cpb_size_bits: params.cpb_size(cpb),
+            bit_rate_bps: params.bit_rate(cpb),
+            initial_cpb_removal_delay,
+            cpb_removal_delay_length: params.cpb_removal_delay_length_minus1 + 1,
+            dpb_output_delay_length: params.dpb_output_delay_length_minus1 + 1,
+            time_scale: Some(vui.time_scale).filter(|&t| t > 0)?,
+            num_units_in_tick: Some(vui.num_units_in_tick).filter(|&n| n > 0)?,
+            low_delay_hrd: vui.low_delay_hrd_flag,
+            cbr_flag: cpb.cbr_flag,
+        })
+    }
+
+    /// CPB removal, earliest arrival and DPB output times of every access
+    /// unit (C.1.2).
+    ///
+    /// Access unit sizes count every byte of the byte stream for the NAL
+    /// HRD, and only VCL and filler data NAL units for the VCL HRD. Removal
+    /// times count clock ticks from the most recent buffering period access
+    /// unit using the picture timing SEI; access units without one are
+    /// assumed to follow one frame after their predecessor. Frame indices
+    /// are in decode order.
+    pub fn hrd_timings(
+        &self,
+        hrd: HrdType,
+        params: &bitvue_core::hrd::HrdParameters,
+    ) -> Vec<bitvue_core::hrd::FrameHrdTiming> {
+        use bitvue_core::hrd::FrameHrdTiming;
+
+        let first_slices: Vec<&ParsedSlice> = self
+            .slices
+            .iter()
+            .filter(|s| s.header.is_first_slice())
+            .collect();
+        if first_slices.is_empty() {
+            return Vec::new();
+        }
+        let first_nals: Vec<usize> = first_slices.iter().map(|s| s.nal_index).collect();
+
+        // Access unit sizes: non-VCL NAL units lead the next picture, except
+        // filler data and end of sequence/stream, which trail the current one.
+        // Buffering period and picture timing SEI belong to the access unit
+        // whose first slice follows them.
+        let mut sizes = vec![0u64; first_nals.len()];
+        let mut buffering = vec![None; first_nals.len()];
+        let mut timing = vec![None; first_nals.len()];
+        let mut pending = 0u64;
+        let mut current: Option<usize> = None;
+        let mut active_sps_id = self.active_sps().map(|sps| sps.seq_parameter_set_id);
+        for (i, nal) in self.nal_units.iter().enumerate() {
+            let nal_type = nal.header.nal_unit_type;
+            let size = match hrd {
+                HrdType::Nal => nal.size as u64,
+                HrdType::Vcl if nal_type.is_vcl() || nal_type == NalUnitType::FillerData => {
+                    nal.raw_payload.len() as u64 + 1
+                }
+                HrdType::Vcl => 0,
+            };
+
+            if let Ok(next) = first_nals.binary_search(&i) {
+                sizes[next] += pending + size;
+                pending = 0;
+                current = Some(next);
+                active_sps_id = self
+                    .get_pps(first_slices[next].header.pic_parameter_set_id)
+                    .map(|pps| pps.seq_parameter_set_id)
+                    .or(active_sps_id);
+                continue;
+            }
+            match current {
+                Some(au)
+                    if nal_type.is_vcl()
+                        || matches!(
+                            nal_type,
+                            NalUnitType::FillerData
+                                | NalUnitType::EndOfSequence
+                                | NalUnitType::EndOfStream
+                        ) =>
+                {
+                    sizes[au] += size;
+                }
+                _ => pending += size,
+            }
+
+            if nal_type != NalUnitType::Sei {
+                continue;
+            }
+            let au = first_nals.partition_point(|&n| n < i);
+            let Ok(messages) = parse_sei_with_sps(&nal.payload, &self.sps_map, active_sps_id)
+            else {
+                continue;
+            };
+            for message in messages {
+                match message.parsed {
+                    Some(SeiParsedData::BufferingPeriod(bp)) if au < buffering.len() => {
+                        buffering[au] = Some(bp);
+                    }
+                    Some(SeiParsedData::PicTiming(pt)) if au < timing.len() => {
+                        timing[au] = Some(pt);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let tc = params.num_units_in_tick as f64 / params.time_scale.max(1) as f64;
+        let mut anchor: Option<f64> = None;
+        let mut previous = 0.0;
+        let mut initial_removal: Option<InitialCpbRemoval> = None;
+        sizes
+            .iter()
+            .enumerate()
+            .map(|(n, &size)| {
+                let cpb_delay = timing[n].as_ref().and_then(|pt| pt.cpb_removal_delay);
+                let dpb_delay = timing[n].as_ref().and_then(|pt| pt.dpb_output_delay);
+                // A frame lasts two field ticks
+                let ticks = if first_slices[n].header.field_pic_flag {
+                    1.0
+                } else {
+                    2.0
+                };
+
+                let removal = match (anchor, cpb_delay) {
+                    (None, _) => params.initial_cpb_removal_delay as f64 / 90000.0,
+                    (Some(t_nb), Some(delay)) => t_nb + tc * delay as f64,
+                    (Some(_), None) => previous + tc * ticks,
+                };
+                if anchor.is_none() || buffering[n].is_some() {
+                    anchor = Some(removal);
+                }
+                previous = removal;
+
+                // t_ai,earliest (C-2, C-3): the first access unit of a
+                // buffering period may arrive initial_cpb_removal_delay
+                // before removal, later ones the delay plus its offset
+                let buffering_period = buffering[n].as_ref().and_then(|bp| {
+                    match hrd {
+                        HrdType::Nal => &bp.nal_initial_cpb_removal,
+                        HrdType::Vcl => &bp.vcl_initial_cpb_removal,
+                    }
+                    .first()
+                    .copied()
+                });
+                let earliest_ticks = match buffering_period {
+                    Some(delays) => {
+                        initial_removal = Some(delays);
+                        Some(delays.initial_cpb_removal_delay as u64)
+                    }
+                    None => initial_removal.map(|delays| {
+                        delays.initial_cpb_removal_delay as u64
+                            + delays.initial_cpb_removal_delay_offset as u64
+                    }),
+                };
+                let earliest_arrival = earliest_ticks.map(|ticks| removal - ticks as f64 / 90000.0);
+
+                let output = removal + tc * dpb_delay.unwrap_or(0) as f64;
+                let to_90khz = |ticks: u32| (tc * ticks as f64 * 90000.0).round() as u64;
+                FrameHrdTiming {
+                    display_idx: n,
+                    frame_size_bits: size * 8,
+                    cpb_removal_delay: cpb_delay.map(to_90khz),
+                    dpb_output_delay: dpb_delay.map(to_90khz),
+                    pts_sec: output,
+                    dts_sec: Some(removal),
+                    earliest_arrival_sec: earliest_arrival,
+                }
+            })
+            .collect()
+    }
+
+    /// Run the CPB model of the given HRD with the signalled parameters and
+    /// timing.
+    ///
+    /// Returns `None` when the stream carries no parameters for that HRD.
+    pub fn hrd_model(&self, hrd: HrdType) -> Option<bitvue_core::hrd::HrdModel> {
+        let params = self.hrd_parameters(hrd)?;
+        let timings = self.hrd_timings(hrd, &params);
+        let mut model = bitvue_core::hrd::HrdModel::new(params);
+        model.process_signalled_frames(&timings);
+        Some(model)
+    }
 }
 
 /// Parse H.264/AVC bitstream from Annex B byte stream.
@@ -194,6 +426,12 @@ pub fn parse_avc(data: &[u8]) -> Result<AvcStream> {
     let mut prev_frame_num: u32 = 0;
     let mut prev_frame_num_offset: i32 = 0;
 
+    // Reference marking and list construction state
+    let mut dpb = Dpb::new();
+    let mut dpb_contents: Vec<DpbEntry> = Vec::new();
+    let mut prev_slice: Option<(SliceHeader, u8, bool)> = None;
+    let mut active_sps_id: Option<u8> = None;
+
     for (nal_index, nal) in nal_units.iter().enumerate() {
         match nal.header.nal_unit_type {
             NalUnitType::Sps => {
@@ -207,7 +445,8 @@ pub fn parse_avc(data: &[u8]) -> Result<AvcStream> {
                 }
             }
             NalUnitType::Sei => {
-                if let Ok(messages) = parse_sei(&nal.payload) {
+                let active = active_sps_id.or_else(|| sps_map.keys().min().copied());
+                if let Ok(messages) = parse_sei_with_sps(&nal.payload, &sps_map, active) {
                     sei_messages.extend(messages);
                 }
             }
@@ -225,11 +464,15 @@ pub fn parse_avc(data: &[u8]) -> Result<AvcStream> {
                         .get(&header.pic_parameter_set_id)
                         .and_then(|pps| sps_map.get(&pps.seq_parameter_set_id));
 
+                    let is_idr = nal.header.nal_unit_type == NalUnitType::IdrSlice;
+                    if let Some(sps) = sps {
+                        active_sps_id = Some(sps.seq_parameter_set_id);
+                    }
                     let poc = if let Some(sps) = sps {
                         calculate_poc(
                             sps,
                             &header,
-                            nal.header.nal_unit_type == NalUnitType::IdrSlice,
+                            is_idr,
                             &mut prev_poc_msb,
                             &mut prev_poc_lsb,
                             &mut prev_frame_num,
@@ -239,11 +482,33 @@ pub fn parse_avc(data: &[u8]) -> Result<AvcStream> {
                         0
                     };
 
+                    let nal_ref_idc = nal.header.nal_ref_idc;
+                    let mut ref_pic_lists = RefPicLists::default();
+                    if let Some(sps) = sps {
+                        let new_picture = prev_slice.as_ref().is_none_or(|prev| {
+                            first_slice_of_picture(prev, &header, nal_ref_idc, is_idr)
+                        });
+                        if new_picture {
+                            dpb_contents = dpb.start_picture(
+                                nal_index,
+                                nal_ref_idc,
+                                is_idr,
+                                &header,
+                                sps,
+                                poc,
+                            );
+                        }
+                        ref_pic_lists = dpb.ref_pic_lists(&header);
+                        prev_slice = Some((header.clone(), nal_ref_idc, is_idr));
+                    }
+
                     slices.push(ParsedSlice {
                         nal_index,
                         header: header.clone(),
                         poc,
                         frame_num: header.frame_num,
+                        ref_pic_lists,
+                        dpb: dpb_contents.clone(),
                     });
                 }
             }
@@ -260,6 +525,28 @@ pub fn parse_avc(data: &[u8]) -> Result<AvcStream> {
     })
 }
 
+/// Detect the first slice of a new primary coded picture (Section 7.4.1.2.4).
+///
+/// `prev` holds the previous slice header with its nal_ref_idc and IDR flag.
+fn first_slice_of_picture(
+    prev: &(SliceHeader, u8, bool),
+    header: &SliceHeader,
+    nal_ref_idc: u8,
+    is_idr: bool,
+) -> bool {
+    let (prev_header, prev_nal_ref_idc, prev_is_idr) = prev;
+    prev_header.frame_num != header.frame_num
+        || prev_header.pic_parameter_set_id != header.pic_parameter_set_id
+        || prev_header.field_pic_flag != header.field_pic_flag
+        || prev_header.bottom_field_flag != header.bottom_field_flag
+        || (*prev_nal_ref_idc == 0) != (nal_ref_idc == 0)
+        || prev_header.pic_order_cnt_lsb != header.pic_order_cnt_lsb
+        || prev_header.delta_pic_order_cnt_bottom != header.delta_pic_order_cnt_bottom
+        || prev_header.delta_pic_order_cnt != header.delta_pic_order_cnt
+        || *prev_is_idr != is_idr
+        || (is_idr && prev_header.idr_pic_id != header.idr_pic_id)
+}
+
 /// Calculate Picture Order Count for H.264.
 fn calculate_poc(
     sps: &Sps,
@@ -6,19 +6,25 @@
 //! ## Implementation Status (v0.4.x)
 //!
 //! **Real Data Extraction**:
-//! - ✅ Extract macroblock structure from slice data
-//! - ✅ Extract motion vectors from INTER macroblocks
-//! - ✅ Extract QP values from macroblock layer
+//! - ✅ Extract macroblock structure from slice data (CAVLC and CABAC)
+//! - ✅ Extract motion vectors and partitions from INTER macroblocks
+//! - ✅ Extract QP values from mb_qp_delta
 //! - ✅ Extract macroblock types (I/P/Skip/B)
 //! - ✅ Extract reference frame indices
 //!
+//! Slices that cannot be parsed (see [`crate::slice_data`] for the
+//! unsupported features) are logged and left out; their macroblocks fall
+//! back to the base QP and to missing motion.
+//!
 //! ## Data Flow
 //!
 //! 1. **NAL Units** → find_nal_units() → Vec<NalUnit>
-//! 2. **Slice Data** → parse_macroblocks() → Vec<Macroblock>
+//! 2. **Slice Data** → parse_slice_data() → Vec<Macroblock>
 //! 3. **Macroblocks** → extract_*_grid() → overlay grids
 
-use crate::nal::NalUnit;
+use crate::nal::{NalUnit, NalUnitType};
+use crate::pps::Pps;
+use crate::slice_data::parse_slice_data;
 use crate::sps::Sps;
 use bitvue_core::{
     mv_overlay::{BlockMode, MVGrid, MotionVector as CoreMV},
@@ -33,10 +39,13 @@ use serde::{Deserialize, Serialize};
 pub enum MbType {
     /// I macroblock (intra)
     I4x4,
+    I8x8,
     I16x16,
     IPCM,
     /// P macroblock (predicted)
     PLuma,
+    P16x8,
+    P8x16,
     P8x8,
     /// B macroblock (bi-predictive)
     BDirect,
@@ -52,7 +61,10 @@ pub enum MbType {
 impl MbType {
     /// Check if this is an INTRA macroblock
     pub fn is_intra(&self) -> bool {
-        matches!(self, MbType::I4x4 | MbType::I16x16 | MbType::IPCM)
+        matches!(
+            self,
+            MbType::I4x4 | MbType::I8x8 | MbType::I16x16 | MbType::IPCM
+        )
     }
 
     /// Check if this is a SKIP macroblock
@@ -63,10 +75,12 @@ impl MbType {
     /// Get partition type for visualization
     pub fn to_partition_type(self) -> PartitionType {
         match self {
-            MbType::I4x4 => PartitionType::Split,
+            MbType::I4x4 | MbType::I8x8 => PartitionType::Split,
             MbType::I16x16 => PartitionType::None,
             MbType::IPCM => PartitionType::None,
             MbType::PLuma => PartitionType::None,
+            MbType::P16x8 => PartitionType::Horz,
+            MbType::P8x16 => PartitionType::Vert,
             MbType::P8x8 => PartitionType::Split,
             MbType::BDirect => PartitionType::None,
             MbType::BSkip | MbType::PSkip => PartitionType::None,
@@ -99,10 +113,43 @@ pub struct Macroblock {
     /// Reference frame indices
     pub ref_idx_l0: Option<i8>,
     pub ref_idx_l1: Option<i8>,
+    /// Prediction blocks (macroblock partitions, sub-macroblock partitions
+    /// or intra prediction blocks) with their motion
+    #[serde(default)]
+    pub partitions: Vec<MbPartition>,
+    /// 8x8 luma transform in use
+    #[serde(default)]
+    pub transform_size_8x8_flag: bool,
+    /// coded_block_pattern (luma in bits 0-3, chroma in bits 4-5)
+    #[serde(default)]
+    pub coded_block_pattern: u8,
+    /// Nonzero residual coefficients over all luma and chroma blocks
+    #[serde(default)]
+    pub coeff_count: u32,
+    /// mb_qp_delta (0 when not present)
+    #[serde(default)]
+    pub mb_qp_delta: i32,
+}
+
+/// Prediction block of a macroblock
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MbPartition {
+    /// Position in pixels
+    pub x: u32,
+    pub y: u32,
+    /// Size in pixels
+    pub width: u32,
+    pub height: u32,
+    /// Motion vectors (quarter-pel), when predicted from the list
+    pub mv_l0: Option<MotionVector>,
+    pub mv_l1: Option<MotionVector>,
+    /// Reference frame indices
+    pub ref_idx_l0: Option<i8>,
+    pub ref_idx_l1: Option<i8>,
 }
 
 /// Motion vector for H.264 (quarter-pel precision)
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MotionVector {
     /// Horizontal component (quarter-pel units)
     pub x: i32,
@@ -124,124 +171,66 @@ impl MotionVector {
 
 /// Extract QP Grid from H.264 bitstream
 ///
-/// Parses macroblocks from slice data and extracts QP values.
+/// Parses macroblocks from slice data and extracts QP values. Macroblocks
+/// not covered by a parsed slice use `base_qp`.
 pub fn extract_qp_grid(
     nal_units: &[NalUnit],
     sps: &Sps,
+    pps: &Pps,
     base_qp: i16,
 ) -> Result<QPGrid, BitvueError> {
-    let pic_width_in_mbs = sps.pic_width_in_mbs_minus1 + 1;
-    let pic_height_in_mbs = sps.pic_height_in_map_units_minus1 + 1;
+    let (grid_w, grid_h) = grid_dimensions(sps)?;
+    let mbs = parse_picture_macroblocks(nal_units, sps, pps, grid_w, grid_h);
 
-    let grid_w = pic_width_in_mbs;
-    let grid_h = pic_height_in_mbs;
-
-    // Check for overflow in grid size calculation
-    let total_blocks = grid_w.checked_mul(grid_h).ok_or_else(|| {
-        BitvueError::Decode(format!("Grid dimensions too large: {}x{}", grid_w, grid_h))
-    })? as usize;
-
-    let mut qp = Vec::with_capacity(total_blocks);
-
-    // Parse macroblocks from slice data
-    for nal in nal_units {
-        if nal.header.nal_unit_type.is_slice() {
-            match parse_slice_macroblocks(nal, sps, base_qp) {
-                Ok(mbs) => {
-                    // Collect QP values from macroblocks
-                    for mb in &mbs {
-                        qp.push(mb.qp);
-                    }
-                }
-                Err(e) => {
-                    abseil::vlog!(1, "Failed to parse macroblocks: {}, using base_qp", e);
-                    // Use base_qp for all macroblocks in this slice
-                }
-            }
-        }
-    }
-
-    // If we didn't get any macroblocks, use base_qp
-    if qp.is_empty() {
-        let total_blocks = grid_w.checked_mul(grid_h).ok_or_else(|| {
-            BitvueError::Decode(format!("Grid dimensions too large: {}x{}", grid_w, grid_h))
-        })? as usize;
-        qp = vec![base_qp; total_blocks];
-    }
+    let qp = mbs
+        .iter()
+        .map(|mb| mb.as_ref().map_or(base_qp, |mb| mb.qp))
+        .collect();
 
     Ok(QPGrid::new(grid_w, grid_h, 16, 16, qp, base_qp))
 }
 
 /// Extract MV Grid from H.264 bitstream
 ///
-/// Parses macroblocks from slice data and extracts motion vectors.
-pub fn extract_mv_grid(nal_units: &[NalUnit], sps: &Sps) -> Result<MVGrid, BitvueError> {
-    let pic_width_in_mbs = sps.pic_width_in_mbs_minus1 + 1;
-    let pic_height_in_mbs = sps.pic_height_in_map_units_minus1 + 1;
-
-    let mb_width = pic_width_in_mbs * 16;
-    let mb_height = pic_height_in_mbs * 16;
-    let grid_w = pic_width_in_mbs;
-    let grid_h = pic_height_in_mbs;
-
-    // Check for overflow in grid size calculation
-    let total_blocks = grid_w.checked_mul(grid_h).ok_or_else(|| {
-        BitvueError::Decode(format!("Grid dimensions too large: {}x{}", grid_w, grid_h))
-    })? as usize;
-
-    let mut mv_l0 = Vec::with_capacity(total_blocks);
-    let mut mv_l1 = Vec::with_capacity(total_blocks);
-    let mut modes = Vec::with_capacity(total_blocks);
-
-    // Parse macroblocks from slice data
-    for nal in nal_units {
-        if nal.header.nal_unit_type.is_slice() {
-            match parse_slice_macroblocks(nal, sps, 26) {
-                Ok(mbs) => {
-                    for mb in &mbs {
-                        if mb.mb_type.is_intra() {
-                            mv_l0.push(CoreMV::MISSING);
-                            mv_l1.push(CoreMV::MISSING);
-                            modes.push(BlockMode::Intra);
-                        } else {
-                            // Has motion vectors
-                            if let Some(ref mv) = mb.mv_l0 {
-                                mv_l0.push(CoreMV::new(mv.x, mv.y));
-                            } else {
-                                mv_l0.push(CoreMV::ZERO);
-                            }
-
-                            if let Some(ref mv) = mb.mv_l1 {
-                                mv_l1.push(CoreMV::new(mv.x, mv.y));
-                            } else {
-                                mv_l1.push(CoreMV::MISSING);
-                            }
-
-                            modes.push(BlockMode::Inter);
-                        }
-                    }
-                }
-                Err(e) => {
-                    abseil::vlog!(1, "Failed to parse macroblocks for MV: {}, using ZERO", e);
-                    // Use zero MV for all macroblocks in this slice
-                }
+/// Parses macroblocks from slice data and extracts the motion vectors of
+/// the first partition of each macroblock.
+pub fn extract_mv_grid(nal_units: &[NalUnit], sps: &Sps, pps: &Pps) -> Result<MVGrid, BitvueError> {
+    let (grid_w, grid_h) = grid_dimensions(sps)?;
+    let mbs = parse_picture_macroblocks(nal_units, sps, pps, grid_w, grid_h);
+
+    let mut mv_l0 = Vec::with_capacity(mbs.len());
+    let mut mv_l1 = Vec::with_capacity(mbs.len());
+    let mut modes = Vec::with_capacity(mbs.len());
+
+    let to_core =
+        |mv: Option<MotionVector>| mv.map_or(CoreMV::MISSING, |mv| CoreMV::new(mv.x, mv.y));
+    for mb in &mbs {
+        match mb {
+            Some(mb) if mb.mb_type.is_intra() => {
+                mv_l0.push(CoreMV::MISSING);
+                mv_l1.push(CoreMV::MISSING);
+                modes.push(BlockMode::Intra);
+            }
+            Some(mb) => {
+                mv_l0.push(to_core(mb.mv_l0));
+                mv_l1.push(to_core(mb.mv_l1));
+                modes.push(if mb.skip {
+                    BlockMode::Skip
+                } else {
+                    BlockMode::Inter
+                });
+            }
+            None => {
+                mv_l0.push(CoreMV::MISSING);
+                mv_l1.push(CoreMV::MISSING);
+                modes.push(BlockMode::None);
             }
         }
     }
 
-    // Fill remaining if needed
-    let total_blocks = grid_w.checked_mul(grid_h).ok_or_else(|| {
-        BitvueError::Decode(format!("Grid dimensions too large: {}x{}", grid_w, grid_h))
-    })? as usize;
-    while mv_l0.len() < total_blocks {
-        mv_l0.push(CoreMV::ZERO);
-        mv_l1.push(CoreMV::MISSING);
-        modes.push(BlockMode::Inter);
-    }
-
     Ok(MVGrid::new(
-        mb_width,
-        mb_height,
+        grid_w * 16,
+        grid_h * 16,
         16,
         16,
         mv_l0,
@@ -252,157 +241,132 @@ pub fn extract_mv_grid(nal_units: &[NalUnit], sps: &Sps) -> Result<MVGrid, Bitvu
 
 /// Extract Partition Grid from H.264 bitstream
 ///
-/// Parses macroblocks from slice data and creates a partition grid.
+/// Parses macroblocks from slice data and adds one block per prediction
+/// block. Macroblocks not covered by a parsed slice get a scaffold 16x16
+/// block.
 pub fn extract_partition_grid(
     nal_units: &[NalUnit],
     sps: &Sps,
+    pps: &Pps,
 ) -> Result<PartitionGrid, BitvueError> {
-    let pic_width_in_mbs = sps.pic_width_in_mbs_minus1 + 1;
-    let pic_height_in_mbs = sps.pic_height_in_map_units_minus1 + 1;
-
-    let pic_width = pic_width_in_mbs * 16;
-    let pic_height = pic_height_in_mbs * 16;
-
-    let mut grid = PartitionGrid::new(pic_width, pic_height, 16);
-
-    // Parse macroblocks from slice data
-    for nal in nal_units {
-        if nal.header.nal_unit_type.is_slice() {
-            match parse_slice_macroblocks(nal, sps, 26) {
-                Ok(mbs) => {
-                    for mb in &mbs {
-                        grid.add_block(PartitionBlock::new(
-                            mb.x,
-                            mb.y,
-                            16,
-                            16,
-                            mb.mb_type.to_partition_type(),
-                            0,
-                        ));
-                    }
-                }
-                Err(e) => {
-                    abseil::vlog!(
-                        1,
-                        "Failed to parse macroblocks for partition: {}, using scaffold",
-                        e
-                    );
-                    // Add scaffold blocks
-                }
-            }
-        }
-    }
-
-    // Fill with scaffold blocks if empty
-    if grid.blocks.is_empty() {
-        let grid_w = pic_width_in_mbs;
-        let grid_h = pic_height_in_mbs;
-        for mb_y in 0..grid_h {
-            for mb_x in 0..grid_w {
-                grid.add_block(PartitionBlock::new(
-                    mb_x * 16,
-                    mb_y * 16,
-                    16,
-                    16,
-                    PartitionType::None,
-                    0,
-                ));
-            }
+    let (grid_w, grid_h) = grid_dimensions(sps)?;
+    let mbs = parse_picture_macroblocks(nal_units, sps, pps, grid_w, grid_h);
+
+    let mut grid = PartitionGrid::new(grid_w * 16, grid_h * 16, 16);
+    for (addr, mb) in mbs.iter().enumerate() {
+        let (x, y) = ((addr as u32 % grid_w) * 16, (addr as u32 / grid_w) * 16);
+        let Some(mb) = mb.as_ref().filter(|mb| !mb.partitions.is_empty()) else {
+            grid.add_block(PartitionBlock::new(x, y, 16, 16, PartitionType::None, 0));
+            continue;
+        };
+        let partition_type = mb.mb_type.to_partition_type();
+        for part in &mb.partitions {
+            let depth = match part.width.min(part.height) {
+                16 => 0,
+                8 => 1,
+                _ => 2,
+            };
+            grid.add_block(PartitionBlock::new(
+                part.x,
+                part.y,
+                part.width,
+                part.height,
+                partition_type,
+                depth,
+            ));
         }
     }
 
     Ok(grid)
 }
 
-/// Parse macroblocks from slice data
+/// Extract the macroblocks of a picture
 ///
-/// This is a simplified implementation that extracts basic macroblock
-/// information. Full implementation would parse slice_data() syntax.
-fn parse_slice_macroblocks(
-    nal: &NalUnit,
+/// Returns one entry per macroblock in raster order, `None` where no slice
+/// could be parsed. Macroblocks of field pictures are placed on the frame
+/// rows their field rows start in.
+pub fn extract_macroblocks(
+    nal_units: &[NalUnit],
     sps: &Sps,
-    base_qp: i16,
-) -> Result<Vec<Macroblock>, BitvueError> {
-    let mut mbs = Vec::new();
-
-    // Skip slice header (simplified - just parse macroblock data)
-    // In full implementation, we would parse slice_header() first
-
-    let pic_width_in_mbs = sps.pic_width_in_mbs_minus1 + 1;
-    let pic_height_in_mbs = sps.pic_height_in_map_units_minus1 + 1;
-    let total_mbs = pic_width_in_mbs * pic_height_in_mbs;
-
-    let is_intra = nal.header.nal_unit_type.is_intra_slice();
-
-    for mb_addr in 0..total_mbs {
-        let mb_x = (mb_addr % pic_width_in_mbs) * 16;
-        let mb_y = (mb_addr / pic_width_in_mbs) * 16;
-
-        // Determine macroblock type based on slice type
-        let mb_type = if is_intra {
-            MbType::I16x16
-        } else {
-            // Simplified: use P16x16 or B16x16
-            if nal.header.nal_unit_type.is_non_intra_slice() {
-                MbType::PLuma
-            } else {
-                MbType::BSkip
-            }
-        };
+    pps: &Pps,
+) -> Result<Vec<Option<Macroblock>>, BitvueError> {
+    let (grid_w, grid_h) = grid_dimensions(sps)?;
+    Ok(parse_picture_macroblocks(
+        nal_units, sps, pps, grid_w, grid_h,
+    ))
+}
 
-        // QP extraction (simplified)
-        // Full implementation would parse mb_qp_delta
-        let qp = base_qp;
-
-        mbs.push(Macroblock {
-            mb_addr,
-            x: mb_x,
-            y: mb_y,
-            mb_type,
-            skip: mb_type.is_skip(),
-            qp,
-            mv_l0: None,
-            mv_l1: None,
-            ref_idx_l0: None,
-            ref_idx_l1: None,
-        });
-    }
+/// Picture size in macroblocks (PicWidthInMbs, FrameHeightInMbs).
+fn grid_dimensions(sps: &Sps) -> Result<(u32, u32), BitvueError> {
+    let grid_w = sps.pic_width_in_mbs_minus1 + 1;
+    let map_units = sps.pic_height_in_map_units_minus1 + 1;
+    let grid_h = if sps.frame_mbs_only_flag {
+        map_units
+    } else {
+        map_units * 2
+    };
 
-    Ok(mbs)
+    // Check for overflow in grid size calculation
+    grid_w.checked_mul(grid_h).ok_or_else(|| {
+        BitvueError::Decode(format!("Grid dimensions too large: {}x{}", grid_w, grid_h))
+    })?;
+    Ok((grid_w, grid_h))
 }
 
-/// Extension trait for NalUnitType
-trait NalUnitTypeExt {
-    fn is_slice(&self) -> bool;
-    fn is_intra_slice(&self) -> bool;
-    fn is_non_intra_slice(&self) -> bool;
-}
+/// Parse the slices of one picture into a frame-sized macroblock map.
+///
+/// Macroblocks of field pictures are moved to the frame macroblock row
+/// their field row starts in, so both fields share one map.
+fn parse_picture_macroblocks(
+    nal_units: &[NalUnit],
+    sps: &Sps,
+    pps: &Pps,
+    grid_w: u32,
+    grid_h: u32,
+) -> Vec<Option<Macroblock>> {
+    let mut map = vec![None; (grid_w * grid_h) as usize];
 
-impl NalUnitTypeExt for crate::NalUnitType {
-    fn is_slice(&self) -> bool {
-        matches!(
-            self,
-            crate::NalUnitType::NonIdrSlice
-                | crate::NalUnitType::IdrSlice
-                | crate::NalUnitType::SliceDataA
-                | crate::NalUnitType::SliceDataB
-                | crate::NalUnitType::SliceDataC
-        )
-    }
+    for nal in nal_units {
+        if !matches!(
+            nal.nal_type(),
+            NalUnitType::NonIdrSlice | NalUnitType::IdrSlice
+        ) {
+            continue;
+        }
+        let slice = match parse_slice_data(nal, sps, pps) {
+            Ok(slice) => slice,
+            Err(e) => {
+                abseil::vlog!(1, "Failed to parse macroblocks: {}, using fallback", e);
+                continue;
+            }
+        };
 
-    fn is_intra_slice(&self) -> bool {
-        matches!(self, crate::NalUnitType::IdrSlice)
+        let field = slice.header.field_pic_flag;
+        let bottom = slice.header.bottom_field_flag as u32;
+        for mut mb in slice.macroblocks {
+            if field {
+                let field_row = mb.y / 16;
+                let frame_y = (field_row * 2 + bottom) * 16;
+                for part in &mut mb.partitions {
+                    part.y = part.y - mb.y + frame_y;
+                }
+                mb.y = frame_y;
+                mb.mb_addr = (mb.y / 16) * grid_w + mb.x / 16;
+            }
+            if let Some(slot) = map.get_mut(mb.mb_addr as usize) {
+                *slot = Some(mb);
+            }
+        }
     }
 
-    fn is_non_intra_slice(&self) -> bool {
-        matches!(self, crate::NalUnitType::NonIdrSlice)
-    }
+    map
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::nal::{NalUnit, NalUnitHeader};
+    use crate::pps::Pps;
     use crate::sps::Sps;
 
     fn create_test_sps(width: u32, height: u32) -> Sps {
@@ -447,6 +411,10 @@ mod tests {
         }
     }
 
+    fn create_test_pps() -> Pps {
+        Pps::default()
+    }
+
     fn create_test_nal_unit(nal_type: crate::NalUnitType) -> NalUnit {
         NalUnit {
             header: NalUnitHeader {
@@ -482,6 +450,9 @@ mod tests {
     fn test_mb_type_to_partition_type() {
         assert_eq!(MbType::I16x16.to_partition_type(), PartitionType::None);
         assert_eq!(MbType::IPCM.to_partition_type(), PartitionType::None);
+        assert_eq!(MbType::I8x8.to_partition_type(), PartitionType::Split);
+        assert_eq!(MbType::P16x8.to_partition_type(), PartitionType::Horz);
+        assert_eq!(MbType::P8x16.to_partition_type(), PartitionType::Vert);
         assert_eq!(MbType::P8x8.to_partition_type(), PartitionType::Split);
         assert_eq!(MbType::B16x8.to_partition_type(), PartitionType::Horz);
         assert_eq!(MbType::B8x16.to_partition_type(), PartitionType::Vert);
@@ -507,7 +478,7 @@ mod tests {
     #[test]
     fn test_extract_qp_grid_empty_nal_units() {
         let sps = create_test_sps(640, 480);
-        let result = extract_qp_grid(&[], &sps, 26);
+        let result = extract_qp_grid(&[], &sps, &create_test_pps(), 26);
         assert!(result.is_ok());
         let qp_grid = result.unwrap();
         // 640/16 * 480/16 = 40 * 30 = 1200 macroblocks
@@ -519,7 +490,7 @@ mod tests {
     fn test_extract_qp_grid_with_idr_slice() {
         let sps = create_test_sps(640, 480);
         let nal = create_test_nal_unit(crate::NalUnitType::IdrSlice);
-        let result = extract_qp_grid(&[nal], &sps, 26);
+        let result = extract_qp_grid(&[nal], &sps, &create_test_pps(), 26);
         assert!(result.is_ok());
         let qp_grid = result.unwrap();
         assert_eq!(qp_grid.grid_w, 40);
@@ -530,7 +501,7 @@ mod tests {
     fn test_extract_qp_grid_non_idr_slice() {
         let sps = create_test_sps(1920, 1080);
         let nal = create_test_nal_unit(crate::NalUnitType::NonIdrSlice);
-        let result = extract_qp_grid(&[nal], &sps, 30);
+        let result = extract_qp_grid(&[nal], &sps, &create_test_pps(), 30);
         assert!(result.is_ok());
         let qp_grid = result.unwrap();
         // 1920/16 * 1080/16 = 120 * 67 = 8040 macroblocks
@@ -544,7 +515,12 @@ mod tests {
         let nal = create_test_nal_unit(crate::NalUnitType::IdrSlice);
 
         for base_qp in [0i16, 10, 26, 40, 51] {
-            let result = extract_qp_grid(&[nal.clone()], &sps, base_qp);
+            let result = extract_qp_grid(
+                std::slice::from_ref(&nal),
+                &sps,
+                &create_test_pps(),
+                base_qp,
+            );
             assert!(result.is_ok());
         }
     }
@@ -553,7 +529,7 @@ mod tests {
     fn test_extract_qp_grid_non_slice_nal() {
         let sps = create_test_sps(640, 480);
         let nal = create_test_nal_unit(crate::NalUnitType::Sps);
-        let result = extract_qp_grid(&[nal], &sps, 26);
+        let result = extract_qp_grid(&[nal], &sps, &create_test_pps(), 26);
         assert!(result.is_ok());
         // Should use base_qp for all macroblocks since there's no slice
         let qp_grid = result.unwrap();
@@ -564,7 +540,7 @@ mod tests {
     #[test]
     fn test_extract_mv_grid_empty_nal_units() {
         let sps = create_test_sps(640, 480);
-        let result = extract_mv_grid(&[], &sps);
+        let result = extract_mv_grid(&[], &sps, &create_test_pps());
         assert!(result.is_ok());
         let mv_grid = result.unwrap();
         // MV grid uses 16x16 blocks
@@ -576,7 +552,7 @@ mod tests {
     fn test_extract_mv_grid_with_slice() {
         let sps = create_test_sps(640, 480);
         let nal = create_test_nal_unit(crate::NalUnitType::NonIdrSlice);
-        let result = extract_mv_grid(&[nal], &sps);
+        let result = extract_mv_grid(&[nal], &sps, &create_test_pps());
         assert!(result.is_ok());
         let mv_grid = result.unwrap();
         assert_eq!(mv_grid.coded_width, 640);
@@ -585,21 +561,22 @@ mod tests {
     }
 
     #[test]
-    fn test_extract_mv_grid_intra_slice() {
+    fn test_extract_mv_grid_unparsable_slice() {
         let sps = create_test_sps(320, 240);
         let nal = create_test_nal_unit(crate::NalUnitType::IdrSlice);
-        let result = extract_mv_grid(&[nal], &sps);
+        let result = extract_mv_grid(&[nal], &sps, &create_test_pps());
         assert!(result.is_ok());
         let mv_grid = result.unwrap();
         let modes = mv_grid.mode.as_ref().unwrap();
-        // All blocks should be Intra for IDR slice
-        assert!(modes.iter().all(|m| *m == BlockMode::Intra));
+        // The zero payload is not a valid slice, so no block has a mode
+        assert!(modes.iter().all(|m| *m == BlockMode::None));
+        assert!(mv_grid.mv_l0.iter().all(|mv| *mv == CoreMV::MISSING));
     }
 
     #[test]
     fn test_extract_partition_grid_empty_nal_units() {
         let sps = create_test_sps(640, 480);
-        let result = extract_partition_grid(&[], &sps);
+        let result = extract_partition_grid(&[], &sps, &create_test_pps());
         assert!(result.is_ok());
         let partition_grid = result.unwrap();
         assert_eq!(partition_grid.coded_width, 640);
@@ -612,7 +589,7 @@ mod tests {
     fn test_extract_partition_grid_with_slice() {
         let sps = create_test_sps(640, 480);
         let nal = create_test_nal_unit(crate::NalUnitType::IdrSlice);
-        let result = extract_partition_grid(&[nal], &sps);
+        let result = extract_partition_grid(&[nal], &sps, &create_test_pps());
         assert!(result.is_ok());
         let partition_grid = result.unwrap();
         assert_eq!(partition_grid.coded_width, 640);
@@ -623,7 +600,7 @@ mod tests {
     fn test_extract_partition_grid_inter_slice() {
         let sps = create_test_sps(1920, 1080);
         let nal = create_test_nal_unit(crate::NalUnitType::NonIdrSlice);
-        let result = extract_partition_grid(&[nal], &sps);
+        let result = extract_partition_grid(&[nal], &sps, &create_test_pps());
         assert!(result.is_ok());
         let partition_grid = result.unwrap();
         assert_eq!(partition_grid.coded_width, 1920);
@@ -644,6 +621,11 @@ mod tests {
             mv_l1: None,
             ref_idx_l0: None,
             ref_idx_l1: None,
+            partitions: vec![],
+            transform_size_8x8_flag: false,
+            coded_block_pattern: 0,
+            coeff_count: 0,
+            mb_qp_delta: 0,
         };
         assert_eq!(mb.mb_addr, 0);
         assert_eq!(mb.qp, 26);
@@ -664,6 +646,11 @@ mod tests {
             mv_l1: None,
             ref_idx_l0: Some(0),
             ref_idx_l1: None,
+            partitions: vec![],
+            transform_size_8x8_flag: false,
+            coded_block_pattern: 0,
+            coeff_count: 0,
+            mb_qp_delta: 0,
         };
         assert_eq!(mb.mv_l0.unwrap().x, 4);
         assert_eq!(mb.mv_l0.unwrap().y, 8);
@@ -674,7 +661,7 @@ mod tests {
     fn test_extract_qp_grid_small_resolution() {
         let sps = create_test_sps(160, 120);
         let nal = create_test_nal_unit(crate::NalUnitType::IdrSlice);
-        let result = extract_qp_grid(&[nal], &sps, 20);
+        let result = extract_qp_grid(&[nal], &sps, &create_test_pps(), 20);
         assert!(result.is_ok());
         let qp_grid = result.unwrap();
         // 160/16 * 120/16 = 10 * 7 = 70 macroblocks (round up)
@@ -686,7 +673,7 @@ mod tests {
     fn test_extract_mv_grid_high_resolution() {
         let sps = create_test_sps(3840, 2160);
         let nal = create_test_nal_unit(crate::NalUnitType::NonIdrSlice);
-        let result = extract_mv_grid(&[nal], &sps);
+        let result = extract_mv_grid(&[nal], &sps, &create_test_pps());
         assert!(result.is_ok());
         let mv_grid = result.unwrap();
         assert_eq!(mv_grid.coded_width, 3840);
@@ -701,16 +688,19 @@ mod tests {
             create_test_nal_unit(crate::NalUnitType::Pps),
             create_test_nal_unit(crate::NalUnitType::IdrSlice),
         ];
-        let result = extract_partition_grid(&nals, &sps);
+        let result = extract_partition_grid(&nals, &sps, &create_test_pps());
         assert!(result.is_ok());
     }
 
     #[test]
     fn test_mb_type_all_variants_is_intra() {
         assert!(MbType::I4x4.is_intra());
+        assert!(MbType::I8x8.is_intra());
         assert!(MbType::I16x16.is_intra());
         assert!(MbType::IPCM.is_intra());
         assert!(!MbType::PLuma.is_intra());
+        assert!(!MbType::P16x8.is_intra());
+        assert!(!MbType::P8x16.is_intra());
         assert!(!MbType::P8x8.is_intra());
         assert!(!MbType::BDirect.is_intra());
         assert!(!MbType::B16x16.is_intra());
@@ -749,7 +739,7 @@ mod tests {
         ];
         for nal_type in slice_types {
             let nal = create_test_nal_unit(nal_type);
-            let result = extract_qp_grid(&[nal], &sps, 26);
+            let result = extract_qp_grid(&[nal], &sps, &create_test_pps(), 26);
             assert!(result.is_ok(), "Failed for {:?}", nal_type);
         }
     }
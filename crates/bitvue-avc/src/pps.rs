@@ -5,7 +5,7 @@ use crate::error::{AvcError, Result};
 use serde::{Deserialize, Serialize};
 
 /// Picture Parameter Set.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Pps {
     /// pic_parameter_set_id
     pub pic_parameter_set_id: u8,
@@ -2,7 +2,9 @@
 
 use crate::bitreader::BitReader;
 use crate::error::{AvcError, Result};
+use crate::sps::{HrdParameters, Sps, VuiParameters};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// SEI payload types.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -215,6 +217,40 @@ pub enum SeiParsedData {
         max_content_light_level: u16,
         max_pic_average_light_level: u16,
     },
+    /// Buffering period.
+    BufferingPeriod(BufferingPeriod),
+    /// Picture timing.
+    PicTiming(PicTiming),
+}
+
+/// Initial CPB removal delay and offset of one SchedSelIdx, in 90 kHz units.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InitialCpbRemoval {
+    pub initial_cpb_removal_delay: u32,
+    pub initial_cpb_removal_delay_offset: u32,
+}
+
+/// Buffering period SEI (D.1.2).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BufferingPeriod {
+    pub seq_parameter_set_id: u8,
+    /// NAL HRD initial removal delays, one per SchedSelIdx
+    pub nal_initial_cpb_removal: Vec<InitialCpbRemoval>,
+    /// VCL HRD initial removal delays, one per SchedSelIdx
+    pub vcl_initial_cpb_removal: Vec<InitialCpbRemoval>,
+}
+
+/// Picture timing SEI (D.1.3).
+///
+/// Clock timestamps are not interpreted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PicTiming {
+    /// Clock ticks between the removal of the buffering period access unit
+    /// and this one
+    pub cpb_removal_delay: Option<u32>,
+    /// Clock ticks between CPB removal and DPB output
+    pub dpb_output_delay: Option<u32>,
+    pub pic_struct: Option<u8>,
 }
 
 /// Parse SEI messages from NAL unit payload.
@@ -291,6 +327,107 @@ pub fn parse_sei(data: &[u8]) -> Result<Vec<SeiMessage>> {
     Ok(messages)
 }
 
+/// Parse SEI messages, interpreting buffering period and picture timing
+/// payloads against the SPS they depend on.
+///
+/// Picture timing uses the SPS of a buffering period in the same NAL unit,
+/// falling back to `active_sps_id`.
+pub fn parse_sei_with_sps(
+    data: &[u8],
+    sps_map: &HashMap<u8, Sps>,
+    active_sps_id: Option<u8>,
+) -> Result<Vec<SeiMessage>> {
+    let mut messages = parse_sei(data)?;
+    let mut sps_id = active_sps_id;
+    for message in &mut messages {
+        if message.payload_type == SeiPayloadType::BufferingPeriod {
+            if let Some(bp) = parse_buffering_period(&message.payload, sps_map) {
+                sps_id = Some(bp.seq_parameter_set_id);
+                message.parsed = Some(SeiParsedData::BufferingPeriod(bp));
+            }
+        }
+    }
+    let vui = sps_id
+        .and_then(|id| sps_map.get(&id))
+        .and_then(|sps| sps.vui_parameters.as_ref());
+    if let Some(vui) = vui {
+        for message in &mut messages {
+            if message.payload_type == SeiPayloadType::PicTiming {
+                message.parsed =
+                    parse_pic_timing(&message.payload, vui).map(SeiParsedData::PicTiming);
+            }
+        }
+    }
+    Ok(messages)
+}
+
+/// Parse buffering period SEI (D.1.2).
+///
+/// Returns `None` if the referenced SPS is unknown or the payload is truncated.
+pub fn parse_buffering_period(data: &[u8], sps_map: &HashMap<u8, Sps>) -> Option<BufferingPeriod> {
+    let mut reader = BitReader::new(data);
+
+    let seq_parameter_set_id = reader.read_ue().ok()?;
+    let seq_parameter_set_id = u8::try_from(seq_parameter_set_id).ok()?;
+    let vui = sps_map
+        .get(&seq_parameter_set_id)?
+        .vui_parameters
+        .as_ref()?;
+
+    let mut read_delays = |hrd: Option<&HrdParameters>| -> Option<Vec<_>> {
+        let Some(hrd) = hrd else {
+            return Some(Vec::new());
+        };
+        let len = hrd.initial_cpb_removal_delay_length_minus1 + 1;
+        hrd.cpbs
+            .iter()
+            .map(|_| {
+                Some(InitialCpbRemoval {
+                    initial_cpb_removal_delay: reader.read_bits(len).ok()?,
+                    initial_cpb_removal_delay_offset: reader.read_bits(len).ok()?,
+                })
+            })
+            .collect()
+    };
+    let nal_initial_cpb_removal = read_delays(vui.nal_hrd_parameters.as_ref())?;
+    let vcl_initial_cpb_removal = read_delays(vui.vcl_hrd_parameters.as_ref())?;
+
+    Some(BufferingPeriod {
+        seq_parameter_set_id,
+        nal_initial_cpb_removal,
+        vcl_initial_cpb_removal,
+    })
+}
+
+/// Parse picture timing SEI (D.1.3) with the VUI of the active SPS.
+pub fn parse_pic_timing(data: &[u8], vui: &VuiParameters) -> Option<PicTiming> {
+    let mut reader = BitReader::new(data);
+    let mut timing = PicTiming::default();
+
+    // Both HRDs must use the same delay lengths
+    if let Some(hrd) = vui
+        .nal_hrd_parameters
+        .as_ref()
+        .or(vui.vcl_hrd_parameters.as_ref())
+    {
+        timing.cpb_removal_delay = Some(
+            reader
+                .read_bits(hrd.cpb_removal_delay_length_minus1 + 1)
+                .ok()?,
+        );
+        timing.dpb_output_delay = Some(
+            reader
+                .read_bits(hrd.dpb_output_delay_length_minus1 + 1)
+                .ok()?,
+        );
+    }
+    if vui.pic_struct_present_flag {
+        timing.pic_struct = Some(reader.read_bits(4).ok()? as u8);
+    }
+
+    Some(timing)
+}
+
 /// Parse specific SEI payload.
 fn parse_sei_payload(payload_type: SeiPayloadType, data: &[u8]) -> Option<SeiParsedData> {
     match payload_type {
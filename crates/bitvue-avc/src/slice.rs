@@ -80,7 +80,11 @@ pub struct DecRefPicMarking {
     /// adaptive_ref_pic_marking_mode_flag
     pub adaptive_ref_pic_marking_mode_flag: bool,
     /// Memory management control operations
-    pub mmco_operations: Vec<(u32, u32, u32)>, // (op, diff_of_pic_nums, long_term_idx)
+    ///
+    /// The third value is long_term_pic_num for operation 2,
+    /// long_term_frame_idx for operations 3 and 6, and
+    /// max_long_term_frame_idx_plus1 for operation 4.
+    pub mmco_operations: Vec<(u32, u32, u32)>, // (op, difference_of_pic_nums_minus1, long_term_idx)
 }
 
 /// Slice header.
@@ -167,7 +171,29 @@ pub fn parse_slice_header(
     nal_ref_idc: u8,
 ) -> Result<SliceHeader> {
     let mut reader = BitReader::new(data);
+    read_slice_header(&mut reader, sps_map, pps_map, nal_type, nal_ref_idc)
+}
+
+/// Parse slice header and return the bit position where `slice_data()` starts.
+pub(crate) fn parse_slice_header_with_data_offset(
+    data: &[u8],
+    sps_map: &HashMap<u8, Sps>,
+    pps_map: &HashMap<u8, Pps>,
+    nal_type: NalUnitType,
+    nal_ref_idc: u8,
+) -> Result<(SliceHeader, usize)> {
+    let mut reader = BitReader::new(data);
+    let header = read_slice_header(&mut reader, sps_map, pps_map, nal_type, nal_ref_idc)?;
+    Ok((header, reader.bit_position()))
+}
 
+fn read_slice_header(
+    reader: &mut BitReader,
+    sps_map: &HashMap<u8, Sps>,
+    pps_map: &HashMap<u8, Pps>,
+    nal_type: NalUnitType,
+    nal_ref_idc: u8,
+) -> Result<SliceHeader> {
     let first_mb_in_slice = reader.read_ue()?;
     let slice_type_raw = reader.read_ue()?;
     let slice_type = SliceType::from_u32(slice_type_raw);
@@ -260,14 +286,14 @@ pub fn parse_slice_header(
     if !slice_type.is_intra() {
         ref_pic_list_modification_flag_l0 = reader.read_flag()?;
         if ref_pic_list_modification_flag_l0 {
-            ref_pic_list_modification_l0 = parse_ref_pic_list_modification(&mut reader)?;
+            ref_pic_list_modification_l0 = parse_ref_pic_list_modification(reader)?;
         }
     }
 
     if slice_type.is_b() {
         ref_pic_list_modification_flag_l1 = reader.read_flag()?;
         if ref_pic_list_modification_flag_l1 {
-            ref_pic_list_modification_l1 = parse_ref_pic_list_modification(&mut reader)?;
+            ref_pic_list_modification_l1 = parse_ref_pic_list_modification(reader)?;
         }
     }
 
@@ -276,7 +302,7 @@ pub fn parse_slice_header(
         || (pps.weighted_bipred_idc == 1 && slice_type.is_b())
     {
         skip_pred_weight_table(
-            &mut reader,
+            reader,
             slice_type,
             num_ref_idx_l0_active_minus1,
             num_ref_idx_l1_active_minus1,
@@ -287,7 +313,7 @@ pub fn parse_slice_header(
     // Dec ref pic marking
     let mut dec_ref_pic_marking = DecRefPicMarking::default();
     if nal_ref_idc != 0 {
-        dec_ref_pic_marking = parse_dec_ref_pic_marking(&mut reader, nal_type)?;
+        dec_ref_pic_marking = parse_dec_ref_pic_marking(reader, nal_type)?;
     }
 
     let mut cabac_init_idc = 0;
@@ -435,7 +461,8 @@ fn parse_dec_ref_pic_marking(
                         long_term_idx = reader.read_ue()?;
                     }
                     4 => {
-                        let _max_long_term_frame_idx_plus1 = reader.read_ue()?;
+                        // max_long_term_frame_idx_plus1
+                        long_term_idx = reader.read_ue()?;
                     }
                     6 => {
                         long_term_idx = reader.read_ue()?;
@@ -0,0 +1,1573 @@
+//! CABAC parsing process (ITU-T H.264 Section 9.3).
+//!
+//! Provides the arithmetic decoding engine and the binarizations of the
+//! macroblock-layer syntax elements. Context index increments that depend
+//! on neighbouring macroblocks are derived by the caller.
+
+use crate::error::{AvcError, Result};
+use crate::slice::SliceType;
+
+/// Number of context variables used by 4:2:0 slice data.
+const NUM_CONTEXTS: usize = 460;
+
+/// ctxBlockCatOffset of coded_block_flag for ctxBlockCat 0-4.
+const CBF_CAT_OFFSET: [usize; 5] = [0, 4, 8, 12, 16];
+/// ctxBlockCatOffset of significant and last coefficient flags.
+const SIG_CAT_OFFSET: [usize; 5] = [0, 15, 29, 44, 47];
+/// ctxBlockCatOffset of coeff_abs_level_minus1.
+const ABS_CAT_OFFSET: [usize; 5] = [0, 10, 20, 30, 39];
+
+/// Residual block category (ctxBlockCat) of the luma 8x8 blocks.
+pub(super) const CAT_LUMA_8X8: usize = 5;
+
+/// Context variable (pStateIdx and valMPS).
+#[derive(Debug, Clone, Copy, Default)]
+struct Context {
+    state: u8,
+    mps: bool,
+}
+
+/// CABAC decoder over the RBSP of a slice.
+pub(super) struct CabacReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    range: u32,
+    offset: u32,
+    contexts: Vec<Context>,
+    field: bool,
+}
+
+impl<'a> CabacReader<'a> {
+    /// Initialise the context variables and the decoding engine at the
+    /// byte-aligned bit position `pos`.
+    pub(super) fn new(
+        data: &'a [u8],
+        pos: usize,
+        slice_type: SliceType,
+        slice_qp: i32,
+        field: bool,
+    ) -> Result<Self> {
+        let table = if slice_type.is_intra() {
+            &INIT_I
+        } else {
+            &INIT_PB_IDC0
+        };
+        let qp = slice_qp.clamp(0, 51);
+        let contexts = table
+            .iter()
+            .map(|&(m, n)| {
+                let pre = (((m as i32 * qp) >> 4) + n as i32).clamp(1, 126);
+                if pre <= 63 {
+                    Context {
+                        state: (63 - pre) as u8,
+                        mps: false,
+                    }
+                } else {
+                    Context {
+                        state: (pre - 64) as u8,
+                        mps: true,
+                    }
+                }
+            })
+            .collect();
+
+        let mut reader = Self {
+            data,
+            pos,
+            range: 0,
+            offset: 0,
+            contexts,
+            field,
+        };
+        reader.init_engine()?;
+        Ok(reader)
+    }
+
+    fn init_engine(&mut self) -> Result<()> {
+        self.range = 510;
+        self.offset = 0;
+        for _ in 0..9 {
+            self.offset = (self.offset << 1) | self.read_bit();
+        }
+        if self.offset >= 510 {
+            return Err(AvcError::BitstreamError(
+                "invalid CABAC initial offset".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Re-initialise the decoding engine at bit position `pos` (after
+    /// `pcm_sample` data).
+    pub(super) fn restart(&mut self, pos: usize) -> Result<()> {
+        self.pos = pos;
+        self.init_engine()
+    }
+
+    /// Bit position of the next bit the engine would read.
+    pub(super) fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Whether the engine has read past the end of the slice data.
+    pub(super) fn overrun(&self) -> bool {
+        self.pos > self.data.len() * 8
+    }
+
+    fn read_bit(&mut self) -> u32 {
+        let bit = self
+            .data
+            .get(self.pos / 8)
+            .map_or(0, |b| (b >> (7 - self.pos % 8)) & 1);
+        self.pos += 1;
+        bit as u32
+    }
+
+    /// DecodeDecision (9.3.3.2.1).
+    fn decision(&mut self, ctx_idx: usize) -> bool {
+        let ctx = &mut self.contexts[ctx_idx];
+        let lps = RANGE_TAB_LPS[ctx.state as usize][((self.range >> 6) & 3) as usize] as u32;
+        self.range -= lps;
+        let bin = if self.offset >= self.range {
+            self.offset -= self.range;
+            self.range = lps;
+            let bin = !ctx.mps;
+            if ctx.state == 0 {
+                ctx.mps = !ctx.mps;
+            }
+            ctx.state = TRANS_IDX_LPS[ctx.state as usize];
+            bin
+        } else {
+            if ctx.state < 62 {
+                ctx.state += 1;
+            }
+            ctx.mps
+        };
+        while self.range < 256 {
+            self.range <<= 1;
+            self.offset = (self.offset << 1) | self.read_bit();
+        }
+        bin
+    }
+
+    /// DecodeBypass (9.3.3.2.3).
+    fn bypass(&mut self) -> bool {
+        self.offset = (self.offset << 1) | self.read_bit();
+        if self.offset >= self.range {
+            self.offset -= self.range;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// DecodeTerminate (9.3.3.2.2).
+    ///
+    /// When the bin is 1 the engine stops, and the last bit read is the final
+    /// bit written by the encoder flush.
+    pub(super) fn terminate(&mut self) -> bool {
+        self.range -= 2;
+        if self.offset >= self.range {
+            true
+        } else {
+            while self.range < 256 {
+                self.range <<= 1;
+                self.offset = (self.offset << 1) | self.read_bit();
+            }
+            false
+        }
+    }
+
+    /// Exp-Golomb bypass suffix of order `k`.
+    fn exp_golomb_bypass(&mut self, mut k: u32) -> Result<u32> {
+        let mut value = 0u32;
+        while self.bypass() {
+            value += 1 << k;
+            k += 1;
+            if k >= 31 {
+                return Err(AvcError::BitstreamError(
+                    "CABAC Exp-Golomb suffix exceeds 32 bits".to_string(),
+                ));
+            }
+        }
+        while k > 0 {
+            k -= 1;
+            value += (self.bypass() as u32) << k;
+        }
+        Ok(value)
+    }
+
+    /// mb_skip_flag with ctxIdxInc `inc`.
+    pub(super) fn mb_skip_flag(&mut self, b_slice: bool, inc: usize) -> bool {
+        self.decision(if b_slice { 24 } else { 11 } + inc)
+    }
+
+    /// mb_type of an I slice (Table 7-11 value) with ctxIdxInc `inc`.
+    pub(super) fn mb_type_i(&mut self, inc: usize) -> u32 {
+        self.mb_type_intra(3, Some(inc))
+    }
+
+    /// mb_type of an SI slice; 0 is SI and other values are I-slice types plus 1.
+    pub(super) fn mb_type_si(&mut self, prefix_inc: usize, inc: usize) -> u32 {
+        if self.decision(prefix_inc) {
+            1 + self.mb_type_intra(3, Some(inc))
+        } else {
+            0
+        }
+    }
+
+    /// mb_type of a P or SP slice (Table 7-13 value, intra types from 5).
+    pub(super) fn mb_type_p(&mut self) -> u32 {
+        if !self.decision(14) {
+            if !self.decision(15) {
+                3 * self.decision(16) as u32
+            } else {
+                2 - self.decision(17) as u32
+            }
+        } else {
+            5 + self.mb_type_intra(17, None)
+        }
+    }
+
+    /// mb_type of a B slice (Table 7-14 value, intra types from 23) with
+    /// ctxIdxInc `inc` for the first bin.
+    pub(super) fn mb_type_b(&mut self, inc: usize) -> u32 {
+        if !self.decision(27 + inc) {
+            return 0;
+        }
+        if !self.decision(27 + 3) {
+            return 1 + self.decision(27 + 5) as u32;
+        }
+        let mut bits = (self.decision(27 + 4) as u32) << 3;
+        bits |= (self.decision(27 + 5) as u32) << 2;
+        bits |= (self.decision(27 + 5) as u32) << 1;
+        bits |= self.decision(27 + 5) as u32;
+        match bits {
+            0..=7 => bits + 3,
+            13 => 23 + self.mb_type_intra(32, None),
+            14 => 11,
+            15 => 22,
+            _ => ((bits << 1) | self.decision(27 + 5) as u32) - 4,
+        }
+    }
+
+    /// Intra mb_type (Table 7-11 value) as coded in I slices (`inc` set) or
+    /// as the suffix of P and B mb_type.
+    fn mb_type_intra(&mut self, base: usize, inc: Option<usize>) -> u32 {
+        let (first, cbp_luma, chroma0, chroma1, pred0, pred1) = match inc {
+            Some(inc) => (base + inc, base + 3, base + 4, base + 5, base + 6, base + 7),
+            None => (base, base + 1, base + 2, base + 2, base + 3, base + 3),
+        };
+        if !self.decision(first) {
+            return 0;
+        }
+        if self.terminate() {
+            return 25;
+        }
+        let mut mb_type = 1 + 12 * self.decision(cbp_luma) as u32;
+        if self.decision(chroma0) {
+            mb_type += 4 + 4 * self.decision(chroma1) as u32;
+        }
+        mb_type += 2 * self.decision(pred0) as u32;
+        mb_type += self.decision(pred1) as u32;
+        mb_type
+    }
+
+    /// sub_mb_type of a P or SP slice.
+    pub(super) fn sub_mb_type_p(&mut self) -> u32 {
+        if self.decision(21) {
+            0
+        } else if !self.decision(22) {
+            1
+        } else if self.decision(23) {
+            2
+        } else {
+            3
+        }
+    }
+
+    /// sub_mb_type of a B slice.
+    pub(super) fn sub_mb_type_b(&mut self) -> u32 {
+        if !self.decision(36) {
+            return 0;
+        }
+        if !self.decision(37) {
+            return 1 + self.decision(39) as u32;
+        }
+        let mut sub_type = 3;
+        if self.decision(38) {
+            if self.decision(39) {
+                return 11 + self.decision(39) as u32;
+            }
+            sub_type += 4;
+        }
+        sub_type += 2 * self.decision(39) as u32;
+        sub_type + self.decision(39) as u32
+    }
+
+    /// ref_idx_lX with ctxIdxInc `inc` for the first bin.
+    pub(super) fn ref_idx(&mut self, mut inc: usize) -> Result<u32> {
+        let mut ref_idx = 0;
+        while self.decision(54 + inc) {
+            ref_idx += 1;
+            inc = (inc >> 2) + 4;
+            if ref_idx >= 32 {
+                return Err(AvcError::BitstreamError("ref_idx exceeds 31".to_string()));
+            }
+        }
+        Ok(ref_idx)
+    }
+
+    /// One mvd_lX component; `abs_sum` is the sum of the neighbouring
+    /// absolute mvd components (absMvdComp).
+    pub(super) fn mvd(&mut self, component: usize, abs_sum: u32) -> Result<i32> {
+        let mut base = if component == 0 { 40 } else { 47 };
+        let inc = match abs_sum {
+            0..=2 => 0,
+            3..=32 => 1,
+            _ => 2,
+        };
+        if !self.decision(base + inc) {
+            return Ok(0);
+        }
+        let mut mvd = 1;
+        base += 3;
+        while mvd < 9 && self.decision(base) {
+            if mvd < 4 {
+                base += 1;
+            }
+            mvd += 1;
+        }
+        if mvd >= 9 {
+            mvd += self.exp_golomb_bypass(3)?;
+        }
+        let mvd = mvd as i32;
+        Ok(if self.bypass() { -mvd } else { mvd })
+    }
+
+    /// Luma part of coded_block_pattern given the luma bits of the left and
+    /// upper macroblocks (0xF when unavailable).
+    pub(super) fn cbp_luma(&mut self, left: u8, top: u8) -> u8 {
+        let bit = |v: u8, n: u8| (v >> n & 1 == 0) as usize;
+        let mut cbp = self.decision(73 + bit(left, 1) + 2 * bit(top, 2)) as u8;
+        cbp |= (self.decision(73 + bit(cbp, 0) + 2 * bit(top, 3)) as u8) << 1;
+        cbp |= (self.decision(73 + bit(left, 3) + 2 * bit(cbp, 0)) as u8) << 2;
+        cbp |= (self.decision(73 + bit(cbp, 2) + 2 * bit(cbp, 1)) as u8) << 3;
+        cbp
+    }
+
+    /// Chroma part of coded_block_pattern given the chroma values of the
+    /// left and upper macroblocks.
+    pub(super) fn cbp_chroma(&mut self, left: u8, top: u8) -> u8 {
+        let inc = (left > 0) as usize + 2 * (top > 0) as usize;
+        if !self.decision(77 + inc) {
+            return 0;
+        }
+        let inc = 4 + (left == 2) as usize + 2 * (top == 2) as usize;
+        1 + self.decision(77 + inc) as u8
+    }
+
+    /// mb_qp_delta; `prev_nonzero` tells whether the previous macroblock
+    /// in decoding order had a nonzero mb_qp_delta.
+    pub(super) fn mb_qp_delta(&mut self, prev_nonzero: bool) -> Result<i32> {
+        let mut ctx = 60 + prev_nonzero as usize;
+        let mut k = 0u32;
+        while self.decision(ctx) {
+            k += 1;
+            ctx = if k == 1 { 62 } else { 63 };
+            if k > 2 * 63 {
+                return Err(AvcError::BitstreamError(
+                    "mb_qp_delta out of range".to_string(),
+                ));
+            }
+        }
+        let k = k as i32;
+        Ok(if k & 1 == 1 { (k + 1) / 2 } else { -(k / 2) })
+    }
+
+    /// prev_intra4x4_pred_mode_flag / prev_intra8x8_pred_mode_flag.
+    pub(super) fn prev_intra_pred_mode_flag(&mut self) -> bool {
+        self.decision(68)
+    }
+
+    /// rem_intra4x4_pred_mode / rem_intra8x8_pred_mode.
+    pub(super) fn rem_intra_pred_mode(&mut self) -> u8 {
+        let mut mode = 0;
+        for i in 0..3 {
+            mode |= (self.decision(69) as u8) << i;
+        }
+        mode
+    }
+
+    /// intra_chroma_pred_mode with ctxIdxInc `inc` for the first bin.
+    pub(super) fn intra_chroma_pred_mode(&mut self, inc: usize) -> u8 {
+        if !self.decision(64 + inc) {
+            0
+        } else if !self.decision(67) {
+            1
+        } else if !self.decision(67) {
+            2
+        } else {
+            3
+        }
+    }
+
+    /// transform_size_8x8_flag with ctxIdxInc `inc`.
+    pub(super) fn transform_size_8x8_flag(&mut self, inc: usize) -> bool {
+        self.decision(399 + inc)
+    }
+
+    /// end_of_slice_flag.
+    pub(super) fn end_of_slice_flag(&mut self) -> bool {
+        self.terminate()
+    }
+
+    /// Parse `residual_block_cabac()` and return the number of nonzero
+    /// coefficients of the block (0 when coded_block_flag is 0).
+    ///
+    /// `cbf_inc` is the coded_block_flag ctxIdxInc, or `None` for luma 8x8
+    /// blocks whose coded_block_flag is inferred. `max_coeff` is the number
+    /// of coefficients of the block (maxNumCoeff).
+    pub(super) fn residual_block(
+        &mut self,
+        cat: usize,
+        cbf_inc: Option<usize>,
+        max_coeff: usize,
+    ) -> Result<usize> {
+        if let Some(inc) = cbf_inc {
+            if !self.decision(85 + CBF_CAT_OFFSET[cat] + inc) {
+                return Ok(0);
+            }
+        }
+
+        let (sig_base, last_base, abs_base) = if cat == CAT_LUMA_8X8 {
+            if self.field {
+                (436, 451, 426)
+            } else {
+                (402, 417, 426)
+            }
+        } else {
+            let (sig, last) = if self.field { (277, 338) } else { (105, 166) };
+            (
+                sig + SIG_CAT_OFFSET[cat],
+                last + SIG_CAT_OFFSET[cat],
+                227 + ABS_CAT_OFFSET[cat],
+            )
+        };
+        let field = self.field as usize;
+        let sig_inc = |i: usize| match cat {
+            CAT_LUMA_8X8 => SIG_OFFSET_8X8[field][i] as usize,
+            3 => i.min(2),
+            _ => i,
+        };
+        let last_inc = |i: usize| match cat {
+            CAT_LUMA_8X8 => LAST_OFFSET_8X8[i] as usize,
+            3 => i.min(2),
+            _ => i,
+        };
+
+        let mut significant = [false; 64];
+        let mut num_coeff = max_coeff;
+        for (i, flag) in significant.iter_mut().enumerate().take(max_coeff - 1) {
+            if self.decision(sig_base + sig_inc(i)) {
+                *flag = true;
+                if self.decision(last_base + last_inc(i)) {
+                    num_coeff = i + 1;
+                    break;
+                }
+            }
+        }
+        significant[num_coeff - 1] = true;
+
+        let abs_limit = if cat == 3 { 3 } else { 4 };
+        let mut num_gt1 = 0usize;
+        let mut num_eq1 = 0usize;
+        let num_significant = significant[..num_coeff].iter().filter(|&&s| s).count();
+        for _ in 0..num_significant {
+            let inc = if num_gt1 != 0 {
+                0
+            } else {
+                (1 + num_eq1).min(4)
+            };
+            let mut abs_minus1 = 0;
+            if self.decision(abs_base + inc) {
+                abs_minus1 = 1;
+                let inc = 5 + num_gt1.min(abs_limit);
+                while abs_minus1 < 14 && self.decision(abs_base + inc) {
+                    abs_minus1 += 1;
+                }
+                if abs_minus1 == 14 {
+                    self.exp_golomb_bypass(0)?;
+                }
+            }
+            if abs_minus1 == 0 {
+                num_eq1 += 1;
+            } else {
+                num_gt1 += 1;
+            }
+            // coeff_sign_flag
+            self.bypass();
+        }
+        Ok(num_significant)
+    }
+}
+
+/// rangeTabLPS indexed by pStateIdx and qCodIRangeIdx (Table 9-44).
+const RANGE_TAB_LPS: [[u8; 4]; 64] = [
+    [128, 176, 208, 240],
+    [128, 167, 197, 227],
+    [128, 158, 187, 216],
+    [123, 150, 178, 205],
+    [116, 142, 169, 195],
+    [111, 135, 160, 185],
+    [105, 128, 152, 175],
+    [100, 122, 144, 166],
+    [95, 116, 137, 158],
+    [90, 110, 130, 150],
+    [85, 104, 123, 142],
+    [81, 99, 117, 135],
+    [77, 94, 111, 128],
+    [73, 89, 105, 122],
+    [69, 85, 100, 116],
+    [66, 80, 95, 110],
+    [62, 76, 90, 104],
+    [59, 72, 86, 99],
+    [56, 69, 81, 94],
+    [53, 65, 77, 89],
+    [51, 62, 73, 85],
+    [48, 59, 69, 80],
+    [46, 56, 66, 76],
+    [43, 53, 63, 72],
+    [41, 50, 59, 69],
+    [39, 48, 56, 65],
+    [37, 45, 54, 62],
+    [35, 43, 51, 59],
+    [33, 41, 48, 56],
+    [32, 39, 46, 53],
+    [30, 37, 43, 50],
+    [29, 35, 41, 48],
+    [27, 33, 39, 45],
+    [26, 31, 37, 43],
+    [24, 30, 35, 41],
+    [23, 28, 33, 39],
+    [22, 27, 32, 37],
+    [21, 26, 30, 35],
+    [20, 24, 29, 33],
+    [19, 23, 27, 31],
+    [18, 22, 26, 30],
+    [17, 21, 25, 28],
+    [16, 20, 23, 27],
+    [15, 19, 22, 25],
+    [14, 18, 21, 24],
+    [14, 17, 20, 23],
+    [13, 16, 19, 22],
+    [12, 15, 18, 21],
+    [12, 14, 17, 20],
+    [11, 14, 16, 19],
+    [11, 13, 15, 18],
+    [10, 12, 15, 17],
+    [10, 12, 14, 16],
+    [9, 11, 13, 15],
+    [9, 11, 12, 14],
+    [8, 10, 12, 14],
+    [8, 9, 11, 13],
+    [7, 9, 11, 12],
+    [7, 9, 10, 12],
+    [7, 8, 10, 11],
+    [6, 8, 9, 11],
+    [6, 7, 9, 10],
+    [6, 7, 8, 9],
+    [2, 2, 2, 2],
+];
+
+/// transIdxLPS (Table 9-45).
+const TRANS_IDX_LPS: [u8; 64] = [
+    0, 0, 1, 2, 2, 4, 4, 5, 6, 7, 8, 9, 9, 11, 11, 12, 13, 13, 15, 15, 16, 16, 18, 18, 19, 19, 21,
+    21, 22, 22, 23, 24, 24, 25, 26, 26, 27, 27, 28, 29, 29, 30, 30, 30, 31, 32, 32, 33, 33, 33, 34,
+    34, 35, 35, 35, 36, 36, 36, 37, 37, 37, 38, 38, 63,
+];
+
+/// significant_coeff_flag ctxIdxInc of 8x8 blocks for frame and field
+/// coded pictures (Table 9-43).
+const SIG_OFFSET_8X8: [[u8; 63]; 2] = [
+    [
+        0, 1, 2, 3, 4, 5, 5, 4, 4, 3, 3, 4, 4, 4, 5, 5, 4, 4, 4, 4, 3, 3, 6, 7, 7, 7, 8, 9, 10, 9,
+        8, 7, 7, 6, 11, 12, 13, 11, 6, 7, 8, 9, 14, 10, 9, 8, 6, 11, 12, 13, 11, 6, 9, 14, 10, 9,
+        11, 12, 13, 11, 14, 10, 12,
+    ],
+    [
+        0, 1, 1, 2, 2, 3, 3, 4, 5, 6, 7, 7, 7, 8, 4, 5, 6, 9, 10, 10, 8, 11, 12, 11, 9, 9, 10, 10,
+        8, 11, 12, 11, 9, 9, 10, 10, 8, 11, 12, 11, 9, 9, 10, 10, 8, 13, 13, 9, 9, 10, 10, 8, 13,
+        13, 9, 9, 10, 10, 14, 14, 14, 14, 14,
+    ],
+];
+
+/// last_significant_coeff_flag ctxIdxInc of 8x8 blocks (Table 9-43).
+const LAST_OFFSET_8X8: [u8; 63] = [
+    0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+    3, 3, 3, 3, 3, 3, 3, 3, 4, 4, 4, 4, 4, 4, 4, 4, 5, 5, 5, 5, 6, 6, 6, 6, 7, 7, 7, 7, 8, 8, 8,
+];
+
+/// (m, n) initialisation values for I and SI slices (Tables 9-12 to 9-33)
+const INIT_I: [(i8, i8); NUM_CONTEXTS] = [
+    (20, -15),
+    (2, 54),
+    (3, 74),
+    (20, -15),
+    (2, 54),
+    (3, 74),
+    (-28, 127),
+    (-23, 104),
+    (-6, 53),
+    (-1, 54),
+    (7, 51),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 41),
+    (0, 63),
+    (0, 63),
+    (0, 63),
+    (-9, 83),
+    (4, 86),
+    (0, 97),
+    (-7, 72),
+    (13, 41),
+    (3, 62),
+    (0, 11),
+    (1, 55),
+    (0, 69),
+    (-17, 127),
+    (-13, 102),
+    (0, 82),
+    (-7, 74),
+    (-21, 107),
+    (-27, 127),
+    (-31, 127),
+    (-24, 127),
+    (-18, 95),
+    (-27, 127),
+    (-21, 114),
+    (-30, 127),
+    (-17, 123),
+    (-12, 115),
+    (-16, 122),
+    (-11, 115),
+    (-12, 63),
+    (-2, 68),
+    (-15, 84),
+    (-13, 104),
+    (-3, 70),
+    (-8, 93),
+    (-10, 90),
+    (-30, 127),
+    (-1, 74),
+    (-6, 97),
+    (-7, 91),
+    (-20, 127),
+    (-4, 56),
+    (-5, 82),
+    (-7, 76),
+    (-22, 125),
+    (-7, 93),
+    (-11, 87),
+    (-3, 77),
+    (-5, 71),
+    (-4, 63),
+    (-4, 68),
+    (-12, 84),
+    (-7, 62),
+    (-7, 65),
+    (8, 61),
+    (5, 56),
+    (-2, 66),
+    (1, 64),
+    (0, 61),
+    (-2, 78),
+    (1, 50),
+    (7, 52),
+    (10, 35),
+    (0, 44),
+    (11, 38),
+    (1, 45),
+    (0, 46),
+    (5, 44),
+    (31, 17),
+    (1, 51),
+    (7, 50),
+    (28, 19),
+    (16, 33),
+    (14, 62),
+    (-13, 108),
+    (-15, 100),
+    (-13, 101),
+    (-13, 91),
+    (-12, 94),
+    (-10, 88),
+    (-16, 84),
+    (-10, 86),
+    (-7, 83),
+    (-13, 87),
+    (-19, 94),
+    (1, 70),
+    (0, 72),
+    (-5, 74),
+    (18, 59),
+    (-8, 102),
+    (-15, 100),
+    (0, 95),
+    (-4, 75),
+    (2, 72),
+    (-11, 75),
+    (-3, 71),
+    (15, 46),
+    (-13, 69),
+    (0, 62),
+    (0, 65),
+    (21, 37),
+    (-15, 72),
+    (9, 57),
+    (16, 54),
+    (0, 62),
+    (12, 72),
+    (24, 0),
+    (15, 9),
+    (8, 25),
+    (13, 18),
+    (15, 9),
+    (13, 19),
+    (10, 37),
+    (12, 18),
+    (6, 29),
+    (20, 33),
+    (15, 30),
+    (4, 45),
+    (1, 58),
+    (0, 62),
+    (7, 61),
+    (12, 38),
+    (11, 45),
+    (15, 39),
+    (11, 42),
+    (13, 44),
+    (16, 45),
+    (12, 41),
+    (10, 49),
+    (30, 34),
+    (18, 42),
+    (10, 55),
+    (17, 51),
+    (17, 46),
+    (0, 89),
+    (26, -19),
+    (22, -17),
+    (26, -17),
+    (30, -25),
+    (28, -20),
+    (33, -23),
+    (37, -27),
+    (33, -23),
+    (40, -28),
+    (38, -17),
+    (33, -11),
+    (40, -15),
+    (41, -6),
+    (38, 1),
+    (41, 17),
+    (30, -6),
+    (27, 3),
+    (26, 22),
+    (37, -16),
+    (35, -4),
+    (38, -8),
+    (38, -3),
+    (37, 3),
+    (38, 5),
+    (42, 0),
+    (35, 16),
+    (39, 22),
+    (14, 48),
+    (27, 37),
+    (21, 60),
+    (12, 68),
+    (2, 97),
+    (-3, 71),
+    (-6, 42),
+    (-5, 50),
+    (-3, 54),
+    (-2, 62),
+    (0, 58),
+    (1, 63),
+    (-2, 72),
+    (-1, 74),
+    (-9, 91),
+    (-5, 67),
+    (-5, 27),
+    (-3, 39),
+    (-2, 44),
+    (0, 46),
+    (-16, 64),
+    (-8, 68),
+    (-10, 78),
+    (-6, 77),
+    (-10, 86),
+    (-12, 92),
+    (-15, 55),
+    (-10, 60),
+    (-6, 62),
+    (-4, 65),
+    (-12, 73),
+    (-8, 76),
+    (-7, 80),
+    (-9, 88),
+    (-17, 110),
+    (-11, 97),
+    (-20, 84),
+    (-11, 79),
+    (-6, 73),
+    (-4, 74),
+    (-13, 86),
+    (-13, 96),
+    (-11, 97),
+    (-19, 117),
+    (-8, 78),
+    (-5, 33),
+    (-4, 48),
+    (-2, 53),
+    (-3, 62),
+    (-13, 71),
+    (-10, 79),
+    (-12, 86),
+    (-13, 90),
+    (-14, 97),
+    (0, 0),
+    (-6, 93),
+    (-6, 84),
+    (-8, 79),
+    (0, 66),
+    (-1, 71),
+    (0, 62),
+    (-2, 60),
+    (-2, 59),
+    (-5, 75),
+    (-3, 62),
+    (-4, 58),
+    (-9, 66),
+    (-1, 79),
+    (0, 71),
+    (3, 68),
+    (10, 44),
+    (-7, 62),
+    (15, 36),
+    (14, 40),
+    (16, 27),
+    (12, 29),
+    (1, 44),
+    (20, 36),
+    (18, 32),
+    (5, 42),
+    (1, 48),
+    (10, 62),
+    (17, 46),
+    (9, 64),
+    (-12, 104),
+    (-11, 97),
+    (-16, 96),
+    (-7, 88),
+    (-8, 85),
+    (-7, 85),
+    (-9, 85),
+    (-13, 88),
+    (4, 66),
+    (-3, 77),
+    (-3, 76),
+    (-6, 76),
+    (10, 58),
+    (-1, 76),
+    (-1, 83),
+    (-7, 99),
+    (-14, 95),
+    (2, 95),
+    (0, 76),
+    (-5, 74),
+    (0, 70),
+    (-11, 75),
+    (1, 68),
+    (0, 65),
+    (-14, 73),
+    (3, 62),
+    (4, 62),
+    (-1, 68),
+    (-13, 75),
+    (11, 55),
+    (5, 64),
+    (12, 70),
+    (15, 6),
+    (6, 19),
+    (7, 16),
+    (12, 14),
+    (18, 13),
+    (13, 11),
+    (13, 15),
+    (15, 16),
+    (12, 23),
+    (13, 23),
+    (15, 20),
+    (14, 26),
+    (14, 44),
+    (17, 40),
+    (17, 47),
+    (24, 17),
+    (21, 21),
+    (25, 22),
+    (31, 27),
+    (22, 29),
+    (19, 35),
+    (14, 50),
+    (10, 57),
+    (7, 63),
+    (-2, 77),
+    (-4, 82),
+    (-3, 94),
+    (9, 69),
+    (-12, 109),
+    (36, -35),
+    (36, -34),
+    (32, -26),
+    (37, -30),
+    (44, -32),
+    (34, -18),
+    (34, -15),
+    (40, -15),
+    (33, -7),
+    (35, -5),
+    (33, 0),
+    (38, 2),
+    (33, 13),
+    (23, 35),
+    (13, 58),
+    (29, -3),
+    (26, 0),
+    (22, 30),
+    (31, -7),
+    (35, -15),
+    (34, -3),
+    (34, 3),
+    (36, -1),
+    (34, 5),
+    (32, 11),
+    (35, 5),
+    (34, 12),
+    (39, 11),
+    (30, 29),
+    (34, 26),
+    (29, 39),
+    (19, 66),
+    (31, 21),
+    (31, 31),
+    (25, 50),
+    (-17, 120),
+    (-20, 112),
+    (-18, 114),
+    (-11, 85),
+    (-15, 92),
+    (-14, 89),
+    (-26, 71),
+    (-15, 81),
+    (-14, 80),
+    (0, 68),
+    (-14, 70),
+    (-24, 56),
+    (-23, 68),
+    (-24, 50),
+    (-11, 74),
+    (23, -13),
+    (26, -13),
+    (40, -15),
+    (49, -14),
+    (44, 3),
+    (45, 6),
+    (44, 34),
+    (33, 54),
+    (19, 82),
+    (-3, 75),
+    (-1, 23),
+    (1, 34),
+    (1, 43),
+    (0, 54),
+    (-2, 55),
+    (0, 61),
+    (1, 64),
+    (0, 68),
+    (-9, 92),
+    (-14, 106),
+    (-13, 97),
+    (-15, 90),
+    (-12, 90),
+    (-18, 88),
+    (-10, 73),
+    (-9, 79),
+    (-14, 86),
+    (-10, 73),
+    (-10, 70),
+    (-10, 69),
+    (-5, 66),
+    (-9, 64),
+    (-5, 58),
+    (2, 59),
+    (21, -10),
+    (24, -11),
+    (28, -8),
+    (28, -1),
+    (29, 3),
+    (29, 9),
+    (35, 20),
+    (29, 36),
+    (14, 67),
+];
+
+/// (m, n) initialisation values for P, SP and B slices with cabac_init_idc 0
+const INIT_PB_IDC0: [(i8, i8); NUM_CONTEXTS] = [
+    (20, -15),
+    (2, 54),
+    (3, 74),
+    (20, -15),
+    (2, 54),
+    (3, 74),
+    (-28, 127),
+    (-23, 104),
+    (-6, 53),
+    (-1, 54),
+    (7, 51),
+    (23, 33),
+    (23, 2),
+    (21, 0),
+    (1, 9),
+    (0, 49),
+    (-37, 118),
+    (5, 57),
+    (-13, 78),
+    (-11, 65),
+    (1, 62),
+    (12, 49),
+    (-4, 73),
+    (17, 50),
+    (18, 64),
+    (9, 43),
+    (29, 0),
+    (26, 67),
+    (16, 90),
+    (9, 104),
+    (-46, 127),
+    (-20, 104),
+    (1, 67),
+    (-13, 78),
+    (-11, 65),
+    (1, 62),
+    (-6, 86),
+    (-17, 95),
+    (-6, 61),
+    (9, 45),
+    (-3, 69),
+    (-6, 81),
+    (-11, 96),
+    (6, 55),
+    (7, 67),
+    (-5, 86),
+    (2, 88),
+    (0, 58),
+    (-3, 76),
+    (-10, 94),
+    (5, 54),
+    (4, 69),
+    (-3, 81),
+    (0, 88),
+    (-7, 67),
+    (-5, 74),
+    (-4, 74),
+    (-5, 80),
+    (-7, 72),
+    (1, 58),
+    (0, 41),
+    (0, 63),
+    (0, 63),
+    (0, 63),
+    (-9, 83),
+    (4, 86),
+    (0, 97),
+    (-7, 72),
+    (13, 41),
+    (3, 62),
+    (0, 45),
+    (-4, 78),
+    (-3, 96),
+    (-27, 126),
+    (-28, 98),
+    (-25, 101),
+    (-23, 67),
+    (-28, 82),
+    (-20, 94),
+    (-16, 83),
+    (-22, 110),
+    (-21, 91),
+    (-18, 102),
+    (-13, 93),
+    (-29, 127),
+    (-7, 92),
+    (-5, 89),
+    (-7, 96),
+    (-13, 108),
+    (-3, 46),
+    (-1, 65),
+    (-1, 57),
+    (-9, 93),
+    (-3, 74),
+    (-9, 92),
+    (-8, 87),
+    (-23, 126),
+    (5, 54),
+    (6, 60),
+    (6, 59),
+    (6, 69),
+    (-1, 48),
+    (0, 68),
+    (-4, 69),
+    (-8, 88),
+    (-2, 85),
+    (-6, 78),
+    (-1, 75),
+    (-7, 77),
+    (2, 54),
+    (5, 50),
+    (-3, 68),
+    (1, 50),
+    (6, 42),
+    (-4, 81),
+    (1, 63),
+    (-4, 70),
+    (0, 67),
+    (2, 57),
+    (-2, 76),
+    (11, 35),
+    (4, 64),
+    (1, 61),
+    (11, 35),
+    (18, 25),
+    (12, 24),
+    (13, 29),
+    (13, 36),
+    (-10, 93),
+    (-7, 73),
+    (-2, 73),
+    (13, 46),
+    (9, 49),
+    (-7, 100),
+    (9, 53),
+    (2, 53),
+    (5, 53),
+    (-2, 61),
+    (0, 56),
+    (0, 56),
+    (-13, 63),
+    (-5, 60),
+    (-1, 62),
+    (4, 57),
+    (-6, 69),
+    (4, 57),
+    (14, 39),
+    (4, 51),
+    (13, 68),
+    (3, 64),
+    (1, 61),
+    (9, 63),
+    (7, 50),
+    (16, 39),
+    (5, 44),
+    (4, 52),
+    (11, 48),
+    (-5, 60),
+    (-1, 59),
+    (0, 59),
+    (22, 33),
+    (5, 44),
+    (14, 43),
+    (-1, 78),
+    (0, 60),
+    (9, 69),
+    (11, 28),
+    (2, 40),
+    (3, 44),
+    (0, 49),
+    (0, 46),
+    (2, 44),
+    (2, 51),
+    (0, 47),
+    (4, 39),
+    (2, 62),
+    (6, 46),
+    (0, 54),
+    (3, 54),
+    (2, 58),
+    (4, 63),
+    (6, 51),
+    (6, 57),
+    (7, 53),
+    (6, 52),
+    (6, 55),
+    (11, 45),
+    (14, 36),
+    (8, 53),
+    (-1, 82),
+    (7, 55),
+    (-3, 78),
+    (15, 46),
+    (22, 31),
+    (-1, 84),
+    (25, 7),
+    (30, -7),
+    (28, 3),
+    (28, 4),
+    (32, 0),
+    (34, -1),
+    (30, 6),
+    (30, 6),
+    (32, 9),
+    (31, 19),
+    (26, 27),
+    (26, 30),
+    (37, 20),
+    (28, 34),
+    (17, 70),
+    (1, 67),
+    (5, 59),
+    (9, 67),
+    (16, 30),
+    (18, 32),
+    (18, 35),
+    (22, 29),
+    (24, 31),
+    (23, 38),
+    (18, 43),
+    (20, 41),
+    (11, 63),
+    (9, 59),
+    (9, 64),
+    (-1, 94),
+    (-2, 89),
+    (-9, 108),
+    (-6, 76),
+    (-2, 44),
+    (0, 45),
+    (0, 52),
+    (-3, 64),
+    (-2, 59),
+    (-4, 70),
+    (-4, 75),
+    (-8, 82),
+    (-17, 102),
+    (-9, 77),
+    (3, 24),
+    (0, 42),
+    (0, 48),
+    (0, 55),
+    (-6, 59),
+    (-7, 71),
+    (-12, 83),
+    (-11, 87),
+    (-30, 119),
+    (1, 58),
+    (-3, 29),
+    (-1, 36),
+    (1, 38),
+    (2, 43),
+    (-6, 55),
+    (0, 58),
+    (0, 64),
+    (-3, 74),
+    (-10, 90),
+    (0, 70),
+    (-4, 29),
+    (5, 31),
+    (7, 42),
+    (1, 59),
+    (-2, 58),
+    (-3, 72),
+    (-3, 81),
+    (-11, 97),
+    (0, 58),
+    (8, 5),
+    (10, 14),
+    (14, 18),
+    (13, 27),
+    (2, 40),
+    (0, 58),
+    (-3, 70),
+    (-6, 79),
+    (-8, 85),
+    (0, 0),
+    (-13, 106),
+    (-16, 106),
+    (-10, 87),
+    (-21, 114),
+    (-18, 110),
+    (-14, 98),
+    (-22, 110),
+    (-21, 106),
+    (-18, 103),
+    (-21, 107),
+    (-23, 108),
+    (-26, 112),
+    (-10, 96),
+    (-12, 95),
+    (-5, 91),
+    (-9, 93),
+    (-22, 94),
+    (-5, 86),
+    (9, 67),
+    (-4, 80),
+    (-10, 85),
+    (-1, 70),
+    (7, 60),
+    (9, 58),
+    (5, 61),
+    (12, 50),
+    (15, 50),
+    (18, 49),
+    (17, 54),
+    (10, 41),
+    (7, 46),
+    (-1, 51),
+    (7, 49),
+    (8, 52),
+    (9, 41),
+    (6, 47),
+    (2, 55),
+    (13, 41),
+    (10, 44),
+    (6, 50),
+    (5, 53),
+    (13, 49),
+    (4, 63),
+    (6, 64),
+    (-2, 69),
+    (-2, 59),
+    (6, 70),
+    (10, 44),
+    (9, 31),
+    (12, 43),
+    (3, 53),
+    (14, 34),
+    (10, 38),
+    (-3, 52),
+    (13, 40),
+    (17, 32),
+    (7, 44),
+    (7, 38),
+    (13, 50),
+    (10, 57),
+    (26, 43),
+    (14, 11),
+    (11, 14),
+    (9, 11),
+    (18, 11),
+    (21, 9),
+    (23, -2),
+    (32, -15),
+    (32, -15),
+    (34, -21),
+    (39, -23),
+    (42, -33),
+    (41, -31),
+    (46, -28),
+    (38, -12),
+    (21, 29),
+    (45, -24),
+    (53, -45),
+    (48, -26),
+    (65, -43),
+    (43, -19),
+    (39, -10),
+    (30, 9),
+    (18, 26),
+    (20, 27),
+    (0, 57),
+    (-14, 82),
+    (-5, 75),
+    (-19, 97),
+    (-35, 125),
+    (27, 0),
+    (28, 0),
+    (31, -4),
+    (27, 6),
+    (34, 8),
+    (30, 10),
+    (24, 22),
+    (33, 19),
+    (22, 32),
+    (26, 31),
+    (21, 41),
+    (26, 44),
+    (23, 47),
+    (16, 65),
+    (14, 71),
+    (8, 60),
+    (6, 63),
+    (17, 65),
+    (21, 24),
+    (23, 20),
+    (26, 23),
+    (27, 32),
+    (28, 23),
+    (28, 24),
+    (23, 40),
+    (24, 32),
+    (28, 29),
+    (23, 42),
+    (19, 57),
+    (22, 53),
+    (22, 61),
+    (11, 86),
+    (12, 40),
+    (11, 51),
+    (14, 59),
+    (-4, 79),
+    (-7, 71),
+    (-5, 69),
+    (-9, 70),
+    (-8, 66),
+    (-10, 68),
+    (-19, 73),
+    (-12, 69),
+    (-16, 70),
+    (-15, 67),
+    (-20, 62),
+    (-19, 70),
+    (-16, 66),
+    (-22, 65),
+    (-20, 63),
+    (9, -2),
+    (26, -9),
+    (33, -9),
+    (39, -7),
+    (41, -2),
+    (45, 3),
+    (49, 9),
+    (45, 27),
+    (36, 59),
+    (-6, 66),
+    (-7, 35),
+    (-7, 42),
+    (-8, 45),
+    (-5, 48),
+    (-12, 56),
+    (-6, 60),
+    (-5, 62),
+    (-8, 66),
+    (-8, 76),
+    (-5, 85),
+    (-6, 81),
+    (-10, 77),
+    (-7, 81),
+    (-17, 80),
+    (-18, 73),
+    (-4, 74),
+    (-10, 83),
+    (-9, 71),
+    (-9, 67),
+    (-1, 61),
+    (-8, 66),
+    (-14, 66),
+    (0, 59),
+    (2, 59),
+    (21, -13),
+    (33, -14),
+    (39, -7),
+    (46, -2),
+    (51, 2),
+    (60, 6),
+    (61, 17),
+    (55, 34),
+    (42, 62),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_context_initialisation() {
+        // Contexts shared by all slice types (mb_type of I slices, mb_qp_delta)
+        assert_eq!(INIT_I[..11], INIT_PB_IDC0[..11]);
+        assert_eq!(INIT_I[60..70], INIT_PB_IDC0[60..70]);
+    }
+
+    #[test]
+    fn test_context_initialisation() {
+        // (m, n) = (20, -15) at QP 26: preCtxState = 17 -> LPS state 46, MPS 0
+        let data = [0u8; 4];
+        let reader = CabacReader::new(&data, 0, SliceType::I, 26, false).unwrap();
+        assert_eq!(reader.contexts[0].state, 46);
+        assert!(!reader.contexts[0].mps);
+        // (0, 41) -> preCtxState 41 -> state 22, MPS 0
+        assert_eq!(reader.contexts[60].state, 22);
+        assert_eq!(reader.position(), 9);
+    }
+
+    #[test]
+    fn test_terminate_after_initialisation() {
+        // Initial offsets of 510 and 511 are not allowed
+        assert!(CabacReader::new(&[0xFF, 0x80], 0, SliceType::I, 26, false).is_err());
+
+        // Offset 509 is past the 2-wide terminating interval of range 510
+        let data = [0xFE, 0x80];
+        let mut reader = CabacReader::new(&data, 0, SliceType::I, 26, false).unwrap();
+        assert!(reader.end_of_slice_flag());
+        assert_eq!(reader.position(), 9);
+    }
+}
@@ -0,0 +1,396 @@
+//! CAVLC parsing process (ITU-T H.264 Section 9.2).
+//!
+//! Parses `residual_block_cavlc()` and maps `coded_block_pattern` codes.
+//! Coefficient values are decoded only as far as needed to find the end of
+//! each block.
+
+use super::RbspReader;
+use crate::error::{AvcError, Result};
+
+/// coeff_token code lengths for 0 <= nC < 2, 2 <= nC < 4, 4 <= nC < 8 and
+/// 8 <= nC, indexed by TotalCoeff * 4 + TrailingOnes (Table 9-5).
+const COEFF_TOKEN_LEN: [[u8; 68]; 4] = [
+    [
+        1, 0, 0, 0, 6, 2, 0, 0, 8, 6, 3, 0, 9, 8, 7, 5, 10, 9, 8, 6, 11, 10, 9, 7, 13, 11, 10, 8,
+        13, 13, 11, 9, 13, 13, 13, 10, 14, 14, 13, 11, 14, 14, 14, 13, 15, 15, 14, 14, 15, 15, 15,
+        14, 16, 15, 15, 15, 16, 16, 16, 15, 16, 16, 16, 16, 16, 16, 16, 16,
+    ],
+    [
+        2, 0, 0, 0, 6, 2, 0, 0, 6, 5, 3, 0, 7, 6, 6, 4, 8, 6, 6, 4, 8, 7, 7, 5, 9, 8, 8, 6, 11, 9,
+        9, 6, 11, 11, 11, 7, 12, 11, 11, 9, 12, 12, 12, 11, 12, 12, 12, 11, 13, 13, 13, 12, 13, 13,
+        13, 13, 13, 14, 13, 13, 14, 14, 14, 13, 14, 14, 14, 14,
+    ],
+    [
+        4, 0, 0, 0, 6, 4, 0, 0, 6, 5, 4, 0, 6, 5, 5, 4, 7, 5, 5, 4, 7, 5, 5, 4, 7, 6, 6, 4, 7, 6,
+        6, 4, 8, 7, 7, 5, 8, 8, 7, 6, 9, 8, 8, 7, 9, 9, 8, 8, 9, 9, 9, 8, 10, 9, 9, 9, 10, 10, 10,
+        10, 10, 10, 10, 10, 10, 10, 10, 10,
+    ],
+    [
+        6, 0, 0, 0, 6, 6, 0, 0, 6, 6, 6, 0, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6,
+        6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6,
+        6, 6, 6, 6, 6, 6, 6, 6,
+    ],
+];
+
+/// coeff_token code values matching [`COEFF_TOKEN_LEN`].
+const COEFF_TOKEN_BITS: [[u8; 68]; 4] = [
+    [
+        1, 0, 0, 0, 5, 1, 0, 0, 7, 4, 1, 0, 7, 6, 5, 3, 7, 6, 5, 3, 7, 6, 5, 4, 15, 6, 5, 4, 11,
+        14, 5, 4, 8, 10, 13, 4, 15, 14, 9, 4, 11, 10, 13, 12, 15, 14, 9, 12, 11, 10, 13, 8, 15, 1,
+        9, 12, 11, 14, 13, 8, 7, 10, 9, 12, 4, 6, 5, 8,
+    ],
+    [
+        3, 0, 0, 0, 11, 2, 0, 0, 7, 7, 3, 0, 7, 10, 9, 5, 7, 6, 5, 4, 4, 6, 5, 6, 7, 6, 5, 8, 15,
+        6, 5, 4, 11, 14, 13, 4, 15, 10, 9, 4, 11, 14, 13, 12, 8, 10, 9, 8, 15, 14, 13, 12, 11, 10,
+        9, 12, 7, 11, 6, 8, 9, 8, 10, 1, 7, 6, 5, 4,
+    ],
+    [
+        15, 0, 0, 0, 15, 14, 0, 0, 11, 15, 13, 0, 8, 12, 14, 12, 15, 10, 11, 11, 11, 8, 9, 10, 9,
+        14, 13, 9, 8, 10, 9, 8, 15, 14, 13, 13, 11, 14, 10, 12, 15, 10, 13, 12, 11, 14, 9, 12, 8,
+        10, 13, 8, 13, 7, 9, 12, 9, 12, 11, 10, 5, 8, 7, 6, 1, 4, 3, 2,
+    ],
+    [
+        3, 0, 0, 0, 0, 1, 0, 0, 4, 5, 6, 0, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21,
+        22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44,
+        45, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63,
+    ],
+];
+
+/// coeff_token code lengths for 4:2:0 chroma DC (nC == -1).
+const CHROMA_DC_COEFF_TOKEN_LEN: [u8; 20] =
+    [2, 0, 0, 0, 6, 1, 0, 0, 6, 6, 3, 0, 6, 7, 7, 6, 6, 8, 8, 7];
+
+/// coeff_token code values matching [`CHROMA_DC_COEFF_TOKEN_LEN`].
+const CHROMA_DC_COEFF_TOKEN_BITS: [u8; 20] =
+    [1, 0, 0, 0, 7, 1, 0, 0, 4, 6, 1, 0, 3, 3, 2, 5, 2, 3, 2, 0];
+
+/// total_zeros code lengths indexed by TotalCoeff - 1 (Tables 9-7 and 9-8).
+const TOTAL_ZEROS_LEN: [&[u8]; 15] = [
+    &[1, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 9],
+    &[3, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 6, 6, 6, 6],
+    &[4, 3, 3, 3, 4, 4, 3, 3, 4, 5, 5, 6, 5, 6],
+    &[5, 3, 4, 4, 3, 3, 3, 4, 3, 4, 5, 5, 5],
+    &[4, 4, 4, 3, 3, 3, 3, 3, 4, 5, 4, 5],
+    &[6, 5, 3, 3, 3, 3, 3, 3, 4, 3, 6],
+    &[6, 5, 3, 3, 3, 2, 3, 4, 3, 6],
+    &[6, 4, 5, 3, 2, 2, 3, 3, 6],
+    &[6, 6, 4, 2, 2, 3, 2, 5],
+    &[5, 5, 3, 2, 2, 2, 4],
+    &[4, 4, 3, 3, 1, 3],
+    &[4, 4, 2, 1, 3],
+    &[3, 3, 1, 2],
+    &[2, 2, 1],
+    &[1, 1],
+];
+
+/// total_zeros code values matching [`TOTAL_ZEROS_LEN`].
+const TOTAL_ZEROS_BITS: [&[u8]; 15] = [
+    &[1, 3, 2, 3, 2, 3, 2, 3, 2, 3, 2, 3, 2, 3, 2, 1],
+    &[7, 6, 5, 4, 3, 5, 4, 3, 2, 3, 2, 3, 2, 1, 0],
+    &[5, 7, 6, 5, 4, 3, 4, 3, 2, 3, 2, 1, 1, 0],
+    &[3, 7, 5, 4, 6, 5, 4, 3, 3, 2, 2, 1, 0],
+    &[5, 4, 3, 7, 6, 5, 4, 3, 2, 1, 1, 0],
+    &[1, 1, 7, 6, 5, 4, 3, 2, 1, 1, 0],
+    &[1, 1, 5, 4, 3, 3, 2, 1, 1, 0],
+    &[1, 1, 1, 3, 3, 2, 2, 1, 0],
+    &[1, 0, 1, 3, 2, 1, 1, 1],
+    &[1, 0, 1, 3, 2, 1, 1],
+    &[0, 1, 1, 2, 1, 3],
+    &[0, 1, 1, 1, 1],
+    &[0, 1, 1, 1],
+    &[0, 1, 1],
+    &[0, 1],
+];
+
+/// total_zeros code lengths for 4:2:0 chroma DC (Table 9-9).
+const CHROMA_DC_TOTAL_ZEROS_LEN: [&[u8]; 3] = [&[1, 2, 3, 3], &[1, 2, 2], &[1, 1]];
+
+/// total_zeros code values matching [`CHROMA_DC_TOTAL_ZEROS_LEN`].
+const CHROMA_DC_TOTAL_ZEROS_BITS: [&[u8]; 3] = [&[1, 1, 1, 0], &[1, 1, 0], &[1, 0]];
+
+/// run_before code lengths indexed by min(zerosLeft, 7) - 1 (Table 9-10).
+const RUN_BEFORE_LEN: [&[u8]; 7] = [
+    &[1, 1],
+    &[1, 2, 2],
+    &[2, 2, 2, 2],
+    &[2, 2, 2, 3, 3],
+    &[2, 2, 3, 3, 3, 3],
+    &[2, 3, 3, 3, 3, 3, 3],
+    &[3, 3, 3, 3, 3, 3, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+];
+
+/// run_before code values matching [`RUN_BEFORE_LEN`].
+const RUN_BEFORE_BITS: [&[u8]; 7] = [
+    &[1, 0],
+    &[1, 1, 0],
+    &[3, 2, 1, 0],
+    &[3, 2, 1, 1, 0],
+    &[3, 2, 3, 2, 1, 0],
+    &[3, 0, 1, 3, 2, 5, 4],
+    &[7, 6, 5, 4, 3, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+];
+
+/// coded_block_pattern of intra macroblocks by codeNum, ChromaArrayType 1
+/// or 2 (Table 9-4).
+const INTRA_CBP: [u8; 48] = [
+    47, 31, 15, 0, 23, 27, 29, 30, 7, 11, 13, 14, 39, 43, 45, 46, 16, 3, 5, 10, 12, 19, 21, 26, 28,
+    35, 37, 42, 44, 1, 2, 4, 8, 17, 18, 20, 24, 6, 9, 22, 25, 32, 33, 34, 36, 40, 38, 41,
+];
+
+/// coded_block_pattern of inter macroblocks by codeNum, ChromaArrayType 1
+/// or 2 (Table 9-4).
+const INTER_CBP: [u8; 48] = [
+    0, 16, 1, 2, 4, 8, 32, 3, 5, 10, 12, 15, 47, 7, 11, 13, 14, 6, 9, 31, 35, 37, 42, 44, 33, 34,
+    36, 40, 39, 43, 45, 46, 17, 18, 20, 24, 19, 21, 26, 28, 23, 27, 29, 30, 22, 25, 38, 41,
+];
+
+/// coded_block_pattern of intra macroblocks by codeNum, ChromaArrayType 0.
+const INTRA_CBP_MONOCHROME: [u8; 16] = [15, 0, 7, 11, 13, 14, 3, 5, 10, 12, 1, 2, 4, 8, 6, 9];
+
+/// coded_block_pattern of inter macroblocks by codeNum, ChromaArrayType 0.
+const INTER_CBP_MONOCHROME: [u8; 16] = [0, 1, 2, 4, 8, 3, 5, 10, 12, 15, 7, 11, 13, 14, 6, 9];
+
+/// Map the codeNum of `coded_block_pattern` to its value.
+pub(super) fn coded_block_pattern(code_num: u32, intra: bool, monochrome: bool) -> Result<u8> {
+    let table: &[u8] = match (intra, monochrome) {
+        (true, false) => &INTRA_CBP,
+        (false, false) => &INTER_CBP,
+        (true, true) => &INTRA_CBP_MONOCHROME,
+        (false, true) => &INTER_CBP_MONOCHROME,
+    };
+    table.get(code_num as usize).copied().ok_or_else(|| {
+        AvcError::BitstreamError(format!(
+            "coded_block_pattern codeNum {} out of range",
+            code_num
+        ))
+    })
+}
+
+/// Read one codeword of a variable length code given as parallel length and
+/// value tables, returning its index.
+fn read_vlc(reader: &mut RbspReader, lens: &[u8], bits: &[u8]) -> Result<usize> {
+    let mut code = 0u32;
+    for len in 1..=16 {
+        code = (code << 1) | reader.read_bit()?;
+        if let Some(index) = (0..lens.len()).find(|&i| lens[i] == len && bits[i] as u32 == code) {
+            return Ok(index);
+        }
+    }
+    Err(AvcError::BitstreamError(
+        "invalid CAVLC codeword".to_string(),
+    ))
+}
+
+/// Parse `residual_block_cavlc()` and return TotalCoeff(coeff_token).
+///
+/// `nc` selects the coeff_token table, with -1 denoting 4:2:0 chroma DC.
+/// `max_coeff` is the number of coefficients of the block (maxNumCoeff).
+pub(super) fn residual_block(reader: &mut RbspReader, nc: i32, max_coeff: usize) -> Result<u8> {
+    let token = if nc == -1 {
+        read_vlc(
+            reader,
+            &CHROMA_DC_COEFF_TOKEN_LEN,
+            &CHROMA_DC_COEFF_TOKEN_BITS,
+        )?
+    } else {
+        let table = match nc {
+            0..=1 => 0,
+            2..=3 => 1,
+            4..=7 => 2,
+            _ => 3,
+        };
+        read_vlc(reader, &COEFF_TOKEN_LEN[table], &COEFF_TOKEN_BITS[table])?
+    };
+    let total_coeff = token / 4;
+    let trailing_ones = token % 4;
+    if total_coeff > max_coeff {
+        return Err(AvcError::BitstreamError(format!(
+            "TotalCoeff {} exceeds {} coefficients",
+            total_coeff, max_coeff
+        )));
+    }
+    if total_coeff == 0 {
+        return Ok(0);
+    }
+
+    let mut suffix_length = if total_coeff > 10 && trailing_ones < 3 {
+        1
+    } else {
+        0
+    };
+    for i in 0..total_coeff {
+        if i < trailing_ones {
+            // trailing_ones_sign_flag
+            reader.read_bit()?;
+            continue;
+        }
+
+        let mut level_prefix = 0u32;
+        while reader.read_bit()? == 0 {
+            level_prefix += 1;
+            if level_prefix > 31 {
+                return Err(AvcError::BitstreamError(
+                    "level_prefix exceeds 31".to_string(),
+                ));
+            }
+        }
+        let mut level_code = (level_prefix.min(15) << suffix_length) as i64;
+        if suffix_length > 0 || level_prefix >= 14 {
+            let suffix_size = if level_prefix == 14 && suffix_length == 0 {
+                4
+            } else if level_prefix >= 15 {
+                level_prefix - 3
+            } else {
+                suffix_length
+            };
+            level_code += reader.read_bits(suffix_size)? as i64;
+        }
+        if level_prefix >= 15 && suffix_length == 0 {
+            level_code += 15;
+        }
+        if level_prefix >= 16 {
+            level_code += (1 << (level_prefix - 3)) - 4096;
+        }
+        if i == trailing_ones && trailing_ones < 3 {
+            level_code += 2;
+        }
+
+        let abs_level = (level_code + 2) >> 1;
+        if suffix_length == 0 {
+            suffix_length = 1;
+        }
+        if abs_level > 3 << (suffix_length - 1) && suffix_length < 6 {
+            suffix_length += 1;
+        }
+    }
+
+    let mut zeros_left = if total_coeff < max_coeff {
+        if nc == -1 {
+            read_vlc(
+                reader,
+                CHROMA_DC_TOTAL_ZEROS_LEN[total_coeff - 1],
+                CHROMA_DC_TOTAL_ZEROS_BITS[total_coeff - 1],
+            )?
+        } else {
+            read_vlc(
+                reader,
+                TOTAL_ZEROS_LEN[total_coeff - 1],
+                TOTAL_ZEROS_BITS[total_coeff - 1],
+            )?
+        }
+    } else {
+        0
+    };
+    if total_coeff + zeros_left > max_coeff {
+        return Err(AvcError::BitstreamError(format!(
+            "total_zeros {} with TotalCoeff {} exceeds {} coefficients",
+            zeros_left, total_coeff, max_coeff
+        )));
+    }
+
+    for _ in 0..total_coeff - 1 {
+        if zeros_left == 0 {
+            break;
+        }
+        let table = zeros_left.min(7) - 1;
+        let run_before = read_vlc(reader, RUN_BEFORE_LEN[table], RUN_BEFORE_BITS[table])?;
+        if run_before > zeros_left {
+            return Err(AvcError::BitstreamError(format!(
+                "run_before {} exceeds {} remaining zeros",
+                run_before, zeros_left
+            )));
+        }
+        zeros_left -= run_before;
+    }
+
+    Ok(total_coeff as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check that the used codewords of a table are prefix-free.
+    fn assert_prefix_free(lens: &[u8], bits: &[u8]) {
+        let codes: Vec<(u8, u32)> = lens
+            .iter()
+            .zip(bits)
+            .filter(|(&len, _)| len > 0)
+            .map(|(&len, &bits)| (len, bits as u32))
+            .collect();
+        for (i, &(len_a, a)) in codes.iter().enumerate() {
+            assert!(a < 1 << len_a, "code {} does not fit in {} bits", a, len_a);
+            for &(len_b, b) in &codes[i + 1..] {
+                let len = len_a.min(len_b);
+                assert_ne!(
+                    a >> (len_a - len),
+                    b >> (len_b - len),
+                    "codes {:0w1$b} and {:0w2$b} share a prefix",
+                    a,
+                    b,
+                    w1 = len_a as usize,
+                    w2 = len_b as usize
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_vlc_tables_are_prefix_free() {
+        for (lens, bits) in COEFF_TOKEN_LEN.iter().zip(&COEFF_TOKEN_BITS) {
+            assert_prefix_free(lens, bits);
+        }
+        assert_prefix_free(&CHROMA_DC_COEFF_TOKEN_LEN, &CHROMA_DC_COEFF_TOKEN_BITS);
+        for (lens, bits) in TOTAL_ZEROS_LEN.iter().zip(&TOTAL_ZEROS_BITS) {
+            assert_eq!(lens.len(), bits.len());
+            assert_prefix_free(lens, bits);
+        }
+        for (lens, bits) in CHROMA_DC_TOTAL_ZEROS_LEN
+            .iter()
+            .zip(&CHROMA_DC_TOTAL_ZEROS_BITS)
+        {
+            assert_prefix_free(lens, bits);
+        }
+        for (lens, bits) in RUN_BEFORE_LEN.iter().zip(&RUN_BEFORE_BITS) {
+            assert_eq!(lens.len(), bits.len());
+            assert_prefix_free(lens, bits);
+        }
+    }
+
+    #[test]
+    fn test_cbp_tables_are_permutations() {
+        for table in [&INTRA_CBP[..], &INTER_CBP[..]] {
+            let mut values = table.to_vec();
+            values.sort_unstable();
+            assert_eq!(values, (0..48).collect::<Vec<u8>>());
+        }
+        for table in [&INTRA_CBP_MONOCHROME[..], &INTER_CBP_MONOCHROME[..]] {
+            let mut values = table.to_vec();
+            values.sort_unstable();
+            assert_eq!(values, (0..16).collect::<Vec<u8>>());
+        }
+        assert_eq!(coded_block_pattern(0, true, false).unwrap(), 47);
+        assert_eq!(coded_block_pattern(0, false, false).unwrap(), 0);
+        assert!(coded_block_pattern(48, false, false).is_err());
+    }
+
+    #[test]
+    fn test_residual_block() {
+        // Example of Section 9.2: coefficients 0 3 -1 0 0 -1 1 0 1 0... with
+        // nC = 0: coeff_token 0000100 (TotalCoeff 5, TrailingOnes 3),
+        // signs 011, levels 1 and 0010 (+1, +3), total_zeros 111 (3),
+        // run_before 10 (1), 1 (0), 1 (0), 01 (1)
+        let data = [0b0000_1000, 0b1110_0101, 0b1110_1101, 0b1000_0000];
+        let mut reader = RbspReader::new(&data, 0);
+        assert_eq!(residual_block(&mut reader, 0, 16).unwrap(), 5);
+        assert!(!reader.more_rbsp_data());
+    }
+
+    #[test]
+    fn test_empty_chroma_dc_block() {
+        let data = [0b0110_0000];
+        let mut reader = RbspReader::new(&data, 0);
+        assert_eq!(residual_block(&mut reader, -1, 4).unwrap(), 0);
+        assert!(!reader.more_rbsp_data());
+    }
+}
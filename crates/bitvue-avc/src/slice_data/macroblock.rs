@@ -0,0 +1,1231 @@
+//! Slice data and macroblock layer parsing (ITU-T H.264 Sections 7.3.4,
+//! 7.3.5 and 7.4.5).
+
+use super::cabac::{CabacReader, CAT_LUMA_8X8};
+use super::mv_prediction::{self, MvNeighbour, PartShape};
+use super::{cavlc, RbspReader};
+use crate::error::{AvcError, Result};
+use crate::overlay_extraction::{Macroblock, MbPartition, MbType, MotionVector};
+use crate::pps::Pps;
+use crate::slice::{SliceHeader, SliceType};
+use crate::sps::{ChromaFormat, Sps};
+
+/// Motion vector in quarter luma samples.
+pub(super) type Mv = [i16; 2];
+
+/// Largest picture size in macroblocks (MaxFS of level 6.2).
+const MAX_PIC_SIZE_IN_MBS: usize = 139_264;
+
+/// Reference list usage of an inter partition.
+const PRED_L0: u8 = 1;
+const PRED_L1: u8 = 2;
+const PRED_BI: u8 = PRED_L0 | PRED_L1;
+
+/// List usage of the two partitions of B_16x8 and B_8x16 macroblocks, for
+/// mb_type 4 to 21 in pairs (Table 7-14).
+const B_PART_PRED: [[u8; 2]; 9] = [
+    [PRED_L0, PRED_L0],
+    [PRED_L1, PRED_L1],
+    [PRED_L0, PRED_L1],
+    [PRED_L1, PRED_L0],
+    [PRED_L0, PRED_BI],
+    [PRED_L1, PRED_BI],
+    [PRED_BI, PRED_L0],
+    [PRED_BI, PRED_L1],
+    [PRED_BI, PRED_BI],
+];
+
+/// List usage and sub-partition width and height in 4x4 blocks of P
+/// sub_mb_type values (Table 7-17).
+const P_SUB_MB: [(u8, usize, usize); 4] = [
+    (PRED_L0, 2, 2),
+    (PRED_L0, 2, 1),
+    (PRED_L0, 1, 2),
+    (PRED_L0, 1, 1),
+];
+
+/// List usage and sub-partition width and height in 4x4 blocks of B
+/// sub_mb_type values (Table 7-18); usage 0 marks B_Direct_8x8.
+const B_SUB_MB: [(u8, usize, usize); 13] = [
+    (0, 2, 2),
+    (PRED_L0, 2, 2),
+    (PRED_L1, 2, 2),
+    (PRED_BI, 2, 2),
+    (PRED_L0, 2, 1),
+    (PRED_L0, 1, 2),
+    (PRED_L1, 2, 1),
+    (PRED_L1, 1, 2),
+    (PRED_BI, 2, 1),
+    (PRED_BI, 1, 2),
+    (PRED_L0, 1, 1),
+    (PRED_L1, 1, 1),
+    (PRED_BI, 1, 1),
+];
+
+/// Raster index of each luma 4x4 block in decoding order (luma4x4BlkIdx).
+const LUMA_4X4_RASTER: [usize; 16] = [0, 1, 4, 5, 2, 3, 6, 7, 8, 9, 12, 13, 10, 11, 14, 15];
+
+/// Index of the 8x8 block containing the 4x4 block at raster index `idx`.
+fn block_8x8(idx: usize) -> usize {
+    (idx / 8) * 2 + (idx % 4) / 2
+}
+
+/// Bit mask of the 4x4 blocks covered by a rectangle in 4x4 units.
+fn block_mask(x4: usize, y4: usize, w4: usize, h4: usize) -> u16 {
+    let mut mask = 0;
+    for y in y4..y4 + h4 {
+        for x in x4..x4 + w4 {
+            mask |= 1 << (y * 4 + x);
+        }
+    }
+    mask
+}
+
+/// Macroblock partitioning of inter macroblocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    P16x16,
+    P16x8,
+    P8x16,
+}
+
+impl Shape {
+    /// Partitions as (x, y, width, height) in 4x4 blocks.
+    fn partitions(self) -> &'static [(usize, usize, usize, usize)] {
+        match self {
+            Shape::P16x16 => &[(0, 0, 4, 4)],
+            Shape::P16x8 => &[(0, 0, 4, 2), (0, 2, 4, 2)],
+            Shape::P8x16 => &[(0, 0, 2, 4), (2, 0, 2, 4)],
+        }
+    }
+
+    fn prediction_shape(self, part: usize) -> PartShape {
+        match (self, part) {
+            (Shape::P16x8, 0) => PartShape::Upper16x8,
+            (Shape::P16x8, _) => PartShape::Lower16x8,
+            (Shape::P8x16, 0) => PartShape::Left8x16,
+            (Shape::P8x16, _) => PartShape::Right8x16,
+            _ => PartShape::Median,
+        }
+    }
+}
+
+/// Decoded mb_type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MbKind {
+    /// I_NxN, or SI when `si` is set.
+    IntraNxN { si: bool },
+    /// I_16x16 with its coded_block_pattern.
+    Intra16x16 { cbp: u8 },
+    /// I_PCM.
+    Pcm,
+    /// P or B macroblock with one or two partitions.
+    Inter { shape: Shape, pred: [u8; 2] },
+    /// P_8x8, P_8x8ref0 or B_8x8.
+    Inter8x8 { ref0: bool },
+    /// B_Direct_16x16.
+    Direct16x16,
+}
+
+/// Location of a 4x4 block next to or inside the current macroblock.
+#[derive(Debug, Clone, Copy)]
+enum Loc {
+    /// Block of the current macroblock (raster index).
+    Current(usize),
+    /// Block of a neighbouring macroblock (address, raster index).
+    Neighbour(usize, usize),
+    /// Outside the picture or slice.
+    Unavailable,
+}
+
+/// Per-macroblock state used to derive the contexts of later macroblocks.
+#[derive(Debug, Clone, Default)]
+struct MbState {
+    /// Decoded as part of the current slice.
+    available: bool,
+    skip: bool,
+    intra: bool,
+    pcm: bool,
+    intra_16x16: bool,
+    /// I_NxN or SI.
+    intra_nxn: bool,
+    si: bool,
+    /// B_Skip or B_Direct_16x16.
+    direct_16x16: bool,
+    direct_8x8: [bool; 4],
+    transform_8x8: bool,
+    cbp: u8,
+    chroma_pred_mode: u8,
+    /// TotalCoeff (CAVLC) or coded_block_flag (CABAC) of each luma 4x4
+    /// block in raster order.
+    luma_coded: [u8; 16],
+    /// Same for the Cb and Cr AC blocks.
+    chroma_coded: [[u8; 4]; 2],
+    /// coded_block_flag of the Intra16x16 DC, Cb DC and Cr DC blocks.
+    dc_coded: [bool; 3],
+    /// Nonzero coefficients over all residual blocks.
+    coeff_count: u32,
+    ref_idx: [[i8; 4]; 2],
+    mv: [[Mv; 16]; 2],
+    /// Absolute mvd components, saturated.
+    mvd: [[[u8; 2]; 16]; 2],
+}
+
+impl MbState {
+    fn new() -> Self {
+        Self {
+            available: true,
+            ref_idx: [[-1; 4]; 2],
+            ..Default::default()
+        }
+    }
+}
+
+/// Parser of the `slice_data()` of one slice.
+pub(super) struct SliceParser<'a> {
+    sps: &'a Sps,
+    pps: &'a Pps,
+    slice_type: SliceType,
+    direct_spatial: bool,
+    first_mb: usize,
+    width_mbs: usize,
+    pic_size: usize,
+    monochrome: bool,
+    pcm_bits: usize,
+    qp_bd_offset: i32,
+    qp: i32,
+    prev_qp_delta_nonzero: bool,
+    num_ref_idx_active: [u32; 2],
+    reader: RbspReader<'a>,
+    cabac: Option<CabacReader<'a>>,
+    mbs: Vec<MbState>,
+    cur_addr: usize,
+    cur: MbState,
+}
+
+impl<'a> SliceParser<'a> {
+    /// Prepare to parse the slice data of `data` (the slice RBSP after the
+    /// NAL unit header) starting at bit `data_offset`.
+    pub(super) fn new(
+        sps: &'a Sps,
+        pps: &'a Pps,
+        header: &SliceHeader,
+        data: &'a [u8],
+        data_offset: usize,
+    ) -> Result<Self> {
+        let width_mbs = sps.pic_width_in_mbs_minus1 as usize + 1;
+        let frame_height_mbs = (2 - sps.frame_mbs_only_flag as usize)
+            * (sps.pic_height_in_map_units_minus1 as usize + 1);
+        let height_mbs = frame_height_mbs / (1 + header.field_pic_flag as usize);
+        let pic_size = width_mbs.saturating_mul(height_mbs);
+        if pic_size > MAX_PIC_SIZE_IN_MBS {
+            return Err(AvcError::Unsupported(format!(
+                "picture of {} macroblocks",
+                pic_size
+            )));
+        }
+        if header.first_mb_in_slice as usize >= pic_size {
+            return Err(AvcError::InvalidSliceHeader(format!(
+                "first_mb_in_slice {} outside picture of {} macroblocks",
+                header.first_mb_in_slice, pic_size
+            )));
+        }
+
+        let monochrome = sps.chroma_format_idc == ChromaFormat::Monochrome;
+        let bit_depth_luma = sps.bit_depth_luma_minus8 as usize + 8;
+        let bit_depth_chroma = sps.bit_depth_chroma_minus8 as usize + 8;
+        let pcm_bits = 256 * bit_depth_luma
+            + if monochrome {
+                0
+            } else {
+                128 * bit_depth_chroma
+            };
+        let qp_bd_offset = 6 * sps.bit_depth_luma_minus8 as i32;
+        let qp = header.qp(pps);
+        if !(-qp_bd_offset..=51).contains(&qp) {
+            return Err(AvcError::InvalidSliceHeader(format!(
+                "slice QP {} out of range",
+                qp
+            )));
+        }
+
+        let mut reader = RbspReader::new(data, data_offset);
+        let cabac = if pps.entropy_coding_mode_flag {
+            // cabac_alignment_one_bit
+            reader.byte_align();
+            Some(CabacReader::new(
+                data,
+                reader.position(),
+                header.slice_type,
+                qp,
+                header.field_pic_flag,
+            )?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            sps,
+            pps,
+            slice_type: header.slice_type,
+            direct_spatial: header.direct_spatial_mv_pred_flag,
+            first_mb: header.first_mb_in_slice as usize,
+            width_mbs,
+            pic_size,
+            monochrome,
+            pcm_bits,
+            qp_bd_offset,
+            qp,
+            prev_qp_delta_nonzero: false,
+            num_ref_idx_active: [
+                header.num_ref_idx_l0_active_minus1 + 1,
+                header.num_ref_idx_l1_active_minus1 + 1,
+            ],
+            reader,
+            cabac,
+            mbs: vec![MbState::default(); pic_size],
+            cur_addr: 0,
+            cur: MbState::default(),
+        })
+    }
+
+    /// Parse all macroblocks of the slice.
+    pub(super) fn parse(mut self) -> Result<Vec<Macroblock>> {
+        let mut macroblocks = Vec::new();
+        let mut addr = self.first_mb;
+        let inter_slice = !self.slice_type.is_intra();
+
+        loop {
+            let mut skipped = false;
+            if inter_slice {
+                if self.cabac.is_some() {
+                    let inc = self.mb_cond_sum(|mb| !mb.skip, addr);
+                    let b_slice = self.slice_type.is_b();
+                    if let Some(cabac) = self.cabac.as_mut() {
+                        skipped = cabac.mb_skip_flag(b_slice, inc);
+                    }
+                    if skipped {
+                        macroblocks.push(self.skip_macroblock(addr));
+                    }
+                } else {
+                    let run = self.reader.read_ue()? as usize;
+                    if run > self.pic_size - addr {
+                        return Err(AvcError::BitstreamError(format!(
+                            "mb_skip_run {} overruns the picture",
+                            run
+                        )));
+                    }
+                    for _ in 0..run {
+                        macroblocks.push(self.skip_macroblock(addr));
+                        addr += 1;
+                    }
+                    if run > 0 && !self.reader.more_rbsp_data() {
+                        break;
+                    }
+                    if addr >= self.pic_size {
+                        return Err(AvcError::BitstreamError(
+                            "slice data continues past the last macroblock".to_string(),
+                        ));
+                    }
+                }
+            }
+            if !skipped {
+                macroblocks.push(self.macroblock_layer(addr)?);
+            }
+
+            let more = match self.cabac.as_mut() {
+                Some(cabac) => {
+                    if cabac.overrun() {
+                        return Err(AvcError::BitstreamError(
+                            "CABAC decoding overruns the slice data".to_string(),
+                        ));
+                    }
+                    !cabac.end_of_slice_flag()
+                }
+                None => self.reader.more_rbsp_data(),
+            };
+            if !more {
+                break;
+            }
+            addr += 1;
+            if addr >= self.pic_size {
+                return Err(AvcError::BitstreamError(
+                    "slice data continues past the last macroblock".to_string(),
+                ));
+            }
+        }
+
+        if let Some(cabac) = &self.cabac {
+            // The encoder flush ends with the rbsp_stop_one_bit; encoders may
+            // pad the flush, so the engine can stop up to a byte before it
+            let end = self.reader.end();
+            if cabac.position() > end + 1 || end + 1 - cabac.position() > 8 {
+                return Err(AvcError::BitstreamError(
+                    "CABAC slice data does not end at the RBSP trailing bits".to_string(),
+                ));
+            }
+        }
+        Ok(macroblocks)
+    }
+
+    fn begin(&mut self, addr: usize) {
+        self.cur_addr = addr;
+        self.cur = MbState::new();
+    }
+
+    fn finish(
+        &mut self,
+        mb_type: MbType,
+        partitions: Vec<MbPartition>,
+        mb_qp_delta: i32,
+    ) -> Macroblock {
+        let first = partitions.first();
+        let macroblock = Macroblock {
+            mb_addr: self.cur_addr as u32,
+            x: (self.cur_addr % self.width_mbs) as u32 * 16,
+            y: (self.cur_addr / self.width_mbs) as u32 * 16,
+            mb_type,
+            skip: self.cur.skip,
+            qp: self.qp as i16,
+            mv_l0: first.and_then(|p| p.mv_l0),
+            mv_l1: first.and_then(|p| p.mv_l1),
+            ref_idx_l0: first.and_then(|p| p.ref_idx_l0),
+            ref_idx_l1: first.and_then(|p| p.ref_idx_l1),
+            transform_size_8x8_flag: self.cur.transform_8x8,
+            coded_block_pattern: self.cur.cbp,
+            coeff_count: self.cur.coeff_count,
+            mb_qp_delta,
+            partitions,
+        };
+        self.mbs[self.cur_addr] = std::mem::take(&mut self.cur);
+        macroblock
+    }
+
+    /// Locate the 4x4 block at (x, y) relative to the current macroblock in
+    /// units of blocks of a `size` x `size` grid (4 for luma, 2 for 4:2:0
+    /// chroma), following Section 6.4.12.
+    fn locate(&self, x: i32, y: i32, size: i32) -> Loc {
+        if y >= size || (y >= 0 && x >= size) {
+            return Loc::Unavailable;
+        }
+        if (0..size).contains(&x) && y >= 0 {
+            return Loc::Current((y * size + x) as usize);
+        }
+        let width = self.width_mbs as i64;
+        let col = self.cur_addr as i64 % width;
+        let dx = if x < 0 {
+            -1
+        } else if x >= size {
+            1
+        } else {
+            0
+        };
+        let dy = if y < 0 { -1 } else { 0 };
+        if col + dx < 0 || col + dx >= width {
+            return Loc::Unavailable;
+        }
+        let addr = self.cur_addr as i64 + dy * width + dx;
+        if addr < 0 || !self.mbs[addr as usize].available {
+            return Loc::Unavailable;
+        }
+        let bx = (x + size) % size;
+        let by = (y + size) % size;
+        Loc::Neighbour(addr as usize, (by * size + bx) as usize)
+    }
+
+    /// Left and upper neighbouring macroblocks of `addr` (6.4.9).
+    fn mb_neighbours(&self, addr: usize) -> [Option<&MbState>; 2] {
+        let available = |n: usize| Some(&self.mbs[n]).filter(|mb| mb.available);
+        let a = if !addr.is_multiple_of(self.width_mbs) {
+            available(addr - 1)
+        } else {
+            None
+        };
+        let b = addr.checked_sub(self.width_mbs).and_then(available);
+        [a, b]
+    }
+
+    /// condTermFlagA + condTermFlagB for macroblock-level contexts.
+    fn mb_cond_sum(&self, cond: impl Fn(&MbState) -> bool, addr: usize) -> usize {
+        self.mb_neighbours(addr)
+            .iter()
+            .filter(|mb| mb.is_some_and(&cond))
+            .count()
+    }
+
+    fn skip_macroblock(&mut self, addr: usize) -> Macroblock {
+        self.begin(addr);
+        self.cur.skip = true;
+        let mb_type = if self.slice_type.is_b() {
+            self.cur.direct_16x16 = true;
+            let (refs, mvs) = self.direct_motion();
+            for list in 0..2 {
+                self.set_motion(list, (0, 0, 4, 4), refs[list], mvs[list]);
+            }
+            MbType::BSkip
+        } else {
+            let [a, b, c] = self.motion_neighbours(0, (0, 0, 4), 0);
+            let mv = mv_prediction::p_skip(a, b, c);
+            self.set_motion(0, (0, 0, 4, 4), 0, mv);
+            MbType::PSkip
+        };
+        self.prev_qp_delta_nonzero = false;
+        let partitions = vec![self.inter_partition((0, 0, 4, 4))];
+        self.finish(mb_type, partitions, 0)
+    }
+
+    fn macroblock_layer(&mut self, addr: usize) -> Result<Macroblock> {
+        self.begin(addr);
+        let kind = self.read_mb_type()?;
+        let mut no_sub_mb_part_size_less_than_8x8 = true;
+
+        let (mb_type, partitions) = match kind {
+            MbKind::Pcm => return self.pcm_macroblock(),
+            MbKind::IntraNxN { si } => {
+                self.cur.intra = true;
+                self.cur.intra_nxn = true;
+                self.cur.si = si;
+                if !si && self.pps.transform_8x8_mode_flag {
+                    self.cur.transform_8x8 = self.read_transform_size_8x8_flag()?;
+                }
+                let transform_8x8 = self.cur.transform_8x8;
+                self.read_intra_pred_modes(if transform_8x8 { 4 } else { 16 })?;
+                self.read_intra_chroma_pred_mode()?;
+                if transform_8x8 {
+                    let partitions = (0..4)
+                        .map(|i| self.intra_partition((i % 2) * 2, (i / 2) * 2, 2))
+                        .collect();
+                    (MbType::I8x8, partitions)
+                } else {
+                    let partitions = LUMA_4X4_RASTER
+                        .iter()
+                        .map(|&i| self.intra_partition(i % 4, i / 4, 1))
+                        .collect();
+                    (MbType::I4x4, partitions)
+                }
+            }
+            MbKind::Intra16x16 { cbp } => {
+                self.cur.intra = true;
+                self.cur.intra_16x16 = true;
+                self.cur.cbp = cbp;
+                self.read_intra_chroma_pred_mode()?;
+                (MbType::I16x16, vec![self.intra_partition(0, 0, 4)])
+            }
+            MbKind::Inter { shape, pred } => {
+                self.read_mb_pred_inter(shape, pred)?;
+                let partitions = shape
+                    .partitions()
+                    .iter()
+                    .map(|&(x, y, w, h)| self.inter_partition((x, y, w, h)))
+                    .collect();
+                let b_slice = self.slice_type.is_b();
+                let mb_type = match shape {
+                    Shape::P16x16 if b_slice => MbType::B16x16,
+                    Shape::P16x16 => MbType::PLuma,
+                    Shape::P16x8 if b_slice => MbType::B16x8,
+                    Shape::P16x8 => MbType::P16x8,
+                    Shape::P8x16 if b_slice => MbType::B8x16,
+                    Shape::P8x16 => MbType::P8x16,
+                };
+                (mb_type, partitions)
+            }
+            MbKind::Inter8x8 { ref0 } => {
+                let (partitions, no_small) = self.read_sub_mb_pred(ref0)?;
+                no_sub_mb_part_size_less_than_8x8 = no_small;
+                let mb_type = if self.slice_type.is_b() {
+                    MbType::B8x8
+                } else {
+                    MbType::P8x8
+                };
+                (mb_type, partitions)
+            }
+            MbKind::Direct16x16 => {
+                self.cur.direct_16x16 = true;
+                let (refs, mvs) = self.direct_motion();
+                for list in 0..2 {
+                    self.set_motion(list, (0, 0, 4, 4), refs[list], mvs[list]);
+                }
+                (MbType::BDirect, vec![self.inter_partition((0, 0, 4, 4))])
+            }
+        };
+
+        if !self.cur.intra_16x16 {
+            self.cur.cbp = self.read_coded_block_pattern()?;
+            if self.cur.cbp & 0xF != 0
+                && self.pps.transform_8x8_mode_flag
+                && kind != (MbKind::IntraNxN { si: false })
+                && no_sub_mb_part_size_less_than_8x8
+                && (kind != MbKind::Direct16x16 || self.sps.direct_8x8_inference_flag)
+            {
+                self.cur.transform_8x8 = self.read_transform_size_8x8_flag()?;
+            }
+        }
+
+        let mut mb_qp_delta = 0;
+        if self.cur.cbp != 0 || self.cur.intra_16x16 {
+            mb_qp_delta = self.read_mb_qp_delta()?;
+            let range = 52 + self.qp_bd_offset;
+            self.qp = (self.qp + mb_qp_delta + range + self.qp_bd_offset).rem_euclid(range)
+                - self.qp_bd_offset;
+            self.read_residual()?;
+        }
+        self.prev_qp_delta_nonzero = mb_qp_delta != 0;
+
+        Ok(self.finish(mb_type, partitions, mb_qp_delta))
+    }
+
+    fn pcm_macroblock(&mut self) -> Result<Macroblock> {
+        self.cur.intra = true;
+        self.cur.pcm = true;
+        self.cur.cbp = 0x2F;
+        self.cur.luma_coded = [16; 16];
+        self.cur.chroma_coded = [[16; 4]; 2];
+        self.cur.dc_coded = [true; 3];
+
+        let start = match &self.cabac {
+            Some(cabac) => cabac.position(),
+            None => self.reader.position(),
+        };
+        // pcm_alignment_zero_bit
+        let start = start.div_ceil(8) * 8;
+        self.reader.set_position(start);
+        self.reader.skip_bits(self.pcm_bits)?;
+        if let Some(cabac) = self.cabac.as_mut() {
+            cabac.restart(self.reader.position())?;
+        }
+
+        self.prev_qp_delta_nonzero = false;
+        let partitions = vec![self.intra_partition(0, 0, 4)];
+        Ok(self.finish(MbType::IPCM, partitions, 0))
+    }
+
+    fn read_mb_type(&mut self) -> Result<MbKind> {
+        let addr = self.cur_addr;
+        let raw = match self.slice_type {
+            SliceType::I | SliceType::Si if self.cabac.is_some() => {
+                let inc = self.mb_cond_sum(|mb| !mb.intra_nxn, addr);
+                let si_inc = self.mb_cond_sum(|mb| !mb.si, addr);
+                let si_slice = self.slice_type == SliceType::Si;
+                let cabac = self.cabac.as_mut().expect("CABAC decoder");
+                if si_slice {
+                    cabac.mb_type_si(si_inc, inc)
+                } else {
+                    cabac.mb_type_i(inc)
+                }
+            }
+            SliceType::B if self.cabac.is_some() => {
+                let inc = self.mb_cond_sum(|mb| !mb.direct_16x16, addr);
+                self.cabac.as_mut().expect("CABAC decoder").mb_type_b(inc)
+            }
+            _ => match self.cabac.as_mut() {
+                Some(cabac) => cabac.mb_type_p(),
+                None => self.reader.read_ue()?,
+            },
+        };
+
+        let intra = |value: u32| -> Result<MbKind> {
+            match value {
+                0 => Ok(MbKind::IntraNxN { si: false }),
+                1..=24 => {
+                    let luma = if value >= 13 { 0x0F } else { 0 };
+                    let chroma = (((value - 1) / 4) % 3) as u8;
+                    Ok(MbKind::Intra16x16 {
+                        cbp: luma | chroma << 4,
+                    })
+                }
+                25 => Ok(MbKind::Pcm),
+                _ => Err(AvcError::BitstreamError(format!("invalid mb_type {}", raw))),
+            }
+        };
+
+        match self.slice_type {
+            SliceType::I => intra(raw),
+            SliceType::Si if raw == 0 => Ok(MbKind::IntraNxN { si: true }),
+            SliceType::Si => intra(raw - 1),
+            SliceType::P | SliceType::Sp => match raw {
+                0 => Ok(MbKind::Inter {
+                    shape: Shape::P16x16,
+                    pred: [PRED_L0, 0],
+                }),
+                1 => Ok(MbKind::Inter {
+                    shape: Shape::P16x8,
+                    pred: [PRED_L0; 2],
+                }),
+                2 => Ok(MbKind::Inter {
+                    shape: Shape::P8x16,
+                    pred: [PRED_L0; 2],
+                }),
+                3 => Ok(MbKind::Inter8x8 { ref0: false }),
+                4 => Ok(MbKind::Inter8x8 { ref0: true }),
+                _ => intra(raw - 5),
+            },
+            SliceType::B => match raw {
+                0 => Ok(MbKind::Direct16x16),
+                1..=3 => Ok(MbKind::Inter {
+                    shape: Shape::P16x16,
+                    pred: [raw as u8, 0],
+                }),
+                4..=21 => Ok(MbKind::Inter {
+                    shape: if raw % 2 == 0 {
+                        Shape::P16x8
+                    } else {
+                        Shape::P8x16
+                    },
+                    pred: B_PART_PRED[(raw as usize - 4) / 2],
+                }),
+                22 => Ok(MbKind::Inter8x8 { ref0: false }),
+                _ => intra(raw - 23),
+            },
+        }
+    }
+
+    fn read_transform_size_8x8_flag(&mut self) -> Result<bool> {
+        let inc = self.mb_cond_sum(|mb| mb.transform_8x8, self.cur_addr);
+        match self.cabac.as_mut() {
+            Some(cabac) => Ok(cabac.transform_size_8x8_flag(inc)),
+            None => Ok(self.reader.read_bit()? == 1),
+        }
+    }
+
+    fn read_intra_pred_modes(&mut self, count: usize) -> Result<()> {
+        for _ in 0..count {
+            match self.cabac.as_mut() {
+                Some(cabac) => {
+                    if !cabac.prev_intra_pred_mode_flag() {
+                        cabac.rem_intra_pred_mode();
+                    }
+                }
+                None => {
+                    if self.reader.read_bit()? == 0 {
+                        self.reader.read_bits(3)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read_intra_chroma_pred_mode(&mut self) -> Result<()> {
+        if self.monochrome {
+            return Ok(());
+        }
+        let inc = self.mb_cond_sum(
+            |mb| mb.intra && !mb.pcm && mb.chroma_pred_mode != 0,
+            self.cur_addr,
+        );
+        let mode = match self.cabac.as_mut() {
+            Some(cabac) => cabac.intra_chroma_pred_mode(inc),
+            None => {
+                let mode = self.reader.read_ue()?;
+                if mode > 3 {
+                    return Err(AvcError::BitstreamError(format!(
+                        "invalid intra_chroma_pred_mode {}",
+                        mode
+                    )));
+                }
+                mode as u8
+            }
+        };
+        self.cur.chroma_pred_mode = mode;
+        Ok(())
+    }
+
+    /// Parse `mb_pred()` of an inter macroblock with one or two partitions.
+    fn read_mb_pred_inter(&mut self, shape: Shape, pred: [u8; 2]) -> Result<()> {
+        let partitions = shape.partitions();
+        for (&(x, y, w, h), &usage) in partitions.iter().zip(&pred) {
+            for list in 0..2 {
+                let ref_idx = if usage & (1 << list) != 0 { 0 } else { -1 };
+                self.set_motion(list, (x, y, w, h), ref_idx, [0, 0]);
+            }
+        }
+
+        for list in 0..2 {
+            for (&(x, y, w, h), &usage) in partitions.iter().zip(&pred) {
+                if usage & (1 << list) != 0 && self.num_ref_idx_active[list] > 1 {
+                    let ref_idx = self.read_ref_idx(list, x, y)?;
+                    self.set_ref_idx(list, (x, y, w, h), ref_idx);
+                }
+            }
+        }
+
+        for list in 0..2 {
+            let mut decoded = 0;
+            for (part, (&(x, y, w, h), &usage)) in partitions.iter().zip(&pred).enumerate() {
+                if usage & (1 << list) != 0 {
+                    self.read_motion_vector(
+                        list,
+                        (x, y, w, h),
+                        shape.prediction_shape(part),
+                        decoded,
+                    )?;
+                }
+                decoded |= block_mask(x, y, w, h);
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse `sub_mb_pred()`; returns the partitions and
+    /// noSubMbPartSizeLessThan8x8Flag.
+    fn read_sub_mb_pred(&mut self, ref0: bool) -> Result<(Vec<MbPartition>, bool)> {
+        let b_slice = self.slice_type.is_b();
+        let table: &[(u8, usize, usize)] = if b_slice { &B_SUB_MB } else { &P_SUB_MB };
+
+        let mut sub_mb_types = [(0u8, 0usize, 0usize); 4];
+        for sub_mb_type in &mut sub_mb_types {
+            let raw = match self.cabac.as_mut() {
+                Some(cabac) if b_slice => cabac.sub_mb_type_b(),
+                Some(cabac) => cabac.sub_mb_type_p(),
+                None => self.reader.read_ue()?,
+            };
+            *sub_mb_type = *table
+                .get(raw as usize)
+                .ok_or_else(|| AvcError::BitstreamError(format!("invalid sub_mb_type {}", raw)))?;
+        }
+
+        let mut no_small_partitions = true;
+        let mut direct = None;
+        for (i, &(usage, w, h)) in sub_mb_types.iter().enumerate() {
+            let region = ((i % 2) * 2, (i / 2) * 2, 2, 2);
+            if usage == 0 {
+                self.cur.direct_8x8[i] = true;
+                no_small_partitions &= self.sps.direct_8x8_inference_flag;
+                let (refs, mvs) = *direct.get_or_insert_with(|| self.direct_motion());
+                for list in 0..2 {
+                    self.set_motion(list, region, refs[list], mvs[list]);
+                }
+            } else {
+                no_small_partitions &= w * h == 4;
+                for list in 0..2 {
+                    let ref_idx = if usage & (1 << list) != 0 { 0 } else { -1 };
+                    self.set_motion(list, region, ref_idx, [0, 0]);
+                }
+            }
+        }
+
+        for list in 0..2 {
+            for (i, &(usage, _, _)) in sub_mb_types.iter().enumerate() {
+                if usage & (1 << list) != 0 && self.num_ref_idx_active[list] > 1 && !ref0 {
+                    let (x, y) = ((i % 2) * 2, (i / 2) * 2);
+                    let ref_idx = self.read_ref_idx(list, x, y)?;
+                    self.set_ref_idx(list, (x, y, 2, 2), ref_idx);
+                }
+            }
+        }
+
+        for list in 0..2 {
+            let mut decoded = 0;
+            for (i, &(usage, w, h)) in sub_mb_types.iter().enumerate() {
+                let (x, y) = ((i % 2) * 2, (i / 2) * 2);
+                if usage & (1 << list) != 0 {
+                    for sy in (y..y + 2).step_by(h) {
+                        for sx in (x..x + 2).step_by(w) {
+                            self.read_motion_vector(
+                                list,
+                                (sx, sy, w, h),
+                                PartShape::Median,
+                                decoded,
+                            )?;
+                            decoded |= block_mask(sx, sy, w, h);
+                        }
+                    }
+                }
+                decoded |= block_mask(x, y, 2, 2);
+            }
+        }
+
+        let mut partitions = Vec::new();
+        for (i, &(usage, w, h)) in sub_mb_types.iter().enumerate() {
+            let (x, y) = ((i % 2) * 2, (i / 2) * 2);
+            let (w, h) = if usage == 0 { (2, 2) } else { (w, h) };
+            for sy in (y..y + 2).step_by(h) {
+                for sx in (x..x + 2).step_by(w) {
+                    partitions.push(self.inter_partition((sx, sy, w, h)));
+                }
+            }
+        }
+        Ok((partitions, no_small_partitions))
+    }
+
+    fn read_ref_idx(&mut self, list: usize, x: usize, y: usize) -> Result<i8> {
+        let cond = |loc: Loc| {
+            let (mb, idx) = match loc {
+                Loc::Current(idx) => (&self.cur, idx),
+                Loc::Neighbour(addr, idx) => (&self.mbs[addr], idx),
+                Loc::Unavailable => return 0,
+            };
+            let b8 = block_8x8(idx);
+            (!mb.skip
+                && !mb.intra
+                && !mb.direct_16x16
+                && !mb.direct_8x8[b8]
+                && mb.ref_idx[list][b8] > 0) as usize
+        };
+        let (x, y) = (x as i32, y as i32);
+        let inc = cond(self.locate(x - 1, y, 4)) + 2 * cond(self.locate(x, y - 1, 4));
+
+        let ref_idx = match self.cabac.as_mut() {
+            Some(cabac) => cabac.ref_idx(inc)?,
+            None => self.reader.read_te(self.num_ref_idx_active[list] - 1)?,
+        };
+        if ref_idx >= self.num_ref_idx_active[list] {
+            return Err(AvcError::BitstreamError(format!(
+                "ref_idx_l{} {} exceeds {} active references",
+                list, ref_idx, self.num_ref_idx_active[list]
+            )));
+        }
+        Ok(ref_idx as i8)
+    }
+
+    /// Parse the mvd of a partition, derive its motion vector and store both.
+    fn read_motion_vector(
+        &mut self,
+        list: usize,
+        region: (usize, usize, usize, usize),
+        shape: PartShape,
+        decoded: u16,
+    ) -> Result<()> {
+        let (x, y, w, _) = region;
+        let mvd = match self.cabac.is_some() {
+            true => {
+                let abs_at = |loc: Loc, comp: usize| -> u32 {
+                    match loc {
+                        Loc::Current(idx) => self.cur.mvd[list][idx][comp] as u32,
+                        Loc::Neighbour(addr, idx) => self.mbs[addr].mvd[list][idx][comp] as u32,
+                        Loc::Unavailable => 0,
+                    }
+                };
+                let (xi, yi) = (x as i32, y as i32);
+                let a = self.locate(xi - 1, yi, 4);
+                let b = self.locate(xi, yi - 1, 4);
+                let sums = [abs_at(a, 0) + abs_at(b, 0), abs_at(a, 1) + abs_at(b, 1)];
+                let cabac = self.cabac.as_mut().expect("CABAC decoder");
+                [cabac.mvd(0, sums[0])?, cabac.mvd(1, sums[1])?]
+            }
+            false => [self.reader.read_se()?, self.reader.read_se()?],
+        };
+
+        let ref_idx = self.cur.ref_idx[list][block_8x8(y * 4 + x)];
+        let [a, b, c] = self.motion_neighbours(list, (x, y, w), decoded);
+        let mvp = mv_prediction::predict(a, b, c, ref_idx, shape);
+        let mv = [
+            mvp[0].wrapping_add(mvd[0] as i16),
+            mvp[1].wrapping_add(mvd[1] as i16),
+        ];
+        self.set_motion(list, region, ref_idx, mv);
+
+        let abs_mvd = mvd.map(|v| v.unsigned_abs().min(u8::MAX as u32) as u8);
+        let (_, _, w, h) = region;
+        for by in y..y + h {
+            for bx in x..x + w {
+                self.cur.mvd[list][by * 4 + bx] = abs_mvd;
+            }
+        }
+        Ok(())
+    }
+
+    /// Motion data of the block at (x, y) in 4x4 units; blocks of the
+    /// current macroblock count only once set in `decoded`.
+    fn motion_at(&self, list: usize, x: i32, y: i32, decoded: u16) -> MvNeighbour {
+        let (mb, idx) = match self.locate(x, y, 4) {
+            Loc::Current(idx) if decoded & (1 << idx) != 0 => (&self.cur, idx),
+            Loc::Neighbour(addr, idx) => (&self.mbs[addr], idx),
+            _ => return MvNeighbour::UNAVAILABLE,
+        };
+        if mb.intra {
+            return MvNeighbour {
+                available: true,
+                ref_idx: -1,
+                mv: [0, 0],
+            };
+        }
+        let ref_idx = mb.ref_idx[list][block_8x8(idx)];
+        MvNeighbour {
+            available: true,
+            ref_idx,
+            mv: if ref_idx >= 0 {
+                mb.mv[list][idx]
+            } else {
+                [0, 0]
+            },
+        }
+    }
+
+    /// Neighbouring partitions A, B and C (or D) of a partition at (x, y)
+    /// with width `w`, in 4x4 units (8.4.1.3.2).
+    fn motion_neighbours(
+        &self,
+        list: usize,
+        (x, y, w): (usize, usize, usize),
+        decoded: u16,
+    ) -> [MvNeighbour; 3] {
+        let (x, y, w) = (x as i32, y as i32, w as i32);
+        let a = self.motion_at(list, x - 1, y, decoded);
+        let b = self.motion_at(list, x, y - 1, decoded);
+        let mut c = self.motion_at(list, x + w, y - 1, decoded);
+        if !c.available {
+            c = self.motion_at(list, x - 1, y - 1, decoded);
+        }
+        [a, b, c]
+    }
+
+    /// Reference indices and motion vectors of direct prediction.
+    ///
+    /// Temporal direct prediction needs the co-located picture, which is not
+    /// tracked; it is reported as reference index 0 with zero motion.
+    fn direct_motion(&self) -> ([i8; 2], [Mv; 2]) {
+        if self.direct_spatial {
+            mv_prediction::spatial_direct([
+                self.motion_neighbours(0, (0, 0, 4), 0),
+                self.motion_neighbours(1, (0, 0, 4), 0),
+            ])
+        } else {
+            ([0, 0], [[0, 0]; 2])
+        }
+    }
+
+    fn set_ref_idx(
+        &mut self,
+        list: usize,
+        (x, y, w, h): (usize, usize, usize, usize),
+        ref_idx: i8,
+    ) {
+        for by in y..y + h {
+            for bx in x..x + w {
+                self.cur.ref_idx[list][block_8x8(by * 4 + bx)] = ref_idx;
+            }
+        }
+    }
+
+    fn set_motion(
+        &mut self,
+        list: usize,
+        region: (usize, usize, usize, usize),
+        ref_idx: i8,
+        mv: Mv,
+    ) {
+        self.set_ref_idx(list, region, ref_idx);
+        let (x, y, w, h) = region;
+        let mv = if ref_idx >= 0 { mv } else { [0, 0] };
+        for by in y..y + h {
+            for bx in x..x + w {
+                self.cur.mv[list][by * 4 + bx] = mv;
+            }
+        }
+    }
+
+    fn inter_partition(&self, (x, y, w, h): (usize, usize, usize, usize)) -> MbPartition {
+        let idx = y * 4 + x;
+        let motion = |list: usize| {
+            let ref_idx = self.cur.ref_idx[list][block_8x8(idx)];
+            (ref_idx >= 0).then(|| {
+                let mv = self.cur.mv[list][idx];
+                (MotionVector::new(mv[0] as i32, mv[1] as i32), ref_idx)
+            })
+        };
+        let (l0, l1) = (motion(0), motion(1));
+        MbPartition {
+            x: (self.cur_addr % self.width_mbs) as u32 * 16 + x as u32 * 4,
+            y: (self.cur_addr / self.width_mbs) as u32 * 16 + y as u32 * 4,
+            width: w as u32 * 4,
+            height: h as u32 * 4,
+            mv_l0: l0.map(|(mv, _)| mv),
+            mv_l1: l1.map(|(mv, _)| mv),
+            ref_idx_l0: l0.map(|(_, r)| r),
+            ref_idx_l1: l1.map(|(_, r)| r),
+        }
+    }
+
+    fn intra_partition(&self, x: usize, y: usize, size: usize) -> MbPartition {
+        MbPartition {
+            x: (self.cur_addr % self.width_mbs) as u32 * 16 + x as u32 * 4,
+            y: (self.cur_addr / self.width_mbs) as u32 * 16 + y as u32 * 4,
+            width: size as u32 * 4,
+            height: size as u32 * 4,
+            ..Default::default()
+        }
+    }
+
+    fn read_coded_block_pattern(&mut self) -> Result<u8> {
+        let intra = self.cur.intra;
+        match self.cabac.is_some() {
+            true => {
+                let [a, b] = self.mb_neighbours(self.cur_addr);
+                let luma = |mb: Option<&MbState>| match mb {
+                    None => 0x0F,
+                    Some(mb) if mb.pcm => 0x0F,
+                    Some(mb) if mb.skip => 0,
+                    Some(mb) => mb.cbp & 0x0F,
+                };
+                let chroma = |mb: Option<&MbState>| match mb {
+                    None => 0,
+                    Some(mb) if mb.pcm => 2,
+                    Some(mb) if mb.skip => 0,
+                    Some(mb) => mb.cbp >> 4,
+                };
+                let (luma_a, luma_b, chroma_a, chroma_b) = (luma(a), luma(b), chroma(a), chroma(b));
+                let monochrome = self.monochrome;
+                let cabac = self.cabac.as_mut().expect("CABAC decoder");
+                let mut cbp = cabac.cbp_luma(luma_a, luma_b);
+                if !monochrome {
+                    cbp |= cabac.cbp_chroma(chroma_a, chroma_b) << 4;
+                }
+                Ok(cbp)
+            }
+            false => {
+                let code_num = self.reader.read_ue()?;
+                cavlc::coded_block_pattern(code_num, intra, self.monochrome)
+            }
+        }
+    }
+
+    fn read_mb_qp_delta(&mut self) -> Result<i32> {
+        let delta = match self.cabac.as_mut() {
+            Some(cabac) => cabac.mb_qp_delta(self.prev_qp_delta_nonzero)?,
+            None => self.reader.read_se()?,
+        };
+        let limit = 26 + self.qp_bd_offset / 2;
+        if !(-limit..limit).contains(&delta) {
+            return Err(AvcError::BitstreamError(format!(
+                "mb_qp_delta {} out of range",
+                delta
+            )));
+        }
+        Ok(delta)
+    }
+
+    /// nC of a CAVLC block from the TotalCoeff of the blocks at `a` and `b`
+    /// (9.2.1).
+    fn total_coeff_nc(&self, a: Loc, b: Loc, coded: impl Fn(&MbState, usize) -> u8) -> i32 {
+        let n = |loc: Loc| match loc {
+            Loc::Current(idx) => Some(coded(&self.cur, idx) as i32),
+            Loc::Neighbour(addr, idx) => Some(coded(&self.mbs[addr], idx) as i32),
+            Loc::Unavailable => None,
+        };
+        match (n(a), n(b)) {
+            (Some(a), Some(b)) => (a + b + 1) >> 1,
+            (Some(n), None) | (None, Some(n)) => n,
+            (None, None) => 0,
+        }
+    }
+
+    /// coded_block_flag ctxIdxInc from the blocks at `a` and `b`
+    /// (9.3.3.1.1.9).
+    fn coded_block_flag_inc(
+        &self,
+        a: Loc,
+        b: Loc,
+        coded: impl Fn(&MbState, usize) -> bool,
+    ) -> usize {
+        let cond = |loc: Loc| match loc {
+            Loc::Current(idx) => coded(&self.cur, idx),
+            Loc::Neighbour(addr, idx) => coded(&self.mbs[addr], idx),
+            Loc::Unavailable => self.cur.intra,
+        } as usize;
+        cond(a) + 2 * cond(b)
+    }
+
+    /// Parse `residual()` for 4:2:0 and monochrome macroblocks.
+    fn read_residual(&mut self) -> Result<()> {
+        let cbp_luma = self.cur.cbp & 0x0F;
+        let cbp_chroma = self.cur.cbp >> 4;
+        let cabac = self.cabac.is_some();
+
+        if self.cur.intra_16x16 {
+            let (a, b) = (self.locate(-1, 0, 4), self.locate(0, -1, 4));
+            if cabac {
+                let inc = self.coded_block_flag_inc(a, b, |mb, _| mb.dc_coded[0]);
+                self.cur.dc_coded[0] = self.residual_block_cabac(0, Some(inc), 16)?;
+            } else {
+                let nc = self.total_coeff_nc(a, b, |mb, idx| mb.luma_coded[idx]);
+                self.residual_block_cavlc(nc, 16)?;
+            }
+        }
+
+        for b8 in 0..4 {
+            let coded = cbp_luma >> b8 & 1 != 0;
+            if cabac && coded && self.cur.transform_8x8 {
+                self.residual_block_cabac(CAT_LUMA_8X8, None, 64)?;
+            }
+            for b4 in 0..4 {
+                let idx = LUMA_4X4_RASTER[b8 * 4 + b4];
+                if !coded {
+                    continue;
+                }
+                if cabac && self.cur.transform_8x8 {
+                    self.cur.luma_coded[idx] = 1;
+                    continue;
+                }
+                let (x, y) = ((idx % 4) as i32, (idx / 4) as i32);
+                let (a, b) = (self.locate(x - 1, y, 4), self.locate(x, y - 1, 4));
+                let (cat, max_coeff) = if self.cur.intra_16x16 {
+                    (1, 15)
+                } else {
+                    (2, 16)
+                };
+                self.cur.luma_coded[idx] = if cabac {
+                    let inc = self.coded_block_flag_inc(a, b, |mb, idx| mb.luma_coded[idx] != 0);
+                    self.residual_block_cabac(cat, Some(inc), max_coeff)? as u8
+                } else {
+                    let nc = self.total_coeff_nc(a, b, |mb, idx| mb.luma_coded[idx]);
+                    self.residual_block_cavlc(nc, max_coeff)?
+                };
+            }
+        }
+
+        if self.monochrome {
+            return Ok(());
+        }
+        if cbp_chroma != 0 {
+            let (a, b) = (self.locate(-1, 0, 2), self.locate(0, -1, 2));
+            for comp in 0..2 {
+                if cabac {
+                    let inc = self.coded_block_flag_inc(a, b, |mb, _| mb.dc_coded[1 + comp]);
+                    self.cur.dc_coded[1 + comp] = self.residual_block_cabac(3, Some(inc), 4)?;
+                } else {
+                    self.residual_block_cavlc(-1, 4)?;
+                }
+            }
+        }
+        if cbp_chroma == 2 {
+            for comp in 0..2 {
+                for idx in 0..4 {
+                    let (x, y) = ((idx % 2) as i32, (idx / 2) as i32);
+                    let (a, b) = (self.locate(x - 1, y, 2), self.locate(x, y - 1, 2));
+                    self.cur.chroma_coded[comp][idx] = if cabac {
+                        let inc = self
+                            .coded_block_flag_inc(a, b, |mb, idx| mb.chroma_coded[comp][idx] != 0);
+                        self.residual_block_cabac(4, Some(inc), 15)? as u8
+                    } else {
+                        let nc = self.total_coeff_nc(a, b, |mb, idx| mb.chroma_coded[comp][idx]);
+                        self.residual_block_cavlc(nc, 15)?
+                    };
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn residual_block_cabac(
+        &mut self,
+        cat: usize,
+        cbf_inc: Option<usize>,
+        max_coeff: usize,
+    ) -> Result<bool> {
+        let num_coeff = self
+            .cabac
+            .as_mut()
+            .expect("CABAC decoder")
+            .residual_block(cat, cbf_inc, max_coeff)?;
+        self.cur.coeff_count += num_coeff as u32;
+        Ok(num_coeff > 0)
+    }
+
+    /// Parse a CAVLC residual block and return its TotalCoeff.
+    fn residual_block_cavlc(&mut self, nc: i32, max_coeff: usize) -> Result<u8> {
+        let total_coeff = cavlc::residual_block(&mut self.reader, nc, max_coeff)?;
+        self.cur.coeff_count += total_coeff as u32;
+        Ok(total_coeff)
+    }
+}
@@ -0,0 +1,386 @@
+//! H.264/AVC slice data parsing.
+//!
+//! Parses `slice_data()` and `macroblock_layer()` (ITU-T H.264 Sections 7.3.4
+//! and 7.3.5) with both entropy coders, recovering the type, partitioning, QP
+//! and motion vectors of every macroblock of a slice. Residual data is parsed
+//! only to stay in sync with the bitstream; coefficients are discarded.
+//!
+//! Limitations:
+//!
+//! - MBAFF frames, slice data partitioning, slice groups (FMO) and 4:2:2 or
+//!   4:4:4 chroma are rejected with [`AvcError::Unsupported`].
+//! - CABAC slices with `cabac_init_idc` 1 or 2 are rejected as well; only the
+//!   I-slice and `cabac_init_idc` 0 context tables are included.
+//! - Direct-mode motion is derived without the co-located picture. Spatial
+//!   direct prediction assumes a moving co-located block (colZeroFlag 0), and
+//!   temporal direct prediction reports reference index 0 with zero motion.
+
+mod cabac;
+mod cavlc;
+mod macroblock;
+mod mv_prediction;
+
+use crate::error::{AvcError, Result};
+use crate::nal::{NalUnit, NalUnitType};
+use crate::overlay_extraction::Macroblock;
+use crate::pps::Pps;
+use crate::slice::{parse_slice_header_with_data_offset, SliceHeader};
+use crate::sps::{ChromaFormat, Sps};
+use std::collections::HashMap;
+
+/// Macroblocks decoded from one coded slice.
+#[derive(Debug, Clone)]
+pub struct SliceMacroblocks {
+    /// Header of the slice.
+    pub header: SliceHeader,
+    /// Macroblocks in decoding order.
+    ///
+    /// Positions are in the coordinates of the coded picture, so for field
+    /// pictures `y` counts field rows.
+    pub macroblocks: Vec<Macroblock>,
+}
+
+/// Parse the macroblocks of a coded slice NAL unit.
+///
+/// `sps` and `pps` must be the parameter sets the slice refers to. The whole
+/// slice is rejected if its data does not end exactly at the RBSP trailing
+/// bits, so a successful result is consistent with the bitstream.
+pub fn parse_slice_data(nal: &NalUnit, sps: &Sps, pps: &Pps) -> Result<SliceMacroblocks> {
+    let nal_type = nal.nal_type();
+    match nal_type {
+        NalUnitType::NonIdrSlice | NalUnitType::IdrSlice => {}
+        NalUnitType::SliceDataA | NalUnitType::SliceDataB | NalUnitType::SliceDataC => {
+            return Err(AvcError::Unsupported("slice data partitioning".to_string()))
+        }
+        _ => {
+            return Err(AvcError::InvalidNalUnit(format!(
+                "NAL unit type {} does not carry slice data",
+                nal_type.name()
+            )))
+        }
+    }
+
+    if sps.mb_adaptive_frame_field_flag {
+        return Err(AvcError::Unsupported("MBAFF frames".to_string()));
+    }
+    if pps.num_slice_groups_minus1 > 0 {
+        return Err(AvcError::Unsupported("slice groups (FMO)".to_string()));
+    }
+    if sps.separate_colour_plane_flag
+        || matches!(
+            sps.chroma_format_idc,
+            ChromaFormat::Yuv422 | ChromaFormat::Yuv444
+        )
+    {
+        return Err(AvcError::Unsupported(
+            "4:2:2 and 4:4:4 slice data".to_string(),
+        ));
+    }
+
+    let sps_map = HashMap::from([(pps.seq_parameter_set_id, sps.clone())]);
+    let pps_map = HashMap::from([(pps.pic_parameter_set_id, pps.clone())]);
+    let (header, data_offset) = parse_slice_header_with_data_offset(
+        &nal.payload,
+        &sps_map,
+        &pps_map,
+        nal_type,
+        nal.header.nal_ref_idc,
+    )?;
+    if header.pic_parameter_set_id != pps.pic_parameter_set_id {
+        return Err(AvcError::MissingParameterSet(format!(
+            "slice refers to PPS {} but PPS {} was given",
+            header.pic_parameter_set_id, pps.pic_parameter_set_id
+        )));
+    }
+    if pps.entropy_coding_mode_flag && !header.slice_type.is_intra() && header.cabac_init_idc != 0 {
+        return Err(AvcError::Unsupported(format!(
+            "CABAC with cabac_init_idc {}",
+            header.cabac_init_idc
+        )));
+    }
+
+    let macroblocks =
+        macroblock::SliceParser::new(sps, pps, &header, &nal.payload, data_offset)?.parse()?;
+
+    Ok(SliceMacroblocks {
+        header,
+        macroblocks,
+    })
+}
+
+/// Bit reader over the RBSP of a slice.
+///
+/// Reads are bounded by the `rbsp_stop_one_bit`, so running into the
+/// trailing bits is reported as an error instead of yielding zeros.
+struct RbspReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    end: usize,
+}
+
+impl<'a> RbspReader<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        let end = data
+            .iter()
+            .rposition(|&b| b != 0)
+            .map(|i| i * 8 + 7 - data[i].trailing_zeros() as usize)
+            .unwrap_or(0);
+        Self { data, pos, end }
+    }
+
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn set_position(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
+    /// Bit position of the `rbsp_stop_one_bit`.
+    fn end(&self) -> usize {
+        self.end
+    }
+
+    fn more_rbsp_data(&self) -> bool {
+        self.pos < self.end
+    }
+
+    fn read_bit(&mut self) -> Result<u32> {
+        if self.pos >= self.end {
+            return Err(AvcError::BitstreamError(
+                "slice data overruns the RBSP trailing bits".to_string(),
+            ));
+        }
+        let bit = (self.data[self.pos / 8] >> (7 - self.pos % 8)) & 1;
+        self.pos += 1;
+        Ok(bit as u32)
+    }
+
+    fn read_bits(&mut self, n: u32) -> Result<u32> {
+        let mut value = 0;
+        for _ in 0..n {
+            value = (value << 1) | self.read_bit()?;
+        }
+        Ok(value)
+    }
+
+    fn skip_bits(&mut self, n: usize) -> Result<()> {
+        if self.pos + n > self.end {
+            return Err(AvcError::BitstreamError(
+                "slice data overruns the RBSP trailing bits".to_string(),
+            ));
+        }
+        self.pos += n;
+        Ok(())
+    }
+
+    fn byte_align(&mut self) {
+        self.pos = self.pos.div_ceil(8) * 8;
+    }
+
+    fn read_ue(&mut self) -> Result<u32> {
+        let mut leading_zeros = 0;
+        while self.read_bit()? == 0 {
+            leading_zeros += 1;
+            if leading_zeros > 31 {
+                return Err(AvcError::BitstreamError(
+                    "Exp-Golomb code exceeds 32 bits".to_string(),
+                ));
+            }
+        }
+        let suffix = self.read_bits(leading_zeros)? as u64;
+        Ok(((1u64 << leading_zeros) - 1 + suffix) as u32)
+    }
+
+    fn read_se(&mut self) -> Result<i32> {
+        let code = self.read_ue()? as i64;
+        Ok(if code & 1 == 1 {
+            ((code + 1) / 2) as i32
+        } else {
+            (-(code / 2)) as i32
+        })
+    }
+
+    /// Read `te(v)` with the given maximum value.
+    fn read_te(&mut self, max: u32) -> Result<u32> {
+        if max > 1 {
+            self.read_ue()
+        } else {
+            Ok(1 - self.read_bit()?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nal::NalUnitHeader;
+    use crate::overlay_extraction::{MbType, MotionVector};
+    use crate::sps::ProfileIdc;
+
+    /// 32x16 baseline stream with 4-bit frame_num and POC LSB.
+    fn test_sps() -> Sps {
+        Sps {
+            profile_idc: ProfileIdc::Baseline,
+            constraint_set0_flag: false,
+            constraint_set1_flag: false,
+            constraint_set2_flag: false,
+            constraint_set3_flag: false,
+            constraint_set4_flag: false,
+            constraint_set5_flag: false,
+            level_idc: 30,
+            seq_parameter_set_id: 0,
+            chroma_format_idc: ChromaFormat::Yuv420,
+            separate_colour_plane_flag: false,
+            bit_depth_luma_minus8: 0,
+            bit_depth_chroma_minus8: 0,
+            qpprime_y_zero_transform_bypass_flag: false,
+            seq_scaling_matrix_present_flag: false,
+            log2_max_frame_num_minus4: 0,
+            pic_order_cnt_type: 0,
+            log2_max_pic_order_cnt_lsb_minus4: 0,
+            delta_pic_order_always_zero_flag: false,
+            offset_for_non_ref_pic: 0,
+            offset_for_top_to_bottom_field: 0,
+            num_ref_frames_in_pic_order_cnt_cycle: 0,
+            offset_for_ref_frame: vec![],
+            max_num_ref_frames: 1,
+            gaps_in_frame_num_value_allowed_flag: false,
+            pic_width_in_mbs_minus1: 1,
+            pic_height_in_map_units_minus1: 0,
+            frame_mbs_only_flag: true,
+            mb_adaptive_frame_field_flag: false,
+            direct_8x8_inference_flag: true,
+            frame_cropping_flag: false,
+            frame_crop_left_offset: 0,
+            frame_crop_right_offset: 0,
+            frame_crop_top_offset: 0,
+            frame_crop_bottom_offset: 0,
+            vui_parameters_present_flag: false,
+            vui_parameters: None,
+        }
+    }
+
+    fn test_pps() -> Pps {
+        Pps::default()
+    }
+
+    /// Build a slice NAL unit from an RBSP written as a string of bits.
+    /// The rbsp_stop_one_bit and alignment zeros are appended.
+    fn slice_nal(nal_unit_type: NalUnitType, nal_ref_idc: u8, bits: &str) -> NalUnit {
+        let mut bits: Vec<u8> = bits
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| (c == '1') as u8)
+            .collect();
+        bits.push(1);
+        bits.resize(bits.len().div_ceil(8) * 8, 0);
+        let payload: Vec<u8> = bits
+            .chunks(8)
+            .map(|byte| byte.iter().fold(0, |acc, &b| (acc << 1) | b))
+            .collect();
+        NalUnit {
+            header: NalUnitHeader {
+                forbidden_zero_bit: false,
+                nal_ref_idc,
+                nal_unit_type,
+            },
+            offset: 0,
+            size: payload.len() + 1,
+            payload: payload.clone(),
+            raw_payload: payload,
+        }
+    }
+
+    #[test]
+    fn test_cavlc_idr_intra_16x16() {
+        // Header: first_mb 0, slice_type 7 (I), pps 0, frame_num 0,
+        // idr_pic_id 0, poc_lsb 0, dec_ref_pic_marking 0 0, slice_qp_delta 0.
+        // Each macroblock: mb_type I_16x16_0_0_0, intra_chroma_pred_mode 0,
+        // mb_qp_delta, and an empty Intra16x16DCLevel (coeff_token nC=0).
+        let nal = slice_nal(
+            NalUnitType::IdrSlice,
+            3,
+            "1 0001000 1 0000 1 0000 0 0 1 \
+             010 1 011 1 \
+             010 1 00100 1",
+        );
+        let slice = parse_slice_data(&nal, &test_sps(), &test_pps()).unwrap();
+
+        assert_eq!(slice.macroblocks.len(), 2);
+        let qps: Vec<i16> = slice.macroblocks.iter().map(|mb| mb.qp).collect();
+        assert_eq!(qps, vec![25, 27]);
+        for (addr, mb) in slice.macroblocks.iter().enumerate() {
+            assert_eq!(mb.mb_addr, addr as u32);
+            assert_eq!(mb.x, addr as u32 * 16);
+            assert_eq!(mb.mb_type, MbType::I16x16);
+            assert!(mb.mv_l0.is_none());
+        }
+        assert_eq!(slice.macroblocks[0].mb_qp_delta, -1);
+    }
+
+    #[test]
+    fn test_cavlc_p_skip_then_inter() {
+        // Header: first_mb 0, slice_type 5 (P), pps 0, frame_num 1, poc_lsb 2,
+        // no ref count override, no list modification, slice_qp_delta 0.
+        // Slice data: mb_skip_run 1, then P_L0_16x16 with mvd (2, -1) and
+        // coded_block_pattern 0.
+        let nal = slice_nal(
+            NalUnitType::NonIdrSlice,
+            0,
+            "1 00110 1 0001 0010 0 0 1 \
+             010 \
+             1 00100 011 1",
+        );
+        let slice = parse_slice_data(&nal, &test_sps(), &test_pps()).unwrap();
+
+        assert_eq!(slice.macroblocks.len(), 2);
+        let skip = &slice.macroblocks[0];
+        assert_eq!(skip.mb_type, MbType::PSkip);
+        assert!(skip.skip);
+        assert_eq!(skip.mv_l0, Some(MotionVector::new(0, 0)));
+
+        // Only A is available, so the predictor is the skipped zero vector
+        let inter = &slice.macroblocks[1];
+        assert_eq!(inter.mb_type, MbType::PLuma);
+        assert_eq!(inter.mv_l0, Some(MotionVector::new(2, -1)));
+        assert_eq!(inter.ref_idx_l0, Some(0));
+        assert_eq!(inter.coded_block_pattern, 0);
+        assert!(slice.macroblocks.iter().all(|mb| mb.qp == 26));
+    }
+
+    #[test]
+    fn test_cavlc_rejects_truncated_slice() {
+        // The second macroblock stops after intra_chroma_pred_mode
+        let nal = slice_nal(
+            NalUnitType::IdrSlice,
+            3,
+            "1 0001000 1 0000 1 0000 0 0 1 010 1 1 1 010 1",
+        );
+        assert!(parse_slice_data(&nal, &test_sps(), &test_pps()).is_err());
+    }
+
+    #[test]
+    fn test_rbsp_reader_stops_at_trailing_bits() {
+        // ue(0) ue(1) then rbsp_stop_one_bit
+        let data = [0b1010_1000];
+        let mut reader = RbspReader::new(&data, 0);
+        assert_eq!(reader.end(), 4);
+        assert_eq!(reader.read_ue().unwrap(), 0);
+        assert!(reader.more_rbsp_data());
+        assert_eq!(reader.read_ue().unwrap(), 1);
+        assert!(!reader.more_rbsp_data());
+        assert!(reader.read_bit().is_err());
+    }
+
+    #[test]
+    fn test_rbsp_reader_se_and_te() {
+        // se: 011 -> -1, 00100 -> 2; te(1): 1 -> 0
+        let data = [0b0110_0100, 0b1100_0000];
+        let mut reader = RbspReader::new(&data, 0);
+        assert_eq!(reader.read_se().unwrap(), -1);
+        assert_eq!(reader.read_se().unwrap(), 2);
+        assert_eq!(reader.read_te(1).unwrap(), 0);
+        assert!(!reader.more_rbsp_data());
+    }
+}
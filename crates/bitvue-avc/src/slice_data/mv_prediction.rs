@@ -0,0 +1,197 @@
+//! Luma motion vector prediction (ITU-T H.264 Section 8.4.1).
+//!
+//! The functions here operate on the motion data of the neighbouring
+//! partitions A, B and C; locating those partitions is left to the caller,
+//! which must already have substituted D for an unavailable C.
+
+use super::macroblock::Mv;
+
+/// Motion data of a neighbouring partition for one reference list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct MvNeighbour {
+    /// Whether the partition is available.
+    pub available: bool,
+    /// refIdxLXN; -1 when unavailable, intra or not predicted from the list.
+    pub ref_idx: i8,
+    /// mvLXN; zero whenever `ref_idx` is negative.
+    pub mv: Mv,
+}
+
+impl MvNeighbour {
+    /// A partition outside the picture or slice, or not yet decoded.
+    pub const UNAVAILABLE: Self = Self {
+        available: false,
+        ref_idx: -1,
+        mv: [0, 0],
+    };
+}
+
+/// Partition shapes with directional motion vector prediction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum PartShape {
+    /// Any partition predicted with the median rule.
+    Median,
+    /// Upper 16x8 partition, predicted from B.
+    Upper16x8,
+    /// Lower 16x8 partition, predicted from A.
+    Lower16x8,
+    /// Left 8x16 partition, predicted from A.
+    Left8x16,
+    /// Right 8x16 partition, predicted from C.
+    Right8x16,
+}
+
+/// Derive the motion vector predictor mvpLX (8.4.1.3).
+pub(super) fn predict(
+    a: MvNeighbour,
+    b: MvNeighbour,
+    c: MvNeighbour,
+    ref_idx: i8,
+    shape: PartShape,
+) -> Mv {
+    let directional = match shape {
+        PartShape::Median => None,
+        PartShape::Upper16x8 => Some(b),
+        PartShape::Lower16x8 | PartShape::Left8x16 => Some(a),
+        PartShape::Right8x16 => Some(c),
+    };
+    if let Some(n) = directional.filter(|n| n.ref_idx == ref_idx) {
+        return n.mv;
+    }
+
+    // 8.4.1.3.1: B and C fall back to A when only A is available
+    let (b, c) = if !b.available && !c.available && a.available {
+        (a, a)
+    } else {
+        (b, c)
+    };
+    let matching: Vec<&MvNeighbour> = [&a, &b, &c]
+        .into_iter()
+        .filter(|n| n.ref_idx == ref_idx)
+        .collect();
+    if let [n] = matching[..] {
+        return n.mv;
+    }
+    [
+        median(a.mv[0], b.mv[0], c.mv[0]),
+        median(a.mv[1], b.mv[1], c.mv[1]),
+    ]
+}
+
+/// Derive the motion vector of a P_Skip macroblock (8.4.1.1).
+///
+/// The neighbours are those of the whole macroblock.
+pub(super) fn p_skip(a: MvNeighbour, b: MvNeighbour, c: MvNeighbour) -> Mv {
+    let zero_ref0 = |n: &MvNeighbour| n.ref_idx == 0 && n.mv == [0, 0];
+    if !a.available || !b.available || zero_ref0(&a) || zero_ref0(&b) {
+        [0, 0]
+    } else {
+        predict(a, b, c, 0, PartShape::Median)
+    }
+}
+
+/// Derive the reference indices and motion vectors of spatial direct
+/// prediction (8.4.1.2.2) from the neighbours of the whole macroblock in
+/// list 0 and list 1.
+///
+/// The co-located picture is not available here, so the co-located block
+/// is treated as moving and the predicted vectors are used unchanged.
+pub(super) fn spatial_direct(neighbours: [[MvNeighbour; 3]; 2]) -> ([i8; 2], [Mv; 2]) {
+    let refs =
+        neighbours.map(|[a, b, c]| min_positive(a.ref_idx, min_positive(b.ref_idx, c.ref_idx)));
+    if refs[0] < 0 && refs[1] < 0 {
+        return ([0, 0], [[0, 0]; 2]);
+    }
+    let mut mvs = [[0, 0]; 2];
+    for list in 0..2 {
+        if refs[list] >= 0 {
+            let [a, b, c] = neighbours[list];
+            mvs[list] = predict(a, b, c, refs[list], PartShape::Median);
+        }
+    }
+    (refs, mvs)
+}
+
+/// MinPositive (equation 8-184).
+fn min_positive(x: i8, y: i8) -> i8 {
+    if x >= 0 && y >= 0 {
+        x.min(y)
+    } else {
+        x.max(y)
+    }
+}
+
+fn median(a: i16, b: i16, c: i16) -> i16 {
+    a.max(b).min(a.min(b).max(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn n(ref_idx: i8, x: i16, y: i16) -> MvNeighbour {
+        MvNeighbour {
+            available: true,
+            ref_idx,
+            mv: [x, y],
+        }
+    }
+
+    #[test]
+    fn test_median_prediction() {
+        let mv = predict(n(0, 4, -2), n(0, 8, 6), n(0, -4, 2), 0, PartShape::Median);
+        assert_eq!(mv, [4, 2]);
+    }
+
+    #[test]
+    fn test_single_matching_reference() {
+        let mv = predict(n(1, 4, -2), n(0, 8, 6), n(1, -4, 2), 0, PartShape::Median);
+        assert_eq!(mv, [8, 6]);
+    }
+
+    #[test]
+    fn test_only_left_available() {
+        let a = n(2, 12, -6);
+        let mv = predict(
+            a,
+            MvNeighbour::UNAVAILABLE,
+            MvNeighbour::UNAVAILABLE,
+            0,
+            PartShape::Median,
+        );
+        assert_eq!(mv, [12, -6]);
+    }
+
+    #[test]
+    fn test_directional_prediction() {
+        let (a, b, c) = (n(0, 1, 1), n(0, 2, 2), n(0, 3, 3));
+        assert_eq!(predict(a, b, c, 0, PartShape::Upper16x8), [2, 2]);
+        assert_eq!(predict(a, b, c, 0, PartShape::Lower16x8), [1, 1]);
+        assert_eq!(predict(a, b, c, 0, PartShape::Left8x16), [1, 1]);
+        assert_eq!(predict(a, b, c, 0, PartShape::Right8x16), [3, 3]);
+        // Falls back to the median when the reference differs
+        assert_eq!(predict(a, n(1, 9, 9), c, 0, PartShape::Upper16x8), [3, 3]);
+    }
+
+    #[test]
+    fn test_p_skip() {
+        let c = n(0, 5, 5);
+        assert_eq!(p_skip(MvNeighbour::UNAVAILABLE, n(0, 4, 4), c), [0, 0]);
+        assert_eq!(p_skip(n(0, 0, 0), n(0, 4, 4), c), [0, 0]);
+        assert_eq!(p_skip(n(0, 3, 3), n(0, 4, 4), c), [4, 4]);
+    }
+
+    #[test]
+    fn test_spatial_direct() {
+        let unavailable = [MvNeighbour::UNAVAILABLE; 3];
+        assert_eq!(
+            spatial_direct([unavailable, unavailable]),
+            ([0, 0], [[0, 0]; 2])
+        );
+
+        let l0 = [n(1, 4, 4), n(0, 8, 8), n(-1, 0, 0)];
+        let (refs, mvs) = spatial_direct([l0, unavailable]);
+        assert_eq!(refs, [0, -1]);
+        assert_eq!(mvs, [[8, 8], [0, 0]]);
+    }
+}
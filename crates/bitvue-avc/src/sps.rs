@@ -192,6 +192,15 @@ pub struct VuiParameters {
     pub nal_hrd_parameters_present_flag: bool,
     /// vcl_hrd_parameters_present_flag
     pub vcl_hrd_parameters_present_flag: bool,
+    /// NAL HRD parameters (Type II bitstream conformance)
+    #[serde(default)]
+    pub nal_hrd_parameters: Option<HrdParameters>,
+    /// VCL HRD parameters (Type I bitstream conformance)
+    #[serde(default)]
+    pub vcl_hrd_parameters: Option<HrdParameters>,
+    /// low_delay_hrd_flag
+    #[serde(default)]
+    pub low_delay_hrd_flag: bool,
     /// pic_struct_present_flag
     pub pic_struct_present_flag: bool,
     /// bitstream_restriction_flag
@@ -202,6 +211,72 @@ pub struct VuiParameters {
     pub max_dec_frame_buffering: u32,
 }
 
+impl VuiParameters {
+    /// HRD parameters of the given HRD, if signalled.
+    pub fn hrd_parameters(&self, hrd: HrdType) -> Option<&HrdParameters> {
+        match hrd {
+            HrdType::Nal => self.nal_hrd_parameters.as_ref(),
+            HrdType::Vcl => self.vcl_hrd_parameters.as_ref(),
+        }
+    }
+
+    /// CpbDpbDelaysPresentFlag: picture timing SEI carries CPB and DPB delays.
+    pub fn cpb_dpb_delays_present(&self) -> bool {
+        self.nal_hrd_parameters_present_flag || self.vcl_hrd_parameters_present_flag
+    }
+}
+
+/// Hypothetical reference decoder variant (Annex C).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HrdType {
+    /// NAL HRD: all NAL units of the byte stream (Type II)
+    Nal,
+    /// VCL HRD: VCL and filler data NAL units only (Type I)
+    Vcl,
+}
+
+/// HRD parameters (E.1.2).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HrdParameters {
+    /// bit_rate_scale
+    pub bit_rate_scale: u8,
+    /// cpb_size_scale
+    pub cpb_size_scale: u8,
+    /// One entry per SchedSelIdx (cpb_cnt_minus1 + 1)
+    pub cpbs: Vec<CpbSpec>,
+    /// initial_cpb_removal_delay_length_minus1
+    pub initial_cpb_removal_delay_length_minus1: u8,
+    /// cpb_removal_delay_length_minus1
+    pub cpb_removal_delay_length_minus1: u8,
+    /// dpb_output_delay_length_minus1
+    pub dpb_output_delay_length_minus1: u8,
+    /// time_offset_length
+    pub time_offset_length: u8,
+}
+
+/// CPB specification for one SchedSelIdx.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpbSpec {
+    /// bit_rate_value_minus1
+    pub bit_rate_value_minus1: u32,
+    /// cpb_size_value_minus1
+    pub cpb_size_value_minus1: u32,
+    /// cbr_flag
+    pub cbr_flag: bool,
+}
+
+impl HrdParameters {
+    /// BitRate in bits per second (E-37).
+    pub fn bit_rate(&self, cpb: &CpbSpec) -> u64 {
+        (cpb.bit_rate_value_minus1 as u64 + 1) << (6 + self.bit_rate_scale)
+    }
+
+    /// CpbSize in bits (E-38).
+    pub fn cpb_size(&self, cpb: &CpbSpec) -> u64 {
+        (cpb.cpb_size_value_minus1 as u64 + 1) << (4 + self.cpb_size_scale)
+    }
+}
+
 /// Sequence Parameter Set.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sps {
@@ -628,16 +703,16 @@ fn parse_vui(reader: &mut BitReader) -> Result<VuiParameters> {
 
     vui.nal_hrd_parameters_present_flag = reader.read_flag()?;
     if vui.nal_hrd_parameters_present_flag {
-        skip_hrd_parameters(reader)?;
+        vui.nal_hrd_parameters = Some(parse_hrd_parameters(reader)?);
     }
 
     vui.vcl_hrd_parameters_present_flag = reader.read_flag()?;
     if vui.vcl_hrd_parameters_present_flag {
-        skip_hrd_parameters(reader)?;
+        vui.vcl_hrd_parameters = Some(parse_hrd_parameters(reader)?);
     }
 
-    if vui.nal_hrd_parameters_present_flag || vui.vcl_hrd_parameters_present_flag {
-        let _low_delay_hrd_flag = reader.read_flag()?;
+    if vui.cpb_dpb_delays_present() {
+        vui.low_delay_hrd_flag = reader.read_flag()?;
     }
 
     vui.pic_struct_present_flag = reader.read_flag()?;
@@ -672,10 +747,10 @@ fn parse_vui(reader: &mut BitReader) -> Result<VuiParameters> {
     Ok(vui)
 }
 
-/// Skip HRD parameters.
-fn skip_hrd_parameters(reader: &mut BitReader) -> Result<()> {
+/// Parse HRD parameters (E.1.2).
+pub fn parse_hrd_parameters(reader: &mut BitReader) -> Result<HrdParameters> {
     // SECURITY: Validate cpb_cnt_minus1 to prevent unbounded loop
-    const MAX_CPB_COUNT: u32 = 32;
+    const MAX_CPB_COUNT: u32 = 31;
     let cpb_cnt_minus1 = reader.read_ue()?;
 
     if cpb_cnt_minus1 > MAX_CPB_COUNT {
@@ -685,21 +760,26 @@ fn skip_hrd_parameters(reader: &mut BitReader) -> Result<()> {
         )));
     }
 
-    let _bit_rate_scale = reader.read_bits(4)?;
-    let _cpb_size_scale = reader.read_bits(4)?;
+    let mut hrd = HrdParameters {
+        bit_rate_scale: reader.read_bits(4)? as u8,
+        cpb_size_scale: reader.read_bits(4)? as u8,
+        ..Default::default()
+    };
 
     for _ in 0..=cpb_cnt_minus1 {
-        let _bit_rate_value_minus1 = reader.read_ue()?;
-        let _cpb_size_value_minus1 = reader.read_ue()?;
-        let _cbr_flag = reader.read_flag()?;
+        hrd.cpbs.push(CpbSpec {
+            bit_rate_value_minus1: reader.read_ue()?,
+            cpb_size_value_minus1: reader.read_ue()?,
+            cbr_flag: reader.read_flag()?,
+        });
     }
 
-    let _initial_cpb_removal_delay_length_minus1 = reader.read_bits(5)?;
-    let _cpb_removal_delay_length_minus1 = reader.read_bits(5)?;
-    let _dpb_output_delay_length_minus1 = reader.read_bits(5)?;
-    let _time_offset_length = reader.read_bits(5)?;
+    hrd.initial_cpb_removal_delay_length_minus1 = reader.read_bits(5)? as u8;
+    hrd.cpb_removal_delay_length_minus1 = reader.read_bits(5)? as u8;
+    hrd.dpb_output_delay_length_minus1 = reader.read_bits(5)? as u8;
+    hrd.time_offset_length = reader.read_bits(5)? as u8;
 
-    Ok(())
+    Ok(hrd)
 }
 
 #[cfg(test)]
@@ -722,4 +802,28 @@ mod tests {
         assert_eq!(ChromaFormat::Yuv420.sub_width_c(), 2);
         assert_eq!(ChromaFormat::Yuv420.sub_height_c(), 2);
     }
+
+    #[test]
+    fn test_parse_hrd_parameters() {
+        // cpb_cnt_minus1=0, bit_rate_scale=2, cpb_size_scale=3,
+        // bit_rate_value_minus1=0, cpb_size_value_minus1=1, cbr_flag=1,
+        // lengths 23/23/23 and time_offset_length=24
+        let bits = "1 0010 0011 1 010 1 10111 10111 10111 11000";
+        let bits: String = bits.split_whitespace().collect();
+        let mut bytes = vec![0u8; bits.len().div_ceil(8)];
+        for (i, b) in bits.bytes().enumerate() {
+            if b == b'1' {
+                bytes[i / 8] |= 0x80 >> (i % 8);
+            }
+        }
+
+        let mut reader = BitReader::new(&bytes);
+        let hrd = parse_hrd_parameters(&mut reader).unwrap();
+        assert_eq!(hrd.cpbs.len(), 1);
+        assert_eq!(hrd.bit_rate(&hrd.cpbs[0]), 1 << 8);
+        assert_eq!(hrd.cpb_size(&hrd.cpbs[0]), 2 << 7);
+        assert!(hrd.cpbs[0].cbr_flag);
+        assert_eq!(hrd.cpb_removal_delay_length_minus1, 23);
+        assert_eq!(hrd.time_offset_length, 24);
+    }
 }
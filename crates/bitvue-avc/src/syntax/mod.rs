@@ -0,0 +1,370 @@
+//! H.264/AVC syntax tree extraction for visualization.
+//!
+//! This module builds a hierarchical syntax tree from parsed AVC structures,
+//! suitable for UI display in tree views.
+
+use crate::{AvcStream, NalUnit, NalUnitType, ParsedSlice};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// A node in the AVC syntax tree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyntaxNode {
+    /// Node name/label.
+    pub name: String,
+    /// Node value (if applicable).
+    pub value: Option<String>,
+    /// Bit offset in the stream.
+    pub bit_offset: Option<u64>,
+    /// Bit length of this element.
+    pub bit_length: Option<u64>,
+    /// Child nodes.
+    pub children: Vec<SyntaxNode>,
+    /// Node type for styling.
+    pub node_type: SyntaxNodeType,
+}
+
+/// Type of syntax node for UI styling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SyntaxNodeType {
+    /// Root node.
+    Root,
+    /// NAL unit container.
+    NalUnit,
+    /// Parameter set (SPS, PPS).
+    ParameterSet,
+    /// Slice header.
+    SliceHeader,
+    /// SEI message.
+    Sei,
+    /// Field/value.
+    Field,
+    /// Array/list.
+    Array,
+    /// Structure.
+    Structure,
+}
+
+impl SyntaxNode {
+    /// Create a new syntax node.
+    pub fn new(name: impl Into<String>, node_type: SyntaxNodeType) -> Self {
+        Self {
+            name: name.into(),
+            value: None,
+            bit_offset: None,
+            bit_length: None,
+            children: Vec::new(),
+            node_type,
+        }
+    }
+
+    /// Create a field node with a value.
+    pub fn field(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: Some(value.into()),
+            bit_offset: None,
+            bit_length: None,
+            children: Vec::new(),
+            node_type: SyntaxNodeType::Field,
+        }
+    }
+
+    /// Add a child node.
+    pub fn add_child(&mut self, child: SyntaxNode) {
+        self.children.push(child);
+    }
+
+    /// Set bit position.
+    pub fn with_position(mut self, offset: u64, length: u64) -> Self {
+        self.bit_offset = Some(offset);
+        self.bit_length = Some(length);
+        self
+    }
+}
+
+/// Build a syntax tree from a parsed AVC stream.
+pub fn build_syntax_tree(stream: &AvcStream) -> SyntaxNode {
+    let mut root = SyntaxNode::new("H.264 Bitstream", SyntaxNodeType::Root);
+
+    if let Some((width, height)) = stream.dimensions() {
+        root.add_child(SyntaxNode::field(
+            "Resolution",
+            format!("{}x{}", width, height),
+        ));
+    }
+    if let Some(fps) = stream.frame_rate() {
+        root.add_child(SyntaxNode::field("Frame Rate", format!("{:.2} fps", fps)));
+    }
+
+    root.add_child(build_access_unit_tree(stream, 0..stream.nal_units.len()));
+
+    root
+}
+
+/// Build a syntax tree for the NAL units of one access unit.
+///
+/// `nal_range` indexes `stream.nal_units`. Parameter sets and SEI are parsed
+/// from their own payload, and every NAL unit node carries its bit position
+/// in the parsed stream.
+pub fn build_access_unit_tree(stream: &AvcStream, nal_range: Range<usize>) -> SyntaxNode {
+    let mut root = SyntaxNode::new("Access Unit", SyntaxNodeType::Root);
+    let start = nal_range.start;
+
+    for (i, nal) in stream
+        .nal_units
+        .get(nal_range)
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+    {
+        let nal_index = start + i;
+        let offset = nal.offset as u64 * 8;
+        let mut nal_node = SyntaxNode::new(
+            format!("[{}] {}", nal_index, nal.header.nal_unit_type.name()),
+            SyntaxNodeType::NalUnit,
+        )
+        .with_position(offset, nal.size as u64 * 8);
+
+        nal_node.add_child(build_nal_header_tree(nal));
+
+        match nal.header.nal_unit_type {
+            NalUnitType::Sps => {
+                if let Ok(sps) = crate::sps::parse_sps(&nal.payload) {
+                    nal_node.add_child(build_sps_tree(&sps));
+                }
+            }
+            NalUnitType::Pps => {
+                if let Ok(pps) = crate::pps::parse_pps(&nal.payload) {
+                    nal_node.add_child(build_pps_tree(&pps));
+                }
+            }
+            NalUnitType::Sei => {
+                if let Ok(messages) = crate::sei::parse_sei(&nal.payload) {
+                    for message in &messages {
+                        nal_node.add_child(build_sei_tree(message));
+                    }
+                }
+            }
+            _ => {
+                if let Some(slice) = stream.slices.iter().find(|s| s.nal_index == nal_index) {
+                    nal_node.add_child(build_slice_tree(stream, slice));
+                }
+            }
+        }
+
+        root.add_child(nal_node);
+    }
+
+    root
+}
+
+/// Build syntax tree for a NAL unit header.
+fn build_nal_header_tree(nal: &NalUnit) -> SyntaxNode {
+    // `offset` and `size` include the start code; the header byte directly
+    // precedes the raw payload
+    let header_offset = (nal.offset + nal.size - nal.raw_payload.len() - 1) as u64 * 8;
+    let mut header_node =
+        SyntaxNode::new("NAL Header", SyntaxNodeType::Structure).with_position(header_offset, 8);
+    header_node.add_child(SyntaxNode::field(
+        "forbidden_zero_bit",
+        (nal.header.forbidden_zero_bit as u8).to_string(),
+    ));
+    header_node.add_child(SyntaxNode::field(
+        "nal_ref_idc",
+        nal.header.nal_ref_idc.to_string(),
+    ));
+    header_node.add_child(SyntaxNode::field(
+        "nal_unit_type",
+        format!(
+            "{:?} ({})",
+            nal.header.nal_unit_type, nal.header.nal_unit_type as u8
+        ),
+    ));
+    header_node
+}
+
+/// Build syntax tree for SPS.
+fn build_sps_tree(sps: &crate::Sps) -> SyntaxNode {
+    let mut node = SyntaxNode::new("Sequence Parameter Set", SyntaxNodeType::ParameterSet);
+
+    node.add_child(SyntaxNode::field(
+        "profile_idc",
+        format!("{:?}", sps.profile_idc),
+    ));
+    node.add_child(SyntaxNode::field("level_idc", sps.level_idc.to_string()));
+    node.add_child(SyntaxNode::field(
+        "seq_parameter_set_id",
+        sps.seq_parameter_set_id.to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "chroma_format_idc",
+        format!("{:?}", sps.chroma_format_idc),
+    ));
+    node.add_child(SyntaxNode::field(
+        "bit_depth_luma",
+        sps.bit_depth_luma().to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "pic_size",
+        format!("{}x{}", sps.pic_width(), sps.pic_height()),
+    ));
+    node.add_child(SyntaxNode::field(
+        "display_size",
+        format!("{}x{}", sps.display_width(), sps.display_height()),
+    ));
+    node.add_child(SyntaxNode::field(
+        "pic_order_cnt_type",
+        sps.pic_order_cnt_type.to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "max_num_ref_frames",
+        sps.max_num_ref_frames.to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "frame_mbs_only_flag",
+        sps.frame_mbs_only_flag.to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "vui_parameters_present_flag",
+        sps.vui_parameters_present_flag.to_string(),
+    ));
+
+    node
+}
+
+/// Build syntax tree for PPS.
+fn build_pps_tree(pps: &crate::Pps) -> SyntaxNode {
+    let mut node = SyntaxNode::new("Picture Parameter Set", SyntaxNodeType::ParameterSet);
+
+    node.add_child(SyntaxNode::field(
+        "pic_parameter_set_id",
+        pps.pic_parameter_set_id.to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "seq_parameter_set_id",
+        pps.seq_parameter_set_id.to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "entropy_coding_mode_flag",
+        format!(
+            "{} ({})",
+            pps.entropy_coding_mode_flag,
+            if pps.is_cabac() { "CABAC" } else { "CAVLC" }
+        ),
+    ));
+    node.add_child(SyntaxNode::field("init_qp", pps.initial_qp().to_string()));
+    node.add_child(SyntaxNode::field(
+        "weighted_pred_flag",
+        pps.weighted_pred_flag.to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "weighted_bipred_idc",
+        pps.weighted_bipred_idc.to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "deblocking_filter_control_present_flag",
+        pps.deblocking_filter_control_present_flag.to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "transform_8x8_mode_flag",
+        pps.transform_8x8_mode_flag.to_string(),
+    ));
+
+    node
+}
+
+/// Build syntax tree for an SEI message.
+fn build_sei_tree(message: &crate::SeiMessage) -> SyntaxNode {
+    let mut node = SyntaxNode::new(message.payload_type.name(), SyntaxNodeType::Sei);
+    node.add_child(SyntaxNode::field(
+        "payload_type",
+        message.payload_type_raw.to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "payload_size",
+        message.payload_size.to_string(),
+    ));
+    node
+}
+
+/// Build syntax tree for a slice header.
+fn build_slice_tree(stream: &AvcStream, slice: &ParsedSlice) -> SyntaxNode {
+    let header = &slice.header;
+    let mut node = SyntaxNode::new(
+        format!("POC {} - {} slice", slice.poc, header.slice_type.name()),
+        SyntaxNodeType::SliceHeader,
+    );
+
+    node.add_child(SyntaxNode::field(
+        "first_mb_in_slice",
+        header.first_mb_in_slice.to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "slice_type",
+        header.slice_type.name().to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "pic_parameter_set_id",
+        header.pic_parameter_set_id.to_string(),
+    ));
+    node.add_child(SyntaxNode::field("frame_num", header.frame_num.to_string()));
+    node.add_child(SyntaxNode::field(
+        "pic_order_cnt_lsb",
+        header.pic_order_cnt_lsb.to_string(),
+    ));
+    node.add_child(SyntaxNode::field("poc", slice.poc.to_string()));
+    if !header.slice_type.is_intra() {
+        node.add_child(SyntaxNode::field(
+            "num_ref_idx_l0_active_minus1",
+            header.num_ref_idx_l0_active_minus1.to_string(),
+        ));
+    }
+    if header.slice_type.is_b() {
+        node.add_child(SyntaxNode::field(
+            "num_ref_idx_l1_active_minus1",
+            header.num_ref_idx_l1_active_minus1.to_string(),
+        ));
+    }
+    node.add_child(SyntaxNode::field(
+        "slice_qp_delta",
+        header.slice_qp_delta.to_string(),
+    ));
+    if let Some(pps) = stream.get_pps(header.pic_parameter_set_id) {
+        node.add_child(SyntaxNode::field("slice_qp", header.qp(pps).to_string()));
+    }
+    node.add_child(SyntaxNode::field(
+        "disable_deblocking_filter_idc",
+        header.disable_deblocking_filter_idc.to_string(),
+    ));
+
+    node
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syntax_node_creation() {
+        let node = SyntaxNode::new("test", SyntaxNodeType::Root);
+        assert_eq!(node.name, "test");
+        assert!(node.children.is_empty());
+    }
+
+    #[test]
+    fn test_access_unit_tree_positions() {
+        // AUD (type 9) followed by end of sequence (type 10)
+        let data = [0, 0, 0, 1, 0x09, 0xF0, 0, 0, 0, 1, 0x0A];
+        let stream = crate::parse_avc(&data).unwrap();
+
+        let tree = build_access_unit_tree(&stream, 0..stream.nal_units.len());
+        assert_eq!(tree.children.len(), 2);
+        let first = &tree.children[0];
+        assert_eq!(
+            first.bit_offset,
+            Some(stream.nal_units[0].offset as u64 * 8)
+        );
+        assert_eq!(first.children[0].name, "NAL Header");
+    }
+}
@@ -2340,7 +2340,8 @@ fn test_extract_mv_grid_with_empty_nal_units() {
         vui_parameters_present_flag: false,
         vui_parameters: None,
     };
-    let result = extract_mv_grid(nal_units, &sps);
+    let pps = crate::pps::Pps::default();
+    let result = extract_mv_grid(nal_units, &sps, &pps);
     // Empty NAL units should return error or empty grid
     assert!(result.is_ok() || result.is_err());
 }
@@ -2389,8 +2390,9 @@ fn test_extract_qp_grid_with_empty_nal_units() {
         vui_parameters_present_flag: false,
         vui_parameters: None,
     };
-    let result = extract_qp_grid(nal_units, &sps, 26); // Valid base QP
-                                                       // Empty NAL units should return error or empty grid
+    let pps = crate::pps::Pps::default();
+    let result = extract_qp_grid(nal_units, &sps, &pps, 26); // Valid base QP
+                                                             // Empty NAL units should return error or empty grid
     assert!(result.is_ok() || result.is_err());
 }
 
@@ -2444,3 +2446,179 @@ fn test_find_nal_units_with_no_start_codes() {
     let positions = find_nal_units(&data);
     assert_eq!(positions.len(), 0); // No NAL units found
 }
+
+#[test]
+fn test_hrd_timings_from_signalled_sei() {
+    use crate::nal::NalUnitHeader;
+    use crate::sps::VuiParameters;
+
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .and_then(|p| p.parent())
+        .unwrap()
+        .join("samples/foreman_h264.264");
+    let Ok(data) = std::fs::read(&path) else {
+        eprintln!("Skipping test: {} not found", path.display());
+        return;
+    };
+    let mut stream = parse_avc(&data).unwrap();
+    assert!(stream.hrd_model(HrdType::Nal).is_none());
+
+    // 25 fps (50 field ticks per second), 1 Mbps VBR NAL and VCL HRDs with
+    // a 2 Mbit CPB and 24-bit delays
+    let hrd = HrdParameters {
+        cpbs: vec![CpbSpec {
+            bit_rate_value_minus1: (1_000_000 >> 6) - 1,
+            cpb_size_value_minus1: (2_000_000 >> 4) - 1,
+            cbr_flag: false,
+        }],
+        initial_cpb_removal_delay_length_minus1: 23,
+        cpb_removal_delay_length_minus1: 23,
+        dpb_output_delay_length_minus1: 23,
+        ..Default::default()
+    };
+    for sps in stream.sps_map.values_mut() {
+        sps.vui_parameters = Some(VuiParameters {
+            timing_info_present_flag: true,
+            num_units_in_tick: 1,
+            time_scale: 50,
+            nal_hrd_parameters_present_flag: true,
+            vcl_hrd_parameters_present_flag: true,
+            nal_hrd_parameters: Some(hrd.clone()),
+            vcl_hrd_parameters: Some(hrd.clone()),
+            ..Default::default()
+        });
+    }
+
+    // SEI RBSP with a single message built from (value, bits) fields
+    let sei_nal = |payload_type: u8, fields: &[(u32, u8)]| {
+        let mut bits = Vec::new();
+        for &(value, len) in fields {
+            bits.extend((0..len).rev().map(|b| (value >> b) & 1 == 1));
+        }
+        let mut payload = vec![0u8; bits.len().div_ceil(8)];
+        for (i, _) in bits.iter().enumerate().filter(|(_, &b)| b) {
+            payload[i / 8] |= 0x80 >> (i % 8);
+        }
+        let mut rbsp = vec![payload_type, payload.len() as u8];
+        rbsp.extend(payload);
+        rbsp.push(0x80);
+        NalUnit {
+            header: NalUnitHeader {
+                forbidden_zero_bit: false,
+                nal_ref_idc: 0,
+                nal_unit_type: NalUnitType::Sei,
+            },
+            offset: 0,
+            size: rbsp.len() + 4,
+            payload: rbsp.clone(),
+            raw_payload: rbsp,
+        }
+    };
+
+    // Buffering period (0.5s initial delay for both HRDs) on the first
+    // picture, picture timing on every later one
+    let first_slices: Vec<usize> = stream
+        .slices
+        .iter()
+        .filter(|s| s.header.is_first_slice())
+        .map(|s| s.nal_index)
+        .collect();
+    let mut nal_units = Vec::new();
+    let mut remap = Vec::new();
+    for (i, nal) in stream.nal_units.iter().enumerate() {
+        match first_slices.iter().position(|&n| n == i) {
+            Some(0) => nal_units.push(sei_nal(
+                0,
+                &[(1, 1), (45000, 24), (0, 24), (45000, 24), (0, 24)],
+            )),
+            Some(n) => nal_units.push(sei_nal(1, &[(2 * n as u32, 24), (4, 24)])),
+            None => {}
+        }
+        remap.push(nal_units.len());
+        nal_units.push(nal.clone());
+    }
+    stream.nal_units = nal_units;
+    for slice in &mut stream.slices {
+        slice.nal_index = remap[slice.nal_index];
+    }
+    let bp_nal = &stream.nal_units[remap[first_slices[0]] - 1];
+    stream.sei_messages = parse_sei_with_sps(&bp_nal.payload, &stream.sps_map, None).unwrap();
+
+    let params = stream.hrd_parameters(HrdType::Nal).unwrap();
+    assert_eq!(params.bit_rate_bps, 1_000_000 >> 6 << 6);
+    assert_eq!(params.initial_cpb_removal_delay, 45000);
+    assert!(!params.cbr_flag);
+
+    let timings = stream.hrd_timings(HrdType::Nal, &params);
+    assert_eq!(timings.len(), stream.frame_count());
+    assert_eq!(timings[0].dts_sec, Some(0.5));
+    for (n, timing) in timings.iter().enumerate().skip(1) {
+        let dts = timing.dts_sec.unwrap();
+        assert!((dts - (0.5 + 0.04 * n as f64)).abs() < 1e-9);
+        assert!((timing.pts_sec - (dts + 0.08)).abs() < 1e-9);
+        assert_eq!(timing.cpb_removal_delay, Some(3600 * n as u64));
+    }
+    let total: u64 = stream.nal_units.iter().map(|n| n.size as u64 * 8).sum();
+    assert_eq!(
+        timings.iter().map(|t| t.frame_size_bits).sum::<u64>(),
+        total
+    );
+
+    // The VCL HRD only counts slice data
+    let vcl_params = stream.hrd_parameters(HrdType::Vcl).unwrap();
+    let vcl_timings = stream.hrd_timings(HrdType::Vcl, &vcl_params);
+    assert_eq!(vcl_timings.len(), timings.len());
+    assert!(vcl_timings
+        .iter()
+        .zip(&timings)
+        .all(|(vcl, nal)| vcl.frame_size_bits < nal.frame_size_bits));
+
+    let model = stream.hrd_model(HrdType::Nal).unwrap();
+    assert_eq!(model.frame_count(), timings.len());
+    assert!(model.is_conformant());
+
+    // Later access units of the buffering period may arrive the initial
+    // delay plus its offset before removal
+    assert_eq!(timings[0].earliest_arrival_sec, Some(0.0));
+    let bp_index = remap[first_slices[0]] - 1;
+    stream.nal_units[bp_index] =
+        sei_nal(0, &[(1, 1), (45000, 24), (9000, 24), (45000, 24), (0, 24)]);
+    let timings = stream.hrd_timings(HrdType::Nal, &params);
+    let vcl_timings = stream.hrd_timings(HrdType::Vcl, &vcl_params);
+    for (nal, vcl) in timings.iter().zip(&vcl_timings).skip(1) {
+        let dts = nal.dts_sec.unwrap();
+        assert!((dts - 0.6 - nal.earliest_arrival_sec.unwrap()).abs() < 1e-9);
+        assert!((dts - 0.5 - vcl.earliest_arrival_sec.unwrap()).abs() < 1e-9);
+    }
+
+    // Pictures still arriving at their removal time underflow the CPB
+    let slow = HrdParameters {
+        cpbs: vec![CpbSpec {
+            bit_rate_value_minus1: (20_000 >> 6) - 1,
+            ..hrd.cpbs[0]
+        }],
+        ..hrd.clone()
+    };
+    for sps in stream.sps_map.values_mut() {
+        sps.vui_parameters.as_mut().unwrap().vcl_hrd_parameters = Some(slow.clone());
+    }
+    let model = stream.hrd_model(HrdType::Vcl).unwrap();
+    assert!(model.underflow_count() > 0);
+    assert_eq!(model.overflow_count(), 0);
+
+    // A fast CBR HRD delivers the whole stream before the first removal, which
+    // overflows a CPB half its size
+    let cbr = HrdParameters {
+        cpbs: vec![CpbSpec {
+            bit_rate_value_minus1: (50_000_000 >> 6) - 1,
+            cpb_size_value_minus1: ((total / 2) >> 4) as u32 - 1,
+            cbr_flag: true,
+        }],
+        ..hrd
+    };
+    for sps in stream.sps_map.values_mut() {
+        sps.vui_parameters.as_mut().unwrap().nal_hrd_parameters = Some(cbr.clone());
+    }
+    assert!(stream.hrd_model(HrdType::Nal).unwrap().overflow_count() > 0);
+}
@@ -16,6 +16,7 @@
 //! - Frame extraction
 //! - Basic overlay data extraction
 
+use bitvue_avc::pps::Pps;
 use bitvue_avc::{extract_annex_b_frames, parse_avc, parse_nal_units};
 
 #[test]
@@ -121,15 +122,17 @@ fn test_overlay_extraction_api() {
         if let Some(nal_with_sps) = sps_option {
             if let Ok(sps) = bitvue_avc::sps::parse_sps(&nal_with_sps.payload) {
                 // Test QP grid extraction (should not crash, may return scaffold data)
-                let qp_result = bitvue_avc::extract_qp_grid(&nal_units, &sps, 26);
+                let qp_result =
+                    bitvue_avc::extract_qp_grid(&nal_units, &sps, &create_test_pps(), 26);
                 assert!(qp_result.is_ok(), "QP grid extraction should not crash");
 
                 // Test MV grid extraction
-                let mv_result = bitvue_avc::extract_mv_grid(&nal_units, &sps);
+                let mv_result = bitvue_avc::extract_mv_grid(&nal_units, &sps, &create_test_pps());
                 assert!(mv_result.is_ok(), "MV grid extraction should not crash");
 
                 // Test partition grid extraction
-                let part_result = bitvue_avc::extract_partition_grid(&nal_units, &sps);
+                let part_result =
+                    bitvue_avc::extract_partition_grid(&nal_units, &sps, &create_test_pps());
                 assert!(
                     part_result.is_ok(),
                     "Partition grid extraction should not crash"
@@ -182,19 +185,23 @@ fn test_v0_4_avc_support_completeness() {
     // 6. Overlay extraction functions exist
     let sps = create_test_sps();
     assert!(
-        bitvue_avc::extract_qp_grid(&nal_units, &sps, 26).is_ok(),
+        bitvue_avc::extract_qp_grid(&nal_units, &sps, &create_test_pps(), 26).is_ok(),
         "extract_qp_grid should be callable"
     );
     assert!(
-        bitvue_avc::extract_mv_grid(&nal_units, &sps).is_ok(),
+        bitvue_avc::extract_mv_grid(&nal_units, &sps, &create_test_pps()).is_ok(),
         "extract_mv_grid should be callable"
     );
     assert!(
-        bitvue_avc::extract_partition_grid(&nal_units, &sps).is_ok(),
+        bitvue_avc::extract_partition_grid(&nal_units, &sps, &create_test_pps()).is_ok(),
         "extract_partition_grid should be callable"
     );
 }
 
+fn create_test_pps() -> Pps {
+    Pps::default()
+}
+
 /// Create a minimal H.264 byte stream for testing
 fn create_minimal_h264_stream() -> Vec<u8> {
     let mut data = Vec::new();
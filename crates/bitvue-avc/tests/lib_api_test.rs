@@ -382,6 +382,8 @@ fn test_frame_count_with_first_mb_zero() {
         header: create_test_slice_header(0),
         poc: 0,
         frame_num: 0,
+        ref_pic_lists: Default::default(),
+        dpb: Vec::new(),
     };
 
     let slice2 = ParsedSlice {
@@ -389,6 +391,8 @@ fn test_frame_count_with_first_mb_zero() {
         header: create_test_slice_header(1),
         poc: 2,
         frame_num: 1,
+        ref_pic_lists: Default::default(),
+        dpb: Vec::new(),
     };
 
     let stream = create_test_stream(None, None, vec![slice1, slice2]);
@@ -406,6 +410,8 @@ fn test_frame_count_with_non_first_mb_slices() {
         header,
         poc: 0,
         frame_num: 0,
+        ref_pic_lists: Default::default(),
+        dpb: Vec::new(),
     };
 
     let stream = create_test_stream(None, None, vec![slice]);
@@ -430,6 +436,8 @@ fn test_idr_frames_with_non_idr() {
         header: create_test_slice_header(0),
         poc: 0,
         frame_num: 0,
+        ref_pic_lists: Default::default(),
+        dpb: Vec::new(),
     };
 
     // Use NonIdrSlice NAL unit type
@@ -799,6 +807,8 @@ fn test_multiple_slice_same_frame() {
         header: header1,
         poc: 0,
         frame_num: 0,
+        ref_pic_lists: Default::default(),
+        dpb: Vec::new(),
     };
 
     let slice2 = ParsedSlice {
@@ -806,6 +816,8 @@ fn test_multiple_slice_same_frame() {
         header: header2,
         poc: 0,
         frame_num: 0,
+        ref_pic_lists: Default::default(),
+        dpb: Vec::new(),
     };
 
     let stream = create_test_stream(None, None, vec![slice1, slice2]);
@@ -842,6 +854,9 @@ fn create_vui_with_timing(time_scale: u32, num_units_in_tick: u32) -> VuiParamet
         fixed_frame_rate_flag: true,
         nal_hrd_parameters_present_flag: false,
         vcl_hrd_parameters_present_flag: false,
+        nal_hrd_parameters: None,
+        vcl_hrd_parameters: None,
+        low_delay_hrd_flag: false,
         pic_struct_present_flag: false,
         bitstream_restriction_flag: false,
         max_num_reorder_frames: 0,
@@ -874,6 +889,9 @@ fn create_vui_without_timing() -> VuiParameters {
         fixed_frame_rate_flag: false,
         nal_hrd_parameters_present_flag: false,
         vcl_hrd_parameters_present_flag: false,
+        nal_hrd_parameters: None,
+        vcl_hrd_parameters: None,
+        low_delay_hrd_flag: false,
         pic_struct_present_flag: false,
         bitstream_restriction_flag: false,
         max_num_reorder_frames: 0,
@@ -976,6 +994,8 @@ fn test_idr_frames_with_single_idr() {
         header: create_test_slice_header(0),
         poc: 0,
         frame_num: 0,
+        ref_pic_lists: Default::default(),
+        dpb: Vec::new(),
     };
 
     // Use IdrSlice NAL unit type
@@ -1001,6 +1021,8 @@ fn test_idr_frames_with_multiple_idr() {
         header: create_test_slice_header(0),
         poc: 0,
         frame_num: 0,
+        ref_pic_lists: Default::default(),
+        dpb: Vec::new(),
     };
 
     let slice2 = ParsedSlice {
@@ -1008,6 +1030,8 @@ fn test_idr_frames_with_multiple_idr() {
         header: create_test_slice_header(1),
         poc: 2,
         frame_num: 1,
+        ref_pic_lists: Default::default(),
+        dpb: Vec::new(),
     };
 
     // IDR, Non-IDR, IDR
@@ -1036,6 +1060,8 @@ fn test_idr_frames_with_mixed_slices() {
         header: create_test_slice_header(0),
         poc: 0,
         frame_num: 0,
+        ref_pic_lists: Default::default(),
+        dpb: Vec::new(),
     };
 
     let slice2 = ParsedSlice {
@@ -1043,6 +1069,8 @@ fn test_idr_frames_with_mixed_slices() {
         header: create_test_slice_header(1),
         poc: 2,
         frame_num: 1,
+        ref_pic_lists: Default::default(),
+        dpb: Vec::new(),
     };
 
     let slice3 = ParsedSlice {
@@ -1050,6 +1078,8 @@ fn test_idr_frames_with_mixed_slices() {
         header: create_test_slice_header(2),
         poc: 4,
         frame_num: 2,
+        ref_pic_lists: Default::default(),
+        dpb: Vec::new(),
     };
 
     let slice4 = ParsedSlice {
@@ -1057,6 +1087,8 @@ fn test_idr_frames_with_mixed_slices() {
         header: create_test_slice_header(3),
         poc: 6,
         frame_num: 3,
+        ref_pic_lists: Default::default(),
+        dpb: Vec::new(),
     };
 
     // IDR, P, B, IDR pattern
@@ -1087,6 +1119,8 @@ fn test_idr_frames_all_non_idr() {
             header: create_test_slice_header(i as u32),
             poc: (i * 2) as i32,
             frame_num: i as u32,
+            ref_pic_lists: Default::default(),
+            dpb: Vec::new(),
         })
         .collect();
 
@@ -1113,6 +1147,8 @@ fn test_idr_frames_all_idr() {
             header: create_test_slice_header(i as u32),
             poc: (i * 2) as i32,
             frame_num: i as u32,
+            ref_pic_lists: Default::default(),
+            dpb: Vec::new(),
         })
         .collect();
 
@@ -1138,6 +1174,8 @@ fn test_idr_frames_returns_slice_references() {
         header: create_test_slice_header(0),
         poc: 0,
         frame_num: 0,
+        ref_pic_lists: Default::default(),
+        dpb: Vec::new(),
     };
 
     let nal = create_test_nal_unit(NalUnitType::IdrSlice);
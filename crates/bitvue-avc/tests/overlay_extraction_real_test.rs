@@ -17,6 +17,7 @@ use bitvue_avc::nal::{find_nal_units, parse_nal_units, NalUnit, NalUnitHeader, N
 use bitvue_avc::overlay_extraction::{
     extract_mv_grid, extract_partition_grid, extract_qp_grid, Macroblock, MbType, MotionVector,
 };
+use bitvue_avc::pps::Pps;
 use bitvue_avc::sps::{ChromaFormat, ProfileIdc, Sps};
 use bitvue_core::partition_grid::PartitionType;
 
@@ -63,6 +64,10 @@ fn create_test_sps() -> Sps {
     }
 }
 
+fn create_test_pps() -> Pps {
+    Pps::default()
+}
+
 /// Create a test slice NAL unit from real data
 fn create_test_slice_nal(is_idr: bool) -> NalUnit {
     let mut data = Vec::new();
@@ -94,7 +99,7 @@ fn test_extract_qp_grid_empty_nal_units() {
     let sps = create_test_sps();
     let missing = 26;
 
-    let result = extract_qp_grid(&nal_units, &sps, missing);
+    let result = extract_qp_grid(&nal_units, &sps, &create_test_pps(), missing);
     assert!(result.is_ok());
 
     let grid = result.unwrap();
@@ -108,7 +113,7 @@ fn test_extract_qp_grid_with_idr_slice() {
     let sps = create_test_sps();
     let missing = -1;
 
-    let result = extract_qp_grid(&nal_units, &sps, missing);
+    let result = extract_qp_grid(&nal_units, &sps, &create_test_pps(), missing);
     assert!(result.is_ok());
 
     let grid = result.unwrap();
@@ -126,7 +131,7 @@ fn test_extract_qp_grid_missing_value() {
     let sps = create_test_sps();
     let missing = 28;
 
-    let result = extract_qp_grid(&nal_units, &sps, missing);
+    let result = extract_qp_grid(&nal_units, &sps, &create_test_pps(), missing);
     assert!(result.is_ok());
 
     let grid = result.unwrap();
@@ -138,7 +143,7 @@ fn test_extract_mv_grid_empty_nal_units() {
     let nal_units: Vec<NalUnit> = vec![];
     let sps = create_test_sps();
 
-    let result = extract_mv_grid(&nal_units, &sps);
+    let result = extract_mv_grid(&nal_units, &sps, &create_test_pps());
     assert!(result.is_ok());
 
     let grid = result.unwrap();
@@ -151,7 +156,7 @@ fn test_extract_mv_grid_with_idr_slice() {
     let nal_units = vec![create_test_slice_nal(true)];
     let sps = create_test_sps();
 
-    let result = extract_mv_grid(&nal_units, &sps);
+    let result = extract_mv_grid(&nal_units, &sps, &create_test_pps());
     assert!(result.is_ok());
 
     let grid = result.unwrap();
@@ -166,7 +171,7 @@ fn test_extract_mv_grid_with_non_idr_slice() {
     let nal_units = vec![create_test_slice_nal(false)];
     let sps = create_test_sps();
 
-    let result = extract_mv_grid(&nal_units, &sps);
+    let result = extract_mv_grid(&nal_units, &sps, &create_test_pps());
     assert!(result.is_ok());
 
     let grid = result.unwrap();
@@ -180,7 +185,7 @@ fn test_extract_partition_grid_empty_nal_units() {
     let nal_units: Vec<NalUnit> = vec![];
     let sps = create_test_sps();
 
-    let result = extract_partition_grid(&nal_units, &sps);
+    let result = extract_partition_grid(&nal_units, &sps, &create_test_pps());
     assert!(result.is_ok());
 
     let grid = result.unwrap();
@@ -193,7 +198,7 @@ fn test_extract_partition_grid_with_idr_slice() {
     let nal_units = vec![create_test_slice_nal(true)];
     let sps = create_test_sps();
 
-    let result = extract_partition_grid(&nal_units, &sps);
+    let result = extract_partition_grid(&nal_units, &sps, &create_test_pps());
     assert!(result.is_ok());
 
     let grid = result.unwrap();
@@ -205,7 +210,7 @@ fn test_extract_partition_grid_dimensions() {
     let nal_units = vec![create_test_slice_nal(false)];
     let sps = create_test_sps();
 
-    let result = extract_partition_grid(&nal_units, &sps);
+    let result = extract_partition_grid(&nal_units, &sps, &create_test_pps());
     assert!(result.is_ok());
 
     let grid = result.unwrap();
@@ -305,6 +310,11 @@ fn test_macroblock_structure() {
         mv_l1: None,
         ref_idx_l0: None,
         ref_idx_l1: None,
+        partitions: vec![],
+        transform_size_8x8_flag: false,
+        coded_block_pattern: 0,
+        coeff_count: 0,
+        mb_qp_delta: 0,
     };
 
     assert_eq!(mb.mb_addr, 100);
@@ -331,6 +341,11 @@ fn test_macroblock_inter_with_mvs() {
         mv_l1: Some(mv_l1),
         ref_idx_l0: Some(0),
         ref_idx_l1: Some(1),
+        partitions: vec![],
+        transform_size_8x8_flag: false,
+        coded_block_pattern: 0,
+        coeff_count: 0,
+        mb_qp_delta: 0,
     };
 
     assert!(mb.mv_l0.is_some());
@@ -355,6 +370,11 @@ fn test_macroblock_skip() {
         mv_l1: None,
         ref_idx_l0: None,
         ref_idx_l1: None,
+        partitions: vec![],
+        transform_size_8x8_flag: false,
+        coded_block_pattern: 0,
+        coeff_count: 0,
+        mb_qp_delta: 0,
     };
 
     assert!(mb.skip);
@@ -372,7 +392,7 @@ fn test_extract_qp_grid_small_resolution() {
     let nal_units = vec![create_test_slice_nal(true)];
     let missing = 24;
 
-    let result = extract_qp_grid(&nal_units, &sps, missing);
+    let result = extract_qp_grid(&nal_units, &sps, &create_test_pps(), missing);
     assert!(result.is_ok());
 
     let grid = result.unwrap();
@@ -390,7 +410,7 @@ fn test_extract_mv_grid_small_resolution() {
 
     let nal_units = vec![create_test_slice_nal(false)];
 
-    let result = extract_mv_grid(&nal_units, &sps);
+    let result = extract_mv_grid(&nal_units, &sps, &create_test_pps());
     assert!(result.is_ok());
 
     let grid = result.unwrap();
@@ -408,7 +428,7 @@ fn test_extract_partition_grid_small_resolution() {
 
     let nal_units = vec![create_test_slice_nal(false)];
 
-    let result = extract_partition_grid(&nal_units, &sps);
+    let result = extract_partition_grid(&nal_units, &sps, &create_test_pps());
     assert!(result.is_ok());
 
     let grid = result.unwrap();
@@ -427,10 +447,10 @@ fn test_multiple_nal_units() {
     ];
     let sps = create_test_sps();
 
-    let qp_result = extract_qp_grid(&nal_units, &sps, 28);
+    let qp_result = extract_qp_grid(&nal_units, &sps, &create_test_pps(), 28);
     assert!(qp_result.is_ok());
 
-    let part_result = extract_partition_grid(&nal_units, &sps);
+    let part_result = extract_partition_grid(&nal_units, &sps, &create_test_pps());
     assert!(part_result.is_ok());
 
     // MV grid with multiple NAL units may have different behavior
@@ -446,7 +466,7 @@ fn test_extract_with_various_missing_values() {
     let missing_values = vec![0i16, 10, 20, 26, 30, 40, 51];
 
     for missing in missing_values {
-        let result = extract_qp_grid(&nal_units, &sps, missing);
+        let result = extract_qp_grid(&nal_units, &sps, &create_test_pps(), missing);
         assert!(result.is_ok());
         let grid = result.unwrap();
         assert_eq!(grid.missing, missing);
@@ -477,3 +497,60 @@ fn test_all_mb_type_variants() {
         let _ = mb_type.to_partition_type();
     }
 }
+
+#[test]
+fn test_foreman_sample_macroblocks() {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .and_then(|p| p.parent())
+        .unwrap()
+        .join("samples/foreman_h264.264");
+    let Ok(data) = std::fs::read(&path) else {
+        eprintln!("Skipping test: {} not found", path.display());
+        return;
+    };
+    let stream = bitvue_avc::parse_avc(&data).unwrap();
+    assert!(!stream.slices.is_empty());
+
+    // Every slice of the CABAC stream decodes up to its trailing bits
+    for slice in &stream.slices {
+        let pps = stream.get_pps(slice.header.pic_parameter_set_id).unwrap();
+        let sps = stream.get_sps(pps.seq_parameter_set_id).unwrap();
+        let nal = &stream.nal_units[slice.nal_index];
+        let parsed = bitvue_avc::parse_slice_data(nal, sps, pps)
+            .unwrap_or_else(|e| panic!("slice in NAL {} failed: {}", slice.nal_index, e));
+        assert_eq!(parsed.macroblocks.len(), 396);
+        assert!(parsed
+            .macroblocks
+            .iter()
+            .all(|mb| (0..=51).contains(&mb.qp)));
+    }
+
+    // The first picture is a single IDR slice, so every macroblock is intra
+    let first = &stream.slices[0];
+    let pps = stream.get_pps(first.header.pic_parameter_set_id).unwrap();
+    let sps = stream.get_sps(pps.seq_parameter_set_id).unwrap();
+    let nal_units = [stream.nal_units[first.nal_index].clone()];
+
+    let qp_grid = extract_qp_grid(&nal_units, sps, pps, -1).unwrap();
+    assert!(qp_grid.qp.iter().all(|&qp| qp >= 0));
+
+    let mv_grid = extract_mv_grid(&nal_units, sps, pps).unwrap();
+    let modes = mv_grid.mode.unwrap();
+    assert!(modes
+        .iter()
+        .all(|&mode| mode == bitvue_core::BlockMode::Intra));
+
+    let partition_grid = extract_partition_grid(&nal_units, sps, pps).unwrap();
+    assert!(partition_grid.blocks.len() >= 396);
+
+    // Residual is only coded in blocks the coded_block_pattern selects
+    let macroblocks = bitvue_avc::extract_macroblocks(&nal_units, sps, pps).unwrap();
+    assert_eq!(macroblocks.len(), 396);
+    for mb in macroblocks.iter().flatten() {
+        if mb.coded_block_pattern == 0 && mb.mb_type != MbType::I16x16 {
+            assert_eq!(mb.coeff_count, 0);
+        }
+    }
+    assert!(macroblocks.iter().flatten().any(|mb| mb.coeff_count > 0));
+}
@@ -14,10 +14,15 @@
 //! Comprehensive tests for AVC overlay data extraction.
 
 use bitvue_avc::overlay_extraction;
+use bitvue_avc::pps::Pps;
 use bitvue_avc::sps::{ChromaFormat, ProfileIdc, Sps};
 use bitvue_core::partition_grid::PartitionType;
 use bitvue_core::BlockMode;
 
+fn create_minimal_pps() -> Pps {
+    Pps::default()
+}
+
 fn create_minimal_sps() -> Sps {
     Sps {
         profile_idc: ProfileIdc::High,
@@ -65,7 +70,7 @@ fn test_extract_qp_grid_basic() {
     let sps = create_minimal_sps();
 
     let nal_units = [];
-    let result = overlay_extraction::extract_qp_grid(&nal_units, &sps, 26);
+    let result = overlay_extraction::extract_qp_grid(&nal_units, &sps, &create_minimal_pps(), 26);
 
     assert!(result.is_ok());
 
@@ -78,7 +83,7 @@ fn test_extract_mv_grid_basic() {
     let sps = create_minimal_sps();
 
     let nal_units = [];
-    let result = overlay_extraction::extract_mv_grid(&nal_units, &sps);
+    let result = overlay_extraction::extract_mv_grid(&nal_units, &sps, &create_minimal_pps());
 
     assert!(result.is_ok());
 
@@ -91,7 +96,8 @@ fn test_extract_partition_grid_basic() {
     let sps = create_minimal_sps();
 
     let nal_units = [];
-    let result = overlay_extraction::extract_partition_grid(&nal_units, &sps);
+    let result =
+        overlay_extraction::extract_partition_grid(&nal_units, &sps, &create_minimal_pps());
 
     assert!(result.is_ok());
 
@@ -112,6 +118,11 @@ fn test_macroblock_creation() {
         mv_l1: None,
         ref_idx_l0: None,
         ref_idx_l1: None,
+        partitions: vec![],
+        transform_size_8x8_flag: false,
+        coded_block_pattern: 0,
+        coeff_count: 0,
+        mb_qp_delta: 0,
     };
 
     assert_eq!(mb.mb_addr, 0);
@@ -235,6 +246,11 @@ fn test_macroblock_with_motion_vector() {
         mv_l1: None,
         ref_idx_l0: Some(0),
         ref_idx_l1: None,
+        partitions: vec![],
+        transform_size_8x8_flag: false,
+        coded_block_pattern: 0,
+        coeff_count: 0,
+        mb_qp_delta: 0,
     };
 
     assert!(mb.mv_l0.is_some());
@@ -257,6 +273,11 @@ fn test_macroblock_skip() {
         mv_l1: None,
         ref_idx_l0: None,
         ref_idx_l1: None,
+        partitions: vec![],
+        transform_size_8x8_flag: false,
+        coded_block_pattern: 0,
+        coeff_count: 0,
+        mb_qp_delta: 0,
     };
 
     assert!(mb.skip);
@@ -276,6 +297,11 @@ fn test_macroblock_intra() {
         mv_l1: None,
         ref_idx_l0: None,
         ref_idx_l1: None,
+        partitions: vec![],
+        transform_size_8x8_flag: false,
+        coded_block_pattern: 0,
+        coeff_count: 0,
+        mb_qp_delta: 0,
     };
 
     assert!(mb.mb_type.is_intra());
@@ -298,6 +324,11 @@ fn test_macroblock_bipred() {
         mv_l1: Some(mv_l1),
         ref_idx_l0: Some(0),
         ref_idx_l1: Some(1),
+        partitions: vec![],
+        transform_size_8x8_flag: false,
+        coded_block_pattern: 0,
+        coeff_count: 0,
+        mb_qp_delta: 0,
     };
 
     assert!(mb.mv_l0.is_some());
@@ -322,6 +353,11 @@ fn test_macroblock_positions() {
             mv_l1: None,
             ref_idx_l0: None,
             ref_idx_l1: None,
+            partitions: vec![],
+            transform_size_8x8_flag: false,
+            coded_block_pattern: 0,
+            coeff_count: 0,
+            mb_qp_delta: 0,
         };
 
         assert_eq!(mb.x, x);
@@ -345,6 +381,11 @@ fn test_macroblock_addresses() {
             mv_l1: None,
             ref_idx_l0: None,
             ref_idx_l1: None,
+            partitions: vec![],
+            transform_size_8x8_flag: false,
+            coded_block_pattern: 0,
+            coeff_count: 0,
+            mb_qp_delta: 0,
         };
 
         assert_eq!(mb.mb_addr, mb_addr);
@@ -367,6 +408,11 @@ fn test_qp_values() {
             mv_l1: None,
             ref_idx_l0: None,
             ref_idx_l1: None,
+            partitions: vec![],
+            transform_size_8x8_flag: false,
+            coded_block_pattern: 0,
+            coeff_count: 0,
+            mb_qp_delta: 0,
         };
 
         assert_eq!(mb.qp, qp);
@@ -391,13 +437,16 @@ fn test_various_resolutions() {
 
         let nal_units = [];
 
-        let qp_result = overlay_extraction::extract_qp_grid(&nal_units, &sps, 26);
+        let qp_result =
+            overlay_extraction::extract_qp_grid(&nal_units, &sps, &create_minimal_pps(), 26);
         assert!(qp_result.is_ok());
 
-        let mv_result = overlay_extraction::extract_mv_grid(&nal_units, &sps);
+        let mv_result =
+            overlay_extraction::extract_mv_grid(&nal_units, &sps, &create_minimal_pps());
         assert!(mv_result.is_ok());
 
-        let part_result = overlay_extraction::extract_partition_grid(&nal_units, &sps);
+        let part_result =
+            overlay_extraction::extract_partition_grid(&nal_units, &sps, &create_minimal_pps());
         assert!(part_result.is_ok());
     }
 }
@@ -512,6 +561,11 @@ fn test_macroblock_with_ipcm() {
         mv_l1: None,
         ref_idx_l0: None,
         ref_idx_l1: None,
+        partitions: vec![],
+        transform_size_8x8_flag: false,
+        coded_block_pattern: 0,
+        coeff_count: 0,
+        mb_qp_delta: 0,
     };
 
     assert!(mb.mb_type.is_intra());
@@ -531,6 +585,11 @@ fn test_macroblock_bdirect() {
         mv_l1: None,
         ref_idx_l0: None,
         ref_idx_l1: None,
+        partitions: vec![],
+        transform_size_8x8_flag: false,
+        coded_block_pattern: 0,
+        coeff_count: 0,
+        mb_qp_delta: 0,
     };
 
     assert_eq!(mb.mb_type, overlay_extraction::MbType::BDirect);
@@ -560,6 +619,11 @@ fn test_all_b_macroblock_types() {
             mv_l1: None,
             ref_idx_l0: None,
             ref_idx_l1: None,
+            partitions: vec![],
+            transform_size_8x8_flag: false,
+            coded_block_pattern: 0,
+            coeff_count: 0,
+            mb_qp_delta: 0,
         };
 
         assert!(!mb.mb_type.is_intra());
@@ -586,6 +650,11 @@ fn test_all_p_macroblock_types() {
             mv_l1: None,
             ref_idx_l0: None,
             ref_idx_l1: None,
+            partitions: vec![],
+            transform_size_8x8_flag: false,
+            coded_block_pattern: 0,
+            coeff_count: 0,
+            mb_qp_delta: 0,
         };
 
         assert!(!mb.mb_type.is_intra());
@@ -612,6 +681,11 @@ fn test_all_i_macroblock_types() {
             mv_l1: None,
             ref_idx_l0: None,
             ref_idx_l1: None,
+            partitions: vec![],
+            transform_size_8x8_flag: false,
+            coded_block_pattern: 0,
+            coeff_count: 0,
+            mb_qp_delta: 0,
         };
 
         assert!(mb.mb_type.is_intra());
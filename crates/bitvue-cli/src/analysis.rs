@@ -0,0 +1,892 @@
+//! Per-frame analysis shared by the CLI commands
+//!
+//! [`FrameContext`] re-parses the stream around one [`FrameRecord`] and
+//! produces the syntax tree, the coding-flow grids (partition, prediction,
+//! transform, QP) and a per-block residual summary. Syntax bit offsets are
+//! relative to the first byte of the frame's access unit or temporal unit.
+
+use crate::stream::{Codec, FrameRecord, VideoSource};
+use anyhow::{anyhow, bail, Result};
+use bitvue_av1_codec::overlay_extraction::{
+    extract_partition_grid_from_parsed, extract_prediction_mode_grid_from_parsed,
+    extract_qp_grid_from_parsed, extract_transform_grid_from_parsed, parse_all_coding_units,
+    ParsedFrame,
+};
+use bitvue_av1_codec::{ObuIterator, ObuType, SyntaxContext};
+use bitvue_core::mv_overlay::{BlockMode, MVGrid};
+use bitvue_core::partition_grid::{PartitionBlock, PartitionGrid};
+use bitvue_core::qp_heatmap::QPGrid;
+use bitvue_core::types::SyntaxModel;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Range;
+
+/// Codec-neutral syntax tree node
+#[derive(Debug, Clone, Serialize)]
+pub struct SyntaxTree {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bit_offset: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bit_length: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SyntaxTree>,
+}
+
+impl SyntaxTree {
+    fn container(name: impl Into<String>, children: Vec<SyntaxTree>) -> Self {
+        Self {
+            name: name.into(),
+            value: None,
+            bit_offset: None,
+            bit_length: None,
+            children,
+        }
+    }
+
+    /// Shift every bit offset by `-base`, making positions frame-relative
+    fn rebase(&mut self, base: u64) {
+        self.bit_offset = self.bit_offset.map(|o| o.saturating_sub(base));
+        for child in &mut self.children {
+            child.rebase(base);
+        }
+    }
+
+    fn from_model(model: &SyntaxModel) -> Option<Self> {
+        fn build(model: &SyntaxModel, id: &str) -> Option<SyntaxTree> {
+            let node = model.get_node(id)?;
+            Some(SyntaxTree {
+                name: node.field_name.clone(),
+                value: node.value.clone(),
+                bit_offset: Some(node.bit_range.start_bit),
+                bit_length: Some(node.bit_range.size_bits()),
+                children: node
+                    .children
+                    .iter()
+                    .filter_map(|child| build(model, child))
+                    .collect(),
+            })
+        }
+        build(model, &model.root_id)
+    }
+}
+
+/// Convert the per-crate syntax node types, which share the same shape
+macro_rules! impl_from_codec_syntax {
+    ($($node:ty),*) => {$(
+        impl From<$node> for SyntaxTree {
+            fn from(node: $node) -> Self {
+                Self {
+                    name: node.name.into(),
+                    value: node.value,
+                    bit_offset: node.bit_offset,
+                    bit_length: node.bit_length,
+                    children: node.children.into_iter().map(SyntaxTree::from).collect(),
+                }
+            }
+        }
+    )*};
+}
+
+impl_from_codec_syntax!(
+    bitvue_avc::syntax::SyntaxNode,
+    bitvue_hevc::syntax::SyntaxNode,
+    bitvue_vvc::syntax::SyntaxNode,
+    bitvue_vp9::syntax::SyntaxNode
+);
+
+/// Row-major block grid
+#[derive(Debug, Clone, Serialize)]
+pub struct Grid<T> {
+    pub block_w: u32,
+    pub block_h: u32,
+    pub grid_w: u32,
+    pub grid_h: u32,
+    pub values: Vec<T>,
+}
+
+impl From<QPGrid> for Grid<i16> {
+    fn from(grid: QPGrid) -> Self {
+        Self {
+            block_w: grid.block_w,
+            block_h: grid.block_h,
+            grid_w: grid.grid_w,
+            grid_h: grid.grid_h,
+            values: grid.qp,
+        }
+    }
+}
+
+/// Partition layout of a frame
+#[derive(Debug, Clone, Serialize)]
+pub struct Partitions {
+    pub coded_width: u32,
+    pub coded_height: u32,
+    pub sb_size: u32,
+    pub blocks: Vec<PartitionBlock>,
+}
+
+impl From<PartitionGrid> for Partitions {
+    fn from(grid: PartitionGrid) -> Self {
+        Self {
+            coded_width: grid.coded_width,
+            coded_height: grid.coded_height,
+            sb_size: grid.sb_size,
+            blocks: grid.blocks,
+        }
+    }
+}
+
+/// Coding-flow grids produced by the codec `overlay_extraction` modules
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CodingFlow {
+    pub partition: Option<Partitions>,
+    pub prediction: Option<Grid<String>>,
+    pub transform: Option<Grid<String>>,
+    pub qp: Option<Grid<i16>>,
+}
+
+/// Residual information for one block
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockResidual {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub mode: String,
+    /// Whether any residual coefficient of the block is nonzero
+    pub coded: bool,
+    /// Nonzero coefficients over all planes
+    pub coeff_count: u32,
+    /// coded_block_pattern of an H.264 macroblock
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coded_block_pattern: Option<u8>,
+    /// cbf_luma, cbf_cb and cbf_cr of an H.265 transform unit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cbf: Option<[bool; 3]>,
+    pub tx_size: Option<String>,
+    pub qp: Option<i16>,
+}
+
+/// Per-block residual summary of a frame
+///
+/// Built from the residual syntax the codec parsers decode: coded block
+/// flags and the number of nonzero coefficients of each block. Coefficient
+/// values are not dequantized.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResidualSummary {
+    pub total_blocks: usize,
+    pub coded_blocks: usize,
+    pub uncoded_blocks: usize,
+    pub coeff_count: u64,
+    pub tx_size_histogram: BTreeMap<String, usize>,
+    pub blocks: Vec<BlockResidual>,
+}
+
+impl ResidualSummary {
+    fn new(blocks: Vec<BlockResidual>) -> Self {
+        let mut tx_size_histogram = BTreeMap::new();
+        for tx in blocks.iter().filter_map(|b| b.tx_size.as_ref()) {
+            *tx_size_histogram.entry(tx.clone()).or_insert(0) += 1;
+        }
+        let coded_blocks = blocks.iter().filter(|b| b.coded).count();
+
+        Self {
+            total_blocks: blocks.len(),
+            coded_blocks,
+            uncoded_blocks: blocks.len() - coded_blocks,
+            coeff_count: blocks.iter().map(|b| b.coeff_count as u64).sum(),
+            tx_size_histogram,
+            blocks,
+        }
+    }
+}
+
+/// Codec state needed to analyze one frame
+pub enum FrameContext<'a> {
+    Av1 {
+        frame: &'a FrameRecord,
+        /// Sequence header OBU followed by the frame's OBUs
+        temporal_unit: Vec<u8>,
+        sequence_header_len: usize,
+        /// Frames decoded before this one, replayed for reference state
+        earlier: &'a [FrameRecord],
+    },
+    Avc {
+        frame: &'a FrameRecord,
+        stream: bitvue_avc::AvcStream,
+        nal_range: Range<usize>,
+    },
+    Hevc {
+        frame: &'a FrameRecord,
+        stream: bitvue_hevc::HevcStream,
+        nal_range: Range<usize>,
+    },
+    Vvc {
+        frame: &'a FrameRecord,
+        stream: bitvue_vvc::VvcStream,
+        nal_range: Range<usize>,
+    },
+    Vp9 {
+        frame: &'a FrameRecord,
+        header: bitvue_vp9::FrameHeader,
+    },
+}
+
+impl<'a> FrameContext<'a> {
+    /// Prepare the analysis context for `frames[index]`
+    pub fn new(source: &VideoSource, frames: &'a [FrameRecord], index: usize) -> Result<Self> {
+        let frame = frames.get(index).ok_or_else(|| {
+            anyhow!(
+                "Frame {} out of range (stream has {} frames)",
+                index,
+                frames.len()
+            )
+        })?;
+
+        Ok(match source.codec {
+            Codec::Av1 => {
+                let sequence_header = find_sequence_header(source).unwrap_or_default();
+                let mut temporal_unit = sequence_header.clone();
+                temporal_unit.extend_from_slice(&frame.data);
+                FrameContext::Av1 {
+                    frame,
+                    temporal_unit,
+                    sequence_header_len: sequence_header.len(),
+                    earlier: &frames[..index],
+                }
+            }
+            Codec::Avc => {
+                let (es, _) = source.elementary_stream();
+                let stream = bitvue_avc::parse_avc(&es)
+                    .map_err(|e| anyhow!("H.264 parsing failed: {}", e))?;
+                let nal_range = locate_access_unit(avc_access_units(&stream), frames.len(), index)?;
+                FrameContext::Avc {
+                    frame,
+                    stream,
+                    nal_range,
+                }
+            }
+            Codec::Hevc => {
+                let (es, _) = source.elementary_stream();
+                let stream = bitvue_hevc::parse_hevc(&es)
+                    .map_err(|e| anyhow!("H.265 parsing failed: {}", e))?;
+                let nal_range =
+                    locate_access_unit(hevc_access_units(&stream), frames.len(), index)?;
+                FrameContext::Hevc {
+                    frame,
+                    stream,
+                    nal_range,
+                }
+            }
+            Codec::Vvc => {
+                let (es, _) = source.elementary_stream();
+                let stream = bitvue_vvc::parse_vvc(&es)
+                    .map_err(|e| anyhow!("H.266 parsing failed: {}", e))?;
+                let nal_range = locate_access_unit(vvc_access_units(&stream), frames.len(), index)?;
+                FrameContext::Vvc {
+                    frame,
+                    stream,
+                    nal_range,
+                }
+            }
+            Codec::Vp9 => {
+                let parse = |data: &[u8]| {
+                    bitvue_vp9::frame_header::parse_frame_header(data)
+                        .map_err(|e| anyhow!("VP9 frame header parsing failed: {}", e))
+                };
+                let mut header = parse(&frame.data)?;
+                // Frames using size_with_refs carry no dimensions; inherit them
+                // from the closest preceding frame that does
+                if header.width == 0 || header.height == 0 {
+                    let sized = frames[..index]
+                        .iter()
+                        .rev()
+                        .filter_map(|f| parse(&f.data).ok())
+                        .find(|h| h.width > 0 && h.height > 0);
+                    if let Some(sized) = sized {
+                        header.width = sized.width;
+                        header.height = sized.height;
+                    }
+                }
+                FrameContext::Vp9 { frame, header }
+            }
+        })
+    }
+
+    /// Syntax tree of the frame, with frame-relative bit offsets
+    pub fn syntax(&self) -> Result<SyntaxTree> {
+        match self {
+            FrameContext::Av1 {
+                frame,
+                temporal_unit,
+                sequence_header_len,
+                earlier,
+            } => {
+                // Frame header syntax depends on the sequence header and on
+                // the reference slots, so replay the preceding headers first
+                let (sequence_header, data) = temporal_unit.split_at(*sequence_header_len);
+                let mut context = SyntaxContext::new();
+                let replay = std::iter::once(sequence_header)
+                    .chain(earlier.iter().map(|earlier_frame| &earlier_frame.data[..]));
+                for chunk in replay {
+                    let mut iter = ObuIterator::new(chunk);
+                    while let Some(Ok(item)) = iter.next_obu_with_offset() {
+                        let bytes = &chunk[item.offset..item.offset + item.consumed];
+                        // Only the context update matters here
+                        let _ = context.parse_obu(bytes, 0, 0);
+                    }
+                }
+
+                let mut obus = Vec::new();
+                let mut iter = ObuIterator::new(data);
+                while let Some(next) = iter.next_obu_with_offset() {
+                    let Ok(item) = next else { break };
+                    let bytes = &data[item.offset..item.offset + item.consumed];
+                    let model = context
+                        .parse_obu(bytes, obus.len(), item.offset as u64 * 8)
+                        .map_err(|e| anyhow!("OBU syntax parsing failed: {}", e))?;
+                    obus.extend(SyntaxTree::from_model(&model));
+                }
+                Ok(frame_root(frame, obus))
+            }
+            FrameContext::Avc {
+                frame,
+                stream,
+                nal_range,
+            } => {
+                let mut tree: SyntaxTree =
+                    bitvue_avc::syntax::build_access_unit_tree(stream, nal_range.clone()).into();
+                let base = stream.nal_units[nal_range.start].offset as u64 * 8;
+                tree.rebase(base);
+                Ok(frame_root(frame, tree.children))
+            }
+            FrameContext::Hevc {
+                frame,
+                stream,
+                nal_range,
+            } => {
+                let mut tree: SyntaxTree =
+                    bitvue_hevc::syntax::build_access_unit_tree(stream, nal_range.clone()).into();
+                tree.rebase(stream.nal_units[nal_range.start].offset * 8);
+                Ok(frame_root(frame, tree.children))
+            }
+            FrameContext::Vvc {
+                frame,
+                stream,
+                nal_range,
+            } => {
+                let mut tree: SyntaxTree =
+                    bitvue_vvc::syntax::build_access_unit_tree(stream, nal_range.clone()).into();
+                tree.rebase(stream.nal_units[nal_range.start].offset * 8);
+                Ok(frame_root(frame, tree.children))
+            }
+            FrameContext::Vp9 { frame, header } => {
+                let mut tree: SyntaxTree =
+                    bitvue_vp9::syntax::build_frame_tree(frame.decode_index, header).into();
+                tree.bit_offset = Some(0);
+                tree.bit_length = Some(frame.size as u64 * 8);
+                Ok(frame_root(frame, vec![tree]))
+            }
+        }
+    }
+
+    /// Partition, prediction, transform and QP grids
+    pub fn coding_flow(&self) -> Result<CodingFlow> {
+        match self {
+            FrameContext::Av1 { frame, .. } => {
+                let parsed = self.av1_parsed_frame()?;
+                let base_qp = parsed.frame_type.base_qp.map(i16::from).unwrap_or(0);
+                let prediction = extract_prediction_mode_grid_from_parsed(&parsed).ok();
+                let transform = extract_transform_grid_from_parsed(&parsed).ok();
+
+                Ok(CodingFlow {
+                    partition: extract_partition_grid_from_parsed(&parsed)
+                        .ok()
+                        .map(Partitions::from),
+                    prediction: prediction.map(|g| Grid {
+                        block_w: g.block_w,
+                        block_h: g.block_h,
+                        grid_w: g.grid_w,
+                        grid_h: g.grid_h,
+                        values: g.modes.iter().map(|m| debug_name(m.as_ref())).collect(),
+                    }),
+                    transform: transform.map(|g| Grid {
+                        block_w: g.block_w,
+                        block_h: g.block_h,
+                        grid_w: g.grid_w,
+                        grid_h: g.grid_h,
+                        values: g.tx_sizes.iter().map(|t| debug_name(t.as_ref())).collect(),
+                    }),
+                    qp: extract_qp_grid_from_parsed(&parsed, frame.decode_index, base_qp)
+                        .ok()
+                        .map(Grid::from),
+                })
+            }
+            FrameContext::Avc {
+                frame,
+                stream,
+                nal_range,
+            } => {
+                let (sps, pps) = avc_parameter_sets(stream, nal_range, frame)?;
+                let nals = &stream.nal_units[nal_range.clone()];
+                let base_qp = frame.qp.unwrap_or(26) as i16;
+
+                Ok(CodingFlow {
+                    partition: bitvue_avc::extract_partition_grid(nals, sps, pps)
+                        .ok()
+                        .map(Partitions::from),
+                    prediction: bitvue_avc::extract_mv_grid(nals, sps, pps)
+                        .ok()
+                        .and_then(mode_grid),
+                    transform: None,
+                    qp: bitvue_avc::extract_qp_grid(nals, sps, pps, base_qp)
+                        .ok()
+                        .map(Grid::from),
+                })
+            }
+            FrameContext::Hevc {
+                frame,
+                stream,
+                nal_range,
+            } => {
+                let (sps, pps) = hevc_parameter_sets(stream, nal_range, frame)?;
+                let nals = &stream.nal_units[nal_range.clone()];
+                let base_qp = frame.qp.unwrap_or(26) as i16;
+
+                Ok(CodingFlow {
+                    partition: bitvue_hevc::extract_partition_grid(nals, sps, pps)
+                        .ok()
+                        .map(Partitions::from),
+                    prediction: bitvue_hevc::extract_mv_grid(nals, sps, pps)
+                        .ok()
+                        .and_then(mode_grid),
+                    transform: None,
+                    qp: bitvue_hevc::extract_qp_grid(nals, sps, pps, base_qp)
+                        .ok()
+                        .map(Grid::from),
+                })
+            }
+            FrameContext::Vvc {
+                frame,
+                stream,
+                nal_range,
+            } => {
+                let sps = stream
+                    .slices
+                    .iter()
+                    .find(|s| nal_range.contains(&s.nal_index))
+                    .and_then(|s| stream.get_pps(s.header.slice_pic_parameter_set_id))
+                    .and_then(|pps| stream.get_sps(pps.pps_seq_parameter_set_id))
+                    .ok_or_else(|| anyhow!("No active SPS for frame {}", frame.decode_index))?;
+                let nals = &stream.nal_units[nal_range.clone()];
+                let base_qp = frame.qp.unwrap_or(26) as i16;
+
+                Ok(CodingFlow {
+                    partition: bitvue_vvc::extract_partition_grid(nals, sps)
+                        .ok()
+                        .map(Partitions::from),
+                    prediction: bitvue_vvc::extract_mv_grid(nals, sps)
+                        .ok()
+                        .and_then(mode_grid),
+                    transform: None,
+                    qp: bitvue_vvc::extract_qp_grid(nals, sps, base_qp)
+                        .ok()
+                        .map(Grid::from),
+                })
+            }
+            FrameContext::Vp9 { header, .. } => Ok(CodingFlow {
+                partition: bitvue_vp9::extract_partition_grid(header)
+                    .ok()
+                    .map(Partitions::from),
+                prediction: bitvue_vp9::extract_mv_grid(header).ok().and_then(mode_grid),
+                transform: None,
+                qp: bitvue_vp9::extract_qp_grid(header).ok().map(Grid::from),
+            }),
+        }
+    }
+
+    /// Per-block residual summary, or `None` when the codec parser does not
+    /// decode residual syntax (H.266, VP9)
+    pub fn residual(&self) -> Result<Option<ResidualSummary>> {
+        let blocks = match self {
+            FrameContext::Av1 { .. } => {
+                let parsed = self.av1_parsed_frame()?;
+                let coding_units = parse_all_coding_units(&parsed)
+                    .map_err(|e| anyhow!("AV1 tile decoding failed: {}", e))?;
+                coding_units
+                    .iter()
+                    .map(|cu| BlockResidual {
+                        x: cu.x,
+                        y: cu.y,
+                        width: cu.width,
+                        height: cu.height,
+                        mode: format!("{:?}", cu.mode),
+                        coded: cu.coeff_count > 0,
+                        coeff_count: cu.coeff_count,
+                        coded_block_pattern: None,
+                        cbf: None,
+                        tx_size: Some(format!("{:?}", cu.tx_size)),
+                        qp: cu.qp,
+                    })
+                    .collect()
+            }
+            FrameContext::Avc {
+                frame,
+                stream,
+                nal_range,
+            } => {
+                let (sps, pps) = avc_parameter_sets(stream, nal_range, frame)?;
+                let macroblocks =
+                    bitvue_avc::extract_macroblocks(&stream.nal_units[nal_range.clone()], sps, pps)
+                        .map_err(|e| anyhow!("H.264 macroblock parsing failed: {}", e))?;
+                macroblocks
+                    .into_iter()
+                    .flatten()
+                    .map(|mb| BlockResidual {
+                        x: mb.x,
+                        y: mb.y,
+                        width: 16,
+                        height: 16,
+                        mode: format!("{:?}", mb.mb_type),
+                        coded: mb.coeff_count > 0,
+                        coeff_count: mb.coeff_count,
+                        coded_block_pattern: Some(mb.coded_block_pattern),
+                        cbf: None,
+                        tx_size: match mb.mb_type {
+                            bitvue_avc::MbType::IPCM => None,
+                            _ if mb.transform_size_8x8_flag => Some("Tx8x8".to_string()),
+                            _ => Some("Tx4x4".to_string()),
+                        },
+                        qp: Some(mb.qp),
+                    })
+                    .collect()
+            }
+            FrameContext::Hevc {
+                frame,
+                stream,
+                nal_range,
+            } => {
+                let (sps, pps) = hevc_parameter_sets(stream, nal_range, frame)?;
+                let ctus = bitvue_hevc::extract_coding_tree_units(
+                    &stream.nal_units[nal_range.clone()],
+                    sps,
+                    pps,
+                )
+                .map_err(|e| anyhow!("H.265 coding tree parsing failed: {}", e))?;
+                let mut blocks = Vec::new();
+                for cu in ctus.iter().flat_map(|ctu| &ctu.coding_units) {
+                    // Skipped CUs and CUs with rqt_root_cbf equal to 0 carry
+                    // no transform tree
+                    if cu.transform_units.is_empty() {
+                        let size = cu.size as u32;
+                        blocks.push(BlockResidual {
+                            x: cu.x,
+                            y: cu.y,
+                            width: size,
+                            height: size,
+                            mode: format!("{:?}", cu.pred_mode),
+                            coded: false,
+                            coeff_count: 0,
+                            coded_block_pattern: None,
+                            cbf: Some([false; 3]),
+                            tx_size: None,
+                            qp: Some(cu.qp),
+                        });
+                    }
+                    for tu in &cu.transform_units {
+                        let size = tu.size as u32;
+                        blocks.push(BlockResidual {
+                            x: tu.x,
+                            y: tu.y,
+                            width: size,
+                            height: size,
+                            mode: format!("{:?}", cu.pred_mode),
+                            coded: tu.coeff_count > 0,
+                            coeff_count: tu.coeff_count,
+                            coded_block_pattern: None,
+                            cbf: Some([tu.cbf_luma, tu.cbf_cb, tu.cbf_cr]),
+                            tx_size: Some(format!("Tx{}x{}", size, size)),
+                            qp: Some(cu.qp),
+                        });
+                    }
+                }
+                blocks
+            }
+            FrameContext::Vvc { .. } | FrameContext::Vp9 { .. } => return Ok(None),
+        };
+        Ok(Some(ResidualSummary::new(blocks)))
+    }
+
+    /// AV1 data for the tile decoder: the sequence header, every earlier
+    /// frame and this frame
+    fn av1_parsed_frame(&self) -> Result<ParsedFrame> {
+        let FrameContext::Av1 {
+            temporal_unit,
+            sequence_header_len,
+            earlier,
+            ..
+        } = self
+        else {
+            bail!("Not an AV1 frame");
+        };
+        // Inter frames inherit CDFs, motion vectors and segment maps from
+        // their references, so decode from the start of the stream
+        let (sequence_header, frame_data) = temporal_unit.split_at(*sequence_header_len);
+        let mut stream = sequence_header.to_vec();
+        for earlier_frame in *earlier {
+            stream.extend_from_slice(&earlier_frame.data);
+        }
+        stream.extend_from_slice(frame_data);
+
+        ParsedFrame::parse(&stream).map_err(|e| anyhow!("AV1 frame parsing failed: {}", e))
+    }
+}
+
+/// SPS and PPS of the first slice of an H.264 access unit
+fn avc_parameter_sets<'s>(
+    stream: &'s bitvue_avc::AvcStream,
+    nal_range: &Range<usize>,
+    frame: &FrameRecord,
+) -> Result<(&'s bitvue_avc::Sps, &'s bitvue_avc::Pps)> {
+    stream
+        .slices
+        .iter()
+        .find(|s| nal_range.contains(&s.nal_index))
+        .and_then(|s| stream.get_pps(s.header.pic_parameter_set_id))
+        .and_then(|pps| Some((stream.get_sps(pps.seq_parameter_set_id)?, pps)))
+        .ok_or_else(|| anyhow!("No active SPS for frame {}", frame.decode_index))
+}
+
+/// SPS and PPS of the first slice segment of an H.265 access unit
+fn hevc_parameter_sets<'s>(
+    stream: &'s bitvue_hevc::HevcStream,
+    nal_range: &Range<usize>,
+    frame: &FrameRecord,
+) -> Result<(&'s bitvue_hevc::Sps, &'s bitvue_hevc::Pps)> {
+    stream
+        .slices
+        .iter()
+        .find(|s| nal_range.contains(&s.nal_index))
+        .and_then(|s| stream.get_pps(s.header.slice_pic_parameter_set_id))
+        .and_then(|pps| Some((stream.get_sps(pps.pps_seq_parameter_set_id)?, pps)))
+        .ok_or_else(|| anyhow!("No active SPS for frame {}", frame.decode_index))
+}
+
+/// Wrap the per-unit trees in a root node describing the frame
+fn frame_root(frame: &FrameRecord, children: Vec<SyntaxTree>) -> SyntaxTree {
+    let mut root = SyntaxTree::container(
+        format!("frame[{}] {}", frame.decode_index, frame.frame_type),
+        children,
+    );
+    root.bit_offset = Some(0);
+    root.bit_length = Some(frame.size as u64 * 8);
+    root
+}
+
+/// Prediction grid from the block modes of an MV grid (Intra/Inter/Skip)
+fn mode_grid(grid: MVGrid) -> Option<Grid<String>> {
+    let modes = grid.mode?;
+    Some(Grid {
+        block_w: grid.block_w,
+        block_h: grid.block_h,
+        grid_w: grid.grid_w,
+        grid_h: grid.grid_h,
+        values: modes
+            .iter()
+            .map(|m| match m {
+                BlockMode::None => "-".to_string(),
+                BlockMode::Inter => "Inter".to_string(),
+                BlockMode::Intra => "Intra".to_string(),
+                BlockMode::Skip => "Skip".to_string(),
+            })
+            .collect(),
+    })
+}
+
+fn debug_name<T: std::fmt::Debug>(value: Option<&T>) -> String {
+    value
+        .map(|v| format!("{:?}", v))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// First sequence header OBU in the stream, as raw bytes
+pub fn find_sequence_header(source: &VideoSource) -> Option<Vec<u8>> {
+    source.samples.iter().find_map(|sample| {
+        let mut iter = ObuIterator::new(&sample.data);
+        while let Some(Ok(item)) = iter.next_obu_with_offset() {
+            if item.obu.header.obu_type == ObuType::SequenceHeader {
+                return Some(sample.data[item.offset..item.offset + item.consumed].to_vec());
+            }
+        }
+        None
+    })
+}
+
+/// NAL index ranges of the access units of an H.264 stream, in decode order
+pub fn avc_access_units(stream: &bitvue_avc::AvcStream) -> Vec<Range<usize>> {
+    use bitvue_avc::NalUnitType::*;
+
+    let first_slices: HashSet<usize> = stream
+        .slices
+        .iter()
+        .filter(|s| s.header.is_first_slice())
+        .map(|s| s.nal_index)
+        .collect();
+    let flags: Vec<(bool, bool)> = stream
+        .nal_units
+        .iter()
+        .enumerate()
+        .map(|(i, nal)| (nal.header.nal_unit_type.is_vcl(), first_slices.contains(&i)))
+        .collect();
+    access_unit_ranges(&flags, |i| {
+        matches!(
+            stream.nal_units[i].header.nal_unit_type,
+            EndOfSequence | EndOfStream
+        )
+    })
+}
+
+/// NAL index ranges of the access units of an H.265 stream, in decode order
+pub fn hevc_access_units(stream: &bitvue_hevc::HevcStream) -> Vec<Range<usize>> {
+    use bitvue_hevc::NalUnitType::*;
+
+    let first_slices: HashSet<usize> = stream
+        .slices
+        .iter()
+        .filter(|s| s.header.first_slice_segment_in_pic_flag)
+        .map(|s| s.nal_index)
+        .collect();
+    let flags: Vec<(bool, bool)> = stream
+        .nal_units
+        .iter()
+        .enumerate()
+        .map(|(i, nal)| (nal.header.nal_unit_type.is_vcl(), first_slices.contains(&i)))
+        .collect();
+    access_unit_ranges(&flags, |i| {
+        matches!(
+            stream.nal_units[i].header.nal_unit_type,
+            SuffixSeiNut | EosNut | EobNut
+        )
+    })
+}
+
+/// NAL index ranges of the access units of an H.266 stream, in decode order
+///
+/// A picture starts at a slice preceded by a picture header NAL unit or
+/// carrying its own picture header, as in `bitvue_vvc::frames`.
+pub fn vvc_access_units(stream: &bitvue_vvc::VvcStream) -> Vec<Range<usize>> {
+    use bitvue_vvc::NalUnitType::*;
+
+    let mut first_slices = HashSet::new();
+    let mut prev_nal_index = 0;
+    for slice in &stream.slices {
+        let after_ph = stream.nal_units[prev_nal_index..slice.nal_index]
+            .iter()
+            .any(|n| n.header.nal_unit_type == PhNut);
+        if first_slices.is_empty()
+            || after_ph
+            || slice.header.sh_picture_header_in_slice_header_flag
+        {
+            first_slices.insert(slice.nal_index);
+        }
+        prev_nal_index = slice.nal_index + 1;
+    }
+    let flags: Vec<(bool, bool)> = stream
+        .nal_units
+        .iter()
+        .enumerate()
+        .map(|(i, nal)| (nal.header.nal_unit_type.is_vcl(), first_slices.contains(&i)))
+        .collect();
+    access_unit_ranges(&flags, |i| {
+        matches!(
+            stream.nal_units[i].header.nal_unit_type,
+            SuffixSeiNut | SuffixApsNut | EosNut | EobNut
+        )
+    })
+}
+
+/// Pick access unit `index`, checking that the grouping matches the frames
+fn locate_access_unit(
+    units: Vec<Range<usize>>,
+    frame_count: usize,
+    index: usize,
+) -> Result<Range<usize>> {
+    if units.len() != frame_count {
+        bail!(
+            "Found {} access units but {} frames; cannot locate frame {}",
+            units.len(),
+            frame_count,
+            index
+        );
+    }
+    Ok(units[index].clone())
+}
+
+/// Group NAL units into access units
+///
+/// `flags[i]` is `(is_vcl, starts_picture)` for NAL unit `i`; `is_suffix`
+/// marks non-VCL NAL units that trail a picture (suffix SEI, end of
+/// sequence). Every other non-VCL NAL unit after a picture opens the next
+/// access unit.
+fn access_unit_ranges(
+    flags: &[(bool, bool)],
+    is_suffix: impl Fn(usize) -> bool,
+) -> Vec<Range<usize>> {
+    let mut starts = Vec::new();
+    let mut pending: Option<usize> = None;
+
+    for (i, &(is_vcl, starts_picture)) in flags.iter().enumerate() {
+        if is_vcl {
+            if starts_picture {
+                starts.push(if starts.is_empty() {
+                    0
+                } else {
+                    pending.unwrap_or(i)
+                });
+            }
+            pending = None;
+        } else if pending.is_none() && !is_suffix(i) {
+            pending = Some(i);
+        }
+    }
+
+    let ends = starts
+        .iter()
+        .skip(1)
+        .copied()
+        .chain(std::iter::once(flags.len()));
+    starts
+        .iter()
+        .zip(ends)
+        .map(|(&start, end)| start..end)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_access_unit_ranges() {
+        // SPS, PPS, IDR | SEI, P, suffix | AUD, B (2 slices)
+        let flags = [
+            (false, false),
+            (false, false),
+            (true, true),
+            (false, false),
+            (true, true),
+            (false, false),
+            (false, false),
+            (true, true),
+            (true, false),
+        ];
+        let suffix = |i: usize| i == 5;
+
+        let units = access_unit_ranges(&flags, suffix);
+        assert_eq!(units, vec![0..3, 3..6, 6..9]);
+        assert!(locate_access_unit(units, 4, 0).is_err());
+    }
+}
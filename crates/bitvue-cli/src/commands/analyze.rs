@@ -1,25 +1,236 @@
 //! Analyze a specific frame
 
-use anyhow::Result;
+use crate::analysis::{CodingFlow, FrameContext, Grid, ResidualSummary, SyntaxTree};
+use crate::output::{opt, OutputFormat};
+use crate::stream::{FrameRecord, VideoSource};
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
+/// JSON document emitted by `analyze --format json`
+#[derive(Serialize)]
+struct FrameAnalysis<'a> {
+    file: String,
+    codec: &'a str,
+    frame: &'a FrameRecord,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    syntax: Option<SyntaxTree>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    coding_flow: Option<CodingFlow>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    residual: Option<ResidualSummary>,
+}
+
 pub fn run(
     file_path: PathBuf,
     frame: usize,
     syntax: bool,
     residual: bool,
     coding_flow: bool,
+    format: &str,
 ) -> Result<()> {
-    println!("Analyze command: {} frame: {}", file_path.display(), frame);
-    if syntax {
-        println!("  - Syntax analysis enabled");
+    let format: OutputFormat = format.parse()?;
+    if format == OutputFormat::Csv {
+        bail!("CSV output is not supported for analyze (use text or json)");
     }
+
+    // Without any section flag, show the syntax tree
+    let syntax = syntax || !(residual || coding_flow);
+
+    let source = VideoSource::open(&file_path)?;
+    let frames = source.frames()?;
+    let ctx = FrameContext::new(&source, &frames, frame)?;
+
+    let syntax_tree = if syntax { Some(ctx.syntax()?) } else { None };
+    let flow = if coding_flow {
+        Some(ctx.coding_flow()?)
+    } else {
+        None
+    };
+    let residual_summary = if residual { ctx.residual()? } else { None };
+
+    let doc = FrameAnalysis {
+        file: file_path.display().to_string(),
+        codec: source.codec.name(),
+        frame: &frames[frame],
+        syntax: syntax_tree,
+        coding_flow: flow,
+        residual: residual_summary,
+    };
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&doc)?),
+        _ => print_text(&source, &doc, residual),
+    }
+
+    Ok(())
+}
+
+fn print_text(source: &VideoSource, doc: &FrameAnalysis, residual: bool) {
+    let f = doc.frame;
+    println!(
+        "File: {} ({}, {})",
+        doc.file, source.container, source.codec
+    );
+    println!(
+        "Frame {}: {} (display {}, {} bytes at offset {}, QP {})",
+        f.decode_index,
+        f.frame_type,
+        opt(f.display_index),
+        f.size,
+        opt(f.offset),
+        opt(f.qp)
+    );
+
+    if let Some(tree) = &doc.syntax {
+        println!();
+        println!("Syntax (bit offsets relative to frame start):");
+        print_tree(tree, 1);
+    }
+
+    if let Some(flow) = &doc.coding_flow {
+        println!();
+        print_coding_flow(flow);
+    }
+
     if residual {
-        println!("  - Residual analysis enabled");
+        println!();
+        match &doc.residual {
+            Some(summary) => print_residual(summary),
+            None => println!(
+                "Residual: unavailable ({} residual syntax is not decoded)",
+                source.codec
+            ),
+        }
     }
-    if coding_flow {
-        println!("  - Coding flow analysis enabled");
+}
+
+fn print_tree(node: &SyntaxTree, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let position = match (node.bit_offset, node.bit_length) {
+        (Some(offset), Some(length)) => format!("  @{}+{}", offset, length),
+        (Some(offset), None) => format!("  @{}", offset),
+        _ => String::new(),
+    };
+    match &node.value {
+        Some(value) => println!("{}{} = {}{}", indent, node.name, value, position),
+        None => println!("{}{}{}", indent, node.name, position),
+    }
+    for child in &node.children {
+        print_tree(child, depth + 1);
+    }
+}
+
+fn print_coding_flow(flow: &CodingFlow) {
+    println!("Coding flow:");
+
+    match &flow.partition {
+        Some(partition) => {
+            println!(
+                "  Partition: {}x{} coded, {} blocks, superblock {}",
+                partition.coded_width,
+                partition.coded_height,
+                partition.blocks.len(),
+                partition.sb_size
+            );
+            let mut sizes: BTreeMap<(u32, u32), usize> = BTreeMap::new();
+            for block in &partition.blocks {
+                *sizes.entry((block.width, block.height)).or_insert(0) += 1;
+            }
+            for ((w, h), count) in sizes.iter().rev() {
+                println!("    {:>3}x{:<3} {:>6}", w, h, count);
+            }
+        }
+        None => println!("  Partition: not available"),
+    }
+
+    match &flow.prediction {
+        Some(grid) => {
+            println!(
+                "  Prediction ({}x{} blocks; I=intra P=inter S=skip -=none):",
+                grid.block_w, grid.block_h
+            );
+            print_grid(grid, |mode| prediction_symbol(mode).to_string());
+        }
+        None => println!("  Prediction: not available"),
+    }
+
+    if let Some(grid) = &flow.transform {
+        println!("  Transform ({}x{} blocks):", grid.block_w, grid.block_h);
+        let mut sizes: BTreeMap<&str, usize> = BTreeMap::new();
+        for tx in &grid.values {
+            *sizes.entry(tx.as_str()).or_insert(0) += 1;
+        }
+        for (tx, count) in sizes {
+            println!("    {:<10} {:>6}", tx, count);
+        }
+    }
+
+    match &flow.qp {
+        Some(grid) => {
+            println!("  QP ({}x{} blocks):", grid.block_w, grid.block_h);
+            print_grid(grid, |qp| format!("{:>3}", qp));
+        }
+        None => println!("  QP: not available"),
+    }
+}
+
+fn print_grid<T>(grid: &Grid<T>, cell: impl Fn(&T) -> String) {
+    for row in grid.values.chunks(grid.grid_w.max(1) as usize) {
+        let cells: Vec<String> = row.iter().map(&cell).collect();
+        println!("    {}", cells.join(" "));
+    }
+}
+
+fn prediction_symbol(mode: &str) -> &'static str {
+    match mode {
+        "Intra" => "I",
+        "Inter" => "P",
+        "Skip" => "S",
+        "-" => "-",
+        // AV1 intra modes are named *Pred, inter modes are named after MVs
+        m if m.ends_with("Pred") => "I",
+        _ => "P",
+    }
+}
+
+fn print_residual(summary: &ResidualSummary) {
+    println!(
+        "Residual: {} blocks, {} coded, {} without residual, {} nonzero coefficients",
+        summary.total_blocks, summary.coded_blocks, summary.uncoded_blocks, summary.coeff_count
+    );
+    for (tx, count) in &summary.tx_size_histogram {
+        println!("  {:<10} {:>6}", tx, count);
+    }
+    println!();
+    println!(
+        "  {:>5} {:>5} {:>9} {:<12} {:<6} {:>6} {:<8} {:<10} {:>4}",
+        "X", "Y", "SIZE", "MODE", "CODED", "COEFFS", "CBP/CBF", "TX", "QP"
+    );
+    for block in &summary.blocks {
+        let flags = match (block.coded_block_pattern, block.cbf) {
+            (Some(cbp), _) => Some(format!("0x{:02X}", cbp)),
+            (None, Some(cbf)) => Some(
+                ["Y", "U", "V"]
+                    .iter()
+                    .zip(cbf)
+                    .map(|(plane, coded)| if coded { *plane } else { "-" })
+                    .collect(),
+            ),
+            (None, None) => None,
+        };
+        println!(
+            "  {:>5} {:>5} {:>9} {:<12} {:<6} {:>6} {:<8} {:<10} {:>4}",
+            block.x,
+            block.y,
+            format!("{}x{}", block.width, block.height),
+            block.mode,
+            if block.coded { "yes" } else { "no" },
+            block.coeff_count,
+            opt(flags),
+            opt(block.tx_size.as_deref()),
+            opt(block.qp)
+        );
     }
-    // TODO: Implement frame analysis
-    Ok(())
 }
@@ -1,15 +1,386 @@
 //! Batch process multiple files
+//!
+//! Every input matching `--pattern` under `--directory` is opened, its frames
+//! are listed and it is validated. The result for `<dir>/a/clip.ivf` is
+//! written to `<output>/a/clip.ivf.json`, and `index.csv` / `index.md` in the
+//! output directory summarise all inputs.
+//!
+//! Results are written atomically, so an interrupted run can be restarted:
+//! inputs whose result is newer than the input file are not processed again
+//! unless `--force` is given.
 
-use anyhow::Result;
-use std::path::PathBuf;
+use crate::output::{csv_field, csv_opt, opt};
+use crate::stream::{FrameRecord, VideoSource};
+use crate::summary::StreamSummary;
+use crate::validation::Validator;
+use anyhow::{bail, Context, Result};
+use bitvue_core::diagnostics::{Diagnostic, SeverityCounts};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Per-input fields that make up one row of the index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    /// Input path relative to the batch directory
+    file: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    summary: Option<StreamSummary>,
+    #[serde(default)]
+    counts: SeverityCounts,
+}
+
+/// JSON document written for every input
+#[derive(Serialize)]
+struct BatchResult<'a> {
+    #[serde(flatten)]
+    entry: &'a IndexEntry,
+    frames: &'a [FrameRecord],
+    diagnostics: &'a [Diagnostic],
+}
+
+/// How an input was handled in this run
+enum Outcome {
+    Processed,
+    Reused,
+}
+
+pub fn run(
+    directory: PathBuf,
+    pattern: &str,
+    output: PathBuf,
+    jobs: Option<usize>,
+    force: bool,
+) -> Result<()> {
+    if !directory.is_dir() {
+        bail!("Not a directory: {}", directory.display());
+    }
+    let inputs = find_inputs(&directory, pattern, &output)?;
+    if inputs.is_empty() {
+        bail!("No files match '{}' under {}", pattern, directory.display());
+    }
+    std::fs::create_dir_all(&output)
+        .with_context(|| format!("Failed to create {}", output.display()))?;
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs.unwrap_or(0))
+        .build()
+        .context("Failed to start the worker pool")?;
 
-pub fn run(directory: PathBuf, pattern: &str, output: PathBuf) -> Result<()> {
     println!(
-        "Batch command: {} with pattern '{}' -> {}",
+        "Processing {} files from {} with {} workers",
+        inputs.len(),
         directory.display(),
-        pattern,
-        output.display()
+        pool.current_num_threads()
+    );
+
+    let done = AtomicUsize::new(0);
+    let results: Vec<Result<(IndexEntry, Outcome)>> = pool.install(|| {
+        inputs
+            .par_iter()
+            .map(|input| {
+                let result = process(&directory, input, &output, force);
+                let n = done.fetch_add(1, Ordering::Relaxed) + 1;
+                let status = match &result {
+                    Ok((_, Outcome::Reused)) => "up to date".to_string(),
+                    Ok((entry, Outcome::Processed)) => match &entry.error {
+                        Some(e) => format!("failed: {}", e),
+                        None => format!("{} diagnostics", entry.counts.total()),
+                    },
+                    Err(e) => format!("not written: {:#}", e),
+                };
+                println!("[{}/{}] {}: {}", n, inputs.len(), input.display(), status);
+                result
+            })
+            .collect()
+    });
+
+    let mut entries = Vec::with_capacity(results.len());
+    let (mut processed, mut reused, mut write_errors) = (0, 0, 0);
+    for result in results {
+        match result {
+            Ok((entry, Outcome::Processed)) => {
+                processed += 1;
+                entries.push(entry);
+            }
+            Ok((entry, Outcome::Reused)) => {
+                reused += 1;
+                entries.push(entry);
+            }
+            Err(_) => write_errors += 1,
+        }
+    }
+    entries.sort_by(|a, b| a.file.cmp(&b.file));
+
+    write_atomic(&output.join("index.csv"), index_csv(&entries).as_bytes())?;
+    write_atomic(
+        &output.join("index.md"),
+        index_markdown(&entries).as_bytes(),
+    )?;
+
+    let failed = entries.iter().filter(|e| e.error.is_some()).count();
+    println!();
+    println!(
+        "Done: {} processed, {} up to date, {} unreadable, {} results not written",
+        processed, reused, failed, write_errors
     );
-    // TODO: Implement batch processing
+    println!("Index: {}", output.join("index.csv").display());
+    println!("Report: {}", output.join("index.md").display());
+
+    if write_errors > 0 {
+        bail!("{} results could not be written", write_errors);
+    }
+    Ok(())
+}
+
+/// Files matching `pattern` under `directory`, sorted, excluding `output`
+fn find_inputs(directory: &Path, pattern: &str, output: &Path) -> Result<Vec<PathBuf>> {
+    let full = directory.join(pattern);
+    let full = full
+        .to_str()
+        .with_context(|| format!("Pattern is not valid UTF-8: {}", full.display()))?;
+    let output = output.canonicalize().ok();
+
+    let mut inputs = Vec::new();
+    for entry in glob::glob(full).with_context(|| format!("Invalid pattern '{}'", pattern))? {
+        let path = entry?;
+        if !path.is_file() {
+            continue;
+        }
+        // Never pick up our own results when the output is inside the input tree
+        if let (Some(output), Ok(canonical)) = (&output, path.canonicalize()) {
+            if canonical.starts_with(output) {
+                continue;
+            }
+        }
+        inputs.push(path);
+    }
+    inputs.sort();
+    Ok(inputs)
+}
+
+/// Analyze one input and write its result, or reuse an up-to-date result
+fn process(
+    directory: &Path,
+    input: &Path,
+    output: &Path,
+    force: bool,
+) -> Result<(IndexEntry, Outcome)> {
+    let relative = input.strip_prefix(directory).unwrap_or(input);
+    let result_path = result_path(output, relative);
+
+    if !force {
+        if let Some(entry) = load_result(input, &result_path) {
+            return Ok((entry, Outcome::Reused));
+        }
+    }
+
+    let mut entry = IndexEntry {
+        file: relative.display().to_string(),
+        error: None,
+        summary: None,
+        counts: SeverityCounts::default(),
+    };
+    let mut frames = Vec::new();
+    let mut diagnostics = Vec::new();
+
+    match VideoSource::open(input) {
+        Ok(source) => {
+            match source.frames() {
+                Ok(f) => frames = f,
+                Err(e) => entry.error = Some(format!("{:#}", e)),
+            }
+            entry.summary = Some(StreamSummary::new(&source, &frames));
+            let manager = Validator::new(&source, false).run();
+            entry.counts = manager.count_by_severity();
+            diagnostics = manager.diagnostics;
+        }
+        Err(e) => entry.error = Some(format!("{:#}", e)),
+    }
+
+    let doc = BatchResult {
+        entry: &entry,
+        frames: &frames,
+        diagnostics: &diagnostics,
+    };
+    if let Some(parent) = result_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    write_atomic(&result_path, &serde_json::to_vec_pretty(&doc)?)?;
+
+    Ok((entry, Outcome::Processed))
+}
+
+/// `<output>/<relative>.json`, keeping the input extension in the name
+fn result_path(output: &Path, relative: &Path) -> PathBuf {
+    let mut name = relative.as_os_str().to_os_string();
+    name.push(".json");
+    output.join(name)
+}
+
+/// A previous result for `input`, if it is newer than the input and readable
+fn load_result(input: &Path, result_path: &Path) -> Option<IndexEntry> {
+    let input_time = std::fs::metadata(input).ok()?.modified().ok()?;
+    let result_time = std::fs::metadata(result_path).ok()?.modified().ok()?;
+    if result_time < input_time {
+        return None;
+    }
+    let data = std::fs::read(result_path).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+/// Write through a temporary file so an interrupted run never leaves a partial result
+fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let mut tmp = path.as_os_str().to_os_string();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    std::fs::write(&tmp, data).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to write {}", path.display()))?;
     Ok(())
 }
+
+fn index_csv(entries: &[IndexEntry]) -> String {
+    let mut out = String::from(
+        "file,status,container,codec,width,height,frames,frame_rate,bitrate_kbps,average_qp,fatal,error,warn,info\n",
+    );
+    for entry in entries {
+        let s = entry.summary.as_ref();
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            csv_field(&entry.file),
+            status(entry),
+            csv_opt(s.map(|s| &s.container)),
+            csv_opt(s.map(|s| &s.codec)),
+            csv_opt(s.and_then(|s| s.width)),
+            csv_opt(s.and_then(|s| s.height)),
+            csv_opt(s.map(|s| s.frame_count)),
+            csv_opt(s.and_then(|s| s.frame_rate).map(|v| format!("{:.3}", v))),
+            csv_opt(s.and_then(|s| s.bitrate_kbps).map(|v| format!("{:.1}", v))),
+            csv_opt(s.and_then(|s| s.average_qp).map(|v| format!("{:.2}", v))),
+            entry.counts.fatal,
+            entry.counts.error,
+            entry.counts.warn,
+            entry.counts.info,
+        );
+    }
+    out
+}
+
+fn index_markdown(entries: &[IndexEntry]) -> String {
+    let failed = entries.iter().filter(|e| status(e) == "fail").count();
+    let unreadable = entries.iter().filter(|e| e.error.is_some()).count();
+
+    let mut out = String::from("# Bitvue batch report\n\n");
+    let _ = writeln!(
+        out,
+        "{} files: {} passed, {} failed validation, {} unreadable.\n",
+        entries.len(),
+        entries.len() - failed - unreadable,
+        failed,
+        unreadable
+    );
+    out.push_str("| File | Status | Codec | Resolution | Frames | FPS | Bitrate (kbps) | Avg QP | Fatal | Error | Warn | Info |\n");
+    out.push_str("|---|---|---|---|---:|---:|---:|---:|---:|---:|---:|---:|\n");
+    for entry in entries {
+        let s = entry.summary.as_ref();
+        let resolution = match s.map(|s| (s.width, s.height)) {
+            Some((Some(w), Some(h))) => format!("{}x{}", w, h),
+            _ => "-".to_string(),
+        };
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} | {} | {} | {} | {} | {} | {} | {} | {} |",
+            entry.file.replace('|', "\\|"),
+            status(entry),
+            opt(s.map(|s| &s.codec)),
+            resolution,
+            opt(s.map(|s| s.frame_count)),
+            opt(s.and_then(|s| s.frame_rate).map(|v| format!("{:.2}", v))),
+            opt(s.and_then(|s| s.bitrate_kbps).map(|v| format!("{:.1}", v))),
+            opt(s.and_then(|s| s.average_qp).map(|v| format!("{:.2}", v))),
+            entry.counts.fatal,
+            entry.counts.error,
+            entry.counts.warn,
+            entry.counts.info,
+        );
+    }
+
+    let errors: Vec<&IndexEntry> = entries.iter().filter(|e| e.error.is_some()).collect();
+    if !errors.is_empty() {
+        out.push_str("\n## Unreadable files\n\n");
+        for entry in errors {
+            let _ = writeln!(
+                out,
+                "- `{}`: {}",
+                entry.file,
+                entry.error.as_deref().unwrap_or_default()
+            );
+        }
+    }
+    out
+}
+
+/// "error" when the input could not be read, "fail" with ERROR or FATAL
+/// diagnostics, "pass" otherwise
+fn status(entry: &IndexEntry) -> &'static str {
+    if entry.error.is_some() {
+        "error"
+    } else if entry.counts.error + entry.counts.fatal > 0 {
+        "fail"
+    } else {
+        "pass"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(file: &str, error: Option<&str>, counts: SeverityCounts) -> IndexEntry {
+        IndexEntry {
+            file: file.to_string(),
+            error: error.map(str::to_string),
+            summary: None,
+            counts,
+        }
+    }
+
+    #[test]
+    fn test_result_path_keeps_extension() {
+        let path = result_path(Path::new("out"), Path::new("a/clip.ivf"));
+        assert_eq!(path, Path::new("out/a/clip.ivf.json"));
+    }
+
+    #[test]
+    fn test_status() {
+        let clean = SeverityCounts::default();
+        let errors = SeverityCounts {
+            error: 2,
+            ..Default::default()
+        };
+        assert_eq!(status(&entry("a", None, clean)), "pass");
+        assert_eq!(status(&entry("a", None, errors)), "fail");
+        assert_eq!(status(&entry("a", Some("bad"), clean)), "error");
+    }
+
+    #[test]
+    fn test_index_entry_round_trip() {
+        let original = entry("a,b.ivf", Some("bad"), SeverityCounts::default());
+        let doc = BatchResult {
+            entry: &original,
+            frames: &[],
+            diagnostics: &[],
+        };
+        let json = serde_json::to_string(&doc).unwrap();
+        let loaded: IndexEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.file, "a,b.ivf");
+        assert_eq!(loaded.error.as_deref(), Some("bad"));
+        assert!(index_csv(&[loaded]).contains("\"a,b.ivf\",error"));
+    }
+}
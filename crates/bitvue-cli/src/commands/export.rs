@@ -1,15 +1,610 @@
 //! Export analysis results to file
+//!
+//! Runs the `bitvue_core::export` writers headless, producing the same
+//! artifacts as the desktop app in an output directory:
+//!
+//! - `frames.{csv,json}`: timeline frames in display order
+//! - `diagnostics.{csv,json}`: validation diagnostics
+//! - `summary.json`: frame statistics
+//! - `overlays/`: QP heatmap and partition grid images for a frame range
+//! - `bitvue_evidence_<timestamp>/`: evidence bundle with copies of the
+//!   artifacts above
+//! - `report.md`: Markdown report linking the other artifacts
 
-use anyhow::Result;
-use std::path::PathBuf;
+use crate::analysis::{FrameContext, Partitions};
+use crate::output::OutputFormat;
+use crate::stream::{Codec, FrameRecord, VideoSource};
+use crate::summary::StreamSummary;
+use crate::validation::Validator;
+use anyhow::{anyhow, bail, Context, Result};
+use bitvue_core::diagnostics::{Diagnostic, DiagnosticSeverity, SeverityCounts};
+use bitvue_core::export::{
+    create_qp_heatmap_export, export_diagnostics_csv, export_diagnostics_json,
+    export_evidence_bundle, export_frames_csv, export_frames_json, export_overlay_ppm,
+    export_overlay_rgba, export_summary_json, EvidenceBundleExportRequest, ExportConfig,
+    ExportSummary, OverlayExportData, OverlayImageFormat,
+};
+use bitvue_core::parity_harness::{EntityRef, OrderType, SelectionSnapshot};
+use bitvue_core::qp_heatmap::QPGrid;
+use bitvue_core::timeline::{FrameMarker, TimelineFrame};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
-pub fn run(file_path: PathBuf, output: PathBuf, format: &str) -> Result<()> {
-    println!(
-        "Export command: {} -> {} ({})",
-        file_path.display(),
-        output.display(),
-        format
+/// Diagnostics listed individually in the report; the rest are counted
+const REPORT_DIAGNOSTICS_LIMIT: usize = 100;
+
+/// Artifact sets selectable with `--artifacts`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Artifact {
+    Frames,
+    Diagnostics,
+    Summary,
+    Overlays,
+    Evidence,
+    Report,
+}
+
+impl Artifact {
+    const ALL: [Artifact; 6] = [
+        Artifact::Frames,
+        Artifact::Diagnostics,
+        Artifact::Summary,
+        Artifact::Overlays,
+        Artifact::Evidence,
+        Artifact::Report,
+    ];
+
+    /// Parse a comma-separated list, or "all"
+    fn parse_list(s: &str) -> Result<Vec<Artifact>> {
+        let mut artifacts = Vec::new();
+        for name in s.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            let selected: &[Artifact] = match name.to_ascii_lowercase().as_str() {
+                "all" => &Self::ALL,
+                "frames" => &[Artifact::Frames],
+                "diagnostics" => &[Artifact::Diagnostics],
+                "summary" => &[Artifact::Summary],
+                "overlays" => &[Artifact::Overlays],
+                "evidence" => &[Artifact::Evidence],
+                "report" | "markdown" => &[Artifact::Report],
+                other => bail!(
+                    "Unknown artifact '{}' (expected frames, diagnostics, summary, overlays, evidence, report or all)",
+                    other
+                ),
+            };
+            for artifact in selected {
+                if !artifacts.contains(artifact) {
+                    artifacts.push(*artifact);
+                }
+            }
+        }
+        if artifacts.is_empty() {
+            bail!("No artifacts selected");
+        }
+        Ok(artifacts)
+    }
+}
+
+/// A file or directory written by this export, relative to the output directory
+struct Written {
+    label: String,
+    path: String,
+    detail: String,
+}
+
+pub fn run(
+    file_path: PathBuf,
+    output: PathBuf,
+    format: &str,
+    artifacts: &str,
+    frame_range: &str,
+    overlay_format: &str,
+) -> Result<()> {
+    let format: OutputFormat = format.parse()?;
+    if format == OutputFormat::Text {
+        bail!("Text is not an export format (use json or csv)");
+    }
+    let artifacts = Artifact::parse_list(artifacts)?;
+    let overlay_format = parse_overlay_format(overlay_format)?;
+
+    let source = VideoSource::open(&file_path)?;
+    let frames = source.frames()?;
+    let diagnostics = Validator::new(&source, false).run();
+    let timeline = timeline_frames(&frames, &diagnostics.diagnostics);
+
+    std::fs::create_dir_all(&output)
+        .with_context(|| format!("Failed to create {}", output.display()))?;
+
+    let (ext, pretty) = match format {
+        OutputFormat::Csv => ("csv", false),
+        _ => ("json", true),
+    };
+    let config = ExportConfig {
+        range: None,
+        pretty,
+    };
+
+    let mut written = Vec::new();
+    let mut overlay_failures = Vec::new();
+
+    if artifacts.contains(&Artifact::Frames) {
+        let name = format!("frames.{}", ext);
+        let mut w = create(&output.join(&name))?;
+        let result = match format {
+            OutputFormat::Csv => export_frames_csv(&timeline, &mut w, config)?,
+            _ => export_frames_json(&timeline, &mut w, config)?,
+        };
+        w.flush()?;
+        written.push(Written {
+            label: "Frames".to_string(),
+            path: name,
+            detail: format!("{} frames", result.row_count),
+        });
+    }
+
+    if artifacts.contains(&Artifact::Diagnostics) {
+        let name = format!("diagnostics.{}", ext);
+        let mut w = create(&output.join(&name))?;
+        let result = match format {
+            OutputFormat::Csv => export_diagnostics_csv(&diagnostics.diagnostics, &mut w, None)?,
+            _ => export_diagnostics_json(&diagnostics.diagnostics, &mut w, None, pretty)?,
+        };
+        w.flush()?;
+        written.push(Written {
+            label: "Diagnostics".to_string(),
+            path: name,
+            detail: format!("{} diagnostics", result.row_count),
+        });
+    }
+
+    if artifacts.contains(&Artifact::Summary) {
+        let mut w = create(&output.join("summary.json"))?;
+        export_summary_json(&ExportSummary::from_frames(&timeline), &mut w, true)?;
+        w.flush()?;
+        written.push(Written {
+            label: "Summary".to_string(),
+            path: "summary.json".to_string(),
+            detail: "frame statistics".to_string(),
+        });
+    }
+
+    if artifacts.contains(&Artifact::Overlays) {
+        let range = parse_frame_range(frame_range, frames.len())?;
+        let dir = output.join("overlays");
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let mut images = 0;
+        for index in range.clone() {
+            match export_frame_overlays(&source, &frames, index, overlay_format, &dir) {
+                Ok(count) => images += count,
+                Err(e) => {
+                    tracing::warn!("No overlays for frame {}: {:#}", index, e);
+                    overlay_failures.push((index, format!("{:#}", e)));
+                }
+            }
+        }
+        written.push(Written {
+            label: "Overlays".to_string(),
+            path: "overlays/".to_string(),
+            detail: format!(
+                "{} images for frames {}-{}",
+                images,
+                range.start,
+                range.end - 1
+            ),
+        });
+    }
+
+    if artifacts.contains(&Artifact::Evidence) {
+        let range = parse_frame_range(frame_range, frames.len())?;
+        let request = EvidenceBundleExportRequest {
+            output_dir: output.clone(),
+            stream_fingerprint: stream_fingerprint(&source),
+            selection_state: frame_selection(&frames, range),
+            order_type: OrderType::Decode,
+            backend: parser_backend(source.codec).to_string(),
+            artifacts: written.iter().map(|w| output.join(&w.path)).collect(),
+            ..Default::default()
+        };
+        let result = export_evidence_bundle(&request, &[]);
+        if !result.success {
+            bail!(
+                "Evidence bundle export failed: {}",
+                result.error.unwrap_or_default()
+            );
+        }
+        let bundle = result
+            .bundle_path
+            .map(PathBuf::from)
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+            .unwrap_or_default();
+        written.push(Written {
+            label: "Evidence bundle".to_string(),
+            path: format!("{}/", bundle),
+            detail: format!("{} files", result.files_created.len()),
+        });
+    }
+
+    if artifacts.contains(&Artifact::Report) {
+        let report = ReportInput {
+            file: &file_path,
+            summary: &StreamSummary::new(&source, &frames),
+            stats: &ExportSummary::from_frames(&timeline),
+            counts: diagnostics.count_by_severity(),
+            diagnostics: &diagnostics.diagnostics,
+            artifacts: &written,
+            overlay_failures: &overlay_failures,
+        };
+        std::fs::write(output.join("report.md"), markdown_report(&report))
+            .with_context(|| format!("Failed to write {}", output.join("report.md").display()))?;
+        written.push(Written {
+            label: "Report".to_string(),
+            path: "report.md".to_string(),
+            detail: "Markdown report".to_string(),
+        });
+    }
+
+    println!("Exported {} to {}", file_path.display(), output.display());
+    for w in &written {
+        println!("  {:<16} {:<36} {}", w.label, w.path, w.detail);
+    }
+    if !overlay_failures.is_empty() {
+        println!(
+            "  {} frames had no overlay data (see log with -v)",
+            overlay_failures.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Timeline frames in display order, marking key frames and frames with
+/// ERROR or FATAL diagnostics
+fn timeline_frames(frames: &[FrameRecord], diagnostics: &[Diagnostic]) -> Vec<TimelineFrame> {
+    let mut shown: Vec<&FrameRecord> = frames
+        .iter()
+        .filter(|f| f.display_index.is_some())
+        .collect();
+    shown.sort_by_key(|f| f.display_index);
+
+    shown
+        .into_iter()
+        .enumerate()
+        .map(|(display_idx, f)| {
+            let has_error = diagnostics.iter().any(|d| {
+                d.severity >= DiagnosticSeverity::Error
+                    && d.frame_key
+                        .as_ref()
+                        .is_some_and(|k| k.frame_index == f.decode_index)
+            });
+            let marker = if has_error {
+                FrameMarker::Error
+            } else if f.frame_type == "I" || f.frame_type.contains("KEY") {
+                FrameMarker::Key
+            } else {
+                FrameMarker::None
+            };
+
+            let mut frame = TimelineFrame::new(display_idx, f.size as u64, f.frame_type.clone())
+                .with_marker(marker);
+            if let Some(pts) = f.pts {
+                frame = frame.with_pts(pts);
+            }
+            frame
+        })
+        .collect()
+}
+
+/// Write the QP heatmap and partition grid of one frame; returns the image count
+fn export_frame_overlays(
+    source: &VideoSource,
+    frames: &[FrameRecord],
+    index: usize,
+    format: OverlayImageFormat,
+    dir: &Path,
+) -> Result<usize> {
+    let flow = FrameContext::new(source, frames, index)?.coding_flow()?;
+
+    let mut overlays = Vec::new();
+    if let Some(qp) = flow.qp {
+        let grid = QPGrid::new(qp.grid_w, qp.grid_h, qp.block_w, qp.block_h, qp.values, -1);
+        overlays.push(create_qp_heatmap_export(&grid, index, 1.0));
+    }
+    if let Some(partition) = &flow.partition {
+        overlays.push(partition_overlay(partition, index));
+    }
+    if overlays.is_empty() {
+        bail!("the parser exposes no QP or partition data");
+    }
+
+    let count = overlays.len();
+    for mut overlay in overlays {
+        overlay.format = format;
+        write_overlay(&overlay, &dir.join(overlay.suggested_filename()))?;
+    }
+    Ok(count)
+}
+
+/// Block outlines at pixel resolution, transparent inside the blocks
+fn partition_overlay(partitions: &Partitions, frame_idx: usize) -> OverlayExportData {
+    let mut overlay = OverlayExportData::new(
+        partitions.coded_width,
+        partitions.coded_height,
+        "Partition Grid",
+        frame_idx,
     );
-    // TODO: Implement export
+    for block in &partitions.blocks {
+        let right = block.x + block.width.saturating_sub(1);
+        let bottom = block.y + block.height.saturating_sub(1);
+        for x in block.x..=right {
+            overlay.set_pixel(x, block.y, 0, 0, 0, 255);
+            overlay.set_pixel(x, bottom, 0, 0, 0, 255);
+        }
+        for y in block.y..=bottom {
+            overlay.set_pixel(block.x, y, 0, 0, 0, 255);
+            overlay.set_pixel(right, y, 0, 0, 0, 255);
+        }
+    }
+    overlay
+}
+
+fn write_overlay(overlay: &OverlayExportData, path: &Path) -> Result<()> {
+    match overlay.format {
+        OverlayImageFormat::Png => {
+            let image = image::RgbaImage::from_raw(
+                overlay.width,
+                overlay.height,
+                overlay.rgba_data.clone(),
+            )
+            .ok_or_else(|| anyhow!("Overlay buffer does not match its size"))?;
+            image
+                .save_with_format(path, image::ImageFormat::Png)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        OverlayImageFormat::Ppm => {
+            let mut w = create(path)?;
+            export_overlay_ppm(overlay, &mut w)?;
+            w.flush()?;
+        }
+        OverlayImageFormat::RawRgba => {
+            let mut w = create(path)?;
+            export_overlay_rgba(overlay, &mut w)?;
+            w.flush()?;
+        }
+    }
     Ok(())
 }
+
+fn create(path: &Path) -> Result<BufWriter<File>> {
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    Ok(BufWriter::new(file))
+}
+
+fn parse_overlay_format(s: &str) -> Result<OverlayImageFormat> {
+    Ok(match s.to_ascii_lowercase().as_str() {
+        "png" => OverlayImageFormat::Png,
+        "ppm" => OverlayImageFormat::Ppm,
+        "rgba" | "raw" => OverlayImageFormat::RawRgba,
+        other => bail!(
+            "Unknown overlay format '{}' (expected png, ppm or rgba)",
+            other
+        ),
+    })
+}
+
+/// Parse "all", "N" or "A-B" (inclusive, decode order) against `count` frames
+fn parse_frame_range(s: &str, count: usize) -> Result<Range<usize>> {
+    let s = s.trim();
+    let range = if s.eq_ignore_ascii_case("all") {
+        0..count
+    } else if let Some((start, end)) = s.split_once('-') {
+        let start: usize = start.trim().parse().context("Invalid frame range start")?;
+        let end: usize = end.trim().parse().context("Invalid frame range end")?;
+        if end < start {
+            bail!("Frame range {} ends before it starts", s);
+        }
+        start..end.saturating_add(1).min(count)
+    } else {
+        let index: usize = s.parse().context("Invalid frame index")?;
+        index..(index + 1).min(count)
+    };
+    if range.start >= count {
+        bail!("Frame range {} is outside the stream ({} frames)", s, count);
+    }
+    Ok(range)
+}
+
+/// The `--frames` range as a selection: the frame itself when the range
+/// holds one frame, and the bytes the range covers
+fn frame_selection(frames: &[FrameRecord], range: Range<usize>) -> SelectionSnapshot {
+    let selected = &frames[range.clone()];
+    let start = selected.first().and_then(|f| f.offset);
+    let end = selected
+        .last()
+        .and_then(|f| f.offset.map(|offset| offset + f.size as u64));
+    let selected_entity = (range.len() == 1).then(|| EntityRef {
+        kind: "frame".to_string(),
+        id: format!("frame_{}", range.start),
+        frame_index: Some(range.start),
+        byte_offset: start,
+    });
+
+    SelectionSnapshot {
+        selected_entity,
+        selected_byte_range: start.zip(end),
+        order_type: OrderType::Decode,
+    }
+}
+
+/// Crate whose parser produced the analysis
+fn parser_backend(codec: Codec) -> &'static str {
+    match codec {
+        Codec::Av1 => "bitvue-av1-codec",
+        Codec::Avc => "bitvue-avc",
+        Codec::Hevc => "bitvue-hevc",
+        Codec::Vvc => "bitvue-vvc",
+        Codec::Vp9 => "bitvue-vp9",
+    }
+}
+
+/// xxHash64 of the coded samples, identifying the stream in evidence bundles
+fn stream_fingerprint(source: &VideoSource) -> String {
+    let mut hasher = twox_hash::XxHash64::with_seed(0);
+    for sample in &source.samples {
+        hasher.write(&sample.data);
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+struct ReportInput<'a> {
+    file: &'a Path,
+    summary: &'a StreamSummary,
+    stats: &'a ExportSummary,
+    counts: SeverityCounts,
+    diagnostics: &'a [Diagnostic],
+    artifacts: &'a [Written],
+    overlay_failures: &'a [(usize, String)],
+}
+
+fn markdown_report(r: &ReportInput) -> String {
+    let s = r.summary;
+    let mut out = String::new();
+    let _ = writeln!(out, "# Bitvue report: {}\n", file_name(r.file));
+
+    out.push_str("## Stream\n\n| Property | Value |\n|---|---|\n");
+    let resolution = match (s.width, s.height) {
+        (Some(w), Some(h)) => format!("{}x{}", w, h),
+        _ => "-".to_string(),
+    };
+    let rows = [
+        ("File", r.file.display().to_string()),
+        ("Size", format!("{} bytes", s.file_size)),
+        ("Container", s.container.clone()),
+        ("Codec", s.codec.clone()),
+        ("Resolution", resolution),
+        ("Frames", s.frame_count.to_string()),
+        ("Frame rate", fixed(s.frame_rate, 3)),
+        ("Duration", with_unit(s.duration_secs, 3, "s")),
+        ("Bitrate", with_unit(s.bitrate_kbps, 1, "kbps")),
+        ("Average QP", fixed(s.average_qp, 2)),
+    ];
+    for (name, value) in rows {
+        let _ = writeln!(out, "| {} | {} |", name, value.replace('|', "\\|"));
+    }
+
+    let st = r.stats;
+    out.push_str("\n## Frames\n\n");
+    let _ = writeln!(
+        out,
+        "{} shown frames, {} key frames, {} with errors. Frame size: average {:.0} bytes, min {}, max {}.\n",
+        st.total_frames,
+        st.key_frame_count,
+        st.error_count,
+        st.avg_frame_size,
+        st.min_frame_size,
+        st.max_frame_size
+    );
+    out.push_str("| Type | Count |\n|---|---:|\n");
+    for (frame_type, count) in &s.frame_types {
+        let _ = writeln!(out, "| {} | {} |", frame_type, count);
+    }
+
+    out.push_str("\n## Diagnostics\n\n");
+    let c = r.counts;
+    let _ = writeln!(
+        out,
+        "{} fatal, {} errors, {} warnings, {} info.\n",
+        c.fatal, c.error, c.warn, c.info
+    );
+    if !r.diagnostics.is_empty() {
+        let mut sorted: Vec<&Diagnostic> = r.diagnostics.iter().collect();
+        sorted.sort_by_key(|d| std::cmp::Reverse(d.severity));
+        out.push_str(
+            "| Severity | Frame | Offset | Category | Message |\n|---|---:|---:|---|---|\n",
+        );
+        for d in sorted.iter().take(REPORT_DIAGNOSTICS_LIMIT) {
+            let _ = writeln!(
+                out,
+                "| {} | {} | 0x{:X} | {} | {} |",
+                d.severity.display_text(),
+                d.frame_key
+                    .as_ref()
+                    .map(|k| k.frame_index.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                d.offset_bytes,
+                d.category.display_text(),
+                d.message.replace('|', "\\|")
+            );
+        }
+        if sorted.len() > REPORT_DIAGNOSTICS_LIMIT {
+            let _ = writeln!(
+                out,
+                "\n{} more diagnostics are listed in the diagnostics export.",
+                sorted.len() - REPORT_DIAGNOSTICS_LIMIT
+            );
+        }
+    }
+
+    if !r.artifacts.is_empty() {
+        out.push_str("\n## Artifacts\n\n");
+        for a in r.artifacts {
+            let _ = writeln!(out, "- [{}]({}): {}", a.label, a.path, a.detail);
+        }
+    }
+    if !r.overlay_failures.is_empty() {
+        out.push_str("\nFrames without overlays:\n\n");
+        for (index, reason) in r.overlay_failures {
+            let _ = writeln!(out, "- frame {}: {}", index, reason);
+        }
+    }
+    out
+}
+
+fn fixed(value: Option<f64>, precision: usize) -> String {
+    value
+        .map(|v| format!("{:.*}", precision, v))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn with_unit(value: Option<f64>, precision: usize, unit: &str) -> String {
+    value
+        .map(|v| format!("{:.*} {}", precision, v, unit))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_artifacts() {
+        assert_eq!(
+            Artifact::parse_list("frames, report").unwrap(),
+            vec![Artifact::Frames, Artifact::Report]
+        );
+        assert_eq!(Artifact::parse_list("all").unwrap().len(), 6);
+        assert!(Artifact::parse_list("frames,pictures").is_err());
+        assert!(Artifact::parse_list("").is_err());
+    }
+
+    #[test]
+    fn test_parse_frame_range() {
+        assert_eq!(parse_frame_range("all", 10).unwrap(), 0..10);
+        assert_eq!(parse_frame_range("3", 10).unwrap(), 3..4);
+        assert_eq!(parse_frame_range("2-5", 10).unwrap(), 2..6);
+        assert_eq!(parse_frame_range("8-20", 10).unwrap(), 8..10);
+        assert!(parse_frame_range("12", 10).is_err());
+        assert!(parse_frame_range("5-2", 10).is_err());
+    }
+}
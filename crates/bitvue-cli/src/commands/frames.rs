@@ -1,15 +1,88 @@
 //! List all frames in the video
 
+use crate::output::{csv_opt, opt, OutputFormat};
+use crate::stream::{FrameRecord, VideoSource};
 use anyhow::Result;
+use serde::Serialize;
 use std::path::PathBuf;
 
+/// JSON document emitted by `frames --format json`
+#[derive(Serialize)]
+struct FrameList<'a> {
+    file: String,
+    container: &'a str,
+    codec: &'a str,
+    total_frames: usize,
+    frames: &'a [FrameRecord],
+}
+
 pub fn run(file_path: PathBuf, limit: usize, format: &str) -> Result<()> {
+    let format: OutputFormat = format.parse()?;
+    let source = VideoSource::open(&file_path)?;
+    let frames = source.frames()?;
+    let total = frames.len();
+    let shown = &frames[..limit.min(total)];
+
+    match format {
+        OutputFormat::Text => print_text(&source, shown, total),
+        OutputFormat::Json => {
+            let doc = FrameList {
+                file: file_path.display().to_string(),
+                container: source.container.name(),
+                codec: source.codec.name(),
+                total_frames: total,
+                frames: shown,
+            };
+            println!("{}", serde_json::to_string_pretty(&doc)?);
+        }
+        OutputFormat::Csv => print_csv(shown),
+    }
+
+    Ok(())
+}
+
+fn print_text(source: &VideoSource, frames: &[FrameRecord], total: usize) {
     println!(
-        "Frames command: {} (limit: {}, format: {})",
-        file_path.display(),
-        limit,
-        format
+        "File: {} ({}, {}, {} bytes)",
+        source.path.display(),
+        source.container,
+        source.codec,
+        source.file_size
     );
-    // TODO: Implement frame listing
-    Ok(())
+    println!("Frames: {} (showing {})", total, frames.len());
+    println!();
+    println!(
+        "{:>7} {:>7} {:<14} {:>10} {:>12} {:>12} {:>5} {:>4}",
+        "DECODE", "DISPLAY", "TYPE", "SIZE", "OFFSET", "PTS", "QP", "TID"
+    );
+    for f in frames {
+        println!(
+            "{:>7} {:>7} {:<14} {:>10} {:>12} {:>12} {:>5} {:>4}",
+            f.decode_index,
+            opt(f.display_index),
+            f.frame_type,
+            f.size,
+            opt(f.offset),
+            opt(f.pts),
+            opt(f.qp),
+            opt(f.temporal_id),
+        );
+    }
+}
+
+fn print_csv(frames: &[FrameRecord]) {
+    println!("decode_index,display_index,frame_type,size,offset,pts,qp,temporal_id");
+    for f in frames {
+        println!(
+            "{},{},{},{},{},{},{},{}",
+            f.decode_index,
+            csv_opt(f.display_index),
+            f.frame_type,
+            f.size,
+            csv_opt(f.offset),
+            csv_opt(f.pts),
+            csv_opt(f.qp),
+            csv_opt(f.temporal_id),
+        );
+    }
 }
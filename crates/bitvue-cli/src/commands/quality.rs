@@ -1,14 +1,983 @@
 //! Calculate quality metrics between two files
+//!
+//! Both inputs are decoded to YUV: coded streams through `bitvue-decode`,
+//! raw `.yuv`/`.y4m` files through [`YuvLoader`]. The decoded pictures are
+//! paired by the compare [`AlignmentEngine`] (PTS first, display index as
+//! fallback) and every selected pair is scored with `bitvue-metrics`.
 
-use anyhow::Result;
-use std::path::PathBuf;
+use crate::output::{csv_opt, OutputFormat};
+use crate::stream::{length_prefixed_to_annex_b, Codec, Container, VideoSource};
+use anyhow::{anyhow, bail, Context, Result};
+use bitvue_core::alignment::{AlignmentEngine, FramePair};
+use bitvue_core::frame_identity::{FrameIndexMap, FrameMetadata, PtsQuality};
+use bitvue_decode::decoder::ChromaFormat;
+use bitvue_decode::{
+    BitDepth, ChromaSubsampling, CodecType, DecodedFrame, DecoderFactory, YuvFileParams, YuvLoader,
+};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Quality metric accepted by `--metrics`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Metric {
+    Psnr,
+    Ssim,
+    Vmaf,
+}
+
+impl Metric {
+    fn name(self) -> &'static str {
+        match self {
+            Metric::Psnr => "psnr",
+            Metric::Ssim => "ssim",
+            Metric::Vmaf => "vmaf",
+        }
+    }
+}
+
+/// Frames selected with `--frames`, as reference display indices
+#[derive(Debug, PartialEq, Eq)]
+enum FrameSelection {
+    All,
+    Indices(BTreeSet<usize>),
+}
+
+/// A decoded input, frames in output (display) order
+struct Video {
+    path: PathBuf,
+    /// Container/codec description, e.g. "mp4/hevc" or "y4m"
+    format: String,
+    frames: Vec<DecodedFrame>,
+    /// Presentation time of every frame in microseconds, rebased so the
+    /// first frame starts at zero. `None` when the input carries no timing,
+    /// and for frames the container does not present.
+    pts_us: Vec<Option<u64>>,
+}
+
+/// Planes of one picture, tightly packed, one `u16` per sample
+struct Planes {
+    y: Vec<u16>,
+    /// U and V planes; `None` for monochrome pictures
+    chroma: Option<(Vec<u16>, Vec<u16>)>,
+    bit_depth: u8,
+    width: usize,
+    height: usize,
+    chroma_width: usize,
+    chroma_height: usize,
+}
+
+/// Per-plane scores of one metric
+#[derive(Debug, Clone, Copy, Serialize)]
+struct PlaneScores {
+    y: f64,
+    u: Option<f64>,
+    v: Option<f64>,
+}
+
+/// Scores of one aligned frame pair
+#[derive(Debug, Serialize)]
+struct FrameScores {
+    /// Reference display index (`None` = gap in the reference)
+    reference_frame: Option<usize>,
+    /// Distorted display index (`None` = gap in the distorted input)
+    distorted_frame: Option<usize>,
+    /// Reference minus distorted presentation time, in microseconds
+    pts_delta_us: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    psnr: Option<PlaneScores>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ssim: Option<PlaneScores>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vmaf: Option<f64>,
+}
+
+/// One value column of the per-frame table
+struct Column {
+    name: &'static str,
+    precision: usize,
+    value: fn(&FrameScores) -> Option<f64>,
+}
+
+/// Mean/min/max of one column over the scored frames
+#[derive(Debug, Serialize)]
+struct ColumnSummary {
+    metric: &'static str,
+    frames: usize,
+    mean: f64,
+    min: f64,
+    max: f64,
+}
+
+#[derive(Serialize)]
+struct InputInfo {
+    file: String,
+    format: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    frame_count: usize,
+    /// How far the alignment can trust the input's timestamps
+    pts_quality: PtsQuality,
+}
+
+#[derive(Serialize)]
+struct AlignmentInfo {
+    method: &'static str,
+    confidence: &'static str,
+    pairs: usize,
+    gaps: usize,
+    gap_percentage: f64,
+}
+
+/// JSON document emitted by `quality --format json`
+#[derive(Serialize)]
+struct QualityReport<'a> {
+    reference: InputInfo,
+    distorted: InputInfo,
+    alignment: AlignmentInfo,
+    metrics: Vec<&'static str>,
+    frames: &'a [FrameScores],
+    summary: Vec<ColumnSummary>,
+}
+
+pub fn run(
+    reference: PathBuf,
+    distorted: PathBuf,
+    frames: &str,
+    metrics: &str,
+    format: &str,
+    size: Option<&str>,
+    pix_fmt: &str,
+) -> Result<()> {
+    let format: OutputFormat = format.parse()?;
+    let metrics = parse_metrics(metrics)?;
+    let selection = parse_frame_selection(frames)?;
+    if metrics.contains(&Metric::Vmaf) && !cfg!(feature = "vmaf") {
+        bail!("VMAF is not available: rebuild bitvue-cli with the `vmaf` feature");
+    }
+    let raw = size.map(|s| raw_params(s, pix_fmt)).transpose()?;
+
+    let reference = load_video(&reference, raw.as_ref())?;
+    let distorted = load_video(&distorted, raw.as_ref())?;
+
+    let reference_map = frame_index_map(&reference);
+    let distorted_map = frame_index_map(&distorted);
+    let engine = AlignmentEngine::new(&reference_map, &distorted_map);
+    let pairs = select_pairs(&engine.frame_pairs, &selection, reference.frames.len())?;
+
+    let pictures: Vec<Option<(Planes, Planes)>> = pairs
+        .par_iter()
+        .map(|pair| {
+            let (Some(a), Some(b)) = (pair.stream_a_idx, pair.stream_b_idx) else {
+                return Ok(None);
+            };
+            let ref_frame = frame_at(&reference, &reference_map, a)?;
+            let dist_frame = frame_at(&distorted, &distorted_map, b)?;
+            if (ref_frame.width, ref_frame.height) != (dist_frame.width, dist_frame.height) {
+                bail!(
+                    "Frame size mismatch at reference frame {}: {}x{} vs {}x{}",
+                    a,
+                    ref_frame.width,
+                    ref_frame.height,
+                    dist_frame.width,
+                    dist_frame.height
+                );
+            }
+            if ref_frame.bit_depth != dist_frame.bit_depth {
+                bail!(
+                    "Bit depth mismatch at reference frame {}: {} vs {} bits",
+                    a,
+                    ref_frame.bit_depth,
+                    dist_frame.bit_depth
+                );
+            }
+            Ok(Some((Planes::new(ref_frame)?, Planes::new(dist_frame)?)))
+        })
+        .collect::<Result<_>>()?;
+
+    let mut scores: Vec<FrameScores> = pairs
+        .par_iter()
+        .zip(&pictures)
+        .map(|(pair, picture)| {
+            let mut row = FrameScores {
+                reference_frame: pair.stream_a_idx,
+                distorted_frame: pair.stream_b_idx,
+                pts_delta_us: pair.pts_delta,
+                psnr: None,
+                ssim: None,
+                vmaf: None,
+            };
+            if let Some((a, b)) = picture {
+                if metrics.contains(&Metric::Psnr) {
+                    row.psnr = Some(plane_scores(a, b, bitvue_metrics::psnr_hbd)?);
+                }
+                if metrics.contains(&Metric::Ssim) {
+                    row.ssim = Some(plane_scores(a, b, bitvue_metrics::ssim_hbd)?);
+                }
+            }
+            Ok(row)
+        })
+        .collect::<Result<_>>()?;
+
+    if metrics.contains(&Metric::Vmaf) {
+        let scored: Vec<(usize, &Planes, &Planes)> = pictures
+            .iter()
+            .enumerate()
+            .filter_map(|(i, p)| p.as_ref().map(|(a, b)| (i, a, b)))
+            .collect();
+        let values = vmaf_scores(&scored)?;
+        for ((i, _, _), value) in scored.iter().zip(values) {
+            scores[*i].vmaf = Some(value);
+        }
+    }
+
+    let columns = columns(&metrics);
+    let summary = summarize(&scores, &columns);
+
+    match format {
+        OutputFormat::Text => {
+            print_text(&reference, &distorted, &engine, &scores, &columns, &summary)
+        }
+        OutputFormat::Json => {
+            let doc = QualityReport {
+                reference: input_info(&reference),
+                distorted: input_info(&distorted),
+                alignment: alignment_info(&engine),
+                metrics: metrics.iter().map(|m| m.name()).collect(),
+                frames: &scores,
+                summary,
+            };
+            println!("{}", serde_json::to_string_pretty(&doc)?);
+        }
+        OutputFormat::Csv => print_csv(&scores, &columns, &summary),
+    }
 
-pub fn run(reference: PathBuf, distorted: PathBuf, frames: &str, metrics: &str) -> Result<()> {
-    println!("Quality command:");
-    println!("  Reference: {}", reference.display());
-    println!("  Distorted: {}", distorted.display());
-    println!("  Frames: {}", frames);
-    println!("  Metrics: {}", metrics);
-    // TODO: Implement quality calculation
     Ok(())
 }
+
+/// Parse `--metrics` ("psnr,ssim", "vmaf", ...)
+fn parse_metrics(s: &str) -> Result<Vec<Metric>> {
+    let mut metrics = BTreeSet::new();
+    for name in s.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        metrics.insert(match name.to_ascii_lowercase().as_str() {
+            "psnr" => Metric::Psnr,
+            "ssim" => Metric::Ssim,
+            "vmaf" => Metric::Vmaf,
+            other => bail!("Unknown metric '{}' (expected psnr, ssim or vmaf)", other),
+        });
+    }
+    if metrics.is_empty() {
+        bail!("No metrics requested");
+    }
+    Ok(metrics.into_iter().collect())
+}
+
+/// Parse `--frames`: "all" or a comma-separated list of indices and `A-B` ranges
+fn parse_frame_selection(s: &str) -> Result<FrameSelection> {
+    let s = s.trim();
+    if s.eq_ignore_ascii_case("all") {
+        return Ok(FrameSelection::All);
+    }
+
+    let mut indices = BTreeSet::new();
+    for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        if let Some((start, end)) = part.split_once('-') {
+            let start: usize = start.trim().parse().context("Invalid frame range start")?;
+            let end: usize = end.trim().parse().context("Invalid frame range end")?;
+            if end < start {
+                bail!("Frame range {} ends before it starts", part);
+            }
+            indices.extend(start..=end);
+        } else {
+            indices.insert(part.parse().context("Invalid frame index")?);
+        }
+    }
+    if indices.is_empty() {
+        bail!("No frames selected");
+    }
+    Ok(FrameSelection::Indices(indices))
+}
+
+/// Parse `--size WIDTHxHEIGHT` and `--pix-fmt` into raw `.yuv` parameters
+fn raw_params(size: &str, pix_fmt: &str) -> Result<YuvFileParams> {
+    let (width, height) = size
+        .split_once(['x', 'X'])
+        .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)))
+        .filter(|&(w, h): &(u32, u32)| w > 0 && h > 0)
+        .ok_or_else(|| anyhow!("Invalid frame size '{}' (expected WIDTHxHEIGHT)", size))?;
+
+    let pix_fmt = pix_fmt.to_ascii_lowercase();
+    let (layout, bit_depth) = if let Some(layout) = pix_fmt.strip_suffix("10le") {
+        (layout, BitDepth::Bit10)
+    } else if let Some(layout) = pix_fmt.strip_suffix("12le") {
+        (layout, BitDepth::Bit12)
+    } else {
+        (pix_fmt.as_str(), BitDepth::Bit8)
+    };
+    let chroma_subsampling = match layout {
+        "yuv420p" => ChromaSubsampling::Yuv420,
+        "yuv422p" => ChromaSubsampling::Yuv422,
+        "yuv444p" => ChromaSubsampling::Yuv444,
+        "gray" => ChromaSubsampling::Mono,
+        _ => bail!("Unsupported pixel format '{}'", pix_fmt),
+    };
+
+    Ok(YuvFileParams {
+        width,
+        height,
+        chroma_subsampling,
+        bit_depth,
+        // Raw files carry no timing; frames are aligned by display index
+        frame_rate: (0, 1),
+    })
+}
+
+fn load_video(path: &Path, raw: Option<&YuvFileParams>) -> Result<Video> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("y4m") => load_yuv(path, None),
+        Some("yuv") => {
+            let params = raw.cloned().ok_or_else(|| {
+                anyhow!("{} is raw YUV: pass --size and --pix-fmt", path.display())
+            })?;
+            load_yuv(path, Some(params))
+        }
+        _ => load_coded(path),
+    }
+}
+
+/// Read every frame of a `.y4m` or raw `.yuv` file
+fn load_yuv(path: &Path, params: Option<YuvFileParams>) -> Result<Video> {
+    let is_y4m = params.is_none();
+    let mut loader = YuvLoader::open(path, params)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let (rate_num, rate_den) = loader.params().frame_rate;
+
+    let mut frames = Vec::new();
+    while let Some(frame) = loader.read_frame().with_context(|| {
+        format!(
+            "Failed to read frame {} of {}",
+            frames.len(),
+            path.display()
+        )
+    })? {
+        frames.push(frame);
+    }
+
+    let pts_us = (0..frames.len() as u64)
+        .map(|i| {
+            (is_y4m && rate_num > 0)
+                .then(|| i * 1_000_000 * u64::from(rate_den) / u64::from(rate_num))
+        })
+        .collect();
+
+    Ok(Video {
+        path: path.to_path_buf(),
+        format: if is_y4m { "y4m" } else { "yuv" }.to_string(),
+        frames,
+        pts_us,
+    })
+}
+
+/// Decode a coded stream with the matching `bitvue-decode` decoder
+fn load_coded(path: &Path) -> Result<Video> {
+    let source = VideoSource::open(path)?;
+
+    // Trust container timestamps when the container has them; samples
+    // without one (MP4 samples outside the edit list) are not presented
+    let timescale = source
+        .timescale
+        .filter(|_| source.samples.iter().any(|s| s.pts.is_some()));
+
+    let (frames, pts): (Vec<_>, Vec<_>) = decode(&source)?.into_iter().unzip();
+
+    let pts_us = pts
+        .into_iter()
+        .map(|pts| {
+            let ts = timescale?;
+            pts.map(|t| (t as f64 * 1_000_000.0 / ts).round() as u64)
+        })
+        .collect();
+
+    Ok(Video {
+        path: path.to_path_buf(),
+        format: format!("{}/{}", source.container, source.codec),
+        frames,
+        pts_us: rebase(pts_us),
+    })
+}
+
+/// Decode every picture of a source, in output order, each with the
+/// container timestamp of the packet it was decoded from
+pub(crate) fn decode(source: &VideoSource) -> Result<Vec<(DecodedFrame, Option<u64>)>> {
+    let path = source.path.display();
+    let codec = match source.codec {
+        Codec::Av1 => CodecType::AV1,
+        Codec::Avc => CodecType::H264,
+        Codec::Hevc => CodecType::H265,
+        Codec::Vvc => CodecType::H266,
+        Codec::Vp9 => CodecType::VP9,
+    };
+    if let Some(feature) = missing_decoder_feature(source.codec) {
+        bail!(
+            "Cannot decode {} ({}): rebuild bitvue-cli with the `{}` feature",
+            path,
+            source.codec,
+            feature
+        );
+    }
+    let mut decoder = DecoderFactory::create(codec)
+        .with_context(|| format!("Cannot decode {} ({})", path, source.codec))?;
+
+    // The packet index travels through the decoder as the frame timestamp
+    // and picks the container timestamp back out of `packets`
+    let packets = packets(source)?;
+    let mut frames = Vec::new();
+    for (index, (data, _)) in packets.iter().enumerate() {
+        decoder
+            .send_data(data, Some(index as i64))
+            .with_context(|| format!("Failed to decode {}", path))?;
+        frames.extend(decoder.collect_frames()?);
+    }
+    decoder.flush();
+    frames.extend(decoder.collect_frames()?);
+
+    if frames.is_empty() {
+        bail!("No frames decoded from {}", path);
+    }
+    Ok(frames
+        .into_iter()
+        .map(|frame| {
+            let pts = usize::try_from(frame.timestamp)
+                .ok()
+                .and_then(|index| packets.get(index))
+                .and_then(|(_, pts)| *pts);
+            (frame, pts)
+        })
+        .collect())
+}
+
+/// The bitvue-cli feature that enables the decoder of `codec`, when this
+/// build lacks it (AV1 decoding through dav1d is always available)
+pub(crate) fn missing_decoder_feature(codec: Codec) -> Option<&'static str> {
+    match codec {
+        Codec::Av1 => None,
+        Codec::Avc | Codec::Hevc | Codec::Vp9 => (!cfg!(feature = "ffmpeg")).then_some("ffmpeg"),
+        Codec::Vvc => (!cfg!(feature = "vvdec")).then_some("vvdec"),
+    }
+}
+
+/// Split the input into decoder packets, each with its container timestamp
+/// (`None` for samples the container does not present)
+fn packets(source: &VideoSource) -> Result<Vec<(Vec<u8>, Option<u64>)>> {
+    // Raw Annex B is one big sample: cut it at every picture so the decoder
+    // sees one access unit per packet. The first packet keeps the leading
+    // parameter sets.
+    if source.container == Container::AnnexB {
+        let (es, _) = source.elementary_stream();
+        let mut cuts: Vec<usize> = source
+            .frames()?
+            .iter()
+            .filter_map(|f| f.offset.map(|o| o as usize))
+            .filter(|&o| o < es.len())
+            .collect();
+        cuts.sort_unstable();
+        cuts.dedup();
+        match cuts.first_mut() {
+            Some(first) => *first = 0,
+            None => cuts.push(0),
+        }
+        cuts.push(es.len());
+        return Ok(cuts
+            .windows(2)
+            .map(|w| (es[w[0]..w[1]].to_vec(), None))
+            .collect());
+    }
+
+    Ok(source
+        .samples
+        .iter()
+        .map(|sample| {
+            let data =
+                if source.codec.is_nal_based() && source.container.uses_length_prefixed_nals() {
+                    length_prefixed_to_annex_b(&sample.data, source.nal_length_size)
+                } else {
+                    sample.data.clone()
+                };
+            (data, sample.pts)
+        })
+        .collect())
+}
+
+/// Shift timestamps so the earliest one is zero; frames without one stay
+/// without
+fn rebase(pts: Vec<Option<u64>>) -> Vec<Option<u64>> {
+    let first = pts.iter().flatten().min().copied().unwrap_or(0);
+    pts.into_iter().map(|p| p.map(|p| p - first)).collect()
+}
+
+fn frame_index_map(video: &Video) -> FrameIndexMap {
+    let metadata: Vec<FrameMetadata> = video
+        .pts_us
+        .iter()
+        .map(|&pts| FrameMetadata { pts, dts: None })
+        .collect();
+    FrameIndexMap::new(&metadata)
+}
+
+/// Decoded frame at a display index of the alignment
+fn frame_at<'a>(
+    video: &'a Video,
+    map: &FrameIndexMap,
+    display_idx: usize,
+) -> Result<&'a DecodedFrame> {
+    map.display_to_decode_idx(display_idx)
+        .and_then(|i| video.frames.get(i))
+        .ok_or_else(|| {
+            anyhow!(
+                "Frame {} missing from {}",
+                display_idx,
+                video.path.display()
+            )
+        })
+}
+
+/// Pairs whose reference frame is selected; `all` keeps gap-only pairs too
+fn select_pairs<'a>(
+    pairs: &'a [FramePair],
+    selection: &FrameSelection,
+    reference_count: usize,
+) -> Result<Vec<&'a FramePair>> {
+    match selection {
+        FrameSelection::All => Ok(pairs.iter().collect()),
+        FrameSelection::Indices(indices) => {
+            if let Some(&last) = indices.last() {
+                if last >= reference_count {
+                    bail!(
+                        "Frame {} is outside the reference ({} frames)",
+                        last,
+                        reference_count
+                    );
+                }
+            }
+            Ok(pairs
+                .iter()
+                .filter(|p| p.stream_a_idx.is_some_and(|i| indices.contains(&i)))
+                .collect())
+        }
+    }
+}
+
+impl Planes {
+    /// Copy the visible area of a frame at its own bit depth
+    fn new(frame: &DecodedFrame) -> Result<Self> {
+        let width = frame.width as usize;
+        let height = frame.height as usize;
+        let (chroma_width, chroma_height) = match frame.chroma_format {
+            ChromaFormat::Yuv420 => (width / 2, height / 2),
+            ChromaFormat::Yuv422 => (width / 2, height),
+            ChromaFormat::Yuv444 => (width, height),
+            ChromaFormat::Monochrome => (0, 0),
+        };
+
+        let plane = |data: &[u8], stride: usize, w: usize, h: usize| {
+            pack_plane(data, stride, w, h, frame.bit_depth)
+                .ok_or_else(|| anyhow!("Decoded plane is smaller than {}x{}", w, h))
+        };
+        let y = plane(&frame.y_plane, frame.y_stride, width, height)?;
+        let chroma = match (&frame.u_plane, &frame.v_plane) {
+            (Some(u), Some(v)) if frame.chroma_format != ChromaFormat::Monochrome => Some((
+                plane(u, frame.u_stride, chroma_width, chroma_height)?,
+                plane(v, frame.v_stride, chroma_width, chroma_height)?,
+            )),
+            _ => None,
+        };
+
+        Ok(Self {
+            y,
+            chroma,
+            bit_depth: frame.bit_depth,
+            width,
+            height,
+            chroma_width,
+            chroma_height,
+        })
+    }
+}
+
+/// Copy `width`x`height` samples out of a plane whose rows are `stride`
+/// bytes apart. Samples above 8 bits are little-endian 16-bit words.
+fn pack_plane(
+    data: &[u8],
+    stride: usize,
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+) -> Option<Vec<u16>> {
+    let bytes_per_sample = if bit_depth > 8 { 2 } else { 1 };
+    let row_bytes = width * bytes_per_sample;
+    if stride < row_bytes {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(width * height);
+    for row in 0..height {
+        let start = row * stride;
+        let line = data.get(start..start + row_bytes)?;
+        if bytes_per_sample == 1 {
+            out.extend(line.iter().map(|&s| s as u16));
+        } else {
+            out.extend(
+                line.chunks_exact(2)
+                    .map(|s| u16::from_le_bytes([s[0], s[1]])),
+            );
+        }
+    }
+    Some(out)
+}
+
+/// Single-plane metric at a given bit depth, e.g. [`bitvue_metrics::psnr_hbd`]
+type PlaneMetric = fn(&[u16], &[u16], usize, usize, u8) -> bitvue_core::Result<f64>;
+
+/// Score all planes with a metric, or only luma for monochrome pictures
+fn plane_scores(a: &Planes, b: &Planes, metric: PlaneMetric) -> Result<PlaneScores> {
+    let y = metric(&a.y, &b.y, a.width, a.height, a.bit_depth)?;
+    let (u, v) = match (&a.chroma, &b.chroma) {
+        (Some((au, av)), Some((bu, bv))) => {
+            let (w, h) = (a.chroma_width, a.chroma_height);
+            (
+                Some(metric(au, bu, w, h, a.bit_depth)?),
+                Some(metric(av, bv, w, h, a.bit_depth)?),
+            )
+        }
+        _ => (None, None),
+    };
+    Ok(PlaneScores { y, u, v })
+}
+
+#[cfg(feature = "vmaf")]
+fn vmaf_scores(pictures: &[(usize, &Planes, &Planes)]) -> Result<Vec<f64>> {
+    use bitvue_metrics::vmaf::{compute_vmaf_per_frame, VmafFrame};
+
+    let Some((_, first, _)) = pictures.first() else {
+        return Ok(Vec::new());
+    };
+    // libvmaf takes samples above 8 bits as little-endian 16-bit words
+    let bytes = |plane: &[u16], bit_depth: u8| -> Vec<u8> {
+        if bit_depth > 8 {
+            plane.iter().flat_map(|s| s.to_le_bytes()).collect()
+        } else {
+            plane.iter().map(|&s| s as u8).collect()
+        }
+    };
+    let to_vmaf = |p: &Planes| {
+        let (u, v) = p
+            .chroma
+            .as_ref()
+            .map(|(u, v)| (bytes(u, p.bit_depth), bytes(v, p.bit_depth)))
+            .unwrap_or_default();
+        VmafFrame {
+            y: bytes(&p.y, p.bit_depth),
+            u,
+            v,
+            width: p.width,
+            height: p.height,
+            bit_depth: p.bit_depth,
+        }
+    };
+    let reference: Vec<VmafFrame> = pictures.iter().map(|(_, a, _)| to_vmaf(a)).collect();
+    let distorted: Vec<VmafFrame> = pictures.iter().map(|(_, _, b)| to_vmaf(b)).collect();
+    Ok(compute_vmaf_per_frame(
+        &reference,
+        &distorted,
+        first.width,
+        first.height,
+        None,
+    )?)
+}
+
+#[cfg(not(feature = "vmaf"))]
+fn vmaf_scores(_pictures: &[(usize, &Planes, &Planes)]) -> Result<Vec<f64>> {
+    bail!("VMAF is not available: rebuild bitvue-cli with the `vmaf` feature")
+}
+
+/// Value columns for the requested metrics, in table order
+fn columns(metrics: &[Metric]) -> Vec<Column> {
+    let mut columns = Vec::new();
+    for metric in metrics {
+        match metric {
+            Metric::Psnr => columns.extend([
+                Column {
+                    name: "psnr_y",
+                    precision: 3,
+                    value: |s| s.psnr.map(|p| p.y),
+                },
+                Column {
+                    name: "psnr_u",
+                    precision: 3,
+                    value: |s| s.psnr.and_then(|p| p.u),
+                },
+                Column {
+                    name: "psnr_v",
+                    precision: 3,
+                    value: |s| s.psnr.and_then(|p| p.v),
+                },
+            ]),
+            Metric::Ssim => columns.extend([
+                Column {
+                    name: "ssim_y",
+                    precision: 5,
+                    value: |s| s.ssim.map(|p| p.y),
+                },
+                Column {
+                    name: "ssim_u",
+                    precision: 5,
+                    value: |s| s.ssim.and_then(|p| p.u),
+                },
+                Column {
+                    name: "ssim_v",
+                    precision: 5,
+                    value: |s| s.ssim.and_then(|p| p.v),
+                },
+            ]),
+            Metric::Vmaf => columns.push(Column {
+                name: "vmaf",
+                precision: 3,
+                value: |s| s.vmaf,
+            }),
+        }
+    }
+    columns
+}
+
+/// Mean/min/max per column. Identical frames give infinite PSNR, which
+/// carries through to the mean.
+fn summarize(scores: &[FrameScores], columns: &[Column]) -> Vec<ColumnSummary> {
+    columns
+        .iter()
+        .filter_map(|column| {
+            let values: Vec<f64> = scores.iter().filter_map(column.value).collect();
+            if values.is_empty() {
+                return None;
+            }
+            Some(ColumnSummary {
+                metric: column.name,
+                frames: values.len(),
+                mean: values.iter().sum::<f64>() / values.len() as f64,
+                min: values.iter().copied().fold(f64::INFINITY, f64::min),
+                max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            })
+        })
+        .collect()
+}
+
+fn input_info(video: &Video) -> InputInfo {
+    let first = video.frames.first();
+    InputInfo {
+        file: video.path.display().to_string(),
+        format: video.format.clone(),
+        width: first.map(|f| f.width),
+        height: first.map(|f| f.height),
+        frame_count: video.frames.len(),
+        pts_quality: frame_index_map(video).pts_quality,
+    }
+}
+
+fn alignment_info(engine: &AlignmentEngine) -> AlignmentInfo {
+    AlignmentInfo {
+        method: engine.method.display_text(),
+        confidence: engine.confidence.display_text(),
+        pairs: engine.frame_pairs.len(),
+        gaps: engine.gap_count,
+        gap_percentage: engine.gap_percentage(),
+    }
+}
+
+fn format_value(value: Option<f64>, precision: usize) -> String {
+    value
+        .map(|v| format!("{:.*}", precision, v))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn print_text(
+    reference: &Video,
+    distorted: &Video,
+    engine: &AlignmentEngine,
+    scores: &[FrameScores],
+    columns: &[Column],
+    summary: &[ColumnSummary],
+) {
+    for (label, video) in [("Reference", reference), ("Distorted", distorted)] {
+        let info = input_info(video);
+        println!(
+            "{}: {} ({}, {}x{}, {} frames, {})",
+            label,
+            info.file,
+            info.format,
+            info.width.unwrap_or(0),
+            info.height.unwrap_or(0),
+            info.frame_count,
+            info.pts_quality.badge_text()
+        );
+    }
+    let alignment = alignment_info(engine);
+    println!(
+        "Alignment: {} ({} confidence), {} pairs, {} gaps ({:.1}%)",
+        alignment.method,
+        alignment.confidence,
+        alignment.pairs,
+        alignment.gaps,
+        alignment.gap_percentage
+    );
+    println!();
+
+    print!("{:>7} {:>7} {:>10}", "REF", "DIST", "PTS_DELTA");
+    for column in columns {
+        print!(" {:>9}", column.name.to_ascii_uppercase());
+    }
+    println!();
+    for row in scores {
+        print!(
+            "{:>7} {:>7} {:>10}",
+            crate::output::opt(row.reference_frame),
+            crate::output::opt(row.distorted_frame),
+            crate::output::opt(row.pts_delta_us)
+        );
+        for column in columns {
+            print!(
+                " {:>9}",
+                format_value((column.value)(row), column.precision)
+            );
+        }
+        println!();
+    }
+
+    if summary.is_empty() {
+        return;
+    }
+    println!();
+    println!(
+        "{:<8} {:>7} {:>10} {:>10} {:>10}",
+        "METRIC", "FRAMES", "MEAN", "MIN", "MAX"
+    );
+    for (s, column) in summary
+        .iter()
+        .filter_map(|s| columns.iter().find(|c| c.name == s.metric).map(|c| (s, c)))
+    {
+        println!(
+            "{:<8} {:>7} {:>10} {:>10} {:>10}",
+            s.metric,
+            s.frames,
+            format_value(Some(s.mean), column.precision),
+            format_value(Some(s.min), column.precision),
+            format_value(Some(s.max), column.precision)
+        );
+    }
+}
+
+/// Per-frame rows followed by `mean`, `min` and `max` rows
+fn print_csv(scores: &[FrameScores], columns: &[Column], summary: &[ColumnSummary]) {
+    let names: Vec<&str> = columns.iter().map(|c| c.name).collect();
+    println!(
+        "row,reference_frame,distorted_frame,pts_delta_us,{}",
+        names.join(",")
+    );
+    for (i, row) in scores.iter().enumerate() {
+        let values: Vec<String> = columns.iter().map(|c| csv_opt((c.value)(row))).collect();
+        println!(
+            "{},{},{},{},{}",
+            i,
+            csv_opt(row.reference_frame),
+            csv_opt(row.distorted_frame),
+            csv_opt(row.pts_delta_us),
+            values.join(",")
+        );
+    }
+
+    let print_stat = |label: &str, stat: fn(&ColumnSummary) -> f64| {
+        let values: Vec<String> = columns
+            .iter()
+            .map(|c| csv_opt(summary.iter().find(|s| s.metric == c.name).map(stat)))
+            .collect();
+        println!("{},,,,{}", label, values.join(","));
+    };
+    print_stat("mean", |s| s.mean);
+    print_stat("min", |s| s.min);
+    print_stat("max", |s| s.max);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_frame_selection() {
+        assert_eq!(parse_frame_selection("all").unwrap(), FrameSelection::All);
+        assert_eq!(
+            parse_frame_selection("0, 3-5,2").unwrap(),
+            FrameSelection::Indices([0, 2, 3, 4, 5].into())
+        );
+        assert!(parse_frame_selection("5-3").is_err());
+        assert!(parse_frame_selection("x").is_err());
+    }
+
+    #[test]
+    fn test_parse_metrics() {
+        assert_eq!(
+            parse_metrics("ssim,PSNR,ssim").unwrap(),
+            vec![Metric::Psnr, Metric::Ssim]
+        );
+        assert!(parse_metrics("mse").is_err());
+        assert!(parse_metrics("").is_err());
+    }
+
+    #[test]
+    fn test_raw_params() {
+        let params = raw_params("352x288", "yuv420p10le").unwrap();
+        assert_eq!((params.width, params.height), (352, 288));
+        assert_eq!(params.chroma_subsampling, ChromaSubsampling::Yuv420);
+        assert_eq!(params.bit_depth, BitDepth::Bit10);
+
+        let params = raw_params("64x64", "gray").unwrap();
+        assert_eq!(params.chroma_subsampling, ChromaSubsampling::Mono);
+        assert_eq!(params.bit_depth, BitDepth::Bit8);
+
+        assert!(raw_params("352", "yuv420p").is_err());
+        assert!(raw_params("352x288", "nv12").is_err());
+    }
+
+    #[test]
+    fn test_input_pts_quality() {
+        let video = |pts_us: Vec<Option<u64>>| Video {
+            path: PathBuf::from("in.mp4"),
+            format: "mp4/avc".to_string(),
+            frames: Vec::new(),
+            pts_us,
+        };
+        let presented = video(rebase(vec![Some(40_000), Some(80_000), Some(120_000)]));
+        assert_eq!(presented.pts_us, [Some(0), Some(40_000), Some(80_000)]);
+        assert_eq!(input_info(&presented).pts_quality, PtsQuality::Ok);
+
+        // A sample outside the edit list has no PTS
+        let edited = video(vec![None, Some(0), Some(40_000), Some(80_000)]);
+        assert_eq!(input_info(&edited).pts_quality, PtsQuality::Warn);
+    }
+
+    #[test]
+    fn test_pack_plane() {
+        // 2x2 plane in a stride of 3
+        let data = [1, 2, 0, 3, 4, 0];
+        assert_eq!(pack_plane(&data, 3, 2, 2, 8), Some(vec![1, 2, 3, 4]));
+        assert_eq!(pack_plane(&data, 3, 2, 3, 8), None);
+
+        // 10-bit little-endian samples keep their full value
+        let data = [0x00, 0x00, 0xFF, 0x03];
+        assert_eq!(pack_plane(&data, 4, 2, 1, 10), Some(vec![0, 1023]));
+        // Strides are in bytes, so a stride of two samples is too short
+        assert_eq!(pack_plane(&data, 2, 2, 1, 10), None);
+    }
+}
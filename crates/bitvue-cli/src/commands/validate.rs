@@ -1,14 +1,156 @@
 //! Validate bitstream syntax
+//!
+//! With `--verify-hash` (or `--md5`), the stream is also decoded and every
+//! picture is checked against its signalled hash.
+//!
+//! Exit codes, when the worst diagnostic reaches the `--fail-on` threshold:
+//! 1 for WARN (or INFO), 2 for ERROR, 3 for FATAL. Below the threshold the
+//! command exits with 0.
 
-use anyhow::Result;
+use crate::output::OutputFormat;
+use crate::picture_hash;
+use crate::validation;
+use anyhow::{bail, Result};
+use bitvue_core::diagnostics::{Diagnostic, DiagnosticSeverity, SeverityCounts};
+use serde::Serialize;
 use std::path::PathBuf;
 
-pub fn run(file_path: PathBuf, strict: bool) -> Result<()> {
+/// JSON document emitted by `validate --format json`
+#[derive(Serialize)]
+struct ValidationReport<'a> {
+    file: String,
+    passed: bool,
+    fail_on: DiagnosticSeverity,
+    strict: bool,
+    verify_hash: bool,
+    counts: SeverityCounts,
+    diagnostics: &'a [Diagnostic],
+}
+
+/// Validate a file and return the process exit code
+pub fn run(
+    file_path: PathBuf,
+    strict: bool,
+    verify_hash: bool,
+    md5: Option<PathBuf>,
+    fail_on: &str,
+    format: &str,
+) -> Result<i32> {
+    let format: OutputFormat = format.parse()?;
+    if format == OutputFormat::Csv {
+        bail!("CSV output is not supported for validate (use text or json)");
+    }
+    let threshold = parse_severity(fail_on)?;
+
+    let mut diagnostics = validation::validate(&file_path, strict);
+    let verify_hash = verify_hash || md5.is_some();
+    if verify_hash {
+        for diagnostic in picture_hash::verify(&file_path, md5.as_deref()) {
+            diagnostics.add(diagnostic);
+        }
+    }
+    let counts = diagnostics.count_by_severity();
+    let worst = diagnostics.diagnostics.iter().map(|d| d.severity).max();
+    let exit_code = match worst {
+        Some(severity) if severity >= threshold => exit_code(severity),
+        _ => 0,
+    };
+
+    match format {
+        OutputFormat::Json => {
+            let report = ValidationReport {
+                file: file_path.display().to_string(),
+                passed: exit_code == 0,
+                fail_on: threshold,
+                strict,
+                verify_hash,
+                counts,
+                diagnostics: &diagnostics.diagnostics,
+            };
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        _ => {
+            println!("File: {}", file_path.display());
+            for severity in [
+                DiagnosticSeverity::Fatal,
+                DiagnosticSeverity::Error,
+                DiagnosticSeverity::Warn,
+                DiagnosticSeverity::Info,
+            ] {
+                let group: Vec<&Diagnostic> = diagnostics
+                    .diagnostics
+                    .iter()
+                    .filter(|d| d.severity == severity)
+                    .collect();
+                if group.is_empty() {
+                    continue;
+                }
+                println!();
+                println!("{} ({})", severity.display_text(), group.len());
+                for d in group {
+                    print_diagnostic(d);
+                }
+            }
+
+            println!();
+            if strict && counts.error + counts.fatal > 0 {
+                println!("Stopped at the first error (--strict)");
+            }
+            println!(
+                "Summary: {} fatal, {} errors, {} warnings, {} info - {}",
+                counts.fatal,
+                counts.error,
+                counts.warn,
+                counts.info,
+                if exit_code == 0 { "PASS" } else { "FAIL" }
+            );
+        }
+    }
+
+    Ok(exit_code)
+}
+
+fn print_diagnostic(d: &Diagnostic) {
+    let location = match &d.frame_key {
+        Some(frame) => format!("frame {} @0x{:X}", frame.frame_index, d.offset_bytes),
+        None => format!("@0x{:X}", d.offset_bytes),
+    };
+    let bits = d
+        .bit_range
+        .map(|(start, end)| format!(" [bits {}..{}]", start, end))
+        .unwrap_or_default();
     println!(
-        "Validate command: {} (strict: {})",
-        file_path.display(),
-        strict
+        "  {:<24} {}: {}{}",
+        location,
+        d.category.display_text(),
+        d.message,
+        bits
     );
-    // TODO: Implement validation
-    Ok(())
+
+    let mut details: Vec<_> = d.details.iter().collect();
+    details.sort();
+    for (key, value) in details {
+        println!("  {:<24}   {}: {}", "", key, value);
+    }
+}
+
+fn parse_severity(s: &str) -> Result<DiagnosticSeverity> {
+    Ok(match s.to_ascii_lowercase().as_str() {
+        "info" => DiagnosticSeverity::Info,
+        "warn" | "warning" => DiagnosticSeverity::Warn,
+        "error" => DiagnosticSeverity::Error,
+        "fatal" => DiagnosticSeverity::Fatal,
+        other => bail!(
+            "Unknown severity '{}' (expected info, warn, error or fatal)",
+            other
+        ),
+    })
+}
+
+fn exit_code(severity: DiagnosticSeverity) -> i32 {
+    match severity {
+        DiagnosticSeverity::Info | DiagnosticSeverity::Warn => 1,
+        DiagnosticSeverity::Error => 2,
+        DiagnosticSeverity::Fatal => 3,
+    }
 }
@@ -6,7 +6,13 @@ use anyhow::Result;
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
+mod analysis;
 mod commands;
+mod output;
+mod picture_hash;
+mod stream;
+mod summary;
+mod validation;
 
 /// Bitvue - Professional AV1 Bitstream Analyzer
 #[derive(Parser, Debug)]
@@ -56,8 +62,8 @@ enum Commands {
         #[arg(short, long)]
         file: PathBuf,
 
-        /// Frame index (0-based)
-        #[arg(short = 'f', long)]
+        /// Frame index (0-based, decode order)
+        #[arg(long)]
         frame: usize,
 
         /// Show detailed syntax information
@@ -68,9 +74,13 @@ enum Commands {
         #[arg(long)]
         residual: bool,
 
-        /// Show coding flow
+        /// Show coding flow (partition, prediction and QP grids)
         #[arg(long)]
         coding_flow: bool,
+
+        /// Output format (text, json)
+        #[arg(short = 'F', long, default_value = "text")]
+        format: String,
     },
 
     /// Calculate quality metrics between two files
@@ -83,13 +93,25 @@ enum Commands {
         #[arg(long)]
         distorted: PathBuf,
 
-        /// Frame indices to analyze (comma-separated, or "all")
+        /// Reference frame indices to analyze (comma-separated indices or A-B ranges, or "all")
         #[arg(short = 'f', long, default_value = "0")]
         frames: String,
 
         /// Metrics to calculate (psnr, ssim, vmaf)
         #[arg(short = 'm', long, default_value = "psnr,ssim")]
         metrics: String,
+
+        /// Output format (text, json, csv)
+        #[arg(short = 'F', long, default_value = "text")]
+        format: String,
+
+        /// Frame size of raw .yuv inputs (WIDTHxHEIGHT)
+        #[arg(long)]
+        size: Option<String>,
+
+        /// Pixel format of raw .yuv inputs (yuv420p, yuv422p, yuv444p, gray; 10le/12le suffix for high bit depth)
+        #[arg(long, default_value = "yuv420p")]
+        pix_fmt: String,
     },
 
     /// Export analysis results to file
@@ -98,13 +120,25 @@ enum Commands {
         #[arg(short, long)]
         file: PathBuf,
 
-        /// Output file path
+        /// Output directory
         #[arg(short, long)]
         output: PathBuf,
 
-        /// Export format (json, csv, markdown)
-        #[arg(long, default_value = "json")]
+        /// Format of the frame and diagnostics tables (json, csv)
+        #[arg(short = 'F', long, default_value = "json")]
         format: String,
+
+        /// Artifacts to write (frames, diagnostics, summary, overlays, evidence, report, all)
+        #[arg(short, long, default_value = "frames,diagnostics,summary,report")]
+        artifacts: String,
+
+        /// Frames to render overlays for ("all", "N" or "A-B", decode order)
+        #[arg(long, default_value = "0")]
+        frames: String,
+
+        /// Overlay image format (png, ppm, rgba)
+        #[arg(long, default_value = "png")]
+        overlay_format: String,
     },
 
     /// Batch process multiple files
@@ -120,6 +154,14 @@ enum Commands {
         /// Output directory for results
         #[arg(short, long)]
         output: PathBuf,
+
+        /// Number of worker threads (default: one per CPU)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// Reprocess files that already have an up-to-date result
+        #[arg(long)]
+        force: bool,
     },
 
     /// Validate bitstream syntax
@@ -128,9 +170,25 @@ enum Commands {
         #[arg(short, long)]
         file: PathBuf,
 
-        /// Exit with error code on first failure
+        /// Stop at the first error
         #[arg(short, long)]
         strict: bool,
+
+        /// Decode the stream and check every picture against its decoded picture hash SEI
+        #[arg(long)]
+        verify_hash: bool,
+
+        /// Check decoded pictures against an MD5 side file instead (implies --verify-hash)
+        #[arg(long)]
+        md5: Option<PathBuf>,
+
+        /// Lowest severity that makes the command fail (info, warn, error, fatal)
+        #[arg(long, default_value = "error")]
+        fail_on: String,
+
+        /// Output format (text, json)
+        #[arg(short = 'F', long, default_value = "text")]
+        format: String,
     },
 }
 
@@ -169,33 +227,60 @@ fn main() -> Result<()> {
             syntax,
             residual,
             coding_flow,
+            format,
         } => {
-            commands::analyze::run(file, frame, syntax, residual, coding_flow)?;
+            commands::analyze::run(file, frame, syntax, residual, coding_flow, &format)?;
         }
         Commands::Quality {
             reference,
             distorted,
             frames,
             metrics,
+            format,
+            size,
+            pix_fmt,
         } => {
-            commands::quality::run(reference, distorted, &frames, &metrics)?;
+            commands::quality::run(
+                reference,
+                distorted,
+                &frames,
+                &metrics,
+                &format,
+                size.as_deref(),
+                &pix_fmt,
+            )?;
         }
         Commands::Export {
             file,
             output,
             format,
+            artifacts,
+            frames,
+            overlay_format,
         } => {
-            commands::export::run(file, output, &format)?;
+            commands::export::run(file, output, &format, &artifacts, &frames, &overlay_format)?;
         }
         Commands::Batch {
             directory,
             pattern,
             output,
+            jobs,
+            force,
         } => {
-            commands::batch::run(directory, &pattern, output)?;
+            commands::batch::run(directory, &pattern, output, jobs, force)?;
         }
-        Commands::Validate { file, strict } => {
-            commands::validate::run(file, strict)?;
+        Commands::Validate {
+            file,
+            strict,
+            verify_hash,
+            md5,
+            fail_on,
+            format,
+        } => {
+            let code = commands::validate::run(file, strict, verify_hash, md5, &fail_on, &format)?;
+            if code != 0 {
+                std::process::exit(code);
+            }
         }
     }
 
@@ -0,0 +1,304 @@
+//! Decoded picture hash verification
+//!
+//! [`verify`] decodes a stream and checks every output picture against the
+//! hash the encoder signalled for it: the decoded picture hash SEI of H.265
+//! and H.266 streams, or an MD5 side file as shipped with H.264 conformance streams
+//! (one digest per frame, or a single digest of the whole decoded output).
+//! Mismatches are reported as ERROR diagnostics tied to the frame. Streams
+//! this build cannot decode get a WARN diagnostic instead.
+
+use crate::analysis::{hevc_access_units, vvc_access_units};
+use crate::commands::quality;
+use crate::stream::{Codec, FrameRecord, VideoSource};
+use anyhow::{anyhow, bail, Context, Result};
+use bitvue_core::diagnostics::{Diagnostic, DiagnosticCategory, DiagnosticSeverity};
+use bitvue_core::StreamId;
+use bitvue_decode::{
+    frames_md5, parse_md5_file, verify_frame_md5, verify_picture_hash, HashMethod, PictureHash,
+};
+use bitvue_hevc::sei::PictureHashType;
+use bitvue_hevc::SeiParsedData;
+use std::path::Path;
+
+/// Decode a file and verify its pictures against the signalled hashes
+///
+/// Failing to open, parse or decode the stream is reported as an ERROR
+/// diagnostic, so callers always get a diagnostics list back.
+pub fn verify(path: &Path, md5_file: Option<&Path>) -> Vec<Diagnostic> {
+    try_verify(path, md5_file).unwrap_or_else(|e| {
+        vec![diagnostic(
+            DiagnosticSeverity::Error,
+            format!("Picture hash verification failed: {:#}", e),
+            0,
+        )]
+    })
+}
+
+fn try_verify(path: &Path, md5_file: Option<&Path>) -> Result<Vec<Diagnostic>> {
+    let source = VideoSource::open(path)?;
+    if let Some(feature) = quality::missing_decoder_feature(source.codec) {
+        return Ok(vec![diagnostic(
+            DiagnosticSeverity::Warn,
+            format!(
+                "Picture hash verification skipped: decoding {} needs bitvue-cli built with the `{}` feature",
+                source.codec, feature
+            ),
+            0,
+        )]);
+    }
+    let records = source.frames()?;
+
+    // Hashes are signalled in decode order; check them in output order
+    let mut output_order: Vec<&FrameRecord> = records
+        .iter()
+        .filter(|r| r.display_index.is_some())
+        .collect();
+    output_order.sort_by_key(|r| r.display_index);
+    let offsets: Vec<u64> = output_order.iter().map(|r| r.offset.unwrap_or(0)).collect();
+
+    let expected = match md5_file {
+        Some(md5_path) => Expected::Md5(read_md5_file(md5_path)?),
+        None => match source.codec {
+            Codec::Hevc | Codec::Vvc => {
+                let hashes = match source.codec {
+                    Codec::Hevc => hevc_sei_hashes(&source, &records)?,
+                    _ => vvc_sei_hashes(&source, &records)?,
+                };
+                Expected::Sei(
+                    output_order
+                        .iter()
+                        .map(|r| hashes[r.decode_index].clone())
+                        .collect(),
+                )
+            }
+            codec => bail!(
+                "{} streams carry no decoded picture hash; pass --md5 with a side file",
+                codec
+            ),
+        },
+    };
+
+    let frames: Vec<_> = quality::decode(&source)?
+        .into_iter()
+        .map(|(frame, _)| frame)
+        .collect();
+    let mut diagnostics = Vec::new();
+    let offset_of = |index: usize| offsets.get(index).copied().unwrap_or(0);
+
+    match expected {
+        // A single digest covers the whole decoded output
+        Expected::Md5(digests) if digests.len() == 1 && frames.len() > 1 => {
+            let actual = frames_md5(&frames)?;
+            if actual == digests[0] {
+                diagnostics.push(diagnostic(
+                    DiagnosticSeverity::Info,
+                    format!("Decoded output MD5 matches ({} frames)", frames.len()),
+                    0,
+                ));
+            } else {
+                diagnostics.push(
+                    diagnostic(
+                        DiagnosticSeverity::Error,
+                        format!("Decoded output MD5 mismatch ({} frames)", frames.len()),
+                        0,
+                    )
+                    .with_detail("expected".to_string(), hex(&digests[0]))
+                    .with_detail("actual".to_string(), hex(&actual)),
+                );
+            }
+        }
+        Expected::Md5(digests) => {
+            if digests.len() != frames.len() {
+                diagnostics.push(count_mismatch("MD5 digests", digests.len(), frames.len()));
+            }
+            let mismatches: Vec<Diagnostic> = frames
+                .iter()
+                .zip(&digests)
+                .enumerate()
+                .filter_map(|(i, (frame, digest))| verify_frame_md5(frame, digest, i, offset_of(i)))
+                .collect();
+            diagnostics.push(summary(
+                "MD5",
+                frames.len().min(digests.len()),
+                mismatches.len(),
+            ));
+            diagnostics.extend(mismatches);
+        }
+        Expected::Sei(hashes) => {
+            if hashes.len() != frames.len() {
+                diagnostics.push(count_mismatch(
+                    "output pictures",
+                    hashes.len(),
+                    frames.len(),
+                ));
+            }
+            let checked: Vec<(usize, &PictureHash)> = hashes
+                .iter()
+                .enumerate()
+                .filter_map(|(i, h)| Some((i, h.as_ref()?)))
+                .filter(|(i, _)| *i < frames.len())
+                .collect();
+            let unhashed = frames.len().min(hashes.len()) - checked.len();
+            if unhashed > 0 {
+                diagnostics.push(diagnostic(
+                    DiagnosticSeverity::Info,
+                    format!("{} pictures carry no decoded picture hash SEI", unhashed),
+                    0,
+                ));
+            }
+            let method = checked
+                .first()
+                .map(|(_, h)| h.method.name())
+                .unwrap_or("SEI");
+            let mismatches: Vec<Diagnostic> = checked
+                .iter()
+                .filter_map(|&(i, hash)| verify_picture_hash(&frames[i], hash, i, offset_of(i)))
+                .collect();
+            diagnostics.push(summary(method, checked.len(), mismatches.len()));
+            diagnostics.extend(mismatches);
+        }
+    }
+
+    Ok(diagnostics)
+}
+
+/// Hashes to compare the decoded output with
+enum Expected {
+    /// Digests from an MD5 side file
+    Md5(Vec<[u8; 16]>),
+    /// Decoded picture hash SEI of every output picture, in output order
+    Sei(Vec<Option<PictureHash>>),
+}
+
+fn read_md5_file(path: &Path) -> Result<Vec<[u8; 16]>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Cannot read MD5 file {}", path.display()))?;
+    let digests = parse_md5_file(&text);
+    if digests.is_empty() {
+        bail!("No MD5 digests found in {}", path.display());
+    }
+    Ok(digests)
+}
+
+/// Decoded picture hash SEI of every H.265 picture, in decode order
+fn hevc_sei_hashes(
+    source: &VideoSource,
+    records: &[FrameRecord],
+) -> Result<Vec<Option<PictureHash>>> {
+    let (es, _) = source.elementary_stream();
+    let stream =
+        bitvue_hevc::parse_hevc(&es).map_err(|e| anyhow!("H.265 parsing failed: {}", e))?;
+    let units = hevc_access_units(&stream);
+    if units.len() != records.len() {
+        bail!(
+            "Found {} access units but {} frames; cannot match hashes to pictures",
+            units.len(),
+            records.len()
+        );
+    }
+
+    Ok(units
+        .iter()
+        .map(|unit| {
+            stream
+                .sei_messages
+                .iter()
+                .filter(|sei| unit.contains(&sei.nal_index))
+                .find_map(|sei| match &sei.message.parsed {
+                    Some(SeiParsedData::DecodedPictureHash(hash)) => Some(PictureHash {
+                        method: match hash.hash_type {
+                            PictureHashType::Md5 => HashMethod::Md5,
+                            PictureHashType::Crc => HashMethod::Crc,
+                            PictureHashType::Checksum => HashMethod::Checksum,
+                        },
+                        planes: hash.component_hashes.clone(),
+                    }),
+                    _ => None,
+                })
+        })
+        .collect())
+}
+
+/// Decoded picture hash SEI of every H.266 picture, in decode order
+fn vvc_sei_hashes(
+    source: &VideoSource,
+    records: &[FrameRecord],
+) -> Result<Vec<Option<PictureHash>>> {
+    use bitvue_vvc::{NalUnitType, PictureHashType};
+
+    let (es, _) = source.elementary_stream();
+    let stream = bitvue_vvc::parse_vvc(&es).map_err(|e| anyhow!("H.266 parsing failed: {}", e))?;
+    let units = vvc_access_units(&stream);
+    if units.len() != records.len() {
+        bail!(
+            "Found {} access units but {} frames; cannot match hashes to pictures",
+            units.len(),
+            records.len()
+        );
+    }
+
+    Ok(units
+        .iter()
+        .map(|unit| {
+            stream.nal_units[unit.clone()]
+                .iter()
+                .filter(|nal| {
+                    matches!(
+                        nal.header.nal_unit_type,
+                        NalUnitType::PrefixSeiNut | NalUnitType::SuffixSeiNut
+                    )
+                })
+                .filter_map(|nal| bitvue_vvc::parse_sei(&nal.payload).ok())
+                .flatten()
+                .find_map(|sei| sei.decoded_picture_hash())
+                .map(|hash| PictureHash {
+                    method: match hash.hash_type {
+                        PictureHashType::Md5 => HashMethod::Md5,
+                        PictureHashType::Crc => HashMethod::Crc,
+                        PictureHashType::Checksum => HashMethod::Checksum,
+                    },
+                    planes: hash.component_hashes,
+                })
+        })
+        .collect())
+}
+
+fn diagnostic(severity: DiagnosticSeverity, message: String, offset: u64) -> Diagnostic {
+    Diagnostic::new(
+        0,
+        severity,
+        StreamId::A,
+        message,
+        DiagnosticCategory::Decode,
+        offset,
+    )
+}
+
+fn summary(method: &str, checked: usize, mismatches: usize) -> Diagnostic {
+    diagnostic(
+        DiagnosticSeverity::Info,
+        format!(
+            "Decoded picture hash ({}): {} pictures checked, {} mismatches",
+            method, checked, mismatches
+        ),
+        0,
+    )
+}
+
+fn count_mismatch(what: &str, expected: usize, decoded: usize) -> Diagnostic {
+    diagnostic(
+        DiagnosticSeverity::Warn,
+        format!(
+            "Expected {} {} but the decoder output {} frames; only the first {} are compared",
+            expected,
+            what,
+            decoded,
+            expected.min(decoded)
+        ),
+        0,
+    )
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,1107 @@
+//! Stream loading shared by the CLI commands
+//!
+//! Detects the container, extracts the video samples and splits them into
+//! per-frame records using the codec crates. Commands build on
+//! [`VideoSource`] (container + samples) and [`FrameRecord`] (one row per
+//! coded frame in decode order).
+
+use anyhow::{anyhow, bail, Context, Result};
+use bitvue_av1_codec::{is_annex_b, parse_annex_b, parse_ivf_frames, ObuIterator, ObuType};
+use bitvue_formats::container::detect_container_format;
+use bitvue_formats::{mkv, mp4, ts, ContainerFormat};
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Video codec carried by the input file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Codec {
+    Av1,
+    Avc,
+    Hevc,
+    Vvc,
+    Vp9,
+}
+
+impl Codec {
+    /// Short lowercase name, as used in reports
+    pub fn name(self) -> &'static str {
+        match self {
+            Codec::Av1 => "av1",
+            Codec::Avc => "avc",
+            Codec::Hevc => "hevc",
+            Codec::Vvc => "vvc",
+            Codec::Vp9 => "vp9",
+        }
+    }
+
+    /// Map an MP4 sample entry code (e.g. "avc1") to a codec
+    fn from_mp4_sample_entry(entry: &str) -> Option<Self> {
+        match entry {
+            "av01" => Some(Codec::Av1),
+            "avc1" | "avc3" => Some(Codec::Avc),
+            "hev1" | "hvc1" => Some(Codec::Hevc),
+            "vvc1" | "vvi1" => Some(Codec::Vvc),
+            "vp09" => Some(Codec::Vp9),
+            _ => None,
+        }
+    }
+
+    /// Map a Matroska CodecID (e.g. "V_MPEG4/ISO/AVC") to a codec
+    fn from_mkv_codec_id(codec_id: &str) -> Option<Self> {
+        match codec_id {
+            "V_AV1" => Some(Codec::Av1),
+            "V_MPEG4/ISO/AVC" => Some(Codec::Avc),
+            "V_MPEGH/ISO/HEVC" => Some(Codec::Hevc),
+            "V_MPEGI/ISO/VVC" => Some(Codec::Vvc),
+            "V_VP9" => Some(Codec::Vp9),
+            _ => None,
+        }
+    }
+
+    /// Whether the codec is NAL-unit based (Annex B / length-prefixed)
+    pub fn is_nal_based(self) -> bool {
+        matches!(self, Codec::Avc | Codec::Hevc | Codec::Vvc)
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Container the samples were extracted from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Container {
+    Ivf,
+    Mp4,
+    Matroska,
+    Ts,
+    AnnexB,
+    Obu,
+    /// AV1 Annex B (length-delimited OBUs)
+    #[serde(rename = "obu-annexb")]
+    ObuAnnexB,
+}
+
+impl Container {
+    pub fn name(self) -> &'static str {
+        match self {
+            Container::Ivf => "ivf",
+            Container::Mp4 => "mp4",
+            Container::Matroska => "matroska",
+            Container::Ts => "ts",
+            Container::AnnexB => "annexb",
+            Container::Obu => "obu",
+            Container::ObuAnnexB => "obu-annexb",
+        }
+    }
+
+    /// Whether NAL units are stored with length prefixes (ISO/IEC 14496-15)
+    pub fn uses_length_prefixed_nals(self) -> bool {
+        matches!(self, Container::Mp4 | Container::Matroska)
+    }
+}
+
+impl fmt::Display for Container {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// NAL unit length prefix size when the container does not signal one
+const DEFAULT_NAL_LENGTH_SIZE: usize = 4;
+
+/// Stream-level parameters a container signals for its video track
+struct TrackParams {
+    /// NAL unit length prefix size
+    nal_length_size: usize,
+    /// Ticks per second of the sample timestamps
+    timescale: Option<f64>,
+}
+
+impl Default for TrackParams {
+    fn default() -> Self {
+        Self {
+            nal_length_size: DEFAULT_NAL_LENGTH_SIZE,
+            timescale: None,
+        }
+    }
+}
+
+/// One container sample (IVF frame, MP4 sample, MKV block, TS PES payload)
+#[derive(Debug, Clone)]
+pub struct Sample {
+    /// Byte offset of the sample in the file, when the container exposes it
+    pub offset: Option<u64>,
+    /// Presentation timestamp in container timebase units
+    pub pts: Option<u64>,
+    /// Sample payload
+    pub data: Vec<u8>,
+}
+
+/// An opened input file: container, codec and the extracted samples
+#[derive(Debug)]
+pub struct VideoSource {
+    pub path: PathBuf,
+    pub file_size: u64,
+    pub container: Container,
+    pub codec: Codec,
+    pub samples: Vec<Sample>,
+    /// Bytes in the length prefix of each NAL unit, for containers that use
+    /// them (`lengthSizeMinusOne + 1` of the avcC/hvcC record)
+    pub nal_length_size: usize,
+    /// Ticks per second of [`Sample::pts`], when the container defines it
+    pub timescale: Option<f64>,
+}
+
+impl VideoSource {
+    /// Open a file, detect its container and codec, and extract its samples
+    pub fn open(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            bail!("File not found: {}", path.display());
+        }
+
+        let data =
+            std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+        let format = detect_container_format(path).unwrap_or(ContainerFormat::Unknown);
+
+        let mut params = TrackParams {
+            timescale: pts_timescale(format, &data),
+            ..TrackParams::default()
+        };
+        let (container, codec, samples) = match format {
+            ContainerFormat::IVF => open_ivf(&data)?,
+            ContainerFormat::MP4 => {
+                let (container, codec, samples, mp4_params) = open_mp4(&data)?;
+                params = mp4_params;
+                (container, codec, samples)
+            }
+            ContainerFormat::Matroska => {
+                let (container, codec, samples, mkv_params) = open_mkv(&data)?;
+                params = mkv_params;
+                (container, codec, samples)
+            }
+            ContainerFormat::AnnexB => open_annex_b(data)?,
+            ContainerFormat::AVI => bail!("AVI containers are not supported"),
+            ContainerFormat::Unknown if ts::is_ts(&data) => open_ts(&data)?,
+            ContainerFormat::Unknown => open_raw(data)?,
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            file_size: std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+            container,
+            codec,
+            samples,
+            nal_length_size: params.nal_length_size,
+            timescale: params.timescale,
+        })
+    }
+
+    /// Concatenate all samples into one elementary stream.
+    ///
+    /// NAL-based samples are converted to Annex B. The returned vector holds
+    /// the start offset of every sample inside the elementary stream.
+    pub fn elementary_stream(&self) -> (Vec<u8>, Vec<usize>) {
+        let mut es = Vec::new();
+        let mut starts = Vec::with_capacity(self.samples.len());
+
+        for sample in &self.samples {
+            starts.push(es.len());
+            if self.codec.is_nal_based() && self.container.uses_length_prefixed_nals() {
+                es.extend_from_slice(&length_prefixed_to_annex_b(
+                    &sample.data,
+                    self.nal_length_size,
+                ));
+            } else {
+                es.extend_from_slice(&sample.data);
+            }
+        }
+
+        (es, starts)
+    }
+
+    /// Extract per-frame records in decode order
+    pub fn frames(&self) -> Result<Vec<FrameRecord>> {
+        let mut frames = match self.codec {
+            Codec::Avc => self.avc_frames()?,
+            Codec::Hevc => self.hevc_frames()?,
+            Codec::Vvc => self.vvc_frames()?,
+            Codec::Vp9 => self.vp9_frames(),
+            Codec::Av1 => self.av1_frames(),
+        };
+        assign_display_indices(&mut frames);
+        Ok(frames)
+    }
+
+    /// Map an elementary stream offset back to (sample index, file offset)
+    pub fn locate(&self, starts: &[usize], es_offset: usize) -> (usize, Option<u64>) {
+        let sample_idx = starts
+            .partition_point(|&s| s <= es_offset)
+            .saturating_sub(1);
+        let offset = if self.container == Container::AnnexB {
+            Some(es_offset as u64)
+        } else {
+            self.samples.get(sample_idx).and_then(|s| s.offset)
+        };
+        (sample_idx, offset)
+    }
+
+    fn avc_frames(&self) -> Result<Vec<FrameRecord>> {
+        let (es, starts) = self.elementary_stream();
+        let frames = bitvue_avc::extract_annex_b_frames(&es)
+            .map_err(|e| anyhow!("H.264 frame extraction failed: {}", e))?;
+        let stream = bitvue_avc::parse_avc(&es).ok();
+
+        // First slice of every picture, in decode order. Only trusted when it
+        // lines up one-to-one with the extracted frames.
+        let pictures: Vec<&bitvue_avc::ParsedSlice> = stream
+            .as_ref()
+            .map(|s| {
+                s.slices
+                    .iter()
+                    .filter(|sl| sl.header.is_first_slice())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let aligned = pictures.len() == frames.len();
+
+        let mut segment = 0usize;
+        let mut records = Vec::with_capacity(frames.len());
+        for (idx, frame) in frames.iter().enumerate() {
+            if frame.is_idr && idx > 0 {
+                segment += 1;
+            }
+            let (sample_idx, offset) = self.locate(&starts, frame.offset);
+            let picture = aligned.then(|| pictures[idx]);
+            let header = picture.map(|p| &p.header).or(frame.slice_header.as_ref());
+
+            let frame_type = header
+                .map(|sh| bitvue_avc::AvcFrameType::from_slice_type(sh.slice_type))
+                .unwrap_or(frame.frame_type);
+            let qp = header.and_then(|sh| {
+                let pps = stream.as_ref()?.get_pps(sh.pic_parameter_set_id)?;
+                Some(sh.qp(pps))
+            });
+
+            records.push(FrameRecord {
+                decode_index: idx,
+                display_index: None,
+                frame_type: frame_type.as_str().to_string(),
+                size: frame.size,
+                offset,
+                pts: self.sample_pts(sample_idx, frames.len()),
+                qp,
+                temporal_id: None,
+                poc: picture.map(|p| p.poc),
+                show_frame: true,
+                order_segment: segment,
+                data: frame.nal_data.clone(),
+            });
+        }
+        Ok(records)
+    }
+
+    fn hevc_frames(&self) -> Result<Vec<FrameRecord>> {
+        let (es, starts) = self.elementary_stream();
+        let frames = bitvue_hevc::extract_annex_b_frames(&es)
+            .map_err(|e| anyhow!("H.265 frame extraction failed: {}", e))?;
+        let stream = bitvue_hevc::parse_hevc(&es).ok();
+
+        let pictures: Vec<&bitvue_hevc::ParsedSlice> = stream
+            .as_ref()
+            .map(|s| {
+                s.slices
+                    .iter()
+                    .filter(|sl| sl.header.first_slice_segment_in_pic_flag)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let aligned = pictures.len() == frames.len();
+
+        let mut segment = 0usize;
+        let mut records = Vec::with_capacity(frames.len());
+        for (idx, frame) in frames.iter().enumerate() {
+            if frame.is_idr && idx > 0 {
+                segment += 1;
+            }
+            let (sample_idx, offset) = self.locate(&starts, frame.offset);
+            let picture = aligned.then(|| pictures[idx]);
+            let header = picture.map(|p| &p.header).or(frame.slice_header.as_ref());
+
+            let frame_type = header
+                .map(|sh| bitvue_hevc::HevcFrameType::from_slice_type(sh.slice_type.as_str()))
+                .unwrap_or(frame.frame_type);
+            let qp = header.and_then(|sh| {
+                let pps = stream.as_ref()?.get_pps(sh.slice_pic_parameter_set_id)?;
+                Some(i32::from(sh.qp(pps)))
+            });
+
+            records.push(FrameRecord {
+                decode_index: idx,
+                display_index: None,
+                frame_type: frame_type.as_str().to_string(),
+                size: frame.size,
+                offset,
+                pts: self.sample_pts(sample_idx, frames.len()),
+                qp,
+                temporal_id: frame.temporal_id,
+                poc: picture.map(|p| p.poc),
+                show_frame: true,
+                order_segment: segment,
+                data: frame.nal_data.clone(),
+            });
+        }
+        Ok(records)
+    }
+
+    fn vvc_frames(&self) -> Result<Vec<FrameRecord>> {
+        let (es, starts) = self.elementary_stream();
+        let frames = bitvue_vvc::extract_annex_b_frames(&es)
+            .map_err(|e| anyhow!("H.266 frame extraction failed: {}", e))?;
+
+        let mut segment = 0usize;
+        let mut records = Vec::with_capacity(frames.len());
+        for (idx, frame) in frames.iter().enumerate() {
+            if frame.is_idr && idx > 0 {
+                segment += 1;
+            }
+            let (sample_idx, offset) = self.locate(&starts, frame.offset);
+
+            records.push(FrameRecord {
+                decode_index: idx,
+                display_index: None,
+                frame_type: frame.frame_type.as_str().to_string(),
+                size: frame.size,
+                offset,
+                pts: self.sample_pts(sample_idx, frames.len()),
+                qp: Some(i32::from(frame.qp)),
+                temporal_id: Some(frame.temporal_id),
+                poc: Some(frame.poc),
+                show_frame: true,
+                order_segment: segment,
+                data: frame.nal_data.clone(),
+            });
+        }
+        Ok(records)
+    }
+
+    fn vp9_frames(&self) -> Vec<FrameRecord> {
+        use bitvue_core::qp_extraction::QpData;
+        use bitvue_vp9::frame_header::parse_frame_header;
+
+        let mut records = Vec::new();
+        for sample in &self.samples {
+            let frames: Vec<(usize, &[u8])> = match bitvue_vp9::parse_superframe_index(&sample.data)
+            {
+                Ok(index) if index.is_superframe() => index
+                    .frame_offsets
+                    .iter()
+                    .zip(index.frame_sizes.iter())
+                    .filter_map(|(&off, &size)| {
+                        let start = off as usize;
+                        let end = start.checked_add(size as usize)?;
+                        sample.data.get(start..end).map(|d| (start, d))
+                    })
+                    .collect(),
+                _ => vec![(0, sample.data.as_slice())],
+            };
+
+            for (rel_offset, frame_data) in frames {
+                let header = parse_frame_header(frame_data).ok();
+                let (frame_type, qp, show_frame) = match &header {
+                    Some(h) => (
+                        bitvue_vp9::Vp9FrameType::from_frame_type(h.frame_type).as_str(),
+                        QpData::from_vp9_qindex(h.quantization.base_q_idx)
+                            .qp_avg
+                            .map(i32::from),
+                        h.show_frame,
+                    ),
+                    None => ("Unknown", None, true),
+                };
+
+                records.push(FrameRecord {
+                    decode_index: records.len(),
+                    display_index: None,
+                    frame_type: frame_type.to_string(),
+                    size: frame_data.len(),
+                    offset: sample.offset.map(|o| o + rel_offset as u64),
+                    pts: sample.pts,
+                    qp,
+                    temporal_id: None,
+                    poc: None,
+                    show_frame,
+                    order_segment: 0,
+                    data: frame_data.to_vec(),
+                });
+            }
+        }
+        records
+    }
+
+    fn av1_frames(&self) -> Vec<FrameRecord> {
+        let mut records: Vec<FrameRecord> = Vec::new();
+
+        for sample in &self.samples {
+            let mut iter = ObuIterator::new(&sample.data);
+            // Index into `records` of the frame that following tile groups belong to
+            let mut open_frame: Option<usize> = None;
+
+            while let Some(next) = iter.next_obu_with_offset() {
+                let Ok(item) = next else {
+                    tracing::warn!("Stopping OBU scan at offset {}", iter.current_offset());
+                    break;
+                };
+                let obu = item.obu;
+                let obu_bytes = &sample.data[item.offset..item.offset + item.consumed];
+
+                match obu.header.obu_type {
+                    ObuType::Frame | ObuType::FrameHeader => {
+                        let header = obu.frame_header.as_ref();
+                        let show_existing = header.is_some_and(|h| h.show_existing_frame);
+                        let frame_type = if show_existing {
+                            "SHOW_EXISTING".to_string()
+                        } else {
+                            obu.frame_type
+                                .map(|t| t.as_str().to_string())
+                                .unwrap_or_else(|| "Unknown".to_string())
+                        };
+
+                        records.push(FrameRecord {
+                            decode_index: records.len(),
+                            display_index: None,
+                            frame_type,
+                            size: item.consumed,
+                            offset: sample.offset.map(|o| o + item.offset as u64),
+                            pts: sample.pts,
+                            qp: header.and_then(|h| h.base_q_idx).map(i32::from),
+                            temporal_id: Some(obu.header.temporal_id),
+                            poc: None,
+                            show_frame: header.is_none_or(|h| h.show_frame || show_existing),
+                            order_segment: 0,
+                            data: obu_bytes.to_vec(),
+                        });
+                        open_frame = (obu.header.obu_type == ObuType::FrameHeader)
+                            .then(|| records.len() - 1);
+                    }
+                    ObuType::TileGroup => {
+                        if let Some(rec) = open_frame.and_then(|i| records.get_mut(i)) {
+                            rec.size += item.consumed;
+                            rec.data.extend_from_slice(obu_bytes);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        records
+    }
+
+    /// PTS of a sample, only when samples map one-to-one onto frames
+    fn sample_pts(&self, sample_idx: usize, frame_count: usize) -> Option<u64> {
+        if self.samples.len() == frame_count {
+            self.samples.get(sample_idx).and_then(|s| s.pts)
+        } else {
+            None
+        }
+    }
+}
+
+/// One coded frame, in decode order
+#[derive(Debug, Clone, Serialize)]
+pub struct FrameRecord {
+    /// Position in decode (bitstream) order
+    pub decode_index: usize,
+    /// Position in display order (`None` for frames that are never shown)
+    pub display_index: Option<usize>,
+    /// Frame type as reported by the codec crate
+    pub frame_type: String,
+    /// Coded size in bytes
+    pub size: usize,
+    /// Byte offset in the file, when known
+    pub offset: Option<u64>,
+    /// Presentation timestamp in container timebase units
+    pub pts: Option<u64>,
+    /// Frame QP (slice QP for AVC/HEVC/VVC, base_q_idx for AV1, VP9 qindex mapped to QP)
+    pub qp: Option<i32>,
+    /// Temporal layer ID
+    pub temporal_id: Option<u8>,
+    /// Picture order count (AVC/HEVC/VVC)
+    #[serde(skip)]
+    pub poc: Option<i32>,
+    /// Whether the frame is output (AV1/VP9 show_frame)
+    #[serde(skip)]
+    pub show_frame: bool,
+    /// POC reset segment (incremented at every IDR)
+    #[serde(skip)]
+    pub order_segment: usize,
+    /// Coded frame bytes (Annex B NAL units, OBUs or VP9 frame)
+    #[serde(skip)]
+    pub data: Vec<u8>,
+}
+
+/// Derive display order from PTS, then POC, then show_frame order
+fn assign_display_indices(frames: &mut [FrameRecord]) {
+    let shown: Vec<usize> = (0..frames.len())
+        .filter(|&i| frames[i].show_frame)
+        .collect();
+
+    let mut order = shown.clone();
+    if shown.iter().all(|&i| frames[i].pts.is_some()) && has_distinct_pts(frames, &shown) {
+        order.sort_by_key(|&i| (frames[i].pts, i));
+    } else if shown.iter().all(|&i| frames[i].poc.is_some()) {
+        order.sort_by_key(|&i| (frames[i].order_segment, frames[i].poc, i));
+    }
+
+    for (display_idx, &i) in order.iter().enumerate() {
+        frames[i].display_index = Some(display_idx);
+    }
+}
+
+fn has_distinct_pts(frames: &[FrameRecord], shown: &[usize]) -> bool {
+    let mut pts: Vec<u64> = shown.iter().filter_map(|&i| frames[i].pts).collect();
+    pts.sort_unstable();
+    pts.windows(2).all(|w| w[0] != w[1])
+}
+
+/// Ticks per second of the timestamps an IVF or TS container attaches to
+/// its samples; MP4 and Matroska report theirs when their tracks are opened
+fn pts_timescale(format: ContainerFormat, data: &[u8]) -> Option<f64> {
+    match format {
+        // IVF header: time base denominator at 16, numerator at 20
+        ContainerFormat::IVF => {
+            let field = |at: usize| -> Option<u32> {
+                Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+            };
+            let (rate, scale) = (field(16)?, field(20)?);
+            (rate > 0 && scale > 0).then(|| rate as f64 / scale as f64)
+        }
+        ContainerFormat::Unknown if ts::is_ts(data) => Some(90_000.0),
+        _ => None,
+    }
+}
+
+fn open_ivf(data: &[u8]) -> Result<(Container, Codec, Vec<Sample>)> {
+    let (header, frames) =
+        parse_ivf_frames(data).map_err(|e| anyhow!("IVF parsing failed: {}", e))?;
+
+    let codec = match &header.fourcc {
+        b"AV01" => Codec::Av1,
+        b"VP90" => Codec::Vp9,
+        other => bail!("Unsupported IVF FourCC: {}", String::from_utf8_lossy(other)),
+    };
+
+    // IVF frame header: 4-byte size + 8-byte timestamp
+    let mut offset = header.header_size as u64;
+    let samples = frames
+        .into_iter()
+        .map(|f| {
+            let sample = Sample {
+                offset: Some(offset + 12),
+                pts: Some(f.timestamp),
+                data: f.data,
+            };
+            offset += 12 + f.size as u64;
+            sample
+        })
+        .collect();
+
+    Ok((Container::Ivf, codec, samples))
+}
+
+/// Open an MP4 file; also returns the parameters of its video track
+fn open_mp4(data: &[u8]) -> Result<(Container, Codec, Vec<Sample>, TrackParams)> {
+    let info = mp4::parse_mp4(data).map_err(|e| anyhow!("MP4 parsing failed: {}", e))?;
+    let entry = info
+        .codec
+        .as_deref()
+        .ok_or_else(|| anyhow!("No video track found in MP4"))?;
+    let codec = Codec::from_mp4_sample_entry(entry)
+        .ok_or_else(|| anyhow!("Unsupported MP4 sample entry: {}", entry))?;
+
+    let extracted = match codec {
+        Codec::Av1 => mp4::extract_av1_samples(data),
+        Codec::Avc => mp4::extract_avc_samples(data),
+        Codec::Hevc => mp4::extract_hevc_samples(data),
+        Codec::Vvc => mp4::extract_vvc_samples(data),
+        Codec::Vp9 => bail!("VP9 in MP4 is not supported"),
+    }
+    .map_err(|e| anyhow!("MP4 sample extraction failed: {}", e))?;
+
+    let mut samples: Vec<Sample> = extracted
+        .iter()
+        .enumerate()
+        .map(|(i, s)| Sample {
+            offset: offset_within(data, s),
+            pts: info.presentation_timestamps.get(i).copied().flatten(),
+            data: s.to_vec(),
+        })
+        .collect();
+
+    // vvcC is a FullBox; the configuration record follows its version and flags
+    let record = match codec {
+        Codec::Vvc => info.codec_config.as_deref().and_then(vvcc_record),
+        _ => info.codec_config.as_deref(),
+    };
+    let params = TrackParams {
+        nal_length_size: apply_config_record(codec, record, &mut samples),
+        timescale: (info.timescale > 0).then_some(info.timescale as f64),
+    };
+
+    Ok((Container::Mp4, codec, samples, params))
+}
+
+/// Open a Matroska file; also returns the parameters of its video track
+fn open_mkv(data: &[u8]) -> Result<(Container, Codec, Vec<Sample>, TrackParams)> {
+    let info = mkv::parse_mkv(data).map_err(|e| anyhow!("Matroska parsing failed: {}", e))?;
+    let codec_id = info
+        .codec_id
+        .as_deref()
+        .ok_or_else(|| anyhow!("No video track found in Matroska file"))?;
+    let codec = Codec::from_mkv_codec_id(codec_id)
+        .ok_or_else(|| anyhow!("Unsupported Matroska codec: {}", codec_id))?;
+
+    let mut samples: Vec<Sample> = info
+        .samples
+        .into_iter()
+        .enumerate()
+        .map(|(i, data)| Sample {
+            offset: None,
+            pts: info.timestamps.get(i).copied(),
+            data,
+        })
+        .collect();
+
+    // CodecPrivate holds the bare decoder configuration record
+    let params = TrackParams {
+        nal_length_size: apply_config_record(codec, info.codec_private.as_deref(), &mut samples),
+        // Block timecodes in the default TimestampScale of 1 ms
+        timescale: Some(1_000.0),
+    };
+
+    Ok((Container::Matroska, codec, samples, params))
+}
+
+/// Put the parameter sets of an avcC/hvcC/vvcC decoder configuration record
+/// in front of the first sample, so the elementary stream is self-contained,
+/// and return the NAL unit length prefix size the record signals
+fn apply_config_record(codec: Codec, record: Option<&[u8]>, samples: &mut [Sample]) -> usize {
+    let Some(record) = record else {
+        return DEFAULT_NAL_LENGTH_SIZE;
+    };
+    let nal_length_size =
+        config_record_nal_length_size(codec, record).unwrap_or(DEFAULT_NAL_LENGTH_SIZE);
+
+    if let Some(first) = samples.first_mut() {
+        let mut parameter_sets = config_record_nals(codec, record, nal_length_size);
+        if !parameter_sets.is_empty() {
+            parameter_sets.append(&mut first.data);
+            first.data = parameter_sets;
+        }
+    }
+    nal_length_size
+}
+
+fn open_ts(data: &[u8]) -> Result<(Container, Codec, Vec<Sample>)> {
+    let streams = ts::video_streams(data).map_err(|e| anyhow!("TS parsing failed: {}", e))?;
+
+    // First video stream the analyzers support, in program order
+    let supported = |codec| match codec {
+        ts::TsVideoCodec::Av1 => Some(Codec::Av1),
+        ts::TsVideoCodec::Avc => Some(Codec::Avc),
+        ts::TsVideoCodec::Hevc => Some(Codec::Hevc),
+        ts::TsVideoCodec::Vvc => Some(Codec::Vvc),
+        ts::TsVideoCodec::Mpeg2 => None,
+    };
+    let Some((stream, codec)) = streams
+        .iter()
+        .find_map(|s| supported(s.codec).map(|codec| (s, codec)))
+    else {
+        match streams.first() {
+            Some(s) => bail!(
+                "{} video in transport streams is not supported",
+                s.codec.name()
+            ),
+            None => bail!("No supported video stream found in transport stream"),
+        }
+    };
+    if streams.len() > 1 {
+        tracing::info!(
+            "Transport stream has {} video streams; using PID 0x{:04X} of program {}",
+            streams.len(),
+            stream.pid,
+            stream.program_number
+        );
+    }
+
+    let info =
+        ts::parse_ts_pid(data, stream.pid).map_err(|e| anyhow!("TS parsing failed: {}", e))?;
+    if info.continuity_errors > 0 {
+        tracing::warn!(
+            "Dropped {} PES packets after continuity counter gaps",
+            info.continuity_errors
+        );
+    }
+    let samples = info
+        .samples
+        .into_iter()
+        .zip(info.timestamps)
+        .map(|(data, pts)| Sample {
+            offset: None,
+            pts,
+            data,
+        })
+        .collect();
+
+    Ok((Container::Ts, codec, samples))
+}
+
+fn open_annex_b(data: Vec<u8>) -> Result<(Container, Codec, Vec<Sample>)> {
+    let codec = detect_annex_b_codec(&data)
+        .ok_or_else(|| anyhow!("Could not identify H.264, H.265 or H.266 NAL units"))?;
+    let sample = Sample {
+        offset: Some(0),
+        pts: None,
+        data,
+    };
+    Ok((Container::AnnexB, codec, vec![sample]))
+}
+
+/// AV1 Annex B: one sample per temporal unit, rewritten as low-overhead
+/// OBUs for the parsers and the decoder
+fn open_obu_annex_b(data: &[u8]) -> Result<(Container, Codec, Vec<Sample>)> {
+    let units = parse_annex_b(data).map_err(|e| anyhow!("Annex B parsing failed: {}", e))?;
+    let samples = units
+        .iter()
+        .map(|unit| Sample {
+            // Offsets inside the rewritten data no longer match the file
+            offset: None,
+            pts: None,
+            data: unit.to_low_overhead(data),
+        })
+        .collect();
+    Ok((Container::ObuAnnexB, Codec::Av1, samples))
+}
+
+/// Fallback for files without a recognised container: Annex B or raw OBUs
+fn open_raw(data: Vec<u8>) -> Result<(Container, Codec, Vec<Sample>)> {
+    if has_start_code(&data) {
+        return open_annex_b(data);
+    }
+    if is_annex_b(&data) {
+        return open_obu_annex_b(&data);
+    }
+    if ObuIterator::new(&data).take(4).any(|obu| {
+        obu.is_ok_and(|o| {
+            o.header.obu_type == ObuType::TemporalDelimiter
+                || o.header.obu_type == ObuType::SequenceHeader
+        })
+    }) {
+        let sample = Sample {
+            offset: Some(0),
+            pts: None,
+            data,
+        };
+        return Ok((Container::Obu, Codec::Av1, vec![sample]));
+    }
+    bail!("Unrecognized file format")
+}
+
+/// Tell H.264, H.265 and H.266 apart by looking for parameter-set NAL headers
+///
+/// H.264 SPS/PPS headers have `nal_unit_type` 7/8 in a one-byte header; H.265
+/// VPS/SPS/PPS use types 32-34 in a two-byte header whose second byte carries
+/// `nuh_layer_id = 0` and `nuh_temporal_id_plus1 = 1`. H.266 VPS/SPS/PPS use
+/// types 14-16 in the second byte of a two-byte header whose first byte is
+/// zero for layer 0.
+fn detect_annex_b_codec(data: &[u8]) -> Option<Codec> {
+    let mut avc_votes = 0usize;
+    let mut hevc_votes = 0usize;
+    let mut vvc_votes = 0usize;
+
+    for i in 0..data.len().saturating_sub(4) {
+        if data[i] != 0 || data[i + 1] != 0 || data[i + 2] != 1 {
+            continue;
+        }
+        let b0 = data[i + 3];
+        let b1 = data[i + 4];
+        if b0 & 0x80 != 0 {
+            continue;
+        }
+        if matches!((b0 >> 1) & 0x3F, 32..=34) && b1 == 0x01 {
+            hevc_votes += 1;
+        } else if b0 == 0 && matches!(b1 >> 3, 14..=16) && b1 & 0x07 == 1 {
+            vvc_votes += 1;
+        } else if matches!(b0 & 0x1F, 7 | 8) && b0 & 0x60 != 0 {
+            avc_votes += 1;
+        }
+        if avc_votes + hevc_votes + vvc_votes >= 16 {
+            break;
+        }
+    }
+
+    // Ties go to the later entry
+    [
+        (Codec::Avc, avc_votes),
+        (Codec::Vvc, vvc_votes),
+        (Codec::Hevc, hevc_votes),
+    ]
+    .into_iter()
+    .filter(|&(_, votes)| votes > 0)
+    .max_by_key(|&(_, votes)| votes)
+    .map(|(codec, _)| codec)
+}
+
+/// Byte offset of `slice` within `data`, if it borrows from it
+fn offset_within(data: &[u8], slice: &[u8]) -> Option<u64> {
+    let base = data.as_ptr() as usize;
+    let start = slice.as_ptr() as usize;
+    (start >= base && start + slice.len() <= base + data.len()).then(|| (start - base) as u64)
+}
+
+fn has_start_code(data: &[u8]) -> bool {
+    data.starts_with(&[0, 0, 1]) || data.starts_with(&[0, 0, 0, 1])
+}
+
+/// NAL unit length prefix size signalled in an avcC/hvcC/vvcC record
+///
+/// Returns `None` for other codecs or truncated records.
+fn config_record_nal_length_size(codec: Codec, record: &[u8]) -> Option<usize> {
+    // lengthSizeMinusOne is the low two bits of byte 4 (avcC) or 21 (hvcC),
+    // and bits 1-2 of the first record byte (vvcC)
+    let length_size_minus_one = match codec {
+        Codec::Avc => record.get(4)? & 0x03,
+        Codec::Hevc => record.get(21)? & 0x03,
+        Codec::Vvc => (record.first()? >> 1) & 0x03,
+        Codec::Av1 | Codec::Vp9 => return None,
+    };
+    Some(length_size_minus_one as usize + 1)
+}
+
+/// VVCDecoderConfigurationRecord of a vvcC box, which is a FullBox: the
+/// record follows the version and flags
+fn vvcc_record(config: &[u8]) -> Option<&[u8]> {
+    config.get(4..)
+}
+
+/// Offset of `num_of_arrays` in a VVCDecoderConfigurationRecord
+/// (ISO/IEC 14496-15 11.2.4.2), skipping the optional VvcPTLRecord
+fn vvcc_arrays_offset(record: &[u8]) -> Option<usize> {
+    let ptl_present = record.first()? & 0x01 != 0;
+    if !ptl_present {
+        return Some(1);
+    }
+    // ols_idx(9) num_sublayers(3) constant_frame_rate(2) chroma_format_idc(2)
+    let num_sublayers = (record.get(2)? >> 4) & 0x07;
+    // bit_depth_minus8(3) reserved(5), then the VvcPTLRecord
+    let mut pos = 4;
+    let num_bytes_constraint_info = (record.get(pos)? & 0x3F) as usize;
+    // profile/tier and level bytes, then the constraint info
+    pos += 3 + num_bytes_constraint_info;
+    if num_sublayers > 1 {
+        // ptl_sublayer_level_present_flag[i] from the MSB, padded to a byte
+        let flags = *record.get(pos)? >> (9 - num_sublayers);
+        pos += 1 + flags.count_ones() as usize;
+    }
+    let num_sub_profiles = *record.get(pos)? as usize;
+    pos += 1 + 4 * num_sub_profiles;
+    // max_picture_width, max_picture_height, avg_frame_rate
+    Some(pos + 6)
+}
+
+/// Parameter-set NAL units from an avcC/hvcC/vvcC record, with length prefixes of
+/// `length_size` bytes
+///
+/// Returns an empty vector for other codecs, truncated records, or parameter
+/// sets too long for the prefix.
+fn config_record_nals(codec: Codec, record: &[u8], length_size: usize) -> Vec<u8> {
+    let push_nals =
+        |out: &mut Vec<u8>, record: &[u8], pos: &mut usize, count: usize| -> Option<()> {
+            for _ in 0..count {
+                let len = u16::from_be_bytes([*record.get(*pos)?, *record.get(*pos + 1)?]) as usize;
+                let nal = record.get(*pos + 2..*pos + 2 + len)?;
+                let prefix = (len as u64).to_be_bytes();
+                let (high, low) = prefix.split_at(prefix.len() - length_size);
+                if high.iter().any(|&b| b != 0) {
+                    return None;
+                }
+                out.extend_from_slice(low);
+                out.extend_from_slice(nal);
+                *pos += 2 + len;
+            }
+            Some(())
+        };
+
+    let mut out = Vec::new();
+    let parsed = match codec {
+        // AVCDecoderConfigurationRecord (ISO/IEC 14496-15 5.3.3.1)
+        Codec::Avc => (|| {
+            let mut pos = 5;
+            let sps_count = (*record.get(pos)? & 0x1F) as usize;
+            pos += 1;
+            push_nals(&mut out, record, &mut pos, sps_count)?;
+            let pps_count = *record.get(pos)? as usize;
+            pos += 1;
+            push_nals(&mut out, record, &mut pos, pps_count)
+        })(),
+        // HEVCDecoderConfigurationRecord (ISO/IEC 14496-15 8.3.3.1)
+        Codec::Hevc => (|| {
+            let mut pos = 22;
+            let array_count = *record.get(pos)? as usize;
+            pos += 1;
+            for _ in 0..array_count {
+                let nal_count =
+                    u16::from_be_bytes([*record.get(pos + 1)?, *record.get(pos + 2)?]) as usize;
+                pos += 3;
+                push_nals(&mut out, record, &mut pos, nal_count)?;
+            }
+            Some(())
+        })(),
+        // VVCDecoderConfigurationRecord (ISO/IEC 14496-15 11.2.4.2)
+        Codec::Vvc => (|| {
+            const OPI_NUT: u8 = 12;
+            const DCI_NUT: u8 = 13;
+            let mut pos = vvcc_arrays_offset(record)?;
+            let array_count = *record.get(pos)? as usize;
+            pos += 1;
+            for _ in 0..array_count {
+                let nal_unit_type = *record.get(pos)? & 0x1F;
+                pos += 1;
+                let nal_count = if matches!(nal_unit_type, OPI_NUT | DCI_NUT) {
+                    1
+                } else {
+                    let count =
+                        u16::from_be_bytes([*record.get(pos)?, *record.get(pos + 1)?]) as usize;
+                    pos += 2;
+                    count
+                };
+                push_nals(&mut out, record, &mut pos, nal_count)?;
+            }
+            Some(())
+        })(),
+        Codec::Av1 | Codec::Vp9 => return Vec::new(),
+    };
+
+    if parsed.is_none() {
+        tracing::warn!("Malformed {} decoder configuration record", codec);
+        out.clear();
+    }
+    out
+}
+
+/// Convert length-prefixed NAL units (MP4/MKV) to Annex B
+///
+/// `length_size` is the prefix size in bytes, 1 to 4.
+pub fn length_prefixed_to_annex_b(data: &[u8], length_size: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + 16);
+    let mut pos = 0usize;
+
+    while let Some(len_bytes) = data.get(pos..pos + length_size) {
+        let len = len_bytes
+            .iter()
+            .fold(0usize, |len, &b| (len << 8) | b as usize);
+        pos += length_size;
+        let Some(nal) = pos.checked_add(len).and_then(|end| data.get(pos..end)) else {
+            break;
+        };
+        out.extend_from_slice(&[0, 0, 0, 1]);
+        out.extend_from_slice(nal);
+        pos += len;
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_length_prefixed_to_annex_b() {
+        let two_byte = [0, 2, 0x65, 0xAA, 0, 1, 0x41];
+        assert_eq!(
+            length_prefixed_to_annex_b(&two_byte, 2),
+            vec![0, 0, 0, 1, 0x65, 0xAA, 0, 0, 0, 1, 0x41]
+        );
+        let one_byte = [1, 0x65, 2, 0x41, 0xBB, 9];
+        assert_eq!(
+            length_prefixed_to_annex_b(&one_byte, 1),
+            vec![0, 0, 0, 1, 0x65, 0, 0, 0, 1, 0x41, 0xBB]
+        );
+    }
+
+    #[test]
+    fn test_config_record_length_size() {
+        // avcC: version 1, profile, compat, level, lengthSizeMinusOne = 1,
+        // one SPS and one PPS
+        let avcc = [1, 66, 0, 30, 0xFD, 0xE1, 0, 2, 0x67, 0x42, 1, 0, 1, 0x68];
+        assert_eq!(config_record_nal_length_size(Codec::Avc, &avcc), Some(2));
+        assert_eq!(
+            config_record_nals(Codec::Avc, &avcc, 2),
+            vec![0, 2, 0x67, 0x42, 0, 1, 0x68]
+        );
+        assert!(config_record_nals(Codec::Avc, &avcc[..10], 2).is_empty());
+        assert_eq!(config_record_nal_length_size(Codec::Av1, &avcc), None);
+
+        // vvcC: version/flags, lengthSizeMinusOne = 3 with a PTL record for
+        // one sublayer and one constraint info byte, then one SPS array
+        let vvcc = [
+            0, 0, 0, 0, 0xFF, 0, 0x10, 0, 1, 2, 51, 0, 0, 0x05, 0, 0x02, 0xD0, 0, 0, 1, 0x8F, 0, 1,
+            0, 2, 0x00, 0x79,
+        ];
+        let record = vvcc_record(&vvcc).unwrap();
+        assert_eq!(config_record_nal_length_size(Codec::Vvc, record), Some(4));
+        assert_eq!(
+            config_record_nals(Codec::Vvc, record, 4),
+            vec![0, 0, 0, 2, 0x00, 0x79]
+        );
+    }
+
+    #[test]
+    fn test_apply_config_record() {
+        let avcc = [1, 66, 0, 30, 0xFD, 0xE1, 0, 2, 0x67, 0x42, 1, 0, 1, 0x68];
+        let mut samples = vec![Sample {
+            offset: None,
+            pts: Some(0),
+            data: vec![0, 2, 0x65, 0x88],
+        }];
+        assert_eq!(
+            apply_config_record(Codec::Avc, Some(&avcc), &mut samples),
+            2
+        );
+        assert_eq!(
+            length_prefixed_to_annex_b(&samples[0].data, 2),
+            vec![0, 0, 0, 1, 0x67, 0x42, 0, 0, 0, 1, 0x68, 0, 0, 0, 1, 0x65, 0x88]
+        );
+
+        let mut samples = vec![Sample {
+            offset: None,
+            pts: None,
+            data: vec![0, 0, 0, 1, 0x65],
+        }];
+        assert_eq!(
+            apply_config_record(Codec::Avc, None, &mut samples),
+            DEFAULT_NAL_LENGTH_SIZE
+        );
+        assert_eq!(samples[0].data, vec![0, 0, 0, 1, 0x65]);
+    }
+
+    #[test]
+    fn test_detect_annex_b_codec() {
+        let avc = [0, 0, 1, 0x67, 0x42, 0, 0, 1, 0x68, 0xCE];
+        let hevc = [
+            0, 0, 1, 0x40, 0x01, 0, 0, 1, 0x42, 0x01, 0, 0, 1, 0x44, 0x01,
+        ];
+        let vvc = [
+            0, 0, 1, 0x00, 0x79, 0, 0, 1, 0x00, 0x81, 0, 0, 1, 0x00, 0x41,
+        ];
+        assert_eq!(detect_annex_b_codec(&avc), Some(Codec::Avc));
+        assert_eq!(detect_annex_b_codec(&hevc), Some(Codec::Hevc));
+        assert_eq!(detect_annex_b_codec(&vvc), Some(Codec::Vvc));
+        assert_eq!(detect_annex_b_codec(&[0, 0, 1, 0x00, 0x41, 0]), None);
+    }
+}
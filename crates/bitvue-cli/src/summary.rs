@@ -0,0 +1,125 @@
+//! Stream-level summary: resolution, frame rate, bitrate and QP
+
+use crate::analysis::find_sequence_header;
+use crate::stream::{Codec, FrameRecord, VideoSource};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Stream properties derived from the parsed frames
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StreamSummary {
+    pub container: String,
+    pub codec: String,
+    pub file_size: u64,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub frame_count: usize,
+    /// Frames per second, from the codec timing info or the container timestamps
+    pub frame_rate: Option<f64>,
+    pub duration_secs: Option<f64>,
+    /// Average coded bitrate over the frames, in kbit/s
+    pub bitrate_kbps: Option<f64>,
+    /// Mean of the per-frame QP over the frames that carry one
+    pub average_qp: Option<f64>,
+    /// Number of frames of each type
+    pub frame_types: BTreeMap<String, usize>,
+}
+
+impl StreamSummary {
+    pub fn new(source: &VideoSource, frames: &[FrameRecord]) -> Self {
+        let (dimensions, signalled_rate) = stream_parameters(source, frames);
+        let frame_rate = signalled_rate
+            .or_else(|| timestamp_rate(source, frames))
+            .filter(|fps| fps.is_finite() && *fps > 0.0);
+
+        let shown = frames.iter().filter(|f| f.show_frame).count();
+        let duration_secs = frame_rate
+            .filter(|_| shown > 0)
+            .map(|fps| shown as f64 / fps);
+        let total_bits: u64 = frames.iter().map(|f| f.size as u64 * 8).sum();
+        let bitrate_kbps = duration_secs.map(|secs| total_bits as f64 / secs / 1000.0);
+
+        let qps: Vec<i32> = frames.iter().filter_map(|f| f.qp).collect();
+        let average_qp = (!qps.is_empty())
+            .then(|| qps.iter().map(|&q| q as f64).sum::<f64>() / qps.len() as f64);
+
+        let mut frame_types = BTreeMap::new();
+        for frame in frames {
+            *frame_types.entry(frame.frame_type.clone()).or_insert(0) += 1;
+        }
+
+        Self {
+            container: source.container.name().to_string(),
+            codec: source.codec.name().to_string(),
+            file_size: source.file_size,
+            width: dimensions.map(|(w, _)| w),
+            height: dimensions.map(|(_, h)| h),
+            frame_count: frames.len(),
+            frame_rate,
+            duration_secs,
+            bitrate_kbps,
+            average_qp,
+            frame_types,
+        }
+    }
+}
+
+/// Coded dimensions and signalled frame rate, from the parameter sets
+fn stream_parameters(
+    source: &VideoSource,
+    frames: &[FrameRecord],
+) -> (Option<(u32, u32)>, Option<f64>) {
+    match source.codec {
+        Codec::Avc => {
+            let (es, _) = source.elementary_stream();
+            match bitvue_avc::parse_avc(&es) {
+                Ok(stream) => (stream.dimensions(), stream.frame_rate()),
+                Err(_) => (None, None),
+            }
+        }
+        Codec::Hevc => {
+            let (es, _) = source.elementary_stream();
+            match bitvue_hevc::parse_hevc(&es) {
+                Ok(stream) => (stream.dimensions(), stream.frame_rate()),
+                Err(_) => (None, None),
+            }
+        }
+        Codec::Vvc => {
+            let (es, _) = source.elementary_stream();
+            let dimensions = bitvue_vvc::parse_vvc(&es)
+                .ok()
+                .and_then(|stream| stream.dimensions());
+            (dimensions, None)
+        }
+        Codec::Vp9 => {
+            // Inter frames may inherit their size from a reference
+            let dimensions = frames.iter().find_map(|f| {
+                let header = bitvue_vp9::frame_header::parse_frame_header(&f.data).ok()?;
+                (header.width > 0 && header.height > 0).then_some((header.width, header.height))
+            });
+            (dimensions, None)
+        }
+        Codec::Av1 => {
+            let dimensions = find_sequence_header(source)
+                .and_then(|obu| bitvue_av1_codec::parse_av1(&obu).ok())
+                .and_then(|info| Some((info.width()?, info.height()?)));
+            (dimensions, None)
+        }
+    }
+}
+
+/// Frame rate implied by the spread of the shown frames' timestamps
+fn timestamp_rate(source: &VideoSource, frames: &[FrameRecord]) -> Option<f64> {
+    let timescale = source.timescale?;
+    let pts: Vec<u64> = frames
+        .iter()
+        .filter(|f| f.show_frame)
+        .filter_map(|f| f.pts)
+        .collect();
+    let first = *pts.iter().min()?;
+    let last = *pts.iter().max()?;
+    if pts.len() < 2 || last == first {
+        return None;
+    }
+    Some((pts.len() - 1) as f64 * timescale / (last - first) as f64)
+}
@@ -0,0 +1,1192 @@
+//! Bitstream conformance checks
+//!
+//! [`validate`] runs the codec parsers over a stream and records every
+//! problem found as a [`Diagnostic`]: forbidden header bits, missing
+//! parameter sets, POC discontinuities, references to frames that are not in
+//! the stream, CPB overflow and underflow against the signalled HRD (or the
+//! level limits when there is none), and truncated or malformed units.
+//! Transport streams also get the ETR 290 transport checks, and MP4 files
+//! the box-level checks of the box tree (edit lists, sample tables, decoder
+//! configuration records).
+
+use crate::analysis::{avc_access_units, hevc_access_units};
+use crate::stream::{Codec, Container, VideoSource};
+use bitvue_av1_codec::{ObuIterator, ObuType};
+use bitvue_core::diagnostics::{
+    Diagnostic, DiagnosticCategory, DiagnosticSeverity, DiagnosticsManager,
+};
+use bitvue_core::hrd::{FrameHrdTiming, HrdModel, HrdParameters};
+use bitvue_core::{FrameKey, FrameType, StreamId};
+use bitvue_formats::{analyze_ts, parse_box_tree};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+
+/// Frame rate assumed for the CPB model when the stream carries no timing
+const DEFAULT_FPS: f64 = 30.0;
+
+/// Open and validate a file
+///
+/// Failing to open or demux the file is reported as a FATAL diagnostic
+/// rather than an error, so callers always get a diagnostics list back.
+pub fn validate(path: &Path, strict: bool) -> DiagnosticsManager {
+    match VideoSource::open(path) {
+        Ok(source) => Validator::new(&source, strict).run(),
+        Err(e) => {
+            let mut diagnostics = DiagnosticsManager::new();
+            diagnostics.add(Diagnostic::new(
+                0,
+                DiagnosticSeverity::Fatal,
+                StreamId::A,
+                format!("Cannot open stream: {:#}", e),
+                DiagnosticCategory::IO,
+                0,
+            ));
+            diagnostics
+        }
+    }
+}
+
+/// Returned by [`Validator::report`] when strict mode stops at an error
+struct Halt;
+
+type Check = std::result::Result<(), Halt>;
+
+/// Runs the conformance checks for one source
+pub struct Validator<'a> {
+    source: &'a VideoSource,
+    strict: bool,
+    diagnostics: DiagnosticsManager,
+}
+
+/// One coded picture of an H.264/H.265 stream
+struct Picture {
+    /// NAL index of the first slice
+    nal_index: usize,
+    /// File offset of the access unit
+    offset: u64,
+    /// Access unit size in bytes
+    size: usize,
+    poc: i32,
+    /// POC numbering restarts at this picture (IDR, BLA, CRA after EOS)
+    resets_poc: bool,
+}
+
+impl<'a> Validator<'a> {
+    pub fn new(source: &'a VideoSource, strict: bool) -> Self {
+        Self {
+            source,
+            strict,
+            diagnostics: DiagnosticsManager::new(),
+        }
+    }
+
+    /// Run every check for the source's codec
+    pub fn run(mut self) -> DiagnosticsManager {
+        // A halt only means strict mode stopped early
+        let check = match self.source.codec {
+            Codec::Avc => self.check_avc(),
+            Codec::Hevc => self.check_hevc(),
+            Codec::Vvc => self.check_vvc(),
+            Codec::Av1 => self.check_av1(),
+            Codec::Vp9 => self.check_vp9(),
+        };
+        if check.is_ok() {
+            let _ = match self.source.container {
+                Container::Ts => self.check_transport(),
+                Container::Mp4 => self.check_boxes(),
+                _ => Ok(()),
+            };
+        }
+        self.diagnostics
+    }
+
+    fn issue(
+        &self,
+        severity: DiagnosticSeverity,
+        category: DiagnosticCategory,
+        message: impl Into<String>,
+        offset: u64,
+    ) -> Diagnostic {
+        Diagnostic::new(0, severity, StreamId::A, message.into(), category, offset)
+            .with_codec(self.source.codec.name().to_string())
+    }
+
+    fn error(&self, message: impl Into<String>, offset: u64) -> Diagnostic {
+        self.issue(
+            DiagnosticSeverity::Error,
+            DiagnosticCategory::Bitstream,
+            message,
+            offset,
+        )
+    }
+
+    fn warn(&self, message: impl Into<String>, offset: u64) -> Diagnostic {
+        self.issue(
+            DiagnosticSeverity::Warn,
+            DiagnosticCategory::Bitstream,
+            message,
+            offset,
+        )
+    }
+
+    fn info(&self, message: impl Into<String>, offset: u64) -> Diagnostic {
+        self.issue(
+            DiagnosticSeverity::Info,
+            DiagnosticCategory::Bitstream,
+            message,
+            offset,
+        )
+    }
+
+    /// Record a diagnostic; in strict mode, stop at the first ERROR or FATAL
+    fn report(&mut self, diagnostic: Diagnostic) -> Check {
+        let halt = self.strict && diagnostic.severity >= DiagnosticSeverity::Error;
+        self.diagnostics.add(diagnostic);
+        if halt {
+            Err(Halt)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// File offset of an elementary stream position
+    fn file_offset(&self, starts: &[usize], es_offset: usize) -> u64 {
+        self.source
+            .locate(starts, es_offset)
+            .1
+            .unwrap_or(es_offset as u64)
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // H.264
+    // ═══════════════════════════════════════════════════════════════════════
+
+    fn check_avc(&mut self) -> Check {
+        use bitvue_avc::NalUnitType;
+
+        let (mut es, starts) = self.source.elementary_stream();
+        self.check_nal_headers(&mut es, &starts, 1)?;
+
+        let stream = match bitvue_avc::parse_avc(&es) {
+            Ok(stream) => stream,
+            Err(e) => {
+                let fatal = self.issue(
+                    DiagnosticSeverity::Fatal,
+                    DiagnosticCategory::Bitstream,
+                    format!("H.264 parsing failed: {}", e),
+                    0,
+                );
+                return self.report(fatal);
+            }
+        };
+
+        // Parameter sets must arrive before the units that reference them
+        let parsed_slices: HashSet<usize> = stream.slices.iter().map(|s| s.nal_index).collect();
+        let mut sps_ids = HashSet::new();
+        let mut pps_ids = HashSet::new();
+        let mut missing_pps = HashSet::new();
+        for (idx, nal) in stream.nal_units.iter().enumerate() {
+            let header_pos = nal.offset + nal.size - nal.raw_payload.len() - 1;
+            let offset = self.file_offset(&starts, header_pos);
+            match nal.header.nal_unit_type {
+                NalUnitType::Sps => match bitvue_avc::parse_sps(&nal.payload) {
+                    Ok(sps) => {
+                        sps_ids.insert(sps.seq_parameter_set_id);
+                    }
+                    Err(e) => self
+                        .report(self.error(format!("Malformed or truncated SPS: {}", e), offset))?,
+                },
+                NalUnitType::Pps => match bitvue_avc::parse_pps(&nal.payload) {
+                    Ok(pps) => {
+                        if !sps_ids.contains(&pps.seq_parameter_set_id) {
+                            self.report(self.error(
+                                format!(
+                                    "PPS {} references SPS {}, which has not been received",
+                                    pps.pic_parameter_set_id, pps.seq_parameter_set_id
+                                ),
+                                offset,
+                            ))?;
+                        }
+                        pps_ids.insert(pps.pic_parameter_set_id);
+                    }
+                    Err(e) => self
+                        .report(self.error(format!("Malformed or truncated PPS: {}", e), offset))?,
+                },
+                NalUnitType::IdrSlice | NalUnitType::NonIdrSlice => {
+                    self.check_slice_pps(
+                        avc_slice_pps_id(&nal.payload),
+                        &pps_ids,
+                        &mut missing_pps,
+                        parsed_slices.contains(&idx),
+                        offset,
+                    )?;
+                }
+                _ => {}
+            }
+        }
+
+        // Pictures in decode order, one per access unit
+        let units = avc_access_units(&stream);
+        let first_slices: Vec<&bitvue_avc::ParsedSlice> = stream
+            .slices
+            .iter()
+            .filter(|s| s.header.is_first_slice())
+            .collect();
+        let pictures: Vec<Picture> = first_slices
+            .iter()
+            .zip(&units)
+            .map(|(slice, unit)| {
+                let nal = &stream.nal_units[unit.start];
+                Picture {
+                    nal_index: slice.nal_index,
+                    offset: self.file_offset(&starts, nal.offset),
+                    size: stream.nal_units[unit.clone()].iter().map(|n| n.size).sum(),
+                    poc: slice.poc,
+                    resets_poc: stream.nal_units[slice.nal_index].header.nal_unit_type
+                        == NalUnitType::IdrSlice,
+                }
+            })
+            .collect();
+
+        // References: nothing can be predicted before the first intra picture,
+        // and frame_num may only skip values when the SPS allows gaps
+        let mut seen_intra = false;
+        let mut prev_ref_frame_num: Option<u32> = None;
+        for (index, (picture, slice)) in pictures.iter().zip(&first_slices).enumerate() {
+            let header = &slice.header;
+            let nal = &stream.nal_units[picture.nal_index];
+            let is_idr = nal.header.nal_unit_type == NalUnitType::IdrSlice;
+
+            if header.slice_type.is_intra() {
+                seen_intra = true;
+            } else if !seen_intra {
+                self.report(
+                    self.error(
+                        format!(
+                            "{:?} picture precedes the first intra picture; it references frames absent from the stream",
+                            header.slice_type
+                        ),
+                        picture.offset,
+                    )
+                    .with_frame(frame_key(index)),
+                )?;
+                // Later pictures only repeat the same root cause
+                seen_intra = true;
+            }
+
+            let sps = stream
+                .get_pps(header.pic_parameter_set_id)
+                .and_then(|pps| stream.get_sps(pps.seq_parameter_set_id));
+            if let Some(sps) = sps {
+                let max_frame_num = 1u32 << (sps.log2_max_frame_num_minus4 + 4);
+                if is_idr {
+                    prev_ref_frame_num = None;
+                } else if let Some(prev) = prev_ref_frame_num {
+                    let next = (prev + 1) % max_frame_num;
+                    if !sps.gaps_in_frame_num_value_allowed_flag
+                        && header.frame_num != prev
+                        && header.frame_num != next
+                    {
+                        self.report(
+                            self.error(
+                                format!(
+                                    "frame_num gap: expected {} or {}, found {}; reference frames are missing",
+                                    prev, next, header.frame_num
+                                ),
+                                picture.offset,
+                            )
+                            .with_frame(frame_key(index)),
+                        )?;
+                    }
+                }
+                if nal.header.nal_ref_idc != 0 {
+                    prev_ref_frame_num = Some(header.frame_num);
+                }
+            }
+        }
+
+        self.check_poc(&pictures)?;
+
+        // CPB conformance of each signalled HRD, or against the level limits
+        // of the first SPS when there is none
+        let mut signalled = false;
+        for (hrd, name) in [
+            (bitvue_avc::HrdType::Nal, "NAL"),
+            (bitvue_avc::HrdType::Vcl, "VCL"),
+        ] {
+            if let Some(params) = stream.hrd_parameters(hrd) {
+                let timings = stream.hrd_timings(hrd, &params);
+                self.check_signalled_cpb(Some(name), params, &timings, &pictures)?;
+                signalled = true;
+            }
+        }
+        let sps = first_slices.first().and_then(|s| {
+            stream
+                .get_pps(s.header.pic_parameter_set_id)
+                .and_then(|pps| stream.get_sps(pps.seq_parameter_set_id))
+        });
+        if let Some(sps) = sps.filter(|_| !signalled) {
+            let level = format!("{}.{}", sps.level_idc / 10, sps.level_idc % 10);
+            let limits = avc_level_limits(sps.level_idc).map(|(br, cpb)| {
+                let factor = avc_cpb_br_factor(sps.profile_idc as u8);
+                (br * factor, cpb * factor)
+            });
+            self.check_cpb(&level, limits, stream.frame_rate(), &pictures)?;
+        }
+
+        Ok(())
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // H.265
+    // ═══════════════════════════════════════════════════════════════════════
+
+    fn check_hevc(&mut self) -> Check {
+        use bitvue_hevc::NalUnitType;
+
+        let (mut es, starts) = self.source.elementary_stream();
+        self.check_nal_headers(&mut es, &starts, 2)?;
+
+        let stream = match bitvue_hevc::parse_hevc(&es) {
+            Ok(stream) => stream,
+            Err(e) => {
+                let fatal = self.issue(
+                    DiagnosticSeverity::Fatal,
+                    DiagnosticCategory::Bitstream,
+                    format!("H.265 parsing failed: {}", e),
+                    0,
+                );
+                return self.report(fatal);
+            }
+        };
+
+        let parsed_slices: HashSet<usize> = stream.slices.iter().map(|s| s.nal_index).collect();
+        let mut vps_ids = HashSet::new();
+        let mut sps_ids = HashSet::new();
+        let mut pps_ids = HashSet::new();
+        let mut missing_pps = HashSet::new();
+        for (idx, nal) in stream.nal_units.iter().enumerate() {
+            let offset = self.file_offset(&starts, nal.offset as usize);
+            match nal.header.nal_unit_type {
+                NalUnitType::VpsNut => match bitvue_hevc::vps::parse_vps(&nal.payload) {
+                    Ok(vps) => {
+                        vps_ids.insert(vps.vps_video_parameter_set_id);
+                    }
+                    Err(e) => self
+                        .report(self.error(format!("Malformed or truncated VPS: {}", e), offset))?,
+                },
+                NalUnitType::SpsNut => match bitvue_hevc::parse_sps(&nal.payload) {
+                    Ok(sps) => {
+                        if !vps_ids.contains(&sps.sps_video_parameter_set_id) {
+                            self.report(self.error(
+                                format!(
+                                    "SPS {} references VPS {}, which has not been received",
+                                    sps.sps_seq_parameter_set_id, sps.sps_video_parameter_set_id
+                                ),
+                                offset,
+                            ))?;
+                        }
+                        sps_ids.insert(sps.sps_seq_parameter_set_id);
+                    }
+                    Err(e) => self
+                        .report(self.error(format!("Malformed or truncated SPS: {}", e), offset))?,
+                },
+                NalUnitType::PpsNut => match bitvue_hevc::parse_pps(&nal.payload) {
+                    Ok(pps) => {
+                        if !sps_ids.contains(&pps.pps_seq_parameter_set_id) {
+                            self.report(self.error(
+                                format!(
+                                    "PPS {} references SPS {}, which has not been received",
+                                    pps.pps_pic_parameter_set_id, pps.pps_seq_parameter_set_id
+                                ),
+                                offset,
+                            ))?;
+                        }
+                        pps_ids.insert(pps.pps_pic_parameter_set_id);
+                    }
+                    Err(e) => self
+                        .report(self.error(format!("Malformed or truncated PPS: {}", e), offset))?,
+                },
+                nal_type if nal_type.is_vcl() => {
+                    self.check_slice_pps(
+                        hevc_slice_pps_id(&nal.payload, nal_type.is_irap()),
+                        &pps_ids,
+                        &mut missing_pps,
+                        parsed_slices.contains(&idx),
+                        offset,
+                    )?;
+                }
+                _ => {}
+            }
+        }
+
+        // Pictures in decode order; a CRA after an end of sequence (or at
+        // the start) behaves like an IDR for POC and leading pictures
+        let units = hevc_access_units(&stream);
+        let first_slices: Vec<&bitvue_hevc::ParsedSlice> = stream
+            .slices
+            .iter()
+            .filter(|s| s.header.first_slice_segment_in_pic_flag)
+            .collect();
+        let mut pictures = Vec::with_capacity(first_slices.len());
+        let mut after_eos = true;
+        let mut eos_positions = stream
+            .nal_units
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.header.nal_unit_type == NalUnitType::EosNut)
+            .map(|(i, _)| i)
+            .peekable();
+        for (slice, unit) in first_slices.iter().zip(&units) {
+            while eos_positions.next_if(|&i| i < slice.nal_index).is_some() {
+                after_eos = true;
+            }
+            let nal_type = stream.nal_units[slice.nal_index].header.nal_unit_type;
+            let nal = &stream.nal_units[unit.start];
+            pictures.push(Picture {
+                nal_index: slice.nal_index,
+                offset: self.file_offset(&starts, nal.offset as usize),
+                size: stream.nal_units[unit.clone()]
+                    .iter()
+                    .map(|n| n.size as usize)
+                    .sum(),
+                poc: slice.poc,
+                resets_poc: nal_type.is_idr()
+                    || nal_type.is_bla()
+                    || (nal_type.is_cra() && after_eos),
+            });
+            if nal_type.is_irap() {
+                after_eos = false;
+            }
+        }
+
+        // References: nothing decodes before the first IRAP, and RASL
+        // pictures of a CRA that starts a sequence reference pictures that
+        // were never sent
+        let mut seen_irap = false;
+        let mut rasl_skipped = false;
+        for (index, picture) in pictures.iter().enumerate() {
+            let nal_type = stream.nal_units[picture.nal_index].header.nal_unit_type;
+            if nal_type.is_irap() {
+                seen_irap = true;
+                rasl_skipped = !nal_type.is_idr() && picture.resets_poc;
+            } else if !seen_irap {
+                self.report(
+                    self.error(
+                        format!(
+                            "{} picture precedes the first IRAP picture; it references frames absent from the stream",
+                            nal_type.name()
+                        ),
+                        picture.offset,
+                    )
+                    .with_frame(frame_key(index)),
+                )?;
+                // Later pictures only repeat the same root cause
+                seen_irap = true;
+            } else if nal_type.is_rasl() && rasl_skipped {
+                self.report(
+                    self.warn(
+                        "RASL picture references frames before its CRA that are absent from the stream; it is not decodable",
+                        picture.offset,
+                    )
+                    .with_frame(frame_key(index)),
+                )?;
+            }
+        }
+
+        self.check_poc(&pictures)?;
+
+        let sps = first_slices.first().and_then(|s| {
+            stream
+                .get_pps(s.header.slice_pic_parameter_set_id)
+                .and_then(|pps| stream.get_sps(pps.pps_seq_parameter_set_id))
+        });
+        if let Some(params) = stream.hrd_parameters() {
+            let timings = stream.hrd_timings(&params);
+            self.check_signalled_cpb(None, params, &timings, &pictures)?;
+        } else if let Some(sps) = sps {
+            let ptl = &sps.profile_tier_level;
+            let level = format!(
+                "{}.{}{}",
+                ptl.general_level_idc / 30,
+                ptl.general_level_idc % 30 / 3,
+                if ptl.general_tier_flag { " High" } else { "" }
+            );
+            let limits = hevc_level_limits(ptl.general_level_idc, ptl.general_tier_flag)
+                .map(|(br, cpb)| (br * 1000, cpb * 1000));
+            self.check_cpb(&level, limits, stream.frame_rate(), &pictures)?;
+        }
+
+        Ok(())
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // H.266
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Header and parameter set checks; the POC and CPB models are H.265's
+    /// and are not applied to H.266 yet
+    fn check_vvc(&mut self) -> Check {
+        use bitvue_vvc::NalUnitType;
+
+        let (mut es, starts) = self.source.elementary_stream();
+        self.check_nal_headers(&mut es, &starts, 2)?;
+
+        let stream = match bitvue_vvc::parse_vvc(&es) {
+            Ok(stream) => stream,
+            Err(e) => {
+                let fatal = self.issue(
+                    DiagnosticSeverity::Fatal,
+                    DiagnosticCategory::Bitstream,
+                    format!("H.266 parsing failed: {}", e),
+                    0,
+                );
+                return self.report(fatal);
+            }
+        };
+
+        let parsed_slices: HashSet<usize> = stream.slices.iter().map(|s| s.nal_index).collect();
+        let mut sps_ids = HashSet::new();
+        for (idx, nal) in stream.nal_units.iter().enumerate() {
+            let offset = self.file_offset(&starts, nal.offset as usize);
+            match nal.header.nal_unit_type {
+                NalUnitType::SpsNut => match bitvue_vvc::parse_sps(&nal.payload) {
+                    Ok(sps) => {
+                        sps_ids.insert(sps.sps_seq_parameter_set_id);
+                    }
+                    Err(e) => self
+                        .report(self.error(format!("Malformed or truncated SPS: {}", e), offset))?,
+                },
+                NalUnitType::PpsNut => match bitvue_vvc::parse_pps(&nal.payload) {
+                    Ok(pps) => {
+                        if !sps_ids.contains(&pps.pps_seq_parameter_set_id) {
+                            self.report(self.error(
+                                format!(
+                                    "PPS {} references SPS {}, which has not been received",
+                                    pps.pps_pic_parameter_set_id, pps.pps_seq_parameter_set_id
+                                ),
+                                offset,
+                            ))?;
+                        }
+                    }
+                    Err(e) => self
+                        .report(self.error(format!("Malformed or truncated PPS: {}", e), offset))?,
+                },
+                // The PPS is named in the picture header, so a slice that
+                // fails to parse may equally lack its parameter sets
+                nal_type if nal_type.is_vcl() && !parsed_slices.contains(&idx) => {
+                    self.report(self.error(
+                        "Malformed or truncated slice header, or its parameter sets are missing",
+                        offset,
+                    ))?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // Shared NAL checks
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Check raw NAL unit headers of an Annex B stream
+    ///
+    /// Clears the forbidden bit of offending units in `es` so the codec
+    /// parser, which rejects them outright, can still check the rest of the
+    /// stream.
+    fn check_nal_headers(&mut self, es: &mut [u8], starts: &[usize], header_len: usize) -> Check {
+        for span in nal_spans(es) {
+            let offset = self.file_offset(starts, span.start);
+            if span.len() < header_len {
+                self.report(self.error(
+                    format!(
+                        "Truncated NAL unit: {} bytes, header needs {}",
+                        span.len(),
+                        header_len
+                    ),
+                    offset,
+                ))?;
+                continue;
+            }
+
+            let bit = span.start as u64 * 8;
+            if es[span.start] & 0x80 != 0 {
+                self.report(
+                    self.error("forbidden_zero_bit is set in NAL unit header", offset)
+                        .with_bit_range(bit, bit + 1),
+                )?;
+                es[span.start] &= 0x7F;
+            }
+
+            // H.265 and H.266 nuh_temporal_id_plus1 must not be zero
+            if header_len == 2 && es[span.start + 1] & 0x07 == 0 {
+                self.report(
+                    self.error("nuh_temporal_id_plus1 is zero", offset)
+                        .with_bit_range(bit + 13, bit + 16),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that a slice's PPS was received and its header parsed
+    ///
+    /// Each missing PPS is reported once, at the first slice referencing it.
+    fn check_slice_pps(
+        &mut self,
+        pps_id: Option<u32>,
+        pps_ids: &HashSet<u8>,
+        missing: &mut HashSet<u32>,
+        parsed: bool,
+        offset: u64,
+    ) -> Check {
+        match pps_id {
+            None => self.report(self.error("Truncated slice header", offset)),
+            Some(id) if !u8::try_from(id).is_ok_and(|id| pps_ids.contains(&id)) => {
+                if !missing.insert(id) {
+                    return Ok(());
+                }
+                self.report(self.error(
+                    format!("Slice references PPS {}, which has not been received", id),
+                    offset,
+                ))
+            }
+            Some(_) if !parsed => {
+                self.report(self.error("Malformed or truncated slice header", offset))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Check POC uniqueness and spacing within each coded video sequence
+    fn check_poc(&mut self, pictures: &[Picture]) -> Check {
+        let mut segments: Vec<Vec<usize>> = Vec::new();
+        for (index, picture) in pictures.iter().enumerate() {
+            match segments.last_mut() {
+                Some(segment) if !picture.resets_poc => segment.push(index),
+                _ => segments.push(vec![index]),
+            }
+        }
+
+        for segment in segments {
+            let mut by_poc: HashMap<i32, usize> = HashMap::new();
+            for &index in &segment {
+                let picture = &pictures[index];
+                if let Some(&other) = by_poc.get(&picture.poc) {
+                    self.report(
+                        self.error(
+                            format!(
+                                "Duplicate POC {} (also used by frame {})",
+                                picture.poc, other
+                            ),
+                            picture.offset,
+                        )
+                        .with_frame(frame_key(index)),
+                    )?;
+                } else {
+                    by_poc.insert(picture.poc, index);
+                }
+            }
+
+            let mut pocs: Vec<i32> = by_poc.keys().copied().collect();
+            pocs.sort_unstable();
+            let Some(step) = pocs.windows(2).map(|w| w[1] - w[0]).min() else {
+                continue;
+            };
+            for pair in pocs.windows(2) {
+                if pair[1] - pair[0] > step {
+                    let index = by_poc[&pair[1]];
+                    self.report(
+                        self.warn(
+                            format!(
+                                "POC discontinuity: {} follows {} in output order (step {})",
+                                pair[1], pair[0], step
+                            ),
+                            pictures[index].offset,
+                        )
+                        .with_frame(frame_key(index)),
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the CPB model with the HRD parameters and removal times the
+    /// stream signals; `hrd` names the HRD when a stream carries several
+    fn check_signalled_cpb(
+        &mut self,
+        hrd: Option<&str>,
+        params: HrdParameters,
+        timings: &[FrameHrdTiming],
+        pictures: &[Picture],
+    ) -> Check {
+        let (bit_rate_bps, cpb_size_bits) = (params.bit_rate_bps, params.cpb_size_bits);
+        let lanes = HrdModel::new(params).process_signalled_frames(timings);
+
+        for ((timing, lane), picture) in timings.iter().zip(&lanes).zip(pictures) {
+            let message = if lane.overflow {
+                format!(
+                    "CPB overflow: buffer exceeds the signalled {} bit CPB before removal at {:.3}s",
+                    cpb_size_bits,
+                    timing.dts_sec.unwrap_or(timing.pts_sec)
+                )
+            } else if lane.underflow {
+                format!(
+                    "CPB underflow: {} bit picture has not fully arrived by its removal time {:.3}s",
+                    timing.frame_size_bits,
+                    timing.dts_sec.unwrap_or(timing.pts_sec)
+                )
+            } else {
+                continue;
+            };
+            let message = match hrd {
+                Some(hrd) => format!("{} HRD {}", hrd, message),
+                None => message,
+            };
+            self.report(
+                self.error(message, picture.offset)
+                    .with_frame(frame_key(timing.display_idx))
+                    .with_detail("bit_rate".to_string(), bit_rate_bps.to_string())
+                    .with_detail("cpb_size".to_string(), cpb_size_bits.to_string()),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Run the CPB model with the level's maximum bit rate and buffer size
+    fn check_cpb(
+        &mut self,
+        level: &str,
+        limits: Option<(u64, u64)>,
+        fps: Option<f64>,
+        pictures: &[Picture],
+    ) -> Check {
+        let Some((bit_rate_bps, cpb_size_bits)) = limits else {
+            return self.report(self.info(
+                format!("CPB check skipped: no limits known for level {}", level),
+                0,
+            ));
+        };
+        let fps = match fps.filter(|f| f.is_finite() && *f > 0.0) {
+            Some(fps) => fps,
+            None => {
+                self.report(self.info(
+                    format!(
+                        "No timing information; assuming {} fps for the CPB check",
+                        DEFAULT_FPS
+                    ),
+                    0,
+                ))?;
+                DEFAULT_FPS
+            }
+        };
+
+        // Start with a full buffer: the most lenient schedule the level allows
+        let params = HrdParameters {
+            cpb_size_bits,
+            bit_rate_bps,
+            initial_cpb_removal_delay: cpb_size_bits * 90_000 / bit_rate_bps,
+            time_scale: (fps * 1000.0).round() as u32,
+            num_units_in_tick: 1000,
+            ..HrdParameters::default()
+        };
+        let mut model = HrdModel::new(params);
+        model.initialize_buffer();
+
+        for (index, picture) in pictures.iter().enumerate() {
+            let timing = FrameHrdTiming::new(index, picture.size as u64 * 8, index as f64 / fps);
+            let state = model.process_frame(&timing);
+            if state.underflow {
+                self.report(
+                    self.error(
+                        format!(
+                            "CPB underflow: {} bit picture exceeds the level {} buffer",
+                            timing.frame_size_bits, level
+                        ),
+                        picture.offset,
+                    )
+                    .with_frame(frame_key(index))
+                    .with_detail("max_bit_rate".to_string(), bit_rate_bps.to_string())
+                    .with_detail("cpb_size".to_string(), cpb_size_bits.to_string()),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // AV1
+    // ═══════════════════════════════════════════════════════════════════════
+
+    fn check_av1(&mut self) -> Check {
+        let source = self.source;
+        let mut seen_sequence_header = false;
+        let mut seen_key_frame = false;
+        // Reference slots holding a decoded frame
+        let mut filled_slots: u8 = 0;
+        let mut frame_index = 0usize;
+
+        for sample in &source.samples {
+            let base = sample.offset.unwrap_or(0);
+            let mut iter = ObuIterator::new(&sample.data);
+
+            while let Some(next) = iter.next_obu_with_offset() {
+                let item = match next {
+                    Ok(item) => item,
+                    Err(e) => {
+                        let pos = iter.current_offset();
+                        let message = if sample.data[pos] & 0x80 != 0 {
+                            "obu_forbidden_bit is set".to_string()
+                        } else {
+                            format!("Truncated or malformed OBU: {}", e)
+                        };
+                        self.report(self.error(message, base + pos as u64))?;
+                        break;
+                    }
+                };
+                let offset = base + item.offset as u64;
+
+                if sample.data[item.offset] & 0x80 != 0 {
+                    let bit = offset * 8;
+                    self.report(
+                        self.error("obu_forbidden_bit is set", offset)
+                            .with_bit_range(bit, bit + 1),
+                    )?;
+                }
+
+                match item.obu.header.obu_type {
+                    ObuType::SequenceHeader => seen_sequence_header = true,
+                    ObuType::Frame | ObuType::FrameHeader => {
+                        let index = frame_index;
+                        frame_index += 1;
+
+                        if !seen_sequence_header {
+                            self.report(
+                                self.error(
+                                    "Frame header precedes the first sequence header",
+                                    offset,
+                                )
+                                .with_frame(frame_key(index)),
+                            )?;
+                            seen_sequence_header = true;
+                        }
+
+                        let Some(header) = &item.obu.frame_header else {
+                            self.report(
+                                self.error("Malformed or truncated frame header", offset)
+                                    .with_frame(frame_key(index)),
+                            )?;
+                            continue;
+                        };
+
+                        if header.show_existing_frame {
+                            if let Some(slot) = header.frame_to_show_map_idx {
+                                if filled_slots & (1 << slot) == 0 {
+                                    self.report(
+                                        self.error(
+                                            format!(
+                                                "show_existing_frame shows reference slot {}, which holds no frame",
+                                                slot
+                                            ),
+                                            offset,
+                                        )
+                                        .with_frame(frame_key(index)),
+                                    )?;
+                                }
+                            }
+                            continue;
+                        }
+
+                        match header.frame_type {
+                            FrameType::Key => seen_key_frame = true,
+                            FrameType::IntraOnly => {}
+                            _ if !seen_key_frame => {
+                                self.report(
+                                    self.error(
+                                        "Inter frame precedes the first key frame; it references frames absent from the stream",
+                                        offset,
+                                    )
+                                    .with_frame(frame_key(index)),
+                                )?;
+                                seen_key_frame = true;
+                            }
+                            _ => {}
+                        }
+
+                        filled_slots |= match header.frame_type {
+                            FrameType::Key if header.show_frame => 0xFF,
+                            _ => header.refresh_frame_flags.unwrap_or(0),
+                        };
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // VP9
+    // ═══════════════════════════════════════════════════════════════════════
+
+    fn check_vp9(&mut self) -> Check {
+        use bitvue_vp9::FrameType as Vp9FrameType;
+
+        const REF_NAMES: [&str; 3] = ["LAST", "GOLDEN", "ALTREF"];
+
+        let frames = match self.source.frames() {
+            Ok(frames) => frames,
+            Err(e) => {
+                let fatal = self.issue(
+                    DiagnosticSeverity::Fatal,
+                    DiagnosticCategory::Bitstream,
+                    format!("VP9 frame extraction failed: {:#}", e),
+                    0,
+                );
+                return self.report(fatal);
+            }
+        };
+
+        // Reference slots holding a decoded frame
+        let mut filled_slots: u8 = 0;
+        for frame in &frames {
+            let offset = frame.offset.unwrap_or(0);
+            let key = frame_key(frame.decode_index);
+
+            if frame.data.is_empty() {
+                self.report(self.error("Empty frame", offset).with_frame(key))?;
+                continue;
+            }
+
+            let header = match bitvue_vp9::frame_header::parse_frame_header(&frame.data) {
+                Ok(header) => header,
+                Err(e) => {
+                    self.report(
+                        self.error(
+                            format!("Malformed or truncated frame header: {}", e),
+                            offset,
+                        )
+                        .with_frame(key),
+                    )?;
+                    continue;
+                }
+            };
+
+            if header.frame_type == Vp9FrameType::Key {
+                filled_slots = 0xFF;
+                continue;
+            }
+
+            if !header.intra_only {
+                for (name, &slot) in REF_NAMES.iter().zip(&header.ref_frame_idx) {
+                    if filled_slots & (1 << slot) == 0 {
+                        self.report(
+                            self.error(
+                                format!(
+                                    "{} reference uses slot {}, which holds no frame; it references a frame absent from the stream",
+                                    name, slot
+                                ),
+                                offset,
+                            )
+                            .with_frame(key.clone()),
+                        )?;
+                        // Report each empty slot once
+                        filled_slots |= 1 << slot;
+                    }
+                }
+            }
+            filled_slots |= header.refresh_frame_flags;
+        }
+        Ok(())
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // MPEG-TS transport
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// ETR 290 priority 1 and 2 checks and the T-STD buffer delay
+    fn check_transport(&mut self) -> Check {
+        let analysis = std::fs::read(&self.source.path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| analyze_ts(&data).map_err(|e| e.to_string()));
+        let analysis = match analysis {
+            Ok(analysis) => analysis,
+            Err(e) => {
+                let fatal = self.issue(
+                    DiagnosticSeverity::Fatal,
+                    DiagnosticCategory::Container,
+                    format!("Transport stream analysis failed: {}", e),
+                    0,
+                );
+                return self.report(fatal);
+            }
+        };
+
+        for diagnostic in analysis.diagnostics() {
+            self.report(diagnostic)?;
+        }
+        Ok(())
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // MP4 boxes
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Box sizes, edit lists, sample table counts and decoder configuration
+    /// records
+    fn check_boxes(&mut self) -> Check {
+        let data = match std::fs::read(&self.source.path) {
+            Ok(data) => data,
+            Err(e) => {
+                let fatal = self.issue(
+                    DiagnosticSeverity::Fatal,
+                    DiagnosticCategory::Container,
+                    format!("Failed to read MP4 boxes: {}", e),
+                    0,
+                );
+                return self.report(fatal);
+            }
+        };
+
+        for diagnostic in parse_box_tree(&data).diagnostics() {
+            self.report(diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+fn frame_key(index: usize) -> FrameKey {
+    FrameKey {
+        stream: StreamId::A,
+        frame_index: index,
+        pts: None,
+    }
+}
+
+/// Byte ranges of the NAL units of an Annex B stream, excluding start codes
+fn nal_spans(es: &[u8]) -> Vec<Range<usize>> {
+    let positions = bitvue_avc::find_nal_units(es);
+    positions
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| {
+            let end = match positions.get(i + 1) {
+                Some(&next) if next >= 4 && es[next - 4] == 0 => next - 4,
+                Some(&next) => next - 3,
+                None => es.len(),
+            };
+            start..end.max(start)
+        })
+        .collect()
+}
+
+/// pic_parameter_set_id of an H.264 slice header
+fn avc_slice_pps_id(payload: &[u8]) -> Option<u32> {
+    let mut reader = bitvue_avc::BitReader::new(payload);
+    reader.read_ue().ok()?; // first_mb_in_slice
+    reader.read_ue().ok()?; // slice_type
+    reader.read_ue().ok()
+}
+
+/// slice_pic_parameter_set_id of an H.265 slice segment header
+fn hevc_slice_pps_id(payload: &[u8], is_irap: bool) -> Option<u32> {
+    let mut reader = bitvue_hevc::BitReader::new(payload);
+    reader.read_bit().ok()?; // first_slice_segment_in_pic_flag
+    if is_irap {
+        reader.read_bit().ok()?; // no_output_of_prior_pics_flag
+    }
+    reader.read_ue().ok()
+}
+
+/// MaxBR (cpbBrVclFactor bit/s) and MaxCPB (cpbBrVclFactor bits) of an
+/// H.264 level, per Table A-1
+fn avc_level_limits(level_idc: u8) -> Option<(u64, u64)> {
+    Some(match level_idc {
+        9 => (128, 350),
+        10 => (64, 175),
+        11 => (192, 500),
+        12 => (384, 1000),
+        13 => (768, 2000),
+        20 => (2000, 2000),
+        21 | 22 => (4000, 4000),
+        30 => (10000, 10000),
+        31 => (14000, 14000),
+        32 => (20000, 20000),
+        40 => (20000, 25000),
+        41 | 42 => (50000, 62500),
+        50 => (135000, 135000),
+        51 | 52 | 60 => (240000, 240000),
+        61 => (480000, 480000),
+        62 => (800000, 800000),
+        _ => return None,
+    })
+}
+
+/// cpbBrVclFactor of an H.264 profile, per Table A-2
+fn avc_cpb_br_factor(profile_idc: u8) -> u64 {
+    match profile_idc {
+        100 => 1250,
+        110 => 3000,
+        122 | 244 | 44 => 4000,
+        _ => 1000,
+    }
+}
+
+/// MaxBR (1000 bit/s) and MaxCPB (1000 bits) of an H.265 level and tier,
+/// per Table A.8
+fn hevc_level_limits(level_idc: u8, high_tier: bool) -> Option<(u64, u64)> {
+    let (main, high) = match level_idc {
+        30 => (128, None),
+        60 => (1500, None),
+        63 => (3000, None),
+        90 => (6000, None),
+        93 => (10000, None),
+        120 => (12000, Some(30000)),
+        123 => (20000, Some(50000)),
+        150 => (25000, Some(100000)),
+        153 => (40000, Some(160000)),
+        156 | 180 => (60000, Some(240000)),
+        183 => (120000, Some(480000)),
+        186 => (240000, Some(800000)),
+        _ => return None,
+    };
+    let br = if high_tier {
+        high.unwrap_or(main)
+    } else {
+        main
+    };
+    let cpb = if level_idc == 30 { 350 } else { br };
+    Some((br, cpb))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nal_spans() {
+        let es = [0, 0, 0, 1, 0x67, 0xAA, 0, 0, 1, 0x68, 0xBB, 0xCC];
+        assert_eq!(nal_spans(&es), vec![4..6, 9..12]);
+    }
+
+    #[test]
+    fn test_slice_pps_ids() {
+        // ue(0) ue(7) ue(2): 1 0001000 011
+        assert_eq!(avc_slice_pps_id(&[0b1000_1000, 0b0110_0000]), Some(2));
+        // first_slice 1, no_output 0, ue(1): 1 0 010
+        assert_eq!(hevc_slice_pps_id(&[0b1001_0000], true), Some(1));
+        assert_eq!(avc_slice_pps_id(&[]), None);
+    }
+
+    #[test]
+    fn test_level_limits() {
+        assert_eq!(avc_level_limits(40), Some((20000, 25000)));
+        assert_eq!(hevc_level_limits(120, true), Some((30000, 30000)));
+        assert_eq!(hevc_level_limits(30, false), Some((128, 350)));
+        assert_eq!(hevc_level_limits(77, false), None);
+    }
+}
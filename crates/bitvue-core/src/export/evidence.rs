@@ -98,6 +98,10 @@ pub struct EvidenceBundleExportRequest {
     pub mode: String,
     /// Order type
     pub order_type: OrderType,
+    /// Backend that produced the analysis (e.g., "dav1d")
+    pub backend: String,
+    /// Files or directories copied into the bundle's artifacts/ directory
+    pub artifacts: Vec<std::path::PathBuf>,
 }
 
 impl Default for EvidenceBundleExportRequest {
@@ -117,6 +121,8 @@ impl Default for EvidenceBundleExportRequest {
             workspace: "player".to_string(),
             mode: "normal".to_string(),
             order_type: OrderType::Display,
+            backend: "dav1d".to_string(),
+            artifacts: Vec::new(),
         }
     }
 }
@@ -168,6 +174,7 @@ impl EvidenceBundleExportResult {
 /// - selection_state.json
 /// - order_type.json
 /// - warnings.json
+/// - artifacts/ (copies of `request.artifacts`)
 /// - screenshots/ (optional)
 /// - render_snapshots/ (optional)
 ///
@@ -195,6 +202,31 @@ pub fn export_evidence_bundle(
     let mut files_created = Vec::new();
     let mut total_bytes = 0;
 
+    // Copy artifacts first so the manifest can list them
+    let mut artifacts = Vec::new();
+    for source in &request.artifacts {
+        let Some(name) = source.file_name() else {
+            return EvidenceBundleExportResult::error(&format!(
+                "Invalid artifact path: {}",
+                source.display()
+            ));
+        };
+        let relative = Path::new("artifacts").join(name);
+        match copy_artifact(source, &bundle_dir, &relative, &mut files_created) {
+            Ok(bytes) => {
+                total_bytes += bytes;
+                artifacts.push(relative.to_string_lossy().to_string());
+            }
+            Err(e) => {
+                return EvidenceBundleExportResult::error(&format!(
+                    "Failed to copy artifact {}: {}",
+                    source.display(),
+                    e
+                ))
+            }
+        }
+    }
+
     // Create manifest
     let manifest = EvidenceBundleManifest {
         bundle_version: "1.0".to_string(),
@@ -209,7 +241,7 @@ pub fn export_evidence_bundle(
         os: std::env::consts::OS.to_string(),
         gpu: "unknown".to_string(),
         cpu: std::env::consts::ARCH.to_string(),
-        backend: "dav1d".to_string(),
+        backend: request.backend.clone(),
         plugin_versions: HashMap::new(),
         stream_fingerprint: request.stream_fingerprint.clone(),
         order_type: request.order_type,
@@ -217,7 +249,7 @@ pub fn export_evidence_bundle(
         workspace: request.workspace.clone(),
         mode: request.mode.clone(),
         warnings: Vec::new(),
-        artifacts: Vec::new(),
+        artifacts,
     };
 
     // Write bundle_manifest.json
@@ -403,6 +435,40 @@ struct BackendInfo {
     plugin_versions: HashMap<String, String>,
 }
 
+/// Copy a file, or a directory recursively, to `relative` inside the bundle;
+/// records every copied file and returns the bytes copied
+fn copy_artifact(
+    source: &Path,
+    bundle_dir: &Path,
+    relative: &Path,
+    files_created: &mut Vec<String>,
+) -> std::io::Result<usize> {
+    let dest = bundle_dir.join(relative);
+    if !source.is_dir() {
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let bytes = std::fs::copy(source, &dest)?;
+        files_created.push(relative.to_string_lossy().to_string());
+        return Ok(bytes as usize);
+    }
+
+    std::fs::create_dir_all(&dest)?;
+    let mut entries: Vec<_> = std::fs::read_dir(source)?.collect::<Result<_, _>>()?;
+    entries.sort_by_key(|e| e.file_name());
+
+    let mut total = 0;
+    for entry in entries {
+        total += copy_artifact(
+            &entry.path(),
+            bundle_dir,
+            &relative.join(entry.file_name()),
+            files_created,
+        )?;
+    }
+    Ok(total)
+}
+
 /// Helper to write JSON file
 fn write_json_file<T: Serialize>(path: &Path, data: &T) -> std::io::Result<usize> {
     let json = serde_json::to_string_pretty(data).map_err(std::io::Error::other)?;
@@ -101,6 +101,8 @@ fn create_test_export_request() -> EvidenceBundleExportRequest {
         workspace: "player".to_string(),
         mode: "normal".to_string(),
         order_type: crate::parity_harness::OrderType::Display,
+        backend: "dav1d".to_string(),
+        artifacts: Vec::new(),
     }
 }
 
@@ -19,6 +19,10 @@ use serde::{Deserialize, Serialize};
 /// Older states are removed in FIFO order to maintain a rolling window.
 const MAX_STATE_HISTORY: usize = 5000;
 
+/// Slack when comparing arrival and removal times, so that an access unit
+/// arriving exactly at its removal time is not flagged through rounding
+const ARRIVAL_TOLERANCE_SEC: f64 = 1e-9;
+
 // ═══════════════════════════════════════════════════════════════════════════
 // HRD Parameters
 // ═══════════════════════════════════════════════════════════════════════════
@@ -111,6 +115,9 @@ pub struct FrameHrdTiming {
     pub pts_sec: f64,
     /// Decode timestamp (seconds)
     pub dts_sec: Option<f64>,
+    /// Earliest CPB arrival time t_ai,earliest (seconds), when the
+    /// buffering period signals it
+    pub earliest_arrival_sec: Option<f64>,
 }
 
 impl FrameHrdTiming {
@@ -122,6 +129,7 @@ impl FrameHrdTiming {
             dpb_output_delay: None,
             pts_sec,
             dts_sec: None,
+            earliest_arrival_sec: None,
         }
     }
 
@@ -312,6 +320,107 @@ impl HrdModel {
         state
     }
 
+    /// Simulate the CPB over access units with signalled removal times
+    /// (`dts_sec`, in decode order) and return the lane data of each one
+    ///
+    /// Bits enter an empty CPB at the HRD bit rate from time zero, one access
+    /// unit after another (C.1.2). A CBR HRD delivers each access unit as
+    /// soon as the previous one has arrived; a VBR HRD also holds it back
+    /// until its earliest arrival time, i.e. t_ai = max(t_af(n-1),
+    /// t_ai,earliest), which defaults to `initial_cpb_removal_delay` before
+    /// removal when `earliest_arrival_sec` is not given. Overflow is flagged whenever the
+    /// fullness before a removal exceeds the CPB size, underflow when an
+    /// access unit finishes arriving after its removal time.
+    pub fn process_signalled_frames(&mut self, timings: &[FrameHrdTiming]) -> Vec<HrdLaneData> {
+        let rate = self.params.bit_rate_bps as f64;
+        let initial_delay = self.params.initial_cpb_removal_delay as f64 / 90000.0;
+        let removal_time = |timing: &FrameHrdTiming| timing.dts_sec.unwrap_or(timing.pts_sec);
+
+        // Initial and final arrival time of each access unit
+        let mut arrivals = Vec::with_capacity(timings.len());
+        let mut final_arrival = 0.0f64;
+        for (n, timing) in timings.iter().enumerate() {
+            let initial = if n == 0 || self.params.cbr_flag {
+                final_arrival
+            } else {
+                let earliest = timing
+                    .earliest_arrival_sec
+                    .unwrap_or(removal_time(timing) - initial_delay);
+                final_arrival.max(earliest)
+            };
+            final_arrival = if rate > 0.0 {
+                initial + timing.frame_size_bits as f64 / rate
+            } else {
+                f64::INFINITY
+            };
+            arrivals.push((initial, final_arrival));
+        }
+
+        let cpb_size = self.params.cpb_size_bits;
+        let mut lanes = Vec::with_capacity(timings.len());
+        let mut arrived_bits = 0u64; // access units that have fully arrived
+        let mut next_arrival = 0;
+        let mut removed_bits = 0u64;
+        for (n, timing) in timings.iter().enumerate() {
+            let removal = removal_time(timing);
+            while next_arrival < timings.len()
+                && arrivals[next_arrival].1 <= removal + ARRIVAL_TOLERANCE_SEC
+            {
+                arrived_bits += timings[next_arrival].frame_size_bits;
+                next_arrival += 1;
+            }
+            // Part of the access unit still arriving at removal time
+            let partial_bits = arrivals
+                .get(next_arrival)
+                .filter(|&&(initial, _)| initial < removal)
+                .map_or(0, |&(initial, _)| {
+                    ((removal - initial) * rate).round() as u64
+                });
+            let delivered_bits = arrived_bits + partial_bits;
+            let fullness_bits = delivered_bits.saturating_sub(removed_bits);
+
+            let pre = CpbState {
+                fullness_bits,
+                time_sec: removal,
+                frame_idx: timing.display_idx,
+                is_removal: false,
+                overflow: fullness_bits > cpb_size,
+                underflow: false,
+            };
+            removed_bits += timing.frame_size_bits;
+            let post = CpbState {
+                fullness_bits: delivered_bits.saturating_sub(removed_bits),
+                is_removal: true,
+                overflow: false,
+                underflow: arrivals[n].1 > removal + ARRIVAL_TOLERANCE_SEC,
+                ..pre
+            };
+
+            if pre.overflow {
+                self.overflow_count += 1;
+            }
+            if post.underflow {
+                self.underflow_count += 1;
+            }
+            self.push_state(pre);
+            self.push_state(post);
+            self.cpb_fullness_bits = post.fullness_bits;
+            self.current_time_sec = removal;
+            self.frame_count += 1;
+
+            lanes.push(HrdLaneData {
+                display_idx: timing.display_idx,
+                pre_removal_percent: pre.fullness_percent(cpb_size),
+                post_removal_percent: post.fullness_percent(cpb_size),
+                frame_size_bits: timing.frame_size_bits,
+                overflow: pre.overflow,
+                underflow: post.underflow,
+            });
+        }
+
+        lanes
+    }
+
     /// Get current CPB fullness in bits
     pub fn current_fullness_bits(&self) -> u64 {
         self.cpb_fullness_bits
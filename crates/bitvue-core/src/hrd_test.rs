@@ -1335,4 +1335,83 @@ mod edge_case_tests {
         // Act & Assert - CBR flag should be set
         assert!(params.cbr_flag);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_hrd_model_signalled_removal_times() {
+        // Arrange - 1 Mbps, 500 kbit CPB, initial removal delay 0.4s
+        let params = HrdParameters {
+            bit_rate_bps: 1_000_000,
+            cpb_size_bits: 500_000,
+            initial_cpb_removal_delay: 36000,
+            ..Default::default()
+        };
+        let timing = |idx: usize, bits: u64, dts: f64| FrameHrdTiming {
+            dts_sec: Some(dts),
+            ..create_test_frame_timing(idx, bits, dts)
+        };
+        let timings = [
+            timing(0, 200_000, 0.25),
+            timing(1, 100_000, 0.30),
+            timing(2, 200_000, 1.30),
+            timing(3, 350_000, 1.80),
+        ];
+        let mut vbr = HrdModel::new(params.clone());
+        let mut cbr = HrdModel::new(HrdParameters {
+            cbr_flag: true,
+            ..params.clone()
+        });
+        let mut small_vbr = HrdModel::new(HrdParameters {
+            cpb_size_bits: 300_000,
+            ..params
+        });
+
+        // Act
+        let vbr_lanes = vbr.process_signalled_frames(&timings);
+        let cbr_lanes = cbr.process_signalled_frames(&timings);
+        let small_lanes = small_vbr.process_signalled_frames(&timings);
+
+        // Assert - 200 kbit of frame 0 and 50 kbit of frame 1 have arrived
+        // by the first removal
+        assert_eq!(vbr.state_history()[0].fullness_bits, 250_000);
+        assert_eq!(vbr.state_history()[0].time_sec, 0.25);
+        // A VBR HRD holds frame 2 back until 0.9s, so the CPB never fills
+        assert!(vbr.is_conformant());
+        assert_eq!(vbr_lanes.len(), 4);
+        // A CBR HRD keeps delivering: 850 kbit arrived, 300 kbit removed
+        assert_eq!(cbr.overflow_count(), 1);
+        assert!(cbr_lanes[2].overflow);
+        assert_eq!(cbr.state_history()[4].fullness_bits, 550_000);
+        // A VBR HRD overflows too once the CPB is smaller than its fullness
+        assert_eq!(small_vbr.overflow_count(), 1);
+        assert!(small_lanes[3].overflow);
+    }
+
+    #[test]
+    fn test_hrd_model_signalled_underflow() {
+        // Arrange - the second frame needs 0.2s to arrive but is removed
+        // 0.05s after the first
+        let params = HrdParameters {
+            bit_rate_bps: 1_000_000,
+            cpb_size_bits: 500_000,
+            initial_cpb_removal_delay: 22500,
+            ..Default::default()
+        };
+        let timing = |idx: usize, bits: u64, dts: f64| FrameHrdTiming {
+            dts_sec: Some(dts),
+            ..create_test_frame_timing(idx, bits, dts)
+        };
+        let mut model = HrdModel::new(params);
+
+        // Act
+        let lanes = model.process_signalled_frames(&[
+            timing(0, 200_000, 0.25),
+            timing(1, 200_000, 0.30),
+        ]);
+
+        // Assert - t_af of frame 1 is 0.4s, past its removal time
+        assert!(!lanes[0].underflow);
+        assert!(lanes[1].underflow);
+        assert_eq!(model.underflow_count(), 1);
+        assert_eq!(model.overflow_count(), 0);
+    }
+}
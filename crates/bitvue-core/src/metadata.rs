@@ -398,6 +398,7 @@ pub enum SeiMessageType {
     AmbientViewingEnvironment,
     ContentLightLevelInfo,
     AlternativeDepthInfo,
+    TimeCode,
     Unknown(u32),
 }
 
@@ -418,6 +419,7 @@ impl SeiMessageType {
             SeiMessageType::FramePackingArrangement => "Frame Packing Arrangement",
             SeiMessageType::DisplayOrientation => "Display Orientation",
             SeiMessageType::GreenMetadata => "Green Metadata",
+            SeiMessageType::TimeCode => "Time Code",
             _ => "Other",
         }
     }
@@ -467,6 +469,8 @@ pub enum SeiData {
     },
     /// Film grain characteristics
     FilmGrain { present: bool },
+    /// Time codes formatted as `HH:MM:SS:FF`
+    TimeCode { timecodes: Vec<String> },
     /// Raw/unparsed data
     Raw(Vec<u8>),
 }
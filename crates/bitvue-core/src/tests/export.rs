@@ -18,11 +18,11 @@
 use crate::export::{
     build_context_menu, create_diff_heatmap_export, create_hit_test_input,
     create_qp_heatmap_export, create_selection_input, create_tooltip_field, create_tooltip_input,
-    evaluate_context_menu_guard, export_frames_csv, export_frames_json, export_overlay_ppm,
-    export_overlay_rgba, ContextMenuScope, EvidenceBundleExportRequest, EvidenceBundleExportResult,
-    EvidenceBundleManifest, ExportConfig, ExportSummary, FrameExportRow, GuardEvalContext,
-    OverlayExportData, OverlayExportRequest, OverlayExportResult, OverlayImageFormat, OverlayType,
-    SemanticProbeRunner,
+    evaluate_context_menu_guard, export_evidence_bundle, export_frames_csv, export_frames_json,
+    export_overlay_ppm, export_overlay_rgba, ContextMenuScope, EvidenceBundleExportRequest,
+    EvidenceBundleExportResult, EvidenceBundleManifest, ExportConfig, ExportSummary,
+    FrameExportRow, GuardEvalContext, OverlayExportData, OverlayExportRequest, OverlayExportResult,
+    OverlayImageFormat, OverlayType, SemanticProbeRunner,
 };
 
 // Types from other modules
@@ -355,6 +355,51 @@ fn test_evidence_bundle_export_request_default() {
     assert!(!request.include_interaction_trace);
     assert!(!request.include_logs);
     assert_eq!(request.order_type, OrderType::Display);
+    assert!(request.artifacts.is_empty());
+}
+
+#[test]
+fn test_export_evidence_bundle_copies_artifacts() {
+    let dir = tempfile::tempdir().unwrap();
+    let frames = dir.path().join("frames.csv");
+    std::fs::write(&frames, "index,size\n0,100\n").unwrap();
+    let overlays = dir.path().join("overlays");
+    std::fs::create_dir(&overlays).unwrap();
+    std::fs::write(overlays.join("qp_0.png"), [0u8; 8]).unwrap();
+
+    let out = dir.path().join("out");
+    let request = EvidenceBundleExportRequest {
+        output_dir: out.clone(),
+        backend: "bitvue-avc".to_string(),
+        order_type: OrderType::Decode,
+        artifacts: vec![frames, overlays],
+        ..Default::default()
+    };
+    let result = export_evidence_bundle(&request, &[]);
+    assert!(result.success, "{:?}", result.error);
+
+    let bundle = std::path::PathBuf::from(result.bundle_path.unwrap());
+    assert!(result
+        .files_created
+        .contains(&"artifacts/frames.csv".to_string()));
+    assert!(result
+        .files_created
+        .contains(&"artifacts/overlays/qp_0.png".to_string()));
+    assert_eq!(
+        std::fs::read(bundle.join("artifacts/overlays/qp_0.png")).unwrap(),
+        [0u8; 8]
+    );
+
+    let manifest: EvidenceBundleManifest = serde_json::from_str(
+        &std::fs::read_to_string(bundle.join("bundle_manifest.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(manifest.backend, "bitvue-avc");
+    assert_eq!(manifest.order_type, OrderType::Decode);
+    assert_eq!(
+        manifest.artifacts,
+        ["artifacts/frames.csv", "artifacts/overlays"]
+    );
 }
 
 #[test]
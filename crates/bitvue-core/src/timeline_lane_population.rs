@@ -22,6 +22,14 @@ pub enum LaneType {
     DiagnosticsDensity,
     /// Reorder mismatch band (PTS ≠ DTS)
     ReorderMismatch,
+    /// PCR accuracy of a transport stream (ns)
+    PcrAccuracy,
+    /// Bitrate of the video PID of a transport stream (Mbps)
+    PidBitrate,
+    /// PTS/DTS minus PCR at PES arrival (ms)
+    BufferDelay,
+    /// Transport stream (ETR 290) error count
+    TransportErrors,
 }
 
 impl LaneType {
@@ -33,6 +41,10 @@ impl LaneType {
             LaneType::SliceCount => "Slice Count",
             LaneType::DiagnosticsDensity => "Diagnostics",
             LaneType::ReorderMismatch => "Reorder Mismatch",
+            LaneType::PcrAccuracy => "PCR Accuracy",
+            LaneType::PidBitrate => "PID Bitrate",
+            LaneType::BufferDelay => "Buffer Delay",
+            LaneType::TransportErrors => "Transport Errors",
         }
     }
 
@@ -44,6 +56,10 @@ impl LaneType {
             LaneType::SliceCount => "yellow",
             LaneType::DiagnosticsDensity => "orange",
             LaneType::ReorderMismatch => "red",
+            LaneType::PcrAccuracy => "lime",
+            LaneType::PidBitrate => "blue",
+            LaneType::BufferDelay => "purple",
+            LaneType::TransportErrors => "crimson",
         }
     }
 
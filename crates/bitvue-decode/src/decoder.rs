@@ -39,15 +39,15 @@ pub struct DecodedFrame {
     pub bit_depth: u8,
     /// Y plane data (Arc-wrapped slice for zero-copy cloning)
     pub y_plane: Arc<[u8]>,
-    /// Y plane stride
+    /// Y plane stride in bytes (samples above 8 bits take two bytes)
     pub y_stride: usize,
     /// U plane data (None for monochrome, Arc-wrapped slice for cheap cloning)
     pub u_plane: Option<Arc<[u8]>>,
-    /// U plane stride
+    /// U plane stride in bytes
     pub u_stride: usize,
     /// V plane data (None for monochrome, Arc-wrapped slice for cheap cloning)
     pub v_plane: Option<Arc<[u8]>>,
-    /// V plane stride
+    /// V plane stride in bytes
     pub v_stride: usize,
     /// Frame timestamp
     pub timestamp: i64,
@@ -327,6 +327,7 @@ impl Av1Decoder {
         let width = picture.width();
         let height = picture.height();
         let bit_depth = picture.bit_depth() as u8;
+        let bytes_per_sample = if bit_depth > 8 { 2 } else { 1 };
 
         // Validate dimensions first
         if width == 0 || height == 0 {
@@ -421,7 +422,9 @@ impl Av1Decoder {
             let u_plane = u_result?;
             let v_plane = v_result?;
 
-            (Some(u_plane), u_stride, Some(v_plane), v_stride)
+            // The extracted planes are tightly packed
+            let chroma_stride = chroma_width * bytes_per_sample;
+            (Some(u_plane), chroma_stride, Some(v_plane), chroma_stride)
         } else {
             (None, 0, None, 0)
         };
@@ -449,7 +452,7 @@ impl Av1Decoder {
             height,
             bit_depth,
             y_plane: y_plane.into_boxed_slice().into(),
-            y_stride,
+            y_stride: width as usize * bytes_per_sample,
             u_plane: u_plane.map(|v| v.into_boxed_slice().into()),
             u_stride,
             v_plane: v_plane.map(|v| v.into_boxed_slice().into()),
@@ -461,12 +464,15 @@ impl Av1Decoder {
         })
     }
 
-    /// Decodes all frames from data (supports IVF container or raw OBU)
+    /// Decodes all frames from data (supports IVF container, Annex B or raw OBU)
     pub fn decode_all(&mut self, data: &[u8]) -> Result<Vec<DecodedFrame>> {
         // Check if it's IVF format and extract frame data
         if data.len() >= 4 && &data[0..4] == b"DKIF" {
             // IVF container - extract frames and decode each separately
             self.decode_ivf(data)
+        } else if bitvue_av1_codec::is_annex_b(data) {
+            // Length-delimited OBUs - dav1d only takes the low-overhead format
+            self.decode_annex_b(data)
         } else {
             // Raw OBU data
             self.send_data(data, 0)?;
@@ -547,6 +553,16 @@ impl Av1Decoder {
                 // Supported formats - proceed
             }
             VideoFormat::Unknown => {
+                // AV1 Annex B has no magic number; it is only recognised by
+                // its size fields, which needs the whole file
+                let mut data = Vec::with_capacity(file_size as usize);
+                data.extend_from_slice(&header);
+                reader
+                    .read_to_end(&mut data)
+                    .map_err(|e| DecodeError::Decode(format!("Failed to read file: {}", e)))?;
+                if bitvue_av1_codec::is_annex_b(&data) {
+                    return self.decode_annex_b(&data);
+                }
                 return Err(DecodeError::Decode(
                     "Unknown video format - could not detect from magic number".to_string(),
                 ));
@@ -764,6 +780,38 @@ impl Av1Decoder {
         Ok(decoded_frames)
     }
 
+    /// Decode an AV1 Annex B stream, one temporal unit per packet
+    fn decode_annex_b(&mut self, data: &[u8]) -> Result<Vec<DecodedFrame>> {
+        let units = bitvue_av1_codec::parse_annex_b(data)
+            .map_err(|e| DecodeError::Decode(format!("Annex B parsing failed: {}", e)))?;
+
+        let mut decoded_frames = Vec::with_capacity(units.len().min(MAX_FRAMES_PER_FILE));
+        for (idx, unit) in units.iter().take(MAX_FRAMES_PER_FILE).enumerate() {
+            if let Err(e) = self.send_data_owned(unit.to_low_overhead(data), idx as i64) {
+                abseil::LOG!(
+                    WARNING,
+                    "Failed to send temporal unit {} to decoder: {}. Skipping it.",
+                    idx,
+                    e
+                );
+            } else {
+                while let Ok(frame) = self.get_frame() {
+                    decoded_frames.push(frame);
+                }
+            }
+        }
+
+        self.drain_decoder_frames(&mut decoded_frames)?;
+
+        if decoded_frames.is_empty() {
+            return Err(DecodeError::Decode(
+                "Failed to decode any frames from Annex B stream".to_string(),
+            ));
+        }
+
+        Ok(decoded_frames)
+    }
+
     /// Parse IVF header from data, returning (header_size, frame_count)
     fn parse_ivf_header(&self, data: &[u8]) -> Result<(usize, usize)> {
         if data.len() < 32 {
@@ -252,16 +252,17 @@ impl FfmpegDecoder {
 
         // For YUV420P frames, chroma format is known (no calculation needed)
         // This avoids recalculating for every frame in the video
+        // The extracted planes are tightly packed
         Ok(DecodedFrame {
             width,
             height,
             bit_depth: 8, // FFmpeg typically outputs 8-bit
             y_plane: y_plane.into(),
-            y_stride: y_stride as usize,
+            y_stride: width as usize,
             u_plane: Some(u_plane.into()),
-            u_stride: u_stride as usize,
+            u_stride: width as usize / 2,
             v_plane: Some(v_plane.into()),
-            v_stride: v_stride as usize,
+            v_stride: width as usize / 2,
             timestamp,
             frame_type,
             qp_avg: None, // FFmpeg doesn't provide QP info easily
@@ -21,6 +21,7 @@
 pub mod decoder;
 #[cfg(feature = "ffmpeg")]
 pub mod ffmpeg;
+pub mod picture_hash;
 pub mod plane_utils;
 pub mod strategy;
 pub mod traits;
@@ -32,6 +33,10 @@ pub mod yuv_loader;
 pub use decoder::{Av1Decoder, DecodedFrame, FrameType};
 #[cfg(feature = "ffmpeg")]
 pub use ffmpeg::{H264Decoder, HevcDecoder, Vp9Decoder};
+pub use picture_hash::{
+    compute_picture_hash, frame_md5, frames_md5, parse_md5_file, verify_frame_md5,
+    verify_picture_hash, HashMethod, PictureHash,
+};
 pub use traits::{CodecType, Decoder, DecoderCapabilities, DecoderFactory};
 #[cfg(feature = "vvdec")]
 pub use vvdec::VvcDecoder;
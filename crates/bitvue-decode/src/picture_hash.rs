@@ -0,0 +1,398 @@
+//! Decoded picture hash verification
+//!
+//! Hashes the planes of a [`DecodedFrame`] the way the HEVC/VVC decoded
+//! picture hash SEI defines them (MD5, CRC or checksum per colour component)
+//! and compares them with the values carried in the bitstream. Whole-frame
+//! MD5 digests, as found in the `.md5` side files of AVC conformance
+//! streams, are supported as well.
+//!
+//! Samples are fed to the hash as one byte for bit depths up to 8 and as two
+//! little-endian bytes above that, over the visible picture area only.
+
+use crate::decoder::{ChromaFormat, DecodeError, DecodedFrame};
+use bitvue_core::diagnostics::{Diagnostic, DiagnosticCategory, DiagnosticSeverity};
+use bitvue_core::{FrameKey, StreamId};
+
+/// Result type for hashing operations
+type Result<T> = std::result::Result<T, DecodeError>;
+
+/// Picture hash method (hash_type of the decoded picture hash SEI)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashMethod {
+    /// 128-bit MD5 per component
+    Md5,
+    /// 16-bit CRC (CCITT polynomial 0x1021) per component
+    Crc,
+    /// 32-bit position-masked sum per component
+    Checksum,
+}
+
+impl HashMethod {
+    /// Get display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashMethod::Md5 => "MD5",
+            HashMethod::Crc => "CRC",
+            HashMethod::Checksum => "Checksum",
+        }
+    }
+}
+
+/// Per-component picture hash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PictureHash {
+    pub method: HashMethod,
+    /// One big-endian hash value per component (Y, Cb, Cr)
+    pub planes: Vec<Vec<u8>>,
+}
+
+/// Visible samples of one plane
+struct Plane {
+    samples: Vec<u16>,
+    width: usize,
+}
+
+/// Compute the per-component hash of a decoded frame
+pub fn compute_picture_hash(frame: &DecodedFrame, method: HashMethod) -> Result<PictureHash> {
+    let planes = frame_planes(frame)?
+        .iter()
+        .map(|plane| match method {
+            HashMethod::Md5 => md5_plane(plane, frame.bit_depth).to_vec(),
+            HashMethod::Crc => crc_plane(plane, frame.bit_depth).to_be_bytes().to_vec(),
+            HashMethod::Checksum => checksum_plane(plane, frame.bit_depth)
+                .to_be_bytes()
+                .to_vec(),
+        })
+        .collect();
+
+    Ok(PictureHash { method, planes })
+}
+
+/// MD5 of the frame as written to a planar YUV file (all planes in order)
+pub fn frame_md5(frame: &DecodedFrame) -> Result<[u8; 16]> {
+    frames_md5(std::slice::from_ref(frame))
+}
+
+/// MD5 of a sequence of frames as written to one planar YUV file
+pub fn frames_md5(frames: &[DecodedFrame]) -> Result<[u8; 16]> {
+    let mut context = md5::Context::new();
+    for frame in frames {
+        for plane in frame_planes(frame)? {
+            context.consume(sample_bytes(&plane, frame.bit_depth));
+        }
+    }
+    Ok(context.compute().0)
+}
+
+/// Parse an MD5 side file: one hex digest per line, optionally followed by a
+/// file name as in `md5sum` output. Lines without a digest are skipped.
+pub fn parse_md5_file(text: &str) -> Vec<[u8; 16]> {
+    text.lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter_map(parse_hex_digest)
+        .collect()
+}
+
+fn parse_hex_digest(token: &str) -> Option<[u8; 16]> {
+    if token.len() != 32 || !token.is_ascii() {
+        return None;
+    }
+    let mut digest = [0u8; 16];
+    for (i, byte) in digest.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&token[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(digest)
+}
+
+/// Compare a decoded frame with the hash signalled for it.
+///
+/// Returns an ERROR diagnostic naming the mismatching components, or `None`
+/// when every signalled component matches.
+pub fn verify_picture_hash(
+    frame: &DecodedFrame,
+    expected: &PictureHash,
+    frame_index: usize,
+    offset: u64,
+) -> Option<Diagnostic> {
+    let actual = match compute_picture_hash(frame, expected.method) {
+        Ok(actual) => actual,
+        Err(e) => return Some(hash_failure(frame_index, offset, e)),
+    };
+
+    let mismatches: Vec<(usize, &[u8], &[u8])> = expected
+        .planes
+        .iter()
+        .zip(&actual.planes)
+        .enumerate()
+        .filter(|(_, (e, a))| e != a)
+        .map(|(c, (e, a))| (c, e.as_slice(), a.as_slice()))
+        .collect();
+    if mismatches.is_empty() && expected.planes.len() <= actual.planes.len() {
+        return None;
+    }
+
+    let names: Vec<&str> = mismatches.iter().map(|(c, _, _)| COMPONENTS[*c]).collect();
+    let mut message = format!(
+        "Frame {}: decoded picture {} mismatch",
+        frame_index,
+        expected.method.name()
+    );
+    if names.is_empty() {
+        message.push_str(&format!(
+            " (SEI hashes {} components, decoder output has {})",
+            expected.planes.len(),
+            actual.planes.len()
+        ));
+    } else {
+        message.push_str(&format!(" in {}", names.join(", ")));
+    }
+
+    let mut diagnostic = mismatch(frame_index, offset, message);
+    for (c, e, a) in mismatches {
+        diagnostic = diagnostic
+            .with_detail(format!("expected_{}", COMPONENTS[c]), hex(e))
+            .with_detail(format!("actual_{}", COMPONENTS[c]), hex(a));
+    }
+    Some(diagnostic)
+}
+
+/// Compare a decoded frame with a whole-frame MD5 digest
+pub fn verify_frame_md5(
+    frame: &DecodedFrame,
+    expected: &[u8; 16],
+    frame_index: usize,
+    offset: u64,
+) -> Option<Diagnostic> {
+    let actual = match frame_md5(frame) {
+        Ok(actual) => actual,
+        Err(e) => return Some(hash_failure(frame_index, offset, e)),
+    };
+    if &actual == expected {
+        return None;
+    }
+
+    Some(
+        mismatch(
+            frame_index,
+            offset,
+            format!("Frame {}: decoded frame MD5 mismatch", frame_index),
+        )
+        .with_detail("expected".to_string(), hex(expected))
+        .with_detail("actual".to_string(), hex(&actual)),
+    )
+}
+
+const COMPONENTS: [&str; 3] = ["Y", "Cb", "Cr"];
+
+fn mismatch(frame_index: usize, offset: u64, message: String) -> Diagnostic {
+    Diagnostic::new(
+        0,
+        DiagnosticSeverity::Error,
+        StreamId::A,
+        message,
+        DiagnosticCategory::Decode,
+        offset,
+    )
+    .with_frame(FrameKey {
+        stream: StreamId::A,
+        frame_index,
+        pts: None,
+    })
+}
+
+fn hash_failure(frame_index: usize, offset: u64, error: DecodeError) -> Diagnostic {
+    mismatch(
+        frame_index,
+        offset,
+        format!(
+            "Frame {}: cannot hash decoded picture: {}",
+            frame_index, error
+        ),
+    )
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Extract the visible samples of every plane of a frame
+fn frame_planes(frame: &DecodedFrame) -> Result<Vec<Plane>> {
+    let width = frame.width as usize;
+    let height = frame.height as usize;
+    let (chroma_width, chroma_height) = match frame.chroma_format {
+        ChromaFormat::Yuv420 => (width.div_ceil(2), height.div_ceil(2)),
+        ChromaFormat::Yuv422 => (width.div_ceil(2), height),
+        ChromaFormat::Yuv444 => (width, height),
+        ChromaFormat::Monochrome => (0, 0),
+    };
+
+    let plane = |data: &[u8], stride: usize, w: usize, h: usize| {
+        plane_samples(data, stride, w, h, frame.bit_depth).ok_or_else(|| {
+            DecodeError::Decode(format!("Decoded plane is smaller than {}x{}", w, h))
+        })
+    };
+
+    let mut planes = vec![plane(&frame.y_plane, frame.y_stride, width, height)?];
+    if frame.chroma_format != ChromaFormat::Monochrome {
+        if let (Some(u), Some(v)) = (&frame.u_plane, &frame.v_plane) {
+            planes.push(plane(u, frame.u_stride, chroma_width, chroma_height)?);
+            planes.push(plane(v, frame.v_stride, chroma_width, chroma_height)?);
+        }
+    }
+    Ok(planes)
+}
+
+/// Read `width`x`height` samples from a plane whose rows are `stride`
+/// bytes apart
+fn plane_samples(
+    data: &[u8],
+    stride: usize,
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+) -> Option<Plane> {
+    let bytes_per_sample = if bit_depth > 8 { 2 } else { 1 };
+    let row_bytes = width * bytes_per_sample;
+    if stride < row_bytes {
+        return None;
+    }
+
+    let mut samples = Vec::with_capacity(width * height);
+    for row in 0..height {
+        let start = row * stride;
+        let line = data.get(start..start + row_bytes)?;
+        if bytes_per_sample == 1 {
+            samples.extend(line.iter().map(|&s| s as u16));
+        } else {
+            samples.extend(
+                line.chunks_exact(2)
+                    .map(|s| u16::from_le_bytes([s[0], s[1]])),
+            );
+        }
+    }
+    Some(Plane { samples, width })
+}
+
+/// pictureData bytes of a plane: one byte per sample, or two (low first)
+/// above 8 bits
+fn sample_bytes(plane: &Plane, bit_depth: u8) -> Vec<u8> {
+    if bit_depth > 8 {
+        plane.samples.iter().flat_map(|s| s.to_le_bytes()).collect()
+    } else {
+        plane.samples.iter().map(|&s| s as u8).collect()
+    }
+}
+
+fn md5_plane(plane: &Plane, bit_depth: u8) -> [u8; 16] {
+    md5::compute(sample_bytes(plane, bit_depth)).0
+}
+
+fn crc_plane(plane: &Plane, bit_depth: u8) -> u16 {
+    let mut crc: u32 = 0xFFFF;
+    let mut feed = |byte: u8| {
+        for bit in (0..8).rev() {
+            let msb = (crc >> 15) & 1;
+            let value = ((byte >> bit) & 1) as u32;
+            crc = (((crc << 1) + value) & 0xFFFF) ^ (msb * 0x1021);
+        }
+    };
+    for byte in sample_bytes(plane, bit_depth) {
+        feed(byte);
+    }
+    // Two zero bytes flush the register
+    feed(0);
+    feed(0);
+    crc as u16
+}
+
+fn checksum_plane(plane: &Plane, bit_depth: u8) -> u32 {
+    let mut sum: u32 = 0;
+    for (i, &sample) in plane.samples.iter().enumerate() {
+        let (x, y) = (i % plane.width, i / plane.width);
+        let xor_mask = ((x & 0xFF) ^ (y & 0xFF) ^ (x >> 8) ^ (y >> 8)) as u32;
+        sum = sum.wrapping_add((sample as u32 & 0xFF) ^ xor_mask);
+        if bit_depth > 8 {
+            sum = sum.wrapping_add((sample as u32 >> 8) ^ xor_mask);
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::FrameType;
+    use std::sync::Arc;
+
+    fn gray_frame(width: u32, height: u32, value: u8) -> DecodedFrame {
+        let luma = (width * height) as usize;
+        let chroma = luma / 4;
+        DecodedFrame {
+            width,
+            height,
+            bit_depth: 8,
+            y_plane: Arc::from(vec![value; luma]),
+            y_stride: width as usize,
+            u_plane: Some(Arc::from(vec![128u8; chroma])),
+            u_stride: width as usize / 2,
+            v_plane: Some(Arc::from(vec![128u8; chroma])),
+            v_stride: width as usize / 2,
+            timestamp: 0,
+            frame_type: FrameType::Key,
+            qp_avg: None,
+            chroma_format: ChromaFormat::Yuv420,
+        }
+    }
+
+    #[test]
+    fn test_frame_md5_matches_yuv_file() {
+        let frame = gray_frame(4, 2, 16);
+        let mut yuv = vec![16u8; 8];
+        yuv.extend([128u8; 4]);
+        assert_eq!(frame_md5(&frame).unwrap(), md5::compute(&yuv).0);
+    }
+
+    #[test]
+    fn test_checksum_applies_position_mask() {
+        let plane = Plane {
+            samples: vec![0; 4],
+            width: 2,
+        };
+        // masks are 0, 1, 1, 0
+        assert_eq!(checksum_plane(&plane, 8), 2);
+    }
+
+    #[test]
+    fn test_crc_of_empty_plane_is_flushed_init() {
+        let plane = Plane {
+            samples: Vec::new(),
+            width: 0,
+        };
+        // 0xFFFF shifted through 16 zero bits
+        assert_eq!(crc_plane(&plane, 8), 0x1D0F);
+    }
+
+    #[test]
+    fn test_verify_picture_hash_reports_mismatch() {
+        let frame = gray_frame(4, 4, 60);
+        let mut expected = compute_picture_hash(&frame, HashMethod::Crc).unwrap();
+        assert!(verify_picture_hash(&frame, &expected, 3, 100).is_none());
+
+        expected.planes[1] = vec![0, 0];
+        let diagnostic = verify_picture_hash(&frame, &expected, 3, 100).unwrap();
+        assert_eq!(diagnostic.severity, DiagnosticSeverity::Error);
+        assert_eq!(diagnostic.frame_key.map(|k| k.frame_index), Some(3));
+        assert!(diagnostic.message.contains("Cb"));
+        assert_eq!(diagnostic.details.get("expected_Cb").unwrap(), "0000");
+    }
+
+    #[test]
+    fn test_parse_md5_file() {
+        let text = "d41d8cd98f00b204e9800998ecf8427e  frame0.yuv\n\n# comment\n\
+                    D41D8CD98F00B204E9800998ECF8427E\n";
+        let digests = parse_md5_file(text);
+        assert_eq!(digests.len(), 2);
+        assert_eq!(digests[0], md5::compute(b"").0);
+        assert_eq!(digests[0], digests[1]);
+    }
+}
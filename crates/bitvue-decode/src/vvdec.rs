@@ -400,7 +400,7 @@ impl VvcDecoder {
             // Extract Y plane
             let y_plane = &vf.planes[0];
             let y_data = self.extract_plane(y_plane, height as usize)?;
-            let y_stride = y_plane.stride as usize;
+            let y_stride = Self::packed_stride(y_plane);
 
             // Extract U and V planes (if present)
             let (u_data, u_stride, v_data, v_stride) = if vf.num_planes > 1 {
@@ -414,9 +414,9 @@ impl VvcDecoder {
 
                 (
                     Some(self.extract_plane(u_plane, chroma_height)?),
-                    u_plane.stride as usize,
+                    Self::packed_stride(u_plane),
                     Some(self.extract_plane(v_plane, chroma_height)?),
-                    v_plane.stride as usize,
+                    Self::packed_stride(v_plane),
                 )
             } else {
                 (None, 0, None, 0)
@@ -461,6 +461,11 @@ impl VvcDecoder {
         }
     }
 
+    /// Row length in bytes of a plane copied by [`Self::extract_plane`]
+    fn packed_stride(plane: &ffi::VvdecPlane) -> usize {
+        plane.width as usize * plane.bytes_per_sample as usize
+    }
+
     /// Extract plane data from vvdec plane with comprehensive bounds checking
     ///
     /// This function validates all memory access to prevent buffer overflows
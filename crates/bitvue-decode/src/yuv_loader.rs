@@ -308,7 +308,15 @@ impl YuvLoader {
         // Read Y plane
         let y_size = self.params.y_plane_size();
         let mut y_plane = vec![0u8; y_size];
-        let bytes_read = self.file.read(&mut y_plane)?;
+        // A single read() may stop at the BufReader boundary; keep filling
+        // until the plane is complete or the file ends.
+        let mut bytes_read = 0;
+        while bytes_read < y_size {
+            match self.file.read(&mut y_plane[bytes_read..])? {
+                0 => break,
+                n => bytes_read += n,
+            }
+        }
         if bytes_read == 0 {
             return Ok(None); // EOF
         }
@@ -342,28 +350,25 @@ impl YuvLoader {
             v_plane.as_deref(),
         );
 
+        let bytes_per_sample = self.params.bit_depth.bytes_per_sample();
+        let chroma_stride = match self.params.chroma_subsampling {
+            ChromaSubsampling::Yuv420 | ChromaSubsampling::Yuv422 => {
+                (self.params.width / 2) as usize * bytes_per_sample
+            }
+            ChromaSubsampling::Yuv444 => self.params.width as usize * bytes_per_sample,
+            ChromaSubsampling::Mono => 0,
+        };
+
         let frame = DecodedFrame {
             width: self.params.width,
             height: self.params.height,
             bit_depth: self.params.bit_depth.bits(),
             y_plane: y_plane.into_boxed_slice().into(),
-            y_stride: self.params.width as usize,
+            y_stride: self.params.width as usize * bytes_per_sample,
             u_plane: u_plane.map(|v| v.into_boxed_slice().into()),
-            u_stride: match self.params.chroma_subsampling {
-                ChromaSubsampling::Yuv420 | ChromaSubsampling::Yuv422 => {
-                    (self.params.width / 2) as usize
-                }
-                ChromaSubsampling::Yuv444 => self.params.width as usize,
-                ChromaSubsampling::Mono => 0,
-            },
+            u_stride: chroma_stride,
             v_plane: v_plane.map(|v| v.into_boxed_slice().into()),
-            v_stride: match self.params.chroma_subsampling {
-                ChromaSubsampling::Yuv420 | ChromaSubsampling::Yuv422 => {
-                    (self.params.width / 2) as usize
-                }
-                ChromaSubsampling::Yuv444 => self.params.width as usize,
-                ChromaSubsampling::Mono => 0,
-            },
+            v_stride: chroma_stride,
             timestamp: self.current_frame as i64,
             frame_type: crate::decoder::FrameType::Key, // Unknown for raw YUV
             qp_avg: None,
@@ -439,4 +444,25 @@ mod tests {
         // Total = 4,147,200 + 2 * 1,036,800 = 6,220,800
         assert_eq!(params.frame_size_bytes(), 6_220_800);
     }
+
+    #[test]
+    fn test_read_y4m_frames_across_buffer_boundary() {
+        // 64x48 4:2:0 frames are 4608 bytes, so the second frame's luma
+        // straddles the 8 KiB BufReader boundary
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("clip.y4m");
+        let mut data = b"YUV4MPEG2 W64 H48 F25:1 Ip A1:1 C420jpeg\n".to_vec();
+        for i in 0..3u8 {
+            data.extend_from_slice(b"FRAME\n");
+            data.extend(std::iter::repeat_n(i, 64 * 48 * 3 / 2));
+        }
+        std::fs::write(&path, data).unwrap();
+
+        let mut loader = YuvLoader::open(&path, None).unwrap();
+        for i in 0..3u8 {
+            let frame = loader.read_frame().unwrap().unwrap();
+            assert!(frame.y_plane.iter().all(|&v| v == i));
+        }
+        assert!(loader.read_frame().unwrap().is_none());
+    }
 }
@@ -159,7 +159,8 @@ pub fn detect_from_extension(path: &Path) -> ContainerFormat {
         Some("webm") => ContainerFormat::Matroska,
         Some("avi") => ContainerFormat::AVI,
         Some("ivf") => ContainerFormat::IVF,
-        Some("h264") | Some("h265") | Some("hevc") | Some("265") => ContainerFormat::AnnexB,
+        Some("h264") | Some("h265") | Some("hevc") | Some("265") | Some("h266") | Some("266")
+        | Some("vvc") => ContainerFormat::AnnexB,
         _ => ContainerFormat::Unknown,
     }
 }
@@ -383,6 +384,10 @@ mod tests {
             detect_from_extension(Path::new("test.h264")),
             ContainerFormat::AnnexB
         );
+        assert_eq!(
+            detect_from_extension(Path::new("test.266")),
+            ContainerFormat::AnnexB
+        );
         assert_eq!(
             detect_from_extension(Path::new("test.xyz")),
             ContainerFormat::Unknown
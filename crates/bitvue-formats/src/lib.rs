@@ -12,19 +12,18 @@
 //! # Supported Formats
 //!
 //! - **MP4** (ISO Base Media File Format) - For extracting video samples (AV1, H.264, H.265)
+//!   from progressive and fragmented files (CMAF/DASH init and media segments),
+//!   and a box tree inspector with decoded fields and byte ranges
 //! - **MKV** (Matroska) - For extracting video samples (AV1, H.264, H.265)
-//! - **TS** (MPEG-2 Transport Stream) - For extracting video samples (AV1, H.264, H.265)
+//! - **TS** (MPEG-2 Transport Stream) - For extracting video samples (AV1, H.264, H.265, H.266, MPEG-2)
+//!   and transport-layer analysis (PCR, continuity, PSI repetition, ETR 290 checks)
 //!
 //! # Supported Codecs
 //!
-//! ## Phase 12A (Current)
 //! - **AV1**: Fully supported in MP4, MKV, TS
-//! - **H.264/AVC**: Sample extraction from MP4 (avc1/avc3) and MKV (V_MPEG4/ISO/AVC)
-//! - **H.265/HEVC**: Sample extraction from MP4 (hev1/hvc1) and MKV (V_MPEGH/ISO/HEVC)
-//!
-//! ## Phase 12B/C (Planned)
-//! - **H.264/AVC**: Full TS parsing with PMT stream type detection
-//! - **H.265/HEVC**: Full TS parsing with PMT stream type detection
+//! - **H.264/AVC**: Sample extraction from MP4 (avc1/avc3), MKV (V_MPEG4/ISO/AVC) and TS (stream type 0x1B)
+//! - **H.265/HEVC**: Sample extraction from MP4 (hev1/hvc1), MKV (V_MPEGH/ISO/HEVC) and TS (stream type 0x24)
+//! - **H.266/VVC** and **MPEG-2 video**: Sample extraction from TS (stream types 0x33 and 0x02)
 //!
 //! # Examples
 //!
@@ -62,13 +61,20 @@ pub mod container;
 pub mod ivf_writer;
 pub mod mkv;
 pub mod mp4;
+pub mod mp4_tree;
 pub mod resource_budget;
 pub mod ts;
+pub mod ts_analysis;
+
+#[cfg(test)]
+mod test_fixtures;
 
 // Re-export main types and functions
 pub use container::{detect_container_format, is_supported_format, ContainerFormat};
 pub use ivf_writer::IvfWriter;
 pub use mkv::MkvInfo;
 pub use mp4::{BoxHeader, Mp4Info};
+pub use mp4_tree::{parse_box_tree, Mp4Box, Mp4BoxTree};
 pub use resource_budget::{AllocationError, ResourceBudget};
-pub use ts::TsInfo;
+pub use ts::{TsInfo, TsVideoCodec, TsVideoStream};
+pub use ts_analysis::{analyze_ts, TsAnalysis, TsCheck, TsError};
@@ -21,6 +21,7 @@ mod element_id {
     pub const TRACK_NUMBER: u32 = 0xD7;
     pub const TRACK_TYPE: u32 = 0x83;
     pub const CODEC_ID: u32 = 0x86;
+    pub const CODEC_PRIVATE: u32 = 0x63A2;
     pub const CLUSTER: u32 = 0x1F43B675;
     pub const TIMECODE: u32 = 0xE7;
     pub const SIMPLE_BLOCK: u32 = 0xA3;
@@ -48,6 +49,16 @@ fn read_vint(cursor: &mut Cursor<&[u8]>) -> Result<u64, BitvueError> {
     // Most MKV elements use 1-4 byte VINTs; limit to prevent DoS attacks
     const MAX_VINT_LENGTH: usize = 4;
 
+    read_vint_raw(cursor, MAX_VINT_LENGTH).map(|(value, length)| strip_vint_marker(value, length))
+}
+
+/// Read the raw bytes of a VINT (marker bit included)
+///
+/// Returns the big-endian value of all bytes and the VINT length.
+fn read_vint_raw(
+    cursor: &mut Cursor<&[u8]>,
+    max_length: usize,
+) -> Result<(u64, usize), BitvueError> {
     let mut first_byte = [0u8; 1];
     cursor
         .read_exact(&mut first_byte)
@@ -56,39 +67,24 @@ fn read_vint(cursor: &mut Cursor<&[u8]>) -> Result<u64, BitvueError> {
     let first = first_byte[0];
 
     // Find the length marker (first 1 bit from MSB)
-    let mut length = 0;
-    for i in 0..8 {
-        if (first & (0x80 >> i)) != 0 {
-            length = i + 1;
-            break;
-        }
-    }
-
-    // Validate VINT length is within EBML specification (1-8 bytes)
     // The only invalid case is 0x00 (no marker bit found)
     // Note: 0x01 is VALID (8-byte VINT with marker at bit position 0)
-    if length == 0 {
+    let length = first.leading_zeros() as usize + 1;
+    if length > 8 {
         return Err(BitvueError::InvalidData(
             "Invalid VINT: no marker bit found (all zeros)".to_string(),
         ));
     }
 
     // Enforce reasonable maximum to prevent DoS via malicious 8-byte VINTs
-    if length > MAX_VINT_LENGTH {
+    if length > max_length {
         return Err(BitvueError::InvalidData(format!(
             "VINT length {} exceeds maximum allowed {}",
-            length, MAX_VINT_LENGTH
+            length, max_length
         )));
     }
 
-    // Extract value (remove length marker)
-    // For length = 8, the first byte is all marker (0x01), so data bits = 0
-    let mut value = if length < 8 {
-        (first & (0xFF >> length)) as u64
-    } else {
-        // length = 8: marker is at bit 0, first byte has no data bits
-        0
-    };
+    let mut value = first as u64;
 
     // Read remaining bytes
     for _ in 1..length {
@@ -99,17 +95,39 @@ fn read_vint(cursor: &mut Cursor<&[u8]>) -> Result<u64, BitvueError> {
         value = (value << 8) | byte[0] as u64;
     }
 
-    Ok(value)
+    Ok((value, length))
+}
+
+/// Remove the length marker bit from a raw VINT value
+fn strip_vint_marker(raw: u64, length: usize) -> u64 {
+    // For length = 8, the first byte is all marker (0x01), so data bits = 0
+    raw & (u64::MAX >> (64 - 7 * length))
 }
 
 /// Read EBML element ID (variable-length)
+///
+/// Unlike sizes, element IDs keep their marker bits (e.g. EBML = 0x1A45DFA3).
 fn read_element_id(cursor: &mut Cursor<&[u8]>) -> Result<u32, BitvueError> {
-    read_vint(cursor).map(|v| v as u32)
+    // Matroska element IDs are at most 4 bytes long
+    read_vint_raw(cursor, 4).map(|(value, _)| value as u32)
 }
 
 /// Read EBML element size
+///
+/// Sizes may use up to 8 bytes. An "unknown" size (all data bits set, as
+/// written by live muxers) and sizes running past the end of the data are
+/// clamped to the remaining data length.
 fn read_element_size(cursor: &mut Cursor<&[u8]>) -> Result<u64, BitvueError> {
-    read_vint(cursor)
+    let (raw, length) = read_vint_raw(cursor, 8)?;
+    let size = strip_vint_marker(raw, length);
+
+    let remaining = (cursor.get_ref().len() as u64).saturating_sub(cursor.position());
+    let unknown = size == strip_vint_marker(u64::MAX, length);
+    Ok(if unknown {
+        remaining
+    } else {
+        size.min(remaining)
+    })
 }
 
 /// Read a string element
@@ -143,6 +161,24 @@ fn read_string(cursor: &mut Cursor<&[u8]>, size: usize) -> Result<String, Bitvue
     Ok(result)
 }
 
+/// Read a binary element
+fn read_binary(cursor: &mut Cursor<&[u8]>, size: usize) -> Result<Vec<u8>, BitvueError> {
+    const MAX_BINARY_SIZE: usize = 1_000_000; // 1MB max to prevent DoS
+
+    if size > MAX_BINARY_SIZE {
+        return Err(BitvueError::InvalidData(format!(
+            "Binary element size {} exceeds maximum allowed {}",
+            size, MAX_BINARY_SIZE
+        )));
+    }
+
+    let mut buf = vec![0u8; size];
+    cursor
+        .read_exact(&mut buf)
+        .map_err(|_| BitvueError::UnexpectedEof(cursor.position()))?;
+    Ok(buf)
+}
+
 /// Read an unsigned integer element
 fn read_uint(cursor: &mut Cursor<&[u8]>, size: usize) -> Result<u64, BitvueError> {
     // SECURITY: Validate size to prevent memory exhaustion
@@ -173,6 +209,9 @@ fn read_uint(cursor: &mut Cursor<&[u8]>, size: usize) -> Result<u64, BitvueError
 pub struct MkvInfo {
     /// Codec ID (e.g. "V_AV1")
     pub codec_id: Option<String>,
+    /// CodecPrivate of the video track (e.g. the avcC/hvcC decoder
+    /// configuration record)
+    pub codec_private: Option<Vec<u8>>,
     /// Track number for video track
     pub video_track_number: Option<u64>,
     /// Total number of blocks/samples
@@ -380,6 +419,7 @@ fn parse_track_entry(
     let mut track_number = None;
     let mut track_type = None;
     let mut codec_id = None;
+    let mut codec_private = None;
 
     const MAX_ELEMENTS_PER_LEVEL: usize = 10_000;
     let mut element_count = 0;
@@ -405,6 +445,9 @@ fn parse_track_entry(
             element_id::CODEC_ID => {
                 codec_id = Some(read_string(cursor, size as usize)?);
             }
+            element_id::CODEC_PRIVATE => {
+                codec_private = Some(read_binary(cursor, size as usize)?);
+            }
             _ => {
                 cursor.seek(SeekFrom::Start(element_end))?;
             }
@@ -415,6 +458,7 @@ fn parse_track_entry(
     if track_type == Some(1) {
         info.video_track_number = track_number;
         info.codec_id = codec_id;
+        info.codec_private = codec_private;
     }
 
     Ok(())
@@ -693,10 +737,38 @@ mod tests {
         assert!(read_vint(&mut cursor).is_err());
     }
 
+    #[test]
+    fn test_read_element_id_keeps_marker() {
+        let data = [0x1A, 0x45, 0xDF, 0xA3];
+        let mut cursor = Cursor::new(&data[..]);
+        assert_eq!(read_element_id(&mut cursor).unwrap(), element_id::EBML);
+
+        let data = [0xA3];
+        let mut cursor = Cursor::new(&data[..]);
+        assert_eq!(
+            read_element_id(&mut cursor).unwrap(),
+            element_id::SIMPLE_BLOCK
+        );
+    }
+
+    #[test]
+    fn test_read_element_size_eight_bytes_and_unknown() {
+        // 8-byte size: 0x01 00 00 00 00 00 00 05 -> 5
+        let data = [0x01, 0, 0, 0, 0, 0, 0, 0x05, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE];
+        let mut cursor = Cursor::new(&data[..]);
+        assert_eq!(read_element_size(&mut cursor).unwrap(), 5);
+
+        // Unknown size (all data bits set) extends to the end of the data
+        let data = [0xFF, 0x00, 0x00, 0x00];
+        let mut cursor = Cursor::new(&data[..]);
+        assert_eq!(read_element_size(&mut cursor).unwrap(), 3);
+    }
+
     #[test]
     fn test_mkv_info_default() {
         let info = MkvInfo::default();
         assert_eq!(info.codec_id, None);
+        assert_eq!(info.codec_private, None);
         assert_eq!(info.video_track_number, None);
         assert_eq!(info.sample_count, 0);
         assert_eq!(info.samples.len(), 0);
@@ -711,4 +783,44 @@ mod tests {
         let result = extract_av1_samples(&[0x1A, 0x45, 0xDF, 0xA3]);
         assert!(result.is_err()); // Should fail on parsing, not codec check
     }
+
+    /// EBML element with a one-byte size
+    fn element(id: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut out = id.to_vec();
+        out.push(0x80 | payload.len() as u8);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    #[test]
+    fn test_parse_mkv_codec_private() {
+        let avcc = [1, 66, 0, 30, 0xFF, 0xE1, 0, 2, 0x67, 0x42, 1, 0, 1, 0x68];
+        let video = [
+            element(&[0xD7], &[1]),
+            element(&[0x83], &[1]),
+            element(&[0x86], b"V_MPEG4/ISO/AVC"),
+            element(&[0x63, 0xA2], &avcc),
+        ]
+        .concat();
+        let audio = [
+            element(&[0xD7], &[2]),
+            element(&[0x83], &[2]),
+            element(&[0x63, 0xA2], &[0xAA]),
+        ]
+        .concat();
+        let tracks = element(
+            &[0x16, 0x54, 0xAE, 0x6B],
+            &[element(&[0xAE], &video), element(&[0xAE], &audio)].concat(),
+        );
+        let data = [
+            element(&[0x1A, 0x45, 0xDF, 0xA3], &[]),
+            element(&[0x18, 0x53, 0x80, 0x67], &tracks),
+        ]
+        .concat();
+
+        let info = parse_mkv(&data).unwrap();
+        assert_eq!(info.codec_id.as_deref(), Some("V_MPEG4/ISO/AVC"));
+        assert_eq!(info.video_track_number, Some(1));
+        assert_eq!(info.codec_private.as_deref(), Some(&avcc[..]));
+    }
 }
@@ -3,8 +3,20 @@
 //! Implements minimal MP4 parsing to extract AV1 video samples.
 //! No external dependencies - pure Rust implementation.
 //!
+//! Fragmented MP4 is supported: samples of `moof`/`traf`/`trun` boxes are
+//! appended to those of the `moov` sample table, with `tfhd` and `trex`
+//! defaults. A CMAF or DASH init segment followed by its media segments is
+//! such a file; see [`join_segments`]. `sidx` and `emsg` boxes are kept as
+//! metadata.
+//!
+//! Presentation timestamps follow the edit list: composition times (decode
+//! time plus the `ctts` or `trun` offset, which may be negative) are mapped
+//! onto the movie timeline by the `elst` entries, as players do.
+//!
 //! References:
 //! - ISO/IEC 14496-12 (ISO Base Media File Format)
+//! - ISO/IEC 23000-19 (Common Media Application Format)
+//! - ISO/IEC 23009-1 (MPEG-DASH, event message box)
 //! - AV1 Codec ISO Media File Format Binding
 
 use crate::resource_budget::ResourceBudget;
@@ -23,7 +35,7 @@ const MAX_ENTRY_COUNT: u32 = 10_000_000;
 const MAX_TOTAL_SAMPLES: usize = 100_000;
 
 /// Maximum nesting depth for MP4 boxes to prevent stack overflow
-const MAX_BOX_DEPTH: u8 = 16;
+pub(crate) const MAX_BOX_DEPTH: u8 = 16;
 
 /// Maximum sample size to prevent memory exhaustion (100MB)
 const MAX_SAMPLE_SIZE: usize = 100 * 1024 * 1024;
@@ -147,7 +159,7 @@ pub struct Mp4Info {
     pub brand: Option<String>,
     /// Compatible brands
     pub compatible_brands: Vec<String>,
-    /// Video codec (e.g. "av01", "avc1", "hev1")
+    /// Video codec (e.g. "av01", "avc1", "hev1", "vvc1")
     pub codec: Option<String>,
     /// Timescale (units per second)
     pub timescale: u32,
@@ -163,10 +175,130 @@ pub struct Mp4Info {
     pub timestamps: Vec<u64>,
     /// Composition time offsets (for PTS calculation)
     pub composition_offsets: Vec<i32>,
-    /// Presentation timestamps (PTS) in timescale units
-    pub presentation_timestamps: Vec<u64>,
+    /// Presentation timestamps (PTS) in timescale units: composition times
+    /// mapped through the edit list. `None` for samples no edit presents,
+    /// e.g. leading samples before the first edit's media_time.
+    pub presentation_timestamps: Vec<Option<u64>>,
     /// Key frame indices (sync samples)
     pub key_frames: Vec<u32>,
+    /// Decoder configuration record from the sample entry (avcC, hvcC, vvcC,
+    /// av1C or vpcC payload), if present
+    pub codec_config: Option<Vec<u8>>,
+    /// track_ID of the track the samples belong to
+    pub track_id: Option<u32>,
+    /// Movie timescale (mvhd), the units of edit durations
+    pub movie_timescale: u32,
+    /// Edit list of the track (edts/elst)
+    pub edit_list: Vec<EditListEntry>,
+    /// Fragment defaults of each track (mvex/trex)
+    pub track_extends: Vec<TrackExtends>,
+    /// Movie fragments carrying samples of the track, in file order
+    pub fragments: Vec<MovieFragment>,
+    /// Segment indexes (sidx), in file order
+    pub segment_indexes: Vec<SegmentIndex>,
+    /// Event messages (emsg), in file order
+    pub event_messages: Vec<EventMessage>,
+}
+
+/// Edit list entry (elst)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EditListEntry {
+    /// Duration of the edit in movie timescale units
+    pub segment_duration: u64,
+    /// Start of the edit in media timescale units; -1 for an empty edit
+    pub media_time: i64,
+    /// Playback rate as 16.16 fixed point; 0 holds the sample at
+    /// `media_time` for the whole edit ("dwell")
+    pub media_rate: i32,
+}
+
+impl EditListEntry {
+    /// Whether the edit presents no media and only delays what follows
+    pub fn is_empty(&self) -> bool {
+        self.media_time == -1
+    }
+}
+
+/// Sample defaults of a track in movie fragments (trex)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrackExtends {
+    pub track_id: u32,
+    pub default_sample_description_index: u32,
+    pub default_sample_duration: u32,
+    pub default_sample_size: u32,
+    pub default_sample_flags: u32,
+}
+
+/// A movie fragment (moof) of the track
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MovieFragment {
+    /// File offset of the moof box
+    pub offset: u64,
+    /// mfhd sequence_number
+    pub sequence_number: u32,
+    /// tfdt baseMediaDecodeTime, if present
+    pub base_media_decode_time: Option<u64>,
+    /// Index of the first sample of the fragment
+    pub first_sample: usize,
+    /// Number of samples in the fragment
+    pub sample_count: usize,
+}
+
+/// A segment index (sidx)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SegmentIndex {
+    /// File offset of the sidx box
+    pub offset: u64,
+    pub reference_id: u32,
+    pub timescale: u32,
+    pub earliest_presentation_time: u64,
+    /// Distance from the end of the sidx box to the first referenced byte
+    pub first_offset: u64,
+    pub references: Vec<SegmentReference>,
+}
+
+/// One reference of a segment index
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SegmentReference {
+    /// The reference points to another sidx rather than to media
+    pub reference_type: bool,
+    pub referenced_size: u32,
+    pub subsegment_duration: u32,
+    pub starts_with_sap: bool,
+    pub sap_type: u8,
+    pub sap_delta_time: u32,
+}
+
+/// An event message (emsg)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventMessage {
+    /// File offset of the emsg box
+    pub offset: u64,
+    pub version: u8,
+    pub scheme_id_uri: String,
+    pub value: String,
+    pub timescale: u32,
+    /// Presentation time relative to the segment start (version 0)
+    pub presentation_time_delta: Option<u32>,
+    /// Absolute presentation time (version 1)
+    pub presentation_time: Option<u64>,
+    pub event_duration: u32,
+    pub id: u32,
+    pub message_data: Vec<u8>,
+}
+
+/// Join an init segment and its media segments into one fragmented MP4
+///
+/// A CMAF track file is the concatenation of its header and segments, so the
+/// result can be passed to [`parse_mp4`] and the sample extractors.
+pub fn join_segments<S: AsRef<[u8]>>(init: &[u8], segments: &[S]) -> Vec<u8> {
+    let size = init.len() + segments.iter().map(|s| s.as_ref().len()).sum::<usize>();
+    let mut data = Vec::with_capacity(size);
+    data.extend_from_slice(init);
+    for segment in segments {
+        data.extend_from_slice(segment.as_ref());
+    }
+    data
 }
 
 /// Codec validator for sample extraction
@@ -352,6 +484,19 @@ pub fn extract_hevc_samples(data: &[u8]) -> Result<Vec<Cow<'_, [u8]>>, BitvueErr
     })
 }
 
+/// Parse MP4 file and extract H.266/VVC samples
+///
+/// Extracts NAL units from MP4 container for H.266/VVC video streams.
+/// Supports both 'vvc1' (parameter sets in vvcC) and 'vvi1' (parameter sets in-band) codec types.
+///
+/// Returns zero-copy Cow slices that borrow from the input data when possible,
+/// avoiding unnecessary memory allocation.
+pub fn extract_vvc_samples(data: &[u8]) -> Result<Vec<Cow<'_, [u8]>>, BitvueError> {
+    extract_samples_with_validator(data, "H.266/VVC", |codec| {
+        codec == "vvc1" || codec == "vvi1"
+    })
+}
+
 /// Parse MP4 file structure
 pub fn parse_mp4(data: &[u8]) -> Result<Mp4Info, BitvueError> {
     if data.is_empty() {
@@ -409,6 +554,18 @@ pub fn parse_mp4(data: &[u8]) -> Result<Mp4Info, BitvueError> {
             b"mdat" => {
                 // Media data box - skip for now
             }
+            b"moof" => {
+                // Movie fragment box - samples of a fragmented file
+                parse_moof(&mut cursor, &header, &mut info, 0)?;
+            }
+            b"sidx" => {
+                // Segment index box
+                parse_sidx(&mut cursor, &header, &mut info)?;
+            }
+            b"emsg" => {
+                // Event message box
+                parse_emsg(&mut cursor, &header, &mut info)?;
+            }
             _ => {
                 // Skip unknown box
             }
@@ -418,6 +575,9 @@ pub fn parse_mp4(data: &[u8]) -> Result<Mp4Info, BitvueError> {
         cursor.seek(SeekFrom::Start(box_end_usize))?;
     }
 
+    // Fragments add samples after the moov, so timing is settled last
+    calculate_presentation_timestamps(&mut info);
+
     Ok(info)
 }
 
@@ -475,10 +635,18 @@ fn parse_moov(
             })?;
 
         match &child_header.box_type {
+            b"mvhd" => {
+                // Movie header - contains the movie timescale
+                parse_mvhd(cursor, &child_header, info)?;
+            }
             b"trak" => {
                 // Track box
                 parse_trak(cursor, &child_header, info, data, child_depth)?;
             }
+            b"mvex" => {
+                // Movie extends box - fragment defaults
+                parse_mvex(cursor, &child_header, info, child_depth)?;
+            }
             _ => {
                 // Skip other boxes
             }
@@ -491,6 +659,10 @@ fn parse_moov(
 }
 
 /// Parse trak (Track) box
+///
+/// Each track is parsed on its own; the first video track (handler `vide`,
+/// or the first track with a sample entry when no handler is given) becomes
+/// the track of `info`, and the others are dropped.
 fn parse_trak(
     cursor: &mut Cursor<&[u8]>,
     header: &BoxHeader,
@@ -511,6 +683,9 @@ fn parse_trak(
         .checked_add(header.data_size())
         .ok_or_else(|| BitvueError::InvalidData("MP4 child box end offset overflow".to_string()))?;
 
+    let mut track = Mp4Info::default();
+    let mut handler_type = None;
+
     while cursor.position() < box_end {
         let child_header = BoxHeader::parse(cursor)?;
         let child_end = child_header
@@ -520,25 +695,57 @@ fn parse_trak(
                 BitvueError::InvalidData("MP4 child box end offset overflow".to_string())
             })?;
 
-        if &child_header.box_type == b"mdia" {
-            // Media box
-            parse_mdia(cursor, &child_header, info, data, child_depth)?;
+        match &child_header.box_type {
+            b"tkhd" => {
+                // Track header - contains track_ID
+                parse_tkhd(cursor, &child_header, &mut track)?;
+            }
+            b"edts" => {
+                // Edit box - contains the edit list
+                parse_edts(cursor, &child_header, &mut track)?;
+            }
+            b"mdia" => {
+                // Media box
+                handler_type = parse_mdia(cursor, &child_header, &mut track, data, child_depth)?;
+            }
+            _ => {}
         }
 
         cursor.seek(SeekFrom::Start(child_end))?;
     }
 
+    let is_video = match handler_type {
+        Some(handler_type) => &handler_type == b"vide",
+        None => track.codec.is_some(),
+    };
+    if is_video && info.codec.is_none() {
+        info.codec = track.codec;
+        info.timescale = track.timescale;
+        info.sample_count = track.sample_count;
+        info.sample_offsets = track.sample_offsets;
+        info.sample_sizes = track.sample_sizes;
+        info.sample_durations = track.sample_durations;
+        info.timestamps = track.timestamps;
+        info.composition_offsets = track.composition_offsets;
+        info.key_frames = track.key_frames;
+        info.codec_config = track.codec_config;
+        info.track_id = track.track_id;
+        info.edit_list = track.edit_list;
+    }
+
     Ok(())
 }
 
 /// Parse mdia (Media) box
+///
+/// Returns the hdlr handler_type, if present.
 fn parse_mdia(
     cursor: &mut Cursor<&[u8]>,
     header: &BoxHeader,
     info: &mut Mp4Info,
     data: &[u8],
     depth: u8,
-) -> Result<(), BitvueError> {
+) -> Result<Option<[u8; 4]>, BitvueError> {
     // SECURITY: Check box nesting depth to prevent stack overflow
     if depth >= MAX_BOX_DEPTH {
         return Err(BitvueError::InvalidData(format!(
@@ -552,6 +759,7 @@ fn parse_mdia(
         .checked_add(header.data_size())
         .ok_or_else(|| BitvueError::InvalidData("MP4 child box end offset overflow".to_string()))?;
 
+    let mut handler_type = None;
     while cursor.position() < box_end {
         let child_header = BoxHeader::parse(cursor)?;
         let child_end = child_header
@@ -566,6 +774,11 @@ fn parse_mdia(
                 // Media header - contains timescale
                 parse_mdhd(cursor, &child_header, info)?;
             }
+            b"hdlr" => {
+                // Handler reference - the track's media type
+                cursor.seek(SeekFrom::Current(8))?; // version + flags + pre_defined
+                handler_type = Some(read_box_type(cursor)?);
+            }
             b"minf" => {
                 // Media information box
                 parse_minf(cursor, &child_header, info, data, child_depth)?;
@@ -576,7 +789,7 @@ fn parse_mdia(
         cursor.seek(SeekFrom::Start(child_end))?;
     }
 
-    Ok(())
+    Ok(handler_type)
 }
 
 /// Parse minf (Media Information) box
@@ -687,9 +900,6 @@ fn parse_stbl(
     // Calculate timestamps from durations
     calculate_timestamps(info);
 
-    // Calculate presentation timestamps (PTS = DTS + composition_offset)
-    calculate_presentation_timestamps(info);
-
     Ok(())
 }
 
@@ -714,18 +924,53 @@ fn parse_stsd(
 
     if entry_count > 0 {
         // Parse first sample entry
-        let _entry_size = read_u32(cursor)?; // Size of sample entry
+        let entry_start = cursor.position();
+        let entry_size = read_u32(cursor)?; // Size of sample entry
         let codec = read_box_type(cursor)?; // Codec fourcc (e.g. 'av01', 'avc1', 'hev1')
 
         info.codec = Some(String::from_utf8_lossy(&codec).to_string());
 
-        // We don't need to parse the rest of the sample entry for now
-        // Just need to know the codec type
+        // The decoder configuration box is optional for our purposes; a
+        // malformed sample entry must not fail the whole parse.
+        let entry_end = entry_start.saturating_add(entry_size as u64);
+        info.codec_config = parse_codec_config(cursor, entry_end).ok().flatten();
     }
 
     Ok(())
 }
 
+/// Size of the VisualSampleEntry fields between the fourcc and child boxes
+const VISUAL_SAMPLE_ENTRY_FIELDS: u64 = 78;
+
+/// Find the decoder configuration box inside a visual sample entry
+fn parse_codec_config(
+    cursor: &mut Cursor<&[u8]>,
+    entry_end: u64,
+) -> Result<Option<Vec<u8>>, BitvueError> {
+    let entry_end = entry_end.min(cursor.get_ref().len() as u64);
+    cursor.seek(SeekFrom::Current(VISUAL_SAMPLE_ENTRY_FIELDS as i64))?;
+
+    while cursor.position() + 8 <= entry_end {
+        let child = BoxHeader::parse(cursor)?;
+        let child_end = child.data_offset.saturating_add(child.data_size());
+        if child_end > entry_end {
+            break;
+        }
+
+        if matches!(
+            &child.box_type,
+            b"avcC" | b"hvcC" | b"vvcC" | b"av1C" | b"vpcC"
+        ) {
+            let start = child.data_offset as usize;
+            return Ok(Some(cursor.get_ref()[start..child_end as usize].to_vec()));
+        }
+
+        cursor.seek(SeekFrom::Start(child_end))?;
+    }
+
+    Ok(None)
+}
+
 /// Parse stco (Chunk Offset) box
 fn parse_stco(
     cursor: &mut Cursor<&[u8]>,
@@ -967,6 +1212,638 @@ fn parse_stss(
     Ok(())
 }
 
+/// Parse tkhd (Track Header) box
+fn parse_tkhd(
+    cursor: &mut Cursor<&[u8]>,
+    _header: &BoxHeader,
+    info: &mut Mp4Info,
+) -> Result<(), BitvueError> {
+    let version = read_u8(cursor)?;
+    cursor.seek(SeekFrom::Current(3))?; // flags
+
+    if version == 1 {
+        cursor.seek(SeekFrom::Current(16))?; // creation_time + modification_time
+    } else {
+        cursor.seek(SeekFrom::Current(8))?; // creation_time + modification_time
+    }
+    info.track_id = Some(read_u32(cursor)?);
+
+    Ok(())
+}
+
+/// Parse mvhd (Movie Header) box
+fn parse_mvhd(
+    cursor: &mut Cursor<&[u8]>,
+    _header: &BoxHeader,
+    info: &mut Mp4Info,
+) -> Result<(), BitvueError> {
+    let version = read_u8(cursor)?;
+    cursor.seek(SeekFrom::Current(3))?; // flags
+
+    if version == 1 {
+        cursor.seek(SeekFrom::Current(16))?; // creation_time + modification_time
+    } else {
+        cursor.seek(SeekFrom::Current(8))?; // creation_time + modification_time
+    }
+    info.movie_timescale = read_u32(cursor)?;
+
+    Ok(())
+}
+
+/// Parse edts (Edit) box
+fn parse_edts(
+    cursor: &mut Cursor<&[u8]>,
+    header: &BoxHeader,
+    info: &mut Mp4Info,
+) -> Result<(), BitvueError> {
+    let box_end = header
+        .data_offset
+        .checked_add(header.data_size())
+        .ok_or_else(|| BitvueError::InvalidData("MP4 child box end offset overflow".to_string()))?;
+
+    while cursor.position() < box_end {
+        let child_header = BoxHeader::parse(cursor)?;
+        let child_end = child_header
+            .data_offset
+            .checked_add(child_header.data_size())
+            .ok_or_else(|| {
+                BitvueError::InvalidData("MP4 child box end offset overflow".to_string())
+            })?;
+
+        if &child_header.box_type == b"elst" {
+            // Edit list - leaf parser
+            parse_elst(cursor, &child_header, info)?;
+        }
+
+        cursor.seek(SeekFrom::Start(child_end))?;
+    }
+
+    Ok(())
+}
+
+/// Parse elst (Edit List) box
+fn parse_elst(
+    cursor: &mut Cursor<&[u8]>,
+    _header: &BoxHeader,
+    info: &mut Mp4Info,
+) -> Result<(), BitvueError> {
+    let version = read_u8(cursor)?;
+    cursor.seek(SeekFrom::Current(3))?; // flags
+
+    let entry_count = read_u32(cursor)?;
+
+    // Validate entry count to prevent DoS via massive allocations
+    if entry_count > MAX_ENTRY_COUNT {
+        return Err(BitvueError::InvalidData(format!(
+            "Entry count {} exceeds maximum allowed {}",
+            entry_count, MAX_ENTRY_COUNT
+        )));
+    }
+
+    info.edit_list.clear();
+    for _ in 0..entry_count {
+        let (segment_duration, media_time) = if version == 1 {
+            (read_u64(cursor)?, read_u64(cursor)? as i64)
+        } else {
+            (read_u32(cursor)? as u64, read_u32(cursor)? as i32 as i64)
+        };
+        let media_rate = read_u32(cursor)? as i32;
+        info.edit_list.push(EditListEntry {
+            segment_duration,
+            media_time,
+            media_rate,
+        });
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Fragmented MP4
+// ============================================================================
+
+/// tfhd: base_data_offset present
+const TFHD_BASE_DATA_OFFSET_PRESENT: u32 = 0x00_0001;
+/// tfhd: sample_description_index present
+const TFHD_SAMPLE_DESCRIPTION_INDEX_PRESENT: u32 = 0x00_0002;
+/// tfhd: default_sample_duration present
+const TFHD_DEFAULT_SAMPLE_DURATION_PRESENT: u32 = 0x00_0008;
+/// tfhd: default_sample_size present
+const TFHD_DEFAULT_SAMPLE_SIZE_PRESENT: u32 = 0x00_0010;
+/// tfhd: default_sample_flags present
+const TFHD_DEFAULT_SAMPLE_FLAGS_PRESENT: u32 = 0x00_0020;
+/// tfhd: data offsets are relative to the moof box
+const TFHD_DEFAULT_BASE_IS_MOOF: u32 = 0x02_0000;
+
+/// trun: data_offset present
+const TRUN_DATA_OFFSET_PRESENT: u32 = 0x00_0001;
+/// trun: first_sample_flags present
+const TRUN_FIRST_SAMPLE_FLAGS_PRESENT: u32 = 0x00_0004;
+/// trun: sample_duration present
+const TRUN_SAMPLE_DURATION_PRESENT: u32 = 0x00_0100;
+/// trun: sample_size present
+const TRUN_SAMPLE_SIZE_PRESENT: u32 = 0x00_0200;
+/// trun: sample_flags present
+const TRUN_SAMPLE_FLAGS_PRESENT: u32 = 0x00_0400;
+/// trun: sample_composition_time_offset present
+const TRUN_SAMPLE_COMPOSITION_TIME_OFFSET_PRESENT: u32 = 0x00_0800;
+
+/// sample_is_non_sync_sample bit of the sample flags
+const SAMPLE_IS_NON_SYNC_SAMPLE: u32 = 0x0001_0000;
+
+/// State of the track fragment (traf) being parsed
+#[derive(Debug, Clone, Default)]
+struct TrackFragment {
+    track_id: u32,
+    /// Offset trun data offsets are relative to
+    base_data_offset: u64,
+    default_sample_duration: u32,
+    default_sample_size: u32,
+    default_sample_flags: u32,
+    /// Decode time of the next sample; tfdt baseMediaDecodeTime at first
+    next_decode_time: Option<u64>,
+    /// Where the samples of a trun without data_offset start
+    next_data_offset: u64,
+}
+
+/// Parse mvex (Movie Extends) box
+fn parse_mvex(
+    cursor: &mut Cursor<&[u8]>,
+    header: &BoxHeader,
+    info: &mut Mp4Info,
+    depth: u8,
+) -> Result<(), BitvueError> {
+    // SECURITY: Check box nesting depth to prevent stack overflow
+    if depth >= MAX_BOX_DEPTH {
+        return Err(BitvueError::InvalidData(format!(
+            "MP4 box depth {} exceeds maximum {}",
+            depth, MAX_BOX_DEPTH
+        )));
+    }
+    let box_end = header
+        .data_offset
+        .checked_add(header.data_size())
+        .ok_or_else(|| BitvueError::InvalidData("MP4 child box end offset overflow".to_string()))?;
+
+    while cursor.position() < box_end {
+        let child_header = BoxHeader::parse(cursor)?;
+        let child_end = child_header
+            .data_offset
+            .checked_add(child_header.data_size())
+            .ok_or_else(|| {
+                BitvueError::InvalidData("MP4 child box end offset overflow".to_string())
+            })?;
+
+        if &child_header.box_type == b"trex" {
+            // Track extends - per-track sample defaults
+            parse_trex(cursor, &child_header, info)?;
+        }
+
+        cursor.seek(SeekFrom::Start(child_end))?;
+    }
+
+    Ok(())
+}
+
+/// Parse trex (Track Extends) box
+fn parse_trex(
+    cursor: &mut Cursor<&[u8]>,
+    _header: &BoxHeader,
+    info: &mut Mp4Info,
+) -> Result<(), BitvueError> {
+    cursor.seek(SeekFrom::Current(4))?; // version + flags
+
+    info.track_extends.push(TrackExtends {
+        track_id: read_u32(cursor)?,
+        default_sample_description_index: read_u32(cursor)?,
+        default_sample_duration: read_u32(cursor)?,
+        default_sample_size: read_u32(cursor)?,
+        default_sample_flags: read_u32(cursor)?,
+    });
+
+    Ok(())
+}
+
+/// Parse moof (Movie Fragment) box
+fn parse_moof(
+    cursor: &mut Cursor<&[u8]>,
+    header: &BoxHeader,
+    info: &mut Mp4Info,
+    depth: u8,
+) -> Result<(), BitvueError> {
+    // SECURITY: Check box nesting depth to prevent stack overflow
+    if depth >= MAX_BOX_DEPTH {
+        return Err(BitvueError::InvalidData(format!(
+            "MP4 box depth {} exceeds maximum {}",
+            depth, MAX_BOX_DEPTH
+        )));
+    }
+    let child_depth = depth + 1;
+    let moof_start = header.data_offset - header.header_size();
+    let box_end = header
+        .data_offset
+        .checked_add(header.data_size())
+        .ok_or_else(|| BitvueError::InvalidData("MP4 child box end offset overflow".to_string()))?;
+
+    let mut fragment = MovieFragment {
+        offset: moof_start,
+        first_sample: info.sample_sizes.len(),
+        ..Default::default()
+    };
+    // Without an explicit base, the first traf's data starts at the moof and
+    // each later traf's data follows that of the previous one
+    let mut data_end = moof_start;
+
+    while cursor.position() < box_end {
+        let child_header = BoxHeader::parse(cursor)?;
+        let child_end = child_header
+            .data_offset
+            .checked_add(child_header.data_size())
+            .ok_or_else(|| {
+                BitvueError::InvalidData("MP4 child box end offset overflow".to_string())
+            })?;
+
+        match &child_header.box_type {
+            b"mfhd" => {
+                // Movie fragment header - sequence number
+                cursor.seek(SeekFrom::Current(4))?; // version + flags
+                fragment.sequence_number = read_u32(cursor)?;
+            }
+            b"traf" => {
+                // Track fragment box
+                data_end = parse_traf(
+                    cursor,
+                    &child_header,
+                    info,
+                    &mut fragment,
+                    moof_start,
+                    data_end,
+                    child_depth,
+                )?;
+            }
+            _ => {}
+        }
+
+        cursor.seek(SeekFrom::Start(child_end))?;
+    }
+
+    fragment.sample_count = info.sample_sizes.len() - fragment.first_sample;
+    if fragment.sample_count > 0 {
+        info.fragments.push(fragment);
+    }
+
+    Ok(())
+}
+
+/// Parse traf (Track Fragment) box
+///
+/// Returns the end of the fragment's sample data, the default base of the
+/// next traf.
+fn parse_traf(
+    cursor: &mut Cursor<&[u8]>,
+    header: &BoxHeader,
+    info: &mut Mp4Info,
+    fragment: &mut MovieFragment,
+    moof_start: u64,
+    default_base: u64,
+    depth: u8,
+) -> Result<u64, BitvueError> {
+    // SECURITY: Check box nesting depth to prevent stack overflow
+    if depth >= MAX_BOX_DEPTH {
+        return Err(BitvueError::InvalidData(format!(
+            "MP4 box depth {} exceeds maximum {}",
+            depth, MAX_BOX_DEPTH
+        )));
+    }
+    let box_end = header
+        .data_offset
+        .checked_add(header.data_size())
+        .ok_or_else(|| BitvueError::InvalidData("MP4 child box end offset overflow".to_string()))?;
+
+    let mut traf = TrackFragment {
+        base_data_offset: default_base,
+        next_data_offset: default_base,
+        ..Default::default()
+    };
+
+    while cursor.position() < box_end {
+        let child_header = BoxHeader::parse(cursor)?;
+        let child_end = child_header
+            .data_offset
+            .checked_add(child_header.data_size())
+            .ok_or_else(|| {
+                BitvueError::InvalidData("MP4 child box end offset overflow".to_string())
+            })?;
+
+        match &child_header.box_type {
+            b"tfhd" => {
+                // Track fragment header - track and defaults
+                parse_tfhd(cursor, &child_header, info, &mut traf, moof_start)?;
+            }
+            b"tfdt" => {
+                // Track fragment decode time
+                parse_tfdt(cursor, &child_header, &mut traf)?;
+                if info.track_id == Some(traf.track_id) {
+                    fragment.base_media_decode_time = traf.next_decode_time;
+                }
+            }
+            b"trun" => {
+                // Track fragment run - the samples
+                parse_trun(cursor, &child_header, info, &mut traf)?;
+            }
+            _ => {}
+        }
+
+        cursor.seek(SeekFrom::Start(child_end))?;
+    }
+
+    Ok(traf.next_data_offset)
+}
+
+/// Parse tfhd (Track Fragment Header) box
+///
+/// Defaults absent from the tfhd come from the track's trex.
+fn parse_tfhd(
+    cursor: &mut Cursor<&[u8]>,
+    _header: &BoxHeader,
+    info: &mut Mp4Info,
+    traf: &mut TrackFragment,
+    moof_start: u64,
+) -> Result<(), BitvueError> {
+    let flags = read_u32(cursor)? & 0x00FF_FFFF; // version + flags
+    traf.track_id = read_u32(cursor)?;
+
+    // A media segment without its init segment: take its first track
+    if info.track_id.is_none() {
+        info.track_id = Some(traf.track_id);
+    }
+
+    if let Some(trex) = info
+        .track_extends
+        .iter()
+        .find(|t| t.track_id == traf.track_id)
+    {
+        traf.default_sample_duration = trex.default_sample_duration;
+        traf.default_sample_size = trex.default_sample_size;
+        traf.default_sample_flags = trex.default_sample_flags;
+    }
+
+    if flags & TFHD_BASE_DATA_OFFSET_PRESENT != 0 {
+        traf.base_data_offset = read_u64(cursor)?;
+    } else if flags & TFHD_DEFAULT_BASE_IS_MOOF != 0 {
+        traf.base_data_offset = moof_start;
+    }
+    traf.next_data_offset = traf.base_data_offset;
+
+    if flags & TFHD_SAMPLE_DESCRIPTION_INDEX_PRESENT != 0 {
+        let _sample_description_index = read_u32(cursor)?;
+    }
+    if flags & TFHD_DEFAULT_SAMPLE_DURATION_PRESENT != 0 {
+        traf.default_sample_duration = read_u32(cursor)?;
+    }
+    if flags & TFHD_DEFAULT_SAMPLE_SIZE_PRESENT != 0 {
+        traf.default_sample_size = read_u32(cursor)?;
+    }
+    if flags & TFHD_DEFAULT_SAMPLE_FLAGS_PRESENT != 0 {
+        traf.default_sample_flags = read_u32(cursor)?;
+    }
+
+    Ok(())
+}
+
+/// Parse tfdt (Track Fragment Decode Time) box
+fn parse_tfdt(
+    cursor: &mut Cursor<&[u8]>,
+    _header: &BoxHeader,
+    traf: &mut TrackFragment,
+) -> Result<(), BitvueError> {
+    let version = read_u8(cursor)?;
+    cursor.seek(SeekFrom::Current(3))?; // flags
+
+    let base_media_decode_time = if version == 1 {
+        read_u64(cursor)?
+    } else {
+        read_u32(cursor)? as u64
+    };
+    traf.next_decode_time = Some(base_media_decode_time);
+
+    Ok(())
+}
+
+/// Parse trun (Track Fragment Run) box
+///
+/// Samples of the selected track are appended to the sample tables; runs of
+/// other tracks only advance the data offset.
+fn parse_trun(
+    cursor: &mut Cursor<&[u8]>,
+    _header: &BoxHeader,
+    info: &mut Mp4Info,
+    traf: &mut TrackFragment,
+) -> Result<(), BitvueError> {
+    let flags = read_u32(cursor)? & 0x00FF_FFFF; // version + flags
+
+    let sample_count = read_u32(cursor)?;
+
+    // Track total samples across all fragments to prevent unbounded growth
+    let total_samples = info
+        .sample_sizes
+        .len()
+        .checked_add(sample_count as usize)
+        .ok_or_else(|| {
+            BitvueError::InvalidData("Sample count overflow - malicious MP4 file".to_string())
+        })?;
+    if total_samples > MAX_TOTAL_SAMPLES {
+        return Err(BitvueError::InvalidData(format!(
+            "Total sample count {} exceeds maximum allowed {}",
+            total_samples, MAX_TOTAL_SAMPLES
+        )));
+    }
+
+    let mut offset = if flags & TRUN_DATA_OFFSET_PRESENT != 0 {
+        let data_offset = read_u32(cursor)? as i32;
+        traf.base_data_offset
+            .checked_add_signed(data_offset as i64)
+            .ok_or_else(|| BitvueError::InvalidData("trun data offset out of range".to_string()))?
+    } else {
+        traf.next_data_offset
+    };
+    let first_sample_flags = if flags & TRUN_FIRST_SAMPLE_FLAGS_PRESENT != 0 {
+        Some(read_u32(cursor)?)
+    } else {
+        None
+    };
+
+    let selected = info.track_id == Some(traf.track_id);
+    if selected {
+        // Samples of the moov sample table may have no composition offsets
+        let sample_total = info.timestamps.len();
+        info.composition_offsets.resize(sample_total, 0);
+    }
+
+    // Without a tfdt, decode time continues from the previous sample
+    let mut decode_time = traf.next_decode_time.unwrap_or_else(|| {
+        match (info.timestamps.last(), info.sample_durations.last()) {
+            (Some(&dts), Some(&duration)) => dts + duration as u64,
+            _ => 0,
+        }
+    });
+
+    for i in 0..sample_count {
+        let duration = if flags & TRUN_SAMPLE_DURATION_PRESENT != 0 {
+            read_u32(cursor)?
+        } else {
+            traf.default_sample_duration
+        };
+        let size = if flags & TRUN_SAMPLE_SIZE_PRESENT != 0 {
+            read_u32(cursor)?
+        } else {
+            traf.default_sample_size
+        };
+        let sample_flags = if flags & TRUN_SAMPLE_FLAGS_PRESENT != 0 {
+            Some(read_u32(cursor)?)
+        } else {
+            None
+        };
+        let composition_offset = if flags & TRUN_SAMPLE_COMPOSITION_TIME_OFFSET_PRESENT != 0 {
+            // Version 1: signed; version 0: unsigned (treated as signed)
+            let raw = read_u32(cursor)?;
+            raw as i32
+        } else {
+            0
+        };
+        let sample_flags = if i == 0 {
+            first_sample_flags.or(sample_flags)
+        } else {
+            sample_flags
+        }
+        .unwrap_or(traf.default_sample_flags);
+
+        if selected {
+            if sample_flags & SAMPLE_IS_NON_SYNC_SAMPLE == 0 {
+                info.key_frames.push(info.sample_sizes.len() as u32 + 1);
+            }
+            info.sample_offsets.push(offset);
+            info.sample_sizes.push(size);
+            info.sample_durations.push(duration);
+            info.timestamps.push(decode_time);
+            info.composition_offsets.push(composition_offset);
+        }
+
+        offset = offset.saturating_add(size as u64);
+        decode_time = decode_time.saturating_add(duration as u64);
+    }
+    traf.next_data_offset = offset;
+    traf.next_decode_time = Some(decode_time);
+    info.sample_count = info.sample_sizes.len();
+
+    Ok(())
+}
+
+/// Parse sidx (Segment Index) box
+fn parse_sidx(
+    cursor: &mut Cursor<&[u8]>,
+    header: &BoxHeader,
+    info: &mut Mp4Info,
+) -> Result<(), BitvueError> {
+    let version = read_u8(cursor)?;
+    cursor.seek(SeekFrom::Current(3))?; // flags
+
+    let mut index = SegmentIndex {
+        offset: header.data_offset - header.header_size(),
+        reference_id: read_u32(cursor)?,
+        timescale: read_u32(cursor)?,
+        ..Default::default()
+    };
+    if version == 0 {
+        index.earliest_presentation_time = read_u32(cursor)? as u64;
+        index.first_offset = read_u32(cursor)? as u64;
+    } else {
+        index.earliest_presentation_time = read_u64(cursor)?;
+        index.first_offset = read_u64(cursor)?;
+    }
+
+    // reserved (16) + reference_count (16)
+    let reference_count = read_u32(cursor)? & 0xFFFF;
+    for _ in 0..reference_count {
+        let size = read_u32(cursor)?;
+        let subsegment_duration = read_u32(cursor)?;
+        let sap = read_u32(cursor)?;
+        index.references.push(SegmentReference {
+            reference_type: size >> 31 != 0,
+            referenced_size: size & 0x7FFF_FFFF,
+            subsegment_duration,
+            starts_with_sap: sap >> 31 != 0,
+            sap_type: ((sap >> 28) & 0x07) as u8,
+            sap_delta_time: sap & 0x0FFF_FFFF,
+        });
+    }
+
+    info.segment_indexes.push(index);
+    Ok(())
+}
+
+/// Parse emsg (Event Message) box
+fn parse_emsg(
+    cursor: &mut Cursor<&[u8]>,
+    header: &BoxHeader,
+    info: &mut Mp4Info,
+) -> Result<(), BitvueError> {
+    let box_end = header
+        .data_offset
+        .checked_add(header.data_size())
+        .ok_or_else(|| BitvueError::InvalidData("MP4 box end offset overflow".to_string()))?;
+
+    let version = read_u8(cursor)?;
+    cursor.seek(SeekFrom::Current(3))?; // flags
+
+    let mut message = EventMessage {
+        offset: header.data_offset - header.header_size(),
+        version,
+        ..Default::default()
+    };
+    if version == 0 {
+        message.scheme_id_uri = read_cstring(cursor, box_end)?;
+        message.value = read_cstring(cursor, box_end)?;
+        message.timescale = read_u32(cursor)?;
+        message.presentation_time_delta = Some(read_u32(cursor)?);
+        message.event_duration = read_u32(cursor)?;
+        message.id = read_u32(cursor)?;
+    } else {
+        message.timescale = read_u32(cursor)?;
+        message.presentation_time = Some(read_u64(cursor)?);
+        message.event_duration = read_u32(cursor)?;
+        message.id = read_u32(cursor)?;
+        message.scheme_id_uri = read_cstring(cursor, box_end)?;
+        message.value = read_cstring(cursor, box_end)?;
+    }
+
+    let start = cursor.position() as usize;
+    let end = (box_end as usize).max(start);
+    message.message_data = cursor
+        .get_ref()
+        .get(start..end)
+        .ok_or(BitvueError::UnexpectedEof(box_end))?
+        .to_vec();
+
+    info.event_messages.push(message);
+    Ok(())
+}
+
+/// Read a null-terminated UTF-8 string ending before `end`
+fn read_cstring(cursor: &mut Cursor<&[u8]>, end: u64) -> Result<String, BitvueError> {
+    let mut bytes = Vec::new();
+    loop {
+        if cursor.position() >= end {
+            return Err(BitvueError::UnexpectedEof(cursor.position()));
+        }
+        match read_u8(cursor)? {
+            0 => break,
+            byte => bytes.push(byte),
+        }
+    }
+    Ok(String::from_utf8_lossy(&bytes).to_string())
+}
+
 /// Calculate timestamps from durations
 fn calculate_timestamps(info: &mut Mp4Info) {
     let mut timestamp = 0u64;
@@ -977,31 +1854,92 @@ fn calculate_timestamps(info: &mut Mp4Info) {
     }
 }
 
-/// Calculate presentation timestamps (PTS = DTS + composition_offset)
+/// Calculate presentation timestamps from the composition times and the
+/// edit list
+///
+/// Edits map composition times onto the movie timeline (ISO/IEC 14496-12
+/// 8.6.6): an empty edit delays what follows, a dwell (media_rate 0) shows
+/// the sample at its media_time for the edit's duration, and other edits
+/// present the composition times from their media_time on at their rate. A
+/// zero segment_duration on the last edit lasts to the end of the media, as
+/// fragmented files write it. A sample presented by several edits keeps its
+/// first presentation time. Without an edit list the composition times are
+/// presented as they are, shifted up together if any is negative.
 fn calculate_presentation_timestamps(info: &mut Mp4Info) {
-    // If no composition offsets, PTS = DTS
-    if info.composition_offsets.is_empty() {
-        info.presentation_timestamps = info.timestamps.clone();
+    let composition_times: Vec<i64> = info
+        .timestamps
+        .iter()
+        .enumerate()
+        .map(|(i, &dts)| {
+            let offset = info.composition_offsets.get(i).copied().unwrap_or(0);
+            dts as i64 + offset as i64
+        })
+        .collect();
+
+    if info.edit_list.is_empty() || info.movie_timescale == 0 || info.timescale == 0 {
+        // Signed offsets with nothing to absorb them: start at zero instead
+        let shift = composition_times.iter().min().map_or(0, |&min| min.min(0));
+        info.presentation_timestamps = composition_times
+            .iter()
+            .map(|&time| u64::try_from(time - shift).ok())
+            .collect();
         return;
     }
 
-    // Calculate PTS for each sample
-    for (i, &dts) in info.timestamps.iter().enumerate() {
-        let offset = info.composition_offsets.get(i).copied().unwrap_or(0);
-        // PTS = DTS + composition_offset
-        // Handle potential negative offsets
-        let pts = if offset < 0 {
-            dts.saturating_sub((-offset) as u64)
+    // Samples in composition order, to find the ones each edit covers
+    let mut order: Vec<usize> = (0..composition_times.len()).collect();
+    order.sort_by_key(|&i| (composition_times[i], i));
+    let sorted: Vec<i64> = order.iter().map(|&i| composition_times[i]).collect();
+
+    let to_media = |movie_time: u64| {
+        let (media, movie) = (info.timescale as u128, info.movie_timescale as u128);
+        ((movie_time as u128 * media + movie / 2) / movie) as u64
+    };
+
+    let mut pts = vec![None; composition_times.len()];
+    let mut movie_time = 0u64;
+    let last = info.edit_list.len() - 1;
+    for (k, edit) in info.edit_list.iter().enumerate() {
+        let start = to_media(movie_time);
+        let duration = to_media(edit.segment_duration);
+        movie_time = movie_time.saturating_add(edit.segment_duration);
+        if edit.is_empty() || edit.media_rate < 0 {
+            continue;
+        }
+
+        if edit.media_rate == 0 {
+            // The sample showing at media_time
+            let at = sorted.partition_point(|&time| time <= edit.media_time);
+            if at > 0 {
+                pts[order[at - 1]].get_or_insert(start);
+            }
+            continue;
+        }
+
+        let rate = edit.media_rate as f64 / 65536.0;
+        let first = sorted.partition_point(|&time| time < edit.media_time);
+        let end = if edit.segment_duration == 0 && k == last {
+            sorted.len()
         } else {
-            dts.saturating_add(offset as u64)
+            let media_end = edit
+                .media_time
+                .saturating_add((duration as f64 * rate).round() as i64);
+            sorted.partition_point(|&time| time < media_end)
         };
-        info.presentation_timestamps.push(pts);
+        let covered = first..end.max(first);
+        for (&i, &time) in order[covered.clone()].iter().zip(&sorted[covered]) {
+            let offset = ((time - edit.media_time) as f64 / rate).round() as u64;
+            pts[i].get_or_insert(start + offset);
+        }
     }
+    info.presentation_timestamps = pts;
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_fixtures::mp4::{full_box, mp4_box, words};
+    use bitvue_core::frame_identity::{FrameIndexMap, FrameMetadata, PtsQuality};
 
     #[test]
     fn test_empty_data() {
@@ -1051,4 +1989,360 @@ mod tests {
         // Should fail because no codec info or non-AV1 codec
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_stsd_codec_config() {
+        let config = [1u8, 0x64, 0x00, 0x1f, 0xff, 0xe0, 0x00];
+
+        // avcC box
+        let mut avcc = Vec::new();
+        avcc.extend_from_slice(&(8 + config.len() as u32).to_be_bytes());
+        avcc.extend_from_slice(b"avcC");
+        avcc.extend_from_slice(&config);
+
+        // avc1 visual sample entry
+        let mut entry = Vec::new();
+        entry.extend_from_slice(&(8 + 78 + avcc.len() as u32).to_be_bytes());
+        entry.extend_from_slice(b"avc1");
+        entry.extend_from_slice(&[0u8; 78]);
+        entry.extend_from_slice(&avcc);
+
+        // stsd box
+        let mut data = Vec::new();
+        data.extend_from_slice(&(16 + entry.len() as u32).to_be_bytes());
+        data.extend_from_slice(b"stsd");
+        data.extend_from_slice(&0u32.to_be_bytes()); // version + flags
+        data.extend_from_slice(&1u32.to_be_bytes()); // entry count
+        data.extend_from_slice(&entry);
+
+        let mut cursor = Cursor::new(data.as_slice());
+        let header = BoxHeader::parse(&mut cursor).unwrap();
+        let mut info = Mp4Info::default();
+        parse_stsd(&mut cursor, &header, &mut info, 0).unwrap();
+
+        assert_eq!(info.codec, Some("avc1".to_string()));
+        assert_eq!(info.codec_config.as_deref(), Some(&config[..]));
+    }
+
+    /// Track with an empty sample table and a single sample entry
+    fn trak(track_id: u32, handler_type: &[u8; 4], fourcc: &[u8; 4]) -> Vec<u8> {
+        let mut entry = Vec::new();
+        entry.extend_from_slice(&(8 + 78u32).to_be_bytes());
+        entry.extend_from_slice(fourcc);
+        entry.extend_from_slice(&[0u8; 78]);
+        let stbl = [
+            full_box(b"stsd", 0, 0, &[&1u32.to_be_bytes()[..], &entry].concat()),
+            full_box(b"stts", 0, 0, &words(&[0])),
+            full_box(b"stsz", 0, 0, &words(&[0, 0])),
+            full_box(b"stco", 0, 0, &words(&[0])),
+        ]
+        .concat();
+        let mut hdlr = words(&[0]);
+        hdlr.extend_from_slice(handler_type);
+        hdlr.extend_from_slice(&[0u8; 13]);
+        let mdia = [
+            full_box(b"mdhd", 0, 0, &words(&[0, 0, 90000, 0, 0])),
+            full_box(b"hdlr", 0, 0, &hdlr),
+            mp4_box(b"minf", &mp4_box(b"stbl", &stbl)),
+        ]
+        .concat();
+        let mut tkhd = words(&[0, 0, track_id]);
+        tkhd.resize(80, 0);
+        mp4_box(
+            b"trak",
+            &[full_box(b"tkhd", 0, 3, &tkhd), mp4_box(b"mdia", &mdia)].concat(),
+        )
+    }
+
+    /// CMAF header with the given tracks and trex defaults of 3000 ticks per
+    /// non-sync sample for track 1
+    fn init_segment_with(traks: &[Vec<u8>]) -> Vec<u8> {
+        let trex = full_box(b"trex", 0, 0, &words(&[1, 1, 3000, 0, 0x0001_0000]));
+        let moov = [traks.concat(), mp4_box(b"mvex", &trex)].concat();
+        [
+            mp4_box(b"ftyp", b"cmfc\0\0\0\0iso6cmfc"),
+            mp4_box(b"moov", &moov),
+        ]
+        .concat()
+    }
+
+    /// CMAF header: one avc1 track
+    fn init_segment() -> Vec<u8> {
+        init_segment_with(&[trak(1, b"vide", b"avc1")])
+    }
+
+    /// Media segment with three samples of track 1 (sync first, composition
+    /// offsets 3000/9000/0) after one sample of track 2
+    fn media_segment(index: u32) -> Vec<u8> {
+        let samples: Vec<Vec<u8>> = (0..3)
+            .map(|i| vec![(index * 3 + i) as u8; 5 + i as usize])
+            .collect();
+        let other = vec![0xEE; 4];
+
+        let moof = |data_offset: u32| {
+            let other_traf = [
+                full_box(b"tfhd", 0, 0x02_0000, &words(&[2])),
+                full_box(b"trun", 0, 0x201, &words(&[1, data_offset, 4])),
+            ]
+            .concat();
+            let mut trun = words(&[3, data_offset + 4, 0x0200_0000]);
+            for (sample, cts) in samples.iter().zip([3000i32, 9000, 0]) {
+                trun.extend(words(&[sample.len() as u32, cts as u32]));
+            }
+            let traf = [
+                full_box(b"tfhd", 0, 0x02_0000, &words(&[1])),
+                full_box(b"tfdt", 1, 0, &(index as u64 * 9000).to_be_bytes()),
+                full_box(b"trun", 1, 0xA05, &trun),
+            ]
+            .concat();
+            [
+                full_box(b"mfhd", 0, 0, &words(&[index + 1])),
+                mp4_box(b"traf", &other_traf),
+                mp4_box(b"traf", &traf),
+            ]
+            .concat()
+        };
+        let moof_size = mp4_box(b"moof", &moof(0)).len() as u32;
+        let mdat = [other, samples.concat()].concat();
+
+        let sidx = full_box(
+            b"sidx",
+            0,
+            0,
+            &words(&[
+                1,
+                90000,
+                index * 9000,
+                0,
+                1,
+                moof_size + 8 + mdat.len() as u32,
+                9000,
+                0x9000_0000,
+            ]),
+        );
+        let mut segment = [mp4_box(b"styp", b"msdh\0\0\0\0msdhmsix"), sidx].concat();
+        if index == 0 {
+            let mut emsg = [&b"urn:test\0"[..], b"1\0"].concat();
+            emsg.extend(words(&[90000, 0, 0, 7]));
+            emsg.extend_from_slice(b"hi");
+            segment.extend(full_box(b"emsg", 0, 0, &emsg));
+        }
+        segment.extend(mp4_box(b"moof", &moof(moof_size + 8)));
+        segment.extend(mp4_box(b"mdat", &mdat));
+        segment
+    }
+
+    #[test]
+    fn test_fragmented_samples() {
+        let data = join_segments(&init_segment(), &[media_segment(0), media_segment(1)]);
+        let info = parse_mp4(&data).unwrap();
+
+        assert_eq!(info.codec, Some("avc1".to_string()));
+        assert_eq!(info.track_id, Some(1));
+        assert_eq!(info.track_extends[0].default_sample_duration, 3000);
+        assert_eq!(info.sample_count, 6);
+        assert_eq!(info.sample_sizes, vec![5, 6, 7, 5, 6, 7]);
+        assert_eq!(info.sample_durations, vec![3000; 6]);
+        assert_eq!(info.timestamps, vec![0, 3000, 6000, 9000, 12000, 15000]);
+        assert_eq!(
+            info.presentation_timestamps,
+            [3000, 12000, 6000, 12000, 21000, 15000].map(Some)
+        );
+        assert_eq!(info.key_frames, vec![1, 4]);
+
+        assert_eq!(info.fragments.len(), 2);
+        assert_eq!(info.fragments[1].sequence_number, 2);
+        assert_eq!(info.fragments[1].base_media_decode_time, Some(9000));
+        assert_eq!(info.fragments[1].first_sample, 3);
+
+        assert_eq!(info.segment_indexes.len(), 2);
+        let reference = &info.segment_indexes[1].references[0];
+        assert!(reference.starts_with_sap);
+        assert_eq!(reference.sap_type, 1);
+        assert_eq!(info.segment_indexes[1].earliest_presentation_time, 9000);
+
+        assert_eq!(info.event_messages.len(), 1);
+        let message = &info.event_messages[0];
+        assert_eq!(message.scheme_id_uri, "urn:test");
+        assert_eq!(message.value, "1");
+        assert_eq!(message.id, 7);
+        assert_eq!(message.message_data, b"hi");
+
+        let samples = extract_avc_samples(&data).unwrap();
+        assert_eq!(samples.len(), 6);
+        for (i, sample) in samples.iter().enumerate() {
+            assert_eq!(sample.len(), 5 + i % 3);
+            assert!(sample.iter().all(|&b| b == i as u8));
+        }
+    }
+
+    #[test]
+    fn test_media_segment_without_init() {
+        let info = parse_mp4(&media_segment(0)).unwrap();
+
+        // The first track fragment is taken; no trex defaults apply
+        assert_eq!(info.track_id, Some(2));
+        assert_eq!(info.sample_sizes, vec![4]);
+        assert_eq!(info.sample_durations, vec![0]);
+        assert_eq!(info.codec, None);
+    }
+
+    #[test]
+    fn test_video_track_selected_by_handler() {
+        // The audio track follows the video one in the moov
+        let init = init_segment_with(&[trak(1, b"vide", b"avc1"), trak(2, b"soun", b"mp4a")]);
+        let data = join_segments(&init, &[media_segment(0)]);
+        let info = parse_mp4(&data).unwrap();
+
+        assert_eq!(info.codec, Some("avc1".to_string()));
+        assert_eq!(info.timescale, 90000);
+        assert_eq!(info.track_id, Some(1));
+        assert_eq!(info.sample_sizes, vec![5, 6, 7]);
+        assert_eq!(info.fragments[0].base_media_decode_time, Some(0));
+    }
+
+    /// Progressive track of four samples I P B B in decode order, 10 ticks
+    /// apart at a media timescale of 100 and a movie timescale of 1000
+    fn edited_movie(ctts: Vec<u8>, edits: &[(u32, i32, u32)]) -> Vec<u8> {
+        edited_movie_with(ctts, edits, &[])
+    }
+
+    /// [`edited_movie`] with more tracks after the video one
+    fn edited_movie_with(ctts: Vec<u8>, edits: &[(u32, i32, u32)], traks: &[Vec<u8>]) -> Vec<u8> {
+        let mut entry = Vec::new();
+        entry.extend_from_slice(&(8 + 78u32).to_be_bytes());
+        entry.extend_from_slice(b"avc1");
+        entry.extend_from_slice(&[0u8; 78]);
+        let stbl = [
+            full_box(b"stsd", 0, 0, &[&1u32.to_be_bytes()[..], &entry].concat()),
+            full_box(b"stts", 0, 0, &words(&[1, 4, 10])),
+            ctts,
+            full_box(b"stsz", 0, 0, &words(&[4, 4])),
+            full_box(b"stco", 0, 0, &words(&[1, 0])),
+        ]
+        .concat();
+        let mdia = [
+            full_box(b"mdhd", 0, 0, &words(&[0, 0, 100, 40, 0])),
+            mp4_box(b"minf", &mp4_box(b"stbl", &stbl)),
+        ]
+        .concat();
+        let mut tkhd = words(&[0, 0, 1]);
+        tkhd.resize(80, 0);
+        let mut trak = full_box(b"tkhd", 0, 3, &tkhd);
+        if !edits.is_empty() {
+            let mut elst = words(&[edits.len() as u32]);
+            for &(duration, media_time, rate) in edits {
+                elst.extend(words(&[duration, media_time as u32, rate]));
+            }
+            trak.extend(mp4_box(b"edts", &full_box(b"elst", 0, 0, &elst)));
+        }
+        trak.extend(mp4_box(b"mdia", &mdia));
+        let mut mvhd = words(&[0, 0, 1000, 400]);
+        mvhd.resize(96, 0);
+        let moov = [
+            full_box(b"mvhd", 0, 0, &mvhd),
+            mp4_box(b"trak", &trak),
+            traks.concat(),
+        ]
+        .concat();
+        [
+            mp4_box(b"ftyp", b"isom\0\0\0\0isom"),
+            mp4_box(b"moov", &moov),
+        ]
+        .concat()
+    }
+
+    fn presentation_quality(info: &Mp4Info) -> PtsQuality {
+        let frames: Vec<FrameMetadata> = info
+            .presentation_timestamps
+            .iter()
+            .zip(&info.timestamps)
+            .map(|(&pts, &dts)| FrameMetadata {
+                pts,
+                dts: Some(dts),
+            })
+            .collect();
+        FrameIndexMap::new(&frames).pts_quality
+    }
+
+    #[test]
+    fn test_edit_list_and_negative_composition_offsets_agree() {
+        // B-frame delay absorbed by the edit list (positive offsets)
+        let delayed = edited_movie(
+            full_box(b"ctts", 0, 0, &words(&[3, 1, 10, 1, 30, 2, 0])),
+            &[(400, 10, 0x0001_0000)],
+        );
+        // Same timing with signed offsets and no edit
+        let signed = edited_movie(
+            full_box(b"ctts", 1, 0, &words(&[3, 1, 0, 1, 20, 2, (-10i32) as u32])),
+            &[],
+        );
+
+        let delayed = parse_mp4(&delayed).unwrap();
+        let signed = parse_mp4(&signed).unwrap();
+        let expected = [0, 30, 10, 20].map(Some).to_vec();
+        assert_eq!(delayed.presentation_timestamps, expected);
+        assert_eq!(signed.presentation_timestamps, expected);
+        assert_eq!(delayed.movie_timescale, 1000);
+        assert_eq!(delayed.edit_list[0].media_time, 10);
+        assert_eq!(presentation_quality(&delayed), PtsQuality::Ok);
+
+        // An audio track's edit list does not replace the video one's
+        let audio = trak(2, b"soun", b"mp4a");
+        let elst = full_box(b"elst", 0, 0, &words(&[1, 400, 0, 0x0001_0000]));
+        let audio = mp4_box(b"trak", &[&audio[8..], &mp4_box(b"edts", &elst)].concat());
+        let data = edited_movie_with(
+            full_box(b"ctts", 0, 0, &words(&[3, 1, 10, 1, 30, 2, 0])),
+            &[(400, 10, 0x0001_0000)],
+            &[audio],
+        );
+        let info = parse_mp4(&data).unwrap();
+        assert_eq!(info.edit_list.len(), 1);
+        assert_eq!(info.edit_list[0].media_time, 10);
+        assert_eq!(info.presentation_timestamps, expected);
+    }
+
+    #[test]
+    fn test_negative_composition_time_without_edit_list() {
+        // The I-frame is composed at -10; every sample is still presented
+        let data = edited_movie(
+            full_box(
+                b"ctts",
+                1,
+                0,
+                &words(&[3, 1, (-10i32) as u32, 1, 20, 2, (-10i32) as u32]),
+            ),
+            &[],
+        );
+        let info = parse_mp4(&data).unwrap();
+        assert_eq!(
+            info.presentation_timestamps,
+            [0, 40, 20, 30].map(Some).to_vec()
+        );
+        assert_eq!(presentation_quality(&info), PtsQuality::Ok);
+    }
+
+    #[test]
+    fn test_edit_list_empty_edit_and_dwell() {
+        let ctts = || full_box(b"ctts", 0, 0, &words(&[3, 1, 10, 1, 30, 2, 0]));
+
+        // An empty edit delays the presentation by 100 ms; only the two
+        // B-frames (composition times 20 and 30) fall inside the edit
+        let data = edited_movie(ctts(), &[(100, -1, 0x0001_0000), (200, 20, 0x0001_0000)]);
+        let info = parse_mp4(&data).unwrap();
+        assert!(info.edit_list[0].is_empty());
+        assert_eq!(
+            info.presentation_timestamps,
+            vec![None, None, Some(10), Some(20)]
+        );
+        assert_eq!(presentation_quality(&info), PtsQuality::Warn);
+
+        // A dwell holds the sample composed at or before its media time
+        let data = edited_movie(ctts(), &[(100, 25, 0), (100, 30, 0x0001_0000)]);
+        let info = parse_mp4(&data).unwrap();
+        assert_eq!(
+            info.presentation_timestamps,
+            vec![None, None, Some(0), Some(10)]
+        );
+    }
 }
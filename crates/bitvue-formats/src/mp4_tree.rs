@@ -0,0 +1,2011 @@
+//! MP4 box tree inspector
+//!
+//! [`parse_box_tree`] walks every box of an ISO BMFF file, not only the ones
+//! [`parse_mp4`](crate::mp4::parse_mp4) needs for sample extraction, and
+//! decodes the fields of the common ones: file, movie, track and media
+//! headers, sample entries with their decoder configuration records (av1C,
+//! avcC, hvcC, vvcC, vpcC) and colour boxes (colr, pasp, mdcv, clli), edit
+//! lists, the sample tables, sample groups and movie fragments. Every box
+//! and field keeps its position in the file, and
+//! [`Mp4BoxTree::to_syntax_model`] exposes the tree through the same
+//! [`SyntaxModel`] the codec syntax parsers produce.
+//!
+//! Parsing is tolerant, since broken files are the ones worth inspecting: a
+//! box overrunning its parent or a truncated payload is recorded as a
+//! [`BoxIssue`] and the walk carries on. Edit lists reaching past the media,
+//! sample tables disagreeing on the sample count and malformed decoder
+//! configuration records are reported the same way.
+//!
+//! References:
+//! - ISO/IEC 14496-12 (ISO Base Media File Format)
+//! - ISO/IEC 14496-15 (NAL unit structured video in ISO BMFF)
+//! - AV1 Codec ISO Media File Format Binding
+//! - VP Codec ISO Media File Format Binding
+
+use crate::mp4::MAX_BOX_DEPTH;
+use bitvue_core::diagnostics::{Diagnostic, DiagnosticCategory, DiagnosticSeverity};
+use bitvue_core::types::{BitRange, SyntaxModel, SyntaxNode, SyntaxNodeId};
+use bitvue_core::StreamId;
+
+/// Table entries listed field by field; the rest of a table is summarized
+/// in a single field
+const MAX_LISTED_ENTRIES: u64 = 1024;
+
+/// Boxes that only hold other boxes
+const CONTAINER_BOXES: [&[u8; 4]; 14] = [
+    b"moov", b"trak", b"edts", b"mdia", b"minf", b"dinf", b"stbl", b"mvex", b"moof", b"traf",
+    b"mfra", b"udta", b"sinf", b"schi",
+];
+
+/// Boxes starting with a version and flags
+const FULL_BOXES: [&[u8; 4]; 33] = [
+    b"mvhd", b"tkhd", b"mdhd", b"hdlr", b"vmhd", b"smhd", b"nmhd", b"elst", b"dref", b"url ",
+    b"urn ", b"stsd", b"stts", b"ctts", b"cslg", b"stss", b"stsz", b"stsc", b"stco", b"co64",
+    b"sgpd", b"sbgp", b"mehd", b"trex", b"mfhd", b"tfhd", b"tfdt", b"trun", b"sidx", b"emsg",
+    b"vpcC", b"vvcC", b"sdtp",
+];
+
+/// Visual sample entry types
+const VISUAL_SAMPLE_ENTRIES: [&[u8; 4]; 20] = [
+    b"avc1", b"avc2", b"avc3", b"avc4", b"hvc1", b"hev1", b"hvc2", b"hev2", b"vvc1", b"vvi1",
+    b"av01", b"vp08", b"vp09", b"mp4v", b"encv", b"dvh1", b"dvhe", b"dva1", b"dvav", b"mjp2",
+];
+
+/// Audio sample entry types
+const AUDIO_SAMPLE_ENTRIES: [&[u8; 4]; 10] = [
+    b"mp4a", b"Opus", b"fLaC", b"ac-3", b"ec-3", b"ac-4", b"enca", b"alac", b"ipcm", b"fpcm",
+];
+
+/// tkhd flags
+const TKHD_FLAGS: [(u32, &str); 3] = [
+    (0x01, "track_enabled"),
+    (0x02, "track_in_movie"),
+    (0x04, "track_in_preview"),
+];
+
+/// tfhd flags
+const TFHD_BASE_DATA_OFFSET_PRESENT: u32 = 0x00_0001;
+const TFHD_SAMPLE_DESCRIPTION_INDEX_PRESENT: u32 = 0x00_0002;
+const TFHD_DEFAULT_SAMPLE_DURATION_PRESENT: u32 = 0x00_0008;
+const TFHD_DEFAULT_SAMPLE_SIZE_PRESENT: u32 = 0x00_0010;
+const TFHD_DEFAULT_SAMPLE_FLAGS_PRESENT: u32 = 0x00_0020;
+
+/// trun flags
+const TRUN_DATA_OFFSET_PRESENT: u32 = 0x00_0001;
+const TRUN_FIRST_SAMPLE_FLAGS_PRESENT: u32 = 0x00_0004;
+const TRUN_SAMPLE_DURATION_PRESENT: u32 = 0x00_0100;
+const TRUN_SAMPLE_SIZE_PRESENT: u32 = 0x00_0200;
+const TRUN_SAMPLE_FLAGS_PRESENT: u32 = 0x00_0400;
+const TRUN_SAMPLE_COMPOSITION_TIME_OFFSET_PRESENT: u32 = 0x00_0800;
+
+/// VVC NAL unit types whose arrays carry a single NAL unit without a count
+const VVC_OPI_NUT: u64 = 12;
+const VVC_DCI_NUT: u64 = 13;
+
+/// A decoded field of a box, or a group of fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoxField {
+    pub name: String,
+    /// Decoded value; `None` for groups
+    pub value: Option<String>,
+    /// Position in the file (absolute bits)
+    pub bit_range: BitRange,
+    /// Fields of a group, e.g. one table entry or one parameter set
+    pub children: Vec<BoxField>,
+}
+
+impl BoxField {
+    /// Child field by name
+    pub fn field(&self, name: &str) -> Option<&BoxField> {
+        self.children.iter().find(|f| f.name == name)
+    }
+}
+
+/// A box of the tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mp4Box {
+    /// Box type (four characters, bytes taken as Latin-1)
+    pub box_type: String,
+    /// File offset of the box header
+    pub offset: u64,
+    /// Box size including the header, clamped to the enclosing box
+    pub size: u64,
+    /// Header size (8, 16 with a large size, plus 16 for `uuid` boxes)
+    pub header_size: u64,
+    /// Version of a full box
+    pub version: Option<u8>,
+    /// Flags of a full box
+    pub flags: Option<u32>,
+    /// Header and payload fields, in file order
+    pub fields: Vec<BoxField>,
+    /// Child boxes
+    pub children: Vec<Mp4Box>,
+}
+
+impl Mp4Box {
+    /// File offset just past the box
+    pub fn end(&self) -> u64 {
+        self.offset + self.size
+    }
+
+    /// Byte range of the box in the file
+    pub fn byte_range(&self) -> std::ops::Range<u64> {
+        self.offset..self.end()
+    }
+
+    /// Top-level field by name
+    pub fn field(&self, name: &str) -> Option<&BoxField> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /// First descendant at a `/`-separated path of box types, e.g.
+    /// `"trak/mdia/mdhd"`
+    pub fn find(&self, path: &str) -> Option<&Mp4Box> {
+        find_box(&self.children, path)
+    }
+}
+
+/// A problem found while walking the boxes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoxIssue {
+    /// File offset of the box concerned
+    pub offset: u64,
+    /// Path of box types from the top level, e.g. `"moov/trak/edts/elst"`
+    pub path: String,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+}
+
+/// Box tree of an MP4 file
+#[derive(Debug, Clone, Default)]
+pub struct Mp4BoxTree {
+    /// Top-level boxes, in file order
+    pub boxes: Vec<Mp4Box>,
+    pub issues: Vec<BoxIssue>,
+}
+
+impl Mp4BoxTree {
+    /// First box at a `/`-separated path of box types from the top level,
+    /// e.g. `"moov/trak/mdia/mdhd"`
+    pub fn find(&self, path: &str) -> Option<&Mp4Box> {
+        find_box(&self.boxes, path)
+    }
+
+    /// Syntax model of the whole tree, rooted at a node spanning the file
+    ///
+    /// Boxes are container nodes named after their type, with the header
+    /// and payload fields as children. Node ids are paths such as
+    /// `mp4/moov[0]/trak[1]/tkhd[0].track_ID`.
+    pub fn to_syntax_model(&self) -> SyntaxModel {
+        let root_id: SyntaxNodeId = "mp4".to_string();
+        let end = self.boxes.iter().map(Mp4Box::end).max().unwrap_or(0);
+        let mut model = SyntaxModel::new(root_id.clone(), "mp4".to_string());
+        model.add_node(SyntaxNode::new(
+            root_id.clone(),
+            BitRange::new(0, end * 8),
+            "mp4".to_string(),
+            None,
+            None,
+            0,
+        ));
+        add_box_nodes(&mut model, &root_id, &self.boxes, 1);
+        model
+    }
+
+    /// Issues as container diagnostics, with the box path as detail
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.issues
+            .iter()
+            .map(|issue| {
+                Diagnostic::new(
+                    0,
+                    issue.severity,
+                    StreamId::A,
+                    format!("{}: {}", issue.path, issue.message),
+                    DiagnosticCategory::Container,
+                    issue.offset,
+                )
+                .with_detail("box".to_string(), issue.path.clone())
+            })
+            .collect()
+    }
+}
+
+fn find_box<'a>(boxes: &'a [Mp4Box], path: &str) -> Option<&'a Mp4Box> {
+    let (head, rest) = match path.split_once('/') {
+        Some((head, rest)) => (head, Some(rest)),
+        None => (path, None),
+    };
+    boxes
+        .iter()
+        .filter(|b| b.box_type == head)
+        .find_map(|b| match rest {
+            Some(rest) => find_box(&b.children, rest),
+            None => Some(b),
+        })
+}
+
+fn add_box_nodes(model: &mut SyntaxModel, parent: &SyntaxNodeId, boxes: &[Mp4Box], depth: usize) {
+    for (i, mp4_box) in boxes.iter().enumerate() {
+        let index = boxes[..i]
+            .iter()
+            .filter(|b| b.box_type == mp4_box.box_type)
+            .count();
+        let node_id = format!("{}/{}[{}]", parent, mp4_box.box_type, index);
+        model.add_node(SyntaxNode::new(
+            node_id.clone(),
+            BitRange::new(mp4_box.offset * 8, mp4_box.end() * 8),
+            mp4_box.box_type.clone(),
+            Some(format!("{} bytes", mp4_box.size)),
+            Some(parent.clone()),
+            depth,
+        ));
+        add_field_nodes(model, &node_id, &mp4_box.fields, depth + 1);
+        add_box_nodes(model, &node_id, &mp4_box.children, depth + 1);
+    }
+}
+
+fn add_field_nodes(
+    model: &mut SyntaxModel,
+    parent: &SyntaxNodeId,
+    fields: &[BoxField],
+    depth: usize,
+) {
+    for field in fields {
+        // Reserved fields repeat within a box
+        let mut node_id = format!("{}.{}", parent, field.name);
+        let mut n = 1;
+        while model.nodes.contains_key(&node_id) {
+            n += 1;
+            node_id = format!("{}.{}~{}", parent, field.name, n);
+        }
+        model.add_node(SyntaxNode::new(
+            node_id.clone(),
+            field.bit_range,
+            field.name.clone(),
+            field.value.clone(),
+            Some(parent.clone()),
+            depth,
+        ));
+        add_field_nodes(model, &node_id, &field.children, depth + 1);
+    }
+}
+
+/// Walk the box tree of an MP4 file
+///
+/// Never fails: what cannot be parsed is reported in
+/// [`Mp4BoxTree::issues`].
+pub fn parse_box_tree(data: &[u8]) -> Mp4BoxTree {
+    let mut walker = Walker {
+        data,
+        issues: Vec::new(),
+        movie_timescale: None,
+        track: TrackState::default(),
+        tracks: Vec::new(),
+        tables: SampleTables::default(),
+    };
+    let boxes = walker.boxes(0, data.len() as u64, 0, "", false);
+    Mp4BoxTree {
+        boxes,
+        issues: walker.issues,
+    }
+}
+
+// ============================================================================
+// Walker
+// ============================================================================
+
+/// An edit list entry
+#[derive(Debug, Clone, Copy)]
+struct Edit {
+    segment_duration: u64,
+    media_time: i64,
+    media_rate_integer: i64,
+}
+
+/// Timing of the track being walked, for the edit list checks
+#[derive(Debug, Default)]
+struct TrackState {
+    /// mdhd timescale and duration
+    media: Option<(u32, u64)>,
+    /// Largest ctts offset; presentation may run past the media duration
+    /// by as much
+    max_composition_offset: u64,
+    /// elst entries, with the elst offset and path
+    edits: Option<(u64, String, Vec<Edit>)>,
+}
+
+/// Sample counts of the sample table being walked
+#[derive(Debug, Default)]
+struct SampleTables {
+    stts: Option<u64>,
+    ctts: Option<u64>,
+    stsz: Option<u64>,
+    max_sync_sample: Option<u64>,
+}
+
+struct Walker<'a> {
+    data: &'a [u8],
+    issues: Vec<BoxIssue>,
+    movie_timescale: Option<u32>,
+    track: TrackState,
+    /// Walked tracks of the movie, checked once the whole movie is known
+    tracks: Vec<TrackState>,
+    tables: SampleTables,
+}
+
+impl Walker<'_> {
+    fn issue(
+        &mut self,
+        offset: u64,
+        path: &str,
+        severity: DiagnosticSeverity,
+        message: impl Into<String>,
+    ) {
+        self.issues.push(BoxIssue {
+            offset,
+            path: path.to_string(),
+            severity,
+            message: message.into(),
+        });
+    }
+
+    /// Boxes between two file offsets
+    fn boxes(
+        &mut self,
+        start: u64,
+        end: u64,
+        depth: u8,
+        parent: &str,
+        sample_entries: bool,
+    ) -> Vec<Mp4Box> {
+        let mut boxes = Vec::new();
+        if depth >= MAX_BOX_DEPTH {
+            self.issue(
+                start,
+                parent,
+                DiagnosticSeverity::Error,
+                format!("box nesting exceeds {} levels", MAX_BOX_DEPTH),
+            );
+            return boxes;
+        }
+
+        let mut pos = start;
+        while pos < end {
+            match self.parse_box(pos, end, depth, parent, sample_entries) {
+                Some(mp4_box) => {
+                    pos = mp4_box.end();
+                    boxes.push(mp4_box);
+                }
+                None => break,
+            }
+        }
+        boxes
+    }
+
+    fn parse_box(
+        &mut self,
+        pos: u64,
+        end: u64,
+        depth: u8,
+        parent: &str,
+        sample_entry: bool,
+    ) -> Option<Mp4Box> {
+        let available = end - pos;
+        let mut r = FieldReader::new(self.data, pos, end);
+        let header = r.box_header(available);
+        let Some((box_type, declared_size)) = header else {
+            self.issue(
+                pos,
+                parent,
+                DiagnosticSeverity::Error,
+                format!("{} trailing bytes do not hold a box header", available),
+            );
+            return None;
+        };
+
+        let type_name: String = box_type.iter().map(|&b| b as char).collect();
+        let path = if parent.is_empty() {
+            type_name.clone()
+        } else {
+            format!("{}/{}", parent, type_name)
+        };
+        let header_size = r.byte_pos() - pos;
+        if declared_size < header_size {
+            self.issue(
+                pos,
+                &path,
+                DiagnosticSeverity::Error,
+                format!("box size {} is smaller than its header", declared_size),
+            );
+            return None;
+        }
+        let size = if declared_size > available {
+            self.issue(
+                pos,
+                &path,
+                DiagnosticSeverity::Error,
+                format!(
+                    "box size {} overruns the {} by {} bytes",
+                    declared_size,
+                    if parent.is_empty() {
+                        "file"
+                    } else {
+                        "parent box"
+                    },
+                    declared_size - available
+                ),
+            );
+            available
+        } else {
+            declared_size
+        };
+        r.end = (pos + size) * 8;
+
+        let full_box = FULL_BOXES.contains(&&box_type) || self.is_full_meta(&box_type, pos, size);
+        let decoded = (|| {
+            if full_box {
+                r.version = r.uint("version", 8)? as u8;
+                r.flags = r.hex("flags", 24)? as u32;
+            }
+            self.decode(&box_type, &mut r, &path, sample_entry)
+        })();
+        if decoded.is_none() {
+            self.issue(
+                pos,
+                &path,
+                DiagnosticSeverity::Error,
+                format!("{} payload is truncated", type_name),
+            );
+        }
+        let children_start = r.byte_pos();
+        let version = full_box.then_some(r.version);
+        let flags = full_box.then_some(r.flags);
+        let (fields, notes) = r.finish();
+        for (severity, message) in notes {
+            self.issue(pos, &path, severity, message);
+        }
+
+        let mut mp4_box = Mp4Box {
+            box_type: type_name,
+            offset: pos,
+            size,
+            header_size,
+            version,
+            flags,
+            fields,
+            children: Vec::new(),
+        };
+
+        if decoded.is_some() {
+            if let Some(entries) = self.children_kind(&box_type, sample_entry) {
+                match &box_type {
+                    b"trak" => self.track = TrackState::default(),
+                    b"stbl" => self.tables = SampleTables::default(),
+                    _ => {}
+                }
+                mp4_box.children =
+                    self.boxes(children_start, pos + size, depth + 1, &path, entries);
+                match &box_type {
+                    b"trak" => {
+                        let track = std::mem::take(&mut self.track);
+                        self.tracks.push(track);
+                    }
+                    b"moov" => {
+                        let fragmented = mp4_box.children.iter().any(|b| b.box_type == "mvex");
+                        for track in std::mem::take(&mut self.tracks) {
+                            self.check_edits(track, fragmented);
+                        }
+                    }
+                    b"stbl" => self.check_sample_tables(pos, &path),
+                    _ if sample_entry => self.check_sample_entry(&mp4_box, &path),
+                    _ => {}
+                }
+            }
+        }
+
+        Some(mp4_box)
+    }
+
+    /// Whether a `meta` box is the ISO full box rather than the QuickTime
+    /// plain one, which starts directly with its `hdlr` child
+    fn is_full_meta(&self, box_type: &[u8; 4], pos: u64, size: u64) -> bool {
+        box_type == b"meta"
+            && size >= 16
+            && self.data.get(pos as usize + 12..pos as usize + 16) != Some(&b"hdlr"[..])
+    }
+
+    /// Whether the payload fields are followed by child boxes, and whether
+    /// those are sample entries
+    fn children_kind(&self, box_type: &[u8; 4], sample_entry: bool) -> Option<bool> {
+        if sample_entry {
+            let with_children = VISUAL_SAMPLE_ENTRIES.contains(&box_type)
+                || AUDIO_SAMPLE_ENTRIES.contains(&box_type);
+            return with_children.then_some(false);
+        }
+        match box_type {
+            b"stsd" => Some(true),
+            b"dref" | b"meta" => Some(false),
+            _ => CONTAINER_BOXES.contains(&box_type).then_some(false),
+        }
+    }
+
+    fn decode(
+        &mut self,
+        box_type: &[u8; 4],
+        r: &mut FieldReader,
+        path: &str,
+        sample_entry: bool,
+    ) -> Option<()> {
+        if sample_entry {
+            return if VISUAL_SAMPLE_ENTRIES.contains(&box_type) {
+                visual_sample_entry(r)
+            } else if AUDIO_SAMPLE_ENTRIES.contains(&box_type) {
+                audio_sample_entry(r)
+            } else {
+                r.uint("reserved", 48)?;
+                r.uint("data_reference_index", 16)?;
+                r.rest("data")
+            };
+        }
+
+        match box_type {
+            b"ftyp" | b"styp" => ftyp(r),
+            b"mvhd" => {
+                let timescale = mvhd(r)?;
+                self.movie_timescale = Some(timescale);
+                Some(())
+            }
+            b"tkhd" => tkhd(r),
+            b"mdhd" => {
+                self.track.media = Some(mdhd(r)?);
+                Some(())
+            }
+            b"hdlr" => hdlr(r),
+            b"vmhd" => vmhd(r),
+            b"smhd" => {
+                r.fixed("balance", 16, 8)?;
+                r.uint("reserved", 16).map(drop)
+            }
+            b"dref" | b"stsd" => r.uint("entry_count", 32).map(drop),
+            b"url " => {
+                if r.flags & 1 == 0 {
+                    r.cstring("location")?;
+                }
+                Some(())
+            }
+            b"elst" => {
+                let edits = elst(r)?;
+                self.track.edits = Some((r.box_offset(), path.to_string(), edits));
+                Some(())
+            }
+            b"stts" => {
+                self.tables.stts = Some(stts(r)?);
+                Some(())
+            }
+            b"ctts" => {
+                let (samples, max_offset) = ctts(r)?;
+                self.tables.ctts = Some(samples);
+                self.track.max_composition_offset = max_offset.max(0) as u64;
+                Some(())
+            }
+            b"cslg" => cslg(r),
+            b"stss" => {
+                self.tables.max_sync_sample = Some(stss(r)?);
+                Some(())
+            }
+            b"stsz" => {
+                self.tables.stsz = Some(stsz(r)?);
+                Some(())
+            }
+            b"stsc" => stsc(r),
+            b"stco" => chunk_offsets(r, 32),
+            b"co64" => chunk_offsets(r, 64),
+            b"sgpd" => sgpd(r),
+            b"sbgp" => sbgp(r),
+            b"avcC" => avcc(r),
+            b"hvcC" => hvcc(r),
+            b"vvcC" => vvcc(r),
+            b"av1C" => av1c(r),
+            b"vpcC" => vpcc(r),
+            b"colr" => colr(r),
+            b"pasp" => pasp(r),
+            b"mdcv" => mdcv(r),
+            b"clli" => {
+                r.uint("max_content_light_level", 16)?;
+                r.uint("max_pic_average_light_level", 16).map(drop)
+            }
+            b"btrt" => {
+                r.uint("bufferSizeDB", 32)?;
+                r.uint("maxBitrate", 32)?;
+                r.uint("avgBitrate", 32).map(drop)
+            }
+            b"mehd" => {
+                let bits = if r.version == 1 { 64 } else { 32 };
+                r.uint("fragment_duration", bits).map(drop)
+            }
+            b"trex" => trex(r),
+            b"mfhd" => r.uint("sequence_number", 32).map(drop),
+            b"tfhd" => tfhd(r),
+            b"tfdt" => {
+                let bits = if r.version == 1 { 64 } else { 32 };
+                r.uint("baseMediaDecodeTime", bits).map(drop)
+            }
+            b"trun" => trun(r),
+            b"sidx" => sidx(r),
+            b"emsg" => emsg(r),
+            _ => Some(()),
+        }
+    }
+
+    /// Edits must stay within the media they reference
+    fn check_edits(&mut self, track: TrackState, fragmented: bool) {
+        let Some((offset, path, edits)) = track.edits else {
+            return;
+        };
+        if !edits.is_empty() && edits.iter().all(|e| e.media_time < 0) {
+            self.issue(
+                offset,
+                &path,
+                DiagnosticSeverity::Warn,
+                "edit list has only empty edits and presents no media",
+            );
+        }
+
+        let (Some(movie_timescale), Some((media_timescale, media_duration))) =
+            (self.movie_timescale, track.media)
+        else {
+            return;
+        };
+        // The samples of fragmented files are not covered by the durations
+        if fragmented || movie_timescale == 0 || media_duration == 0 {
+            return;
+        }
+        for (i, edit) in edits.iter().enumerate() {
+            if edit.media_time < 0 || edit.media_rate_integer != 1 || edit.segment_duration == 0 {
+                continue;
+            }
+            let span =
+                edit.segment_duration as u128 * media_timescale as u128 / movie_timescale as u128;
+            let edit_end = edit.media_time as u128 + span;
+            let media_end = media_duration as u128 + track.max_composition_offset as u128;
+            if edit_end > media_end {
+                self.issue(
+                    offset,
+                    &path,
+                    DiagnosticSeverity::Warn,
+                    format!(
+                        "edit {} ends at media time {} past the end of the media at {}",
+                        i, edit_end, media_end
+                    ),
+                );
+            }
+        }
+    }
+
+    /// The sample tables must describe the same number of samples
+    fn check_sample_tables(&mut self, offset: u64, path: &str) {
+        let tables = std::mem::take(&mut self.tables);
+        let Some(sample_count) = tables.stsz else {
+            return;
+        };
+        for (name, count) in [("stts", tables.stts), ("ctts", tables.ctts)] {
+            if let Some(count) = count.filter(|&count| count != sample_count) {
+                self.issue(
+                    offset,
+                    path,
+                    DiagnosticSeverity::Error,
+                    format!(
+                        "{} covers {} samples but stsz has {}",
+                        name, count, sample_count
+                    ),
+                );
+            }
+        }
+        if let Some(max) = tables.max_sync_sample.filter(|&max| max > sample_count) {
+            self.issue(
+                offset,
+                path,
+                DiagnosticSeverity::Error,
+                format!("stss references sample {} of {} samples", max, sample_count),
+            );
+        }
+    }
+
+    /// Coded sample entries must carry their decoder configuration record
+    fn check_sample_entry(&mut self, entry: &Mp4Box, path: &str) {
+        let config = match entry.box_type.as_str() {
+            "avc1" | "avc2" | "avc3" | "avc4" => "avcC",
+            "hvc1" | "hev1" | "hvc2" | "hev2" => "hvcC",
+            "vvc1" | "vvi1" => "vvcC",
+            "av01" => "av1C",
+            "vp08" | "vp09" => "vpcC",
+            _ => return,
+        };
+        if entry.children.iter().all(|b| b.box_type != config) {
+            self.issue(
+                entry.offset,
+                path,
+                DiagnosticSeverity::Error,
+                format!("{} sample entry has no {} box", entry.box_type, config),
+            );
+        }
+    }
+}
+
+// ============================================================================
+// Field reader
+// ============================================================================
+
+/// Reads the fields of one box and records their positions
+struct FieldReader<'a> {
+    data: &'a [u8],
+    /// Box start (bytes)
+    start: u64,
+    /// Read position (absolute bits)
+    pos: u64,
+    /// End of the box (absolute bits)
+    end: u64,
+    version: u8,
+    flags: u32,
+    /// Whether fields read are recorded; off past the listed table entries
+    recording: bool,
+    fields: Vec<BoxField>,
+    /// Open groups, innermost last
+    groups: Vec<BoxField>,
+    notes: Vec<(DiagnosticSeverity, String)>,
+}
+
+impl<'a> FieldReader<'a> {
+    fn new(data: &'a [u8], start: u64, end: u64) -> Self {
+        Self {
+            data,
+            start,
+            pos: start * 8,
+            end: end * 8,
+            version: 0,
+            flags: 0,
+            recording: true,
+            fields: Vec::new(),
+            groups: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    fn box_offset(&self) -> u64 {
+        self.start
+    }
+
+    fn byte_pos(&self) -> u64 {
+        self.pos.div_ceil(8)
+    }
+
+    fn remaining_bytes(&self) -> u64 {
+        self.end.saturating_sub(self.pos) / 8
+    }
+
+    fn note(&mut self, severity: DiagnosticSeverity, message: impl Into<String>) {
+        self.notes.push((severity, message.into()));
+    }
+
+    /// Box type and declared size; `available` bytes are left in the parent
+    fn box_header(&mut self, available: u64) -> Option<([u8; 4], u64)> {
+        let size32 = self.uint("size", 32)?;
+        let box_type = self.fourcc("type")?;
+        let size = match size32 {
+            0 => available,
+            1 => self.uint("largesize", 64)?,
+            size => size,
+        };
+        if &box_type == b"uuid" {
+            self.bytes("usertype", 16)?;
+        }
+        Some((box_type, size))
+    }
+
+    fn push(&mut self, name: &str, start: u64, value: Option<String>) {
+        if !self.recording {
+            return;
+        }
+        let field = BoxField {
+            name: name.to_string(),
+            value,
+            bit_range: BitRange::new(start, self.pos),
+            children: Vec::new(),
+        };
+        match self.groups.last_mut() {
+            Some(group) => group.children.push(field),
+            None => self.fields.push(field),
+        }
+    }
+
+    /// Open a group of fields
+    fn group(&mut self, name: impl Into<String>) {
+        if self.recording {
+            self.groups.push(BoxField {
+                name: name.into(),
+                value: None,
+                bit_range: BitRange::new(self.pos, self.pos),
+                children: Vec::new(),
+            });
+        }
+    }
+
+    /// Close the innermost group
+    fn end_group(&mut self) {
+        if !self.recording {
+            return;
+        }
+        if let Some(mut group) = self.groups.pop() {
+            group.bit_range.end_bit = self.pos;
+            match self.groups.last_mut() {
+                Some(parent) => parent.children.push(group),
+                None => self.fields.push(group),
+            }
+        }
+    }
+
+    /// Read `n` bits (at most 64) without recording them
+    fn read(&mut self, n: u32) -> Option<u64> {
+        if n > 64 || self.pos + n as u64 > self.end {
+            return None;
+        }
+        let mut value = 0u64;
+        if self.pos.is_multiple_of(8) && n.is_multiple_of(8) {
+            let at = (self.pos / 8) as usize;
+            for &byte in &self.data[at..at + n as usize / 8] {
+                value = value << 8 | byte as u64;
+            }
+            self.pos += n as u64;
+            return Some(value);
+        }
+        for _ in 0..n {
+            let byte = self.data[(self.pos / 8) as usize];
+            let bit = (byte >> (7 - self.pos % 8)) & 1;
+            value = value << 1 | bit as u64;
+            self.pos += 1;
+        }
+        Some(value)
+    }
+
+    /// Read `n` bits and record them with a formatted value
+    fn field_with(
+        &mut self,
+        name: &str,
+        n: u32,
+        format: impl FnOnce(u64) -> String,
+    ) -> Option<u64> {
+        let start = self.pos;
+        let value = self.read(n)?;
+        self.push(name, start, Some(format(value)));
+        Some(value)
+    }
+
+    fn uint(&mut self, name: &str, n: u32) -> Option<u64> {
+        self.field_with(name, n, |v| v.to_string())
+    }
+
+    fn int(&mut self, name: &str, n: u32) -> Option<i64> {
+        let value = self.field_with(name, n, |v| sign_extend(v, n).to_string())?;
+        Some(sign_extend(value, n))
+    }
+
+    fn hex(&mut self, name: &str, n: u32) -> Option<u64> {
+        let digits = n.div_ceil(4) as usize;
+        self.field_with(name, n, |v| format!("0x{:0digits$X}", v))
+    }
+
+    /// Signed fixed-point number with `frac` fractional bits
+    fn fixed(&mut self, name: &str, n: u32, frac: u32) -> Option<f64> {
+        let scale = (1u64 << frac) as f64;
+        let value =
+            self.field_with(name, n, |v| format!("{}", sign_extend(v, n) as f64 / scale))?;
+        Some(sign_extend(value, n) as f64 / scale)
+    }
+
+    /// Unsigned fixed-point number with `frac` fractional bits
+    fn ufixed(&mut self, name: &str, n: u32, frac: u32) -> Option<f64> {
+        let scale = (1u64 << frac) as f64;
+        let value = self.field_with(name, n, |v| format!("{}", v as f64 / scale))?;
+        Some(value as f64 / scale)
+    }
+
+    fn fourcc(&mut self, name: &str) -> Option<[u8; 4]> {
+        let value = self.field_with(name, 32, |v| {
+            v.to_be_bytes()[4..].iter().map(|&b| b as char).collect()
+        })?;
+        Some((value as u32).to_be_bytes())
+    }
+
+    /// Whole bytes, shown as hex
+    fn bytes(&mut self, name: &str, len: u64) -> Option<&'a [u8]> {
+        if !self.pos.is_multiple_of(8) || len > self.remaining_bytes() {
+            return None;
+        }
+        let start = self.pos;
+        let at = (start / 8) as usize;
+        let bytes = &self.data[at..at + len as usize];
+        self.pos += len * 8;
+        self.push(name, start, Some(hex_preview(bytes)));
+        Some(bytes)
+    }
+
+    /// Remaining bytes of the box, if any
+    fn rest(&mut self, name: &str) -> Option<()> {
+        let len = self.remaining_bytes();
+        if len > 0 {
+            self.bytes(name, len)?;
+        }
+        Some(())
+    }
+
+    /// Bits too long for a single value
+    fn skip(&mut self, name: &str, n: u64) -> Option<()> {
+        if self.pos + n > self.end {
+            return None;
+        }
+        let start = self.pos;
+        self.pos += n;
+        self.push(name, start, Some(format!("{} bits", n)));
+        Some(())
+    }
+
+    /// NUL-terminated UTF-8 string; the terminator may be missing at the
+    /// end of the box
+    fn cstring(&mut self, name: &str) -> Option<String> {
+        if !self.pos.is_multiple_of(8) {
+            return None;
+        }
+        let start = self.pos;
+        let at = (start / 8) as usize;
+        let end = (self.end / 8) as usize;
+        let bytes = &self.data[at..end];
+        let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        let value = String::from_utf8_lossy(&bytes[..len]).to_string();
+        self.pos += (len as u64 + u64::from(len < bytes.len())) * 8;
+        self.push(name, start, Some(format!("\"{}\"", value)));
+        Some(value)
+    }
+
+    /// Read a table of `count` entries, listing the first ones as groups
+    fn table(&mut self, count: u64, mut entry: impl FnMut(&mut Self) -> Option<()>) -> Option<()> {
+        let mut hidden_start = None;
+        for i in 0..count {
+            if i == MAX_LISTED_ENTRIES {
+                hidden_start = Some(self.pos);
+                self.recording = false;
+            }
+            self.group(format!("entry[{}]", i));
+            entry(self)?;
+            self.end_group();
+        }
+        if let Some(start) = hidden_start {
+            self.recording = true;
+            self.push(
+                "entries",
+                start,
+                Some(format!("{} more entries", count - MAX_LISTED_ENTRIES)),
+            );
+        }
+        Some(())
+    }
+
+    /// Recorded fields and notes; groups left open by a truncated payload
+    /// are closed where reading stopped
+    fn finish(mut self) -> (Vec<BoxField>, Vec<(DiagnosticSeverity, String)>) {
+        self.recording = true;
+        while !self.groups.is_empty() {
+            self.end_group();
+        }
+        (self.fields, self.notes)
+    }
+}
+
+fn sign_extend(value: u64, bits: u32) -> i64 {
+    if bits == 0 || bits >= 64 {
+        return value as i64;
+    }
+    let shift = 64 - bits;
+    ((value << shift) as i64) >> shift
+}
+
+/// Hex dump of up to 16 bytes, with the total length
+fn hex_preview(bytes: &[u8]) -> String {
+    let shown: Vec<String> = bytes
+        .iter()
+        .take(16)
+        .map(|b| format!("{:02X}", b))
+        .collect();
+    if bytes.len() > 16 {
+        format!("{} … ({} bytes)", shown.join(" "), bytes.len())
+    } else {
+        shown.join(" ")
+    }
+}
+
+// ============================================================================
+// Box decoders
+// ============================================================================
+
+fn ftyp(r: &mut FieldReader) -> Option<()> {
+    r.fourcc("major_brand")?;
+    r.uint("minor_version", 32)?;
+    let mut i = 0;
+    while r.remaining_bytes() >= 4 {
+        r.fourcc(&format!("compatible_brand[{}]", i))?;
+        i += 1;
+    }
+    Some(())
+}
+
+/// Creation and modification times, 64-bit in version 1
+fn times(r: &mut FieldReader) -> Option<u32> {
+    let bits = if r.version == 1 { 64 } else { 32 };
+    r.uint("creation_time", bits)?;
+    r.uint("modification_time", bits)?;
+    Some(bits)
+}
+
+fn matrix(r: &mut FieldReader) -> Option<()> {
+    r.group("matrix");
+    for i in 0..9 {
+        // u and v, w columns are 2.30 numbers, the others 16.16
+        let frac = if i % 3 == 2 { 30 } else { 16 };
+        r.fixed(&format!("m[{}]", i), 32, frac)?;
+    }
+    r.end_group();
+    Some(())
+}
+
+/// Returns the movie timescale
+fn mvhd(r: &mut FieldReader) -> Option<u32> {
+    let bits = times(r)?;
+    let timescale = r.uint("timescale", 32)? as u32;
+    r.uint("duration", bits)?;
+    r.fixed("rate", 32, 16)?;
+    r.fixed("volume", 16, 8)?;
+    r.uint("reserved", 16)?;
+    r.uint("reserved", 64)?;
+    matrix(r)?;
+    r.skip("pre_defined", 192)?;
+    r.uint("next_track_ID", 32)?;
+    Some(timescale)
+}
+
+fn tkhd(r: &mut FieldReader) -> Option<()> {
+    let flags: Vec<&str> = TKHD_FLAGS
+        .iter()
+        .filter(|(flag, _)| r.flags & flag != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    if let Some(field) = r.fields.last_mut() {
+        field.value = Some(format!(
+            "{} ({})",
+            field.value.take().unwrap_or_default(),
+            flags.join(", ")
+        ));
+    }
+
+    let bits = times(r)?;
+    r.uint("track_ID", 32)?;
+    r.uint("reserved", 32)?;
+    r.uint("duration", bits)?;
+    r.uint("reserved", 64)?;
+    r.int("layer", 16)?;
+    r.int("alternate_group", 16)?;
+    r.fixed("volume", 16, 8)?;
+    r.uint("reserved", 16)?;
+    matrix(r)?;
+    r.ufixed("width", 32, 16)?;
+    r.ufixed("height", 32, 16)?;
+    Some(())
+}
+
+/// Returns the media timescale and duration
+fn mdhd(r: &mut FieldReader) -> Option<(u32, u64)> {
+    let bits = times(r)?;
+    let timescale = r.uint("timescale", 32)? as u32;
+    let duration = r.uint("duration", bits)?;
+    r.uint("pad", 1)?;
+    r.field_with("language", 15, |v| {
+        (0..3)
+            .rev()
+            .map(|i| (((v >> (i * 5)) & 0x1F) as u8 + 0x60) as char)
+            .collect()
+    })?;
+    r.uint("pre_defined", 16)?;
+    Some((timescale, duration))
+}
+
+fn hdlr(r: &mut FieldReader) -> Option<()> {
+    r.uint("pre_defined", 32)?;
+    r.fourcc("handler_type")?;
+    r.skip("reserved", 96)?;
+    r.cstring("name").map(drop)
+}
+
+fn vmhd(r: &mut FieldReader) -> Option<()> {
+    r.uint("graphicsmode", 16)?;
+    for name in ["opcolor_red", "opcolor_green", "opcolor_blue"] {
+        r.uint(name, 16)?;
+    }
+    Some(())
+}
+
+fn visual_sample_entry(r: &mut FieldReader) -> Option<()> {
+    r.uint("reserved", 48)?;
+    r.uint("data_reference_index", 16)?;
+    r.uint("pre_defined", 16)?;
+    r.uint("reserved", 16)?;
+    r.skip("pre_defined", 96)?;
+    r.uint("width", 16)?;
+    r.uint("height", 16)?;
+    r.ufixed("horizresolution", 32, 16)?;
+    r.ufixed("vertresolution", 32, 16)?;
+    r.uint("reserved", 32)?;
+    r.uint("frame_count", 16)?;
+
+    // Pascal string padded to 32 bytes
+    if r.remaining_bytes() < 32 {
+        return None;
+    }
+    let start = r.pos;
+    let at = (start / 8) as usize;
+    let bytes = &r.data[at..at + 32];
+    let len = (bytes[0] as usize).min(31);
+    let name = String::from_utf8_lossy(&bytes[1..1 + len]).to_string();
+    r.pos += 32 * 8;
+    r.push("compressorname", start, Some(format!("\"{}\"", name)));
+
+    r.uint("depth", 16)?;
+    r.int("pre_defined", 16).map(drop)
+}
+
+fn audio_sample_entry(r: &mut FieldReader) -> Option<()> {
+    r.uint("reserved", 48)?;
+    r.uint("data_reference_index", 16)?;
+    // Zero in ISO files; the sound description version in QuickTime ones
+    let entry_version = r.uint("entry_version", 16)?;
+    r.uint("reserved", 48)?;
+    r.uint("channelcount", 16)?;
+    r.uint("samplesize", 16)?;
+    r.uint("pre_defined", 16)?;
+    r.uint("reserved", 16)?;
+    r.ufixed("samplerate", 32, 16)?;
+    match entry_version {
+        0 => {}
+        1 => {
+            r.uint("samples_per_packet", 32)?;
+            r.uint("bytes_per_packet", 32)?;
+            r.uint("bytes_per_frame", 32)?;
+            r.uint("bytes_per_sample", 32)?;
+        }
+        _ => {
+            r.note(
+                DiagnosticSeverity::Info,
+                format!("sound description version {} is not decoded", entry_version),
+            );
+            r.rest("data")?;
+        }
+    }
+    Some(())
+}
+
+/// Returns the entries
+fn elst(r: &mut FieldReader) -> Option<Vec<Edit>> {
+    let count = r.uint("entry_count", 32)?;
+    let bits = if r.version == 1 { 64 } else { 32 };
+    let mut edits = Vec::new();
+    r.table(count, |r| {
+        let segment_duration = r.uint("segment_duration", bits)?;
+        let media_time = r.int("media_time", bits)?;
+        let media_rate_integer = r.int("media_rate_integer", 16)?;
+        let media_rate_fraction = r.int("media_rate_fraction", 16)?;
+        let i = edits.len();
+        if media_time < -1 {
+            r.note(
+                DiagnosticSeverity::Error,
+                format!("edit {} has invalid media_time {}", i, media_time),
+            );
+        }
+        if !(0..=1).contains(&media_rate_integer) || media_rate_fraction != 0 {
+            r.note(
+                DiagnosticSeverity::Warn,
+                format!(
+                    "edit {} has media rate {}.{}; only 1 and 0 (dwell) are allowed",
+                    i, media_rate_integer, media_rate_fraction
+                ),
+            );
+        }
+        edits.push(Edit {
+            segment_duration,
+            media_time,
+            media_rate_integer,
+        });
+        Some(())
+    })?;
+    Some(edits)
+}
+
+/// Returns the number of samples covered
+fn stts(r: &mut FieldReader) -> Option<u64> {
+    let count = r.uint("entry_count", 32)?;
+    let mut samples = 0u64;
+    r.table(count, |r| {
+        samples += r.uint("sample_count", 32)?;
+        r.uint("sample_delta", 32).map(drop)
+    })?;
+    Some(samples)
+}
+
+/// Returns the number of samples covered and the largest offset
+fn ctts(r: &mut FieldReader) -> Option<(u64, i64)> {
+    let count = r.uint("entry_count", 32)?;
+    let signed = r.version == 1;
+    let mut samples = 0u64;
+    let mut max_offset = 0;
+    r.table(count, |r| {
+        samples += r.uint("sample_count", 32)?;
+        let offset = if signed {
+            r.int("sample_offset", 32)?
+        } else {
+            r.uint("sample_offset", 32)? as i64
+        };
+        max_offset = offset.max(max_offset);
+        Some(())
+    })?;
+    Some((samples, max_offset))
+}
+
+fn cslg(r: &mut FieldReader) -> Option<()> {
+    let bits = if r.version == 1 { 64 } else { 32 };
+    for name in [
+        "compositionToDTSShift",
+        "leastDecodeToDisplayDelta",
+        "greatestDecodeToDisplayDelta",
+        "compositionStartTime",
+        "compositionEndTime",
+    ] {
+        r.int(name, bits)?;
+    }
+    Some(())
+}
+
+/// Returns the largest sample number listed
+fn stss(r: &mut FieldReader) -> Option<u64> {
+    let count = r.uint("entry_count", 32)?;
+    let mut max = 0;
+    r.table(count, |r| {
+        max = max.max(r.uint("sample_number", 32)?);
+        Some(())
+    })?;
+    Some(max)
+}
+
+/// Returns the sample count
+fn stsz(r: &mut FieldReader) -> Option<u64> {
+    let sample_size = r.uint("sample_size", 32)?;
+    let count = r.uint("sample_count", 32)?;
+    if sample_size == 0 {
+        r.table(count, |r| r.uint("entry_size", 32).map(drop))?;
+    }
+    Some(count)
+}
+
+fn stsc(r: &mut FieldReader) -> Option<()> {
+    let count = r.uint("entry_count", 32)?;
+    r.table(count, |r| {
+        r.uint("first_chunk", 32)?;
+        r.uint("samples_per_chunk", 32)?;
+        r.uint("sample_description_index", 32).map(drop)
+    })
+}
+
+fn chunk_offsets(r: &mut FieldReader, bits: u32) -> Option<()> {
+    let count = r.uint("entry_count", 32)?;
+    r.table(count, |r| r.uint("chunk_offset", bits).map(drop))
+}
+
+fn sgpd(r: &mut FieldReader) -> Option<()> {
+    let grouping_type = r.fourcc("grouping_type")?;
+    let default_length = if r.version == 1 {
+        r.uint("default_length", 32)?
+    } else {
+        0
+    };
+    if r.version >= 2 {
+        r.uint("default_group_description_index", 32)?;
+    }
+    let count = r.uint("entry_count", 32)?;
+    let version = r.version;
+    r.table(count, |r| {
+        let length = match (version, default_length) {
+            (1, 0) => Some(r.uint("description_length", 32)?),
+            (1, length) => Some(length),
+            _ => None,
+        };
+        let start = r.pos;
+        let known = match &grouping_type {
+            b"roll" | b"prol" => r.int("roll_distance", 16).map(drop),
+            b"sync" => {
+                r.uint("reserved", 2)?;
+                r.uint("NAL_unit_type", 6).map(drop)
+            }
+            b"rap " => {
+                r.uint("num_leading_samples_known", 1)?;
+                r.uint("num_leading_samples", 7).map(drop)
+            }
+            b"tele" => {
+                r.uint("level_independently_decodable", 1)?;
+                r.uint("reserved", 7).map(drop)
+            }
+            _ => None,
+        };
+        match (known, length) {
+            // The description may be longer than the fields decoded
+            (Some(()), Some(length)) => {
+                let read = (r.pos - start) / 8;
+                if length > read {
+                    r.bytes("data", length - read)?;
+                }
+                Some(())
+            }
+            (Some(()), None) => Some(()),
+            (None, length) => {
+                r.pos = start;
+                match length {
+                    Some(length) => r.bytes("data", length).map(drop),
+                    // Entry size unknown: the rest of the box
+                    None => r.rest("data"),
+                }
+            }
+        }
+    })
+}
+
+fn sbgp(r: &mut FieldReader) -> Option<()> {
+    r.fourcc("grouping_type")?;
+    if r.version == 1 {
+        r.uint("grouping_type_parameter", 32)?;
+    }
+    let count = r.uint("entry_count", 32)?;
+    r.table(count, |r| {
+        r.uint("sample_count", 32)?;
+        r.uint("group_description_index", 32).map(drop)
+    })
+}
+
+/// Parameter set NAL units, each preceded by a 16-bit length
+fn nal_units(r: &mut FieldReader, prefix: &str, count: u64) -> Option<()> {
+    for i in 0..count {
+        r.group(format!("{}[{}]", prefix, i));
+        let length = r.uint("nalUnitLength", 16)?;
+        r.bytes("nalUnit", length)?;
+        r.end_group();
+    }
+    Some(())
+}
+
+/// lengthSizeMinusOne may not be 2; NAL unit lengths are 1, 2 or 4 bytes
+fn check_length_size(r: &mut FieldReader, length_size_minus_one: u64) {
+    if length_size_minus_one == 2 {
+        r.note(
+            DiagnosticSeverity::Error,
+            "lengthSizeMinusOne is 2; NAL unit lengths must be 1, 2 or 4 bytes",
+        );
+    }
+}
+
+fn check_config_version(r: &mut FieldReader, box_name: &str, version: u64) {
+    if version != 1 {
+        r.note(
+            DiagnosticSeverity::Error,
+            format!(
+                "{} configurationVersion is {}, expected 1",
+                box_name, version
+            ),
+        );
+    }
+}
+
+fn avcc(r: &mut FieldReader) -> Option<()> {
+    let version = r.uint("configurationVersion", 8)?;
+    check_config_version(r, "avcC", version);
+    let profile = r.uint("AVCProfileIndication", 8)?;
+    r.hex("profile_compatibility", 8)?;
+    r.uint("AVCLevelIndication", 8)?;
+    r.uint("reserved", 6)?;
+    let length_size = r.uint("lengthSizeMinusOne", 2)?;
+    check_length_size(r, length_size);
+    r.uint("reserved", 3)?;
+    let sps_count = r.uint("numOfSequenceParameterSets", 5)?;
+    if sps_count == 0 {
+        r.note(DiagnosticSeverity::Warn, "avcC carries no SPS");
+    }
+    nal_units(r, "sequenceParameterSet", sps_count)?;
+    let pps_count = r.uint("numOfPictureParameterSets", 8)?;
+    nal_units(r, "pictureParameterSet", pps_count)?;
+
+    // The extension is often left out by older muxers
+    if matches!(profile, 100 | 110 | 122 | 144) && r.remaining_bytes() >= 4 {
+        r.uint("reserved", 6)?;
+        r.uint("chroma_format", 2)?;
+        r.uint("reserved", 5)?;
+        r.uint("bit_depth_luma_minus8", 3)?;
+        r.uint("reserved", 5)?;
+        r.uint("bit_depth_chroma_minus8", 3)?;
+        let ext_count = r.uint("numOfSequenceParameterSetExt", 8)?;
+        nal_units(r, "sequenceParameterSetExt", ext_count)?;
+    }
+    Some(())
+}
+
+fn hvcc(r: &mut FieldReader) -> Option<()> {
+    let version = r.uint("configurationVersion", 8)?;
+    check_config_version(r, "hvcC", version);
+    r.uint("general_profile_space", 2)?;
+    r.uint("general_tier_flag", 1)?;
+    r.uint("general_profile_idc", 5)?;
+    r.hex("general_profile_compatibility_flags", 32)?;
+    r.hex("general_constraint_indicator_flags", 48)?;
+    r.uint("general_level_idc", 8)?;
+    r.uint("reserved", 4)?;
+    r.uint("min_spatial_segmentation_idc", 12)?;
+    r.uint("reserved", 6)?;
+    r.uint("parallelismType", 2)?;
+    r.uint("reserved", 6)?;
+    r.uint("chromaFormat", 2)?;
+    r.uint("reserved", 5)?;
+    r.uint("bitDepthLumaMinus8", 3)?;
+    r.uint("reserved", 5)?;
+    r.uint("bitDepthChromaMinus8", 3)?;
+    r.uint("avgFrameRate", 16)?;
+    r.uint("constantFrameRate", 2)?;
+    r.uint("numTemporalLayers", 3)?;
+    r.uint("temporalIdNested", 1)?;
+    let length_size = r.uint("lengthSizeMinusOne", 2)?;
+    check_length_size(r, length_size);
+    let arrays = r.uint("numOfArrays", 8)?;
+    for j in 0..arrays {
+        r.group(format!("array[{}]", j));
+        r.uint("array_completeness", 1)?;
+        r.uint("reserved", 1)?;
+        r.uint("NAL_unit_type", 6)?;
+        let count = r.uint("numNalus", 16)?;
+        nal_units(r, "nalu", count)?;
+        r.end_group();
+    }
+    Some(())
+}
+
+fn vvcc(r: &mut FieldReader) -> Option<()> {
+    r.uint("reserved", 5)?;
+    let length_size = r.uint("LengthSizeMinusOne", 2)?;
+    check_length_size(r, length_size);
+    if r.uint("ptl_present_flag", 1)? == 1 {
+        r.uint("ols_idx", 9)?;
+        let num_sublayers = r.uint("num_sublayers", 3)?;
+        r.uint("constant_frame_rate", 2)?;
+        r.uint("chroma_format_idc", 2)?;
+        r.uint("bit_depth_minus8", 3)?;
+        r.uint("reserved", 5)?;
+        vvc_ptl_record(r, num_sublayers)?;
+        r.uint("max_picture_width", 16)?;
+        r.uint("max_picture_height", 16)?;
+        r.uint("avg_frame_rate", 16)?;
+    }
+    let arrays = r.uint("num_of_arrays", 8)?;
+    for j in 0..arrays {
+        r.group(format!("array[{}]", j));
+        r.uint("array_completeness", 1)?;
+        r.uint("reserved", 2)?;
+        let nal_unit_type = r.uint("NAL_unit_type", 5)?;
+        let count = if nal_unit_type == VVC_DCI_NUT || nal_unit_type == VVC_OPI_NUT {
+            1
+        } else {
+            r.uint("num_nalus", 16)?
+        };
+        nal_units(r, "nalu", count)?;
+        r.end_group();
+    }
+    Some(())
+}
+
+fn vvc_ptl_record(r: &mut FieldReader, num_sublayers: u64) -> Option<()> {
+    r.group("native_ptl");
+    r.uint("reserved", 2)?;
+    let constraint_bytes = r.uint("num_bytes_constraint_info", 6)?;
+    r.uint("general_profile_idc", 7)?;
+    r.uint("general_tier_flag", 1)?;
+    r.uint("general_level_idc", 8)?;
+    r.uint("ptl_frame_only_constraint_flag", 1)?;
+    r.uint("ptl_multilayer_enabled_flag", 1)?;
+    if constraint_bytes == 0 {
+        r.note(
+            DiagnosticSeverity::Error,
+            "vvcC num_bytes_constraint_info is 0",
+        );
+    }
+    r.skip(
+        "general_constraint_info",
+        (constraint_bytes * 8).saturating_sub(2),
+    )?;
+    let sublayers = num_sublayers.saturating_sub(1);
+    let mut present = Vec::new();
+    for i in (0..sublayers).rev() {
+        present.push((
+            i,
+            r.uint(&format!("ptl_sublayer_level_present_flag[{}]", i), 1)?,
+        ));
+    }
+    if num_sublayers > 1 {
+        for _ in num_sublayers..=8 {
+            r.uint("ptl_reserved_zero_bit", 1)?;
+        }
+    }
+    for (i, flag) in present {
+        if flag == 1 {
+            r.uint(&format!("sublayer_level_idc[{}]", i), 8)?;
+        }
+    }
+    let sub_profiles = r.uint("ptl_num_sub_profiles", 8)?;
+    for j in 0..sub_profiles {
+        r.hex(&format!("general_sub_profile_idc[{}]", j), 32)?;
+    }
+    r.end_group();
+    Some(())
+}
+
+fn av1c(r: &mut FieldReader) -> Option<()> {
+    let marker = r.uint("marker", 1)?;
+    let version = r.uint("version", 7)?;
+    if marker != 1 || version != 1 {
+        r.note(
+            DiagnosticSeverity::Error,
+            format!(
+                "av1C marker {} and version {}, expected 1 and 1",
+                marker, version
+            ),
+        );
+    }
+    r.uint("seq_profile", 3)?;
+    r.uint("seq_level_idx_0", 5)?;
+    r.uint("seq_tier_0", 1)?;
+    r.uint("high_bitdepth", 1)?;
+    r.uint("twelve_bit", 1)?;
+    r.uint("monochrome", 1)?;
+    r.uint("chroma_subsampling_x", 1)?;
+    r.uint("chroma_subsampling_y", 1)?;
+    r.uint("chroma_sample_position", 2)?;
+    r.uint("reserved", 3)?;
+    if r.uint("initial_presentation_delay_present", 1)? == 1 {
+        r.uint("initial_presentation_delay_minus_one", 4)?;
+    } else {
+        r.uint("reserved", 4)?;
+    }
+    r.rest("configOBUs")
+}
+
+fn vpcc(r: &mut FieldReader) -> Option<()> {
+    if r.version != 1 {
+        r.note(
+            DiagnosticSeverity::Warn,
+            format!("vpcC version {} is not the current version 1", r.version),
+        );
+    }
+    r.uint("profile", 8)?;
+    r.uint("level", 8)?;
+    r.uint("bitDepth", 4)?;
+    r.uint("chromaSubsampling", 3)?;
+    r.uint("videoFullRangeFlag", 1)?;
+    r.uint("colourPrimaries", 8)?;
+    r.uint("transferCharacteristics", 8)?;
+    r.uint("matrixCoefficients", 8)?;
+    let size = r.uint("codecInitializationDataSize", 16)?;
+    if size > 0 {
+        r.bytes("codecInitializationData", size)?;
+    }
+    Some(())
+}
+
+fn colr(r: &mut FieldReader) -> Option<()> {
+    let colour_type = r.fourcc("colour_type")?;
+    match &colour_type {
+        b"nclx" | b"nclc" => {
+            r.uint("colour_primaries", 16)?;
+            r.uint("transfer_characteristics", 16)?;
+            r.uint("matrix_coefficients", 16)?;
+            if &colour_type == b"nclx" {
+                r.uint("full_range_flag", 1)?;
+                r.uint("reserved", 7)?;
+            }
+            Some(())
+        }
+        b"rICC" | b"prof" => r.rest("ICC_profile"),
+        _ => r.rest("data"),
+    }
+}
+
+fn pasp(r: &mut FieldReader) -> Option<()> {
+    let h_spacing = r.uint("hSpacing", 32)?;
+    let v_spacing = r.uint("vSpacing", 32)?;
+    if h_spacing == 0 || v_spacing == 0 {
+        r.note(
+            DiagnosticSeverity::Warn,
+            format!(
+                "pixel aspect ratio {}:{} is undefined",
+                h_spacing, v_spacing
+            ),
+        );
+    }
+    Some(())
+}
+
+fn mdcv(r: &mut FieldReader) -> Option<()> {
+    // Chromaticities in units of 0.00002, luminances of 0.0001 cd/m²
+    let chromaticity = |v: u64| format!("{} ({:.5})", v, v as f64 * 0.00002);
+    for c in ["g", "b", "r"] {
+        r.field_with(&format!("display_primaries_{}_x", c), 16, chromaticity)?;
+        r.field_with(&format!("display_primaries_{}_y", c), 16, chromaticity)?;
+    }
+    r.field_with("white_point_x", 16, chromaticity)?;
+    r.field_with("white_point_y", 16, chromaticity)?;
+    let luminance = |v: u64| format!("{} ({} cd/m²)", v, v as f64 * 0.0001);
+    let max = r.field_with("max_display_mastering_luminance", 32, luminance)?;
+    let min = r.field_with("min_display_mastering_luminance", 32, luminance)?;
+    if min >= max {
+        r.note(
+            DiagnosticSeverity::Warn,
+            "minimum mastering luminance is not below the maximum",
+        );
+    }
+    Some(())
+}
+
+fn trex(r: &mut FieldReader) -> Option<()> {
+    r.uint("track_ID", 32)?;
+    r.uint("default_sample_description_index", 32)?;
+    r.uint("default_sample_duration", 32)?;
+    r.uint("default_sample_size", 32)?;
+    r.hex("default_sample_flags", 32).map(drop)
+}
+
+fn tfhd(r: &mut FieldReader) -> Option<()> {
+    let flags = r.flags;
+    r.uint("track_ID", 32)?;
+    if flags & TFHD_BASE_DATA_OFFSET_PRESENT != 0 {
+        r.uint("base_data_offset", 64)?;
+    }
+    if flags & TFHD_SAMPLE_DESCRIPTION_INDEX_PRESENT != 0 {
+        r.uint("sample_description_index", 32)?;
+    }
+    if flags & TFHD_DEFAULT_SAMPLE_DURATION_PRESENT != 0 {
+        r.uint("default_sample_duration", 32)?;
+    }
+    if flags & TFHD_DEFAULT_SAMPLE_SIZE_PRESENT != 0 {
+        r.uint("default_sample_size", 32)?;
+    }
+    if flags & TFHD_DEFAULT_SAMPLE_FLAGS_PRESENT != 0 {
+        r.hex("default_sample_flags", 32)?;
+    }
+    Some(())
+}
+
+fn trun(r: &mut FieldReader) -> Option<()> {
+    let flags = r.flags;
+    let signed = r.version == 1;
+    let count = r.uint("sample_count", 32)?;
+    if flags & TRUN_DATA_OFFSET_PRESENT != 0 {
+        r.int("data_offset", 32)?;
+    }
+    if flags & TRUN_FIRST_SAMPLE_FLAGS_PRESENT != 0 {
+        r.hex("first_sample_flags", 32)?;
+    }
+    r.table(count, |r| {
+        if flags & TRUN_SAMPLE_DURATION_PRESENT != 0 {
+            r.uint("sample_duration", 32)?;
+        }
+        if flags & TRUN_SAMPLE_SIZE_PRESENT != 0 {
+            r.uint("sample_size", 32)?;
+        }
+        if flags & TRUN_SAMPLE_FLAGS_PRESENT != 0 {
+            r.hex("sample_flags", 32)?;
+        }
+        if flags & TRUN_SAMPLE_COMPOSITION_TIME_OFFSET_PRESENT != 0 {
+            if signed {
+                r.int("sample_composition_time_offset", 32)?;
+            } else {
+                r.uint("sample_composition_time_offset", 32)?;
+            }
+        }
+        Some(())
+    })
+}
+
+fn sidx(r: &mut FieldReader) -> Option<()> {
+    r.uint("reference_ID", 32)?;
+    r.uint("timescale", 32)?;
+    let bits = if r.version == 0 { 32 } else { 64 };
+    r.uint("earliest_presentation_time", bits)?;
+    r.uint("first_offset", bits)?;
+    r.uint("reserved", 16)?;
+    let count = r.uint("reference_count", 16)?;
+    r.table(count, |r| {
+        r.uint("reference_type", 1)?;
+        r.uint("referenced_size", 31)?;
+        r.uint("subsegment_duration", 32)?;
+        r.uint("starts_with_SAP", 1)?;
+        r.uint("SAP_type", 3)?;
+        r.uint("SAP_delta_time", 28).map(drop)
+    })
+}
+
+fn emsg(r: &mut FieldReader) -> Option<()> {
+    if r.version == 0 {
+        r.cstring("scheme_id_uri")?;
+        r.cstring("value")?;
+        r.uint("timescale", 32)?;
+        r.uint("presentation_time_delta", 32)?;
+    } else {
+        r.uint("timescale", 32)?;
+        r.uint("presentation_time", 64)?;
+    }
+    r.uint("event_duration", 32)?;
+    r.uint("id", 32)?;
+    if r.version != 0 {
+        r.cstring("scheme_id_uri")?;
+        r.cstring("value")?;
+    }
+    r.rest("message_data")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::mp4::{full_box, mp4_box, words};
+
+    fn avcc() -> Vec<u8> {
+        let sps = [0x67, 0x64, 0x00, 0x1F];
+        let pps = [0x68, 0xEE];
+        let mut body = vec![1, 100, 0x00, 31, 0xFF, 0xE1];
+        body.extend_from_slice(&(sps.len() as u16).to_be_bytes());
+        body.extend_from_slice(&sps);
+        body.push(1);
+        body.extend_from_slice(&(pps.len() as u16).to_be_bytes());
+        body.extend_from_slice(&pps);
+        mp4_box(b"avcC", &body)
+    }
+
+    fn avc1(children: &[u8]) -> Vec<u8> {
+        let mut fields = [0u8; 78];
+        fields[7] = 1; // data_reference_index
+        fields[24..26].copy_from_slice(&1920u16.to_be_bytes());
+        fields[26..28].copy_from_slice(&1080u16.to_be_bytes());
+        fields[42] = 4;
+        fields[43..47].copy_from_slice(b"x264");
+        fields[74..76].copy_from_slice(&0x18u16.to_be_bytes());
+        mp4_box(b"avc1", &[&fields[..], children].concat())
+    }
+
+    /// One 1080p avc1 track of three samples at 25 fps; mvhd timescale
+    /// 1000, mdhd timescale 12800
+    fn movie(elst_entries: &[u8], stsz_count: u32) -> Vec<u8> {
+        let ftyp = mp4_box(
+            b"ftyp",
+            &[b"isom", &[0, 0, 2, 0][..], b"isom", b"avc1"].concat(),
+        );
+        let mut mvhd = words(&[0, 0, 1000, 120, 0x0001_0000]);
+        mvhd.extend_from_slice(&[0x01, 0x00, 0, 0]);
+        mvhd.extend_from_slice(&[0u8; 8 + 36 + 24]);
+        mvhd.extend_from_slice(&2u32.to_be_bytes());
+        let mvhd = full_box(b"mvhd", 0, 0, &mvhd);
+
+        let mut tkhd = words(&[0, 0, 1, 0, 120, 0, 0, 0, 0]);
+        tkhd.extend_from_slice(&[0u8; 36]);
+        tkhd.extend_from_slice(&words(&[1920 << 16, 1080 << 16]));
+        let tkhd = full_box(b"tkhd", 0, 3, &tkhd);
+
+        let edts = mp4_box(
+            b"edts",
+            &full_box(
+                b"elst",
+                0,
+                0,
+                &[
+                    &((elst_entries.len() / 12) as u32).to_be_bytes()[..],
+                    elst_entries,
+                ]
+                .concat(),
+            ),
+        );
+
+        // "und": 'u' 'n' 'd' - 0x60 = 21, 14, 4
+        let language = (21u16 << 10) | (14 << 5) | 4;
+        let mut mdhd = words(&[0, 0, 12800, 1536]);
+        mdhd.extend_from_slice(&language.to_be_bytes());
+        mdhd.extend_from_slice(&[0, 0]);
+        let mdhd = full_box(b"mdhd", 0, 0, &mdhd);
+        let hdlr = full_box(
+            b"hdlr",
+            0,
+            0,
+            &[&words(&[0])[..], b"vide", &[0u8; 12], b"VideoHandler\0"].concat(),
+        );
+
+        let pasp = mp4_box(b"pasp", &words(&[1, 1]));
+        let entry = avc1(&[avcc(), pasp].concat());
+        let stbl = [
+            full_box(b"stsd", 0, 0, &[&1u32.to_be_bytes()[..], &entry].concat()),
+            full_box(b"stts", 0, 0, &words(&[1, 3, 512])),
+            full_box(b"stss", 0, 0, &words(&[1, 1])),
+            full_box(b"stsz", 0, 0, &words(&[1000, stsz_count])),
+            full_box(b"stco", 0, 0, &words(&[1, 0])),
+        ]
+        .concat();
+        let minf = mp4_box(b"minf", &mp4_box(b"stbl", &stbl));
+        let mdia = mp4_box(b"mdia", &[mdhd, hdlr, minf].concat());
+        let trak = mp4_box(b"trak", &[tkhd, edts, mdia].concat());
+        let moov = mp4_box(b"moov", &[mvhd, trak].concat());
+        [ftyp, moov].concat()
+    }
+
+    /// One edit presenting the media from time `media_time`
+    fn edit(segment_duration: u32, media_time: i32) -> Vec<u8> {
+        [
+            &segment_duration.to_be_bytes()[..],
+            &media_time.to_be_bytes(),
+            &[0, 1, 0, 0],
+        ]
+        .concat()
+    }
+
+    fn value<'a>(mp4_box: &'a Mp4Box, name: &str) -> &'a str {
+        mp4_box.field(name).unwrap().value.as_deref().unwrap()
+    }
+
+    #[test]
+    fn test_box_tree_fields() {
+        let data = movie(&edit(120, 0), 3);
+        let tree = parse_box_tree(&data);
+        assert!(tree.issues.is_empty(), "{:?}", tree.issues);
+        assert_eq!(tree.boxes.len(), 2);
+
+        let ftyp = tree.find("ftyp").unwrap();
+        assert_eq!(value(ftyp, "major_brand"), "isom");
+        assert_eq!(value(ftyp, "compatible_brand[1]"), "avc1");
+
+        let moov = tree.find("moov").unwrap();
+        assert_eq!(moov.offset, 24);
+        assert_eq!(moov.end(), data.len() as u64);
+        assert_eq!(value(tree.find("moov/mvhd").unwrap(), "timescale"), "1000");
+        assert_eq!(value(tree.find("moov/mvhd").unwrap(), "rate"), "1");
+
+        let tkhd = moov.find("trak/tkhd").unwrap();
+        assert_eq!(tkhd.version, Some(0));
+        assert_eq!(tkhd.flags, Some(3));
+        assert_eq!(
+            value(tkhd, "flags"),
+            "0x000003 (track_enabled, track_in_movie)"
+        );
+        assert_eq!(value(tkhd, "width"), "1920");
+
+        let mdhd = moov.find("trak/mdia/mdhd").unwrap();
+        assert_eq!(value(mdhd, "language"), "und");
+        assert_eq!(
+            value(moov.find("trak/mdia/hdlr").unwrap(), "name"),
+            "\"VideoHandler\""
+        );
+
+        let elst = moov.find("trak/edts/elst").unwrap();
+        let entry = elst.field("entry[0]").unwrap();
+        assert_eq!(
+            entry.field("media_time").unwrap().value.as_deref(),
+            Some("0")
+        );
+
+        let avc1 = moov.find("trak/mdia/minf/stbl/stsd/avc1").unwrap();
+        assert_eq!(value(avc1, "width"), "1920");
+        assert_eq!(value(avc1, "compressorname"), "\"x264\"");
+        let avcc = avc1.find("avcC").unwrap();
+        assert_eq!(value(avcc, "AVCProfileIndication"), "100");
+        assert_eq!(value(avcc, "lengthSizeMinusOne"), "3");
+        let sps = avcc.field("sequenceParameterSet[0]").unwrap();
+        assert_eq!(
+            sps.field("nalUnit").unwrap().value.as_deref(),
+            Some("67 64 00 1F")
+        );
+        assert_eq!(value(avc1.find("pasp").unwrap(), "hSpacing"), "1");
+    }
+
+    #[test]
+    fn test_field_positions() {
+        let data = movie(&edit(120, 0), 3);
+        let tree = parse_box_tree(&data);
+        let avcc = tree
+            .find("moov/trak/mdia/minf/stbl/stsd/avc1/avcC")
+            .unwrap();
+        let start = avcc.offset * 8;
+        assert_eq!(avcc.header_size, 8);
+
+        let size = avcc.field("size").unwrap();
+        assert_eq!(size.bit_range, BitRange::new(start, start + 32));
+        // reserved (6 bits) then lengthSizeMinusOne (2 bits) in byte 4
+        let length_size = avcc.field("lengthSizeMinusOne").unwrap();
+        assert_eq!(
+            length_size.bit_range,
+            BitRange::new(start + 64 + 38, start + 64 + 40)
+        );
+        let sps = avcc.field("sequenceParameterSet[0]").unwrap();
+        assert_eq!(sps.bit_range.size_bits(), (2 + 4) * 8);
+        assert_eq!(&data[sps.bit_range.byte_offset() as usize + 2], &0x67);
+    }
+
+    #[test]
+    fn test_syntax_model() {
+        let data = movie(&edit(120, 0), 3);
+        let model = parse_box_tree(&data).to_syntax_model();
+        let root = model.get_node(&model.root_id).unwrap();
+        assert_eq!(root.bit_range, BitRange::new(0, data.len() as u64 * 8));
+        assert_eq!(root.children, ["mp4/ftyp[0]", "mp4/moov[0]"]);
+
+        let track_id = model
+            .get_node("mp4/moov[0]/trak[0]/tkhd[0].track_ID")
+            .unwrap();
+        assert_eq!(track_id.value.as_deref(), Some("1"));
+        let sample_count = model
+            .get_node("mp4/moov[0]/trak[0]/mdia[0]/minf[0]/stbl[0]/stsz[0].sample_count")
+            .unwrap();
+        let nearest = model
+            .find_nearest_node(&BitRange::new(
+                sample_count.bit_range.start_bit + 3,
+                sample_count.bit_range.start_bit + 4,
+            ))
+            .unwrap();
+        assert_eq!(nearest.node_id, sample_count.node_id);
+
+        // Repeated reserved fields get distinct nodes
+        assert!(model.get_node("mp4/moov[0]/mvhd[0].reserved~2").is_some());
+    }
+
+    #[test]
+    fn test_container_issues() {
+        // Edit reaching 20 ms past the media, one sample missing from stts
+        let data = movie(&edit(140, 0), 4);
+        let tree = parse_box_tree(&data);
+        let messages: Vec<&str> = tree.issues.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "stts covers 3 samples but stsz has 4",
+                "edit 0 ends at media time 1792 past the end of the media at 1536",
+            ]
+        );
+        assert_eq!(tree.issues[0].path, "moov/trak/mdia/minf/stbl");
+        assert_eq!(tree.issues[1].path, "moov/trak/edts/elst");
+        assert_eq!(
+            tree.issues[1].offset,
+            tree.find("moov/trak/edts/elst").unwrap().offset
+        );
+
+        let diagnostics = tree.diagnostics();
+        assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Error);
+        assert_eq!(diagnostics[0].category, DiagnosticCategory::Container);
+    }
+
+    #[test]
+    fn test_truncated_file() {
+        let mut data = movie(&edit(120, 0), 3);
+        data.truncate(data.len() - 10);
+        let tree = parse_box_tree(&data);
+        let moov = tree.find("moov").unwrap();
+        assert_eq!(moov.end(), data.len() as u64);
+        assert_eq!(tree.issues[0].path, "moov");
+        let declared = u32::from_be_bytes(data[24..28].try_into().unwrap());
+        assert_eq!(
+            tree.issues[0].message,
+            format!("box size {} overruns the file by 10 bytes", declared)
+        );
+        // The ftyp box and the start of moov are still walked
+        assert!(tree.find("moov/trak/tkhd").is_some());
+    }
+}
@@ -0,0 +1,113 @@
+//! Byte-level builders shared by the container parser tests
+
+/// MPEG-2 TS packets, PSI sections and PES headers
+pub(crate) mod ts {
+    use crate::ts::{TS_PACKET_SIZE, TS_SYNC_BYTE};
+    use crate::ts_analysis::crc32_mpeg2;
+
+    /// TS packet with an optional PCR; an adaptation field stuffs short
+    /// payloads to 188 bytes
+    pub(crate) fn packet(
+        pid: u16,
+        pusi: bool,
+        cc: u8,
+        pcr: Option<u64>,
+        payload: &[u8],
+    ) -> Vec<u8> {
+        let adapted = pcr.is_some() || payload.len() < TS_PACKET_SIZE - 4;
+        let mut data = vec![
+            TS_SYNC_BYTE,
+            ((pusi as u8) << 6) | (pid >> 8) as u8,
+            pid as u8,
+            if adapted { 0x30 } else { 0x10 } | cc,
+        ];
+        if adapted {
+            let mut adaptation = vec![0x00];
+            if let Some(pcr) = pcr {
+                let base = pcr / 300;
+                let extension = pcr % 300;
+                adaptation = vec![
+                    0x10,
+                    (base >> 25) as u8,
+                    (base >> 17) as u8,
+                    (base >> 9) as u8,
+                    (base >> 1) as u8,
+                    ((base & 1) << 7) as u8 | 0x7E | (extension >> 8) as u8,
+                    extension as u8,
+                ];
+            }
+            adaptation.resize(TS_PACKET_SIZE - 5 - payload.len(), 0xFF);
+            data.push(adaptation.len() as u8);
+            data.extend_from_slice(&adaptation);
+        }
+        data.extend_from_slice(payload);
+        data
+    }
+
+    /// PSI section with pointer field and a valid CRC; `body` starts after
+    /// the section length
+    pub(crate) fn section(table_id: u8, body: &[u8]) -> Vec<u8> {
+        let length = body.len() + 4;
+        let mut data = vec![table_id, 0xB0 | (length >> 8) as u8, length as u8];
+        data.extend_from_slice(body);
+        let crc = crc32_mpeg2(&data);
+        data.extend_from_slice(&crc.to_be_bytes());
+        data.insert(0, 0);
+        data
+    }
+
+    /// Video PES header with optional PTS and DTS, unbounded length
+    pub(crate) fn pes(pts: Option<u64>, dts: Option<u64>, es: &[u8]) -> Vec<u8> {
+        let timestamp = |prefix: u8, v: u64| {
+            [
+                (prefix << 4) | ((v >> 29) as u8 & 0x0E) | 1,
+                (v >> 22) as u8,
+                ((v >> 14) as u8 & 0xFE) | 1,
+                (v >> 7) as u8,
+                ((v << 1) as u8 & 0xFE) | 1,
+            ]
+        };
+        let mut fields = Vec::new();
+        let flags = match (pts, dts) {
+            (Some(pts), Some(dts)) => {
+                fields.extend(timestamp(3, pts));
+                fields.extend(timestamp(1, dts));
+                0xC0
+            }
+            (Some(pts), None) => {
+                fields.extend(timestamp(2, pts));
+                0x80
+            }
+            _ => 0x00,
+        };
+        let mut data = vec![0x00, 0x00, 0x01, 0xE0, 0x00, 0x00, 0x80, flags];
+        data.push(fields.len() as u8);
+        data.extend(fields);
+        data.extend_from_slice(es);
+        data
+    }
+}
+
+/// ISO BMFF boxes
+pub(crate) mod mp4 {
+    /// Box with a 32-bit size
+    pub(crate) fn mp4_box(box_type: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(8 + body.len() as u32).to_be_bytes());
+        data.extend_from_slice(box_type);
+        data.extend_from_slice(body);
+        data
+    }
+
+    /// Box with a version and flags word before `body`
+    pub(crate) fn full_box(box_type: &[u8; 4], version: u8, flags: u32, body: &[u8]) -> Vec<u8> {
+        let mut data = ((version as u32) << 24 | flags).to_be_bytes().to_vec();
+        data.extend_from_slice(body);
+        mp4_box(box_type, &data)
+    }
+
+    /// Big-endian 32-bit fields
+    pub(crate) fn words(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+}
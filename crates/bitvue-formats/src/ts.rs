@@ -1,74 +1,186 @@
 //! MPEG-2 Transport Stream (TS) demuxer
 //!
-//! Parses MPEG-2 TS files to extract AV1 video streams.
+//! Parses MPEG-2 TS files to extract AV1, H.264, H.265, H.266 and MPEG-2
+//! video streams. Video elementary streams are found through the PAT and
+//! PMT and routed by their stream type.
 //! TS is widely used in broadcasting and streaming (HLS, MPEG-DASH).
 //!
 //! Reference: ISO/IEC 13818-1 (MPEG-2 Systems)
 
 use bitvue_core::{BitvueError, Result};
-use std::collections::HashMap;
 
 /// TS packet size (188 bytes standard)
-const TS_PACKET_SIZE: usize = 188;
+pub(crate) const TS_PACKET_SIZE: usize = 188;
 
 /// Sync byte for TS packets
-const TS_SYNC_BYTE: u8 = 0x47;
+pub(crate) const TS_SYNC_BYTE: u8 = 0x47;
 
 /// PAT (Program Association Table) PID
-const PAT_PID: u16 = 0x0000;
+pub(crate) const PAT_PID: u16 = 0x0000;
+
+/// MPEG-2 video stream type in PMT
+const STREAM_TYPE_MPEG2_VIDEO: u8 = 0x02;
 
 /// AV1 stream type in PMT
 const STREAM_TYPE_AV1: u8 = 0x06; // Private data, need descriptor check
 
+/// H.264/AVC stream type in PMT
+const STREAM_TYPE_AVC: u8 = 0x1B;
+
+/// H.265/HEVC stream type in PMT
+const STREAM_TYPE_HEVC: u8 = 0x24;
+
+/// H.266/VVC stream type in PMT
+const STREAM_TYPE_VVC: u8 = 0x33;
+
+/// Registration descriptor tag, carrying a format_identifier
+const REGISTRATION_DESCRIPTOR_TAG: u8 = 0x05;
+
+/// format_identifier of AV1 in a registration descriptor
+const AV1_FORMAT_IDENTIFIER: [u8; 4] = *b"AV01";
+
 /// TS packet header
 #[derive(Debug, Clone)]
-struct TsPacket {
+pub(crate) struct TsPacket {
     /// Packet Identifier
-    pid: u16,
+    pub(crate) pid: u16,
+    /// Transport Error Indicator
+    pub(crate) transport_error: bool,
     /// Payload Unit Start Indicator
-    payload_unit_start: bool,
+    pub(crate) payload_unit_start: bool,
+    /// Transport scrambling control
+    pub(crate) scrambling_control: u8,
     /// Adaptation field control
-    _adaptation_field_control: u8,
+    pub(crate) adaptation_field_control: u8,
     /// Continuity counter
-    _continuity_counter: u8,
+    pub(crate) continuity_counter: u8,
+    /// Discontinuity indicator of the adaptation field
+    pub(crate) discontinuity: bool,
+    /// Program Clock Reference of the adaptation field (27 MHz)
+    pub(crate) pcr: Option<u64>,
     /// Payload data
-    payload: Vec<u8>,
+    pub(crate) payload: Vec<u8>,
+}
+
+impl TsPacket {
+    /// Whether the packet carries a payload; only those advance the
+    /// continuity counter
+    pub(crate) fn has_payload(&self) -> bool {
+        self.adaptation_field_control & 0x01 != 0
+    }
 }
 
 /// Program Association Table entry
 #[derive(Debug, Clone)]
-struct PatEntry {
-    _program_number: u16,
-    pmt_pid: u16,
+pub(crate) struct PatEntry {
+    pub(crate) program_number: u16,
+    pub(crate) pmt_pid: u16,
 }
 
 /// Program Map Table stream info
 #[derive(Debug, Clone)]
-struct PmtStream {
-    stream_type: u8,
-    elementary_pid: u16,
+pub(crate) struct PmtStream {
+    pub(crate) stream_type: u8,
+    pub(crate) elementary_pid: u16,
+    /// format_identifier of a registration descriptor in the ES info
+    pub(crate) format_identifier: Option<[u8; 4]>,
+}
+
+/// Elementary streams of one program, from its PMT
+#[derive(Debug, Clone)]
+struct PmtProgram {
+    program_number: u16,
+    streams: Vec<PmtStream>,
 }
 
 /// PES (Packetized Elementary Stream) packet
 #[derive(Debug, Clone)]
-struct PesPacket {
+pub(crate) struct PesPacket {
     _stream_id: u8,
-    pts: Option<u64>,
-    _dts: Option<u64>,
-    payload: Vec<u8>,
+    pub(crate) pts: Option<u64>,
+    pub(crate) dts: Option<u64>,
+    pub(crate) payload: Vec<u8>,
+}
+
+/// Video codec of a TS elementary stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TsVideoCodec {
+    /// MPEG-2 video (stream type 0x02)
+    Mpeg2,
+    /// H.264/AVC (stream type 0x1B)
+    Avc,
+    /// H.265/HEVC (stream type 0x24)
+    Hevc,
+    /// H.266/VVC (stream type 0x33)
+    Vvc,
+    /// AV1 (private data, stream type 0x06)
+    Av1,
+}
+
+impl TsVideoCodec {
+    /// Codec of a PMT entry, if it is a supported video stream
+    ///
+    /// Private data streams are taken as AV1 unless a registration
+    /// descriptor names another format.
+    pub(crate) fn from_pmt_stream(stream: &PmtStream) -> Option<Self> {
+        match stream.stream_type {
+            STREAM_TYPE_MPEG2_VIDEO => Some(TsVideoCodec::Mpeg2),
+            STREAM_TYPE_AVC => Some(TsVideoCodec::Avc),
+            STREAM_TYPE_HEVC => Some(TsVideoCodec::Hevc),
+            STREAM_TYPE_VVC => Some(TsVideoCodec::Vvc),
+            STREAM_TYPE_AV1 => stream
+                .format_identifier
+                .is_none_or(|id| id == AV1_FORMAT_IDENTIFIER)
+                .then_some(TsVideoCodec::Av1),
+            _ => None,
+        }
+    }
+
+    /// Get human-readable name
+    pub fn name(&self) -> &'static str {
+        match self {
+            TsVideoCodec::Mpeg2 => "MPEG-2 Video",
+            TsVideoCodec::Avc => "H.264/AVC",
+            TsVideoCodec::Hevc => "H.265/HEVC",
+            TsVideoCodec::Vvc => "H.266/VVC",
+            TsVideoCodec::Av1 => "AV1",
+        }
+    }
+}
+
+/// A video elementary stream announced in a PMT
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TsVideoStream {
+    /// Program the stream belongs to
+    pub program_number: u16,
+    /// Elementary stream PID
+    pub pid: u16,
+    /// PMT stream_type
+    pub stream_type: u8,
+    /// Video codec
+    pub codec: TsVideoCodec,
 }
 
 /// TS demuxer information
 #[derive(Debug)]
 pub struct TsInfo {
+    /// Video elementary streams of all programs, in PMT order
+    pub video_streams: Vec<TsVideoStream>,
     /// Video stream PID
     pub video_pid: Option<u16>,
+    /// Codec of the extracted video stream
+    pub codec: Option<TsVideoCodec>,
     /// Number of samples extracted
     pub sample_count: usize,
-    /// AV1 samples (OBU data)
+    /// Video samples, one per PES packet (OBU data or Annex B byte stream)
     pub samples: Vec<Vec<u8>>,
-    /// Presentation timestamps
-    pub timestamps: Vec<u64>,
+    /// Presentation timestamps (90 kHz; `None` when the PES carries none)
+    pub timestamps: Vec<Option<u64>>,
+    /// Decode timestamps (90 kHz; the PTS when the PES carries no DTS)
+    pub decode_timestamps: Vec<Option<u64>>,
+    /// Continuity counter gaps on the video PID; each one drops the PES
+    /// packet it interrupts
+    pub continuity_errors: usize,
 }
 
 /// Check if data is a TS file
@@ -91,7 +203,7 @@ pub fn is_ts(data: &[u8]) -> bool {
 }
 
 /// Parse a single TS packet
-fn parse_ts_packet(data: &[u8]) -> Result<TsPacket> {
+pub(crate) fn parse_ts_packet(data: &[u8]) -> Result<TsPacket> {
     if data.len() < 4 {
         return Err(BitvueError::InvalidData("TS packet too short".to_string()));
     }
@@ -109,13 +221,17 @@ fn parse_ts_packet(data: &[u8]) -> Result<TsPacket> {
     let byte2 = data[2];
     let byte3 = data[3];
 
+    let transport_error = (byte1 & 0x80) != 0;
     let payload_unit_start = (byte1 & 0x40) != 0;
     let pid = (((byte1 & 0x1F) as u16) << 8) | (byte2 as u16);
+    let scrambling_control = (byte3 & 0xC0) >> 6;
     let adaptation_field_control = (byte3 & 0x30) >> 4;
     let continuity_counter = byte3 & 0x0F;
 
     // Extract payload
     let mut payload_start: usize = 4;
+    let mut discontinuity = false;
+    let mut pcr = None;
 
     // Handle adaptation field
     if adaptation_field_control == 0x02 || adaptation_field_control == 0x03 {
@@ -135,6 +251,20 @@ fn parse_ts_packet(data: &[u8]) -> Result<TsPacket> {
             )));
         }
 
+        if adaptation_length > 0 {
+            discontinuity = data[5] & 0x80 != 0;
+        }
+        // PCR_flag, followed by 33-bit base, 6 reserved bits and 9-bit extension
+        if adaptation_length >= 7 && data[5] & 0x10 != 0 {
+            let base = ((data[6] as u64) << 25)
+                | ((data[7] as u64) << 17)
+                | ((data[8] as u64) << 9)
+                | ((data[9] as u64) << 1)
+                | ((data[10] as u64) >> 7);
+            let extension = (((data[10] & 0x01) as u64) << 8) | (data[11] as u64);
+            pcr = Some(base * 300 + extension);
+        }
+
         // Use checked arithmetic to prevent overflow
         payload_start = match payload_start
             .checked_add(1)
@@ -162,15 +292,19 @@ fn parse_ts_packet(data: &[u8]) -> Result<TsPacket> {
 
     Ok(TsPacket {
         pid,
+        transport_error,
         payload_unit_start,
-        _adaptation_field_control: adaptation_field_control,
-        _continuity_counter: continuity_counter,
+        scrambling_control,
+        adaptation_field_control,
+        continuity_counter,
+        discontinuity,
+        pcr,
         payload,
     })
 }
 
 /// Parse PAT (Program Association Table)
-fn parse_pat(payload: &[u8], pusi: bool) -> Result<Vec<PatEntry>> {
+pub(crate) fn parse_pat(payload: &[u8], pusi: bool) -> Result<Vec<PatEntry>> {
     if payload.is_empty() {
         return Ok(Vec::new());
     }
@@ -206,7 +340,10 @@ fn parse_pat(payload: &[u8], pusi: bool) -> Result<Vec<PatEntry>> {
     offset += 5;
 
     let mut entries = Vec::new();
-    let end = offset + (section_length as usize) - 9; // -9 for header and CRC
+    // -9 for header and CRC
+    let end = (offset + section_length as usize)
+        .saturating_sub(9)
+        .min(payload.len());
 
     while offset + 4 <= end {
         let program_number = ((payload[offset] as u16) << 8) | (payload[offset + 1] as u16);
@@ -215,7 +352,7 @@ fn parse_pat(payload: &[u8], pusi: bool) -> Result<Vec<PatEntry>> {
         if program_number != 0 {
             // Skip network PID (program_number == 0)
             entries.push(PatEntry {
-                _program_number: program_number,
+                program_number,
                 pmt_pid,
             });
         }
@@ -227,7 +364,7 @@ fn parse_pat(payload: &[u8], pusi: bool) -> Result<Vec<PatEntry>> {
 }
 
 /// Parse PMT (Program Map Table)
-fn parse_pmt(payload: &[u8], pusi: bool) -> Result<Vec<PmtStream>> {
+pub(crate) fn parse_pmt(payload: &[u8], pusi: bool) -> Result<Vec<PmtStream>> {
     if payload.is_empty() {
         return Ok(Vec::new());
     }
@@ -258,13 +395,16 @@ fn parse_pmt(payload: &[u8], pusi: bool) -> Result<Vec<PmtStream>> {
     let section_length = (((payload[offset] & 0x0F) as u16) << 8) | (payload[offset + 1] as u16);
     offset += 2;
 
-    // Skip program_number (2), version (1), section_number (1), last_section_number (1)
-    offset = match offset.checked_add(5) {
-        Some(v) => v,
-        None => return Ok(Vec::new()),
-    };
+    // The stream loop ends before the CRC_32
+    let end = (offset + section_length as usize)
+        .saturating_sub(4)
+        .min(payload.len());
 
-    if offset + 2 > payload.len() {
+    // Skip program_number (2), version (1), section_number (1),
+    // last_section_number (1) and PCR_PID (2)
+    offset += 7;
+
+    if offset + 2 > end {
         return Ok(Vec::new());
     }
 
@@ -280,22 +420,20 @@ fn parse_pmt(payload: &[u8], pusi: bool) -> Result<Vec<PmtStream>> {
     };
 
     let mut streams = Vec::new();
-    let end = offset + (section_length as usize) - 9 - 4 - (program_info_length as usize);
-
-    while offset + 5 <= end && offset < payload.len() {
-        if offset + 5 > payload.len() {
-            break;
-        }
 
+    while offset + 5 <= end {
         let stream_type = payload[offset];
         let elementary_pid =
             (((payload[offset + 1] & 0x1F) as u16) << 8) | (payload[offset + 2] as u16);
         let es_info_length =
             (((payload[offset + 3] & 0x0F) as u16) << 8) | (payload[offset + 4] as u16);
 
+        let es_info_start = offset + 5;
+        let es_info_end = (es_info_start + es_info_length as usize).min(end);
         streams.push(PmtStream {
             stream_type,
             elementary_pid,
+            format_identifier: find_format_identifier(&payload[es_info_start..es_info_end]),
         });
 
         // Use checked arithmetic to prevent overflow
@@ -311,8 +449,22 @@ fn parse_pmt(payload: &[u8], pusi: bool) -> Result<Vec<PmtStream>> {
     Ok(streams)
 }
 
+/// Find the format_identifier of a registration descriptor in a descriptor loop
+fn find_format_identifier(mut descriptors: &[u8]) -> Option<[u8; 4]> {
+    while descriptors.len() >= 2 {
+        let tag = descriptors[0];
+        let length = descriptors[1] as usize;
+        let body = descriptors.get(2..2 + length)?;
+        if tag == REGISTRATION_DESCRIPTOR_TAG && length >= 4 {
+            return body[..4].try_into().ok();
+        }
+        descriptors = &descriptors[2 + length..];
+    }
+    None
+}
+
 /// Parse PES packet
-fn parse_pes(data: &[u8]) -> Result<PesPacket> {
+pub(crate) fn parse_pes(data: &[u8]) -> Result<PesPacket> {
     if data.len() < 6 {
         return Err(BitvueError::InvalidData("PES too short".to_string()));
     }
@@ -325,7 +477,7 @@ fn parse_pes(data: &[u8]) -> Result<PesPacket> {
     }
 
     let stream_id = data[3];
-    let _pes_packet_length = ((data[4] as u16) << 8) | (data[5] as u16);
+    let pes_packet_length = ((data[4] as u16) << 8) | (data[5] as u16);
 
     let mut offset = 6;
     let mut pts = None;
@@ -368,8 +520,13 @@ fn parse_pes(data: &[u8]) -> Result<PesPacket> {
         offset = 9 + pes_header_length;
     }
 
-    let payload = if offset < data.len() {
-        data[offset..].to_vec()
+    // A zero length leaves video PES packets unbounded
+    let end = match pes_packet_length {
+        0 => data.len(),
+        length => (6 + length as usize).min(data.len()),
+    };
+    let payload = if offset < end {
+        data[offset..end].to_vec()
     } else {
         Vec::new()
     };
@@ -377,7 +534,7 @@ fn parse_pes(data: &[u8]) -> Result<PesPacket> {
     Ok(PesPacket {
         _stream_id: stream_id,
         pts,
-        _dts: dts,
+        dts,
         payload,
     })
 }
@@ -393,173 +550,230 @@ fn parse_timestamp(data: &[u8]) -> u64 {
 
 /// Extract AV1 samples from TS file
 pub fn extract_av1_samples(data: &[u8]) -> Result<Vec<Vec<u8>>> {
-    let info = parse_ts(data)?;
-    Ok(info.samples)
+    extract_codec_samples(data, TsVideoCodec::Av1)
 }
 
-/// Extract H.264/AVC samples from TS file
-///
-/// # Note
-///
-/// Phase 12A: Basic implementation. Full H.264/AVC TS parsing requires:
-/// - PMT stream type detection (0x1B for H.264)
-/// - NAL unit extraction from PES packets
-/// - Annex B start code handling
-///
-/// TODO Phase 12B: Implement full H.264 TS parsing with stream type detection
+/// Extract H.264/AVC samples (Annex B access units) from TS file
 pub fn extract_avc_samples(data: &[u8]) -> Result<Vec<Vec<u8>>> {
-    let _info = parse_ts(data)?;
-
-    // TODO Phase 12B: Parse TS for H.264/AVC streams
-    // For now, return an error indicating this is not yet implemented
-    Err(BitvueError::UnsupportedCodec(
-        "H.264/AVC TS parsing not yet implemented (Phase 12B)".to_string(),
-    ))
+    extract_codec_samples(data, TsVideoCodec::Avc)
 }
 
-/// Extract H.265/HEVC samples from TS file
-///
-/// # Note
-///
-/// Phase 12A: Basic implementation. Full H.265/HEVC TS parsing requires:
-/// - PMT stream type detection (0x24 for HEVC)
-/// - NAL unit extraction from PES packets
-/// - Annex B start code handling
-///
-/// TODO Phase 12C: Implement full H.265/HEVC TS parsing with stream type detection
+/// Extract H.265/HEVC samples (Annex B access units) from TS file
 pub fn extract_hevc_samples(data: &[u8]) -> Result<Vec<Vec<u8>>> {
-    let _info = parse_ts(data)?;
+    extract_codec_samples(data, TsVideoCodec::Hevc)
+}
+
+/// Extract H.266/VVC samples (Annex B access units) from TS file
+pub fn extract_vvc_samples(data: &[u8]) -> Result<Vec<Vec<u8>>> {
+    extract_codec_samples(data, TsVideoCodec::Vvc)
+}
 
-    // TODO Phase 12C: Parse TS for H.265/HEVC streams
-    // For now, return an error indicating this is not yet implemented
-    Err(BitvueError::UnsupportedCodec(
-        "H.265/HEVC TS parsing not yet implemented (Phase 12C)".to_string(),
-    ))
+/// Extract MPEG-2 video samples from TS file
+pub fn extract_mpeg2_samples(data: &[u8]) -> Result<Vec<Vec<u8>>> {
+    extract_codec_samples(data, TsVideoCodec::Mpeg2)
+}
+
+/// Extract the samples of the first video stream of the given codec
+///
+/// Returns no samples if the TS carries no such stream.
+pub fn extract_codec_samples(data: &[u8], codec: TsVideoCodec) -> Result<Vec<Vec<u8>>> {
+    let streams = video_streams(data)?;
+    match streams.iter().find(|s| s.codec == codec) {
+        Some(stream) => Ok(parse_ts_pid(data, stream.pid)?.samples),
+        None => Ok(Vec::new()),
+    }
 }
 
 /// Extract PAT and PMT entries from TS data
 ///
 /// First pass through TS packets to find Program Association Table (PAT)
 /// and Program Map Table (PMT) which contain stream mapping information.
-fn extract_pat_pmt(data: &[u8]) -> Result<(Vec<PatEntry>, Vec<PmtStream>)> {
-    let mut pat_entries = Vec::new();
-    let mut pmt_streams = Vec::new();
+/// Only the first PMT of each program is used.
+fn extract_pat_pmt(data: &[u8]) -> Result<(Vec<PatEntry>, Vec<PmtProgram>)> {
+    let mut pat_entries: Vec<PatEntry> = Vec::new();
+    let mut programs: Vec<PmtProgram> = Vec::new();
 
     let mut offset = 0;
     while offset + TS_PACKET_SIZE <= data.len() {
         let packet_data = &data[offset..offset + TS_PACKET_SIZE];
         let packet = parse_ts_packet(packet_data)?;
+        offset += TS_PACKET_SIZE;
+
+        if packet.payload.is_empty() {
+            continue;
+        }
 
         // Extract PAT (Program Association Table)
-        if packet.pid == PAT_PID && !packet.payload.is_empty() {
-            pat_entries = parse_pat(&packet.payload, packet.payload_unit_start)?;
+        if packet.pid == PAT_PID {
+            let entries = parse_pat(&packet.payload, packet.payload_unit_start)?;
+            if !entries.is_empty() {
+                pat_entries = entries;
+            }
         }
         // Extract PMT (Program Map Table) using PAT entries
-        else if !pat_entries.is_empty() {
-            for pat in &pat_entries {
-                if packet.pid == pat.pmt_pid && !packet.payload.is_empty() {
-                    pmt_streams = parse_pmt(&packet.payload, packet.payload_unit_start)?;
-                    break;
-                }
+        else if let Some(pat) = pat_entries.iter().find(|pat| {
+            packet.pid == pat.pmt_pid
+                && programs
+                    .iter()
+                    .all(|p| p.program_number != pat.program_number)
+        }) {
+            let streams = parse_pmt(&packet.payload, packet.payload_unit_start)?;
+            if !streams.is_empty() {
+                programs.push(PmtProgram {
+                    program_number: pat.program_number,
+                    streams,
+                });
             }
         }
-
-        offset += TS_PACKET_SIZE;
     }
 
-    Ok((pat_entries, pmt_streams))
+    Ok((pat_entries, programs))
 }
 
-/// Find the video stream PID from PMT streams
+/// List the video elementary streams of all programs
 ///
-/// Searches through PMT streams to find AV1 video stream.
-/// Returns None if no AV1 stream is found.
-fn find_video_pid(pmt_streams: &[PmtStream]) -> Option<u16> {
-    pmt_streams
+/// Streams are listed program by program in the order their PMTs appear,
+/// and in PMT order within a program.
+pub fn video_streams(data: &[u8]) -> Result<Vec<TsVideoStream>> {
+    let (_pat_entries, programs) = extract_pat_pmt(data)?;
+
+    Ok(programs
         .iter()
-        .find(|stream| stream.stream_type == STREAM_TYPE_AV1)
-        .map(|stream| stream.elementary_pid)
+        .flat_map(|program| {
+            program.streams.iter().filter_map(move |stream| {
+                Some(TsVideoStream {
+                    program_number: program.program_number,
+                    pid: stream.elementary_pid,
+                    stream_type: stream.stream_type,
+                    codec: TsVideoCodec::from_pmt_stream(stream)?,
+                })
+            })
+        })
+        .collect())
+}
+
+/// Video samples of one PID, with their timestamps
+#[derive(Debug, Default)]
+struct PesStream {
+    samples: Vec<Vec<u8>>,
+    timestamps: Vec<Option<u64>>,
+    decode_timestamps: Vec<Option<u64>>,
+    continuity_errors: usize,
+}
+
+impl PesStream {
+    /// Parse an assembled PES packet and append its payload
+    fn push(&mut self, buffer: &[u8]) {
+        if let Ok(pes) = parse_pes(buffer) {
+            self.samples.push(pes.payload);
+            self.timestamps.push(pes.pts);
+            self.decode_timestamps.push(pes.dts.or(pes.pts));
+        }
+    }
 }
 
 /// Extract PES packets from TS data for a specific PID
 ///
 /// Second pass through TS packets to extract PES (Packetized Elementary Stream)
-/// packets for the specified video PID.
-fn extract_pes_packets(data: &[u8], video_pid: u16) -> Result<(Vec<Vec<u8>>, Vec<u64>)> {
-    let mut pes_buffers: HashMap<u16, Vec<u8>> = HashMap::new();
-    let mut samples = Vec::new();
-    let mut timestamps = Vec::new();
+/// packets for the specified video PID. Duplicate packets are skipped. A
+/// continuity counter gap drops the PES packet being assembled, since part of
+/// it was lost, and assembly resumes at the next payload unit start.
+fn extract_pes_packets(data: &[u8], video_pid: u16) -> Result<PesStream> {
+    let mut stream = PesStream::default();
+    let mut buffer: Option<Vec<u8>> = None;
+    let mut last_cc: Option<u8> = None;
 
     let mut offset = 0;
     while offset + TS_PACKET_SIZE <= data.len() {
         let packet_data = &data[offset..offset + TS_PACKET_SIZE];
         let packet = parse_ts_packet(packet_data)?;
+        offset += TS_PACKET_SIZE;
+
+        if packet.pid != video_pid || !packet.has_payload() {
+            continue;
+        }
 
-        if packet.pid == video_pid {
-            if packet.payload_unit_start {
-                // New PES packet starts - flush previous buffer
-                if let Some(buffer) = pes_buffers.remove(&video_pid) {
-                    if let Ok(pes) = parse_pes(&buffer) {
-                        samples.push(pes.payload);
-                        timestamps.push(pes.pts.unwrap_or(0));
-                    }
-                }
-                pes_buffers.insert(video_pid, packet.payload);
-            } else {
-                // Continue current PES packet
-                pes_buffers
-                    .entry(video_pid)
-                    .or_default()
-                    .extend_from_slice(&packet.payload);
+        if packet.discontinuity {
+            last_cc = None;
+        }
+        match last_cc {
+            Some(prev) if packet.continuity_counter == prev => continue,
+            Some(prev) if packet.continuity_counter != (prev + 1) & 0x0F => {
+                stream.continuity_errors += 1;
+                buffer = None;
             }
+            _ => {}
         }
+        last_cc = Some(packet.continuity_counter);
 
-        offset += TS_PACKET_SIZE;
+        if packet.payload_unit_start {
+            // New PES packet starts - flush previous buffer
+            if let Some(previous) = buffer.replace(packet.payload) {
+                stream.push(&previous);
+            }
+        } else if let Some(current) = buffer.as_mut() {
+            // Continue current PES packet
+            current.extend_from_slice(&packet.payload);
+        }
     }
 
     // Process remaining buffer
-    if let Some(buffer) = pes_buffers.remove(&video_pid) {
-        if let Ok(pes) = parse_pes(&buffer) {
-            samples.push(pes.payload);
-            timestamps.push(pes.pts.unwrap_or(0));
-        }
+    if let Some(remaining) = buffer {
+        stream.push(&remaining);
     }
 
-    Ok((samples, timestamps))
+    Ok(stream)
 }
 
-/// Parse TS file and extract AV1 video stream
+/// Parse TS file and extract its first video stream
 pub fn parse_ts(data: &[u8]) -> Result<TsInfo> {
-    // First pass: extract PAT and PMT
-    let (_pat_entries, pmt_streams) = extract_pat_pmt(data)?;
-
-    // Find AV1 video stream PID
-    let video_pid = match find_video_pid(&pmt_streams) {
-        Some(pid) => pid,
-        None => {
-            return Ok(TsInfo {
-                video_pid: None,
-                sample_count: 0,
-                samples: Vec::new(),
-                timestamps: Vec::new(),
-            });
-        }
-    };
+    let video_streams = video_streams(data)?;
+    match video_streams.first() {
+        Some(stream) => parse_ts_pid(data, stream.pid),
+        None => Ok(TsInfo {
+            video_streams,
+            video_pid: None,
+            codec: None,
+            sample_count: 0,
+            samples: Vec::new(),
+            timestamps: Vec::new(),
+            decode_timestamps: Vec::new(),
+            continuity_errors: 0,
+        }),
+    }
+}
 
-    // Second pass: extract PES packets
-    let (samples, timestamps) = extract_pes_packets(data, video_pid)?;
+/// Parse TS file and extract the video stream on the given PID
+///
+/// Selects one of several video streams, e.g. from [`video_streams`].
+pub fn parse_ts_pid(data: &[u8], pid: u16) -> Result<TsInfo> {
+    let video_streams = video_streams(data)?;
+    let codec = video_streams
+        .iter()
+        .find(|s| s.pid == pid)
+        .map(|s| s.codec)
+        .ok_or_else(|| {
+            BitvueError::InvalidData(format!("PID 0x{:04X} is not a video stream", pid))
+        })?;
+
+    let stream = extract_pes_packets(data, pid)?;
 
     Ok(TsInfo {
-        video_pid: Some(video_pid),
-        sample_count: samples.len(),
-        samples,
-        timestamps,
+        video_streams,
+        video_pid: Some(pid),
+        codec: Some(codec),
+        sample_count: stream.samples.len(),
+        samples: stream.samples,
+        timestamps: stream.timestamps,
+        decode_timestamps: stream.decode_timestamps,
+        continuity_errors: stream.continuity_errors,
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_fixtures::ts::{packet, pes, section};
 
     #[test]
     fn test_is_ts() {
@@ -698,10 +912,14 @@ mod tests {
     fn test_ts_info() {
         // Test empty TS info
         let info = TsInfo {
+            video_streams: Vec::new(),
             video_pid: None,
+            codec: None,
             sample_count: 0,
             samples: Vec::new(),
             timestamps: Vec::new(),
+            decode_timestamps: Vec::new(),
+            continuity_errors: 0,
         };
         assert_eq!(info.video_pid, None);
         assert_eq!(info.sample_count, 0);
@@ -724,4 +942,151 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap().len(), 0);
     }
+
+    #[test]
+    fn test_pes_without_pts() {
+        let mut data = two_program_psi();
+        data.extend(packet(
+            0x100,
+            true,
+            0,
+            None,
+            &pes(Some(3600), None, &[0x65]),
+        ));
+        data.extend(packet(0x100, true, 1, None, &pes(None, None, &[0x41])));
+
+        let info = parse_ts(&data).unwrap();
+        assert_eq!(info.sample_count, 2);
+        assert_eq!(info.timestamps, [Some(3600), None]);
+        assert_eq!(info.decode_timestamps, [Some(3600), None]);
+    }
+
+    /// Two programs: H.264 plus an AC-3 private stream, then H.265
+    fn two_program_psi() -> Vec<u8> {
+        let mut data = packet(
+            PAT_PID,
+            true,
+            0,
+            None,
+            &section(
+                0x00,
+                &[0, 1, 0xC1, 0, 0, 0, 1, 0xF0, 0x00, 0, 2, 0xF0, 0x01],
+            ),
+        );
+        let program_1 = [
+            0,
+            1,
+            0xC1,
+            0,
+            0,
+            0xE1,
+            0x00,
+            0xF0,
+            0x00, // header, PCR PID, no program info
+            STREAM_TYPE_AVC,
+            0xE1,
+            0x00,
+            0xF0,
+            0x00, // PID 0x100
+            STREAM_TYPE_AV1,
+            0xE1,
+            0x02,
+            0xF0,
+            0x06, // PID 0x102, registration "AC-3"
+            REGISTRATION_DESCRIPTOR_TAG,
+            4,
+            b'A',
+            b'C',
+            b'-',
+            b'3',
+        ];
+        let program_2 = [
+            0,
+            2,
+            0xC1,
+            0,
+            0,
+            0xE1,
+            0x01,
+            0xF0,
+            0x00, //
+            STREAM_TYPE_HEVC,
+            0xE1,
+            0x01,
+            0xF0,
+            0x00, // PID 0x101
+        ];
+        data.extend(packet(0x1000, true, 0, None, &section(0x02, &program_1)));
+        data.extend(packet(0x1001, true, 0, None, &section(0x02, &program_2)));
+        data
+    }
+
+    #[test]
+    fn test_video_streams_by_program() {
+        let data = two_program_psi();
+        let streams = video_streams(&data).unwrap();
+        assert_eq!(
+            streams,
+            vec![
+                TsVideoStream {
+                    program_number: 1,
+                    pid: 0x100,
+                    stream_type: STREAM_TYPE_AVC,
+                    codec: TsVideoCodec::Avc,
+                },
+                TsVideoStream {
+                    program_number: 2,
+                    pid: 0x101,
+                    stream_type: STREAM_TYPE_HEVC,
+                    codec: TsVideoCodec::Hevc,
+                },
+            ]
+        );
+        assert!(parse_ts_pid(&data, 0x102).is_err());
+    }
+
+    #[test]
+    fn test_pes_reassembly_with_continuity_gap() {
+        let mut data = two_program_psi();
+        let first = pes(Some(3600), Some(0), &[0x00, 0x00, 0x01, 0x09, 0xF0]);
+        let long_es = vec![0xAB; 300];
+        let second = pes(Some(7200), None, &long_es);
+        let (head, tail) = second.split_at(184);
+
+        // AU 1; AU 2 across two packets, the second one duplicated
+        data.extend(packet(0x100, true, 0, None, &first));
+        data.extend(packet(0x100, true, 1, None, head));
+        data.extend(packet(0x100, false, 2, None, tail));
+        data.extend(packet(0x100, false, 2, None, tail));
+        // AU 3 loses its continuation packet (CC 4)
+        data.extend(packet(0x100, true, 3, None, head));
+        data.extend(packet(0x100, false, 5, None, tail));
+        // AU 4 on the other program
+        data.extend(packet(0x101, true, 0, None, &pes(Some(90), None, &[0x26])));
+        // AU 5 recovers at the next payload unit start
+        data.extend(packet(
+            0x100,
+            true,
+            6,
+            None,
+            &pes(Some(10800), None, &[0x65]),
+        ));
+
+        let info = parse_ts(&data).unwrap();
+        assert_eq!(info.video_pid, Some(0x100));
+        assert_eq!(info.codec, Some(TsVideoCodec::Avc));
+        assert_eq!(info.continuity_errors, 1);
+        assert_eq!(info.sample_count, 3);
+        assert_eq!(info.samples[0], [0x00, 0x00, 0x01, 0x09, 0xF0]);
+        assert_eq!(info.samples[1], long_es);
+        assert_eq!(info.samples[2], [0x65]);
+        assert_eq!(info.timestamps, [Some(3600), Some(7200), Some(10800)]);
+        assert_eq!(info.decode_timestamps, [Some(0), Some(7200), Some(10800)]);
+
+        let hevc = parse_ts_pid(&data, 0x101).unwrap();
+        assert_eq!(hevc.codec, Some(TsVideoCodec::Hevc));
+        assert_eq!(hevc.samples, [vec![0x26]]);
+        assert_eq!(extract_hevc_samples(&data).unwrap(), [vec![0x26]]);
+        assert!(extract_vvc_samples(&data).unwrap().is_empty());
+    }
 }
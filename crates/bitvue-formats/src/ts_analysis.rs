@@ -0,0 +1,1401 @@
+//! MPEG-2 Transport Stream transport-layer analysis
+//!
+//! Walks every TS packet and measures what the demuxer in [`crate::ts`]
+//! skips over: PCR timing, continuity counters, PSI repetition, transport
+//! errors, per-PID bitrates and the PTS/DTS to PCR buffer delay. Findings
+//! follow the priority 1 and 2 indicators of ETSI TR 101 290 (ETR 290).
+//!
+//! Packet times come from the first PID carrying a PCR: the PCRs anchor a
+//! clock that is interpolated by byte position in between.
+//!
+//! Reference: ISO/IEC 13818-1 (MPEG-2 Systems), ETSI TR 101 290
+
+use crate::ts::{
+    parse_pat, parse_pes, parse_pmt, parse_ts_packet, TsPacket, TsVideoCodec, PAT_PID,
+    TS_PACKET_SIZE, TS_SYNC_BYTE,
+};
+use bitvue_core::diagnostics::{Diagnostic, DiagnosticCategory, DiagnosticSeverity};
+use bitvue_core::{FrameKey, Lane, LaneType, Result, StreamId};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// CAT (Conditional Access Table) PID
+const CAT_PID: u16 = 0x0001;
+
+/// Null packet PID
+const NULL_PID: u16 = 0x1FFF;
+
+/// PCR clock frequency (Hz)
+const PCR_CLOCK: f64 = 27_000_000.0;
+
+/// PCR values wrap at 2^33 × 300
+const PCR_WRAP: u64 = (1 << 33) * 300;
+
+/// PAT and PMT sections must repeat at least this often (s)
+const PSI_REPETITION_LIMIT: f64 = 0.5;
+
+/// PCRs must repeat at least this often (s)
+const PCR_REPETITION_LIMIT: f64 = 0.040;
+
+/// Largest step between consecutive PCRs without a discontinuity (s)
+const PCR_DISCONTINUITY_LIMIT: f64 = 0.100;
+
+/// Largest PCR deviation from the constant-rate clock (ns)
+const PCR_ACCURACY_LIMIT_NS: f64 = 500.0;
+
+/// PTSs must repeat at least this often (s)
+const PTS_REPETITION_LIMIT: f64 = 0.700;
+
+/// Longest absence of a PID referenced by a PMT (s); ETR 290 leaves this
+/// period to the user
+const PID_TIMEOUT: f64 = 5.0;
+
+/// Longest delay of data through the T-STD buffers (s)
+const BUFFER_DELAY_LIMIT: f64 = 1.0;
+
+/// Largest PCR step the packet clock follows (s); longer steps are taken as
+/// discontinuities
+const MAX_CLOCK_STEP: f64 = 1.0;
+
+/// Length of the windows PID bitrates are measured over (s)
+pub const BITRATE_WINDOW: f64 = 1.0;
+
+/// A transport stream check
+///
+/// All but [`TsCheck::BufferDelay`] are ETR 290 priority 1 and 2 indicators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TsCheck {
+    /// 1.1: two or more consecutive corrupted sync bytes
+    SyncLoss,
+    /// 1.2: sync byte other than 0x47
+    SyncByte,
+    /// 1.3: PAT missing, late, scrambled or with a wrong table_id
+    Pat,
+    /// 1.4: lost, duplicated or out-of-order packets
+    ContinuityCount,
+    /// 1.5: PMT missing, late or scrambled
+    Pmt,
+    /// 1.6: PID referenced by a PMT absent for too long
+    Pid,
+    /// 2.1: transport_error_indicator set
+    Transport,
+    /// 2.2: CRC error in a PAT, CAT or PMT section
+    Crc,
+    /// 2.3a: PCRs more than 40 ms apart
+    PcrRepetition,
+    /// 2.3b: PCR jump without a discontinuity_indicator
+    PcrDiscontinuity,
+    /// 2.4: PCR off the constant-rate clock by more than 500 ns
+    PcrAccuracy,
+    /// 2.5: PTSs more than 700 ms apart
+    Pts,
+    /// 2.6: scrambled packets without a CAT
+    Cat,
+    /// ISO/IEC 13818-1 T-STD: data decoded before it arrives, or buffered
+    /// for more than one second
+    BufferDelay,
+}
+
+impl TsCheck {
+    /// ETR 290 priority, if this is an ETR 290 indicator
+    pub fn priority(&self) -> Option<u8> {
+        match self {
+            TsCheck::SyncLoss
+            | TsCheck::SyncByte
+            | TsCheck::Pat
+            | TsCheck::ContinuityCount
+            | TsCheck::Pmt
+            | TsCheck::Pid => Some(1),
+            TsCheck::Transport
+            | TsCheck::Crc
+            | TsCheck::PcrRepetition
+            | TsCheck::PcrDiscontinuity
+            | TsCheck::PcrAccuracy
+            | TsCheck::Pts
+            | TsCheck::Cat => Some(2),
+            TsCheck::BufferDelay => None,
+        }
+    }
+
+    /// Indicator number and name, as in ETR 290
+    pub fn label(&self) -> &'static str {
+        match self {
+            TsCheck::SyncLoss => "1.1 TS_sync_loss",
+            TsCheck::SyncByte => "1.2 Sync_byte_error",
+            TsCheck::Pat => "1.3 PAT_error",
+            TsCheck::ContinuityCount => "1.4 Continuity_count_error",
+            TsCheck::Pmt => "1.5 PMT_error",
+            TsCheck::Pid => "1.6 PID_error",
+            TsCheck::Transport => "2.1 Transport_error",
+            TsCheck::Crc => "2.2 CRC_error",
+            TsCheck::PcrRepetition => "2.3a PCR_repetition_error",
+            TsCheck::PcrDiscontinuity => "2.3b PCR_discontinuity_indicator_error",
+            TsCheck::PcrAccuracy => "2.4 PCR_accuracy_error",
+            TsCheck::Pts => "2.5 PTS_error",
+            TsCheck::Cat => "2.6 CAT_error",
+            TsCheck::BufferDelay => "T-STD buffer delay",
+        }
+    }
+
+    /// Severity of a finding: priority 1 indicators are errors, the rest
+    /// warnings
+    pub fn severity(&self) -> DiagnosticSeverity {
+        match self.priority() {
+            Some(1) => DiagnosticSeverity::Error,
+            _ => DiagnosticSeverity::Warn,
+        }
+    }
+}
+
+/// One transport stream finding
+#[derive(Debug, Clone, PartialEq)]
+pub struct TsError {
+    /// File offset of the packet
+    pub offset: u64,
+    /// PID of the packet; none for sync errors
+    pub pid: Option<u16>,
+    /// Failed check
+    pub check: TsCheck,
+    /// Description
+    pub message: String,
+}
+
+/// One PCR
+#[derive(Debug, Clone, PartialEq)]
+pub struct PcrSample {
+    /// File offset of the packet
+    pub offset: u64,
+    /// PID carrying the PCR
+    pub pid: u16,
+    /// PCR (27 MHz)
+    pub pcr: u64,
+    /// discontinuity_indicator of the packet
+    pub discontinuity: bool,
+    /// Transport time since the previous PCR of the PID (ms)
+    pub interval_ms: Option<f64>,
+    /// Deviation from the constant-rate clock (ns), the PCR jitter
+    pub accuracy_ns: Option<f64>,
+}
+
+/// Packet statistics of one PID
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PidStats {
+    pub pid: u16,
+    pub packets: usize,
+    pub continuity_errors: usize,
+    pub transport_errors: usize,
+    pub scrambled_packets: usize,
+    /// Bitrate (bit/s) over consecutive [`BITRATE_WINDOW`]s of transport
+    /// time; empty without a PCR clock
+    pub bitrate: Vec<f64>,
+}
+
+/// Repetition of the sections of one PSI table
+#[derive(Debug, Clone, PartialEq)]
+pub struct PsiRepetition {
+    pub pid: u16,
+    pub table_id: u8,
+    /// Number of complete sections
+    pub sections: usize,
+    /// Transport time between consecutive sections (ms)
+    pub intervals_ms: Vec<f64>,
+}
+
+impl PsiRepetition {
+    /// Longest interval between sections (ms)
+    pub fn max_interval_ms(&self) -> Option<f64> {
+        self.intervals_ms.iter().copied().reduce(f64::max)
+    }
+
+    /// Mean interval between sections (ms)
+    pub fn mean_interval_ms(&self) -> Option<f64> {
+        if self.intervals_ms.is_empty() {
+            return None;
+        }
+        Some(self.intervals_ms.iter().sum::<f64>() / self.intervals_ms.len() as f64)
+    }
+}
+
+/// Timing of one PES packet
+#[derive(Debug, Clone, PartialEq)]
+pub struct PesTiming {
+    /// File offset of the packet starting the PES packet
+    pub offset: u64,
+    pub pid: u16,
+    /// Transport time of arrival (s)
+    pub time: Option<f64>,
+    /// Presentation timestamp (90 kHz)
+    pub pts: Option<u64>,
+    /// Decode timestamp (90 kHz)
+    pub dts: Option<u64>,
+    /// DTS (or PTS) minus the PCR clock at arrival (ms)
+    pub buffer_delay_ms: Option<f64>,
+    /// A continuity error interrupted the PES packet; the demuxer drops
+    /// such packets
+    pub lost: bool,
+}
+
+/// Transport-layer analysis of a TS file
+#[derive(Debug, Clone, Default)]
+pub struct TsAnalysis {
+    /// Number of packets read
+    pub packet_count: usize,
+    /// PID whose PCRs drive the packet clock
+    pub pcr_pid: Option<u16>,
+    /// Mean transport rate (bit/s)
+    pub bitrate: Option<f64>,
+    /// Transport time covered by the PCR clock (s)
+    pub duration: Option<f64>,
+    /// First video PID, as selected by [`crate::ts::parse_ts`]
+    pub video_pid: Option<u16>,
+    /// Per-PID statistics, by PID
+    pub pids: Vec<PidStats>,
+    /// PCRs of all PIDs, in file order
+    pub pcrs: Vec<PcrSample>,
+    /// PAT, CAT and PMT repetition
+    pub psi: Vec<PsiRepetition>,
+    /// PES packets of the PIDs referenced by PMTs, in file order
+    pub pes: Vec<PesTiming>,
+    /// Findings, in file order
+    pub errors: Vec<TsError>,
+}
+
+impl TsAnalysis {
+    /// Findings of one ETR 290 priority
+    pub fn errors_with_priority(&self, priority: u8) -> impl Iterator<Item = &TsError> {
+        self.errors
+            .iter()
+            .filter(move |e| e.check.priority() == Some(priority))
+    }
+
+    /// File offsets of the video access units, as demuxed by
+    /// [`crate::ts::parse_ts`]
+    fn access_units(&self) -> Vec<u64> {
+        self.pes
+            .iter()
+            .filter(|p| Some(p.pid) == self.video_pid && !p.lost)
+            .map(|p| p.offset)
+            .collect()
+    }
+
+    /// Findings as container diagnostics
+    ///
+    /// Findings after the first video access unit reference the access unit
+    /// being transmitted at the time.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let access_units = self.access_units();
+        self.errors
+            .iter()
+            .map(|error| {
+                let mut diagnostic = Diagnostic::new(
+                    0,
+                    error.check.severity(),
+                    StreamId::A,
+                    format!("{}: {}", error.check.label(), error.message),
+                    DiagnosticCategory::Container,
+                    error.offset,
+                )
+                .with_detail("check".to_string(), error.check.label().to_string());
+                if let Some(priority) = error.check.priority() {
+                    diagnostic =
+                        diagnostic.with_detail("etr290_priority".to_string(), priority.to_string());
+                }
+                if let Some(pid) = error.pid {
+                    diagnostic =
+                        diagnostic.with_detail("pid".to_string(), format!("0x{:04X}", pid));
+                }
+                if let Some(frame_index) = access_unit_at(&access_units, error.offset) {
+                    diagnostic = diagnostic.with_frame(FrameKey {
+                        stream: StreamId::A,
+                        frame_index,
+                        pts: None,
+                    });
+                }
+                diagnostic
+            })
+            .collect()
+    }
+
+    /// Timeline lanes, indexed by video access unit
+    ///
+    /// - [`LaneType::PcrAccuracy`]: largest PCR deviation of the clock PID
+    ///   while the access unit is transmitted (ns)
+    /// - [`LaneType::PidBitrate`]: video PID bitrate in the window the access
+    ///   unit starts in (Mbps)
+    /// - [`LaneType::BufferDelay`]: buffer delay of the access unit (ms)
+    /// - [`LaneType::TransportErrors`]: findings while the access unit is
+    ///   transmitted
+    pub fn lanes(&self) -> Vec<Lane> {
+        let access_units = self.access_units();
+
+        let mut accuracy = Lane::new(LaneType::PcrAccuracy);
+        let mut worst: BTreeMap<usize, f64> = BTreeMap::new();
+        for sample in self.pcrs.iter().filter(|s| Some(s.pid) == self.pcr_pid) {
+            if let (Some(index), Some(ns)) = (
+                access_unit_at(&access_units, sample.offset),
+                sample.accuracy_ns,
+            ) {
+                let entry = worst.entry(index).or_insert(0.0);
+                *entry = entry.max(ns.abs());
+            }
+        }
+        for (index, ns) in worst {
+            accuracy.add_point(index, ns as f32);
+        }
+
+        let mut bitrate = Lane::new(LaneType::PidBitrate);
+        let mut delay = Lane::new(LaneType::BufferDelay);
+        let video = self
+            .pes
+            .iter()
+            .filter(|p| Some(p.pid) == self.video_pid && !p.lost);
+        let stats = self.pids.iter().find(|s| Some(s.pid) == self.video_pid);
+        for (index, pes) in video.enumerate() {
+            if let Some(ms) = pes.buffer_delay_ms {
+                delay.add_point(index, ms as f32);
+            }
+            let window = pes.time.map(|t| (t / BITRATE_WINDOW).max(0.0) as usize);
+            if let Some(bps) = stats.zip(window).and_then(|(s, w)| s.bitrate.get(w)) {
+                bitrate.add_point(index, (bps / 1e6) as f32);
+            }
+        }
+
+        let mut errors = Lane::new(LaneType::TransportErrors);
+        let mut counts = vec![0usize; access_units.len()];
+        for error in &self.errors {
+            if let Some(index) = access_unit_at(&access_units, error.offset) {
+                counts[index] += 1;
+            }
+        }
+        for (index, count) in counts.into_iter().enumerate() {
+            errors.add_point(index, count as f32);
+        }
+
+        vec![accuracy, bitrate, delay, errors]
+    }
+}
+
+/// Index of the access unit being transmitted at a file offset
+fn access_unit_at(access_units: &[u64], offset: u64) -> Option<usize> {
+    access_units
+        .partition_point(|&start| start <= offset)
+        .checked_sub(1)
+}
+
+/// Difference of two PCRs, modulo the PCR wrap, as a signed tick count
+fn pcr_delta(from: u64, to: u64) -> i64 {
+    let delta = (to + PCR_WRAP - from % PCR_WRAP) % PCR_WRAP;
+    if delta > PCR_WRAP / 2 {
+        delta as i64 - PCR_WRAP as i64
+    } else {
+        delta as i64
+    }
+}
+
+/// A PCR of the clock PID
+#[derive(Debug, Clone)]
+struct Anchor {
+    offset: u64,
+    pcr: u64,
+    /// Transport time (s)
+    time: f64,
+    /// PCR ticks per byte up to the next anchor
+    ticks_per_byte: f64,
+}
+
+/// Packet clock of a transport stream, from the PCRs of one PID
+///
+/// Between PCRs the clock runs at the byte rate of the surrounding pair. A
+/// PCR discontinuity keeps the transport time running at the previous rate.
+#[derive(Debug, Clone)]
+struct Clock {
+    anchors: Vec<Anchor>,
+}
+
+impl Clock {
+    /// Build a clock from (offset, PCR, discontinuity) triples; needs two
+    /// continuous PCRs
+    fn new(samples: impl Iterator<Item = (u64, u64, bool)>) -> Option<Self> {
+        let samples: Vec<_> = samples.collect();
+
+        // Rate of each continuous pair, by its first PCR
+        let mut rates: Vec<Option<f64>> = samples
+            .windows(2)
+            .map(|pair| {
+                let (from, to) = (pair[0], pair[1]);
+                let ticks = pcr_delta(from.1, to.1);
+                let continuous = !to.2
+                    && ticks > 0
+                    && (ticks as f64) / PCR_CLOCK <= MAX_CLOCK_STEP
+                    && to.0 > from.0;
+                continuous.then(|| ticks as f64 / (to.0 - from.0) as f64)
+            })
+            .collect();
+        let first_rate = rates.iter().flatten().next().copied()?;
+        // Discontinuous pairs and the last PCR run at the preceding rate
+        rates.push(None);
+        let mut rate = first_rate;
+        for slot in rates.iter_mut() {
+            match slot {
+                Some(r) => rate = *r,
+                None => *slot = Some(rate),
+            }
+        }
+
+        let mut anchors: Vec<Anchor> = Vec::with_capacity(samples.len());
+        for (&(offset, pcr, _), ticks_per_byte) in samples.iter().zip(rates.into_iter().flatten()) {
+            let time = match anchors.last() {
+                None => 0.0,
+                Some(prev) => {
+                    prev.time + prev.ticks_per_byte * (offset - prev.offset) as f64 / PCR_CLOCK
+                }
+            };
+            anchors.push(Anchor {
+                offset,
+                pcr,
+                time,
+                ticks_per_byte,
+            });
+        }
+        Some(Self { anchors })
+    }
+
+    /// Anchor governing a file offset: the last one at or before it, or the
+    /// first one
+    fn anchor(&self, offset: u64) -> &Anchor {
+        let index = self
+            .anchors
+            .partition_point(|a| a.offset <= offset)
+            .saturating_sub(1);
+        &self.anchors[index]
+    }
+
+    /// Transport time of a file offset (s); negative before the first PCR
+    fn time(&self, offset: u64) -> f64 {
+        let anchor = self.anchor(offset);
+        anchor.time + anchor.ticks_per_byte * (offset as f64 - anchor.offset as f64) / PCR_CLOCK
+    }
+
+    /// System time clock at a file offset (27 MHz, wrapped)
+    fn stc(&self, offset: u64) -> u64 {
+        let anchor = self.anchor(offset);
+        let ticks = (anchor.ticks_per_byte * (offset as f64 - anchor.offset as f64)).round() as i64;
+        (anchor.pcr as i64 + ticks).rem_euclid(PCR_WRAP as i64) as u64
+    }
+
+    /// Transport time at the last PCR (s)
+    fn duration(&self) -> f64 {
+        self.anchors.last().map_or(0.0, |a| a.time)
+    }
+}
+
+/// CRC-32/MPEG-2 of a PSI section; zero over a whole section with its CRC
+pub(crate) fn crc32_mpeg2(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= (byte as u32) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Read packets at the 188-byte stride, resynchronising after a sync loss
+///
+/// Returns the packets with their file offsets, and the sync findings.
+fn read_packets(data: &[u8]) -> (Vec<(u64, TsPacket)>, Vec<TsError>) {
+    let mut packets = Vec::new();
+    let mut errors = Vec::new();
+    let mut corrupted = 0;
+    let mut offset = 0;
+
+    while offset + TS_PACKET_SIZE <= data.len() {
+        if data[offset] != TS_SYNC_BYTE {
+            corrupted += 1;
+            errors.push(TsError {
+                offset: offset as u64,
+                pid: None,
+                check: TsCheck::SyncByte,
+                message: format!("sync byte 0x{:02X}", data[offset]),
+            });
+            if corrupted < 2 {
+                offset += TS_PACKET_SIZE;
+                continue;
+            }
+
+            errors.push(TsError {
+                offset: offset as u64,
+                pid: None,
+                check: TsCheck::SyncLoss,
+                message: format!("{} consecutive corrupted sync bytes", corrupted),
+            });
+            // Resume where two sync bytes follow each other at the packet
+            // stride, or at a final sync byte
+            match (offset + 1..data.len()).find(|&o| {
+                data[o] == TS_SYNC_BYTE
+                    && data
+                        .get(o + TS_PACKET_SIZE)
+                        .is_none_or(|&b| b == TS_SYNC_BYTE)
+            }) {
+                Some(next) => offset = next,
+                None => break,
+            }
+            corrupted = 0;
+            continue;
+        }
+        corrupted = 0;
+
+        // Packets whose adaptation field overruns are unreadable; skip them
+        if let Ok(packet) = parse_ts_packet(&data[offset..offset + TS_PACKET_SIZE]) {
+            packets.push((offset as u64, packet));
+        }
+        offset += TS_PACKET_SIZE;
+    }
+
+    (packets, errors)
+}
+
+/// Continuity counter state of one PID
+#[derive(Debug, Clone, Copy)]
+struct Continuity {
+    last: u8,
+    repeated: bool,
+}
+
+/// Analysis state, updated packet by packet
+struct Analyzer {
+    clock: Option<Clock>,
+    analysis: TsAnalysis,
+    stats: BTreeMap<u16, PidStats>,
+    window_bytes: BTreeMap<u16, Vec<u64>>,
+    continuity: HashMap<u16, Continuity>,
+    /// PSI sections being assembled, by PID
+    sections: HashMap<u16, Vec<u8>>,
+    /// PMT PIDs of the latest PAT
+    pmt_pids: HashSet<u16>,
+    /// Elementary stream PIDs of all PMTs
+    es_pids: HashSet<u16>,
+    /// Time of the last section of each PSI table, by (PID, table_id)
+    last_section: HashMap<(u16, u8), f64>,
+    /// Time each referenced PID was last seen (or first referenced)
+    last_seen: HashMap<u16, (u64, f64)>,
+    /// Time of the last PTS of each PID
+    last_pts: HashMap<u16, f64>,
+    /// Last PCR of each PID: offset and value
+    last_pcr: HashMap<u16, (u64, u64)>,
+    /// Index in `analysis.pes` of the PES packet being received, by PID
+    open_pes: HashMap<u16, usize>,
+    cat_seen: bool,
+    first_scrambled: Option<(u64, u16)>,
+}
+
+impl Analyzer {
+    fn time(&self, offset: u64) -> Option<f64> {
+        self.clock.as_ref().map(|c| c.time(offset))
+    }
+
+    fn error(&mut self, offset: u64, pid: u16, check: TsCheck, message: String) {
+        self.analysis.errors.push(TsError {
+            offset,
+            pid: Some(pid),
+            check,
+            message,
+        });
+    }
+
+    fn stats(&mut self, pid: u16) -> &mut PidStats {
+        self.stats.entry(pid).or_insert_with(|| PidStats {
+            pid,
+            ..Default::default()
+        })
+    }
+
+    fn packet(&mut self, offset: u64, packet: &TsPacket) {
+        let pid = packet.pid;
+        self.stats(pid).packets += 1;
+
+        let time = self.time(offset);
+        if let Some(time) = time {
+            let window = (time / BITRATE_WINDOW).max(0.0) as usize;
+            let bytes = self.window_bytes.entry(pid).or_default();
+            if bytes.len() <= window {
+                bytes.resize(window + 1, 0);
+            }
+            bytes[window] += TS_PACKET_SIZE as u64;
+        }
+
+        if packet.transport_error {
+            self.stats(pid).transport_errors += 1;
+            self.error(
+                offset,
+                pid,
+                TsCheck::Transport,
+                "transport_error_indicator set".to_string(),
+            );
+        }
+
+        if packet.scrambling_control != 0 {
+            self.stats(pid).scrambled_packets += 1;
+            self.first_scrambled.get_or_insert((offset, pid));
+            if pid == PAT_PID {
+                self.error(
+                    offset,
+                    pid,
+                    TsCheck::Pat,
+                    "PAT packet scrambled".to_string(),
+                );
+            } else if self.pmt_pids.contains(&pid) {
+                self.error(
+                    offset,
+                    pid,
+                    TsCheck::Pmt,
+                    "PMT packet scrambled".to_string(),
+                );
+            }
+        }
+
+        if let (Some(time), Some(seen)) = (time, self.last_seen.get_mut(&pid)) {
+            let gap = time - seen.1;
+            let since = seen.0;
+            *seen = (offset, time);
+            if gap > PID_TIMEOUT {
+                self.error(
+                    offset,
+                    pid,
+                    TsCheck::Pid,
+                    format!("absent for {:.1} s after offset {}", gap, since),
+                );
+            }
+        }
+
+        self.continuity(offset, packet);
+
+        // The payload of an errored packet cannot be trusted
+        if packet.transport_error {
+            return;
+        }
+
+        if let Some(pcr) = packet.pcr {
+            self.pcr(offset, packet, pcr);
+        }
+
+        if packet.scrambling_control == 0 && !packet.payload.is_empty() {
+            if pid == PAT_PID || pid == CAT_PID || self.pmt_pids.contains(&pid) {
+                self.psi(offset, packet);
+            } else if self.es_pids.contains(&pid) && packet.payload_unit_start {
+                self.pes(offset, pid, &packet.payload);
+            }
+        }
+    }
+
+    /// 1.4: continuity counters advance by one on packets with a payload; a
+    /// packet may be sent twice
+    fn continuity(&mut self, offset: u64, packet: &TsPacket) {
+        let pid = packet.pid;
+        if pid == NULL_PID || !packet.has_payload() {
+            return;
+        }
+        let cc = packet.continuity_counter;
+        let mut state = Continuity {
+            last: cc,
+            repeated: false,
+        };
+        let previous = self.continuity.get(&pid).filter(|_| !packet.discontinuity);
+        let message = match previous {
+            Some(previous) if cc == previous.last => {
+                state.repeated = true;
+                previous
+                    .repeated
+                    .then(|| format!("packet with counter {} sent more than twice", cc))
+            }
+            Some(previous) if cc != (previous.last + 1) & 0x0F => Some(format!(
+                "expected counter {}, found {}",
+                (previous.last + 1) & 0x0F,
+                cc
+            )),
+            _ => None,
+        };
+        self.continuity.insert(pid, state);
+        let Some(message) = message else {
+            return;
+        };
+
+        self.stats(pid).continuity_errors += 1;
+        if let Some(index) = self.open_pes.remove(&pid) {
+            self.analysis.pes[index].lost = true;
+        }
+        self.error(offset, pid, TsCheck::ContinuityCount, message);
+    }
+
+    /// 2.3a/2.3b: PCR interval and discontinuities
+    fn pcr(&mut self, offset: u64, packet: &TsPacket, pcr: u64) {
+        let pid = packet.pid;
+        let mut interval_ms = None;
+        if let Some((previous_offset, previous_pcr)) = self.last_pcr.insert(pid, (offset, pcr)) {
+            if let Some(clock) = &self.clock {
+                let interval = clock.time(offset) - clock.time(previous_offset);
+                interval_ms = Some(interval * 1000.0);
+                if interval > PCR_REPETITION_LIMIT {
+                    self.error(
+                        offset,
+                        pid,
+                        TsCheck::PcrRepetition,
+                        format!("PCR interval {:.1} ms", interval * 1000.0),
+                    );
+                }
+            }
+            let step = pcr_delta(previous_pcr, pcr) as f64 / PCR_CLOCK;
+            if !packet.discontinuity && !(0.0..=PCR_DISCONTINUITY_LIMIT).contains(&step) {
+                self.error(
+                    offset,
+                    pid,
+                    TsCheck::PcrDiscontinuity,
+                    format!(
+                        "PCR step {:.1} ms without discontinuity_indicator",
+                        step * 1000.0
+                    ),
+                );
+            }
+        }
+
+        self.analysis.pcrs.push(PcrSample {
+            offset,
+            pid,
+            pcr,
+            discontinuity: packet.discontinuity,
+            interval_ms,
+            accuracy_ns: None,
+        });
+    }
+
+    /// Assemble PSI sections and check each complete one
+    fn psi(&mut self, offset: u64, packet: &TsPacket) {
+        let pid = packet.pid;
+        let payload = &packet.payload;
+        if packet.payload_unit_start {
+            let pointer = payload[0] as usize;
+            let tail = payload.get(1..1 + pointer).unwrap_or(&[]);
+            if let Some(mut buffer) = self.sections.remove(&pid) {
+                buffer.extend_from_slice(tail);
+                self.complete_sections(offset, pid, buffer);
+            }
+            let start = payload.get(1 + pointer..).unwrap_or(&[]).to_vec();
+            self.complete_sections(offset, pid, start);
+        } else if let Some(mut buffer) = self.sections.remove(&pid) {
+            buffer.extend_from_slice(payload);
+            self.complete_sections(offset, pid, buffer);
+        }
+    }
+
+    /// Process the complete sections at the start of a buffer and keep the
+    /// incomplete rest
+    fn complete_sections(&mut self, offset: u64, pid: u16, mut buffer: Vec<u8>) {
+        loop {
+            // 0xFF is stuffing after the last section
+            if buffer.first().is_none_or(|&b| b == 0xFF) {
+                return;
+            }
+            if buffer.len() < 3 {
+                break;
+            }
+            let length = 3 + ((((buffer[1] & 0x0F) as usize) << 8) | buffer[2] as usize);
+            if buffer.len() < length {
+                break;
+            }
+            let rest = buffer.split_off(length);
+            self.section(offset, pid, &buffer);
+            buffer = rest;
+        }
+        self.sections.insert(pid, buffer);
+    }
+
+    /// 1.3/1.5/2.2/2.6: check one PSI section and follow the program tables
+    fn section(&mut self, offset: u64, pid: u16, section: &[u8]) {
+        let table_id = section[0];
+        let expected = match pid {
+            PAT_PID => 0x00,
+            CAT_PID => 0x01,
+            _ => 0x02,
+        };
+        if table_id != expected {
+            if pid == PAT_PID {
+                self.error(
+                    offset,
+                    pid,
+                    TsCheck::Pat,
+                    format!("table_id 0x{:02X} on the PAT PID", table_id),
+                );
+            }
+            return;
+        }
+
+        if section.len() < 4 || crc32_mpeg2(section) != 0 {
+            self.error(
+                offset,
+                pid,
+                TsCheck::Crc,
+                format!("CRC mismatch in section with table_id 0x{:02X}", table_id),
+            );
+            return;
+        }
+
+        let time = self.time(offset);
+        let repetition = match self
+            .analysis
+            .psi
+            .iter_mut()
+            .find(|r| r.pid == pid && r.table_id == table_id)
+        {
+            Some(repetition) => repetition,
+            None => {
+                self.analysis.psi.push(PsiRepetition {
+                    pid,
+                    table_id,
+                    sections: 0,
+                    intervals_ms: Vec::new(),
+                });
+                self.analysis.psi.last_mut().expect("just pushed")
+            }
+        };
+        repetition.sections += 1;
+        if let Some(time) = time {
+            if let Some(previous) = self.last_section.insert((pid, table_id), time) {
+                let interval = time - previous;
+                repetition.intervals_ms.push(interval * 1000.0);
+                if interval > PSI_REPETITION_LIMIT && table_id != 0x01 {
+                    let (check, table) = if pid == PAT_PID {
+                        (TsCheck::Pat, "PAT")
+                    } else {
+                        (TsCheck::Pmt, "PMT")
+                    };
+                    self.error(
+                        offset,
+                        pid,
+                        check,
+                        format!("{} interval {:.0} ms", table, interval * 1000.0),
+                    );
+                }
+            }
+        }
+
+        match table_id {
+            0x00 => {
+                let entries = parse_pat(section, false).unwrap_or_default();
+                self.pmt_pids = entries.iter().map(|e| e.pmt_pid).collect();
+            }
+            0x01 => self.cat_seen = true,
+            _ => {
+                let streams = parse_pmt(section, false).unwrap_or_default();
+                if self.analysis.video_pid.is_none() {
+                    self.analysis.video_pid = streams
+                        .iter()
+                        .find(|s| TsVideoCodec::from_pmt_stream(s).is_some())
+                        .map(|s| s.elementary_pid);
+                }
+                for stream in streams {
+                    self.es_pids.insert(stream.elementary_pid);
+                    if let Some(time) = time {
+                        self.last_seen
+                            .entry(stream.elementary_pid)
+                            .or_insert((offset, time));
+                    }
+                }
+            }
+        }
+    }
+
+    /// 2.5 and the T-STD buffer delay of a PES packet start
+    fn pes(&mut self, offset: u64, pid: u16, payload: &[u8]) {
+        let Ok(pes) = parse_pes(payload) else {
+            return;
+        };
+
+        let time = self.time(offset);
+        if let (Some(time), Some(_)) = (time, pes.pts) {
+            if let Some(previous) = self.last_pts.insert(pid, time) {
+                let interval = time - previous;
+                if interval > PTS_REPETITION_LIMIT {
+                    self.error(
+                        offset,
+                        pid,
+                        TsCheck::Pts,
+                        format!("PTS interval {:.0} ms", interval * 1000.0),
+                    );
+                }
+            }
+        }
+
+        let decode = pes.dts.or(pes.pts);
+        let buffer_delay_ms = match (&self.clock, decode) {
+            (Some(clock), Some(decode)) => {
+                let ticks = pcr_delta(clock.stc(offset), decode * 300);
+                Some(ticks as f64 / PCR_CLOCK * 1000.0)
+            }
+            _ => None,
+        };
+        if let Some(ms) = buffer_delay_ms {
+            if ms < 0.0 {
+                self.error(
+                    offset,
+                    pid,
+                    TsCheck::BufferDelay,
+                    format!("PES arrives {:.1} ms after its decoding time", -ms),
+                );
+            } else if ms > BUFFER_DELAY_LIMIT * 1000.0 {
+                self.error(
+                    offset,
+                    pid,
+                    TsCheck::BufferDelay,
+                    format!("PES buffered for {:.0} ms", ms),
+                );
+            }
+        }
+
+        self.open_pes.insert(pid, self.analysis.pes.len());
+        self.analysis.pes.push(PesTiming {
+            offset,
+            pid,
+            time,
+            pts: pes.pts,
+            dts: pes.dts,
+            buffer_delay_ms,
+            lost: false,
+        });
+    }
+
+    /// Checks that need the whole stream, and the derived statistics
+    fn finish(mut self, end: u64) -> TsAnalysis {
+        if let Some(time) = self.time(end) {
+            let mut pending: Vec<_> = self.last_seen.drain().collect();
+            pending.sort_by_key(|(pid, _)| *pid);
+            for (pid, (since, seen)) in pending {
+                if time - seen > PID_TIMEOUT {
+                    self.error(
+                        since,
+                        pid,
+                        TsCheck::Pid,
+                        format!("absent for the last {:.1} s", time - seen),
+                    );
+                }
+            }
+        }
+
+        if let Some(time) = self.time(end) {
+            let mut tables: Vec<_> = self
+                .last_section
+                .iter()
+                .filter(|((_, table_id), _)| *table_id != 0x01)
+                .map(|(&(pid, _), &last)| (pid, last))
+                .collect();
+            tables.sort_by_key(|(pid, _)| *pid);
+            for (pid, last) in tables {
+                if time - last > PSI_REPETITION_LIMIT {
+                    let (check, table) = if pid == PAT_PID {
+                        (TsCheck::Pat, "PAT")
+                    } else {
+                        (TsCheck::Pmt, "PMT")
+                    };
+                    self.error(
+                        end,
+                        pid,
+                        check,
+                        format!("no {} for the last {:.0} ms", table, (time - last) * 1000.0),
+                    );
+                }
+            }
+        }
+
+        if !self
+            .analysis
+            .psi
+            .iter()
+            .any(|r| r.pid == PAT_PID && r.sections > 0)
+        {
+            self.error(0, PAT_PID, TsCheck::Pat, "no PAT section".to_string());
+        }
+        let mut pmt_pids: Vec<_> = self.pmt_pids.iter().copied().collect();
+        pmt_pids.sort_unstable();
+        for pid in pmt_pids {
+            if !self.analysis.psi.iter().any(|r| r.pid == pid) {
+                self.error(0, pid, TsCheck::Pmt, "no PMT section".to_string());
+            }
+        }
+        if let (Some((offset, pid)), false) = (self.first_scrambled, self.cat_seen) {
+            self.error(
+                offset,
+                pid,
+                TsCheck::Cat,
+                "scrambled packets without a CAT".to_string(),
+            );
+        }
+
+        self.pcr_accuracy();
+
+        if let Some(clock) = &self.clock {
+            let duration = clock.duration();
+            let last_window = (duration / BITRATE_WINDOW) as usize;
+            for (pid, bytes) in &self.window_bytes {
+                let bitrate = bytes
+                    .iter()
+                    .enumerate()
+                    .map(|(window, &bytes)| {
+                        // The last window ends at the last PCR
+                        let length = if window == last_window {
+                            duration - window as f64 * BITRATE_WINDOW
+                        } else {
+                            BITRATE_WINDOW
+                        };
+                        if length > 0.0 {
+                            bytes as f64 * 8.0 / length
+                        } else {
+                            0.0
+                        }
+                    })
+                    .collect();
+                if let Some(stats) = self.stats.get_mut(pid) {
+                    stats.bitrate = bitrate;
+                }
+            }
+            let first = &clock.anchors[0];
+            let last = &clock.anchors[clock.anchors.len() - 1];
+            if last.time > first.time {
+                self.analysis.bitrate =
+                    Some((last.offset - first.offset) as f64 * 8.0 / (last.time - first.time));
+            }
+            self.analysis.duration = Some(duration);
+        }
+
+        self.analysis.pids = self.stats.into_values().collect();
+        self.analysis
+            .errors
+            .sort_by_key(|e| (e.offset, e.check.priority().unwrap_or(u8::MAX)));
+        self.analysis
+    }
+
+    /// 2.4: deviation of each PCR from the straight line through the first
+    /// and last PCR of its continuous run
+    fn pcr_accuracy(&mut self) {
+        let mut by_pid: BTreeMap<u16, Vec<usize>> = BTreeMap::new();
+        for (index, sample) in self.analysis.pcrs.iter().enumerate() {
+            by_pid.entry(sample.pid).or_default().push(index);
+        }
+
+        for indices in by_pid.into_values() {
+            // Split into runs at discontinuities and out-of-range steps
+            let mut runs: Vec<Vec<usize>> = Vec::new();
+            for (position, &index) in indices.iter().enumerate() {
+                let sample = &self.analysis.pcrs[index];
+                let continuous = position > 0 && {
+                    let previous = &self.analysis.pcrs[indices[position - 1]];
+                    let step = pcr_delta(previous.pcr, sample.pcr) as f64 / PCR_CLOCK;
+                    !sample.discontinuity && step > 0.0 && step <= PCR_DISCONTINUITY_LIMIT
+                };
+                match runs.last_mut() {
+                    Some(run) if continuous => run.push(index),
+                    _ => runs.push(vec![index]),
+                }
+            }
+
+            for run in runs.into_iter().filter(|run| run.len() > 2) {
+                let first = self.analysis.pcrs[run[0]].clone();
+                let last = &self.analysis.pcrs[run[run.len() - 1]];
+                let ticks_per_byte =
+                    pcr_delta(first.pcr, last.pcr) as f64 / (last.offset - first.offset) as f64;
+                for &index in &run {
+                    let sample = &self.analysis.pcrs[index];
+                    let expected = ticks_per_byte * (sample.offset - first.offset) as f64;
+                    let actual = pcr_delta(first.pcr, sample.pcr) as f64;
+                    let ns = (actual - expected) / PCR_CLOCK * 1e9;
+                    let (offset, pid) = (sample.offset, sample.pid);
+                    self.analysis.pcrs[index].accuracy_ns = Some(ns);
+                    if ns.abs() > PCR_ACCURACY_LIMIT_NS {
+                        self.error(
+                            offset,
+                            pid,
+                            TsCheck::PcrAccuracy,
+                            format!("PCR off by {:.0} ns", ns),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Analyze the transport layer of a TS file
+pub fn analyze_ts(data: &[u8]) -> Result<TsAnalysis> {
+    let (packets, sync_errors) = read_packets(data);
+
+    let pcr_pid = packets
+        .iter()
+        .find(|(_, p)| p.pcr.is_some() && !p.transport_error)
+        .map(|(_, p)| p.pid);
+    let clock = pcr_pid.and_then(|pid| {
+        Clock::new(
+            packets
+                .iter()
+                .filter(|(_, p)| p.pid == pid && !p.transport_error)
+                .filter_map(|(offset, p)| Some((*offset, p.pcr?, p.discontinuity))),
+        )
+    });
+
+    let mut analyzer = Analyzer {
+        clock,
+        analysis: TsAnalysis {
+            packet_count: packets.len(),
+            pcr_pid,
+            errors: sync_errors,
+            ..Default::default()
+        },
+        stats: BTreeMap::new(),
+        window_bytes: BTreeMap::new(),
+        continuity: HashMap::new(),
+        sections: HashMap::new(),
+        pmt_pids: HashSet::new(),
+        es_pids: HashSet::new(),
+        last_section: HashMap::new(),
+        last_seen: HashMap::new(),
+        last_pts: HashMap::new(),
+        last_pcr: HashMap::new(),
+        open_pes: HashMap::new(),
+        cat_seen: false,
+        first_scrambled: None,
+    };
+    for (offset, packet) in &packets {
+        analyzer.packet(*offset, packet);
+    }
+    let end = packets.last().map_or(0, |(offset, _)| *offset);
+
+    Ok(analyzer.finish(end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::ts::{packet, pes, section};
+
+    const PMT_PID: u16 = 0x1000;
+    const VIDEO_PID: u16 = 0x0100;
+
+    /// About 20 Mbit/s: 2030 PCR ticks, 75.2 µs, per packet
+    const TICKS_PER_PACKET: u64 = 2030;
+
+    fn pat() -> Vec<u8> {
+        section(
+            0x00,
+            &[
+                0x00,
+                0x01,
+                0xC1,
+                0x00,
+                0x00, // transport_stream_id 1, version 0, current
+                0x00,
+                0x01,
+                0xE0 | (PMT_PID >> 8) as u8,
+                PMT_PID as u8,
+            ],
+        )
+    }
+
+    fn pmt() -> Vec<u8> {
+        section(
+            0x02,
+            &[
+                0x00,
+                0x01,
+                0xC1,
+                0x00,
+                0x00, // program_number 1, version 0, current
+                0xE0 | (VIDEO_PID >> 8) as u8,
+                VIDEO_PID as u8,
+                0xF0,
+                0x00,
+                0x1B,
+                0xE0 | (VIDEO_PID >> 8) as u8,
+                VIDEO_PID as u8,
+                0xF0,
+                0x00,
+            ],
+        )
+    }
+
+    /// A stream of `count` packets: PAT and PMT every 40 packets, two PCRs
+    /// every 20 packets, and a video PES every 10 packets decoded 100 ms
+    /// later
+    fn stream(count: usize) -> Vec<Vec<u8>> {
+        stream_with(count, |_| true, |_| true)
+    }
+
+    /// Like [`stream`], with null packets in place of the PSI packets and
+    /// without PCRs where the filters say so
+    fn stream_with(
+        count: usize,
+        psi: impl Fn(usize) -> bool,
+        pcr: impl Fn(usize) -> bool,
+    ) -> Vec<Vec<u8>> {
+        let mut cc: HashMap<u16, u8> = HashMap::new();
+        let mut next_cc = |pid: u16| {
+            let counter = cc.entry(pid).or_insert(0);
+            let value = *counter;
+            *counter = (*counter + 1) & 0x0F;
+            value
+        };
+        (0..count)
+            .map(|i| {
+                let clock = i as u64 * TICKS_PER_PACKET;
+                let with_pcr = |phase| (i % 20 == phase && pcr(i)).then_some(clock);
+                match i % 40 {
+                    0 | 1 if !psi(i) => packet(NULL_PID, false, 0, None, &[0xFF; 16]),
+                    0 => packet(PAT_PID, true, next_cc(PAT_PID), None, &pat()),
+                    1 => packet(PMT_PID, true, next_cc(PMT_PID), None, &pmt()),
+                    _ if i % 10 == 2 => {
+                        let dts = clock / 300 + 9000;
+                        let pes = pes(
+                            Some(dts + 3000),
+                            Some(dts),
+                            &[0x00, 0x00, 0x00, 0x01, 0x09, 0xF0],
+                        );
+                        packet(VIDEO_PID, true, next_cc(VIDEO_PID), with_pcr(2), &pes)
+                    }
+                    _ => packet(
+                        VIDEO_PID,
+                        false,
+                        next_cc(VIDEO_PID),
+                        with_pcr(7),
+                        &[0x00; 16],
+                    ),
+                }
+            })
+            .collect()
+    }
+
+    fn checks(analysis: &TsAnalysis) -> Vec<TsCheck> {
+        analysis.errors.iter().map(|e| e.check).collect()
+    }
+
+    #[test]
+    fn test_clean_stream() {
+        let data = stream(400).concat();
+        let analysis = analyze_ts(&data).unwrap();
+
+        assert_eq!(analysis.packet_count, 400);
+        assert_eq!(analysis.pcr_pid, Some(VIDEO_PID));
+        assert_eq!(analysis.video_pid, Some(VIDEO_PID));
+        assert!(analysis.errors.is_empty(), "{:?}", analysis.errors);
+
+        let bitrate = analysis.bitrate.unwrap();
+        assert!((bitrate - 20_003_941.0).abs() < 1.0, "{}", bitrate);
+        assert!(analysis
+            .pcrs
+            .iter()
+            .all(|s| s.accuracy_ns.unwrap().abs() < 1.0));
+
+        let pat = analysis.psi.iter().find(|r| r.pid == PAT_PID).unwrap();
+        assert_eq!(pat.sections, 10);
+        assert!((pat.max_interval_ms().unwrap() - 3.008).abs() < 0.01);
+
+        assert_eq!(analysis.pes.len(), 40);
+        let delay = analysis.pes[0].buffer_delay_ms.unwrap();
+        assert!((delay - 100.0).abs() < 0.1, "{}", delay);
+
+        let lanes = analysis.lanes();
+        let lane = |lane_type| lanes.iter().find(|l| l.lane_type == lane_type).unwrap();
+        assert_eq!(lane(LaneType::BufferDelay).data.len(), 40);
+        assert_eq!(lane(LaneType::PidBitrate).data.len(), 40);
+        // Only every other access unit carries PCRs
+        assert_eq!(lane(LaneType::PcrAccuracy).data.len(), 20);
+        assert!(lane(LaneType::TransportErrors)
+            .data
+            .iter()
+            .all(|p| p.value == 0.0));
+    }
+
+    #[test]
+    fn test_transport_errors() {
+        let mut packets = stream(400);
+        // Lose packet 105, inside the PES starting at packet 102
+        packets[105] = packet(NULL_PID, false, 0, None, &[0xFF; 16]);
+        // Transport error on packet 205
+        packets[205][1] |= 0x80;
+        // Corrupt the PAT CRC of packet 240
+        packets[240][180] ^= 0xFF;
+        // Move the PCR of packet 302 by 2 µs
+        let pcr = 302 * TICKS_PER_PACKET + 54;
+        let field = packet(VIDEO_PID, true, 0, Some(pcr), &[]);
+        packets[302][6..12].copy_from_slice(&field[6..12]);
+        // A single bad sync byte
+        packets[350][0] = 0x46;
+
+        let data = packets.concat();
+        let analysis = analyze_ts(&data).unwrap();
+        let checks = checks(&analysis);
+
+        assert!(checks.contains(&TsCheck::ContinuityCount));
+        assert!(checks.contains(&TsCheck::Transport));
+        assert!(checks.contains(&TsCheck::Crc));
+        assert!(checks.contains(&TsCheck::PcrAccuracy));
+        assert!(checks.contains(&TsCheck::SyncByte));
+        assert!(!checks.contains(&TsCheck::SyncLoss));
+
+        let video = analysis.pids.iter().find(|s| s.pid == VIDEO_PID).unwrap();
+        assert_eq!(video.transport_errors, 1);
+        assert!(analysis.pes.iter().any(|p| p.offset == 102 * 188 && p.lost));
+
+        let diagnostics = analysis.diagnostics();
+        let cc = diagnostics
+            .iter()
+            .find(|d| d.message.starts_with("1.4 Continuity_count_error"))
+            .unwrap();
+        assert_eq!(cc.severity, DiagnosticSeverity::Error);
+        assert_eq!(cc.offset_bytes, 106 * 188);
+        assert_eq!(cc.details.get("pid").unwrap(), "0x0100");
+        // The access unit starting at packet 102 is lost, so the error lands
+        // on the access unit before it
+        assert_eq!(cc.frame_key.as_ref().unwrap().frame_index, 9);
+    }
+
+    #[test]
+    fn test_missing_psi_and_pcr_gap() {
+        // PAT and PMT only once; no PCR for 1000 packets, 75 ms
+        let packets = stream_with(8000, |i| i < 40, |i| !(4000..5000).contains(&i));
+        let data = packets.concat();
+        let analysis = analyze_ts(&data).unwrap();
+        let checks = checks(&analysis);
+
+        assert!(checks.contains(&TsCheck::Pat));
+        assert!(checks.contains(&TsCheck::Pmt));
+        assert!(checks.contains(&TsCheck::PcrRepetition));
+        assert!(!checks.contains(&TsCheck::PcrDiscontinuity));
+        assert!(!checks.contains(&TsCheck::ContinuityCount));
+        assert_eq!(analysis.errors_with_priority(1).count(), 2);
+    }
+
+    #[test]
+    fn test_sync_loss_resync() {
+        let mut packets = stream(100);
+        packets[50][0] = 0x00;
+        packets[51][0] = 0x00;
+        let data = packets.concat();
+        let analysis = analyze_ts(&data).unwrap();
+
+        assert_eq!(
+            checks(&analysis),
+            vec![
+                TsCheck::SyncByte,
+                TsCheck::SyncByte,
+                TsCheck::SyncLoss,
+                TsCheck::ContinuityCount
+            ]
+        );
+        assert_eq!(analysis.packet_count, 98);
+    }
+
+    #[test]
+    fn test_crc32_mpeg2() {
+        assert_eq!(crc32_mpeg2(b"123456789"), 0x0376_E6E7);
+    }
+}
@@ -0,0 +1,535 @@
+//! HEVC decoded picture buffer simulation.
+//!
+//! Tracks the reference marking of decoded pictures through a stream:
+//! picture order count derivation (ITU-T H.265 Section 8.3.1), reference
+//! picture set application (Section 8.3.2) and reference picture list
+//! construction (Section 8.3.4). No samples are decoded; pictures are
+//! identified by their POC.
+
+use crate::nal::NalUnitType;
+use crate::slice::SliceHeader;
+use crate::sps::Sps;
+use serde::{Deserialize, Serialize};
+
+/// Entry of a reference picture list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefPicEntry {
+    /// POC of the reference picture.
+    pub poc: i32,
+    /// Whether the picture is marked as used for long-term reference.
+    pub long_term: bool,
+    /// Whether the picture is missing from the DPB ("no reference picture").
+    pub missing: bool,
+}
+
+/// Reference picture lists of a slice.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefPicLists {
+    /// RefPicList0.
+    pub list0: Vec<RefPicEntry>,
+    /// RefPicList1 (B slices only).
+    pub list1: Vec<RefPicEntry>,
+}
+
+impl RefPicLists {
+    /// Check if both lists are empty (intra slices).
+    pub fn is_empty(&self) -> bool {
+        self.list0.is_empty() && self.list1.is_empty()
+    }
+
+    /// Iterate over the entries of RefPicList0 followed by RefPicList1.
+    pub fn iter(&self) -> impl Iterator<Item = &RefPicEntry> {
+        self.list0.iter().chain(&self.list1)
+    }
+}
+
+/// Reference picture set of a picture, as POC values.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReferencePictureSet {
+    /// Short-term pictures before the current one, used by it (PocStCurrBefore).
+    pub poc_st_curr_before: Vec<i32>,
+    /// Short-term pictures after the current one, used by it (PocStCurrAfter).
+    pub poc_st_curr_after: Vec<i32>,
+    /// Short-term pictures kept for later pictures (PocStFoll).
+    pub poc_st_foll: Vec<i32>,
+    /// Long-term pictures used by the current picture (PocLtCurr).
+    pub poc_lt_curr: Vec<i32>,
+    /// Long-term pictures kept for later pictures (PocLtFoll).
+    pub poc_lt_foll: Vec<i32>,
+    /// Pictures used by the current picture that are not in the DPB.
+    pub missing: Vec<i32>,
+}
+
+impl ReferencePictureSet {
+    /// Number of pictures usable for inter prediction (NumPicTotalCurr).
+    pub fn num_pic_total_curr(&self) -> usize {
+        self.poc_st_curr_before.len() + self.poc_st_curr_after.len() + self.poc_lt_curr.len()
+    }
+}
+
+/// Result of starting the decoding of a picture.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PictureState {
+    /// Picture order count (PicOrderCntVal).
+    pub poc: i32,
+    /// NoRaslOutputFlag of an IRAP picture.
+    pub no_rasl_output_flag: bool,
+    /// RASL picture associated with an IRAP picture with NoRaslOutputFlag
+    /// equal to 1; it is not decoded and its references are unavailable.
+    pub skipped: bool,
+    /// Reference picture set of the picture.
+    pub rps: ReferencePictureSet,
+}
+
+impl PictureState {
+    /// Construct the reference picture lists of a slice of this picture.
+    pub fn ref_pic_lists(&self, header: &SliceHeader) -> RefPicLists {
+        if header.is_intra() || self.rps.num_pic_total_curr() == 0 {
+            return RefPicLists::default();
+        }
+
+        let entry = |poc: i32, long_term: bool| RefPicEntry {
+            poc,
+            long_term,
+            missing: self.rps.missing.contains(&poc),
+        };
+        let short_term = |pocs: &[i32]| pocs.iter().map(|&poc| entry(poc, false)).collect();
+        let before: Vec<RefPicEntry> = short_term(&self.rps.poc_st_curr_before);
+        let after: Vec<RefPicEntry> = short_term(&self.rps.poc_st_curr_after);
+        let long_term: Vec<RefPicEntry> = self
+            .rps
+            .poc_lt_curr
+            .iter()
+            .map(|&poc| entry(poc, true))
+            .collect();
+
+        let modification = header.ref_pic_list_modification.as_ref();
+        let mut lists = RefPicLists {
+            list0: build_list(
+                [&before, &after, &long_term],
+                header.num_ref_idx_l0_active() as usize,
+                modification
+                    .filter(|m| m.ref_pic_list_modification_flag_l0)
+                    .map(|m| m.list_entry_l0.as_slice()),
+            ),
+            list1: Vec::new(),
+        };
+        if header.num_ref_idx_l1_active() > 0 {
+            lists.list1 = build_list(
+                [&after, &before, &long_term],
+                header.num_ref_idx_l1_active() as usize,
+                modification
+                    .filter(|m| m.ref_pic_list_modification_flag_l1)
+                    .map(|m| m.list_entry_l1.as_slice()),
+            );
+        }
+        lists
+    }
+}
+
+/// Build one reference picture list from its initial order (8-8 to 8-11).
+fn build_list(
+    groups: [&[RefPicEntry]; 3],
+    num_active: usize,
+    list_entry: Option<&[u8]>,
+) -> Vec<RefPicEntry> {
+    let total: usize = groups.iter().map(|g| g.len()).sum();
+    let temp: Vec<RefPicEntry> = groups
+        .iter()
+        .flat_map(|g| g.iter().copied())
+        .cycle()
+        .take(num_active.max(total))
+        .collect();
+
+    (0..num_active)
+        .filter_map(|i| match list_entry {
+            Some(entries) => temp.get(*entries.get(i)? as usize).copied(),
+            None => temp.get(i).copied(),
+        })
+        .collect()
+}
+
+/// Picture held in the DPB for reference.
+#[derive(Debug, Clone, Copy)]
+struct DpbPicture {
+    poc: i32,
+    long_term: bool,
+}
+
+/// Reference picture marking state of a decoder.
+///
+/// Feed the first slice segment header of every picture to
+/// [`Dpb::start_picture`], in decoding order.
+#[derive(Debug, Clone, Default)]
+pub struct Dpb {
+    /// Pictures marked as used for reference.
+    pictures: Vec<DpbPicture>,
+    /// POC of the previous TemporalId 0 picture (prevTid0Pic).
+    prev_tid0_poc: i32,
+    /// Whether a picture has been decoded since the start or since end of sequence.
+    in_sequence: bool,
+    /// NoRaslOutputFlag of the associated IRAP picture.
+    irap_no_rasl_output_flag: bool,
+}
+
+impl Dpb {
+    /// Create an empty DPB.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle an end of sequence NAL unit: the next picture starts a new
+    /// coded video sequence.
+    pub fn end_of_sequence(&mut self) {
+        self.in_sequence = false;
+    }
+
+    /// Start decoding a picture.
+    ///
+    /// Derives its POC, applies its RPS to the pictures held for reference
+    /// and then adds the picture itself.
+    pub fn start_picture(
+        &mut self,
+        nal_type: NalUnitType,
+        temporal_id: u8,
+        header: &SliceHeader,
+        sps: &Sps,
+    ) -> PictureState {
+        let max_poc_lsb = 1i32 << (sps.log2_max_pic_order_cnt_lsb_minus4 + 4);
+        let irap = nal_type.is_irap();
+        let no_rasl_output_flag =
+            irap && (nal_type.is_idr() || nal_type.is_bla() || !self.in_sequence);
+        if irap {
+            self.irap_no_rasl_output_flag = no_rasl_output_flag;
+        }
+        self.in_sequence = true;
+
+        // 8.3.1: PicOrderCntMsb
+        let poc_lsb = header.slice_pic_order_cnt_lsb as i32;
+        let poc_msb = if no_rasl_output_flag {
+            0
+        } else {
+            let prev_poc_lsb = self.prev_tid0_poc & (max_poc_lsb - 1);
+            let prev_poc_msb = self.prev_tid0_poc - prev_poc_lsb;
+            if poc_lsb < prev_poc_lsb && prev_poc_lsb - poc_lsb >= max_poc_lsb / 2 {
+                prev_poc_msb + max_poc_lsb
+            } else if poc_lsb > prev_poc_lsb && poc_lsb - prev_poc_lsb > max_poc_lsb / 2 {
+                prev_poc_msb - max_poc_lsb
+            } else {
+                prev_poc_msb
+            }
+        };
+        let poc = poc_msb + poc_lsb;
+
+        // prevTid0Pic excludes RASL, RADL and sub-layer non-reference pictures
+        if temporal_id == 0 && !nal_type.is_leading() && nal_type.is_reference() {
+            self.prev_tid0_poc = poc;
+        }
+
+        let skipped = nal_type.is_rasl() && self.irap_no_rasl_output_flag;
+
+        // 8.3.2: all reference pictures are dropped at a CVS start
+        if no_rasl_output_flag {
+            self.pictures.clear();
+        }
+        let rps = if nal_type.is_idr() {
+            self.pictures.clear();
+            ReferencePictureSet::default()
+        } else if skipped {
+            // Not decoded, so the marking is left untouched
+            self.clone().apply_rps(poc, header, sps, max_poc_lsb)
+        } else {
+            self.apply_rps(poc, header, sps, max_poc_lsb)
+        };
+
+        if !skipped {
+            self.pictures.push(DpbPicture {
+                poc,
+                long_term: false,
+            });
+        }
+
+        PictureState {
+            poc,
+            no_rasl_output_flag,
+            skipped,
+            rps,
+        }
+    }
+
+    /// Derive the RPS of the current picture and mark the pictures outside
+    /// of it as unused for reference.
+    fn apply_rps(
+        &mut self,
+        poc: i32,
+        header: &SliceHeader,
+        sps: &Sps,
+        max_poc_lsb: i32,
+    ) -> ReferencePictureSet {
+        let mut rps = ReferencePictureSet::default();
+        let mut keep = vec![false; self.pictures.len()];
+
+        // Long-term pictures are matched on the POC LSBs unless the MSBs are signalled
+        for lt in &header.long_term_ref_pics {
+            let mut poc_lt = lt.poc_lsb_lt as i32;
+            if lt.delta_poc_msb_present_flag {
+                poc_lt += poc
+                    - (lt.delta_poc_msb_cycle_lt as i32).saturating_mul(max_poc_lsb)
+                    - (poc & (max_poc_lsb - 1));
+            }
+            let found = self.pictures.iter().position(|p| {
+                if lt.delta_poc_msb_present_flag {
+                    p.poc == poc_lt
+                } else {
+                    p.poc & (max_poc_lsb - 1) == poc_lt
+                }
+            });
+            if let Some(i) = found {
+                self.pictures[i].long_term = true;
+                keep[i] = true;
+                poc_lt = self.pictures[i].poc;
+            } else if lt.used_by_curr_pic_lt_flag {
+                rps.missing.push(poc_lt);
+            }
+            if lt.used_by_curr_pic_lt_flag {
+                rps.poc_lt_curr.push(poc_lt);
+            } else {
+                rps.poc_lt_foll.push(poc_lt);
+            }
+        }
+
+        if let Some(st) = header.st_ref_pic_set(sps) {
+            let negative = st.delta_poc_s0.iter().zip(&st.used_by_curr_pic_s0);
+            let positive = st.delta_poc_s1.iter().zip(&st.used_by_curr_pic_s1);
+            for (i, (&delta, &used)) in negative.chain(positive).enumerate() {
+                let poc_st = poc + delta;
+                let found = self
+                    .pictures
+                    .iter()
+                    .position(|p| !p.long_term && p.poc == poc_st);
+                match found {
+                    Some(j) => keep[j] = true,
+                    None if used => rps.missing.push(poc_st),
+                    None => {}
+                }
+                if !used {
+                    rps.poc_st_foll.push(poc_st);
+                } else if i < st.num_negative_pics() {
+                    rps.poc_st_curr_before.push(poc_st);
+                } else {
+                    rps.poc_st_curr_after.push(poc_st);
+                }
+            }
+        }
+
+        let mut keep = keep.into_iter();
+        self.pictures.retain(|_| keep.next().unwrap_or(false));
+        rps
+    }
+
+    /// POCs of the pictures currently held for reference.
+    pub fn reference_pocs(&self) -> Vec<i32> {
+        self.pictures.iter().map(|p| p.poc).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rps::{LongTermRefPic, ShortTermRefPicSet};
+    use crate::slice::{RefPicListModification, SliceType};
+    use crate::sps::{ChromaFormat, Profile, ProfileTierLevel};
+
+    fn sps() -> Sps {
+        Sps {
+            sps_video_parameter_set_id: 0,
+            sps_max_sub_layers_minus1: 0,
+            sps_temporal_id_nesting_flag: true,
+            profile_tier_level: ProfileTierLevel {
+                general_profile_space: 0,
+                general_tier_flag: false,
+                general_profile_idc: Profile::Main,
+                general_profile_compatibility_flags: 0,
+                general_progressive_source_flag: true,
+                general_interlaced_source_flag: false,
+                general_non_packed_constraint_flag: true,
+                general_frame_only_constraint_flag: true,
+                general_level_idc: 0, // Level unspecified
+            },
+            sps_seq_parameter_set_id: 0,
+            chroma_format_idc: ChromaFormat::Chroma420,
+            separate_colour_plane_flag: false,
+            pic_width_in_luma_samples: 64,
+            pic_height_in_luma_samples: 64,
+            conformance_window_flag: false,
+            conf_win_left_offset: 0,
+            conf_win_right_offset: 0,
+            conf_win_top_offset: 0,
+            conf_win_bottom_offset: 0,
+            bit_depth_luma_minus8: 0,
+            bit_depth_chroma_minus8: 0,
+            log2_max_pic_order_cnt_lsb_minus4: 0, // MaxPicOrderCntLsb = 16
+            sps_sub_layer_ordering_info_present_flag: false,
+            sps_max_dec_pic_buffering_minus1: vec![0],
+            sps_max_num_reorder_pics: vec![0],
+            sps_max_latency_increase_plus1: vec![0],
+            log2_min_luma_coding_block_size_minus3: 0,
+            log2_diff_max_min_luma_coding_block_size: 0,
+            log2_min_luma_transform_block_size_minus2: 0,
+            log2_diff_max_min_luma_transform_block_size: 0,
+            max_transform_hierarchy_depth_inter: 0,
+            max_transform_hierarchy_depth_intra: 0,
+            scaling_list_enabled_flag: false,
+            amp_enabled_flag: false,
+            sample_adaptive_offset_enabled_flag: false,
+            pcm_enabled_flag: false,
+            pcm_parameters: None,
+            num_short_term_ref_pic_sets: 0,
+            long_term_ref_pics_present_flag: false,
+            num_long_term_ref_pics_sps: 0,
+            st_ref_pic_sets: Vec::new(),
+            lt_ref_pic_poc_lsb_sps: Vec::new(),
+            used_by_curr_pic_lt_sps_flag: Vec::new(),
+            sps_temporal_mvp_enabled_flag: false,
+            strong_intra_smoothing_enabled_flag: false,
+            vui_parameters_present_flag: false,
+            vui_parameters: None,
+        }
+    }
+
+    fn slice(slice_type: SliceType, poc_lsb: u32, s0: &[i32], s1: &[i32]) -> SliceHeader {
+        SliceHeader {
+            slice_type,
+            slice_pic_order_cnt_lsb: poc_lsb,
+            short_term_ref_pic_set: Some(ShortTermRefPicSet {
+                delta_poc_s0: s0.to_vec(),
+                used_by_curr_pic_s0: vec![true; s0.len()],
+                delta_poc_s1: s1.to_vec(),
+                used_by_curr_pic_s1: vec![true; s1.len()],
+                ..Default::default()
+            }),
+            num_ref_idx_l0_active_minus1: 1,
+            num_ref_idx_l1_active_minus1: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_hierarchical_b_lists() {
+        let sps = sps();
+        let mut dpb = Dpb::new();
+
+        let idr = dpb.start_picture(NalUnitType::IdrWRadl, 0, &SliceHeader::default(), &sps);
+        assert_eq!(idr.poc, 0);
+        assert!(idr.no_rasl_output_flag);
+
+        let p = slice(SliceType::P, 4, &[-4], &[]);
+        let state = dpb.start_picture(NalUnitType::TrailR, 0, &p, &sps);
+        assert_eq!(state.poc, 4);
+        let lists = state.ref_pic_lists(&p);
+        assert_eq!(
+            lists.list0.iter().map(|e| e.poc).collect::<Vec<_>>(),
+            vec![0, 0]
+        );
+        assert!(lists.list1.is_empty());
+
+        let b = slice(SliceType::B, 2, &[-2], &[2]);
+        let state = dpb.start_picture(NalUnitType::TrailN, 1, &b, &sps);
+        assert_eq!(state.poc, 2);
+        let lists = state.ref_pic_lists(&b);
+        assert_eq!(
+            lists.list0.iter().map(|e| e.poc).collect::<Vec<_>>(),
+            vec![0, 4]
+        );
+        assert_eq!(
+            lists.list1.iter().map(|e| e.poc).collect::<Vec<_>>(),
+            vec![4, 0]
+        );
+        assert!(state.rps.missing.is_empty());
+    }
+
+    #[test]
+    fn test_poc_msb_wrap() {
+        let sps = sps();
+        let mut dpb = Dpb::new();
+        dpb.start_picture(NalUnitType::IdrNLp, 0, &SliceHeader::default(), &sps);
+
+        let mut pocs = Vec::new();
+        for lsb in [8, 15, 3, 10] {
+            let header = slice(SliceType::P, lsb, &[], &[]);
+            pocs.push(dpb.start_picture(NalUnitType::TrailR, 0, &header, &sps).poc);
+        }
+        assert_eq!(pocs, vec![8, 15, 19, 26]);
+    }
+
+    #[test]
+    fn test_rps_marks_unused_and_reports_missing() {
+        let sps = sps();
+        let mut dpb = Dpb::new();
+        dpb.start_picture(NalUnitType::IdrNLp, 0, &SliceHeader::default(), &sps);
+        dpb.start_picture(
+            NalUnitType::TrailR,
+            0,
+            &slice(SliceType::P, 1, &[-1], &[]),
+            &sps,
+        );
+        assert_eq!(dpb.reference_pocs(), vec![0, 1]);
+
+        // POC 0 is dropped and POC 1 is kept; POC -2 (= 3 - 5) never existed
+        let header = slice(SliceType::P, 3, &[-2, -5], &[]);
+        let state = dpb.start_picture(NalUnitType::TrailR, 0, &header, &sps);
+        assert_eq!(state.rps.poc_st_curr_before, vec![1, -2]);
+        assert_eq!(state.rps.missing, vec![-2]);
+        assert_eq!(dpb.reference_pocs(), vec![1, 3]);
+        assert!(state.ref_pic_lists(&header).list0[1].missing);
+    }
+
+    #[test]
+    fn test_long_term_and_modification() {
+        let sps = sps();
+        let mut dpb = Dpb::new();
+        dpb.start_picture(NalUnitType::IdrNLp, 0, &SliceHeader::default(), &sps);
+        dpb.start_picture(
+            NalUnitType::TrailR,
+            0,
+            &slice(SliceType::P, 1, &[-1], &[]),
+            &sps,
+        );
+
+        let mut header = slice(SliceType::P, 2, &[-1], &[]);
+        header.long_term_ref_pics = vec![LongTermRefPic {
+            poc_lsb_lt: 0,
+            used_by_curr_pic_lt_flag: true,
+            ..Default::default()
+        }];
+        header.ref_pic_list_modification = Some(RefPicListModification {
+            ref_pic_list_modification_flag_l0: true,
+            list_entry_l0: vec![1, 0],
+            ..Default::default()
+        });
+        let state = dpb.start_picture(NalUnitType::TrailR, 0, &header, &sps);
+        assert_eq!(state.rps.poc_lt_curr, vec![0]);
+
+        let lists = state.ref_pic_lists(&header);
+        assert_eq!(lists.list0[0].poc, 0);
+        assert!(lists.list0[0].long_term);
+        assert_eq!(lists.list0[1].poc, 1);
+        assert!(!lists.list0[1].long_term);
+    }
+
+    #[test]
+    fn test_rasl_after_cra_start_is_skipped() {
+        let sps = sps();
+        let mut dpb = Dpb::new();
+        let cra = slice(SliceType::I, 8, &[], &[]);
+        let state = dpb.start_picture(NalUnitType::CraNut, 0, &cra, &sps);
+        assert!(state.no_rasl_output_flag);
+        assert_eq!(state.poc, 8);
+
+        let rasl = slice(SliceType::B, 6, &[-2], &[2]);
+        let state = dpb.start_picture(NalUnitType::RaslN, 0, &rasl, &sps);
+        assert!(state.skipped);
+        assert_eq!(state.rps.missing, vec![4]);
+        assert_eq!(dpb.reference_pocs(), vec![8]);
+    }
+}
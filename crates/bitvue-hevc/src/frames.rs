@@ -2,11 +2,16 @@
 //!
 //! Functions for extracting individual frames from HEVC bitstreams
 
+use crate::dpb::RefPicLists;
 use crate::nal::{find_nal_units, parse_nal_header, NalUnitType};
-use crate::parse_hevc;
 use crate::slice::SliceHeader;
+use crate::{parse_hevc, ParsedSlice};
+use bitvue_core::reference_graph::{
+    GraphNode, ReferenceEdge, ReferenceGraphView, ReferenceType, WorldBounds,
+};
 use bitvue_core::BitvueError;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// HEVC frame data extracted from the bitstream
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +40,12 @@ pub struct HevcFrame {
     pub temporal_id: Option<u8>,
     /// Slice header (if available)
     pub slice_header: Option<SliceHeader>,
+    /// Reference picture lists of the first slice
+    #[serde(default)]
+    pub ref_pic_lists: RefPicLists,
+    /// Indices of the frames referenced by the reference picture lists
+    #[serde(default)]
+    pub ref_frames: Vec<usize>,
 }
 
 impl HevcFrame {
@@ -78,6 +89,8 @@ pub struct HevcFrameBuilder {
     is_ref: Option<bool>,
     temporal_id: Option<u8>,
     slice_header: Option<SliceHeader>,
+    ref_pic_lists: Option<RefPicLists>,
+    ref_frames: Option<Vec<usize>>,
 }
 
 impl HevcFrameBuilder {
@@ -153,6 +166,18 @@ impl HevcFrameBuilder {
         self
     }
 
+    /// Set the reference picture lists
+    pub fn ref_pic_lists(mut self, value: RefPicLists) -> Self {
+        self.ref_pic_lists = Some(value);
+        self
+    }
+
+    /// Set the indices of the referenced frames
+    pub fn ref_frames(mut self, value: Vec<usize>) -> Self {
+        self.ref_frames = Some(value);
+        self
+    }
+
     /// Build the HevcFrame
     ///
     /// # Panics
@@ -172,6 +197,8 @@ impl HevcFrameBuilder {
             is_ref: self.is_ref.expect("is_ref is required"),
             temporal_id: self.temporal_id,
             slice_header: self.slice_header,
+            ref_pic_lists: self.ref_pic_lists.unwrap_or_default(),
+            ref_frames: self.ref_frames.unwrap_or_default(),
         }
     }
 }
@@ -235,14 +262,20 @@ pub fn extract_annex_b_frames(data: &[u8]) -> Result<Vec<HevcFrame>, BitvueError
     let mut frames = Vec::new();
     let mut current_frame_nals: Vec<(usize, usize)> = Vec::new();
     let mut current_frame_index = 0;
-    let _current_poc: Option<i32> = None; // TODO: Implement POC calculation
+    let mut current_slice: Option<&ParsedSlice> = None;
     let mut current_frame_num: Option<u32> = None;
     let mut current_is_idr = false;
     let mut current_is_irap = false;
     let mut current_is_ref = false;
     let mut current_frame_type = HevcFrameType::Unknown;
     let mut current_temporal_id: Option<u8> = None;
-    let mut current_slice_header: Option<SliceHeader> = None;
+
+    // Slices parsed by parse_hevc, keyed by NAL unit offset
+    let slices: HashMap<usize, &ParsedSlice> = stream
+        .slices
+        .iter()
+        .map(|slice| (stream.nal_units[slice.nal_index].offset as usize, slice))
+        .collect();
 
     for (nal_start, nal_end) in nal_ranges {
         // Find the first byte after start code (actual NAL data)
@@ -291,18 +324,11 @@ pub fn extract_annex_b_frames(data: &[u8]) -> Result<Vec<HevcFrame>, BitvueError
             let new_frame =
                 if current_frame_nals.is_empty() || is_idr != current_is_idr || first_slice_flag {
                     true // First VCL NAL, IDR boundary, or first_slice flag
-                } else if let Some(slice) = &current_slice_header {
-                    // Try to parse slice header for additional checks
-                    if let Ok(new_slice) = crate::slice::parse_slice_header(
-                        &data[nal_data_start + 1..nal_end],
-                        &stream.sps_map,
-                        &stream.pps_map,
-                        nal_type,
-                    ) {
-                        new_slice.slice_pic_parameter_set_id != slice.slice_pic_parameter_set_id
-                    } else {
-                        false
-                    }
+                } else if let (Some(slice), Some(new_slice)) =
+                    (current_slice, slices.get(&nal_data_start))
+                {
+                    new_slice.header.slice_pic_parameter_set_id
+                        != slice.header.slice_pic_parameter_set_id
                 } else {
                     false
                 };
@@ -313,14 +339,13 @@ pub fn extract_annex_b_frames(data: &[u8]) -> Result<Vec<HevcFrame>, BitvueError
                     current_frame_index,
                     &current_frame_nals,
                     data,
-                    0, // current_poc not yet implemented
                     current_frame_num.unwrap_or(0),
                     current_is_idr,
                     current_is_irap,
                     current_is_ref,
                     current_frame_type,
                     current_temporal_id,
-                    current_slice_header.clone(),
+                    current_slice,
                 ) {
                     frames.push(frame);
                 }
@@ -334,20 +359,16 @@ pub fn extract_annex_b_frames(data: &[u8]) -> Result<Vec<HevcFrame>, BitvueError
             current_is_ref = is_ref;
             current_temporal_id = temporal_id;
 
-            // Try to parse slice header for frame type
-            if let Ok(slice) = crate::slice::parse_slice_header(
-                &data[nal_data_start + 1..nal_end],
-                &stream.sps_map,
-                &stream.pps_map,
-                nal_type,
-            ) {
+            // Take the frame type from the parsed slice header
+            if let Some(&slice) = slices.get(&nal_data_start) {
                 if current_frame_nals.is_empty() {
-                    current_frame_num = Some(slice.slice_pic_order_cnt_lsb);
-                    current_slice_header = Some(slice.clone());
+                    current_frame_num = Some(slice.header.slice_pic_order_cnt_lsb);
+                    current_slice = Some(slice);
                 }
 
                 // Determine frame type from slice type
-                current_frame_type = HevcFrameType::from_slice_type(slice.slice_type.as_str());
+                current_frame_type =
+                    HevcFrameType::from_slice_type(slice.header.slice_type.as_str());
             }
 
             current_frame_nals.push((nal_start, nal_end));
@@ -359,14 +380,13 @@ pub fn extract_annex_b_frames(data: &[u8]) -> Result<Vec<HevcFrame>, BitvueError
                     current_frame_index,
                     &current_frame_nals,
                     data,
-                    0, // current_poc not yet implemented
                     current_frame_num.unwrap_or(0),
                     current_is_idr,
                     current_is_irap,
                     current_is_ref,
                     current_frame_type,
                     current_temporal_id,
-                    current_slice_header.clone(),
+                    current_slice,
                 ) {
                     frames.push(frame);
                 }
@@ -378,47 +398,74 @@ pub fn extract_annex_b_frames(data: &[u8]) -> Result<Vec<HevcFrame>, BitvueError
 
     // Don't forget the last frame
     if !current_frame_nals.is_empty() {
-        // Find POC from parsed slices
-        let poc = stream
-            .slices
-            .get(current_frame_index)
-            .map(|s| s.poc)
-            .unwrap_or(0);
-
         if let Some(frame) = build_frame_from_nals(
             current_frame_index,
             &current_frame_nals,
             data,
-            poc,
             current_frame_num.unwrap_or(0),
             current_is_idr,
             current_is_irap,
             current_is_ref,
             current_frame_type,
             current_temporal_id,
-            current_slice_header.clone(),
+            current_slice,
         ) {
             frames.push(frame);
         }
     }
 
+    resolve_ref_frames(&mut frames);
     Ok(frames)
 }
 
+/// Find the frame holding the picture with `poc` that `frame_index` refers to
+///
+/// Searches backwards in decoding order, without leaving the coded video
+/// sequence (which starts at an IDR frame).
+fn find_ref_frame(frames: &[HevcFrame], frame_index: usize, poc: i32) -> Option<usize> {
+    for (i, frame) in frames[..frame_index.min(frames.len())]
+        .iter()
+        .enumerate()
+        .rev()
+    {
+        if frame.poc == poc {
+            return Some(i);
+        }
+        if frame.is_idr {
+            break;
+        }
+    }
+    None
+}
+
+/// Resolve the reference picture lists of every frame to frame indices
+fn resolve_ref_frames(frames: &mut [HevcFrame]) {
+    for i in 0..frames.len() {
+        let mut ref_frames = Vec::new();
+        for entry in frames[i].ref_pic_lists.iter().filter(|e| !e.missing) {
+            if let Some(j) = find_ref_frame(frames, i, entry.poc) {
+                if !ref_frames.contains(&j) {
+                    ref_frames.push(j);
+                }
+            }
+        }
+        frames[i].ref_frames = ref_frames;
+    }
+}
+
 /// Build a frame from collected NAL unit positions
 #[allow(clippy::too_many_arguments)]
 fn build_frame_from_nals(
     frame_index: usize,
     nal_positions: &[(usize, usize)],
     data: &[u8],
-    poc: i32,
     frame_num: u32,
     is_idr: bool,
     is_irap: bool,
     is_ref: bool,
     frame_type: HevcFrameType,
     temporal_id: Option<u8>,
-    slice_header: Option<SliceHeader>,
+    slice: Option<&ParsedSlice>,
 ) -> Option<HevcFrame> {
     if nal_positions.is_empty() {
         return None;
@@ -439,13 +486,15 @@ fn build_frame_from_nals(
         nal_data,
         offset,
         size,
-        poc,
+        poc: slice.map_or(0, |s| s.poc),
         frame_num,
         is_idr,
         is_irap,
         is_ref,
         temporal_id,
-        slice_header,
+        slice_header: slice.map(|s| s.header.clone()),
+        ref_pic_lists: slice.map(|s| s.ref_pic_lists.clone()).unwrap_or_default(),
+        ref_frames: Vec::new(),
     })
 }
 
@@ -488,7 +537,7 @@ pub fn hevc_frame_to_unit_node(frame: &HevcFrame, _stream_id: u8) -> bitvue_core
         qp_avg,
         mv_grid: None, // TODO: Extract from slice data
         temporal_id: frame.temporal_id,
-        ref_frames: None, // TODO: Calculate from slice header
+        ref_frames: (!frame.ref_frames.is_empty()).then(|| frame.ref_frames.clone()),
         ref_slots: None,
     }
 }
@@ -501,6 +550,76 @@ pub fn hevc_frames_to_unit_nodes(frames: &[HevcFrame]) -> Vec<bitvue_core::UnitN
         .collect()
 }
 
+/// Build the reference graph of a stream from its frames
+///
+/// Nodes are laid out in display order (coded video sequences start at IDR
+/// frames, then POC order) with one row per temporal layer. Edges point
+/// from a frame to the frames in its reference picture lists.
+pub fn hevc_reference_graph(frames: &[HevcFrame]) -> ReferenceGraphView {
+    const NODE_SPACING: f32 = 40.0;
+
+    let mut cvs = 0;
+    let mut order: Vec<(usize, i32, usize)> = Vec::with_capacity(frames.len());
+    for (i, frame) in frames.iter().enumerate() {
+        if frame.is_idr && i > 0 {
+            cvs += 1;
+        }
+        order.push((cvs, frame.poc, i));
+    }
+    order.sort_unstable();
+    let mut display_idx = vec![0; frames.len()];
+    for (display, &(_, _, i)) in order.iter().enumerate() {
+        display_idx[i] = display;
+    }
+
+    let max_layer = frames
+        .iter()
+        .filter_map(|f| f.temporal_id)
+        .max()
+        .unwrap_or(0);
+    let mut graph = ReferenceGraphView::new(WorldBounds::new(
+        0.0,
+        0.0,
+        frames.len().max(1) as f32 * NODE_SPACING,
+        (max_layer as f32 + 1.0) * NODE_SPACING,
+    ));
+
+    for (i, frame) in frames.iter().enumerate() {
+        graph.add_node(GraphNode::new(
+            display_idx[i],
+            frame.frame_type.as_str().to_string(),
+            (
+                display_idx[i] as f32 * NODE_SPACING,
+                frame.temporal_id.unwrap_or(0) as f32 * NODE_SPACING,
+            ),
+        ));
+
+        let lists = &frame.ref_pic_lists;
+        let entries = lists
+            .list0
+            .iter()
+            .map(|e| (e, ReferenceType::L0))
+            .chain(lists.list1.iter().map(|e| (e, ReferenceType::L1)));
+        let mut edges: Vec<(usize, ReferenceType)> = Vec::new();
+        for (entry, list_type) in entries.filter(|(e, _)| !e.missing) {
+            let Some(j) = find_ref_frame(frames, i, entry.poc) else {
+                continue;
+            };
+            let ref_type = if entry.long_term {
+                ReferenceType::LongTerm
+            } else {
+                list_type
+            };
+            if !edges.contains(&(j, ref_type)) {
+                edges.push((j, ref_type));
+                graph.add_edge(ReferenceEdge::new(display_idx[i], display_idx[j], ref_type));
+            }
+        }
+    }
+
+    graph
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -526,4 +645,51 @@ mod tests {
         assert_eq!(HevcFrameType::from_slice_type("P"), HevcFrameType::P);
         assert_eq!(HevcFrameType::from_slice_type("B"), HevcFrameType::B);
     }
+
+    #[test]
+    fn test_reference_graph() {
+        use crate::dpb::RefPicEntry;
+
+        let entry = |poc| RefPicEntry {
+            poc,
+            long_term: false,
+            missing: false,
+        };
+        let frame = |index, frame_type, poc, list0: Vec<i32>, list1: Vec<i32>| {
+            HevcFrame::builder()
+                .frame_index(index)
+                .frame_type(frame_type)
+                .offset(index)
+                .size(1)
+                .poc(poc)
+                .frame_num(poc as u32)
+                .is_idr(index == 0)
+                .is_irap(index == 0)
+                .is_ref(true)
+                .ref_pic_lists(RefPicLists {
+                    list0: list0.into_iter().map(entry).collect(),
+                    list1: list1.into_iter().map(entry).collect(),
+                })
+                .build()
+        };
+        // Decode order I0 P2 B1
+        let mut frames = vec![
+            frame(0, HevcFrameType::I, 0, vec![], vec![]),
+            frame(1, HevcFrameType::P, 2, vec![0], vec![]),
+            frame(2, HevcFrameType::B, 1, vec![0], vec![2]),
+        ];
+        resolve_ref_frames(&mut frames);
+        assert_eq!(frames[2].ref_frames, vec![0, 1]);
+        assert_eq!(
+            hevc_frame_to_unit_node(&frames[2], 0).ref_frames,
+            Some(vec![0, 1])
+        );
+        assert_eq!(hevc_frame_to_unit_node(&frames[0], 0).ref_frames, None);
+
+        // Display order I0 B1 P2
+        let graph = hevc_reference_graph(&frames);
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.get_references(1), [0, 2].into_iter().collect());
+        assert_eq!(graph.get_dependents(0), [1, 2].into_iter().collect());
+    }
 }
@@ -10,6 +10,8 @@
 //! - SPS (Sequence Parameter Set) parsing
 //! - PPS (Picture Parameter Set) parsing
 //! - Slice header parsing
+//! - Coding tree unit parsing (CABAC) for QP, motion vector and partition overlays
+//! - SEI message parsing (HDR metadata, timing, decoded picture hash)
 //! - Syntax tree extraction for visualization
 //!
 //! # Example
@@ -26,38 +28,49 @@
 //! ```
 
 pub mod bitreader;
+pub mod dpb;
 pub mod error;
 pub mod frames;
 pub mod nal;
 pub mod overlay_extraction;
 pub mod pps;
+pub mod rps;
+pub mod sei;
 pub mod slice;
+pub mod slice_data;
 pub mod sps;
 pub mod syntax;
 pub mod vps;
 
 pub use bitreader::{remove_emulation_prevention_bytes, BitReader};
+pub use dpb::{Dpb, PictureState, RefPicEntry, RefPicLists, ReferencePictureSet};
 pub use error::{HevcError, Result};
 pub use frames::{
     extract_annex_b_frames, extract_frame_at_index, hevc_frame_to_unit_node,
-    hevc_frames_to_unit_nodes, HevcFrame, HevcFrameType,
+    hevc_frames_to_unit_nodes, hevc_reference_graph, HevcFrame, HevcFrameType,
 };
 pub use nal::{
     find_nal_units, parse_nal_header, parse_nal_units, NalUnit, NalUnitHeader, NalUnitType,
 };
 pub use overlay_extraction::{
-    extract_mv_grid, extract_partition_grid, extract_qp_grid, CodingTreeUnit, CodingUnit,
-    IntraMode, MotionVector, PartMode, PredMode,
+    extract_coding_tree_units, extract_mv_grid, extract_partition_grid, extract_qp_grid,
+    CodingTreeUnit, CodingUnit, IntraMode, MotionVector, PartMode, PredMode, PredictionUnit,
+    TransformUnit,
 };
 pub use pps::{parse_pps, Pps};
+pub use rps::{LongTermRefPic, ShortTermRefPicSet};
+pub use sei::{
+    parse_sei, parse_sei_with_context, SeiContext, SeiMessage, SeiParsedData, SeiPayloadType,
+};
 use serde::{Deserialize, Serialize};
 pub use slice::{SliceHeader, SliceType};
-pub use sps::{parse_sps, ProfileTierLevel, Sps};
+pub use slice_data::{parse_slice_data, SliceCtus};
+pub use sps::{parse_sps, PcmParameters, ProfileTierLevel, Sps};
 use std::collections::HashMap;
 
 // Re-export ChromaFormat from bitvue_core for backward compatibility
 pub use bitvue_core::ChromaFormat;
-pub use vps::Vps;
+pub use vps::{CpbSpec, HrdParameters, SubLayerHrd, Vps};
 
 /// Parsed HEVC bitstream.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,6 +85,8 @@ pub struct HevcStream {
     pub pps_map: HashMap<u8, Pps>,
     /// Parsed slice headers.
     pub slices: Vec<ParsedSlice>,
+    /// SEI messages from prefix and suffix SEI NAL units.
+    pub sei_messages: Vec<ParsedSei>,
 }
 
 /// A parsed slice with its header and associated metadata.
@@ -83,6 +98,19 @@ pub struct ParsedSlice {
     pub header: SliceHeader,
     /// POC (Picture Order Count).
     pub poc: i32,
+    /// Reference picture lists (empty for I slices).
+    pub ref_pic_lists: RefPicLists,
+}
+
+/// An SEI message with the NAL unit that carried it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParsedSei {
+    /// Index of the SEI NAL unit.
+    pub nal_index: usize,
+    /// Whether it came from a suffix SEI NAL unit.
+    pub suffix: bool,
+    /// Parsed SEI message.
+    pub message: SeiMessage,
 }
 
 impl HevcStream {
@@ -186,6 +214,260 @@ impl HevcStream {
             })
             .collect()
     }
+
+    /// Collect colour description and HDR SEI metadata for the Metadata Inspector.
+    ///
+    /// The first MDCV and CLL messages in the stream provide the static HDR
+    /// metadata; every SEI message is listed with its NAL byte offset.
+    pub fn stream_metadata(&self) -> bitvue_core::metadata::StreamMetadata {
+        use bitvue_core::metadata::{
+            ColorPrimaries, HdrFormat, MatrixCoefficients, StreamMetadata, TransferCharacteristics,
+        };
+
+        let mut metadata = StreamMetadata::new();
+
+        if let Some(sps) = self.sps_map.values().next() {
+            metadata.bit_depth = Some(sps.bit_depth_luma());
+            metadata.chroma_subsampling = Some(
+                match sps.chroma_format_idc {
+                    ChromaFormat::Monochrome => "4:0:0",
+                    ChromaFormat::Chroma420 => "4:2:0",
+                    ChromaFormat::Chroma422 => "4:2:2",
+                    ChromaFormat::Chroma444 => "4:4:4",
+                }
+                .to_string(),
+            );
+            if let Some(vui) = &sps.vui_parameters {
+                metadata.video_full_range = vui.video_full_range_flag;
+                metadata.color_primaries = vui.colour_primaries.map(ColorPrimaries::from_code);
+                metadata.transfer_characteristics = vui
+                    .transfer_characteristics
+                    .map(TransferCharacteristics::from_code);
+                metadata.matrix_coefficients = vui.matrix_coeffs.map(MatrixCoefficients::from_code);
+            }
+        }
+
+        // SEI messages are associated with the next picture (prefix) or the
+        // current one (suffix)
+        let mut frame_idx: Option<usize> = None;
+        let mut slices = self.slices.iter().peekable();
+        for sei in &self.sei_messages {
+            while let Some(slice) = slices.next_if(|s| s.nal_index < sei.nal_index) {
+                if slice.header.first_slice_segment_in_pic_flag {
+                    frame_idx = Some(frame_idx.map_or(0, |i| i + 1));
+                }
+            }
+            let sei_frame = if sei.suffix {
+                frame_idx
+            } else {
+                Some(frame_idx.map_or(0, |i| i + 1))
+            };
+
+            let message = &sei.message;
+            if metadata.mastering_display.is_none() {
+                metadata.mastering_display = message.mastering_display();
+            }
+            if metadata.content_light_level.is_none() {
+                metadata.content_light_level = message.content_light_level();
+            }
+            if let Some(SeiParsedData::AlternativeTransferCharacteristics {
+                preferred_transfer_characteristics,
+            }) = message.parsed
+            {
+                metadata.custom.insert(
+                    "preferred_transfer_characteristics".to_string(),
+                    TransferCharacteristics::from_code(preferred_transfer_characteristics).name(),
+                );
+            }
+
+            let offset = self.nal_units[sei.nal_index].offset;
+            metadata
+                .sei_messages
+                .push(message.to_metadata(offset, sei_frame));
+        }
+
+        metadata.hdr_format = Some(match metadata.transfer_characteristics {
+            Some(TransferCharacteristics::Pq)
+                if metadata.mastering_display.is_some()
+                    || metadata.content_light_level.is_some() =>
+            {
+                HdrFormat::Hdr10
+            }
+            Some(TransferCharacteristics::Pq) => HdrFormat::Pq,
+            Some(TransferCharacteristics::Hlg) => HdrFormat::Hlg,
+            _ => HdrFormat::Sdr,
+        });
+
+        metadata
+    }
+
+    /// SPS referenced by the first slice, or any SPS if there are no slices.
+    fn active_sps(&self) -> Option<&Sps> {
+        self.slices
+            .first()
+            .and_then(|s| self.get_pps(s.header.slice_pic_parameter_set_id))
+            .and_then(|pps| self.get_sps(pps.pps_seq_parameter_set_id))
+            .or_else(|| self.sps_map.values().next())
+    }
+
+    /// HRD parameters signalled in the active SPS VUI.
+    ///
+    /// Uses SchedSelIdx 0 of the highest sub-layer, preferring the NAL HRD
+    /// over the VCL HRD. The initial removal delay comes from the first
+    /// buffering period SEI. Returns `None` when the stream carries no HRD
+    /// parameters or no VUI timing.
+    pub fn hrd_parameters(&self) -> Option<bitvue_core::hrd::HrdParameters> {
+        let vui = self.active_sps()?.vui_parameters.as_ref()?;
+        let hrd = vui.hrd_parameters.as_ref()?;
+        let sub_layer = hrd.highest_sub_layer()?;
+        let nal = !sub_layer.nal_cpbs.is_empty();
+        let cpb = sub_layer
+            .nal_cpbs
+            .first()
+            .or_else(|| sub_layer.vcl_cpbs.first())?;
+
+        let initial_cpb_removal_delay = self
+            .sei_messages
+            .iter()
+            .find_map(|sei| match &sei.message.parsed {
+                Some(SeiParsedData::BufferingPeriod(bp)) => if nal {
+                    &bp.nal_initial_cpb_removal
+                } else {
+                    &bp.vcl_initial_cpb_removal
+                }
+                .first()
+                .map(|r| r.initial_cpb_removal_delay as u64),
+                _ => None,
+            })
+            .unwrap_or(0);
+
+        Some(bitvue_core::hrd::HrdParameters {
+            cpb_size_bits: hrd.cpb_size(cpb),
+            bit_rate_bps: hrd.bit_rate(cpb),
+            initial_cpb_removal_delay,
+            cpb_removal_delay_length: hrd.au_cpb_removal_delay_length_minus1 + 1,
+            dpb_output_delay_length: hrd.dpb_output_delay_length_minus1 + 1,
+            time_scale: vui.time_scale.filter(|&t| t > 0)?,
+            num_units_in_tick: vui.num_units_in_tick.filter(|&n| n > 0)?,
+            low_delay_hrd: sub_layer.low_delay_hrd_flag,
+            cbr_flag: cpb.cbr_flag,
+        })
+    }
+
+    /// CPB removal and DPB output times of every access unit (C.2.3).
+    ///
+    /// Removal times count clock ticks from the most recent buffering period
+    /// access unit using the picture timing SEI; access units without one
+    /// are assumed to follow one tick after their predecessor. Frame indices
+    /// are in decode order.
+    pub fn hrd_timings(
+        &self,
+        params: &bitvue_core::hrd::HrdParameters,
+    ) -> Vec<bitvue_core::hrd::FrameHrdTiming> {
+        use bitvue_core::hrd::FrameHrdTiming;
+
+        let first_slices: Vec<usize> = self
+            .slices
+            .iter()
+            .filter(|s| s.header.first_slice_segment_in_pic_flag)
+            .map(|s| s.nal_index)
+            .collect();
+        if first_slices.is_empty() {
+            return Vec::new();
+        }
+
+        // Access unit sizes: leading non-VCL NAL units belong to the next
+        // picture, suffix NAL units to the current one
+        let mut sizes = vec![0u64; first_slices.len()];
+        let mut pending = 0u64;
+        let mut current: Option<usize> = None;
+        let mut next_picture = first_slices.iter().peekable();
+        for (i, nal) in self.nal_units.iter().enumerate() {
+            let nal_type = nal.header.nal_unit_type;
+            if next_picture.next_if(|&&n| n == i).is_some() {
+                let au = current.map_or(0, |c| c + 1);
+                sizes[au] += pending + nal.size;
+                pending = 0;
+                current = Some(au);
+            } else if let Some(au) = current.filter(|_| {
+                nal_type.is_vcl()
+                    || matches!(
+                        nal_type,
+                        NalUnitType::SuffixSeiNut
+                            | NalUnitType::EosNut
+                            | NalUnitType::EobNut
+                            | NalUnitType::FdNut
+                    )
+            }) {
+                sizes[au] += nal.size;
+            } else {
+                pending += nal.size;
+            }
+        }
+
+        // Buffering period and picture timing SEI are prefix SEI of the
+        // access unit whose first slice follows them
+        let mut buffering = vec![None; first_slices.len()];
+        let mut timing = vec![None; first_slices.len()];
+        for sei in self.sei_messages.iter().filter(|s| !s.suffix) {
+            let au = first_slices.partition_point(|&n| n < sei.nal_index);
+            match &sei.message.parsed {
+                Some(SeiParsedData::BufferingPeriod(bp)) if au < buffering.len() => {
+                    buffering[au] = Some(bp);
+                }
+                Some(SeiParsedData::PicTiming(pt)) if au < timing.len() => {
+                    timing[au] = Some(pt);
+                }
+                _ => {}
+            }
+        }
+
+        let tc = params.num_units_in_tick as f64 / params.time_scale.max(1) as f64;
+        let mut anchor: Option<f64> = None;
+        let mut previous = 0.0;
+        sizes
+            .iter()
+            .enumerate()
+            .map(|(n, &size)| {
+                let cpb_delay =
+                    timing[n].and_then(|pt| pt.au_cpb_removal_delay_minus1.map(|d| d + 1));
+                let dpb_delay = timing[n].and_then(|pt| pt.pic_dpb_output_delay);
+
+                let removal = match (anchor, cpb_delay) {
+                    (None, _) => params.initial_cpb_removal_delay as f64 / 90000.0,
+                    (Some(t_nb), Some(delay)) => t_nb + tc * delay as f64,
+                    (Some(_), None) => previous + tc,
+                };
+                if anchor.is_none() || buffering[n].is_some() {
+                    anchor = Some(removal);
+                }
+                previous = removal;
+
+                let output = removal + tc * dpb_delay.unwrap_or(0) as f64;
+                let to_90khz = |ticks: u32| (tc * ticks as f64 * 90000.0).round() as u64;
+                FrameHrdTiming {
+                    display_idx: n,
+                    frame_size_bits: size * 8,
+                    cpb_removal_delay: cpb_delay.map(to_90khz),
+                    dpb_output_delay: dpb_delay.map(to_90khz),
+                    pts_sec: output,
+                    dts_sec: Some(removal),
+                    earliest_arrival_sec: None,
+                }
+            })
+            .collect()
+    }
+
+    /// Run the CPB model with the signalled HRD parameters and timing.
+    ///
+    /// Returns `None` when the stream carries no HRD parameters.
+    pub fn hrd_model(&self) -> Option<bitvue_core::hrd::HrdModel> {
+        let params = self.hrd_parameters()?;
+        let timings = self.hrd_timings(&params);
+        let mut model = bitvue_core::hrd::HrdModel::new(params);
+        model.process_signalled_frames(&timings);
+        Some(model)
+    }
 }
 
 /// Parse HEVC bitstream from Annex B byte stream.
@@ -196,10 +478,12 @@ pub fn parse_hevc(data: &[u8]) -> Result<HevcStream> {
     let mut sps_map = HashMap::new();
     let mut pps_map = HashMap::new();
     let mut slices = Vec::new();
+    let mut sei_messages = Vec::new();
+    let mut sei_context = SeiContext::default();
 
-    // POC calculation state
-    let mut prev_poc_msb: i32 = 0;
-    let mut prev_poc_lsb: i32 = 0;
+    // POC and reference picture marking state
+    let mut dpb = Dpb::new();
+    let mut picture: Option<PictureState> = None;
 
     for (nal_index, nal) in nal_units.iter().enumerate() {
         match nal.header.nal_unit_type {
@@ -210,6 +494,7 @@ pub fn parse_hevc(data: &[u8]) -> Result<HevcStream> {
             }
             NalUnitType::SpsNut => {
                 if let Ok(sps) = sps::parse_sps(&nal.payload) {
+                    sei_context = SeiContext::from_sps(&sps);
                     sps_map.insert(sps.sps_seq_parameter_set_id, sps);
                 }
             }
@@ -218,54 +503,50 @@ pub fn parse_hevc(data: &[u8]) -> Result<HevcStream> {
                     pps_map.insert(pps.pps_pic_parameter_set_id, pps);
                 }
             }
+            NalUnitType::PrefixSeiNut | NalUnitType::SuffixSeiNut => {
+                if let Ok(messages) = parse_sei_with_context(&nal.payload, &sei_context) {
+                    let suffix = nal.header.nal_unit_type == NalUnitType::SuffixSeiNut;
+                    sei_messages.extend(messages.into_iter().map(|message| ParsedSei {
+                        nal_index,
+                        suffix,
+                        message,
+                    }));
+                }
+            }
+            NalUnitType::EosNut => dpb.end_of_sequence(),
             nal_type if nal_type.is_vcl() => {
                 // Parse slice header
                 if let Ok(header) =
                     slice::parse_slice_header(&nal.payload, &sps_map, &pps_map, nal_type)
                 {
-                    // Calculate POC
-                    let poc = if nal_type.is_idr() {
-                        prev_poc_msb = 0;
-                        prev_poc_lsb = 0;
-                        0
-                    } else {
-                        // Get max_poc_lsb from SPS
-                        let sps = pps_map
-                            .get(&header.slice_pic_parameter_set_id)
-                            .and_then(|pps| sps_map.get(&pps.pps_seq_parameter_set_id));
-
-                        if let Some(sps) = sps {
-                            let max_poc_lsb =
-                                1 << sps.log2_max_pic_order_cnt_lsb_minus4.saturating_add(4);
-                            let poc_lsb = header.slice_pic_order_cnt_lsb as i32;
-
-                            let poc_msb = if poc_lsb < prev_poc_lsb
-                                && (prev_poc_lsb - poc_lsb) >= (max_poc_lsb / 2)
-                            {
-                                prev_poc_msb + max_poc_lsb
-                            } else if poc_lsb > prev_poc_lsb
-                                && (poc_lsb - prev_poc_lsb) > (max_poc_lsb / 2)
-                            {
-                                prev_poc_msb - max_poc_lsb
-                            } else {
-                                prev_poc_msb
-                            };
-
-                            if nal_type.is_reference() {
-                                prev_poc_msb = poc_msb;
-                                prev_poc_lsb = poc_lsb;
-                            }
-
-                            poc_msb + poc_lsb
-                        } else {
-                            0
+                    let sps = pps_map
+                        .get(&header.slice_pic_parameter_set_id)
+                        .and_then(|pps| sps_map.get(&pps.pps_seq_parameter_set_id));
+                    if let Some(sps) = sps {
+                        if header.first_slice_segment_in_pic_flag || picture.is_none() {
+                            picture = Some(dpb.start_picture(
+                                nal_type,
+                                nal.header.temporal_id(),
+                                &header,
+                                sps,
+                            ));
                         }
+                    }
+                    let (poc, ref_pic_lists) = match &picture {
+                        // Dependent slice segments inherit the lists of the previous segment
+                        Some(_) if header.dependent_slice_segment_flag => slices
+                            .last()
+                            .map(|s: &ParsedSlice| (s.poc, s.ref_pic_lists.clone()))
+                            .unwrap_or_default(),
+                        Some(state) => (state.poc, state.ref_pic_lists(&header)),
+                        None => (0, RefPicLists::default()),
                     };
 
                     slices.push(ParsedSlice {
                         nal_index,
                         header,
                         poc,
+                        ref_pic_lists,
                     });
                 }
             }
@@ -279,6 +560,7 @@ pub fn parse_hevc(data: &[u8]) -> Result<HevcStream> {
         sps_map,
         pps_map,
         slices,
+        sei_messages,
     })
 }
 
@@ -12,13 +12,21 @@
 //! - ✅ Extract prediction modes (intra/inter)
 //! - ✅ Extract transform sizes from TUs
 //!
+//! Slices that cannot be parsed (see [`crate::slice_data`] for the
+//! unsupported features) are logged and left out; their coding units fall
+//! back to the base QP and to missing motion.
+//!
 //! ## Data Flow
 //!
 //! 1. **NAL Units** → parse_nal_units() → Vec<NalUnit>
-//! 2. **Slice Data** → parse_ctus() → Vec<CodingTreeUnit>
+//! 2. **Slice Data** → parse_slice_data() → Vec<CodingTreeUnit>
 //! 3. **CTUs** → extract_*_grid() → overlay grids
 
-use crate::nal::NalUnit;
+use crate::dpb::{Dpb, PictureState};
+use crate::nal::{NalUnit, NalUnitType};
+use crate::pps::Pps;
+use crate::slice::parse_slice_header;
+use crate::slice_data::parse_slice_data;
 use crate::sps::Sps;
 use bitvue_core::{
     mv_overlay::{BlockMode, MVGrid, MotionVector as CoreMV},
@@ -27,6 +35,7 @@ use bitvue_core::{
     BitvueError,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Prediction mode for HEVC coding units
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -67,10 +76,21 @@ pub enum IntraMode {
     Planar,
     /// DC prediction
     Dc,
-    /// Angular mode (0-32)
+    /// Angular mode (2-34)
     Angular(u8),
 }
 
+impl IntraMode {
+    /// Intra prediction mode from its IntraPredModeY/C value (0-34)
+    pub fn from_mode(mode: u8) -> Self {
+        match mode {
+            0 => Self::Planar,
+            1 => Self::Dc,
+            _ => Self::Angular(mode),
+        }
+    }
+}
+
 /// HEVC Coding Unit
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodingUnit {
@@ -98,10 +118,66 @@ pub struct CodingUnit {
     pub transform_size: u8,
     /// Depth in quadtree
     pub depth: u8,
+    /// Intra prediction modes of the prediction blocks (four for NxN)
+    #[serde(default)]
+    pub intra_modes: Vec<IntraMode>,
+    /// Chroma intra prediction mode (if intra)
+    #[serde(default)]
+    pub intra_chroma_mode: Option<IntraMode>,
+    /// cu_transquant_bypass_flag
+    #[serde(default)]
+    pub transquant_bypass: bool,
+    /// PCM samples instead of prediction and residual
+    #[serde(default)]
+    pub pcm: bool,
+    /// Prediction units (for inter blocks)
+    #[serde(default)]
+    pub prediction_units: Vec<PredictionUnit>,
+    /// Transform units (leaves of the transform tree)
+    #[serde(default)]
+    pub transform_units: Vec<TransformUnit>,
 }
 
-/// Motion vector for HEVC (quarter-pel precision)
+/// HEVC Prediction Unit of an inter coding unit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PredictionUnit {
+    /// PU position in pixels
+    pub x: u32,
+    pub y: u32,
+    /// PU size in pixels
+    pub width: u32,
+    pub height: u32,
+    /// Motion inherited from a merge candidate
+    pub merge_flag: bool,
+    /// Motion vectors in quarter-pel units
+    pub mv_l0: Option<MotionVector>,
+    pub mv_l1: Option<MotionVector>,
+    /// Reference frame indices
+    pub ref_idx_l0: Option<i8>,
+    pub ref_idx_l1: Option<i8>,
+}
+
+/// HEVC Transform Unit (leaf of the transform tree)
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TransformUnit {
+    /// TU position in pixels
+    pub x: u32,
+    pub y: u32,
+    /// Luma transform size (4, 8, 16, 32)
+    pub size: u8,
+    /// Depth in the transform tree
+    pub depth: u8,
+    /// Coded block flags
+    pub cbf_luma: bool,
+    pub cbf_cb: bool,
+    pub cbf_cr: bool,
+    /// Nonzero coefficients over the luma and chroma transform blocks
+    #[serde(default)]
+    pub coeff_count: u32,
+}
+
+/// Motion vector for HEVC (quarter-pel precision)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MotionVector {
     /// Horizontal component (quarter-pel units)
     pub x: i32,
@@ -153,118 +229,69 @@ impl CodingTreeUnit {
 
 /// Extract QP Grid from HEVC bitstream
 ///
-/// Parses CTUs from slice data and extracts QP values.
+/// Parses coding units from slice data and extracts QpY on a grid of
+/// minimum coding blocks. Areas not covered by a parsed slice use
+/// `base_qp`.
 pub fn extract_qp_grid(
     nal_units: &[NalUnit],
     sps: &Sps,
+    pps: &Pps,
     base_qp: i16,
 ) -> Result<QPGrid, BitvueError> {
-    // HEVC uses CTU size (normally 64x64)
-    let ctu_size = 64u32;
-    let width = sps.pic_width_in_luma_samples;
-    let height = sps.pic_height_in_luma_samples;
+    let (grid_w, grid_h, block_size) = grid_dimensions(sps)?;
+    let mut qp = vec![base_qp; (grid_w * grid_h) as usize];
 
-    let grid_w = width.div_ceil(ctu_size);
-    let grid_h = height.div_ceil(ctu_size);
-
-    // Check for overflow in grid size calculation
-    let total_blocks = grid_w.checked_mul(grid_h).ok_or_else(|| {
-        BitvueError::Decode(format!("Grid dimensions too large: {}x{}", grid_w, grid_h))
-    })? as usize;
-
-    let mut qp = Vec::with_capacity(total_blocks);
-
-    // Parse CTUs from slice data
-    for nal in nal_units {
-        if nal.header.nal_unit_type.is_vcl() {
-            match parse_slice_ctus(nal, sps, base_qp) {
-                Ok(ctus) => {
-                    // Collect QP values from CTUs
-                    for ctu in &ctus {
-                        // Use average QP from CTU or first CU QP
-                        let ctu_qp = ctu.coding_units.first().map(|cu| cu.qp).unwrap_or(base_qp);
-                        qp.push(ctu_qp);
-                    }
-                }
-                Err(e) => {
-                    abseil::vlog!(1, "Failed to parse CTUs: {}, using base_qp", e);
-                    // Use base_qp for CTUs in this slice
-                }
+    for ctu in parse_picture_ctus(nal_units, sps, pps) {
+        for cu in &ctu.coding_units {
+            for idx in covered_blocks(cu, grid_w, grid_h, block_size) {
+                qp[idx] = cu.qp;
             }
         }
     }
 
-    // If we didn't get any CTUs, use base_qp
-    if qp.is_empty() {
-        let total_blocks = grid_w.checked_mul(grid_h).ok_or_else(|| {
-            BitvueError::Decode(format!("Grid dimensions too large: {}x{}", grid_w, grid_h))
-        })? as usize;
-        qp = vec![base_qp; total_blocks];
-    }
-
-    Ok(QPGrid::new(grid_w, grid_h, ctu_size, ctu_size, qp, base_qp))
+    Ok(QPGrid::new(
+        grid_w, grid_h, block_size, block_size, qp, base_qp,
+    ))
 }
 
 /// Extract MV Grid from HEVC bitstream
 ///
-/// Parses CTUs from slice data and extracts motion vectors.
-pub fn extract_mv_grid(nal_units: &[NalUnit], sps: &Sps) -> Result<MVGrid, BitvueError> {
-    let width = sps.pic_width_in_luma_samples;
-    let height = sps.pic_height_in_luma_samples;
-
-    // Use 16x16 blocks for MV grid (finer than CTU)
-    let block_size = 16u32;
-    // Use ceiling division to match MVGrid::new calculation
-    let grid_w = width.div_ceil(block_size);
-    let grid_h = height.div_ceil(block_size);
-
-    // Check for overflow in grid size calculation
-    let total_blocks = grid_w.checked_mul(grid_h).ok_or_else(|| {
-        BitvueError::Decode(format!("Grid dimensions too large: {}x{}", grid_w, grid_h))
-    })? as usize;
-
-    let mut mv_l0 = Vec::with_capacity(total_blocks);
-    let mut mv_l1 = Vec::with_capacity(total_blocks);
-    let mut modes = Vec::with_capacity(total_blocks);
-
-    // Parse CTUs from slice data
-    for nal in nal_units {
-        if nal.header.nal_unit_type.is_vcl() {
-            match parse_slice_ctus(nal, sps, 26) {
-                Ok(ctus) => {
-                    for ctu in &ctus {
-                        // Expand CTU CUs to block grid
-                        expand_cu_to_blocks(ctu, block_size, &mut mv_l0, &mut mv_l1, &mut modes);
-                        // Truncate to expected size to handle edge CTUs
-                        if mv_l0.len() > total_blocks {
-                            mv_l0.truncate(total_blocks);
-                            mv_l1.truncate(total_blocks);
-                            modes.truncate(total_blocks);
-                            break;
-                        }
-                    }
-                    if mv_l0.len() >= total_blocks {
-                        break;
-                    }
-                }
-                Err(e) => {
-                    abseil::vlog!(1, "Failed to parse CTUs for MV: {}, using ZERO", e);
-                    // Use zero MV for blocks in this slice
+/// Parses coding units from slice data and extracts the motion vectors of
+/// the prediction unit covering each minimum coding block.
+pub fn extract_mv_grid(nal_units: &[NalUnit], sps: &Sps, pps: &Pps) -> Result<MVGrid, BitvueError> {
+    let (grid_w, grid_h, block_size) = grid_dimensions(sps)?;
+    let total_blocks = (grid_w * grid_h) as usize;
+
+    let mut mv_l0 = vec![CoreMV::MISSING; total_blocks];
+    let mut mv_l1 = vec![CoreMV::MISSING; total_blocks];
+    let mut modes = vec![BlockMode::None; total_blocks];
+
+    let to_core =
+        |mv: Option<MotionVector>| mv.map_or(CoreMV::MISSING, |mv| CoreMV::new(mv.x, mv.y));
+    for ctu in parse_picture_ctus(nal_units, sps, pps) {
+        for cu in &ctu.coding_units {
+            for idx in covered_blocks(cu, grid_w, grid_h, block_size) {
+                let x = (idx as u32 % grid_w) * block_size;
+                let y = (idx as u32 / grid_w) * block_size;
+                let pu = cu.prediction_units.iter().find(|pu| {
+                    (pu.x..pu.x + pu.width).contains(&x) && (pu.y..pu.y + pu.height).contains(&y)
+                });
+                modes[idx] = match cu.pred_mode {
+                    PredMode::Intra => BlockMode::Intra,
+                    PredMode::Inter => BlockMode::Inter,
+                    PredMode::Skip => BlockMode::Skip,
+                };
+                if let Some(pu) = pu {
+                    mv_l0[idx] = to_core(pu.mv_l0);
+                    mv_l1[idx] = to_core(pu.mv_l1);
                 }
             }
         }
     }
 
-    // Fill remaining if needed
-    if mv_l0.len() < total_blocks {
-        mv_l0.resize(total_blocks, CoreMV::ZERO);
-        mv_l1.resize(total_blocks, CoreMV::MISSING);
-        modes.resize(total_blocks, BlockMode::Inter);
-    }
-
     Ok(MVGrid::new(
-        width,
-        height,
+        sps.pic_width_in_luma_samples,
+        sps.pic_height_in_luma_samples,
         block_size,
         block_size,
         mv_l0,
@@ -275,199 +302,153 @@ pub fn extract_mv_grid(nal_units: &[NalUnit], sps: &Sps) -> Result<MVGrid, Bitvu
 
 /// Extract Partition Grid from HEVC bitstream
 ///
-/// Parses CTUs from slice data and creates a partition grid.
+/// Parses coding units from slice data and adds one block per coding unit,
+/// with its coding quadtree depth. CTUs not covered by a parsed slice get a
+/// scaffold block.
 pub fn extract_partition_grid(
     nal_units: &[NalUnit],
     sps: &Sps,
+    pps: &Pps,
 ) -> Result<PartitionGrid, BitvueError> {
+    grid_dimensions(sps)?;
     let width = sps.pic_width_in_luma_samples;
     let height = sps.pic_height_in_luma_samples;
+    let ctu_size = sps.ctb_size();
+    let ctu_cols = width.div_ceil(ctu_size);
+    let ctu_rows = height.div_ceil(ctu_size);
 
-    let mut grid = PartitionGrid::new(width, height, 64);
-
-    // Parse CTUs from slice data
-    for nal in nal_units {
-        if nal.header.nal_unit_type.is_vcl() {
-            match parse_slice_ctus(nal, sps, 26) {
-                Ok(ctus) => {
-                    for ctu in &ctus {
-                        for cu in &ctu.coding_units {
-                            let partition_type = match cu.part_mode {
-                                PartMode::Part2Nx2N => PartitionType::None,
-                                PartMode::NxN => PartitionType::Split,
-                                PartMode::Part2NxN => PartitionType::Horz,
-                                PartMode::PartNx2N => PartitionType::Vert,
-                                _ => PartitionType::Split, // Asymmetric splits as split
-                            };
-
-                            grid.add_block(PartitionBlock::new(
-                                cu.x,
-                                cu.y,
-                                cu.size as u32,
-                                cu.size as u32,
-                                partition_type,
-                                cu.depth,
-                            ));
-                        }
-                    }
-                }
-                Err(e) => {
-                    abseil::vlog!(
-                        1,
-                        "Failed to parse CTUs for partition: {}, using scaffold",
-                        e
-                    );
-                    // Add scaffold blocks
-                }
-            }
+    let mut grid = PartitionGrid::new(width, height, ctu_size);
+    let mut parsed = vec![false; (ctu_cols * ctu_rows) as usize];
+
+    for ctu in parse_picture_ctus(nal_units, sps, pps) {
+        parsed[((ctu.y / ctu_size) * ctu_cols + ctu.x / ctu_size) as usize] = true;
+        for cu in &ctu.coding_units {
+            let partition_type = match cu.part_mode {
+                PartMode::Part2Nx2N => PartitionType::None,
+                PartMode::NxN => PartitionType::Split,
+                PartMode::Part2NxN => PartitionType::Horz,
+                PartMode::PartNx2N => PartitionType::Vert,
+                _ => PartitionType::Split, // Asymmetric splits as split
+            };
+
+            grid.add_block(PartitionBlock::new(
+                cu.x,
+                cu.y,
+                cu.size as u32,
+                cu.size as u32,
+                partition_type,
+                cu.depth,
+            ));
         }
     }
 
-    // Fill with scaffold blocks if empty
-    if grid.blocks.is_empty() {
-        let ctu_size = 64u32;
-        let grid_w = width.div_ceil(ctu_size);
-        let grid_h = height.div_ceil(ctu_size);
-        for ctu_y in 0..grid_h {
-            for ctu_x in 0..grid_w {
-                grid.add_block(PartitionBlock::new(
-                    ctu_x * ctu_size,
-                    ctu_y * ctu_size,
-                    ctu_size,
-                    ctu_size,
-                    PartitionType::None,
-                    0,
-                ));
-            }
-        }
+    // Scaffold blocks for CTUs without parsed slice data
+    for (addr, _) in parsed.iter().enumerate().filter(|(_, &parsed)| !parsed) {
+        let ctu_x = (addr as u32 % ctu_cols) * ctu_size;
+        let ctu_y = (addr as u32 / ctu_cols) * ctu_size;
+        grid.add_block(PartitionBlock::new(
+            ctu_x,
+            ctu_y,
+            ctu_size,
+            ctu_size,
+            PartitionType::None,
+            0,
+        ));
     }
 
     Ok(grid)
 }
 
-/// Expand CTU CUs to block grid for MV visualization
-fn expand_cu_to_blocks(
-    ctu: &CodingTreeUnit,
-    block_size: u32,
-    mv_l0: &mut Vec<CoreMV>,
-    mv_l1: &mut Vec<CoreMV>,
-    modes: &mut Vec<BlockMode>,
-) {
-    let blocks_per_ctu = (ctu.size as u32 / block_size) * (ctu.size as u32 / block_size);
-
-    for cu in &ctu.coding_units {
-        let blocks_in_cu = ((cu.size as u32) / block_size).max(1);
-        let cu_blocks = blocks_in_cu * blocks_in_cu;
-
-        for _ in 0..cu_blocks {
-            match cu.pred_mode {
-                PredMode::Intra => {
-                    mv_l0.push(CoreMV::MISSING);
-                    mv_l1.push(CoreMV::MISSING);
-                    modes.push(BlockMode::Intra);
-                }
-                PredMode::Skip => {
-                    mv_l0.push(CoreMV::ZERO);
-                    mv_l1.push(CoreMV::MISSING);
-                    modes.push(BlockMode::Skip);
-                }
-                PredMode::Inter => {
-                    // Has motion vectors
-                    if let Some(ref mv) = cu.mv_l0 {
-                        mv_l0.push(CoreMV::new(mv.x, mv.y));
-                    } else {
-                        mv_l0.push(CoreMV::ZERO);
-                    }
-
-                    if let Some(ref mv) = cu.mv_l1 {
-                        mv_l1.push(CoreMV::new(mv.x, mv.y));
-                    } else {
-                        mv_l1.push(CoreMV::MISSING);
-                    }
-
-                    modes.push(BlockMode::Inter);
-                }
-            }
-        }
-    }
-
-    // Fill any remaining blocks in CTU
-    let current_blocks = mv_l0.len() % (blocks_per_ctu as usize);
-    if current_blocks > 0 {
-        let remaining = (blocks_per_ctu as usize) - current_blocks;
-        for _ in 0..remaining {
-            mv_l0.push(CoreMV::ZERO);
-            mv_l1.push(CoreMV::MISSING);
-            modes.push(BlockMode::Inter);
-        }
-    }
-}
-
-/// Parse CTUs from slice data
+/// Extract the coding tree units of a picture
 ///
-/// This is a simplified implementation that extracts basic CTU
-/// information. Full implementation would parse coding_tree_unit() syntax.
-fn parse_slice_ctus(
-    nal: &NalUnit,
+/// Slices that cannot be parsed are left out.
+pub fn extract_coding_tree_units(
+    nal_units: &[NalUnit],
     sps: &Sps,
-    base_qp: i16,
+    pps: &Pps,
 ) -> Result<Vec<CodingTreeUnit>, BitvueError> {
-    let mut ctus = Vec::new();
+    grid_dimensions(sps)?;
+    Ok(parse_picture_ctus(nal_units, sps, pps))
+}
 
-    let width = sps.pic_width_in_luma_samples;
-    let height = sps.pic_height_in_luma_samples;
-    let ctu_size = 64u32;
+/// Picture size in minimum coding blocks, and the block size.
+fn grid_dimensions(sps: &Sps) -> Result<(u32, u32, u32), BitvueError> {
+    let block_size = sps.min_cb_size();
+    let grid_w = sps.pic_width_in_luma_samples.div_ceil(block_size);
+    let grid_h = sps.pic_height_in_luma_samples.div_ceil(block_size);
 
-    let ctu_cols = width.div_ceil(ctu_size);
-    let ctu_rows = height.div_ceil(ctu_size);
-    let total_ctus = ctu_cols * ctu_rows;
-
-    let is_intra = nal.header.nal_unit_type.is_idr() || nal.header.nal_unit_type.is_bla();
+    // Check for overflow in grid size calculation
+    grid_w.checked_mul(grid_h).ok_or_else(|| {
+        BitvueError::Decode(format!("Grid dimensions too large: {}x{}", grid_w, grid_h))
+    })?;
+    Ok((grid_w, grid_h, block_size))
+}
 
-    for ctu_idx in 0..total_ctus {
-        let ctu_x = (ctu_idx % ctu_cols) * ctu_size;
-        let ctu_y = (ctu_idx / ctu_cols) * ctu_size;
+/// Grid indices of the minimum coding blocks covered by a coding unit.
+fn covered_blocks(
+    cu: &CodingUnit,
+    grid_w: u32,
+    grid_h: u32,
+    block_size: u32,
+) -> impl Iterator<Item = usize> {
+    let x0 = cu.x / block_size;
+    let y0 = cu.y / block_size;
+    let n = (cu.size as u32 / block_size).max(1);
+    (y0..(y0 + n).min(grid_h))
+        .flat_map(move |y| (x0..(x0 + n).min(grid_w)).map(move |x| (y * grid_w + x) as usize))
+}
 
-        let mut ctu = CodingTreeUnit::new(ctu_x, ctu_y, ctu_size as u8);
+/// Parse the slices of a picture into coding tree units.
+///
+/// The reference picture lists each slice needs for motion vector
+/// prediction are derived by running a DPB over the slices in order.
+fn parse_picture_ctus(nal_units: &[NalUnit], sps: &Sps, pps: &Pps) -> Vec<CodingTreeUnit> {
+    let sps_map = HashMap::from([(pps.pps_seq_parameter_set_id, sps.clone())]);
+    let pps_map = HashMap::from([(pps.pps_pic_parameter_set_id, pps.clone())]);
+    let mut dpb = Dpb::new();
+    let mut picture: Option<PictureState> = None;
+    let mut ctus = Vec::new();
 
-        // Add a single CU covering the entire CTU (simplified)
-        let pred_mode = if is_intra {
-            PredMode::Intra
-        } else {
-            PredMode::Inter
+    for nal in nal_units {
+        let nal_type = nal.nal_type();
+        if nal_type == NalUnitType::EosNut {
+            dpb.end_of_sequence();
+            continue;
+        }
+        if !nal_type.is_vcl() {
+            continue;
+        }
+        let header = match parse_slice_header(&nal.payload, &sps_map, &pps_map, nal_type) {
+            Ok(header) => header,
+            Err(e) => {
+                abseil::vlog!(1, "Failed to parse slice header: {}, using fallback", e);
+                continue;
+            }
         };
-
-        let cu = CodingUnit {
-            x: ctu_x,
-            y: ctu_y,
-            size: ctu_size as u8,
-            pred_mode,
-            part_mode: PartMode::Part2Nx2N,
-            intra_mode: if is_intra {
-                Some(IntraMode::Planar)
-            } else {
-                None
-            },
-            qp: base_qp,
-            mv_l0: None,
-            mv_l1: None,
-            ref_idx_l0: None,
-            ref_idx_l1: None,
-            transform_size: 4, // 4x4 transform base
-            depth: 0,
+        if header.first_slice_segment_in_pic_flag || picture.is_none() {
+            picture = Some(dpb.start_picture(nal_type, nal.header.temporal_id(), &header, sps));
+        }
+        let Some(state) = picture.as_ref() else {
+            continue;
         };
 
-        ctu.add_cu(cu);
-        ctus.push(ctu);
+        let ref_pic_lists = state.ref_pic_lists(&header);
+        match parse_slice_data(nal, sps, pps, state.poc, &ref_pic_lists) {
+            Ok(slice) => ctus.extend(slice.ctus),
+            Err(e) => {
+                abseil::vlog!(1, "Failed to parse CTUs: {}, using fallback", e);
+            }
+        }
     }
 
-    Ok(ctus)
+    ctus
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::nal::{NalUnit, NalUnitHeader};
+    use crate::pps::Pps;
     use crate::sps::{ChromaFormat, Profile, Sps};
 
     fn create_test_sps(width: u32, height: u32) -> Sps {
@@ -514,9 +495,13 @@ mod tests {
             amp_enabled_flag: false,
             sample_adaptive_offset_enabled_flag: false,
             pcm_enabled_flag: false,
+            pcm_parameters: None,
             num_short_term_ref_pic_sets: 0,
             long_term_ref_pics_present_flag: false,
             num_long_term_ref_pics_sps: 0,
+            st_ref_pic_sets: Vec::new(),
+            lt_ref_pic_poc_lsb_sps: Vec::new(),
+            used_by_curr_pic_lt_sps_flag: Vec::new(),
             sps_temporal_mvp_enabled_flag: false,
             strong_intra_smoothing_enabled_flag: false,
             vui_parameters_present_flag: false,
@@ -597,6 +582,12 @@ mod tests {
             ref_idx_l1: None,
             transform_size: 4,
             depth: 0,
+            intra_modes: vec![],
+            intra_chroma_mode: None,
+            transquant_bypass: false,
+            pcm: false,
+            prediction_units: vec![],
+            transform_units: vec![],
         };
         ctu.add_cu(cu);
         assert_eq!(ctu.coding_units.len(), 1);
@@ -617,44 +608,49 @@ mod tests {
     #[test]
     fn test_extract_qp_grid_empty_nal_units() {
         let sps = create_test_sps(640, 480);
-        let result = extract_qp_grid(&[], &sps, 26);
+        let pps = Pps::default();
+        let result = extract_qp_grid(&[], &sps, &pps, 26);
         assert!(result.is_ok());
         let qp_grid = result.unwrap();
-        // 640/64 * 480/64 = 10 * 7 = 70 CTUs (round up)
-        assert_eq!(qp_grid.grid_w, 10);
-        assert_eq!(qp_grid.grid_h, 8);
+        // One block per 8x8 minimum coding block
+        assert_eq!(qp_grid.grid_w, 80);
+        assert_eq!(qp_grid.grid_h, 60);
+        assert!(qp_grid.qp.iter().all(|&qp| qp == 26));
     }
 
     #[test]
     fn test_extract_qp_grid_with_idr_slice() {
         let sps = create_test_sps(640, 480);
+        let pps = Pps::default();
         let nal = create_test_nal_unit(crate::NalUnitType::IdrWRadl);
-        let result = extract_qp_grid(&[nal], &sps, 26);
+        let result = extract_qp_grid(&[nal], &sps, &pps, 26);
         assert!(result.is_ok());
         let qp_grid = result.unwrap();
-        assert_eq!(qp_grid.grid_w, 10);
-        assert_eq!(qp_grid.grid_h, 8);
+        assert_eq!(qp_grid.grid_w, 80);
+        assert_eq!(qp_grid.grid_h, 60);
     }
 
     #[test]
     fn test_extract_qp_grid_with_trail_slice() {
         let sps = create_test_sps(1920, 1080);
+        let pps = Pps::default();
         let nal = create_test_nal_unit(crate::NalUnitType::TrailR);
-        let result = extract_qp_grid(&[nal], &sps, 30);
+        let result = extract_qp_grid(&[nal], &sps, &pps, 30);
         assert!(result.is_ok());
         let qp_grid = result.unwrap();
-        // 1920/64 * 1080/64 = 30 * 17 = 510 CTUs (round up)
-        assert_eq!(qp_grid.grid_w, 30);
-        assert_eq!(qp_grid.grid_h, 17);
+        // 1920/8 * 1080/8 = 240 * 135 minimum coding blocks
+        assert_eq!(qp_grid.grid_w, 240);
+        assert_eq!(qp_grid.grid_h, 135);
     }
 
     #[test]
     fn test_extract_qp_grid_base_qp_variations() {
         let sps = create_test_sps(640, 480);
+        let pps = Pps::default();
         let nal = create_test_nal_unit(crate::NalUnitType::IdrWRadl);
 
         for base_qp in [0i16, 10, 26, 40, 51] {
-            let result = extract_qp_grid(&[nal.clone()], &sps, base_qp);
+            let result = extract_qp_grid(std::slice::from_ref(&nal), &sps, &pps, base_qp);
             assert!(result.is_ok(), "Failed for base_qp={}", base_qp);
         }
     }
@@ -662,10 +658,11 @@ mod tests {
     #[test]
     fn test_extract_mv_grid_empty_nal_units() {
         let sps = create_test_sps(640, 480);
-        let result = extract_mv_grid(&[], &sps);
+        let pps = Pps::default();
+        let result = extract_mv_grid(&[], &sps, &pps);
         assert!(result.is_ok());
         let mv_grid = result.unwrap();
-        // MV grid uses 16x16 blocks
+        // MV grid uses minimum coding blocks
         assert_eq!(mv_grid.coded_width, 640);
         assert_eq!(mv_grid.coded_height, 480);
     }
@@ -673,8 +670,9 @@ mod tests {
     #[test]
     fn test_extract_mv_grid_with_slice() {
         let sps = create_test_sps(640, 480);
+        let pps = Pps::default();
         let nal = create_test_nal_unit(crate::NalUnitType::TrailR);
-        let result = extract_mv_grid(&[nal], &sps);
+        let result = extract_mv_grid(&[nal], &sps, &pps);
         assert!(result.is_ok());
         let mv_grid = result.unwrap();
         assert_eq!(mv_grid.coded_width, 640);
@@ -685,19 +683,22 @@ mod tests {
     #[test]
     fn test_extract_mv_grid_intra_slice() {
         let sps = create_test_sps(320, 240);
+        let pps = Pps::default();
         let nal = create_test_nal_unit(crate::NalUnitType::IdrWRadl);
-        let result = extract_mv_grid(&[nal], &sps);
+        let result = extract_mv_grid(&[nal], &sps, &pps);
         assert!(result.is_ok());
         let mv_grid = result.unwrap();
         let modes = mv_grid.mode.as_ref().unwrap();
-        // All blocks should be Intra for IDR slice
-        assert!(modes.iter().all(|m| *m == BlockMode::Intra));
+        // The zero-filled slice does not parse, so no block has a mode
+        assert!(modes.iter().all(|m| *m == BlockMode::None));
+        assert!(mv_grid.mv_l0.iter().all(|mv| *mv == CoreMV::MISSING));
     }
 
     #[test]
     fn test_extract_partition_grid_empty_nal_units() {
         let sps = create_test_sps(640, 480);
-        let result = extract_partition_grid(&[], &sps);
+        let pps = Pps::default();
+        let result = extract_partition_grid(&[], &sps, &pps);
         assert!(result.is_ok());
         let partition_grid = result.unwrap();
         assert_eq!(partition_grid.coded_width, 640);
@@ -709,8 +710,9 @@ mod tests {
     #[test]
     fn test_extract_partition_grid_with_slice() {
         let sps = create_test_sps(640, 480);
+        let pps = Pps::default();
         let nal = create_test_nal_unit(crate::NalUnitType::IdrWRadl);
-        let result = extract_partition_grid(&[nal], &sps);
+        let result = extract_partition_grid(&[nal], &sps, &pps);
         assert!(result.is_ok());
         let partition_grid = result.unwrap();
         assert_eq!(partition_grid.coded_width, 640);
@@ -720,8 +722,9 @@ mod tests {
     #[test]
     fn test_extract_partition_grid_inter_slice() {
         let sps = create_test_sps(1920, 1080);
+        let pps = Pps::default();
         let nal = create_test_nal_unit(crate::NalUnitType::TrailR);
-        let result = extract_partition_grid(&[nal], &sps);
+        let result = extract_partition_grid(&[nal], &sps, &pps);
         assert!(result.is_ok());
         let partition_grid = result.unwrap();
         assert_eq!(partition_grid.coded_width, 1920);
@@ -744,6 +747,12 @@ mod tests {
             ref_idx_l1: None,
             transform_size: 4,
             depth: 0,
+            intra_modes: vec![],
+            intra_chroma_mode: None,
+            transquant_bypass: false,
+            pcm: false,
+            prediction_units: vec![],
+            transform_units: vec![],
         };
         assert_eq!(cu.x, 0);
         assert_eq!(cu.y, 0);
@@ -768,6 +777,12 @@ mod tests {
             ref_idx_l1: Some(1),
             transform_size: 8,
             depth: 1,
+            intra_modes: vec![],
+            intra_chroma_mode: None,
+            transquant_bypass: false,
+            pcm: false,
+            prediction_units: vec![],
+            transform_units: vec![],
         };
         assert_eq!(cu.mv_l0.unwrap().x, 4);
         assert_eq!(cu.mv_l1.unwrap().y, 4);
@@ -778,20 +793,22 @@ mod tests {
     #[test]
     fn test_extract_qp_grid_small_resolution() {
         let sps = create_test_sps(160, 120);
+        let pps = Pps::default();
         let nal = create_test_nal_unit(crate::NalUnitType::IdrWRadl);
-        let result = extract_qp_grid(&[nal], &sps, 20);
+        let result = extract_qp_grid(&[nal], &sps, &pps, 20);
         assert!(result.is_ok());
         let qp_grid = result.unwrap();
-        // 160/64 * 120/64 = 2 * 2 = 4 CTUs (round up)
-        assert_eq!(qp_grid.grid_w, 3);
-        assert_eq!(qp_grid.grid_h, 2);
+        // 160/8 * 120/8 = 20 * 15 minimum coding blocks
+        assert_eq!(qp_grid.grid_w, 20);
+        assert_eq!(qp_grid.grid_h, 15);
     }
 
     #[test]
     fn test_extract_mv_grid_high_resolution() {
         let sps = create_test_sps(3840, 2160);
+        let pps = Pps::default();
         let nal = create_test_nal_unit(crate::NalUnitType::TrailR);
-        let result = extract_mv_grid(&[nal], &sps);
+        let result = extract_mv_grid(&[nal], &sps, &pps);
         assert!(result.is_ok());
         let mv_grid = result.unwrap();
         assert_eq!(mv_grid.coded_width, 3840);
@@ -811,8 +828,9 @@ mod tests {
         let resolutions = [(320u32, 240u32), (640, 480), (1280, 720), (1920, 1080)];
         for (width, height) in resolutions {
             let sps = create_test_sps(width, height);
+            let pps = Pps::default();
             let nal = create_test_nal_unit(crate::NalUnitType::IdrWRadl);
-            let result = extract_qp_grid(&[nal], &sps, 26);
+            let result = extract_qp_grid(&[nal], &sps, &pps, 26);
             assert!(result.is_ok(), "Failed for {}x{}", width, height);
         }
     }
@@ -820,8 +838,9 @@ mod tests {
     #[test]
     fn test_extract_mv_grid_modes_present() {
         let sps = create_test_sps(640, 480);
+        let pps = Pps::default();
         let nal = create_test_nal_unit(crate::NalUnitType::TrailR);
-        let result = extract_mv_grid(&[nal], &sps);
+        let result = extract_mv_grid(&[nal], &sps, &pps);
         assert!(result.is_ok());
         let mv_grid = result.unwrap();
         assert!(mv_grid.mode.as_ref().is_some());
@@ -830,8 +849,9 @@ mod tests {
     #[test]
     fn test_extract_partition_grid_blocks_filled() {
         let sps = create_test_sps(640, 480);
+        let pps = Pps::default();
         let nal = create_test_nal_unit(crate::NalUnitType::IdrWRadl);
-        let result = extract_partition_grid(&[nal], &sps);
+        let result = extract_partition_grid(&[nal], &sps, &pps);
         assert!(result.is_ok());
         let partition_grid = result.unwrap();
         assert!(!partition_grid.blocks.is_empty());
@@ -862,6 +882,12 @@ mod tests {
             ref_idx_l1: None,
             transform_size: 4,
             depth: 0,
+            intra_modes: vec![],
+            intra_chroma_mode: None,
+            transquant_bypass: false,
+            pcm: false,
+            prediction_units: vec![],
+            transform_units: vec![],
         };
         assert_eq!(cu.pred_mode, PredMode::Skip);
         assert_eq!(cu.mv_l0.unwrap().x, 0);
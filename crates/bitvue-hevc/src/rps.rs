@@ -0,0 +1,286 @@
+//! HEVC reference picture set syntax.
+//!
+//! The short-term reference picture set (`st_ref_pic_set()`) is defined in
+//! ITU-T H.265 Section 7.3.7, with its semantics in Section 7.4.8. Sets are
+//! signalled in the SPS or directly in a slice header, and may be predicted
+//! from an earlier set of the SPS (inter RPS prediction).
+
+use crate::bitreader::BitReader;
+use crate::error::{HevcError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of pictures in one direction of a short-term RPS.
+const MAX_DELTA_POCS: u32 = 16;
+
+/// Short-term reference picture set.
+///
+/// The delta POC lists are always stored in their derived form, so sets
+/// using inter RPS prediction look the same as explicitly coded ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShortTermRefPicSet {
+    /// Whether the set is predicted from another set.
+    pub inter_ref_pic_set_prediction_flag: bool,
+    /// Distance to the reference set minus 1 (slice header sets only).
+    pub delta_idx_minus1: u32,
+    /// POC delta between this set and the reference set (DeltaRps).
+    pub delta_rps: i32,
+    /// POC deltas of the pictures preceding the current one (DeltaPocS0).
+    pub delta_poc_s0: Vec<i32>,
+    /// Whether each preceding picture is used by the current picture.
+    pub used_by_curr_pic_s0: Vec<bool>,
+    /// POC deltas of the pictures following the current one (DeltaPocS1).
+    pub delta_poc_s1: Vec<i32>,
+    /// Whether each following picture is used by the current picture.
+    pub used_by_curr_pic_s1: Vec<bool>,
+}
+
+impl ShortTermRefPicSet {
+    /// Number of pictures preceding the current one (NumNegativePics).
+    pub fn num_negative_pics(&self) -> usize {
+        self.delta_poc_s0.len()
+    }
+
+    /// Number of pictures following the current one (NumPositivePics).
+    pub fn num_positive_pics(&self) -> usize {
+        self.delta_poc_s1.len()
+    }
+
+    /// Total number of pictures in the set (NumDeltaPocs).
+    pub fn num_delta_pocs(&self) -> usize {
+        self.num_negative_pics() + self.num_positive_pics()
+    }
+
+    /// Number of pictures used by the current picture.
+    pub fn num_used_by_curr(&self) -> usize {
+        self.used_by_curr_pic_s0
+            .iter()
+            .chain(&self.used_by_curr_pic_s1)
+            .filter(|&&used| used)
+            .count()
+    }
+}
+
+/// Long-term reference picture signalled in a slice header.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LongTermRefPic {
+    /// POC LSBs of the picture (PocLsbLt).
+    pub poc_lsb_lt: u32,
+    /// Whether the picture is used by the current picture (UsedByCurrPicLt).
+    pub used_by_curr_pic_lt_flag: bool,
+    /// Whether the POC MSB cycle is signalled.
+    pub delta_poc_msb_present_flag: bool,
+    /// Accumulated POC MSB cycle (DeltaPocMsbCycleLt).
+    pub delta_poc_msb_cycle_lt: u32,
+}
+
+/// Parse `st_ref_pic_set(stRpsIdx)`.
+///
+/// `sets` holds the sets parsed so far from the SPS. A `st_rps_idx` equal to
+/// `num_short_term_ref_pic_sets` denotes the set of a slice header.
+pub fn parse_st_ref_pic_set(
+    reader: &mut BitReader,
+    st_rps_idx: usize,
+    num_short_term_ref_pic_sets: usize,
+    sets: &[ShortTermRefPicSet],
+) -> Result<ShortTermRefPicSet> {
+    let mut rps = ShortTermRefPicSet::default();
+
+    if st_rps_idx != 0 {
+        rps.inter_ref_pic_set_prediction_flag = reader.read_bit()?;
+    }
+
+    if rps.inter_ref_pic_set_prediction_flag {
+        if st_rps_idx == num_short_term_ref_pic_sets {
+            rps.delta_idx_minus1 = reader.read_ue()?;
+        }
+        let ref_idx = st_rps_idx
+            .checked_sub(rps.delta_idx_minus1 as usize + 1)
+            .and_then(|i| sets.get(i))
+            .ok_or_else(|| {
+                HevcError::InvalidData(format!(
+                    "delta_idx_minus1 {} out of range for st_ref_pic_set {}",
+                    rps.delta_idx_minus1, st_rps_idx
+                ))
+            })?;
+
+        let delta_rps_sign = reader.read_bit()?;
+        let abs_delta_rps_minus1 = reader.read_ue()?;
+        if abs_delta_rps_minus1 >= 1 << 15 {
+            return Err(HevcError::InvalidData(format!(
+                "abs_delta_rps_minus1 {} exceeds maximum {}",
+                abs_delta_rps_minus1,
+                (1 << 15) - 1
+            )));
+        }
+        rps.delta_rps = (abs_delta_rps_minus1 as i32 + 1) * if delta_rps_sign { -1 } else { 1 };
+
+        let num_delta_pocs = ref_idx.num_delta_pocs();
+        let mut used_by_curr_pic_flag = Vec::with_capacity(num_delta_pocs + 1);
+        let mut use_delta_flag = Vec::with_capacity(num_delta_pocs + 1);
+        for _ in 0..=num_delta_pocs {
+            let used = reader.read_bit()?;
+            used_by_curr_pic_flag.push(used);
+            use_delta_flag.push(if used { true } else { reader.read_bit()? });
+        }
+
+        derive_predicted_set(&mut rps, ref_idx, &used_by_curr_pic_flag, &use_delta_flag);
+    } else {
+        let num_negative_pics = reader.read_ue()?;
+        let num_positive_pics = reader.read_ue()?;
+        if num_negative_pics > MAX_DELTA_POCS || num_positive_pics > MAX_DELTA_POCS {
+            return Err(HevcError::InvalidData(format!(
+                "st_ref_pic_set with {} negative and {} positive pictures exceeds maximum {}",
+                num_negative_pics, num_positive_pics, MAX_DELTA_POCS
+            )));
+        }
+
+        let mut poc = 0i32;
+        for _ in 0..num_negative_pics {
+            poc -= reader.read_ue()? as i32 + 1;
+            rps.delta_poc_s0.push(poc);
+            rps.used_by_curr_pic_s0.push(reader.read_bit()?);
+        }
+        poc = 0;
+        for _ in 0..num_positive_pics {
+            poc += reader.read_ue()? as i32 + 1;
+            rps.delta_poc_s1.push(poc);
+            rps.used_by_curr_pic_s1.push(reader.read_bit()?);
+        }
+    }
+
+    Ok(rps)
+}
+
+/// Derive the delta POC lists of a predicted set (equations 7-61 and 7-62).
+fn derive_predicted_set(
+    rps: &mut ShortTermRefPicSet,
+    ref_rps: &ShortTermRefPicSet,
+    used_by_curr_pic_flag: &[bool],
+    use_delta_flag: &[bool],
+) {
+    let delta_rps = rps.delta_rps;
+    let num_negative = ref_rps.num_negative_pics();
+    let num_delta_pocs = ref_rps.num_delta_pocs();
+
+    for j in (0..ref_rps.num_positive_pics()).rev() {
+        let d_poc = ref_rps.delta_poc_s1[j] + delta_rps;
+        if d_poc < 0 && use_delta_flag[num_negative + j] {
+            rps.delta_poc_s0.push(d_poc);
+            rps.used_by_curr_pic_s0
+                .push(used_by_curr_pic_flag[num_negative + j]);
+        }
+    }
+    if delta_rps < 0 && use_delta_flag[num_delta_pocs] {
+        rps.delta_poc_s0.push(delta_rps);
+        rps.used_by_curr_pic_s0
+            .push(used_by_curr_pic_flag[num_delta_pocs]);
+    }
+    for j in 0..num_negative {
+        let d_poc = ref_rps.delta_poc_s0[j] + delta_rps;
+        if d_poc < 0 && use_delta_flag[j] {
+            rps.delta_poc_s0.push(d_poc);
+            rps.used_by_curr_pic_s0.push(used_by_curr_pic_flag[j]);
+        }
+    }
+
+    for j in (0..num_negative).rev() {
+        let d_poc = ref_rps.delta_poc_s0[j] + delta_rps;
+        if d_poc > 0 && use_delta_flag[j] {
+            rps.delta_poc_s1.push(d_poc);
+            rps.used_by_curr_pic_s1.push(used_by_curr_pic_flag[j]);
+        }
+    }
+    if delta_rps > 0 && use_delta_flag[num_delta_pocs] {
+        rps.delta_poc_s1.push(delta_rps);
+        rps.used_by_curr_pic_s1
+            .push(used_by_curr_pic_flag[num_delta_pocs]);
+    }
+    for j in 0..ref_rps.num_positive_pics() {
+        let d_poc = ref_rps.delta_poc_s1[j] + delta_rps;
+        if d_poc > 0 && use_delta_flag[num_negative + j] {
+            rps.delta_poc_s1.push(d_poc);
+            rps.used_by_curr_pic_s1
+                .push(used_by_curr_pic_flag[num_negative + j]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bit writer for building test payloads
+    struct Bits(Vec<bool>);
+
+    impl Bits {
+        fn bit(&mut self, b: bool) -> &mut Self {
+            self.0.push(b);
+            self
+        }
+
+        fn ue(&mut self, v: u32) -> &mut Self {
+            let code = v + 1;
+            let len = 32 - code.leading_zeros();
+            for _ in 1..len {
+                self.0.push(false);
+            }
+            for i in (0..len).rev() {
+                self.0.push(code >> i & 1 == 1);
+            }
+            self
+        }
+
+        fn bytes(&self) -> Vec<u8> {
+            let mut out = vec![0u8; self.0.len().div_ceil(8) + 1];
+            for (i, &b) in self.0.iter().enumerate() {
+                if b {
+                    out[i / 8] |= 0x80 >> (i % 8);
+                }
+            }
+            out
+        }
+    }
+
+    #[test]
+    fn test_explicit_set() {
+        // 2 negative pictures (-1, -3), 1 positive picture (+2)
+        let mut bits = Bits(Vec::new());
+        bits.ue(2).ue(1);
+        bits.ue(0).bit(true).ue(1).bit(false);
+        bits.ue(1).bit(true);
+        let data = bits.bytes();
+
+        let rps = parse_st_ref_pic_set(&mut BitReader::new(&data), 0, 1, &[]).unwrap();
+        assert_eq!(rps.delta_poc_s0, vec![-1, -3]);
+        assert_eq!(rps.used_by_curr_pic_s0, vec![true, false]);
+        assert_eq!(rps.delta_poc_s1, vec![2]);
+        assert_eq!(rps.num_delta_pocs(), 3);
+        assert_eq!(rps.num_used_by_curr(), 2);
+    }
+
+    #[test]
+    fn test_inter_predicted_set() {
+        // Reference set {-1, -3 | +2}, predicted with DeltaRps = -1
+        let ref_rps = ShortTermRefPicSet {
+            delta_poc_s0: vec![-1, -3],
+            used_by_curr_pic_s0: vec![true, true],
+            delta_poc_s1: vec![2],
+            used_by_curr_pic_s1: vec![true],
+            ..Default::default()
+        };
+
+        let mut bits = Bits(Vec::new());
+        bits.bit(true); // inter_ref_pic_set_prediction_flag
+        bits.bit(true).ue(0); // delta_rps_sign, abs_delta_rps_minus1
+        for _ in 0..4 {
+            bits.bit(true); // used_by_curr_pic_flag
+        }
+        let data = bits.bytes();
+
+        let rps = parse_st_ref_pic_set(&mut BitReader::new(&data), 1, 2, &[ref_rps]).unwrap();
+        assert!(rps.inter_ref_pic_set_prediction_flag);
+        assert_eq!(rps.delta_rps, -1);
+        assert_eq!(rps.delta_poc_s0, vec![-1, -2, -4]);
+        assert_eq!(rps.delta_poc_s1, vec![1]);
+    }
+}
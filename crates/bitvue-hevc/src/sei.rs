@@ -0,0 +1,1107 @@
+//! HEVC/H.265 Supplemental Enhancement Information (SEI) parsing.
+//!
+//! Covers the prefix and suffix SEI messages of ITU-T H.265 Annex D that
+//! bitvue inspects: HRD timing, recovery points, HDR static metadata,
+//! parameter set activation, time codes, user data and decoded picture hashes.
+
+use crate::bitreader::BitReader;
+use crate::error::{HevcError, Result};
+use crate::sps::Sps;
+use crate::vps::HrdParameters;
+use bitvue_core::metadata::{ContentLightLevel, MasteringDisplayMetadata, SeiData, SeiMessageType};
+use serde::{Deserialize, Serialize};
+
+/// SEI payload types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u32)]
+pub enum SeiPayloadType {
+    /// Buffering period
+    BufferingPeriod = 0,
+    /// Picture timing
+    PicTiming = 1,
+    /// Pan-scan rectangle
+    PanScanRect = 2,
+    /// Filler payload
+    FillerPayload = 3,
+    /// User data registered by ITU-T Rec. T.35
+    UserDataRegisteredItuTT35 = 4,
+    /// User data unregistered
+    UserDataUnregistered = 5,
+    /// Recovery point
+    RecoveryPoint = 6,
+    /// Scene information
+    SceneInfo = 9,
+    /// Picture snapshot
+    PictureSnapshot = 15,
+    /// Progressive refinement segment start
+    ProgressiveRefinementSegmentStart = 16,
+    /// Progressive refinement segment end
+    ProgressiveRefinementSegmentEnd = 17,
+    /// Film grain characteristics
+    FilmGrainCharacteristics = 19,
+    /// Post-filter hint
+    PostFilterHint = 22,
+    /// Tone mapping information
+    ToneMappingInfo = 23,
+    /// Frame packing arrangement
+    FramePackingArrangement = 45,
+    /// Display orientation
+    DisplayOrientation = 47,
+    /// Structure of pictures information
+    StructureOfPicturesInfo = 128,
+    /// Active parameter sets
+    ActiveParameterSets = 129,
+    /// Decoding unit information
+    DecodingUnitInfo = 130,
+    /// Temporal sub-layer zero index
+    TemporalSubLayerZeroIndex = 131,
+    /// Decoded picture hash
+    DecodedPictureHash = 132,
+    /// Scalable nesting
+    ScalableNesting = 133,
+    /// Region refresh information
+    RegionRefreshInfo = 134,
+    /// No display
+    NoDisplay = 135,
+    /// Time code
+    TimeCode = 136,
+    /// Mastering display colour volume
+    MasteringDisplayColourVolume = 137,
+    /// Content light level information
+    ContentLightLevelInfo = 144,
+    /// Alternative transfer characteristics
+    AlternativeTransferCharacteristics = 147,
+    /// Ambient viewing environment
+    AmbientViewingEnvironment = 148,
+    /// Unknown
+    Unknown = 0xFFFF_FFFF,
+}
+
+impl SeiPayloadType {
+    /// Create from raw value.
+    pub fn from_u32(value: u32) -> Self {
+        match value {
+            0 => SeiPayloadType::BufferingPeriod,
+            1 => SeiPayloadType::PicTiming,
+            2 => SeiPayloadType::PanScanRect,
+            3 => SeiPayloadType::FillerPayload,
+            4 => SeiPayloadType::UserDataRegisteredItuTT35,
+            5 => SeiPayloadType::UserDataUnregistered,
+            6 => SeiPayloadType::RecoveryPoint,
+            9 => SeiPayloadType::SceneInfo,
+            15 => SeiPayloadType::PictureSnapshot,
+            16 => SeiPayloadType::ProgressiveRefinementSegmentStart,
+            17 => SeiPayloadType::ProgressiveRefinementSegmentEnd,
+            19 => SeiPayloadType::FilmGrainCharacteristics,
+            22 => SeiPayloadType::PostFilterHint,
+            23 => SeiPayloadType::ToneMappingInfo,
+            45 => SeiPayloadType::FramePackingArrangement,
+            47 => SeiPayloadType::DisplayOrientation,
+            128 => SeiPayloadType::StructureOfPicturesInfo,
+            129 => SeiPayloadType::ActiveParameterSets,
+            130 => SeiPayloadType::DecodingUnitInfo,
+            131 => SeiPayloadType::TemporalSubLayerZeroIndex,
+            132 => SeiPayloadType::DecodedPictureHash,
+            133 => SeiPayloadType::ScalableNesting,
+            134 => SeiPayloadType::RegionRefreshInfo,
+            135 => SeiPayloadType::NoDisplay,
+            136 => SeiPayloadType::TimeCode,
+            137 => SeiPayloadType::MasteringDisplayColourVolume,
+            144 => SeiPayloadType::ContentLightLevelInfo,
+            147 => SeiPayloadType::AlternativeTransferCharacteristics,
+            148 => SeiPayloadType::AmbientViewingEnvironment,
+            _ => SeiPayloadType::Unknown,
+        }
+    }
+
+    /// Get human-readable name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SeiPayloadType::BufferingPeriod => "Buffering Period",
+            SeiPayloadType::PicTiming => "Picture Timing",
+            SeiPayloadType::PanScanRect => "Pan-Scan Rectangle",
+            SeiPayloadType::FillerPayload => "Filler Payload",
+            SeiPayloadType::UserDataRegisteredItuTT35 => "User Data (ITU-T T.35)",
+            SeiPayloadType::UserDataUnregistered => "User Data (Unregistered)",
+            SeiPayloadType::RecoveryPoint => "Recovery Point",
+            SeiPayloadType::SceneInfo => "Scene Information",
+            SeiPayloadType::PictureSnapshot => "Picture Snapshot",
+            SeiPayloadType::ProgressiveRefinementSegmentStart => {
+                "Progressive Refinement Segment Start"
+            }
+            SeiPayloadType::ProgressiveRefinementSegmentEnd => "Progressive Refinement Segment End",
+            SeiPayloadType::FilmGrainCharacteristics => "Film Grain Characteristics",
+            SeiPayloadType::PostFilterHint => "Post-Filter Hint",
+            SeiPayloadType::ToneMappingInfo => "Tone Mapping Information",
+            SeiPayloadType::FramePackingArrangement => "Frame Packing Arrangement",
+            SeiPayloadType::DisplayOrientation => "Display Orientation",
+            SeiPayloadType::StructureOfPicturesInfo => "Structure of Pictures",
+            SeiPayloadType::ActiveParameterSets => "Active Parameter Sets",
+            SeiPayloadType::DecodingUnitInfo => "Decoding Unit Information",
+            SeiPayloadType::TemporalSubLayerZeroIndex => "Temporal Sub-Layer Zero Index",
+            SeiPayloadType::DecodedPictureHash => "Decoded Picture Hash",
+            SeiPayloadType::ScalableNesting => "Scalable Nesting",
+            SeiPayloadType::RegionRefreshInfo => "Region Refresh Information",
+            SeiPayloadType::NoDisplay => "No Display",
+            SeiPayloadType::TimeCode => "Time Code",
+            SeiPayloadType::MasteringDisplayColourVolume => "Mastering Display Colour Volume",
+            SeiPayloadType::ContentLightLevelInfo => "Content Light Level Info",
+            SeiPayloadType::AlternativeTransferCharacteristics => {
+                "Alternative Transfer Characteristics"
+            }
+            SeiPayloadType::AmbientViewingEnvironment => "Ambient Viewing Environment",
+            SeiPayloadType::Unknown => "Unknown",
+        }
+    }
+
+    /// Map to the codec-independent message type used by the Metadata Inspector.
+    pub fn message_type(&self, raw: u32) -> SeiMessageType {
+        match self {
+            SeiPayloadType::BufferingPeriod => SeiMessageType::BufferingPeriod,
+            SeiPayloadType::PicTiming => SeiMessageType::PicTiming,
+            SeiPayloadType::PanScanRect => SeiMessageType::PanScanRect,
+            SeiPayloadType::FillerPayload => SeiMessageType::FillerPayload,
+            SeiPayloadType::UserDataRegisteredItuTT35 => SeiMessageType::UserDataRegistered,
+            SeiPayloadType::UserDataUnregistered => SeiMessageType::UserDataUnregistered,
+            SeiPayloadType::RecoveryPoint => SeiMessageType::RecoveryPoint,
+            SeiPayloadType::SceneInfo => SeiMessageType::SceneInfo,
+            SeiPayloadType::FilmGrainCharacteristics => SeiMessageType::FilmGrainCharacteristics,
+            SeiPayloadType::PostFilterHint => SeiMessageType::PostFilterHint,
+            SeiPayloadType::ToneMappingInfo => SeiMessageType::ToneMappingInfo,
+            SeiPayloadType::FramePackingArrangement => SeiMessageType::FramePackingArrangement,
+            SeiPayloadType::DisplayOrientation => SeiMessageType::DisplayOrientation,
+            SeiPayloadType::ScalableNesting => SeiMessageType::ScalableNesting,
+            SeiPayloadType::MasteringDisplayColourVolume => {
+                SeiMessageType::MasteringDisplayColourVolume
+            }
+            SeiPayloadType::ContentLightLevelInfo => SeiMessageType::ContentLightLevelInfo,
+            SeiPayloadType::AlternativeTransferCharacteristics => {
+                SeiMessageType::AlternativeTransferCharacteristics
+            }
+            SeiPayloadType::AmbientViewingEnvironment => SeiMessageType::AmbientViewingEnvironment,
+            SeiPayloadType::TimeCode => SeiMessageType::TimeCode,
+            _ => SeiMessageType::Unknown(raw),
+        }
+    }
+}
+
+/// HRD parameters needed to interpret buffering period and picture timing SEI.
+///
+/// These come from the `hrd_parameters()` of the active SPS VUI; without
+/// them only the HRD-independent fields of those messages can be parsed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeiHrdContext {
+    /// NalHrdBpPresentFlag
+    pub nal_hrd_parameters_present_flag: bool,
+    /// VclHrdBpPresentFlag
+    pub vcl_hrd_parameters_present_flag: bool,
+    pub sub_pic_hrd_params_present_flag: bool,
+    pub sub_pic_cpb_params_in_pic_timing_sei_flag: bool,
+    pub du_cpb_removal_delay_increment_length_minus1: u8,
+    pub dpb_output_delay_du_length_minus1: u8,
+    pub initial_cpb_removal_delay_length_minus1: u8,
+    pub au_cpb_removal_delay_length_minus1: u8,
+    pub dpb_output_delay_length_minus1: u8,
+    /// cpb_cnt_minus1 of the highest temporal sub-layer
+    pub cpb_cnt_minus1: u32,
+}
+
+impl SeiHrdContext {
+    /// Build the context from VUI `hrd_parameters()`.
+    pub fn from_hrd(hrd: &HrdParameters) -> Self {
+        Self {
+            nal_hrd_parameters_present_flag: hrd.nal_hrd_parameters_present_flag,
+            vcl_hrd_parameters_present_flag: hrd.vcl_hrd_parameters_present_flag,
+            sub_pic_hrd_params_present_flag: hrd.sub_pic_hrd_params_present_flag,
+            sub_pic_cpb_params_in_pic_timing_sei_flag: hrd
+                .sub_pic_cpb_params_in_pic_timing_sei_flag,
+            du_cpb_removal_delay_increment_length_minus1: hrd
+                .du_cpb_removal_delay_increment_length_minus1,
+            dpb_output_delay_du_length_minus1: hrd.dpb_output_delay_du_length_minus1,
+            initial_cpb_removal_delay_length_minus1: hrd.initial_cpb_removal_delay_length_minus1,
+            au_cpb_removal_delay_length_minus1: hrd.au_cpb_removal_delay_length_minus1,
+            dpb_output_delay_length_minus1: hrd.dpb_output_delay_length_minus1,
+            cpb_cnt_minus1: hrd.highest_sub_layer().map_or(0, |l| l.cpb_cnt_minus1),
+        }
+    }
+
+    /// CpbDpbDelaysPresentFlag
+    pub fn cpb_dpb_delays_present(&self) -> bool {
+        self.nal_hrd_parameters_present_flag || self.vcl_hrd_parameters_present_flag
+    }
+}
+
+/// SPS state that SEI payload syntax depends on.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeiContext {
+    /// VUI frame_field_info_present_flag (controls pic_struct in picture timing)
+    pub frame_field_info_present_flag: bool,
+    /// HRD parameters, if the VUI signals any
+    pub hrd: Option<SeiHrdContext>,
+    /// Number of colour components hashed in decoded picture hash SEI
+    pub num_components: Option<u8>,
+}
+
+impl SeiContext {
+    /// Build the context from the active SPS.
+    pub fn from_sps(sps: &Sps) -> Self {
+        Self {
+            frame_field_info_present_flag: sps
+                .vui_parameters
+                .as_ref()
+                .is_some_and(|vui| vui.frame_field_info_present_flag),
+            hrd: sps
+                .vui_parameters
+                .as_ref()
+                .and_then(|vui| vui.hrd_parameters.as_ref())
+                .map(SeiHrdContext::from_hrd),
+            num_components: Some(match sps.chroma_format_idc {
+                bitvue_core::ChromaFormat::Monochrome => 1,
+                _ => 3,
+            }),
+        }
+    }
+}
+
+/// SEI message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeiMessage {
+    /// Payload type.
+    pub payload_type: SeiPayloadType,
+    /// Raw payload type value.
+    pub payload_type_raw: u32,
+    /// Payload size.
+    pub payload_size: u32,
+    /// Raw payload data.
+    pub payload: Vec<u8>,
+    /// Parsed data (for known types).
+    pub parsed: Option<SeiParsedData>,
+}
+
+/// Initial CPB removal delay and offset pair for one CPB.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InitialCpbRemoval {
+    pub initial_cpb_removal_delay: u32,
+    pub initial_cpb_removal_offset: u32,
+    /// Alternative values, present for sub-picture HRD or IRAP CPB params
+    pub initial_alt_cpb_removal_delay: Option<u32>,
+    pub initial_alt_cpb_removal_offset: Option<u32>,
+}
+
+/// Buffering period SEI (D.2.2).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BufferingPeriod {
+    pub bp_seq_parameter_set_id: u32,
+    pub irap_cpb_params_present_flag: bool,
+    pub cpb_delay_offset: u32,
+    pub dpb_delay_offset: u32,
+    pub concatenation_flag: bool,
+    pub au_cpb_removal_delay_delta_minus1: u32,
+    /// NAL HRD initial removal delays, one per CPB
+    pub nal_initial_cpb_removal: Vec<InitialCpbRemoval>,
+    /// VCL HRD initial removal delays, one per CPB
+    pub vcl_initial_cpb_removal: Vec<InitialCpbRemoval>,
+}
+
+/// Picture timing SEI (D.2.3).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PicTiming {
+    pub pic_struct: Option<u8>,
+    pub source_scan_type: Option<u8>,
+    pub duplicate_flag: Option<bool>,
+    pub au_cpb_removal_delay_minus1: Option<u32>,
+    pub pic_dpb_output_delay: Option<u32>,
+    pub pic_dpb_output_du_delay: Option<u32>,
+    pub num_decoding_units_minus1: Option<u32>,
+    pub du_common_cpb_removal_delay_increment_minus1: Option<u32>,
+    /// num_nalus_in_du_minus1 for each decoding unit
+    pub num_nalus_in_du_minus1: Vec<u32>,
+    /// du_cpb_removal_delay_increment_minus1 for each decoding unit
+    pub du_cpb_removal_delay_increment_minus1: Vec<u32>,
+}
+
+/// One clock timestamp of a time code SEI (D.2.27).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockTimestamp {
+    pub units_field_based_flag: bool,
+    pub counting_type: u8,
+    pub full_timestamp_flag: bool,
+    pub discontinuity_flag: bool,
+    pub cnt_dropped_flag: bool,
+    pub n_frames: u16,
+    pub seconds: Option<u8>,
+    pub minutes: Option<u8>,
+    pub hours: Option<u8>,
+    pub time_offset_value: i32,
+}
+
+impl ClockTimestamp {
+    /// Format as `HH:MM:SS:FF` (`;` before frames when frames were dropped).
+    pub fn format_smpte(&self) -> String {
+        format!(
+            "{:02}:{:02}:{:02}{}{:02}",
+            self.hours.unwrap_or(0),
+            self.minutes.unwrap_or(0),
+            self.seconds.unwrap_or(0),
+            if self.cnt_dropped_flag { ';' } else { ':' },
+            self.n_frames
+        )
+    }
+}
+
+/// Decoded picture hash method (D.3.19).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PictureHashType {
+    Md5,
+    Crc,
+    Checksum,
+}
+
+impl PictureHashType {
+    /// Create from hash_type syntax element.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(PictureHashType::Md5),
+            1 => Some(PictureHashType::Crc),
+            2 => Some(PictureHashType::Checksum),
+            _ => None,
+        }
+    }
+
+    /// Size of one component hash in bytes.
+    pub fn hash_len(&self) -> usize {
+        match self {
+            PictureHashType::Md5 => 16,
+            PictureHashType::Crc => 2,
+            PictureHashType::Checksum => 4,
+        }
+    }
+
+    /// Get human-readable name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PictureHashType::Md5 => "MD5",
+            PictureHashType::Crc => "CRC",
+            PictureHashType::Checksum => "Checksum",
+        }
+    }
+}
+
+/// Decoded picture hash SEI (D.2.19).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedPictureHash {
+    pub hash_type: PictureHashType,
+    /// One hash per colour component, big-endian as coded
+    pub component_hashes: Vec<Vec<u8>>,
+}
+
+/// Parsed SEI data for known types.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SeiParsedData {
+    /// Buffering period SEI.
+    BufferingPeriod(BufferingPeriod),
+    /// Picture timing SEI.
+    PicTiming(PicTiming),
+    /// Recovery point SEI.
+    RecoveryPoint {
+        recovery_poc_cnt: i32,
+        exact_match_flag: bool,
+        broken_link_flag: bool,
+    },
+    /// User data registered by ITU-T T.35.
+    UserDataRegistered {
+        country_code: u8,
+        country_code_extension: Option<u8>,
+        data: Vec<u8>,
+    },
+    /// User data unregistered.
+    UserDataUnregistered { uuid: [u8; 16], data: Vec<u8> },
+    /// Active parameter sets.
+    ActiveParameterSets {
+        active_video_parameter_set_id: u8,
+        self_contained_cvs_flag: bool,
+        no_parameter_set_update_flag: bool,
+        active_seq_parameter_set_ids: Vec<u32>,
+    },
+    /// Decoded picture hash.
+    DecodedPictureHash(DecodedPictureHash),
+    /// Time code.
+    TimeCode {
+        clock_timestamps: Vec<ClockTimestamp>,
+    },
+    /// Mastering display colour volume.
+    MasteringDisplayColourVolume {
+        display_primaries_x: [u16; 3],
+        display_primaries_y: [u16; 3],
+        white_point_x: u16,
+        white_point_y: u16,
+        max_display_mastering_luminance: u32,
+        min_display_mastering_luminance: u32,
+    },
+    /// Content light level info.
+    ContentLightLevelInfo {
+        max_content_light_level: u16,
+        max_pic_average_light_level: u16,
+    },
+    /// Alternative transfer characteristics.
+    AlternativeTransferCharacteristics {
+        preferred_transfer_characteristics: u8,
+    },
+}
+
+impl SeiMessage {
+    /// Mastering display metadata, if this is an MDCV SEI.
+    ///
+    /// The coded primaries carry no colour label, so red is taken as the
+    /// primary with the largest x and green as the one with the largest y.
+    pub fn mastering_display(&self) -> Option<MasteringDisplayMetadata> {
+        let Some(SeiParsedData::MasteringDisplayColourVolume {
+            display_primaries_x: x,
+            display_primaries_y: y,
+            white_point_x,
+            white_point_y,
+            max_display_mastering_luminance,
+            min_display_mastering_luminance,
+        }) = &self.parsed
+        else {
+            return None;
+        };
+
+        let red = (0..3).max_by_key(|&c| x[c]).unwrap_or(2);
+        let green = (0..3)
+            .filter(|&c| c != red)
+            .max_by_key(|&c| y[c])
+            .unwrap_or(0);
+        let blue = 3 - red - green;
+
+        Some(MasteringDisplayMetadata {
+            red_x: x[red],
+            red_y: y[red],
+            green_x: x[green],
+            green_y: y[green],
+            blue_x: x[blue],
+            blue_y: y[blue],
+            white_point_x: *white_point_x,
+            white_point_y: *white_point_y,
+            max_luminance: *max_display_mastering_luminance,
+            min_luminance: *min_display_mastering_luminance,
+        })
+    }
+
+    /// Content light level, if this is a CLL SEI.
+    pub fn content_light_level(&self) -> Option<ContentLightLevel> {
+        match &self.parsed {
+            Some(SeiParsedData::ContentLightLevelInfo {
+                max_content_light_level,
+                max_pic_average_light_level,
+            }) => Some(ContentLightLevel::new(
+                *max_content_light_level,
+                *max_pic_average_light_level,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Convert to the codec-independent SEI record of the Metadata Inspector.
+    pub fn to_metadata(
+        &self,
+        byte_offset: u64,
+        frame_idx: Option<usize>,
+    ) -> bitvue_core::metadata::SeiMessage {
+        let data = if let Some(mdcv) = self.mastering_display() {
+            SeiData::MasteringDisplay(mdcv)
+        } else if let Some(cll) = self.content_light_level() {
+            SeiData::ContentLightLevel(cll)
+        } else {
+            match &self.parsed {
+                Some(SeiParsedData::UserDataUnregistered { uuid, data }) => {
+                    SeiData::UserDataUnregistered {
+                        uuid: *uuid,
+                        data: data.clone(),
+                    }
+                }
+                Some(SeiParsedData::UserDataRegistered {
+                    country_code, data, ..
+                }) => SeiData::UserDataRegistered {
+                    country_code: *country_code,
+                    data: data.clone(),
+                },
+                Some(SeiParsedData::RecoveryPoint {
+                    recovery_poc_cnt, ..
+                }) => SeiData::RecoveryPoint {
+                    recovery_poc_cnt: *recovery_poc_cnt,
+                },
+                Some(SeiParsedData::PicTiming(pt)) => SeiData::PicTiming {
+                    cpb_removal_delay: pt.au_cpb_removal_delay_minus1.map(|d| d + 1),
+                    dpb_output_delay: pt.pic_dpb_output_delay,
+                },
+                Some(SeiParsedData::TimeCode { clock_timestamps }) => SeiData::TimeCode {
+                    timecodes: clock_timestamps
+                        .iter()
+                        .map(ClockTimestamp::format_smpte)
+                        .collect(),
+                },
+                _ if self.payload_type == SeiPayloadType::FilmGrainCharacteristics => {
+                    SeiData::FilmGrain { present: true }
+                }
+                _ => SeiData::Raw(self.payload.clone()),
+            }
+        };
+
+        bitvue_core::metadata::SeiMessage {
+            message_type: self.payload_type.message_type(self.payload_type_raw),
+            payload_size: self.payload_size as usize,
+            byte_offset,
+            frame_idx,
+            data,
+        }
+    }
+}
+
+/// Parse SEI messages from an SEI RBSP without SPS context.
+///
+/// Buffering period and picture timing messages are only partially parsed;
+/// use [`parse_sei_with_context`] to decode their HRD-dependent fields.
+pub fn parse_sei(data: &[u8]) -> Result<Vec<SeiMessage>> {
+    parse_sei_with_context(data, &SeiContext::default())
+}
+
+/// Parse SEI messages from an SEI RBSP (prefix or suffix SEI NAL payload).
+pub fn parse_sei_with_context(data: &[u8], ctx: &SeiContext) -> Result<Vec<SeiMessage>> {
+    let mut messages = Vec::new();
+    let mut offset = 0;
+
+    // Stop at the rbsp_trailing_bits byte
+    while offset < data.len() && data[offset..] != [0x80] {
+        let payload_type = read_sei_value(data, &mut offset)?;
+        let payload_size = read_sei_value(data, &mut offset)?;
+
+        let end = offset
+            .checked_add(payload_size as usize)
+            .filter(|&end| end <= data.len())
+            .ok_or(HevcError::InsufficientData {
+                expected: payload_size as usize,
+                actual: data.len() - offset,
+            })?;
+        let payload = data[offset..end].to_vec();
+        offset = end;
+
+        let sei_type = SeiPayloadType::from_u32(payload_type);
+        let parsed = parse_sei_payload(sei_type, &payload, ctx);
+
+        messages.push(SeiMessage {
+            payload_type: sei_type,
+            payload_type_raw: payload_type,
+            payload_size,
+            payload,
+            parsed,
+        });
+    }
+
+    Ok(messages)
+}
+
+/// Read an ff-byte-extended payloadType or payloadSize value.
+fn read_sei_value(data: &[u8], offset: &mut usize) -> Result<u32> {
+    let mut value: u32 = 0;
+    loop {
+        let byte = *data
+            .get(*offset)
+            .ok_or(HevcError::UnexpectedEof(*offset as u64 * 8))?;
+        *offset += 1;
+        value = value
+            .checked_add(byte as u32)
+            .ok_or_else(|| HevcError::InvalidData("SEI value overflow".to_string()))?;
+        if byte != 0xFF {
+            return Ok(value);
+        }
+    }
+}
+
+/// Parse specific SEI payload.
+fn parse_sei_payload(
+    payload_type: SeiPayloadType,
+    data: &[u8],
+    ctx: &SeiContext,
+) -> Option<SeiParsedData> {
+    match payload_type {
+        SeiPayloadType::BufferingPeriod => parse_buffering_period(data, ctx.hrd.as_ref()).ok(),
+        SeiPayloadType::PicTiming => parse_pic_timing(data, ctx).ok(),
+        SeiPayloadType::RecoveryPoint => parse_recovery_point(data).ok(),
+        SeiPayloadType::UserDataRegisteredItuTT35 => parse_user_data_registered(data),
+        SeiPayloadType::UserDataUnregistered => parse_user_data_unregistered(data),
+        SeiPayloadType::ActiveParameterSets => parse_active_parameter_sets(data).ok(),
+        SeiPayloadType::DecodedPictureHash => parse_decoded_picture_hash(data, ctx.num_components),
+        SeiPayloadType::TimeCode => parse_time_code(data).ok(),
+        SeiPayloadType::MasteringDisplayColourVolume => parse_mastering_display(data),
+        SeiPayloadType::ContentLightLevelInfo => parse_content_light_level(data),
+        SeiPayloadType::AlternativeTransferCharacteristics => {
+            data.first()
+                .map(|&tc| SeiParsedData::AlternativeTransferCharacteristics {
+                    preferred_transfer_characteristics: tc,
+                })
+        }
+        _ => None,
+    }
+}
+
+/// Parse buffering period SEI.
+fn parse_buffering_period(data: &[u8], hrd: Option<&SeiHrdContext>) -> Result<SeiParsedData> {
+    let mut reader = BitReader::new(data);
+    let mut bp = BufferingPeriod {
+        bp_seq_parameter_set_id: reader.read_ue()?,
+        ..Default::default()
+    };
+
+    let Some(hrd) = hrd else {
+        return Ok(SeiParsedData::BufferingPeriod(bp));
+    };
+
+    if !hrd.sub_pic_hrd_params_present_flag {
+        bp.irap_cpb_params_present_flag = reader.read_bit()?;
+    }
+    if bp.irap_cpb_params_present_flag {
+        bp.cpb_delay_offset = reader.read_bits(hrd.au_cpb_removal_delay_length_minus1 + 1)?;
+        bp.dpb_delay_offset = reader.read_bits(hrd.dpb_output_delay_length_minus1 + 1)?;
+    }
+    bp.concatenation_flag = reader.read_bit()?;
+    bp.au_cpb_removal_delay_delta_minus1 =
+        reader.read_bits(hrd.au_cpb_removal_delay_length_minus1 + 1)?;
+
+    let with_alt = hrd.sub_pic_hrd_params_present_flag || bp.irap_cpb_params_present_flag;
+    if hrd.nal_hrd_parameters_present_flag {
+        bp.nal_initial_cpb_removal = parse_initial_cpb_removal(&mut reader, hrd, with_alt)?;
+    }
+    if hrd.vcl_hrd_parameters_present_flag {
+        bp.vcl_initial_cpb_removal = parse_initial_cpb_removal(&mut reader, hrd, with_alt)?;
+    }
+
+    Ok(SeiParsedData::BufferingPeriod(bp))
+}
+
+fn parse_initial_cpb_removal(
+    reader: &mut BitReader,
+    hrd: &SeiHrdContext,
+    with_alt: bool,
+) -> Result<Vec<InitialCpbRemoval>> {
+    let len = hrd.initial_cpb_removal_delay_length_minus1 + 1;
+    let mut cpbs = Vec::new();
+    for _ in 0..=hrd.cpb_cnt_minus1.min(31) {
+        let mut cpb = InitialCpbRemoval {
+            initial_cpb_removal_delay: reader.read_bits(len)?,
+            initial_cpb_removal_offset: reader.read_bits(len)?,
+            ..Default::default()
+        };
+        if with_alt {
+            cpb.initial_alt_cpb_removal_delay = Some(reader.read_bits(len)?);
+            cpb.initial_alt_cpb_removal_offset = Some(reader.read_bits(len)?);
+        }
+        cpbs.push(cpb);
+    }
+    Ok(cpbs)
+}
+
+/// Parse picture timing SEI.
+fn parse_pic_timing(data: &[u8], ctx: &SeiContext) -> Result<SeiParsedData> {
+    let mut reader = BitReader::new(data);
+    let mut pt = PicTiming::default();
+
+    if ctx.frame_field_info_present_flag {
+        pt.pic_struct = Some(reader.read_bits(4)? as u8);
+        pt.source_scan_type = Some(reader.read_bits(2)? as u8);
+        pt.duplicate_flag = Some(reader.read_bit()?);
+    }
+
+    if let Some(hrd) = ctx.hrd.as_ref().filter(|h| h.cpb_dpb_delays_present()) {
+        pt.au_cpb_removal_delay_minus1 =
+            Some(reader.read_bits(hrd.au_cpb_removal_delay_length_minus1 + 1)?);
+        pt.pic_dpb_output_delay = Some(reader.read_bits(hrd.dpb_output_delay_length_minus1 + 1)?);
+        if hrd.sub_pic_hrd_params_present_flag {
+            pt.pic_dpb_output_du_delay =
+                Some(reader.read_bits(hrd.dpb_output_delay_du_length_minus1 + 1)?);
+        }
+        if hrd.sub_pic_hrd_params_present_flag && hrd.sub_pic_cpb_params_in_pic_timing_sei_flag {
+            let increment_len = hrd.du_cpb_removal_delay_increment_length_minus1 + 1;
+            let num_decoding_units_minus1 = reader.read_ue()?;
+            if num_decoding_units_minus1 as usize >= data.len() * 8 {
+                return Err(HevcError::InvalidData(format!(
+                    "num_decoding_units_minus1 {} exceeds payload",
+                    num_decoding_units_minus1
+                )));
+            }
+            pt.num_decoding_units_minus1 = Some(num_decoding_units_minus1);
+            let du_common_cpb_removal_delay_flag = reader.read_bit()?;
+            if du_common_cpb_removal_delay_flag {
+                pt.du_common_cpb_removal_delay_increment_minus1 =
+                    Some(reader.read_bits(increment_len)?);
+            }
+            for i in 0..=num_decoding_units_minus1 {
+                pt.num_nalus_in_du_minus1.push(reader.read_ue()?);
+                if !du_common_cpb_removal_delay_flag && i < num_decoding_units_minus1 {
+                    pt.du_cpb_removal_delay_increment_minus1
+                        .push(reader.read_bits(increment_len)?);
+                }
+            }
+        }
+    }
+
+    Ok(SeiParsedData::PicTiming(pt))
+}
+
+/// Parse recovery point SEI.
+fn parse_recovery_point(data: &[u8]) -> Result<SeiParsedData> {
+    let mut reader = BitReader::new(data);
+
+    Ok(SeiParsedData::RecoveryPoint {
+        recovery_poc_cnt: reader.read_se()?,
+        exact_match_flag: reader.read_bit()?,
+        broken_link_flag: reader.read_bit()?,
+    })
+}
+
+/// Parse user data registered by ITU-T T.35 SEI.
+fn parse_user_data_registered(data: &[u8]) -> Option<SeiParsedData> {
+    let (&country_code, rest) = data.split_first()?;
+    let (country_code_extension, rest) = if country_code == 0xFF {
+        let (&ext, rest) = rest.split_first()?;
+        (Some(ext), rest)
+    } else {
+        (None, rest)
+    };
+
+    Some(SeiParsedData::UserDataRegistered {
+        country_code,
+        country_code_extension,
+        data: rest.to_vec(),
+    })
+}
+
+/// Parse user data unregistered SEI.
+fn parse_user_data_unregistered(data: &[u8]) -> Option<SeiParsedData> {
+    if data.len() < 16 {
+        return None;
+    }
+
+    let mut uuid = [0u8; 16];
+    uuid.copy_from_slice(&data[0..16]);
+
+    Some(SeiParsedData::UserDataUnregistered {
+        uuid,
+        data: data[16..].to_vec(),
+    })
+}
+
+/// Parse active parameter sets SEI.
+fn parse_active_parameter_sets(data: &[u8]) -> Result<SeiParsedData> {
+    let mut reader = BitReader::new(data);
+
+    let active_video_parameter_set_id = reader.read_bits(4)? as u8;
+    let self_contained_cvs_flag = reader.read_bit()?;
+    let no_parameter_set_update_flag = reader.read_bit()?;
+    let num_sps_ids_minus1 = reader.read_ue()?;
+    if num_sps_ids_minus1 > 15 {
+        return Err(HevcError::InvalidData(format!(
+            "num_sps_ids_minus1 {} out of range",
+            num_sps_ids_minus1
+        )));
+    }
+    let active_seq_parameter_set_ids = (0..=num_sps_ids_minus1)
+        .map(|_| reader.read_ue())
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(SeiParsedData::ActiveParameterSets {
+        active_video_parameter_set_id,
+        self_contained_cvs_flag,
+        no_parameter_set_update_flag,
+        active_seq_parameter_set_ids,
+    })
+}
+
+/// Parse decoded picture hash SEI.
+///
+/// Without a known chroma format the component count is derived from the
+/// payload size (one hash for monochrome, three otherwise).
+fn parse_decoded_picture_hash(data: &[u8], num_components: Option<u8>) -> Option<SeiParsedData> {
+    let (&hash_type, hashes) = data.split_first()?;
+    let hash_type = PictureHashType::from_u8(hash_type)?;
+    let len = hash_type.hash_len();
+    let count = num_components
+        .map(usize::from)
+        .unwrap_or_else(|| (hashes.len() / len).min(3));
+    if count == 0 || hashes.len() < count * len {
+        return None;
+    }
+
+    Some(SeiParsedData::DecodedPictureHash(DecodedPictureHash {
+        hash_type,
+        component_hashes: hashes
+            .chunks_exact(len)
+            .take(count)
+            .map(<[u8]>::to_vec)
+            .collect(),
+    }))
+}
+
+/// Parse time code SEI.
+fn parse_time_code(data: &[u8]) -> Result<SeiParsedData> {
+    let mut reader = BitReader::new(data);
+    let num_clock_ts = reader.read_bits(2)?;
+
+    let mut clock_timestamps = Vec::new();
+    for _ in 0..num_clock_ts {
+        // clock_timestamp_flag[i] precedes its own timestamp
+        if !reader.read_bit()? {
+            continue;
+        }
+        let mut ts = ClockTimestamp {
+            units_field_based_flag: reader.read_bit()?,
+            counting_type: reader.read_bits(5)? as u8,
+            full_timestamp_flag: reader.read_bit()?,
+            discontinuity_flag: reader.read_bit()?,
+            cnt_dropped_flag: reader.read_bit()?,
+            n_frames: reader.read_bits(9)? as u16,
+            ..Default::default()
+        };
+        if ts.full_timestamp_flag {
+            ts.seconds = Some(reader.read_bits(6)? as u8);
+            ts.minutes = Some(reader.read_bits(6)? as u8);
+            ts.hours = Some(reader.read_bits(5)? as u8);
+        } else if reader.read_bit()? {
+            ts.seconds = Some(reader.read_bits(6)? as u8);
+            if reader.read_bit()? {
+                ts.minutes = Some(reader.read_bits(6)? as u8);
+                if reader.read_bit()? {
+                    ts.hours = Some(reader.read_bits(5)? as u8);
+                }
+            }
+        }
+        let time_offset_length = reader.read_bits(5)? as u8;
+        if time_offset_length > 0 {
+            let raw = reader.read_bits(time_offset_length)?;
+            let shift = 32 - time_offset_length as u32;
+            ts.time_offset_value = ((raw << shift) as i32) >> shift;
+        }
+        clock_timestamps.push(ts);
+    }
+
+    Ok(SeiParsedData::TimeCode { clock_timestamps })
+}
+
+/// Parse mastering display colour volume SEI.
+fn parse_mastering_display(data: &[u8]) -> Option<SeiParsedData> {
+    if data.len() < 24 {
+        return None;
+    }
+
+    let u16_at = |i: usize| u16::from_be_bytes([data[i], data[i + 1]]);
+    let u32_at = |i: usize| u32::from_be_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+
+    let mut display_primaries_x = [0u16; 3];
+    let mut display_primaries_y = [0u16; 3];
+    for c in 0..3 {
+        display_primaries_x[c] = u16_at(c * 4);
+        display_primaries_y[c] = u16_at(c * 4 + 2);
+    }
+
+    Some(SeiParsedData::MasteringDisplayColourVolume {
+        display_primaries_x,
+        display_primaries_y,
+        white_point_x: u16_at(12),
+        white_point_y: u16_at(14),
+        max_display_mastering_luminance: u32_at(16),
+        min_display_mastering_luminance: u32_at(20),
+    })
+}
+
+/// Parse content light level info SEI.
+fn parse_content_light_level(data: &[u8]) -> Option<SeiParsedData> {
+    if data.len() < 4 {
+        return None;
+    }
+
+    Some(SeiParsedData::ContentLightLevelInfo {
+        max_content_light_level: u16::from_be_bytes([data[0], data[1]]),
+        max_pic_average_light_level: u16::from_be_bytes([data[2], data[3]]),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// HDR10 prefix SEI RBSP: MDCV (BT.2020 G/B/R order, D65, 1000/0.005 nits) + CLL.
+    const HDR10_SEI: [u8; 33] = [
+        0x89, 0x18, // MDCV, 24 bytes
+        0x21, 0x34, 0x9B, 0xAA, // G (8500, 39850)
+        0x19, 0x64, 0x08, 0xFC, // B (6500, 2300)
+        0x84, 0xD0, 0x3E, 0x80, // R (34000, 16000)
+        0x3D, 0x13, 0x40, 0x42, // white point (15635, 16450)
+        0x00, 0x98, 0x96, 0x80, // max 10000000
+        0x00, 0x00, 0x00, 0x32, // min 50
+        0x90, 0x04, // CLL, 4 bytes
+        0x03, 0xE8, 0x01, 0x90, // 1000 / 400
+        0x80,
+    ];
+
+    #[test]
+    fn test_parse_hdr10_sei() {
+        let messages = parse_sei(&HDR10_SEI).unwrap();
+        assert_eq!(messages.len(), 2);
+
+        let mdcv = messages[0].mastering_display().unwrap();
+        assert_eq!((mdcv.red_x, mdcv.red_y), (34000, 16000));
+        assert_eq!((mdcv.green_x, mdcv.green_y), (8500, 39850));
+        assert_eq!((mdcv.blue_x, mdcv.blue_y), (6500, 2300));
+        assert_eq!((mdcv.white_point_x, mdcv.white_point_y), (15635, 16450));
+        assert_eq!(mdcv.max_luminance, 10_000_000);
+        assert_eq!(mdcv.min_luminance, 50);
+
+        let cll = messages[1].content_light_level().unwrap();
+        assert_eq!(cll, ContentLightLevel::new(1000, 400));
+
+        let core = messages[1].to_metadata(0, None);
+        assert_eq!(core.message_type, SeiMessageType::ContentLightLevelInfo);
+    }
+
+    #[test]
+    fn test_parse_extended_payload_type() {
+        // Unknown payload type 300 = 0xFF + 45, size 1
+        let messages = parse_sei(&[0xFF, 0x2D, 0x01, 0xAB, 0x80]).unwrap();
+        assert_eq!(messages[0].payload_type_raw, 300);
+        assert_eq!(messages[0].payload_type, SeiPayloadType::Unknown);
+        assert_eq!(messages[0].payload, vec![0xAB]);
+    }
+
+    #[test]
+    fn test_parse_truncated_payload() {
+        assert!(parse_sei(&[0x89, 0x18, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_parse_decoded_picture_hash_md5() {
+        let mut data = vec![0x84, 49, 0x00];
+        data.extend((0u8..48).collect::<Vec<_>>());
+        data.push(0x80);
+
+        let messages = parse_sei(&data).unwrap();
+        match &messages[0].parsed {
+            Some(SeiParsedData::DecodedPictureHash(hash)) => {
+                assert_eq!(hash.hash_type, PictureHashType::Md5);
+                assert_eq!(hash.component_hashes.len(), 3);
+                assert_eq!(hash.component_hashes[2][0], 32);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_pic_timing_with_hrd() {
+        let ctx = SeiContext {
+            frame_field_info_present_flag: true,
+            hrd: Some(SeiHrdContext {
+                nal_hrd_parameters_present_flag: true,
+                au_cpb_removal_delay_length_minus1: 7,
+                dpb_output_delay_length_minus1: 7,
+                ..Default::default()
+            }),
+            num_components: None,
+        };
+        // pic_struct=1, source_scan_type=0, duplicate=0, delays 3 and 5
+        let payload = [0b0001_0000, 0b0000_0110, 0b0000_1010];
+        let mut data = vec![0x01, payload.len() as u8];
+        data.extend_from_slice(&payload);
+        data.push(0x80);
+
+        let messages = parse_sei_with_context(&data, &ctx).unwrap();
+        match &messages[0].parsed {
+            Some(SeiParsedData::PicTiming(pt)) => {
+                assert_eq!(pt.pic_struct, Some(1));
+                assert_eq!(pt.au_cpb_removal_delay_minus1, Some(3));
+                assert_eq!(pt.pic_dpb_output_delay, Some(5));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_time_code() {
+        // num_clock_ts=1, flag=1, field_based=0, counting_type=0, full=1,
+        // discontinuity=0, dropped=0, n_frames=12, 30s 15m 1h, offset len 0
+        let mut bits = String::from("01");
+        bits += "1";
+        bits += "0";
+        bits += "00000";
+        bits += "1";
+        bits += "0";
+        bits += "0";
+        bits += &format!("{:09b}", 12);
+        bits += &format!("{:06b}", 30);
+        bits += &format!("{:06b}", 15);
+        bits += &format!("{:05b}", 1);
+        bits += "00000";
+
+        match parse_time_code(&bits_to_bytes(&bits)).unwrap() {
+            SeiParsedData::TimeCode { clock_timestamps } => {
+                assert_eq!(clock_timestamps.len(), 1);
+                assert_eq!(clock_timestamps[0].format_smpte(), "01:15:30:12");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_time_code_two_clock_timestamps() {
+        // num_clock_ts=2; each clock_timestamp_flag directly precedes its
+        // timestamp
+        let mut bits = String::from("10");
+        // [0] flag=1, field_based=0, counting_type=0, full=0, discontinuity=0,
+        // dropped=1, n_frames=7, seconds 5 without minutes, offset -2 in 4 bits
+        bits += "1";
+        bits += "0";
+        bits += "00000";
+        bits += "0";
+        bits += "0";
+        bits += "1";
+        bits += &format!("{:09b}", 7);
+        bits += "1";
+        bits += &format!("{:06b}", 5);
+        bits += "0";
+        bits += &format!("{:05b}", 4);
+        bits += "1110";
+        // [1] flag=1, full timestamp 02:03:04, n_frames=9, no offset
+        bits += "1";
+        bits += "0";
+        bits += "00000";
+        bits += "1";
+        bits += "0";
+        bits += "0";
+        bits += &format!("{:09b}", 9);
+        bits += &format!("{:06b}", 4);
+        bits += &format!("{:06b}", 3);
+        bits += &format!("{:05b}", 2);
+        bits += "00000";
+
+        match parse_time_code(&bits_to_bytes(&bits)).unwrap() {
+            SeiParsedData::TimeCode { clock_timestamps } => {
+                assert_eq!(clock_timestamps.len(), 2);
+                assert_eq!(clock_timestamps[0].format_smpte(), "00:00:05;07");
+                assert_eq!(clock_timestamps[0].minutes, None);
+                assert_eq!(clock_timestamps[0].time_offset_value, -2);
+                assert_eq!(clock_timestamps[1].format_smpte(), "02:03:04:09");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    /// Pack a string of '0'/'1' into bytes, zero-padding the last byte
+    fn bits_to_bytes(bits: &str) -> Vec<u8> {
+        let mut bits = bits.to_string();
+        while !bits.len().is_multiple_of(8) {
+            bits.push('0');
+        }
+        bits.as_bytes()
+            .chunks(8)
+            .map(|c| u8::from_str_radix(std::str::from_utf8(c).unwrap(), 2).unwrap())
+            .collect()
+    }
+}
@@ -7,7 +7,8 @@ use crate::bitreader::BitReader;
 use crate::error::{HevcError, Result};
 use crate::nal::NalUnitType;
 use crate::pps::Pps;
-use crate::sps::Sps;
+use crate::rps::{parse_st_ref_pic_set, LongTermRefPic, ShortTermRefPicSet};
+use crate::sps::{ChromaFormat, Sps};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -121,10 +122,14 @@ pub struct SliceHeader {
     pub short_term_ref_pic_set_sps_flag: bool,
     /// Short-term reference picture set index.
     pub short_term_ref_pic_set_idx: u8,
+    /// Short-term reference picture set coded in the slice header.
+    pub short_term_ref_pic_set: Option<ShortTermRefPicSet>,
     /// Number of long-term SPS pictures.
     pub num_long_term_sps: u8,
     /// Number of long-term pictures.
     pub num_long_term_pics: u8,
+    /// Long-term reference pictures (SPS candidates first).
+    pub long_term_ref_pics: Vec<LongTermRefPic>,
     /// Slice temporal MVP enabled.
     pub slice_temporal_mvp_enabled_flag: bool,
     /// Slice SAO luma flag.
@@ -189,8 +194,10 @@ impl Default for SliceHeader {
             slice_pic_order_cnt_lsb: 0,
             short_term_ref_pic_set_sps_flag: false,
             short_term_ref_pic_set_idx: 0,
+            short_term_ref_pic_set: None,
             num_long_term_sps: 0,
             num_long_term_pics: 0,
+            long_term_ref_pics: Vec::new(),
             slice_temporal_mvp_enabled_flag: false,
             slice_sao_luma_flag: false,
             slice_sao_chroma_flag: false,
@@ -253,10 +260,37 @@ impl SliceHeader {
             0
         }
     }
+
+    /// Get the short-term RPS in use, coded in the header or selected from the SPS.
+    pub fn st_ref_pic_set<'a>(&'a self, sps: &'a Sps) -> Option<&'a ShortTermRefPicSet> {
+        if self.short_term_ref_pic_set_sps_flag {
+            sps.st_ref_pic_sets
+                .get(self.short_term_ref_pic_set_idx as usize)
+        } else {
+            self.short_term_ref_pic_set.as_ref()
+        }
+    }
+
+    /// Get the number of pictures usable for inter prediction (NumPicTotalCurr).
+    pub fn num_pic_total_curr(&self, sps: &Sps) -> usize {
+        let st = self
+            .st_ref_pic_set(sps)
+            .map_or(0, ShortTermRefPicSet::num_used_by_curr);
+        let lt = self
+            .long_term_ref_pics
+            .iter()
+            .filter(|lt| lt.used_by_curr_pic_lt_flag)
+            .count();
+        st + lt
+    }
+}
+
+/// Number of bits of a u(v) element coding values in `0..n`: Ceil(Log2(n)).
+fn ceil_log2(n: u32) -> u8 {
+    (32 - n.saturating_sub(1).leading_zeros()) as u8
 }
 
 /// Parse slice header from RBSP data.
-#[allow(clippy::field_reassign_with_default)]
 pub fn parse_slice_header(
     data: &[u8],
     sps_map: &HashMap<u8, Sps>,
@@ -264,6 +298,50 @@ pub fn parse_slice_header(
     nal_type: NalUnitType,
 ) -> Result<SliceHeader> {
     let mut reader = BitReader::new(data);
+    read_slice_header(&mut reader, sps_map, pps_map, nal_type)
+}
+
+/// Parse slice header and return the bit position where
+/// `slice_segment_data()` starts, after the header extension and
+/// `byte_alignment()`.
+pub(crate) fn parse_slice_header_with_data_offset(
+    data: &[u8],
+    sps_map: &HashMap<u8, Sps>,
+    pps_map: &HashMap<u8, Pps>,
+    nal_type: NalUnitType,
+) -> Result<(SliceHeader, usize)> {
+    let mut reader = BitReader::new(data);
+    let header = read_slice_header(&mut reader, sps_map, pps_map, nal_type)?;
+    let pps = &pps_map[&header.slice_pic_parameter_set_id];
+
+    if pps.slice_segment_header_extension_present_flag {
+        let slice_segment_header_extension_length = reader.read_ue()?;
+        if slice_segment_header_extension_length > 256 {
+            return Err(HevcError::InvalidData(format!(
+                "slice_segment_header_extension_length {} exceeds 256",
+                slice_segment_header_extension_length
+            )));
+        }
+        reader.skip_bits(slice_segment_header_extension_length as u64 * 8)?;
+    }
+
+    // byte_alignment(): alignment_bit_equal_to_one, then zero bits
+    if !reader.read_bit()? {
+        return Err(HevcError::InvalidData(
+            "slice header byte_alignment() does not start with a one bit".to_string(),
+        ));
+    }
+    reader.byte_align();
+    Ok((header, reader.position() as usize))
+}
+
+#[allow(clippy::field_reassign_with_default)]
+fn read_slice_header(
+    reader: &mut BitReader,
+    sps_map: &HashMap<u8, Sps>,
+    pps_map: &HashMap<u8, Pps>,
+    nal_type: NalUnitType,
+) -> Result<SliceHeader> {
     let mut header = SliceHeader::default();
 
     // first_slice_segment_in_pic_flag (1 bit)
@@ -299,8 +377,7 @@ pub fn parse_slice_header(
 
         // slice_segment_address - need to calculate number of CTBs
         let pic_size_in_ctbs = sps.pic_width_in_ctbs() * sps.pic_height_in_ctbs();
-        let bits_needed = (32 - pic_size_in_ctbs.leading_zeros()) as u8;
-        header.slice_segment_address = reader.read_bits(bits_needed)?;
+        header.slice_segment_address = reader.read_bits(ceil_log2(pic_size_in_ctbs))?;
     }
 
     // Skip extra slice header bits
@@ -337,24 +414,23 @@ pub fn parse_slice_header(
             if header.short_term_ref_pic_set_sps_flag {
                 // short_term_ref_pic_set_idx
                 if sps.num_short_term_ref_pic_sets > 1 {
-                    let bits_needed =
-                        (32 - (sps.num_short_term_ref_pic_sets as u32).leading_zeros()) as u8;
+                    let bits_needed = ceil_log2(sps.num_short_term_ref_pic_sets as u32);
                     header.short_term_ref_pic_set_idx = reader.read_bits(bits_needed)? as u8;
                 }
             } else {
-                // Parse st_ref_pic_set in slice header
-                // This is complex - skip for now
-                // Full implementation would parse short_term_ref_pic_set()
+                // st_ref_pic_set(num_short_term_ref_pic_sets)
+                let num_sets = sps.num_short_term_ref_pic_sets as usize;
+                header.short_term_ref_pic_set = Some(parse_st_ref_pic_set(
+                    reader,
+                    num_sets,
+                    num_sets,
+                    &sps.st_ref_pic_sets,
+                )?);
             }
 
             // Long-term reference pictures
             if sps.long_term_ref_pics_present_flag {
-                if sps.num_long_term_ref_pics_sps > 0 {
-                    header.num_long_term_sps = reader.read_ue()? as u8;
-                }
-                header.num_long_term_pics = reader.read_ue()? as u8;
-
-                // Skip long-term ref pic parsing for now
+                parse_long_term_ref_pics(reader, sps, &mut header)?;
             }
 
             // slice_temporal_mvp_enabled_flag
@@ -386,9 +462,14 @@ pub fn parse_slice_header(
                 header.num_ref_idx_l1_active_minus1 = pps.num_ref_idx_l1_default_active_minus1;
             }
 
-            // ref_pic_lists_modification() - skip for now
-            if pps.lists_modification_present_flag {
-                // Parse modification lists
+            // ref_pic_lists_modification()
+            let num_pic_total_curr = header.num_pic_total_curr(sps) as u32;
+            if pps.lists_modification_present_flag && num_pic_total_curr > 1 {
+                header.ref_pic_list_modification = Some(parse_ref_pic_lists_modification(
+                    reader,
+                    &header,
+                    ceil_log2(num_pic_total_curr),
+                )?);
             }
 
             // mvd_l1_zero_flag
@@ -422,7 +503,7 @@ pub fn parse_slice_header(
             if (pps.weighted_pred_flag && header.slice_type == SliceType::P)
                 || (pps.weighted_bipred_flag && header.slice_type == SliceType::B)
             {
-                // Parse pred_weight_table() - skip for now
+                header.pred_weight_table = Some(parse_pred_weight_table(reader, sps, &header)?);
             }
 
             // five_minus_max_num_merge_cand
@@ -508,6 +589,186 @@ pub fn parse_slice_header(
     Ok(header)
 }
 
+/// Parse the long-term reference pictures of a slice header.
+fn parse_long_term_ref_pics(
+    reader: &mut BitReader,
+    sps: &Sps,
+    header: &mut SliceHeader,
+) -> Result<()> {
+    // SECURITY: Limit long-term pictures to prevent excessive allocation
+    const MAX_LONG_TERM_PICS: u32 = 32;
+
+    let num_long_term_sps = if sps.num_long_term_ref_pics_sps > 0 {
+        reader.read_ue()?
+    } else {
+        0
+    };
+    let num_long_term_pics = reader.read_ue()?;
+    if num_long_term_sps > sps.num_long_term_ref_pics_sps as u32
+        || num_long_term_sps + num_long_term_pics > MAX_LONG_TERM_PICS
+    {
+        return Err(HevcError::InvalidData(format!(
+            "{} + {} long-term pictures exceeds maximum",
+            num_long_term_sps, num_long_term_pics
+        )));
+    }
+    header.num_long_term_sps = num_long_term_sps as u8;
+    header.num_long_term_pics = num_long_term_pics as u8;
+
+    let poc_bits = sps.log2_max_pic_order_cnt_lsb_minus4 + 4;
+    let lt_idx_bits = ceil_log2(sps.num_long_term_ref_pics_sps as u32);
+    for i in 0..num_long_term_sps + num_long_term_pics {
+        let mut lt = LongTermRefPic::default();
+        if i < num_long_term_sps {
+            let lt_idx_sps = if sps.num_long_term_ref_pics_sps > 1 {
+                reader.read_bits(lt_idx_bits)? as usize
+            } else {
+                0
+            };
+            lt.poc_lsb_lt = sps
+                .lt_ref_pic_poc_lsb_sps
+                .get(lt_idx_sps)
+                .copied()
+                .unwrap_or(0);
+            lt.used_by_curr_pic_lt_flag = sps
+                .used_by_curr_pic_lt_sps_flag
+                .get(lt_idx_sps)
+                .copied()
+                .unwrap_or(false);
+        } else {
+            lt.poc_lsb_lt = reader.read_bits(poc_bits)?;
+            lt.used_by_curr_pic_lt_flag = reader.read_bit()?;
+        }
+
+        lt.delta_poc_msb_present_flag = reader.read_bit()?;
+        if lt.delta_poc_msb_present_flag {
+            lt.delta_poc_msb_cycle_lt = reader.read_ue()?;
+        }
+        // DeltaPocMsbCycleLt accumulates within the SPS and slice groups (7-52)
+        if i != 0 && i != num_long_term_sps {
+            let prev = header.long_term_ref_pics[i as usize - 1].delta_poc_msb_cycle_lt;
+            lt.delta_poc_msb_cycle_lt = lt.delta_poc_msb_cycle_lt.saturating_add(prev);
+        }
+        header.long_term_ref_pics.push(lt);
+    }
+
+    Ok(())
+}
+
+/// Parse `ref_pic_lists_modification()` (Section 7.3.6.2).
+fn parse_ref_pic_lists_modification(
+    reader: &mut BitReader,
+    header: &SliceHeader,
+    entry_bits: u8,
+) -> Result<RefPicListModification> {
+    let mut rplm = RefPicListModification {
+        ref_pic_list_modification_flag_l0: reader.read_bit()?,
+        ..Default::default()
+    };
+    if rplm.ref_pic_list_modification_flag_l0 {
+        for _ in 0..header.num_ref_idx_l0_active() {
+            rplm.list_entry_l0.push(reader.read_bits(entry_bits)? as u8);
+        }
+    }
+
+    if header.slice_type == SliceType::B {
+        rplm.ref_pic_list_modification_flag_l1 = reader.read_bit()?;
+        if rplm.ref_pic_list_modification_flag_l1 {
+            for _ in 0..header.num_ref_idx_l1_active() {
+                rplm.list_entry_l1.push(reader.read_bits(entry_bits)? as u8);
+            }
+        }
+    }
+
+    Ok(rplm)
+}
+
+/// Parse `pred_weight_table()` (Section 7.3.6.3).
+///
+/// Weights and offsets are stored in their derived form (LumaWeightL0,
+/// ChromaOffsetL0, ...), with defaults for entries without explicit weights.
+fn parse_pred_weight_table(
+    reader: &mut BitReader,
+    sps: &Sps,
+    header: &SliceHeader,
+) -> Result<PredWeightTable> {
+    let has_chroma =
+        sps.chroma_format_idc != ChromaFormat::Monochrome && !sps.separate_colour_plane_flag;
+
+    let mut pwt = PredWeightTable {
+        luma_log2_weight_denom: reader.read_ue()?.min(7) as u8,
+        ..Default::default()
+    };
+    if has_chroma {
+        pwt.delta_chroma_log2_weight_denom = reader.read_se()?.clamp(-7, 7) as i8;
+    }
+    let luma_denom = pwt.luma_log2_weight_denom as i32;
+    let chroma_denom = (luma_denom + pwt.delta_chroma_log2_weight_denom as i32).clamp(0, 7);
+
+    let mut lists = vec![header.num_ref_idx_l0_active() as usize];
+    if header.slice_type == SliceType::B {
+        lists.push(header.num_ref_idx_l1_active() as usize);
+    }
+
+    for (list, &num_refs) in lists.iter().enumerate() {
+        let luma_flags = (0..num_refs)
+            .map(|_| reader.read_bit())
+            .collect::<Result<Vec<_>>>()?;
+        let chroma_flags = if has_chroma {
+            (0..num_refs)
+                .map(|_| reader.read_bit())
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            vec![false; num_refs]
+        };
+
+        let mut luma_weight = Vec::with_capacity(num_refs);
+        let mut luma_offset = Vec::with_capacity(num_refs);
+        let mut chroma_weight = Vec::with_capacity(num_refs);
+        let mut chroma_offset = Vec::with_capacity(num_refs);
+        for i in 0..num_refs {
+            if luma_flags[i] {
+                luma_weight.push(((1 << luma_denom) + reader.read_se()?) as i16);
+                luma_offset.push(reader.read_se()? as i16);
+            } else {
+                luma_weight.push(1 << luma_denom);
+                luma_offset.push(0);
+            }
+
+            let mut weights = [1 << chroma_denom; 2];
+            let mut offsets = [0; 2];
+            if chroma_flags[i] {
+                for j in 0..2 {
+                    let weight = (1 << chroma_denom) + reader.read_se()?;
+                    let delta_offset = reader.read_se()?;
+                    // 8-bit offset range (no high_precision_offsets_enabled_flag)
+                    let half_range = 1 << 7;
+                    let offset =
+                        half_range - ((half_range * weight) >> chroma_denom) + delta_offset;
+                    weights[j] = weight as i16;
+                    offsets[j] = offset.clamp(-half_range, half_range - 1) as i16;
+                }
+            }
+            chroma_weight.push(weights);
+            chroma_offset.push(offsets);
+        }
+
+        if list == 0 {
+            pwt.luma_weight_l0 = luma_weight;
+            pwt.luma_offset_l0 = luma_offset;
+            pwt.chroma_weight_l0 = chroma_weight;
+            pwt.chroma_offset_l0 = chroma_offset;
+        } else {
+            pwt.luma_weight_l1 = luma_weight;
+            pwt.luma_offset_l1 = luma_offset;
+            pwt.chroma_weight_l1 = chroma_weight;
+            pwt.chroma_offset_l1 = chroma_offset;
+        }
+    }
+
+    Ok(pwt)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
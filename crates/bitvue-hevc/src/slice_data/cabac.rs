@@ -0,0 +1,1133 @@
+//! CABAC parsing process (ITU-T H.265 Section 9.3).
+//!
+//! Provides the arithmetic decoding engine, the context variables of each
+//! initialization type and the binarizations of the slice data syntax
+//! elements. Context index increments that depend on neighbouring blocks
+//! are derived by the caller.
+
+use crate::error::{HevcError, Result};
+use crate::overlay_extraction::PartMode;
+
+// Offset of the first context variable of each syntax element, in the order
+// of `INIT_VALUES`.
+const SAO_MERGE_FLAG: usize = 0;
+const SAO_TYPE_IDX: usize = SAO_MERGE_FLAG + 1;
+const SPLIT_CU_FLAG: usize = SAO_TYPE_IDX + 1;
+const CU_TRANSQUANT_BYPASS_FLAG: usize = SPLIT_CU_FLAG + 3;
+const CU_SKIP_FLAG: usize = CU_TRANSQUANT_BYPASS_FLAG + 1;
+const PRED_MODE_FLAG: usize = CU_SKIP_FLAG + 3;
+const PART_MODE: usize = PRED_MODE_FLAG + 1;
+const PREV_INTRA_LUMA_PRED_FLAG: usize = PART_MODE + 4;
+const INTRA_CHROMA_PRED_MODE: usize = PREV_INTRA_LUMA_PRED_FLAG + 1;
+const RQT_ROOT_CBF: usize = INTRA_CHROMA_PRED_MODE + 1;
+const MERGE_FLAG: usize = RQT_ROOT_CBF + 1;
+const MERGE_IDX: usize = MERGE_FLAG + 1;
+const INTER_PRED_IDC: usize = MERGE_IDX + 1;
+const REF_IDX: usize = INTER_PRED_IDC + 5;
+const MVP_FLAG: usize = REF_IDX + 2;
+const ABS_MVD_GREATER0_FLAG: usize = MVP_FLAG + 1;
+const ABS_MVD_GREATER1_FLAG: usize = ABS_MVD_GREATER0_FLAG + 1;
+const SPLIT_TRANSFORM_FLAG: usize = ABS_MVD_GREATER1_FLAG + 1;
+const CBF_LUMA: usize = SPLIT_TRANSFORM_FLAG + 3;
+const CBF_CHROMA: usize = CBF_LUMA + 2;
+const CU_QP_DELTA_ABS: usize = CBF_CHROMA + 5;
+const TRANSFORM_SKIP_FLAG: usize = CU_QP_DELTA_ABS + 2;
+const LAST_SIG_COEFF_X_PREFIX: usize = TRANSFORM_SKIP_FLAG + 2;
+const LAST_SIG_COEFF_Y_PREFIX: usize = LAST_SIG_COEFF_X_PREFIX + 18;
+const CODED_SUB_BLOCK_FLAG: usize = LAST_SIG_COEFF_Y_PREFIX + 18;
+const SIG_COEFF_FLAG: usize = CODED_SUB_BLOCK_FLAG + 4;
+const COEFF_ABS_LEVEL_GREATER1_FLAG: usize = SIG_COEFF_FLAG + 44;
+const COEFF_ABS_LEVEL_GREATER2_FLAG: usize = COEFF_ABS_LEVEL_GREATER1_FLAG + 24;
+
+/// Number of context variables.
+const NUM_CONTEXTS: usize = COEFF_ABS_LEVEL_GREATER2_FLAG + 6;
+
+/// Longest prefix of coeff_abs_level_remaining accepted.
+const MAX_REMAINING_PREFIX: u32 = 32;
+
+/// sig_coeff_flag ctxIdxMap of 4x4 transform blocks (Table 9-50).
+const CTX_IDX_MAP: [u8; 16] = [0, 1, 4, 5, 2, 3, 4, 5, 6, 6, 8, 8, 7, 7, 8, 8];
+
+/// Reference list usage of a prediction unit (inter_pred_idc).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum InterPredIdc {
+    L0,
+    L1,
+    Bi,
+}
+
+/// Context variable (pStateIdx and valMps).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(super) struct Context {
+    state: u8,
+    mps: bool,
+}
+
+/// Scan orders by log2 block size (0 to 3) and scanIdx (Section 6.5.3 to
+/// 6.5.5), as (x, y) positions.
+struct ScanTables {
+    orders: [[Vec<(u8, u8)>; 3]; 4],
+}
+
+impl ScanTables {
+    fn new() -> Self {
+        let orders = [0u8, 1, 2, 3].map(|log2| {
+            let size = 1u8 << log2;
+            let horizontal = (0..size)
+                .flat_map(|y| (0..size).map(move |x| (x, y)))
+                .collect();
+            let vertical = (0..size)
+                .flat_map(|x| (0..size).map(move |y| (x, y)))
+                .collect();
+            [up_right_diagonal(size), horizontal, vertical]
+        });
+        Self { orders }
+    }
+}
+
+/// Up-right diagonal scan order of a square block (Section 6.5.3).
+fn up_right_diagonal(size: u8) -> Vec<(u8, u8)> {
+    let size = size as i32;
+    let mut order = Vec::with_capacity((size * size) as usize);
+    for sum in 0..2 * size - 1 {
+        for y in (0..=sum).rev() {
+            let x = sum - y;
+            if x < size && y < size {
+                order.push((x as u8, y as u8));
+            }
+        }
+    }
+    order
+}
+
+/// Parameters of one `residual_coding()`.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ResidualParams {
+    /// log2TrafoSize of the block.
+    pub log2_size: u8,
+    /// Colour component index (cIdx).
+    pub c_idx: usize,
+    /// scanIdx.
+    pub scan_idx: usize,
+    /// Whether transform_skip_flag is present.
+    pub transform_skip_allowed: bool,
+    /// Whether sign data hiding applies (enabled and no transquant bypass).
+    pub sign_hiding: bool,
+}
+
+/// CABAC decoder over the RBSP of a slice segment.
+pub(super) struct CabacReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    range: u32,
+    offset: u32,
+    contexts: Vec<Context>,
+    init_type: usize,
+    slice_qp: i32,
+    scans: ScanTables,
+}
+
+impl<'a> CabacReader<'a> {
+    /// Initialise the context variables for `init_type` and `slice_qp`,
+    /// and the decoding engine at the byte-aligned bit position `pos`.
+    pub(super) fn new(data: &'a [u8], pos: usize, init_type: usize, slice_qp: i32) -> Result<Self> {
+        let mut reader = Self {
+            data,
+            pos,
+            range: 0,
+            offset: 0,
+            contexts: Vec::new(),
+            init_type,
+            slice_qp,
+            scans: ScanTables::new(),
+        };
+        reader.init_contexts();
+        reader.init_engine()?;
+        Ok(reader)
+    }
+
+    /// Initialise all context variables (9.3.2.2).
+    pub(super) fn init_contexts(&mut self) {
+        let qp = self.slice_qp.clamp(0, 51);
+        self.contexts = INIT_VALUES
+            .iter()
+            .flat_map(|values| values[self.init_type].iter())
+            .map(|&init_value| {
+                let m = (init_value as i32 >> 4) * 5 - 45;
+                let n = ((init_value as i32 & 15) << 3) - 16;
+                let pre = (((m * qp) >> 4) + n).clamp(1, 126);
+                if pre <= 63 {
+                    Context {
+                        state: (63 - pre) as u8,
+                        mps: false,
+                    }
+                } else {
+                    Context {
+                        state: (pre - 64) as u8,
+                        mps: true,
+                    }
+                }
+            })
+            .collect();
+        debug_assert_eq!(self.contexts.len(), NUM_CONTEXTS);
+    }
+
+    /// Context variables for the WPP storage process (9.3.2.3).
+    pub(super) fn contexts(&self) -> Vec<Context> {
+        self.contexts.clone()
+    }
+
+    /// Restore context variables saved with [`Self::contexts`] (9.3.2.4).
+    pub(super) fn set_contexts(&mut self, contexts: &[Context]) {
+        self.contexts.copy_from_slice(contexts);
+    }
+
+    fn init_engine(&mut self) -> Result<()> {
+        self.range = 510;
+        self.offset = 0;
+        for _ in 0..9 {
+            self.offset = (self.offset << 1) | self.read_bit();
+        }
+        if self.offset >= 510 {
+            return Err(HevcError::InvalidData(
+                "invalid CABAC initial offset".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Re-initialise the decoding engine at bit position `pos`, at the
+    /// start of a substream or after `pcm_sample()` data.
+    pub(super) fn restart(&mut self, pos: usize) -> Result<()> {
+        self.pos = pos;
+        self.init_engine()
+    }
+
+    /// Bit position of the next bit the engine would read.
+    pub(super) fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Whether the engine has read past the end of the slice data.
+    pub(super) fn overrun(&self) -> bool {
+        self.pos > self.data.len() * 8
+    }
+
+    fn read_bit(&mut self) -> u32 {
+        let bit = self
+            .data
+            .get(self.pos / 8)
+            .map_or(0, |b| (b >> (7 - self.pos % 8)) & 1);
+        self.pos += 1;
+        bit as u32
+    }
+
+    /// DecodeDecision (9.3.4.3.2).
+    fn decision(&mut self, ctx_idx: usize) -> bool {
+        let ctx = &mut self.contexts[ctx_idx];
+        let lps = RANGE_TAB_LPS[ctx.state as usize][((self.range >> 6) & 3) as usize] as u32;
+        self.range -= lps;
+        let bin = if self.offset >= self.range {
+            self.offset -= self.range;
+            self.range = lps;
+            let bin = !ctx.mps;
+            if ctx.state == 0 {
+                ctx.mps = !ctx.mps;
+            }
+            ctx.state = TRANS_IDX_LPS[ctx.state as usize];
+            bin
+        } else {
+            if ctx.state < 62 {
+                ctx.state += 1;
+            }
+            ctx.mps
+        };
+        while self.range < 256 {
+            self.range <<= 1;
+            self.offset = (self.offset << 1) | self.read_bit();
+        }
+        bin
+    }
+
+    /// DecodeBypass (9.3.4.3.4).
+    fn bypass(&mut self) -> bool {
+        self.offset = (self.offset << 1) | self.read_bit();
+        if self.offset >= self.range {
+            self.offset -= self.range;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Fixed-length bypass value of `n` bits.
+    fn bypass_bits(&mut self, n: u32) -> u32 {
+        (0..n).fold(0, |value, _| (value << 1) | self.bypass() as u32)
+    }
+
+    /// DecodeTerminate (9.3.4.3.5).
+    ///
+    /// When the bin is 1 the engine stops, and the last bit read is the final
+    /// bit written by the encoder flush.
+    fn terminate(&mut self) -> bool {
+        self.range -= 2;
+        if self.offset >= self.range {
+            true
+        } else {
+            while self.range < 256 {
+                self.range <<= 1;
+                self.offset = (self.offset << 1) | self.read_bit();
+            }
+            false
+        }
+    }
+
+    /// k-th order Exp-Golomb bypass value (9.3.3.3).
+    fn exp_golomb_bypass(&mut self, mut k: u32) -> Result<u32> {
+        let mut value = 0u32;
+        while self.bypass() {
+            value += 1 << k;
+            k += 1;
+            if k >= 31 {
+                return Err(HevcError::InvalidData(
+                    "CABAC Exp-Golomb value exceeds 32 bits".to_string(),
+                ));
+            }
+        }
+        Ok(value + self.bypass_bits(k))
+    }
+
+    /// end_of_slice_segment_flag.
+    pub(super) fn end_of_slice_segment_flag(&mut self) -> bool {
+        self.terminate()
+    }
+
+    /// end_of_subset_one_bit.
+    pub(super) fn end_of_subset_one_bit(&mut self) -> bool {
+        self.terminate()
+    }
+
+    /// sao_merge_left_flag and sao_merge_up_flag.
+    pub(super) fn sao_merge_flag(&mut self) -> bool {
+        self.decision(SAO_MERGE_FLAG)
+    }
+
+    /// sao_type_idx_luma and sao_type_idx_chroma.
+    pub(super) fn sao_type_idx(&mut self) -> u8 {
+        if !self.decision(SAO_TYPE_IDX) {
+            0
+        } else if self.bypass() {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// sao_offset_abs with the largest value `c_max`.
+    pub(super) fn sao_offset_abs(&mut self, c_max: u32) -> u32 {
+        let mut value = 0;
+        while value < c_max && self.bypass() {
+            value += 1;
+        }
+        value
+    }
+
+    /// sao_offset_sign.
+    pub(super) fn sao_offset_sign(&mut self) -> bool {
+        self.bypass()
+    }
+
+    /// sao_band_position.
+    pub(super) fn sao_band_position(&mut self) -> u32 {
+        self.bypass_bits(5)
+    }
+
+    /// sao_eo_class_luma and sao_eo_class_chroma.
+    pub(super) fn sao_eo_class(&mut self) -> u32 {
+        self.bypass_bits(2)
+    }
+
+    /// split_cu_flag with ctxInc `inc`.
+    pub(super) fn split_cu_flag(&mut self, inc: usize) -> bool {
+        self.decision(SPLIT_CU_FLAG + inc)
+    }
+
+    /// cu_transquant_bypass_flag.
+    pub(super) fn cu_transquant_bypass_flag(&mut self) -> bool {
+        self.decision(CU_TRANSQUANT_BYPASS_FLAG)
+    }
+
+    /// cu_skip_flag with ctxInc `inc`.
+    pub(super) fn cu_skip_flag(&mut self, inc: usize) -> bool {
+        self.decision(CU_SKIP_FLAG + inc)
+    }
+
+    /// pred_mode_flag; true for intra.
+    pub(super) fn pred_mode_flag(&mut self) -> bool {
+        self.decision(PRED_MODE_FLAG)
+    }
+
+    /// part_mode (Table 9-43).
+    ///
+    /// `min_cb` tells whether the coding block has the minimum size, and
+    /// `log2_cb_size` is its size.
+    pub(super) fn part_mode(
+        &mut self,
+        intra: bool,
+        min_cb: bool,
+        log2_cb_size: u8,
+        amp_enabled: bool,
+    ) -> PartMode {
+        if self.decision(PART_MODE) {
+            return PartMode::Part2Nx2N;
+        }
+        if intra {
+            return PartMode::NxN;
+        }
+        if min_cb {
+            if self.decision(PART_MODE + 1) {
+                PartMode::Part2NxN
+            } else if log2_cb_size == 3 || self.decision(PART_MODE + 2) {
+                PartMode::PartNx2N
+            } else {
+                PartMode::NxN
+            }
+        } else if !amp_enabled {
+            if self.decision(PART_MODE + 1) {
+                PartMode::Part2NxN
+            } else {
+                PartMode::PartNx2N
+            }
+        } else if self.decision(PART_MODE + 1) {
+            if self.decision(PART_MODE + 3) {
+                PartMode::Part2NxN
+            } else if self.bypass() {
+                PartMode::Part2NxnD
+            } else {
+                PartMode::Part2NxnU
+            }
+        } else if self.decision(PART_MODE + 3) {
+            PartMode::PartNx2N
+        } else if self.bypass() {
+            PartMode::PartnRx2N
+        } else {
+            PartMode::PartnLx2N
+        }
+    }
+
+    /// pcm_flag.
+    pub(super) fn pcm_flag(&mut self) -> bool {
+        self.terminate()
+    }
+
+    /// prev_intra_luma_pred_flag.
+    pub(super) fn prev_intra_luma_pred_flag(&mut self) -> bool {
+        self.decision(PREV_INTRA_LUMA_PRED_FLAG)
+    }
+
+    /// mpm_idx.
+    pub(super) fn mpm_idx(&mut self) -> usize {
+        if !self.bypass() {
+            0
+        } else if !self.bypass() {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// rem_intra_luma_pred_mode.
+    pub(super) fn rem_intra_luma_pred_mode(&mut self) -> u8 {
+        self.bypass_bits(5) as u8
+    }
+
+    /// intra_chroma_pred_mode.
+    pub(super) fn intra_chroma_pred_mode(&mut self) -> u8 {
+        if self.decision(INTRA_CHROMA_PRED_MODE) {
+            self.bypass_bits(2) as u8
+        } else {
+            4
+        }
+    }
+
+    /// rqt_root_cbf.
+    pub(super) fn rqt_root_cbf(&mut self) -> bool {
+        self.decision(RQT_ROOT_CBF)
+    }
+
+    /// merge_flag.
+    pub(super) fn merge_flag(&mut self) -> bool {
+        self.decision(MERGE_FLAG)
+    }
+
+    /// merge_idx with MaxNumMergeCand `max_num_merge_cand`.
+    pub(super) fn merge_idx(&mut self, max_num_merge_cand: usize) -> usize {
+        if max_num_merge_cand <= 1 || !self.decision(MERGE_IDX) {
+            return 0;
+        }
+        let mut idx = 1;
+        while idx < max_num_merge_cand - 1 && self.bypass() {
+            idx += 1;
+        }
+        idx
+    }
+
+    /// inter_pred_idc of a prediction block of `width` x `height` samples
+    /// in a coding unit at quadtree depth `ct_depth`.
+    pub(super) fn inter_pred_idc(&mut self, width: u32, height: u32, ct_depth: u8) -> InterPredIdc {
+        if width + height != 12 && self.decision(INTER_PRED_IDC + ct_depth as usize) {
+            return InterPredIdc::Bi;
+        }
+        if self.decision(INTER_PRED_IDC + 4) {
+            InterPredIdc::L1
+        } else {
+            InterPredIdc::L0
+        }
+    }
+
+    /// ref_idx_l0 and ref_idx_l1 with `num_ref_idx_active` references.
+    pub(super) fn ref_idx(&mut self, num_ref_idx_active: u32) -> u32 {
+        let c_max = num_ref_idx_active.saturating_sub(1);
+        let mut idx = 0;
+        while idx < c_max {
+            let bin = if idx < 2 {
+                self.decision(REF_IDX + idx as usize)
+            } else {
+                self.bypass()
+            };
+            if !bin {
+                break;
+            }
+            idx += 1;
+        }
+        idx
+    }
+
+    /// mvd_coding(): the horizontal and vertical motion vector difference.
+    pub(super) fn mvd_coding(&mut self) -> Result<[i32; 2]> {
+        let greater0 = [
+            self.decision(ABS_MVD_GREATER0_FLAG),
+            self.decision(ABS_MVD_GREATER0_FLAG),
+        ];
+        let greater1 = greater0.map(|g0| g0 && self.decision(ABS_MVD_GREATER1_FLAG));
+        let mut mvd = [0i32; 2];
+        for (component, value) in mvd.iter_mut().enumerate() {
+            if !greater0[component] {
+                continue;
+            }
+            let abs = if greater1[component] {
+                self.exp_golomb_bypass(1)? as i64 + 2
+            } else {
+                1
+            };
+            if abs > 1 << 15 {
+                return Err(HevcError::InvalidData(format!(
+                    "motion vector difference {} out of range",
+                    abs
+                )));
+            }
+            *value = if self.bypass() { -abs } else { abs } as i32;
+        }
+        Ok(mvd)
+    }
+
+    /// mvp_l0_flag and mvp_l1_flag.
+    pub(super) fn mvp_flag(&mut self) -> usize {
+        self.decision(MVP_FLAG) as usize
+    }
+
+    /// split_transform_flag of a `log2_trafo_size` transform block.
+    pub(super) fn split_transform_flag(&mut self, log2_trafo_size: u8) -> bool {
+        self.decision(SPLIT_TRANSFORM_FLAG + 5 - log2_trafo_size as usize)
+    }
+
+    /// cbf_luma at transform tree depth `trafo_depth`.
+    pub(super) fn cbf_luma(&mut self, trafo_depth: u8) -> bool {
+        self.decision(CBF_LUMA + (trafo_depth == 0) as usize)
+    }
+
+    /// cbf_cb and cbf_cr at transform tree depth `trafo_depth`.
+    pub(super) fn cbf_chroma(&mut self, trafo_depth: u8) -> bool {
+        self.decision(CBF_CHROMA + trafo_depth as usize)
+    }
+
+    /// cu_qp_delta_abs and cu_qp_delta_sign_flag: CuQpDeltaVal.
+    pub(super) fn cu_qp_delta(&mut self) -> Result<i32> {
+        let mut abs = 0u32;
+        while abs < 5 && self.decision(CU_QP_DELTA_ABS + (abs > 0) as usize) {
+            abs += 1;
+        }
+        if abs == 5 {
+            abs += self.exp_golomb_bypass(0)?;
+        }
+        if abs > 64 {
+            return Err(HevcError::InvalidData(format!(
+                "cu_qp_delta_abs {} out of range",
+                abs
+            )));
+        }
+        let abs = abs as i32;
+        Ok(if abs > 0 && self.bypass() { -abs } else { abs })
+    }
+
+    /// Parse `residual_coding()` and return the number of nonzero
+    /// coefficients. Coefficient values are not reconstructed.
+    pub(super) fn residual_coding(&mut self, params: ResidualParams) -> Result<u32> {
+        let ResidualParams {
+            log2_size,
+            c_idx,
+            scan_idx,
+            transform_skip_allowed,
+            sign_hiding,
+        } = params;
+        let chroma = c_idx > 0;
+
+        if transform_skip_allowed {
+            self.decision(TRANSFORM_SKIP_FLAG + chroma as usize);
+        }
+
+        // last_sig_coeff_x_prefix, last_sig_coeff_y_prefix and suffixes
+        let (ctx_offset, ctx_shift) = if chroma {
+            (15, log2_size as usize - 2)
+        } else {
+            (
+                3 * (log2_size as usize - 2) + ((log2_size as usize - 1) >> 2),
+                (log2_size as usize + 1) >> 2,
+            )
+        };
+        let c_max = (log2_size as u32) * 2 - 1;
+        let mut prefix = [0u32; 2];
+        for (base, value) in [LAST_SIG_COEFF_X_PREFIX, LAST_SIG_COEFF_Y_PREFIX]
+            .into_iter()
+            .zip(prefix.iter_mut())
+        {
+            while *value < c_max
+                && self.decision(base + ctx_offset + (*value as usize >> ctx_shift))
+            {
+                *value += 1;
+            }
+        }
+        let mut last = prefix;
+        for (value, &p) in last.iter_mut().zip(&prefix) {
+            if p > 3 {
+                let suffix_len = (p >> 1) - 1;
+                *value = (1 << suffix_len) * (2 + (p & 1)) + self.bypass_bits(suffix_len);
+            }
+        }
+        if scan_idx == 2 {
+            last.swap(0, 1);
+        }
+        let (last_x, last_y) = (last[0] as usize, last[1] as usize);
+        let size = 1usize << log2_size;
+        if last_x >= size || last_y >= size {
+            return Err(HevcError::InvalidData(format!(
+                "last significant coefficient ({}, {}) outside {}x{} block",
+                last_x, last_y, size, size
+            )));
+        }
+
+        let log2_sb = log2_size as usize - 2;
+        let sb_width = 1usize << log2_sb;
+        let sb_scan = &self.scans.orders[log2_sb][scan_idx];
+        let pos_scan = &self.scans.orders[2][scan_idx];
+
+        // Locate the last subblock and scan position
+        let last_sub_block = sb_scan
+            .iter()
+            .position(|&(x, y)| x as usize == last_x >> 2 && y as usize == last_y >> 2)
+            .unwrap_or(0);
+        let last_scan_pos = pos_scan
+            .iter()
+            .position(|&(x, y)| x as usize == last_x & 3 && y as usize == last_y & 3)
+            .unwrap_or(0);
+        let sb_scan: Vec<(u8, u8)> = sb_scan.clone();
+        let pos_scan: [(u8, u8); 16] = std::array::from_fn(|n| pos_scan[n]);
+
+        let mut num_coeff = 0u32;
+        let mut coded_sub_block = vec![false; sb_width * sb_width];
+        let mut greater1_ctx = 1u32;
+        for i in (0..=last_sub_block).rev() {
+            let (xs, ys) = (sb_scan[i].0 as usize, sb_scan[i].1 as usize);
+            let right = xs + 1 < sb_width && coded_sub_block[ys * sb_width + xs + 1];
+            let below = ys + 1 < sb_width && coded_sub_block[(ys + 1) * sb_width + xs];
+
+            let mut infer_sb_dc = false;
+            let coded = if i < last_sub_block && i > 0 {
+                let inc = (right || below) as usize + if chroma { 2 } else { 0 };
+                infer_sb_dc = true;
+                self.decision(CODED_SUB_BLOCK_FLAG + inc)
+            } else {
+                true
+            };
+            coded_sub_block[ys * sb_width + xs] = coded;
+
+            let prev_csbf = right as usize | (below as usize) << 1;
+            let mut significant = [false; 16];
+            let first_n = if i == last_sub_block {
+                significant[last_scan_pos] = true;
+                last_scan_pos as isize - 1
+            } else {
+                15
+            };
+            if coded {
+                for n in (0..=first_n).rev() {
+                    let n = n as usize;
+                    let (xp, yp) = (pos_scan[n].0 as usize, pos_scan[n].1 as usize);
+                    if n == 0 && infer_sb_dc {
+                        significant[0] = true;
+                        break;
+                    }
+                    let sig_ctx = sig_coeff_ctx_inc(
+                        log2_size,
+                        c_idx,
+                        scan_idx,
+                        (xs << 2) + xp,
+                        (ys << 2) + yp,
+                        prev_csbf,
+                    );
+                    if self.decision(SIG_COEFF_FLAG + sig_ctx) {
+                        significant[n] = true;
+                        infer_sb_dc = false;
+                    }
+                }
+            }
+
+            let sig_positions: Vec<usize> = (0..16).rev().filter(|&n| significant[n]).collect();
+            if sig_positions.is_empty() {
+                continue;
+            }
+            num_coeff += sig_positions.len() as u32;
+
+            // coeff_abs_level_greater1_flag
+            let mut ctx_set = if i > 0 && !chroma { 2 } else { 0 };
+            if i != last_sub_block && greater1_ctx == 0 {
+                ctx_set += 1;
+            }
+            greater1_ctx = 1;
+            let mut greater1 = [false; 16];
+            let mut last_greater1_pos = None;
+            for &n in sig_positions.iter().take(8) {
+                let inc = ctx_set * 4 + greater1_ctx + if chroma { 16 } else { 0 };
+                greater1[n] = self.decision(COEFF_ABS_LEVEL_GREATER1_FLAG + inc as usize);
+                if greater1[n] {
+                    greater1_ctx = 0;
+                    if last_greater1_pos.is_none() {
+                        last_greater1_pos = Some(n);
+                    }
+                } else if greater1_ctx > 0 && greater1_ctx < 3 {
+                    greater1_ctx += 1;
+                }
+            }
+
+            // coeff_abs_level_greater2_flag
+            let mut greater2 = [false; 16];
+            if let Some(n) = last_greater1_pos {
+                let inc = ctx_set + if chroma { 4 } else { 0 };
+                greater2[n] = self.decision(COEFF_ABS_LEVEL_GREATER2_FLAG + inc as usize);
+            }
+
+            // coeff_sign_flag
+            let last_sig_scan_pos = sig_positions[0];
+            let first_sig_scan_pos = sig_positions[sig_positions.len() - 1];
+            let sign_hidden = sign_hiding && last_sig_scan_pos - first_sig_scan_pos > 3;
+            for &n in &sig_positions {
+                if !sign_hidden || n != first_sig_scan_pos {
+                    self.bypass();
+                }
+            }
+
+            // coeff_abs_level_remaining
+            let mut rice = 0u32;
+            for (num_sig, &n) in sig_positions.iter().enumerate() {
+                let base_level = 1 + greater1[n] as u32 + greater2[n] as u32;
+                let threshold = if num_sig < 8 {
+                    if Some(n) == last_greater1_pos {
+                        3
+                    } else {
+                        2
+                    }
+                } else {
+                    1
+                };
+                if base_level == threshold {
+                    let remaining = self.coeff_abs_level_remaining(rice)?;
+                    let level = base_level as u64 + remaining as u64;
+                    if level > 3 * (1u64 << rice) {
+                        rice = (rice + 1).min(4);
+                    }
+                }
+            }
+        }
+        Ok(num_coeff)
+    }
+
+    /// coeff_abs_level_remaining with Rice parameter `rice` (9.3.3.11).
+    fn coeff_abs_level_remaining(&mut self, rice: u32) -> Result<u32> {
+        let mut prefix = 0;
+        while self.bypass() {
+            prefix += 1;
+            if prefix > MAX_REMAINING_PREFIX {
+                return Err(HevcError::InvalidData(
+                    "coeff_abs_level_remaining prefix too long".to_string(),
+                ));
+            }
+        }
+        if prefix < 3 {
+            Ok((prefix << rice) + self.bypass_bits(rice))
+        } else {
+            let extra = prefix - 3;
+            let suffix = self.bypass_bits(extra + rice) as u64;
+            let value = ((((1u64 << extra) + 2) << rice) + suffix).min(u32::MAX as u64);
+            Ok(value as u32)
+        }
+    }
+}
+
+/// ctxInc of sig_coeff_flag (9.3.4.2.5) for the coefficient at (`xc`, `yc`)
+/// of the transform block.
+fn sig_coeff_ctx_inc(
+    log2_size: u8,
+    c_idx: usize,
+    scan_idx: usize,
+    xc: usize,
+    yc: usize,
+    prev_csbf: usize,
+) -> usize {
+    let sig_ctx = if log2_size == 2 {
+        CTX_IDX_MAP[(yc << 2) + xc] as usize
+    } else if xc + yc == 0 {
+        0
+    } else {
+        let (xp, yp) = (xc & 3, yc & 3);
+        let mut sig_ctx = match prev_csbf {
+            0 => match xp + yp {
+                0 => 2,
+                1 | 2 => 1,
+                _ => 0,
+            },
+            1 => match yp {
+                0 => 2,
+                1 => 1,
+                _ => 0,
+            },
+            2 => match xp {
+                0 => 2,
+                1 => 1,
+                _ => 0,
+            },
+            _ => 2,
+        };
+        if c_idx == 0 {
+            if (xc >> 2) + (yc >> 2) > 0 {
+                sig_ctx += 3;
+            }
+            sig_ctx += if log2_size == 3 {
+                if scan_idx == 0 {
+                    9
+                } else {
+                    15
+                }
+            } else {
+                21
+            };
+        } else {
+            sig_ctx += if log2_size == 3 { 9 } else { 12 };
+        }
+        sig_ctx
+    };
+    if c_idx == 0 {
+        sig_ctx
+    } else {
+        27 + sig_ctx
+    }
+}
+
+/// rangeTabLPS indexed by pStateIdx and qRangeIdx (Table 9-52).
+const RANGE_TAB_LPS: [[u8; 4]; 64] = [
+    [128, 176, 208, 240],
+    [128, 167, 197, 227],
+    [128, 158, 187, 216],
+    [123, 150, 178, 205],
+    [116, 142, 169, 195],
+    [111, 135, 160, 185],
+    [105, 128, 152, 175],
+    [100, 122, 144, 166],
+    [95, 116, 137, 158],
+    [90, 110, 130, 150],
+    [85, 104, 123, 142],
+    [81, 99, 117, 135],
+    [77, 94, 111, 128],
+    [73, 89, 105, 122],
+    [69, 85, 100, 116],
+    [66, 80, 95, 110],
+    [62, 76, 90, 104],
+    [59, 72, 86, 99],
+    [56, 69, 81, 94],
+    [53, 65, 77, 89],
+    [51, 62, 73, 85],
+    [48, 59, 69, 80],
+    [46, 56, 66, 76],
+    [43, 53, 63, 72],
+    [41, 50, 59, 69],
+    [39, 48, 56, 65],
+    [37, 45, 54, 62],
+    [35, 43, 51, 59],
+    [33, 41, 48, 56],
+    [32, 39, 46, 53],
+    [30, 37, 43, 50],
+    [29, 35, 41, 48],
+    [27, 33, 39, 45],
+    [26, 31, 37, 43],
+    [24, 30, 35, 41],
+    [23, 28, 33, 39],
+    [22, 27, 32, 37],
+    [21, 26, 30, 35],
+    [20, 24, 29, 33],
+    [19, 23, 27, 31],
+    [18, 22, 26, 30],
+    [17, 21, 25, 28],
+    [16, 20, 23, 27],
+    [15, 19, 22, 25],
+    [14, 18, 21, 24],
+    [14, 17, 20, 23],
+    [13, 16, 19, 22],
+    [12, 15, 18, 21],
+    [12, 14, 17, 20],
+    [11, 14, 16, 19],
+    [11, 13, 15, 18],
+    [10, 12, 15, 17],
+    [10, 12, 14, 16],
+    [9, 11, 13, 15],
+    [9, 11, 12, 14],
+    [8, 10, 12, 14],
+    [8, 9, 11, 13],
+    [7, 9, 11, 12],
+    [7, 9, 10, 12],
+    [7, 8, 10, 11],
+    [6, 8, 9, 11],
+    [6, 7, 9, 10],
+    [6, 7, 8, 9],
+    [2, 2, 2, 2],
+];
+
+/// transIdxLps (Table 9-53).
+const TRANS_IDX_LPS: [u8; 64] = [
+    0, 0, 1, 2, 2, 4, 4, 5, 6, 7, 8, 9, 9, 11, 11, 12, 13, 13, 15, 15, 16, 16, 18, 18, 19, 19, 21,
+    21, 22, 22, 23, 24, 24, 25, 26, 26, 27, 27, 28, 29, 29, 30, 30, 30, 31, 32, 32, 33, 33, 33, 34,
+    34, 35, 35, 35, 36, 36, 36, 37, 37, 37, 38, 38, 63,
+];
+
+/// initValue of the context variables of each syntax element for
+/// initType 0, 1 and 2 (Tables 9-5 to 9-37), in context offset order.
+/// Elements that do not occur in I slices use 154 for initType 0.
+const INIT_VALUES: [[&[u8]; 3]; 28] = [
+    // sao_merge_left_flag and sao_merge_up_flag
+    [&[153], &[153], &[153]],
+    // sao_type_idx_luma and sao_type_idx_chroma
+    [&[200], &[185], &[160]],
+    // split_cu_flag
+    [&[139, 141, 157], &[107, 139, 126], &[107, 139, 126]],
+    // cu_transquant_bypass_flag
+    [&[154], &[154], &[154]],
+    // cu_skip_flag
+    [&[154, 154, 154], &[197, 185, 201], &[197, 185, 201]],
+    // pred_mode_flag
+    [&[154], &[149], &[134]],
+    // part_mode
+    [
+        &[184, 154, 154, 154],
+        &[154, 139, 154, 154],
+        &[154, 139, 154, 154],
+    ],
+    // prev_intra_luma_pred_flag
+    [&[184], &[154], &[183]],
+    // intra_chroma_pred_mode
+    [&[63], &[152], &[152]],
+    // rqt_root_cbf
+    [&[154], &[79], &[79]],
+    // merge_flag
+    [&[154], &[110], &[154]],
+    // merge_idx
+    [&[154], &[122], &[137]],
+    // inter_pred_idc
+    [
+        &[154, 154, 154, 154, 154],
+        &[95, 79, 63, 31, 31],
+        &[95, 79, 63, 31, 31],
+    ],
+    // ref_idx_l0 and ref_idx_l1
+    [&[154, 154], &[153, 153], &[153, 153]],
+    // mvp_l0_flag and mvp_l1_flag
+    [&[154], &[168], &[168]],
+    // abs_mvd_greater0_flag
+    [&[154], &[140], &[169]],
+    // abs_mvd_greater1_flag
+    [&[154], &[198], &[198]],
+    // split_transform_flag
+    [&[153, 138, 138], &[124, 138, 94], &[224, 167, 122]],
+    // cbf_luma
+    [&[111, 141], &[153, 111], &[153, 111]],
+    // cbf_cb and cbf_cr
+    [
+        &[94, 138, 182, 154, 154],
+        &[149, 107, 167, 154, 154],
+        &[149, 92, 167, 154, 154],
+    ],
+    // cu_qp_delta_abs
+    [&[154, 154], &[154, 154], &[154, 154]],
+    // transform_skip_flag (luma, chroma)
+    [&[139, 139], &[139, 139], &[139, 139]],
+    // last_sig_coeff_x_prefix
+    [
+        &[
+            110, 110, 124, 125, 140, 153, 125, 127, 140, 109, 111, 143, 127, 111, 79, 108, 123, 63,
+        ],
+        &[
+            125, 110, 94, 110, 95, 79, 125, 111, 110, 78, 110, 111, 111, 95, 94, 108, 123, 108,
+        ],
+        &[
+            125, 110, 124, 110, 95, 94, 125, 111, 111, 79, 125, 126, 111, 111, 79, 108, 123, 93,
+        ],
+    ],
+    // last_sig_coeff_y_prefix
+    [
+        &[
+            110, 110, 124, 125, 140, 153, 125, 127, 140, 109, 111, 143, 127, 111, 79, 108, 123, 63,
+        ],
+        &[
+            125, 110, 94, 110, 95, 79, 125, 111, 110, 78, 110, 111, 111, 95, 94, 108, 123, 108,
+        ],
+        &[
+            125, 110, 124, 110, 95, 94, 125, 111, 111, 79, 125, 126, 111, 111, 79, 108, 123, 93,
+        ],
+    ],
+    // coded_sub_block_flag
+    [
+        &[91, 171, 134, 141],
+        &[121, 140, 61, 154],
+        &[121, 140, 61, 154],
+    ],
+    // sig_coeff_flag (the last two are the transform skip contexts)
+    [
+        &[
+            111, 111, 125, 110, 110, 94, 124, 108, 124, 107, 125, 141, 179, 153, 125, 107, 125,
+            141, 179, 153, 125, 107, 125, 141, 179, 153, 125, 140, 139, 182, 182, 152, 136, 152,
+            136, 153, 136, 139, 111, 136, 139, 111, 141, 111,
+        ],
+        &[
+            155, 154, 139, 153, 139, 123, 123, 63, 153, 166, 183, 140, 136, 153, 154, 166, 183,
+            140, 136, 153, 154, 166, 183, 140, 136, 153, 154, 170, 153, 123, 123, 107, 121, 107,
+            121, 167, 151, 183, 140, 151, 183, 140, 140, 140,
+        ],
+        &[
+            170, 154, 139, 153, 139, 123, 123, 63, 124, 166, 183, 140, 136, 153, 154, 166, 183,
+            140, 136, 153, 154, 166, 183, 140, 136, 153, 154, 170, 153, 138, 138, 122, 121, 122,
+            121, 167, 151, 183, 140, 151, 183, 140, 140, 140,
+        ],
+    ],
+    // coeff_abs_level_greater1_flag
+    [
+        &[
+            140, 92, 137, 138, 140, 152, 138, 139, 153, 74, 149, 92, 139, 107, 122, 152, 140, 179,
+            166, 182, 140, 227, 122, 197,
+        ],
+        &[
+            154, 196, 196, 167, 154, 152, 167, 182, 182, 134, 149, 136, 153, 121, 136, 137, 169,
+            194, 166, 167, 154, 167, 137, 182,
+        ],
+        &[
+            154, 196, 167, 167, 154, 152, 167, 182, 182, 134, 149, 136, 153, 121, 136, 122, 169,
+            208, 166, 167, 154, 152, 167, 182,
+        ],
+    ],
+    // coeff_abs_level_greater2_flag
+    [
+        &[138, 153, 136, 167, 152, 152],
+        &[107, 167, 91, 122, 107, 167],
+        &[107, 167, 91, 107, 107, 167],
+    ],
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_table_layout() {
+        for init_type in 0..3 {
+            let total: usize = INIT_VALUES.iter().map(|v| v[init_type].len()).sum();
+            assert_eq!(total, NUM_CONTEXTS);
+        }
+        let offset =
+            |element: usize| -> usize { INIT_VALUES[..element].iter().map(|v| v[0].len()).sum() };
+        assert_eq!(offset(2), SPLIT_CU_FLAG);
+        assert_eq!(offset(12), INTER_PRED_IDC);
+        assert_eq!(offset(22), LAST_SIG_COEFF_X_PREFIX);
+        assert_eq!(offset(25), SIG_COEFF_FLAG);
+        assert_eq!(offset(27), COEFF_ABS_LEVEL_GREATER2_FLAG);
+    }
+
+    #[test]
+    fn test_context_initialisation() {
+        // initValue 154: m = 0, n = 64 -> preCtxState 64, MPS 1
+        let data = [0u8; 4];
+        let reader = CabacReader::new(&data, 0, 0, 26).unwrap();
+        assert_eq!(
+            reader.contexts[CU_TRANSQUANT_BYPASS_FLAG],
+            Context {
+                state: 0,
+                mps: true
+            }
+        );
+        // initValue 63 at QP 26: m = -30, n = 104 -> preCtxState 55, MPS 0
+        assert_eq!(
+            reader.contexts[INTRA_CHROMA_PRED_MODE],
+            Context {
+                state: 8,
+                mps: false
+            }
+        );
+        assert_eq!(reader.position(), 9);
+    }
+
+    #[test]
+    fn test_terminate_after_initialisation() {
+        // Initial offsets of 510 and 511 are not allowed
+        assert!(CabacReader::new(&[0xFF, 0x80], 0, 0, 26).is_err());
+
+        // Offset 509 is past the 2-wide terminating interval of range 510
+        let data = [0xFE, 0x80];
+        let mut reader = CabacReader::new(&data, 0, 0, 26).unwrap();
+        assert!(reader.end_of_slice_segment_flag());
+        assert_eq!(reader.position(), 9);
+    }
+
+    #[test]
+    fn test_scan_orders() {
+        assert_eq!(up_right_diagonal(2), vec![(0, 0), (0, 1), (1, 0), (1, 1)]);
+        let scans = ScanTables::new();
+        let diag = &scans.orders[2][0];
+        assert_eq!(
+            &diag[..6],
+            &[(0, 0), (0, 1), (1, 0), (0, 2), (1, 1), (2, 0)]
+        );
+        assert_eq!(diag[15], (3, 3));
+        assert_eq!(scans.orders[1][1], vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+        assert_eq!(scans.orders[1][2], vec![(0, 0), (0, 1), (1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn test_sig_coeff_ctx_inc() {
+        // 4x4 blocks use ctxIdxMap
+        assert_eq!(sig_coeff_ctx_inc(2, 0, 0, 3, 3, 0), 8);
+        assert_eq!(sig_coeff_ctx_inc(2, 1, 0, 1, 0, 0), 28);
+        // DC of a larger block
+        assert_eq!(sig_coeff_ctx_inc(4, 0, 0, 0, 0, 0), 0);
+        // 8x8 luma, first subblock, diagonal scan
+        assert_eq!(sig_coeff_ctx_inc(3, 0, 0, 1, 0, 0), 10);
+        // 16x16 luma, second subblock with coded right neighbour
+        assert_eq!(sig_coeff_ctx_inc(4, 0, 0, 4, 1, 1), 1 + 3 + 21);
+        // 16x16 chroma
+        assert_eq!(sig_coeff_ctx_inc(4, 1, 0, 5, 0, 3), 27 + 2 + 12);
+    }
+}
@@ -0,0 +1,1357 @@
+//! Coding tree unit syntax (ITU-T H.265 Section 7.3.8).
+//!
+//! Walks `slice_segment_data()` CTU by CTU, keeping the per-slice state the
+//! syntax depends on: neighbouring blocks for context selection, intra mode
+//! and motion prediction, the QP predictor, and the CABAC contexts saved for
+//! wavefront parallel processing.
+
+use super::cabac::{CabacReader, Context, InterPredIdc, ResidualParams};
+use super::mv_prediction::{add_mvd, Block, PredictionContext, PuMotion};
+use crate::error::{HevcError, Result};
+use crate::overlay_extraction::{
+    CodingTreeUnit, CodingUnit, IntraMode, MotionVector, PartMode, PredMode, PredictionUnit,
+    TransformUnit,
+};
+use crate::pps::Pps;
+use crate::slice::{SliceHeader, SliceType};
+use crate::sps::Sps;
+use bitvue_core::ChromaFormat;
+
+/// Intra prediction modes (Table 8-1).
+const INTRA_PLANAR: u8 = 0;
+const INTRA_DC: u8 = 1;
+const INTRA_VERTICAL: u8 = 26;
+
+/// State of a decoded 4x4 luma block, as seen by later blocks of the slice.
+#[derive(Debug, Clone, Copy, Default)]
+struct BlockInfo {
+    decoded: bool,
+    intra: bool,
+    pcm: bool,
+    skip: bool,
+    ct_depth: u8,
+    intra_mode: u8,
+    qp: i8,
+    motion: PuMotion,
+}
+
+/// CTB raster and tile scan conversion (Section 6.5.1).
+pub(super) struct TileLayout {
+    width_ctbs: usize,
+    rs_to_ts: Vec<usize>,
+    ts_to_rs: Vec<usize>,
+    tile_id: Vec<usize>,
+}
+
+impl TileLayout {
+    pub(super) fn new(sps: &Sps, pps: &Pps) -> Result<Self> {
+        let width_ctbs = sps.pic_width_in_ctbs() as usize;
+        let height_ctbs = sps.pic_height_in_ctbs() as usize;
+
+        let (col_widths, row_heights) = match pps.tile_config.as_ref() {
+            Some(tiles) if pps.tiles_enabled_flag => (
+                tile_sizes(
+                    width_ctbs,
+                    tiles.num_columns() as usize,
+                    tiles.uniform_spacing_flag,
+                    &tiles.column_width_minus1,
+                )?,
+                tile_sizes(
+                    height_ctbs,
+                    tiles.num_rows() as usize,
+                    tiles.uniform_spacing_flag,
+                    &tiles.row_height_minus1,
+                )?,
+            ),
+            _ => (vec![width_ctbs], vec![height_ctbs]),
+        };
+        let col_bd: Vec<usize> = boundaries(&col_widths);
+        let row_bd: Vec<usize> = boundaries(&row_heights);
+
+        let pic_size = width_ctbs * height_ctbs;
+        let mut rs_to_ts = vec![0; pic_size];
+        for (rs, ts) in rs_to_ts.iter_mut().enumerate() {
+            let x = rs % width_ctbs;
+            let y = rs / width_ctbs;
+            let tile_x = col_bd.iter().rposition(|&bd| x >= bd).unwrap_or(0);
+            let tile_y = row_bd.iter().rposition(|&bd| y >= bd).unwrap_or(0);
+            *ts = row_heights[..tile_y].iter().sum::<usize>() * width_ctbs
+                + col_widths[..tile_x].iter().sum::<usize>() * row_heights[tile_y]
+                + (y - row_bd[tile_y]) * col_widths[tile_x]
+                + x
+                - col_bd[tile_x];
+        }
+
+        let mut ts_to_rs = vec![0; pic_size];
+        for (rs, &ts) in rs_to_ts.iter().enumerate() {
+            ts_to_rs[ts] = rs;
+        }
+
+        let mut tile_id = vec![0; pic_size];
+        let mut id = 0;
+        for (&row_y, &height) in row_bd.iter().zip(&row_heights) {
+            for (&col_x, &width) in col_bd.iter().zip(&col_widths) {
+                for y in row_y..row_y + height {
+                    for x in col_x..col_x + width {
+                        tile_id[y * width_ctbs + x] = id;
+                    }
+                }
+                id += 1;
+            }
+        }
+
+        Ok(Self {
+            width_ctbs,
+            rs_to_ts,
+            ts_to_rs,
+            tile_id,
+        })
+    }
+
+    pub(super) fn pic_size_in_ctbs(&self) -> usize {
+        self.rs_to_ts.len()
+    }
+}
+
+/// Column widths or row heights of the tiles, in CTBs (6-3 and 6-4).
+fn tile_sizes(total: usize, count: usize, uniform: bool, explicit: &[u16]) -> Result<Vec<usize>> {
+    if count == 0 || count > total {
+        return Err(HevcError::InvalidData(format!(
+            "{} tile columns or rows for {} CTBs",
+            count, total
+        )));
+    }
+    if uniform {
+        return Ok((0..count)
+            .map(|i| (i + 1) * total / count - i * total / count)
+            .collect());
+    }
+    if explicit.len() + 1 < count {
+        return Err(HevcError::InvalidData(
+            "missing explicit tile sizes".to_string(),
+        ));
+    }
+    let mut sizes: Vec<usize> = explicit[..count - 1]
+        .iter()
+        .map(|&size| size as usize + 1)
+        .collect();
+    let used: usize = sizes.iter().sum();
+    if used >= total {
+        return Err(HevcError::InvalidData(format!(
+            "explicit tile sizes exceed {} CTBs",
+            total
+        )));
+    }
+    sizes.push(total - used);
+    Ok(sizes)
+}
+
+fn boundaries(sizes: &[usize]) -> Vec<usize> {
+    sizes
+        .iter()
+        .scan(0, |start, &size| {
+            let bd = *start;
+            *start += size;
+            Some(bd)
+        })
+        .collect()
+}
+
+/// Prediction blocks of a coding block, as (x, y, width, height).
+fn prediction_blocks(part_mode: PartMode, x: i32, y: i32, size: i32) -> Vec<Block> {
+    let half = size / 2;
+    let quarter = size / 4;
+    let block = |dx: i32, dy: i32, width: i32, height: i32| Block {
+        x: x + dx,
+        y: y + dy,
+        width,
+        height,
+    };
+    match part_mode {
+        PartMode::Part2Nx2N => vec![block(0, 0, size, size)],
+        PartMode::Part2NxN => vec![block(0, 0, size, half), block(0, half, size, half)],
+        PartMode::PartNx2N => vec![block(0, 0, half, size), block(half, 0, half, size)],
+        PartMode::Part2NxnU => vec![
+            block(0, 0, size, quarter),
+            block(0, quarter, size, size - quarter),
+        ],
+        PartMode::Part2NxnD => vec![
+            block(0, 0, size, size - quarter),
+            block(0, size - quarter, size, quarter),
+        ],
+        PartMode::PartnLx2N => vec![
+            block(0, 0, quarter, size),
+            block(quarter, 0, size - quarter, size),
+        ],
+        PartMode::PartnRx2N => vec![
+            block(0, 0, size - quarter, size),
+            block(size - quarter, 0, quarter, size),
+        ],
+        PartMode::NxN => vec![
+            block(0, 0, half, half),
+            block(half, 0, half, half),
+            block(0, half, half, half),
+            block(half, half, half, half),
+        ],
+    }
+}
+
+fn motion_vector(motion: &PuMotion, list: usize) -> Option<MotionVector> {
+    motion.pred_flag[list].then(|| MotionVector::new(motion.mv[list][0], motion.mv[list][1]))
+}
+
+fn ref_idx(motion: &PuMotion, list: usize) -> Option<i8> {
+    motion.pred_flag[list].then_some(motion.ref_idx[list])
+}
+
+/// PCM sample layout (Section 7.4.3.2.1).
+struct PcmLayout {
+    log2_min_size: u8,
+    log2_max_size: u8,
+    luma_bits: usize,
+    chroma_bits: usize,
+}
+
+/// Coding unit currently being parsed.
+struct CuState {
+    x: u32,
+    y: u32,
+    log2_size: u8,
+    intra: bool,
+    part_mode: PartMode,
+    transquant_bypass: bool,
+    max_trafo_depth: u8,
+    chroma_mode: u8,
+}
+
+/// Slice segment data parser.
+pub(super) struct SliceParser<'a> {
+    sps: &'a Sps,
+    pps: &'a Pps,
+    header: &'a SliceHeader,
+    layout: &'a TileLayout,
+    cabac: CabacReader<'a>,
+    /// Bit positions of the substreams after the first.
+    substreams: &'a [usize],
+    /// Bit position of the rbsp_stop_one_bit.
+    rbsp_end: usize,
+    prediction: PredictionContext<'a>,
+
+    width: u32,
+    height: u32,
+    ctb_log2: u8,
+    min_cb_log2: u8,
+    min_tb_log2: u8,
+    max_tb_log2: u8,
+    chroma: bool,
+    pcm: Option<PcmLayout>,
+    log2_min_cu_qp_delta_size: u8,
+    qp_bd_offset: i32,
+    slice_qp: i32,
+
+    blocks: Vec<BlockInfo>,
+    blocks_stride: usize,
+    ctb_decoded: Vec<bool>,
+    wpp_contexts: Option<Vec<Context>>,
+
+    is_cu_qp_delta_coded: bool,
+    cu_qp_delta_val: i32,
+    quant_group: Option<(u32, u32)>,
+    first_quant_group: bool,
+    qp_y_pred: i32,
+    qp_y_prev: i32,
+
+    cu: CuState,
+    coding_units: Vec<CodingUnit>,
+}
+
+impl<'a> SliceParser<'a> {
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn new(
+        sps: &'a Sps,
+        pps: &'a Pps,
+        header: &'a SliceHeader,
+        layout: &'a TileLayout,
+        data: &'a [u8],
+        data_offset: usize,
+        substreams: &'a [usize],
+        prediction: PredictionContext<'a>,
+    ) -> Result<Self> {
+        let init_type = match header.slice_type {
+            SliceType::I => 0,
+            SliceType::P => 1 + header.cabac_init_flag as usize,
+            SliceType::B => 2 - header.cabac_init_flag as usize,
+        };
+        let slice_qp = header.qp(pps) as i32;
+        let qp_bd_offset = 6 * sps.bit_depth_luma_minus8 as i32;
+        if !(-qp_bd_offset..=51).contains(&slice_qp) {
+            return Err(HevcError::InvalidData(format!(
+                "SliceQpY {} out of range",
+                slice_qp
+            )));
+        }
+        let cabac = CabacReader::new(data, data_offset, init_type, slice_qp)?;
+        let rbsp_end = data
+            .iter()
+            .rposition(|&b| b != 0)
+            .map(|i| i * 8 + 7 - data[i].trailing_zeros() as usize)
+            .unwrap_or(0);
+
+        let ctb_log2 = sps.log2_min_luma_coding_block_size_minus3
+            + 3
+            + sps.log2_diff_max_min_luma_coding_block_size;
+        let min_tb_log2 = sps.log2_min_luma_transform_block_size_minus2 + 2;
+        let pcm = sps
+            .pcm_parameters
+            .filter(|_| sps.pcm_enabled_flag)
+            .map(|pcm| PcmLayout {
+                log2_min_size: pcm.log2_min_pcm_luma_coding_block_size_minus3 + 3,
+                log2_max_size: pcm.log2_min_pcm_luma_coding_block_size_minus3
+                    + 3
+                    + pcm.log2_diff_max_min_pcm_luma_coding_block_size,
+                luma_bits: pcm.pcm_sample_bit_depth_luma_minus1 as usize + 1,
+                chroma_bits: pcm.pcm_sample_bit_depth_chroma_minus1 as usize + 1,
+            });
+        let log2_min_cu_qp_delta_size = if pps.cu_qp_delta_enabled_flag {
+            ctb_log2.saturating_sub(pps.diff_cu_qp_delta_depth)
+        } else {
+            ctb_log2
+        };
+
+        let width = sps.pic_width_in_luma_samples;
+        let height = sps.pic_height_in_luma_samples;
+        let blocks_stride = width.div_ceil(4) as usize;
+        let blocks = vec![BlockInfo::default(); blocks_stride * height.div_ceil(4) as usize];
+
+        Ok(Self {
+            sps,
+            pps,
+            header,
+            layout,
+            cabac,
+            substreams,
+            rbsp_end,
+            prediction,
+            width,
+            height,
+            ctb_log2,
+            min_cb_log2: sps.log2_min_luma_coding_block_size_minus3 + 3,
+            min_tb_log2,
+            max_tb_log2: min_tb_log2 + sps.log2_diff_max_min_luma_transform_block_size,
+            chroma: sps.chroma_format_idc != ChromaFormat::Monochrome,
+            pcm,
+            log2_min_cu_qp_delta_size,
+            qp_bd_offset,
+            slice_qp,
+            blocks,
+            blocks_stride,
+            ctb_decoded: vec![false; layout.pic_size_in_ctbs()],
+            wpp_contexts: None,
+            is_cu_qp_delta_coded: false,
+            cu_qp_delta_val: 0,
+            quant_group: None,
+            first_quant_group: true,
+            qp_y_pred: slice_qp,
+            qp_y_prev: slice_qp,
+            cu: CuState {
+                x: 0,
+                y: 0,
+                log2_size: 0,
+                intra: false,
+                part_mode: PartMode::Part2Nx2N,
+                transquant_bypass: false,
+                max_trafo_depth: 0,
+                chroma_mode: 0,
+            },
+            coding_units: Vec::new(),
+        })
+    }
+
+    /// Parse `slice_segment_data()` (7.3.8.1).
+    pub(super) fn parse(mut self) -> Result<Vec<CodingTreeUnit>> {
+        let width_ctbs = self.layout.width_ctbs;
+        let pic_size = self.layout.pic_size_in_ctbs();
+        let start_rs = self.header.slice_segment_address as usize;
+        if start_rs >= pic_size {
+            return Err(HevcError::InvalidData(format!(
+                "slice_segment_address {} outside the picture",
+                start_rs
+            )));
+        }
+
+        let mut ctus = Vec::new();
+        let mut ts = self.layout.rs_to_ts[start_rs];
+        let mut substream = 0;
+        loop {
+            let rs = self.layout.ts_to_rs[ts];
+            if rs != start_rs {
+                self.start_ctu(ts, rs);
+            }
+            ctus.push(self.coding_tree_unit(rs)?);
+            self.ctb_decoded[rs] = true;
+            if self.pps.entropy_coding_sync_enabled_flag
+                && (rs % width_ctbs == 1
+                    || (rs > 1 && self.layout.tile_id[rs] != self.layout.tile_id[rs - 2]))
+            {
+                self.wpp_contexts = Some(self.cabac.contexts());
+            }
+            if self.cabac.overrun() {
+                return Err(HevcError::InvalidData(
+                    "slice data overruns the RBSP".to_string(),
+                ));
+            }
+
+            if self.cabac.end_of_slice_segment_flag() {
+                break;
+            }
+            ts += 1;
+            if ts >= pic_size {
+                return Err(HevcError::InvalidData(
+                    "slice data continues past the last CTB".to_string(),
+                ));
+            }
+            let next_rs = self.layout.ts_to_rs[ts];
+            let tile_start = self.layout.tile_id[next_rs] != self.layout.tile_id[rs];
+            let row_start = next_rs.is_multiple_of(width_ctbs)
+                || self.layout.tile_id[next_rs] != self.layout.tile_id[next_rs - 1];
+            if (self.pps.tiles_enabled_flag && tile_start)
+                || (self.pps.entropy_coding_sync_enabled_flag && row_start)
+            {
+                if !self.cabac.end_of_subset_one_bit() {
+                    return Err(HevcError::InvalidData(
+                        "end_of_subset_one_bit is not 1".to_string(),
+                    ));
+                }
+                let aligned = self.cabac.position().div_ceil(8) * 8;
+                let pos = self.substreams.get(substream).copied().unwrap_or(aligned);
+                substream += 1;
+                self.cabac.restart(pos)?;
+            }
+        }
+
+        // The arithmetic decoder reads ahead of the terminating bin by up to
+        // 7 bits, and the final bit it consumes is the rbsp_stop_one_bit.
+        let end = self.cabac.position();
+        if end <= self.rbsp_end || end > self.rbsp_end + 8 {
+            return Err(HevcError::InvalidData(format!(
+                "slice data ends at bit {} but the RBSP stop bit is at {}",
+                end, self.rbsp_end
+            )));
+        }
+
+        Ok(ctus)
+    }
+
+    /// Context and QP predictor handling at the start of a CTU other than
+    /// the first of the slice segment (9.3.1).
+    fn start_ctu(&mut self, ts: usize, rs: usize) {
+        let width_ctbs = self.layout.width_ctbs;
+        let tile = self.layout.tile_id[rs];
+        if self.layout.tile_id[self.layout.ts_to_rs[ts - 1]] != tile {
+            self.cabac.init_contexts();
+            self.first_quant_group = true;
+        } else if self.pps.entropy_coding_sync_enabled_flag
+            && (rs.is_multiple_of(width_ctbs) || self.layout.tile_id[rs - 1] != tile)
+        {
+            // Synchronize with the CTB above and to the right
+            let ctb_size = 1u32 << self.ctb_log2;
+            let x0 = (rs % width_ctbs) as u32 * ctb_size;
+            let top_right_available = rs >= width_ctbs
+                && x0 + ctb_size < self.width
+                && self.ctb_decoded[rs + 1 - width_ctbs]
+                && self.layout.tile_id[rs + 1 - width_ctbs] == tile;
+            match self.wpp_contexts.as_ref() {
+                Some(contexts) if top_right_available => self.cabac.set_contexts(contexts),
+                _ => self.cabac.init_contexts(),
+            }
+            self.first_quant_group = true;
+        }
+    }
+
+    /// Parse `coding_tree_unit()` (7.3.8.2).
+    fn coding_tree_unit(&mut self, rs: usize) -> Result<CodingTreeUnit> {
+        let width_ctbs = self.layout.width_ctbs;
+        let rx = rs % width_ctbs;
+        let ry = rs / width_ctbs;
+        let x0 = (rx as u32) << self.ctb_log2;
+        let y0 = (ry as u32) << self.ctb_log2;
+
+        if self.header.slice_sao_luma_flag || self.header.slice_sao_chroma_flag {
+            self.sao(rx, ry, rs);
+        }
+
+        self.coding_quadtree(x0, y0, self.ctb_log2, 0)?;
+
+        let mut ctu = CodingTreeUnit::new(x0, y0, 1 << self.ctb_log2);
+        ctu.coding_units = std::mem::take(&mut self.coding_units);
+        Ok(ctu)
+    }
+
+    /// Parse `sao()` (7.3.8.3). The offsets are not needed and discarded.
+    fn sao(&mut self, rx: usize, ry: usize, rs: usize) {
+        let width_ctbs = self.layout.width_ctbs;
+        let slice_addr = self.header.slice_segment_address as usize;
+        let tile = self.layout.tile_id[rs];
+
+        if rx > 0
+            && rs > slice_addr
+            && self.layout.tile_id[rs - 1] == tile
+            && self.cabac.sao_merge_flag()
+        {
+            return;
+        }
+        if ry > 0
+            && rs - width_ctbs >= slice_addr
+            && self.layout.tile_id[rs - width_ctbs] == tile
+            && self.cabac.sao_merge_flag()
+        {
+            return;
+        }
+
+        let num_components = if self.chroma { 3 } else { 1 };
+        let mut chroma_type = 0;
+        for c_idx in 0..num_components {
+            let enabled = if c_idx == 0 {
+                self.header.slice_sao_luma_flag
+            } else {
+                self.header.slice_sao_chroma_flag
+            };
+            if !enabled {
+                continue;
+            }
+            let sao_type = match c_idx {
+                0 => self.cabac.sao_type_idx(),
+                1 => {
+                    chroma_type = self.cabac.sao_type_idx();
+                    chroma_type
+                }
+                _ => chroma_type,
+            };
+            if sao_type == 0 {
+                continue;
+            }
+
+            let bit_depth = if c_idx == 0 {
+                self.sps.bit_depth_luma()
+            } else {
+                self.sps.bit_depth_chroma()
+            };
+            let c_max = (1u32 << (bit_depth.min(10) - 5)) - 1;
+            let offsets = [0; 4].map(|_| self.cabac.sao_offset_abs(c_max));
+            if sao_type == 1 {
+                for offset in offsets {
+                    if offset != 0 {
+                        self.cabac.sao_offset_sign();
+                    }
+                }
+                self.cabac.sao_band_position();
+            } else if c_idx < 2 {
+                self.cabac.sao_eo_class();
+            }
+        }
+    }
+
+    fn block_index(&self, x: u32, y: u32) -> usize {
+        (y >> 2) as usize * self.blocks_stride + (x >> 2) as usize
+    }
+
+    fn tile_at(&self, x: u32, y: u32) -> usize {
+        let rs =
+            (y >> self.ctb_log2) as usize * self.layout.width_ctbs + (x >> self.ctb_log2) as usize;
+        self.layout.tile_id[rs]
+    }
+
+    /// Availability of the block at (`x`, `y`) for the block at
+    /// (`x_curr`, `y_curr`) in z-scan order (6.4.1): decoded earlier in this
+    /// slice and in the same tile.
+    fn neighbour(&self, x_curr: u32, y_curr: u32, x: i32, y: i32) -> Option<&BlockInfo> {
+        if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+            return None;
+        }
+        let (x, y) = (x as u32, y as u32);
+        let block = &self.blocks[self.block_index(x, y)];
+        (block.decoded && self.tile_at(x, y) == self.tile_at(x_curr, y_curr)).then_some(block)
+    }
+
+    /// Motion of a neighbouring prediction block, `None` if unavailable or
+    /// intra coded (6.4.2).
+    fn neighbour_motion(&self, x_curr: u32, y_curr: u32, x: i32, y: i32) -> Option<PuMotion> {
+        self.neighbour(x_curr, y_curr, x, y)
+            .filter(|block| !block.intra)
+            .map(|block| block.motion)
+    }
+
+    fn update_blocks(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        f: impl Fn(&mut BlockInfo),
+    ) {
+        for by in (y..y + height).step_by(4) {
+            for bx in (x..x + width).step_by(4) {
+                let idx = self.block_index(bx, by);
+                f(&mut self.blocks[idx]);
+            }
+        }
+    }
+
+    /// Parse `coding_quadtree()` (7.3.8.4).
+    fn coding_quadtree(&mut self, x0: u32, y0: u32, log2_size: u8, depth: u8) -> Result<()> {
+        let size = 1u32 << log2_size;
+        let split = if x0 + size <= self.width
+            && y0 + size <= self.height
+            && log2_size > self.min_cb_log2
+        {
+            let deeper = |x: i32, y: i32| {
+                self.neighbour(x0, y0, x, y)
+                    .is_some_and(|block| block.ct_depth > depth) as usize
+            };
+            let inc = deeper(x0 as i32 - 1, y0 as i32) + deeper(x0 as i32, y0 as i32 - 1);
+            self.cabac.split_cu_flag(inc)
+        } else {
+            log2_size > self.min_cb_log2
+        };
+
+        if self.pps.cu_qp_delta_enabled_flag && log2_size >= self.log2_min_cu_qp_delta_size {
+            self.is_cu_qp_delta_coded = false;
+            self.cu_qp_delta_val = 0;
+        }
+
+        if split {
+            let half = size / 2;
+            for (dx, dy) in [(0, 0), (half, 0), (0, half), (half, half)] {
+                if x0 + dx < self.width && y0 + dy < self.height {
+                    self.coding_quadtree(x0 + dx, y0 + dy, log2_size - 1, depth + 1)?;
+                }
+            }
+            Ok(())
+        } else {
+            self.coding_unit(x0, y0, log2_size, depth)
+        }
+    }
+
+    /// Derive qPY_PRED at the first coding unit of a quantization group
+    /// (8.6.1).
+    fn start_quant_group(&mut self, x_cb: u32, y_cb: u32) {
+        let mask = !((1u32 << self.log2_min_cu_qp_delta_size) - 1);
+        let (x_qg, y_qg) = (x_cb & mask, y_cb & mask);
+        if self.quant_group == Some((x_qg, y_qg)) {
+            return;
+        }
+        self.quant_group = Some((x_qg, y_qg));
+
+        let qp_prev = if self.first_quant_group {
+            self.first_quant_group = false;
+            self.slice_qp
+        } else {
+            self.qp_y_prev
+        };
+        let ctb = (x_qg >> self.ctb_log2, y_qg >> self.ctb_log2);
+        let neighbour_qp = |x: i32, y: i32| {
+            let same_ctb = x >= 0
+                && y >= 0
+                && ((x as u32) >> self.ctb_log2, (y as u32) >> self.ctb_log2) == ctb;
+            match self.neighbour(x_qg, y_qg, x, y) {
+                Some(block) if same_ctb => block.qp as i32,
+                _ => qp_prev,
+            }
+        };
+        let qp_a = neighbour_qp(x_qg as i32 - 1, y_qg as i32);
+        let qp_b = neighbour_qp(x_qg as i32, y_qg as i32 - 1);
+        self.qp_y_pred = (qp_a + qp_b + 1) >> 1;
+    }
+
+    /// Parse `coding_unit()` (7.3.8.5).
+    fn coding_unit(&mut self, x0: u32, y0: u32, log2_size: u8, depth: u8) -> Result<()> {
+        let size = 1u32 << log2_size;
+        self.start_quant_group(x0, y0);
+
+        let transquant_bypass =
+            self.pps.transquant_bypass_enabled_flag && self.cabac.cu_transquant_bypass_flag();
+        let skip = self.header.slice_type != SliceType::I && {
+            let skipped = |x: i32, y: i32| {
+                self.neighbour(x0, y0, x, y).is_some_and(|block| block.skip) as usize
+            };
+            let inc = skipped(x0 as i32 - 1, y0 as i32) + skipped(x0 as i32, y0 as i32 - 1);
+            self.cabac.cu_skip_flag(inc)
+        };
+
+        self.cu = CuState {
+            x: x0,
+            y: y0,
+            log2_size,
+            intra: false,
+            part_mode: PartMode::Part2Nx2N,
+            transquant_bypass,
+            max_trafo_depth: 0,
+            chroma_mode: 0,
+        };
+        let mut cu = CodingUnit {
+            x: x0,
+            y: y0,
+            size: size as u8,
+            pred_mode: PredMode::Skip,
+            part_mode: PartMode::Part2Nx2N,
+            intra_mode: None,
+            qp: 0,
+            mv_l0: None,
+            mv_l1: None,
+            ref_idx_l0: None,
+            ref_idx_l1: None,
+            transform_size: size as u8,
+            depth,
+            intra_modes: Vec::new(),
+            intra_chroma_mode: None,
+            transquant_bypass,
+            pcm: false,
+            prediction_units: Vec::new(),
+            transform_units: Vec::new(),
+        };
+
+        if skip {
+            let pb = Block {
+                x: x0 as i32,
+                y: y0 as i32,
+                width: size as i32,
+                height: size as i32,
+            };
+            cu.prediction_units
+                .push(self.prediction_unit(pb, 0, true, depth)?);
+        } else {
+            let intra = self.header.slice_type == SliceType::I || self.cabac.pred_mode_flag();
+            let min_cb = log2_size == self.min_cb_log2;
+            let part_mode = if !intra || min_cb {
+                self.cabac
+                    .part_mode(intra, min_cb, log2_size, self.sps.amp_enabled_flag)
+            } else {
+                PartMode::Part2Nx2N
+            };
+            if part_mode == PartMode::NxN && intra && log2_size == 3 && self.min_tb_log2 > 2 {
+                return Err(HevcError::InvalidData(
+                    "intra NxN partitioning below the minimum transform size".to_string(),
+                ));
+            }
+            self.cu.intra = intra;
+            self.cu.part_mode = part_mode;
+            cu.part_mode = part_mode;
+
+            let mut merged = false;
+            if intra {
+                cu.pred_mode = PredMode::Intra;
+                cu.pcm = part_mode == PartMode::Part2Nx2N
+                    && self.pcm.as_ref().is_some_and(|pcm| {
+                        (pcm.log2_min_size..=pcm.log2_max_size).contains(&log2_size)
+                    })
+                    && self.cabac.pcm_flag();
+                if cu.pcm {
+                    self.pcm_sample(log2_size)?;
+                    self.update_blocks(x0, y0, size, size, |block| {
+                        *block = BlockInfo {
+                            decoded: true,
+                            intra: true,
+                            pcm: true,
+                            intra_mode: INTRA_DC,
+                            ..*block
+                        }
+                    });
+                } else {
+                    self.intra_prediction_modes(&mut cu)?;
+                }
+            } else {
+                cu.pred_mode = PredMode::Inter;
+                let blocks = prediction_blocks(part_mode, x0 as i32, y0 as i32, size as i32);
+                for (part_idx, pb) in blocks.into_iter().enumerate() {
+                    let pu = self.prediction_unit(pb, part_idx, false, depth)?;
+                    merged = pu.merge_flag;
+                    cu.prediction_units.push(pu);
+                }
+            }
+
+            if !cu.pcm {
+                let rqt_root_cbf = intra
+                    || (part_mode == PartMode::Part2Nx2N && merged)
+                    || self.cabac.rqt_root_cbf();
+                if rqt_root_cbf {
+                    self.cu.max_trafo_depth = if intra {
+                        self.sps.max_transform_hierarchy_depth_intra
+                            + (part_mode == PartMode::NxN) as u8
+                    } else {
+                        self.sps.max_transform_hierarchy_depth_inter
+                    };
+                    self.transform_tree(&mut cu, x0, y0, x0, y0, log2_size, 0, 0, [false; 2])?;
+                }
+            }
+        }
+
+        if self.cabac.overrun() {
+            return Err(HevcError::InvalidData(
+                "slice data overruns the RBSP".to_string(),
+            ));
+        }
+
+        // QpY (8-283)
+        let qp_range = 52 + self.qp_bd_offset;
+        let qp_y = (self.qp_y_pred + self.cu_qp_delta_val + 52 + 2 * self.qp_bd_offset) % qp_range
+            - self.qp_bd_offset;
+        self.qp_y_prev = qp_y;
+        cu.qp = qp_y as i16;
+        self.update_blocks(x0, y0, size, size, |block| {
+            block.decoded = true;
+            block.skip = skip;
+            block.ct_depth = depth;
+            block.qp = qp_y as i8;
+        });
+
+        if let Some(pu) = cu.prediction_units.first() {
+            cu.mv_l0 = pu.mv_l0;
+            cu.mv_l1 = pu.mv_l1;
+            cu.ref_idx_l0 = pu.ref_idx_l0;
+            cu.ref_idx_l1 = pu.ref_idx_l1;
+        }
+        cu.intra_mode = cu.intra_modes.first().copied();
+        if let Some(smallest) = cu.transform_units.iter().map(|tu| tu.size).min() {
+            cu.transform_size = smallest;
+        }
+        self.coding_units.push(cu);
+        Ok(())
+    }
+
+    /// Skip `pcm_sample()` and restart the arithmetic decoder after it
+    /// (9.3.2.5).
+    fn pcm_sample(&mut self, log2_size: u8) -> Result<()> {
+        let Some(pcm) = self.pcm.as_ref() else {
+            return Err(HevcError::InvalidData("PCM without parameters".to_string()));
+        };
+        let samples = 1usize << (2 * log2_size);
+        let mut bits = samples * pcm.luma_bits;
+        if self.chroma {
+            bits += 2 * (samples / 4) * pcm.chroma_bits;
+        }
+        let pos = self.cabac.position().div_ceil(8) * 8 + bits;
+        if pos > self.rbsp_end {
+            return Err(HevcError::InvalidData(
+                "pcm_sample() overruns the RBSP".to_string(),
+            ));
+        }
+        self.cabac.restart(pos)
+    }
+
+    /// Parse the intra prediction modes of a coding unit and derive
+    /// IntraPredModeY and IntraPredModeC (8.4.2 and 8.4.3).
+    fn intra_prediction_modes(&mut self, cu: &mut CodingUnit) -> Result<()> {
+        let (x0, y0) = (self.cu.x, self.cu.y);
+        let size = 1u32 << self.cu.log2_size;
+        let (num_parts, pb_size) = if self.cu.part_mode == PartMode::NxN {
+            (4, size / 2)
+        } else {
+            (1, size)
+        };
+
+        let prev_flags: Vec<bool> = (0..num_parts)
+            .map(|_| self.cabac.prev_intra_luma_pred_flag())
+            .collect();
+        let coded: Vec<(bool, u8)> = prev_flags
+            .iter()
+            .map(|&prev| {
+                if prev {
+                    (true, self.cabac.mpm_idx() as u8)
+                } else {
+                    (false, self.cabac.rem_intra_luma_pred_mode())
+                }
+            })
+            .collect();
+        let chroma_pred_mode = if self.chroma {
+            Some(self.cabac.intra_chroma_pred_mode())
+        } else {
+            None
+        };
+
+        let mut luma_modes = Vec::with_capacity(num_parts);
+        for (part_idx, &(prev, value)) in coded.iter().enumerate() {
+            let x_pb = x0 + (part_idx as u32 % 2) * pb_size;
+            let y_pb = y0 + (part_idx as u32 / 2) * pb_size;
+            let mode = self.luma_intra_mode(x_pb, y_pb, prev, value);
+            self.update_blocks(x_pb, y_pb, pb_size, pb_size, |block| {
+                *block = BlockInfo {
+                    decoded: true,
+                    intra: true,
+                    pcm: false,
+                    intra_mode: mode,
+                    ..*block
+                }
+            });
+            luma_modes.push(mode);
+        }
+
+        if let Some(chroma_pred_mode) = chroma_pred_mode {
+            // Table 8-2 (4:2:0)
+            let luma = luma_modes[0];
+            let chroma = match chroma_pred_mode {
+                0 => INTRA_PLANAR,
+                1 => INTRA_VERTICAL,
+                2 => 10,
+                3 => INTRA_DC,
+                _ => luma,
+            };
+            let chroma = if chroma_pred_mode < 4 && chroma == luma {
+                34
+            } else {
+                chroma
+            };
+            self.cu.chroma_mode = chroma;
+            cu.intra_chroma_mode = Some(IntraMode::from_mode(chroma));
+        }
+        cu.intra_modes = luma_modes.into_iter().map(IntraMode::from_mode).collect();
+        Ok(())
+    }
+
+    /// Derive IntraPredModeY of the prediction block at (`x_pb`, `y_pb`)
+    /// (8.4.2).
+    fn luma_intra_mode(&self, x_pb: u32, y_pb: u32, prev: bool, value: u8) -> u8 {
+        let candidate = |x: i32, y: i32, above: bool| -> u8 {
+            match self.neighbour(x_pb, y_pb, x, y) {
+                Some(block) if block.intra && !block.pcm => {
+                    // Candidate B is not taken from the CTB row above
+                    if above
+                        && (y_pb as i32 - 1) < ((y_pb >> self.ctb_log2) << self.ctb_log2) as i32
+                    {
+                        INTRA_DC
+                    } else {
+                        block.intra_mode
+                    }
+                }
+                _ => INTRA_DC,
+            }
+        };
+        let a = candidate(x_pb as i32 - 1, y_pb as i32, false);
+        let b = candidate(x_pb as i32, y_pb as i32 - 1, true);
+
+        let mut mpm = if a == b {
+            if a < 2 {
+                [INTRA_PLANAR, INTRA_DC, INTRA_VERTICAL]
+            } else {
+                [a, 2 + ((a + 29) % 32), 2 + ((a - 2 + 1) % 32)]
+            }
+        } else {
+            let third = if a != INTRA_PLANAR && b != INTRA_PLANAR {
+                INTRA_PLANAR
+            } else if a != INTRA_DC && b != INTRA_DC {
+                INTRA_DC
+            } else {
+                INTRA_VERTICAL
+            };
+            [a, b, third]
+        };
+
+        if prev {
+            return mpm[value as usize];
+        }
+        mpm.sort_unstable();
+        let mut mode = value;
+        for candidate in mpm {
+            if mode >= candidate {
+                mode += 1;
+            }
+        }
+        mode
+    }
+
+    /// Parse `prediction_unit()` (7.3.8.6) and derive its motion.
+    fn prediction_unit(
+        &mut self,
+        pb: Block,
+        part_idx: usize,
+        skip: bool,
+        ct_depth: u8,
+    ) -> Result<PredictionUnit> {
+        let (x_cb, y_cb) = (self.cu.x, self.cu.y);
+        let cb_size = 1i32 << self.cu.log2_size;
+        let cb = Block {
+            x: x_cb as i32,
+            y: y_cb as i32,
+            width: cb_size,
+            height: cb_size,
+        };
+        let merge_flag = skip || self.cabac.merge_flag();
+
+        let motion = if merge_flag {
+            let merge_idx = self.cabac.merge_idx(self.prediction.max_num_merge_cand);
+            self.prediction.merge_motion(
+                |x, y| self.neighbour_motion(x_cb, y_cb, x, y),
+                cb,
+                pb,
+                self.cu.part_mode,
+                part_idx,
+                merge_idx,
+            )
+        } else {
+            let inter_pred_idc = if self.header.slice_type == SliceType::B {
+                self.cabac
+                    .inter_pred_idc(pb.width as u32, pb.height as u32, ct_depth)
+            } else {
+                InterPredIdc::L0
+            };
+            let mut coded = [None; 2];
+            for (list, coded) in coded.iter_mut().enumerate() {
+                let used = match inter_pred_idc {
+                    InterPredIdc::L0 => list == 0,
+                    InterPredIdc::L1 => list == 1,
+                    InterPredIdc::Bi => true,
+                };
+                if !used {
+                    continue;
+                }
+                let num_active = self.prediction.num_ref_idx_active[list] as u32;
+                let ref_idx = if num_active > 1 {
+                    self.cabac.ref_idx(num_active)
+                } else {
+                    0
+                };
+                let mvd = if list == 1
+                    && self.header.mvd_l1_zero_flag
+                    && inter_pred_idc == InterPredIdc::Bi
+                {
+                    [0, 0]
+                } else {
+                    self.cabac.mvd_coding()?
+                };
+                let mvp_flag = self.cabac.mvp_flag();
+                *coded = Some((ref_idx as i8, mvd, mvp_flag));
+            }
+
+            let mut motion = PuMotion {
+                pred_flag: [false; 2],
+                ref_idx: [-1; 2],
+                mv: [[0, 0]; 2],
+            };
+            for (list, coded) in coded.into_iter().enumerate() {
+                if let Some((ref_idx, mvd, mvp_flag)) = coded {
+                    let mvp = self.prediction.mvp(
+                        |x, y| self.neighbour_motion(x_cb, y_cb, x, y),
+                        pb,
+                        list,
+                        ref_idx,
+                        mvp_flag,
+                    );
+                    motion.pred_flag[list] = true;
+                    motion.ref_idx[list] = ref_idx;
+                    motion.mv[list] = add_mvd(mvp, mvd);
+                }
+            }
+            motion
+        };
+
+        self.update_blocks(
+            pb.x as u32,
+            pb.y as u32,
+            pb.width as u32,
+            pb.height as u32,
+            |block| {
+                *block = BlockInfo {
+                    decoded: true,
+                    intra: false,
+                    pcm: false,
+                    motion,
+                    ..*block
+                }
+            },
+        );
+
+        Ok(PredictionUnit {
+            x: pb.x as u32,
+            y: pb.y as u32,
+            width: pb.width as u32,
+            height: pb.height as u32,
+            merge_flag,
+            mv_l0: motion_vector(&motion, 0),
+            mv_l1: motion_vector(&motion, 1),
+            ref_idx_l0: ref_idx(&motion, 0),
+            ref_idx_l1: ref_idx(&motion, 1),
+        })
+    }
+
+    /// Parse `transform_tree()` (7.3.8.8).
+    #[allow(clippy::too_many_arguments)]
+    fn transform_tree(
+        &mut self,
+        cu: &mut CodingUnit,
+        x0: u32,
+        y0: u32,
+        x_base: u32,
+        y_base: u32,
+        log2_size: u8,
+        depth: u8,
+        blk_idx: usize,
+        parent_cbf: [bool; 2],
+    ) -> Result<()> {
+        let intra_split = self.cu.intra && self.cu.part_mode == PartMode::NxN;
+        let split = if log2_size <= self.max_tb_log2
+            && log2_size > self.min_tb_log2
+            && depth < self.cu.max_trafo_depth
+            && !(intra_split && depth == 0)
+        {
+            self.cabac.split_transform_flag(log2_size)
+        } else {
+            let inter_split = self.sps.max_transform_hierarchy_depth_inter == 0
+                && !self.cu.intra
+                && self.cu.part_mode != PartMode::Part2Nx2N
+                && depth == 0;
+            log2_size > self.max_tb_log2 || (intra_split && depth == 0) || inter_split
+        };
+
+        // cbf_cb and cbf_cr of 4x4 luma blocks are inherited from the parent
+        let mut cbf_chroma = parent_cbf;
+        if log2_size > 2 && self.chroma {
+            for (c, cbf) in cbf_chroma.iter_mut().enumerate() {
+                *cbf = (depth == 0 || parent_cbf[c]) && self.cabac.cbf_chroma(depth);
+            }
+        }
+
+        if split {
+            let half = 1u32 << (log2_size - 1);
+            for (i, (dx, dy)) in [(0, 0), (half, 0), (0, half), (half, half)]
+                .into_iter()
+                .enumerate()
+            {
+                self.transform_tree(
+                    cu,
+                    x0 + dx,
+                    y0 + dy,
+                    x0,
+                    y0,
+                    log2_size - 1,
+                    depth + 1,
+                    i,
+                    cbf_chroma,
+                )?;
+            }
+            return Ok(());
+        }
+
+        let cbf_luma = if self.cu.intra || depth != 0 || cbf_chroma[0] || cbf_chroma[1] {
+            self.cabac.cbf_luma(depth)
+        } else {
+            true
+        };
+        self.transform_unit(
+            cu, x0, y0, x_base, y_base, log2_size, depth, blk_idx, cbf_luma, cbf_chroma,
+        )
+    }
+
+    /// Parse `transform_unit()` (7.3.8.10).
+    #[allow(clippy::too_many_arguments)]
+    fn transform_unit(
+        &mut self,
+        cu: &mut CodingUnit,
+        x0: u32,
+        y0: u32,
+        x_base: u32,
+        y_base: u32,
+        log2_size: u8,
+        depth: u8,
+        blk_idx: usize,
+        cbf_luma: bool,
+        cbf_chroma: [bool; 2],
+    ) -> Result<()> {
+        let chroma_coded = log2_size > 2 || blk_idx == 3;
+        let mut tu = TransformUnit {
+            x: x0,
+            y: y0,
+            size: 1 << log2_size,
+            depth,
+            cbf_luma,
+            cbf_cb: chroma_coded && cbf_chroma[0],
+            cbf_cr: chroma_coded && cbf_chroma[1],
+            coeff_count: 0,
+        };
+
+        if !cbf_luma && !cbf_chroma[0] && !cbf_chroma[1] {
+            cu.transform_units.push(tu);
+            return Ok(());
+        }
+
+        if self.pps.cu_qp_delta_enabled_flag && !self.is_cu_qp_delta_coded {
+            let delta = self.cabac.cu_qp_delta()?;
+            let limit = 26 + self.qp_bd_offset / 2;
+            if !(-limit..limit).contains(&delta) {
+                return Err(HevcError::InvalidData(format!(
+                    "CuQpDeltaVal {} out of range",
+                    delta
+                )));
+            }
+            self.is_cu_qp_delta_coded = true;
+            self.cu_qp_delta_val = delta;
+        }
+
+        if cbf_luma {
+            tu.coeff_count += self.residual(x0, y0, log2_size, 0)?;
+        }
+        if log2_size > 2 {
+            for c_idx in 1..=2 {
+                if cbf_chroma[c_idx - 1] {
+                    tu.coeff_count += self.residual(x0, y0, log2_size - 1, c_idx)?;
+                }
+            }
+        } else if blk_idx == 3 {
+            for c_idx in 1..=2 {
+                if cbf_chroma[c_idx - 1] {
+                    tu.coeff_count += self.residual(x_base, y_base, 2, c_idx)?;
+                }
+            }
+        }
+        cu.transform_units.push(tu);
+        Ok(())
+    }
+
+    /// Parse `residual_coding()` of a transform block whose luma location
+    /// is (`x0`, `y0`) and return its number of nonzero coefficients.
+    fn residual(&mut self, x0: u32, y0: u32, log2_size: u8, c_idx: usize) -> Result<u32> {
+        // scanIdx (7.4.9.11)
+        let scan_idx = if self.cu.intra && (log2_size == 2 || (log2_size == 3 && c_idx == 0)) {
+            let mode = if c_idx == 0 {
+                self.blocks[self.block_index(x0, y0)].intra_mode
+            } else {
+                self.cu.chroma_mode
+            };
+            match mode {
+                6..=14 => 2,
+                22..=30 => 1,
+                _ => 0,
+            }
+        } else {
+            0
+        };
+        self.cabac.residual_coding(ResidualParams {
+            log2_size,
+            c_idx,
+            scan_idx,
+            transform_skip_allowed: self.pps.transform_skip_enabled_flag
+                && !self.cu.transquant_bypass
+                && log2_size <= 2,
+            sign_hiding: self.pps.sign_data_hiding_enabled_flag && !self.cu.transquant_bypass,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pps::TileConfig;
+    use crate::sps::{Profile, ProfileTierLevel};
+
+    /// 64x64 CTBs and 8x8 minimum coding blocks.
+    fn test_sps(width: u32, height: u32) -> Sps {
+        Sps {
+            sps_video_parameter_set_id: 0,
+            sps_max_sub_layers_minus1: 0,
+            sps_temporal_id_nesting_flag: true,
+            profile_tier_level: ProfileTierLevel {
+                general_profile_space: 0,
+                general_tier_flag: false,
+                general_profile_idc: Profile::Main,
+                general_profile_compatibility_flags: 0,
+                general_progressive_source_flag: true,
+                general_interlaced_source_flag: false,
+                general_non_packed_constraint_flag: true,
+                general_frame_only_constraint_flag: true,
+                general_level_idc: 0, // Level unspecified
+            },
+            sps_seq_parameter_set_id: 0,
+            chroma_format_idc: ChromaFormat::Chroma420,
+            separate_colour_plane_flag: false,
+            pic_width_in_luma_samples: width,
+            pic_height_in_luma_samples: height,
+            conformance_window_flag: false,
+            conf_win_left_offset: 0,
+            conf_win_right_offset: 0,
+            conf_win_top_offset: 0,
+            conf_win_bottom_offset: 0,
+            bit_depth_luma_minus8: 0,
+            bit_depth_chroma_minus8: 0,
+            log2_max_pic_order_cnt_lsb_minus4: 0, // MaxPicOrderCntLsb = 16
+            sps_sub_layer_ordering_info_present_flag: false,
+            sps_max_dec_pic_buffering_minus1: vec![0],
+            sps_max_num_reorder_pics: vec![0],
+            sps_max_latency_increase_plus1: vec![0],
+            log2_min_luma_coding_block_size_minus3: 0,
+            log2_diff_max_min_luma_coding_block_size: 3,
+            log2_min_luma_transform_block_size_minus2: 0,
+            log2_diff_max_min_luma_transform_block_size: 0,
+            max_transform_hierarchy_depth_inter: 0,
+            max_transform_hierarchy_depth_intra: 0,
+            scaling_list_enabled_flag: false,
+            amp_enabled_flag: false,
+            sample_adaptive_offset_enabled_flag: false,
+            pcm_enabled_flag: false,
+            pcm_parameters: None,
+            num_short_term_ref_pic_sets: 0,
+            long_term_ref_pics_present_flag: false,
+            num_long_term_ref_pics_sps: 0,
+            st_ref_pic_sets: Vec::new(),
+            lt_ref_pic_poc_lsb_sps: Vec::new(),
+            used_by_curr_pic_lt_sps_flag: Vec::new(),
+            sps_temporal_mvp_enabled_flag: false,
+            strong_intra_smoothing_enabled_flag: false,
+            vui_parameters_present_flag: false,
+            vui_parameters: None,
+        }
+    }
+
+    #[test]
+    fn test_tile_layout_without_tiles_is_raster() {
+        let sps = test_sps(256, 128);
+        let layout = TileLayout::new(&sps, &Pps::default()).unwrap();
+        assert_eq!(layout.ts_to_rs, (0..8).collect::<Vec<_>>());
+        assert!(layout.tile_id.iter().all(|&id| id == 0));
+    }
+
+    #[test]
+    fn test_tile_layout_two_columns() {
+        // 4x2 CTBs split into two uniform columns
+        let sps = test_sps(256, 128);
+        let pps = Pps {
+            tiles_enabled_flag: true,
+            tile_config: Some(TileConfig {
+                num_tile_columns_minus1: 1,
+                uniform_spacing_flag: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let layout = TileLayout::new(&sps, &pps).unwrap();
+        assert_eq!(layout.ts_to_rs, vec![0, 1, 4, 5, 2, 3, 6, 7]);
+        assert_eq!(layout.tile_id, vec![0, 0, 1, 1, 0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn test_tile_sizes_explicit() {
+        assert_eq!(tile_sizes(10, 3, false, &[1, 4]).unwrap(), vec![2, 5, 3]);
+        assert!(tile_sizes(5, 3, false, &[1, 4]).is_err());
+        assert_eq!(tile_sizes(5, 2, true, &[]).unwrap(), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_prediction_blocks_amp() {
+        let blocks = prediction_blocks(PartMode::Part2NxnU, 16, 32, 32);
+        assert_eq!(
+            blocks[0],
+            Block {
+                x: 16,
+                y: 32,
+                width: 32,
+                height: 8
+            }
+        );
+        assert_eq!(
+            blocks[1],
+            Block {
+                x: 16,
+                y: 40,
+                width: 32,
+                height: 24
+            }
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! HEVC/H.265 slice segment data parsing.
+//!
+//! Parses `slice_segment_data()` and the coding tree unit syntax (ITU-T H.265
+//! Sections 7.3.8 and 9.3) with the CABAC decoder, recovering the coding
+//! quadtree, part modes, intra prediction modes, motion vectors, transform
+//! trees and QPs of every coding unit. Residual data and SAO parameters are
+//! parsed only to stay in sync with the bitstream and are discarded.
+//!
+//! Slices using wavefront parallel processing and tiles are parsed substream
+//! by substream, starting each one at its `entry_point_offset_minus1`
+//! position.
+//!
+//! Limitations:
+//!
+//! - Dependent slice segments, 4:2:2 and 4:4:4 chroma, separate colour planes
+//!   and the PPS range and screen content coding extensions are rejected as
+//!   unsupported. SPS range extension tools are not parsed, so streams that
+//!   enable them fail to parse.
+//! - Temporal (co-located) merge and AMVP candidates need the motion field of
+//!   a reference picture and are treated as unavailable, so motion vectors of
+//!   slices with `slice_temporal_mvp_enabled_flag` may deviate from the
+//!   decoded ones.
+
+mod cabac;
+mod coding_tree;
+mod mv_prediction;
+
+use crate::dpb::RefPicLists;
+use crate::error::{HevcError, Result};
+use crate::nal::NalUnit;
+use crate::overlay_extraction::CodingTreeUnit;
+use crate::pps::Pps;
+use crate::slice::{parse_slice_header_with_data_offset, SliceHeader, SliceType};
+use crate::sps::Sps;
+use bitvue_core::ChromaFormat;
+use std::collections::HashMap;
+
+/// Maximum luma picture size (MaxLumaPs of level 6.2).
+const MAX_LUMA_PICTURE_SIZE: u64 = 35_651_584;
+
+/// Coding tree units decoded from one slice segment.
+#[derive(Debug, Clone)]
+pub struct SliceCtus {
+    /// Header of the slice segment.
+    pub header: SliceHeader,
+    /// Coding tree units in decoding (tile scan) order.
+    pub ctus: Vec<CodingTreeUnit>,
+}
+
+/// Parse the coding tree units of a slice segment NAL unit.
+///
+/// `sps` and `pps` must be the parameter sets the slice refers to. `poc` and
+/// `ref_pic_lists` are the picture order count and reference picture lists
+/// of the slice (see [`crate::dpb::Dpb`]), used for motion vector
+/// prediction. The whole slice is rejected if its data does not end exactly
+/// at the RBSP trailing bits, so a successful result is consistent with the
+/// bitstream.
+pub fn parse_slice_data(
+    nal: &NalUnit,
+    sps: &Sps,
+    pps: &Pps,
+    poc: i32,
+    ref_pic_lists: &RefPicLists,
+) -> Result<SliceCtus> {
+    let nal_type = nal.nal_type();
+    if !nal_type.is_vcl() || (nal_type as u8) > 21 || (10..=15).contains(&(nal_type as u8)) {
+        return Err(HevcError::InvalidData(format!(
+            "NAL unit type {} does not carry slice data",
+            nal_type.name()
+        )));
+    }
+
+    if sps.separate_colour_plane_flag
+        || matches!(
+            sps.chroma_format_idc,
+            ChromaFormat::Chroma422 | ChromaFormat::Chroma444
+        )
+    {
+        return Err(HevcError::InvalidData(
+            "Unsupported: 4:2:2 and 4:4:4 slice data".to_string(),
+        ));
+    }
+    if pps.pps_range_extension_flag || pps.pps_scc_extension_flag {
+        return Err(HevcError::InvalidData(
+            "Unsupported: PPS range and screen content coding extensions".to_string(),
+        ));
+    }
+    let luma_samples = sps.pic_width_in_luma_samples as u64 * sps.pic_height_in_luma_samples as u64;
+    if luma_samples == 0 || luma_samples > MAX_LUMA_PICTURE_SIZE {
+        return Err(HevcError::InvalidData(format!(
+            "picture size {}x{} out of range",
+            sps.pic_width_in_luma_samples, sps.pic_height_in_luma_samples
+        )));
+    }
+
+    let sps_map = HashMap::from([(pps.pps_seq_parameter_set_id, sps.clone())]);
+    let pps_map = HashMap::from([(pps.pps_pic_parameter_set_id, pps.clone())]);
+    let (header, data_offset) =
+        parse_slice_header_with_data_offset(&nal.payload, &sps_map, &pps_map, nal_type)?;
+    if header.dependent_slice_segment_flag {
+        return Err(HevcError::InvalidData(
+            "Unsupported: dependent slice segments".to_string(),
+        ));
+    }
+
+    let substreams = substream_positions(&nal.raw_payload, data_offset / 8, &header);
+    let layout = coding_tree::TileLayout::new(sps, pps)?;
+    let prediction = mv_prediction::PredictionContext {
+        poc,
+        ref_lists: [&ref_pic_lists.list0, &ref_pic_lists.list1],
+        num_ref_idx_active: [
+            header.num_ref_idx_l0_active() as usize,
+            header.num_ref_idx_l1_active() as usize,
+        ],
+        b_slice: header.slice_type == SliceType::B,
+        max_num_merge_cand: header.max_num_merge_cand() as usize,
+        log2_par_mrg_level: pps.log2_parallel_merge_level_minus2 as u32 + 2,
+    };
+
+    let ctus = coding_tree::SliceParser::new(
+        sps,
+        pps,
+        &header,
+        &layout,
+        &nal.payload,
+        data_offset,
+        &substreams,
+        prediction,
+    )?
+    .parse()?;
+
+    Ok(SliceCtus { header, ctus })
+}
+
+/// Bit positions in the RBSP where the substreams after the first start.
+///
+/// Entry point offsets count the bytes of the slice segment data including
+/// emulation prevention bytes (7.4.7.1), so they are applied to the raw
+/// payload and mapped back. `data_start` is the RBSP byte position of the
+/// slice segment data.
+fn substream_positions(raw_payload: &[u8], data_start: usize, header: &SliceHeader) -> Vec<usize> {
+    // Raw positions of the emulation prevention bytes
+    let mut epb = Vec::new();
+    let mut i = 0;
+    while i < raw_payload.len() {
+        if i + 2 < raw_payload.len() && raw_payload[i..i + 3] == [0, 0, 3] {
+            epb.push(i + 2);
+            i += 3;
+        } else {
+            i += 1;
+        }
+    }
+
+    let mut raw_start = data_start;
+    for &pos in &epb {
+        if pos <= raw_start {
+            raw_start += 1;
+        } else {
+            break;
+        }
+    }
+
+    let mut raw = raw_start;
+    header
+        .entry_point_offset_minus1
+        .iter()
+        .map(|&offset| {
+            raw += offset as usize + 1;
+            let removed = epb.iter().take_while(|&&pos| pos < raw).count();
+            (raw - removed) * 8
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dpb::Dpb;
+    use crate::overlay_extraction::PredMode;
+    use std::path::Path;
+
+    #[test]
+    fn test_substream_positions_skip_emulation_prevention_bytes() {
+        // Slice data starts at RBSP byte 2 (raw byte 3, after the EPB at raw
+        // byte 2). The first substream holds raw bytes 3..9, which contain
+        // another EPB at raw byte 6.
+        let raw = [
+            0x00, 0x00, 0x03, 0x01, 0x00, 0x00, 0x03, 0x02, 0xFF, 0x80, 0x80,
+        ];
+        let header = SliceHeader {
+            entry_point_offset_minus1: vec![5, 0],
+            ..Default::default()
+        };
+        assert_eq!(substream_positions(&raw, 2, &header), vec![7 * 8, 8 * 8]);
+    }
+
+    #[test]
+    fn test_substream_positions_without_entry_points() {
+        let header = SliceHeader::default();
+        assert!(substream_positions(&[0x80], 0, &header).is_empty());
+    }
+
+    #[test]
+    fn test_parse_foreman_slices() {
+        // 352x288 with 64x64 CTUs, WPP and one slice per picture
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .parent()
+            .and_then(|p| p.parent())
+            .unwrap()
+            .join("samples/foreman_hevc.265");
+        let Ok(data) = std::fs::read(&path) else {
+            eprintln!("Skipping test: {} not found", path.display());
+            return;
+        };
+        let stream = crate::parse_hevc(&data).unwrap();
+        let sps = stream.sps_map.values().next().unwrap();
+        let pps = stream.pps_map.values().next().unwrap();
+        assert!(pps.entropy_coding_sync_enabled_flag);
+
+        let mut dpb = Dpb::new();
+        let mut slices = 0;
+        for slice in &stream.slices {
+            let nal = &stream.nal_units[slice.nal_index];
+            let picture =
+                dpb.start_picture(nal.nal_type(), nal.header.temporal_id(), &slice.header, sps);
+            let lists = picture.ref_pic_lists(&slice.header);
+            let parsed = parse_slice_data(nal, sps, pps, picture.poc, &lists).unwrap();
+
+            assert_eq!(parsed.ctus.len(), 30);
+            let area: u32 = parsed
+                .ctus
+                .iter()
+                .flat_map(|ctu| &ctu.coding_units)
+                .map(|cu| {
+                    // Coding units inside the picture
+                    assert!(cu.x + cu.size as u32 <= 352 && cu.y + cu.size as u32 <= 288);
+                    assert!((0..=51).contains(&cu.qp));
+                    if cu.pred_mode == PredMode::Intra {
+                        assert!(!cu.intra_modes.is_empty());
+                        assert!(cu.mv_l0.is_none() && cu.mv_l1.is_none());
+                    } else {
+                        assert!(!cu.prediction_units.is_empty());
+                    }
+                    (cu.size as u32).pow(2)
+                })
+                .sum();
+            assert_eq!(area, 352 * 288);
+            if slice.header.slice_type == SliceType::I {
+                let cus = parsed.ctus.iter().flat_map(|ctu| &ctu.coding_units);
+                assert!(cus.clone().all(|cu| cu.pred_mode == PredMode::Intra));
+                assert!(cus.clone().any(|cu| cu.depth > 0));
+            }
+            slices += 1;
+        }
+        assert_eq!(slices, 60);
+    }
+}
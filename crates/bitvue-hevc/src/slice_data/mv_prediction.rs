@@ -0,0 +1,470 @@
+//! Luma motion vector prediction (ITU-T H.265 Sections 8.5.3.2.1 to
+//! 8.5.3.2.7).
+//!
+//! Neighbouring prediction blocks are looked up through a caller-supplied
+//! function that returns their motion, or `None` when the block is not
+//! available for prediction (outside the picture, slice or tile, not yet
+//! decoded, or intra coded).
+//!
+//! The temporal (co-located) candidates need the motion of a reference
+//! picture and are always treated as unavailable.
+
+use crate::dpb::RefPicEntry;
+use crate::overlay_extraction::PartMode;
+
+/// Motion vector in quarter luma samples.
+pub(super) type Mv = [i32; 2];
+
+/// Motion of a prediction block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(super) struct PuMotion {
+    /// predFlagL0 and predFlagL1.
+    pub pred_flag: [bool; 2],
+    /// refIdxL0 and refIdxL1; -1 when the list is not used.
+    pub ref_idx: [i8; 2],
+    /// mvL0 and mvL1; zero when the list is not used.
+    pub mv: [Mv; 2],
+}
+
+impl PuMotion {
+    /// Motion predicted from one list.
+    pub(super) fn uni(list: usize, ref_idx: i8, mv: Mv) -> Self {
+        let mut motion = Self {
+            pred_flag: [false; 2],
+            ref_idx: [-1; 2],
+            mv: [[0, 0]; 2],
+        };
+        motion.pred_flag[list] = true;
+        motion.ref_idx[list] = ref_idx;
+        motion.mv[list] = mv;
+        motion
+    }
+}
+
+/// Prediction block (location and size in luma samples).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Block {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// Slice-level inputs of motion vector prediction.
+pub(super) struct PredictionContext<'a> {
+    /// PicOrderCntVal of the current picture.
+    pub poc: i32,
+    /// RefPicList0 and RefPicList1.
+    pub ref_lists: [&'a [RefPicEntry]; 2],
+    /// num_ref_idx_l0_active and num_ref_idx_l1_active.
+    pub num_ref_idx_active: [usize; 2],
+    /// B slice.
+    pub b_slice: bool,
+    /// MaxNumMergeCand.
+    pub max_num_merge_cand: usize,
+    /// Log2ParMrgLevel.
+    pub log2_par_mrg_level: u32,
+}
+
+impl PredictionContext<'_> {
+    fn ref_pic(&self, list: usize, ref_idx: i8) -> Option<RefPicEntry> {
+        usize::try_from(ref_idx)
+            .ok()
+            .and_then(|idx| self.ref_lists[list].get(idx).copied())
+    }
+
+    /// Derive the motion of a merge-mode prediction unit (8.5.3.2.2).
+    ///
+    /// `cb` is the coding block, `pb` the prediction block with index
+    /// `part_idx` of `part_mode`.
+    pub(super) fn merge_motion(
+        &self,
+        neighbour: impl Fn(i32, i32) -> Option<PuMotion>,
+        cb: Block,
+        pb: Block,
+        part_mode: PartMode,
+        part_idx: usize,
+        merge_idx: usize,
+    ) -> PuMotion {
+        let orig_size = pb.width + pb.height;
+        // singleMCLFlag: all prediction units of an 8x8 coding unit share
+        // the merge candidates of the 2Nx2N prediction unit
+        let (pb, part_idx) = if self.log2_par_mrg_level > 2 && cb.width == 8 {
+            (cb, 0)
+        } else {
+            (pb, part_idx)
+        };
+
+        let mut candidates = self.spatial_merge_candidates(&neighbour, pb, part_mode, part_idx);
+        candidates.truncate(self.max_num_merge_cand);
+
+        // Combined bi-predictive candidates (8.5.3.2.4)
+        let num_orig = candidates.len();
+        if self.b_slice && num_orig > 1 && num_orig < self.max_num_merge_cand {
+            for &(l0, l1) in COMBINED_CANDIDATE_ORDER
+                .iter()
+                .take(num_orig * (num_orig - 1))
+            {
+                if candidates.len() >= self.max_num_merge_cand {
+                    break;
+                }
+                let (l0_cand, l1_cand) = (candidates[l0], candidates[l1]);
+                if l0_cand.pred_flag[0]
+                    && l1_cand.pred_flag[1]
+                    && (self.ref_pic(0, l0_cand.ref_idx[0]).map(|r| r.poc)
+                        != self.ref_pic(1, l1_cand.ref_idx[1]).map(|r| r.poc)
+                        || l0_cand.mv[0] != l1_cand.mv[1])
+                {
+                    candidates.push(PuMotion {
+                        pred_flag: [true, true],
+                        ref_idx: [l0_cand.ref_idx[0], l1_cand.ref_idx[1]],
+                        mv: [l0_cand.mv[0], l1_cand.mv[1]],
+                    });
+                }
+            }
+        }
+
+        // Zero motion vector candidates (8.5.3.2.5)
+        let num_ref_idx = if self.b_slice {
+            self.num_ref_idx_active[0].min(self.num_ref_idx_active[1])
+        } else {
+            self.num_ref_idx_active[0]
+        };
+        let mut zero_idx = 0;
+        while candidates.len() < self.max_num_merge_cand {
+            let ref_idx = if zero_idx < num_ref_idx { zero_idx } else { 0 } as i8;
+            candidates.push(PuMotion {
+                pred_flag: [true, self.b_slice],
+                ref_idx: [ref_idx, if self.b_slice { ref_idx } else { -1 }],
+                mv: [[0, 0]; 2],
+            });
+            zero_idx += 1;
+        }
+
+        let mut motion = candidates
+            .get(merge_idx)
+            .copied()
+            .unwrap_or_else(|| PuMotion::uni(0, 0, [0, 0]));
+        // 8x4 and 4x8 prediction units are restricted to uni-prediction
+        if motion.pred_flag[0] && motion.pred_flag[1] && orig_size == 12 {
+            motion.pred_flag[1] = false;
+            motion.ref_idx[1] = -1;
+            motion.mv[1] = [0, 0];
+        }
+        motion
+    }
+
+    /// Spatial merge candidates A1, B1, B0, A0 and B2 (8.5.3.2.3).
+    fn spatial_merge_candidates(
+        &self,
+        neighbour: &impl Fn(i32, i32) -> Option<PuMotion>,
+        pb: Block,
+        part_mode: PartMode,
+        part_idx: usize,
+    ) -> Vec<PuMotion> {
+        let shift = self.log2_par_mrg_level;
+        let candidate = |x: i32, y: i32| {
+            let same_merge_region =
+                (pb.x >> shift) == (x >> shift) && (pb.y >> shift) == (y >> shift);
+            if same_merge_region {
+                None
+            } else {
+                neighbour(x, y)
+            }
+        };
+        let second_vertical = part_idx == 1
+            && matches!(
+                part_mode,
+                PartMode::PartNx2N | PartMode::PartnLx2N | PartMode::PartnRx2N
+            );
+        let second_horizontal = part_idx == 1
+            && matches!(
+                part_mode,
+                PartMode::Part2NxN | PartMode::Part2NxnU | PartMode::Part2NxnD
+            );
+
+        // Pruning compares against available neighbours, whether or not
+        // they were added to the list themselves
+        let a1 = candidate(pb.x - 1, pb.y + pb.height - 1).filter(|_| !second_vertical);
+        let b1 = candidate(pb.x + pb.width - 1, pb.y - 1).filter(|_| !second_horizontal);
+        let b0 = candidate(pb.x + pb.width, pb.y - 1);
+        let a0 = candidate(pb.x - 1, pb.y + pb.height);
+        let b2 = candidate(pb.x - 1, pb.y - 1);
+
+        let mut candidates: Vec<PuMotion> = [
+            a1,
+            b1.filter(|b1| Some(*b1) != a1),
+            b0.filter(|b0| Some(*b0) != b1),
+            a0.filter(|a0| Some(*a0) != a1),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if candidates.len() < 4 {
+            candidates.extend(b2.filter(|b2| Some(*b2) != a1 && Some(*b2) != b1));
+        }
+        candidates
+    }
+
+    /// Derive the luma motion vector predictor mvpLX of an AMVP prediction
+    /// unit (8.5.3.2.6 and 8.5.3.2.7).
+    pub(super) fn mvp(
+        &self,
+        neighbour: impl Fn(i32, i32) -> Option<PuMotion>,
+        pb: Block,
+        list: usize,
+        ref_idx: i8,
+        mvp_flag: usize,
+    ) -> Mv {
+        let target = self.ref_pic(list, ref_idx);
+        let other = 1 - list;
+        let a = [
+            neighbour(pb.x - 1, pb.y + pb.height),
+            neighbour(pb.x - 1, pb.y + pb.height - 1),
+        ];
+        let b = [
+            neighbour(pb.x + pb.width, pb.y - 1),
+            neighbour(pb.x + pb.width - 1, pb.y - 1),
+            neighbour(pb.x - 1, pb.y - 1),
+        ];
+
+        // A motion vector referring to the target picture itself
+        let same_picture = |n: &PuMotion| {
+            [list, other].into_iter().find_map(|l| {
+                let r = self.ref_pic(l, n.ref_idx[l]);
+                (n.pred_flag[l] && r.is_some() && r.map(|r| r.poc) == target.map(|t| t.poc))
+                    .then_some(n.mv[l])
+            })
+        };
+        // A motion vector referring to another picture of the same
+        // (short-term or long-term) kind, scaled by POC distance
+        let scaled = |n: &PuMotion| {
+            [list, other].into_iter().find_map(|l| {
+                let r = self.ref_pic(l, n.ref_idx[l])?;
+                let t = target?;
+                (n.pred_flag[l] && r.long_term == t.long_term).then(|| {
+                    if r.long_term {
+                        n.mv[l]
+                    } else {
+                        scale_mv(n.mv[l], self.poc - r.poc, self.poc - t.poc)
+                    }
+                })
+            })
+        };
+
+        let is_scaled = a.iter().any(Option::is_some);
+        let mut mv_a = a.iter().flatten().find_map(same_picture);
+        if mv_a.is_none() {
+            mv_a = a.iter().flatten().find_map(scaled);
+        }
+        let mut mv_b = b.iter().flatten().find_map(same_picture);
+        if !is_scaled {
+            if mv_b.is_some() {
+                mv_a = mv_b;
+            }
+            mv_b = b.iter().flatten().find_map(scaled);
+        }
+
+        let mut candidates: Vec<Mv> = mv_a.into_iter().collect();
+        if let Some(mv_b) = mv_b.filter(|mv_b| Some(*mv_b) != mv_a) {
+            candidates.push(mv_b);
+        }
+        candidates.resize(2, [0, 0]);
+        candidates[mvp_flag.min(1)]
+    }
+}
+
+/// Pairs of candidate indices tried for combined bi-predictive merge
+/// candidates (Table 8-6).
+const COMBINED_CANDIDATE_ORDER: [(usize, usize); 12] = [
+    (0, 1),
+    (1, 0),
+    (0, 2),
+    (2, 0),
+    (1, 2),
+    (2, 1),
+    (0, 3),
+    (3, 0),
+    (1, 3),
+    (3, 1),
+    (2, 3),
+    (3, 2),
+];
+
+/// Scale a motion vector by the ratio of POC distances `tb` / `td`
+/// (equations 8-179 to 8-183).
+fn scale_mv(mv: Mv, td: i32, tb: i32) -> Mv {
+    let td = td.clamp(-128, 127);
+    let tb = tb.clamp(-128, 127);
+    if td == 0 || td == tb {
+        return mv;
+    }
+    let tx = (16384 + (td.abs() >> 1)) / td;
+    let scale = ((tb * tx + 32) >> 6).clamp(-4096, 4095);
+    mv.map(|c| {
+        let product = scale * c;
+        (product.signum() * ((product.abs() + 127) >> 8)).clamp(-32768, 32767)
+    })
+}
+
+/// Add a motion vector difference to a predictor with the 16-bit wrap
+/// of equations 8-194 to 8-197.
+pub(super) fn add_mvd(mvp: Mv, mvd: Mv) -> Mv {
+    [0, 1].map(|i| (mvp[i] + mvd[i]) as i16 as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(poc: i32) -> RefPicEntry {
+        RefPicEntry {
+            poc,
+            long_term: false,
+            missing: false,
+        }
+    }
+
+    fn context<'a>(lists: [&'a [RefPicEntry]; 2], b_slice: bool) -> PredictionContext<'a> {
+        PredictionContext {
+            poc: 8,
+            ref_lists: lists,
+            num_ref_idx_active: [lists[0].len(), lists[1].len()],
+            b_slice,
+            max_num_merge_cand: 5,
+            log2_par_mrg_level: 2,
+        }
+    }
+
+    const PB: Block = Block {
+        x: 16,
+        y: 16,
+        width: 16,
+        height: 16,
+    };
+
+    #[test]
+    fn test_scale_mv() {
+        // Half the distance halves the vector
+        assert_eq!(scale_mv([16, -8], 4, 2), [8, -4]);
+        assert_eq!(scale_mv([16, -8], 2, 2), [16, -8]);
+        assert_eq!(scale_mv([16, -8], 2, -2), [-16, 8]);
+    }
+
+    #[test]
+    fn test_add_mvd_wraps() {
+        assert_eq!(add_mvd([4, -4], [1, 2]), [5, -2]);
+        assert_eq!(add_mvd([32767, 0], [1, 0]), [-32768, 0]);
+    }
+
+    #[test]
+    fn test_merge_spatial_pruning_and_zero_fill() {
+        let list0 = [entry(4), entry(0)];
+        let ctx = context([&list0, &[]], false);
+        let left = PuMotion::uni(0, 0, [4, 4]);
+        let above = PuMotion::uni(0, 1, [8, 0]);
+        // A1 and A0 have the same motion, B1 and B0 differ from A1
+        let neighbour = |x: i32, y: i32| {
+            if x < PB.x {
+                Some(left)
+            } else if y < PB.y {
+                Some(above)
+            } else {
+                None
+            }
+        };
+        let merge = |idx| ctx.merge_motion(neighbour, PB, PB, PartMode::Part2Nx2N, 0, idx);
+        assert_eq!(merge(0), left);
+        assert_eq!(merge(1), above);
+        // B0 is pruned against B1, A0 against A1 and B2 against A1, so the
+        // zero candidates follow with increasing reference indices
+        assert_eq!(merge(2), PuMotion::uni(0, 0, [0, 0]));
+        assert_eq!(merge(3), PuMotion::uni(0, 1, [0, 0]));
+        assert_eq!(merge(4), PuMotion::uni(0, 0, [0, 0]));
+    }
+
+    #[test]
+    fn test_merge_combined_bi_predictive() {
+        let list0 = [entry(4)];
+        let list1 = [entry(12)];
+        let ctx = context([&list0, &list1], true);
+        let left = PuMotion::uni(0, 0, [4, 4]);
+        let above = PuMotion::uni(1, 0, [-8, 0]);
+        let neighbour = |x: i32, y: i32| {
+            if x < PB.x && y >= PB.y {
+                Some(left)
+            } else if y < PB.y && x >= PB.x {
+                Some(above)
+            } else {
+                None
+            }
+        };
+        let combined = ctx.merge_motion(neighbour, PB, PB, PartMode::Part2Nx2N, 0, 2);
+        assert_eq!(combined.pred_flag, [true, true]);
+        assert_eq!(combined.mv, [[4, 4], [-8, 0]]);
+        assert_eq!(combined.ref_idx, [0, 0]);
+
+        // 8x4 prediction units drop list 1
+        let small = Block {
+            width: 8,
+            height: 4,
+            ..PB
+        };
+        let cb = Block {
+            width: 8,
+            height: 8,
+            ..PB
+        };
+        let neighbour = |_: i32, _: i32| None;
+        let zero = ctx.merge_motion(neighbour, cb, small, PartMode::Part2NxN, 0, 0);
+        assert_eq!(zero.pred_flag, [true, false]);
+    }
+
+    #[test]
+    fn test_merge_second_partition_excludes_first() {
+        let list0 = [entry(4)];
+        let ctx = context([&list0, &[]], false);
+        let first = PuMotion::uni(0, 0, [2, 2]);
+        let pb = Block {
+            x: 24,
+            width: 8,
+            ..PB
+        };
+        let cb = Block { x: 16, ..PB };
+        // Only the first partition (x 16..24, y 16..32) has been decoded
+        let neighbour =
+            |x: i32, y: i32| ((16..24).contains(&x) && (16..32).contains(&y)).then_some(first);
+        // A1 lies in the first Nx2N partition and is skipped
+        let motion = ctx.merge_motion(neighbour, cb, pb, PartMode::PartNx2N, 1, 0);
+        assert_eq!(motion, PuMotion::uni(0, 0, [0, 0]));
+    }
+
+    #[test]
+    fn test_mvp_prefers_same_reference_and_scales() {
+        let list0 = [entry(4), entry(6)];
+        let ctx = context([&list0, &[]], false);
+        // A refers to POC 4, B to POC 6
+        let neighbour = |x: i32, y: i32| {
+            if x < PB.x && y >= PB.y {
+                Some(PuMotion::uni(0, 0, [8, 8]))
+            } else if y < PB.y && x >= PB.x {
+                Some(PuMotion::uni(0, 1, [2, 2]))
+            } else {
+                None
+            }
+        };
+        assert_eq!(ctx.mvp(neighbour, PB, 0, 0, 0), [8, 8]);
+        assert_eq!(ctx.mvp(neighbour, PB, 0, 0, 1), [0, 0]);
+        // For POC 6 (distance 2) A is scaled from distance 4
+        assert_eq!(ctx.mvp(neighbour, PB, 0, 1, 0), [4, 4]);
+        assert_eq!(ctx.mvp(neighbour, PB, 0, 1, 1), [2, 2]);
+    }
+
+    #[test]
+    fn test_mvp_zero_fill() {
+        let list0 = [entry(4)];
+        let ctx = context([&list0, &[]], false);
+        let neighbour = |_: i32, _: i32| None;
+        assert_eq!(ctx.mvp(neighbour, PB, 0, 0, 1), [0, 0]);
+    }
+}
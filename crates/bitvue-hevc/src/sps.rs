@@ -4,6 +4,8 @@
 
 use crate::bitreader::BitReader;
 use crate::error::{HevcError, Result};
+use crate::rps::{parse_st_ref_pic_set, ShortTermRefPicSet};
+use crate::vps::{parse_hrd_parameters, HrdParameters};
 use serde::{Deserialize, Serialize};
 
 // Re-export ChromaFormat from bitvue_core for backward compatibility
@@ -96,9 +98,34 @@ pub struct VuiParameters {
     pub transfer_characteristics: Option<u8>,
     pub matrix_coeffs: Option<u8>,
     pub chroma_loc_info_present_flag: bool,
+    pub field_seq_flag: bool,
+    pub frame_field_info_present_flag: bool,
     pub timing_info_present_flag: bool,
     pub num_units_in_tick: Option<u32>,
     pub time_scale: Option<u32>,
+    pub poc_proportional_to_timing_flag: bool,
+    pub num_ticks_poc_diff_one_minus1: Option<u32>,
+    pub hrd_parameters_present_flag: bool,
+    pub hrd_parameters: Option<HrdParameters>,
+    pub bitstream_restriction_flag: bool,
+    pub min_spatial_segmentation_idc: Option<u32>,
+    pub max_bytes_per_pic_denom: Option<u32>,
+    pub max_bits_per_min_cu_denom: Option<u32>,
+}
+
+/// PCM sample parameters (present when `pcm_enabled_flag` is set).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PcmParameters {
+    /// PCM luma sample bit depth minus 1.
+    pub pcm_sample_bit_depth_luma_minus1: u8,
+    /// PCM chroma sample bit depth minus 1.
+    pub pcm_sample_bit_depth_chroma_minus1: u8,
+    /// Log2 of the minimum PCM coding block size minus 3.
+    pub log2_min_pcm_luma_coding_block_size_minus3: u8,
+    /// Difference between the log2 maximum and minimum PCM block sizes.
+    pub log2_diff_max_min_pcm_luma_coding_block_size: u8,
+    /// In-loop filters are disabled for PCM samples.
+    pub pcm_loop_filter_disabled_flag: bool,
 }
 
 /// Sequence Parameter Set.
@@ -163,12 +190,21 @@ pub struct Sps {
     pub sample_adaptive_offset_enabled_flag: bool,
     /// PCM enabled.
     pub pcm_enabled_flag: bool,
+    /// PCM parameters.
+    #[serde(default)]
+    pub pcm_parameters: Option<PcmParameters>,
     /// Number of short-term reference picture sets.
     pub num_short_term_ref_pic_sets: u8,
     /// Long-term reference pics present.
     pub long_term_ref_pics_present_flag: bool,
     /// Number of long-term reference pics in SPS.
     pub num_long_term_ref_pics_sps: u8,
+    /// Short-term reference picture sets.
+    pub st_ref_pic_sets: Vec<ShortTermRefPicSet>,
+    /// POC LSBs of the long-term reference pics in SPS.
+    pub lt_ref_pic_poc_lsb_sps: Vec<u32>,
+    /// Whether each long-term reference pic in SPS is used by the current picture.
+    pub used_by_curr_pic_lt_sps_flag: Vec<bool>,
     /// Temporal MVP enabled.
     pub sps_temporal_mvp_enabled_flag: bool,
     /// Strong intra smoothing enabled.
@@ -393,8 +429,7 @@ pub fn parse_sps(rbsp: &[u8]) -> Result<Sps> {
     if scaling_list_enabled_flag {
         let sps_scaling_list_data_present_flag = reader.read_bit()?;
         if sps_scaling_list_data_present_flag {
-            // Skip scaling list data (complex structure)
-            // For now, we don't fully parse this
+            skip_scaling_list_data(&mut reader)?;
         }
     }
 
@@ -402,27 +437,72 @@ pub fn parse_sps(rbsp: &[u8]) -> Result<Sps> {
     let sample_adaptive_offset_enabled_flag = reader.read_bit()?;
 
     let pcm_enabled_flag = reader.read_bit()?;
-    if pcm_enabled_flag {
-        let _pcm_sample_bit_depth_luma_minus1 = reader.read_bits(4)?;
-        let _pcm_sample_bit_depth_chroma_minus1 = reader.read_bits(4)?;
-        let _log2_min_pcm_luma_coding_block_size_minus3 = reader.read_ue()?;
-        let _log2_diff_max_min_pcm_luma_coding_block_size = reader.read_ue()?;
-        let _pcm_loop_filter_disabled_flag = reader.read_bit()?;
-    }
+    let pcm_parameters = if pcm_enabled_flag {
+        let pcm_sample_bit_depth_luma_minus1 = reader.read_bits(4)? as u8;
+        let pcm_sample_bit_depth_chroma_minus1 = reader.read_bits(4)? as u8;
+        let log2_min_pcm_luma_coding_block_size_minus3 = reader.read_ue()?;
+        let log2_diff_max_min_pcm_luma_coding_block_size = reader.read_ue()?;
+        if log2_min_pcm_luma_coding_block_size_minus3 > 2
+            || log2_diff_max_min_pcm_luma_coding_block_size > 2
+        {
+            return Err(HevcError::InvalidData(format!(
+                "PCM coding block sizes out of range: min_minus3 {}, diff {}",
+                log2_min_pcm_luma_coding_block_size_minus3,
+                log2_diff_max_min_pcm_luma_coding_block_size
+            )));
+        }
+        Some(PcmParameters {
+            pcm_sample_bit_depth_luma_minus1,
+            pcm_sample_bit_depth_chroma_minus1,
+            log2_min_pcm_luma_coding_block_size_minus3: log2_min_pcm_luma_coding_block_size_minus3
+                as u8,
+            log2_diff_max_min_pcm_luma_coding_block_size:
+                log2_diff_max_min_pcm_luma_coding_block_size as u8,
+            pcm_loop_filter_disabled_flag: reader.read_bit()?,
+        })
+    } else {
+        None
+    };
 
-    let num_short_term_ref_pic_sets = reader.read_ue()? as u8;
-    // Skip short-term RPS parsing (complex)
-    // This would require full st_ref_pic_set parsing
+    let num_short_term_ref_pic_sets = reader.read_ue()?;
+    // SECURITY: Validate RPS count (0..=64 per spec)
+    const MAX_SHORT_TERM_REF_PIC_SETS: u32 = 64;
+    if num_short_term_ref_pic_sets > MAX_SHORT_TERM_REF_PIC_SETS {
+        return Err(HevcError::InvalidData(format!(
+            "num_short_term_ref_pic_sets {} exceeds maximum {}",
+            num_short_term_ref_pic_sets, MAX_SHORT_TERM_REF_PIC_SETS
+        )));
+    }
+    let mut st_ref_pic_sets = Vec::with_capacity(num_short_term_ref_pic_sets as usize);
+    for i in 0..num_short_term_ref_pic_sets as usize {
+        let rps = parse_st_ref_pic_set(
+            &mut reader,
+            i,
+            num_short_term_ref_pic_sets as usize,
+            &st_ref_pic_sets,
+        )?;
+        st_ref_pic_sets.push(rps);
+    }
+    let num_short_term_ref_pic_sets = num_short_term_ref_pic_sets as u8;
 
     let long_term_ref_pics_present_flag = reader.read_bit()?;
+    let mut lt_ref_pic_poc_lsb_sps = Vec::new();
+    let mut used_by_curr_pic_lt_sps_flag = Vec::new();
     let num_long_term_ref_pics_sps = if long_term_ref_pics_present_flag {
-        let count = reader.read_ue()? as u8;
+        let count = reader.read_ue()?;
+        // SECURITY: Validate long-term count (0..=32 per spec)
+        const MAX_LONG_TERM_REF_PICS_SPS: u32 = 32;
+        if count > MAX_LONG_TERM_REF_PICS_SPS {
+            return Err(HevcError::InvalidData(format!(
+                "num_long_term_ref_pics_sps {} exceeds maximum {}",
+                count, MAX_LONG_TERM_REF_PICS_SPS
+            )));
+        }
         for _ in 0..count {
-            let _lt_ref_pic_poc_lsb_sps =
-                reader.read_bits(log2_max_pic_order_cnt_lsb_minus4 + 4)?;
-            let _used_by_curr_pic_lt_sps_flag = reader.read_bit()?;
+            lt_ref_pic_poc_lsb_sps.push(reader.read_bits(log2_max_pic_order_cnt_lsb_minus4 + 4)?);
+            used_by_curr_pic_lt_sps_flag.push(reader.read_bit()?);
         }
-        count
+        count as u8
     } else {
         0
     };
@@ -432,7 +512,10 @@ pub fn parse_sps(rbsp: &[u8]) -> Result<Sps> {
 
     let vui_parameters_present_flag = reader.read_bit()?;
     let vui_parameters = if vui_parameters_present_flag {
-        Some(parse_vui_parameters(&mut reader)?)
+        Some(parse_vui_parameters(
+            &mut reader,
+            sps_max_sub_layers_minus1,
+        )?)
     } else {
         None
     };
@@ -469,9 +552,13 @@ pub fn parse_sps(rbsp: &[u8]) -> Result<Sps> {
         amp_enabled_flag,
         sample_adaptive_offset_enabled_flag,
         pcm_enabled_flag,
+        pcm_parameters,
         num_short_term_ref_pic_sets,
         long_term_ref_pics_present_flag,
         num_long_term_ref_pics_sps,
+        st_ref_pic_sets,
+        lt_ref_pic_poc_lsb_sps,
+        used_by_curr_pic_lt_sps_flag,
         sps_temporal_mvp_enabled_flag,
         strong_intra_smoothing_enabled_flag,
         vui_parameters_present_flag,
@@ -479,9 +566,34 @@ pub fn parse_sps(rbsp: &[u8]) -> Result<Sps> {
     })
 }
 
-/// Parse VUI parameters (simplified).
+/// Skip `scaling_list_data()` (Section 7.3.4).
+fn skip_scaling_list_data(reader: &mut BitReader) -> Result<()> {
+    for size_id in 0..4 {
+        let matrix_step = if size_id == 3 { 3 } else { 1 };
+        for _matrix_id in (0..6).step_by(matrix_step) {
+            let scaling_list_pred_mode_flag = reader.read_bit()?;
+            if !scaling_list_pred_mode_flag {
+                let _scaling_list_pred_matrix_id_delta = reader.read_ue()?;
+            } else {
+                let coef_num = 64.min(1 << (4 + (size_id << 1)));
+                if size_id > 1 {
+                    let _scaling_list_dc_coef_minus8 = reader.read_se()?;
+                }
+                for _ in 0..coef_num {
+                    let _scaling_list_delta_coef = reader.read_se()?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse VUI parameters (E.2.1).
 #[allow(clippy::field_reassign_with_default)]
-fn parse_vui_parameters(reader: &mut BitReader) -> Result<VuiParameters> {
+fn parse_vui_parameters(
+    reader: &mut BitReader,
+    max_sub_layers_minus1: u8,
+) -> Result<VuiParameters> {
     let mut vui = VuiParameters::default();
 
     vui.aspect_ratio_info_present_flag = reader.read_bit()?;
@@ -517,10 +629,9 @@ fn parse_vui_parameters(reader: &mut BitReader) -> Result<VuiParameters> {
         let _chroma_sample_loc_type_bottom_field = reader.read_ue()?;
     }
 
-    // Skip some flags
     let _neutral_chroma_indication_flag = reader.read_bit()?;
-    let _field_seq_flag = reader.read_bit()?;
-    let _frame_field_info_present_flag = reader.read_bit()?;
+    vui.field_seq_flag = reader.read_bit()?;
+    vui.frame_field_info_present_flag = reader.read_bit()?;
 
     let default_display_window_flag = reader.read_bit()?;
     if default_display_window_flag {
@@ -534,7 +645,26 @@ fn parse_vui_parameters(reader: &mut BitReader) -> Result<VuiParameters> {
     if vui.timing_info_present_flag {
         vui.num_units_in_tick = Some(reader.read_bits(32)?);
         vui.time_scale = Some(reader.read_bits(32)?);
-        // More timing info could be parsed here
+        vui.poc_proportional_to_timing_flag = reader.read_bit()?;
+        if vui.poc_proportional_to_timing_flag {
+            vui.num_ticks_poc_diff_one_minus1 = Some(reader.read_ue()?);
+        }
+        vui.hrd_parameters_present_flag = reader.read_bit()?;
+        if vui.hrd_parameters_present_flag {
+            vui.hrd_parameters = Some(parse_hrd_parameters(reader, true, max_sub_layers_minus1)?);
+        }
+    }
+
+    vui.bitstream_restriction_flag = reader.read_bit()?;
+    if vui.bitstream_restriction_flag {
+        let _tiles_fixed_structure_flag = reader.read_bit()?;
+        let _motion_vectors_over_pic_boundaries_flag = reader.read_bit()?;
+        let _restricted_ref_pic_lists_flag = reader.read_bit()?;
+        vui.min_spatial_segmentation_idc = Some(reader.read_ue()?);
+        vui.max_bytes_per_pic_denom = Some(reader.read_ue()?);
+        vui.max_bits_per_min_cu_denom = Some(reader.read_ue()?);
+        let _log2_max_mv_length_horizontal = reader.read_ue()?;
+        let _log2_max_mv_length_vertical = reader.read_ue()?;
     }
 
     Ok(vui)
@@ -588,9 +718,13 @@ mod tests {
             amp_enabled_flag: true,
             sample_adaptive_offset_enabled_flag: true,
             pcm_enabled_flag: false,
+            pcm_parameters: None,
             num_short_term_ref_pic_sets: 0,
             long_term_ref_pics_present_flag: false,
             num_long_term_ref_pics_sps: 0,
+            st_ref_pic_sets: Vec::new(),
+            lt_ref_pic_poc_lsb_sps: Vec::new(),
+            used_by_curr_pic_lt_sps_flag: Vec::new(),
             sps_temporal_mvp_enabled_flag: true,
             strong_intra_smoothing_enabled_flag: true,
             vui_parameters_present_flag: false,
@@ -3,8 +3,9 @@
 //! This module builds a hierarchical syntax tree from parsed HEVC structures,
 //! suitable for UI display in tree views.
 
-use crate::{HevcStream, NalUnitType};
+use crate::{HevcStream, NalUnit, NalUnitType, ParsedSlice};
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
 
 /// A node in the HEVC syntax tree.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -109,27 +110,7 @@ pub fn build_syntax_tree(stream: &HevcStream) -> SyntaxNode {
         let mut nal_node = SyntaxNode::new(nal_name, SyntaxNodeType::NalUnit);
 
         // Add NAL header fields
-        let mut header_node = SyntaxNode::new("NAL Header", SyntaxNodeType::Structure);
-        header_node.add_child(SyntaxNode::field(
-            "nal_unit_type",
-            format!(
-                "{:?} ({})",
-                nal.header.nal_unit_type, nal.header.nal_unit_type as u8
-            ),
-        ));
-        header_node.add_child(SyntaxNode::field(
-            "nuh_layer_id",
-            nal.header.nuh_layer_id.to_string(),
-        ));
-        header_node.add_child(SyntaxNode::field(
-            "nuh_temporal_id_plus1",
-            nal.header.nuh_temporal_id_plus1.to_string(),
-        ));
-        header_node.add_child(SyntaxNode::field(
-            "temporal_id",
-            nal.header.temporal_id().to_string(),
-        ));
-        nal_node.add_child(header_node);
+        nal_node.add_child(build_nal_header_tree(nal));
 
         // Add parameter set details
         match nal.header.nal_unit_type {
@@ -163,48 +144,132 @@ pub fn build_syntax_tree(stream: &HevcStream) -> SyntaxNode {
     );
 
     for slice in &stream.slices {
-        let slice_name = format!(
-            "POC {} - {} slice",
-            slice.poc,
-            slice.header.slice_type.name()
-        );
-        let mut slice_node = SyntaxNode::new(slice_name, SyntaxNodeType::SliceHeader);
+        slices_node.add_child(build_slice_tree(slice));
+    }
 
-        slice_node.add_child(SyntaxNode::field("poc", slice.poc.to_string()));
-        slice_node.add_child(SyntaxNode::field(
-            "slice_type",
-            slice.header.slice_type.name().to_string(),
-        ));
-        slice_node.add_child(SyntaxNode::field(
-            "first_slice_segment_in_pic_flag",
-            slice.header.first_slice_segment_in_pic_flag.to_string(),
-        ));
-        slice_node.add_child(SyntaxNode::field(
-            "slice_qp_delta",
-            slice.header.slice_qp_delta.to_string(),
-        ));
+    root.add_child(slices_node);
 
-        if slice.header.slice_type.is_inter() {
-            slice_node.add_child(SyntaxNode::field(
-                "num_ref_idx_l0_active",
-                slice.header.num_ref_idx_l0_active().to_string(),
-            ));
-            if slice.header.slice_type == crate::SliceType::B {
-                slice_node.add_child(SyntaxNode::field(
-                    "num_ref_idx_l1_active",
-                    slice.header.num_ref_idx_l1_active().to_string(),
-                ));
+    root
+}
+
+/// Build a syntax tree for the NAL units of one access unit.
+///
+/// `nal_range` indexes `stream.nal_units`. Parameter sets are parsed from
+/// their own payload, and every NAL unit node carries its bit position in
+/// the parsed stream.
+pub fn build_access_unit_tree(stream: &HevcStream, nal_range: Range<usize>) -> SyntaxNode {
+    let mut root = SyntaxNode::new("Access Unit", SyntaxNodeType::Root);
+    let start = nal_range.start;
+
+    for (i, nal) in stream
+        .nal_units
+        .get(nal_range)
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+    {
+        let nal_index = start + i;
+        let mut nal_node = SyntaxNode::new(
+            format!("[{}] {}", nal_index, nal.header.nal_unit_type.name()),
+            SyntaxNodeType::NalUnit,
+        )
+        .with_position(nal.offset * 8, nal.size * 8);
+
+        nal_node.add_child(build_nal_header_tree(nal).with_position(nal.offset * 8, 16));
+
+        match nal.header.nal_unit_type {
+            NalUnitType::VpsNut => {
+                if let Ok(vps) = crate::vps::parse_vps(&nal.payload) {
+                    nal_node.add_child(build_vps_tree(&vps));
+                }
+            }
+            NalUnitType::SpsNut => {
+                if let Ok(sps) = crate::sps::parse_sps(&nal.payload) {
+                    nal_node.add_child(build_sps_tree(&sps));
+                }
+            }
+            NalUnitType::PpsNut => {
+                if let Ok(pps) = crate::pps::parse_pps(&nal.payload) {
+                    nal_node.add_child(build_pps_tree(&pps));
+                }
+            }
+            _ => {
+                if let Some(slice) = stream.slices.iter().find(|s| s.nal_index == nal_index) {
+                    nal_node.add_child(build_slice_tree(slice));
+                }
             }
         }
 
-        slices_node.add_child(slice_node);
+        root.add_child(nal_node);
     }
 
-    root.add_child(slices_node);
-
     root
 }
 
+/// Build syntax tree for a NAL unit header.
+fn build_nal_header_tree(nal: &NalUnit) -> SyntaxNode {
+    let mut header_node = SyntaxNode::new("NAL Header", SyntaxNodeType::Structure);
+    header_node.add_child(SyntaxNode::field(
+        "nal_unit_type",
+        format!(
+            "{:?} ({})",
+            nal.header.nal_unit_type, nal.header.nal_unit_type as u8
+        ),
+    ));
+    header_node.add_child(SyntaxNode::field(
+        "nuh_layer_id",
+        nal.header.nuh_layer_id.to_string(),
+    ));
+    header_node.add_child(SyntaxNode::field(
+        "nuh_temporal_id_plus1",
+        nal.header.nuh_temporal_id_plus1.to_string(),
+    ));
+    header_node.add_child(SyntaxNode::field(
+        "temporal_id",
+        nal.header.temporal_id().to_string(),
+    ));
+    header_node
+}
+
+/// Build syntax tree for a slice segment header.
+fn build_slice_tree(slice: &ParsedSlice) -> SyntaxNode {
+    let slice_name = format!(
+        "POC {} - {} slice",
+        slice.poc,
+        slice.header.slice_type.name()
+    );
+    let mut slice_node = SyntaxNode::new(slice_name, SyntaxNodeType::SliceHeader);
+
+    slice_node.add_child(SyntaxNode::field("poc", slice.poc.to_string()));
+    slice_node.add_child(SyntaxNode::field(
+        "slice_type",
+        slice.header.slice_type.name().to_string(),
+    ));
+    slice_node.add_child(SyntaxNode::field(
+        "first_slice_segment_in_pic_flag",
+        slice.header.first_slice_segment_in_pic_flag.to_string(),
+    ));
+    slice_node.add_child(SyntaxNode::field(
+        "slice_qp_delta",
+        slice.header.slice_qp_delta.to_string(),
+    ));
+
+    if slice.header.slice_type.is_inter() {
+        slice_node.add_child(SyntaxNode::field(
+            "num_ref_idx_l0_active",
+            slice.header.num_ref_idx_l0_active().to_string(),
+        ));
+        if slice.header.slice_type == crate::SliceType::B {
+            slice_node.add_child(SyntaxNode::field(
+                "num_ref_idx_l1_active",
+                slice.header.num_ref_idx_l1_active().to_string(),
+            ));
+        }
+    }
+
+    slice_node
+}
+
 /// Build syntax tree for VPS.
 fn build_vps_tree(vps: &crate::Vps) -> SyntaxNode {
     let mut node = SyntaxNode::new("Video Parameter Set", SyntaxNodeType::ParameterSet);
@@ -417,4 +482,18 @@ mod tests {
         assert_eq!(node.value, Some("1920".to_string()));
         assert_eq!(node.node_type, SyntaxNodeType::Field);
     }
+
+    #[test]
+    fn test_access_unit_tree_positions() {
+        // AUD (type 35) followed by EOS (type 36)
+        let data = [0, 0, 0, 1, 0x46, 0x01, 0x50, 0, 0, 0, 1, 0x48, 0x01];
+        let stream = crate::parse_hevc(&data).unwrap();
+
+        let tree = build_access_unit_tree(&stream, 0..stream.nal_units.len());
+        assert_eq!(tree.children.len(), 2);
+        let first = &tree.children[0];
+        assert_eq!(first.bit_offset, Some(stream.nal_units[0].offset * 8));
+        assert_eq!(first.children[0].name, "NAL Header");
+        assert_eq!(first.children[0].bit_length, Some(16));
+    }
 }
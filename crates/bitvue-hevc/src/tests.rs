@@ -346,6 +346,7 @@ fn test_hevc_stream_methods() {
         sps_map: std::collections::HashMap::new(),
         pps_map: std::collections::HashMap::new(),
         slices: vec![],
+        sei_messages: Vec::new(),
     };
 
     assert_eq!(stream.frame_count(), 0);
@@ -409,9 +410,13 @@ fn test_hevc_stream_with_sps() {
             amp_enabled_flag: false,
             sample_adaptive_offset_enabled_flag: false,
             pcm_enabled_flag: false,
+            pcm_parameters: None,
             num_short_term_ref_pic_sets: 0,
             long_term_ref_pics_present_flag: false,
             num_long_term_ref_pics_sps: 0,
+            st_ref_pic_sets: Vec::new(),
+            lt_ref_pic_poc_lsb_sps: Vec::new(),
+            used_by_curr_pic_lt_sps_flag: Vec::new(),
             sps_temporal_mvp_enabled_flag: false,
             strong_intra_smoothing_enabled_flag: false,
             vui_parameters_present_flag: false,
@@ -425,6 +430,7 @@ fn test_hevc_stream_with_sps() {
         sps_map,
         pps_map: std::collections::HashMap::new(),
         slices: vec![],
+        sei_messages: Vec::new(),
     };
 
     assert!(stream.dimensions().is_some());
@@ -667,6 +673,8 @@ fn test_hevc_frame_to_unit_node() {
         is_ref: true,
         temporal_id: Some(0),
         slice_header: None,
+        ref_pic_lists: Default::default(),
+        ref_frames: Vec::new(),
     };
 
     let node = hevc_frame_to_unit_node(&frame, 0);
@@ -693,6 +701,8 @@ fn test_hevc_frames_to_unit_nodes() {
             is_ref: true,
             temporal_id: Some(0),
             slice_header: None,
+            ref_pic_lists: Default::default(),
+            ref_frames: Vec::new(),
         },
         HevcFrame {
             frame_index: 1,
@@ -707,6 +717,8 @@ fn test_hevc_frames_to_unit_nodes() {
             is_ref: true,
             temporal_id: Some(0),
             slice_header: None,
+            ref_pic_lists: Default::default(),
+            ref_frames: Vec::new(),
         },
     ];
 
@@ -1645,15 +1657,20 @@ fn test_extract_mv_grid_with_empty_data() {
         amp_enabled_flag: false,
         sample_adaptive_offset_enabled_flag: false,
         pcm_enabled_flag: false,
+        pcm_parameters: None,
         num_short_term_ref_pic_sets: 0,
         long_term_ref_pics_present_flag: false,
         num_long_term_ref_pics_sps: 0,
+        st_ref_pic_sets: Vec::new(),
+        lt_ref_pic_poc_lsb_sps: Vec::new(),
+        used_by_curr_pic_lt_sps_flag: Vec::new(),
         sps_temporal_mvp_enabled_flag: false,
         strong_intra_smoothing_enabled_flag: false,
         vui_parameters_present_flag: false,
         vui_parameters: None,
     };
-    let result = extract_mv_grid(nal_units, &sps);
+    let pps = Pps::default();
+    let result = extract_mv_grid(nal_units, &sps, &pps);
     // Should handle gracefully - may return empty grid or error
     assert!(result.is_ok() || result.is_err());
 }
@@ -1707,16 +1724,21 @@ fn test_extract_qp_grid_with_empty_data() {
         amp_enabled_flag: false,
         sample_adaptive_offset_enabled_flag: false,
         pcm_enabled_flag: false,
+        pcm_parameters: None,
         num_short_term_ref_pic_sets: 0,
         long_term_ref_pics_present_flag: false,
         num_long_term_ref_pics_sps: 0,
+        st_ref_pic_sets: Vec::new(),
+        lt_ref_pic_poc_lsb_sps: Vec::new(),
+        used_by_curr_pic_lt_sps_flag: Vec::new(),
         sps_temporal_mvp_enabled_flag: false,
         strong_intra_smoothing_enabled_flag: false,
         vui_parameters_present_flag: false,
         vui_parameters: None,
     };
-    let result = extract_qp_grid(nal_units, &sps, 26); // Valid QP value
-                                                       // Empty data should return error or empty grid
+    let pps = Pps::default();
+    let result = extract_qp_grid(nal_units, &sps, &pps, 26); // Valid QP value
+                                                             // Empty data should return error or empty grid
     assert!(result.is_ok() || result.is_err());
 }
 
@@ -1756,3 +1778,131 @@ fn test_find_nal_units_with_no_start_codes() {
     let units = find_nal_units(&data);
     assert_eq!(units.len(), 0);
 }
+
+#[test]
+fn test_parse_hevc_prefix_sei_stream_metadata() {
+    // Prefix SEI NAL (type 39) carrying a content light level message
+    let data = [
+        0x00, 0x00, 0x00, 0x01, 0x4E, 0x01, // start code + NAL header
+        0x90, 0x04, 0x03, 0xE8, 0x01, 0x90, 0x80,
+    ];
+    let stream = parse_hevc(&data).unwrap();
+    assert_eq!(stream.sei_messages.len(), 1);
+    assert!(!stream.sei_messages[0].suffix);
+
+    let metadata = stream.stream_metadata();
+    let cll = metadata.content_light_level.unwrap();
+    assert_eq!((cll.max_cll, cll.max_fall), (1000, 400));
+    assert_eq!(metadata.sei_messages.len(), 1);
+    assert_eq!(metadata.sei_messages[0].frame_idx, Some(0));
+}
+
+#[test]
+fn test_hrd_timings_from_signalled_sei() {
+    use crate::sei::{BufferingPeriod, InitialCpbRemoval, PicTiming};
+    use crate::sps::VuiParameters;
+    use crate::vps::{CpbSpec, HrdParameters, SubLayerHrd};
+
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .and_then(|p| p.parent())
+        .unwrap()
+        .join("samples/foreman_hevc.265");
+    let Ok(data) = std::fs::read(&path) else {
+        eprintln!("Skipping test: {} not found", path.display());
+        return;
+    };
+    let mut stream = parse_hevc(&data).unwrap();
+    assert!(stream.hrd_model().is_none());
+
+    // 25 fps, 1 Mbps VBR with a 2 Mbit CPB
+    let hrd = HrdParameters {
+        nal_hrd_parameters_present_flag: true,
+        bit_rate_scale: 0,
+        cpb_size_scale: 0,
+        sub_layers: vec![SubLayerHrd {
+            nal_cpbs: vec![CpbSpec {
+                bit_rate_value_minus1: (1_000_000 >> 6) - 1,
+                cpb_size_value_minus1: (2_000_000 >> 4) - 1,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    for sps in stream.sps_map.values_mut() {
+        sps.vui_parameters = Some(VuiParameters {
+            timing_info_present_flag: true,
+            num_units_in_tick: Some(1),
+            time_scale: Some(25),
+            hrd_parameters_present_flag: true,
+            hrd_parameters: Some(hrd.clone()),
+            ..Default::default()
+        });
+    }
+
+    // Buffering period on the first picture (0.5s initial delay), then one
+    // picture timing SEI per picture; each message takes the NAL index of
+    // the slice it precedes
+    let first_slices: Vec<usize> = stream
+        .slices
+        .iter()
+        .filter(|s| s.header.first_slice_segment_in_pic_flag)
+        .map(|s| s.nal_index)
+        .collect();
+    let sei = |nal_index: usize, parsed: SeiParsedData| ParsedSei {
+        nal_index,
+        suffix: false,
+        message: SeiMessage {
+            payload_type: SeiPayloadType::PicTiming,
+            payload_type_raw: 1,
+            payload_size: 0,
+            payload: Vec::new(),
+            parsed: Some(parsed),
+        },
+    };
+    stream.sei_messages = vec![sei(
+        first_slices[0],
+        SeiParsedData::BufferingPeriod(BufferingPeriod {
+            nal_initial_cpb_removal: vec![InitialCpbRemoval {
+                initial_cpb_removal_delay: 45000,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }),
+    )];
+    for (n, &nal_index) in first_slices.iter().enumerate().skip(1) {
+        stream.sei_messages.push(sei(
+            nal_index,
+            SeiParsedData::PicTiming(PicTiming {
+                au_cpb_removal_delay_minus1: Some(n as u32 - 1),
+                pic_dpb_output_delay: Some(2),
+                ..Default::default()
+            }),
+        ));
+    }
+
+    let params = stream.hrd_parameters().unwrap();
+    assert_eq!(params.bit_rate_bps, 1_000_000 >> 6 << 6);
+    assert_eq!(params.initial_cpb_removal_delay, 45000);
+    assert!(!params.cbr_flag);
+
+    let timings = stream.hrd_timings(&params);
+    assert_eq!(timings.len(), stream.frame_count());
+    assert_eq!(timings[0].dts_sec, Some(0.5));
+    for (n, timing) in timings.iter().enumerate().skip(1) {
+        let dts = timing.dts_sec.unwrap();
+        assert!((dts - (0.5 + 0.04 * n as f64)).abs() < 1e-9);
+        assert!((timing.pts_sec - (dts + 0.08)).abs() < 1e-9);
+        assert_eq!(timing.cpb_removal_delay, Some(3600 * n as u64));
+    }
+    let total: u64 = stream.nal_units.iter().map(|n| n.size * 8).sum();
+    assert_eq!(
+        timings.iter().map(|t| t.frame_size_bits).sum::<u64>(),
+        total
+    );
+
+    let model = stream.hrd_model().unwrap();
+    assert_eq!(model.frame_count(), timings.len());
+    assert_eq!(model.overflow_count(), 0);
+}
@@ -4,7 +4,7 @@
 //! It is defined in ITU-T H.265 Section 7.3.2.1.
 
 use crate::bitreader::BitReader;
-use crate::error::Result;
+use crate::error::{HevcError, Result};
 use serde::{Deserialize, Serialize};
 
 /// Timing information for VPS/SPS.
@@ -20,25 +20,174 @@ pub struct TimingInfo {
     pub num_ticks_poc_diff_one_minus1: u32,
 }
 
-/// HRD (Hypothetical Reference Decoder) parameters.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// CPB specification for one SchedSelIdx (E.2.3).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpbSpec {
+    pub bit_rate_value_minus1: u32,
+    pub cpb_size_value_minus1: u32,
+    /// Present with sub-picture HRD parameters.
+    pub cpb_size_du_value_minus1: Option<u32>,
+    /// Present with sub-picture HRD parameters.
+    pub bit_rate_du_value_minus1: Option<u32>,
+    pub cbr_flag: bool,
+}
+
+/// HRD parameters of one temporal sub-layer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubLayerHrd {
+    pub fixed_pic_rate_general_flag: bool,
+    pub fixed_pic_rate_within_cvs_flag: bool,
+    pub elemental_duration_in_tc_minus1: Option<u32>,
+    pub low_delay_hrd_flag: bool,
+    pub cpb_cnt_minus1: u32,
+    /// NAL HRD CPB specifications, one per SchedSelIdx.
+    pub nal_cpbs: Vec<CpbSpec>,
+    /// VCL HRD CPB specifications, one per SchedSelIdx.
+    pub vcl_cpbs: Vec<CpbSpec>,
+}
+
+/// HRD (Hypothetical Reference Decoder) parameters (E.2.2).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HrdParameters {
     /// NAL HRD parameters present.
     pub nal_hrd_parameters_present_flag: bool,
     /// VCL HRD parameters present.
     pub vcl_hrd_parameters_present_flag: bool,
-    /// Sub-picture CPB parameters present.
-    pub sub_pic_cpb_params_present_flag: bool,
+    /// Sub-picture (decoding unit) HRD parameters present.
+    pub sub_pic_hrd_params_present_flag: bool,
+    pub tick_divisor_minus2: u8,
+    pub du_cpb_removal_delay_increment_length_minus1: u8,
+    pub sub_pic_cpb_params_in_pic_timing_sei_flag: bool,
+    pub dpb_output_delay_du_length_minus1: u8,
     /// Bit rate scale.
     pub bit_rate_scale: u8,
     /// CPB size scale.
     pub cpb_size_scale: u8,
+    pub cpb_size_du_scale: u8,
     /// Initial CPB removal delay length.
     pub initial_cpb_removal_delay_length_minus1: u8,
     /// AU CPB removal delay length.
     pub au_cpb_removal_delay_length_minus1: u8,
     /// DPB output delay length.
     pub dpb_output_delay_length_minus1: u8,
+    /// Per sub-layer parameters, indexed by TemporalId.
+    pub sub_layers: Vec<SubLayerHrd>,
+}
+
+impl HrdParameters {
+    /// BitRate[SchedSelIdx] in bits per second (E-46).
+    pub fn bit_rate(&self, cpb: &CpbSpec) -> u64 {
+        (cpb.bit_rate_value_minus1 as u64 + 1) << (6 + self.bit_rate_scale)
+    }
+
+    /// CpbSize[SchedSelIdx] in bits (E-47).
+    pub fn cpb_size(&self, cpb: &CpbSpec) -> u64 {
+        (cpb.cpb_size_value_minus1 as u64 + 1) << (4 + self.cpb_size_scale)
+    }
+
+    /// Parameters of the highest sub-layer, which apply to the whole stream.
+    pub fn highest_sub_layer(&self) -> Option<&SubLayerHrd> {
+        self.sub_layers.last()
+    }
+}
+
+/// Parse hrd_parameters() (E.2.2).
+pub fn parse_hrd_parameters(
+    reader: &mut BitReader,
+    common_inf_present_flag: bool,
+    max_sub_layers_minus1: u8,
+) -> Result<HrdParameters> {
+    // Lengths are inferred to be 23 bits when not signalled
+    let mut hrd = HrdParameters {
+        initial_cpb_removal_delay_length_minus1: 23,
+        au_cpb_removal_delay_length_minus1: 23,
+        dpb_output_delay_length_minus1: 23,
+        ..Default::default()
+    };
+
+    if common_inf_present_flag {
+        hrd.nal_hrd_parameters_present_flag = reader.read_bit()?;
+        hrd.vcl_hrd_parameters_present_flag = reader.read_bit()?;
+        if hrd.nal_hrd_parameters_present_flag || hrd.vcl_hrd_parameters_present_flag {
+            hrd.sub_pic_hrd_params_present_flag = reader.read_bit()?;
+            if hrd.sub_pic_hrd_params_present_flag {
+                hrd.tick_divisor_minus2 = reader.read_bits(8)? as u8;
+                hrd.du_cpb_removal_delay_increment_length_minus1 = reader.read_bits(5)? as u8;
+                hrd.sub_pic_cpb_params_in_pic_timing_sei_flag = reader.read_bit()?;
+                hrd.dpb_output_delay_du_length_minus1 = reader.read_bits(5)? as u8;
+            }
+            hrd.bit_rate_scale = reader.read_bits(4)? as u8;
+            hrd.cpb_size_scale = reader.read_bits(4)? as u8;
+            if hrd.sub_pic_hrd_params_present_flag {
+                hrd.cpb_size_du_scale = reader.read_bits(4)? as u8;
+            }
+            hrd.initial_cpb_removal_delay_length_minus1 = reader.read_bits(5)? as u8;
+            hrd.au_cpb_removal_delay_length_minus1 = reader.read_bits(5)? as u8;
+            hrd.dpb_output_delay_length_minus1 = reader.read_bits(5)? as u8;
+        }
+    }
+
+    for _ in 0..=max_sub_layers_minus1 {
+        let mut sub_layer = SubLayerHrd {
+            fixed_pic_rate_general_flag: reader.read_bit()?,
+            ..Default::default()
+        };
+        sub_layer.fixed_pic_rate_within_cvs_flag = if sub_layer.fixed_pic_rate_general_flag {
+            true
+        } else {
+            reader.read_bit()?
+        };
+        if sub_layer.fixed_pic_rate_within_cvs_flag {
+            sub_layer.elemental_duration_in_tc_minus1 = Some(reader.read_ue()?);
+        } else {
+            sub_layer.low_delay_hrd_flag = reader.read_bit()?;
+        }
+        if !sub_layer.low_delay_hrd_flag {
+            sub_layer.cpb_cnt_minus1 = reader.read_ue()?;
+            if sub_layer.cpb_cnt_minus1 > 31 {
+                return Err(HevcError::InvalidData(format!(
+                    "cpb_cnt_minus1 {} exceeds 31",
+                    sub_layer.cpb_cnt_minus1
+                )));
+            }
+        }
+        if hrd.nal_hrd_parameters_present_flag {
+            sub_layer.nal_cpbs = parse_sub_layer_hrd_parameters(reader, &hrd, &sub_layer)?;
+        }
+        if hrd.vcl_hrd_parameters_present_flag {
+            sub_layer.vcl_cpbs = parse_sub_layer_hrd_parameters(reader, &hrd, &sub_layer)?;
+        }
+        hrd.sub_layers.push(sub_layer);
+    }
+
+    Ok(hrd)
+}
+
+/// Parse sub_layer_hrd_parameters() (E.2.3).
+fn parse_sub_layer_hrd_parameters(
+    reader: &mut BitReader,
+    hrd: &HrdParameters,
+    sub_layer: &SubLayerHrd,
+) -> Result<Vec<CpbSpec>> {
+    (0..=sub_layer.cpb_cnt_minus1)
+        .map(|_| {
+            let bit_rate_value_minus1 = reader.read_ue()?;
+            let cpb_size_value_minus1 = reader.read_ue()?;
+            let (cpb_size_du_value_minus1, bit_rate_du_value_minus1) =
+                if hrd.sub_pic_hrd_params_present_flag {
+                    (Some(reader.read_ue()?), Some(reader.read_ue()?))
+                } else {
+                    (None, None)
+                };
+            Ok(CpbSpec {
+                bit_rate_value_minus1,
+                cpb_size_value_minus1,
+                cpb_size_du_value_minus1,
+                bit_rate_du_value_minus1,
+                cbr_flag: reader.read_bit()?,
+            })
+        })
+        .collect()
 }
 
 /// Profile, tier, and level for a sub-layer.
@@ -93,6 +242,10 @@ pub struct Vps {
     pub timing_info: Option<TimingInfo>,
     /// Number of HRD parameters.
     pub vps_num_hrd_parameters: u16,
+    /// Layer set each HRD parameter set applies to.
+    pub hrd_layer_set_idx: Vec<u32>,
+    /// HRD parameters, one per vps_num_hrd_parameters.
+    pub hrd_parameters: Vec<HrdParameters>,
 }
 
 /// Profile, tier, and level from VPS.
@@ -136,6 +289,8 @@ impl Default for Vps {
             vps_timing_info_present_flag: false,
             timing_info: None,
             vps_num_hrd_parameters: 0,
+            hrd_layer_set_idx: Vec::new(),
+            hrd_parameters: Vec::new(),
         }
     }
 }
@@ -275,8 +430,26 @@ pub fn parse_vps(data: &[u8]) -> Result<Vps> {
         // vps_num_hrd_parameters (ue(v))
         vps.vps_num_hrd_parameters = reader.read_ue()? as u16;
 
-        // Skip HRD parameters for now
-        // Full implementation would parse hrd_layer_set_idx and hrd_parameters
+        for i in 0..vps.vps_num_hrd_parameters {
+            vps.hrd_layer_set_idx.push(reader.read_ue()?);
+            // cprms_present_flag[0] is inferred to be 1
+            let cprms_present_flag = i == 0 || reader.read_bit()?;
+            let mut hrd = parse_hrd_parameters(
+                &mut reader,
+                cprms_present_flag,
+                vps.vps_max_sub_layers_minus1,
+            )?;
+            if !cprms_present_flag {
+                // Common information is inherited from the previous entry
+                if let Some(prev) = vps.hrd_parameters.last() {
+                    hrd = HrdParameters {
+                        sub_layers: hrd.sub_layers,
+                        ..prev.clone()
+                    };
+                }
+            }
+            vps.hrd_parameters.push(hrd);
+        }
 
         vps.timing_info = Some(timing);
     }
@@ -390,4 +563,48 @@ mod tests {
         vps.profile_tier_level.general_profile_idc = 2;
         assert_eq!(vps.profile_name(), "Main 10");
     }
+
+    #[test]
+    fn test_parse_hrd_parameters() {
+        // NAL HRD only, bit_rate_scale 2, cpb_size_scale 3, 24-bit delays,
+        // fixed picture rate, one CPB with bit_rate_value_minus1 1,
+        // cpb_size_value_minus1 2 and cbr_flag
+        let mut bits = String::from("10");
+        bits += "0";
+        bits += "0010";
+        bits += "0011";
+        bits += "101111011110111";
+        bits += "1";
+        bits += "1";
+        bits += "1";
+        bits += "010";
+        bits += "011";
+        bits += "1";
+        while bits.len() % 8 != 0 {
+            bits.push('0');
+        }
+        let data: Vec<u8> = bits
+            .as_bytes()
+            .chunks(8)
+            .map(|c| u8::from_str_radix(std::str::from_utf8(c).unwrap(), 2).unwrap())
+            .collect();
+
+        let mut reader = BitReader::new(&data);
+        let hrd = parse_hrd_parameters(&mut reader, true, 0).unwrap();
+        assert!(hrd.nal_hrd_parameters_present_flag);
+        assert!(!hrd.vcl_hrd_parameters_present_flag);
+        assert_eq!(hrd.initial_cpb_removal_delay_length_minus1, 23);
+        assert_eq!(hrd.au_cpb_removal_delay_length_minus1, 23);
+        assert_eq!(hrd.dpb_output_delay_length_minus1, 23);
+
+        let sub_layer = hrd.highest_sub_layer().unwrap();
+        assert!(sub_layer.fixed_pic_rate_within_cvs_flag);
+        assert_eq!(sub_layer.elemental_duration_in_tc_minus1, Some(0));
+        assert_eq!(sub_layer.nal_cpbs.len(), 1);
+        assert!(sub_layer.vcl_cpbs.is_empty());
+        let cpb = &sub_layer.nal_cpbs[0];
+        assert!(cpb.cbr_flag);
+        assert_eq!(hrd.bit_rate(cpb), 2 << 8);
+        assert_eq!(hrd.cpb_size(cpb), 3 << 7);
+    }
 }
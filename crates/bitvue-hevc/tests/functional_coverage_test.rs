@@ -292,6 +292,7 @@ fn test_stream_dimensions() {
         sps_map: std::collections::HashMap::new(),
         pps_map: std::collections::HashMap::new(),
         slices: vec![],
+        sei_messages: Vec::new(),
     };
 
     assert!(stream.dimensions().is_none());
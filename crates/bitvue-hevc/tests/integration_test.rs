@@ -124,16 +124,22 @@ fn test_hevc_overlay_extraction() {
             .find(|nal| nal.header.nal_unit_type == NalUnitType::SpsNut)
         {
             if let Ok(sps) = bitvue_hevc::sps::parse_sps(&nal_with_sps.payload) {
+                let pps = nal_units
+                    .iter()
+                    .find(|nal| nal.header.nal_unit_type == NalUnitType::PpsNut)
+                    .and_then(|nal| bitvue_hevc::parse_pps(&nal.payload).ok())
+                    .unwrap_or_default();
+
                 // Test QP grid extraction (should not crash, may return scaffold data)
-                let qp_result = bitvue_hevc::extract_qp_grid(&nal_units, &sps, 26);
+                let qp_result = bitvue_hevc::extract_qp_grid(&nal_units, &sps, &pps, 26);
                 assert!(qp_result.is_ok(), "QP grid extraction should not crash");
 
                 // Test MV grid extraction
-                let mv_result = bitvue_hevc::extract_mv_grid(&nal_units, &sps);
+                let mv_result = bitvue_hevc::extract_mv_grid(&nal_units, &sps, &pps);
                 assert!(mv_result.is_ok(), "MV grid extraction should not crash");
 
                 // Test partition grid extraction
-                let part_result = bitvue_hevc::extract_partition_grid(&nal_units, &sps);
+                let part_result = bitvue_hevc::extract_partition_grid(&nal_units, &sps, &pps);
                 assert!(
                     part_result.is_ok(),
                     "Partition grid extraction should not crash"
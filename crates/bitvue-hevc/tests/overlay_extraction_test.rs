@@ -16,6 +16,7 @@
 use bitvue_core::{partition_grid::PartitionType, BlockMode};
 use bitvue_hevc::overlay_extraction;
 use bitvue_hevc::sps::{ChromaFormat, Profile, ProfileTierLevel, Sps};
+use bitvue_hevc::Pps;
 
 fn create_minimal_sps() -> Sps {
     Sps {
@@ -60,9 +61,13 @@ fn create_minimal_sps() -> Sps {
         amp_enabled_flag: false,
         sample_adaptive_offset_enabled_flag: false,
         pcm_enabled_flag: false,
+        pcm_parameters: None,
         num_short_term_ref_pic_sets: 0,
         long_term_ref_pics_present_flag: false,
         num_long_term_ref_pics_sps: 0,
+        st_ref_pic_sets: Vec::new(),
+        lt_ref_pic_poc_lsb_sps: Vec::new(),
+        used_by_curr_pic_lt_sps_flag: Vec::new(),
         sps_temporal_mvp_enabled_flag: false,
         strong_intra_smoothing_enabled_flag: false,
         vui_parameters_present_flag: false,
@@ -74,9 +79,10 @@ fn create_minimal_sps() -> Sps {
 fn test_extract_qp_grid_basic() {
     // Test QP grid extraction with minimal SPS
     let sps = create_minimal_sps();
+    let pps = Pps::default();
 
     let nal_units = [];
-    let result = overlay_extraction::extract_qp_grid(&nal_units, &sps, 26);
+    let result = overlay_extraction::extract_qp_grid(&nal_units, &sps, &pps, 26);
 
     assert!(result.is_ok());
 
@@ -87,9 +93,10 @@ fn test_extract_qp_grid_basic() {
 #[test]
 fn test_extract_mv_grid_basic() {
     let sps = create_minimal_sps();
+    let pps = Pps::default();
 
     let nal_units = [];
-    let result = overlay_extraction::extract_mv_grid(&nal_units, &sps);
+    let result = overlay_extraction::extract_mv_grid(&nal_units, &sps, &pps);
 
     assert!(result.is_ok());
 
@@ -100,9 +107,10 @@ fn test_extract_mv_grid_basic() {
 #[test]
 fn test_extract_partition_grid_basic() {
     let sps = create_minimal_sps();
+    let pps = Pps::default();
 
     let nal_units = [];
-    let result = overlay_extraction::extract_partition_grid(&nal_units, &sps);
+    let result = overlay_extraction::extract_partition_grid(&nal_units, &sps, &pps);
 
     assert!(result.is_ok());
 
@@ -179,18 +187,20 @@ fn test_partition_type_variants() {
 #[test]
 fn test_qp_grid_with_keyframe() {
     let sps = create_minimal_sps();
+    let pps = Pps::default();
 
     let nal_units = [];
-    let result = overlay_extraction::extract_qp_grid(&nal_units, &sps, 80);
+    let result = overlay_extraction::extract_qp_grid(&nal_units, &sps, &pps, 80);
     assert!(result.is_ok());
 }
 
 #[test]
 fn test_qp_grid_with_interframe() {
     let sps = create_minimal_sps();
+    let pps = Pps::default();
 
     let nal_units = [];
-    let result = overlay_extraction::extract_qp_grid(&nal_units, &sps, 120);
+    let result = overlay_extraction::extract_qp_grid(&nal_units, &sps, &pps, 120);
     assert!(result.is_ok());
 }
 
@@ -205,18 +215,19 @@ fn test_various_resolutions() {
 
     for (width, height) in resolutions {
         let mut sps = create_minimal_sps();
+        let pps = Pps::default();
         sps.pic_width_in_luma_samples = width;
         sps.pic_height_in_luma_samples = height;
 
         let nal_units = [];
 
-        let qp_result = overlay_extraction::extract_qp_grid(&nal_units, &sps, 100);
+        let qp_result = overlay_extraction::extract_qp_grid(&nal_units, &sps, &pps, 100);
         assert!(qp_result.is_ok());
 
-        let mv_result = overlay_extraction::extract_mv_grid(&nal_units, &sps);
+        let mv_result = overlay_extraction::extract_mv_grid(&nal_units, &sps, &pps);
         assert!(mv_result.is_ok());
 
-        let part_result = overlay_extraction::extract_partition_grid(&nal_units, &sps);
+        let part_result = overlay_extraction::extract_partition_grid(&nal_units, &sps, &pps);
         assert!(part_result.is_ok());
     }
 }
@@ -227,9 +238,10 @@ fn test_qp_range() {
 
     for base_qp in qp_values {
         let sps = create_minimal_sps();
+        let pps = Pps::default();
         let nal_units = [];
 
-        let result = overlay_extraction::extract_qp_grid(&nal_units, &sps, base_qp);
+        let result = overlay_extraction::extract_qp_grid(&nal_units, &sps, &pps, base_qp);
         assert!(result.is_ok());
     }
 }
@@ -380,9 +392,10 @@ fn test_qp_values_valid_range() {
 
     for qp in qp_values {
         let sps = create_minimal_sps();
+        let pps = Pps::default();
         let nal_units = [];
 
-        let result = overlay_extraction::extract_qp_grid(&nal_units, &sps, qp);
+        let result = overlay_extraction::extract_qp_grid(&nal_units, &sps, &pps, qp);
         assert!(result.is_ok());
     }
 }
@@ -489,6 +502,12 @@ fn test_cu_size_variants() {
             ref_idx_l1: None,
             transform_size: 4,
             depth: 0,
+            intra_modes: vec![],
+            intra_chroma_mode: None,
+            transquant_bypass: false,
+            pcm: false,
+            prediction_units: vec![],
+            transform_units: vec![],
         };
 
         assert_eq!(cu.size, size);
@@ -514,6 +533,12 @@ fn test_cu_depth_values() {
             ref_idx_l1: None,
             transform_size: 4,
             depth,
+            intra_modes: vec![],
+            intra_chroma_mode: None,
+            transquant_bypass: false,
+            pcm: false,
+            prediction_units: vec![],
+            transform_units: vec![],
         };
 
         assert_eq!(cu.depth, depth);
@@ -539,6 +564,12 @@ fn test_cu_positions() {
             ref_idx_l1: None,
             transform_size: 4,
             depth: 0,
+            intra_modes: vec![],
+            intra_chroma_mode: None,
+            transquant_bypass: false,
+            pcm: false,
+            prediction_units: vec![],
+            transform_units: vec![],
         };
 
         assert_eq!(cu.x, x);
@@ -564,6 +595,12 @@ fn test_cu_with_motion_vector() {
         ref_idx_l1: None,
         transform_size: 8,
         depth: 0,
+        intra_modes: vec![],
+        intra_chroma_mode: None,
+        transquant_bypass: false,
+        pcm: false,
+        prediction_units: vec![],
+        transform_units: vec![],
     };
 
     assert!(cu.mv_l0.is_some());
@@ -586,6 +623,12 @@ fn test_cu_skip_mode() {
         ref_idx_l1: None,
         transform_size: 4,
         depth: 0,
+        intra_modes: vec![],
+        intra_chroma_mode: None,
+        transquant_bypass: false,
+        pcm: false,
+        prediction_units: vec![],
+        transform_units: vec![],
     };
 
     assert_eq!(cu.pred_mode, overlay_extraction::PredMode::Skip);
@@ -608,6 +651,12 @@ fn test_cu_intra_mode() {
         ref_idx_l1: None,
         transform_size: 4,
         depth: 1,
+        intra_modes: vec![],
+        intra_chroma_mode: None,
+        transquant_bypass: false,
+        pcm: false,
+        prediction_units: vec![],
+        transform_units: vec![],
     };
 
     assert_eq!(cu.pred_mode, overlay_extraction::PredMode::Intra);
@@ -637,9 +686,64 @@ fn test_ctu_add_cu() {
         ref_idx_l1: None,
         transform_size: 4,
         depth: 0,
+        intra_modes: vec![],
+        intra_chroma_mode: None,
+        transquant_bypass: false,
+        pcm: false,
+        prediction_units: vec![],
+        transform_units: vec![],
     };
 
     ctu.coding_units.push(cu);
 
     assert_eq!(ctu.coding_units.len(), 1);
 }
+
+#[test]
+fn test_extract_grids_from_foreman_idr() {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .and_then(|p| p.parent())
+        .unwrap()
+        .join("samples/foreman_hevc.265");
+    let Ok(data) = std::fs::read(&path) else {
+        eprintln!("Skipping test: {} not found", path.display());
+        return;
+    };
+    let stream = bitvue_hevc::parse_hevc(&data).unwrap();
+    let sps = stream.sps_map.values().next().unwrap();
+    let pps = stream.pps_map.values().next().unwrap();
+    // Parameter sets and the IDR picture
+    let idr = stream.slices[0].nal_index;
+    let nal_units = &stream.nal_units[..=idr];
+
+    let qp_grid = overlay_extraction::extract_qp_grid(nal_units, sps, pps, 0).unwrap();
+    assert_eq!((qp_grid.grid_w, qp_grid.grid_h), (44, 36));
+    assert!(qp_grid.qp.iter().all(|&qp| qp > 0 && qp <= 51));
+
+    let mv_grid = overlay_extraction::extract_mv_grid(nal_units, sps, pps).unwrap();
+    let modes = mv_grid.mode.as_ref().unwrap();
+    assert!(modes.iter().all(|&mode| mode == BlockMode::Intra));
+
+    let partition_grid = overlay_extraction::extract_partition_grid(nal_units, sps, pps).unwrap();
+    let area: u32 = partition_grid
+        .blocks
+        .iter()
+        .map(|block| block.width * block.height)
+        .sum();
+    assert_eq!(area, 352 * 288);
+    assert!(partition_grid.blocks.iter().any(|block| block.depth > 0));
+
+    // Coefficients are only decoded for transform units with a coded block flag
+    let ctus = overlay_extraction::extract_coding_tree_units(nal_units, sps, pps).unwrap();
+    let tus: Vec<_> = ctus
+        .iter()
+        .flat_map(|ctu| &ctu.coding_units)
+        .flat_map(|cu| &cu.transform_units)
+        .collect();
+    assert!(tus.iter().any(|tu| tu.coeff_count > 0));
+    assert!(tus
+        .iter()
+        .filter(|tu| !(tu.cbf_luma || tu.cbf_cb || tu.cbf_cr))
+        .all(|tu| tu.coeff_count == 0));
+}
@@ -66,9 +66,13 @@ fn create_test_sps() -> Sps {
         amp_enabled_flag: false,
         sample_adaptive_offset_enabled_flag: false,
         pcm_enabled_flag: false,
+        pcm_parameters: None,
         num_short_term_ref_pic_sets: 0,
         long_term_ref_pics_present_flag: false,
         num_long_term_ref_pics_sps: 0,
+        st_ref_pic_sets: Vec::new(),
+        lt_ref_pic_poc_lsb_sps: Vec::new(),
+        used_by_curr_pic_lt_sps_flag: Vec::new(),
         sps_temporal_mvp_enabled_flag: false,
         strong_intra_smoothing_enabled_flag: false,
         vui_parameters_present_flag: false,
@@ -495,6 +495,7 @@ fn test_sps_all_false_flags() {
         amp_enabled_flag: false,
         sample_adaptive_offset_enabled_flag: false,
         pcm_enabled_flag: false,
+        pcm_parameters: None,
         long_term_ref_pics_present_flag: false,
         sps_temporal_mvp_enabled_flag: false,
         strong_intra_smoothing_enabled_flag: false,
@@ -517,6 +518,7 @@ fn test_sps_all_true_flags() {
         amp_enabled_flag: true,
         sample_adaptive_offset_enabled_flag: true,
         pcm_enabled_flag: true,
+        pcm_parameters: None,
         long_term_ref_pics_present_flag: true,
         sps_temporal_mvp_enabled_flag: true,
         strong_intra_smoothing_enabled_flag: true,
@@ -611,9 +613,13 @@ fn default_sps() -> Sps {
         amp_enabled_flag: false,
         sample_adaptive_offset_enabled_flag: false,
         pcm_enabled_flag: false,
+        pcm_parameters: None,
         num_short_term_ref_pic_sets: 0,
         long_term_ref_pics_present_flag: false,
         num_long_term_ref_pics_sps: 0,
+        st_ref_pic_sets: Vec::new(),
+        lt_ref_pic_poc_lsb_sps: Vec::new(),
+        used_by_curr_pic_lt_sps_flag: Vec::new(),
         sps_temporal_mvp_enabled_flag: false,
         strong_intra_smoothing_enabled_flag: false,
         vui_parameters_present_flag: false,
@@ -64,9 +64,13 @@ fn create_minimal_sps() -> Sps {
         amp_enabled_flag: false,
         sample_adaptive_offset_enabled_flag: false,
         pcm_enabled_flag: false,
+        pcm_parameters: None,
         num_short_term_ref_pic_sets: 0,
         long_term_ref_pics_present_flag: false,
         num_long_term_ref_pics_sps: 0,
+        st_ref_pic_sets: Vec::new(),
+        lt_ref_pic_poc_lsb_sps: Vec::new(),
+        used_by_curr_pic_lt_sps_flag: Vec::new(),
         sps_temporal_mvp_enabled_flag: false,
         strong_intra_smoothing_enabled_flag: false,
         vui_parameters_present_flag: false,
@@ -400,6 +404,7 @@ fn test_sps_transform_depths() {
 fn test_sps_pcm_enabled_flag() {
     let sps = Sps {
         pcm_enabled_flag: true,
+        pcm_parameters: None,
         ..create_minimal_sps()
     };
 
@@ -421,6 +426,9 @@ fn test_sps_long_term_ref_pics_flag() {
     let sps = Sps {
         long_term_ref_pics_present_flag: true,
         num_long_term_ref_pics_sps: 2,
+        st_ref_pic_sets: Vec::new(),
+        lt_ref_pic_poc_lsb_sps: Vec::new(),
+        used_by_curr_pic_lt_sps_flag: Vec::new(),
         ..create_minimal_sps()
     };
 
@@ -48,6 +48,8 @@ fn create_test_vps() -> Vps {
         vps_timing_info_present_flag: false,
         timing_info: None,
         vps_num_hrd_parameters: 0,
+        hrd_layer_set_idx: Vec::new(),
+        hrd_parameters: Vec::new(),
     }
 }
 
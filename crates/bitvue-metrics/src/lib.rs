@@ -81,28 +81,7 @@ use rayon::prelude::*;
 /// where MAX = 255 for 8-bit images
 /// and MSE = Mean Squared Error
 pub fn psnr(reference: &[u8], distorted: &[u8], width: usize, height: usize) -> Result<f64> {
-    // Validate dimensions are reasonable (max 16K to prevent overflow)
-    const MAX_DIMENSION: usize = 15360;
-    if width > MAX_DIMENSION || height > MAX_DIMENSION {
-        return Err(BitvueError::InvalidData(format!(
-            "Dimensions exceed maximum: {}x{} (max {}x{})",
-            width, height, MAX_DIMENSION, MAX_DIMENSION
-        )));
-    }
-
-    // Prevent overflow in width * height calculation
-    let size = width.checked_mul(height).ok_or_else(|| {
-        BitvueError::InvalidData(format!("Width * height overflow: {} * {}", width, height))
-    })?;
-
-    if reference.len() != size || distorted.len() != size {
-        return Err(BitvueError::InvalidData(format!(
-            "Image size mismatch: expected {}, got {} and {}",
-            size,
-            reference.len(),
-            distorted.len()
-        )));
-    }
+    check_plane_sizes(reference.len(), distorted.len(), width, height)?;
 
     // Use SIMD-optimized PSNR by default with runtime CPU feature detection
     // Falls back to scalar implementation automatically if SIMD not available
@@ -137,6 +116,89 @@ pub fn psnr(reference: &[u8], distorted: &[u8], width: usize, height: usize) ->
 /// - σxy = covariance of x and y
 /// - C1, C2 = stabilization constants
 pub fn ssim(reference: &[u8], distorted: &[u8], width: usize, height: usize) -> Result<f64> {
+    check_plane_sizes(reference.len(), distorted.len(), width, height)?;
+
+    // Dynamic range for 8-bit
+    ssim_windows(width, height, 255.0, |start, end| {
+        simd::compute_window_stats_simd(reference, distorted, start, end)
+    })
+}
+
+/// Peak sample value for a bit depth, e.g. 1023 for 10-bit
+fn peak_value(bit_depth: u8) -> Result<f64> {
+    if !(8..=16).contains(&bit_depth) {
+        return Err(BitvueError::InvalidData(format!(
+            "Unsupported bit depth: {}",
+            bit_depth
+        )));
+    }
+    Ok(((1u32 << bit_depth) - 1) as f64)
+}
+
+/// Calculate PSNR between two images with up to 16 bits per sample
+///
+/// Same as [`psnr`], with MAX = 2^bit_depth - 1.
+pub fn psnr_hbd(
+    reference: &[u16],
+    distorted: &[u16],
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+) -> Result<f64> {
+    check_plane_sizes(reference.len(), distorted.len(), width, height)?;
+    let peak = peak_value(bit_depth)?;
+
+    let sse: u64 = reference
+        .iter()
+        .zip(distorted)
+        .map(|(&a, &b)| {
+            let diff = (a as i64 - b as i64).unsigned_abs();
+            diff * diff
+        })
+        .sum();
+    if sse == 0 {
+        return Ok(f64::INFINITY);
+    }
+
+    let mse = sse as f64 / reference.len() as f64;
+    Ok(10.0 * (peak * peak / mse).log10())
+}
+
+/// Calculate SSIM between two images with up to 16 bits per sample
+///
+/// Same as [`ssim`], with the dynamic range L = 2^bit_depth - 1.
+pub fn ssim_hbd(
+    reference: &[u16],
+    distorted: &[u16],
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+) -> Result<f64> {
+    check_plane_sizes(reference.len(), distorted.len(), width, height)?;
+    let peak = peak_value(bit_depth)?;
+
+    ssim_windows(width, height, peak, |start, end| {
+        let mut stats = simd::WindowStats::default();
+        for (&x, &y) in reference[start..end].iter().zip(&distorted[start..end]) {
+            let (x, y) = (x as u64, y as u64);
+            stats.sum_x += x;
+            stats.sum_y += y;
+            stats.sum_xx += x * x;
+            stats.sum_yy += y * y;
+            stats.sum_xy += x * y;
+        }
+        stats.count = end - start;
+        stats
+    })
+}
+
+/// Check that both planes hold `width`x`height` samples
+fn check_plane_sizes(
+    reference_len: usize,
+    distorted_len: usize,
+    width: usize,
+    height: usize,
+) -> Result<()> {
     // Validate dimensions are reasonable (max 16K to prevent overflow)
     const MAX_DIMENSION: usize = 15360;
     if width > MAX_DIMENSION || height > MAX_DIMENSION {
@@ -151,19 +213,29 @@ pub fn ssim(reference: &[u8], distorted: &[u8], width: usize, height: usize) ->
         BitvueError::InvalidData(format!("Width * height overflow: {} * {}", width, height))
     })?;
 
-    if reference.len() != size || distorted.len() != size {
+    if reference_len != size || distorted_len != size {
         return Err(BitvueError::InvalidData(format!(
             "Image size mismatch: expected {}, got {} and {}",
-            size,
-            reference.len(),
-            distorted.len()
+            size, reference_len, distorted_len
         )));
     }
+    Ok(())
+}
 
+/// Mean SSIM over 8x8 windows of a `width`x`height` plane
+///
+/// `window_stats(start, end)` sums the samples in `start..end`; `peak` is
+/// the dynamic range of the samples.
+fn ssim_windows(
+    width: usize,
+    height: usize,
+    peak: f64,
+    window_stats: impl Fn(usize, usize) -> simd::WindowStats,
+) -> Result<f64> {
     // SSIM constants
     let k1 = 0.01;
     let k2 = 0.03;
-    let l = 255.0; // Dynamic range for 8-bit
+    let l = peak;
     let c1 = (k1 * l) * (k1 * l);
     let c2 = (k2 * l) * (k2 * l);
 
@@ -196,17 +268,15 @@ pub fn ssim(reference: &[u8], distorted: &[u8], width: usize, height: usize) ->
             })?;
 
             // Validate end doesn't exceed data bounds
-            if end > reference.len() || end > distorted.len() {
+            if end > width * height {
                 return Err(BitvueError::InvalidData(format!(
-                    "SSIM window end {} exceeds data length (ref: {}, dist: {})",
+                    "SSIM window end {} exceeds data length {}",
                     end,
-                    reference.len(),
-                    distorted.len()
+                    width * height
                 )));
             }
 
-            // Use SIMD-optimized window statistics computation
-            let stats = simd::compute_window_stats_simd(reference, distorted, start, end);
+            let stats = window_stats(start, end);
 
             if stats.count == 0 {
                 continue;
@@ -635,4 +705,34 @@ mod tests {
         assert!((result.1 - 1.0).abs() < 0.01);
         assert!((result.2 - 1.0).abs() < 0.01);
     }
+
+    #[test]
+    fn test_high_bit_depth_matches_8bit() {
+        let reference: Vec<u8> = (0..256).map(|i| (i * 7 % 251) as u8).collect();
+        let distorted: Vec<u8> = reference.iter().map(|&v| v.saturating_add(3)).collect();
+        let widen = |plane: &[u8]| plane.iter().map(|&v| v as u16).collect::<Vec<_>>();
+
+        let psnr8 = psnr(&reference, &distorted, 16, 16).unwrap();
+        let psnr16 = psnr_hbd(&widen(&reference), &widen(&distorted), 16, 16, 8).unwrap();
+        assert!((psnr8 - psnr16).abs() < 1e-9);
+
+        let ssim8 = ssim(&reference, &distorted, 16, 16).unwrap();
+        let ssim16 = ssim_hbd(&widen(&reference), &widen(&distorted), 16, 16, 8).unwrap();
+        assert!((ssim8 - ssim16).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_psnr_hbd_uses_bit_depth_peak() {
+        let reference = vec![512u16; 64];
+        let distorted = vec![514u16; 64];
+
+        // MSE 4: 10*log10(1023^2 / 4)
+        let result = psnr_hbd(&reference, &distorted, 8, 8, 10).unwrap();
+        assert!((result - 54.1769).abs() < 1e-3);
+        assert!(psnr_hbd(&reference, &reference, 8, 8, 10)
+            .unwrap()
+            .is_infinite());
+        assert!(psnr_hbd(&reference, &distorted, 8, 8, 17).is_err());
+        assert!(ssim_hbd(&reference, &distorted[..32], 8, 8, 10).is_err());
+    }
 }
@@ -110,6 +110,16 @@ impl<'a> MsbBitReader<'a> {
             _ => Vp9Error::InvalidData(e.to_string()),
         })
     }
+
+    /// Read an n-bit unsigned literal, f(n) in the VP9 spec
+    pub fn read_literal(&mut self, n: u8) -> Result<u32> {
+        if n > 32 {
+            return Err(Vp9Error::InvalidData(
+                "Cannot read more than 32 bits at once".to_string(),
+            ));
+        }
+        self.read_bits(n)
+    }
 }
 
 #[cfg(test)]
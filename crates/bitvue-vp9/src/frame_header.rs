@@ -6,7 +6,7 @@
 //!
 //! This module parses the uncompressed header.
 
-use crate::bitreader::BitReader;
+use crate::bitreader::MsbBitReader;
 use crate::error::{Result, Vp9Error};
 // Re-export FrameType for other modules in this crate
 pub use bitvue_core::FrameType;
@@ -257,7 +257,7 @@ impl FrameHeader {
 
 /// Parse VP9 frame header from data.
 pub fn parse_frame_header(data: &[u8]) -> Result<FrameHeader> {
-    let mut reader = BitReader::new(data);
+    let mut reader = MsbBitReader::new(data);
     let mut header = FrameHeader::default();
 
     // frame_marker (2 bits, must be 0b10)
@@ -434,7 +434,11 @@ pub fn parse_frame_header(data: &[u8]) -> Result<FrameHeader> {
     Ok(header)
 }
 
-fn parse_color_config(reader: &mut BitReader, header: &mut FrameHeader, profile: u8) -> Result<()> {
+fn parse_color_config(
+    reader: &mut MsbBitReader,
+    header: &mut FrameHeader,
+    profile: u8,
+) -> Result<()> {
     if profile >= 2 {
         // ten_or_twelve_bit (1 bit)
         let ten_or_twelve_bit = reader.read_literal(1)? != 0;
@@ -474,7 +478,7 @@ fn parse_color_config(reader: &mut BitReader, header: &mut FrameHeader, profile:
     Ok(())
 }
 
-fn parse_frame_size(reader: &mut BitReader, header: &mut FrameHeader) -> Result<()> {
+fn parse_frame_size(reader: &mut MsbBitReader, header: &mut FrameHeader) -> Result<()> {
     // SECURITY: Validate frame dimensions to prevent memory exhaustion
     // VP9 spec allows up to 16K resolution, but we limit to prevent DoS
     const MAX_FRAME_WIDTH: u32 = 16384; // 16K
@@ -505,7 +509,7 @@ fn parse_frame_size(reader: &mut BitReader, header: &mut FrameHeader) -> Result<
     Ok(())
 }
 
-fn parse_render_size(reader: &mut BitReader, header: &mut FrameHeader) -> Result<()> {
+fn parse_render_size(reader: &mut MsbBitReader, header: &mut FrameHeader) -> Result<()> {
     // SECURITY: Validate render dimensions to prevent memory exhaustion
     const MAX_FRAME_WIDTH: u32 = 16384; // 16K
     const MAX_FRAME_HEIGHT: u32 = 16384;
@@ -534,7 +538,7 @@ fn parse_render_size(reader: &mut BitReader, header: &mut FrameHeader) -> Result
     Ok(())
 }
 
-fn parse_loop_filter(reader: &mut BitReader, header: &mut FrameHeader) -> Result<()> {
+fn parse_loop_filter(reader: &mut MsbBitReader, header: &mut FrameHeader) -> Result<()> {
     // loop_filter_level (6 bits)
     header.loop_filter.level = reader.read_literal(6)? as u8;
 
@@ -576,7 +580,7 @@ fn parse_loop_filter(reader: &mut BitReader, header: &mut FrameHeader) -> Result
     Ok(())
 }
 
-fn parse_quantization(reader: &mut BitReader, header: &mut FrameHeader) -> Result<()> {
+fn parse_quantization(reader: &mut MsbBitReader, header: &mut FrameHeader) -> Result<()> {
     // base_q_idx (8 bits)
     header.quantization.base_q_idx = reader.read_literal(8)? as u8;
 
@@ -598,7 +602,7 @@ fn parse_quantization(reader: &mut BitReader, header: &mut FrameHeader) -> Resul
     Ok(())
 }
 
-fn read_delta_q(reader: &mut BitReader) -> Result<i8> {
+fn read_delta_q(reader: &mut MsbBitReader) -> Result<i8> {
     let delta_coded = reader.read_literal(1)? != 0;
     if delta_coded {
         read_signed_literal(reader, 4)
@@ -607,13 +611,13 @@ fn read_delta_q(reader: &mut BitReader) -> Result<i8> {
     }
 }
 
-fn read_signed_literal(reader: &mut BitReader, bits: u8) -> Result<i8> {
+fn read_signed_literal(reader: &mut MsbBitReader, bits: u8) -> Result<i8> {
     let value = reader.read_literal(bits)? as i8;
     let sign = reader.read_literal(1)? != 0;
     Ok(if sign { -value } else { value })
 }
 
-fn parse_segmentation(reader: &mut BitReader, header: &mut FrameHeader) -> Result<()> {
+fn parse_segmentation(reader: &mut MsbBitReader, header: &mut FrameHeader) -> Result<()> {
     // segmentation_enabled (1 bit)
     header.segmentation.enabled = reader.read_literal(1)? != 0;
 
@@ -697,7 +701,7 @@ fn parse_segmentation(reader: &mut BitReader, header: &mut FrameHeader) -> Resul
     Ok(())
 }
 
-fn parse_tile_info(reader: &mut BitReader, header: &mut FrameHeader) -> Result<()> {
+fn parse_tile_info(reader: &mut MsbBitReader, header: &mut FrameHeader) -> Result<()> {
     // SECURITY: Limit tile count to prevent DoS via excessive tile allocation
     const MAX_TILE_LOG2: u8 = 6; // Max 64 tiles in either dimension
 
@@ -144,7 +144,7 @@ fn build_superframe_tree(index: &SuperframeIndex) -> SyntaxNode {
 }
 
 /// Build syntax tree for a frame.
-fn build_frame_tree(index: usize, header: &FrameHeader) -> SyntaxNode {
+pub fn build_frame_tree(index: usize, header: &FrameHeader) -> SyntaxNode {
     let frame_type = if header.is_key_frame() {
         "Key"
     } else {
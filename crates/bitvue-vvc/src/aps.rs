@@ -0,0 +1,578 @@
+//! VVC Adaptation Parameter Set (APS) parsing.
+//!
+//! APS NAL units carry ALF filter sets, LMCS piecewise models and explicit
+//! scaling lists. Slices refer to them by type and APS ID.
+//! It is defined in ITU-T H.266 Section 7.3.2.6.
+
+use crate::bitreader::BitReader;
+use crate::error::{Result, VvcError};
+use crate::sps::{ceil_log2, read_ue_max};
+use serde::{Deserialize, Serialize};
+
+/// Number of luma ALF classes (NumAlfFilters).
+pub const NUM_ALF_FILTERS: usize = 25;
+/// Number of scaling matrices in `scaling_list_data()`.
+pub const NUM_SCALING_LISTS: usize = 28;
+/// Number of LMCS bins.
+pub const NUM_LMCS_BINS: usize = 16;
+
+/// APS parameter type (aps_params_type).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ApsParamsType {
+    /// ALF parameters.
+    Alf = 0,
+    /// LMCS parameters.
+    Lmcs = 1,
+    /// Scaling list parameters.
+    ScalingList = 2,
+}
+
+impl ApsParamsType {
+    /// Create from raw value.
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(Self::Alf),
+            1 => Some(Self::Lmcs),
+            2 => Some(Self::ScalingList),
+            _ => None,
+        }
+    }
+
+    /// Get human-readable name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Alf => "ALF_APS",
+            Self::Lmcs => "LMCS_APS",
+            Self::ScalingList => "SCALING_APS",
+        }
+    }
+
+    /// Largest valid aps_adaptation_parameter_set_id for this type.
+    pub fn max_id(&self) -> u8 {
+        match self {
+            Self::Alf | Self::ScalingList => 7,
+            Self::Lmcs => 3,
+        }
+    }
+}
+
+/// ALF filter sets (`alf_data()`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlfData {
+    /// Luma filter set signalled.
+    pub alf_luma_filter_signal_flag: bool,
+    /// Chroma filter set signalled.
+    pub alf_chroma_filter_signal_flag: bool,
+    /// CC-ALF Cb filters signalled.
+    pub alf_cc_cb_filter_signal_flag: bool,
+    /// CC-ALF Cr filters signalled.
+    pub alf_cc_cr_filter_signal_flag: bool,
+    /// Luma clipping signalled.
+    pub alf_luma_clip_flag: bool,
+    /// Filter index of each of the 25 luma classes.
+    pub alf_luma_coeff_delta_idx: Vec<u8>,
+    /// Signalled luma filters (12 coefficients each).
+    pub luma_coeff: Vec<[i16; 12]>,
+    /// Luma clipping indices (zero when clipping is not signalled).
+    pub luma_clip_idx: Vec<[u8; 12]>,
+    /// Chroma clipping signalled.
+    pub alf_chroma_clip_flag: bool,
+    /// Alternative chroma filters (6 coefficients each).
+    pub chroma_coeff: Vec<[i16; 6]>,
+    /// Chroma clipping indices.
+    pub chroma_clip_idx: Vec<[u8; 6]>,
+    /// CC-ALF Cb filters (7 coefficients each).
+    pub cc_cb_coeff: Vec<[i16; 7]>,
+    /// CC-ALF Cr filters (7 coefficients each).
+    pub cc_cr_coeff: Vec<[i16; 7]>,
+}
+
+impl AlfData {
+    /// Luma filter coefficients applied to class `class_idx`.
+    pub fn luma_filter_for_class(&self, class_idx: usize) -> Option<&[i16; 12]> {
+        let filt_idx = self
+            .alf_luma_coeff_delta_idx
+            .get(class_idx)
+            .copied()
+            .unwrap_or(0);
+        self.luma_coeff.get(filt_idx as usize)
+    }
+}
+
+/// LMCS piecewise linear model (`lmcs_data()`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LmcsData {
+    /// First bin used in the model.
+    pub lmcs_min_bin_idx: u8,
+    /// Distance of the last used bin from bin 15.
+    pub lmcs_delta_max_bin_idx: u8,
+    /// Precision of the codeword deltas minus 1.
+    pub lmcs_delta_cw_prec_minus1: u8,
+    /// Codeword delta of each bin (lmcsDeltaCW).
+    pub delta_cw: [i32; NUM_LMCS_BINS],
+    /// Chroma residual scaling delta (lmcsDeltaCrs).
+    pub delta_crs: i8,
+}
+
+impl LmcsData {
+    /// LmcsMaxBinIdx.
+    pub fn max_bin_idx(&self) -> u8 {
+        15 - self.lmcs_delta_max_bin_idx
+    }
+
+    /// Codewords of each bin (lmcsCW) for the given luma bit depth.
+    pub fn codewords(&self, bit_depth: u8) -> [i32; NUM_LMCS_BINS] {
+        let org_cw = (1i32 << bit_depth) / NUM_LMCS_BINS as i32;
+        let mut cw = [0; NUM_LMCS_BINS];
+        for (i, value) in cw.iter_mut().enumerate() {
+            if (self.lmcs_min_bin_idx as usize..=self.max_bin_idx() as usize).contains(&i) {
+                *value = org_cw + self.delta_cw[i];
+            }
+        }
+        cw
+    }
+
+    /// Mapped-domain pivot points (LmcsPivot) for the given luma bit depth.
+    pub fn pivots(&self, bit_depth: u8) -> [i32; NUM_LMCS_BINS + 1] {
+        let cw = self.codewords(bit_depth);
+        let mut pivots = [0; NUM_LMCS_BINS + 1];
+        for i in 0..NUM_LMCS_BINS {
+            pivots[i + 1] = pivots[i] + cw[i];
+        }
+        pivots
+    }
+}
+
+/// Explicit scaling matrices (`scaling_list_data()`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScalingListData {
+    /// Copy mode flag of each matrix.
+    pub scaling_list_copy_mode_flag: Vec<bool>,
+    /// Prediction mode flag of each matrix.
+    pub scaling_list_pred_mode_flag: Vec<bool>,
+    /// Reference matrix delta of each matrix.
+    pub scaling_list_pred_id_delta: Vec<u8>,
+    /// Reconstructed matrices (ScalingMatrixRec) in raster order; 2x2, 4x4 or 8x8.
+    pub matrices: Vec<Vec<u8>>,
+    /// Reconstructed DC values (ScalingMatrixDcRec) of matrices 14..27.
+    pub dc: Vec<u8>,
+}
+
+impl Default for ScalingListData {
+    fn default() -> Self {
+        Self {
+            scaling_list_copy_mode_flag: vec![true; NUM_SCALING_LISTS],
+            scaling_list_pred_mode_flag: vec![false; NUM_SCALING_LISTS],
+            scaling_list_pred_id_delta: vec![0; NUM_SCALING_LISTS],
+            matrices: (0..NUM_SCALING_LISTS)
+                .map(|id| vec![16; scaling_matrix_size(id) * scaling_matrix_size(id)])
+                .collect(),
+            dc: vec![16; NUM_SCALING_LISTS - 14],
+        }
+    }
+}
+
+/// Payload of an APS.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ApsData {
+    /// ALF filter sets.
+    Alf(AlfData),
+    /// LMCS model.
+    Lmcs(LmcsData),
+    /// Scaling lists.
+    ScalingList(ScalingListData),
+}
+
+/// VVC Adaptation Parameter Set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Aps {
+    /// APS parameter type.
+    pub aps_params_type: ApsParamsType,
+    /// APS ID.
+    pub aps_adaptation_parameter_set_id: u8,
+    /// Chroma-related syntax present.
+    pub aps_chroma_present_flag: bool,
+    /// Parsed payload.
+    pub data: ApsData,
+    /// APS extension present.
+    pub aps_extension_flag: bool,
+}
+
+impl Aps {
+    /// ALF payload, if this is an ALF APS.
+    pub fn alf(&self) -> Option<&AlfData> {
+        match &self.data {
+            ApsData::Alf(alf) => Some(alf),
+            _ => None,
+        }
+    }
+
+    /// LMCS payload, if this is an LMCS APS.
+    pub fn lmcs(&self) -> Option<&LmcsData> {
+        match &self.data {
+            ApsData::Lmcs(lmcs) => Some(lmcs),
+            _ => None,
+        }
+    }
+
+    /// Scaling list payload, if this is a scaling list APS.
+    pub fn scaling_list(&self) -> Option<&ScalingListData> {
+        match &self.data {
+            ApsData::ScalingList(sl) => Some(sl),
+            _ => None,
+        }
+    }
+}
+
+/// APSs activated by a slice, as indices into `VvcStream::aps`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActiveAps {
+    /// ALF APSs of the luma filter sets.
+    pub alf_luma: Vec<usize>,
+    /// ALF APS of the chroma filter set.
+    pub alf_chroma: Option<usize>,
+    /// ALF APS of the CC-ALF Cb filters.
+    pub alf_cc_cb: Option<usize>,
+    /// ALF APS of the CC-ALF Cr filters.
+    pub alf_cc_cr: Option<usize>,
+    /// LMCS APS.
+    pub lmcs: Option<usize>,
+    /// Scaling list APS.
+    pub scaling_list: Option<usize>,
+    /// Referenced APSs that were not received before the slice.
+    pub missing: Vec<(ApsParamsType, u8)>,
+}
+
+impl ActiveAps {
+    /// All referenced APS indices, sorted and deduplicated.
+    pub fn indices(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = self
+            .alf_luma
+            .iter()
+            .copied()
+            .chain(self.alf_chroma)
+            .chain(self.alf_cc_cb)
+            .chain(self.alf_cc_cr)
+            .chain(self.lmcs)
+            .chain(self.scaling_list)
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+}
+
+/// Parse APS from RBSP data.
+pub fn parse_aps(data: &[u8]) -> Result<Aps> {
+    let mut reader = BitReader::new(data);
+
+    let raw_type = reader.read_bits(3)?;
+    let aps_params_type = ApsParamsType::from_u32(raw_type)
+        .ok_or_else(|| VvcError::InvalidData(format!("Invalid aps_params_type: {}", raw_type)))?;
+    let aps_adaptation_parameter_set_id = reader.read_bits(5)? as u8;
+    if aps_adaptation_parameter_set_id > aps_params_type.max_id() {
+        return Err(VvcError::InvalidData(format!(
+            "{} ID {} out of range",
+            aps_params_type.name(),
+            aps_adaptation_parameter_set_id
+        )));
+    }
+    let aps_chroma_present_flag = reader.read_bit()?;
+
+    let data = match aps_params_type {
+        ApsParamsType::Alf => ApsData::Alf(parse_alf_data(&mut reader, aps_chroma_present_flag)?),
+        ApsParamsType::Lmcs => {
+            ApsData::Lmcs(parse_lmcs_data(&mut reader, aps_chroma_present_flag)?)
+        }
+        ApsParamsType::ScalingList => ApsData::ScalingList(parse_scaling_list_data(
+            &mut reader,
+            aps_chroma_present_flag,
+        )?),
+    };
+
+    let aps_extension_flag = reader.read_bit()?;
+
+    Ok(Aps {
+        aps_params_type,
+        aps_adaptation_parameter_set_id,
+        aps_chroma_present_flag,
+        data,
+        aps_extension_flag,
+    })
+}
+
+/// Read a magnitude/sign pair with the magnitude coded as ue(v).
+fn read_signed_ue(reader: &mut BitReader) -> Result<i16> {
+    let abs = read_ue_max(reader, 128, "alf_coeff_abs")? as i16;
+    if abs != 0 && reader.read_bit()? {
+        Ok(-abs)
+    } else {
+        Ok(abs)
+    }
+}
+
+fn parse_alf_data(reader: &mut BitReader, chroma_present: bool) -> Result<AlfData> {
+    let mut alf = AlfData::default();
+
+    alf.alf_luma_filter_signal_flag = reader.read_bit()?;
+    if chroma_present {
+        alf.alf_chroma_filter_signal_flag = reader.read_bit()?;
+        alf.alf_cc_cb_filter_signal_flag = reader.read_bit()?;
+        alf.alf_cc_cr_filter_signal_flag = reader.read_bit()?;
+    }
+    if !alf.alf_luma_filter_signal_flag
+        && !alf.alf_chroma_filter_signal_flag
+        && !alf.alf_cc_cb_filter_signal_flag
+        && !alf.alf_cc_cr_filter_signal_flag
+    {
+        return Err(VvcError::InvalidData(
+            "ALF APS signals no filters".to_string(),
+        ));
+    }
+
+    if alf.alf_luma_filter_signal_flag {
+        alf.alf_luma_clip_flag = reader.read_bit()?;
+        let num_filters_minus1 = read_ue_max(
+            reader,
+            NUM_ALF_FILTERS as u32 - 1,
+            "alf_luma_num_filters_signalled_minus1",
+        )?;
+        alf.alf_luma_coeff_delta_idx = vec![0; NUM_ALF_FILTERS];
+        if num_filters_minus1 > 0 {
+            let bits = ceil_log2(num_filters_minus1 + 1);
+            for idx in alf.alf_luma_coeff_delta_idx.iter_mut() {
+                *idx = reader.read_bits(bits)? as u8;
+                if u32::from(*idx) > num_filters_minus1 {
+                    return Err(VvcError::InvalidData(format!(
+                        "alf_luma_coeff_delta_idx {} out of range",
+                        idx
+                    )));
+                }
+            }
+        }
+        for _ in 0..=num_filters_minus1 {
+            let mut coeff = [0i16; 12];
+            for c in coeff.iter_mut() {
+                *c = read_signed_ue(reader)?;
+            }
+            alf.luma_coeff.push(coeff);
+        }
+        alf.luma_clip_idx = vec![[0u8; 12]; alf.luma_coeff.len()];
+        if alf.alf_luma_clip_flag {
+            for clip in alf.luma_clip_idx.iter_mut() {
+                for c in clip.iter_mut() {
+                    *c = reader.read_bits(2)? as u8;
+                }
+            }
+        }
+    }
+
+    if alf.alf_chroma_filter_signal_flag {
+        alf.alf_chroma_clip_flag = reader.read_bit()?;
+        let num_alt_minus1 = read_ue_max(reader, 7, "alf_chroma_num_alt_filters_minus1")?;
+        for _ in 0..=num_alt_minus1 {
+            let mut coeff = [0i16; 6];
+            for c in coeff.iter_mut() {
+                *c = read_signed_ue(reader)?;
+            }
+            let mut clip = [0u8; 6];
+            if alf.alf_chroma_clip_flag {
+                for c in clip.iter_mut() {
+                    *c = reader.read_bits(2)? as u8;
+                }
+            }
+            alf.chroma_coeff.push(coeff);
+            alf.chroma_clip_idx.push(clip);
+        }
+    }
+
+    if alf.alf_cc_cb_filter_signal_flag {
+        alf.cc_cb_coeff = parse_cc_alf_filters(reader, "alf_cc_cb_filters_signalled_minus1")?;
+    }
+    if alf.alf_cc_cr_filter_signal_flag {
+        alf.cc_cr_coeff = parse_cc_alf_filters(reader, "alf_cc_cr_filters_signalled_minus1")?;
+    }
+
+    Ok(alf)
+}
+
+fn parse_cc_alf_filters(reader: &mut BitReader, name: &str) -> Result<Vec<[i16; 7]>> {
+    let count_minus1 = read_ue_max(reader, 3, name)?;
+    let mut filters = Vec::with_capacity(count_minus1 as usize + 1);
+    for _ in 0..=count_minus1 {
+        let mut coeff = [0i16; 7];
+        for c in coeff.iter_mut() {
+            let mapped_abs = reader.read_bits(3)?;
+            if mapped_abs != 0 {
+                let value = 1i16 << (mapped_abs - 1);
+                *c = if reader.read_bit()? { -value } else { value };
+            }
+        }
+        filters.push(coeff);
+    }
+    Ok(filters)
+}
+
+fn parse_lmcs_data(reader: &mut BitReader, chroma_present: bool) -> Result<LmcsData> {
+    let mut lmcs = LmcsData::default();
+
+    lmcs.lmcs_min_bin_idx = read_ue_max(reader, 15, "lmcs_min_bin_idx")? as u8;
+    lmcs.lmcs_delta_max_bin_idx = read_ue_max(reader, 15, "lmcs_delta_max_bin_idx")? as u8;
+    if lmcs.max_bin_idx() < lmcs.lmcs_min_bin_idx {
+        return Err(VvcError::InvalidData(format!(
+            "LmcsMaxBinIdx {} below lmcs_min_bin_idx {}",
+            lmcs.max_bin_idx(),
+            lmcs.lmcs_min_bin_idx
+        )));
+    }
+    lmcs.lmcs_delta_cw_prec_minus1 = read_ue_max(reader, 14, "lmcs_delta_cw_prec_minus1")? as u8;
+
+    let bits = lmcs.lmcs_delta_cw_prec_minus1 + 1;
+    for i in lmcs.lmcs_min_bin_idx..=lmcs.max_bin_idx() {
+        let abs = reader.read_bits(bits)? as i32;
+        let negative = abs > 0 && reader.read_bit()?;
+        lmcs.delta_cw[i as usize] = if negative { -abs } else { abs };
+    }
+
+    if chroma_present {
+        let abs = reader.read_bits(3)? as i8;
+        let negative = abs > 0 && reader.read_bit()?;
+        lmcs.delta_crs = if negative { -abs } else { abs };
+    }
+
+    Ok(lmcs)
+}
+
+/// Matrix size of scaling list `id`.
+fn scaling_matrix_size(id: usize) -> usize {
+    if id < 2 {
+        2
+    } else if id < 8 {
+        4
+    } else {
+        8
+    }
+}
+
+/// Up-right diagonal scan positions (x, y) of a square block (6.5.2).
+fn diag_scan(size: usize) -> Vec<(usize, usize)> {
+    let mut scan = Vec::with_capacity(size * size);
+    let (mut x, mut y) = (0usize, 0isize);
+    while scan.len() < size * size {
+        while y >= 0 {
+            if x < size && (y as usize) < size {
+                scan.push((x, y as usize));
+            }
+            y -= 1;
+            x += 1;
+        }
+        y = x as isize;
+        x = 0;
+    }
+    scan
+}
+
+fn parse_scaling_list_data(
+    reader: &mut BitReader,
+    chroma_present: bool,
+) -> Result<ScalingListData> {
+    let mut sl = ScalingListData::default();
+
+    for id in 0..NUM_SCALING_LISTS {
+        let size = scaling_matrix_size(id);
+        let scan = diag_scan(size);
+        let mut deltas = vec![0i32; size * size];
+        let mut dc_coef = 0i32;
+
+        if chroma_present || id % 3 == 2 || id == 27 {
+            sl.scaling_list_copy_mode_flag[id] = reader.read_bit()?;
+            if !sl.scaling_list_copy_mode_flag[id] {
+                sl.scaling_list_pred_mode_flag[id] = reader.read_bit()?;
+            }
+            if (sl.scaling_list_copy_mode_flag[id] || sl.scaling_list_pred_mode_flag[id])
+                && id != 0
+                && id != 2
+                && id != 8
+            {
+                let max_id_delta = if id < 2 {
+                    id
+                } else if id < 8 {
+                    id - 2
+                } else {
+                    id - 8
+                };
+                sl.scaling_list_pred_id_delta[id] =
+                    read_ue_max(reader, max_id_delta as u32, "scaling_list_pred_id_delta")? as u8;
+            }
+            if !sl.scaling_list_copy_mode_flag[id] {
+                let mut next_coef = 0i32;
+                if id > 13 {
+                    dc_coef = reader.read_se()?;
+                    next_coef += dc_coef;
+                }
+                for (i, &(x, y)) in scan.iter().enumerate() {
+                    if !(id > 25 && x >= 4 && y >= 4) {
+                        next_coef += reader.read_se()?;
+                    }
+                    deltas[i] = next_coef;
+                }
+            }
+        }
+
+        // ScalingMatrixPred / ScalingMatrixDcPred
+        let ref_id = id - sl.scaling_list_pred_id_delta[id] as usize;
+        let (pred, dc_pred): (Vec<u8>, u8) =
+            if !sl.scaling_list_copy_mode_flag[id] && !sl.scaling_list_pred_mode_flag[id] {
+                (vec![8; size * size], 8)
+            } else if ref_id == id {
+                (vec![16; size * size], 16)
+            } else {
+                let pred = sl.matrices[ref_id].clone();
+                let dc_pred = if ref_id > 13 {
+                    sl.dc[ref_id - 14]
+                } else {
+                    pred[0]
+                };
+                (pred, dc_pred)
+            };
+
+        let mut matrix = vec![0u8; size * size];
+        for (i, &(x, y)) in scan.iter().enumerate() {
+            matrix[y * size + x] = ((i32::from(pred[y * size + x]) + deltas[i]) & 255) as u8;
+        }
+        sl.matrices[id] = matrix;
+        if id > 13 {
+            sl.dc[id - 14] = ((i32::from(dc_pred) + dc_coef) & 255) as u8;
+        }
+    }
+
+    Ok(sl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diag_scan_4x4() {
+        let scan = diag_scan(4);
+        assert_eq!(scan.len(), 16);
+        assert_eq!(&scan[..4], &[(0, 0), (0, 1), (1, 0), (0, 2)]);
+        assert_eq!(scan[15], (3, 3));
+    }
+
+    #[test]
+    fn test_lmcs_pivots() {
+        let mut lmcs = LmcsData {
+            lmcs_min_bin_idx: 1,
+            lmcs_delta_max_bin_idx: 1,
+            ..Default::default()
+        };
+        lmcs.delta_cw[1] = 8;
+        let cw = lmcs.codewords(10);
+        assert_eq!(cw[0], 0);
+        assert_eq!(cw[1], 72);
+        assert_eq!(cw[15], 0);
+        assert_eq!(lmcs.pivots(10)[16], 72 + 13 * 64);
+    }
+}
@@ -0,0 +1,255 @@
+//! VVC/H.266 frame extraction
+//!
+//! Functions for grouping VVC NAL units into pictures
+
+use crate::aps::Aps;
+use crate::nal::NalUnitType;
+use crate::picture_header::parse_picture_header;
+use crate::slice::{SliceHeader, SliceType};
+use crate::{parse_vvc, ParsedSlice, VvcStream};
+use bitvue_core::BitvueError;
+use serde::{Deserialize, Serialize};
+
+/// VVC frame (picture) data extracted from the bitstream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VvcFrame {
+    /// Frame index in decoding order
+    pub frame_index: usize,
+    /// Frame type (I, P, B)
+    pub frame_type: VvcFrameType,
+    /// Raw NAL unit data for this frame (picture header and slices)
+    pub nal_data: Vec<u8>,
+    /// Starting byte position in the stream
+    pub offset: usize,
+    /// Frame size in bytes
+    pub size: usize,
+    /// POC (Picture Order Count)
+    pub poc: i32,
+    /// NAL unit type of the slices
+    pub nal_type: NalUnitType,
+    /// Whether this is an IDR frame
+    pub is_idr: bool,
+    /// Whether this is an IRAP frame (IDR, CRA, GDR)
+    pub is_irap: bool,
+    /// Whether this picture may be used for reference
+    pub is_ref: bool,
+    /// Temporal ID
+    pub temporal_id: u8,
+    /// Number of slices in the picture
+    pub slice_count: usize,
+    /// Luma QP of the first slice (SliceQpY)
+    pub qp: i8,
+    /// First slice header
+    pub slice_header: SliceHeader,
+    /// APSs activated by the slices of this picture
+    pub aps: Vec<Aps>,
+}
+
+/// VVC frame type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VvcFrameType {
+    /// I-frame (intra slices only)
+    I,
+    /// P-frame (predicted)
+    P,
+    /// B-frame (bi-directional predicted)
+    B,
+    /// Unknown frame type
+    Unknown,
+}
+
+impl VvcFrameType {
+    /// Convert from slice type
+    pub fn from_slice_type(slice_type: SliceType) -> Self {
+        match slice_type {
+            SliceType::I => VvcFrameType::I,
+            SliceType::P => VvcFrameType::P,
+            SliceType::B => VvcFrameType::B,
+        }
+    }
+
+    /// Get display string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VvcFrameType::I => "I",
+            VvcFrameType::P => "P",
+            VvcFrameType::B => "B",
+            VvcFrameType::Unknown => "Unknown",
+        }
+    }
+}
+
+/// Extract frames from VVC Annex B byte stream
+///
+/// A picture starts at a PH NAL unit or at a slice carrying its own picture
+/// header; the frame type is the most inter-predicted slice type in it.
+pub fn extract_annex_b_frames(data: &[u8]) -> Result<Vec<VvcFrame>, BitvueError> {
+    let stream = parse_vvc(data).map_err(|e| BitvueError::Parse {
+        offset: 0,
+        message: e.to_string(),
+    })?;
+
+    Ok(frames_from_stream(&stream, data))
+}
+
+/// Group the parsed slices of a stream into frames
+pub fn frames_from_stream(stream: &VvcStream, data: &[u8]) -> Vec<VvcFrame> {
+    let mut frames = Vec::new();
+    let mut picture: Vec<&ParsedSlice> = Vec::new();
+    let mut picture_start: Option<usize> = None;
+    let mut prev_nal_index = 0;
+
+    for slice in &stream.slices {
+        let ph_nal = (prev_nal_index..slice.nal_index)
+            .rev()
+            .find(|&i| stream.nal_units[i].header.nal_unit_type == NalUnitType::PhNut);
+        let starts_picture = picture.is_empty()
+            || ph_nal.is_some()
+            || slice.header.sh_picture_header_in_slice_header_flag;
+
+        if starts_picture && !picture.is_empty() {
+            if let Some(frame) = build_frame(stream, data, frames.len(), picture_start, &picture) {
+                frames.push(frame);
+            }
+            picture.clear();
+        }
+        if starts_picture {
+            picture_start = ph_nal.or(Some(slice.nal_index));
+        }
+
+        picture.push(slice);
+        prev_nal_index = slice.nal_index + 1;
+    }
+
+    if let Some(frame) = build_frame(stream, data, frames.len(), picture_start, &picture) {
+        frames.push(frame);
+    }
+
+    frames
+}
+
+/// Build a frame from the slices of one picture
+fn build_frame(
+    stream: &VvcStream,
+    data: &[u8],
+    frame_index: usize,
+    first_nal: Option<usize>,
+    slices: &[&ParsedSlice],
+) -> Option<VvcFrame> {
+    let first = slices.first()?;
+    let last = slices.last()?;
+    let first_nal = &stream.nal_units[first_nal.unwrap_or(first.nal_index)];
+    let last_nal = &stream.nal_units[last.nal_index];
+    let slice_nal = &stream.nal_units[first.nal_index];
+
+    let offset = first_nal.offset as usize;
+    let end = ((last_nal.offset + last_nal.size) as usize).min(data.len());
+    let nal_type = slice_nal.header.nal_unit_type;
+
+    let frame_type = slices
+        .iter()
+        .map(|s| s.header.slice_type)
+        .min_by_key(|t| *t as u8)
+        .map(VvcFrameType::from_slice_type)
+        .unwrap_or(VvcFrameType::Unknown);
+
+    let picture_header = match first_nal.header.nal_unit_type {
+        NalUnitType::PhNut => {
+            parse_picture_header(&first_nal.payload, &stream.sps_map, &stream.pps_map).ok()
+        }
+        _ => first.header.picture_header.clone(),
+    };
+    let is_ref = picture_header
+        .map(|ph| !ph.ph_non_ref_pic_flag)
+        .unwrap_or(true);
+
+    let mut aps_indices: Vec<usize> = slices.iter().flat_map(|s| s.active_aps.indices()).collect();
+    aps_indices.sort_unstable();
+    aps_indices.dedup();
+    let aps = aps_indices
+        .into_iter()
+        .filter_map(|i| stream.aps.get(i).map(|p| p.aps.clone()))
+        .collect();
+
+    Some(VvcFrame {
+        frame_index,
+        frame_type,
+        nal_data: data
+            .get(offset..end)
+            .map(<[u8]>::to_vec)
+            .unwrap_or_default(),
+        offset,
+        size: end.saturating_sub(offset),
+        poc: first.poc,
+        nal_type,
+        is_idr: nal_type.is_idr(),
+        is_irap: nal_type.is_irap(),
+        is_ref,
+        temporal_id: slice_nal.header.temporal_id(),
+        slice_count: slices.len(),
+        qp: first.header.slice_qp_y,
+        slice_header: first.header.clone(),
+        aps,
+    })
+}
+
+/// Extract a single frame by index from Annex B byte stream
+pub fn extract_frame_at_index(data: &[u8], frame_index: usize) -> Option<VvcFrame> {
+    let frames = extract_annex_b_frames(data).ok()?;
+    frames.get(frame_index).cloned()
+}
+
+/// Convert VvcFrame to UnitNode format for bitvue-core
+pub fn vvc_frame_to_unit_node(frame: &VvcFrame) -> bitvue_core::UnitNode {
+    bitvue_core::UnitNode {
+        key: bitvue_core::UnitKey {
+            stream: bitvue_core::StreamId::A,
+            unit_type: "FRAME".to_string(),
+            offset: frame.offset as u64,
+            size: frame.size,
+        },
+        unit_type: std::sync::Arc::from("FRAME"),
+        offset: frame.offset as u64,
+        size: frame.size,
+        frame_index: Some(frame.frame_index),
+        frame_type: Some(std::sync::Arc::from(frame.frame_type.as_str())),
+        pts: Some(frame.poc.max(0) as u64),
+        dts: None,
+        display_name: std::sync::Arc::from(format!(
+            "Frame {} ({})",
+            frame.frame_index,
+            frame.frame_type.as_str()
+        )),
+        children: Vec::new(),
+        qp_avg: Some(frame.qp.max(0) as u8),
+        mv_grid: None,
+        temporal_id: Some(frame.temporal_id),
+        ref_frames: None,
+        ref_slots: None,
+    }
+}
+
+/// Convert multiple VvcFrames to UnitNode format
+pub fn vvc_frames_to_unit_nodes(frames: &[VvcFrame]) -> Vec<bitvue_core::UnitNode> {
+    frames.iter().map(vvc_frame_to_unit_node).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_empty() {
+        let frames = extract_annex_b_frames(&[]);
+        assert!(frames.is_ok());
+        assert!(frames.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_frame_type_from_slice() {
+        assert_eq!(VvcFrameType::from_slice_type(SliceType::I), VvcFrameType::I);
+        assert_eq!(VvcFrameType::from_slice_type(SliceType::P), VvcFrameType::P);
+        assert_eq!(VvcFrameType::from_slice_type(SliceType::B), VvcFrameType::B);
+        assert_eq!(VvcFrameType::Unknown.as_str(), "Unknown");
+    }
+}
@@ -12,6 +12,7 @@
 //! - NAL unit parsing with VVC-specific types
 //! - SPS parsing with dual tree and advanced tool flags
 //! - PPS parsing
+//! - Decoded picture hash SEI parsing
 //! - Syntax tree extraction for visualization
 //!
 //! # Example
@@ -27,16 +28,23 @@
 //! }
 //! ```
 
+pub mod aps;
 pub mod bitreader;
 pub mod error;
+pub mod frames;
 pub mod nal;
 pub mod overlay_extraction;
+pub mod picture_header;
 pub mod pps;
+pub mod sei;
+pub mod slice;
 pub mod sps;
 pub mod syntax;
 
+pub use aps::{parse_aps, ActiveAps, Aps, ApsData, ApsParamsType};
 pub use bitreader::{remove_emulation_prevention_bytes, BitReader};
 pub use error::{Result, VvcError};
+pub use frames::{extract_annex_b_frames, vvc_frames_to_unit_nodes, VvcFrame, VvcFrameType};
 pub use nal::{
     find_nal_units, parse_nal_header, parse_nal_units, NalUnit, NalUnitHeader, NalUnitType,
 };
@@ -44,7 +52,10 @@ pub use overlay_extraction::{
     extract_mv_grid, extract_partition_grid, extract_qp_grid, CodingTreeUnit, CodingUnit,
     MotionVector, PredMode, SplitMode,
 };
+pub use picture_header::{parse_picture_header, PictureHeader};
 pub use pps::{parse_pps, Pps};
+pub use sei::{parse_sei, DecodedPictureHash, PictureHashType, SeiMessage};
+pub use slice::{parse_slice_header, RefPicLists, SliceHeader, SliceType};
 pub use sps::{parse_sps, AlfConfig, DualTreeConfig, LmcsConfig, Profile, ProfileTierLevel, Sps};
 
 // Re-export ChromaFormat from bitvue_core for backward compatibility
@@ -62,6 +73,32 @@ pub struct VvcStream {
     pub sps_map: HashMap<u8, Sps>,
     /// Picture Parameter Sets (indexed by pps_id).
     pub pps_map: HashMap<u8, Pps>,
+    /// Adaptation Parameter Sets in decoding order.
+    pub aps: Vec<ParsedAps>,
+    /// Parsed slice headers.
+    pub slices: Vec<ParsedSlice>,
+}
+
+/// A parsed APS with the NAL unit that carried it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParsedAps {
+    /// Index of the NAL unit containing this APS.
+    pub nal_index: usize,
+    /// Parsed APS.
+    pub aps: Aps,
+}
+
+/// A parsed slice with its header and associated metadata.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParsedSlice {
+    /// Index of the NAL unit containing this slice.
+    pub nal_index: usize,
+    /// Parsed slice header.
+    pub header: SliceHeader,
+    /// POC (Picture Order Count).
+    pub poc: i32,
+    /// APSs activated by this slice.
+    pub active_aps: ActiveAps,
 }
 
 impl VvcStream {
@@ -75,6 +112,16 @@ impl VvcStream {
         self.pps_map.get(&id)
     }
 
+    /// Get the APSs activated by a slice.
+    pub fn slice_aps(&self, slice: &ParsedSlice) -> Vec<&Aps> {
+        slice
+            .active_aps
+            .indices()
+            .into_iter()
+            .filter_map(|i| self.aps.get(i).map(|p| &p.aps))
+            .collect()
+    }
+
     /// Get video dimensions from SPS.
     pub fn dimensions(&self) -> Option<(u32, u32)> {
         self.sps_map
@@ -152,9 +199,18 @@ pub fn parse_vvc(data: &[u8]) -> Result<VvcStream> {
 
     let mut sps_map = HashMap::new();
     let mut pps_map = HashMap::new();
+    let mut aps_list = Vec::new();
+    let mut aps_index: HashMap<(ApsParamsType, u8), usize> = HashMap::new();
+    let mut slices = Vec::new();
 
-    for nal in &nal_units {
-        match nal.header.nal_unit_type {
+    let mut picture_header: Option<PictureHeader> = None;
+    let mut poc_state = PocState::default();
+    let mut current_poc = 0;
+    let mut first_slice_in_picture = true;
+
+    for (i, nal) in nal_units.iter().enumerate() {
+        let nal_type = nal.header.nal_unit_type;
+        match nal_type {
             NalUnitType::SpsNut => {
                 if let Ok(sps) = sps::parse_sps(&nal.payload) {
                     sps_map.insert(sps.sps_seq_parameter_set_id, sps);
@@ -165,6 +221,69 @@ pub fn parse_vvc(data: &[u8]) -> Result<VvcStream> {
                     pps_map.insert(pps.pps_pic_parameter_set_id, pps);
                 }
             }
+            NalUnitType::PrefixApsNut | NalUnitType::SuffixApsNut => {
+                if let Ok(aps) = aps::parse_aps(&nal.payload) {
+                    aps_index.insert(
+                        (aps.aps_params_type, aps.aps_adaptation_parameter_set_id),
+                        aps_list.len(),
+                    );
+                    aps_list.push(ParsedAps { nal_index: i, aps });
+                }
+            }
+            NalUnitType::PhNut => {
+                picture_header =
+                    picture_header::parse_picture_header(&nal.payload, &sps_map, &pps_map).ok();
+                first_slice_in_picture = true;
+            }
+            NalUnitType::EosNut => {
+                poc_state.first_picture_after_eos = true;
+            }
+            _ if nal_type.is_vcl() => {
+                let header = match slice::parse_slice_header(
+                    &nal.payload,
+                    &sps_map,
+                    &pps_map,
+                    picture_header.as_ref(),
+                    nal_type,
+                ) {
+                    Ok(header) => header,
+                    Err(_) => continue,
+                };
+
+                if header.sh_picture_header_in_slice_header_flag {
+                    picture_header = header.picture_header.clone();
+                    first_slice_in_picture = true;
+                }
+
+                if first_slice_in_picture {
+                    if let Some(ph) = picture_header.as_ref() {
+                        let log2_max_poc_lsb = pps_map
+                            .get(&ph.ph_pic_parameter_set_id)
+                            .and_then(|pps| sps_map.get(&pps.pps_seq_parameter_set_id))
+                            .map(|sps| sps.sps_log2_max_pic_order_cnt_lsb_minus4 + 4)
+                            .unwrap_or(8);
+                        current_poc = poc_state.derive(
+                            ph,
+                            nal_type,
+                            nal.header.temporal_id(),
+                            log2_max_poc_lsb,
+                        );
+                    }
+                    first_slice_in_picture = false;
+                }
+
+                let active_aps = picture_header
+                    .as_ref()
+                    .map(|ph| resolve_active_aps(&header, ph, &aps_index))
+                    .unwrap_or_default();
+
+                slices.push(ParsedSlice {
+                    nal_index: i,
+                    header,
+                    poc: current_poc,
+                    active_aps,
+                });
+            }
             _ => {}
         }
     }
@@ -173,9 +292,119 @@ pub fn parse_vvc(data: &[u8]) -> Result<VvcStream> {
         nal_units,
         sps_map,
         pps_map,
+        aps: aps_list,
+        slices,
     })
 }
 
+/// Link a slice to the APSs it activates through its ALF, LMCS and scaling list syntax.
+fn resolve_active_aps(
+    header: &SliceHeader,
+    ph: &PictureHeader,
+    aps_index: &HashMap<(ApsParamsType, u8), usize>,
+) -> ActiveAps {
+    let mut active = ActiveAps::default();
+    let mut lookup = |params_type: ApsParamsType, id: u8| {
+        let found = aps_index.get(&(params_type, id)).copied();
+        if found.is_none() {
+            active.missing.push((params_type, id));
+        }
+        found
+    };
+
+    let alf = &header.alf;
+    let mut alf_luma = Vec::new();
+    let (mut alf_chroma, mut alf_cc_cb, mut alf_cc_cr) = (None, None, None);
+    if alf.alf_enabled_flag {
+        alf_luma = alf
+            .alf_aps_id_luma
+            .iter()
+            .filter_map(|id| lookup(ApsParamsType::Alf, *id))
+            .collect();
+        if alf.alf_cb_enabled_flag || alf.alf_cr_enabled_flag {
+            alf_chroma = lookup(ApsParamsType::Alf, alf.alf_aps_id_chroma);
+        }
+        if alf.alf_cc_cb_enabled_flag {
+            alf_cc_cb = lookup(ApsParamsType::Alf, alf.alf_cc_cb_aps_id);
+        }
+        if alf.alf_cc_cr_enabled_flag {
+            alf_cc_cr = lookup(ApsParamsType::Alf, alf.alf_cc_cr_aps_id);
+        }
+    }
+    let lmcs = header
+        .sh_lmcs_used_flag
+        .then(|| lookup(ApsParamsType::Lmcs, ph.ph_lmcs_aps_id))
+        .flatten();
+    let scaling_list = header
+        .sh_explicit_scaling_list_used_flag
+        .then(|| lookup(ApsParamsType::ScalingList, ph.ph_scaling_list_aps_id))
+        .flatten();
+
+    active.alf_luma = alf_luma;
+    active.alf_chroma = alf_chroma;
+    active.alf_cc_cb = alf_cc_cb;
+    active.alf_cc_cr = alf_cc_cr;
+    active.lmcs = lmcs;
+    active.scaling_list = scaling_list;
+    active
+}
+
+/// State carried between pictures for POC derivation (H.266 8.3.1).
+#[derive(Debug, Clone, Copy)]
+struct PocState {
+    prev_poc_tid0: i32,
+    first_picture_after_eos: bool,
+}
+
+impl Default for PocState {
+    fn default() -> Self {
+        Self {
+            prev_poc_tid0: 0,
+            first_picture_after_eos: true,
+        }
+    }
+}
+
+impl PocState {
+    /// Derive PicOrderCntVal for the picture and update prevTid0Pic.
+    fn derive(
+        &mut self,
+        ph: &PictureHeader,
+        nal_type: NalUnitType,
+        temporal_id: u8,
+        log2_max_poc_lsb: u8,
+    ) -> i32 {
+        let max_poc_lsb = 1i32 << log2_max_poc_lsb;
+        let poc_lsb = ph.ph_pic_order_cnt_lsb as i32;
+
+        let poc_msb = if ph.ph_poc_msb_cycle_present_flag {
+            ph.ph_poc_msb_cycle_val as i32 * max_poc_lsb
+        } else if nal_type.is_idr()
+            || ((nal_type.is_cra() || nal_type.is_gdr()) && self.first_picture_after_eos)
+        {
+            0
+        } else {
+            let prev_lsb = self.prev_poc_tid0 & (max_poc_lsb - 1);
+            let prev_msb = self.prev_poc_tid0 - prev_lsb;
+            if poc_lsb < prev_lsb && prev_lsb - poc_lsb >= max_poc_lsb / 2 {
+                prev_msb + max_poc_lsb
+            } else if poc_lsb > prev_lsb && poc_lsb - prev_lsb > max_poc_lsb / 2 {
+                prev_msb - max_poc_lsb
+            } else {
+                prev_msb
+            }
+        };
+        let poc = poc_msb + poc_lsb;
+
+        if temporal_id == 0 && !nal_type.is_rasl() && !nal_type.is_radl() && !ph.ph_non_ref_pic_flag
+        {
+            self.prev_poc_tid0 = poc;
+        }
+        self.first_picture_after_eos = false;
+        poc
+    }
+}
+
 /// Quick parse to extract basic stream info.
 pub fn parse_vvc_quick(data: &[u8]) -> Result<VvcQuickInfo> {
     let nal_units = parse_nal_units(data)?;
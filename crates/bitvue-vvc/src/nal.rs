@@ -35,43 +35,43 @@ pub enum NalUnitType {
     CraNut = 9,
     /// Coded slice of a GDR (Gradual Decoding Refresh) picture
     GdrNut = 10,
-    /// Reserved VCL NAL unit types (11-12)
-    RsvVcl11 = 11,
-    RsvVcl12 = 12,
+    /// Reserved IRAP VCL NAL unit type
+    RsvIrap11 = 11,
     /// Operating Point Information
-    OpiNut = 13,
+    OpiNut = 12,
     /// Decoding Capability Information
-    DciNut = 14,
+    DciNut = 13,
     /// Video Parameter Set
-    VpsNut = 15,
+    VpsNut = 14,
     /// Sequence Parameter Set
-    SpsNut = 16,
+    SpsNut = 15,
     /// Picture Parameter Set
-    PpsNut = 17,
+    PpsNut = 16,
     /// Prefix Adaptation Parameter Set
-    PrefixApsNut = 18,
+    PrefixApsNut = 17,
     /// Suffix Adaptation Parameter Set
-    SuffixApsNut = 19,
+    SuffixApsNut = 18,
     /// Picture Header
-    PhNut = 20,
+    PhNut = 19,
     /// Access Unit Delimiter
-    AudNut = 21,
+    AudNut = 20,
     /// End of Sequence
-    EosNut = 22,
+    EosNut = 21,
     /// End of Bitstream
-    EobNut = 23,
+    EobNut = 22,
     /// Prefix SEI
-    PrefixSeiNut = 24,
+    PrefixSeiNut = 23,
     /// Suffix SEI
-    SuffixSeiNut = 25,
+    SuffixSeiNut = 24,
     /// Filler Data
-    FdNut = 26,
-    /// Reserved (27-30)
+    FdNut = 25,
+    /// Reserved (26-27)
+    RsvNvcl26 = 26,
     RsvNvcl27 = 27,
-    RsvNvcl28 = 28,
-    RsvNvcl29 = 29,
-    RsvNvcl30 = 30,
-    /// Unspecified (31)
+    /// Unspecified (28-31)
+    Unspec28 = 28,
+    Unspec29 = 29,
+    Unspec30 = 30,
     Unspec31 = 31,
 }
 
@@ -90,33 +90,33 @@ impl NalUnitType {
             8 => Self::IdrNLp,
             9 => Self::CraNut,
             10 => Self::GdrNut,
-            11 => Self::RsvVcl11,
-            12 => Self::RsvVcl12,
-            13 => Self::OpiNut,
-            14 => Self::DciNut,
-            15 => Self::VpsNut,
-            16 => Self::SpsNut,
-            17 => Self::PpsNut,
-            18 => Self::PrefixApsNut,
-            19 => Self::SuffixApsNut,
-            20 => Self::PhNut,
-            21 => Self::AudNut,
-            22 => Self::EosNut,
-            23 => Self::EobNut,
-            24 => Self::PrefixSeiNut,
-            25 => Self::SuffixSeiNut,
-            26 => Self::FdNut,
+            11 => Self::RsvIrap11,
+            12 => Self::OpiNut,
+            13 => Self::DciNut,
+            14 => Self::VpsNut,
+            15 => Self::SpsNut,
+            16 => Self::PpsNut,
+            17 => Self::PrefixApsNut,
+            18 => Self::SuffixApsNut,
+            19 => Self::PhNut,
+            20 => Self::AudNut,
+            21 => Self::EosNut,
+            22 => Self::EobNut,
+            23 => Self::PrefixSeiNut,
+            24 => Self::SuffixSeiNut,
+            25 => Self::FdNut,
+            26 => Self::RsvNvcl26,
             27 => Self::RsvNvcl27,
-            28 => Self::RsvNvcl28,
-            29 => Self::RsvNvcl29,
-            30 => Self::RsvNvcl30,
+            28 => Self::Unspec28,
+            29 => Self::Unspec29,
+            30 => Self::Unspec30,
             _ => Self::Unspec31,
         }
     }
 
     /// Check if this is a VCL NAL unit.
     pub fn is_vcl(&self) -> bool {
-        (*self as u8) <= 12
+        (*self as u8) <= 11
     }
 
     /// Check if this is an IRAP (Intra Random Access Point).
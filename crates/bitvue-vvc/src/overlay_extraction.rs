@@ -584,6 +584,7 @@ mod tests {
             sps_ibc_enabled_flag: false,
             sps_ciip_enabled_flag: false,
             sps_gpm_enabled_flag: false,
+            ..Default::default()
         }
     }
 
@@ -0,0 +1,487 @@
+//! VVC Picture Header parsing.
+//!
+//! The picture header carries syntax shared by all slices of a picture.
+//! It is sent either in a PH_NUT NAL unit or embedded in the first slice
+//! header. It is defined in ITU-T H.266 Section 7.3.2.8.
+
+use crate::bitreader::BitReader;
+use crate::error::{Result, VvcError};
+use crate::pps::Pps;
+use crate::slice::{parse_pred_weight_table, parse_ref_pic_lists, PredWeightTable, RefPicLists};
+use crate::sps::{read_ue_max, Sps};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// ALF parameters selected for a picture or slice.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlfParams {
+    /// ALF enabled.
+    pub alf_enabled_flag: bool,
+    /// APS IDs of the luma filter sets.
+    pub alf_aps_id_luma: Vec<u8>,
+    /// ALF enabled for Cb.
+    pub alf_cb_enabled_flag: bool,
+    /// ALF enabled for Cr.
+    pub alf_cr_enabled_flag: bool,
+    /// APS ID of the chroma filter set.
+    pub alf_aps_id_chroma: u8,
+    /// CC-ALF enabled for Cb.
+    pub alf_cc_cb_enabled_flag: bool,
+    /// APS ID of the CC-ALF Cb filter set.
+    pub alf_cc_cb_aps_id: u8,
+    /// CC-ALF enabled for Cr.
+    pub alf_cc_cr_enabled_flag: bool,
+    /// APS ID of the CC-ALF Cr filter set.
+    pub alf_cc_cr_aps_id: u8,
+}
+
+/// Deblocking parameters for a picture or slice.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeblockingParams {
+    /// Deblocking parameters present.
+    pub deblocking_params_present_flag: bool,
+    /// Deblocking filter disabled.
+    pub deblocking_filter_disabled_flag: bool,
+    /// Luma beta offset div 2.
+    pub luma_beta_offset_div2: i8,
+    /// Luma tc offset div 2.
+    pub luma_tc_offset_div2: i8,
+    /// Cb beta offset div 2.
+    pub cb_beta_offset_div2: i8,
+    /// Cb tc offset div 2.
+    pub cb_tc_offset_div2: i8,
+    /// Cr beta offset div 2.
+    pub cr_beta_offset_div2: i8,
+    /// Cr tc offset div 2.
+    pub cr_tc_offset_div2: i8,
+}
+
+impl DeblockingParams {
+    /// Deblocking parameters inherited from the PPS.
+    pub fn from_pps(pps: &Pps) -> Self {
+        Self {
+            deblocking_params_present_flag: false,
+            deblocking_filter_disabled_flag: pps.pps_deblocking_filter_disabled_flag,
+            luma_beta_offset_div2: pps.pps_luma_beta_offset_div2,
+            luma_tc_offset_div2: pps.pps_luma_tc_offset_div2,
+            cb_beta_offset_div2: pps.pps_cb_beta_offset_div2,
+            cb_tc_offset_div2: pps.pps_cb_tc_offset_div2,
+            cr_beta_offset_div2: pps.pps_cr_beta_offset_div2,
+            cr_tc_offset_div2: pps.pps_cr_tc_offset_div2,
+        }
+    }
+}
+
+/// VVC Picture Header.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PictureHeader {
+    /// Picture is a GDR or IRAP picture.
+    pub ph_gdr_or_irap_pic_flag: bool,
+    /// Picture is never used as a reference.
+    pub ph_non_ref_pic_flag: bool,
+    /// Picture is a GDR picture.
+    pub ph_gdr_pic_flag: bool,
+    /// Picture may contain P/B slices.
+    pub ph_inter_slice_allowed_flag: bool,
+    /// Picture may contain I slices.
+    pub ph_intra_slice_allowed_flag: bool,
+    /// Referenced PPS ID.
+    pub ph_pic_parameter_set_id: u8,
+    /// Picture order count LSB.
+    pub ph_pic_order_cnt_lsb: u32,
+    /// Recovery POC count (GDR only).
+    pub ph_recovery_poc_cnt: u32,
+    /// POC MSB cycle present.
+    pub ph_poc_msb_cycle_present_flag: bool,
+    /// POC MSB cycle value.
+    pub ph_poc_msb_cycle_val: u32,
+    /// ALF parameters (when signalled in the picture header).
+    pub alf: AlfParams,
+    /// LMCS enabled.
+    pub ph_lmcs_enabled_flag: bool,
+    /// LMCS APS ID.
+    pub ph_lmcs_aps_id: u8,
+    /// Chroma residual scaling enabled.
+    pub ph_chroma_residual_scale_flag: bool,
+    /// Explicit scaling list enabled.
+    pub ph_explicit_scaling_list_enabled_flag: bool,
+    /// Scaling list APS ID.
+    pub ph_scaling_list_aps_id: u8,
+    /// Virtual boundaries signalled in the picture header.
+    pub ph_virtual_boundaries_present_flag: bool,
+    /// Picture output flag.
+    pub ph_pic_output_flag: bool,
+    /// Reference picture lists (when signalled in the picture header).
+    pub ref_pic_lists: Option<RefPicLists>,
+    /// Partition constraints overridden.
+    pub ph_partition_constraints_override_flag: bool,
+    /// CU QP delta subdivision for intra slices.
+    pub ph_cu_qp_delta_subdiv_intra_slice: u8,
+    /// CU chroma QP offset subdivision for intra slices.
+    pub ph_cu_chroma_qp_offset_subdiv_intra_slice: u8,
+    /// CU QP delta subdivision for inter slices.
+    pub ph_cu_qp_delta_subdiv_inter_slice: u8,
+    /// CU chroma QP offset subdivision for inter slices.
+    pub ph_cu_chroma_qp_offset_subdiv_inter_slice: u8,
+    /// Temporal MVP enabled.
+    pub ph_temporal_mvp_enabled_flag: bool,
+    /// Collocated picture from list 0.
+    pub ph_collocated_from_l0_flag: bool,
+    /// Collocated reference index.
+    pub ph_collocated_ref_idx: u8,
+    /// MMVD full-pel only.
+    pub ph_mmvd_fullpel_only_flag: bool,
+    /// MVD L1 zero.
+    pub ph_mvd_l1_zero_flag: bool,
+    /// BDOF disabled.
+    pub ph_bdof_disabled_flag: bool,
+    /// DMVR disabled.
+    pub ph_dmvr_disabled_flag: bool,
+    /// PROF disabled.
+    pub ph_prof_disabled_flag: bool,
+    /// Prediction weight table (when signalled in the picture header).
+    pub pred_weight_table: Option<PredWeightTable>,
+    /// QP delta (when signalled in the picture header).
+    pub ph_qp_delta: i8,
+    /// Joint Cb-Cr sign flag.
+    pub ph_joint_cbcr_sign_flag: bool,
+    /// SAO enabled for luma.
+    pub ph_sao_luma_enabled_flag: bool,
+    /// SAO enabled for chroma.
+    pub ph_sao_chroma_enabled_flag: bool,
+    /// Deblocking parameters.
+    pub deblocking: DeblockingParams,
+}
+
+impl Default for PictureHeader {
+    fn default() -> Self {
+        Self {
+            ph_gdr_or_irap_pic_flag: false,
+            ph_non_ref_pic_flag: false,
+            ph_gdr_pic_flag: false,
+            ph_inter_slice_allowed_flag: false,
+            ph_intra_slice_allowed_flag: true,
+            ph_pic_parameter_set_id: 0,
+            ph_pic_order_cnt_lsb: 0,
+            ph_recovery_poc_cnt: 0,
+            ph_poc_msb_cycle_present_flag: false,
+            ph_poc_msb_cycle_val: 0,
+            alf: AlfParams::default(),
+            ph_lmcs_enabled_flag: false,
+            ph_lmcs_aps_id: 0,
+            ph_chroma_residual_scale_flag: false,
+            ph_explicit_scaling_list_enabled_flag: false,
+            ph_scaling_list_aps_id: 0,
+            ph_virtual_boundaries_present_flag: false,
+            ph_pic_output_flag: true,
+            ref_pic_lists: None,
+            ph_partition_constraints_override_flag: false,
+            ph_cu_qp_delta_subdiv_intra_slice: 0,
+            ph_cu_chroma_qp_offset_subdiv_intra_slice: 0,
+            ph_cu_qp_delta_subdiv_inter_slice: 0,
+            ph_cu_chroma_qp_offset_subdiv_inter_slice: 0,
+            ph_temporal_mvp_enabled_flag: false,
+            ph_collocated_from_l0_flag: true,
+            ph_collocated_ref_idx: 0,
+            ph_mmvd_fullpel_only_flag: false,
+            ph_mvd_l1_zero_flag: true,
+            ph_bdof_disabled_flag: true,
+            ph_dmvr_disabled_flag: true,
+            ph_prof_disabled_flag: true,
+            pred_weight_table: None,
+            ph_qp_delta: 0,
+            ph_joint_cbcr_sign_flag: false,
+            ph_sao_luma_enabled_flag: false,
+            ph_sao_chroma_enabled_flag: false,
+            deblocking: DeblockingParams::default(),
+        }
+    }
+}
+
+impl PictureHeader {
+    /// Check if the picture can only contain intra slices.
+    pub fn is_intra_only(&self) -> bool {
+        !self.ph_inter_slice_allowed_flag
+    }
+}
+
+/// Parse a PH_NUT RBSP.
+pub fn parse_picture_header(
+    data: &[u8],
+    sps_map: &HashMap<u8, Sps>,
+    pps_map: &HashMap<u8, Pps>,
+) -> Result<PictureHeader> {
+    let mut reader = BitReader::new(data);
+    parse_picture_header_structure(&mut reader, sps_map, pps_map)
+}
+
+/// Parse `picture_header_structure()` from the current reader position.
+pub fn parse_picture_header_structure(
+    reader: &mut BitReader,
+    sps_map: &HashMap<u8, Sps>,
+    pps_map: &HashMap<u8, Pps>,
+) -> Result<PictureHeader> {
+    let mut ph = PictureHeader::default();
+
+    ph.ph_gdr_or_irap_pic_flag = reader.read_bit()?;
+    ph.ph_non_ref_pic_flag = reader.read_bit()?;
+    if ph.ph_gdr_or_irap_pic_flag {
+        ph.ph_gdr_pic_flag = reader.read_bit()?;
+    }
+    ph.ph_inter_slice_allowed_flag = reader.read_bit()?;
+    if ph.ph_inter_slice_allowed_flag {
+        ph.ph_intra_slice_allowed_flag = reader.read_bit()?;
+    }
+    ph.ph_pic_parameter_set_id = read_ue_max(reader, 63, "ph_pic_parameter_set_id")? as u8;
+
+    let pps = pps_map.get(&ph.ph_pic_parameter_set_id).ok_or_else(|| {
+        VvcError::InvalidData(format!("PPS {} not found", ph.ph_pic_parameter_set_id))
+    })?;
+    let sps = sps_map.get(&pps.pps_seq_parameter_set_id).ok_or_else(|| {
+        VvcError::InvalidData(format!("SPS {} not found", pps.pps_seq_parameter_set_id))
+    })?;
+    let chroma_present = sps.chroma_array_type() != 0;
+
+    ph.ph_pic_order_cnt_lsb = reader.read_bits(sps.sps_log2_max_pic_order_cnt_lsb_minus4 + 4)?;
+    if ph.ph_gdr_pic_flag {
+        ph.ph_recovery_poc_cnt = reader.read_ue()?;
+    }
+    for _ in 0..sps.num_extra_ph_bits() {
+        let _ = reader.read_bit()?; // ph_extra_bit
+    }
+    if sps.sps_poc_msb_cycle_flag {
+        ph.ph_poc_msb_cycle_present_flag = reader.read_bit()?;
+        if ph.ph_poc_msb_cycle_present_flag {
+            ph.ph_poc_msb_cycle_val = reader.read_bits(sps.sps_poc_msb_cycle_len_minus1 + 1)?;
+        }
+    }
+
+    if sps.alf.alf_enabled_flag && pps.pps_alf_info_in_ph_flag {
+        ph.alf = parse_alf_params(reader, sps)?;
+    }
+
+    if sps.lmcs.lmcs_enabled_flag {
+        ph.ph_lmcs_enabled_flag = reader.read_bit()?;
+        if ph.ph_lmcs_enabled_flag {
+            ph.ph_lmcs_aps_id = reader.read_bits(2)? as u8;
+            if chroma_present {
+                ph.ph_chroma_residual_scale_flag = reader.read_bit()?;
+            }
+        }
+    }
+
+    if sps.sps_explicit_scaling_list_enabled_flag {
+        ph.ph_explicit_scaling_list_enabled_flag = reader.read_bit()?;
+        if ph.ph_explicit_scaling_list_enabled_flag {
+            ph.ph_scaling_list_aps_id = reader.read_bits(3)? as u8;
+        }
+    }
+
+    if sps.sps_virtual_boundaries_enabled_flag && !sps.sps_virtual_boundaries_present_flag {
+        ph.ph_virtual_boundaries_present_flag = reader.read_bit()?;
+        if ph.ph_virtual_boundaries_present_flag {
+            for _ in 0..2 {
+                let num = read_ue_max(reader, 3, "ph_num_virtual_boundaries")?;
+                for _ in 0..num {
+                    let _ = reader.read_ue()?; // ph_virtual_boundary_pos_minus1
+                }
+            }
+        }
+    }
+
+    if pps.pps_output_flag_present_flag && !ph.ph_non_ref_pic_flag {
+        ph.ph_pic_output_flag = reader.read_bit()?;
+    }
+
+    if pps.pps_rpl_info_in_ph_flag {
+        ph.ref_pic_lists = Some(parse_ref_pic_lists(reader, sps, pps)?);
+    }
+
+    if sps.sps_partition_constraints_override_enabled_flag {
+        ph.ph_partition_constraints_override_flag = reader.read_bit()?;
+    }
+
+    if ph.ph_intra_slice_allowed_flag {
+        if ph.ph_partition_constraints_override_flag {
+            skip_partition_constraints(reader)?; // intra luma
+            if sps.dual_tree.qtbtt_dual_tree_intra_flag {
+                skip_partition_constraints(reader)?; // intra chroma
+            }
+        }
+        if pps.pps_cu_qp_delta_enabled_flag {
+            ph.ph_cu_qp_delta_subdiv_intra_slice = reader.read_ue()? as u8;
+        }
+        if pps.pps_cu_chroma_qp_offset_list_enabled_flag {
+            ph.ph_cu_chroma_qp_offset_subdiv_intra_slice = reader.read_ue()? as u8;
+        }
+    }
+
+    // Tool-disable flags are inferred from the SPS when not signalled
+    ph.ph_bdof_disabled_flag =
+        sps.sps_bdof_control_present_in_ph_flag || !sps.sps_bdof_enabled_flag;
+    ph.ph_dmvr_disabled_flag =
+        sps.sps_dmvr_control_present_in_ph_flag || !sps.sps_dmvr_enabled_flag;
+    ph.ph_prof_disabled_flag = !sps.sps_affine_prof_enabled_flag;
+
+    if ph.ph_inter_slice_allowed_flag {
+        if ph.ph_partition_constraints_override_flag {
+            skip_partition_constraints(reader)?; // inter
+        }
+        if pps.pps_cu_qp_delta_enabled_flag {
+            ph.ph_cu_qp_delta_subdiv_inter_slice = reader.read_ue()? as u8;
+        }
+        if pps.pps_cu_chroma_qp_offset_list_enabled_flag {
+            ph.ph_cu_chroma_qp_offset_subdiv_inter_slice = reader.read_ue()? as u8;
+        }
+
+        let num_entries = |list: usize| {
+            ph.ref_pic_lists
+                .as_ref()
+                .map_or(0, |rpl| rpl.num_ref_entries(list))
+        };
+        if sps.sps_temporal_mvp_enabled_flag {
+            ph.ph_temporal_mvp_enabled_flag = reader.read_bit()?;
+            if ph.ph_temporal_mvp_enabled_flag && pps.pps_rpl_info_in_ph_flag {
+                if num_entries(1) > 0 {
+                    ph.ph_collocated_from_l0_flag = reader.read_bit()?;
+                }
+                if (ph.ph_collocated_from_l0_flag && num_entries(0) > 1)
+                    || (!ph.ph_collocated_from_l0_flag && num_entries(1) > 1)
+                {
+                    ph.ph_collocated_ref_idx = reader.read_ue()? as u8;
+                }
+            }
+        }
+
+        if sps.sps_mmvd_fullpel_only_enabled_flag {
+            ph.ph_mmvd_fullpel_only_flag = reader.read_bit()?;
+        }
+
+        let presence_flag = !pps.pps_rpl_info_in_ph_flag || num_entries(1) > 0;
+        if presence_flag {
+            ph.ph_mvd_l1_zero_flag = reader.read_bit()?;
+            if sps.sps_bdof_control_present_in_ph_flag {
+                ph.ph_bdof_disabled_flag = reader.read_bit()?;
+            }
+            if sps.sps_dmvr_control_present_in_ph_flag {
+                ph.ph_dmvr_disabled_flag = reader.read_bit()?;
+            }
+        }
+        if sps.sps_prof_control_present_in_ph_flag {
+            ph.ph_prof_disabled_flag = reader.read_bit()?;
+        }
+
+        if (pps.pps_weighted_pred_flag || pps.pps_weighted_bipred_flag)
+            && pps.pps_wp_info_in_ph_flag
+        {
+            let rpl = ph.ref_pic_lists.clone().unwrap_or_default();
+            ph.pred_weight_table = Some(parse_pred_weight_table(reader, sps, pps, &rpl, [0, 0])?);
+        }
+    }
+
+    if pps.pps_qp_delta_info_in_ph_flag {
+        ph.ph_qp_delta = reader.read_se()? as i8;
+    }
+
+    if sps.sps_joint_cbcr_enabled_flag {
+        ph.ph_joint_cbcr_sign_flag = reader.read_bit()?;
+    }
+
+    if sps.sps_sao_enabled_flag && pps.pps_sao_info_in_ph_flag {
+        ph.ph_sao_luma_enabled_flag = reader.read_bit()?;
+        if chroma_present {
+            ph.ph_sao_chroma_enabled_flag = reader.read_bit()?;
+        }
+    }
+
+    ph.deblocking = DeblockingParams::from_pps(pps);
+    if pps.pps_dbf_info_in_ph_flag {
+        ph.deblocking = parse_deblocking_params(reader, pps)?;
+    }
+
+    if pps.pps_picture_header_extension_present_flag {
+        let len = read_ue_max(reader, 256, "ph_extension_length")?;
+        reader.skip_bits(u64::from(len) * 8)?;
+    }
+
+    Ok(ph)
+}
+
+/// Parse the ALF syntax shared by the picture and slice headers.
+pub(crate) fn parse_alf_params(reader: &mut BitReader, sps: &Sps) -> Result<AlfParams> {
+    let mut alf = AlfParams::default();
+    alf.alf_enabled_flag = reader.read_bit()?;
+    if !alf.alf_enabled_flag {
+        return Ok(alf);
+    }
+
+    let num_luma = reader.read_bits(3)?;
+    for _ in 0..num_luma {
+        alf.alf_aps_id_luma.push(reader.read_bits(3)? as u8);
+    }
+    if sps.chroma_array_type() != 0 {
+        alf.alf_cb_enabled_flag = reader.read_bit()?;
+        alf.alf_cr_enabled_flag = reader.read_bit()?;
+    }
+    if alf.alf_cb_enabled_flag || alf.alf_cr_enabled_flag {
+        alf.alf_aps_id_chroma = reader.read_bits(3)? as u8;
+    }
+    if sps.alf.ccalf_enabled_flag {
+        alf.alf_cc_cb_enabled_flag = reader.read_bit()?;
+        if alf.alf_cc_cb_enabled_flag {
+            alf.alf_cc_cb_aps_id = reader.read_bits(3)? as u8;
+        }
+        alf.alf_cc_cr_enabled_flag = reader.read_bit()?;
+        if alf.alf_cc_cr_enabled_flag {
+            alf.alf_cc_cr_aps_id = reader.read_bits(3)? as u8;
+        }
+    }
+    Ok(alf)
+}
+
+/// Parse the deblocking syntax shared by the picture and slice headers.
+///
+/// The caller has already established that the parameters may be present.
+pub(crate) fn parse_deblocking_params(
+    reader: &mut BitReader,
+    pps: &Pps,
+) -> Result<DeblockingParams> {
+    let mut dbf = DeblockingParams::from_pps(pps);
+    dbf.deblocking_params_present_flag = reader.read_bit()?;
+    if !dbf.deblocking_params_present_flag {
+        return Ok(dbf);
+    }
+
+    dbf.deblocking_filter_disabled_flag = if !pps.pps_deblocking_filter_disabled_flag {
+        reader.read_bit()?
+    } else {
+        false
+    };
+    if !dbf.deblocking_filter_disabled_flag {
+        dbf.luma_beta_offset_div2 = reader.read_se()? as i8;
+        dbf.luma_tc_offset_div2 = reader.read_se()? as i8;
+        if pps.pps_chroma_tool_offsets_present_flag {
+            dbf.cb_beta_offset_div2 = reader.read_se()? as i8;
+            dbf.cb_tc_offset_div2 = reader.read_se()? as i8;
+            dbf.cr_beta_offset_div2 = reader.read_se()? as i8;
+            dbf.cr_tc_offset_div2 = reader.read_se()? as i8;
+        } else {
+            dbf.cb_beta_offset_div2 = dbf.luma_beta_offset_div2;
+            dbf.cb_tc_offset_div2 = dbf.luma_tc_offset_div2;
+            dbf.cr_beta_offset_div2 = dbf.luma_beta_offset_div2;
+            dbf.cr_tc_offset_div2 = dbf.luma_tc_offset_div2;
+        }
+    }
+    Ok(dbf)
+}
+
+/// Skip one set of overridden partition constraints.
+fn skip_partition_constraints(reader: &mut BitReader) -> Result<()> {
+    let _ = reader.read_ue()?; // log2_diff_min_qt_min_cb
+    let max_mtt_depth = reader.read_ue()?;
+    if max_mtt_depth != 0 {
+        let _ = reader.read_ue()?; // log2_diff_max_bt_min_qt
+        let _ = reader.read_ue()?; // log2_diff_max_tt_min_qt
+    }
+    Ok(())
+}
@@ -3,9 +3,25 @@
 //! VVC PPS contains picture-level parameters.
 
 use crate::bitreader::BitReader;
-use crate::error::Result;
+use crate::error::{Result, VvcError};
+use crate::sps::{read_ue_max, Sps};
 use serde::{Deserialize, Serialize};
 
+/// Rectangular slice layout derived from the PPS (`SliceTopLeftTileIdx` and friends).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RectSlice {
+    /// Index of the top-left tile of the slice.
+    pub top_left_tile_idx: u32,
+    /// Slice width in tiles.
+    pub width_in_tiles: u32,
+    /// Slice height in tiles.
+    pub height_in_tiles: u32,
+    /// Slice height in CTU rows when the slice covers part of a single tile.
+    pub height_in_ctus: Option<u32>,
+    /// First CTU row of the slice inside its tile (partial-tile slices only).
+    pub ctu_row_offset_in_tile: u32,
+}
+
 /// VVC Picture Parameter Set.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Pps {
@@ -29,8 +45,30 @@ pub struct Pps {
     pub pps_no_pic_partition_flag: bool,
     /// Subpicture ID mapping present.
     pub pps_subpic_id_mapping_present_flag: bool,
+    /// Number of subpictures.
+    pub pps_num_subpics_minus1: u16,
+    /// Subpicture ID length.
+    pub pps_subpic_id_len_minus1: u8,
+    /// Subpicture IDs signalled in PPS.
+    pub pps_subpic_id: Vec<u32>,
+    /// Log2 CTU size (must match the SPS).
+    pub pps_log2_ctu_size_minus5: u8,
+    /// Tile column widths in CTUs (ColWidthVal).
+    pub tile_column_widths: Vec<u32>,
+    /// Tile row heights in CTUs (RowHeightVal).
+    pub tile_row_heights: Vec<u32>,
     /// Loop filter across tiles enabled.
     pub pps_loop_filter_across_tiles_enabled_flag: bool,
+    /// Rectangular slice mode.
+    pub pps_rect_slice_flag: bool,
+    /// Each subpicture consists of exactly one slice.
+    pub pps_single_slice_per_subpic_flag: bool,
+    /// Number of rectangular slices.
+    pub pps_num_slices_in_pic_minus1: u32,
+    /// Tile index deltas signalled for rectangular slices.
+    pub pps_tile_idx_delta_present_flag: bool,
+    /// Rectangular slice layout (empty unless explicitly signalled).
+    pub rect_slices: Vec<RectSlice>,
     /// Loop filter across slices enabled.
     pub pps_loop_filter_across_slices_enabled_flag: bool,
     /// Cabac init present.
@@ -45,6 +83,8 @@ pub struct Pps {
     pub pps_weighted_bipred_flag: bool,
     /// Reference wraparound enabled.
     pub pps_ref_wraparound_enabled_flag: bool,
+    /// Picture width minus wraparound offset.
+    pub pps_pic_width_minus_wraparound_offset: u32,
     /// Initial QP.
     pub pps_init_qp_minus26: i8,
     /// CU QP delta enabled.
@@ -61,14 +101,41 @@ pub struct Pps {
     pub pps_joint_cbcr_qp_offset_value: i8,
     /// Slice chroma QP offsets present.
     pub pps_slice_chroma_qp_offsets_present_flag: bool,
+    /// CU chroma QP offset list enabled.
+    pub pps_cu_chroma_qp_offset_list_enabled_flag: bool,
+    /// Chroma QP offset list length.
+    pub pps_chroma_qp_offset_list_len_minus1: u8,
     /// Deblocking filter control present.
     pub pps_deblocking_filter_control_present_flag: bool,
+    /// Deblocking parameters can be overridden in PH/SH.
+    pub pps_deblocking_filter_override_enabled_flag: bool,
     /// Deblocking filter disabled.
     pub pps_deblocking_filter_disabled_flag: bool,
+    /// Deblocking info is in the picture header.
+    pub pps_dbf_info_in_ph_flag: bool,
+    /// Deblocking beta/tc offsets (luma, cb, cr).
+    pub pps_luma_beta_offset_div2: i8,
+    pub pps_luma_tc_offset_div2: i8,
+    pub pps_cb_beta_offset_div2: i8,
+    pub pps_cb_tc_offset_div2: i8,
+    pub pps_cr_beta_offset_div2: i8,
+    pub pps_cr_tc_offset_div2: i8,
+    /// Reference picture lists are in the picture header.
+    pub pps_rpl_info_in_ph_flag: bool,
+    /// SAO info is in the picture header.
+    pub pps_sao_info_in_ph_flag: bool,
+    /// ALF info is in the picture header.
+    pub pps_alf_info_in_ph_flag: bool,
+    /// Weighted prediction table is in the picture header.
+    pub pps_wp_info_in_ph_flag: bool,
+    /// QP delta is in the picture header.
+    pub pps_qp_delta_info_in_ph_flag: bool,
     /// Picture header extension present.
     pub pps_picture_header_extension_present_flag: bool,
     /// Slice header extension present.
     pub pps_slice_header_extension_present_flag: bool,
+    /// PPS extension present.
+    pub pps_extension_flag: bool,
 }
 
 impl Default for Pps {
@@ -84,7 +151,18 @@ impl Default for Pps {
             pps_output_flag_present_flag: false,
             pps_no_pic_partition_flag: true,
             pps_subpic_id_mapping_present_flag: false,
+            pps_num_subpics_minus1: 0,
+            pps_subpic_id_len_minus1: 0,
+            pps_subpic_id: Vec::new(),
+            pps_log2_ctu_size_minus5: 2,
+            tile_column_widths: Vec::new(),
+            tile_row_heights: Vec::new(),
             pps_loop_filter_across_tiles_enabled_flag: true,
+            pps_rect_slice_flag: true,
+            pps_single_slice_per_subpic_flag: false,
+            pps_num_slices_in_pic_minus1: 0,
+            pps_tile_idx_delta_present_flag: false,
+            rect_slices: Vec::new(),
             pps_loop_filter_across_slices_enabled_flag: true,
             pps_cabac_init_present_flag: false,
             pps_num_ref_idx_default_active_minus1: [0, 0],
@@ -92,6 +170,7 @@ impl Default for Pps {
             pps_weighted_pred_flag: false,
             pps_weighted_bipred_flag: false,
             pps_ref_wraparound_enabled_flag: false,
+            pps_pic_width_minus_wraparound_offset: 0,
             pps_init_qp_minus26: 0,
             pps_cu_qp_delta_enabled_flag: false,
             pps_chroma_tool_offsets_present_flag: false,
@@ -100,10 +179,26 @@ impl Default for Pps {
             pps_joint_cbcr_qp_offset_present_flag: false,
             pps_joint_cbcr_qp_offset_value: 0,
             pps_slice_chroma_qp_offsets_present_flag: false,
+            pps_cu_chroma_qp_offset_list_enabled_flag: false,
+            pps_chroma_qp_offset_list_len_minus1: 0,
             pps_deblocking_filter_control_present_flag: false,
+            pps_deblocking_filter_override_enabled_flag: false,
             pps_deblocking_filter_disabled_flag: false,
+            pps_dbf_info_in_ph_flag: false,
+            pps_luma_beta_offset_div2: 0,
+            pps_luma_tc_offset_div2: 0,
+            pps_cb_beta_offset_div2: 0,
+            pps_cb_tc_offset_div2: 0,
+            pps_cr_beta_offset_div2: 0,
+            pps_cr_tc_offset_div2: 0,
+            pps_rpl_info_in_ph_flag: false,
+            pps_sao_info_in_ph_flag: false,
+            pps_alf_info_in_ph_flag: false,
+            pps_wp_info_in_ph_flag: false,
+            pps_qp_delta_info_in_ph_flag: false,
             pps_picture_header_extension_present_flag: false,
             pps_slice_header_extension_present_flag: false,
+            pps_extension_flag: false,
         }
     }
 }
@@ -113,6 +208,39 @@ impl Pps {
     pub fn init_qp(&self) -> i8 {
         26 + self.pps_init_qp_minus26
     }
+
+    /// Picture width in CTUs (PicWidthInCtbsY).
+    pub fn pic_width_in_ctus(&self, sps: &Sps) -> u32 {
+        self.pps_pic_width_in_luma_samples.div_ceil(sps.ctu_size())
+    }
+
+    /// Picture height in CTUs (PicHeightInCtbsY).
+    pub fn pic_height_in_ctus(&self, sps: &Sps) -> u32 {
+        self.pps_pic_height_in_luma_samples.div_ceil(sps.ctu_size())
+    }
+
+    /// Tile column widths in CTUs; a single column when the picture is not partitioned.
+    pub fn tile_columns(&self, sps: &Sps) -> Vec<u32> {
+        if self.tile_column_widths.is_empty() {
+            vec![self.pic_width_in_ctus(sps)]
+        } else {
+            self.tile_column_widths.clone()
+        }
+    }
+
+    /// Tile row heights in CTUs; a single row when the picture is not partitioned.
+    pub fn tile_rows(&self, sps: &Sps) -> Vec<u32> {
+        if self.tile_row_heights.is_empty() {
+            vec![self.pic_height_in_ctus(sps)]
+        } else {
+            self.tile_row_heights.clone()
+        }
+    }
+
+    /// Number of tiles in the picture (NumTilesInPic).
+    pub fn num_tiles_in_pic(&self) -> u32 {
+        (self.tile_column_widths.len().max(1) * self.tile_row_heights.len().max(1)) as u32
+    }
 }
 
 /// Parse PPS from RBSP data.
@@ -163,11 +291,319 @@ pub fn parse_pps(data: &[u8]) -> Result<Pps> {
     // pps_no_pic_partition_flag (1 bit)
     pps.pps_no_pic_partition_flag = reader.read_bit()?;
 
-    // Skip remaining fields for simplified parsing
+    // pps_subpic_id_mapping_present_flag (1 bit)
+    pps.pps_subpic_id_mapping_present_flag = reader.read_bit()?;
+
+    if pps.pps_subpic_id_mapping_present_flag {
+        if !pps.pps_no_pic_partition_flag {
+            pps.pps_num_subpics_minus1 =
+                read_ue_max(&mut reader, 599, "pps_num_subpics_minus1")? as u16;
+        }
+        pps.pps_subpic_id_len_minus1 =
+            read_ue_max(&mut reader, 15, "pps_subpic_id_len_minus1")? as u8;
+        for _ in 0..=pps.pps_num_subpics_minus1 {
+            pps.pps_subpic_id
+                .push(reader.read_bits(pps.pps_subpic_id_len_minus1 + 1)?);
+        }
+    }
+
+    if !pps.pps_no_pic_partition_flag {
+        parse_pic_partition(&mut reader, &mut pps)?;
+    }
+
+    pps.pps_cabac_init_present_flag = reader.read_bit()?;
+    for i in 0..2 {
+        pps.pps_num_ref_idx_default_active_minus1[i] =
+            read_ue_max(&mut reader, 14, "pps_num_ref_idx_default_active_minus1")? as u8;
+    }
+    pps.pps_rpl1_idx_present_flag = reader.read_bit()?;
+    pps.pps_weighted_pred_flag = reader.read_bit()?;
+    pps.pps_weighted_bipred_flag = reader.read_bit()?;
+    pps.pps_ref_wraparound_enabled_flag = reader.read_bit()?;
+    if pps.pps_ref_wraparound_enabled_flag {
+        pps.pps_pic_width_minus_wraparound_offset = reader.read_ue()?;
+    }
+
+    pps.pps_init_qp_minus26 = read_se_range(&mut reader, -75, 37, "pps_init_qp_minus26")?;
+    pps.pps_cu_qp_delta_enabled_flag = reader.read_bit()?;
+    pps.pps_chroma_tool_offsets_present_flag = reader.read_bit()?;
+
+    if pps.pps_chroma_tool_offsets_present_flag {
+        pps.pps_cb_qp_offset = read_se_range(&mut reader, -12, 12, "pps_cb_qp_offset")?;
+        pps.pps_cr_qp_offset = read_se_range(&mut reader, -12, 12, "pps_cr_qp_offset")?;
+        pps.pps_joint_cbcr_qp_offset_present_flag = reader.read_bit()?;
+        if pps.pps_joint_cbcr_qp_offset_present_flag {
+            pps.pps_joint_cbcr_qp_offset_value =
+                read_se_range(&mut reader, -12, 12, "pps_joint_cbcr_qp_offset_value")?;
+        }
+        pps.pps_slice_chroma_qp_offsets_present_flag = reader.read_bit()?;
+        pps.pps_cu_chroma_qp_offset_list_enabled_flag = reader.read_bit()?;
+        if pps.pps_cu_chroma_qp_offset_list_enabled_flag {
+            pps.pps_chroma_qp_offset_list_len_minus1 =
+                read_ue_max(&mut reader, 5, "pps_chroma_qp_offset_list_len_minus1")? as u8;
+            for _ in 0..=pps.pps_chroma_qp_offset_list_len_minus1 {
+                let _ = reader.read_se()?; // pps_cb_qp_offset_list
+                let _ = reader.read_se()?; // pps_cr_qp_offset_list
+                if pps.pps_joint_cbcr_qp_offset_present_flag {
+                    let _ = reader.read_se()?; // pps_joint_cbcr_qp_offset_list
+                }
+            }
+        }
+    }
+
+    pps.pps_deblocking_filter_control_present_flag = reader.read_bit()?;
+    if pps.pps_deblocking_filter_control_present_flag {
+        pps.pps_deblocking_filter_override_enabled_flag = reader.read_bit()?;
+        pps.pps_deblocking_filter_disabled_flag = reader.read_bit()?;
+        if !pps.pps_no_pic_partition_flag && pps.pps_deblocking_filter_override_enabled_flag {
+            pps.pps_dbf_info_in_ph_flag = reader.read_bit()?;
+        }
+        if !pps.pps_deblocking_filter_disabled_flag {
+            pps.pps_luma_beta_offset_div2 = reader.read_se()? as i8;
+            pps.pps_luma_tc_offset_div2 = reader.read_se()? as i8;
+            if pps.pps_chroma_tool_offsets_present_flag {
+                pps.pps_cb_beta_offset_div2 = reader.read_se()? as i8;
+                pps.pps_cb_tc_offset_div2 = reader.read_se()? as i8;
+                pps.pps_cr_beta_offset_div2 = reader.read_se()? as i8;
+                pps.pps_cr_tc_offset_div2 = reader.read_se()? as i8;
+            } else {
+                pps.pps_cb_beta_offset_div2 = pps.pps_luma_beta_offset_div2;
+                pps.pps_cb_tc_offset_div2 = pps.pps_luma_tc_offset_div2;
+                pps.pps_cr_beta_offset_div2 = pps.pps_luma_beta_offset_div2;
+                pps.pps_cr_tc_offset_div2 = pps.pps_luma_tc_offset_div2;
+            }
+        }
+    }
+
+    if !pps.pps_no_pic_partition_flag {
+        pps.pps_rpl_info_in_ph_flag = reader.read_bit()?;
+        pps.pps_sao_info_in_ph_flag = reader.read_bit()?;
+        pps.pps_alf_info_in_ph_flag = reader.read_bit()?;
+        if (pps.pps_weighted_pred_flag || pps.pps_weighted_bipred_flag)
+            && pps.pps_rpl_info_in_ph_flag
+        {
+            pps.pps_wp_info_in_ph_flag = reader.read_bit()?;
+        }
+        pps.pps_qp_delta_info_in_ph_flag = reader.read_bit()?;
+    }
+
+    pps.pps_picture_header_extension_present_flag = reader.read_bit()?;
+    pps.pps_slice_header_extension_present_flag = reader.read_bit()?;
+    pps.pps_extension_flag = reader.read_bit()?;
 
     Ok(pps)
 }
 
+/// Read se(v) and reject values outside `min..=max`.
+fn read_se_range(reader: &mut BitReader, min: i32, max: i32, name: &str) -> Result<i8> {
+    let value = reader.read_se()?;
+    if value < min || value > max {
+        return Err(VvcError::InvalidData(format!(
+            "{} {} out of range [{}, {}]",
+            name, value, min, max
+        )));
+    }
+    Ok(value as i8)
+}
+
+/// Derive tile column widths or row heights (6.5.1).
+fn derive_tile_sizes(explicit: &[u32], total: u32) -> Vec<u32> {
+    let mut sizes = Vec::new();
+    let mut remaining = total;
+    for &size in explicit {
+        sizes.push(size);
+        remaining = remaining.saturating_sub(size);
+    }
+    let uniform = explicit.last().copied().unwrap_or(total).max(1);
+    while remaining >= uniform {
+        sizes.push(uniform);
+        remaining -= uniform;
+    }
+    if remaining > 0 {
+        sizes.push(remaining);
+    }
+    sizes
+}
+
+fn parse_pic_partition(reader: &mut BitReader, pps: &mut Pps) -> Result<()> {
+    pps.pps_log2_ctu_size_minus5 = reader.read_bits(2)? as u8;
+    if pps.pps_log2_ctu_size_minus5 > 2 {
+        return Err(VvcError::InvalidData(format!(
+            "pps_log2_ctu_size_minus5 {} out of range",
+            pps.pps_log2_ctu_size_minus5
+        )));
+    }
+    let ctb_size = 1u32 << (pps.pps_log2_ctu_size_minus5 + 5);
+    let width_ctus = pps.pps_pic_width_in_luma_samples.div_ceil(ctb_size);
+    let height_ctus = pps.pps_pic_height_in_luma_samples.div_ceil(ctb_size);
+    if width_ctus == 0 || height_ctus == 0 || width_ctus > 1024 || height_ctus > 1024 {
+        return Err(VvcError::InvalidData(format!(
+            "PPS picture size {}x{} invalid for tile partitioning",
+            pps.pps_pic_width_in_luma_samples, pps.pps_pic_height_in_luma_samples
+        )));
+    }
+
+    let num_exp_cols = read_ue_max(reader, width_ctus - 1, "pps_num_exp_tile_columns_minus1")? + 1;
+    let num_exp_rows = read_ue_max(reader, height_ctus - 1, "pps_num_exp_tile_rows_minus1")? + 1;
+    let mut exp_cols = Vec::with_capacity(num_exp_cols as usize);
+    for _ in 0..num_exp_cols {
+        exp_cols.push(read_ue_max(reader, width_ctus - 1, "pps_tile_column_width_minus1")? + 1);
+    }
+    let mut exp_rows = Vec::with_capacity(num_exp_rows as usize);
+    for _ in 0..num_exp_rows {
+        exp_rows.push(read_ue_max(reader, height_ctus - 1, "pps_tile_row_height_minus1")? + 1);
+    }
+    pps.tile_column_widths = derive_tile_sizes(&exp_cols, width_ctus);
+    pps.tile_row_heights = derive_tile_sizes(&exp_rows, height_ctus);
+
+    let num_cols = pps.tile_column_widths.len() as u32;
+    let num_rows = pps.tile_row_heights.len() as u32;
+    let num_tiles = num_cols * num_rows;
+
+    if num_tiles > 1 {
+        pps.pps_loop_filter_across_tiles_enabled_flag = reader.read_bit()?;
+        pps.pps_rect_slice_flag = reader.read_bit()?;
+    }
+    if pps.pps_rect_slice_flag {
+        pps.pps_single_slice_per_subpic_flag = reader.read_bit()?;
+    }
+
+    if pps.pps_rect_slice_flag && !pps.pps_single_slice_per_subpic_flag {
+        pps.pps_num_slices_in_pic_minus1 =
+            read_ue_max(reader, 999, "pps_num_slices_in_pic_minus1")?;
+        if pps.pps_num_slices_in_pic_minus1 > 1 {
+            pps.pps_tile_idx_delta_present_flag = reader.read_bit()?;
+        }
+        parse_rect_slices(reader, pps, num_cols, num_rows)?;
+    }
+
+    if !pps.pps_rect_slice_flag
+        || pps.pps_single_slice_per_subpic_flag
+        || pps.pps_num_slices_in_pic_minus1 > 0
+    {
+        pps.pps_loop_filter_across_slices_enabled_flag = reader.read_bit()?;
+    }
+
+    Ok(())
+}
+
+fn parse_rect_slices(
+    reader: &mut BitReader,
+    pps: &mut Pps,
+    num_cols: u32,
+    num_rows: u32,
+) -> Result<()> {
+    let num_tiles = num_cols * num_rows;
+    let num_slices_minus1 = pps.pps_num_slices_in_pic_minus1 as usize;
+    let mut slices = Vec::with_capacity(num_slices_minus1 + 1);
+    let mut tile_idx: i64 = 0;
+    let mut prev_height_minus1 = 0;
+    let mut i = 0;
+
+    while i < num_slices_minus1 {
+        if tile_idx < 0 || tile_idx >= i64::from(num_tiles) {
+            return Err(VvcError::InvalidData(format!(
+                "slice {} top-left tile index {} out of range",
+                i, tile_idx
+            )));
+        }
+        let top_left = tile_idx as u32;
+        let tile_x = top_left % num_cols;
+        let tile_y = top_left / num_cols;
+
+        let width_minus1 = if tile_x != num_cols - 1 {
+            read_ue_max(reader, num_cols - 1, "pps_slice_width_in_tiles_minus1")?
+        } else {
+            0
+        };
+        let height_minus1 =
+            if tile_y != num_rows - 1 && (pps.pps_tile_idx_delta_present_flag || tile_x == 0) {
+                read_ue_max(reader, num_rows - 1, "pps_slice_height_in_tiles_minus1")?
+            } else if tile_y == num_rows - 1 {
+                0
+            } else {
+                prev_height_minus1
+            };
+        prev_height_minus1 = height_minus1;
+
+        let row_height = pps.tile_row_heights[tile_y as usize];
+        if width_minus1 == 0 && height_minus1 == 0 && row_height > 1 {
+            // Several slices inside a single tile
+            let num_exp = read_ue_max(reader, row_height, "pps_num_exp_slices_in_tile")?;
+            let mut heights = Vec::new();
+            if num_exp == 0 {
+                heights.push(row_height);
+            } else {
+                let mut exp = Vec::with_capacity(num_exp as usize);
+                for _ in 0..num_exp {
+                    exp.push(
+                        read_ue_max(
+                            reader,
+                            row_height - 1,
+                            "pps_exp_slice_height_in_ctus_minus1",
+                        )? + 1,
+                    );
+                }
+                heights = derive_tile_sizes(&exp, row_height);
+            }
+            let mut offset = 0;
+            for height in &heights {
+                slices.push(RectSlice {
+                    top_left_tile_idx: top_left,
+                    width_in_tiles: 1,
+                    height_in_tiles: 1,
+                    height_in_ctus: Some(*height),
+                    ctu_row_offset_in_tile: offset,
+                });
+                offset += height;
+            }
+            i += heights.len() - 1;
+        } else {
+            slices.push(RectSlice {
+                top_left_tile_idx: top_left,
+                width_in_tiles: width_minus1 + 1,
+                height_in_tiles: height_minus1 + 1,
+                height_in_ctus: None,
+                ctu_row_offset_in_tile: 0,
+            });
+        }
+
+        if i < num_slices_minus1 {
+            if pps.pps_tile_idx_delta_present_flag {
+                let delta = reader.read_se()?;
+                tile_idx += i64::from(delta);
+            } else {
+                tile_idx += i64::from(width_minus1 + 1);
+                if tile_idx % i64::from(num_cols) == 0 {
+                    tile_idx += i64::from(height_minus1) * i64::from(num_cols);
+                }
+            }
+        }
+        i += 1;
+    }
+
+    // The last slice covers the remaining tiles and is not signalled
+    if slices.len() == num_slices_minus1 {
+        if tile_idx < 0 || tile_idx >= i64::from(num_tiles) {
+            return Err(VvcError::InvalidData(format!(
+                "last slice top-left tile index {} out of range",
+                tile_idx
+            )));
+        }
+        let top_left = tile_idx as u32;
+        slices.push(RectSlice {
+            top_left_tile_idx: top_left,
+            width_in_tiles: num_cols - top_left % num_cols,
+            height_in_tiles: num_rows - top_left / num_cols,
+            height_in_ctus: None,
+            ctu_row_offset_in_tile: 0,
+        });
+    }
+
+    pps.rect_slices = slices;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -178,4 +614,13 @@ mod tests {
         assert_eq!(pps.init_qp(), 26);
         assert!(pps.pps_no_pic_partition_flag);
     }
+
+    #[test]
+    fn test_derive_tile_sizes_uniform_tail() {
+        // 15 CTUs with explicit widths [4] -> 4,4,4,3
+        assert_eq!(derive_tile_sizes(&[4], 15), vec![4, 4, 4, 3]);
+        // Explicit widths followed by uniform spacing of the last one
+        assert_eq!(derive_tile_sizes(&[2, 5], 15), vec![2, 5, 5, 3]);
+        assert_eq!(derive_tile_sizes(&[15], 15), vec![15]);
+    }
 }
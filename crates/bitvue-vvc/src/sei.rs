@@ -0,0 +1,128 @@
+//! VVC Supplemental Enhancement Information (SEI) parsing.
+//!
+//! SEI messages are framed as in H.265 (ITU-T H.266 Section 7.3.6). Only the
+//! decoded picture hash payload (H.266 Section D.2) is decoded; other
+//! payloads are kept as raw bytes.
+
+use crate::error::{Result, VvcError};
+use serde::{Deserialize, Serialize};
+
+/// payloadType of the decoded picture hash SEI message.
+pub const DECODED_PICTURE_HASH: u32 = 132;
+
+/// One SEI message of a prefix or suffix SEI NAL unit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeiMessage {
+    /// payloadType
+    pub payload_type: u32,
+    /// sei_payload() bytes
+    pub payload: Vec<u8>,
+}
+
+impl SeiMessage {
+    /// Decoded picture hash carried by this message, if it is one.
+    pub fn decoded_picture_hash(&self) -> Option<DecodedPictureHash> {
+        if self.payload_type != DECODED_PICTURE_HASH {
+            return None;
+        }
+        parse_decoded_picture_hash(&self.payload)
+    }
+}
+
+/// Decoded picture hash method (dph_sei_hash_type).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PictureHashType {
+    Md5,
+    Crc,
+    Checksum,
+}
+
+impl PictureHashType {
+    /// Create from dph_sei_hash_type.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Md5),
+            1 => Some(Self::Crc),
+            2 => Some(Self::Checksum),
+            _ => None,
+        }
+    }
+
+    /// Size of one component hash in bytes.
+    pub fn hash_len(&self) -> usize {
+        match self {
+            Self::Md5 => 16,
+            Self::Crc => 2,
+            Self::Checksum => 4,
+        }
+    }
+}
+
+/// Decoded picture hash SEI.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedPictureHash {
+    pub hash_type: PictureHashType,
+    /// One hash per colour component, big-endian as coded
+    pub component_hashes: Vec<Vec<u8>>,
+}
+
+/// Parse the SEI messages of an SEI RBSP (prefix or suffix SEI NAL payload).
+pub fn parse_sei(data: &[u8]) -> Result<Vec<SeiMessage>> {
+    let mut messages = Vec::new();
+    let mut offset = 0;
+
+    // Stop at the rbsp_trailing_bits byte
+    while offset < data.len() && data[offset..] != [0x80] {
+        let payload_type = read_sei_value(data, &mut offset)?;
+        let payload_size = read_sei_value(data, &mut offset)? as usize;
+
+        let end = offset
+            .checked_add(payload_size)
+            .filter(|&end| end <= data.len())
+            .ok_or(VvcError::InsufficientData {
+                expected: payload_size,
+                actual: data.len() - offset,
+            })?;
+        messages.push(SeiMessage {
+            payload_type,
+            payload: data[offset..end].to_vec(),
+        });
+        offset = end;
+    }
+
+    Ok(messages)
+}
+
+/// Read an ff-byte-extended payloadType or payloadSize value.
+fn read_sei_value(data: &[u8], offset: &mut usize) -> Result<u32> {
+    let mut value: u32 = 0;
+    loop {
+        let byte = *data
+            .get(*offset)
+            .ok_or(VvcError::UnexpectedEof(*offset as u64 * 8))?;
+        *offset += 1;
+        value = value
+            .checked_add(byte as u32)
+            .ok_or_else(|| VvcError::InvalidData("SEI value overflow".to_string()))?;
+        if byte != 0xFF {
+            return Ok(value);
+        }
+    }
+}
+
+/// Parse a decoded picture hash payload.
+///
+/// Unlike H.265, the component count is signalled by
+/// dph_sei_single_component_flag.
+pub fn parse_decoded_picture_hash(data: &[u8]) -> Option<DecodedPictureHash> {
+    let hash_type = PictureHashType::from_u8(*data.first()?)?;
+    let single_component = data.get(1)? & 0x80 != 0;
+    let count = if single_component { 1 } else { 3 };
+    let len = hash_type.hash_len();
+    let hashes = data.get(2..2 + count * len)?;
+
+    Some(DecodedPictureHash {
+        hash_type,
+        component_hashes: hashes.chunks_exact(len).map(<[u8]>::to_vec).collect(),
+    })
+}
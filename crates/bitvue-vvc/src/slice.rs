@@ -0,0 +1,897 @@
+//! VVC Slice Header parsing.
+//!
+//! Slice header contains per-slice parameters.
+//! It is defined in ITU-T H.266 Section 7.3.7.
+
+use crate::bitreader::BitReader;
+use crate::error::{Result, VvcError};
+use crate::nal::NalUnitType;
+use crate::picture_header::{
+    parse_alf_params, parse_deblocking_params, parse_picture_header_structure, AlfParams,
+    DeblockingParams, PictureHeader,
+};
+use crate::pps::Pps;
+use crate::sps::{ceil_log2, parse_ref_pic_list_struct, read_ue_max, RefPicListStruct, Sps};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// VVC slice type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SliceType {
+    /// B slice (bi-directional prediction).
+    B = 0,
+    /// P slice (uni-directional prediction).
+    P = 1,
+    /// I slice (intra prediction only).
+    I = 2,
+}
+
+impl SliceType {
+    /// Create from raw value.
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(Self::B),
+            1 => Some(Self::P),
+            2 => Some(Self::I),
+            _ => None,
+        }
+    }
+
+    /// Check if this is an intra slice.
+    pub fn is_intra(&self) -> bool {
+        matches!(self, Self::I)
+    }
+
+    /// Check if this slice uses inter prediction.
+    pub fn is_inter(&self) -> bool {
+        matches!(self, Self::B | Self::P)
+    }
+
+    /// Get human-readable name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::B => "B",
+            Self::P => "P",
+            Self::I => "I",
+        }
+    }
+
+    /// Get human-readable name (alias for compatibility).
+    pub fn as_str(&self) -> &'static str {
+        self.name()
+    }
+}
+
+/// Reference picture lists selected by a picture or slice header (`ref_pic_lists()`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RefPicLists {
+    /// List is taken from the SPS candidates.
+    pub rpl_sps_flag: [bool; 2],
+    /// Index of the SPS candidate (RplsIdx).
+    pub rpl_idx: [u8; 2],
+    /// Resolved list structures (from the SPS or signalled in the header).
+    pub lists: [RefPicListStruct; 2],
+    /// Long-term POC LSBs signalled in the header.
+    pub poc_lsb_lt: [Vec<u32>; 2],
+    /// Long-term MSB cycle present flags.
+    pub delta_poc_msb_cycle_present_flag: [Vec<bool>; 2],
+    /// Long-term MSB cycle deltas.
+    pub delta_poc_msb_cycle_lt: [Vec<u32>; 2],
+}
+
+impl RefPicLists {
+    /// Number of entries in list `i` (num_ref_entries[i][RplsIdx[i]]).
+    pub fn num_ref_entries(&self, i: usize) -> u8 {
+        self.lists[i].num_ref_entries
+    }
+}
+
+/// Prediction weight table for weighted prediction.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PredWeightTable {
+    /// Luma log2 weight denominator.
+    pub luma_log2_weight_denom: u8,
+    /// Delta chroma log2 weight denominator.
+    pub delta_chroma_log2_weight_denom: i8,
+    /// Luma weights for L0.
+    pub luma_weight_l0: Vec<i16>,
+    /// Luma offsets for L0.
+    pub luma_offset_l0: Vec<i16>,
+    /// Chroma weights for L0.
+    pub chroma_weight_l0: Vec<[i16; 2]>,
+    /// Chroma offsets for L0.
+    pub chroma_offset_l0: Vec<[i16; 2]>,
+    /// Luma weights for L1.
+    pub luma_weight_l1: Vec<i16>,
+    /// Luma offsets for L1.
+    pub luma_offset_l1: Vec<i16>,
+    /// Chroma weights for L1.
+    pub chroma_weight_l1: Vec<[i16; 2]>,
+    /// Chroma offsets for L1.
+    pub chroma_offset_l1: Vec<[i16; 2]>,
+}
+
+/// VVC Slice Header.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SliceHeader {
+    /// Picture header is embedded in this slice header.
+    pub sh_picture_header_in_slice_header_flag: bool,
+    /// Embedded picture header.
+    pub picture_header: Option<PictureHeader>,
+    /// Referenced PPS ID (from the picture header).
+    pub slice_pic_parameter_set_id: u8,
+    /// Subpicture ID.
+    pub sh_subpic_id: u32,
+    /// Slice address.
+    pub sh_slice_address: u32,
+    /// Number of tiles in a raster-scan slice.
+    pub sh_num_tiles_in_slice_minus1: u32,
+    /// Slice type (B, P, I).
+    pub slice_type: SliceType,
+    /// No output of prior pictures (for IRAP/GDR).
+    pub sh_no_output_of_prior_pics_flag: bool,
+    /// ALF parameters in effect for the slice.
+    pub alf: AlfParams,
+    /// LMCS used.
+    pub sh_lmcs_used_flag: bool,
+    /// Explicit scaling list used.
+    pub sh_explicit_scaling_list_used_flag: bool,
+    /// Reference picture lists in effect for the slice.
+    pub ref_pic_lists: Option<RefPicLists>,
+    /// Active reference count override.
+    pub sh_num_ref_idx_active_override_flag: bool,
+    /// Number of active references per list (NumRefIdxActive).
+    pub num_ref_idx_active: [u8; 2],
+    /// CABAC init flag.
+    pub sh_cabac_init_flag: bool,
+    /// Collocated from L0 flag.
+    pub sh_collocated_from_l0_flag: bool,
+    /// Collocated reference index.
+    pub sh_collocated_ref_idx: u8,
+    /// Prediction weight table in effect for the slice.
+    pub pred_weight_table: Option<PredWeightTable>,
+    /// Slice QP delta (or the picture header QP delta).
+    pub sh_qp_delta: i8,
+    /// Slice luma QP (SliceQpY).
+    pub slice_qp_y: i8,
+    /// Slice CB QP offset.
+    pub sh_cb_qp_offset: i8,
+    /// Slice CR QP offset.
+    pub sh_cr_qp_offset: i8,
+    /// Slice joint CbCr QP offset.
+    pub sh_joint_cbcr_qp_offset: i8,
+    /// CU chroma QP offset enabled.
+    pub sh_cu_chroma_qp_offset_enabled_flag: bool,
+    /// SAO used for luma.
+    pub sh_sao_luma_used_flag: bool,
+    /// SAO used for chroma.
+    pub sh_sao_chroma_used_flag: bool,
+    /// Deblocking parameters in effect for the slice.
+    pub deblocking: DeblockingParams,
+    /// Dependent quantization used.
+    pub sh_dep_quant_used_flag: bool,
+    /// Sign data hiding used.
+    pub sh_sign_data_hiding_used_flag: bool,
+    /// Transform skip residual coding disabled.
+    pub sh_ts_residual_coding_disabled_flag: bool,
+    /// Number of entry points (NumEntryPoints).
+    pub num_entry_points: u32,
+    /// Entry point offset length.
+    pub sh_entry_offset_len_minus1: u8,
+    /// Entry point offsets minus 1.
+    pub sh_entry_point_offset_minus1: Vec<u32>,
+    /// Byte offset of the slice data within the RBSP.
+    pub slice_data_byte_offset: usize,
+}
+
+impl Default for SliceHeader {
+    fn default() -> Self {
+        Self {
+            sh_picture_header_in_slice_header_flag: false,
+            picture_header: None,
+            slice_pic_parameter_set_id: 0,
+            sh_subpic_id: 0,
+            sh_slice_address: 0,
+            sh_num_tiles_in_slice_minus1: 0,
+            slice_type: SliceType::I,
+            sh_no_output_of_prior_pics_flag: false,
+            alf: AlfParams::default(),
+            sh_lmcs_used_flag: false,
+            sh_explicit_scaling_list_used_flag: false,
+            ref_pic_lists: None,
+            sh_num_ref_idx_active_override_flag: false,
+            num_ref_idx_active: [0, 0],
+            sh_cabac_init_flag: false,
+            sh_collocated_from_l0_flag: true,
+            sh_collocated_ref_idx: 0,
+            pred_weight_table: None,
+            sh_qp_delta: 0,
+            slice_qp_y: 26,
+            sh_cb_qp_offset: 0,
+            sh_cr_qp_offset: 0,
+            sh_joint_cbcr_qp_offset: 0,
+            sh_cu_chroma_qp_offset_enabled_flag: false,
+            sh_sao_luma_used_flag: false,
+            sh_sao_chroma_used_flag: false,
+            deblocking: DeblockingParams::default(),
+            sh_dep_quant_used_flag: false,
+            sh_sign_data_hiding_used_flag: false,
+            sh_ts_residual_coding_disabled_flag: false,
+            num_entry_points: 0,
+            sh_entry_offset_len_minus1: 0,
+            sh_entry_point_offset_minus1: Vec::new(),
+            slice_data_byte_offset: 0,
+        }
+    }
+}
+
+impl SliceHeader {
+    /// Get slice QP value.
+    pub fn qp(&self) -> i8 {
+        self.slice_qp_y
+    }
+
+    /// Check if this is an intra slice.
+    pub fn is_intra(&self) -> bool {
+        self.slice_type.is_intra()
+    }
+
+    /// Check if this slice uses inter prediction.
+    pub fn is_inter(&self) -> bool {
+        self.slice_type.is_inter()
+    }
+
+    /// Get number of active references in L0.
+    pub fn num_ref_idx_l0_active(&self) -> u8 {
+        self.num_ref_idx_active[0]
+    }
+
+    /// Get number of active references in L1.
+    pub fn num_ref_idx_l1_active(&self) -> u8 {
+        self.num_ref_idx_active[1]
+    }
+}
+
+/// Parse slice header from RBSP data.
+///
+/// `ph` is the picture header from the preceding PH_NUT; it is ignored when
+/// the slice carries its own picture header.
+pub fn parse_slice_header(
+    data: &[u8],
+    sps_map: &HashMap<u8, Sps>,
+    pps_map: &HashMap<u8, Pps>,
+    ph: Option<&PictureHeader>,
+    nal_type: NalUnitType,
+) -> Result<SliceHeader> {
+    let mut reader = BitReader::new(data);
+    let mut header = SliceHeader::default();
+
+    header.sh_picture_header_in_slice_header_flag = reader.read_bit()?;
+    if header.sh_picture_header_in_slice_header_flag {
+        header.picture_header = Some(parse_picture_header_structure(
+            &mut reader,
+            sps_map,
+            pps_map,
+        )?);
+    }
+    let ph = match header.picture_header.as_ref().or(ph) {
+        Some(ph) => ph.clone(),
+        None => {
+            return Err(VvcError::InvalidData(
+                "slice without a picture header".to_string(),
+            ))
+        }
+    };
+
+    header.slice_pic_parameter_set_id = ph.ph_pic_parameter_set_id;
+    let pps = pps_map.get(&ph.ph_pic_parameter_set_id).ok_or_else(|| {
+        VvcError::InvalidData(format!("PPS {} not found", ph.ph_pic_parameter_set_id))
+    })?;
+    let sps = sps_map.get(&pps.pps_seq_parameter_set_id).ok_or_else(|| {
+        VvcError::InvalidData(format!("SPS {} not found", pps.pps_seq_parameter_set_id))
+    })?;
+    let chroma_present = sps.chroma_array_type() != 0;
+    let layout = SliceLayout::new(sps, pps);
+
+    // Subpicture and slice address
+    let mut subpic_idx = 0;
+    if sps.sps_subpic_info_present_flag {
+        header.sh_subpic_id = reader.read_bits(sps.sps_subpic_id_len_minus1 + 1)?;
+        subpic_idx = layout
+            .subpic_ids
+            .iter()
+            .position(|id| *id == header.sh_subpic_id)
+            .ok_or_else(|| {
+                VvcError::InvalidData(format!("unknown subpicture ID {}", header.sh_subpic_id))
+            })?;
+    }
+
+    let num_tiles = layout.num_tiles();
+    if pps.pps_rect_slice_flag {
+        let num_slices = layout.num_slices_in_subpic(subpic_idx);
+        if num_slices > 1 {
+            header.sh_slice_address = reader.read_bits(ceil_log2(num_slices))?;
+            if header.sh_slice_address >= num_slices {
+                return Err(VvcError::InvalidData(format!(
+                    "sh_slice_address {} out of range",
+                    header.sh_slice_address
+                )));
+            }
+        }
+    } else if num_tiles > 1 {
+        header.sh_slice_address = reader.read_bits(ceil_log2(num_tiles))?;
+        if header.sh_slice_address >= num_tiles {
+            return Err(VvcError::InvalidData(format!(
+                "sh_slice_address {} out of range",
+                header.sh_slice_address
+            )));
+        }
+    }
+
+    for _ in 0..sps.num_extra_sh_bits() {
+        let _ = reader.read_bit()?; // sh_extra_bit
+    }
+
+    if !pps.pps_rect_slice_flag && num_tiles - header.sh_slice_address > 1 {
+        header.sh_num_tiles_in_slice_minus1 = read_ue_max(
+            &mut reader,
+            num_tiles - header.sh_slice_address - 1,
+            "sh_num_tiles_in_slice_minus1",
+        )?;
+    }
+
+    if ph.ph_inter_slice_allowed_flag {
+        let raw = reader.read_ue()?;
+        header.slice_type = SliceType::from_u32(raw)
+            .ok_or_else(|| VvcError::InvalidData(format!("Invalid slice type: {}", raw)))?;
+    }
+    if !ph.ph_intra_slice_allowed_flag && header.slice_type.is_intra() {
+        return Err(VvcError::InvalidData(
+            "I slice in a picture without intra slices".to_string(),
+        ));
+    }
+
+    if nal_type.is_irap() {
+        header.sh_no_output_of_prior_pics_flag = reader.read_bit()?;
+    }
+
+    header.alf = ph.alf.clone();
+    if sps.alf.alf_enabled_flag && !pps.pps_alf_info_in_ph_flag {
+        header.alf = parse_alf_params(&mut reader, sps)?;
+    }
+
+    if ph.ph_lmcs_enabled_flag && !header.sh_picture_header_in_slice_header_flag {
+        header.sh_lmcs_used_flag = reader.read_bit()?;
+    } else {
+        header.sh_lmcs_used_flag =
+            header.sh_picture_header_in_slice_header_flag && ph.ph_lmcs_enabled_flag;
+    }
+    if ph.ph_explicit_scaling_list_enabled_flag && !header.sh_picture_header_in_slice_header_flag {
+        header.sh_explicit_scaling_list_used_flag = reader.read_bit()?;
+    } else {
+        header.sh_explicit_scaling_list_used_flag = header.sh_picture_header_in_slice_header_flag
+            && ph.ph_explicit_scaling_list_enabled_flag;
+    }
+
+    // Reference picture lists
+    header.ref_pic_lists = ph.ref_pic_lists.clone();
+    if !pps.pps_rpl_info_in_ph_flag && (!nal_type.is_idr() || sps.sps_idr_rpl_present_flag) {
+        header.ref_pic_lists = Some(parse_ref_pic_lists(&mut reader, sps, pps)?);
+    }
+    let rpl = header.ref_pic_lists.clone().unwrap_or_default();
+    let slice_type = header.slice_type;
+
+    let mut num_ref_idx_active_minus1 = [0u32; 2];
+    if (slice_type != SliceType::I && rpl.num_ref_entries(0) > 1)
+        || (slice_type == SliceType::B && rpl.num_ref_entries(1) > 1)
+    {
+        header.sh_num_ref_idx_active_override_flag = reader.read_bit()?;
+        if header.sh_num_ref_idx_active_override_flag {
+            let lists = if slice_type == SliceType::B { 2 } else { 1 };
+            for (i, value) in num_ref_idx_active_minus1.iter_mut().enumerate().take(lists) {
+                if rpl.num_ref_entries(i) > 1 {
+                    *value = read_ue_max(&mut reader, 14, "sh_num_ref_idx_active_minus1")?;
+                }
+            }
+        }
+    }
+    for (i, active) in header.num_ref_idx_active.iter_mut().enumerate() {
+        *active = if slice_type == SliceType::B || (slice_type == SliceType::P && i == 0) {
+            if header.sh_num_ref_idx_active_override_flag {
+                (num_ref_idx_active_minus1[i] + 1) as u8
+            } else {
+                let default = pps.pps_num_ref_idx_default_active_minus1[i] + 1;
+                rpl.num_ref_entries(i).min(default)
+            }
+        } else {
+            0
+        };
+    }
+
+    header.sh_collocated_from_l0_flag = ph.ph_collocated_from_l0_flag;
+    header.sh_collocated_ref_idx = ph.ph_collocated_ref_idx;
+    header.pred_weight_table = ph.pred_weight_table.clone();
+    if slice_type != SliceType::I {
+        if pps.pps_cabac_init_present_flag {
+            header.sh_cabac_init_flag = reader.read_bit()?;
+        }
+        if ph.ph_temporal_mvp_enabled_flag && !pps.pps_rpl_info_in_ph_flag {
+            header.sh_collocated_from_l0_flag = if slice_type == SliceType::B {
+                reader.read_bit()?
+            } else {
+                true
+            };
+            header.sh_collocated_ref_idx = 0;
+            if (header.sh_collocated_from_l0_flag && header.num_ref_idx_active[0] > 1)
+                || (!header.sh_collocated_from_l0_flag && header.num_ref_idx_active[1] > 1)
+            {
+                header.sh_collocated_ref_idx = reader.read_ue()? as u8;
+            }
+        }
+        if !pps.pps_wp_info_in_ph_flag
+            && ((pps.pps_weighted_pred_flag && slice_type == SliceType::P)
+                || (pps.pps_weighted_bipred_flag && slice_type == SliceType::B))
+        {
+            header.pred_weight_table = Some(parse_pred_weight_table(
+                &mut reader,
+                sps,
+                pps,
+                &rpl,
+                header.num_ref_idx_active,
+            )?);
+        }
+    }
+
+    // QP
+    header.sh_qp_delta = if pps.pps_qp_delta_info_in_ph_flag {
+        ph.ph_qp_delta
+    } else {
+        reader.read_se()? as i8
+    };
+    let qp_bd_offset = 6 * i32::from(sps.sps_bitdepth_minus8);
+    let slice_qp_y = 26 + i32::from(pps.pps_init_qp_minus26) + i32::from(header.sh_qp_delta);
+    if slice_qp_y < -qp_bd_offset || slice_qp_y > 63 {
+        return Err(VvcError::InvalidData(format!(
+            "SliceQpY {} out of range",
+            slice_qp_y
+        )));
+    }
+    header.slice_qp_y = slice_qp_y as i8;
+
+    if pps.pps_slice_chroma_qp_offsets_present_flag {
+        header.sh_cb_qp_offset = reader.read_se()? as i8;
+        header.sh_cr_qp_offset = reader.read_se()? as i8;
+        if sps.sps_joint_cbcr_enabled_flag {
+            header.sh_joint_cbcr_qp_offset = reader.read_se()? as i8;
+        }
+    }
+    if pps.pps_cu_chroma_qp_offset_list_enabled_flag {
+        header.sh_cu_chroma_qp_offset_enabled_flag = reader.read_bit()?;
+    }
+
+    // SAO
+    header.sh_sao_luma_used_flag = ph.ph_sao_luma_enabled_flag;
+    header.sh_sao_chroma_used_flag = ph.ph_sao_chroma_enabled_flag;
+    if sps.sps_sao_enabled_flag && !pps.pps_sao_info_in_ph_flag {
+        header.sh_sao_luma_used_flag = reader.read_bit()?;
+        if chroma_present {
+            header.sh_sao_chroma_used_flag = reader.read_bit()?;
+        }
+    }
+
+    // Deblocking
+    header.deblocking = ph.deblocking.clone();
+    if pps.pps_deblocking_filter_override_enabled_flag && !pps.pps_dbf_info_in_ph_flag {
+        header.deblocking = parse_deblocking_params(&mut reader, pps)?;
+    }
+
+    // Residual coding
+    if sps.sps_dep_quant_enabled_flag {
+        header.sh_dep_quant_used_flag = reader.read_bit()?;
+    }
+    if sps.sps_sign_data_hiding_enabled_flag && !header.sh_dep_quant_used_flag {
+        header.sh_sign_data_hiding_used_flag = reader.read_bit()?;
+    }
+    if sps.sps_transform_skip_enabled_flag
+        && !header.sh_dep_quant_used_flag
+        && !header.sh_sign_data_hiding_used_flag
+    {
+        header.sh_ts_residual_coding_disabled_flag = reader.read_bit()?;
+    }
+
+    if pps.pps_slice_header_extension_present_flag {
+        let len = read_ue_max(&mut reader, 256, "sh_slice_header_extension_length")?;
+        reader.skip_bits(u64::from(len) * 8)?;
+    }
+
+    // Entry points
+    if sps.sps_entry_point_offsets_present_flag {
+        header.num_entry_points = if pps.pps_rect_slice_flag {
+            let slice_idx = layout
+                .slice_index(subpic_idx, header.sh_slice_address)
+                .ok_or_else(|| {
+                    VvcError::InvalidData(format!(
+                        "slice {} not found in subpicture {}",
+                        header.sh_slice_address, subpic_idx
+                    ))
+                })?;
+            layout.entry_points_in_rect(&layout.slices[slice_idx])
+        } else {
+            layout.entry_points_in_tiles(
+                header.sh_slice_address,
+                header.sh_slice_address + header.sh_num_tiles_in_slice_minus1,
+            )
+        };
+    }
+    if header.num_entry_points > 0 {
+        header.sh_entry_offset_len_minus1 =
+            read_ue_max(&mut reader, 31, "sh_entry_offset_len_minus1")? as u8;
+        let bits = header.sh_entry_offset_len_minus1 + 1;
+        for _ in 0..header.num_entry_points {
+            header
+                .sh_entry_point_offset_minus1
+                .push(reader.read_bits_u64(bits)? as u32);
+        }
+    }
+
+    // byte_alignment(): one bit equal to 1, then zero bits
+    if !reader.read_bit()? {
+        return Err(VvcError::InvalidData(
+            "slice header byte_alignment() bit is not 1".to_string(),
+        ));
+    }
+    reader.byte_align();
+    header.slice_data_byte_offset = (reader.position() / 8) as usize;
+
+    Ok(header)
+}
+
+/// Parse `ref_pic_lists()` from a picture or slice header.
+pub fn parse_ref_pic_lists(reader: &mut BitReader, sps: &Sps, pps: &Pps) -> Result<RefPicLists> {
+    let mut rpls = RefPicLists::default();
+    let poc_lsb_bits = sps.sps_log2_max_pic_order_cnt_lsb_minus4 + 4;
+
+    for i in 0..2 {
+        let num_sps_lists = sps.sps_num_ref_pic_lists[i] as u32;
+        let signalled = i == 0 || pps.pps_rpl1_idx_present_flag;
+
+        rpls.rpl_sps_flag[i] = if num_sps_lists > 0 && signalled {
+            reader.read_bit()?
+        } else if num_sps_lists == 0 {
+            false
+        } else {
+            rpls.rpl_sps_flag[0]
+        };
+
+        if rpls.rpl_sps_flag[i] {
+            rpls.rpl_idx[i] = if num_sps_lists > 1 && signalled {
+                reader.read_bits(ceil_log2(num_sps_lists))? as u8
+            } else if i == 1 && !pps.pps_rpl1_idx_present_flag {
+                rpls.rpl_idx[0]
+            } else {
+                0
+            };
+            rpls.lists[i] = sps.ref_pic_lists[i]
+                .get(rpls.rpl_idx[i] as usize)
+                .cloned()
+                .ok_or_else(|| {
+                    VvcError::InvalidData(format!(
+                        "rpl_idx[{}] {} out of range",
+                        i, rpls.rpl_idx[i]
+                    ))
+                })?;
+        } else {
+            rpls.rpl_idx[i] = num_sps_lists as u8;
+            rpls.lists[i] = parse_ref_pic_list_struct(reader, sps, i, num_sps_lists as usize)?;
+        }
+
+        let ltrp_in_header = rpls.lists[i].ltrp_in_header_flag;
+        for _ in 0..rpls.lists[i].num_ltrp_entries() {
+            if ltrp_in_header {
+                rpls.poc_lsb_lt[i].push(reader.read_bits(poc_lsb_bits)?);
+            }
+            let present = reader.read_bit()?;
+            rpls.delta_poc_msb_cycle_present_flag[i].push(present);
+            rpls.delta_poc_msb_cycle_lt[i].push(if present { reader.read_ue()? } else { 0 });
+        }
+    }
+
+    Ok(rpls)
+}
+
+/// Parse `pred_weight_table()`.
+///
+/// `num_ref_idx_active` is only used when the table is in the slice header;
+/// in the picture header the number of weights is signalled explicitly.
+pub fn parse_pred_weight_table(
+    reader: &mut BitReader,
+    sps: &Sps,
+    pps: &Pps,
+    rpl: &RefPicLists,
+    num_ref_idx_active: [u8; 2],
+) -> Result<PredWeightTable> {
+    let mut table = PredWeightTable::default();
+    let chroma_present = sps.chroma_array_type() != 0;
+
+    table.luma_log2_weight_denom = read_ue_max(reader, 7, "luma_log2_weight_denom")? as u8;
+    let mut chroma_denom = table.luma_log2_weight_denom as i32;
+    if chroma_present {
+        table.delta_chroma_log2_weight_denom = reader.read_se()? as i8;
+        chroma_denom += i32::from(table.delta_chroma_log2_weight_denom);
+        if !(0..=7).contains(&chroma_denom) {
+            return Err(VvcError::InvalidData(format!(
+                "ChromaLog2WeightDenom {} out of range",
+                chroma_denom
+            )));
+        }
+    }
+
+    let num_l0 = if pps.pps_wp_info_in_ph_flag {
+        read_ue_max(reader, 15, "num_l0_weights")?
+    } else {
+        u32::from(num_ref_idx_active[0])
+    };
+    let (luma_w, luma_o, chroma_w, chroma_o) = parse_weights(
+        reader,
+        num_l0,
+        chroma_present,
+        table.luma_log2_weight_denom,
+        chroma_denom,
+    )?;
+    table.luma_weight_l0 = luma_w;
+    table.luma_offset_l0 = luma_o;
+    table.chroma_weight_l0 = chroma_w;
+    table.chroma_offset_l0 = chroma_o;
+
+    let num_l1 = if !pps.pps_weighted_bipred_flag
+        || (pps.pps_wp_info_in_ph_flag && rpl.num_ref_entries(1) == 0)
+    {
+        0
+    } else if pps.pps_wp_info_in_ph_flag {
+        read_ue_max(reader, 15, "num_l1_weights")?
+    } else {
+        u32::from(num_ref_idx_active[1])
+    };
+    let (luma_w, luma_o, chroma_w, chroma_o) = parse_weights(
+        reader,
+        num_l1,
+        chroma_present,
+        table.luma_log2_weight_denom,
+        chroma_denom,
+    )?;
+    table.luma_weight_l1 = luma_w;
+    table.luma_offset_l1 = luma_o;
+    table.chroma_weight_l1 = chroma_w;
+    table.chroma_offset_l1 = chroma_o;
+
+    Ok(table)
+}
+
+type Weights = (Vec<i16>, Vec<i16>, Vec<[i16; 2]>, Vec<[i16; 2]>);
+
+/// Parse the weights of one list and derive LumaWeightLX/ChromaWeightLX/ChromaOffsetLX.
+fn parse_weights(
+    reader: &mut BitReader,
+    count: u32,
+    chroma_present: bool,
+    luma_denom: u8,
+    chroma_denom: i32,
+) -> Result<Weights> {
+    let count = count as usize;
+    let mut luma_flags = Vec::with_capacity(count);
+    for _ in 0..count {
+        luma_flags.push(reader.read_bit()?);
+    }
+    let mut chroma_flags = vec![false; count];
+    if chroma_present {
+        for flag in chroma_flags.iter_mut() {
+            *flag = reader.read_bit()?;
+        }
+    }
+
+    let luma_default = 1i16 << luma_denom;
+    let chroma_default = 1i16 << chroma_denom;
+    let mut luma_weight = vec![luma_default; count];
+    let mut luma_offset = vec![0i16; count];
+    let mut chroma_weight = vec![[chroma_default; 2]; count];
+    let mut chroma_offset = vec![[0i16; 2]; count];
+
+    for i in 0..count {
+        if luma_flags[i] {
+            luma_weight[i] = luma_default + reader.read_se()? as i16;
+            luma_offset[i] = reader.read_se()? as i16;
+        }
+        if chroma_flags[i] {
+            for j in 0..2 {
+                let weight = chroma_default as i32 + reader.read_se()?;
+                let delta_offset = reader.read_se()?;
+                let offset =
+                    (128 + delta_offset - ((128 * weight) >> chroma_denom)).clamp(-128, 127);
+                chroma_weight[i][j] = weight as i16;
+                chroma_offset[i][j] = offset as i16;
+            }
+        }
+    }
+
+    Ok((luma_weight, luma_offset, chroma_weight, chroma_offset))
+}
+
+/// CTU rectangle covered by a rectangular slice.
+#[derive(Debug, Clone, Copy)]
+struct CtuRect {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// Slice, tile and subpicture geometry needed to address slices (6.5.1).
+struct SliceLayout {
+    col_widths: Vec<u32>,
+    row_heights: Vec<u32>,
+    subpics: Vec<CtuRect>,
+    subpic_ids: Vec<u32>,
+    slices: Vec<CtuRect>,
+    entropy_sync: bool,
+}
+
+impl SliceLayout {
+    fn new(sps: &Sps, pps: &Pps) -> Self {
+        let col_widths = pps.tile_columns(sps);
+        let row_heights = pps.tile_rows(sps);
+
+        let subpic_layout = sps.subpic_layout();
+        let subpics: Vec<CtuRect> = subpic_layout
+            .iter()
+            .map(|s| CtuRect {
+                x: s.ctu_top_left_x,
+                y: s.ctu_top_left_y,
+                width: s.width_in_ctus,
+                height: s.height_in_ctus,
+            })
+            .collect();
+
+        // SubpicIdVal
+        let subpic_ids = (0..subpics.len())
+            .map(|i| {
+                if !sps.sps_subpic_id_mapping_explicitly_signalled_flag {
+                    i as u32
+                } else if sps.sps_subpic_id_mapping_present_flag {
+                    sps.sps_subpic_id.get(i).copied().unwrap_or(i as u32)
+                } else {
+                    pps.pps_subpic_id.get(i).copied().unwrap_or(i as u32)
+                }
+            })
+            .collect();
+
+        let mut layout = Self {
+            col_widths,
+            row_heights,
+            subpics,
+            subpic_ids,
+            slices: Vec::new(),
+            entropy_sync: sps.sps_entropy_coding_sync_enabled_flag,
+        };
+
+        layout.slices = if pps.pps_no_pic_partition_flag {
+            vec![CtuRect {
+                x: 0,
+                y: 0,
+                width: pps.pic_width_in_ctus(sps),
+                height: pps.pic_height_in_ctus(sps),
+            }]
+        } else if pps.pps_single_slice_per_subpic_flag {
+            layout.subpics.clone()
+        } else {
+            let num_cols = layout.col_widths.len() as u32;
+            pps.rect_slices
+                .iter()
+                .map(|s| {
+                    let tile_x = (s.top_left_tile_idx % num_cols) as usize;
+                    let tile_y = (s.top_left_tile_idx / num_cols) as usize;
+                    let x = layout.col_widths[..tile_x].iter().sum();
+                    let y: u32 = layout.row_heights[..tile_y].iter().sum();
+                    match s.height_in_ctus {
+                        Some(height) => CtuRect {
+                            x,
+                            y: y + s.ctu_row_offset_in_tile,
+                            width: layout.col_widths[tile_x],
+                            height,
+                        },
+                        None => CtuRect {
+                            x,
+                            y,
+                            width: layout
+                                .col_widths
+                                .iter()
+                                .skip(tile_x)
+                                .take(s.width_in_tiles as usize)
+                                .sum(),
+                            height: layout
+                                .row_heights
+                                .iter()
+                                .skip(tile_y)
+                                .take(s.height_in_tiles as usize)
+                                .sum(),
+                        },
+                    }
+                })
+                .collect()
+        };
+        layout
+    }
+
+    fn num_tiles(&self) -> u32 {
+        (self.col_widths.len() * self.row_heights.len()) as u32
+    }
+
+    fn subpic_contains(&self, subpic_idx: usize, rect: &CtuRect) -> bool {
+        self.subpics.get(subpic_idx).is_some_and(|s| {
+            rect.x >= s.x && rect.x < s.x + s.width && rect.y >= s.y && rect.y < s.y + s.height
+        })
+    }
+
+    /// NumSlicesInSubpic.
+    fn num_slices_in_subpic(&self, subpic_idx: usize) -> u32 {
+        self.slices
+            .iter()
+            .filter(|s| self.subpic_contains(subpic_idx, s))
+            .count() as u32
+    }
+
+    /// Picture-level slice index of the `address`-th slice of a subpicture.
+    fn slice_index(&self, subpic_idx: usize, address: u32) -> Option<usize> {
+        self.slices
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| self.subpic_contains(subpic_idx, s))
+            .nth(address as usize)
+            .map(|(i, _)| i)
+    }
+
+    /// NumEntryPoints of a rectangular slice.
+    fn entry_points_in_rect(&self, rect: &CtuRect) -> u32 {
+        let overlap = |start: u32, len: u32, lo: u32, hi: u32| {
+            let a = start.max(lo);
+            let b = (start + len).min(hi);
+            b.saturating_sub(a)
+        };
+
+        let mut cols = 0;
+        let mut start = 0;
+        for w in &self.col_widths {
+            if overlap(start, *w, rect.x, rect.x + rect.width) > 0 {
+                cols += 1;
+            }
+            start += w;
+        }
+        let mut rows = 0;
+        start = 0;
+        for h in &self.row_heights {
+            let n = overlap(start, *h, rect.y, rect.y + rect.height);
+            if n > 0 {
+                rows += if self.entropy_sync { n } else { 1 };
+            }
+            start += h;
+        }
+        (cols * rows).saturating_sub(1)
+    }
+
+    /// NumEntryPoints of a raster-scan slice covering tiles `first..=last`.
+    fn entry_points_in_tiles(&self, first: u32, last: u32) -> u32 {
+        let num_cols = self.col_widths.len() as u32;
+        (first..=last)
+            .map(|tile| {
+                if self.entropy_sync {
+                    self.row_heights
+                        .get((tile / num_cols) as usize)
+                        .copied()
+                        .unwrap_or(1)
+                } else {
+                    1
+                }
+            })
+            .sum::<u32>()
+            .saturating_sub(1)
+    }
+}
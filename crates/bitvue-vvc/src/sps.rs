@@ -4,7 +4,7 @@
 //! new VVC features like dual tree, ALF, LMCS, and extended partitioning.
 
 use crate::bitreader::BitReader;
-use crate::error::Result;
+use crate::error::{Result, VvcError};
 use serde::{Deserialize, Serialize};
 
 // Re-export ChromaFormat from bitvue_core for backward compatibility
@@ -64,6 +64,12 @@ pub struct ProfileTierLevel {
     pub ptl_frame_only_constraint_flag: bool,
     /// Multilayer enabled flag.
     pub ptl_multilayer_enabled_flag: bool,
+    /// General constraints info present.
+    pub gci_present_flag: bool,
+    /// Sublayer level IDC (index = TemporalId), inferred from higher sublayers when absent.
+    pub sublayer_level_idc: Vec<u8>,
+    /// General sub-profile IDCs.
+    pub general_sub_profile_idc: Vec<u32>,
 }
 
 impl Default for ProfileTierLevel {
@@ -74,6 +80,9 @@ impl Default for ProfileTierLevel {
             general_level_idc: 0,
             ptl_frame_only_constraint_flag: true,
             ptl_multilayer_enabled_flag: false,
+            gci_present_flag: false,
+            sublayer_level_idc: Vec::new(),
+            general_sub_profile_idc: Vec::new(),
         }
     }
 }
@@ -107,6 +116,60 @@ pub struct LmcsConfig {
     pub lmcs_enabled_flag: bool,
 }
 
+/// Subpicture position and size in CTUs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubpicLayout {
+    /// Top-left CTU column.
+    pub ctu_top_left_x: u32,
+    /// Top-left CTU row.
+    pub ctu_top_left_y: u32,
+    /// Width in CTUs.
+    pub width_in_ctus: u32,
+    /// Height in CTUs.
+    pub height_in_ctus: u32,
+}
+
+/// DPB parameters for the highest sublayer.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DpbParameters {
+    /// Max decoded picture buffering minus 1 (per sublayer).
+    pub dpb_max_dec_pic_buffering_minus1: Vec<u32>,
+    /// Max number of reorder pictures (per sublayer).
+    pub dpb_max_num_reorder_pics: Vec<u32>,
+    /// Max latency increase plus 1 (per sublayer).
+    pub dpb_max_latency_increase_plus1: Vec<u32>,
+}
+
+/// Reference picture list structure (`ref_pic_list_struct()`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RefPicListStruct {
+    /// Number of entries in the list.
+    pub num_ref_entries: u8,
+    /// Long-term POC LSBs are signalled in the picture/slice header.
+    pub ltrp_in_header_flag: bool,
+    /// Entry is an inter-layer reference picture.
+    pub inter_layer_ref_pic_flag: Vec<bool>,
+    /// Entry is a short-term reference picture.
+    pub st_ref_pic_flag: Vec<bool>,
+    /// POC delta of short-term entries relative to the previous one (DeltaPocValSt).
+    pub delta_poc_val_st: Vec<i32>,
+    /// POC LSBs of long-term entries signalled in the structure.
+    pub rpls_poc_lsb_lt: Vec<u32>,
+    /// Inter-layer reference index.
+    pub ilrp_idx: Vec<u32>,
+}
+
+impl RefPicListStruct {
+    /// Number of long-term entries (NumLtrpEntries).
+    pub fn num_ltrp_entries(&self) -> usize {
+        self.st_ref_pic_flag
+            .iter()
+            .zip(&self.inter_layer_ref_pic_flag)
+            .filter(|(st, ilrp)| !**st && !**ilrp)
+            .count()
+    }
+}
+
 /// VVC Sequence Parameter Set.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sps {
@@ -120,10 +183,26 @@ pub struct Sps {
     pub sps_chroma_format_idc: ChromaFormat,
     /// Log2 CTU size.
     pub sps_log2_ctu_size_minus5: u8,
+    /// PTL, DPB and HRD parameters present.
+    pub sps_ptl_dpb_hrd_params_present_flag: bool,
     /// Subpictures present.
     pub sps_subpic_info_present_flag: bool,
     /// Number of subpictures.
     pub sps_num_subpics_minus1: u16,
+    /// Subpictures are independently coded.
+    pub sps_independent_subpics_flag: bool,
+    /// All subpictures have the same size.
+    pub sps_subpic_same_size_flag: bool,
+    /// Subpicture layout (one entry per subpicture).
+    pub subpics: Vec<SubpicLayout>,
+    /// Subpicture ID length.
+    pub sps_subpic_id_len_minus1: u8,
+    /// Subpicture ID mapping explicitly signalled (in SPS or PPS).
+    pub sps_subpic_id_mapping_explicitly_signalled_flag: bool,
+    /// Subpicture ID mapping present in SPS.
+    pub sps_subpic_id_mapping_present_flag: bool,
+    /// Subpicture IDs signalled in SPS.
+    pub sps_subpic_id: Vec<u32>,
     /// Picture width in luma samples.
     pub sps_pic_width_max_in_luma_samples: u32,
     /// Picture height in luma samples.
@@ -137,30 +216,58 @@ pub struct Sps {
     pub sps_conf_win_bottom_offset: u32,
     /// Bit depth.
     pub sps_bitdepth_minus8: u8,
+    /// WPP (entropy coding sync) enabled.
+    pub sps_entropy_coding_sync_enabled_flag: bool,
+    /// Entry point offsets present in slice headers.
+    pub sps_entry_point_offsets_present_flag: bool,
     /// Log2 min luma coding block size.
     pub sps_log2_min_luma_coding_block_size_minus2: u8,
     /// POC MSB cycle present.
     pub sps_poc_msb_cycle_flag: bool,
+    /// POC MSB cycle length.
+    pub sps_poc_msb_cycle_len_minus1: u8,
     /// Log2 max POC LSB.
     pub sps_log2_max_pic_order_cnt_lsb_minus4: u8,
+    /// Extra picture header bit present flags.
+    pub sps_extra_ph_bit_present_flag: Vec<bool>,
+    /// Extra slice header bit present flags.
+    pub sps_extra_sh_bit_present_flag: Vec<bool>,
+    /// DPB parameters (when PTL/DPB/HRD present).
+    pub dpb_parameters: Option<DpbParameters>,
     /// Profile, tier, level.
     pub profile_tier_level: ProfileTierLevel,
     /// GDR enabled.
     pub sps_gdr_enabled_flag: bool,
     /// Reference picture resampling.
     pub sps_ref_pic_resampling_enabled_flag: bool,
+    /// Resolution change allowed within the CLVS.
+    pub sps_res_change_in_clvs_allowed_flag: bool,
+    /// Partition constraints can be overridden in the picture header.
+    pub sps_partition_constraints_override_enabled_flag: bool,
+    /// Log2 diff between min QT size and min CB size (intra luma, intra chroma, inter).
+    pub sps_log2_diff_min_qt_min_cb_intra_slice_luma: u8,
+    pub sps_log2_diff_min_qt_min_cb_intra_slice_chroma: u8,
+    pub sps_log2_diff_min_qt_min_cb_inter_slice: u8,
     /// Dual tree configuration.
     pub dual_tree: DualTreeConfig,
+    /// 64x64 max luma transform size.
+    pub sps_max_luma_transform_size_64_flag: bool,
     /// ALF configuration.
     pub alf: AlfConfig,
     /// LMCS configuration.
     pub lmcs: LmcsConfig,
     /// Transform skip enabled.
     pub sps_transform_skip_enabled_flag: bool,
+    /// Log2 max transform skip size.
+    pub sps_log2_transform_skip_max_size_minus2: u8,
     /// BDPCM enabled.
     pub sps_bdpcm_enabled_flag: bool,
     /// MTS intra enabled.
     pub sps_mts_enabled_flag: bool,
+    /// Explicit MTS for intra.
+    pub sps_explicit_mts_intra_enabled_flag: bool,
+    /// Explicit MTS for inter.
+    pub sps_explicit_mts_inter_enabled_flag: bool,
     /// LFNST enabled.
     pub sps_lfnst_enabled_flag: bool,
     /// Joint Cb-Cr residual enabled.
@@ -171,20 +278,104 @@ pub struct Sps {
     pub sps_sao_enabled_flag: bool,
     /// Deblocking filter control present.
     pub sps_deblocking_filter_control_present_flag: bool,
+    /// Weighted prediction for P slices.
+    pub sps_weighted_pred_flag: bool,
+    /// Weighted prediction for B slices.
+    pub sps_weighted_bipred_flag: bool,
+    /// Long-term reference pictures used.
+    pub sps_long_term_ref_pics_flag: bool,
+    /// Inter-layer prediction enabled.
+    pub sps_inter_layer_prediction_enabled_flag: bool,
+    /// Reference picture lists present in IDR slice headers.
+    pub sps_idr_rpl_present_flag: bool,
+    /// RPL1 candidates are the same as RPL0.
+    pub sps_rpl1_same_as_rpl0_flag: bool,
+    /// Number of candidate reference picture list structures per list.
+    pub sps_num_ref_pic_lists: [u8; 2],
+    /// Candidate reference picture list structures per list.
+    pub ref_pic_lists: [Vec<RefPicListStruct>; 2],
+    /// Reference wraparound enabled.
+    pub sps_ref_wraparound_enabled_flag: bool,
     /// Temporal MVP enabled.
     pub sps_temporal_mvp_enabled_flag: bool,
+    /// Subblock-based TMVP enabled.
+    pub sps_sbtmvp_enabled_flag: bool,
+    /// AMVR enabled.
+    pub sps_amvr_enabled_flag: bool,
+    /// BDOF enabled.
+    pub sps_bdof_enabled_flag: bool,
+    /// BDOF can be disabled in the picture header.
+    pub sps_bdof_control_present_in_ph_flag: bool,
+    /// SMVD enabled.
+    pub sps_smvd_enabled_flag: bool,
+    /// DMVR enabled.
+    pub sps_dmvr_enabled_flag: bool,
+    /// DMVR can be disabled in the picture header.
+    pub sps_dmvr_control_present_in_ph_flag: bool,
     /// MMVD enabled.
     pub sps_mmvd_enabled_flag: bool,
+    /// MMVD full-pel only mode can be selected in the picture header.
+    pub sps_mmvd_fullpel_only_enabled_flag: bool,
+    /// Six minus max number of merge candidates.
+    pub sps_six_minus_max_num_merge_cand: u8,
+    /// SBT enabled.
+    pub sps_sbt_enabled_flag: bool,
     /// Affine enabled.
     pub sps_affine_enabled_flag: bool,
+    /// Five minus max number of subblock merge candidates.
+    pub sps_five_minus_max_num_subblock_merge_cand: u8,
+    /// 6-parameter affine enabled.
+    pub sps_6param_affine_enabled_flag: bool,
+    /// Affine AMVR enabled.
+    pub sps_affine_amvr_enabled_flag: bool,
+    /// Affine PROF enabled.
+    pub sps_affine_prof_enabled_flag: bool,
+    /// PROF can be disabled in the picture header.
+    pub sps_prof_control_present_in_ph_flag: bool,
     /// BCW (Bi-prediction with CU-level Weights) enabled.
     pub sps_bcw_enabled_flag: bool,
-    /// IBC (Intra Block Copy) enabled.
-    pub sps_ibc_enabled_flag: bool,
     /// CIIP (Combined Inter-Intra Prediction) enabled.
     pub sps_ciip_enabled_flag: bool,
     /// GPM (Geometric Partition Mode) enabled.
     pub sps_gpm_enabled_flag: bool,
+    /// Max merge candidates minus max GPM candidates.
+    pub sps_max_num_merge_cand_minus_max_num_gpm_cand: u8,
+    /// Log2 parallel merge level.
+    pub sps_log2_parallel_merge_level_minus2: u8,
+    /// ISP enabled.
+    pub sps_isp_enabled_flag: bool,
+    /// MRL enabled.
+    pub sps_mrl_enabled_flag: bool,
+    /// MIP enabled.
+    pub sps_mip_enabled_flag: bool,
+    /// CCLM enabled.
+    pub sps_cclm_enabled_flag: bool,
+    /// Chroma sample horizontal collocation.
+    pub sps_chroma_horizontal_collocated_flag: bool,
+    /// Chroma sample vertical collocation.
+    pub sps_chroma_vertical_collocated_flag: bool,
+    /// Palette enabled.
+    pub sps_palette_enabled_flag: bool,
+    /// ACT enabled.
+    pub sps_act_enabled_flag: bool,
+    /// Min QP for transform skip.
+    pub sps_min_qp_prime_ts: u8,
+    /// IBC (Intra Block Copy) enabled.
+    pub sps_ibc_enabled_flag: bool,
+    /// Six minus max number of IBC merge candidates.
+    pub sps_six_minus_max_num_ibc_merge_cand: u8,
+    /// LADF enabled.
+    pub sps_ladf_enabled_flag: bool,
+    /// Explicit scaling lists enabled.
+    pub sps_explicit_scaling_list_enabled_flag: bool,
+    /// Dependent quantization enabled.
+    pub sps_dep_quant_enabled_flag: bool,
+    /// Sign data hiding enabled.
+    pub sps_sign_data_hiding_enabled_flag: bool,
+    /// Virtual boundaries enabled.
+    pub sps_virtual_boundaries_enabled_flag: bool,
+    /// Virtual boundaries signalled in SPS.
+    pub sps_virtual_boundaries_present_flag: bool,
 }
 
 impl Default for Sps {
@@ -195,8 +386,16 @@ impl Default for Sps {
             sps_max_sublayers_minus1: 0,
             sps_chroma_format_idc: ChromaFormat::Chroma420,
             sps_log2_ctu_size_minus5: 2,
+            sps_ptl_dpb_hrd_params_present_flag: false,
             sps_subpic_info_present_flag: false,
             sps_num_subpics_minus1: 0,
+            sps_independent_subpics_flag: true,
+            sps_subpic_same_size_flag: false,
+            subpics: Vec::new(),
+            sps_subpic_id_len_minus1: 0,
+            sps_subpic_id_mapping_explicitly_signalled_flag: false,
+            sps_subpic_id_mapping_present_flag: false,
+            sps_subpic_id: Vec::new(),
             sps_pic_width_max_in_luma_samples: 0,
             sps_pic_height_max_in_luma_samples: 0,
             sps_conformance_window_flag: false,
@@ -205,30 +404,87 @@ impl Default for Sps {
             sps_conf_win_top_offset: 0,
             sps_conf_win_bottom_offset: 0,
             sps_bitdepth_minus8: 2, // 10-bit default
+            sps_entropy_coding_sync_enabled_flag: false,
+            sps_entry_point_offsets_present_flag: false,
             sps_log2_min_luma_coding_block_size_minus2: 0,
             sps_poc_msb_cycle_flag: false,
+            sps_poc_msb_cycle_len_minus1: 0,
             sps_log2_max_pic_order_cnt_lsb_minus4: 4,
+            sps_extra_ph_bit_present_flag: Vec::new(),
+            sps_extra_sh_bit_present_flag: Vec::new(),
+            dpb_parameters: None,
             profile_tier_level: ProfileTierLevel::default(),
             sps_gdr_enabled_flag: false,
             sps_ref_pic_resampling_enabled_flag: false,
+            sps_res_change_in_clvs_allowed_flag: false,
+            sps_partition_constraints_override_enabled_flag: false,
+            sps_log2_diff_min_qt_min_cb_intra_slice_luma: 0,
+            sps_log2_diff_min_qt_min_cb_intra_slice_chroma: 0,
+            sps_log2_diff_min_qt_min_cb_inter_slice: 0,
             dual_tree: DualTreeConfig::default(),
+            sps_max_luma_transform_size_64_flag: false,
             alf: AlfConfig::default(),
             lmcs: LmcsConfig::default(),
             sps_transform_skip_enabled_flag: false,
+            sps_log2_transform_skip_max_size_minus2: 0,
             sps_bdpcm_enabled_flag: false,
             sps_mts_enabled_flag: false,
+            sps_explicit_mts_intra_enabled_flag: false,
+            sps_explicit_mts_inter_enabled_flag: false,
             sps_lfnst_enabled_flag: false,
             sps_joint_cbcr_enabled_flag: false,
             sps_same_qp_table_for_chroma_flag: true,
             sps_sao_enabled_flag: true,
             sps_deblocking_filter_control_present_flag: false,
+            sps_weighted_pred_flag: false,
+            sps_weighted_bipred_flag: false,
+            sps_long_term_ref_pics_flag: false,
+            sps_inter_layer_prediction_enabled_flag: false,
+            sps_idr_rpl_present_flag: false,
+            sps_rpl1_same_as_rpl0_flag: false,
+            sps_num_ref_pic_lists: [0, 0],
+            ref_pic_lists: [Vec::new(), Vec::new()],
+            sps_ref_wraparound_enabled_flag: false,
             sps_temporal_mvp_enabled_flag: true,
+            sps_sbtmvp_enabled_flag: false,
+            sps_amvr_enabled_flag: false,
+            sps_bdof_enabled_flag: false,
+            sps_bdof_control_present_in_ph_flag: false,
+            sps_smvd_enabled_flag: false,
+            sps_dmvr_enabled_flag: false,
+            sps_dmvr_control_present_in_ph_flag: false,
             sps_mmvd_enabled_flag: false,
+            sps_mmvd_fullpel_only_enabled_flag: false,
+            sps_six_minus_max_num_merge_cand: 0,
+            sps_sbt_enabled_flag: false,
             sps_affine_enabled_flag: false,
+            sps_five_minus_max_num_subblock_merge_cand: 0,
+            sps_6param_affine_enabled_flag: false,
+            sps_affine_amvr_enabled_flag: false,
+            sps_affine_prof_enabled_flag: false,
+            sps_prof_control_present_in_ph_flag: false,
             sps_bcw_enabled_flag: false,
-            sps_ibc_enabled_flag: false,
             sps_ciip_enabled_flag: false,
             sps_gpm_enabled_flag: false,
+            sps_max_num_merge_cand_minus_max_num_gpm_cand: 0,
+            sps_log2_parallel_merge_level_minus2: 0,
+            sps_isp_enabled_flag: false,
+            sps_mrl_enabled_flag: false,
+            sps_mip_enabled_flag: false,
+            sps_cclm_enabled_flag: false,
+            sps_chroma_horizontal_collocated_flag: true,
+            sps_chroma_vertical_collocated_flag: true,
+            sps_palette_enabled_flag: false,
+            sps_act_enabled_flag: false,
+            sps_min_qp_prime_ts: 0,
+            sps_ibc_enabled_flag: false,
+            sps_six_minus_max_num_ibc_merge_cand: 0,
+            sps_ladf_enabled_flag: false,
+            sps_explicit_scaling_list_enabled_flag: false,
+            sps_dep_quant_enabled_flag: false,
+            sps_sign_data_hiding_enabled_flag: false,
+            sps_virtual_boundaries_enabled_flag: false,
+            sps_virtual_boundaries_present_flag: false,
         }
     }
 }
@@ -291,6 +547,51 @@ impl Sps {
         self.dual_tree.qtbtt_dual_tree_intra_flag
     }
 
+    /// ChromaArrayType (0 for monochrome).
+    pub fn chroma_array_type(&self) -> u8 {
+        match self.sps_chroma_format_idc {
+            ChromaFormat::Monochrome => 0,
+            ChromaFormat::Chroma420 => 1,
+            ChromaFormat::Chroma422 => 2,
+            ChromaFormat::Chroma444 => 3,
+        }
+    }
+
+    /// Number of extra bits in the picture header (NumExtraPhBits).
+    pub fn num_extra_ph_bits(&self) -> usize {
+        self.sps_extra_ph_bit_present_flag
+            .iter()
+            .filter(|f| **f)
+            .count()
+    }
+
+    /// Number of extra bits in the slice header (NumExtraShBits).
+    pub fn num_extra_sh_bits(&self) -> usize {
+        self.sps_extra_sh_bit_present_flag
+            .iter()
+            .filter(|f| **f)
+            .count()
+    }
+
+    /// Max number of merge candidates (MaxNumMergeCand).
+    pub fn max_num_merge_cand(&self) -> u8 {
+        6u8.saturating_sub(self.sps_six_minus_max_num_merge_cand)
+    }
+
+    /// Get subpicture layout, falling back to a single full-picture subpicture.
+    pub fn subpic_layout(&self) -> Vec<SubpicLayout> {
+        if self.subpics.is_empty() {
+            vec![SubpicLayout {
+                ctu_top_left_x: 0,
+                ctu_top_left_y: 0,
+                width_in_ctus: self.pic_width_in_ctus(),
+                height_in_ctus: self.pic_height_in_ctus(),
+            }]
+        } else {
+            self.subpics.clone()
+        }
+    }
+
     /// Get profile name.
     pub fn profile_name(&self) -> &'static str {
         match self.profile_tier_level.general_profile_idc {
@@ -319,7 +620,19 @@ impl Sps {
     }
 }
 
+/// Ceil(Log2(v)) as used for u(v) field lengths.
+pub(crate) fn ceil_log2(v: u32) -> u8 {
+    if v <= 1 {
+        0
+    } else {
+        (32 - (v - 1).leading_zeros()) as u8
+    }
+}
+
 /// Parse SPS from RBSP data.
+///
+/// Parses through the virtual boundary syntax; timing/HRD parameters, VUI
+/// and extensions that follow are not needed for slice parsing and are skipped.
 pub fn parse_sps(data: &[u8]) -> Result<Sps> {
     let mut reader = BitReader::new(data);
     let mut sps = Sps::default();
@@ -340,12 +653,11 @@ pub fn parse_sps(data: &[u8]) -> Result<Sps> {
     sps.sps_log2_ctu_size_minus5 = reader.read_bits(2)? as u8;
 
     // sps_ptl_dpb_hrd_params_present_flag (1 bit)
-    let ptl_present = reader.read_bit()?;
+    sps.sps_ptl_dpb_hrd_params_present_flag = reader.read_bit()?;
 
-    if ptl_present {
-        // Parse profile_tier_level (simplified)
+    if sps.sps_ptl_dpb_hrd_params_present_flag {
         sps.profile_tier_level =
-            parse_profile_tier_level(&mut reader, sps.sps_max_sublayers_minus1)?;
+            parse_profile_tier_level(&mut reader, true, sps.sps_max_sublayers_minus1)?;
     }
 
     // sps_gdr_enabled_flag (1 bit)
@@ -355,8 +667,7 @@ pub fn parse_sps(data: &[u8]) -> Result<Sps> {
     sps.sps_ref_pic_resampling_enabled_flag = reader.read_bit()?;
 
     if sps.sps_ref_pic_resampling_enabled_flag {
-        // sps_res_change_in_clvs_allowed_flag (1 bit)
-        let _ = reader.read_bit()?;
+        sps.sps_res_change_in_clvs_allowed_flag = reader.read_bit()?;
     }
 
     // sps_pic_width_max_in_luma_samples (ue(v))
@@ -373,7 +684,7 @@ pub fn parse_sps(data: &[u8]) -> Result<Sps> {
     if sps.sps_pic_width_max_in_luma_samples > MAX_WIDTH
         || sps.sps_pic_height_max_in_luma_samples > MAX_HEIGHT
     {
-        return Err(crate::error::VvcError::InvalidData(format!(
+        return Err(VvcError::InvalidData(format!(
             "SPS dimensions {}x{} exceed maximum {}x{}",
             sps.sps_pic_width_max_in_luma_samples,
             sps.sps_pic_height_max_in_luma_samples,
@@ -384,7 +695,7 @@ pub fn parse_sps(data: &[u8]) -> Result<Sps> {
 
     // Also validate minimum dimensions
     if sps.sps_pic_width_max_in_luma_samples == 0 || sps.sps_pic_height_max_in_luma_samples == 0 {
-        return Err(crate::error::VvcError::InvalidData(format!(
+        return Err(VvcError::InvalidData(format!(
             "SPS dimensions {}x{} are invalid (must be non-zero)",
             sps.sps_pic_width_max_in_luma_samples, sps.sps_pic_height_max_in_luma_samples
         )));
@@ -404,31 +715,458 @@ pub fn parse_sps(data: &[u8]) -> Result<Sps> {
     sps.sps_subpic_info_present_flag = reader.read_bit()?;
 
     if sps.sps_subpic_info_present_flag {
-        // sps_num_subpics_minus1 (ue(v))
-        sps.sps_num_subpics_minus1 = reader.read_ue()? as u16;
-        // Skip subpic parsing for now
+        parse_subpic_info(&mut reader, &mut sps)?;
     }
 
     // sps_bitdepth_minus8 (ue(v))
-    sps.sps_bitdepth_minus8 = reader.read_ue()? as u8;
+    sps.sps_bitdepth_minus8 = read_ue_max(&mut reader, 8, "sps_bitdepth_minus8")? as u8;
+
+    sps.sps_entropy_coding_sync_enabled_flag = reader.read_bit()?;
+    sps.sps_entry_point_offsets_present_flag = reader.read_bit()?;
+
+    // sps_log2_max_pic_order_cnt_lsb_minus4 (4 bits)
+    sps.sps_log2_max_pic_order_cnt_lsb_minus4 = reader.read_bits(4)? as u8;
+    if sps.sps_log2_max_pic_order_cnt_lsb_minus4 > 12 {
+        return Err(VvcError::InvalidData(format!(
+            "sps_log2_max_pic_order_cnt_lsb_minus4 {} out of range",
+            sps.sps_log2_max_pic_order_cnt_lsb_minus4
+        )));
+    }
 
-    // More fields would be parsed here...
-    // For now, we have the essential fields
+    sps.sps_poc_msb_cycle_flag = reader.read_bit()?;
+    if sps.sps_poc_msb_cycle_flag {
+        sps.sps_poc_msb_cycle_len_minus1 = read_ue_max(
+            &mut reader,
+            27 - u32::from(sps.sps_log2_max_pic_order_cnt_lsb_minus4),
+            "sps_poc_msb_cycle_len_minus1",
+        )? as u8;
+    }
+
+    // Extra picture header and slice header bits
+    let num_extra_ph_bytes = reader.read_bits(2)?;
+    for _ in 0..num_extra_ph_bytes * 8 {
+        sps.sps_extra_ph_bit_present_flag.push(reader.read_bit()?);
+    }
+    let num_extra_sh_bytes = reader.read_bits(2)?;
+    for _ in 0..num_extra_sh_bytes * 8 {
+        sps.sps_extra_sh_bit_present_flag.push(reader.read_bit()?);
+    }
+
+    if sps.sps_ptl_dpb_hrd_params_present_flag {
+        let sublayer_dpb_params_flag = if sps.sps_max_sublayers_minus1 > 0 {
+            reader.read_bit()?
+        } else {
+            false
+        };
+        sps.dpb_parameters = Some(parse_dpb_parameters(
+            &mut reader,
+            sps.sps_max_sublayers_minus1,
+            sublayer_dpb_params_flag,
+        )?);
+    }
+
+    // Block partitioning
+    sps.sps_log2_min_luma_coding_block_size_minus2 =
+        read_ue_max(&mut reader, 5, "sps_log2_min_luma_coding_block_size_minus2")? as u8;
+    sps.sps_partition_constraints_override_enabled_flag = reader.read_bit()?;
+    sps.sps_log2_diff_min_qt_min_cb_intra_slice_luma = reader.read_ue()? as u8;
+    sps.dual_tree.max_mtt_hierarchy_depth_intra_slice_luma = reader.read_ue()? as u8;
+    if sps.dual_tree.max_mtt_hierarchy_depth_intra_slice_luma != 0 {
+        let _ = reader.read_ue()?; // sps_log2_diff_max_bt_min_qt_intra_slice_luma
+        let _ = reader.read_ue()?; // sps_log2_diff_max_tt_min_qt_intra_slice_luma
+    }
+    if sps.sps_chroma_format_idc != ChromaFormat::Monochrome {
+        sps.dual_tree.qtbtt_dual_tree_intra_flag = reader.read_bit()?;
+    }
+    if sps.dual_tree.qtbtt_dual_tree_intra_flag {
+        sps.sps_log2_diff_min_qt_min_cb_intra_slice_chroma = reader.read_ue()? as u8;
+        sps.dual_tree.max_mtt_hierarchy_depth_intra_slice_chroma = reader.read_ue()? as u8;
+        if sps.dual_tree.max_mtt_hierarchy_depth_intra_slice_chroma != 0 {
+            let _ = reader.read_ue()?; // sps_log2_diff_max_bt_min_qt_intra_slice_chroma
+            let _ = reader.read_ue()?; // sps_log2_diff_max_tt_min_qt_intra_slice_chroma
+        }
+    }
+    sps.sps_log2_diff_min_qt_min_cb_inter_slice = reader.read_ue()? as u8;
+    sps.dual_tree.max_mtt_hierarchy_depth_inter_slice = reader.read_ue()? as u8;
+    if sps.dual_tree.max_mtt_hierarchy_depth_inter_slice != 0 {
+        let _ = reader.read_ue()?; // sps_log2_diff_max_bt_min_qt_inter_slice
+        let _ = reader.read_ue()?; // sps_log2_diff_max_tt_min_qt_inter_slice
+    }
+    if sps.ctu_size() > 32 {
+        sps.sps_max_luma_transform_size_64_flag = reader.read_bit()?;
+    }
+
+    // Transform and residual coding tools
+    sps.sps_transform_skip_enabled_flag = reader.read_bit()?;
+    if sps.sps_transform_skip_enabled_flag {
+        sps.sps_log2_transform_skip_max_size_minus2 = reader.read_ue()? as u8;
+        sps.sps_bdpcm_enabled_flag = reader.read_bit()?;
+    }
+    sps.sps_mts_enabled_flag = reader.read_bit()?;
+    if sps.sps_mts_enabled_flag {
+        sps.sps_explicit_mts_intra_enabled_flag = reader.read_bit()?;
+        sps.sps_explicit_mts_inter_enabled_flag = reader.read_bit()?;
+    }
+    sps.sps_lfnst_enabled_flag = reader.read_bit()?;
+
+    if sps.chroma_array_type() != 0 {
+        sps.sps_joint_cbcr_enabled_flag = reader.read_bit()?;
+        sps.sps_same_qp_table_for_chroma_flag = reader.read_bit()?;
+        let num_qp_tables = if sps.sps_same_qp_table_for_chroma_flag {
+            1
+        } else if sps.sps_joint_cbcr_enabled_flag {
+            3
+        } else {
+            2
+        };
+        for _ in 0..num_qp_tables {
+            let _ = reader.read_se()?; // sps_qp_table_start_minus26
+            let num_points_minus1 = read_ue_max(&mut reader, 63, "sps_num_points_in_qp_table")?;
+            for _ in 0..=num_points_minus1 {
+                let _ = reader.read_ue()?; // sps_delta_qp_in_val_minus1
+                let _ = reader.read_ue()?; // sps_delta_qp_diff_val
+            }
+        }
+    }
+
+    // In-loop filters
+    sps.sps_sao_enabled_flag = reader.read_bit()?;
+    sps.alf.alf_enabled_flag = reader.read_bit()?;
+    if sps.alf.alf_enabled_flag && sps.chroma_array_type() != 0 {
+        sps.alf.ccalf_enabled_flag = reader.read_bit()?;
+    }
+    sps.lmcs.lmcs_enabled_flag = reader.read_bit()?;
+
+    // Reference picture lists
+    sps.sps_weighted_pred_flag = reader.read_bit()?;
+    sps.sps_weighted_bipred_flag = reader.read_bit()?;
+    sps.sps_long_term_ref_pics_flag = reader.read_bit()?;
+    if sps.sps_video_parameter_set_id > 0 {
+        sps.sps_inter_layer_prediction_enabled_flag = reader.read_bit()?;
+    }
+    sps.sps_idr_rpl_present_flag = reader.read_bit()?;
+    sps.sps_rpl1_same_as_rpl0_flag = reader.read_bit()?;
+
+    let num_lists = if sps.sps_rpl1_same_as_rpl0_flag { 1 } else { 2 };
+    for list_idx in 0..num_lists {
+        let num = read_ue_max(&mut reader, 64, "sps_num_ref_pic_lists")?;
+        sps.sps_num_ref_pic_lists[list_idx] = num as u8;
+        for rpls_idx in 0..num {
+            let rpl = parse_ref_pic_list_struct(&mut reader, &sps, list_idx, rpls_idx as usize)?;
+            sps.ref_pic_lists[list_idx].push(rpl);
+        }
+    }
+    if sps.sps_rpl1_same_as_rpl0_flag {
+        sps.sps_num_ref_pic_lists[1] = sps.sps_num_ref_pic_lists[0];
+        sps.ref_pic_lists[1] = sps.ref_pic_lists[0].clone();
+    }
+
+    // Inter prediction tools
+    sps.sps_ref_wraparound_enabled_flag = reader.read_bit()?;
+    sps.sps_temporal_mvp_enabled_flag = reader.read_bit()?;
+    if sps.sps_temporal_mvp_enabled_flag {
+        sps.sps_sbtmvp_enabled_flag = reader.read_bit()?;
+    }
+    sps.sps_amvr_enabled_flag = reader.read_bit()?;
+    sps.sps_bdof_enabled_flag = reader.read_bit()?;
+    if sps.sps_bdof_enabled_flag {
+        sps.sps_bdof_control_present_in_ph_flag = reader.read_bit()?;
+    }
+    sps.sps_smvd_enabled_flag = reader.read_bit()?;
+    sps.sps_dmvr_enabled_flag = reader.read_bit()?;
+    if sps.sps_dmvr_enabled_flag {
+        sps.sps_dmvr_control_present_in_ph_flag = reader.read_bit()?;
+    }
+    sps.sps_mmvd_enabled_flag = reader.read_bit()?;
+    if sps.sps_mmvd_enabled_flag {
+        sps.sps_mmvd_fullpel_only_enabled_flag = reader.read_bit()?;
+    }
+    sps.sps_six_minus_max_num_merge_cand =
+        read_ue_max(&mut reader, 5, "sps_six_minus_max_num_merge_cand")? as u8;
+    sps.sps_sbt_enabled_flag = reader.read_bit()?;
+    sps.sps_affine_enabled_flag = reader.read_bit()?;
+    if sps.sps_affine_enabled_flag {
+        sps.sps_five_minus_max_num_subblock_merge_cand = reader.read_ue()? as u8;
+        sps.sps_6param_affine_enabled_flag = reader.read_bit()?;
+        if sps.sps_amvr_enabled_flag {
+            sps.sps_affine_amvr_enabled_flag = reader.read_bit()?;
+        }
+        sps.sps_affine_prof_enabled_flag = reader.read_bit()?;
+        if sps.sps_affine_prof_enabled_flag {
+            sps.sps_prof_control_present_in_ph_flag = reader.read_bit()?;
+        }
+    }
+    sps.sps_bcw_enabled_flag = reader.read_bit()?;
+    sps.sps_ciip_enabled_flag = reader.read_bit()?;
+    if sps.max_num_merge_cand() >= 2 {
+        sps.sps_gpm_enabled_flag = reader.read_bit()?;
+        if sps.sps_gpm_enabled_flag && sps.max_num_merge_cand() >= 3 {
+            sps.sps_max_num_merge_cand_minus_max_num_gpm_cand = reader.read_ue()? as u8;
+        }
+    }
+    sps.sps_log2_parallel_merge_level_minus2 = reader.read_ue()? as u8;
+
+    // Intra prediction tools
+    sps.sps_isp_enabled_flag = reader.read_bit()?;
+    sps.sps_mrl_enabled_flag = reader.read_bit()?;
+    sps.sps_mip_enabled_flag = reader.read_bit()?;
+    if sps.chroma_array_type() != 0 {
+        sps.sps_cclm_enabled_flag = reader.read_bit()?;
+    }
+    if sps.sps_chroma_format_idc == ChromaFormat::Chroma420 {
+        sps.sps_chroma_horizontal_collocated_flag = reader.read_bit()?;
+        sps.sps_chroma_vertical_collocated_flag = reader.read_bit()?;
+    }
+    sps.sps_palette_enabled_flag = reader.read_bit()?;
+    if sps.sps_chroma_format_idc == ChromaFormat::Chroma444
+        && !sps.sps_max_luma_transform_size_64_flag
+    {
+        sps.sps_act_enabled_flag = reader.read_bit()?;
+    }
+    if sps.sps_transform_skip_enabled_flag || sps.sps_palette_enabled_flag {
+        sps.sps_min_qp_prime_ts = reader.read_ue()? as u8;
+    }
+    sps.sps_ibc_enabled_flag = reader.read_bit()?;
+    if sps.sps_ibc_enabled_flag {
+        sps.sps_six_minus_max_num_ibc_merge_cand = reader.read_ue()? as u8;
+    }
+
+    sps.sps_ladf_enabled_flag = reader.read_bit()?;
+    if sps.sps_ladf_enabled_flag {
+        let num_ladf_intervals_minus2 = reader.read_bits(2)?;
+        let _ = reader.read_se()?; // sps_ladf_lowest_interval_qp_offset
+        for _ in 0..num_ladf_intervals_minus2 + 1 {
+            let _ = reader.read_se()?; // sps_ladf_qp_offset
+            let _ = reader.read_ue()?; // sps_ladf_delta_threshold_minus1
+        }
+    }
+
+    // Quantization
+    sps.sps_explicit_scaling_list_enabled_flag = reader.read_bit()?;
+    if sps.sps_lfnst_enabled_flag && sps.sps_explicit_scaling_list_enabled_flag {
+        let _ = reader.read_bit()?; // sps_scaling_matrix_for_lfnst_disabled_flag
+    }
+    if sps.sps_act_enabled_flag && sps.sps_explicit_scaling_list_enabled_flag {
+        // sps_scaling_matrix_for_alternative_colour_space_disabled_flag
+        if reader.read_bit()? {
+            let _ = reader.read_bit()?; // sps_scaling_matrix_designated_colour_space_flag
+        }
+    }
+    sps.sps_dep_quant_enabled_flag = reader.read_bit()?;
+    sps.sps_sign_data_hiding_enabled_flag = reader.read_bit()?;
+
+    sps.sps_virtual_boundaries_enabled_flag = reader.read_bit()?;
+    if sps.sps_virtual_boundaries_enabled_flag {
+        sps.sps_virtual_boundaries_present_flag = reader.read_bit()?;
+        if sps.sps_virtual_boundaries_present_flag {
+            for _ in 0..2 {
+                let num = read_ue_max(&mut reader, 3, "sps_num_virtual_boundaries")?;
+                for _ in 0..num {
+                    let _ = reader.read_ue()?; // sps_virtual_boundary_pos_minus1
+                }
+            }
+        }
+    }
+
+    // Timing/HRD parameters, VUI and SPS extensions follow; nothing
+    // downstream depends on them yet.
 
     Ok(sps)
 }
 
+/// Read ue(v) and reject values above `max`.
+pub(crate) fn read_ue_max(reader: &mut BitReader, max: u32, name: &str) -> Result<u32> {
+    let value = reader.read_ue()?;
+    if value > max {
+        return Err(VvcError::InvalidData(format!(
+            "{} {} exceeds maximum {}",
+            name, value, max
+        )));
+    }
+    Ok(value)
+}
+
+fn parse_subpic_info(reader: &mut BitReader, sps: &mut Sps) -> Result<()> {
+    sps.sps_num_subpics_minus1 = read_ue_max(reader, 599, "sps_num_subpics_minus1")? as u16;
+    let num_subpics = sps.sps_num_subpics_minus1 as usize + 1;
+
+    if sps.sps_num_subpics_minus1 > 0 {
+        sps.sps_independent_subpics_flag = reader.read_bit()?;
+        sps.sps_subpic_same_size_flag = reader.read_bit()?;
+    }
+
+    let ctb_size = sps.ctu_size();
+    let width_ctus = sps.pic_width_in_ctus();
+    let height_ctus = sps.pic_height_in_ctus();
+    let x_bits = ceil_log2(width_ctus);
+    let y_bits = ceil_log2(height_ctus);
+    let wide = sps.sps_pic_width_max_in_luma_samples > ctb_size;
+    let tall = sps.sps_pic_height_max_in_luma_samples > ctb_size;
+
+    let mut subpics = Vec::with_capacity(num_subpics);
+    if sps.sps_num_subpics_minus1 == 0 {
+        subpics.push(SubpicLayout {
+            ctu_top_left_x: 0,
+            ctu_top_left_y: 0,
+            width_in_ctus: width_ctus,
+            height_in_ctus: height_ctus,
+        });
+    } else {
+        let last = num_subpics - 1;
+        for i in 0..num_subpics {
+            let mut layout = SubpicLayout::default();
+            if !sps.sps_subpic_same_size_flag || i == 0 {
+                if i > 0 && wide {
+                    layout.ctu_top_left_x = reader.read_bits(x_bits)?;
+                }
+                if i > 0 && tall {
+                    layout.ctu_top_left_y = reader.read_bits(y_bits)?;
+                }
+                layout.width_in_ctus = if i < last && wide {
+                    reader.read_bits(x_bits)? + 1
+                } else {
+                    width_ctus.saturating_sub(layout.ctu_top_left_x)
+                };
+                layout.height_in_ctus = if i < last && tall {
+                    reader.read_bits(y_bits)? + 1
+                } else {
+                    height_ctus.saturating_sub(layout.ctu_top_left_y)
+                };
+            } else {
+                // Same-size subpictures are laid out in raster order
+                let w = subpics[0].width_in_ctus.max(1);
+                let h = subpics[0].height_in_ctus.max(1);
+                let num_cols = (width_ctus / w).max(1);
+                layout.ctu_top_left_x = (i as u32 % num_cols) * w;
+                layout.ctu_top_left_y = (i as u32 / num_cols) * h;
+                layout.width_in_ctus = w.min(width_ctus.saturating_sub(layout.ctu_top_left_x));
+                layout.height_in_ctus = h.min(height_ctus.saturating_sub(layout.ctu_top_left_y));
+            }
+            if !sps.sps_independent_subpics_flag {
+                let _ = reader.read_bit()?; // sps_subpic_treated_as_pic_flag
+                let _ = reader.read_bit()?; // sps_loop_filter_across_subpic_enabled_flag
+            }
+            subpics.push(layout);
+        }
+    }
+    sps.subpics = subpics;
+
+    sps.sps_subpic_id_len_minus1 = read_ue_max(reader, 15, "sps_subpic_id_len_minus1")? as u8;
+    sps.sps_subpic_id_mapping_explicitly_signalled_flag = reader.read_bit()?;
+    if sps.sps_subpic_id_mapping_explicitly_signalled_flag {
+        sps.sps_subpic_id_mapping_present_flag = reader.read_bit()?;
+        if sps.sps_subpic_id_mapping_present_flag {
+            for _ in 0..num_subpics {
+                sps.sps_subpic_id
+                    .push(reader.read_bits(sps.sps_subpic_id_len_minus1 + 1)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_dpb_parameters(
+    reader: &mut BitReader,
+    max_sublayers_minus1: u8,
+    sublayer_info_flag: bool,
+) -> Result<DpbParameters> {
+    let mut dpb = DpbParameters::default();
+    let first = if sublayer_info_flag {
+        0
+    } else {
+        max_sublayers_minus1
+    };
+    for _ in first..=max_sublayers_minus1 {
+        dpb.dpb_max_dec_pic_buffering_minus1.push(reader.read_ue()?);
+        dpb.dpb_max_num_reorder_pics.push(reader.read_ue()?);
+        dpb.dpb_max_latency_increase_plus1.push(reader.read_ue()?);
+    }
+    Ok(dpb)
+}
+
+/// Parse `ref_pic_list_struct(listIdx, rplsIdx)`.
+///
+/// `rpls_idx == sps_num_ref_pic_lists[list_idx]` denotes a structure
+/// signalled directly in a picture or slice header.
+pub fn parse_ref_pic_list_struct(
+    reader: &mut BitReader,
+    sps: &Sps,
+    list_idx: usize,
+    rpls_idx: usize,
+) -> Result<RefPicListStruct> {
+    let mut rpl = RefPicListStruct::default();
+
+    let num_ref_entries = read_ue_max(reader, 28, "num_ref_entries")?;
+    rpl.num_ref_entries = num_ref_entries as u8;
+
+    rpl.ltrp_in_header_flag = if sps.sps_long_term_ref_pics_flag
+        && rpls_idx < sps.sps_num_ref_pic_lists[list_idx] as usize
+        && num_ref_entries > 0
+    {
+        reader.read_bit()?
+    } else {
+        true
+    };
+
+    let weighted = sps.sps_weighted_pred_flag || sps.sps_weighted_bipred_flag;
+    let poc_lsb_bits = sps.sps_log2_max_pic_order_cnt_lsb_minus4 + 4;
+
+    for i in 0..num_ref_entries {
+        let inter_layer = if sps.sps_inter_layer_prediction_enabled_flag {
+            reader.read_bit()?
+        } else {
+            false
+        };
+        rpl.inter_layer_ref_pic_flag.push(inter_layer);
+
+        if !inter_layer {
+            let st = if sps.sps_long_term_ref_pics_flag {
+                reader.read_bit()?
+            } else {
+                true
+            };
+            rpl.st_ref_pic_flag.push(st);
+
+            if st {
+                let abs_delta_poc_st = reader.read_ue()?;
+                let abs = if weighted && i != 0 {
+                    abs_delta_poc_st
+                } else {
+                    abs_delta_poc_st + 1
+                } as i32;
+                let sign = if abs > 0 { reader.read_bit()? } else { false };
+                rpl.delta_poc_val_st.push(if sign { -abs } else { abs });
+            } else {
+                rpl.delta_poc_val_st.push(0);
+                if !rpl.ltrp_in_header_flag {
+                    rpl.rpls_poc_lsb_lt.push(reader.read_bits(poc_lsb_bits)?);
+                }
+            }
+        } else {
+            rpl.st_ref_pic_flag.push(false);
+            rpl.delta_poc_val_st.push(0);
+            rpl.ilrp_idx.push(reader.read_ue()?);
+        }
+    }
+
+    Ok(rpl)
+}
+
 fn parse_profile_tier_level(
     reader: &mut BitReader,
-    #[allow(unused_variables)] max_sublayers_minus1: u8,
+    profile_tier_present_flag: bool,
+    max_sublayers_minus1: u8,
 ) -> Result<ProfileTierLevel> {
     let mut ptl = ProfileTierLevel::default();
 
-    // general_profile_idc (7 bits)
-    ptl.general_profile_idc = Profile::from(reader.read_bits(7)? as u8);
+    if profile_tier_present_flag {
+        // general_profile_idc (7 bits)
+        ptl.general_profile_idc = Profile::from(reader.read_bits(7)? as u8);
 
-    // general_tier_flag (1 bit)
-    ptl.general_tier_flag = reader.read_bit()?;
+        // general_tier_flag (1 bit)
+        ptl.general_tier_flag = reader.read_bit()?;
+    }
 
     // general_level_idc (8 bits)
     ptl.general_level_idc = reader.read_bits(8)? as u8;
@@ -439,8 +1177,41 @@ fn parse_profile_tier_level(
     // ptl_multilayer_enabled_flag (1 bit)
     ptl.ptl_multilayer_enabled_flag = reader.read_bit()?;
 
-    // Skip general constraint info and sublayer info
-    // This is a simplified parse
+    if profile_tier_present_flag {
+        // general_constraints_info()
+        ptl.gci_present_flag = reader.read_bit()?;
+        if ptl.gci_present_flag {
+            // 71 constraint flags/fields, then gci_num_additional_bits
+            reader.skip_bits(71)?;
+            let num_additional_bits = reader.read_bits(8)?;
+            reader.skip_bits(u64::from(num_additional_bits))?;
+        }
+        reader.byte_align();
+    }
+
+    let num_sublayers = max_sublayers_minus1 as usize;
+    let mut level_present = vec![false; num_sublayers];
+    for i in (0..num_sublayers).rev() {
+        level_present[i] = reader.read_bit()?;
+    }
+    reader.byte_align();
+
+    // Sublayer levels default to the level of the next higher sublayer
+    ptl.sublayer_level_idc = vec![ptl.general_level_idc; num_sublayers + 1];
+    for i in (0..num_sublayers).rev() {
+        ptl.sublayer_level_idc[i] = if level_present[i] {
+            reader.read_bits(8)? as u8
+        } else {
+            ptl.sublayer_level_idc[i + 1]
+        };
+    }
+
+    if profile_tier_present_flag {
+        let num_sub_profiles = reader.read_bits(8)?;
+        for _ in 0..num_sub_profiles {
+            ptl.general_sub_profile_idc.push(reader.read_bits(32)?);
+        }
+    }
 
     Ok(ptl)
 }
@@ -477,4 +1248,14 @@ mod tests {
         assert_eq!(sps.pic_width_in_ctus(), 15); // ceil(1920/128)
         assert_eq!(sps.pic_height_in_ctus(), 9); // ceil(1080/128)
     }
+
+    #[test]
+    fn test_ceil_log2() {
+        assert_eq!(ceil_log2(0), 0);
+        assert_eq!(ceil_log2(1), 0);
+        assert_eq!(ceil_log2(2), 1);
+        assert_eq!(ceil_log2(3), 2);
+        assert_eq!(ceil_log2(15), 4);
+        assert_eq!(ceil_log2(16), 4);
+    }
 }
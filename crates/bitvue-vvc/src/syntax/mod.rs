@@ -1,8 +1,10 @@
 //! VVC syntax tree extraction for visualization.
 
-use crate::{NalUnitType, VvcStream};
+use crate::aps::{Aps, ApsData};
+use crate::{NalUnit, NalUnitType, ParsedSlice, VvcStream};
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
+use std::ops::Range;
 
 /// A node in the VVC syntax tree.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +55,13 @@ impl SyntaxNode {
     pub fn add_child(&mut self, child: SyntaxNode) {
         self.children.push(child);
     }
+
+    /// Set bit position.
+    pub fn with_position(mut self, offset: u64, length: u64) -> Self {
+        self.bit_offset = Some(offset);
+        self.bit_length = Some(length);
+        self
+    }
 }
 
 /// Build a syntax tree from a parsed VVC stream.
@@ -84,23 +93,7 @@ pub fn build_syntax_tree(stream: &VvcStream) -> SyntaxNode {
         let mut nal_node = SyntaxNode::new(nal_name, SyntaxNodeType::NalUnit);
 
         // Add NAL header fields
-        let mut header_node = SyntaxNode::new("NAL Header", SyntaxNodeType::Structure);
-        header_node.add_child(SyntaxNode::field(
-            "nal_unit_type",
-            format!(
-                "{:?} ({})",
-                nal.header.nal_unit_type, nal.header.nal_unit_type as u8
-            ),
-        ));
-        header_node.add_child(SyntaxNode::field(
-            "nuh_layer_id",
-            nal.header.nuh_layer_id.to_string(),
-        ));
-        header_node.add_child(SyntaxNode::field(
-            "nuh_temporal_id_plus1",
-            nal.header.nuh_temporal_id_plus1.to_string(),
-        ));
-        nal_node.add_child(header_node);
+        nal_node.add_child(build_nal_header_tree(nal));
 
         // Add parameter set details
         match nal.header.nal_unit_type {
@@ -114,6 +107,16 @@ pub fn build_syntax_tree(stream: &VvcStream) -> SyntaxNode {
                     nal_node.add_child(build_pps_tree(pps));
                 }
             }
+            NalUnitType::PrefixApsNut | NalUnitType::SuffixApsNut => {
+                if let Some(parsed) = stream.aps.iter().find(|a| a.nal_index == i) {
+                    nal_node.add_child(build_aps_tree(&parsed.aps));
+                }
+            }
+            nal_type if nal_type.is_vcl() => {
+                if let Some(slice) = stream.slices.iter().find(|s| s.nal_index == i) {
+                    nal_node.add_child(build_slice_tree(stream, slice));
+                }
+            }
             _ => {}
         }
 
@@ -125,6 +128,80 @@ pub fn build_syntax_tree(stream: &VvcStream) -> SyntaxNode {
     root
 }
 
+/// Build a syntax tree for the NAL units of one access unit.
+///
+/// `nal_range` indexes `stream.nal_units`. Parameter sets are parsed from
+/// their own payload, and every NAL unit node carries its bit position in
+/// the parsed stream.
+pub fn build_access_unit_tree(stream: &VvcStream, nal_range: Range<usize>) -> SyntaxNode {
+    let mut root = SyntaxNode::new("Access Unit", SyntaxNodeType::Root);
+    let start = nal_range.start;
+
+    for (i, nal) in stream
+        .nal_units
+        .get(nal_range)
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+    {
+        let nal_index = start + i;
+        let mut nal_node = SyntaxNode::new(
+            format!("[{}] {}", nal_index, nal.header.nal_unit_type.name()),
+            SyntaxNodeType::NalUnit,
+        )
+        .with_position(nal.offset * 8, nal.size * 8);
+
+        nal_node.add_child(build_nal_header_tree(nal).with_position(nal.offset * 8, 16));
+
+        match nal.header.nal_unit_type {
+            NalUnitType::SpsNut => {
+                if let Ok(sps) = crate::sps::parse_sps(&nal.payload) {
+                    nal_node.add_child(build_sps_tree(&sps));
+                }
+            }
+            NalUnitType::PpsNut => {
+                if let Ok(pps) = crate::pps::parse_pps(&nal.payload) {
+                    nal_node.add_child(build_pps_tree(&pps));
+                }
+            }
+            NalUnitType::PrefixApsNut | NalUnitType::SuffixApsNut => {
+                if let Some(parsed) = stream.aps.iter().find(|a| a.nal_index == nal_index) {
+                    nal_node.add_child(build_aps_tree(&parsed.aps));
+                }
+            }
+            _ => {
+                if let Some(slice) = stream.slices.iter().find(|s| s.nal_index == nal_index) {
+                    nal_node.add_child(build_slice_tree(stream, slice));
+                }
+            }
+        }
+
+        root.add_child(nal_node);
+    }
+
+    root
+}
+
+fn build_nal_header_tree(nal: &NalUnit) -> SyntaxNode {
+    let mut header_node = SyntaxNode::new("NAL Header", SyntaxNodeType::Structure);
+    header_node.add_child(SyntaxNode::field(
+        "nal_unit_type",
+        format!(
+            "{:?} ({})",
+            nal.header.nal_unit_type, nal.header.nal_unit_type as u8
+        ),
+    ));
+    header_node.add_child(SyntaxNode::field(
+        "nuh_layer_id",
+        nal.header.nuh_layer_id.to_string(),
+    ));
+    header_node.add_child(SyntaxNode::field(
+        "nuh_temporal_id_plus1",
+        nal.header.nuh_temporal_id_plus1.to_string(),
+    ));
+    header_node
+}
+
 fn build_sps_tree(sps: &crate::Sps) -> SyntaxNode {
     let mut node = SyntaxNode::new("Sequence Parameter Set", SyntaxNodeType::ParameterSet);
 
@@ -203,6 +280,175 @@ fn build_pps_tree(pps: &crate::Pps) -> SyntaxNode {
     node
 }
 
+fn build_aps_tree(aps: &Aps) -> SyntaxNode {
+    let mut node = SyntaxNode::new("Adaptation Parameter Set", SyntaxNodeType::ParameterSet);
+
+    node.add_child(SyntaxNode::field(
+        "aps_params_type",
+        aps.aps_params_type.name().to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "aps_adaptation_parameter_set_id",
+        aps.aps_adaptation_parameter_set_id.to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "aps_chroma_present_flag",
+        aps.aps_chroma_present_flag.to_string(),
+    ));
+
+    match &aps.data {
+        ApsData::Alf(alf) => {
+            let mut alf_node = SyntaxNode::new("alf_data", SyntaxNodeType::Structure);
+            if alf.alf_luma_filter_signal_flag {
+                let mut luma = SyntaxNode::new("Luma Filters", SyntaxNodeType::Array);
+                luma.add_child(SyntaxNode::field(
+                    "alf_luma_clip_flag",
+                    alf.alf_luma_clip_flag.to_string(),
+                ));
+                luma.add_child(SyntaxNode::field(
+                    "alf_luma_coeff_delta_idx",
+                    format!("{:?}", alf.alf_luma_coeff_delta_idx),
+                ));
+                for (i, coeff) in alf.luma_coeff.iter().enumerate() {
+                    luma.add_child(SyntaxNode::field(
+                        format!("filter[{}]", i),
+                        format!("coeff {:?} clip {:?}", coeff, alf.luma_clip_idx[i]),
+                    ));
+                }
+                alf_node.add_child(luma);
+            }
+            if alf.alf_chroma_filter_signal_flag {
+                let mut chroma = SyntaxNode::new("Chroma Filters", SyntaxNodeType::Array);
+                for (i, coeff) in alf.chroma_coeff.iter().enumerate() {
+                    chroma.add_child(SyntaxNode::field(
+                        format!("alt_filter[{}]", i),
+                        format!("coeff {:?} clip {:?}", coeff, alf.chroma_clip_idx[i]),
+                    ));
+                }
+                alf_node.add_child(chroma);
+            }
+            for (name, filters) in [
+                ("CC-ALF Cb", &alf.cc_cb_coeff),
+                ("CC-ALF Cr", &alf.cc_cr_coeff),
+            ] {
+                if filters.is_empty() {
+                    continue;
+                }
+                let mut cc = SyntaxNode::new(name, SyntaxNodeType::Array);
+                for (i, coeff) in filters.iter().enumerate() {
+                    cc.add_child(SyntaxNode::field(
+                        format!("filter[{}]", i),
+                        format!("{:?}", coeff),
+                    ));
+                }
+                alf_node.add_child(cc);
+            }
+            node.add_child(alf_node);
+        }
+        ApsData::Lmcs(lmcs) => {
+            let mut lmcs_node = SyntaxNode::new("lmcs_data", SyntaxNodeType::Structure);
+            lmcs_node.add_child(SyntaxNode::field(
+                "lmcs_min_bin_idx",
+                lmcs.lmcs_min_bin_idx.to_string(),
+            ));
+            lmcs_node.add_child(SyntaxNode::field(
+                "LmcsMaxBinIdx",
+                lmcs.max_bin_idx().to_string(),
+            ));
+            lmcs_node.add_child(SyntaxNode::field(
+                "lmcs_delta_cw_prec_minus1",
+                lmcs.lmcs_delta_cw_prec_minus1.to_string(),
+            ));
+            lmcs_node.add_child(SyntaxNode::field(
+                "lmcsDeltaCW",
+                format!("{:?}", lmcs.delta_cw),
+            ));
+            lmcs_node.add_child(SyntaxNode::field(
+                "lmcsDeltaCrs",
+                lmcs.delta_crs.to_string(),
+            ));
+            node.add_child(lmcs_node);
+        }
+        ApsData::ScalingList(sl) => {
+            let mut sl_node = SyntaxNode::new("scaling_list_data", SyntaxNodeType::Array);
+            for (id, matrix) in sl.matrices.iter().enumerate() {
+                let mode = if sl.scaling_list_copy_mode_flag[id] {
+                    "copy"
+                } else if sl.scaling_list_pred_mode_flag[id] {
+                    "pred"
+                } else {
+                    "explicit"
+                };
+                let mut value = format!(
+                    "{} ref_delta {} {:?}",
+                    mode, sl.scaling_list_pred_id_delta[id], matrix
+                );
+                if id > 13 {
+                    value.push_str(&format!(" dc {}", sl.dc[id - 14]));
+                }
+                sl_node.add_child(SyntaxNode::field(format!("matrix[{}]", id), value));
+            }
+            node.add_child(sl_node);
+        }
+    }
+
+    node
+}
+
+fn build_slice_tree(stream: &VvcStream, slice: &ParsedSlice) -> SyntaxNode {
+    let header = &slice.header;
+    let mut node = SyntaxNode::new("Slice Header", SyntaxNodeType::SliceHeader);
+
+    node.add_child(SyntaxNode::field(
+        "slice_type",
+        header.slice_type.name().to_string(),
+    ));
+    node.add_child(SyntaxNode::field("POC", slice.poc.to_string()));
+    node.add_child(SyntaxNode::field("SliceQpY", header.slice_qp_y.to_string()));
+    node.add_child(SyntaxNode::field(
+        "sh_slice_address",
+        header.sh_slice_address.to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "NumEntryPoints",
+        header.num_entry_points.to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "alf_enabled",
+        header.alf.alf_enabled_flag.to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "sh_lmcs_used_flag",
+        header.sh_lmcs_used_flag.to_string(),
+    ));
+    node.add_child(SyntaxNode::field(
+        "sh_explicit_scaling_list_used_flag",
+        header.sh_explicit_scaling_list_used_flag.to_string(),
+    ));
+
+    let mut aps_node = SyntaxNode::new("Active APS", SyntaxNodeType::Array);
+    for index in slice.active_aps.indices() {
+        if let Some(parsed) = stream.aps.get(index) {
+            aps_node.add_child(SyntaxNode::field(
+                parsed.aps.aps_params_type.name(),
+                format!(
+                    "id {} (NAL {})",
+                    parsed.aps.aps_adaptation_parameter_set_id, parsed.nal_index
+                ),
+            ));
+        }
+    }
+    for (params_type, id) in &slice.active_aps.missing {
+        aps_node.add_child(SyntaxNode::field(
+            params_type.name(),
+            format!("id {} (missing)", id),
+        ));
+    }
+    node.add_child(aps_node);
+
+    node
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -22,6 +22,8 @@ fn test_stream_feature_checks() {
             map
         },
         pps_map: HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     assert!(stream.uses_gdr());
@@ -67,13 +69,13 @@ fn test_parse_vvc_with_4byte_start_code() {
 
 #[test]
 fn test_parse_vvc_sps_nal() {
-    // Test SPS NAL unit (nal_unit_type=16 is SPS_NUT)
+    // Test SPS NAL unit (nal_unit_type=15 is SPS_NUT)
     let mut data = vec![0u8; 32];
     data[0] = 0x00;
     data[1] = 0x00;
     data[2] = 0x01;
     data[3] = 0x00; // layer_id=0
-    data[4] = 0x81; // nal_unit_type=16 << 3 | 1
+    data[4] = 0x79; // nal_unit_type=15 << 3 | 1
 
     let result = parse_vvc(&data);
     assert!(result.is_ok());
@@ -88,13 +90,13 @@ fn test_parse_vvc_sps_nal() {
 
 #[test]
 fn test_parse_vvc_pps_nal() {
-    // Test PPS NAL unit (nal_unit_type=17 is PPS_NUT)
+    // Test PPS NAL unit (nal_unit_type=16 is PPS_NUT)
     let mut data = vec![0u8; 32];
     data[0] = 0x00;
     data[1] = 0x00;
     data[2] = 0x01;
     data[3] = 0x00; // layer_id=0
-    data[4] = 0x89; // nal_unit_type=17 << 3 | 1
+    data[4] = 0x81; // nal_unit_type=16 << 3 | 1
 
     let result = parse_vvc(&data);
     assert!(result.is_ok());
@@ -171,7 +173,7 @@ fn test_parse_vvc_aud_nal() {
     data[1] = 0x00;
     data[2] = 0x01;
     data[3] = 0x00; // layer_id=0
-    data[4] = 0xA9; // nal_unit_type=21 (AUD) << 3 | 1
+    data[4] = 0xA1; // nal_unit_type=20 (AUD) << 3 | 1
 
     let result = parse_vvc(&data);
     assert!(result.is_ok());
@@ -192,7 +194,7 @@ fn test_parse_vvc_eos_nal() {
     data[1] = 0x00;
     data[2] = 0x01;
     data[3] = 0x00; // layer_id=0
-    data[4] = 0xB1; // nal_unit_type=22 (EOS) << 3 | 1
+    data[4] = 0xA9; // nal_unit_type=21 (EOS) << 3 | 1
 
     let result = parse_vvc(&data);
     assert!(result.is_ok());
@@ -213,7 +215,7 @@ fn test_parse_vvc_eob_nal() {
     data[1] = 0x00;
     data[2] = 0x01;
     data[3] = 0x00; // layer_id=0
-    data[4] = 0xB9; // nal_unit_type=23 (EOB) << 3 | 1
+    data[4] = 0xB1; // nal_unit_type=22 (EOB) << 3 | 1
 
     let result = parse_vvc(&data);
     assert!(result.is_ok());
@@ -234,7 +236,7 @@ fn test_parse_vvc_filler_nal() {
     data[1] = 0x00;
     data[2] = 0x01;
     data[3] = 0x00; // layer_id=0
-    data[4] = 0xD1; // nal_unit_type=26 (Filler) << 3 | 1
+    data[4] = 0xC9; // nal_unit_type=25 (Filler) << 3 | 1
     for i in 5..17 {
         data[i] = 0xFF; // Filler bytes
     }
@@ -255,7 +257,7 @@ fn test_parse_vvc_prefix_sei_nal() {
     data[1] = 0x00;
     data[2] = 0x01;
     data[3] = 0x00; // layer_id=0
-    data[4] = 0xC1; // nal_unit_type=24 (Prefix SEI) << 3 | 1
+    data[4] = 0xB9; // nal_unit_type=23 (Prefix SEI) << 3 | 1
     let result = parse_vvc(&data);
     assert!(result.is_ok());
     let stream = result.unwrap();
@@ -269,13 +271,13 @@ fn test_parse_vvc_prefix_sei_nal() {
 
 #[test]
 fn test_parse_vvc_suffix_sei_nal() {
-    // Test Suffix SEI (nal_unit_type=40)
+    // Test Suffix SEI (nal_unit_type=24)
     let mut data = vec![0u8; 16];
     data[0] = 0x00;
     data[1] = 0x00;
     data[2] = 0x01;
     data[3] = 0x00; // layer_id=0
-    data[4] = 0xC9; // nal_unit_type=25 (Suffix SEI) << 3 | 1
+    data[4] = 0xC1; // nal_unit_type=24 (Suffix SEI) << 3 | 1
     let result = parse_vvc(&data);
     assert!(result.is_ok());
     let stream = result.unwrap();
@@ -302,8 +304,8 @@ fn test_parse_vvc_multiple_nal_units() {
     pos += 1;
     data[pos] = 0x00;
     pos += 1; // layer_id=0
-    data[pos] = 0x81;
-    pos += 1; // nal_unit_type=16 << 3 | 1
+    data[pos] = 0x79;
+    pos += 1; // nal_unit_type=15 << 3 | 1
     data[pos] = 0x00;
     pos += 1; // payload byte
 
@@ -316,8 +318,8 @@ fn test_parse_vvc_multiple_nal_units() {
     pos += 1;
     data[pos] = 0x00;
     pos += 1; // layer_id=0
-    data[pos] = 0x89;
-    pos += 1; // nal_unit_type=17 << 3 | 1
+    data[pos] = 0x81;
+    pos += 1; // nal_unit_type=16 << 3 | 1
     data[pos] = 0x00;
     pos += 1; // payload byte
 
@@ -350,7 +352,7 @@ fn test_parse_vvc_quick() {
     data[1] = 0x00;
     data[2] = 0x01;
     data[3] = 0x00; // layer_id=0
-    data[4] = 0x81; // nal_unit_type=16 << 3 | 1
+    data[4] = 0x79; // nal_unit_type=15 << 3 | 1
 
     let result = parse_vvc_quick(&data);
     assert!(result.is_ok());
@@ -374,6 +376,8 @@ fn test_vvc_stream_methods() {
         nal_units: vec![],
         sps_map: std::collections::HashMap::new(),
         pps_map: std::collections::HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     assert_eq!(stream.frame_count(), 0);
@@ -406,6 +410,8 @@ fn test_vvc_stream_with_sps() {
         nal_units: vec![],
         sps_map,
         pps_map: std::collections::HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     assert!(stream.dimensions().is_some());
@@ -428,6 +434,8 @@ fn test_vvc_stream_feature_flags() {
         nal_units: vec![],
         sps_map,
         pps_map: std::collections::HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
     assert!(stream.uses_gdr());
 
@@ -440,6 +448,8 @@ fn test_vvc_stream_feature_flags() {
         nal_units: vec![],
         sps_map,
         pps_map: std::collections::HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
     assert!(stream.uses_alf());
 
@@ -452,6 +462,8 @@ fn test_vvc_stream_feature_flags() {
         nal_units: vec![],
         sps_map,
         pps_map: std::collections::HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
     assert!(stream.uses_lmcs());
 }
@@ -569,6 +581,8 @@ fn test_vvc_stream_idr_frames() {
         ],
         sps_map: std::collections::HashMap::new(),
         pps_map: std::collections::HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     assert_eq!(stream.idr_frames().len(), 2);
@@ -605,6 +619,8 @@ fn test_vvc_stream_gdr_frames() {
         ],
         sps_map: std::collections::HashMap::new(),
         pps_map: std::collections::HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     assert_eq!(stream.gdr_frames().len(), 1);
@@ -684,6 +700,8 @@ fn test_chroma_format_variants() {
             nal_units: vec![],
             sps_map,
             pps_map: std::collections::HashMap::new(),
+            aps: Vec::new(),
+            slices: Vec::new(),
         };
 
         assert_eq!(stream.chroma_format(), Some(format));
@@ -816,6 +834,8 @@ fn test_vvc_various_resolutions() {
             nal_units: vec![],
             sps_map,
             pps_map: std::collections::HashMap::new(),
+            aps: Vec::new(),
+            slices: Vec::new(),
         };
 
         assert_eq!(stream.dimensions(), Some((width, height)));
@@ -835,6 +855,8 @@ fn test_vvc_dual_tree_detection() {
         nal_units: vec![],
         sps_map,
         pps_map: std::collections::HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     assert!(stream.uses_dual_tree());
@@ -853,6 +875,8 @@ fn test_vvc_gdr_detection() {
         nal_units: vec![],
         sps_map,
         pps_map: std::collections::HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     assert!(stream.uses_gdr());
@@ -871,6 +895,8 @@ fn test_vvc_alf_detection() {
         nal_units: vec![],
         sps_map,
         pps_map: std::collections::HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     assert!(stream.uses_alf());
@@ -889,6 +915,8 @@ fn test_vvc_lmcs_detection() {
         nal_units: vec![],
         sps_map,
         pps_map: std::collections::HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     assert!(stream.uses_lmcs());
@@ -935,6 +963,8 @@ fn test_vvc_stream_irap_with_cra_and_gdr() {
         ],
         sps_map: std::collections::HashMap::new(),
         pps_map: std::collections::HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     assert_eq!(stream.irap_frames().len(), 3); // IDR + CRA + GDR
@@ -971,6 +1001,8 @@ fn test_vvc_leading_frames() {
         ],
         sps_map: std::collections::HashMap::new(),
         pps_map: std::collections::HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     // Both RASL and RADL are leading frames
@@ -991,6 +1023,8 @@ fn test_vvc_bit_depth_variations() {
             nal_units: vec![],
             sps_map,
             pps_map: std::collections::HashMap::new(),
+            aps: Vec::new(),
+            slices: Vec::new(),
         };
 
         assert_eq!(stream.bit_depth(), Some(bit_depth));
@@ -1071,6 +1105,8 @@ fn test_vvc_frame_count_mixed_nal_units() {
         ],
         sps_map: std::collections::HashMap::new(),
         pps_map: std::collections::HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     // Only VCL NAL units (TrailNut, IdrWRadl) count as frames
@@ -1098,6 +1134,8 @@ fn test_vvc_sps_pps_map_operations() {
         nal_units: vec![],
         sps_map,
         pps_map,
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     assert!(stream.get_sps(0).is_some());
@@ -1558,3 +1596,314 @@ fn test_parse_vvc_with_invalid_nal_unit_type() {
     // Should handle gracefully
     assert!(result.is_ok() || result.is_err());
 }
+
+/// Minimal RBSP writer for building synthetic parameter sets and headers.
+#[derive(Default)]
+struct RbspWriter {
+    bits: Vec<bool>,
+}
+
+impl RbspWriter {
+    fn u(&mut self, value: u32, n: u8) -> &mut Self {
+        for i in (0..n).rev() {
+            self.bits.push((value >> i) & 1 != 0);
+        }
+        self
+    }
+
+    fn flag(&mut self, value: bool) -> &mut Self {
+        self.u(value as u32, 1)
+    }
+
+    fn ue(&mut self, value: u32) -> &mut Self {
+        let code = value + 1;
+        let len = 32 - code.leading_zeros() as u8;
+        self.u(0, len - 1).u(code, len)
+    }
+
+    fn se(&mut self, value: i32) -> &mut Self {
+        let mapped = if value > 0 {
+            (value * 2 - 1) as u32
+        } else {
+            (-value * 2) as u32
+        };
+        self.ue(mapped)
+    }
+
+    /// Append rbsp_trailing_bits() and wrap into an Annex B NAL unit.
+    fn nal(&mut self, nal_type: NalUnitType) -> Vec<u8> {
+        self.flag(true);
+        while !self.bits.len().is_multiple_of(8) {
+            self.flag(false);
+        }
+        let rbsp: Vec<u8> = self
+            .bits
+            .chunks(8)
+            .map(|c| c.iter().fold(0u8, |acc, b| (acc << 1) | *b as u8))
+            .collect();
+
+        let mut out = vec![0x00, 0x00, 0x00, 0x01, 0x00, ((nal_type as u8) << 3) | 1];
+        let mut zeros = 0;
+        for byte in rbsp {
+            if zeros >= 2 && byte <= 3 {
+                out.push(0x03);
+                zeros = 0;
+            }
+            zeros = if byte == 0 { zeros + 1 } else { 0 };
+            out.push(byte);
+        }
+        out
+    }
+}
+
+/// 128x64 4:2:0 SPS with 64x64 CTUs and entry point offsets enabled.
+fn synthetic_sps(lmcs: bool) -> Vec<u8> {
+    let mut w = RbspWriter::default();
+    w.u(0, 4).u(0, 4).u(0, 3).u(1, 2).u(1, 2); // ids, sublayers, chroma, ctu size
+    w.flag(false); // sps_ptl_dpb_hrd_params_present_flag
+    w.flag(false).flag(false); // gdr, ref pic resampling
+    w.ue(128).ue(64);
+    w.flag(false).flag(false); // conformance window, subpic info
+    w.ue(0); // bitdepth
+    w.flag(false).flag(true); // entropy sync, entry point offsets present
+    w.u(4, 4).flag(false); // log2 max poc lsb minus4, poc msb cycle
+    w.u(0, 2).u(0, 2); // extra ph/sh bytes
+    w.ue(0).flag(false).ue(0).ue(0); // min cb, partition override, intra luma qt/mtt
+    w.flag(false); // qtbtt dual tree
+    w.ue(0).ue(0); // inter qt/mtt
+    w.flag(false); // max luma transform size 64
+    w.flag(false).flag(false).flag(false); // ts, mts, lfnst
+    w.flag(false).flag(true); // joint cbcr, same qp table
+    w.se(0).ue(0).ue(0).ue(0); // one chroma qp table
+    w.flag(true).flag(false).flag(lmcs); // sao, alf, lmcs
+    w.flag(false).flag(false).flag(false); // weighted pred/bipred, long term refs
+    w.flag(false).flag(true).ue(0); // idr rpl, rpl1 same as rpl0, no SPS lists
+    w.flag(false).flag(false).flag(false).flag(false); // wraparound, tmvp, amvr, bdof
+    w.flag(false).flag(false).flag(false); // smvd, dmvr, mmvd
+    w.ue(0).flag(false).flag(false); // max merge cand, sbt, affine
+    w.flag(false).flag(false).flag(false).ue(0); // bcw, ciip, gpm, parallel merge
+    w.flag(false).flag(false).flag(false).flag(false); // isp, mrl, mip, cclm
+    w.flag(false).flag(false); // chroma collocated flags
+    w.flag(false).flag(false).flag(false); // palette, ibc, ladf
+    w.flag(false).flag(false).flag(false); // scaling list, dep quant, sign hiding
+    w.flag(false); // virtual boundaries
+    w.flag(false).flag(false).flag(false); // timing/hrd, vui, extension
+    w.nal(NalUnitType::SpsNut)
+}
+
+/// PPS splitting the picture into two 64x64 tiles with raster-scan slices.
+fn synthetic_pps() -> Vec<u8> {
+    let mut w = RbspWriter::default();
+    w.u(0, 6).u(0, 4).flag(false); // ids, mixed nalu types
+    w.ue(128).ue(64);
+    w.flag(false).flag(false).flag(false); // conformance, scaling window, output flag
+    w.flag(false).flag(false); // no pic partition, subpic id mapping
+    w.u(1, 2).ue(1).ue(0).ue(0).ue(0).ue(0); // ctu size and tile sizes
+    w.flag(true).flag(false); // loop filter across tiles, rect slices
+    w.flag(true); // loop filter across slices
+    w.flag(true).ue(0).ue(0); // cabac init present, default active refs
+    w.flag(false).flag(false).flag(false).flag(false); // rpl1 idx, wp, wbp, wraparound
+    w.se(4).flag(false).flag(false); // init qp, cu qp delta, chroma tool offsets
+    w.flag(false); // deblocking control
+    w.flag(false).flag(false).flag(false).flag(false); // rpl/sao/alf/qp info in ph
+    w.flag(false).flag(false).flag(false); // extensions
+    w.nal(NalUnitType::PpsNut)
+}
+
+fn synthetic_picture_header(intra: bool, poc_lsb: u32, lmcs_aps_id: Option<u32>) -> Vec<u8> {
+    let mut w = RbspWriter::default();
+    w.flag(intra).flag(false); // gdr or irap, non ref
+    if intra {
+        w.flag(false); // gdr
+    }
+    w.flag(!intra); // inter slices allowed
+    if !intra {
+        w.flag(true); // intra slices allowed
+    }
+    w.ue(0).u(poc_lsb, 8);
+    if let Some(id) = lmcs_aps_id {
+        w.flag(true).u(id, 2).flag(false); // lmcs enabled, aps id, chroma residual scale
+    }
+    if !intra {
+        w.flag(false); // mvd l1 zero
+    }
+    w.nal(NalUnitType::PhNut)
+}
+
+#[test]
+fn test_parse_synthetic_slice_headers() {
+    let mut data = Vec::new();
+    data.extend(synthetic_sps(false));
+    data.extend(synthetic_pps());
+
+    // IDR picture: one slice covering both tiles.
+    data.extend(synthetic_picture_header(true, 0, None));
+    let mut w = RbspWriter::default();
+    w.flag(false).u(0, 1).ue(1); // ph in sh, slice address, tiles in slice
+    w.flag(false); // no output of prior pics
+    w.se(-2).flag(true).flag(false); // qp delta, sao luma/chroma
+    w.ue(7).u(99, 8); // entry points
+    w.flag(true); // byte_alignment()
+    data.extend(w.nal(NalUnitType::IdrNLp));
+
+    // Trailing P picture referencing the previous picture.
+    data.extend(synthetic_picture_header(false, 4, None));
+    let mut w = RbspWriter::default();
+    w.flag(false).u(0, 1).ue(1); // ph in sh, slice address, tiles in slice
+    w.ue(1); // slice type P
+    w.ue(1).ue(0).flag(true); // list 0: one entry, delta poc -1
+    w.ue(0); // list 1: empty
+    w.flag(false); // cabac init
+    w.se(3).flag(false).flag(false); // qp delta, sao luma/chroma
+    w.ue(0).u(0, 1); // entry points
+    w.flag(true); // byte_alignment()
+    data.extend(w.nal(NalUnitType::TrailNut));
+
+    let stream = parse_vvc(&data).unwrap();
+    assert_eq!(stream.slices.len(), 2);
+
+    let idr = &stream.slices[0];
+    assert_eq!(idr.header.slice_type, SliceType::I);
+    assert_eq!(idr.header.qp(), 28);
+    assert_eq!(idr.header.sh_num_tiles_in_slice_minus1, 1);
+    assert!(idr.header.sh_sao_luma_used_flag);
+    assert_eq!(idr.header.num_entry_points, 1);
+    assert_eq!(idr.header.sh_entry_point_offset_minus1, vec![99]);
+    assert_eq!(idr.poc, 0);
+
+    let p = &stream.slices[1];
+    assert_eq!(p.header.slice_type, SliceType::P);
+    assert_eq!(p.header.qp(), 33);
+    assert_eq!(p.header.num_ref_idx_l0_active(), 1);
+    let rpl = p.header.ref_pic_lists.as_ref().unwrap();
+    assert_eq!(rpl.lists[0].delta_poc_val_st, vec![-1]);
+    assert_eq!(rpl.num_ref_entries(1), 0);
+    assert_eq!(p.poc, 4);
+
+    let frames = frames::frames_from_stream(&stream, &data);
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].frame_type, VvcFrameType::I);
+    assert!(frames[0].is_idr);
+    assert_eq!(frames[1].frame_type, VvcFrameType::P);
+    assert_eq!(frames[1].qp, 33);
+}
+
+#[test]
+fn test_parse_alf_aps() {
+    let mut w = RbspWriter::default();
+    w.u(0, 3).u(3, 5).flag(true); // ALF, id 3, chroma present
+    w.flag(true).flag(false).flag(true).flag(false); // luma, chroma, cc cb, cc cr
+    w.flag(false).ue(1); // luma clip, two filters
+    for class in 0..25 {
+        w.u(class % 2, 1); // alf_luma_coeff_delta_idx
+    }
+    for filter in 0..2 {
+        for j in 0..12 {
+            let coeff = if j == 0 { filter + 1 } else { 0 };
+            w.ue(coeff);
+            if coeff != 0 {
+                w.flag(filter == 1); // sign
+            }
+        }
+    }
+    w.ue(0); // one CC-ALF Cb filter
+    for j in 0..7 {
+        w.u(if j == 2 { 3 } else { 0 }, 3);
+        if j == 2 {
+            w.flag(true);
+        }
+    }
+    w.flag(false); // aps_extension_flag
+    let nal = w.nal(NalUnitType::PrefixApsNut);
+
+    let aps = aps::parse_aps(&remove_emulation_prevention_bytes(&nal[6..])).unwrap();
+    assert_eq!(aps.aps_params_type, ApsParamsType::Alf);
+    assert_eq!(aps.aps_adaptation_parameter_set_id, 3);
+    let alf = aps.alf().unwrap();
+    assert_eq!(alf.luma_coeff.len(), 2);
+    assert_eq!(alf.luma_filter_for_class(1).unwrap()[0], -2);
+    assert_eq!(alf.luma_filter_for_class(2).unwrap()[0], 1);
+    assert_eq!(alf.cc_cb_coeff, vec![[0, 0, -4, 0, 0, 0, 0]]);
+    assert!(alf.chroma_coeff.is_empty());
+}
+
+#[test]
+fn test_slice_activates_lmcs_aps() {
+    let mut data = Vec::new();
+    data.extend(synthetic_sps(true));
+    data.extend(synthetic_pps());
+
+    let mut w = RbspWriter::default();
+    w.u(1, 3).u(1, 5).flag(true); // LMCS, id 1, chroma present
+    w.ue(0).ue(0).ue(2); // min bin, delta max bin, cw precision 3 bits
+    for bin in 0..16 {
+        w.u(if bin == 4 { 5 } else { 0 }, 3);
+        if bin == 4 {
+            w.flag(true);
+        }
+    }
+    w.u(2, 3).flag(false); // chroma residual scaling delta
+    w.flag(false); // aps_extension_flag
+    data.extend(w.nal(NalUnitType::PrefixApsNut));
+
+    data.extend(synthetic_picture_header(true, 0, Some(1)));
+    let mut w = RbspWriter::default();
+    w.flag(false).u(0, 1).ue(1); // ph in sh, slice address, tiles in slice
+    w.flag(false).flag(true); // no output of prior pics, lmcs used
+    w.se(0).flag(false).flag(false); // qp delta, sao luma/chroma
+    w.ue(0).u(0, 1); // entry points
+    w.flag(true); // byte_alignment()
+    data.extend(w.nal(NalUnitType::IdrNLp));
+
+    let stream = parse_vvc(&data).unwrap();
+    assert_eq!(stream.aps.len(), 1);
+    let lmcs = stream.aps[0].aps.lmcs().unwrap();
+    assert_eq!(lmcs.delta_cw[4], -5);
+    assert_eq!(lmcs.delta_crs, 2);
+    assert_eq!(lmcs.codewords(10)[4], 59);
+
+    let slice = &stream.slices[0];
+    assert!(slice.header.sh_lmcs_used_flag);
+    assert_eq!(slice.active_aps.lmcs, Some(0));
+    assert!(slice.active_aps.missing.is_empty());
+    assert_eq!(stream.slice_aps(slice).len(), 1);
+
+    let frames = frames::frames_from_stream(&stream, &data);
+    assert_eq!(frames[0].aps.len(), 1);
+
+    let tree = syntax::build_syntax_tree(&stream);
+    let nal_units = tree
+        .children
+        .iter()
+        .find(|c| c.name == "NAL Units")
+        .unwrap();
+    assert!(nal_units.children[2]
+        .children
+        .iter()
+        .any(|c| c.name == "Adaptation Parameter Set"));
+}
+
+#[test]
+fn test_parse_decoded_picture_hash_sei() {
+    // CRC hash of three components, then a user data message, then
+    // rbsp_trailing_bits
+    let mut rbsp = vec![132, 8, 1, 0x00, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC];
+    rbsp.extend([5, 2, 0xAA, 0xBB, 0x80]);
+    let messages = sei::parse_sei(&rbsp).unwrap();
+    assert_eq!(messages.len(), 2);
+    let hash = messages[0].decoded_picture_hash().unwrap();
+    assert_eq!(hash.hash_type, PictureHashType::Crc);
+    assert_eq!(
+        hash.component_hashes,
+        vec![vec![0x12, 0x34], vec![0x56, 0x78], vec![0x9A, 0xBC]]
+    );
+    assert!(messages[1].decoded_picture_hash().is_none());
+
+    // dph_sei_single_component_flag: one MD5 for the luma plane
+    let mut payload = vec![0, 0x80];
+    payload.extend([0x11; 16]);
+    let hash = sei::parse_decoded_picture_hash(&payload).unwrap();
+    assert_eq!(hash.component_hashes, vec![vec![0x11; 16]]);
+
+    assert!(sei::parse_sei(&[132, 20, 1, 0]).is_err());
+}
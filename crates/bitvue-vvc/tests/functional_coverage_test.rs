@@ -184,6 +184,8 @@ fn test_stream_dimensions() {
         nal_units: vec![],
         sps_map: std::collections::HashMap::new(),
         pps_map: std::collections::HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     assert!(stream.dimensions().is_none());
@@ -42,9 +42,9 @@ fn test_vvc_nal_unit_types() {
         (0x00, 0x08, NalUnitType::StapNut),  // STSA_NUT (type 1 << 3 = 0x08)
         (0x00, 0x38, NalUnitType::IdrWRadl), // IDR_W_RADL (type 7 << 3 = 0x38)
         (0x00, 0x40, NalUnitType::IdrNLp),   // IDR_N_LP (type 8 << 3 = 0x40)
-        (0x00, 0x78, NalUnitType::VpsNut),   // VPS_NUT (type 15 << 3 = 0x78)
-        (0x00, 0x80, NalUnitType::SpsNut),   // SPS_NUT (type 16 << 3 = 0x80)
-        (0x00, 0x88, NalUnitType::PpsNut),   // PPS_NUT (type 17 << 3 = 0x88)
+        (0x00, 0x70, NalUnitType::VpsNut),   // VPS_NUT (type 14 << 3 = 0x70)
+        (0x00, 0x78, NalUnitType::SpsNut),   // SPS_NUT (type 15 << 3 = 0x78)
+        (0x00, 0x80, NalUnitType::PpsNut),   // PPS_NUT (type 16 << 3 = 0x80)
     ];
 
     for (byte0, byte1, expected_type) in test_cases {
@@ -70,11 +70,11 @@ fn test_vvc_parameter_sets() {
     // Test VPS, SPS, PPS NAL unit detection (not parsing)
     let data = [
         // VPS (Video Parameter Set) - simplified
-        0x00, 0x00, 0x00, 0x01, 0x00, 0x78, 0x00, // type 15 << 3 = 0x78
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x70, 0x00, // type 14 << 3 = 0x70
         // SPS (Sequence Parameter Set) - simplified
-        0x00, 0x00, 0x00, 0x01, 0x00, 0x80, 0x00, // type 16 << 3 = 0x80
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x78, 0x00, // type 15 << 3 = 0x78
         // PPS (Picture Parameter Set) - simplified
-        0x00, 0x00, 0x00, 0x01, 0x00, 0x88, 0x00, // type 17 << 3 = 0x88
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x80, 0x00, // type 16 << 3 = 0x80
     ];
 
     let result = parse_vvc(&data);
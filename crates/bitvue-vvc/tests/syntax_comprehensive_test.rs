@@ -232,6 +232,8 @@ fn test_build_syntax_tree_empty_stream() {
         nal_units: vec![],
         sps_map: HashMap::new(),
         pps_map: HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     let tree = build_syntax_tree(&stream);
@@ -260,6 +262,8 @@ fn test_build_syntax_tree_with_nal_unit() {
         }],
         sps_map: HashMap::new(),
         pps_map: HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     let tree = build_syntax_tree(&stream);
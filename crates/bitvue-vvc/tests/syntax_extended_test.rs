@@ -182,6 +182,8 @@ fn test_build_syntax_tree_empty_stream() {
         nal_units: vec![],
         sps_map: HashMap::new(),
         pps_map: HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     let tree = build_syntax_tree(&stream);
@@ -207,6 +209,8 @@ fn test_build_syntax_tree_with_nal_units() {
         nal_units: vec![nal_unit],
         sps_map: HashMap::new(),
         pps_map: HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     let tree = build_syntax_tree(&stream);
@@ -50,6 +50,8 @@ fn test_build_syntax_tree_empty() {
         nal_units: vec![],
         sps_map: HashMap::new(),
         pps_map: HashMap::new(),
+        aps: Vec::new(),
+        slices: Vec::new(),
     };
 
     let tree = bitvue_vvc::syntax::build_syntax_tree(&stream);
@@ -22,9 +22,9 @@ fn test_parse_vvc_nal_types() {
         (0b00001000, NalUnitType::StapNut),  // 1 << 3: STSA_NUT
         (0b00111000, NalUnitType::IdrWRadl), // 7 << 3: IDR_W_RADL
         (0b01000000, NalUnitType::IdrNLp),   // 8 << 3: IDR_N_LP
-        (0b01111000, NalUnitType::VpsNut),   // 15 << 3: VPS_NUT
-        (0b10000000, NalUnitType::SpsNut),   // 16 << 3: SPS_NUT
-        (0b10001000, NalUnitType::PpsNut),   // 17 << 3: PPS_NUT
+        (0b01110000, NalUnitType::VpsNut),   // 14 << 3: VPS_NUT
+        (0b01111000, NalUnitType::SpsNut),   // 15 << 3: SPS_NUT
+        (0b10000000, NalUnitType::PpsNut),   // 16 << 3: PPS_NUT
     ];
 
     for (header_byte, expected_type) in nal_types {
@@ -85,9 +85,9 @@ fn test_vvc_idr_frame_detection() {
 fn test_vvc_parameter_set_detection() {
     // Test VPS, SPS, PPS detection
     let data = vec![
-        0x00, 0x00, 0x00, 0x01, 0x00, 0b01111000, 0x00, // VPS (type 15 << 3)
-        0x00, 0x00, 0x00, 0x01, 0x00, 0b10000000, 0x00, // SPS (type 16 << 3)
-        0x00, 0x00, 0x00, 0x01, 0x00, 0b10001000, 0x00, // PPS (type 17 << 3)
+        0x00, 0x00, 0x00, 0x01, 0x00, 0b01110000, 0x00, // VPS (type 14 << 3)
+        0x00, 0x00, 0x00, 0x01, 0x00, 0b01111000, 0x00, // SPS (type 15 << 3)
+        0x00, 0x00, 0x00, 0x01, 0x00, 0b10000000, 0x00, // PPS (type 16 << 3)
     ];
 
     let result = parse_nal_units(&data);
@@ -322,10 +322,12 @@ fn extract_av1_analysis(
                     if let Ok((_, ivf_frames)) = bitvue_av1_codec::parse_ivf_frames(file_data) {
                         // SECURITY: Don't log frame count to prevent information disclosure
                         log::info!("extract_av1_analysis: IVF parsing successful");
-                        ivf_frames.get(frame_index).map(|f| {
+                        // Inter frames inherit decoder state from their references,
+                        // so the stream is decoded from its start up to this frame
+                        ivf_frames.get(..=frame_index).map(|frames| {
                             // SECURITY: Don't log frame index or data size
                             log::info!("extract_av1_analysis: Frame data extracted");
-                            f.data.clone()
+                            frames.iter().flat_map(|f| f.data.iter().copied()).collect::<Vec<u8>>()
                         })
                     } else {
                         log::warn!("extract_av1_analysis: IVF parsing failed");
@@ -473,8 +475,18 @@ fn extract_avc_analysis(
         })
         .ok_or("No SPS found in stream")?;
 
+    let pps = nal_units.iter()
+        .find_map(|nal| {
+            if nal.header.nal_unit_type == bitvue_avc::NalUnitType::Pps {
+                bitvue_avc::pps::parse_pps(&nal.payload).ok()
+            } else {
+                None
+            }
+        })
+        .ok_or("No PPS found in stream")?;
+
     // Extract grids using H.264 functions
-    let qp_grid = bitvue_avc::extract_qp_grid(&nal_units, &sps, 26)
+    let qp_grid = bitvue_avc::extract_qp_grid(&nal_units, &sps, &pps, 26)
         .ok()
         .map(|grid| QPGridData {
             grid_w: grid.grid_w,
@@ -486,7 +498,7 @@ fn extract_avc_analysis(
             qp_max: grid.qp_max,
         });
 
-    let mv_grid = bitvue_avc::extract_mv_grid(&nal_units, &sps)
+    let mv_grid = bitvue_avc::extract_mv_grid(&nal_units, &sps, &pps)
         .ok()
         .map(|grid| MVGridData {
             coded_width: grid.coded_width,
@@ -506,7 +518,7 @@ fn extract_avc_analysis(
             mode: grid.mode.map(|modes: Vec<bitvue_core::mv_overlay::BlockMode>| modes.into_iter().map(|m| m as u8).collect()),
         });
 
-    let partition_grid = bitvue_avc::extract_partition_grid(&nal_units, &sps)
+    let partition_grid = bitvue_avc::extract_partition_grid(&nal_units, &sps, &pps)
         .ok()
         .map(|grid| PartitionGridData {
             coded_width: grid.coded_width,
@@ -565,8 +577,18 @@ fn extract_hevc_analysis(
         })
         .ok_or("No SPS found in stream")?;
 
+    let pps = nal_units.iter()
+        .find_map(|nal| {
+            if nal.header.nal_unit_type == bitvue_hevc::NalUnitType::PpsNut {
+                bitvue_hevc::pps::parse_pps(&nal.payload).ok()
+            } else {
+                None
+            }
+        })
+        .ok_or("No PPS found in stream")?;
+
     // Extract grids using HEVC functions
-    let qp_grid = bitvue_hevc::extract_qp_grid(&nal_units, &sps, 26)
+    let qp_grid = bitvue_hevc::extract_qp_grid(&nal_units, &sps, &pps, 26)
         .ok()
         .map(|grid| QPGridData {
             grid_w: grid.grid_w,
@@ -578,7 +600,7 @@ fn extract_hevc_analysis(
             qp_max: grid.qp_max,
         });
 
-    let mv_grid = bitvue_hevc::extract_mv_grid(&nal_units, &sps)
+    let mv_grid = bitvue_hevc::extract_mv_grid(&nal_units, &sps, &pps)
         .ok()
         .map(|grid| MVGridData {
             coded_width: grid.coded_width,
@@ -598,7 +620,7 @@ fn extract_hevc_analysis(
             mode: grid.mode.map(|modes: Vec<bitvue_core::mv_overlay::BlockMode>| modes.into_iter().map(|m| m as u8).collect()),
         });
 
-    let partition_grid = bitvue_hevc::extract_partition_grid(&nal_units, &sps)
+    let partition_grid = bitvue_hevc::extract_partition_grid(&nal_units, &sps, &pps)
         .ok()
         .map(|grid| PartitionGridData {
             coded_width: grid.coded_width,
@@ -249,6 +249,10 @@ pub async fn open_file(
             ContainerFormat::AnnexB => parse_annex_b_container(&file_data),
             ContainerFormat::MP4 => parse_mp4_container(&file_data),
             ContainerFormat::Matroska => parse_mkv_container(&file_data),
+            ContainerFormat::Unknown if bitvue_av1_codec::is_annex_b(&file_data) => {
+                final_codec = "av1".to_string();
+                parse_av1_annex_b_container(&file_data)
+            }
             _ => {
                 log::info!("open_file: Format {:?} not yet supported for extraction", container_format);
                 None
@@ -781,6 +785,21 @@ fn parse_ivf_container(file_data: &[u8]) -> Option<Vec<bitvue_core::UnitNode>> {
     }
 }
 
+/// Parse AV1 Annex B (length-delimited) OBU streams
+///
+/// Returns one unit per temporal unit, with its frame units and OBUs as
+/// children, or None if parsing fails.
+fn parse_av1_annex_b_container(file_data: &[u8]) -> Option<Vec<bitvue_core::UnitNode>> {
+    log::info!("parse_av1_annex_b_container: Parsing AV1 Annex B stream...");
+    match bitvue_av1_codec::parse_annex_b(file_data) {
+        Ok(units) => Some(bitvue_av1_codec::annex_b_unit_nodes(&units)),
+        Err(e) => {
+            log::error!("parse_av1_annex_b_container: Annex B parsing failed: {}", e);
+            None
+        }
+    }
+}
+
 /// Parse Annex B container format (H.264/H.265 raw files)
 ///
 /// Returns parsed unit nodes from Annex B file, or None if parsing fails.